@@ -0,0 +1,61 @@
+// Analytics benchmarks against seeded local databases (`cargo bench --bench analytics`).
+// Covers the SQL-heavy paths that grow with journal size: comprehensive analytics and
+// grouped analytics at 10k and 50k trades. Replicache pull has no server endpoint in this
+// tree yet, so it isn't covered here.
+
+#[path = "../tests/common/fixtures.rs"]
+mod fixtures;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+use tradstry_backend::models::analytics::options::GroupingType;
+use tradstry_backend::models::analytics::AnalyticsOptions;
+use tradstry_backend::models::stock::stocks::TimeRange;
+use tradstry_backend::service::analytics_engine::AnalyticsEngine;
+
+const JOURNAL_SIZES: &[usize] = &[10_000, 50_000];
+
+fn analytics_benches(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to start tokio runtime");
+    let engine = AnalyticsEngine::new();
+
+    let mut group = c.benchmark_group("analytics");
+    // Each iteration runs dozens of queries; keep total runtime reasonable
+    group.sample_size(10);
+
+    for &trades in JOURNAL_SIZES {
+        let fixture = runtime
+            .block_on(fixtures::seeded_database(trades))
+            .expect("Failed to seed benchmark database");
+        let conn = &fixture.conn;
+
+        for (label, time_range) in [("all_time", TimeRange::AllTime), ("one_year", TimeRange::OneYear)] {
+            group.bench_with_input(BenchmarkId::new(format!("comprehensive/{}", label), trades), &time_range, |b, time_range| {
+                b.to_async(&runtime).iter(|| async {
+                    engine
+                        .calculate_comprehensive_analytics(conn, time_range, AnalyticsOptions::default())
+                        .await
+                        .expect("comprehensive analytics failed")
+                })
+            });
+        }
+
+        let grouping_options = AnalyticsOptions {
+            include_grouped_analytics: true,
+            grouping_types: vec![GroupingType::Symbol, GroupingType::Strategy, GroupingType::TradeDirection],
+            ..AnalyticsOptions::default()
+        };
+        group.bench_with_input(BenchmarkId::new("grouped", trades), &grouping_options, |b, options| {
+            b.to_async(&runtime).iter(|| async {
+                engine
+                    .calculate_grouped_analytics(conn, &TimeRange::AllTime, options)
+                    .await
+                    .expect("grouped analytics failed")
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, analytics_benches);
+criterion_main!(benches);
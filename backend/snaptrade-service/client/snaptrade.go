@@ -239,10 +239,12 @@ func (c *SnapTradeClient) GetTransactions(userId, userSecret, accountId string,
 		// You may need to filter the results after fetching
 	}
 
-	// Add date range if provided
-	if startDate != nil {
-		// Note: Date filtering may need to be done via GetReportingCustomRange
-		// or filtered after fetching
+	// Add date range if provided (YYYY-MM-DD)
+	if startDate != nil && *startDate != "" {
+		req = req.StartDate(*startDate)
+	}
+	if endDate != nil && *endDate != "" {
+		req = req.EndDate(*endDate)
 	}
 
 	activities, _, err := c.client.TransactionsAndReportingApi.GetActivitiesExecute(req)
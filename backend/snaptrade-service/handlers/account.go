@@ -79,7 +79,11 @@ func GetAccountDetail(snapTradeClient *client.SnapTradeClient) fiber.Handler {
 
 // SyncAccountsRequest represents the request to sync accounts
 type SyncAccountsRequest struct {
-	UserSecret string `json:"user_secret"`
+	UserSecret      string  `json:"user_secret"`
+	// Only sync accounts under this brokerage authorization (all accounts when empty)
+	AuthorizationId string  `json:"authorization_id,omitempty"`
+	// Only fetch transactions on or after this date, YYYY-MM-DD (incremental sync)
+	StartDate       *string `json:"start_date,omitempty"`
 }
 
 // SyncAccountsResponse represents the synced data
@@ -125,6 +129,16 @@ func SyncAccounts(snapTradeClient *client.SnapTradeClient) fiber.Handler {
 		response.Holdings = make([]interface{}, 0)
 		response.Transactions = make([]interface{}, 0)
 
+		if req.AuthorizationId != "" {
+			filtered := accounts[:0]
+			for _, account := range accounts {
+				if account.GetBrokerageAuthorization() == req.AuthorizationId {
+					filtered = append(filtered, account)
+				}
+			}
+			accounts = filtered
+		}
+
 		// Convert accounts to interface{} for JSON serialization
 		for _, account := range accounts {
 			response.Accounts = append(response.Accounts, account)
@@ -145,7 +159,7 @@ func SyncAccounts(snapTradeClient *client.SnapTradeClient) fiber.Handler {
 			}
 
 			// Get transactions (last 90 days by default, or all if not specified)
-			transactions, err := snapTradeClient.GetTransactions(userId, req.UserSecret, accountId, nil, nil)
+			transactions, err := snapTradeClient.GetTransactions(userId, req.UserSecret, accountId, req.StartDate, nil)
 			if err == nil {
 				for _, transaction := range transactions {
 					response.Transactions = append(response.Transactions, transaction)
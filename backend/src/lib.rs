@@ -0,0 +1,40 @@
+// Library target for the backend. The server binary in main.rs is built on top of it, and
+// benches/integration tests use it to reach services and analytics directly.
+
+pub mod turso;
+pub mod routes;
+pub mod models;
+pub mod service;
+pub mod websocket;
+pub mod middleware;
+
+use serde::Serialize;
+
+// Routes wrap their scopes with the validator through `crate::jwt_validator`
+pub use middleware::jwt_auth::jwt_validator;
+
+/// Envelope for the server's top-level JSON responses
+#[derive(Serialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub message: Option<String>,
+}
+
+impl<T> ApiResponse<T> {
+    pub fn success(data: T) -> Self {
+        Self {
+            success: true,
+            data: Some(data),
+            message: None,
+        }
+    }
+
+    pub fn error(message: &str) -> ApiResponse<()> {
+        ApiResponse {
+            success: false,
+            data: None,
+            message: Some(message.to_string()),
+        }
+    }
+}
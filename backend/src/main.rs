@@ -1,24 +1,12 @@
-mod turso;
-mod routes;
-mod models;
-mod service;
-mod websocket;
-mod middleware;
+use tradstry_backend::{turso, routes, service, websocket, middleware, jwt_validator, ApiResponse};
 
 use actix_cors::Cors;
 use actix_web::{
-    dev::ServiceRequest,
     middleware::Logger,
     web::{self, Data, Json},
     App, HttpMessage, HttpServer, Result as ActixResult,
 };
-use actix_web_httpauth::{
-    extractors::{
-        bearer::{BearerAuth, Config},
-        AuthenticationError,
-    },
-    middleware::HttpAuthentication,
-};
+use actix_web_httpauth::middleware::HttpAuthentication;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -26,48 +14,28 @@ use turso::{
     AppState,
     get_user_id,
     get_supabase_user_id,
-    validate_jwt_token,
-    validate_supabase_jwt_token,
-    AuthError,
     SupabaseClaims,
 };
 use crate::service::market_engine::ws_proxy::MarketWsProxy;
 use routes::{configure_analytics_routes, configure_user_routes, configure_options_routes, configure_stocks_routes, configure_trade_notes_routes, configure_images_routes, configure_playbook_routes, configure_notebook_routes, configure_ai_chat_routes, configure_ai_insights_routes, configure_ai_reports_routes, configure_trade_tags_routes, configure_watchlist_price_routes, configure_brokerage_routes};
-use websocket::{ConnectionManager, ws_handler};
+use websocket::{ConnectionManager, sse_handler, ws_handler};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-#[derive(Serialize)]
-struct ApiResponse<T> {
-    success: bool,
-    data: Option<T>,
-    message: Option<String>,
-}
-
-impl<T> ApiResponse<T> {
-    fn success(data: T) -> Self {
-        Self {
-            success: true,
-            data: Some(data),
-            message: None,
-        }
-    }
-
-    #[allow(dead_code)]
-    fn error(message: &str) -> ApiResponse<()> {
-        ApiResponse {
-            success: false,
-            data: None,
-            message: Some(message.to_string()),
-        }
-    }
-}
-
 #[derive(Serialize)]
 struct HealthCheck {
     status: String,
     database: String,
     timestamp: chrono::DateTime<chrono::Utc>,
+    /// Cross-tenant leak check, only run for `?deep=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant_isolation: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct HealthQuery {
+    #[serde(default)]
+    deep: bool,
 }
 
 #[derive(Deserialize)]
@@ -88,9 +56,18 @@ async fn main() -> std::io::Result<()> {
     // Initialize application state
     let app_state = AppState::new().await.expect("Failed to initialize app state");
     let app_data = Data::new(app_state);
-    
-    // Initialize WebSocket connection manager
-    let ws_manager = Arc::new(Mutex::new(ConnectionManager::new()));
+
+    // Master key wrapping per-user note encryption keys
+    service::note_encryption::install_master_key(app_data.config.secrets_encryption_key.as_deref());
+    // Published demo token for the API docs playground
+    service::sandbox::install(app_data.config.sandbox.as_ref());
+    // Vendor cost ledger for the admin cost report
+    service::cost_ledger::install(Arc::clone(&app_data.turso_client));
+    // Opt-in feature usage counts
+    service::telemetry::install(Arc::clone(&app_data.turso_client));
+
+    // Initialize WebSocket connection manager, relaying broadcasts to other instances through Redis
+    let ws_manager = Arc::new(Mutex::new(websocket::with_redis_relay(ConnectionManager::new()).await));
     let ws_manager_data = Data::new(Arc::clone(&ws_manager));
 
     // Initialize Market WebSocket Proxy for real-time quotes
@@ -151,6 +128,8 @@ async fn main() -> std::io::Result<()> {
                     actix_web::http::header::AUTHORIZATION,
                     actix_web::http::header::CONTENT_TYPE,
                     actix_web::http::header::HeaderName::from_static("x-requested-with"),
+                    actix_web::http::header::HeaderName::from_static("x-response-format"),
+                    actix_web::http::header::HeaderName::from_static("api-version"),
                 ])
                 .supports_credentials()
                 .max_age(3600)
@@ -164,6 +143,8 @@ async fn main() -> std::io::Result<()> {
                     actix_web::http::header::AUTHORIZATION,
                     actix_web::http::header::CONTENT_TYPE,
                     actix_web::http::header::HeaderName::from_static("x-requested-with"),
+                    actix_web::http::header::HeaderName::from_static("x-response-format"),
+                    actix_web::http::header::HeaderName::from_static("api-version"),
                 ])
                 .supports_credentials()
                 .max_age(3600)
@@ -187,9 +168,17 @@ async fn main() -> std::io::Result<()> {
             .app_data(Data::new(app_data.as_ref().vectorization_service.clone()))
             // CRITICAL: Add TradeNotesService as separate app_data for trade notes routes
             .app_data(Data::new(app_data.as_ref().trade_notes_service.clone()))  
+            // Per-user slots for the concurrency middleware on heavy analytics and AI scopes
+            .app_data(Data::new(app_data.as_ref().tenant_limiter.clone()))
             .wrap(cors)
             .wrap(Logger::default())
             .wrap(actix_web::middleware::from_fn(rate_limit_middleware))
+            // Sandbox token kept away from external side effects and account management
+            .wrap(actix_web::middleware::from_fn(middleware::sandbox::sandbox_guard_middleware))
+            // Legacy vs. camelCase/enveloped bodies, chosen by X-Response-Format
+            .wrap(actix_web::middleware::from_fn(middleware::response_format::response_format_middleware))
+            // /api/v1 and /api/v2 prefixes (or Api-Version header) resolved before routing
+            .wrap(actix_web::middleware::from_fn(middleware::api_version::api_version_middleware))
             // Register user routes FIRST with explicit logging
             .configure(|cfg| {
                 log::info!("Configuring user routes");
@@ -205,6 +194,8 @@ async fn main() -> std::io::Result<()> {
                 log::info!("Configuring stocks routes");
                 configure_stocks_routes(cfg);
             })
+            // Natural-language trade parser (must precede the /api/trades notes scope)
+            .configure(crate::routes::configure_trade_parser_routes)
             // Register trade notes routes (rate limiting handled in middleware)
             .configure(|cfg| {
                 log::info!("Configuring trade notes routes");
@@ -242,6 +233,8 @@ async fn main() -> std::io::Result<()> {
             .configure(|cfg| {
                 log::info!("Configuring WebSocket routes");
                 cfg.route("/api/ws", web::get().to(ws_handler));
+                // Same events over SSE where websockets are blocked
+                cfg.route("/api/events/stream", web::get().to(sse_handler));
             })
             // Register brokerage routes
             .configure(|cfg| {
@@ -264,11 +257,57 @@ fn configure_public_routes(cfg: &mut web::ServiceConfig) {
         .route("/health", web::get().to(health_check))
         .route("/webhooks/supabase", web::post().to(supabase_webhook_handler))
         .route("/webhooks/clerk", web::post().to(clerk_webhook_handler))
+        .route("/webhooks/telegram", web::post().to(crate::routes::telegram::telegram_webhook))
+        .route("/webhooks/snaptrade", web::post().to(crate::routes::brokerage::snaptrade_webhook))
+        .route("/webhooks/inbound-email", web::post().to(crate::routes::inbound_email::inbound_email_webhook))
         .route("/profile", web::get().to(get_profile))
+        // Public read-only report links
+        .route("/shared/reports/{token}", web::get().to(crate::routes::ai_reports::view_shared_report))
+        .route("/shared/year-review/{token}", web::get().to(crate::routes::year_review::view_shared_year_review))
         // Market Data public routes
         .configure(crate::routes::market::configure_market_routes)
         // Cron endpoints (public but secured with cron secret)
-        .route("/api/price-alerts/check-all", web::post().to(crate::routes::watchlist_price::check_all_price_alerts));
+        .route("/api/price-alerts/check-all", web::post().to(crate::routes::watchlist_price::check_all_price_alerts))
+        .route("/api/behavior/drawdown-alerts/check-all", web::post().to(crate::routes::behavior::check_all_drawdowns))
+        .route("/api/notifications/webhooks/daily-recap-all", web::post().to(crate::routes::notification_webhooks::send_all_daily_recaps))
+        .route("/api/integrations/notion/export-all", web::post().to(crate::routes::notion::export_all_notion_journals))
+        .route("/api/ai/coach/weekly-digest-all", web::post().to(crate::routes::ai_coach::send_all_weekly_digests))
+        .route("/api/ai/report-schedules/run-due", web::post().to(crate::routes::ai_report_schedules::run_all_due_report_schedules))
+        // Insights queued while the AI provider was failing
+        .route("/api/admin/insights/regenerate-queued", web::post().to(crate::routes::ai_insights::regenerate_all_queued_insights))
+        .route("/api/admin/tasks/monitor", web::post().to(crate::routes::task_monitor::run_task_monitor))
+        // Per-user data retention cleanup
+        .route("/api/admin/retention/run", web::post().to(crate::routes::retention::run_all_retention))
+        // Playbook target evaluation and degradation alerts
+        .route("/api/admin/playbook-targets/run", web::post().to(crate::routes::playbook::run_all_playbook_target_checks))
+        // Monthly performance statement PDFs
+        .route("/api/admin/statements/run", web::post().to(crate::routes::statements::run_monthly_statements))
+        // Year-in-review reports, run each January
+        .route("/api/admin/year-review/run", web::post().to(crate::routes::year_review::run_year_reviews))
+        // Nightly data integrity checks
+        .route("/api/admin/data-quality/run", web::post().to(crate::routes::data_quality::run_all_data_quality_checks))
+        // Hourly: end-of-day digests whose local send hour has passed
+        .route("/api/notifications/digest/send-due", web::post().to(crate::routes::daily_digest::send_all_daily_digests))
+        // API docs sandbox: published token, nightly reseed
+        .route("/api/sandbox", web::get().to(crate::routes::sandbox::get_sandbox))
+        .route("/api/admin/sandbox/reset", web::post().to(crate::routes::sandbox::reset_sandbox))
+        // Dead tenant cleanup (admin, secured with cron secret)
+        .route("/api/admin/tenants/scan", web::post().to(crate::routes::tenant_cleanup::scan_dead_tenants))
+        .route("/api/admin/tenants/dead", web::get().to(crate::routes::tenant_cleanup::list_dead_tenants))
+        .route("/api/admin/tenants/dead/{id}/reclaim", web::post().to(crate::routes::tenant_cleanup::reclaim_dead_tenant))
+        .route("/api/admin/tenants/{user_id}/images/migrate", web::post().to(crate::routes::image_migration::migrate_tenant_images))
+        // Tenant schema dry run and admin migration (forced runs may drop columns with data)
+        .route("/api/admin/schema/{user_id}/plan", web::get().to(crate::routes::schema_admin::plan_schema_migration))
+        .route("/api/admin/schema/{user_id}/migrate", web::post().to(crate::routes::schema_admin::run_schema_migration))
+        // Per-stage AI request latency (admin, secured with cron secret)
+        .route("/api/admin/ai/latency", web::get().to(crate::routes::ai_latency::get_ai_latency_report))
+        // Vendor cost and gross margin per user/tier (admin, secured with cron secret)
+        .route("/api/admin/costs", web::get().to(crate::routes::cost_report::get_cost_report))
+        .route("/api/admin/users/{user_id}/plan-tier", web::post().to(crate::routes::cost_report::set_plan_tier))
+        // Opt-in feature usage per category (admin, secured with cron secret)
+        .route("/api/admin/telemetry", web::get().to(crate::routes::telemetry::get_usage_report))
+        // Stored per-trade P&L backfill (admin, secured with cron secret)
+        .route("/api/admin/recalculate-pnl", web::post().to(crate::routes::realized_pnl::recalculate_pnl));
 }
 
 use middleware::rate_limit::rate_limit_middleware;
@@ -286,54 +325,75 @@ fn configure_auth_routes(cfg: &mut web::ServiceConfig) {
             .configure(configure_watchlist_price_routes)
             // Push notification routes
             .configure(crate::routes::configure_push_routes)
+            // Prop-firm evaluation profiles
+            .configure(crate::routes::configure_prop_firm_routes)
+            // Discord/Slack notification webhooks
+            .configure(crate::routes::configure_notification_webhook_routes)
+            // Notion journal export integration
+            .configure(crate::routes::configure_notion_routes)
+            // Telegram bot account linking
+            .configure(crate::routes::configure_telegram_routes)
+            // Weekly AI coach digest
+            .configure(crate::routes::configure_ai_coach_routes)
+            // Feedback on AI insights and chat messages
+            .configure(crate::routes::configure_ai_feedback_routes)
+            // Durable facts the assistant remembers about the user
+            .configure(crate::routes::configure_ai_memory_routes)
+            // Localized enum labels
+            .configure(crate::routes::configure_i18n_routes)
+            // Cron-scheduled AI reports
+            .configure(crate::routes::configure_ai_report_schedule_routes)
+            // Storage usage breakdown
+            .configure(crate::routes::configure_storage_routes)
+            // Consecutive-loss circuit breaker
+            .configure(crate::routes::configure_behavior_routes)
+            // Trade drafts from forwarded broker confirmation emails
+            .configure(crate::routes::configure_inbound_email_routes)
+            // Home screen summary
+            .configure(crate::routes::configure_dashboard_routes)
+            // Focus (pomodoro) sessions and their link to trading results
+            .configure(crate::routes::configure_focus_routes)
+            // Team workspaces and lead-only member analytics
+            .configure(crate::routes::configure_organization_routes)
+            // Shock scenarios on open positions
+            .configure(crate::routes::configure_risk_routes)
+            // Per-table data retention settings
+            .configure(crate::routes::configure_retention_routes)
+            // Playbook drafts from clustering untagged trades
+            .configure(crate::routes::configure_setup_discovery_routes)
+            // Monthly P&L goals and pacing
+            .configure(crate::routes::configure_goals_routes)
+            // User-defined automation rules
+            .configure(crate::routes::configure_automation_routes)
+            // Archive old trades to object storage and restore them
+            .configure(crate::routes::configure_trade_archive_routes)
+            // Sample journal for first-run users
+            .configure(crate::routes::configure_onboarding_routes)
+            // Data integrity issues from the nightly checks
+            .configure(crate::routes::configure_data_quality_routes)
+            // Unified journal timeline
+            .configure(crate::routes::configure_activity_routes)
+            // Monthly performance statements
+            .configure(crate::routes::configure_statement_routes)
+            // Cmd+K navigation search
+            .configure(crate::routes::configure_command_palette_routes)
+            // Per-ticker dossier
+            .configure(crate::routes::configure_symbol_journal_routes)
+            // Annual year-in-review reports
+            .configure(crate::routes::configure_year_review_routes)
+            // Undo for bulk deletes and tag deletions
+            .configure(crate::routes::configure_operation_routes)
+            // CSV import with preview and column mapping
+            .configure(crate::routes::configure_import_routes)
+            // Articles, tweets and videos attached to trades
+            .configure(crate::routes::configure_trade_link_routes)
+            // End-of-day email digest settings and preview
+            .configure(crate::routes::configure_daily_digest_routes)
+            // Feature usage telemetry toggle and export
+            .configure(crate::routes::configure_telemetry_routes)
     );
 }
 
-// JWT validation middleware - Updated for Supabase Auth
-pub async fn jwt_validator(
-    req: ServiceRequest,
-    credentials: BearerAuth,
-) -> Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
-    let config = req
-        .app_data::<Config>()
-        .cloned()
-        .unwrap_or_else(Default::default);
-
-    let app_state = req
-        .app_data::<Data<AppState>>()
-        .expect("AppState not found");
-
-    // Try Supabase JWT validation first (no caching)
-    match validate_supabase_jwt_token(
-        credentials.token(),
-        &app_state.config.supabase
-    ).await {
-        Ok(claims) => {
-            // Store Supabase claims in request extensions
-            req.extensions_mut().insert(claims);
-            Ok(req)
-        },
-        Err(AuthError::InvalidIssuer) | Err(AuthError::InvalidToken) => {
-            // Fallback to Clerk validation for migration period
-            match validate_jwt_token(credentials.token(), &app_state.config).await {
-                Ok(claims) => {
-                    // Store Clerk claims in request extensions
-                    req.extensions_mut().insert(claims);
-                    Ok(req)
-                },
-                Err(_) => {
-                    let error = AuthenticationError::from(config).into();
-                    Err((error, req))
-                }
-            }
-        },
-        Err(_) => {
-            let error = AuthenticationError::from(config).into();
-            Err((error, req))
-        }
-    }
-}
-
 // Route handlers
 
 async fn root_handler() -> ActixResult<Json<ApiResponse<HashMap<String, String>>>> {
@@ -344,13 +404,32 @@ async fn root_handler() -> ActixResult<Json<ApiResponse<HashMap<String, String>>
     Ok(Json(ApiResponse::success(data)))
 }
 
-async fn health_check(app_state: Data<AppState>) -> ActixResult<Json<ApiResponse<HealthCheck>>> {
+async fn health_check(
+    req: actix_web::HttpRequest,
+    query: web::Query<HealthQuery>,
+    app_state: Data<AppState>,
+) -> ActixResult<Json<ApiResponse<HealthCheck>>> {
+    // The deep check writes a canary to Qdrant, so it's limited to the cron/ops secret
+    let tenant_isolation = if query.deep {
+        crate::middleware::cron_auth::verify_cron_secret(&req, &app_state.config.cron_secret)?;
+        Some(match app_state.qdrant_tenant_manager.verify_isolation().await {
+            Ok(report) => serde_json::json!(report),
+            Err(e) => serde_json::json!({"isolated": false, "error": e.to_string()}),
+        })
+    } else {
+        None
+    };
+    let isolated = tenant_isolation
+        .as_ref()
+        .is_none_or(|report| report.get("isolated").and_then(|v| v.as_bool()).unwrap_or(false));
+
     match app_state.health_check().await {
         Ok(_) => {
             let health = HealthCheck {
-                status: "healthy".to_string(),
+                status: if isolated { "healthy" } else { "unhealthy" }.to_string(),
                 database: "connected".to_string(),
                 timestamp: chrono::Utc::now(),
+                tenant_isolation,
             };
             Ok(Json(ApiResponse::success(health)))
         }
@@ -359,6 +438,7 @@ async fn health_check(app_state: Data<AppState>) -> ActixResult<Json<ApiResponse
                 status: "unhealthy".to_string(),
                 database: "disconnected".to_string(),
                 timestamp: chrono::Utc::now(),
+                tenant_isolation,
             };
             Ok(Json(ApiResponse::success(health)))
         }
@@ -536,6 +616,9 @@ async fn handle_user_created(
             match app_state.turso_client.create_user_database(user_id, email).await {
                 Ok(_) => {
                     log::info!("Created database for Supabase user: {}", user_id);
+                    if let Err(e) = app_state.qdrant_tenant_manager.provision(user_id).await {
+                        log::warn!("Failed to provision Qdrant collection for user {}: {}", user_id, e);
+                    }
                     Ok(Json(ApiResponse::success(serde_json::json!({
                         "message": "User database created successfully",
                         "user_id": user_id
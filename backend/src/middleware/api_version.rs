@@ -0,0 +1,115 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{HeaderName, HeaderValue},
+        Uri,
+    },
+    middleware::Next,
+    Error, HttpResponse,
+};
+use serde_json::json;
+
+use super::response_format::RESPONSE_FORMAT_HEADER;
+use crate::models::serialization::CURRENT_FORMAT_VERSION;
+
+/// Header naming the API version for unprefixed `/api/...` paths, echoed on every response
+pub const API_VERSION_HEADER: &str = "api-version";
+
+/// v1 is the legacy surface, v2 the current serialization format
+pub const SUPPORTED_API_VERSIONS: [u32; 2] = [1, 2];
+const DEFAULT_API_VERSION: u32 = 1;
+
+/// API version negotiation
+///
+/// Clients pick a version with a path prefix (`/api/v2/analytics/core`) or, for unprefixed
+/// paths, the `Api-Version` header; neither means v1. Routes are registered once under
+/// `/api`, so the prefix is stripped before routing. v1 responses are the legacy bodies;
+/// v2 responses go through the response format shim and come back camelCase in the
+/// versioned envelope.
+pub async fn api_version_middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let header = req
+        .headers()
+        .get(API_VERSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let (version, unprefixed) = match resolve_version(req.path(), header.as_deref()) {
+        Ok(resolved) => resolved,
+        Err(message) => {
+            let response = HttpResponse::BadRequest().json(json!({
+                "success": false,
+                "message": message,
+                "supported_versions": SUPPORTED_API_VERSIONS,
+            }));
+            return Ok(req.into_response(response));
+        }
+    };
+
+    if let Some(path) = unprefixed {
+        let uri = match req.uri().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+        let uri: Uri = uri.parse().map_err(actix_web::error::ErrorBadRequest)?;
+        req.match_info_mut().get_mut().update(&uri);
+        req.head_mut().uri = uri;
+    }
+    if version >= 2 {
+        req.headers_mut().insert(
+            HeaderName::from_static(RESPONSE_FORMAT_HEADER),
+            HeaderValue::from(CURRENT_FORMAT_VERSION),
+        );
+    }
+
+    let mut res = next.call(req).await?;
+    res.headers_mut().insert(HeaderName::from_static(API_VERSION_HEADER), HeaderValue::from(version));
+    Ok(res.map_into_boxed_body())
+}
+
+/// Version for a request path and its `Api-Version` header, plus the path with the version
+/// prefix removed when there was one. A prefix wins over the header.
+fn resolve_version(path: &str, header: Option<&str>) -> Result<(u32, Option<String>), String> {
+    if let Some(rest) = path.strip_prefix("/api/v")
+        && let Some((number, tail)) = rest.split_once('/').or(Some((rest, "")))
+        && !number.is_empty()
+        && number.chars().all(|c| c.is_ascii_digit())
+    {
+        let version = parse_version(number)?;
+        let unprefixed = if tail.is_empty() { "/api".to_string() } else { format!("/api/{}", tail) };
+        return Ok((version, Some(unprefixed)));
+    }
+    match header {
+        Some(value) if path.starts_with("/api") => Ok((parse_version(value.trim().trim_start_matches('v'))?, None)),
+        _ => Ok((DEFAULT_API_VERSION, None)),
+    }
+}
+
+fn parse_version(value: &str) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|version| SUPPORTED_API_VERSIONS.contains(version))
+        .ok_or_else(|| format!("Unsupported API version '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_version_prefix_header_and_default() {
+        assert_eq!(
+            resolve_version("/api/v2/analytics/core", None),
+            Ok((2, Some("/api/analytics/core".to_string())))
+        );
+        assert_eq!(resolve_version("/api/v1/stocks", Some("2")), Ok((1, Some("/api/stocks".to_string()))));
+        assert_eq!(resolve_version("/api/stocks", Some("v2")), Ok((2, None)));
+        assert_eq!(resolve_version("/api/stocks", None), Ok((1, None)));
+        // Not a version prefix
+        assert_eq!(resolve_version("/api/videos", None), Ok((1, None)));
+        assert!(resolve_version("/api/v3/stocks", None).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use actix_web::{
+    body::{BodySize, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderValue},
+    middleware::Next,
+    web::{Bytes, Data},
+    Error, HttpMessage, HttpResponse,
+};
+use base64::Engine;
+use serde_json::json;
+
+use crate::service::concurrency_limiter::{ConcurrencyError, TenantConcurrencyLimiter, TenantPermit};
+use crate::turso::{get_supabase_user_id, SupabaseClaims};
+
+/// Per-user concurrency limit for heavy analytics and AI endpoints
+///
+/// Holds one of the user's slots in the app's `TenantConcurrencyLimiter` until the response
+/// body has been sent, so a streamed chat reply counts for as long as it streams. When the
+/// user's queue is full the request gets a 429 with `Retry-After`.
+pub async fn tenant_concurrency_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let Some(user_id) = request_user_id(&req) else {
+        // Unauthenticated requests are rejected by the handlers themselves
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+    let limiter = req
+        .app_data::<Data<Arc<TenantConcurrencyLimiter>>>()
+        .map(|data| Arc::clone(data.get_ref()))
+        .ok_or_else(|| actix_web::error::ErrorInternalServerError("TenantConcurrencyLimiter not found in request"))?;
+
+    let permit = match limiter.acquire(&user_id).await {
+        Ok(permit) => permit,
+        Err(ConcurrencyError::Busy { retry_after_secs }) => {
+            log::warn!("Concurrency limit reached for user {} on {}", user_id, req.path());
+            let response = HttpResponse::TooManyRequests()
+                .insert_header((header::RETRY_AFTER, HeaderValue::from(retry_after_secs)))
+                .json(json!({
+                    "success": false,
+                    "message": "Too many requests in progress. Please try again shortly.",
+                    "error": "CONCURRENCY_LIMIT_EXCEEDED",
+                    "retry_after": retry_after_secs,
+                }));
+            return Ok(req.into_response(response));
+        }
+    };
+
+    let guard = PermitGuard { permit: Some(permit), limiter, user_id };
+    // On error the guard is dropped here and the slot freed with it
+    let res = next.call(req).await?;
+    Ok(res.map_body(|_, body| PermitBody { body: body.boxed(), _guard: guard }).map_into_boxed_body())
+}
+
+/// Frees the user's slot when dropped, then forgets the user if nothing else is in flight
+struct PermitGuard {
+    permit: Option<TenantPermit>,
+    limiter: Arc<TenantConcurrencyLimiter>,
+    user_id: String,
+}
+
+impl Drop for PermitGuard {
+    fn drop(&mut self) {
+        self.permit.take();
+        self.limiter.release_idle(&self.user_id);
+    }
+}
+
+/// Response body that keeps the slot until the body is finished or the client goes away
+struct PermitBody {
+    body: BoxBody,
+    _guard: PermitGuard,
+}
+
+impl MessageBody for PermitBody {
+    type Error = <BoxBody as MessageBody>::Error;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        Pin::new(&mut self.body).poll_next(cx)
+    }
+}
+
+/// User id from verified claims, or else from the bearer token payload. Like the rate
+/// limiter this only buckets requests; the handlers still verify the token.
+fn request_user_id(req: &ServiceRequest) -> Option<String> {
+    if let Some(claims) = req.extensions().get::<SupabaseClaims>() {
+        return Some(get_supabase_user_id(claims));
+    }
+    let token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())?
+        .strip_prefix("Bearer ")?;
+    let payload = token.split('.').nth(1)?;
+    let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload).ok()?;
+    let claims: SupabaseClaims = serde_json::from_slice(&decoded).ok()?;
+    Some(get_supabase_user_id(&claims))
+}
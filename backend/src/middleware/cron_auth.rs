@@ -0,0 +1,27 @@
+use actix_web::HttpRequest;
+
+/// Verify the `X-Cron-Secret` header sent by the scheduler against `CRON_SECRET`
+pub fn verify_cron_secret(req: &HttpRequest, expected: &str) -> Result<(), actix_web::Error> {
+    let provided = req
+        .headers()
+        .get("X-Cron-Secret")
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Missing cron secret"))?;
+
+    if !secrets_match(provided, expected) {
+        log::warn!("Rejected cron request to {}: invalid cron secret", req.path());
+        return Err(actix_web::error::ErrorUnauthorized("Invalid cron secret"));
+    }
+    Ok(())
+}
+
+/// Compare a provided secret without short-circuiting on the first differing byte
+pub fn secrets_match(provided: &str, expected: &str) -> bool {
+    !expected.is_empty()
+        && provided.len() == expected.len()
+        && provided
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
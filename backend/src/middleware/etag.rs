@@ -0,0 +1,106 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, HeaderValue},
+        Method, StatusCode,
+    },
+    middleware::Next,
+    Error,
+};
+use sha2::{Digest, Sha256};
+
+/// ETag middleware for heavy read endpoints
+///
+/// Successful GET responses are buffered and tagged with a hash of their body. A request
+/// whose `If-None-Match` lists that tag gets an empty 304 instead, so polling clients only
+/// download a payload when it has changed.
+pub async fn etag_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let cacheable = req.method() == Method::GET;
+    with_etag(req, next, cacheable).await
+}
+
+/// Same as [`etag_middleware`], but POST responses are tagged too. For scopes such as
+/// analytics where POST carries the query filters and does not change anything.
+pub async fn etag_query_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let cacheable = req.method() == Method::GET || req.method() == Method::POST;
+    with_etag(req, next, cacheable).await
+}
+
+async fn with_etag(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+    cacheable: bool,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if !cacheable {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    }
+    let if_none_match = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let res = next.call(req).await?;
+    if res.status() != StatusCode::OK || res.headers().contains_key(header::ETAG) {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (mut head, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+
+    let etag = content_etag(&bytes);
+    let headers = head.headers_mut();
+    headers.insert(header::ETAG, HeaderValue::from_str(&etag)?);
+    // Bodies are per user, so shared caches must not store them
+    if !headers.contains_key(header::CACHE_CONTROL) {
+        headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("private, no-cache"));
+    }
+
+    if if_none_match.as_deref().is_some_and(|value| etag_matches(value, &etag)) {
+        headers.remove(header::CONTENT_TYPE);
+        head.head_mut().status = StatusCode::NOT_MODIFIED;
+        return Ok(ServiceResponse::new(req, head.set_body(()).map_into_boxed_body()));
+    }
+    Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()))
+}
+
+/// Strong ETag from the SHA-256 of the response body
+fn content_etag(body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}\"", hex::encode(&digest[..16]))
+}
+
+/// `If-None-Match` check: `*` or any listed tag, compared weakly (a `W/` prefix is ignored)
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|tag| tag.trim() == "*" || opaque(tag) == etag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_etag_matches_lists_and_weak_tags() {
+        let etag = content_etag(b"{\"success\":true}");
+        assert_eq!(etag, content_etag(b"{\"success\":true}"));
+        assert_ne!(etag, content_etag(b"{\"success\":false}"));
+
+        assert!(etag_matches(&etag, &etag));
+        assert!(etag_matches(&format!("\"stale\", W/{}", etag), &etag));
+        assert!(etag_matches("*", &etag));
+        assert!(!etag_matches("\"stale\"", &etag));
+    }
+}
@@ -0,0 +1,122 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::Next,
+    web, Error,
+};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+/// Sparse fieldset middleware
+///
+/// With `?fields=total_pnl,win_rate,risk_metrics.sharpe_ratio` a successful JSON response
+/// is pruned to the listed fields before it is sent. Dotted names select nested fields and
+/// arrays are pruned element by element. In the `{"success": .., "data": ..}` envelope only
+/// `data` is pruned.
+pub async fn fields_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let fields = web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .ok()
+        .and_then(|query| query.get("fields").map(|fields| FieldTree::parse(fields)))
+        .filter(|tree| !tree.children.is_empty());
+
+    let res = next.call(req).await?;
+    let Some(fields) = fields else {
+        return Ok(res.map_into_boxed_body());
+    };
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !res.status().is_success() || !is_json {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let (req, res) = res.into_parts();
+    let (head, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()));
+    };
+
+    let enveloped = value.get("success").is_some() && value.get("data").is_some();
+    let target = if enveloped { &mut value["data"] } else { &mut value };
+    fields.prune(target);
+    let pruned = serde_json::to_vec(&value)?;
+    Ok(ServiceResponse::new(req, head.set_body(pruned).map_into_boxed_body()))
+}
+
+/// Requested field paths
+#[derive(Debug, Default)]
+struct FieldTree {
+    /// Listed by itself, so the whole value is kept ("a,a.b" keeps all of `a`)
+    whole: bool,
+    children: BTreeMap<String, FieldTree>,
+}
+
+impl FieldTree {
+    fn parse(fields: &str) -> Self {
+        let mut root = FieldTree::default();
+        for path in fields.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let mut node = &mut root;
+            for name in path.split('.') {
+                node = node.children.entry(name.to_string()).or_default();
+            }
+            node.whole = true;
+        }
+        root
+    }
+
+    fn prune(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|key, _| self.children.contains_key(key));
+                for (key, child) in map.iter_mut() {
+                    let tree = &self.children[key];
+                    if !tree.whole {
+                        tree.prune(child);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.prune(item)),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_prune_nested_paths_and_arrays() {
+        let fields = FieldTree::parse("total_pnl, risk.sharpe_ratio,trades.symbol");
+        let mut value = json!({
+            "total_pnl": 120.5,
+            "win_rate": 55.0,
+            "risk": {"sharpe_ratio": 1.2, "sortino_ratio": 1.8},
+            "trades": [{"symbol": "AAPL", "pnl": 10.0}, {"symbol": "TSLA", "pnl": -4.0}],
+        });
+        fields.prune(&mut value);
+        assert_eq!(
+            value,
+            json!({
+                "total_pnl": 120.5,
+                "risk": {"sharpe_ratio": 1.2},
+                "trades": [{"symbol": "AAPL"}, {"symbol": "TSLA"}],
+            })
+        );
+
+        let mut value = json!({"risk": {"sharpe_ratio": 1.2, "sortino_ratio": 1.8}});
+        FieldTree::parse("risk.sharpe_ratio,risk").prune(&mut value);
+        assert_eq!(value["risk"]["sortino_ratio"], 1.8);
+    }
+}
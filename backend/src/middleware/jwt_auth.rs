@@ -0,0 +1,52 @@
+use actix_web::{dev::ServiceRequest, web::Data, HttpMessage};
+use actix_web_httpauth::extractors::{
+    bearer::{BearerAuth, Config},
+    AuthenticationError,
+};
+
+use crate::turso::{validate_jwt_token, validate_supabase_jwt_token, AppState, AuthError};
+
+/// JWT validation middleware - Supabase Auth first, Clerk as the migration fallback
+pub async fn jwt_validator(
+    req: ServiceRequest,
+    credentials: BearerAuth,
+) -> Result<ServiceRequest, (actix_web::Error, ServiceRequest)> {
+    let config = req
+        .app_data::<Config>()
+        .cloned()
+        .unwrap_or_else(Default::default);
+
+    let app_state = req
+        .app_data::<Data<AppState>>()
+        .expect("AppState not found");
+
+    // Try Supabase JWT validation first (no caching)
+    match validate_supabase_jwt_token(
+        credentials.token(),
+        &app_state.config.supabase
+    ).await {
+        Ok(claims) => {
+            // Store Supabase claims in request extensions
+            req.extensions_mut().insert(claims);
+            Ok(req)
+        },
+        Err(AuthError::InvalidIssuer) | Err(AuthError::InvalidToken) => {
+            // Fallback to Clerk validation for migration period
+            match validate_jwt_token(credentials.token(), &app_state.config).await {
+                Ok(claims) => {
+                    // Store Clerk claims in request extensions
+                    req.extensions_mut().insert(claims);
+                    Ok(req)
+                },
+                Err(_) => {
+                    let error = AuthenticationError::from(config).into();
+                    Err((error, req))
+                }
+            }
+        },
+        Err(_) => {
+            let error = AuthenticationError::from(config).into();
+            Err((error, req))
+        }
+    }
+}
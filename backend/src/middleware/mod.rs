@@ -1,2 +1,10 @@
 pub mod rate_limit;
-
+pub mod cron_auth;
+pub mod jwt_auth;
+pub mod etag;
+pub mod fields;
+pub mod timeout;
+pub mod concurrency;
+pub mod response_format;
+pub mod api_version;
+pub mod sandbox;
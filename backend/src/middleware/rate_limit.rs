@@ -8,6 +8,7 @@ use actix_web::web::Data;
 use actix_web::http::header::HeaderValue;
 use base64::Engine;
 use crate::turso::{AppState, SupabaseClaims, ClerkClaims, get_supabase_user_id, get_user_id};
+use crate::service::{cost_ledger, telemetry};
 use crate::service::rate_limiter::RateLimitError;
 use serde_json::json;
 
@@ -98,8 +99,15 @@ pub async fn rate_limit_middleware(
 
     match rate_limit_result {
         Ok(result) => {
-            // Rate limit not exceeded - add headers and continue
-            let mut res = next.call(req).await?;
+            // Rate limit not exceeded - add headers and continue; provider costs go to this user
+            let path = req.path().to_string();
+            let mut res = cost_ledger::attribute_to(user_id.clone(), next.call(req)).await?;
+
+            // user_id may come from an unverified token; count usage only under claims the
+            // route's JWT validator accepted
+            if let Some(claims) = res.request().extensions().get::<SupabaseClaims>() {
+                telemetry::record_request(&get_supabase_user_id(claims), &path);
+            }
             
             // Add rate limit headers
             res.headers_mut().insert(
@@ -162,7 +170,7 @@ pub async fn rate_limit_middleware(
             // This prevents Redis outages from breaking the entire API
             log::error!("Rate limit Redis error: {}, allowing request", e);
             
-            let mut res = next.call(req).await?;
+            let mut res = cost_ledger::attribute_to(user_id.clone(), next.call(req)).await?;
             
             // Add error header to indicate rate limit check was skipped
             res.headers_mut().insert(
@@ -0,0 +1,65 @@
+use actix_web::{
+    body::{self, BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{self, HeaderName, HeaderValue},
+    middleware::Next,
+    Error,
+};
+
+use crate::models::serialization::{Envelope, CURRENT_FORMAT_VERSION};
+
+/// Request header a client sets to receive the current response format
+pub const RESPONSE_FORMAT_HEADER: &str = "x-response-format";
+
+/// Compatibility shim between the legacy and current response formats
+///
+/// Handlers keep producing the legacy bodies. Clients that send `X-Response-Format: 2`
+/// get them rewritten into the camelCase, versioned [`Envelope`] format; clients that
+/// don't are served exactly what they got before.
+pub async fn response_format_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let requested = req
+        .headers()
+        .get(RESPONSE_FORMAT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u32>().ok());
+    let res = next.call(req).await?;
+    if requested != Some(CURRENT_FORMAT_VERSION) {
+        return Ok(res.map_into_boxed_body());
+    }
+    into_current_format(res).await
+}
+
+/// Rewrite a JSON response into the current format; other responses pass through
+pub async fn into_current_format<B: MessageBody + 'static>(
+    res: ServiceResponse<B>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let is_json = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return Ok(res.map_into_boxed_body());
+    }
+
+    let is_success = res.status().is_success();
+    let (req, res) = res.into_parts();
+    let (mut head, body) = res.into_parts();
+    let bytes = body::to_bytes(body).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        actix_web::error::ErrorInternalServerError(e.to_string())
+    })?;
+    let Ok(legacy) = serde_json::from_slice(&bytes) else {
+        return Ok(ServiceResponse::new(req, head.set_body(bytes).map_into_boxed_body()));
+    };
+
+    let converted = serde_json::to_vec(&Envelope::from_legacy(legacy, is_success))?;
+    head.headers_mut().insert(
+        HeaderName::from_static(RESPONSE_FORMAT_HEADER),
+        HeaderValue::from(CURRENT_FORMAT_VERSION),
+    );
+    Ok(ServiceResponse::new(req, head.set_body(converted).map_into_boxed_body()))
+}
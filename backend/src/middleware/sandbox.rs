@@ -0,0 +1,35 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    Error, HttpResponse,
+};
+use serde_json::json;
+
+use crate::service::sandbox;
+
+/// Refuse sandbox-token requests to endpoints with side effects outside the sandbox tenant
+///
+/// Runs inside the version middleware, so paths are already unprefixed. Every other token
+/// passes straight through; authentication itself happens in the route scopes.
+pub async fn sandbox_guard_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let is_sandbox = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| sandbox::claims_for_token(token).is_some());
+
+    if is_sandbox && sandbox::blocked_path(req.path()) {
+        let response = HttpResponse::Forbidden().json(json!({
+            "success": false,
+            "message": "Not available in the API sandbox",
+        }));
+        return Ok(req.into_response(response));
+    }
+    next.call(req).await.map(ServiceResponse::map_into_boxed_body)
+}
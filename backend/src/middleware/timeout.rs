@@ -0,0 +1,52 @@
+use actix_web::{
+    body::{BoxBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error, HttpResponse,
+};
+use serde_json::json;
+use std::time::Duration;
+
+/// Analytics queries should finish well within this
+const ANALYTICS_TIMEOUT: Duration = Duration::from_secs(30);
+/// AI generation waits on the model provider; streaming responses only count until the
+/// stream starts
+const AI_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Per-route timeout for analytics endpoints
+pub async fn analytics_timeout_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    with_timeout(req, next, ANALYTICS_TIMEOUT).await
+}
+
+/// Per-route timeout for AI endpoints
+pub async fn ai_timeout_middleware(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    with_timeout(req, next, AI_TIMEOUT).await
+}
+
+/// Run the handler with a deadline. On timeout the handler future is dropped, which
+/// cancels its in-flight Turso queries and provider calls (the same happens when the
+/// client disconnects), and the client gets a 504.
+async fn with_timeout(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+    timeout: Duration,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let request = req.request().clone();
+    match tokio::time::timeout(timeout, next.call(req)).await {
+        Ok(res) => res.map(ServiceResponse::map_into_boxed_body),
+        Err(_) => {
+            log::warn!("{} {} timed out after {}s", request.method(), request.path(), timeout.as_secs());
+            let response = HttpResponse::GatewayTimeout().json(json!({
+                "success": false,
+                "message": "The request took too long and was cancelled",
+            }));
+            Ok(ServiceResponse::new(request, response))
+        }
+    }
+}
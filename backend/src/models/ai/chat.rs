@@ -32,6 +32,9 @@ pub struct ChatMessage {
     #[serde(rename = "created_at")]
     pub timestamp: DateTime<Utc>,
     pub context_vectors: Option<Vec<String>>, // Vector IDs used for context
+    /// What those vectors pointed at (trade, note, ...), kept so history can be exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sources: Option<Vec<ContextSource>>,
     pub token_count: Option<u32>,
 }
 
@@ -44,6 +47,7 @@ impl ChatMessage {
             content,
             timestamp: Utc::now(),
             context_vectors: None,
+            sources: None,
             token_count: None,
         }
     }
@@ -53,6 +57,13 @@ impl ChatMessage {
         self
     }
 
+    /// Record the retrieved sources, along with their vector IDs
+    pub fn with_sources(mut self, sources: &[ContextSource]) -> Self {
+        self.context_vectors = Some(sources.iter().map(|s| s.vector_id.clone()).collect());
+        self.sources = Some(sources.to_vec());
+        self
+    }
+
     pub fn with_token_count(mut self, token_count: u32) -> Self {
         self.token_count = Some(token_count);
         self
@@ -69,6 +80,9 @@ pub struct ChatSession {
     pub updated_at: DateTime<Utc>,
     pub message_count: u32,
     pub last_message_at: Option<DateTime<Utc>>,
+    /// Report whose snapshot is pinned as this session's context
+    #[serde(default)]
+    pub report_id: Option<String>,
 }
 
 impl ChatSession {
@@ -82,6 +96,7 @@ impl ChatSession {
             updated_at: now,
             message_count: 0,
             last_message_at: None,
+            report_id: None,
         }
     }
 
@@ -11,17 +11,20 @@
 //! - **Context Formatting**: Formats trading data context for AI consumption
 //! - **Dynamic Prompts**: Selects appropriate system prompts based on query type
 //! - **Configurable Templates**: Allows runtime configuration of prompt templates
+//! - **Personas**: The coaching voice the user picked in their profile shapes every prompt
 //! 
 //! ## Usage Example
 //! 
-//! ```rust
+//! ```ignore
 //! use crate::models::ai::chat_templates::{ChatPromptConfig, ContextFormatter};
 //! 
 //! // Create a chat service with enhanced prompts
 //! let mut chat_service = AIChatService::new(
 //!     vectorization_service,
-//!     gemini_client,
+//!     hybrid_search_service,
+//!     openrouter_client,
 //!     turso_client,
+//!     voyager_client,
 //!     10, // max_context_vectors
 //! );
 //! 
@@ -38,6 +41,7 @@
 //! // 4. Generate response with enhanced system prompt
 //! ```
 
+use libsql::{params, Connection};
 use serde::{Deserialize, Serialize};
 
 /// System prompt template for chat interactions
@@ -68,6 +72,27 @@ pub struct ChatPromptConfig {
     pub templates: Vec<QueryPromptTemplate>,
     pub context_max_length: usize,
     pub include_relevance_scores: bool,
+    pub persona_templates: Vec<PersonaPromptTemplate>,
+}
+
+/// Coaching voice the user picks for chat and insights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiPersona {
+    StrictRiskManager,
+    SupportiveCoach,
+    QuantAnalyst,
+}
+
+pub const AI_PERSONAS: &[AiPersona] = &[AiPersona::StrictRiskManager, AiPersona::SupportiveCoach, AiPersona::QuantAnalyst];
+
+/// Tone and focus a persona adds to the system prompt
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonaPromptTemplate {
+    pub persona: AiPersona,
+    pub voice: String,
+    pub tone_instructions: String,
+    pub focus: String,
 }
 
 impl SystemPromptTemplate {
@@ -183,9 +208,80 @@ impl QueryPromptTemplate {
     }
 }
 
-impl ChatPromptConfig {
+impl AiPersona {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().replace(['-', ' '], "_").as_str() {
+            "strict_risk_manager" => Some(AiPersona::StrictRiskManager),
+            "supportive_coach" => Some(AiPersona::SupportiveCoach),
+            "quant_analyst" => Some(AiPersona::QuantAnalyst),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AiPersona::StrictRiskManager => "strict_risk_manager",
+            AiPersona::SupportiveCoach => "supportive_coach",
+            AiPersona::QuantAnalyst => "quant_analyst",
+        }
+    }
+}
+
+impl PersonaPromptTemplate {
+    /// Blunt, rule-first risk manager
+    pub fn strict_risk_manager() -> Self {
+        Self {
+            persona: AiPersona::StrictRiskManager,
+            voice: "You are the user's strict risk manager.".to_string(),
+            tone_instructions: "Be direct and blunt. Call out every broken rule, oversized position and missing stop by name, and do not soften bad news.".to_string(),
+            focus: "Lead with risk: position size, stop discipline, drawdown and rule compliance come before P&L.".to_string(),
+        }
+    }
+
+    /// Encouraging coach who frames mistakes as things to practice
+    pub fn supportive_coach() -> Self {
+        Self {
+            persona: AiPersona::SupportiveCoach,
+            voice: "You are the user's supportive trading coach.".to_string(),
+            tone_instructions: "Be warm and encouraging. Acknowledge what went well before what went wrong, and frame mistakes as habits to practice.".to_string(),
+            focus: "Lead with process and psychology: consistency, emotional control and one concrete next step.".to_string(),
+        }
+    }
+
+    /// Numbers-first analyst
+    pub fn quant_analyst() -> Self {
+        Self {
+            persona: AiPersona::QuantAnalyst,
+            voice: "You are a quantitative analyst reviewing the user's trading.".to_string(),
+            tone_instructions: "Be neutral and precise. Back every claim with a number, state sample sizes, and say when a sample is too small to conclude anything.".to_string(),
+            focus: "Lead with statistics: expectancy, win rate, R-multiples, distributions and how they change over time.".to_string(),
+        }
+    }
+
+    pub fn for_persona(persona: AiPersona) -> Self {
+        match persona {
+            AiPersona::StrictRiskManager => Self::strict_risk_manager(),
+            AiPersona::SupportiveCoach => Self::supportive_coach(),
+            AiPersona::QuantAnalyst => Self::quant_analyst(),
+        }
+    }
+
+    /// Section appended to chat and insight system prompts
+    pub fn prompt_section(&self) -> String {
+        format!("Persona:\n{} {}\n{}", self.voice, self.tone_instructions, self.focus)
+    }
+}
+
+/// Persona stored in the user's profile; None when unset, which keeps the default voice
+pub async fn user_persona(conn: &Connection) -> Option<AiPersona> {
+    let mut rows = conn.prepare("SELECT ai_persona FROM user_profile LIMIT 1").await.ok()?.query(params![]).await.ok()?;
+    let value: Option<String> = rows.next().await.ok().flatten()?.get(0).ok()?;
+    value.as_deref().and_then(AiPersona::parse)
+}
+
+impl Default for ChatPromptConfig {
     /// Create default chat prompt configuration
-    pub fn default() -> Self {
+    fn default() -> Self {
         Self {
             default_template: QueryPromptTemplate::general_trading_assistant(),
             templates: vec![
@@ -197,9 +293,12 @@ impl ChatPromptConfig {
             ],
             context_max_length: 4000,
             include_relevance_scores: true,
+            persona_templates: AI_PERSONAS.iter().map(|p| PersonaPromptTemplate::for_persona(*p)).collect(),
         }
     }
-    
+}
+
+impl ChatPromptConfig {
     /// Detect query type and return appropriate template
     pub fn detect_query_type(&self, query: &str) -> &QueryPromptTemplate {
         let query_lower = query.to_lowercase();
@@ -217,6 +316,15 @@ impl ChatPromptConfig {
         &self.default_template
     }
     
+    /// Template for a persona, falling back to the built-in one when it isn't configured
+    pub fn persona_template(&self, persona: AiPersona) -> PersonaPromptTemplate {
+        self.persona_templates
+            .iter()
+            .find(|t| t.persona == persona)
+            .cloned()
+            .unwrap_or_else(|| PersonaPromptTemplate::for_persona(persona))
+    }
+
     /// Get template by name
     pub fn get_template_by_name(&self, name: &str) -> Option<&QueryPromptTemplate> {
         self.templates.iter().find(|t| {
@@ -301,4 +409,23 @@ impl ContextFormatter {
             template.response_format
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_persona_parse_and_templates() {
+        assert_eq!(AiPersona::parse("strict-risk-manager"), Some(AiPersona::StrictRiskManager));
+        assert_eq!(AiPersona::parse(" Quant Analyst "), Some(AiPersona::QuantAnalyst));
+        assert_eq!(AiPersona::parse("drill_sergeant"), None);
+
+        let config = ChatPromptConfig::default();
+        for persona in AI_PERSONAS {
+            assert_eq!(AiPersona::parse(persona.code()), Some(*persona));
+            assert_eq!(config.persona_template(*persona).persona, *persona);
+        }
+        assert!(config.persona_template(AiPersona::SupportiveCoach).prompt_section().starts_with("Persona:\n"));
+    }
+}
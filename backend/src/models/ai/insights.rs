@@ -3,6 +3,7 @@
 use crate::models::stock::stocks::TimeRange;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 /// Insight type enumeration
@@ -14,17 +15,72 @@ pub enum InsightType {
     BehavioralAnalysis,
     MarketAnalysis,
     OpportunityDetection,
+    /// Scheduled weekly coaching digest
+    WeeklyCoach,
+}
+
+impl InsightType {
+    pub const ALL: [InsightType; 7] = [
+        InsightType::TradingPatterns,
+        InsightType::PerformanceAnalysis,
+        InsightType::RiskAssessment,
+        InsightType::BehavioralAnalysis,
+        InsightType::MarketAnalysis,
+        InsightType::OpportunityDetection,
+        InsightType::WeeklyCoach,
+    ];
+
+    /// Value stored in `ai_insights.insight_type` and allowed by its CHECK constraint
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InsightType::TradingPatterns => "trading_patterns",
+            InsightType::PerformanceAnalysis => "performance_analysis",
+            InsightType::RiskAssessment => "risk_assessment",
+            InsightType::BehavioralAnalysis => "behavioral_analysis",
+            InsightType::MarketAnalysis => "market_analysis",
+            InsightType::OpportunityDetection => "opportunity_detection",
+            InsightType::WeeklyCoach => "weekly_coach",
+        }
+    }
+
+    /// Read a stored value; rows from before values were stored plain hold the JSON-quoted
+    /// variant name (`"WeeklyCoach"`)
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|t| t.as_str() == value)
+            .cloned()
+            .or_else(|| serde_json::from_str(value).ok())
+    }
 }
 
 impl std::fmt::Display for InsightType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What produced an insight: the LLM, or the deterministic rules engine used as a fallback
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InsightSource {
+    #[default]
+    Llm,
+    Rules,
+}
+
+impl InsightSource {
+    pub fn as_str(&self) -> &'static str {
         match self {
-            InsightType::TradingPatterns => write!(f, "trading_patterns"),
-            InsightType::PerformanceAnalysis => write!(f, "performance_analysis"),
-            InsightType::RiskAssessment => write!(f, "risk_assessment"),
-            InsightType::BehavioralAnalysis => write!(f, "behavioral_analysis"),
-            InsightType::MarketAnalysis => write!(f, "market_analysis"),
-            InsightType::OpportunityDetection => write!(f, "opportunity_detection"),
+            InsightSource::Llm => "llm",
+            InsightSource::Rules => "rules",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "rules" => InsightSource::Rules,
+            _ => InsightSource::Llm,
         }
     }
 }
@@ -36,6 +92,9 @@ pub struct InsightRequest {
     pub insight_type: InsightType,
     pub include_predictions: Option<bool>,
     pub force_regenerate: Option<bool>,
+    /// Force a generator; by default the LLM is tried first and rules are the fallback
+    #[serde(default)]
+    pub source: Option<InsightSource>,
 }
 
 /// Insight structure
@@ -48,12 +107,32 @@ pub struct Insight {
     pub title: String,
     pub content: String,
     pub key_findings: Vec<String>,
+    /// Evidence behind each key finding, by position; None where the model gave none
+    #[serde(default)]
+    pub finding_evidence: Vec<Option<FindingEvidence>>,
     pub recommendations: Vec<String>,
     pub data_sources: Vec<String>, // Which trades/data informed this
     pub confidence_score: f32,
     pub generated_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
     pub metadata: InsightMetadata,
+    #[serde(default)]
+    pub source: InsightSource,
+    /// Served from cache because the AI provider failed; a regeneration is queued
+    #[serde(default)]
+    pub stale: bool,
+}
+
+/// What a key finding claims to be based on, so its numbers can be checked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FindingEvidence {
+    /// Metric values the finding cites, keyed by the analytics metric name (e.g. `win_rate`)
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    #[serde(default)]
+    pub stock_trade_ids: Vec<i64>,
+    #[serde(default)]
+    pub option_trade_ids: Vec<i64>,
 }
 
 /// Insight metadata
@@ -82,6 +161,7 @@ impl Insight {
             title,
             content,
             key_findings: Vec::new(),
+            finding_evidence: Vec::new(),
             recommendations: Vec::new(),
             data_sources: Vec::new(),
             confidence_score: 0.0,
@@ -94,6 +174,8 @@ impl Insight {
                 processing_time_ms: 0,
                 data_quality_score: 0.0,
             },
+            source: InsightSource::Llm,
+            stale: false,
         }
     }
 
@@ -102,6 +184,11 @@ impl Insight {
         self
     }
 
+    pub fn with_finding_evidence(mut self, evidence: Vec<Option<FindingEvidence>>) -> Self {
+        self.finding_evidence = evidence;
+        self
+    }
+
     pub fn with_recommendations(mut self, recommendations: Vec<String>) -> Self {
         self.recommendations = recommendations;
         self
@@ -117,6 +204,11 @@ impl Insight {
         self
     }
 
+    pub fn with_source(mut self, source: InsightSource) -> Self {
+        self.source = source;
+        self
+    }
+
     pub fn is_expired(&self) -> bool {
         if let Some(expires_at) = self.expires_at {
             Utc::now() > expires_at
@@ -150,6 +242,7 @@ pub struct InsightSummary {
     pub expires_at: Option<DateTime<Utc>>,
     pub key_findings_count: u32,
     pub recommendations_count: u32,
+    pub source: InsightSource,
 }
 
 impl From<Insight> for InsightSummary {
@@ -164,6 +257,7 @@ impl From<Insight> for InsightSummary {
             expires_at: insight.expires_at,
             key_findings_count: insight.key_findings.len() as u32,
             recommendations_count: insight.recommendations.len() as u32,
+            source: insight.source,
         }
     }
 }
@@ -176,6 +270,8 @@ pub enum InsightGenerationStatus {
     Completed,
     Failed,
     Expired,
+    /// Generation failed at the AI provider; retried by the queued regeneration job
+    Queued,
 }
 
 /// Insight generation task
@@ -223,6 +319,12 @@ impl InsightGenerationTask {
         self.completed_at = Some(Utc::now());
         self.error_message = Some(error_message);
     }
+
+    /// Park the task until the AI provider recovers
+    pub fn queue(&mut self, error_message: String) {
+        self.status = InsightGenerationStatus::Queued;
+        self.error_message = Some(error_message);
+    }
 }
 
 /// Insight analytics data
@@ -308,6 +410,15 @@ mod tests {
         assert_eq!(insight.confidence_score, 0.0);
     }
 
+    #[test]
+    fn test_insight_type_stored_form() {
+        assert_eq!(InsightType::WeeklyCoach.as_str(), "weekly_coach");
+        assert_eq!(InsightType::parse("weekly_coach"), Some(InsightType::WeeklyCoach));
+        assert_eq!(InsightType::parse("\"RiskAssessment\""), Some(InsightType::RiskAssessment));
+        assert_eq!(InsightType::parse("weekly"), None);
+        assert!(InsightType::ALL.iter().all(|t| InsightType::parse(t.as_str()).as_ref() == Some(t)));
+    }
+
     #[test]
     fn test_insight_with_findings() {
         let insight = Insight::new(
@@ -349,6 +460,7 @@ mod tests {
             insight_type: InsightType::TradingPatterns,
             include_predictions: Some(true),
             force_regenerate: Some(false),
+            source: None,
         };
 
         let mut task = InsightGenerationTask::new("user123".to_string(), request);
@@ -361,6 +473,9 @@ mod tests {
         task.complete("insight123".to_string());
         assert_eq!(task.status, InsightGenerationStatus::Completed);
         assert_eq!(task.result_insight_id, Some("insight123".to_string()));
+
+        task.queue("provider quota exhausted".to_string());
+        assert_eq!(serde_json::to_string(&task.status).unwrap(), "\"Queued\"");
     }
 
     #[test]
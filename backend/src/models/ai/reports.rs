@@ -2,6 +2,7 @@
 
 use crate::models::stock::stocks::TimeRange;
 use crate::models::ai::insights::Insight;
+use crate::models::analytics::CustomMetricValue;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
@@ -18,17 +19,42 @@ pub enum ReportType {
     Market,
 }
 
-impl std::fmt::Display for ReportType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ReportType {
+    pub const ALL: [ReportType; 6] = [
+        ReportType::Comprehensive,
+        ReportType::Performance,
+        ReportType::Risk,
+        ReportType::Trading,
+        ReportType::Behavioral,
+        ReportType::Market,
+    ];
+
+    /// Value stored in `ai_reports.report_type` and allowed by its CHECK constraint
+    pub fn as_str(&self) -> &'static str {
         match self {
-            ReportType::Comprehensive => write!(f, "comprehensive"),
-            ReportType::Performance => write!(f, "performance"),
-            ReportType::Risk => write!(f, "risk"),
-            ReportType::Trading => write!(f, "trading"),
-            ReportType::Behavioral => write!(f, "behavioral"),
-            ReportType::Market => write!(f, "market"),
+            ReportType::Comprehensive => "comprehensive",
+            ReportType::Performance => "performance",
+            ReportType::Risk => "risk",
+            ReportType::Trading => "trading",
+            ReportType::Behavioral => "behavioral",
+            ReportType::Market => "market",
         }
     }
+
+    /// Read a stored value, accepting the JSON-quoted form older rows were written with
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|t| t.as_str() == value)
+            .cloned()
+            .or_else(|| serde_json::from_str(value).ok())
+    }
+}
+
+impl std::fmt::Display for ReportType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 /// Report section enumeration
@@ -104,6 +130,9 @@ pub struct AnalyticsData {
     pub winning_trades: u32,
     pub losing_trades: u32,
     pub break_even_trades: u32,
+    /// The user's own KPIs for the report period
+    #[serde(default)]
+    pub custom_metrics: Vec<CustomMetricValue>,
 }
 
 /// Trade data for reports
@@ -264,6 +293,7 @@ impl TradingReport {
                 winning_trades: 0,
                 losing_trades: 0,
                 break_even_trades: 0,
+                custom_metrics: Vec::new(),
             },
             insights: Vec::new(),
             trades: Vec::new(),
@@ -480,6 +510,13 @@ mod tests {
         assert_eq!(report.analytics.total_trades, 0);
     }
 
+    #[test]
+    fn test_report_type_stored_form() {
+        assert_eq!(ReportType::parse("risk"), Some(ReportType::Risk));
+        assert_eq!(ReportType::parse("\"behavioral\""), Some(ReportType::Behavioral));
+        assert_eq!(ReportType::parse("weekly"), None);
+    }
+
     #[test]
     fn test_trading_report_with_data() {
         let analytics = AnalyticsData {
@@ -500,6 +537,7 @@ mod tests {
             winning_trades: 7,
             losing_trades: 3,
             break_even_trades: 0,
+            custom_metrics: Vec::new(),
         };
 
         let report = TradingReport::new(
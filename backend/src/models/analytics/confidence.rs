@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// Groups with fewer closed trades than this are flagged `low_sample`
+pub const LOW_SAMPLE_TRADES: u32 = 30;
+/// z score of a two-sided 95% interval
+const Z_95: f64 = 1.96;
+
+/// How much a group's win rate and expectancy can be trusted given how few trades it has
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SampleConfidence {
+    pub sample_size: u32,
+    /// 95% Wilson interval around the win rate, in percent
+    pub win_rate_low: f64,
+    pub win_rate_high: f64,
+    /// Mean P&L per trade and the standard error of that mean
+    pub expectancy: f64,
+    pub expectancy_standard_error: f64,
+    /// Fewer than `LOW_SAMPLE_TRADES` trades; rates and averages are anecdotes
+    pub low_sample: bool,
+}
+
+impl SampleConfidence {
+    /// From the P&L of every closed trade in the group, in any order
+    pub fn from_pnls(pnls: &[f64]) -> Self {
+        let n = pnls.len() as u32;
+        let wins = pnls.iter().filter(|pnl| **pnl > 0.0).count() as u32;
+        let (win_rate_low, win_rate_high) = wilson_interval(wins, n);
+
+        let expectancy = if n > 0 { pnls.iter().sum::<f64>() / n as f64 } else { 0.0 };
+        let expectancy_standard_error = if n > 1 {
+            let variance = pnls.iter().map(|pnl| (pnl - expectancy).powi(2)).sum::<f64>() / (n - 1) as f64;
+            (variance / n as f64).sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            sample_size: n,
+            win_rate_low,
+            win_rate_high,
+            expectancy,
+            expectancy_standard_error,
+            low_sample: n < LOW_SAMPLE_TRADES,
+        }
+    }
+}
+
+/// 95% Wilson score interval for `wins` out of `trades`, in percent. Unlike the normal
+/// approximation it stays inside 0-100 and is honest about tiny samples: 5 wins out of 5
+/// still leaves the lower bound near 57%.
+pub fn wilson_interval(wins: u32, trades: u32) -> (f64, f64) {
+    if trades == 0 {
+        return (0.0, 0.0);
+    }
+    let n = trades as f64;
+    let p = wins as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let denominator = 1.0 + z2 / n;
+    let center = (p + z2 / (2.0 * n)) / denominator;
+    let margin = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+    (((center - margin) * 100.0).max(0.0), ((center + margin) * 100.0).min(100.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wilson_interval_is_wide_for_small_samples() {
+        let (low, high) = wilson_interval(5, 5);
+        assert!((low - 56.55).abs() < 0.1 && high == 100.0);
+
+        let (low, high) = wilson_interval(60, 100);
+        assert!((low - 50.2).abs() < 0.1 && (high - 69.1).abs() < 0.1);
+        assert_eq!(wilson_interval(0, 0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_expectancy_standard_error_and_low_sample() {
+        let confidence = SampleConfidence::from_pnls(&[100.0, -50.0, 100.0, -50.0]);
+        assert_eq!(confidence.expectancy, 25.0);
+        assert!((confidence.expectancy_standard_error - 43.30).abs() < 0.01);
+        assert!(confidence.low_sample);
+
+        let many = SampleConfidence::from_pnls(&[10.0; 30]);
+        assert!(!many.low_sample);
+        assert_eq!(many.expectancy_standard_error, 0.0);
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+/// Consistency score modeled after funded-account evaluation rules.
+///
+/// Combines how concentrated profits are in a single day, how often playbook
+/// rules were followed, and how well drawdowns were contained.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyScore {
+    // Overall 0-100 score and letter grade
+    pub overall_score: f64,
+    pub grade: String,
+
+    // Component scores (0-100)
+    pub profit_concentration_score: f64,
+    pub rule_compliance_score: Option<f64>,
+    pub drawdown_discipline_score: f64,
+
+    // Profit distribution inputs
+    pub trading_days: u32,
+    pub profitable_days: u32,
+    pub total_pnl: f64,
+    pub best_day_pnl: f64,
+    pub best_day_share_percentage: f64,
+
+    // Rule compliance inputs
+    pub rules_checked: u32,
+    pub rules_followed: u32,
+
+    // Drawdown inputs
+    pub maximum_drawdown: f64,
+    pub worst_day_pnl: f64,
+
+    /// True when no single day accounts for more than the configured share of profits
+    pub passes_consistency_rule: bool,
+    pub consistency_rule_threshold_percentage: f64,
+}
+
+/// Stored snapshot of a consistency score for history tracking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyScoreSnapshot {
+    pub id: String,
+    pub snapshot_date: String,
+    pub time_range: String,
+    pub overall_score: f64,
+    pub profit_concentration_score: f64,
+    pub rule_compliance_score: Option<f64>,
+    pub drawdown_discipline_score: f64,
+    pub best_day_share_percentage: f64,
+    pub trading_days: u32,
+    pub total_pnl: f64,
+    pub created_at: String,
+}
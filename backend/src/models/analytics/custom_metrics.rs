@@ -0,0 +1,409 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use super::{CoreMetrics, PerformanceMetrics, RiskMetrics};
+
+/// Longest formula accepted, in characters
+pub const MAX_FORMULA_LENGTH: usize = 500;
+/// Deepest parenthesis / function nesting accepted
+const MAX_NESTING: usize = 32;
+
+/// Shorter names for commonly used built-in metrics
+const ALIASES: [(&str, &str); 5] = [
+    ("net_pnl", "net_profit_loss"),
+    ("max_drawdown", "maximum_drawdown"),
+    ("max_drawdown_pct", "maximum_drawdown_percentage"),
+    ("expectancy", "trade_expectancy"),
+    ("trades", "total_trades"),
+];
+
+/// User-defined KPI, a formula over the built-in metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomMetricDefinition {
+    pub id: String,
+    pub name: String,
+    /// e.g. `net_pnl / max_drawdown`
+    pub formula: String,
+    pub description: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomMetricRequest {
+    pub name: String,
+    pub formula: String,
+    pub description: Option<String>,
+}
+
+/// A custom metric evaluated for one period
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomMetricValue {
+    pub id: String,
+    pub name: String,
+    pub formula: String,
+    /// None when the formula couldn't be evaluated, e.g. a division by zero
+    pub value: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Built-in metric values a formula can reference.
+///
+/// Every numeric field of the core, risk and performance metrics is available by its
+/// serialized name (`win_rate`) or qualified by category (`risk.sharpe_ratio`); bare names
+/// resolve core first, then risk, then performance.
+#[derive(Debug, Clone, Default)]
+pub struct MetricVariables {
+    values: HashMap<String, f64>,
+}
+
+impl MetricVariables {
+    pub fn new(core: &CoreMetrics, risk: &RiskMetrics, performance: &PerformanceMetrics) -> Self {
+        let mut variables = Self::default();
+        variables.add_category("core", serde_json::to_value(core).unwrap_or(Value::Null));
+        variables.add_category("risk", serde_json::to_value(risk).unwrap_or(Value::Null));
+        variables.add_category("performance", serde_json::to_value(performance).unwrap_or(Value::Null));
+        for (alias, target) in ALIASES {
+            if let Some(value) = variables.values.get(target).copied() {
+                variables.values.insert(alias.to_string(), value);
+            }
+        }
+        variables
+    }
+
+    fn add_category(&mut self, category: &str, metrics: Value) {
+        let Value::Object(fields) = metrics else { return };
+        for (name, value) in fields {
+            let Some(value) = value.as_f64() else { continue };
+            self.values.insert(format!("{}.{}", category, name), value);
+            self.values.entry(name).or_insert(value);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.values.get(name).copied()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Variable(String),
+    Negate(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Abs,
+    Min,
+    Max,
+    Sqrt,
+}
+
+/// Parsed custom metric formula.
+///
+/// Formulas are arithmetic only: numbers, metric names, `+ - * /`, parentheses and the
+/// functions `abs`, `min`, `max` and `sqrt`. Nothing is executed beyond evaluating that
+/// expression tree, so a formula can't reach anything other than the metric values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: Expr,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        if source.trim().is_empty() {
+            return Err("Formula is empty".to_string());
+        }
+        if source.chars().count() > MAX_FORMULA_LENGTH {
+            return Err(format!("Formula is longer than {} characters", MAX_FORMULA_LENGTH));
+        }
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0, depth: 0 };
+        let expr = parser.expression()?;
+        if parser.pos < tokens.len() {
+            return Err(format!("Unexpected {} in formula", describe(&tokens[parser.pos])));
+        }
+        Ok(Self { expr })
+    }
+
+    /// Parse and check that every metric name exists
+    pub fn parse_checked(source: &str) -> Result<Self, String> {
+        let formula = Self::parse(source)?;
+        let known = MetricVariables::new(&CoreMetrics::default(), &RiskMetrics::default(), &PerformanceMetrics::default());
+        if let Some(unknown) = formula.variables().into_iter().find(|name| known.get(name).is_none()) {
+            return Err(format!("Unknown metric '{}'", unknown));
+        }
+        Ok(formula)
+    }
+
+    /// Metric names referenced by the formula
+    pub fn variables(&self) -> Vec<String> {
+        fn collect(expr: &Expr, names: &mut Vec<String>) {
+            match expr {
+                Expr::Number(_) => {}
+                Expr::Variable(name) => {
+                    if !names.contains(name) {
+                        names.push(name.clone());
+                    }
+                }
+                Expr::Negate(inner) => collect(inner, names),
+                Expr::Binary(_, left, right) => {
+                    collect(left, names);
+                    collect(right, names);
+                }
+                Expr::Call(_, args) => args.iter().for_each(|arg| collect(arg, names)),
+            }
+        }
+        let mut names = Vec::new();
+        collect(&self.expr, &mut names);
+        names
+    }
+
+    /// Whether the formula references any risk or performance metric, which cost more to compute
+    pub fn needs_extended_metrics(&self) -> bool {
+        let core = serde_json::to_value(CoreMetrics::default()).unwrap_or(Value::Null);
+        self.variables().iter().any(|name| {
+            let name = ALIASES.iter().find(|(alias, _)| *alias == name.as_str()).map_or(name.as_str(), |(_, target)| *target);
+            let name = name.strip_prefix("core.").unwrap_or(name);
+            core.get(name).is_none()
+        })
+    }
+
+    pub fn evaluate(&self, variables: &MetricVariables) -> Result<f64, String> {
+        let value = eval(&self.expr, variables)?;
+        if value.is_finite() { Ok(value) } else { Err("Result is not a finite number".to_string()) }
+    }
+}
+
+fn eval(expr: &Expr, variables: &MetricVariables) -> Result<f64, String> {
+    match expr {
+        Expr::Number(value) => Ok(*value),
+        Expr::Variable(name) => variables.get(name).ok_or_else(|| format!("Unknown metric '{}'", name)),
+        Expr::Negate(inner) => Ok(-eval(inner, variables)?),
+        Expr::Binary(op, left, right) => {
+            let (left, right) = (eval(left, variables)?, eval(right, variables)?);
+            match op {
+                '+' => Ok(left + right),
+                '-' => Ok(left - right),
+                '*' => Ok(left * right),
+                _ if right == 0.0 => Err("Division by zero".to_string()),
+                _ => Ok(left / right),
+            }
+        }
+        Expr::Call(function, args) => {
+            let args = args.iter().map(|arg| eval(arg, variables)).collect::<Result<Vec<_>, _>>()?;
+            match function {
+                Function::Abs => Ok(args[0].abs()),
+                Function::Sqrt if args[0] < 0.0 => Err("Square root of a negative number".to_string()),
+                Function::Sqrt => Ok(args[0].sqrt()),
+                Function::Min => Ok(args.iter().copied().fold(f64::INFINITY, f64::min)),
+                Function::Max => Ok(args.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '+' | '-' | '*' | '/' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            _ if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                let value = text.parse::<f64>().map_err(|_| format!("Invalid number '{}'", text))?;
+                tokens.push(Token::Number(value));
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect::<String>().to_ascii_lowercase()));
+            }
+            _ => return Err(format!("Unexpected character '{}' in formula", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => format!("number {}", value),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Op(op) => format!("'{}'", op),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Comma => "','".to_string(),
+    }
+}
+
+/// Recursive descent parser: expression := term (('+' | '-') term)*,
+/// term := unary (('*' | '/') unary)*, unary := '-' unary | primary
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<Expr, String> {
+        let mut left = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            return self.nested(|parser| Ok(Expr::Negate(Box::new(parser.unary()?))));
+        }
+        if let Some(Token::Op('+')) = self.peek() {
+            self.pos += 1;
+            return self.unary();
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::LParen) => self.nested(|parser| {
+                let expr = parser.expression()?;
+                parser.expect_rparen()?;
+                Ok(expr)
+            }),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                let function = match name.as_str() {
+                    "abs" => Function::Abs,
+                    "min" => Function::Min,
+                    "max" => Function::Max,
+                    "sqrt" => Function::Sqrt,
+                    other => return Err(format!("Unknown function '{}'", other)),
+                };
+                self.pos += 1;
+                self.nested(|parser| {
+                    let mut args = vec![parser.expression()?];
+                    while parser.peek() == Some(&Token::Comma) {
+                        parser.pos += 1;
+                        args.push(parser.expression()?);
+                    }
+                    parser.expect_rparen()?;
+                    let single = matches!(function, Function::Abs | Function::Sqrt);
+                    if single && args.len() != 1 {
+                        return Err(format!("{} takes one argument", name));
+                    }
+                    Ok(Expr::Call(function, args))
+                })
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Variable(name)),
+            Some(token) => Err(format!("Unexpected {} in formula", describe(&token))),
+            None => Err("Formula ends unexpectedly".to_string()),
+        }
+    }
+
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<Expr, String>) -> Result<Expr, String> {
+        self.depth += 1;
+        if self.depth > MAX_NESTING {
+            return Err("Formula is nested too deeply".to_string());
+        }
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn expect_rparen(&mut self) -> Result<(), String> {
+        match self.next() {
+            Some(Token::RParen) => Ok(()),
+            _ => Err("Missing ')' in formula".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formula_parse_and_evaluate() {
+        let core = CoreMetrics { net_profit_loss: 1200.0, total_trades: 40, ..Default::default() };
+        let risk = RiskMetrics { maximum_drawdown: 400.0, ..Default::default() };
+        let variables = MetricVariables::new(&core, &risk, &PerformanceMetrics::default());
+
+        let formula = Formula::parse_checked("net_pnl / max_drawdown").unwrap();
+        assert_eq!(formula.evaluate(&variables), Ok(3.0));
+        assert!(formula.needs_extended_metrics());
+
+        let formula = Formula::parse_checked("-(core.net_profit_loss - 200) / max(trades, 1) * 2").unwrap();
+        assert_eq!(formula.evaluate(&variables), Ok(-50.0));
+        assert!(!formula.needs_extended_metrics());
+
+        let zero = Formula::parse_checked("net_pnl / risk.sharpe_ratio").unwrap();
+        assert_eq!(zero.evaluate(&variables), Err("Division by zero".to_string()));
+
+        assert_eq!(Formula::parse_checked("net_pnl / bogus"), Err("Unknown metric 'bogus'".to_string()));
+        assert!(Formula::parse("net_pnl +").is_err());
+        assert!(Formula::parse("system(\"ls\")").is_err());
+        assert!(Formula::parse(&format!("{}1{}", "(".repeat(40), ")".repeat(40))).is_err());
+    }
+}
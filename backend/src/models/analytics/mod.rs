@@ -3,12 +3,22 @@ pub mod risk;
 pub mod performance;
 pub mod time_series;
 pub mod options;
+pub mod consistency;
+pub mod periods;
+pub mod snapshots;
+pub mod custom_metrics;
+pub mod confidence;
 
 pub use core::CoreMetrics;
 pub use risk::RiskMetrics;
 pub use performance::PerformanceMetrics;
 pub use time_series::TimeSeriesData;
 pub use options::AnalyticsOptions;
+pub use consistency::{ConsistencyScore, ConsistencyScoreSnapshot};
+pub use periods::PeriodDefinition;
+pub use snapshots::{AnalyticsSnapshot, ChangeSet, MetricChange};
+pub use custom_metrics::{CustomMetricDefinition, CustomMetricValue};
+pub use confidence::SampleConfidence;
 
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
@@ -21,6 +31,12 @@ pub struct ComprehensiveAnalytics {
     pub performance_metrics: PerformanceMetrics,
     pub time_series: TimeSeriesData,
     pub grouped_analytics: HashMap<String, GroupedMetrics>,
+    /// The user's own KPIs evaluated for the same period
+    #[serde(default)]
+    pub custom_metrics: Vec<CustomMetricValue>,
+    /// Sample size context for the core win rate and expectancy
+    #[serde(default)]
+    pub confidence: SampleConfidence,
 }
 
 /// Grouped analytics for specific symbols or strategies
@@ -31,6 +47,8 @@ pub struct GroupedMetrics {
     pub core_metrics: CoreMetrics,
     pub risk_metrics: RiskMetrics,
     pub performance_metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub confidence: SampleConfidence,
 }
 
 /// Type of grouping applied to analytics
@@ -40,6 +58,7 @@ pub enum GroupType {
     Strategy,
     TradeDirection,
     TimePeriod,
+    Venue,
 }
 
 /// Time series data point
@@ -21,6 +21,8 @@ pub enum GroupingType {
     Strategy,
     TradeDirection,
     TimePeriod,
+    /// Exchange the trade executed on, for comparing fees and fills across venues
+    Venue,
 }
 
 impl Default for AnalyticsOptions {
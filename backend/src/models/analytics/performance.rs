@@ -34,6 +34,7 @@ pub struct PerformanceMetrics {
     pub quarterly_win_rate: f64,
     
     // Execution metrics
+    /// Average dollars lost past the planned stop on stop-outs (stocks)
     pub average_slippage: f64,
     pub commission_impact_percentage: f64,
 }
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+/// How a user's calendar maps onto trading periods.
+///
+/// Defaults describe a calendar year starting Jan 1, trading days that start at midnight UTC
+/// and a seven-day week starting Monday, which matches how analytics behaved before this was
+/// configurable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodDefinition {
+    /// Month the fiscal year starts in (1-12)
+    pub fiscal_year_start_month: u32,
+    /// Day of month the fiscal year starts on (1-28 so every year has it)
+    pub fiscal_year_start_day: u32,
+    /// Minutes added to exit timestamps before taking the trading date. Futures sessions that
+    /// open the evening before use a positive offset, e.g. CME Globex opening 23:00 UTC → 60.
+    pub session_offset_minutes: i32,
+    /// Weekdays (0 = Sunday … 6 = Saturday) that aren't trading days. Activity on them rolls
+    /// forward to the next trading day, e.g. a Sunday evening futures session counts as Monday.
+    pub excluded_weekdays: Vec<u8>,
+    /// First day of the week for weekly buckets: 0 = Sunday, 1 = Monday
+    #[serde(default = "default_week_start_day")]
+    pub week_start_day: u8,
+}
+
+pub(crate) fn default_week_start_day() -> u8 {
+    1
+}
+
+impl Default for PeriodDefinition {
+    fn default() -> Self {
+        Self {
+            fiscal_year_start_month: 1,
+            fiscal_year_start_day: 1,
+            session_offset_minutes: 0,
+            excluded_weekdays: Vec::new(),
+            week_start_day: default_week_start_day(),
+        }
+    }
+}
+
+impl PeriodDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=12).contains(&self.fiscal_year_start_month) {
+            return Err("fiscal_year_start_month must be between 1 and 12".to_string());
+        }
+        if !(1..=28).contains(&self.fiscal_year_start_day) {
+            return Err("fiscal_year_start_day must be between 1 and 28".to_string());
+        }
+        if self.session_offset_minutes.abs() >= 24 * 60 {
+            return Err("session_offset_minutes must be less than a day".to_string());
+        }
+        if self.excluded_weekdays.iter().any(|d| *d > 6) {
+            return Err("excluded_weekdays must be between 0 (Sunday) and 6 (Saturday)".to_string());
+        }
+        if self.excluded_weekdays.len() >= 7 {
+            return Err("at least one weekday must be a trading day".to_string());
+        }
+        if self.week_start_day > 1 {
+            return Err("week_start_day must be 0 (Sunday) or 1 (Monday)".to_string());
+        }
+        Ok(())
+    }
+
+    /// True when trading dates are plain calendar dates
+    pub fn uses_calendar_days(&self) -> bool {
+        self.session_offset_minutes == 0 && self.excluded_weekdays.is_empty()
+    }
+
+    /// Start of the fiscal year containing `now`
+    pub fn fiscal_year_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let start_this_year = self.fiscal_date(now.year());
+        let start = if now.date_naive() >= start_this_year { start_this_year } else { self.fiscal_date(now.year() - 1) };
+        start.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    /// Start of the fiscal quarter containing `now`
+    pub fn fiscal_quarter_start(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let year_start = self.fiscal_year_start(now).date_naive();
+        let mut quarter_start = year_start;
+        for q in 1..4 {
+            let next = add_months(year_start, 3 * q);
+            if next > now.date_naive() {
+                break;
+            }
+            quarter_start = next;
+        }
+        quarter_start.and_hms_opt(0, 0, 0).unwrap().and_utc()
+    }
+
+    /// First day of the week containing `date`
+    pub fn week_start(&self, date: NaiveDate) -> NaiveDate {
+        let weekday = date.weekday().num_days_from_sunday() as i64;
+        date - Duration::days((weekday + 7 - self.week_start_day as i64) % 7)
+    }
+
+    /// True when `date` is the last day of its week, e.g. Saturday for Sunday-start weeks
+    pub fn is_week_end(&self, date: NaiveDate) -> bool {
+        self.week_start(date + Duration::days(1)) == date + Duration::days(1)
+    }
+
+    /// SQLite expression for the first day of the week containing the date `column`,
+    /// mirroring [`Self::week_start`]
+    pub fn week_start_sql(&self, column: &str) -> String {
+        format!(
+            "date({}, '-' || ((CAST(strftime('%w', {}) AS INTEGER) + 7 - {}) % 7) || ' days')",
+            column, column, self.week_start_day
+        )
+    }
+
+    fn fiscal_date(&self, year: i32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, self.fiscal_year_start_month, self.fiscal_year_start_day)
+            .unwrap_or_else(|| NaiveDate::from_ymd_opt(year, 1, 1).unwrap())
+    }
+
+    /// Days to add so a date falling on weekday `w` lands on the next trading day
+    fn roll_forward_days(&self, weekday: u8) -> i64 {
+        let mut days = 0;
+        while days < 7 && self.excluded_weekdays.contains(&((weekday + days) % 7)) {
+            days += 1;
+        }
+        days as i64
+    }
+
+    /// Trading date an exit timestamp belongs to
+    pub fn trading_date(&self, ts: DateTime<Utc>) -> NaiveDate {
+        let shifted = (ts + Duration::minutes(self.session_offset_minutes as i64)).date_naive();
+        let weekday = shifted.weekday().num_days_from_sunday() as u8;
+        shifted + Duration::days(self.roll_forward_days(weekday))
+    }
+
+    /// Trading days from the session of `from` to the session of `to`; 0 when both fall in
+    /// the same session. Excluded weekdays aren't counted.
+    pub fn trading_days_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+        let (start, end) = (self.trading_date(from), self.trading_date(to));
+        start
+            .iter_days()
+            .skip(1)
+            .take_while(|day| *day <= end)
+            .filter(|day| !self.excluded_weekdays.contains(&(day.weekday().num_days_from_sunday() as u8)))
+            .count() as i64
+    }
+
+    /// SQLite expression for the trading date of `column`, mirroring [`Self::trading_date`]
+    pub fn trading_date_sql(&self, column: &str) -> String {
+        if self.uses_calendar_days() {
+            return column.to_string();
+        }
+        let shifted = format!("{}, '{:+} minutes'", column, self.session_offset_minutes);
+        if self.excluded_weekdays.is_empty() {
+            return format!("date({})", shifted);
+        }
+        let cases: Vec<String> = (0..7u8)
+            .filter_map(|w| match self.roll_forward_days(w) {
+                0 => None,
+                days => Some(format!("WHEN {} THEN '+{} days'", w, days)),
+            })
+            .collect();
+        format!(
+            "date({}, CASE CAST(strftime('%w', {}) AS INTEGER) {} ELSE '+0 days' END)",
+            shifted,
+            shifted,
+            cases.join(" ")
+        )
+    }
+
+    /// Load the user's settings from `user_profile`, falling back to defaults
+    pub async fn load(conn: &Connection) -> Self {
+        let row = match conn
+            .prepare("SELECT fiscal_year_start_month, fiscal_year_start_day, session_offset_minutes, excluded_weekdays, week_start_day FROM user_profile LIMIT 1")
+            .await
+        {
+            Ok(stmt) => match stmt.query(params![]).await {
+                Ok(mut rows) => rows.next().await.ok().flatten(),
+                Err(_) => None,
+            },
+            Err(_) => None,
+        };
+        let Some(row) = row else { return Self::default() };
+
+        let defaults = Self::default();
+        let definition = Self {
+            fiscal_year_start_month: row.get::<Option<i64>>(0).ok().flatten().map(|v| v as u32).unwrap_or(defaults.fiscal_year_start_month),
+            fiscal_year_start_day: row.get::<Option<i64>>(1).ok().flatten().map(|v| v as u32).unwrap_or(defaults.fiscal_year_start_day),
+            session_offset_minutes: row.get::<Option<i64>>(2).ok().flatten().map(|v| v as i32).unwrap_or(0),
+            excluded_weekdays: row.get::<Option<String>>(3).ok().flatten().map(|s| parse_weekdays(&s)).unwrap_or_default(),
+            week_start_day: row.get::<Option<i64>>(4).ok().flatten().map(|v| v as u8).unwrap_or(defaults.week_start_day),
+        };
+        if definition.validate().is_ok() { definition } else { defaults }
+    }
+}
+
+/// Parse a comma-separated weekday list ("0,6")
+pub fn parse_weekdays(value: &str) -> Vec<u8> {
+    let mut days: Vec<u8> = value.split(',').filter_map(|d| d.trim().parse().ok()).filter(|d| *d <= 6).collect();
+    days.sort_unstable();
+    days.dedup();
+    days
+}
+
+pub fn format_weekdays(days: &[u8]) -> String {
+    days.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn add_months(date: NaiveDate, months: u32) -> NaiveDate {
+    let total = date.month0() + months;
+    let year = date.year() + (total / 12) as i32;
+    NaiveDate::from_ymd_opt(year, total % 12 + 1, date.day()).unwrap_or(date)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn definition(month: u32, day: u32) -> PeriodDefinition {
+        PeriodDefinition { fiscal_year_start_month: month, fiscal_year_start_day: day, ..Default::default() }
+    }
+
+    #[test]
+    fn test_fiscal_year_and_quarter_start() {
+        let now = Utc.with_ymd_and_hms(2025, 2, 10, 12, 0, 0).unwrap();
+        let april = definition(4, 1);
+        assert_eq!(april.fiscal_year_start(now).date_naive(), NaiveDate::from_ymd_opt(2024, 4, 1).unwrap());
+        assert_eq!(april.fiscal_quarter_start(now).date_naive(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+
+        let calendar = PeriodDefinition::default();
+        assert_eq!(calendar.fiscal_year_start(now).date_naive(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(calendar.fiscal_quarter_start(now).date_naive(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+    }
+
+    #[test]
+    fn test_trading_date_rolls_sunday_session_into_monday() {
+        let futures = PeriodDefinition { session_offset_minutes: 60, excluded_weekdays: vec![0, 6], ..Default::default() };
+        // Sunday 23:30 UTC → shifted to Monday
+        let sunday_evening = Utc.with_ymd_and_hms(2025, 3, 2, 23, 30, 0).unwrap();
+        assert_eq!(futures.trading_date(sunday_evening), NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        // Saturday rolls forward past Sunday
+        let saturday = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        assert_eq!(futures.trading_date(saturday), NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        // Friday to the Sunday-evening session is one trading day for futures, two calendar days otherwise
+        let friday = Utc.with_ymd_and_hms(2025, 2, 28, 15, 0, 0).unwrap();
+        assert_eq!(futures.trading_days_between(friday, sunday_evening), 1);
+        assert_eq!(PeriodDefinition::default().trading_days_between(friday, sunday_evening), 2);
+        assert_eq!(futures.trading_days_between(sunday_evening, sunday_evening), 0);
+        assert!(futures.validate().is_ok());
+        assert!(PeriodDefinition { excluded_weekdays: (0..7).collect(), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_week_start_follows_setting() {
+        // Wednesday 2025-03-05
+        let wednesday = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        let sunday_start = PeriodDefinition { week_start_day: 0, ..Default::default() };
+        assert_eq!(PeriodDefinition::default().week_start(wednesday), NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        assert_eq!(sunday_start.week_start(wednesday), NaiveDate::from_ymd_opt(2025, 3, 2).unwrap());
+        // A Sunday starts its own week or ends the previous one
+        let sunday = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        assert_eq!(sunday_start.week_start(sunday), sunday);
+        assert_eq!(PeriodDefinition::default().week_start(sunday), NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        assert!(PeriodDefinition::default().is_week_end(sunday));
+        assert!(sunday_start.is_week_end(NaiveDate::from_ymd_opt(2025, 3, 8).unwrap()));
+        assert!(PeriodDefinition { week_start_day: 3, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_trading_date_sql() {
+        assert_eq!(PeriodDefinition::default().trading_date_sql("exit_date"), "exit_date");
+        let shifted = PeriodDefinition { session_offset_minutes: -30, ..Default::default() };
+        assert_eq!(shifted.trading_date_sql("exit_date"), "date(exit_date, '-30 minutes')");
+        assert_eq!(parse_weekdays("6, 0,9,6"), vec![0, 6]);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{CoreMetrics, PerformanceMetrics, RiskMetrics};
+
+/// Metric set computed for one time range, stored so later periods can be compared against it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsSnapshot {
+    pub id: String,
+    pub label: Option<String>,
+    pub time_range: String,
+    pub core_metrics: CoreMetrics,
+    pub risk_metrics: RiskMetrics,
+    pub performance_metrics: PerformanceMetrics,
+    pub created_at: String,
+}
+
+/// One metric that differs between two snapshots
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricChange {
+    /// "core", "risk" or "performance"
+    pub category: String,
+    pub metric: String,
+    pub old: f64,
+    pub new: f64,
+    pub delta: f64,
+    /// Relative change in percent; None when the old value is zero
+    pub pct: Option<f64>,
+}
+
+/// Structured "what changed" between two metric sets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub from: String,
+    pub to: String,
+    /// Ordered by category, then by absolute relative change (largest first)
+    pub changes: Vec<MetricChange>,
+    /// Metrics compared, including unchanged ones
+    pub metrics_compared: usize,
+}
+
+/// Differences smaller than this are treated as floating point noise
+const EPSILON: f64 = 1e-9;
+
+impl ChangeSet {
+    /// Diff two snapshots. Output is deterministic for the same inputs.
+    pub fn between(from: &AnalyticsSnapshot, to: &AnalyticsSnapshot) -> Self {
+        let to_value = |v: serde_json::Result<Value>| v.unwrap_or(Value::Null);
+        let categories = [
+            ("core", to_value(serde_json::to_value(&from.core_metrics)), to_value(serde_json::to_value(&to.core_metrics))),
+            ("risk", to_value(serde_json::to_value(&from.risk_metrics)), to_value(serde_json::to_value(&to.risk_metrics))),
+            ("performance", to_value(serde_json::to_value(&from.performance_metrics)), to_value(serde_json::to_value(&to.performance_metrics))),
+        ];
+        Self::from_values(describe(from), describe(to), &categories)
+    }
+
+    /// Diff the numeric fields of `(category, old, new)` JSON objects
+    pub fn from_values(from: String, to: String, categories: &[(&str, Value, Value)]) -> Self {
+        let mut changes = Vec::new();
+        let mut metrics_compared = 0;
+
+        for (category, old, new) in categories {
+            let (Value::Object(old), Value::Object(new)) = (old, new) else { continue };
+            let mut category_changes = Vec::new();
+            for (metric, old_value) in old {
+                let (Some(old_value), Some(new_value)) = (old_value.as_f64(), new.get(metric).and_then(Value::as_f64)) else { continue };
+                metrics_compared += 1;
+                let delta = new_value - old_value;
+                if delta.abs() < EPSILON || !delta.is_finite() {
+                    continue;
+                }
+                let pct = (old_value.abs() > EPSILON).then(|| delta / old_value.abs() * 100.0);
+                category_changes.push(MetricChange {
+                    category: category.to_string(),
+                    metric: metric.clone(),
+                    old: old_value,
+                    new: new_value,
+                    delta,
+                    pct,
+                });
+            }
+            category_changes.sort_by(|a, b| {
+                let magnitude = |c: &MetricChange| c.pct.map(f64::abs).unwrap_or(f64::INFINITY);
+                magnitude(b).total_cmp(&magnitude(a)).then_with(|| a.metric.cmp(&b.metric))
+            });
+            changes.extend(category_changes);
+        }
+
+        Self { from, to, changes, metrics_compared }
+    }
+
+    /// Changes whose relative move is at least `min_pct` (changes from zero always qualify)
+    pub fn significant(&self, min_pct: f64) -> Vec<&MetricChange> {
+        self.changes.iter().filter(|c| c.pct.is_none_or(|p| p.abs() >= min_pct)).collect()
+    }
+
+    /// Plain-text change list for AI narration
+    pub fn format_for_prompt(&self, min_pct: f64, limit: usize) -> String {
+        let mut lines = vec![format!("Changes from {} to {}:", self.from, self.to)];
+        for change in self.significant(min_pct).into_iter().take(limit) {
+            let pct = change.pct.map(|p| format!(" ({:+.1}%)", p)).unwrap_or_default();
+            lines.push(format!("- {}: {:.2} → {:.2}{}", change.metric, change.old, change.new, pct));
+        }
+        if lines.len() == 1 {
+            lines.push("- No material changes".to_string());
+        }
+        lines.join("\n")
+    }
+
+    /// Short deterministic narrative of the largest moves, e.g. for report summaries
+    pub fn narrate(&self, min_pct: f64, limit: usize) -> String {
+        let moves: Vec<String> = self
+            .significant(min_pct)
+            .into_iter()
+            .take(limit)
+            .map(|c| {
+                let name = c.metric.replace('_', " ");
+                let direction = if c.delta > 0.0 { "up" } else { "down" };
+                match c.pct {
+                    Some(pct) => format!("{} {} {:.1}% ({:.2} → {:.2})", name, direction, pct.abs(), c.old, c.new),
+                    None => format!("{} {} from {:.2} to {:.2}", name, direction, c.old, c.new),
+                }
+            })
+            .collect();
+        if moves.is_empty() {
+            return format!("No material changes versus {}.", self.from);
+        }
+        format!("Versus {}: {}.", self.from, moves.join("; "))
+    }
+}
+
+fn describe(snapshot: &AnalyticsSnapshot) -> String {
+    snapshot.label.clone().unwrap_or_else(|| snapshot.time_range.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_change_set_is_ordered_and_skips_unchanged() {
+        let before = json!({"net_profit_loss": 1000.0, "win_rate": 50.0, "total_trades": 20, "grade": "B"});
+        let after = json!({"net_profit_loss": 1500.0, "win_rate": 55.0, "total_trades": 20, "grade": "A"});
+        let changes = ChangeSet::from_values("March".to_string(), "April".to_string(), &[("core", before, after)]);
+
+        assert_eq!(changes.metrics_compared, 3);
+        assert_eq!(changes.changes.len(), 2);
+        assert_eq!(changes.changes[0].metric, "net_profit_loss");
+        assert_eq!(changes.changes[0].delta, 500.0);
+        assert_eq!(changes.changes[0].pct, Some(50.0));
+        assert_eq!(changes.changes[1].metric, "win_rate");
+        assert_eq!(changes.significant(20.0).len(), 1);
+        assert!(changes.format_for_prompt(0.0, 10).starts_with("Changes from March to April"));
+        assert_eq!(changes.narrate(20.0, 3), "Versus March: net profit loss up 50.0% (1000.00 → 1500.00).");
+    }
+
+    #[test]
+    fn test_change_from_zero_has_no_pct() {
+        let changes = ChangeSet::from_values(
+            "a".to_string(),
+            "b".to_string(),
+            &[("risk", json!({"max_drawdown": 0.0}), json!({"max_drawdown": -250.0}))],
+        );
+        assert_eq!(changes.changes[0].pct, None);
+        assert_eq!(changes.significant(1000.0).len(), 1);
+    }
+}
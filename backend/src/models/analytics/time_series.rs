@@ -7,6 +7,7 @@ use crate::models::analytics::TimeSeriesPoint;
 pub struct TimeSeriesData {
     // Equity curve data
     pub daily_pnl: Vec<TimeSeriesPoint>,
+    /// Dated by the first day of each week, per `week_start_day`
     pub weekly_pnl: Vec<TimeSeriesPoint>,
     pub monthly_pnl: Vec<TimeSeriesPoint>,
     
@@ -30,4 +31,8 @@ pub struct TimeSeriesData {
     pub annualized_return: f64,
     pub total_return_percentage: f64,
     pub total_trades: u32,
+
+    /// The user's first day of the week (0 = Sunday, 1 = Monday), for calendar layouts
+    #[serde(default = "crate::models::analytics::periods::default_week_start_day")]
+    pub week_start_day: u8,
 }
@@ -0,0 +1,213 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use libsql::{Connection, params};
+
+use super::Image;
+use crate::models::timestamps;
+
+/// Layer shapes the markup tool draws
+pub const LAYER_TYPES: [&str; 5] = ["arrow", "line", "text", "rect", "highlight"];
+pub const MAX_LAYERS: usize = 500;
+/// Serialized size cap for one image's layers
+pub const MAX_LAYERS_BYTES: usize = 256 * 1024;
+
+/// Markup drawn over a trade screenshot. `layers` is the markup tool's own JSON, one object
+/// per shape; the server only checks its shape. `version` comes from one counter shared by
+/// every annotation in the database, so clients pull everything changed since the highest
+/// version they have seen, deletions included.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageAnnotation {
+    pub id: String,
+    pub image_id: String,
+    pub layers: serde_json::Value,
+    pub version: i64,
+    pub is_deleted: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Data Transfer Object for saving an image's annotation layers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveAnnotationRequest {
+    pub layers: serde_json::Value,
+    /// Version the client edited; the save is refused when someone else saved since
+    pub base_version: Option<i64>,
+}
+
+/// An image together with its markup, for exports
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotatedImage {
+    #[serde(flatten)]
+    pub image: Image,
+    pub annotation: Option<ImageAnnotation>,
+}
+
+#[derive(Debug)]
+pub enum SaveAnnotationOutcome {
+    Saved(ImageAnnotation),
+    /// `base_version` was stale; carries the stored annotation
+    Conflict(ImageAnnotation),
+    ImageNotFound,
+}
+
+/// Check layers are an array of typed shape objects within the size limits
+pub fn validate_layers(layers: &serde_json::Value) -> Result<(), String> {
+    let items = layers.as_array().ok_or("layers must be an array")?;
+    if items.len() > MAX_LAYERS {
+        return Err(format!("At most {} layers are allowed", MAX_LAYERS));
+    }
+    if layers.to_string().len() > MAX_LAYERS_BYTES {
+        return Err(format!("layers must be under {} KB", MAX_LAYERS_BYTES / 1024));
+    }
+    for (i, item) in items.iter().enumerate() {
+        let kind = item.get("type").and_then(|t| t.as_str()).ok_or_else(|| format!("Layer {} has no type", i))?;
+        if !LAYER_TYPES.contains(&kind) {
+            return Err(format!("Layer {} has unknown type '{}'", i, kind));
+        }
+    }
+    Ok(())
+}
+
+const COLUMNS: &str = "id, image_id, layers, version, is_deleted, created_at, updated_at";
+
+/// Next value of the shared version counter
+const NEXT_VERSION_SQL: &str = "(SELECT COALESCE(MAX(version), 0) + 1 FROM image_annotations)";
+
+impl ImageAnnotation {
+    /// Live annotation for an image
+    pub async fn find_by_image_id(
+        conn: &Connection,
+        image_id: &str,
+    ) -> Result<Option<ImageAnnotation>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare(&format!("SELECT {} FROM image_annotations WHERE image_id = ? AND is_deleted = 0", COLUMNS))
+            .await?
+            .query(params![image_id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replace an image's layers, creating the annotation on first save
+    pub async fn save(
+        conn: &Connection,
+        image_id: &str,
+        request: SaveAnnotationRequest,
+    ) -> Result<SaveAnnotationOutcome, Box<dyn std::error::Error + Send + Sync>> {
+        if Image::find_by_id(conn, image_id).await?.is_none() {
+            return Ok(SaveAnnotationOutcome::ImageNotFound);
+        }
+
+        let tx = conn.transaction().await?;
+        let current = Self::find_by_image_id(&tx, image_id).await?;
+        if let (Some(current), Some(base)) = (&current, request.base_version)
+            && current.version != base
+        {
+            return Ok(SaveAnnotationOutcome::Conflict(current.clone()));
+        }
+
+        let now = timestamps::to_db(&Utc::now());
+        let mut rows = tx
+            .prepare(&format!(
+                "INSERT INTO image_annotations ({COLUMNS}) VALUES (?, ?, ?, {NEXT_VERSION_SQL}, 0, ?, ?)
+                 ON CONFLICT(image_id) DO UPDATE SET
+                     layers = excluded.layers, version = excluded.version, is_deleted = 0, updated_at = excluded.updated_at
+                 RETURNING {COLUMNS}"
+            ))
+            .await?
+            .query(params![Uuid::new_v4().to_string(), image_id, request.layers.to_string(), now.clone(), now])
+            .await?;
+        let saved = match rows.next().await? {
+            Some(row) => Self::from_row(&row)?,
+            None => return Err("Failed to save annotation".into()),
+        };
+        drop(rows);
+        tx.commit().await?;
+        Ok(SaveAnnotationOutcome::Saved(saved))
+    }
+
+    /// Soft delete an image's annotation, leaving a tombstone for clients to pull
+    pub async fn delete(
+        conn: &Connection,
+        image_id: &str,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let result = conn
+            .execute(
+                &format!(
+                    "UPDATE image_annotations SET is_deleted = 1, version = {NEXT_VERSION_SQL}, updated_at = ?
+                     WHERE image_id = ? AND is_deleted = 0"
+                ),
+                params![timestamps::to_db(&Utc::now()), image_id],
+            )
+            .await?;
+        Ok(result > 0)
+    }
+
+    /// Annotations changed after `since_version`, deleted ones included, oldest change first
+    pub async fn changes_since(
+        conn: &Connection,
+        since_version: i64,
+        limit: i64,
+    ) -> Result<Vec<ImageAnnotation>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare(&format!("SELECT {} FROM image_annotations WHERE version > ? ORDER BY version LIMIT ?", COLUMNS))
+            .await?
+            .query(params![since_version, limit])
+            .await?;
+        let mut annotations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            annotations.push(Self::from_row(&row)?);
+        }
+        Ok(annotations)
+    }
+
+    /// A trade note's images in note order, each with its markup
+    pub async fn annotated_images_for_trade_note(
+        conn: &Connection,
+        trade_note_id: &str,
+    ) -> Result<Vec<AnnotatedImage>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut annotated = Vec::new();
+        for image in Image::find_by_trade_note_id(conn, trade_note_id).await? {
+            let annotation = Self::find_by_image_id(conn, &image.id).await?;
+            annotated.push(AnnotatedImage { image, annotation });
+        }
+        Ok(annotated)
+    }
+
+    fn from_row(row: &libsql::Row) -> Result<ImageAnnotation, Box<dyn std::error::Error + Send + Sync>> {
+        let layers: String = row.get(2)?;
+        Ok(ImageAnnotation {
+            id: row.get(0)?,
+            image_id: row.get(1)?,
+            layers: serde_json::from_str(&layers)?,
+            version: row.get(3)?,
+            is_deleted: row.get::<i64>(4)? != 0,
+            created_at: row.get(5)?,
+            updated_at: row.get(6)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_layers() {
+        let layers = json!([
+            {"type": "arrow", "from": [10, 20], "to": [80, 40], "color": "#f00"},
+            {"type": "text", "at": [12, 30], "text": "Entry"}
+        ]);
+        assert!(validate_layers(&layers).is_ok());
+        assert!(validate_layers(&json!([])).is_ok());
+
+        assert!(validate_layers(&json!({"type": "arrow"})).is_err());
+        assert!(validate_layers(&json!([{"from": [0, 0]}])).is_err());
+        assert!(validate_layers(&json!([{"type": "circle"}])).is_err());
+        assert!(validate_layers(&json!(vec![json!({"type": "line"}); MAX_LAYERS + 1])).is_err());
+    }
+}
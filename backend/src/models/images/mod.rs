@@ -1,3 +1,5 @@
 pub mod image;
+pub mod annotation;
 
 pub use image::*;
+pub use annotation::*;
@@ -1,11 +1,17 @@
 pub mod ai;
 pub mod analytics;
 pub mod images;
+pub mod money;
 pub mod notes;
 pub mod options;
 pub mod playbook;
+pub mod pnl;
+pub mod prop_firm;
+pub mod serialization;
 pub mod stock;
 pub mod tags;
+pub mod timestamps;
+pub mod venue;
 
 pub mod notebook;
 
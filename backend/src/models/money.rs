@@ -0,0 +1,94 @@
+//! Money arithmetic.
+//!
+//! Prices, fees and P&L are `Decimal` in the trade models and in the metric code that sums
+//! them, so a running total over thousands of trades doesn't pick up float error. SQLite
+//! still stores these columns as REAL; values cross that boundary through `from_f64`,
+//! `to_f64` and the row helpers below. With the `serde-float` feature a `Decimal`
+//! serializes as a JSON number, so API payloads keep their shape.
+
+use libsql::{Row, Value};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::RoundingStrategy;
+use std::str::FromStr;
+
+pub use rust_decimal::Decimal;
+
+/// Convert via the shortest string that round-trips the float, so a stored `0.1` becomes
+/// exactly `0.1` rather than `0.1000000000000000055511151231257827`. Non-finite and
+/// out-of-range values become zero.
+pub fn from_f64(value: f64) -> Decimal {
+    if !value.is_finite() {
+        return Decimal::ZERO;
+    }
+    Decimal::from_str(&value.to_string())
+        .ok()
+        .or_else(|| Decimal::from_f64(value))
+        .unwrap_or(Decimal::ZERO)
+}
+
+pub fn from_f64_opt(value: Option<f64>) -> Option<Decimal> {
+    value.map(from_f64)
+}
+
+/// For storage and for ratio math (Sharpe, percentages) that stays in f64
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
+
+pub fn to_f64_opt(value: Option<Decimal>) -> Option<f64> {
+    value.map(to_f64)
+}
+
+/// Round half away from zero to cents, the way brokers round cash amounts
+pub fn round_cents(value: Decimal) -> Decimal {
+    value.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Bind a money value as a SQL parameter
+pub fn sql_value(value: Decimal) -> Value {
+    Value::Real(to_f64(value))
+}
+
+pub fn sql_value_opt(value: Option<Decimal>) -> Value {
+    value.map(sql_value).unwrap_or(Value::Null)
+}
+
+/// Read a money column. REAL and INTEGER are converted, numeric TEXT is parsed exactly and
+/// NULL or anything else reads as None.
+pub fn row_decimal_opt(row: &Row, idx: i32) -> Option<Decimal> {
+    match row.get::<Value>(idx) {
+        Ok(Value::Real(v)) => Some(from_f64(v)),
+        Ok(Value::Integer(v)) => Some(Decimal::from(v)),
+        Ok(Value::Text(s)) => Decimal::from_str(s.trim()).ok(),
+        _ => None,
+    }
+}
+
+/// `row_decimal_opt`, with missing values as zero
+pub fn row_decimal(row: &Row, idx: i32) -> Decimal {
+    row_decimal_opt(row, idx).unwrap_or(Decimal::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summing_cents_does_not_drift() {
+        let mut float_total = 0.0_f64;
+        let mut total = Decimal::ZERO;
+        for _ in 0..10_000 {
+            float_total += 0.1;
+            total += from_f64(0.1);
+        }
+        assert_ne!(float_total, 1000.0);
+        assert_eq!(total, Decimal::from(1000));
+        assert_eq!(to_f64(total), 1000.0);
+    }
+
+    #[test]
+    fn test_rounding_and_non_finite() {
+        assert_eq!(round_cents(from_f64(2.675)), from_f64(2.68));
+        assert_eq!(from_f64(f64::NAN), Decimal::ZERO);
+    }
+}
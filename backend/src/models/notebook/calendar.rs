@@ -5,7 +5,8 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalendarEvent {
     pub id: String,
-    pub reminder_id: String,
+    /// Set for reminder events; system-generated events have no reminder
+    pub reminder_id: Option<String>,
     pub event_title: String,
     pub event_description: Option<String>,
     pub start_date: String,
@@ -14,6 +15,10 @@ pub struct CalendarEvent {
     pub end_time: Option<String>,
     pub is_all_day: bool,
     pub is_synced: bool,
+    /// `reminder` for user reminders, otherwise the system feature that generated the event
+    pub source: String,
+    /// Key of the generated event within its source, e.g. the symbol of an earnings event
+    pub source_ref: Option<String>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -47,7 +52,7 @@ pub struct ExternalCalendarEvent {
 impl CalendarEvent {
     pub async fn find_by_date_range(conn: &Connection, start: &str, end: &str) -> Result<Vec<Self>> {
         let stmt = conn.prepare(
-            r#"SELECT id, reminder_id, event_title, event_description, start_date, end_date, start_time, end_time, is_all_day, is_synced, created_at, updated_at
+            r#"SELECT id, reminder_id, event_title, event_description, start_date, end_date, start_time, end_time, is_all_day, is_synced, source, source_ref, created_at, updated_at
                  FROM calendar_events 
                  WHERE start_date >= ? AND end_date <= ? 
                  ORDER BY start_date ASC, start_time ASC"#,
@@ -55,23 +60,45 @@ impl CalendarEvent {
         let mut rows = stmt.query(params![start, end]).await?;
         let mut out = Vec::new();
         while let Some(row) = rows.next().await? {
-            out.push(Self {
-                id: row.get(0)?,
-                reminder_id: row.get(1)?,
-                event_title: row.get(2)?,
-                event_description: row.get(3)?,
-                start_date: row.get(4)?,
-                end_date: row.get(5)?,
-                start_time: row.get(6)?,
-                end_time: row.get(7)?,
-                is_all_day: !matches!(row.get::<i64>(8)?, 0),
-                is_synced: !matches!(row.get::<i64>(9)?, 0),
-                created_at: row.get(10)?,
-                updated_at: row.get(11)?,
-            });
+            out.push(Self::from_row(&row)?);
         }
         Ok(out)
     }
+
+    /// Events of recurring reminders starting on or before `end`, with the reminder's rule
+    pub async fn find_recurring_reminder_events(conn: &Connection, end: &str) -> Result<Vec<(Self, String)>> {
+        let stmt = conn.prepare(
+            r#"SELECT ce.id, ce.reminder_id, ce.event_title, ce.event_description, ce.start_date, ce.end_date, ce.start_time, ce.end_time, ce.is_all_day, ce.is_synced, ce.source, ce.source_ref, ce.created_at, ce.updated_at, r.recurrence
+                 FROM calendar_events ce
+                 JOIN notebook_reminders r ON r.id = ce.reminder_id
+                 WHERE r.recurrence IS NOT NULL AND ce.start_date <= ?"#,
+        ).await?;
+        let mut rows = stmt.query(params![end]).await?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next().await? {
+            out.push((Self::from_row(&row)?, row.get::<String>(14)?));
+        }
+        Ok(out)
+    }
+
+    fn from_row(row: &libsql::Row) -> Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            reminder_id: row.get(1)?,
+            event_title: row.get(2)?,
+            event_description: row.get(3)?,
+            start_date: row.get(4)?,
+            end_date: row.get(5)?,
+            start_time: row.get(6)?,
+            end_time: row.get(7)?,
+            is_all_day: !matches!(row.get::<i64>(8)?, 0),
+            is_synced: !matches!(row.get::<i64>(9)?, 0),
+            source: row.get(10)?,
+            source_ref: row.get(11)?,
+            created_at: row.get(12)?,
+            updated_at: row.get(13)?,
+        })
+    }
 }
 
 impl ExternalCalendarConnection {
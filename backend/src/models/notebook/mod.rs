@@ -2,12 +2,14 @@ pub mod notebook_note;
 pub mod tag;
 pub mod template;
 pub mod reminder;
+pub mod recurrence;
 pub mod calendar;
 
 pub use notebook_note::*;
 pub use tag::*;
 pub use template::*;
 pub use reminder::*;
+pub use recurrence::*;
 pub use calendar::*;
 
 
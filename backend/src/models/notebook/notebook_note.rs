@@ -3,6 +3,13 @@ use chrono::Utc;
 use libsql::{Connection, params};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+use crate::service::note_encryption::{self, NoteCipher};
+use crate::turso::in_transaction;
+
+/// Moves one bulk reorder may carry
+pub const MAX_NOTE_MOVES: usize = 1000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotebookNote {
@@ -33,13 +40,76 @@ pub struct UpdateNoteRequest {
     pub is_deleted: Option<bool>,
 }
 
+/// One note's new place in the tree after a drag-and-drop
+#[derive(Debug, Clone, Deserialize)]
+pub struct NoteMove {
+    pub id: String,
+    /// None moves the note to the top level
+    pub parent_id: Option<String>,
+    pub position: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReorderNotesRequest {
+    pub moves: Vec<NoteMove>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum NoteMoveError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+impl From<libsql::Error> for NoteMoveError {
+    fn from(e: libsql::Error) -> Self {
+        Self::Database(e.into())
+    }
+}
+
+/// Check `moves` against the current `parents` (note id -> parent id): every note and new
+/// parent must exist, no note may be moved twice, and no note may end up its own ancestor
+pub fn check_note_moves(parents: &HashMap<String, Option<String>>, moves: &[NoteMove]) -> Result<(), String> {
+    let mut moved = HashSet::new();
+    let mut after = parents.clone();
+    for m in moves {
+        if !parents.contains_key(&m.id) {
+            return Err(format!("Note not found: {}", m.id));
+        }
+        if !moved.insert(m.id.as_str()) {
+            return Err(format!("Note {} is moved more than once", m.id));
+        }
+        if let Some(parent) = &m.parent_id
+            && !parents.contains_key(parent)
+        {
+            return Err(format!("Parent note not found: {}", parent));
+        }
+        after.insert(m.id.clone(), m.parent_id.clone());
+    }
+
+    // Only a moved note can close a cycle, so walking up from each of them is enough
+    for m in moves {
+        let mut seen = HashSet::from([m.id.as_str()]);
+        let mut current = after.get(&m.id).and_then(|p| p.as_deref());
+        while let Some(id) = current {
+            if !seen.insert(id) {
+                return Err(format!("Moving note {} would make it its own ancestor", m.id));
+            }
+            current = after.get(id).and_then(|p| p.as_deref());
+        }
+    }
+    Ok(())
+}
+
 impl NotebookNote {
     pub async fn create(conn: &Connection, req: CreateNoteRequest) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
         let position = req.position.unwrap_or_default();
         let content = req.content.unwrap_or_else(|| Value::Array(vec![]));
-        let content_str = serde_json::to_string(&content)?;
+        let cipher = NoteCipher::for_connection(conn).await?;
+        let content_str = note_encryption::seal_content(cipher.as_ref(), serde_json::to_string(&content)?)?;
 
         conn.execute(
             r#"INSERT INTO notebook_notes (id, parent_id, title, content, position, is_deleted, created_at, updated_at)
@@ -51,19 +121,21 @@ impl NotebookNote {
     }
 
     pub async fn find_by_id(conn: &Connection, id: &str) -> Result<Self> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let stmt = conn.prepare(
             r#"SELECT id, parent_id, title, content, position, is_deleted, created_at, updated_at
                 FROM notebook_notes WHERE id = ?"#,
         ).await?;
         let mut rows = stmt.query(params![id]).await?;
         if let Some(row) = rows.next().await? {
-            Ok(Self::from_row(row)?)
+            Ok(Self::from_row(row, cipher.as_ref())?)
         } else {
             anyhow::bail!(format!("Note not found: {}", id))
         }
     }
 
     pub async fn find_all(conn: &Connection, parent_id: Option<&str>) -> Result<Vec<Self>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         if let Some(pid) = parent_id {
             let stmt = conn.prepare(
                 r#"SELECT id, parent_id, title, content, position, is_deleted, created_at, updated_at
@@ -71,7 +143,7 @@ impl NotebookNote {
             ).await?;
             let mut rows = stmt.query(params![pid]).await?;
             let mut out = Vec::new();
-            while let Some(row) = rows.next().await? { out.push(Self::from_row(row)?); }
+            while let Some(row) = rows.next().await? { out.push(Self::from_row(row, cipher.as_ref())?); }
             Ok(out)
         } else {
             let stmt = conn.prepare(
@@ -80,7 +152,7 @@ impl NotebookNote {
             ).await?;
             let mut rows = stmt.query(params![]).await?;
             let mut out = Vec::new();
-            while let Some(row) = rows.next().await? { out.push(Self::from_row(row)?); }
+            while let Some(row) = rows.next().await? { out.push(Self::from_row(row, cipher.as_ref())?); }
             Ok(out)
         }
     }
@@ -90,13 +162,14 @@ impl NotebookNote {
     }
 
     pub async fn find_deleted(conn: &Connection) -> Result<Vec<Self>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let stmt = conn.prepare(
             r#"SELECT id, parent_id, title, content, position, is_deleted, created_at, updated_at
                 FROM notebook_notes WHERE is_deleted = 1 ORDER BY updated_at DESC"#,
         ).await?;
         let mut rows = stmt.query(params![]).await?;
         let mut out = Vec::new();
-        while let Some(row) = rows.next().await? { out.push(Self::from_row(row)?); }
+        while let Some(row) = rows.next().await? { out.push(Self::from_row(row, cipher.as_ref())?); }
         Ok(out)
     }
 
@@ -114,7 +187,8 @@ impl NotebookNote {
     pub async fn update(conn: &Connection, id: &str, updates: UpdateNoteRequest) -> Result<Self> {
         if let Some(title) = updates.title { conn.execute("UPDATE notebook_notes SET title = ?, updated_at = ? WHERE id = ?", params![title, Utc::now().to_rfc3339(), id]).await?; }
         if let Some(content) = updates.content { 
-            let content_str = serde_json::to_string(&content)?;
+            let cipher = NoteCipher::for_connection(conn).await?;
+            let content_str = note_encryption::seal_content(cipher.as_ref(), serde_json::to_string(&content)?)?;
             conn.execute("UPDATE notebook_notes SET content = ?, updated_at = ? WHERE id = ?", params![content_str, Utc::now().to_rfc3339(), id]).await?; 
         }
         if let Some(pos) = updates.position { conn.execute("UPDATE notebook_notes SET position = ?, updated_at = ? WHERE id = ?", params![pos, Utc::now().to_rfc3339(), id]).await?; }
@@ -160,8 +234,44 @@ impl NotebookNote {
         Self::find_by_id(conn, id).await
     }
 
-    fn from_row(row: libsql::Row) -> Result<Self> {
-        let content_str: String = row.get(3)?;
+    /// Re-parent and reposition many notes as one atomic change. The tree is read and
+    /// checked inside the same transaction, so concurrent moves can't sneak a cycle in.
+    pub async fn apply_moves(conn: &Connection, moves: &[NoteMove]) -> Result<Vec<Self>, NoteMoveError> {
+        if moves.is_empty() {
+            return Ok(Vec::new());
+        }
+        if moves.len() > MAX_NOTE_MOVES {
+            return Err(NoteMoveError::Invalid(format!("At most {} notes can be moved at once", MAX_NOTE_MOVES)));
+        }
+
+        in_transaction(conn, async |tx: &Connection| -> Result<(), NoteMoveError> {
+            let mut parents = HashMap::new();
+            let mut rows = tx.query("SELECT id, parent_id FROM notebook_notes", params![]).await?;
+            while let Some(row) = rows.next().await? {
+                parents.insert(row.get::<String>(0)?, row.get::<Option<String>>(1)?);
+            }
+            check_note_moves(&parents, moves).map_err(NoteMoveError::Invalid)?;
+
+            let now = Utc::now().to_rfc3339();
+            for m in moves {
+                tx.execute(
+                    "UPDATE notebook_notes SET parent_id = ?, position = ?, updated_at = ? WHERE id = ?",
+                    params![m.parent_id.clone(), m.position, now.clone(), m.id.clone()],
+                ).await?;
+            }
+            Ok(())
+        })
+        .await?;
+
+        let mut notes = Vec::with_capacity(moves.len());
+        for m in moves {
+            notes.push(Self::find_by_id(conn, &m.id).await?);
+        }
+        Ok(notes)
+    }
+
+    fn from_row(row: libsql::Row, cipher: Option<&NoteCipher>) -> Result<Self> {
+        let content_str = note_encryption::open_content(cipher, row.get(3)?)?;
         let content = serde_json::from_str(&content_str).unwrap_or(Value::String(content_str));
         
         Ok(Self {
@@ -177,4 +287,28 @@ impl NotebookNote {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
 
+    fn moved(id: &str, parent_id: Option<&str>) -> NoteMove {
+        NoteMove { id: id.to_string(), parent_id: parent_id.map(str::to_string), position: 0 }
+    }
+
+    #[test]
+    fn test_check_note_moves_rejects_cycles() {
+        // a -> b -> c
+        let parents: HashMap<String, Option<String>> = [
+            ("a".to_string(), None),
+            ("b".to_string(), Some("a".to_string())),
+            ("c".to_string(), Some("b".to_string())),
+        ]
+        .into();
+
+        assert!(check_note_moves(&parents, &[moved("c", None), moved("a", Some("c"))]).is_ok());
+        assert!(check_note_moves(&parents, &[moved("a", Some("c"))]).unwrap_err().contains("own ancestor"));
+        assert!(check_note_moves(&parents, &[moved("b", Some("b"))]).is_err());
+        assert!(check_note_moves(&parents, &[moved("b", Some("zz"))]).unwrap_err().contains("Parent note not found"));
+        assert!(check_note_moves(&parents, &[moved("b", None), moved("b", Some("a"))]).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, Weekday};
+use std::fmt;
+
+/// Days looked ahead for the next occurrence before a rule is considered finished
+const MAX_LOOKAHEAD_DAYS: i64 = 2 * 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// RRULE-style recurrence for reminders, e.g. `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE`.
+///
+/// Supported parts are FREQ (DAILY, WEEKLY, MONTHLY), INTERVAL, BYDAY (daily and weekly
+/// rules) and UNTIL (inclusive date). The shorthands `daily`, `weekdays`, `weekly` and
+/// `monthly` are accepted too. Occurrences keep the wall-clock time and UTC offset of the
+/// first one; monthly rules skip months that don't have its day (no Feb 30).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// Empty means every day for daily rules and the first occurrence's weekday for weekly ones
+    pub by_day: Vec<Weekday>,
+    pub until: Option<NaiveDate>,
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+impl Recurrence {
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let rule = match rule.to_ascii_lowercase().as_str() {
+            "daily" => "FREQ=DAILY",
+            "weekdays" => "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR",
+            "weekly" => "FREQ=WEEKLY",
+            "monthly" => "FREQ=MONTHLY",
+            _ => rule.strip_prefix("RRULE:").unwrap_or(rule),
+        };
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut until = None;
+        for part in rule.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| format!("Invalid recurrence part '{}'", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        other => return Err(format!("Unsupported recurrence frequency '{}'", other)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|i| (1..=365).contains(i))
+                        .ok_or_else(|| "INTERVAL must be between 1 and 365".to_string())?
+                }
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let weekday = WEEKDAYS
+                            .iter()
+                            .find(|(code, _)| code.eq_ignore_ascii_case(day.trim()))
+                            .map(|(_, weekday)| *weekday)
+                            .ok_or_else(|| format!("Invalid BYDAY value '{}'", day))?;
+                        if !by_day.contains(&weekday) {
+                            by_day.push(weekday);
+                        }
+                    }
+                }
+                "UNTIL" => {
+                    let date = value.get(..8).filter(|d| d.chars().all(|c| c.is_ascii_digit()));
+                    until = Some(
+                        date.and_then(|d| NaiveDate::parse_from_str(d, "%Y%m%d").ok())
+                            .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+                            .ok_or_else(|| format!("Invalid UNTIL date '{}'", value))?,
+                    );
+                }
+                other => return Err(format!("Unsupported recurrence part '{}'", other)),
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| "Recurrence needs a FREQ".to_string())?;
+        if frequency == Frequency::Monthly && !by_day.is_empty() {
+            return Err("BYDAY is not supported for monthly recurrence".to_string());
+        }
+        by_day.sort_by_key(|d| d.num_days_from_monday());
+        Ok(Self { frequency, interval, by_day, until })
+    }
+
+    /// True when the series starting on `first` has an occurrence on `date`
+    pub fn occurs_on(&self, first: NaiveDate, date: NaiveDate) -> bool {
+        if date < first || self.until.is_some_and(|until| date > until) {
+            return false;
+        }
+        let interval = self.interval as i64;
+        match self.frequency {
+            Frequency::Daily => {
+                (date - first).num_days() % interval == 0
+                    && (self.by_day.is_empty() || self.by_day.contains(&date.weekday()))
+            }
+            Frequency::Weekly => {
+                let week_start = |d: NaiveDate| d - Duration::days(d.weekday().num_days_from_monday() as i64);
+                let weeks = (week_start(date) - week_start(first)).num_days() / 7;
+                let on_day = if self.by_day.is_empty() {
+                    date.weekday() == first.weekday()
+                } else {
+                    self.by_day.contains(&date.weekday())
+                };
+                weeks % interval == 0 && on_day
+            }
+            Frequency::Monthly => {
+                let months = (date.year() - first.year()) as i64 * 12 + date.month() as i64 - first.month() as i64;
+                months % interval == 0 && date.day() == first.day()
+            }
+        }
+    }
+
+    /// Occurrence dates of the series starting on `first` within `from..=to`
+    pub fn dates_between(&self, first: NaiveDate, from: NaiveDate, to: NaiveDate) -> Vec<NaiveDate> {
+        let to = self.until.map_or(to, |until| to.min(until));
+        let mut date = from.max(first);
+        let mut dates = Vec::new();
+        while date <= to {
+            if self.occurs_on(first, date) {
+                dates.push(date);
+            }
+            date += Duration::days(1);
+        }
+        dates
+    }
+
+    /// First occurrence strictly after `after`, or None once the rule has ended
+    pub fn next_after(&self, first: DateTime<FixedOffset>, after: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+        let offset = *first.offset();
+        let start = first.date_naive().max(after.with_timezone(&offset).date_naive());
+        (0..=MAX_LOOKAHEAD_DAYS)
+            .map(|days| start + Duration::days(days))
+            .take_while(|date| self.until.is_none_or(|until| *date <= until))
+            .filter(|date| self.occurs_on(first.date_naive(), *date))
+            .filter_map(|date| date.and_time(first.time()).and_local_timezone(offset).single())
+            .find(|occurrence| *occurrence > after)
+    }
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval > 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self
+                .by_day
+                .iter()
+                .filter_map(|day| WEEKDAYS.iter().find(|(_, w)| w == day).map(|(code, _)| *code))
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%d"))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_recurrence_expansion_and_next_occurrence() {
+        let weekdays = Recurrence::parse("weekdays").unwrap();
+        assert_eq!(weekdays.to_string(), "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR");
+        // Friday 2025-03-07 through Tuesday 2025-03-11 skips the weekend
+        assert_eq!(
+            weekdays.dates_between(date("2025-03-07"), date("2025-03-01"), date("2025-03-11")),
+            vec![date("2025-03-07"), date("2025-03-10"), date("2025-03-11")]
+        );
+
+        let biweekly = Recurrence::parse("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20250331").unwrap();
+        assert!(biweekly.occurs_on(date("2025-03-03"), date("2025-03-05")));
+        assert!(!biweekly.occurs_on(date("2025-03-03"), date("2025-03-10")));
+        assert!(biweekly.occurs_on(date("2025-03-03"), date("2025-03-17")));
+
+        // Monthly on the 31st skips April
+        let monthly = Recurrence::parse("monthly").unwrap();
+        let first = DateTime::parse_from_rfc3339("2025-03-31T09:30:00-04:00").unwrap();
+        let next = monthly.next_after(first, first).unwrap();
+        assert_eq!(next.to_rfc3339(), "2025-05-31T09:30:00-04:00");
+
+        let ended = Recurrence::parse("FREQ=DAILY;UNTIL=2025-03-31").unwrap();
+        assert!(ended.next_after(first, first).is_none());
+        assert!(Recurrence::parse("FREQ=YEARLY").is_err());
+    }
+}
@@ -1,15 +1,20 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{DateTime, FixedOffset, Utc};
 use libsql::{Connection, params};
 use serde::{Deserialize, Serialize};
 
+use super::recurrence::Recurrence;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NotebookReminder {
     pub id: String,
     pub note_id: String,
     pub title: String,
     pub description: Option<String>,
+    /// Next time the reminder is due; recurring reminders move forward when completed
     pub reminder_time: String,
+    /// RRULE-style rule, see [`Recurrence`]
+    pub recurrence: Option<String>,
     pub is_completed: bool,
     pub created_at: String,
     pub updated_at: String,
@@ -21,6 +26,8 @@ pub struct CreateReminderRequest {
     pub title: String,
     pub description: Option<String>,
     pub reminder_time: String,
+    #[serde(default)]
+    pub recurrence: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -28,17 +35,28 @@ pub struct UpdateReminderRequest {
     pub title: Option<String>,
     pub description: Option<Option<String>>,
     pub reminder_time: Option<String>,
+    /// `Some(None)` makes the reminder one-off again
+    pub recurrence: Option<Option<String>>,
     pub is_completed: Option<bool>,
 }
 
+/// Validate a recurrence rule and store it in its canonical form
+pub fn normalize_recurrence(rule: Option<&str>) -> Result<Option<String>> {
+    match rule.map(str::trim).filter(|r| !r.is_empty()) {
+        Some(rule) => Ok(Some(Recurrence::parse(rule).map_err(anyhow::Error::msg)?.to_string())),
+        None => Ok(None),
+    }
+}
+
 impl NotebookReminder {
     pub async fn create(conn: &Connection, req: CreateReminderRequest) -> Result<Self> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
+        let recurrence = normalize_recurrence(req.recurrence.as_deref())?;
         conn.execute(
-            r#"INSERT INTO notebook_reminders (id, note_id, title, description, reminder_time, is_completed, created_at, updated_at)
-               VALUES (?, ?, ?, ?, ?, 0, ?, ?)"#,
-            params![id.clone(), req.note_id, req.title.clone(), req.description.clone(), req.reminder_time.clone(), now.clone(), now.clone()],
+            r#"INSERT INTO notebook_reminders (id, note_id, title, description, reminder_time, recurrence, is_completed, created_at, updated_at)
+               VALUES (?, ?, ?, ?, ?, ?, 0, ?, ?)"#,
+            params![id.clone(), req.note_id, req.title.clone(), req.description.clone(), req.reminder_time.clone(), recurrence, now.clone(), now.clone()],
         ).await?;
 
         // Create calendar event automatically
@@ -62,7 +80,7 @@ impl NotebookReminder {
 
     pub async fn find_by_id(conn: &Connection, id: &str) -> Result<Self> {
         let stmt = conn.prepare(
-            r#"SELECT id, note_id, title, description, reminder_time, is_completed, created_at, updated_at
+            r#"SELECT id, note_id, title, description, reminder_time, recurrence, is_completed, created_at, updated_at
                 FROM notebook_reminders WHERE id = ?"#,
         ).await?;
         let mut rows = stmt.query(params![id]).await?;
@@ -72,7 +90,7 @@ impl NotebookReminder {
     #[allow(dead_code)]
     pub async fn find_by_note_id(conn: &Connection, note_id: &str) -> Result<Vec<Self>> {
         let stmt = conn.prepare(
-            r#"SELECT id, note_id, title, description, reminder_time, is_completed, created_at, updated_at
+            r#"SELECT id, note_id, title, description, reminder_time, recurrence, is_completed, created_at, updated_at
                 FROM notebook_reminders WHERE note_id = ? ORDER BY reminder_time ASC"#,
         ).await?;
         let mut rows = stmt.query(params![note_id]).await?;
@@ -87,35 +105,54 @@ impl NotebookReminder {
         if let Some(title) = updates.title { sets.push("title = ?".to_string()); params_dyn.push(title); }
         if let Some(desc_opt) = updates.description { match desc_opt { Some(desc) => { sets.push("description = ?".to_string()); params_dyn.push(desc); }, None => sets.push("description = NULL".to_string()) } }
         if let Some(rt) = updates.reminder_time { sets.push("reminder_time = ?".to_string()); params_dyn.push(rt); }
+        if let Some(rule_opt) = updates.recurrence { match normalize_recurrence(rule_opt.as_deref())? { Some(rule) => { sets.push("recurrence = ?".to_string()); params_dyn.push(rule); }, None => sets.push("recurrence = NULL".to_string()) } }
         if let Some(done) = updates.is_completed { sets.push("is_completed = ?".to_string()); params_dyn.push((if done {1}else{0}).to_string()); }
         if sets.is_empty() { return Self::find_by_id(conn, id).await; }
         sets.push("updated_at = ?".to_string()); params_dyn.push(Utc::now().to_rfc3339());
         params_dyn.push(id.to_string());
         let sql = format!("UPDATE notebook_reminders SET {} WHERE id = ?", sets.join(", "));
-        match params_dyn.len() {
-            1 => { conn.execute(sql.as_str(), params![params_dyn[0].as_str(), id]).await?; }
-            2 => { conn.execute(sql.as_str(), params![params_dyn[0].as_str(), params_dyn[1].as_str(), id]).await?; }
-            3 => { conn.execute(sql.as_str(), params![params_dyn[0].as_str(), params_dyn[1].as_str(), params_dyn[2].as_str(), id]).await?; }
-            4 => { conn.execute(sql.as_str(), params![params_dyn[0].as_str(), params_dyn[1].as_str(), params_dyn[2].as_str(), params_dyn[3].as_str(), id]).await?; }
-            _ => { conn.execute(sql.as_str(), params![id]).await?; }
-        }
+        conn.execute(sql.as_str(), libsql::params_from_iter(params_dyn)).await?;
         Self::find_by_id(conn, id).await
     }
 
     pub async fn delete(conn: &Connection, id: &str) -> Result<bool> {
-        // calendar_events has FK ON DELETE CASCADE
+        // Tables rebuilt by schema migrations lose the ON DELETE CASCADE, so don't rely on it
+        conn.execute("DELETE FROM calendar_events WHERE reminder_id = ?", params![id]).await?;
         let affected = conn.execute("DELETE FROM notebook_reminders WHERE id = ?", params![id]).await?;
         Ok(affected > 0)
     }
 
+    /// Complete the current occurrence. A recurring reminder moves on to its next occurrence
+    /// after now and stays open; it is only completed once its rule has ended.
     pub async fn mark_completed(conn: &Connection, id: &str) -> Result<Self> {
-        conn.execute(
-            "UPDATE notebook_reminders SET is_completed = 1, updated_at = ? WHERE id = ?",
-            params![Utc::now().to_rfc3339(), id],
-        ).await?;
+        let reminder = Self::find_by_id(conn, id).await?;
+        let now = Utc::now();
+        if let Some(next) = reminder.next_occurrence(now)? {
+            conn.execute(
+                "UPDATE notebook_reminders SET reminder_time = ?, updated_at = ? WHERE id = ?",
+                params![next.to_rfc3339(), now.to_rfc3339(), id],
+            ).await?;
+        } else {
+            conn.execute(
+                "UPDATE notebook_reminders SET is_completed = 1, updated_at = ? WHERE id = ?",
+                params![now.to_rfc3339(), id],
+            ).await?;
+        }
         Self::find_by_id(conn, id).await
     }
 
+    /// Occurrence following the current one and `now`, for recurring reminders
+    pub fn next_occurrence(&self, now: DateTime<Utc>) -> Result<Option<DateTime<FixedOffset>>> {
+        let Some(rule) = self.recurrence.as_deref() else {
+            return Ok(None);
+        };
+        let recurrence = Recurrence::parse(rule).map_err(anyhow::Error::msg)?;
+        let current = DateTime::parse_from_rfc3339(&self.reminder_time)
+            .map_err(|_| anyhow::anyhow!("Invalid reminder_time format"))?;
+        let after = current.max(now.with_timezone(current.offset()));
+        Ok(recurrence.next_after(current, after))
+    }
+
     fn from_row(row: libsql::Row) -> Result<Self> {
         Ok(Self {
             id: row.get(0)?,
@@ -123,9 +160,10 @@ impl NotebookReminder {
             title: row.get(2)?,
             description: row.get(3)?,
             reminder_time: row.get(4)?,
-            is_completed: !matches!(row.get::<i64>(5)?, 0),
-            created_at: row.get(6)?,
-            updated_at: row.get(7)?,
+            recurrence: row.get(5)?,
+            is_completed: !matches!(row.get::<i64>(6)?, 0),
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
         })
     }
 }
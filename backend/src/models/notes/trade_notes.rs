@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use libsql::{Connection, params};
 
+use crate::service::note_encryption::{self, NoteCipher};
+
 /// Trade note model for user's isolated database
 /// No user_id needed since each user has their own database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +52,7 @@ impl TradeNote {
         conn: &Connection,
         request: CreateTradeNoteRequest,
     ) -> Result<TradeNote, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let id = Uuid::new_v4().to_string();
         let now = Utc::now().to_rfc3339();
 
@@ -66,14 +69,14 @@ impl TradeNote {
             .query(params![
                 id,
                 request.name,
-                request.content,
+                note_encryption::seal_content(cipher.as_ref(), request.content)?,
                 now.clone(),
                 now
             ])
             .await?;
 
         if let Some(row) = rows.next().await? {
-            Ok(TradeNote::from_row(&row)?)
+            Ok(TradeNote::from_row(&row, cipher.as_ref())?)
         } else {
             Err("Failed to create trade note".into())
         }
@@ -84,6 +87,7 @@ impl TradeNote {
         conn: &Connection,
         note_id: &str,
     ) -> Result<Option<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -97,7 +101,7 @@ impl TradeNote {
             .await?;
 
         if let Some(row) = rows.next().await? {
-            Ok(Some(TradeNote::from_row(&row)?))
+            Ok(Some(TradeNote::from_row(&row, cipher.as_ref())?))
         } else {
             Ok(None)
         }
@@ -108,6 +112,7 @@ impl TradeNote {
         conn: &Connection,
         stock_trade_id: i64,
     ) -> Result<Option<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -121,7 +126,7 @@ impl TradeNote {
             .await?;
 
         if let Some(row) = rows.next().await? {
-            Ok(Some(TradeNote::from_row(&row)?))
+            Ok(Some(TradeNote::from_row(&row, cipher.as_ref())?))
         } else {
             Ok(None)
         }
@@ -132,6 +137,7 @@ impl TradeNote {
         conn: &Connection,
         option_trade_id: i64,
     ) -> Result<Option<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -145,7 +151,7 @@ impl TradeNote {
             .await?;
 
         if let Some(row) = rows.next().await? {
-            Ok(Some(TradeNote::from_row(&row)?))
+            Ok(Some(TradeNote::from_row(&row, cipher.as_ref())?))
         } else {
             Ok(None)
         }
@@ -160,7 +166,9 @@ impl TradeNote {
         content: String,
         ai_metadata: Option<String>,
     ) -> Result<TradeNote, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let now = Utc::now().to_rfc3339();
+        let content = note_encryption::seal_content(cipher.as_ref(), content)?;
 
         // Check if note already exists for this trade
         let existing_note = match trade_type {
@@ -194,7 +202,7 @@ impl TradeNote {
                 .await?;
 
             if let Some(row) = rows.next().await? {
-                Ok(TradeNote::from_row(&row)?)
+                Ok(TradeNote::from_row(&row, cipher.as_ref())?)
             } else {
                 Err("Failed to update trade note".into())
             }
@@ -231,7 +239,7 @@ impl TradeNote {
                 .await?;
 
             if let Some(row) = rows.next().await? {
-                Ok(TradeNote::from_row(&row)?)
+                Ok(TradeNote::from_row(&row, cipher.as_ref())?)
             } else {
                 Err("Failed to create trade note".into())
             }
@@ -243,6 +251,7 @@ impl TradeNote {
         conn: &Connection,
         query: TradeNoteQuery,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut sql = String::from(
             r#"
             SELECT id, name, content, trade_type, stock_trade_id, option_trade_id, ai_metadata, created_at, updated_at
@@ -260,9 +269,14 @@ impl TradeNote {
         }
 
         if let Some(search) = &query.search {
-            sql.push_str(" AND (name LIKE ? OR content LIKE ?)");
             let pattern = format!("%{}%", search);
-            query_params.push(libsql::Value::Text(pattern.clone()));
+            // Sealed content can't be matched in SQL; encrypted journals search names only
+            if cipher.is_some() {
+                sql.push_str(" AND name LIKE ?");
+            } else {
+                sql.push_str(" AND (name LIKE ? OR content LIKE ?)");
+                query_params.push(libsql::Value::Text(pattern.clone()));
+            }
             query_params.push(libsql::Value::Text(pattern));
         }
 
@@ -297,7 +311,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -309,6 +323,7 @@ impl TradeNote {
         note_id: &str,
         request: UpdateTradeNoteRequest,
     ) -> Result<Option<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         // Ensure the note exists
         let current_note = Self::find_by_id(conn, note_id).await?;
         if current_note.is_none() {
@@ -331,14 +346,14 @@ impl TradeNote {
             .await?
             .query(params![
                 request.name,
-                request.content,
+                request.content.map(|content| note_encryption::seal_content(cipher.as_ref(), content)).transpose()?,
                 now,
                 note_id
             ])
             .await?;
 
         if let Some(row) = rows.next().await? {
-            Ok(Some(TradeNote::from_row(&row)?))
+            Ok(Some(TradeNote::from_row(&row, cipher.as_ref())?))
         } else {
             Ok(None)
         }
@@ -364,6 +379,7 @@ impl TradeNote {
         conn: &Connection,
         query: &TradeNoteQuery,
     ) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut sql = String::from("SELECT COUNT(*) FROM trade_notes WHERE 1=1");
         let mut query_params: Vec<libsql::Value> = Vec::new();
 
@@ -374,9 +390,14 @@ impl TradeNote {
         }
 
         if let Some(search) = &query.search {
-            sql.push_str(" AND (name LIKE ? OR content LIKE ?)");
             let pattern = format!("%{}%", search);
-            query_params.push(libsql::Value::Text(pattern.clone()));
+            // Sealed content can't be matched in SQL; encrypted journals search names only
+            if cipher.is_some() {
+                sql.push_str(" AND name LIKE ?");
+            } else {
+                sql.push_str(" AND (name LIKE ? OR content LIKE ?)");
+                query_params.push(libsql::Value::Text(pattern.clone()));
+            }
             query_params.push(libsql::Value::Text(pattern));
         }
 
@@ -403,19 +424,23 @@ impl TradeNote {
         }
     }
 
-    /// Search trade notes by content
+    /// Search trade notes by content (by name when the user's notes are encrypted)
     pub async fn search_by_content(
         conn: &Connection,
         search_term: &str,
         limit: Option<i64>,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
-        let mut sql = String::from(
+        let cipher = NoteCipher::for_connection(conn).await?;
+        // Sealed content can't be matched in SQL; encrypted journals search names only
+        let column = if cipher.is_some() { "name" } else { "content" };
+        let mut sql = format!(
             r#"
             SELECT id, name, content, trade_type, stock_trade_id, option_trade_id, ai_metadata, created_at, updated_at
             FROM trade_notes 
-            WHERE content LIKE ?
+            WHERE {} LIKE ?
             ORDER BY updated_at DESC
             "#,
+            column
         );
 
         let mut params_vec: Vec<libsql::Value> = vec![
@@ -435,7 +460,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -446,6 +471,7 @@ impl TradeNote {
         conn: &Connection,
         limit: i64,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -461,7 +487,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -474,6 +500,7 @@ impl TradeNote {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -489,7 +516,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -502,6 +529,7 @@ impl TradeNote {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let mut rows = conn
             .prepare(
                 r#"
@@ -517,7 +545,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -562,6 +590,7 @@ impl TradeNote {
         page: i64,
         page_size: i64,
     ) -> Result<Vec<TradeNote>, Box<dyn std::error::Error + Send + Sync>> {
+        let cipher = NoteCipher::for_connection(conn).await?;
         let offset = (page - 1) * page_size;
 
         let mut rows = conn
@@ -579,7 +608,7 @@ impl TradeNote {
 
         let mut notes = Vec::new();
         while let Some(row) = rows.next().await? {
-            notes.push(TradeNote::from_row(&row)?);
+            notes.push(TradeNote::from_row(&row, cipher.as_ref())?);
         }
 
         Ok(notes)
@@ -588,7 +617,7 @@ impl TradeNote {
 
 /// Convert from libsql row to TradeNote struct
 impl TradeNote {
-    fn from_row(row: &libsql::Row) -> Result<TradeNote, Box<dyn std::error::Error + Send + Sync>> {
+    fn from_row(row: &libsql::Row, cipher: Option<&NoteCipher>) -> Result<TradeNote, Box<dyn std::error::Error + Send + Sync>> {
         // Helper function to parse datetime from various formats (RFC3339, SQLite format, etc.)
         fn parse_dt_any(s: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error + Send + Sync>> {
             // Handle empty strings
@@ -638,7 +667,7 @@ impl TradeNote {
             Ok(TradeNote {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                content: row.get(2)?,
+                content: note_encryption::open_content(cipher, row.get(2)?)?,
                 trade_type: row.get(3).ok(),
                 stock_trade_id: row.get(4).ok(),
                 option_trade_id: row.get(5).ok(),
@@ -660,7 +689,7 @@ impl TradeNote {
             Ok(TradeNote {
                 id: row.get(0)?,
                 name: row.get(1)?,
-                content: row.get(2)?,
+                content: note_encryption::open_content(cipher, row.get(2)?)?,
                 trade_type: None,
                 stock_trade_id: None,
                 option_trade_id: None,
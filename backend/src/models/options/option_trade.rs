@@ -4,6 +4,10 @@ use libsql::{Connection, params};
 
 /// Re-use the TimeRange enum from the stock model
 use crate::models::stock::stocks::TimeRange;
+use crate::models::money::{self, Decimal};
+use crate::models::pnl::{self, OPTION_PNL_SQL, OPTION_PRICE_GAIN_SQL};
+use crate::models::timestamps;
+use crate::models::venue;
 
 /// Trade status enum matching the PostgreSQL enum in your schema
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -103,18 +107,21 @@ pub struct OptionTrade {
     pub trade_direction: TradeDirection,
     pub number_of_contracts: i32,
     pub option_type: OptionType,
-    pub strike_price: f64,
+    pub strike_price: Decimal,
+    #[serde(with = "timestamps::utc_millis")]
     pub expiration_date: DateTime<Utc>,
-    pub entry_price: f64,
-    pub exit_price: Option<f64>,
-    pub total_premium: f64,
-    pub commissions: f64,
+    pub entry_price: Decimal,
+    pub exit_price: Option<Decimal>,
+    pub total_premium: Decimal,
+    pub commissions: Decimal,
     pub implied_volatility: f64,
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub exit_date: Option<DateTime<Utc>>,
     pub status: TradeStatus,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
     pub reviewed: bool,
     pub mistakes: Option<String>,
@@ -122,6 +129,27 @@ pub struct OptionTrade {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub is_deleted: bool,
+    /// Stored by `realized_option_pnl` on write; None until the trade is closed
+    pub realized_pnl: Option<Decimal>,
+    /// Exchange the trade executed on, normalized by `venue::normalize_venue`
+    pub venue: Option<String>,
+    /// Premium sold to open (credit) rather than bought (debit); flips the sign of the P&L
+    pub short_premium: bool,
+}
+
+/// Realized P&L of an option trade, or None until it's closed with an exit price and date.
+/// Like `realized_stock_pnl`, this is the only place the stored column is derived.
+pub fn realized_option_pnl(
+    status: &TradeStatus,
+    short_premium: bool,
+    entry_price: Decimal,
+    exit_price: Option<Decimal>,
+    exited: bool,
+    number_of_contracts: i32,
+    commissions: Decimal,
+) -> Option<Decimal> {
+    let exit_price = exit_price.filter(|_| exited && *status == TradeStatus::Closed)?;
+    Some(pnl::option_pnl(short_premium, entry_price, exit_price, number_of_contracts as i64, commissions))
 }
 
 /// Simplified response for open option trades (only essential fields)
@@ -129,7 +157,8 @@ pub struct OptionTrade {
 #[serde(rename_all = "camelCase")]
 pub struct OpenOptionTrade {
     pub symbol: String,
-    pub entry_price: f64,
+    pub entry_price: Decimal,
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
 }
 
@@ -142,23 +171,32 @@ pub struct CreateOptionRequest {
     pub trade_direction: TradeDirection,
     pub number_of_contracts: i32,
     pub option_type: OptionType,
-    pub strike_price: f64,
+    pub strike_price: Decimal,
+    #[serde(with = "timestamps::utc_millis")]
     pub expiration_date: DateTime<Utc>,
-    pub entry_price: f64,
-    pub total_premium: f64,
-    pub commissions: f64,
+    pub entry_price: Decimal,
+    pub total_premium: Decimal,
+    pub commissions: Decimal,
+    /// Zero or omitted: filled from the live options chain for trades logged as they happen
+    #[serde(default)]
     pub implied_volatility: f64,
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
     pub reviewed: Option<bool>,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
+    /// Exchange code or name the contract traded on (`CBOE`, `XCBO`, ...)
+    pub venue: Option<String>,
+    /// Premium sold to open; inferred from the strategy and direction when omitted
+    #[serde(default)]
+    pub short_premium: Option<bool>,
 }
 
 /// Data Transfer Object for updating option trades
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateOptionRequest {
     pub symbol: Option<String>,
@@ -166,22 +204,45 @@ pub struct UpdateOptionRequest {
     pub trade_direction: Option<TradeDirection>,
     pub number_of_contracts: Option<i32>,
     pub option_type: Option<OptionType>,
-    pub strike_price: Option<f64>,
+    pub strike_price: Option<Decimal>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub expiration_date: Option<DateTime<Utc>>,
-    pub entry_price: Option<f64>,
-    pub exit_price: Option<f64>,
-    pub total_premium: Option<f64>,
-    pub commissions: Option<f64>,
+    pub entry_price: Option<Decimal>,
+    pub exit_price: Option<Decimal>,
+    pub total_premium: Option<Decimal>,
+    pub commissions: Option<Decimal>,
     pub implied_volatility: Option<f64>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub entry_date: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub exit_date: Option<DateTime<Utc>>,
     pub status: Option<TradeStatus>,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
     pub reviewed: Option<bool>,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
+    pub venue: Option<String>,
+    pub short_premium: Option<bool>,
+}
+
+impl UpdateOptionRequest {
+    /// Premium side after this edit: set explicitly, re-inferred when the strategy,
+    /// direction or option type changes, otherwise kept
+    pub fn short_premium_over(&self, current: &OptionTrade) -> bool {
+        if let Some(short_premium) = self.short_premium {
+            return short_premium;
+        }
+        if self.strategy_type.is_none() && self.trade_direction.is_none() && self.option_type.is_none() {
+            return current.short_premium;
+        }
+        pnl::is_short_premium(
+            self.strategy_type.as_deref().unwrap_or(&current.strategy_type),
+            &self.trade_direction.as_ref().unwrap_or(&current.trade_direction).to_string(),
+            &self.option_type.as_ref().unwrap_or(&current.option_type).to_string(),
+        )
+    }
 }
 
 /// Option query parameters for filtering and pagination
@@ -192,7 +253,9 @@ pub struct OptionQuery {
     pub trade_direction: Option<TradeDirection>,
     pub option_type: Option<OptionType>,
     pub status: Option<TradeStatus>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub start_date: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub end_date: Option<DateTime<Utc>>,
     pub time_range: Option<TimeRange>,
     pub limit: Option<i64>,
@@ -219,28 +282,15 @@ impl OptionTrade {
         }
     }
 
-    fn get_opt_f64(row: &libsql::Row, idx: usize) -> Result<Option<f64>, Box<dyn std::error::Error + Send + Sync>> {
-        let i = idx as i32;
-
-        // Get the raw value first to check its type
-        match row.get::<libsql::Value>(i) {
-            Ok(libsql::Value::Real(v)) => Ok(Some(v)),
-            Ok(libsql::Value::Integer(v)) => Ok(Some(v as f64)),
-            Ok(libsql::Value::Text(s)) => {
-                Ok(s.parse::<f64>().ok())
-            },
-            Ok(libsql::Value::Null) => Ok(None),
-            Ok(_) => Ok(None), // For any other type (Blob, etc)
-            Err(_) => Ok(None),
-        }
-    }
-
     /// Create a new option trade in the user's database
     pub async fn create(
         conn: &Connection,
         request: CreateOptionRequest,
     ) -> Result<OptionTrade, Box<dyn std::error::Error + Send + Sync>> {
         let now = Utc::now().to_rfc3339();
+        let short_premium = request.short_premium.unwrap_or_else(|| {
+            pnl::is_short_premium(&request.strategy_type, &request.trade_direction.to_string(), &request.option_type.to_string())
+        });
 
         let mut rows = conn.prepare(
             r#"
@@ -249,13 +299,13 @@ impl OptionTrade {
                 option_type, strike_price, expiration_date, entry_price,
                 total_premium, commissions, implied_volatility, entry_date,
                 status, initial_target, profit_target, trade_ratings,
-                reviewed, mistakes, brokerage_name, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                reviewed, mistakes, brokerage_name, venue, short_premium, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING id, symbol, strategy_type, trade_direction, number_of_contracts,
                      option_type, strike_price, expiration_date, entry_price, exit_price,
                      total_premium, commissions, implied_volatility, entry_date, exit_date,
                      status, initial_target, profit_target, trade_ratings, reviewed, mistakes,
-                     brokerage_name, created_at, updated_at, is_deleted
+                     brokerage_name, created_at, updated_at, is_deleted, realized_pnl, venue, short_premium
             "#,
         )
         .await?
@@ -265,20 +315,22 @@ impl OptionTrade {
             request.trade_direction.to_string(),
             request.number_of_contracts,
             request.option_type.to_string(),
-            request.strike_price,
-            request.expiration_date.to_rfc3339(),
-            request.entry_price,
-            request.total_premium,
-            request.commissions,
+            money::sql_value(request.strike_price),
+            timestamps::to_db(&request.expiration_date),
+            money::sql_value(request.entry_price),
+            money::sql_value(request.total_premium),
+            money::sql_value(request.commissions),
             request.implied_volatility,
-            request.entry_date.to_rfc3339(),
+            timestamps::to_db(&request.entry_date),
             TradeStatus::Open.to_string(),
-            request.initial_target,
-            request.profit_target,
+            money::sql_value_opt(request.initial_target),
+            money::sql_value_opt(request.profit_target),
             request.trade_ratings,
             request.reviewed.unwrap_or(false),
             request.mistakes,
             request.brokerage_name,
+            venue::normalize_venue_opt(request.venue.as_deref()),
+            short_premium,
             now.clone(),
             now
         ])
@@ -303,7 +355,7 @@ impl OptionTrade {
                        option_type, strike_price, expiration_date, entry_price, exit_price,
                        total_premium, commissions, implied_volatility, entry_date, exit_date,
                        status, initial_target, profit_target, trade_ratings, reviewed, mistakes,
-                       brokerage_name, created_at, updated_at, is_deleted
+                       brokerage_name, created_at, updated_at, is_deleted, realized_pnl, venue, short_premium
                 FROM options
                 WHERE id = ?
                 "#,
@@ -330,7 +382,7 @@ impl OptionTrade {
                    option_type, strike_price, expiration_date, entry_price, exit_price,
                    total_premium, commissions, implied_volatility, entry_date, exit_date,
                    status, initial_target, profit_target, trade_ratings, reviewed, mistakes,
-                   brokerage_name, created_at, updated_at, is_deleted
+                   brokerage_name, created_at, updated_at, is_deleted, realized_pnl, venue, short_premium
             FROM options
             WHERE 1=1
             "#,
@@ -366,12 +418,12 @@ impl OptionTrade {
 
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
 
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         // Convert time_range to start_date/end_date if provided
@@ -379,11 +431,11 @@ impl OptionTrade {
             let (start, end) = time_range.to_dates();
             if let Some(start_date) = start {
                 sql.push_str(" AND entry_date >= ?");
-                query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
             }
             if let Some(end_date) = end {
                 sql.push_str(" AND entry_date <= ?");
-                query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
             }
         }
 
@@ -461,12 +513,12 @@ impl OptionTrade {
 
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
         
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         // Convert time_range to start_date/end_date if provided
@@ -474,11 +526,11 @@ impl OptionTrade {
             let (start, end) = time_range.to_dates();
             if let Some(start_date) = start {
                 sql.push_str(" AND entry_date >= ?");
-                query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
             }
             if let Some(end_date) = end {
                 sql.push_str(" AND entry_date <= ?");
-                query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
             }
         }
 
@@ -504,11 +556,10 @@ impl OptionTrade {
         let mut open_trades = Vec::new();
         while let Some(row) = rows.next().await? {
             let symbol: String = row.get(0)?;
-            let entry_price = Self::get_f64(&row, 1)?;
+            let entry_price = money::row_decimal(&row, 1);
             let entry_date_str: String = row.get(2)?;
-            let entry_date = DateTime::parse_from_rfc3339(&entry_date_str)
-                .map_err(|e| format!("Failed to parse entry_date: {}", e))?
-                .with_timezone(&Utc);
+            let entry_date = timestamps::parse_stored(&entry_date_str)
+                .map_err(|e| format!("Failed to parse entry_date: {}", e))?;
             
             open_trades.push(OpenOptionTrade {
                 symbol,
@@ -531,12 +582,22 @@ impl OptionTrade {
         log::info!("Update request: {:?}", request);
 
         // Check if option exists first
-        let current_option = Self::find_by_id(conn, option_id).await?;
-
-        if current_option.is_none() {
+        let Some(current_option) = Self::find_by_id(conn, option_id).await? else {
             log::warn!("Option {} not found", option_id);
             return Ok(None);
-        }
+        };
+
+        // Stored P&L follows the edit, computed from the merged values
+        let short_premium = request.short_premium_over(&current_option);
+        let realized_pnl = realized_option_pnl(
+            request.status.as_ref().unwrap_or(&current_option.status),
+            short_premium,
+            request.entry_price.unwrap_or(current_option.entry_price),
+            request.exit_price.or(current_option.exit_price),
+            request.exit_date.or(current_option.exit_date).is_some(),
+            request.number_of_contracts.unwrap_or(current_option.number_of_contracts),
+            request.commissions.unwrap_or(current_option.commissions),
+        );
 
         let now = Utc::now().to_rfc3339();
         log::info!("Generated timestamp: {}", now);
@@ -567,13 +628,16 @@ impl OptionTrade {
                     reviewed = COALESCE(?, reviewed),
                     mistakes = COALESCE(?, mistakes),
                     brokerage_name = COALESCE(?, brokerage_name),
+                    venue = COALESCE(?, venue),
+                    short_premium = ?,
+                    realized_pnl = ?,
                     updated_at = ?
                 WHERE id = ?
                 RETURNING id, symbol, strategy_type, trade_direction, number_of_contracts,
                          option_type, strike_price, expiration_date, entry_price, exit_price,
                          total_premium, commissions, implied_volatility, entry_date, exit_date,
                          status, initial_target, profit_target, trade_ratings, reviewed, mistakes,
-                         brokerage_name, created_at, updated_at, is_deleted
+                         brokerage_name, created_at, updated_at, is_deleted, realized_pnl, venue, short_premium
                 "#,
             )
             .await?
@@ -583,22 +647,25 @@ impl OptionTrade {
                 request.trade_direction.map(|t| t.to_string()),
                 request.number_of_contracts,
                 request.option_type.map(|t| t.to_string()),
-                request.strike_price,
-                request.expiration_date.map(|d| d.to_rfc3339()),
-                request.entry_price,
-                request.exit_price,
-                request.total_premium,
-                request.commissions,
+                money::sql_value_opt(request.strike_price),
+                request.expiration_date.as_ref().map(timestamps::to_db),
+                money::sql_value_opt(request.entry_price),
+                money::sql_value_opt(request.exit_price),
+                money::sql_value_opt(request.total_premium),
+                money::sql_value_opt(request.commissions),
                 request.implied_volatility,
-                request.entry_date.map(|d| d.to_rfc3339()),
-                request.exit_date.map(|d| d.to_rfc3339()),
+                request.entry_date.as_ref().map(timestamps::to_db),
+                request.exit_date.as_ref().map(timestamps::to_db),
                 request.status.map(|t| t.to_string()),
-                request.initial_target,
-                request.profit_target,
+                money::sql_value_opt(request.initial_target),
+                money::sql_value_opt(request.profit_target),
                 request.trade_ratings,
                 request.reviewed,
                 request.mistakes,
                 request.brokerage_name,
+                venue::normalize_venue_opt(request.venue.as_deref()),
+                short_premium,
+                money::sql_value_opt(realized_pnl),
                 now,
                 option_id
             ])
@@ -634,6 +701,54 @@ impl OptionTrade {
         }
     }
 
+    /// Copy a trade into a new row with `overrides` applied over the original's fields. The
+    /// copy is a separate trade, so it starts unreviewed with no mistakes unless overridden.
+    pub async fn duplicate(
+        conn: &Connection,
+        option_id: i64,
+        overrides: UpdateOptionRequest,
+    ) -> Result<Option<OptionTrade>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(original) = Self::find_by_id(conn, option_id).await? else {
+            return Ok(None);
+        };
+        let short_premium = overrides.short_premium_over(&original);
+        let exit_price = overrides.exit_price.or(original.exit_price);
+        let exit_date = overrides.exit_date.or(original.exit_date);
+        let status = overrides.status.unwrap_or(original.status);
+        let request = CreateOptionRequest {
+            symbol: overrides.symbol.unwrap_or(original.symbol),
+            strategy_type: overrides.strategy_type.unwrap_or(original.strategy_type),
+            trade_direction: overrides.trade_direction.unwrap_or(original.trade_direction),
+            number_of_contracts: overrides.number_of_contracts.unwrap_or(original.number_of_contracts),
+            option_type: overrides.option_type.unwrap_or(original.option_type),
+            strike_price: overrides.strike_price.unwrap_or(original.strike_price),
+            expiration_date: overrides.expiration_date.unwrap_or(original.expiration_date),
+            entry_price: overrides.entry_price.unwrap_or(original.entry_price),
+            total_premium: overrides.total_premium.unwrap_or(original.total_premium),
+            commissions: overrides.commissions.unwrap_or(original.commissions),
+            implied_volatility: overrides.implied_volatility.unwrap_or(original.implied_volatility),
+            entry_date: overrides.entry_date.unwrap_or(original.entry_date),
+            initial_target: overrides.initial_target.or(original.initial_target),
+            profit_target: overrides.profit_target.or(original.profit_target),
+            trade_ratings: overrides.trade_ratings.or(original.trade_ratings),
+            reviewed: overrides.reviewed,
+            mistakes: overrides.mistakes,
+            brokerage_name: overrides.brokerage_name.or(original.brokerage_name),
+            venue: overrides.venue.or(original.venue),
+            short_premium: Some(short_premium),
+        };
+
+        // Closing fields go through update so the stored P&L is computed the usual way
+        let tx = conn.transaction().await?;
+        let mut copy = Self::create(&tx, request).await?;
+        if status != TradeStatus::Open || exit_price.is_some() || exit_date.is_some() {
+            let exit = UpdateOptionRequest { exit_price, exit_date, status: Some(status), ..Default::default() };
+            copy = Self::update(&tx, copy.id, exit).await?.ok_or("Duplicated option trade not found")?;
+        }
+        tx.commit().await?;
+        Ok(Some(copy))
+    }
+
     /// Delete an option trade
     pub async fn delete(
         conn: &Connection,
@@ -682,12 +797,12 @@ impl OptionTrade {
 
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
 
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         let mut rows = conn
@@ -703,32 +818,94 @@ impl OptionTrade {
         }
     }
 
-    /// Calculate total P&L for all options in the user's database
+    /// Calculate total P&L for all options in the user's database.
+    /// Summed per trade in `Decimal` rather than with SQL `SUM` over REALs.
     pub async fn calculate_total_pnl(
         conn: &Connection,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
         let mut rows = conn
-            .prepare(
-                r#"
-                SELECT SUM(
-                    CASE
-                        WHEN exit_price IS NOT NULL THEN
-                            (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                        ELSE 0
-                    END
-                ) as total_pnl
-                FROM options
-                "#,
-            )
+            .prepare("SELECT realized_pnl FROM options WHERE realized_pnl IS NOT NULL")
             .await?
             .query(params![])
             .await?;
 
-        if let Some(row) = rows.next().await? {
-            Ok(row.get::<Option<f64>>(0)?.unwrap_or(0.0))
-        } else {
-            Ok(0.0)
+        Self::sum_pnl_rows(&mut rows).await
+    }
+
+    /// Sum the stored `realized_pnl` in the first column
+    async fn sum_pnl_rows(rows: &mut libsql::Rows) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
+        let mut total = Decimal::ZERO;
+        while let Some(row) = rows.next().await? {
+            total += money::row_decimal(&row, 0);
+        }
+        Ok(total)
+    }
+
+    /// Fill `short_premium` for trades written before the column existed, inferred the way
+    /// `create` infers it. Returns how many rows were classified as sold premium.
+    pub async fn classify_premium_side(
+        conn: &Connection,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare("SELECT id, strategy_type, trade_direction, option_type FROM options WHERE short_premium IS NULL")
+            .await?
+            .query(params![])
+            .await?;
+
+        let mut sides = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let short = pnl::is_short_premium(&row.get::<String>(1)?, &row.get::<String>(2)?, &row.get::<String>(3)?);
+            sides.push((id, short));
         }
+        drop(rows);
+
+        for (id, short) in &sides {
+            conn.execute("UPDATE options SET short_premium = ? WHERE id = ?", params![*short, *id]).await?;
+        }
+        Ok(sides.iter().filter(|(_, short)| *short).count())
+    }
+
+    /// Recompute the stored `realized_pnl` of every option trade. Returns how many rows
+    /// changed.
+    pub async fn recalculate_realized_pnl(
+        conn: &Connection,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare("SELECT id, status, entry_price, exit_price, exit_date, number_of_contracts, commissions, realized_pnl, short_premium FROM options")
+            .await?
+            .query(params![])
+            .await?;
+
+        let mut updates = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let status = row.get::<String>(1)?.parse::<TradeStatus>()
+                .map_err(|e| format!("Invalid status on option {}: {}", id, e))?;
+            let realized_pnl = realized_option_pnl(
+                &status,
+                row.get::<Option<i64>>(8)? == Some(1),
+                money::row_decimal(&row, 2),
+                money::row_decimal_opt(&row, 3),
+                row.get::<Option<String>>(4)?.is_some(),
+                Self::get_f64(&row, 5)? as i32,
+                money::row_decimal(&row, 6),
+            );
+            // Compare as stored so values that only differ below REAL precision aren't rewritten
+            if money::to_f64_opt(realized_pnl) != row.get::<Option<f64>>(7)? {
+                updates.push((id, realized_pnl));
+            }
+        }
+        drop(rows);
+
+        for (id, realized_pnl) in &updates {
+            conn.execute(
+                "UPDATE options SET realized_pnl = ? WHERE id = ?",
+                params![money::sql_value_opt(*realized_pnl), *id],
+            )
+            .await?;
+        }
+        Ok(updates.len())
     }
 
     /// Calculate profit factor (gross profit / gross loss)
@@ -742,7 +919,7 @@ impl OptionTrade {
             r#"
             WITH trade_profits AS (
                 SELECT
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions AS profit
+                    {OPTION_PNL_SQL} AS profit
                 FROM options
                 WHERE exit_date IS NOT NULL
                   AND exit_price IS NOT NULL
@@ -770,7 +947,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -798,7 +975,7 @@ impl OptionTrade {
             WITH trade_results AS (
                 SELECT
                     CASE
-                        WHEN (exit_price - entry_price) > 0 THEN 1
+                        WHEN {OPTION_PRICE_GAIN_SQL} > 0 THEN 1
                         ELSE 0
                     END AS is_winning_trade
                 FROM options
@@ -825,7 +1002,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -852,13 +1029,13 @@ impl OptionTrade {
             r#"
             SELECT
                 COALESCE(ROUND(AVG(
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
+                    {OPTION_PNL_SQL}
                 ), 2), 0) as avg_gain
             FROM options
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) > 0
+              AND {OPTION_PRICE_GAIN_SQL} > 0
               AND ({})
             "#,
             time_condition
@@ -866,7 +1043,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -893,13 +1070,13 @@ impl OptionTrade {
             r#"
             SELECT
                 COALESCE(ROUND(AVG(
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
+                    {OPTION_PNL_SQL}
                 ), 2), 0) as avg_loss
             FROM options
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) < 0
+              AND {OPTION_PRICE_GAIN_SQL} < 0
               AND ({})
             "#,
             time_condition
@@ -907,7 +1084,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -934,13 +1111,13 @@ impl OptionTrade {
             r#"
             SELECT
                 COALESCE(MAX(
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
+                    {OPTION_PNL_SQL}
                 ), 0) as biggest_winner
             FROM options
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) > 0
+              AND {OPTION_PRICE_GAIN_SQL} > 0
               AND ({})
             "#,
             time_condition
@@ -948,7 +1125,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -975,13 +1152,13 @@ impl OptionTrade {
             r#"
             SELECT
                 COALESCE(MIN(
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
+                    {OPTION_PNL_SQL}
                 ), 0) as biggest_loser
             FROM options
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) < 0
+              AND {OPTION_PRICE_GAIN_SQL} < 0
               AND ({})
             "#,
             time_condition
@@ -989,7 +1166,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1022,7 +1199,7 @@ impl OptionTrade {
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) > 0
+              AND {OPTION_PRICE_GAIN_SQL} > 0
               AND ({})
             "#,
             time_condition
@@ -1030,7 +1207,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1063,7 +1240,7 @@ impl OptionTrade {
             WHERE exit_date IS NOT NULL
               AND exit_price IS NOT NULL
               AND status = 'closed'
-              AND (exit_price - entry_price) < 0
+              AND {OPTION_PRICE_GAIN_SQL} < 0
               AND ({})
             "#,
             time_condition
@@ -1071,7 +1248,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1138,7 +1315,7 @@ impl OptionTrade {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1163,32 +1340,22 @@ impl OptionTrade {
         Ok((100.0 - win_rate).round())
     }
 
-    /// Calculate net P&L for the time range
+    /// Calculate net P&L for the time range from stored realized P&L; open positions count
+    /// as zero, the same as the analytics engine
     pub async fn calculate_net_pnl(
         conn: &Connection,
         time_range: TimeRange,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
         let (time_condition, time_params) = time_range.to_sql_condition();
 
         let sql = format!(
-            r#"
-            SELECT
-                COALESCE(SUM(
-                    CASE
-                        WHEN exit_price IS NOT NULL THEN
-                            (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                        ELSE -total_premium  -- Unrealized loss for open positions
-                    END
-                ), 0) as net_pnl
-            FROM options
-            WHERE ({})
-            "#,
+            "SELECT realized_pnl FROM options WHERE realized_pnl IS NOT NULL AND ({})",
             time_condition
         );
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1197,11 +1364,7 @@ impl OptionTrade {
             .query(libsql::params_from_iter(query_params))
             .await?;
 
-        if let Some(row) = rows.next().await? {
-            Ok(row.get::<Option<f64>>(0)?.unwrap_or(0.0))
-        } else {
-            Ok(0.0)
-        }
+        Self::sum_pnl_rows(&mut rows).await
     }
 
     /// Get playbook setups associated with this option trade
@@ -1343,24 +1506,9 @@ impl OptionTrade {
             _ => false,
         };
 
-        // Helper function to parse datetime that can be in either RFC3339 or SQLite format
         let parse_datetime = |datetime_str: &str, field_name: &str| -> Result<DateTime<Utc>, Box<dyn std::error::Error + Send + Sync>> {
-                    if datetime_str.contains('T') {
-                        // RFC3339 format: "2025-10-15T20:55:53.148886+00:00"
-                        Ok(
-                            DateTime::parse_from_rfc3339(datetime_str)
-                                .map_err(|e| format!("Failed to parse {} as RFC3339: {}", field_name, e))?
-                                .with_timezone(&Utc)
-                        )
-                    } else {
-                        // SQLite datetime format: "2025-10-29 07:17:16"
-                        Ok(
-                            chrono::NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%d %H:%M:%S")
-                                .map_err(|e| format!("Failed to parse {} as SQLite datetime: {}", field_name, e))?
-                                .and_utc()
-                        )
-                    }
-                };
+            timestamps::parse_stored(datetime_str).map_err(|e| format!("Failed to parse {}: {}", field_name, e).into())
+        };
 
         let expiration_date = parse_datetime(&expiration_date_str, "expiration_date")?;
         let entry_date = parse_datetime(&entry_date_str, "entry_date")?;
@@ -1399,18 +1547,18 @@ impl OptionTrade {
                 _ => return Err("Failed to get number_of_contracts".into()),
             },
             option_type,
-            strike_price: Self::get_f64(row, 6)?,
+            strike_price: money::row_decimal(row, 6),
             expiration_date,
-            entry_price: Self::get_f64(row, 8)?,
-            exit_price: Self::get_opt_f64(row, 9)?,
-            total_premium: Self::get_f64(row, 10)?,
-            commissions: Self::get_f64(row, 11)?,
+            entry_price: money::row_decimal(row, 8),
+            exit_price: money::row_decimal_opt(row, 9),
+            total_premium: money::row_decimal(row, 10),
+            commissions: money::row_decimal(row, 11),
             implied_volatility: Self::get_f64(row, 12)?,
             entry_date,
             exit_date,
             status,
-            initial_target: Self::get_opt_f64(row, 16)?,
-            profit_target: Self::get_opt_f64(row, 17)?,
+            initial_target: money::row_decimal_opt(row, 16),
+            profit_target: money::row_decimal_opt(row, 17),
             trade_ratings: match row.get::<libsql::Value>(18) {
                 Ok(libsql::Value::Integer(val)) => Some(val as i32),
                 Ok(libsql::Value::Null) => None,
@@ -1423,6 +1571,9 @@ impl OptionTrade {
             created_at,
             updated_at,
             is_deleted,
+            realized_pnl: money::row_decimal_opt(row, 25),
+            venue: row.get(26)?,
+            short_premium: matches!(row.get::<libsql::Value>(27), Ok(libsql::Value::Integer(1))),
         })
     }
 }
\ No newline at end of file
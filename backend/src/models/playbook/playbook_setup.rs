@@ -14,6 +14,8 @@ pub struct Playbook {
     pub color: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set when the playbook is shared by an organization; such playbooks are read-only
+    pub organization_id: Option<String>,
 }
 
 /// Data Transfer Object for creating new playbook setups
@@ -111,7 +113,7 @@ impl Playbook {
         playbook_id: &str,
     ) -> Result<Option<Playbook>, Box<dyn std::error::Error + Send + Sync>> {
         let mut rows = conn
-            .prepare("SELECT id, name, description, icon, emoji, color, created_at, updated_at FROM playbook WHERE id = ?")
+            .prepare("SELECT id, name, description, icon, emoji, color, created_at, updated_at, organization_id FROM playbook WHERE id = ?")
             .await?
             .query(libsql::params![playbook_id])
             .await?;
@@ -128,7 +130,7 @@ impl Playbook {
         conn: &Connection,
         query: PlaybookQuery,
     ) -> Result<Vec<Playbook>, Box<dyn std::error::Error + Send + Sync>> {
-        let mut sql = "SELECT id, name, description, icon, emoji, color, created_at, updated_at FROM playbook".to_string();
+        let mut sql = "SELECT id, name, description, icon, emoji, color, created_at, updated_at, organization_id FROM playbook".to_string();
         let mut params = Vec::new();
         let mut conditions = Vec::new();
 
@@ -303,6 +305,17 @@ impl Playbook {
         Ok(rows.next().await?.is_some())
     }
 
+    /// Whether a playbook is shared by an organization, and so can't be edited locally
+    pub async fn is_organization_shared(conn: &Connection, playbook_id: &str) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare("SELECT 1 FROM playbook WHERE id = ? AND organization_id IS NOT NULL")
+            .await?
+            .query(libsql::params![playbook_id])
+            .await?;
+
+        Ok(rows.next().await?.is_some())
+    }
+
     /// Get playbooks with pagination
     #[allow(dead_code)]
     pub async fn get_paginated(
@@ -313,7 +326,7 @@ impl Playbook {
         let offset = (page - 1) * page_size;
 
         let mut rows = conn
-            .prepare("SELECT id, name, description, icon, emoji, color, created_at, updated_at FROM playbook ORDER BY updated_at DESC, name LIMIT ? OFFSET ?")
+            .prepare("SELECT id, name, description, icon, emoji, color, created_at, updated_at, organization_id FROM playbook ORDER BY updated_at DESC, name LIMIT ? OFFSET ?")
             .await?
             .query(libsql::params![page_size, offset])
             .await?;
@@ -425,7 +438,7 @@ impl Playbook {
     ) -> Result<Vec<Playbook>, Box<dyn std::error::Error + Send + Sync>> {
         let mut rows = conn
             .prepare(
-                "SELECT p.id, p.name, p.description, p.icon, p.emoji, p.color, p.created_at, p.updated_at
+                "SELECT p.id, p.name, p.description, p.icon, p.emoji, p.color, p.created_at, p.updated_at, p.organization_id
                  FROM playbook p
                  INNER JOIN stock_trade_playbook stp ON p.id = stp.setup_id
                  WHERE stp.stock_trade_id = ?
@@ -450,7 +463,7 @@ impl Playbook {
     ) -> Result<Vec<Playbook>, Box<dyn std::error::Error + Send + Sync>> {
         let mut rows = conn
             .prepare(
-                "SELECT p.id, p.name, p.description, p.icon, p.emoji, p.color, p.created_at, p.updated_at
+                "SELECT p.id, p.name, p.description, p.icon, p.emoji, p.color, p.created_at, p.updated_at, p.organization_id
                  FROM playbook p
                  INNER JOIN option_trade_playbook otp ON p.id = otp.setup_id
                  WHERE otp.option_trade_id = ?
@@ -511,6 +524,7 @@ impl Playbook {
         let color: Option<String> = row.get(5)?;
         let created_at_str: String = row.get(6)?;
         let updated_at_str: String = row.get(7)?;
+        let organization_id: Option<String> = row.get(8)?;
 
         // Parse timestamps with flexible parsing and informative errors
         let created_at = parse_flexible_datetime(&created_at_str)
@@ -534,6 +548,7 @@ impl Playbook {
             color,
             created_at,
             updated_at,
+            organization_id,
         })
     }
 }
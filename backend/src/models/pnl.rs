@@ -0,0 +1,162 @@
+//! Realized P&L formulas, in Rust and as SQL.
+//!
+//! Stocks: `(exit - entry) * shares - commissions` for BUY (long) trades and
+//! `(entry - exit) * shares - commissions` for SELL (short) trades.
+//! Options: `(exit - entry) * contracts * 100 - commissions` for premium bought (debit) and
+//! `(entry - exit) * contracts * 100 - commissions` for premium sold (credit), prices quoted
+//! per share. The side is stored on the trade as `short_premium`; see `is_short_premium`.
+//!
+//! The analytics queries aggregate P&L in SQL, the models compute it in Rust when a trade
+//! is written. Both sides come from this module so the formulas can't drift apart.
+
+use crate::models::money;
+use rust_decimal::Decimal;
+
+/// Contracts are quoted per share; one contract covers 100 shares
+pub const OPTION_CONTRACT_MULTIPLIER: i64 = 100;
+
+/// Stock P&L over the unqualified columns of `stocks`; NULL until the trade has an exit price
+pub const STOCK_PNL_SQL: &str = "CASE WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions ELSE 0 END";
+
+/// Per-share gain of an option trade (entry to exit, sign flipped for sold premium)
+pub const OPTION_PRICE_GAIN_SQL: &str = "(CASE WHEN short_premium = 1 THEN entry_price - exit_price ELSE exit_price - entry_price END)";
+
+/// Option P&L over the unqualified columns of `options`; NULL until the trade has an exit price
+pub const OPTION_PNL_SQL: &str = "(CASE WHEN short_premium = 1 THEN entry_price - exit_price ELSE exit_price - entry_price END) * number_of_contracts * 100 - commissions";
+
+/// Realized P&L of a stock trade; `short` for SELL (short) entries
+pub fn stock_pnl(short: bool, entry_price: Decimal, exit_price: Decimal, shares: f64, commissions: Decimal) -> Decimal {
+    let per_share = if short { entry_price - exit_price } else { exit_price - entry_price };
+    per_share * money::from_f64(shares) - commissions
+}
+
+/// Realized P&L of an option trade, prices quoted per share; `short` when the premium was
+/// sold to open, so the trade profits as the price falls
+pub fn option_pnl(short: bool, entry_price: Decimal, exit_price: Decimal, contracts: i64, commissions: Decimal) -> Decimal {
+    let per_share = if short { entry_price - exit_price } else { exit_price - entry_price };
+    per_share * Decimal::from(contracts * OPTION_CONTRACT_MULTIPLIER) - commissions
+}
+
+/// Words in a strategy name that say which side of the premium the trade took
+const LONG_PREMIUM_WORDS: [&str; 4] = ["long", "buy", "bought", "debit"];
+const SHORT_PREMIUM_WORDS: [&str; 14] = [
+    "short", "sell", "sold", "write", "written", "writing", "covered", "secured", "csp", "naked", "credit", "iron", "condor",
+    "butterfly",
+];
+
+/// Whether an option trade sold its premium. Trades carry no explicit side, so this reads
+/// the strategy name first ("Short Put", "Covered Call", "Debit Spread"); when the name says
+/// neither, a call held for a bearish view or a put held for a bullish one can only have
+/// been written. Iron condors and butterflies are credit strategies unless the name says
+/// "long"; a bare "Straddle" or "Strangle" could be either side and falls through.
+pub fn is_short_premium(strategy_type: &str, trade_direction: &str, option_type: &str) -> bool {
+    let strategy = strategy_type.to_lowercase();
+    let words: Vec<&str> = strategy.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()).collect();
+    if words.iter().any(|w| LONG_PREMIUM_WORDS.contains(w)) {
+        return false;
+    }
+    if words.iter().any(|w| SHORT_PREMIUM_WORDS.contains(w)) {
+        return true;
+    }
+    matches!(
+        (option_type.to_lowercase().as_str(), trade_direction.to_lowercase().as_str()),
+        ("call", "bearish") | ("put", "bullish")
+    )
+}
+
+/// Renders the P&L expressions for a query, optionally against a table alias
+/// (`PnlSql::table("s").stock()` for `FROM stocks s JOIN ...`).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PnlSql<'a> {
+    alias: Option<&'a str>,
+}
+
+impl<'a> PnlSql<'a> {
+    pub fn table(alias: &'a str) -> Self {
+        Self { alias: Some(alias) }
+    }
+
+    fn col(&self, name: &str) -> String {
+        match self.alias {
+            Some(alias) => format!("{}.{}", alias, name),
+            None => name.to_string(),
+        }
+    }
+
+    pub fn stock(&self) -> String {
+        let (entry, exit) = (self.col("entry_price"), self.col("exit_price"));
+        let (shares, fees) = (self.col("number_shares"), self.col("commissions"));
+        format!(
+            "CASE WHEN {tt} = 'BUY' THEN ({exit} - {entry}) * {shares} - {fees} WHEN {tt} = 'SELL' THEN ({entry} - {exit}) * {shares} - {fees} ELSE 0 END",
+            tt = self.col("trade_type"),
+        )
+    }
+
+    pub fn option(&self) -> String {
+        let (entry, exit) = (self.col("entry_price"), self.col("exit_price"));
+        format!(
+            "(CASE WHEN {} = 1 THEN {entry} - {exit} ELSE {exit} - {entry} END) * {} * {} - {}",
+            self.col("short_premium"),
+            self.col("number_of_contracts"),
+            OPTION_CONTRACT_MULTIPLIER,
+            self.col("commissions"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::money::from_f64;
+
+    #[test]
+    fn test_stock_and_option_pnl() {
+        assert_eq!(stock_pnl(false, from_f64(10.10), from_f64(10.30), 3.0, from_f64(0.6)), Decimal::ZERO);
+        assert_eq!(stock_pnl(false, from_f64(50.0), from_f64(45.5), 10.0, Decimal::ONE), from_f64(-46.0));
+        assert_eq!(stock_pnl(true, from_f64(50.0), from_f64(45.5), 10.0, Decimal::ZERO), from_f64(45.0));
+        assert_eq!(stock_pnl(true, from_f64(45.5), from_f64(50.0), 0.5, Decimal::ZERO), from_f64(-2.25));
+        assert_eq!(option_pnl(false, from_f64(1.25), from_f64(2.0), 2, from_f64(1.3)), from_f64(148.7));
+        assert_eq!(option_pnl(false, from_f64(2.0), from_f64(0.0), 1, Decimal::ZERO), from_f64(-200.0));
+    }
+
+    #[test]
+    fn test_short_premium_pnl() {
+        // Put sold for 2.50, bought back at 0.40: a winner, not the -210 the long formula gives
+        assert_eq!(option_pnl(true, from_f64(2.5), from_f64(0.4), 1, from_f64(1.3)), from_f64(208.7));
+        // Expired worthless keeps the whole credit
+        assert_eq!(option_pnl(true, from_f64(1.1), Decimal::ZERO, 3, Decimal::ZERO), from_f64(330.0));
+        // Call sold for 1.00 and closed at 3.00 loses
+        assert_eq!(option_pnl(true, from_f64(1.0), from_f64(3.0), 2, Decimal::ZERO), from_f64(-400.0));
+
+        assert!(is_short_premium("Short Put", "Bullish", "Put"));
+        assert!(is_short_premium("covered-call", "Neutral", "Call"));
+        assert!(is_short_premium("Cash Secured Put", "Neutral", "Put"));
+        assert!(is_short_premium("Credit Spread", "Neutral", "Call"));
+        assert!(!is_short_premium("Long Call", "Bullish", "Call"));
+        assert!(!is_short_premium("Debit Spread", "Bearish", "Call"));
+        // No side in the name: direction against the option type means it was written
+        assert!(is_short_premium("Bull Put Spread", "Bullish", "Put"));
+        assert!(is_short_premium("Iron Condor", "Neutral", "Call"));
+        assert!(is_short_premium("Iron Butterfly", "Neutral", "Put"));
+        assert!(!is_short_premium("Long Condor", "Neutral", "Call"));
+        assert!(is_short_premium("Butterfly", "Neutral", "Call"));
+        assert!(!is_short_premium("Long Call Butterfly", "Neutral", "Call"));
+        assert!(!is_short_premium("Straddle", "Neutral", "Call"));
+        assert!(!is_short_premium("Protective Put", "Bearish", "Put"));
+        assert!(!is_short_premium("Shortcut", "Bullish", "Call"));
+    }
+
+    #[test]
+    fn test_sql_fragments_match_the_constants() {
+        assert_eq!(PnlSql::default().stock(), STOCK_PNL_SQL);
+        assert_eq!(PnlSql::default().option(), OPTION_PNL_SQL);
+        assert!(OPTION_PNL_SQL.starts_with(OPTION_PRICE_GAIN_SQL));
+        assert_eq!(
+            PnlSql::table("o").option(),
+            "(CASE WHEN o.short_premium = 1 THEN o.entry_price - o.exit_price ELSE o.exit_price - o.entry_price END) * o.number_of_contracts * 100 - o.commissions"
+        );
+        let stock = PnlSql::table("s").stock();
+        assert!(stock.starts_with("CASE WHEN s.trade_type = 'BUY' THEN (s.exit_price - s.entry_price) * s.number_shares - s.commissions"));
+        assert!(!stock.contains(" exit_price"));
+    }
+}
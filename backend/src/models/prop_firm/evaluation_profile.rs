@@ -0,0 +1,171 @@
+use anyhow::Result;
+use libsql::{Connection, params};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Funded-account evaluation rules a user's history can be simulated against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvaluationProfile {
+    pub id: String,
+    pub name: String,
+    pub account_size: f64,
+    pub profit_target: f64,
+    pub max_daily_loss: f64,
+    pub max_trailing_drawdown: f64,
+    pub min_trading_days: i64,
+    /// Optional max share (%) of profit allowed on a single day
+    pub consistency_threshold_percentage: Option<f64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateEvaluationProfileRequest {
+    pub name: String,
+    pub account_size: f64,
+    pub profit_target: f64,
+    pub max_daily_loss: f64,
+    pub max_trailing_drawdown: f64,
+    pub min_trading_days: Option<i64>,
+    pub consistency_threshold_percentage: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateEvaluationProfileRequest {
+    pub name: Option<String>,
+    pub account_size: Option<f64>,
+    pub profit_target: Option<f64>,
+    pub max_daily_loss: Option<f64>,
+    pub max_trailing_drawdown: Option<f64>,
+    pub min_trading_days: Option<i64>,
+    pub consistency_threshold_percentage: Option<f64>,
+}
+
+impl CreateEvaluationProfileRequest {
+    /// Reject profiles whose limits can't be evaluated
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            anyhow::bail!("Profile name is required");
+        }
+        if self.account_size <= 0.0 || self.profit_target <= 0.0 {
+            anyhow::bail!("Account size and profit target must be positive");
+        }
+        if self.max_daily_loss <= 0.0 || self.max_trailing_drawdown <= 0.0 {
+            anyhow::bail!("Max daily loss and trailing drawdown must be positive");
+        }
+        if self.min_trading_days.unwrap_or(0) < 0 {
+            anyhow::bail!("Minimum trading days cannot be negative");
+        }
+        Ok(())
+    }
+}
+
+const SELECT_COLUMNS: &str = "id, name, account_size, profit_target, max_daily_loss, max_trailing_drawdown, min_trading_days, consistency_threshold_percentage, created_at, updated_at";
+
+impl EvaluationProfile {
+    pub async fn create(conn: &Connection, req: CreateEvaluationProfileRequest) -> Result<Self> {
+        req.validate()?;
+        let id = Uuid::new_v4().to_string();
+
+        conn.execute(
+            "INSERT INTO evaluation_profiles (id, name, account_size, profit_target, max_daily_loss, max_trailing_drawdown, min_trading_days, consistency_threshold_percentage)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                id.clone(),
+                req.name.trim().to_string(),
+                req.account_size,
+                req.profit_target,
+                req.max_daily_loss,
+                req.max_trailing_drawdown,
+                req.min_trading_days.unwrap_or(0),
+                req.consistency_threshold_percentage
+            ],
+        )
+        .await?;
+
+        Self::find_by_id(conn, &id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Evaluation profile was created but could not be retrieved"))
+    }
+
+    pub async fn find_by_id(conn: &Connection, id: &str) -> Result<Option<Self>> {
+        let sql = format!("SELECT {} FROM evaluation_profiles WHERE id = ?", SELECT_COLUMNS);
+        let mut rows = conn.prepare(&sql).await?.query(params![id]).await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find_all(conn: &Connection) -> Result<Vec<Self>> {
+        let sql = format!("SELECT {} FROM evaluation_profiles ORDER BY created_at DESC", SELECT_COLUMNS);
+        let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+
+        let mut profiles = Vec::new();
+        while let Some(row) = rows.next().await? {
+            profiles.push(Self::from_row(&row)?);
+        }
+        Ok(profiles)
+    }
+
+    pub async fn update(conn: &Connection, id: &str, req: UpdateEvaluationProfileRequest) -> Result<Option<Self>> {
+        let existing = match Self::find_by_id(conn, id).await? {
+            Some(profile) => profile,
+            None => return Ok(None),
+        };
+
+        let merged = CreateEvaluationProfileRequest {
+            name: req.name.unwrap_or(existing.name),
+            account_size: req.account_size.unwrap_or(existing.account_size),
+            profit_target: req.profit_target.unwrap_or(existing.profit_target),
+            max_daily_loss: req.max_daily_loss.unwrap_or(existing.max_daily_loss),
+            max_trailing_drawdown: req.max_trailing_drawdown.unwrap_or(existing.max_trailing_drawdown),
+            min_trading_days: Some(req.min_trading_days.unwrap_or(existing.min_trading_days)),
+            consistency_threshold_percentage: req.consistency_threshold_percentage.or(existing.consistency_threshold_percentage),
+        };
+        merged.validate()?;
+
+        conn.execute(
+            "UPDATE evaluation_profiles SET name = ?, account_size = ?, profit_target = ?, max_daily_loss = ?, max_trailing_drawdown = ?, min_trading_days = ?, consistency_threshold_percentage = ? WHERE id = ?",
+            params![
+                merged.name.trim().to_string(),
+                merged.account_size,
+                merged.profit_target,
+                merged.max_daily_loss,
+                merged.max_trailing_drawdown,
+                merged.min_trading_days.unwrap_or(0),
+                merged.consistency_threshold_percentage,
+                id
+            ],
+        )
+        .await?;
+
+        Self::find_by_id(conn, id).await
+    }
+
+    pub async fn delete(conn: &Connection, id: &str) -> Result<bool> {
+        let affected = conn
+            .execute("DELETE FROM evaluation_profiles WHERE id = ?", params![id])
+            .await?;
+        Ok(affected > 0)
+    }
+
+    fn from_row(row: &libsql::Row) -> Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            account_size: row.get(2)?,
+            profit_target: row.get(3)?,
+            max_daily_loss: row.get(4)?,
+            max_trailing_drawdown: row.get(5)?,
+            min_trading_days: row.get(6)?,
+            consistency_threshold_percentage: row.get::<Option<f64>>(7)?,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+}
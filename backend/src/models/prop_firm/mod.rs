@@ -0,0 +1,3 @@
+pub mod evaluation_profile;
+
+pub use evaluation_profile::*;
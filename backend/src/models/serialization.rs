@@ -0,0 +1,177 @@
+//! JSON naming convention and the versioned response envelope.
+//!
+//! Field names in API payloads are camelCase. New models declare
+//! `#[serde(rename_all = "camelCase")]`, as the trade models already do. Older models
+//! (analytics, notebook, AI, ...) still serialize snake_case; rather than break the clients
+//! that read them, their keys are converted at the response boundary for clients that ask
+//! for the current format, and left alone for everyone else.
+//!
+//! The current format also wraps every body in [`Envelope`], replacing the per-module
+//! `{success, data, error | message}` shapes with one carrying the format version.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Envelope version of the current response format. Version 1 is the legacy mixed format.
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+
+/// Versioned response body of the current format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope<T> {
+    pub version: u32,
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<EnvelopeError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvelopeError {
+    pub message: String,
+    /// Machine-readable code where the handler provides one, e.g. `RATE_LIMIT_EXCEEDED`
+    pub code: Option<String>,
+}
+
+impl Envelope<Value> {
+    /// Rewrite a legacy body into the current format: keys become camelCase and the
+    /// module-specific wrapper is replaced by the envelope. Bodies without a `success`
+    /// flag are treated as bare data.
+    pub fn from_legacy(mut body: Value, is_success: bool) -> Self {
+        convert_keys(&mut body, KeyCase::Camel);
+        let Value::Object(mut map) = body else {
+            return Self::wrap(is_success, Some(body), None);
+        };
+        let Some(success) = map.get("success").and_then(Value::as_bool) else {
+            return Self::wrap(is_success, Some(Value::Object(map)), None);
+        };
+
+        let mut message = map.get("message").and_then(Value::as_str).map(str::to_string);
+        let mut code = map.get("code").and_then(Value::as_str).map(str::to_string);
+        match map.remove("error") {
+            // Some modules put a code like RATE_LIMIT_EXCEEDED in `error` next to `message`
+            Some(Value::String(error)) if is_error_code(&error) => code = code.or(Some(error)),
+            Some(Value::String(error)) => message = message.or(Some(error)),
+            Some(Value::Object(error)) => {
+                message = message.or_else(|| error.get("message").and_then(Value::as_str).map(str::to_string))
+            }
+            _ => {}
+        }
+        let data = map.remove("data").filter(|data| !data.is_null());
+
+        if success && is_success {
+            Self::wrap(true, data, None)
+        } else {
+            let message = message.unwrap_or_else(|| "Request failed".to_string());
+            Self::wrap(false, data, Some(EnvelopeError { message, code }))
+        }
+    }
+
+    fn wrap(success: bool, data: Option<Value>, error: Option<EnvelopeError>) -> Self {
+        let error = match (success, error) {
+            (false, None) => Some(EnvelopeError { message: "Request failed".to_string(), code: None }),
+            (_, error) => error,
+        };
+        Self { version: CURRENT_FORMAT_VERSION, success, data, error }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCase {
+    Camel,
+    Snake,
+}
+
+/// Rename object keys throughout a JSON value. Only identifier-like keys are touched, so
+/// map keys that are data (symbols, dates, playbook names) pass through unchanged.
+pub fn convert_keys(value: &mut Value, case: KeyCase) {
+    match value {
+        Value::Object(map) => {
+            let converted: Map<String, Value> = std::mem::take(map)
+                .into_iter()
+                .map(|(key, mut child)| {
+                    convert_keys(&mut child, case);
+                    let key = match case {
+                        KeyCase::Camel if is_snake_identifier(&key) => to_camel_case(&key),
+                        KeyCase::Snake if is_camel_identifier(&key) => to_snake_case(&key),
+                        _ => key,
+                    };
+                    (key, child)
+                })
+                .collect();
+            *map = converted;
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| convert_keys(item, case)),
+        _ => {}
+    }
+}
+
+fn is_error_code(value: &str) -> bool {
+    !value.is_empty() && value.chars().all(|c| c.is_ascii_uppercase() || c == '_')
+}
+
+fn is_snake_identifier(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_lowercase())
+        && key.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn is_camel_identifier(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_lowercase()) && key.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+pub fn to_camel_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' {
+            upper = !out.is_empty();
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn to_snake_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len() + 4);
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            out.push('_');
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_from_legacy_converts_keys_and_wraps() {
+        let legacy = json!({
+            "success": true,
+            "data": {"total_pnl": 10.5, "by_symbol": {"BRK_B": {"win_rate": 50.0}, "2025-03-01": 1}},
+        });
+        let envelope = serde_json::to_value(Envelope::from_legacy(legacy, true)).unwrap();
+        assert_eq!(
+            envelope,
+            json!({
+                "version": 2,
+                "success": true,
+                "data": {"totalPnl": 10.5, "bySymbol": {"BRK_B": {"winRate": 50.0}, "2025-03-01": 1}},
+                "error": null,
+            })
+        );
+
+        let failed = Envelope::from_legacy(json!({"success": false, "message": "Not found"}), false);
+        assert_eq!(failed.error.unwrap().message, "Not found");
+        assert_eq!(to_snake_case("realizedPnl"), "realized_pnl");
+    }
+}
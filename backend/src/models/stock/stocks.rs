@@ -1,6 +1,11 @@
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize, Deserializer};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
 use libsql::{Connection, params};
+use crate::models::analytics::periods::PeriodDefinition;
+use crate::models::money::{self, Decimal};
+use crate::models::pnl;
+use crate::models::timestamps;
+use crate::models::venue;
 
 /// Time range enum for calculations
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -15,6 +20,17 @@ pub enum TimeRange {
     OneYear,
     #[serde(rename = "ytd")]
     YearToDate,
+    #[serde(rename = "mtd")]
+    MonthToDate,
+    #[serde(rename = "qtd")]
+    QuarterToDate,
+    #[serde(rename = "last_month")]
+    LastMonth,
+    #[serde(rename = "last_quarter")]
+    LastQuarter,
+    /// Whole ISO weeks, both ends inclusive (`2025-W10..2025-W12`)
+    #[serde(rename = "iso_weeks")]
+    IsoWeeks { start_year: i32, start_week: u32, end_year: i32, end_week: u32 },
     #[serde(rename = "custom")]
     Custom { start_date: Option<DateTime<Utc>>, end_date: Option<DateTime<Utc>> },
     #[serde(rename = "all_time")]
@@ -22,6 +38,85 @@ pub enum TimeRange {
 }
 
 impl TimeRange {
+    /// Parse the query-string form used by the API: `7d`, `30d`, `90d`, `1y`, `ytd`, `mtd`, `qtd`,
+    /// `last_month`, `last_quarter`, `all_time`, or ISO weeks as `2025-W10` / `2025-W10..2025-W12`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "7d" => Some(TimeRange::SevenDays),
+            "30d" => Some(TimeRange::ThirtyDays),
+            "90d" => Some(TimeRange::NinetyDays),
+            "1y" => Some(TimeRange::OneYear),
+            "ytd" => Some(TimeRange::YearToDate),
+            "mtd" => Some(TimeRange::MonthToDate),
+            "qtd" => Some(TimeRange::QuarterToDate),
+            "last_month" => Some(TimeRange::LastMonth),
+            "last_quarter" => Some(TimeRange::LastQuarter),
+            "all_time" | "alltime" => Some(TimeRange::AllTime),
+            other => {
+                let (start, end) = other.split_once("..").unwrap_or((other, other));
+                let (start_year, start_week) = parse_iso_week(start)?;
+                let (end_year, end_week) = parse_iso_week(end)?;
+                if (end_year, end_week) < (start_year, start_week) {
+                    return None;
+                }
+                Some(TimeRange::IsoWeeks { start_year, start_week, end_year, end_week })
+            }
+        }
+    }
+
+    /// Value stored in `time_range` columns: the query-string form accepted by `parse`, with
+    /// custom ranges kept as their JSON object
+    pub fn to_db(&self) -> String {
+        let plain = match self {
+            TimeRange::SevenDays => "7d",
+            TimeRange::ThirtyDays => "30d",
+            TimeRange::NinetyDays => "90d",
+            TimeRange::OneYear => "1y",
+            TimeRange::YearToDate => "ytd",
+            TimeRange::MonthToDate => "mtd",
+            TimeRange::QuarterToDate => "qtd",
+            TimeRange::LastMonth => "last_month",
+            TimeRange::LastQuarter => "last_quarter",
+            TimeRange::AllTime => "all_time",
+            TimeRange::IsoWeeks { start_year, start_week, end_year, end_week } => {
+                return format!("{}-W{:02}..{}-W{:02}", start_year, start_week, end_year, end_week);
+            },
+            TimeRange::Custom { .. } => return serde_json::to_string(self).unwrap_or_default(),
+        };
+        plain.to_string()
+    }
+
+    /// Read a stored `time_range`; rows written before values were stored plain hold JSON
+    /// (`"7d"` with the quotes)
+    pub fn from_db(value: &str) -> Option<Self> {
+        Self::parse(value).or_else(|| serde_json::from_str(value).ok())
+    }
+
+    /// Start and exclusive end of calendar-anchored ranges (quarters follow the fiscal year).
+    /// Rolling and custom ranges return None.
+    pub fn calendar_bounds(&self, periods: &PeriodDefinition, now: DateTime<Utc>) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+        let month_start = NaiveDate::from_ymd_opt(now.year(), now.month(), 1)?;
+        let midnight = |date: NaiveDate| date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        match self {
+            TimeRange::MonthToDate => Some((midnight(month_start), None)),
+            TimeRange::LastMonth => {
+                let previous = (month_start - Duration::days(1)).with_day(1)?;
+                Some((midnight(previous), Some(midnight(month_start))))
+            },
+            TimeRange::QuarterToDate => Some((periods.fiscal_quarter_start(now), None)),
+            TimeRange::LastQuarter => {
+                let quarter_start = periods.fiscal_quarter_start(now);
+                Some((periods.fiscal_quarter_start(quarter_start - Duration::days(1)), Some(quarter_start)))
+            },
+            TimeRange::IsoWeeks { start_year, start_week, end_year, end_week } => {
+                let start = NaiveDate::from_isoywd_opt(*start_year, *start_week, Weekday::Mon)?;
+                let end = NaiveDate::from_isoywd_opt(*end_year, *end_week, Weekday::Mon)? + Duration::days(7);
+                Some((midnight(start), Some(midnight(end))))
+            },
+            _ => None,
+        }
+    }
+
     /// Convert TimeRange to SQL WHERE clause fragment
     pub fn to_sql_condition(&self) -> (String, Vec<DateTime<Utc>>) {
         match self {
@@ -45,6 +140,17 @@ impl TimeRange {
                 "exit_date >= date('now', 'start of year')".to_string(),
                 vec![]
             ),
+            TimeRange::MonthToDate => (
+                "exit_date >= date('now', 'start of month')".to_string(),
+                vec![]
+            ),
+            TimeRange::LastMonth => (
+                "exit_date >= date('now', 'start of month', '-1 month') AND exit_date < date('now', 'start of month')".to_string(),
+                vec![]
+            ),
+            TimeRange::QuarterToDate | TimeRange::LastQuarter | TimeRange::IsoWeeks { .. } => {
+                bounds_condition(self.calendar_bounds(&PeriodDefinition::default(), Utc::now()))
+            },
             TimeRange::Custom { start_date, end_date } => {
                 let mut conditions = vec![];
                 let mut params = vec![];
@@ -69,6 +175,27 @@ impl TimeRange {
         }
     }
 
+    /// SQL condition honoring the user's period definitions: `ytd` starts at the fiscal year
+    /// start, and when sessions are offset or weekdays excluded, exits are compared by trading date.
+    pub fn to_sql_condition_for(&self, periods: &PeriodDefinition) -> (String, Vec<DateTime<Utc>>) {
+        let (condition, params) = match self {
+            TimeRange::YearToDate if (periods.fiscal_year_start_month, periods.fiscal_year_start_day) != (1, 1) => (
+                "exit_date >= ?".to_string(),
+                vec![periods.fiscal_year_start(Utc::now())]
+            ),
+            TimeRange::QuarterToDate | TimeRange::LastQuarter => {
+                bounds_condition(self.calendar_bounds(periods, Utc::now()))
+            },
+            _ => self.to_sql_condition(),
+        };
+
+        if periods.uses_calendar_days() {
+            return (condition, params);
+        }
+        let trading_date = periods.trading_date_sql("exit_date");
+        (condition.replace("exit_date", &trading_date).replace('?', "date(?)"), params)
+    }
+
     /// Convert TimeRange to start_date and end_date for filtering by entry_date
     pub fn to_dates(&self) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
         let now = Utc::now();
@@ -96,10 +223,49 @@ impl TimeRange {
             },
             TimeRange::Custom { start_date, end_date } => (*start_date, *end_date),
             TimeRange::AllTime => (None, None),
+            _ => match self.calendar_bounds(&PeriodDefinition::default(), now) {
+                Some((start, end)) => (Some(start), Some(end.map_or(now, |end| end.min(now)))),
+                None => (None, None),
+            },
+        }
+    }
+
+    /// Window of the same length immediately before this one, for period-over-period
+    /// comparisons. None for ranges without a start.
+    pub fn previous_period(&self) -> Option<TimeRange> {
+        let (Some(start), end) = self.to_dates() else { return None };
+        let length = end.unwrap_or_else(Utc::now) - start;
+        Some(TimeRange::Custom { start_date: Some(start - length), end_date: Some(start) })
+    }
+
+    /// Approximate length in days, for prompts and report metadata
+    pub fn approx_days(&self) -> u32 {
+        let now = Utc::now();
+        match self.to_dates() {
+            (Some(start), Some(end)) => (end - start).num_days().max(1) as u32,
+            (Some(start), None) => (now - start).num_days().max(1) as u32,
+            _ => 365,
         }
     }
 }
 
+/// `exit_date >= ? AND exit_date < ?` for half-open calendar bounds
+fn bounds_condition(bounds: Option<(DateTime<Utc>, Option<DateTime<Utc>>)>) -> (String, Vec<DateTime<Utc>>) {
+    match bounds {
+        Some((start, Some(end))) => ("exit_date >= ? AND exit_date < ?".to_string(), vec![start, end]),
+        Some((start, None)) => ("exit_date >= ?".to_string(), vec![start]),
+        None => ("1=0".to_string(), vec![]),
+    }
+}
+
+/// "2025-W10" → (2025, 10)
+fn parse_iso_week(value: &str) -> Option<(i32, u32)> {
+    let (year, week) = value.trim().split_once("-w")?;
+    let (year, week) = (year.parse().ok()?, week.parse().ok()?);
+    NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)?;
+    Some((year, week))
+}
+
 /// Trade type enum matching the PostgreSQL enum in your schema
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -167,30 +333,6 @@ impl std::str::FromStr for OrderType {
     }
 }
 
-// Helper function to deserialize DateTime from various formats
-fn deserialize_datetime<'de, D>(deserializer: D) -> Result<DateTime<Utc>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let s = String::deserialize(deserializer)?;
-    DateTime::parse_from_rfc3339(&s)
-        .map(|dt| dt.with_timezone(&Utc))
-        .map_err(serde::de::Error::custom)
-}
-
-fn deserialize_optional_datetime<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    let opt = Option::<String>::deserialize(deserializer)?;
-    match opt {
-        Some(s) => DateTime::parse_from_rfc3339(&s)
-            .map(|dt| Some(dt.with_timezone(&Utc)))
-            .map_err(serde::de::Error::custom),
-        None => Ok(None),
-    }
-}
-
 /// Stock trade model for user's isolated database
 /// No user_id needed since each user has their own database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -200,22 +342,43 @@ pub struct Stock {
     pub symbol: String,
     pub trade_type: TradeType,
     pub order_type: OrderType,
-    pub entry_price: f64,
-    pub exit_price: Option<f64>,
-    pub stop_loss: f64,
-    pub commissions: f64,
+    pub entry_price: Decimal,
+    pub exit_price: Option<Decimal>,
+    pub stop_loss: Decimal,
+    pub commissions: Decimal,
     pub number_shares: f64,
-    pub take_profit: Option<f64>,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub take_profit: Option<Decimal>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub exit_date: Option<DateTime<Utc>>,
     pub reviewed: bool,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Stored by `realized_stock_pnl` on write; None while the trade is open
+    pub realized_pnl: Option<Decimal>,
+    /// Exchange the trade executed on, normalized by `venue::normalize_venue`
+    pub venue: Option<String>,
+}
+
+/// Realized P&L of a stock trade, or None until it has an exit price and date. This is the
+/// formula behind the stored `realized_pnl` column, so list endpoints, sync payloads and
+/// exports read that column instead of re-deriving P&L.
+pub fn realized_stock_pnl(
+    trade_type: &TradeType,
+    entry_price: Decimal,
+    exit_price: Option<Decimal>,
+    exited: bool,
+    number_shares: f64,
+    commissions: Decimal,
+) -> Option<Decimal> {
+    let exit_price = exit_price.filter(|_| exited)?;
+    Some(pnl::stock_pnl(*trade_type == TradeType::SELL, entry_price, exit_price, number_shares, commissions))
 }
 
 /// Simplified response for open stock trades (only essential fields)
@@ -223,7 +386,8 @@ pub struct Stock {
 #[serde(rename_all = "camelCase")]
 pub struct OpenStockTrade {
     pub symbol: String,
-    pub entry_price: f64,
+    pub entry_price: Decimal,
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
 }
 
@@ -234,46 +398,51 @@ pub struct CreateStockRequest {
     pub symbol: String,
     pub trade_type: TradeType,
     pub order_type: OrderType,
-    pub entry_price: f64,
-    pub stop_loss: f64,
+    /// Zero or omitted: estimated from market data at the entry time
+    #[serde(default)]
+    pub entry_price: Decimal,
+    pub stop_loss: Decimal,
     #[serde(default)]  // Allow missing field, defaults to 0.0
-    pub commissions: f64,
+    pub commissions: Decimal,
     pub number_shares: f64,
-    pub take_profit: Option<f64>,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub take_profit: Option<Decimal>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
-    #[serde(deserialize_with = "deserialize_datetime")]
+    #[serde(with = "timestamps::utc_millis")]
     pub entry_date: DateTime<Utc>,
     #[serde(default)]  // Allow missing field, defaults to false
     pub reviewed: Option<bool>,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
+    /// Exchange code or name (`NMS`, `NYSE`, `CBOE`, ...); looked up from symbol metadata when omitted
+    pub venue: Option<String>,
 }
 
 /// Data Transfer Object for updating stock trades
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateStockRequest {
     pub symbol: Option<String>,
     pub trade_type: Option<TradeType>,
     pub order_type: Option<OrderType>,
-    pub entry_price: Option<f64>,
-    pub exit_price: Option<f64>,
-    pub stop_loss: Option<f64>,
-    pub commissions: Option<f64>,
+    pub entry_price: Option<Decimal>,
+    pub exit_price: Option<Decimal>,
+    pub stop_loss: Option<Decimal>,
+    pub commissions: Option<Decimal>,
     pub number_shares: Option<f64>,
-    pub take_profit: Option<f64>,
-    pub initial_target: Option<f64>,
-    pub profit_target: Option<f64>,
+    pub take_profit: Option<Decimal>,
+    pub initial_target: Option<Decimal>,
+    pub profit_target: Option<Decimal>,
     pub trade_ratings: Option<i32>,
-    #[serde(default, deserialize_with = "deserialize_optional_datetime")]
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub entry_date: Option<DateTime<Utc>>,
-    #[serde(default, deserialize_with = "deserialize_optional_datetime")]
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub exit_date: Option<DateTime<Utc>>,
     pub reviewed: Option<bool>,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
+    pub venue: Option<String>,
 }
 
 /// Stock query parameters for filtering and pagination
@@ -282,8 +451,11 @@ pub struct UpdateStockRequest {
 pub struct StockQuery {
     pub symbol: Option<String>,
     pub trade_type: Option<TradeType>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub start_date: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub end_date: Option<DateTime<Utc>>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
     pub updated_after: Option<DateTime<Utc>>,
     pub time_range: Option<TimeRange>,
     pub limit: Option<i64>,
@@ -310,21 +482,6 @@ impl Stock {
         }
     }
     
-    fn get_opt_f64(row: &libsql::Row, idx: usize) -> Result<Option<f64>, Box<dyn std::error::Error + Send + Sync>> {
-        let i = idx as i32;
-        match row.get_value(i) {
-            Ok(libsql::Value::Null) => Ok(None),
-            Ok(libsql::Value::Integer(n)) => Ok(Some(n as f64)),
-            Ok(libsql::Value::Real(r)) => Ok(Some(r)),
-            Ok(libsql::Value::Text(s)) => {
-                let result = s.parse::<f64>().ok();
-                Ok(result)
-            },
-            Ok(libsql::Value::Blob(_b)) => Ok(None),
-            Err(_e) => Ok(None),
-        }
-    }
-
     fn get_bool(row: &libsql::Row, idx: usize) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
         let i = idx as i32;
         // Try i64 (SQLite INTEGER for boolean)
@@ -373,12 +530,12 @@ impl Stock {
                 symbol, trade_type, order_type, entry_price, 
                 stop_loss, commissions, number_shares, take_profit, 
                 initial_target, profit_target, trade_ratings,
-                entry_date, reviewed, mistakes, brokerage_name, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                entry_date, reviewed, mistakes, brokerage_name, venue, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING id, symbol, trade_type, order_type, entry_price,
                      exit_price, stop_loss, commissions, number_shares, take_profit,
                      initial_target, profit_target, trade_ratings,
-                     entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                     entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
             "#,
         )
         .await?
@@ -386,18 +543,19 @@ impl Stock {
             request.symbol,
             request.trade_type.to_string(),
             request.order_type.to_string(),
-            request.entry_price,
-            request.stop_loss,
-            request.commissions,
+            money::sql_value(request.entry_price),
+            money::sql_value(request.stop_loss),
+            money::sql_value(request.commissions),
             request.number_shares,
-            request.take_profit,
-            request.initial_target,
-            request.profit_target,
+            money::sql_value_opt(request.take_profit),
+            money::sql_value_opt(request.initial_target),
+            money::sql_value_opt(request.profit_target),
             request.trade_ratings,
-            request.entry_date.to_rfc3339(),
+            timestamps::to_db(&request.entry_date),
             request.reviewed.unwrap_or(false),
             request.mistakes,
             request.brokerage_name,
+            venue::normalize_venue_opt(request.venue.as_deref()),
             now.clone(),
             now
         ])
@@ -421,7 +579,7 @@ impl Stock {
             SELECT id, symbol, trade_type, order_type, entry_price,
                    exit_price, stop_loss, commissions, number_shares, take_profit,
                    initial_target, profit_target, trade_ratings,
-                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
             FROM stocks 
             WHERE id = ?
             "#,
@@ -447,7 +605,7 @@ impl Stock {
             SELECT id, symbol, trade_type, order_type, entry_price,
                    exit_price, stop_loss, commissions, number_shares, take_profit,
                    initial_target, profit_target, trade_ratings,
-                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
             FROM stocks 
             WHERE 1=1
             "#,
@@ -468,12 +626,12 @@ impl Stock {
         
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
         
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         if let Some(updated_after) = query.updated_after {
@@ -486,11 +644,11 @@ impl Stock {
             let (start, end) = time_range.to_dates();
             if let Some(start_date) = start {
                 sql.push_str(" AND entry_date >= ?");
-                query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
             }
             if let Some(end_date) = end {
                 sql.push_str(" AND entry_date <= ?");
-                query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
             }
         }
 
@@ -558,12 +716,12 @@ impl Stock {
         
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
         
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         if let Some(updated_after) = query.updated_after {
@@ -576,11 +734,11 @@ impl Stock {
             let (start, end) = time_range.to_dates();
             if let Some(start_date) = start {
                 sql.push_str(" AND entry_date >= ?");
-                query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
             }
             if let Some(end_date) = end {
                 sql.push_str(" AND entry_date <= ?");
-                query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+                query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
             }
         }
 
@@ -606,11 +764,10 @@ impl Stock {
         let mut open_trades = Vec::new();
         while let Some(row) = rows.next().await? {
             let symbol: String = row.get(0)?;
-            let entry_price = Self::get_f64(&row, 1)?;
+            let entry_price = money::row_decimal(&row, 1);
             let entry_date_str: String = row.get(2)?;
-            let entry_date = DateTime::parse_from_rfc3339(&entry_date_str)
-                .map_err(|e| format!("Failed to parse entry_date: {}", e))?
-                .with_timezone(&Utc);
+            let entry_date = timestamps::parse_stored(&entry_date_str)
+                .map_err(|e| format!("Failed to parse entry_date: {}", e))?;
             
             open_trades.push(OpenStockTrade {
                 symbol,
@@ -629,11 +786,19 @@ impl Stock {
         request: UpdateStockRequest,
     ) -> Result<Option<Stock>, Box<dyn std::error::Error + Send + Sync>> {
         // Check if stock exists first
-        let current_stock = Self::find_by_id(conn, stock_id).await?;
-        
-        if current_stock.is_none() {
+        let Some(current_stock) = Self::find_by_id(conn, stock_id).await? else {
             return Ok(None);
-        }
+        };
+
+        // Stored P&L follows the edit, computed from the merged values
+        let realized_pnl = realized_stock_pnl(
+            request.trade_type.as_ref().unwrap_or(&current_stock.trade_type),
+            request.entry_price.unwrap_or(current_stock.entry_price),
+            request.exit_price.or(current_stock.exit_price),
+            request.exit_date.or(current_stock.exit_date).is_some(),
+            request.number_shares.unwrap_or(current_stock.number_shares),
+            request.commissions.unwrap_or(current_stock.commissions),
+        );
 
         let now = Utc::now().to_rfc3339();
 
@@ -658,12 +823,14 @@ impl Stock {
                 reviewed = COALESCE(?, reviewed),
                 mistakes = COALESCE(?, mistakes),
                 brokerage_name = COALESCE(?, brokerage_name),
+                venue = COALESCE(?, venue),
+                realized_pnl = ?,
                 updated_at = ?
             WHERE id = ?
             RETURNING id, symbol, trade_type, order_type, entry_price,
                      exit_price, stop_loss, commissions, number_shares, take_profit,
                      initial_target, profit_target, trade_ratings,
-                     entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                     entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
             "#,
         )
             .await?
@@ -671,20 +838,22 @@ impl Stock {
                 request.symbol,
                 request.trade_type.map(|t| t.to_string()),
                 request.order_type.map(|t| t.to_string()),
-                request.entry_price,
-                request.exit_price,
-                request.stop_loss,
-                request.commissions,
+                money::sql_value_opt(request.entry_price),
+                money::sql_value_opt(request.exit_price),
+                money::sql_value_opt(request.stop_loss),
+                money::sql_value_opt(request.commissions),
                 request.number_shares,
-                request.take_profit,
-                request.initial_target,
-                request.profit_target,
+                money::sql_value_opt(request.take_profit),
+                money::sql_value_opt(request.initial_target),
+                money::sql_value_opt(request.profit_target),
                 request.trade_ratings,
-                request.entry_date.map(|d| d.to_rfc3339()),
-                request.exit_date.map(|d| d.to_rfc3339()),
+                request.entry_date.as_ref().map(timestamps::to_db),
+                request.exit_date.as_ref().map(timestamps::to_db),
                 None::<bool>,
                 request.mistakes,
                 request.brokerage_name,
+                venue::normalize_venue_opt(request.venue.as_deref()),
+                money::sql_value_opt(realized_pnl),
                 now,
                 stock_id
             ])
@@ -697,6 +866,48 @@ impl Stock {
         }
     }
 
+    /// Copy a trade into a new row with `overrides` applied over the original's fields. The
+    /// copy is a separate trade, so it starts unreviewed with no mistakes unless overridden.
+    pub async fn duplicate(
+        conn: &Connection,
+        stock_id: i64,
+        overrides: UpdateStockRequest,
+    ) -> Result<Option<Stock>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(original) = Self::find_by_id(conn, stock_id).await? else {
+            return Ok(None);
+        };
+        let exit_price = overrides.exit_price.or(original.exit_price);
+        let exit_date = overrides.exit_date.or(original.exit_date);
+        let request = CreateStockRequest {
+            symbol: overrides.symbol.unwrap_or(original.symbol),
+            trade_type: overrides.trade_type.unwrap_or(original.trade_type),
+            order_type: overrides.order_type.unwrap_or(original.order_type),
+            entry_price: overrides.entry_price.unwrap_or(original.entry_price),
+            stop_loss: overrides.stop_loss.unwrap_or(original.stop_loss),
+            commissions: overrides.commissions.unwrap_or(original.commissions),
+            number_shares: overrides.number_shares.unwrap_or(original.number_shares),
+            take_profit: overrides.take_profit.or(original.take_profit),
+            initial_target: overrides.initial_target.or(original.initial_target),
+            profit_target: overrides.profit_target.or(original.profit_target),
+            trade_ratings: overrides.trade_ratings.or(original.trade_ratings),
+            entry_date: overrides.entry_date.unwrap_or(original.entry_date),
+            reviewed: overrides.reviewed,
+            mistakes: overrides.mistakes,
+            brokerage_name: overrides.brokerage_name.or(original.brokerage_name),
+            venue: overrides.venue.or(original.venue),
+        };
+
+        // Exit fields go through update so the stored P&L is computed the usual way
+        let tx = conn.transaction().await?;
+        let mut copy = Self::create(&tx, request).await?;
+        if exit_price.is_some() || exit_date.is_some() {
+            let exit = UpdateStockRequest { exit_price, exit_date, ..Default::default() };
+            copy = Self::update(&tx, copy.id, exit).await?.ok_or("Duplicated stock trade not found")?;
+        }
+        tx.commit().await?;
+        Ok(Some(copy))
+    }
+
     /// Delete a stock trade
     pub async fn delete(
         conn: &Connection,
@@ -730,12 +941,12 @@ impl Stock {
         
         if let Some(start_date) = query.start_date {
             sql.push_str(" AND entry_date >= ?");
-            query_params.push(libsql::Value::Text(start_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&start_date)));
         }
         
         if let Some(end_date) = query.end_date {
             sql.push_str(" AND entry_date <= ?");
-            query_params.push(libsql::Value::Text(end_date.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&end_date)));
         }
 
         if let Some(updated_after) = query.updated_after {
@@ -757,35 +968,68 @@ impl Stock {
     }
 
     /// Calculations here
-    /// Calculate total P&L for all stocks in the user's database 
+    /// Calculate total P&L for all stocks in the user's database.
+    /// Summed per trade in `Decimal` rather than with SQL `SUM` over REALs.
     pub async fn calculate_total_pnl(
         conn: &Connection,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
         let mut rows = conn
-            .prepare(
-            r#"
-            SELECT SUM(
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        CASE 
-                            WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                            WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                        END
-                    ELSE 0
-                END
-            ) as total_pnl
-            FROM stocks
-            "#,
-        )
+            .prepare("SELECT realized_pnl FROM stocks WHERE realized_pnl IS NOT NULL")
             .await?
             .query(params![])
-        .await?;
+            .await?;
 
-        if let Some(row) = rows.next().await? {
-            Ok(row.get::<Option<f64>>(0)?.unwrap_or(0.0))
-        } else {
-            Ok(0.0)
+        Self::sum_pnl_rows(&mut rows).await
+    }
+
+    /// Sum the stored `realized_pnl` in the first column
+    async fn sum_pnl_rows(rows: &mut libsql::Rows) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
+        let mut total = Decimal::ZERO;
+        while let Some(row) = rows.next().await? {
+            total += money::row_decimal(&row, 0);
+        }
+        Ok(total)
+    }
+
+    /// Recompute the stored `realized_pnl` of every stock trade, e.g. after the formula
+    /// changes or for rows written before the column existed. Returns how many rows changed.
+    pub async fn recalculate_realized_pnl(
+        conn: &Connection,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut rows = conn
+            .prepare("SELECT id, trade_type, entry_price, exit_price, exit_date, number_shares, commissions, realized_pnl FROM stocks")
+            .await?
+            .query(params![])
+            .await?;
+
+        let mut updates = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let trade_type = row.get::<String>(1)?.parse::<TradeType>()
+                .map_err(|e| format!("Invalid trade type on stock {}: {}", id, e))?;
+            let realized_pnl = realized_stock_pnl(
+                &trade_type,
+                money::row_decimal(&row, 2),
+                money::row_decimal_opt(&row, 3),
+                row.get::<Option<String>>(4)?.is_some(),
+                Self::get_f64(&row, 5)?,
+                money::row_decimal(&row, 6),
+            );
+            // Compare as stored so values that only differ below REAL precision aren't rewritten
+            if money::to_f64_opt(realized_pnl) != row.get::<Option<f64>>(7)? {
+                updates.push((id, realized_pnl));
+            }
+        }
+        drop(rows);
+
+        for (id, realized_pnl) in &updates {
+            conn.execute(
+                "UPDATE stocks SET realized_pnl = ? WHERE id = ?",
+                params![money::sql_value_opt(*realized_pnl), *id],
+            )
+            .await?;
         }
+        Ok(updates.len())
     }
 
     /// Calculate profit factor (gross profit / gross loss)
@@ -829,7 +1073,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -885,7 +1129,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -928,7 +1172,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -971,7 +1215,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1017,7 +1261,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1063,7 +1307,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1109,7 +1353,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1155,7 +1399,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1222,7 +1466,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1265,7 +1509,7 @@ impl Stock {
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1290,31 +1534,21 @@ impl Stock {
         Ok((100.0 - win_rate).round())
     }
 
-    /// Calculate net P&L for the time range
+    /// Calculate net P&L for the time range; open trades count as zero
     pub async fn calculate_net_pnl(
         conn: &Connection,
         time_range: TimeRange,
-    ) -> Result<f64, Box<dyn std::error::Error + Send + Sync>> {
+    ) -> Result<Decimal, Box<dyn std::error::Error + Send + Sync>> {
         let (time_condition, time_params) = time_range.to_sql_condition();
         
         let sql = format!(
-            r#"
-            SELECT 
-                COALESCE(SUM(
-                    CASE 
-                        WHEN trade_type = 'BUY' THEN (COALESCE(exit_price, entry_price) - entry_price) * number_shares - COALESCE(commissions, 0)
-                        WHEN trade_type = 'SELL' THEN (entry_price - COALESCE(exit_price, entry_price)) * number_shares - COALESCE(commissions, 0)
-                    END
-                ), 0) as net_pnl
-            FROM stocks
-            WHERE ({})
-            "#,
+            "SELECT realized_pnl FROM stocks WHERE realized_pnl IS NOT NULL AND ({})",
             time_condition
         );
 
         let mut query_params = Vec::new();
         for param in time_params {
-            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+            query_params.push(libsql::Value::Text(timestamps::to_db(&param)));
         }
 
         let mut rows = conn
@@ -1323,11 +1557,7 @@ impl Stock {
             .query(libsql::params_from_iter(query_params))
             .await?;
 
-        if let Some(row) = rows.next().await? {
-            Ok(row.get::<Option<f64>>(0)?.unwrap_or(0.0))
-        } else {
-            Ok(0.0)
-        }
+        Self::sum_pnl_rows(&mut rows).await
     }
 
     /// Convert from libsql row to Stock struct
@@ -1370,7 +1600,7 @@ impl Stock {
         let order_type = order_type_str.parse::<OrderType>()
             .map_err(|e| format!("Invalid order type: {}", e))?;
 
-        // Parse datetime strings (support RFC3339 and SQLite's CURRENT_TIMESTAMP format)
+        // Parse datetime strings (canonical RFC3339 plus the legacy formats older rows use)
         let entry_date_str: String = row.get(13)?;
         let exit_date_str: Option<String> = row.get(14)?;
         let reviewed = Self::get_bool(row, 15)?;
@@ -1379,29 +1609,17 @@ impl Stock {
         let created_at_str: String = row.get(18)?;
         let updated_at_str: String = row.get(19)?;
 
-        fn parse_dt_any(s: &str) -> Result<DateTime<Utc>, Box<dyn std::error::Error + Send + Sync>> {
-            if let Ok(dt) = DateTime::parse_from_rfc3339(s) { return Ok(dt.with_timezone(&Utc)); }
-            if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-                return Ok(DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-            }
-            if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                let ndt = date.and_hms_opt(0, 0, 0).ok_or("invalid date")?;
-                return Ok(DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-            }
-            Err(format!("Unsupported datetime format: {}", s).into())
-        }
-
-        let entry_date = parse_dt_any(&entry_date_str)
+        let entry_date = timestamps::parse_stored(&entry_date_str)
             .map_err(|e| format!("Failed to parse entry_date: {}", e))?;
 
         let exit_date = if let Some(exit_str) = exit_date_str {
-            Some(parse_dt_any(&exit_str)
+            Some(timestamps::parse_stored(&exit_str)
                 .map_err(|e| format!("Failed to parse exit_date: {}", e))?)
         } else { None };
 
-        let created_at = parse_dt_any(&created_at_str)
+        let created_at = timestamps::parse_stored(&created_at_str)
             .map_err(|e| format!("Failed to parse created_at: {}", e))?;
-        let updated_at = parse_dt_any(&updated_at_str)
+        let updated_at = timestamps::parse_stored(&updated_at_str)
             .map_err(|e| format!("Failed to parse updated_at: {}", e))?;
         
         Ok(Stock {
@@ -1409,14 +1627,14 @@ impl Stock {
             symbol: row.get(1)?,
             trade_type,
             order_type,
-            entry_price: Self::get_f64(row, 4)?,
-            exit_price: Self::get_opt_f64(row, 5)?,
-            stop_loss: Self::get_f64(row, 6)?,
-            commissions: Self::get_f64(row, 7)?,
+            entry_price: money::row_decimal(row, 4),
+            exit_price: money::row_decimal_opt(row, 5),
+            stop_loss: money::row_decimal(row, 6),
+            commissions: money::row_decimal(row, 7),
             number_shares: Self::get_f64(row, 8)?,
-            take_profit: Self::get_opt_f64(row, 9)?,
-            initial_target: Self::get_opt_f64(row, 10)?,
-            profit_target: Self::get_opt_f64(row, 11)?,
+            take_profit: money::row_decimal_opt(row, 9),
+            initial_target: money::row_decimal_opt(row, 10),
+            profit_target: money::row_decimal_opt(row, 11),
             trade_ratings: row.get::<Option<i32>>(12)?,
             entry_date,
             exit_date,
@@ -1425,6 +1643,65 @@ impl Stock {
             brokerage_name,
             created_at,
             updated_at,
+            realized_pnl: money::row_decimal_opt(row, 20),
+            venue: row.get(21)?,
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_calendar_time_ranges() {
+        assert_eq!(TimeRange::parse("QTD"), Some(TimeRange::QuarterToDate));
+        assert_eq!(TimeRange::parse("last_month"), Some(TimeRange::LastMonth));
+        assert_eq!(
+            TimeRange::parse("2025-W10..2025-W12"),
+            Some(TimeRange::IsoWeeks { start_year: 2025, start_week: 10, end_year: 2025, end_week: 12 })
+        );
+        assert_eq!(TimeRange::parse("2025-W12..2025-W10"), None);
+        assert_eq!(TimeRange::parse("2025-W54"), None);
+    }
+
+    #[test]
+    fn test_calendar_bounds() {
+        let now = Utc.with_ymd_and_hms(2025, 5, 14, 12, 0, 0).unwrap();
+        let day = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+        let calendar = PeriodDefinition::default();
+
+        assert_eq!(TimeRange::LastMonth.calendar_bounds(&calendar, now), Some((day(2025, 4, 1), Some(day(2025, 5, 1)))));
+        assert_eq!(TimeRange::LastQuarter.calendar_bounds(&calendar, now), Some((day(2025, 1, 1), Some(day(2025, 4, 1)))));
+
+        let fiscal = PeriodDefinition { fiscal_year_start_month: 2, ..Default::default() };
+        assert_eq!(TimeRange::QuarterToDate.calendar_bounds(&fiscal, now), Some((day(2025, 5, 1), None)));
+
+        let weeks = TimeRange::parse("2025-W01").unwrap();
+        assert_eq!(weeks.calendar_bounds(&calendar, now), Some((day(2024, 12, 30), Some(day(2025, 1, 6)))));
+        assert_eq!(TimeRange::SevenDays.calendar_bounds(&calendar, now), None);
+    }
+
+    #[test]
+    fn test_time_range_stored_form() {
+        assert_eq!(TimeRange::ThirtyDays.to_db(), "30d");
+        let weeks = TimeRange::parse("2025-w9..2025-w12").unwrap();
+        assert_eq!(weeks.to_db(), "2025-W09..2025-W12");
+        assert_eq!(TimeRange::from_db(&weeks.to_db()), Some(weeks));
+
+        let custom = TimeRange::Custom { start_date: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()), end_date: None };
+        assert_eq!(TimeRange::from_db(&custom.to_db()), Some(custom));
+        // Rows written before plain values
+        assert_eq!(TimeRange::from_db("\"7d\""), Some(TimeRange::SevenDays));
+        assert_eq!(TimeRange::from_db("\"all_time\""), Some(TimeRange::AllTime));
+    }
+
+    #[test]
+    fn test_realized_pnl_needs_exit() {
+        let price = |v: i64| Decimal::from(v);
+        assert_eq!(realized_stock_pnl(&TradeType::BUY, price(100), Some(price(110)), false, 10.0, price(1)), None);
+        assert_eq!(realized_stock_pnl(&TradeType::BUY, price(100), None, true, 10.0, price(1)), None);
+        assert_eq!(realized_stock_pnl(&TradeType::BUY, price(100), Some(price(110)), true, 10.0, price(1)), Some(price(99)));
+        assert_eq!(realized_stock_pnl(&TradeType::SELL, price(100), Some(price(110)), true, 10.0, price(1)), Some(price(-101)));
+    }
+}
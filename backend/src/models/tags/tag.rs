@@ -14,6 +14,8 @@ pub struct TradeTag {
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Set when the tag is shared by an organization; such tags are read-only
+    pub organization_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -164,7 +166,7 @@ impl TradeTag {
 
     pub async fn find_by_id(conn: &Connection, id: &str) -> Result<Self> {
         let stmt = conn
-            .prepare("SELECT id, category, name, color, description, created_at, updated_at FROM trade_tags WHERE id = ?")
+            .prepare("SELECT id, category, name, color, description, created_at, updated_at, organization_id FROM trade_tags WHERE id = ?")
             .await?;
         let mut rows = stmt.query(params![id]).await?;
 
@@ -182,7 +184,7 @@ impl TradeTag {
 
         if let Some(category) = &query.category {
             let stmt = conn
-                .prepare("SELECT id, category, name, color, description, created_at, updated_at FROM trade_tags WHERE category = ? ORDER BY category, name LIMIT ? OFFSET ?")
+                .prepare("SELECT id, category, name, color, description, created_at, updated_at, organization_id FROM trade_tags WHERE category = ? ORDER BY category, name LIMIT ? OFFSET ?")
                 .await?;
             let mut rows = stmt.query(params![category.clone(), limit, offset]).await?;
 
@@ -193,7 +195,7 @@ impl TradeTag {
             Ok(tags)
         } else {
             let stmt = conn
-                .prepare("SELECT id, category, name, color, description, created_at, updated_at FROM trade_tags ORDER BY category, name LIMIT ? OFFSET ?")
+                .prepare("SELECT id, category, name, color, description, created_at, updated_at, organization_id FROM trade_tags ORDER BY category, name LIMIT ? OFFSET ?")
                 .await?;
             let mut rows = stmt.query(params![limit, offset]).await?;
 
@@ -208,7 +210,7 @@ impl TradeTag {
     #[allow(dead_code)]
     pub async fn find_by_category(conn: &Connection, category: &str) -> Result<Vec<Self>> {
         let stmt = conn
-            .prepare("SELECT id, category, name, color, description, created_at, updated_at FROM trade_tags WHERE category = ? ORDER BY name")
+            .prepare("SELECT id, category, name, color, description, created_at, updated_at, organization_id FROM trade_tags WHERE category = ? ORDER BY name")
             .await?;
         let mut rows = stmt.query(params![category]).await?;
 
@@ -280,6 +282,7 @@ impl TradeTag {
         // Safely get datetime strings
         let created_at_str = Self::get_string(row, 5)?;
         let updated_at_str = Self::get_string(row, 6)?;
+        let organization_id = Self::get_opt_string(row, 7)?;
 
         // Parse datetimes using helper function (handles both RFC3339 and SQLite format)
         let created_at = Self::parse_db_datetime(&created_at_str, "created_at")?;
@@ -293,6 +296,7 @@ impl TradeTag {
             description,
             created_at,
             updated_at,
+            organization_id,
         })
     }
 }
@@ -97,7 +97,7 @@ impl TradeTagAssociation {
     ) -> Result<Vec<TradeTag>> {
         let stmt = conn
             .prepare(
-                "SELECT t.id, t.category, t.name, t.color, t.description, t.created_at, t.updated_at
+                "SELECT t.id, t.category, t.name, t.color, t.description, t.created_at, t.updated_at, t.organization_id
                  FROM trade_tags t
                  INNER JOIN stock_trade_tags stt ON t.id = stt.tag_id
                  WHERE stt.stock_trade_id = ?
@@ -123,6 +123,7 @@ impl TradeTagAssociation {
                 updated_at: DateTime::parse_from_rfc3339(&updated_at_str)
                     .map_err(|e| anyhow::anyhow!("Failed to parse updated_at: {}", e))?
                     .with_timezone(&Utc),
+                organization_id: row.get(7)?,
             });
         }
 
@@ -135,7 +136,7 @@ impl TradeTagAssociation {
     ) -> Result<Vec<TradeTag>> {
         let stmt = conn
             .prepare(
-                "SELECT t.id, t.category, t.name, t.color, t.description, t.created_at, t.updated_at
+                "SELECT t.id, t.category, t.name, t.color, t.description, t.created_at, t.updated_at, t.organization_id
                  FROM trade_tags t
                  INNER JOIN option_trade_tags ott ON t.id = ott.tag_id
                  WHERE ott.option_trade_id = ?
@@ -161,6 +162,7 @@ impl TradeTagAssociation {
                 updated_at: DateTime::parse_from_rfc3339(&updated_at_str)
                     .map_err(|e| anyhow::anyhow!("Failed to parse updated_at: {}", e))?
                     .with_timezone(&Utc),
+                organization_id: row.get(7)?,
             });
         }
 
@@ -0,0 +1,185 @@
+//! Trade timestamp handling.
+//!
+//! Entry, exit and expiration times are stored as RFC3339 in UTC with millisecond precision
+//! and a `Z` suffix (`2025-03-14T14:30:05.123Z`). Every stored value has the same width, so
+//! the string comparisons our SQL filters do (`entry_date >= ?`) order correctly, and
+//! SQLite's `julianday()`/`date()` read them as UTC.
+//!
+//! Request bodies must carry an explicit offset. A bare `2025-03-14T09:30:00` is rejected
+//! rather than guessed at, because guessing is how hold times ended up off by the user's
+//! UTC offset.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat, Timelike, Utc};
+use libsql::{params, Connection};
+use log::{info, warn};
+
+/// Drop anything below a millisecond
+pub fn truncate_to_millis(dt: DateTime<Utc>) -> DateTime<Utc> {
+    let nanos = dt.nanosecond() / 1_000_000 * 1_000_000;
+    dt.with_nanosecond(nanos).unwrap_or(dt)
+}
+
+/// Canonical storage form, e.g. `2025-03-14T14:30:05.123Z`
+pub fn to_db(dt: &DateTime<Utc>) -> String {
+    truncate_to_millis(*dt).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+/// True when `value` is already in the canonical storage form
+pub fn is_canonical(value: &str) -> bool {
+    parse_stored(value).is_ok_and(|dt| to_db(&dt) == value)
+}
+
+/// Parse a value read from the database. Besides RFC3339 with any offset this accepts the
+/// formats older rows were written in: SQLite's `CURRENT_TIMESTAMP` (`2025-03-14 14:30:05`),
+/// offset-less ISO strings and bare dates. Those are UTC by construction, since the server
+/// and SQLite both write UTC.
+pub fn parse_stored(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(truncate_to_millis(dt.with_timezone(&Utc)));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+        if let Ok(naive) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(truncate_to_millis(naive.and_utc()));
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        && let Some(naive) = date.and_hms_opt(0, 0, 0)
+    {
+        return Ok(naive.and_utc());
+    }
+    Err(format!("Unsupported timestamp format: {}", value))
+}
+
+/// Parse a timestamp sent by a client. Requires RFC3339 with an explicit offset (`Z` or
+/// `+hh:mm`), converts to UTC, and checks the value survives a round trip through storage.
+pub fn parse_input(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    let dt = match DateTime::parse_from_rfc3339(value) {
+        Ok(dt) => truncate_to_millis(dt.with_timezone(&Utc)),
+        Err(_) if NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+            || NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").is_ok() =>
+        {
+            return Err(format!(
+                "Timestamp '{}' has no timezone offset; send UTC ('Z') or an explicit offset",
+                value
+            ));
+        }
+        Err(e) => return Err(format!("Invalid RFC3339 timestamp '{}': {}", value, e)),
+    };
+
+    // Years outside 0000-9999 serialize with a sign and break string ordering in SQL
+    let stored = to_db(&dt);
+    match parse_stored(&stored) {
+        Ok(back) if back == dt && stored.len() == 24 => Ok(dt),
+        _ => Err(format!("Timestamp '{}' cannot be stored without loss", value)),
+    }
+}
+
+/// Serde adapter for `DateTime<Utc>` fields: writes the storage form, reads via `parse_input`
+pub mod utc_millis {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::to_db(dt))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        super::parse_input(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// `utc_millis` for `Option<DateTime<Utc>>`; pair with `#[serde(default)]`
+pub mod utc_millis_option {
+    use chrono::{DateTime, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match dt {
+            Some(dt) => serializer.serialize_str(&super::to_db(dt)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) if !value.trim().is_empty() => super::parse_input(&value).map(Some).map_err(serde::de::Error::custom),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Timestamp columns rewritten by `normalize_trade_timestamps`. `updated_at` is left out:
+/// the update triggers overwrite it with `CURRENT_TIMESTAMP` anyway.
+const TRADE_TIMESTAMP_COLUMNS: &[(&str, &[&str])] = &[
+    ("stocks", &["entry_date", "exit_date"]),
+    ("options", &["entry_date", "exit_date", "expiration_date"]),
+];
+
+/// Rewrite trade timestamps that aren't in the canonical form. Idempotent; returns how many
+/// values changed. Unparseable values are logged and left alone.
+pub async fn normalize_trade_timestamps(conn: &Connection) -> Result<usize> {
+    let mut rewritten = 0;
+    for (table, columns) in TRADE_TIMESTAMP_COLUMNS {
+        for column in *columns {
+            // Canonical values are exactly 24 characters and end in Z
+            let sql = format!(
+                "SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL AND (length({column}) != 24 OR substr({column}, 24, 1) != 'Z')"
+            );
+            let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+            let mut updates = Vec::new();
+            while let Some(row) = rows.next().await? {
+                let id: i64 = row.get(0)?;
+                let value: String = row.get(1)?;
+                match parse_stored(&value) {
+                    Ok(dt) => updates.push((id, to_db(&dt))),
+                    Err(e) => warn!("Leaving {}.{} for row {} as is: {}", table, column, id, e),
+                }
+            }
+            drop(rows);
+
+            let update = format!("UPDATE {table} SET {column} = ? WHERE id = ?");
+            for (id, value) in updates {
+                conn.execute(&update, params![value, id]).await?;
+                rewritten += 1;
+            }
+        }
+    }
+    if rewritten > 0 {
+        info!("Normalized {} trade timestamps to UTC milliseconds", rewritten);
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offsets_convert_to_utc_millis() {
+        let dt = parse_input("2025-03-14T09:30:05.123456-05:00").unwrap();
+        assert_eq!(to_db(&dt), "2025-03-14T14:30:05.123Z");
+        assert!(is_canonical(&to_db(&dt)));
+        assert!(!is_canonical("2025-03-14T14:30:05.123456+00:00"));
+    }
+
+    #[test]
+    fn test_input_requires_offset_and_storable_year() {
+        assert!(parse_input("2025-03-14T09:30:00").unwrap_err().contains("no timezone offset"));
+        assert!(parse_input("2025-03-14 09:30:00").is_err());
+        assert!(parse_input("not a date").is_err());
+        assert!(parse_input("2025-03-14T09:30:00Z").is_ok());
+    }
+
+    #[test]
+    fn test_legacy_stored_formats_read_as_utc() {
+        let expected = "2025-10-29T07:17:16.000Z";
+        assert_eq!(to_db(&parse_stored("2025-10-29 07:17:16").unwrap()), expected);
+        assert_eq!(to_db(&parse_stored("2025-10-29T07:17:16").unwrap()), expected);
+        assert_eq!(to_db(&parse_stored("2025-10-29T07:17:16+00:00").unwrap()), expected);
+        assert_eq!(to_db(&parse_stored("2025-10-29").unwrap()), "2025-10-29T00:00:00.000Z");
+    }
+}
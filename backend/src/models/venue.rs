@@ -0,0 +1,78 @@
+//! Exchange / venue a trade executed on.
+//!
+//! Brokers, market data providers and users spell venues differently (`NMS`, `XNAS` and
+//! `Nasdaq` are the same place), so everything is normalized on write to one display
+//! name. Analytics group on the stored value, with trades that have none under `Unknown`.
+
+/// Group name for trades without a recorded venue
+pub const UNKNOWN_VENUE: &str = "Unknown";
+
+/// Longest venue name stored as given when it isn't a known code
+const MAX_VENUE_LEN: usize = 32;
+
+/// Canonical name for a venue code or name: exchange acronyms, MIC codes, Yahoo exchange
+/// codes and common crypto exchange names. Unknown venues are kept, uppercased. Blank
+/// input gives None.
+pub fn normalize_venue(raw: &str) -> Option<String> {
+    let code = raw.trim().to_uppercase();
+    if code.is_empty() {
+        return None;
+    }
+    let canonical = match code.as_str() {
+        "NASDAQ" | "NMS" | "NGM" | "NCM" | "NAS" | "XNAS" | "NASDAQGS" | "NASDAQGM" | "NASDAQCM" => "NASDAQ",
+        "NYSE" | "NYQ" | "XNYS" | "NYS" => "NYSE",
+        "ARCA" | "ARCX" | "PCX" | "NYSEARCA" | "NYSE ARCA" => "NYSE Arca",
+        "AMEX" | "ASE" | "XASE" | "NYSEAMERICAN" | "NYSE AMERICAN" | "NYSEMKT" => "NYSE American",
+        "CBOE" | "XCBO" | "CBO" | "BATS" | "BZX" | "XCBF" | "CBOE BZX" => "CBOE",
+        "OTC" | "PNK" | "OQB" | "OQX" | "OTCMKTS" | "OTC MARKETS" => "OTC",
+        "CRYPTO" | "CCC" | "CCY" => "Crypto",
+        "COINBASE" | "GDAX" | "COINBASE PRO" => "Coinbase",
+        "BINANCE" | "BINANCE.US" | "BINANCEUS" => "Binance",
+        "KRAKEN" => "Kraken",
+        "GEMINI" => "Gemini",
+        _ => return Some(code.chars().take(MAX_VENUE_LEN).collect()),
+    };
+    Some(canonical.to_string())
+}
+
+/// `normalize_venue` over an optional value
+pub fn normalize_venue_opt(raw: Option<&str>) -> Option<String> {
+    raw.and_then(normalize_venue)
+}
+
+/// Venue of a broker (SnapTrade) transaction or position: the symbol's exchange, given
+/// either as a code string or as an object with `code`, `mic_code` and `name`
+pub fn venue_from_brokerage_json(value: &serde_json::Value) -> Option<String> {
+    let exchange = value.get("symbol")?.get("exchange")?;
+    if let Some(code) = exchange.as_str() {
+        return normalize_venue(code);
+    }
+    ["code", "mic_code", "name"]
+        .iter()
+        .filter_map(|key| exchange.get(key).and_then(|v| v.as_str()))
+        .find_map(normalize_venue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_venue() {
+        assert_eq!(normalize_venue("NMS").as_deref(), Some("NASDAQ"));
+        assert_eq!(normalize_venue(" xnas ").as_deref(), Some("NASDAQ"));
+        assert_eq!(normalize_venue("NYQ").as_deref(), Some("NYSE"));
+        assert_eq!(normalize_venue("PCX").as_deref(), Some("NYSE Arca"));
+        assert_eq!(normalize_venue("cboe").as_deref(), Some("CBOE"));
+        assert_eq!(normalize_venue("CCC").as_deref(), Some("Crypto"));
+        assert_eq!(normalize_venue("Coinbase").as_deref(), Some("Coinbase"));
+        assert_eq!(normalize_venue("iex").as_deref(), Some("IEX"));
+        assert_eq!(normalize_venue("   "), None);
+        assert_eq!(normalize_venue_opt(None), None);
+
+        let transaction = serde_json::json!({"symbol": {"symbol": "AAPL", "exchange": {"code": "", "mic_code": "XNAS"}}});
+        assert_eq!(venue_from_brokerage_json(&transaction).as_deref(), Some("NASDAQ"));
+        assert_eq!(venue_from_brokerage_json(&serde_json::json!({"symbol": {"exchange": "NYSE"}})).as_deref(), Some("NYSE"));
+        assert_eq!(venue_from_brokerage_json(&serde_json::json!({"symbol": {"symbol": "AAPL"}})), None);
+    }
+}
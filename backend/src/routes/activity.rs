@@ -0,0 +1,63 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::error;
+use serde::Deserialize;
+
+use crate::models::timestamps;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::activity::{self, ActivityQuery};
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityParams {
+    cursor: Option<String>,
+    limit: Option<usize>,
+    /// RFC3339 or `YYYY-MM-DD`
+    since: Option<String>,
+    /// Comma-separated event kinds
+    kinds: Option<String>,
+}
+
+pub fn configure_activity_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/activity").route("", web::get().to(get_activity))
+}
+
+async fn get_activity(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    params: web::Query<ActivityParams>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let params = params.into_inner();
+
+    let since = match params.since.as_deref().map(timestamps::parse_stored).transpose() {
+        Ok(since) => since,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message}))),
+    };
+    let kinds: Vec<String> = params
+        .kinds
+        .as_deref()
+        .map(|k| k.split(',').map(|k| k.trim().to_string()).filter(|k| !k.is_empty()).collect())
+        .unwrap_or_default();
+    if let Err(message) = activity::validate_kinds(&kinds) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+
+    let query = ActivityQuery {
+        cursor: params.cursor,
+        limit: params.limit.unwrap_or(activity::DEFAULT_LIMIT),
+        since,
+        kinds,
+    };
+    let conn = user_db_connection(&app, &user_id).await?;
+    match activity::feed(&conn, &query).await {
+        Ok(page) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": page}))),
+        Err(e) => {
+            error!("Failed to load activity feed for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load activity feed"})))
+        }
+    }
+}
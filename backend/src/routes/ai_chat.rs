@@ -3,6 +3,10 @@
 use crate::models::ai::chat::{
     ChatRequest
 };
+use crate::service::ai_service::chat_export::{self, ExportFormat};
+use crate::service::ai_service::data_query;
+use crate::service::cost_ledger;
+use crate::service::rate_limiter::{chat_message_cost, RateLimitError, RateLimitResult, CHAT_BURST_CAPACITY};
 use crate::turso::config::SupabaseConfig;
 use crate::turso::auth::validate_supabase_jwt_token;
 use crate::turso::AppState;
@@ -119,11 +123,19 @@ pub async fn send_chat_message(
 
     let conn = get_user_database_connection(&req, &app_state).await?;
     let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    let burst = match check_chat_burst(&app_state, &user_id, &payload.message).await {
+        Ok(burst) => burst,
+        Err(response) => return Ok(response),
+    };
 
-    match app_state.ai_chat_service.generate_response(&user_id, payload.into_inner(), &conn).await {
+    // This scope skips the rate limit middleware, so charge provider costs to the user here
+    let response = cost_ledger::attribute_to(user_id.clone(), app_state.ai_chat_service.generate_response(&user_id, payload.into_inner(), &conn)).await;
+    match response {
         Ok(response) => {
             info!("Successfully generated chat response for user: {}", user_id);
-            Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+            let mut builder = HttpResponse::Ok();
+            chat_burst_headers(&mut builder, &burst);
+            Ok(builder.json(ApiResponse::success(response)))
         }
         Err(e) => {
             error!("Failed to generate chat response for user {}: {}", user_id, e);
@@ -134,6 +146,43 @@ pub async fn send_chat_message(
     }
 }
 
+/// Take the message from the user's chat burst bucket. Err is the 429 to return; Ok(None)
+/// means Redis was unavailable and the message goes through unmetered.
+async fn check_chat_burst(app_state: &AppState, user_id: &str, message: &str) -> std::result::Result<Option<RateLimitResult>, HttpResponse> {
+    match app_state.rate_limiter.check_chat_burst(user_id, chat_message_cost(message)).await {
+        Ok(result) => Ok(Some(result)),
+        Err(RateLimitError::Exceeded { remaining, reset_at }) => {
+            let retry_after = reset_at.saturating_sub(chrono::Utc::now().timestamp().max(0) as u64).max(1);
+            info!("Chat burst limit reached for user {}, retry in {}s", user_id, retry_after);
+            Err(HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", retry_after.to_string()))
+                .insert_header(("X-Chat-Burst-Limit", (CHAT_BURST_CAPACITY as u64).to_string()))
+                .insert_header(("X-Chat-Burst-Remaining", remaining.to_string()))
+                .insert_header(("X-Chat-Burst-Reset", reset_at.to_string()))
+                .json(serde_json::json!({
+                    "success": false,
+                    "message": "You're sending messages faster than the assistant allows. Please wait a moment.",
+                    "error": "CHAT_BURST_LIMIT_EXCEEDED",
+                    "retry_after": retry_after,
+                })))
+        }
+        Err(RateLimitError::Redis(e)) => {
+            error!("Chat burst limiter unavailable, allowing message: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Remaining-quota headers the chat UI displays
+fn chat_burst_headers(builder: &mut actix_web::HttpResponseBuilder, burst: &Option<RateLimitResult>) {
+    if let Some(burst) = burst {
+        builder
+            .insert_header(("X-Chat-Burst-Limit", burst.limit.to_string()))
+            .insert_header(("X-Chat-Burst-Remaining", burst.remaining.to_string()))
+            .insert_header(("X-Chat-Burst-Reset", burst.reset_at.to_string()));
+    }
+}
+
 /// Send a streaming chat message
 pub async fn send_streaming_chat_message(
     req: HttpRequest,
@@ -144,6 +193,10 @@ pub async fn send_streaming_chat_message(
 
     let conn = get_user_database_connection(&req, &app_state).await?;
     let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    let burst = match check_chat_burst(&app_state, &user_id, &payload.message).await {
+        Ok(burst) => burst,
+        Err(response) => return Ok(response),
+    };
 
     let chat_request = ChatRequest {
         message: payload.message.clone(),
@@ -152,7 +205,8 @@ pub async fn send_streaming_chat_message(
         max_context_vectors: payload.max_context_vectors,
     };
 
-    match app_state.ai_chat_service.generate_streaming_response(&user_id, chat_request, &conn).await {
+    let response = cost_ledger::attribute_to(user_id.clone(), app_state.ai_chat_service.generate_streaming_response(&user_id, chat_request, &conn)).await;
+    match response {
         Ok((stream_receiver, _session_id, _message_id)) => {
             info!("Successfully started streaming chat response for user: {}", user_id);
             
@@ -169,7 +223,9 @@ pub async fn send_streaming_chat_message(
                 }
             });
 
-            Ok(HttpResponse::Ok()
+            let mut builder = HttpResponse::Ok();
+            chat_burst_headers(&mut builder, &burst);
+            Ok(builder
                 .content_type("application/x-ndjson")
                 .append_header(("Cache-Control", "no-cache"))
                 .append_header(("Connection", "keep-alive"))
@@ -254,12 +310,22 @@ pub async fn create_chat_session(
         })?;
 
     let title = payload.get("title").and_then(|v| v.as_str()).map(|s| s.to_string());
+    // A report_id pins the session to that report's snapshot instead of live retrieval
+    let report_id = payload.get("report_id").and_then(|v| v.as_str());
+
+    let result = match report_id {
+        Some(report_id) => app_state.ai_chat_service.create_report_session(&conn, &user_id, report_id).await,
+        None => app_state.ai_chat_service.create_session(&conn, &user_id, title).await,
+    };
 
-    match app_state.ai_chat_service.create_session(&conn, &user_id, title).await {
+    match result {
         Ok(session) => {
             info!("Successfully created chat session {} for user: {}", session.id, user_id);
             Ok(HttpResponse::Created().json(ApiResponse::success(session)))
         }
+        Err(e) if e.to_string() == "Report not found" => {
+            Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Report not found".to_string())))
+        }
         Err(e) => {
             error!("Failed to create chat session for user {}: {}", user_id, e);
             Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
@@ -355,14 +421,123 @@ async fn fix_message_counts(
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+fn export_response(sessions: &[crate::models::ai::chat::ChatSessionDetailsResponse], format: ExportFormat, filename: &str) -> HttpResponse {
+    match chat_export::render(sessions, format) {
+        Ok(body) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"{}.{}\"", filename, format.extension())))
+            .body(body),
+        Err(e) => {
+            error!("Failed to render chat export: {}", e);
+            HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to export chat history".to_string()))
+        }
+    }
+}
+
+/// Export one chat session, with the sources each answer referenced
+pub async fn export_chat_session(
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<ExportQuery>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let session_id = path.into_inner();
+    let conn = get_user_database_connection(&req, &app_state).await?;
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    match app_state.ai_chat_service.get_session_details(&conn, &session_id, &user_id).await {
+        Ok(details) => Ok(export_response(&[details], query.format, &format!("chat-{}", session_id))),
+        Err(e) => {
+            error!("Failed to export chat session {} for user {}: {}", session_id, user_id, e);
+            Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Chat session not found".to_string())))
+        }
+    }
+}
+
+/// Export every chat session
+pub async fn export_all_chat_sessions(
+    req: HttpRequest,
+    query: web::Query<ExportQuery>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let conn = get_user_database_connection(&req, &app_state).await?;
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    match app_state.ai_chat_service.get_all_session_details(&conn, &user_id).await {
+        Ok(sessions) => {
+            info!("Exporting {} chat sessions for user: {}", sessions.len(), user_id);
+            Ok(export_response(&sessions, query.format, &format!("chat-history-{}", chrono::Utc::now().format("%Y-%m-%d"))))
+        }
+        Err(e) => {
+            error!("Failed to export chat sessions for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to export chat history".to_string())))
+        }
+    }
+}
+
+/// Aggregate question answered from analytics
+#[derive(Debug, Deserialize)]
+pub struct AskDataRequest {
+    pub question: String,
+}
+
+/// Answer a question about the whole journal ("what's my most profitable setup this
+/// year?") by running whitelisted analytics and citing their numbers
+pub async fn ask_data(
+    req: HttpRequest,
+    payload: web::Json<AskDataRequest>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let question = payload.question.trim();
+    if question.is_empty() || question.chars().count() > data_query::MAX_QUESTION_CHARS {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "Question must be 1 to {} characters",
+            data_query::MAX_QUESTION_CHARS
+        ))));
+    }
+
+    let conn = get_user_database_connection(&req, &app_state).await?;
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    let burst = match check_chat_burst(&app_state, &user_id, question).await {
+        Ok(burst) => burst,
+        Err(response) => return Ok(response),
+    };
+
+    let response = cost_ledger::attribute_to(user_id.clone(), app_state.ai_chat_service.answer_data_question(&conn, question)).await;
+    match response {
+        Ok(answer) => {
+            let mut builder = HttpResponse::Ok();
+            chat_burst_headers(&mut builder, &burst);
+            Ok(builder.json(ApiResponse::success(answer)))
+        }
+        Err(e) => {
+            error!("Failed to answer data question for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to answer question".to_string()
+            )))
+        }
+    }
+}
+
 /// Configure AI chat routes
 pub fn configure_ai_chat_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/ai/chat")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::concurrency::tenant_concurrency_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::timeout::ai_timeout_middleware))
             .route("", web::post().to(send_chat_message))
             .route("/stream", web::post().to(send_streaming_chat_message))
+            .route("/ask-data", web::post().to(ask_data))
             .route("/sessions", web::get().to(get_chat_sessions))
             .route("/sessions", web::post().to(create_chat_session))
+            .route("/sessions/export", web::get().to(export_all_chat_sessions))
+            .route("/sessions/{id}/export", web::get().to(export_chat_session))
             .route("/sessions/{id}", web::get().to(get_chat_session))
             .route("/sessions/{id}/title", web::put().to(update_chat_session_title))
             .route("/sessions/{id}", web::delete().to(delete_chat_session))
@@ -0,0 +1,105 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::ai_service::AICoachService;
+use crate::turso::{AppState, client::TursoClient};
+
+pub fn configure_ai_coach_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/ai/coach")
+        .route("/weekly-digest", web::post().to(generate_weekly_digest))
+}
+
+/// Generate (and deliver) this week's digest for the current user on demand
+async fn generate_weekly_digest(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let conn = app.turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))?;
+
+    let insight = app.ai_coach_service
+        .generate_weekly_digest(&conn, &user_id)
+        .await
+        .map_err(|e| {
+            error!("Weekly coach digest failed for user {}: {}", user_id, e);
+            actix_web::error::ErrorBadGateway("Failed to generate weekly digest")
+        })?;
+
+    match insight {
+        Some(insight) => {
+            let delivery = deliver(&app, &conn, &user_id, &insight).await;
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": insight, "delivery": delivery})))
+        }
+        None => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": null,
+            "message": "No closed trades this week, or a digest was already generated in the last 6 days"
+        }))),
+    }
+}
+
+async fn deliver(
+    app: &AppState,
+    conn: &libsql::Connection,
+    user_id: &str,
+    insight: &crate::models::ai::insights::Insight,
+) -> crate::service::ai_service::coach_service::DigestDelivery {
+    let address = match app.config.email {
+        Some(_) => app.turso_client.get_user_database(user_id).await.ok().flatten().map(|e| e.email),
+        None => None,
+    };
+    let email = app.config.email.as_ref().zip(address.as_deref()).filter(|(_, a)| !a.is_empty());
+    AICoachService::deliver_digest(conn, user_id, insight, &app.config.web_push, email).await
+}
+
+/// Cron endpoint: weekly digest for every active user
+pub async fn send_all_weekly_digests(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for weekly coach: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut generated = 0u64;
+    let mut skipped = 0u64;
+    let mut failure_count = 0u64;
+
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        match app_state.ai_coach_service.generate_weekly_digest(&conn, &user_id).await {
+            Ok(Some(insight)) => {
+                deliver(&app_state, &conn, &user_id, &insight).await;
+                generated += 1;
+            }
+            Ok(None) => skipped += 1,
+            Err(e) => {
+                failure_count += 1;
+                error!("Weekly coach digest failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    let summary = serde_json::json!({
+        "generated": generated,
+        "skipped": skipped,
+        "failure_count": failure_count,
+    });
+    info!("Weekly coach digests completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
@@ -0,0 +1,70 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::error;
+
+use crate::routes::common::{get_user_id_from_ext, user_connection};
+use crate::service::ai_service::feedback_service::{FeedbackService, FeedbackTarget, SubmitFeedbackRequest};
+use crate::service::ai_service::model_selector::ModelSelector;
+use crate::turso::AppState;
+
+pub fn configure_ai_feedback_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/ai/feedback")
+        .route("/insights/{id}", web::post().to(submit_insight_feedback))
+        .route("/insights/{id}", web::get().to(get_insight_feedback))
+        .route("/chat-messages/{id}", web::post().to(submit_chat_feedback))
+        .route("/chat-messages/{id}", web::get().to(get_chat_feedback))
+        .route("/model-scores", web::get().to(get_model_scores))
+}
+
+async fn submit(app: web::Data<AppState>, req: HttpRequest, target: FeedbackTarget, id: String, body: SubmitFeedbackRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let registry = app.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let feedback = FeedbackService::new(&conn)
+        .submit(&registry, &app.model_selector, target, &id, body)
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": feedback})))
+}
+
+async fn get(app: web::Data<AppState>, req: HttpRequest, target: FeedbackTarget, id: String) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let feedback = FeedbackService::new(&conn).get(target, &id).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": feedback})))
+}
+
+async fn submit_insight_feedback(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>, body: web::Json<SubmitFeedbackRequest>) -> actix_web::Result<HttpResponse> {
+    submit(app, req, FeedbackTarget::Insight, path.into_inner(), body.into_inner()).await
+}
+
+async fn get_insight_feedback(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    get(app, req, FeedbackTarget::Insight, path.into_inner()).await
+}
+
+async fn submit_chat_feedback(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>, body: web::Json<SubmitFeedbackRequest>) -> actix_web::Result<HttpResponse> {
+    submit(app, req, FeedbackTarget::ChatMessage, path.into_inner(), body.into_inner()).await
+}
+
+async fn get_chat_feedback(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    get(app, req, FeedbackTarget::ChatMessage, path.into_inner()).await
+}
+
+/// Aggregated quality scores per candidate model (no per-user data)
+async fn get_model_scores(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let registry = app.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let by_prompt = ModelSelector::prompt_scores(&registry).await.map_err(|e| {
+        error!("Failed to load model quality scores: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to load scores")
+    })?;
+    let models: Vec<_> = app.model_selector.scores().into_iter().map(|s| {
+        serde_json::json!({"model": s.model, "positive": s.positive, "negative": s.negative, "quality": s.quality(), "low_performing": s.is_low_performing()})
+    }).collect();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"active_model": app.model_selector.select(), "models": models, "by_prompt": by_prompt}
+    })))
+}
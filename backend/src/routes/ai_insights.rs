@@ -1,13 +1,18 @@
 #![allow(dead_code)]
 
 use crate::models::ai::insights::{
-    InsightRequest, InsightType
+    InsightRequest, InsightSource, InsightType
 };
 use crate::models::stock::stocks::TimeRange;
+use crate::middleware::cron_auth::verify_cron_secret;
 use crate::service::ai_service::insights_service::AIInsightsService;
+use crate::service::ai_service::recommendation_outcomes::{self, RecommendationStatus};
+use crate::service::i18n;
 use crate::turso::client::TursoClient;
 use crate::turso::config::SupabaseConfig;
 use crate::turso::auth::validate_supabase_jwt_token;
+use crate::turso::AppState;
+use crate::turso::locks::{INSIGHT_REGENERATION_JOB, NIGHTLY_JOB_LOCK_TTL};
 use actix_web::{web, HttpRequest, HttpResponse, Result};
 use actix_web_httpauth::middleware::HttpAuthentication;
 use log::{info, error};
@@ -63,6 +68,9 @@ pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
     pub error: Option<String>,
+    /// Localized labels for enum values in `data`, keyed by namespace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<serde_json::Value>,
 }
 
 impl<T> ApiResponse<T> {
@@ -71,6 +79,7 @@ impl<T> ApiResponse<T> {
             success: true,
             data: Some(data),
             error: None,
+            labels: None,
         }
     }
 
@@ -79,8 +88,20 @@ impl<T> ApiResponse<T> {
             success: false,
             data: None,
             error: Some(message),
+            labels: None,
         }
     }
+
+    pub fn with_labels(mut self, labels: serde_json::Value) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+}
+
+/// Insight type and time range labels in the user's locale
+async fn insight_labels(conn: &libsql::Connection) -> serde_json::Value {
+    let locale = i18n::user_locale(conn).await;
+    serde_json::json!(i18n::labels_for(locale, &["insight_type", "time_range"]))
 }
 
 /// Generate insights request
@@ -90,6 +111,9 @@ pub struct GenerateInsightsRequest {
     pub insight_type: String,
     pub include_predictions: Option<bool>,
     pub force_regenerate: Option<bool>,
+    /// `llm` or `rules`; omitted tries the LLM and falls back to rules
+    #[serde(default)]
+    pub source: Option<InsightSource>,
 }
 
 /// Generate insights asynchronously request
@@ -99,6 +123,9 @@ pub struct GenerateInsightsAsyncRequest {
     pub insight_type: String,
     pub include_predictions: Option<bool>,
     pub force_regenerate: Option<bool>,
+    /// `llm` or `rules`; omitted tries the LLM and falls back to rules
+    #[serde(default)]
+    pub source: Option<InsightSource>,
 }
 
 /// Insights list query parameters
@@ -145,12 +172,13 @@ pub async fn generate_insights(
         insight_type,
         include_predictions: payload.include_predictions,
         force_regenerate: payload.force_regenerate,
+        source: payload.source,
     };
 
     match ai_insights_service.generate_insights(&user_id, insight_request, &conn).await {
         Ok(insight) => {
             info!("Successfully generated insights for user: {}", user_id);
-            Ok(HttpResponse::Ok().json(ApiResponse::success(insight)))
+            Ok(HttpResponse::Ok().json(ApiResponse::success(insight).with_labels(insight_labels(&conn).await)))
         }
         Err(e) => {
             error!("Failed to generate insights for user {}: {}", user_id, e);
@@ -183,6 +211,7 @@ pub async fn generate_insights_async(
         insight_type,
         include_predictions: payload.include_predictions,
         force_regenerate: payload.force_regenerate,
+        source: payload.source,
     };
 
     match ai_insights_service.generate_insights_async(&user_id, insight_request, &conn).await {
@@ -284,7 +313,7 @@ pub async fn get_insights(
     ).await {
         Ok(response) => {
             info!("Successfully retrieved {} insights for user: {}", response.total_count, user_id);
-            Ok(HttpResponse::Ok().json(ApiResponse::success(response)))
+            Ok(HttpResponse::Ok().json(ApiResponse::success(response).with_labels(insight_labels(&conn).await)))
         }
         Err(e) => {
             error!("Failed to get insights for user {}: {}", user_id, e);
@@ -314,7 +343,7 @@ pub async fn get_insight(
     match ai_insights_service.get_insight(&conn, &insight_id, &user_id).await {
         Ok(insight) => {
             info!("Successfully retrieved insight {} for user: {}", insight_id, user_id);
-            Ok(HttpResponse::Ok().json(ApiResponse::success(insight)))
+            Ok(HttpResponse::Ok().json(ApiResponse::success(insight).with_labels(insight_labels(&conn).await)))
         }
         Err(e) => {
             error!("Failed to get insight {} for user {}: {}", insight_id, user_id, e);
@@ -325,6 +354,87 @@ pub async fn get_insight(
     }
 }
 
+/// Check the numbers and trades an insight's findings cite against the analytics engine
+pub async fn verify_insight(
+    req: HttpRequest,
+    path: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+    ai_insights_service: web::Data<Arc<AIInsightsService>>,
+) -> Result<HttpResponse> {
+    let insight_id = path.into_inner();
+    info!("Verifying insight: {}", insight_id);
+
+    let conn = get_user_database_connection(&req, &turso_client, &supabase_config).await?;
+    let user_id = get_authenticated_user(&req, &supabase_config).await?;
+
+    match ai_insights_service.verify_insight(&conn, &insight_id, &user_id).await {
+        Ok(verification) => {
+            if verification.contradicted > 0 {
+                log::warn!("Insight {} has {} finding(s) contradicted by analytics", insight_id, verification.contradicted);
+            }
+            Ok(HttpResponse::Ok().json(ApiResponse::success(verification)))
+        }
+        Err(e) => {
+            error!("Failed to verify insight {} for user {}: {}", insight_id, user_id, e);
+            Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error(
+                "Insight not found".to_string()
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecommendationStatusRequest {
+    pub status: RecommendationStatus,
+}
+
+/// Mark one of an insight's recommendations as acted on, dismissed, or back to pending
+pub async fn update_recommendation_status(
+    req: HttpRequest,
+    path: web::Path<(String, u32)>,
+    payload: web::Json<RecommendationStatusRequest>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let (insight_id, index) = path.into_inner();
+    let conn = get_user_database_connection(&req, &turso_client, &supabase_config).await?;
+
+    match recommendation_outcomes::set_status(&conn, &insight_id, index, payload.status).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+            "insight_id": insight_id,
+            "recommendation_index": index,
+            "status": payload.status,
+        })))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Recommendation not found".to_string()))),
+        Err(e) => {
+            error!("Failed to update recommendation {} of insight {}: {}", index, insight_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to update recommendation".to_string()
+            )))
+        }
+    }
+}
+
+/// How results moved in the month after each recommendation, acted-on advice against the rest
+pub async fn get_recommendation_outcomes(
+    req: HttpRequest,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let conn = get_user_database_connection(&req, &turso_client, &supabase_config).await?;
+
+    match recommendation_outcomes::report(&conn).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(ApiResponse::success(report))),
+        Err(e) => {
+            error!("Failed to build recommendation outcome report: {}", e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to load recommendation outcomes".to_string()
+            )))
+        }
+    }
+}
+
 /// Delete insight
 pub async fn delete_insight(
     req: HttpRequest,
@@ -401,6 +511,57 @@ pub async fn get_generation_task_status(
     }
 }
 
+/// Cron endpoint: retry insight generations queued while the AI provider was failing
+pub async fn regenerate_all_queued_insights(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    ai_insights_service: web::Data<Arc<AIInsightsService>>,
+) -> Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for queued insights: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let (mut regenerated, mut still_queued, mut expired, mut failure_count) = (0u64, 0u64, 0u64, 0u64);
+    let mut skipped_locked = 0u64;
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        // Two instances working the same queue would both pick up and update the same tasks
+        let Some(lock) = turso_client.locks().try_acquire(INSIGHT_REGENERATION_JOB, &user_id, NIGHTLY_JOB_LOCK_TTL).await else {
+            skipped_locked += 1;
+            continue;
+        };
+        match ai_insights_service.regenerate_queued(&conn, &user_id).await {
+            Ok(summary) => {
+                regenerated += summary.regenerated;
+                still_queued += summary.still_queued;
+                expired += summary.expired;
+            }
+            Err(e) => {
+                failure_count += 1;
+                error!("Queued insight regeneration failed for user {}: {}", user_id, e);
+            }
+        }
+        lock.release().await.ok();
+    }
+
+    let summary = serde_json::json!({
+        "regenerated": regenerated,
+        "still_queued": still_queued,
+        "expired": expired,
+        "failure_count": failure_count,
+        "skipped_locked": skipped_locked,
+    });
+    info!("Queued insight regeneration completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
+
 /// Parse time range string to enum
 fn parse_time_range(time_range: &str) -> Result<TimeRange> {
     match time_range.to_lowercase().as_str() {
@@ -409,7 +570,11 @@ fn parse_time_range(time_range: &str) -> Result<TimeRange> {
         "90d" | "ninety_days" => Ok(TimeRange::NinetyDays),
         "ytd" | "year_to_date" => Ok(TimeRange::YearToDate),
         "1y" | "one_year" => Ok(TimeRange::OneYear),
-        _ => Err(actix_web::error::ErrorBadRequest(format!("Invalid time range: {}", time_range))),
+        "month_to_date" => Ok(TimeRange::MonthToDate),
+        "quarter_to_date" => Ok(TimeRange::QuarterToDate),
+        other => TimeRange::parse(other)
+            .filter(|range| !matches!(range, TimeRange::AllTime))
+            .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid time range: {}", time_range))),
     }
 }
 
@@ -422,6 +587,7 @@ fn parse_insight_type(insight_type: &str) -> Result<InsightType> {
         "behavioral_analysis" => Ok(InsightType::BehavioralAnalysis),
         "market_analysis" => Ok(InsightType::MarketAnalysis),
         "opportunity_detection" => Ok(InsightType::OpportunityDetection),
+        "weekly_coach" => Ok(InsightType::WeeklyCoach),
         _ => Err(actix_web::error::ErrorBadRequest(format!("Invalid insight type: {}", insight_type))),
     }
 }
@@ -430,12 +596,17 @@ fn parse_insight_type(insight_type: &str) -> Result<InsightType> {
 pub fn configure_ai_insights_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/ai/insights")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::concurrency::tenant_concurrency_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::timeout::ai_timeout_middleware))
             .wrap(HttpAuthentication::bearer(jwt_validator))
             .wrap(actix_web::middleware::from_fn(rate_limit_middleware))
             .route("", web::post().to(generate_insights))
             .route("/async", web::post().to(generate_insights_async))
+            .route("/recommendations/outcomes", web::get().to(get_recommendation_outcomes))
             .route("", web::get().to(get_insights))
             .route("/{id}", web::get().to(get_insight))
+            .route("/{id}/verify", web::get().to(verify_insight))
+            .route("/{id}/recommendations/{index}", web::put().to(update_recommendation_status))
             .route("/{id}", web::delete().to(delete_insight))
             .route("/tasks/{task_id}", web::get().to(get_generation_task_status))
     );
@@ -452,6 +623,9 @@ mod tests {
         assert_eq!(parse_time_range("90d").unwrap(), TimeRange::NinetyDays);
         assert_eq!(parse_time_range("ytd").unwrap(), TimeRange::YearToDate);
         assert_eq!(parse_time_range("1y").unwrap(), TimeRange::OneYear);
+        assert_eq!(parse_time_range("qtd").unwrap(), TimeRange::QuarterToDate);
+        assert_eq!(parse_time_range("last_month").unwrap(), TimeRange::LastMonth);
+        assert!(parse_time_range("2025-W10..2025-W12").is_ok());
         
         assert!(parse_time_range("invalid").is_err());
     }
@@ -0,0 +1,33 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{Duration, Utc};
+use log::error;
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::service::ai_service::request_trace;
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct LatencyReportQuery {
+    /// Window in days, default 7
+    days: Option<i64>,
+    /// Only this endpoint (`chat`, `chat_stream`)
+    endpoint: Option<String>,
+}
+
+/// Admin endpoint: per-stage latency of AI requests (avg, p50, p95, share of total)
+pub async fn get_ai_latency_report(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<LatencyReportQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let days = query.days.unwrap_or(7).clamp(1, 90);
+    let since = (Utc::now() - Duration::days(days)).to_rfc3339();
+    let registry = app_state.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let endpoints = request_trace::latency_report(&registry, &since, query.endpoint.as_deref()).await.map_err(|e| {
+        error!("Failed to build AI latency report: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to build latency report")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"days": days, "since": since, "endpoints": endpoints}})))
+}
@@ -0,0 +1,76 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use serde::Deserialize;
+
+use crate::routes::common::user_connection;
+use crate::service::ai_service::memory_service::{CreateMemoryRequest, MemoryStatus, UpdateMemoryRequest, UserMemoryService};
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    status: Option<MemoryStatus>,
+}
+
+pub fn configure_ai_memory_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/ai/memory")
+        .route("", web::get().to(list_memories))
+        .route("", web::post().to(create_memory))
+        .route("/{id}", web::put().to(update_memory))
+        .route("/{id}", web::delete().to(delete_memory))
+        .route("/{id}/confirm", web::post().to(confirm_memory))
+        .route("/{id}/reject", web::post().to(reject_memory))
+}
+
+/// List memories; `?status=proposed` returns facts awaiting confirmation
+async fn list_memories(app: web::Data<AppState>, req: HttpRequest, query: web::Query<ListQuery>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let memories = UserMemoryService::new(&conn).list(query.status).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": memories})))
+}
+
+async fn create_memory(app: web::Data<AppState>, req: HttpRequest, body: web::Json<CreateMemoryRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let memory = UserMemoryService::new(&conn).create(body.into_inner()).await.map_err(actix_web::error::ErrorBadRequest)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": memory})))
+}
+
+async fn update_memory(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>, body: web::Json<UpdateMemoryRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let memory = UserMemoryService::new(&conn)
+        .update(&path.into_inner(), body.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Memory not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": memory})))
+}
+
+async fn delete_memory(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let deleted = UserMemoryService::new(&conn).delete(&path.into_inner()).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if !deleted {
+        return Err(actix_web::error::ErrorNotFound("Memory not found"));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+async fn set_status(app: web::Data<AppState>, req: HttpRequest, id: String, status: MemoryStatus) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let memory = UserMemoryService::new(&conn)
+        .set_status(&id, status)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Memory not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": memory})))
+}
+
+async fn confirm_memory(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    set_status(app, req, path.into_inner(), MemoryStatus::Confirmed).await
+}
+
+/// Rejected memories are kept so the same fact isn't proposed again
+async fn reject_memory(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    set_status(app, req, path.into_inner(), MemoryStatus::Rejected).await
+}
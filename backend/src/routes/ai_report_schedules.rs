@@ -0,0 +1,108 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::user_connection;
+use crate::service::ai_service::report_scheduler::{
+    run_due_schedules, CreateReportScheduleRequest, ReportScheduleService, UpdateReportScheduleRequest,
+};
+use crate::turso::{AppState, client::TursoClient};
+
+pub fn configure_ai_report_schedule_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/ai/report-schedules")
+        .route("", web::get().to(list_schedules))
+        .route("", web::post().to(create_schedule))
+        .route("/{id}", web::get().to(get_schedule))
+        .route("/{id}", web::put().to(update_schedule))
+        .route("/{id}", web::delete().to(delete_schedule))
+}
+
+async fn list_schedules(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let schedules = ReportScheduleService::new(&conn).list().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": schedules})))
+}
+
+async fn create_schedule(app: web::Data<AppState>, req: HttpRequest, body: web::Json<CreateReportScheduleRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let schedule = ReportScheduleService::new(&conn).create(body.into_inner()).await.map_err(actix_web::error::ErrorBadRequest)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": schedule})))
+}
+
+async fn get_schedule(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let schedule = ReportScheduleService::new(&conn)
+        .get(&path.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Schedule not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": schedule})))
+}
+
+async fn update_schedule(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>, body: web::Json<UpdateReportScheduleRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let schedule = ReportScheduleService::new(&conn)
+        .update(&path.into_inner(), body.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Schedule not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": schedule})))
+}
+
+async fn delete_schedule(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let deleted = ReportScheduleService::new(&conn).delete(&path.into_inner()).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if !deleted {
+        return Err(actix_web::error::ErrorNotFound("Schedule not found"));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+/// Cron endpoint: generate and deliver every due scheduled report. Meant to be called every few minutes.
+pub async fn run_all_due_report_schedules(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for report schedules: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut generated = 0u64;
+    let mut failed = 0u64;
+
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        let address = match app_state.config.email {
+            Some(_) => turso_client.get_user_database(&user_id).await.ok().flatten().map(|e| e.email),
+            None => None,
+        };
+        let email = app_state.config.email.as_ref().zip(address.as_deref()).filter(|(_, a)| !a.is_empty());
+
+        match run_due_schedules(&conn, &user_id, &app_state.ai_reports_service, &app_state.config.web_push, email).await {
+            Ok(summary) => {
+                generated += summary.generated;
+                failed += summary.failed;
+            }
+            Err(e) => {
+                failed += 1;
+                error!("Report schedules failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    let summary = serde_json::json!({"generated": generated, "failed": failed});
+    info!("Scheduled reports completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
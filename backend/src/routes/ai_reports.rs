@@ -2,6 +2,8 @@ use crate::models::ai::reports::{
     ReportRequest, ReportType
 };
 use crate::models::stock::stocks::TimeRange;
+use crate::service::ai_service::report_branding::ReportBranding;
+use crate::service::ai_service::report_sharing::{render_report_html, CreateShareRequest, ReportShareService};
 use crate::turso::{AppState, config::SupabaseConfig};
 use actix_web::{HttpRequest, Result, HttpResponse, web};
 use log::{info, error};
@@ -181,6 +183,12 @@ pub async fn delete_report(
     match app_state.ai_reports_service.delete_report(&conn, &report_id).await {
         Ok(true) => {
             info!("Successfully deleted report {} for user: {}", report_id, user_id);
+            if let Some(sharing) = &app_state.config.report_sharing
+                && let Ok(registry) = app_state.turso_client.get_registry_connection().await
+                && let Err(e) = ReportShareService::new(&registry, sharing).revoke_all(&user_id, &report_id).await
+            {
+                error!("Failed to revoke share links for report {}: {}", report_id, e);
+            }
             Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
                 "success": true,
                 "message": "Report deleted successfully"
@@ -201,6 +209,152 @@ pub async fn delete_report(
     }
 }
 
+/// Share service for the current request, or a 503 when sharing isn't configured
+async fn share_service_parts(app_state: &AppState) -> Result<(libsql::Connection, &crate::turso::config::ReportSharingConfig)> {
+    let config = app_state.config.report_sharing.as_ref()
+        .ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Report sharing is not configured"))?;
+    let registry = app_state.turso_client.get_registry_connection().await
+        .map_err(|e| {
+            error!("Failed to get registry connection: {}", e);
+            actix_web::error::ErrorInternalServerError("Database connection failed")
+        })?;
+    Ok((registry, config))
+}
+
+/// Create an expiring public link to a report
+pub async fn share_report(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<CreateShareRequest>>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let report_id = path.into_inner();
+    let conn = get_user_database_connection(&req, &app_state.turso_client, &app_state.config.supabase).await?;
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    match app_state.ai_reports_service.get_report(&conn, &report_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Report not found".to_string())));
+        }
+        Err(e) => {
+            error!("Failed to get report {} for user {}: {}", report_id, user_id, e);
+            return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to retrieve report".to_string()
+            )));
+        }
+    }
+
+    let (registry, config) = share_service_parts(&app_state).await?;
+    let request = body.map(|b| b.into_inner()).unwrap_or_default();
+    match ReportShareService::new(&registry, config).create(&user_id, &report_id, request).await {
+        Ok(share) => {
+            info!("Created share link {} for report {} (user {})", share.id, report_id, user_id);
+            Ok(HttpResponse::Created().json(ApiResponse::success(share)))
+        }
+        Err(e) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// List links created for a report, with view counts
+pub async fn list_report_shares(
+    req: HttpRequest,
+    path: web::Path<String>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let report_id = path.into_inner();
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    let (registry, config) = share_service_parts(&app_state).await?;
+
+    match ReportShareService::new(&registry, config).list(&user_id, &report_id).await {
+        Ok(shares) => Ok(HttpResponse::Ok().json(ApiResponse::success(shares))),
+        Err(e) => {
+            error!("Failed to list share links for report {}: {}", report_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to retrieve share links".to_string()
+            )))
+        }
+    }
+}
+
+/// Revoke a public link
+pub async fn revoke_report_share(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let (_report_id, share_id) = path.into_inner();
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    let (registry, config) = share_service_parts(&app_state).await?;
+
+    match ReportShareService::new(&registry, config).revoke(&user_id, &share_id).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({"revoked": true})))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Share link not found".to_string()))),
+        Err(e) => {
+            error!("Failed to revoke share link {}: {}", share_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to revoke share link".to_string()
+            )))
+        }
+    }
+}
+
+/// Query parameters for the public report page
+#[derive(Debug, Deserialize)]
+pub struct SharedReportQuery {
+    pub format: Option<String>,
+}
+
+/// Public, read-only view of a shared report (HTML by default, `?format=json` for clients)
+pub async fn view_shared_report(
+    path: web::Path<String>,
+    query: web::Query<SharedReportQuery>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let not_found = || HttpResponse::NotFound().content_type("text/plain; charset=utf-8").body("This link is invalid, expired or has been revoked.");
+    let (registry, config) = share_service_parts(&app_state).await?;
+
+    let share = match ReportShareService::new(&registry, config).resolve(&path.into_inner()).await {
+        Ok(Some(share)) => share,
+        Ok(None) => return Ok(not_found()),
+        Err(e) => {
+            error!("Failed to resolve share link: {}", e);
+            return Err(actix_web::error::ErrorInternalServerError("Failed to load shared report"));
+        }
+    };
+
+    let conn = match app_state.turso_client.get_user_database_connection(&share.user_id).await {
+        Ok(Some(conn)) => conn,
+        _ => return Ok(not_found()),
+    };
+    let report = match app_state.ai_reports_service.get_report(&conn, &share.report_id).await {
+        Ok(Some(report)) => report,
+        Ok(None) => return Ok(not_found()),
+        Err(e) => {
+            error!("Failed to load shared report {}: {}", share.report_id, e);
+            return Err(actix_web::error::ErrorInternalServerError("Failed to load shared report"));
+        }
+    };
+
+    if query.format.as_deref() == Some("json") {
+        // Same content as the HTML page: no trade-level rows
+        let mut report = report;
+        report.trades.clear();
+        return Ok(HttpResponse::Ok()
+            .insert_header(("X-Robots-Tag", "noindex"))
+            .json(ApiResponse::success(report)));
+    }
+    let branding = ReportBranding::load(&conn).await.unwrap_or_else(|e| {
+        error!("Failed to load report branding for user {}: {}", share.user_id, e);
+        ReportBranding::default()
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header(("X-Robots-Tag", "noindex"))
+        .insert_header(("Cache-Control", "no-store"))
+        .body(render_report_html(&report, &branding)))
+}
+
 /// Get the status of a report generation task
 pub async fn get_generation_task_status(
     req: HttpRequest,
@@ -232,7 +386,11 @@ fn parse_time_range(time_range: &str) -> Result<TimeRange> {
         "90d" | "ninety_days" => Ok(TimeRange::NinetyDays),
         "ytd" | "year_to_date" => Ok(TimeRange::YearToDate),
         "1y" | "one_year" => Ok(TimeRange::OneYear),
-        _ => Err(actix_web::error::ErrorBadRequest(format!("Invalid time range: {}", time_range))),
+        "month_to_date" => Ok(TimeRange::MonthToDate),
+        "quarter_to_date" => Ok(TimeRange::QuarterToDate),
+        other => TimeRange::parse(other)
+            .filter(|range| !matches!(range, TimeRange::AllTime))
+            .ok_or_else(|| actix_web::error::ErrorBadRequest(format!("Invalid time range: {}", time_range))),
     }
 }
 
@@ -250,17 +408,63 @@ fn parse_report_type(report_type: &str) -> Result<ReportType> {
     }
 }
 
+/// Branding applied to shared report pages
+pub async fn get_report_branding(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let conn = get_user_database_connection(&req, &app_state.turso_client, &app_state.config.supabase).await?;
+    match ReportBranding::load(&conn).await {
+        Ok(branding) => Ok(HttpResponse::Ok().json(ApiResponse::success(branding))),
+        Err(e) => {
+            error!("Failed to load report branding: {}", e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to retrieve report branding".to_string()
+            )))
+        }
+    }
+}
+
+/// Replace the report branding; omitted or blank fields reset to the default look
+pub async fn update_report_branding(
+    req: HttpRequest,
+    body: web::Json<ReportBranding>,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let branding = body.into_inner().normalized();
+    if let Err(message) = branding.validate() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(message)));
+    }
+    let conn = get_user_database_connection(&req, &app_state.turso_client, &app_state.config.supabase).await?;
+    match branding.save(&conn).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(ApiResponse::success(branding))),
+        Err(e) => {
+            error!("Failed to save report branding: {}", e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(
+                "Failed to save report branding".to_string()
+            )))
+        }
+    }
+}
+
 /// Configure AI reports routes
 pub fn configure_ai_reports_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/ai/reports")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::concurrency::tenant_concurrency_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::timeout::ai_timeout_middleware))
             .wrap(HttpAuthentication::bearer(jwt_validator))
             .wrap(actix_web::middleware::from_fn(rate_limit_middleware))
             .route("", web::post().to(generate_report))
             .route("/async", web::post().to(generate_report_async))
             .route("", web::get().to(get_reports))
+            .route("/branding", web::get().to(get_report_branding))
+            .route("/branding", web::put().to(update_report_branding))
             .route("/{id}", web::get().to(get_report))
             .route("/{id}", web::delete().to(delete_report))
+            .route("/{id}/share", web::post().to(share_report))
+            .route("/{id}/shares", web::get().to(list_report_shares))
+            .route("/{id}/shares/{share_id}", web::delete().to(revoke_report_share))
             .route("/tasks/{task_id}", web::get().to(get_generation_task_status))
     );
 }
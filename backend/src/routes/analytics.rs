@@ -7,7 +7,38 @@ use crate::service::analytics_engine::core_metrics::{
     calculate_individual_stock_trade_analytics,
     calculate_individual_option_trade_analytics,
     calculate_symbol_analytics,
+    calculate_filtered_core_metrics,
+    calculate_sample_confidence,
 };
+use crate::service::analytics_engine::query::TradeFilter;
+use crate::service::analytics_engine::core_metrics::streaks::calculate_streaks;
+use crate::service::analytics_engine::heatmap::calculate_trade_heatmap;
+use crate::service::analytics_engine::attribution::calculate_attribution;
+use crate::service::analytics_engine::rating_buckets::calculate_rating_expectancy;
+use crate::service::analytics_engine::hold_time::calculate_hold_time_distribution;
+use crate::service::analytics_engine::efficiency::calculate_efficiency;
+use crate::service::analytics_engine::daily_aggregates::{load_open_risk, OpenRiskSeries};
+use crate::service::analytics_engine::export::{export_series, ExportMetric};
+use crate::service::analytics_engine::correlation::{calculate_correlation_matrix, DEFAULT_CLUSTER_THRESHOLD, DEFAULT_SYMBOLS};
+use crate::service::market_engine::client::MarketClient;
+use crate::service::analytics_engine::consistency::{
+    save_consistency_snapshot,
+    get_consistency_history,
+    DEFAULT_CONSISTENCY_THRESHOLD,
+};
+use crate::service::analytics_engine::snapshots::{
+    compute_snapshot,
+    save_snapshot,
+    list_snapshots,
+    get_snapshot,
+    delete_snapshot,
+    diff_snapshots,
+    diff_ranges,
+    diff_with_previous_period,
+};
+use crate::models::analytics::{ChangeSet, CoreMetrics, CustomMetricValue, SampleConfidence};
+use crate::models::analytics::custom_metrics::CustomMetricRequest;
+use crate::service::analytics_engine::custom_metrics::{self, MAX_CUSTOM_METRICS};
 use crate::service::analytics_engine::performance_metrics::{
     calculate_duration_performance_metrics,
     DurationPerformanceResponse,
@@ -58,6 +89,12 @@ impl AnalyticsService {
     }
 }
 
+impl Default for AnalyticsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Get authenticated user from request
 async fn get_authenticated_user(
     req: &HttpRequest,
@@ -79,6 +116,9 @@ pub struct AnalyticsRequest {
     pub include_grouped_analytics: Option<bool>,
     pub grouping_types: Option<Vec<String>>,
     pub risk_free_rate: Option<f64>,
+    /// Symbol, direction, tag and account filters (core metrics and rating buckets)
+    #[serde(flatten)]
+    pub filter: TradeFilter,
 }
 
 /// Response wrapper for analytics data
@@ -107,6 +147,18 @@ impl<T> AnalyticsResponse<T> {
     }
 }
 
+/// Core metrics with the user's custom metrics alongside. Filters narrow the core metrics;
+/// risk and performance values referenced by custom formulas cover the whole range.
+#[derive(Debug, Serialize)]
+pub struct CoreAnalytics {
+    #[serde(flatten)]
+    pub core: CoreMetrics,
+    pub custom_metrics: Vec<CustomMetricValue>,
+    /// Win-rate interval, expectancy standard error and the low-sample flag; None when
+    /// the per-trade P&L couldn't be read
+    pub confidence: Option<SampleConfidence>,
+}
+
 /// Get core analytics metrics (from core_metrics.rs)
 pub async fn get_core_analytics(
     req: HttpRequest,
@@ -122,17 +174,31 @@ pub async fn get_core_analytics(
 
     let request = payload.as_deref();
     let time_range = parse_time_range(&request.and_then(|r| r.time_range.clone()));
-    log::info!("Calculating core metrics for time range: {:?}", time_range);
-    let analytics_service = AnalyticsService::new();
+    let filter = request.map(|r| r.filter.clone()).unwrap_or_default();
+    log::info!("Calculating core metrics for time range: {:?}, filter: {:?}", time_range, filter);
 
-    match analytics_service.analytics_engine.calculate_core_metrics(&conn, &time_range).await {
+    match calculate_filtered_core_metrics(&conn, &time_range, &filter).await {
         Ok(metrics) => {
             log::info!("Core metrics calculated - Total trades: {}, Winning: {}, Losing: {}, Net P&L: ${:.2}", 
                       metrics.total_trades, metrics.winning_trades, metrics.losing_trades, metrics.net_profit_loss);
             if metrics.total_trades == 0 {
                 log::warn!("⚠️ Core metrics returned 0 trades. This usually means no closed trades match the time range filter (requires exit_price IS NOT NULL AND exit_date IS NOT NULL)");
             }
-            Ok(HttpResponse::Ok().json(AnalyticsResponse::success(metrics)))
+            let custom_metrics = match custom_metrics::calculate_custom_metrics(&conn, &time_range, &metrics).await {
+                Ok(values) => values,
+                Err(e) => {
+                    log::warn!("Failed to calculate custom metrics: {:?}", e);
+                    Vec::new()
+                }
+            };
+            let confidence = match calculate_sample_confidence(&conn, &time_range, &filter).await {
+                Ok(confidence) => Some(confidence),
+                Err(e) => {
+                    log::warn!("Failed to calculate sample confidence: {:?}", e);
+                    None
+                }
+            };
+            Ok(HttpResponse::Ok().json(AnalyticsResponse::success(CoreAnalytics { core: metrics, custom_metrics, confidence })))
         },
         Err(e) => {
             log::error!("Failed to calculate core metrics: {:?}", e);
@@ -426,19 +492,535 @@ pub async fn get_symbol_analytics(
     }
 }
 
+/// Request parameters for the symbol-by-month heat map
+#[derive(Debug, Deserialize)]
+pub struct HeatmapRequest {
+    pub time_range: Option<String>,
+    /// Keep only the symbols with the largest absolute P&L
+    pub limit: Option<usize>,
+    #[serde(flatten)]
+    pub filter: TradeFilter,
+}
+
+/// Per-symbol, per-month P&L and trade counts for heat map rendering
+pub async fn get_trade_heatmap(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<HeatmapRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range = parse_time_range(&request.and_then(|r| r.time_range.clone()));
+    let filter = request.map(|r| r.filter.clone()).unwrap_or_default();
+
+    match calculate_trade_heatmap(&conn, &time_range, &filter, request.and_then(|r| r.limit)).await {
+        Ok(heatmap) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(heatmap))),
+        Err(e) => {
+            log::error!("Failed to calculate trade heat map: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+/// Expectancy, win rate and average R per setup rating (1-5)
+pub async fn get_rating_expectancy(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<AnalyticsRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range = parse_time_range(&request.and_then(|r| r.time_range.clone()));
+    let filter = request.map(|r| r.filter.clone()).unwrap_or_default();
+
+    match calculate_rating_expectancy(&conn, &time_range, &filter).await {
+        Ok(expectancy) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(expectancy))),
+        Err(e) => {
+            log::error!("Failed to calculate expectancy by rating: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+/// Current and longest win/loss streaks over stocks and options together, in exit order
+pub async fn get_streaks(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<AnalyticsRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range = parse_time_range(&request.and_then(|r| r.time_range.clone()));
+    let filter = request.map(|r| r.filter.clone()).unwrap_or_default();
+
+    match calculate_streaks(&conn, &time_range, &filter).await {
+        Ok(streaks) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(streaks))),
+        Err(e) => {
+            log::error!("Failed to calculate streaks: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+/// P&L decomposed by symbol, playbook, direction and session, with interaction effects
+pub async fn get_attribution(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<AnalyticsRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range = parse_time_range(&request.and_then(|r| r.time_range.clone()));
+    let filter = request.map(|r| r.filter.clone()).unwrap_or_default();
+
+    match calculate_attribution(&conn, &time_range, &filter).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(report))),
+        Err(e) => {
+            log::error!("Failed to calculate P&L attribution: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoldTimeQuery {
+    pub time_range: Option<String>,
+}
+
+/// Hold-time histograms of winners and losers, per asset class and combined
+pub async fn get_hold_time_distribution(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<HoldTimeQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let time_range = parse_time_range(&query.time_range);
+
+    match calculate_hold_time_distribution(&conn, &time_range).await {
+        Ok(distribution) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(distribution))),
+        Err(e) => {
+            log::error!("Failed to calculate hold-time distribution: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TimeRangeQuery {
+    pub time_range: Option<String>,
+}
+
+/// Entry/exit efficiency of recent closed stock trades, from daily candles
+pub async fn get_efficiency(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<TimeRangeQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let market = MarketClient::new(&app_state.config.finance_query)
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let time_range = parse_time_range(&query.time_range);
+
+    match calculate_efficiency(&conn, &market, &time_range).await {
+        Ok(summary) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(summary))),
+        Err(e) => {
+            log::error!("Failed to calculate trade efficiency: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+/// Daily stop-based risk on open stock positions ("heat") over a range
+pub async fn get_open_risk_series(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<TimeRangeQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let time_range = parse_time_range(&query.time_range);
+    let (time_condition, time_params) = time_range.to_sql_condition();
+
+    match load_open_risk(&conn, &time_condition, &time_params).await {
+        Ok(points) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(OpenRiskSeries::new(points)))),
+        Err(e) => {
+            log::error!("Failed to load open risk series: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    /// Series name, e.g. `daily_pnl`, `rolling_win_rate_20`, `open_risk`
+    pub metric: String,
+    /// `csv` (default) or `json`
+    pub format: Option<String>,
+    pub time_range: Option<String>,
+    pub risk_free_rate: Option<f64>,
+}
+
+/// Download a computed time series as CSV or JSON
+pub async fn export_time_series(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<ExportQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let Some(metric) = ExportMetric::parse(&query.metric) else {
+        let known: Vec<&str> = ExportMetric::ALL.iter().map(|m| m.as_str()).collect();
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error(format!("Unknown metric; expected one of: {}", known.join(", ")))));
+    };
+    let format = query.format.as_deref().unwrap_or("csv").to_lowercase();
+    if format != "csv" && format != "json" {
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error("format must be csv or json".to_string())));
+    }
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let time_range = parse_time_range(&query.time_range);
+    let mut options = parse_analytics_options_from_request(None);
+    if let Some(rate) = query.risk_free_rate {
+        options.risk_free_rate = rate;
+    }
+
+    match export_series(&conn, metric, &time_range, &options).await {
+        Ok(table) if format == "json" => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(table.to_records()))),
+        Ok(table) => Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"tradstry-{}.csv\"", metric.as_str())))
+            .body(table.to_csv())),
+        Err(e) => {
+            log::error!("Failed to export {} series: {:?}", metric.as_str(), e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CorrelationQuery {
+    pub time_range: Option<String>,
+    /// How many of the most traded symbols to compare, defaults to 10 (max 20)
+    pub symbols: Option<usize>,
+    /// Correlation at which symbols are clustered together, defaults to 0.7
+    pub threshold: Option<f64>,
+}
+
+/// Daily-return correlation among the most traded symbols, with clusters of symbols
+/// that move together
+pub async fn get_correlation_matrix(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<CorrelationQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let threshold = query.threshold.unwrap_or(DEFAULT_CLUSTER_THRESHOLD);
+    if !(0.0..=1.0).contains(&threshold) {
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error("threshold must be between 0 and 1".to_string())));
+    }
+    let market = MarketClient::new(&app_state.config.finance_query)
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let time_range = parse_time_range(&query.time_range);
+    let limit = query.symbols.unwrap_or(DEFAULT_SYMBOLS);
+
+    match calculate_correlation_matrix(&conn, &app_state.cache_service, &market, &time_range, limit, threshold).await {
+        Ok(matrix) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(matrix))),
+        Err(e) => {
+            log::error!("Failed to calculate correlation matrix: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+
+/// Request parameters for consistency score
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyRequest {
+    pub time_range: Option<String>,
+    /// Max share (%) of total profit allowed on a single day, defaults to 30
+    pub threshold_percentage: Option<f64>,
+}
+
+/// Calculate consistency score and record it in the history
+pub async fn get_consistency_analytics(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<ConsistencyRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range_label = request
+        .and_then(|r| r.time_range.clone())
+        .unwrap_or_else(|| "all_time".to_string());
+    let time_range = parse_time_range(&Some(time_range_label.clone()));
+    let threshold = request
+        .and_then(|r| r.threshold_percentage)
+        .filter(|t| *t > 0.0 && *t <= 100.0)
+        .unwrap_or(DEFAULT_CONSISTENCY_THRESHOLD);
+    let analytics_service = AnalyticsService::new();
+
+    match analytics_service.analytics_engine.calculate_consistency_score(&conn, &time_range, threshold).await {
+        Ok(score) => {
+            if let Err(e) = save_consistency_snapshot(&conn, &time_range_label, &score).await {
+                log::warn!("Failed to record consistency snapshot for user {}: {}", user_id, e);
+            }
+            Ok(HttpResponse::Ok().json(AnalyticsResponse::success(score)))
+        },
+        Err(e) => {
+            log::error!("Failed to calculate consistency score: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
+
+/// Query parameters for consistency history
+#[derive(Debug, Deserialize)]
+pub struct ConsistencyHistoryQuery {
+    pub time_range: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Get recorded consistency score snapshots, newest first
+pub async fn get_consistency_history_analytics(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<ConsistencyHistoryQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let time_range_label = query.time_range.clone().unwrap_or_else(|| "all_time".to_string());
+    let limit = query.limit.unwrap_or(90).clamp(1, 365);
+
+    match get_consistency_history(&conn, &time_range_label, limit).await {
+        Ok(history) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(history))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Request body for storing an analytics snapshot
+#[derive(Debug, Deserialize)]
+pub struct CreateSnapshotRequest {
+    pub time_range: Option<String>,
+    pub label: Option<String>,
+}
+
+/// Compute metrics for a range and store them for later comparison
+pub async fn create_analytics_snapshot(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: Option<web::Json<CreateSnapshotRequest>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let request = payload.as_deref();
+    let time_range_label = request
+        .and_then(|r| r.time_range.clone())
+        .unwrap_or_else(|| "all_time".to_string());
+    let time_range = parse_time_range(&Some(time_range_label.clone()));
+    let label = request.and_then(|r| r.label.clone()).filter(|l| !l.trim().is_empty());
+
+    let snapshot = match compute_snapshot(&conn, &time_range, &time_range_label, label).await {
+        Ok(snapshot) => snapshot,
+        Err(e) => {
+            log::error!("Failed to compute analytics snapshot: {:?}", e);
+            return Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())));
+        }
+    };
+
+    match save_snapshot(&conn, &snapshot).await {
+        Ok(()) => Ok(HttpResponse::Created().json(AnalyticsResponse::success(snapshot))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Query parameters for listing snapshots
+#[derive(Debug, Deserialize)]
+pub struct SnapshotListQuery {
+    pub limit: Option<i64>,
+}
+
+/// List stored analytics snapshots, newest first
+pub async fn get_analytics_snapshots(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<SnapshotListQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 500);
+    match list_snapshots(&conn, limit).await {
+        Ok(snapshots) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(snapshots))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Get a stored analytics snapshot
+pub async fn get_analytics_snapshot(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    match get_snapshot(&conn, &path.into_inner()).await {
+        Ok(Some(snapshot)) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(snapshot))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(AnalyticsResponse::<()>::error("Snapshot not found".to_string()))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Delete a stored analytics snapshot
+pub async fn delete_analytics_snapshot(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    match delete_snapshot(&conn, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(true))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(AnalyticsResponse::<()>::error("Snapshot not found".to_string()))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Query parameters for diffing. Either two snapshot ids (`from`, `to`), two ranges
+/// (`from_range`, `to_range`), or a single `range` compared with the period before it.
+#[derive(Debug, Deserialize)]
+pub struct AnalyticsDiffQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub from_range: Option<String>,
+    pub to_range: Option<String>,
+    pub range: Option<String>,
+}
+
+/// Structured change set between two snapshots or two computed ranges
+pub async fn get_analytics_diff(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<AnalyticsDiffQuery>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let result: anyhow::Result<Option<ChangeSet>> = match (&query.from, &query.to, &query.from_range, &query.to_range, &query.range) {
+        (Some(from), Some(to), _, _, _) => diff_snapshots(&conn, from, to).await,
+        (_, _, Some(from_range), Some(to_range), _) => diff_ranges(
+            &conn,
+            (&parse_time_range(&Some(from_range.clone())), from_range),
+            (&parse_time_range(&Some(to_range.clone())), to_range),
+        ).await.map(Some),
+        (_, _, _, _, Some(range)) => diff_with_previous_period(&conn, &parse_time_range(&Some(range.clone())), range).await,
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error(
+                "Provide from/to snapshot ids, from_range/to_range, or range".to_string()
+            )));
+        }
+    };
+
+    match result {
+        Ok(Some(changes)) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(changes))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(AnalyticsResponse::<()>::error(
+            "Snapshot not found or range has no previous period".to_string()
+        ))),
+        Err(e) => {
+            log::error!("Failed to diff analytics: {:?}", e);
+            Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string())))
+        },
+    }
+}
 
 /// Parse time range from query parameter
 fn parse_time_range(time_range_str: &Option<String>) -> TimeRange {
     match time_range_str {
-        Some(range) => match range.as_str() {
-            "7d" => TimeRange::SevenDays,
-            "30d" => TimeRange::ThirtyDays,
-            "90d" => TimeRange::NinetyDays,
-            "1y" => TimeRange::OneYear,
-            "ytd" => TimeRange::YearToDate,
-            "all_time" => TimeRange::AllTime,
-            _ => TimeRange::AllTime,
-        },
+        Some(range) => TimeRange::parse(range).unwrap_or(TimeRange::AllTime),
         None => TimeRange::AllTime,
     }
 }
@@ -463,6 +1045,7 @@ fn parse_analytics_options(query: &AnalyticsRequest) -> AnalyticsOptions {
             "strategy" => GroupingType::Strategy,
             "trade_direction" => GroupingType::TradeDirection,
             "time_period" => GroupingType::TimePeriod,
+            "venue" => GroupingType::Venue,
             _ => GroupingType::Symbol,
         }).collect()
     }).unwrap_or_else(|| vec![GroupingType::Symbol]);
@@ -496,10 +1079,114 @@ fn parse_analytics_options_from_request(request: Option<&AnalyticsRequest>) -> A
     }
 }
 
+/// List the user's custom metric definitions
+pub async fn get_custom_metrics(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    match custom_metrics::list_definitions(&conn).await {
+        Ok(definitions) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(definitions))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
+fn custom_metric_save_error(e: anyhow::Error) -> HttpResponse {
+    if e.to_string().contains("UNIQUE") {
+        HttpResponse::Conflict().json(AnalyticsResponse::<()>::error("A custom metric with this name already exists".to_string()))
+    } else {
+        HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))
+    }
+}
+
+/// Define a custom metric; the formula is checked before it is stored
+pub async fn create_custom_metric(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    payload: web::Json<CustomMetricRequest>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error(message)));
+    }
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let existing = custom_metrics::list_definitions(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if existing.len() >= MAX_CUSTOM_METRICS {
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error(format!(
+            "At most {} custom metrics can be defined",
+            MAX_CUSTOM_METRICS
+        ))));
+    }
+
+    match custom_metrics::create_definition(&conn, &payload).await {
+        Ok(definition) => Ok(HttpResponse::Created().json(AnalyticsResponse::success(definition))),
+        Err(e) => Ok(custom_metric_save_error(e)),
+    }
+}
+
+/// Replace a custom metric's name, formula and description
+pub async fn update_custom_metric(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    payload: web::Json<CustomMetricRequest>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(AnalyticsResponse::<()>::error(message)));
+    }
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    match custom_metrics::update_definition(&conn, &path.into_inner(), &payload).await {
+        Ok(Some(definition)) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(definition))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(AnalyticsResponse::<()>::error("Custom metric not found".to_string()))),
+        Err(e) => Ok(custom_metric_save_error(e)),
+    }
+}
+
+/// Delete a custom metric definition
+pub async fn delete_custom_metric(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &app_state.config.supabase).await?;
+
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    match custom_metrics::delete_definition(&conn, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(AnalyticsResponse::success(true))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(AnalyticsResponse::<()>::error("Custom metric not found".to_string()))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(AnalyticsResponse::<()>::error(e.to_string()))),
+    }
+}
+
 /// Configure analytics routes
 pub fn configure_analytics_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/analytics")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::concurrency::tenant_concurrency_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::timeout::analytics_timeout_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::fields::fields_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::etag::etag_query_middleware))
             .route("/core", web::post().to(get_core_analytics))
             .route("/risk", web::post().to(get_risk_analytics))
             .route("/performance", web::post().to(get_performance_analytics))
@@ -508,5 +1195,25 @@ pub fn configure_analytics_routes(cfg: &mut web::ServiceConfig) {
             .route("/comprehensive", web::post().to(get_comprehensive_analytics))
             .route("/trade", web::get().to(get_individual_trade_analytics))
             .route("/symbol", web::get().to(get_symbol_analytics))
+            .route("/heatmap", web::post().to(get_trade_heatmap))
+            .route("/ratings", web::post().to(get_rating_expectancy))
+            .route("/streaks", web::post().to(get_streaks))
+            .route("/attribution", web::post().to(get_attribution))
+            .route("/hold-time-distribution", web::get().to(get_hold_time_distribution))
+            .route("/efficiency", web::get().to(get_efficiency))
+            .route("/open-risk", web::get().to(get_open_risk_series))
+            .route("/export", web::get().to(export_time_series))
+            .route("/correlation", web::get().to(get_correlation_matrix))
+            .route("/consistency", web::post().to(get_consistency_analytics))
+            .route("/consistency/history", web::get().to(get_consistency_history_analytics))
+            .route("/snapshots", web::post().to(create_analytics_snapshot))
+            .route("/snapshots", web::get().to(get_analytics_snapshots))
+            .route("/snapshots/{id}", web::get().to(get_analytics_snapshot))
+            .route("/snapshots/{id}", web::delete().to(delete_analytics_snapshot))
+            .route("/diff", web::get().to(get_analytics_diff))
+            .route("/custom-metrics", web::get().to(get_custom_metrics))
+            .route("/custom-metrics", web::post().to(create_custom_metric))
+            .route("/custom-metrics/{id}", web::put().to(update_custom_metric))
+            .route("/custom-metrics/{id}", web::delete().to(delete_custom_metric))
     );
 }
@@ -0,0 +1,127 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::error;
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::automations::{self, AutomationRuleRequest};
+use crate::turso::AppState;
+
+/// Runs returned per rule when no limit is given
+const DEFAULT_RUN_LIMIT: u32 = 50;
+
+pub fn configure_automation_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/automations")
+        .route("", web::get().to(list_rules))
+        .route("", web::post().to(create_rule))
+        .route("/{id}", web::get().to(get_rule))
+        .route("/{id}", web::put().to(update_rule))
+        .route("/{id}", web::delete().to(delete_rule))
+        .route("/{id}/runs", web::get().to(list_runs))
+}
+
+async fn list_rules(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match automations::list_rules(&conn, None).await {
+        Ok(rules) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rules}))),
+        Err(e) => {
+            error!("Failed to list automation rules: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list automation rules"})))
+        }
+    }
+}
+
+async fn create_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<AutomationRuleRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    match automations::create_rule(&conn, &payload).await {
+        Ok(rule) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": rule}))),
+        Err(e) => {
+            error!("Failed to create automation rule: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to create automation rule"})))
+        }
+    }
+}
+
+async fn get_rule(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match automations::get_rule(&conn, &path.into_inner()).await {
+        Ok(Some(rule)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Automation rule not found"}))),
+        Err(e) => {
+            error!("Failed to load automation rule: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load automation rule"})))
+        }
+    }
+}
+
+/// Replace a rule's trigger, conditions and actions
+async fn update_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<AutomationRuleRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    match automations::update_rule(&conn, &path.into_inner(), &payload).await {
+        Ok(Some(rule)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Automation rule not found"}))),
+        Err(e) => {
+            error!("Failed to update automation rule: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update automation rule"})))
+        }
+    }
+}
+
+async fn delete_rule(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match automations::delete_rule(&conn, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Automation rule not found"}))),
+        Err(e) => {
+            error!("Failed to delete automation rule: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to delete automation rule"})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RunsQuery {
+    limit: Option<u32>,
+}
+
+/// Recent executions of a rule, newest first
+async fn list_runs(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<RunsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let limit = query.limit.unwrap_or(DEFAULT_RUN_LIMIT).clamp(1, 500);
+    match automations::list_runs(&conn, &path.into_inner(), limit).await {
+        Ok(runs) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": runs}))),
+        Err(e) => {
+            error!("Failed to list automation runs: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list automation runs"})))
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::{Duration, Utc};
+use libsql::Connection;
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::analytics_engine::drawdown_sizing::{self, SizingRule};
+use crate::models::stock::stocks::TimeRange;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::notifications::drawdown_alert::{self, DrawdownAlertRule};
+use crate::service::notifications::loss_streak::{self, LossStreakRule};
+use crate::service::notifications::overtrading::{self, OvertradingRule};
+use crate::turso::{AppState, client::TursoClient};
+use crate::websocket::ConnectionManager;
+
+async fn user_conn(app: &web::Data<AppState>, req: &HttpRequest) -> actix_web::Result<Connection> {
+    let user_id = get_user_id_from_ext(req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    app.turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))
+}
+
+pub fn configure_behavior_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/behavior")
+        .route("/loss-streak", web::get().to(get_loss_streak_rule))
+        .route("/loss-streak", web::put().to(update_loss_streak_rule))
+        .route("/loss-streak/events", web::get().to(list_loss_streak_events))
+        .route("/drawdown-alerts", web::get().to(get_drawdown_alert_rule))
+        .route("/drawdown-alerts", web::put().to(update_drawdown_alert_rule))
+        .route("/drawdown-alerts/current", web::get().to(get_current_drawdown))
+        .route("/drawdown-alerts/events", web::get().to(list_drawdown_events))
+        .route("/overtrading", web::get().to(get_overtrading_rule))
+        .route("/overtrading", web::put().to(update_overtrading_rule))
+        .route("/overtrading/counter", web::get().to(get_trade_counter))
+        .route("/overtrading/events", web::get().to(list_overtrading_events))
+        .route("/overtrading/stats", web::get().to(get_overtrading_stats))
+        .route("/sizing", web::get().to(get_sizing_rule))
+        .route("/sizing", web::put().to(update_sizing_rule))
+        .route("/sizing/recommendation", web::get().to(get_sizing_recommendation))
+}
+
+/// Consecutive-loss circuit breaker setting
+async fn get_loss_streak_rule(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let rule = LossStreakRule::load(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+async fn update_loss_streak_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<LossStreakRule>,
+) -> actix_web::Result<HttpResponse> {
+    let rule = payload.into_inner();
+    if let Err(message) = rule.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    rule.save(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+#[derive(Debug, Deserialize)]
+struct EventsQuery {
+    /// Look-back window, defaults to 30 days
+    days: Option<i64>,
+}
+
+/// Days the circuit breaker tripped, newest first
+async fn list_loss_streak_events(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<EventsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let since = (Utc::now() - Duration::days(query.days.unwrap_or(30).clamp(1, 365))).format("%Y-%m-%d").to_string();
+    let events = loss_streak::list_events(&conn, &since).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": events})))
+}
+
+/// Daily trade limit setting
+async fn get_overtrading_rule(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let rule = OvertradingRule::load(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+async fn update_overtrading_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<OvertradingRule>,
+) -> actix_web::Result<HttpResponse> {
+    let rule = payload.into_inner();
+    if let Err(message) = rule.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    rule.save(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+/// Trades opened so far today; the websocket pushes the same shape as trades come in
+async fn get_trade_counter(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let counter = overtrading::intraday_counter(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": counter})))
+}
+
+/// Days the trade limit was exceeded, newest first
+async fn list_overtrading_events(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<EventsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let since = (Utc::now() - Duration::days(query.days.unwrap_or(30).clamp(1, 365))).format("%Y-%m-%d").to_string();
+    let events = overtrading::list_events(&conn, &since).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": events})))
+}
+
+#[derive(Debug, Deserialize)]
+struct StatsQuery {
+    /// Analytics time range, defaults to all time
+    time_range: Option<String>,
+}
+
+/// Breach statistics against the current limit; `data` is null when no limit is set
+async fn get_overtrading_stats(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<StatsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let time_range = query.time_range.as_deref().and_then(TimeRange::parse).unwrap_or(TimeRange::AllTime);
+    let stats = overtrading::breach_stats(&conn, &time_range)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": stats})))
+}
+
+/// Drawdown alert thresholds
+async fn get_drawdown_alert_rule(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let rule = DrawdownAlertRule::load(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+async fn update_drawdown_alert_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<DrawdownAlertRule>,
+) -> actix_web::Result<HttpResponse> {
+    let rule = payload.into_inner();
+    if let Err(message) = rule.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    rule.save(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+fn market_client(app: &AppState) -> Option<MarketClient> {
+    MarketClient::new(&app.config.finance_query)
+        .map_err(|e| log::warn!("Drawdown market data unavailable: {}", e))
+        .ok()
+}
+
+/// Equity, peak and drawdown right now; `data` is null until an account size is set
+async fn get_current_drawdown(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let market = market_client(&app);
+    let snapshot = drawdown_alert::current_drawdown(&conn, market.as_ref())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": snapshot})))
+}
+
+/// Drawdown threshold crossings, newest first
+async fn list_drawdown_events(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<EventsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let since = (Utc::now() - Duration::days(query.days.unwrap_or(30).clamp(1, 365))).format("%Y-%m-%d").to_string();
+    let events = drawdown_alert::list_events(&conn, &since).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": events})))
+}
+
+/// Drawdown-aware sizing setting
+async fn get_sizing_rule(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let rule = SizingRule::load(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+async fn update_sizing_rule(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<SizingRule>,
+) -> actix_web::Result<HttpResponse> {
+    let rule = payload.into_inner();
+    if let Err(message) = rule.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    rule.save(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": rule})))
+}
+
+/// Size multiplier for the current drawdown; `data` is null when sizing is off or no account size is set
+async fn get_sizing_recommendation(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let recommendation = drawdown_sizing::recommend_position_size(&conn)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": recommendation})))
+}
+
+/// Cron endpoint: check every user's equity against their drawdown thresholds and alert
+/// on new crossings
+pub async fn check_all_drawdowns(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        log::error!("Failed to list active users for drawdown checks: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let market = market_client(&app_state);
+    let (mut checked, mut alerted, mut failed) = (0u64, 0u64, 0u64);
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            failed += 1;
+            continue;
+        };
+        match drawdown_alert::check_drawdown(&conn, market.as_ref()).await {
+            Ok(Some(event)) => {
+                alerted += 1;
+                log::info!("Drawdown alert for user {}: {:.1}% past the {:.0}% threshold", user_id, event.drawdown_pct, event.threshold_pct);
+                drawdown_alert::notify_drawdown(&conn, &app_state.config.web_push, ws_manager.get_ref(), &user_id, &event).await;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                failed += 1;
+                log::warn!("Drawdown check failed for user {}: {}", user_id, e);
+                continue;
+            }
+        }
+        checked += 1;
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"checked": checked, "alerted": alerted, "failed": failed}
+    })))
+}
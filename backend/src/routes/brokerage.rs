@@ -1,8 +1,7 @@
 use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
 use serde::{Deserialize, Serialize};
-use reqwest::Client;
-use std::time::Duration;
 use std::sync::Arc;
+use tokio::sync::Mutex;
 use log::{info, error, warn};
 use uuid::Uuid;
 use chrono::Utc;
@@ -10,159 +9,33 @@ use libsql::Connection;
 
 use crate::turso::{
     AppState, 
+    in_transaction,
     client::TursoClient, 
     config::{SupabaseConfig, SupabaseClaims
     }};
 
+use crate::turso::locks::BROKERAGE_SYNC_JOB;
 use crate::turso::auth::{
     validate_supabase_jwt_token, 
     get_supabase_user_id
      };
      
-use crate::service::transform;
+use crate::service::brokerage::transform::{self, normalize};
+use crate::service::brokerage::webhooks::{self, SnapTradeWebhook, SnapTradeWebhookService, WebhookAction};
+use crate::service::brokerage::holdings;
+use crate::service::notifications::overtrading::spawn_trade_counter_update;
+use crate::websocket::ConnectionManager;
+use crate::service::brokerage::sync::{
+    self as brokerage_sync, get_existing_account_id, get_existing_holding_id, transaction_exists,
+    BrokerageSyncService, SyncAccountsResponse, SyncFrequency,
+};
+pub use crate::service::brokerage::client::SnapTradeClient;
+use crate::middleware::cron_auth::secrets_match;
 use crate::models::stock::stocks::{Stock, CreateStockRequest, TradeType, OrderType};
 use crate::models::options::option_trade::{OptionTrade, CreateOptionRequest, TradeDirection, OptionType};
-
-/// HTTP client for communicating with Go SnapTrade microservice
-#[derive(Clone)]
-pub struct SnapTradeClient {
-    base_url: String,
-    http: Client,
-}
-
-impl SnapTradeClient {
-    pub fn new(base_url: String) -> anyhow::Result<Self> {
-        let http = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .build()?;
-        
-        Ok(Self { base_url, http })
-    }
-
-    pub async fn call_go_service<T: Serialize>(
-        &self,
-        method: &str,
-        path: &str,
-        body: Option<&T>,
-        user_id: &str,
-        user_secret: Option<&str>,
-    ) -> anyhow::Result<reqwest::Response> {
-        let url = format!("{}{}", self.base_url, path);
-        let mut req = match method {
-            "GET" => self.http.get(&url),
-            "POST" => self.http.post(&url),
-            "PUT" => self.http.put(&url),
-            "DELETE" => self.http.delete(&url),
-            _ => return Err(anyhow::anyhow!("Unsupported method")),
-        };
-
-        req = req.header("X-User-Id", user_id);
-        req = req.header("Content-Type", "application/json");
-        
-        if let Some(secret) = user_secret {
-            req = req.header("X-User-Secret", secret);
-        }
-        
-        if let Some(b) = body {
-            req = req.json(b);
-        }
-
-        let resp = req.send().await?;
-        Ok(resp)
-    }
-}
-
-/// Helper function to get existing account ID by snaptrade_account_id
-async fn get_existing_account_id(
-    conn: &Connection,
-    connection_id: &str,
-    snaptrade_account_id: &str,
-) -> Option<String> {
-    let stmt = match conn
-        .prepare("SELECT id FROM brokerage_accounts WHERE connection_id = ? AND snaptrade_account_id = ?")
-        .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to prepare account query: {}", e);
-            return None;
-        }
-    };
-
-    let mut rows = match stmt.query(libsql::params![connection_id, snaptrade_account_id]).await {
-        Ok(r) => r,
-        Err(e) => {
-            error!("Failed to query account: {}", e);
-            return None;
-        }
-    };
-
-    if let Ok(Some(row)) = rows.next().await
-        && let Ok(id) = row.get::<String>(0) {
-            return Some(id);
-    }
-    None
-}
-
-/// Helper function to get existing holding ID by account_id and symbol
-async fn get_existing_holding_id(
-    conn: &Connection,
-    account_id: &str,
-    symbol: &str,
-) -> Option<String> {
-    let stmt = match conn
-        .prepare("SELECT id FROM brokerage_holdings WHERE account_id = ? AND symbol = ?")
-        .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to prepare holding query: {}", e);
-            return None;
-        }
-    };
-
-    let mut rows = match stmt.query(libsql::params![account_id, symbol]).await {
-        Ok(r) => r,
-        Err(e) => {
-            error!("Failed to query holding: {}", e);
-            return None;
-        }
-    };
-
-    if let Ok(Some(row)) = rows.next().await
-        && let Ok(id) = row.get::<String>(0) {
-            return Some(id);
-    }
-    None
-}
-
-/// Helper function to check if transaction already exists
-async fn transaction_exists(
-    conn: &Connection,
-    account_id: &str,
-    snaptrade_transaction_id: &str,
-) -> bool {
-    let stmt = match conn
-        .prepare("SELECT 1 FROM brokerage_transactions WHERE account_id = ? AND snaptrade_transaction_id = ? LIMIT 1")
-        .await
-    {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to prepare transaction check query: {}", e);
-            return false;
-        }
-    };
-
-    let mut rows = match stmt.query(libsql::params![account_id, snaptrade_transaction_id]).await {
-        Ok(r) => r,
-        Err(e) => {
-            error!("Failed to query transaction: {}", e);
-            return false;
-        }
-    };
-
-    matches!(rows.next().await, Ok(Some(_)))
-}
+use crate::models::money::{self, Decimal};
+use crate::models::timestamps;
+use crate::models::venue;
 
 /// Response wrapper
 #[derive(Debug, Serialize)]
@@ -205,19 +78,14 @@ pub struct ConnectBrokerageResponse {
     pub connection_id: String,
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct SyncAccountsResponse {
-    pub accounts: Vec<serde_json::Value>,
-    pub holdings: Vec<serde_json::Value>,
-    pub transactions: Vec<serde_json::Value>,
-}
-
 #[derive(Serialize)]
 pub struct SyncSummary {
     pub accounts_synced: usize,
     pub holdings_synced: usize,
     pub transactions_synced: usize,
     pub last_sync_at: String,
+    /// Connections skipped because the broker needs the user to log in again
+    pub reauth_required: Vec<serde_json::Value>,
 }
 
 /// Helper: Extract and validate auth from request
@@ -716,7 +584,7 @@ pub async fn list_connections(
     let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
 
     let rows = conn
-        .prepare("SELECT id, connection_id, brokerage_name, status, last_sync_at, created_at, updated_at FROM brokerage_connections WHERE user_id = ? ORDER BY created_at DESC")
+        .prepare("SELECT id, connection_id, brokerage_name, status, last_sync_at, created_at, updated_at, status_reason, reauth_required FROM brokerage_connections WHERE user_id = ? ORDER BY created_at DESC")
         .await
         .map_err(|e| {
             error!("Database error: {}", e);
@@ -771,11 +639,16 @@ pub async fn list_connections(
                 actix_web::error::ErrorInternalServerError("Database get error")
             })?;
 
+        let status_reason: Option<String> = row.get(7).unwrap_or(None);
+        let reauth_required = row.get::<i64>(8).unwrap_or(0) != 0;
+
         connections.push(serde_json::json!({
             "id": id,
             "connection_id": connection_id,
             "brokerage_name": brokerage_name,
             "status": status,
+            "status_reason": status_reason,
+            "reauth_required": reauth_required,
             "last_sync_at": last_sync_at,
             "created_at": created_at,
             "updated_at": updated_at
@@ -854,11 +727,20 @@ pub async fn delete_connection(
     }
 
     // Delete from database (cascade will handle accounts, transactions, holdings)
-    let connection_id_clone = connection_id.clone();
-    conn.execute(
-        "DELETE FROM brokerage_connections WHERE id = ? AND user_id = ?",
-        libsql::params![connection_id_clone, user_id],
-    ).await.map_err(|e| {
+    in_transaction(&conn, async |tx| {
+        tx.execute(
+            "DELETE FROM brokerage_connections WHERE id = ? AND user_id = ?",
+            libsql::params![connection_id.as_str(), user_id.as_str()],
+        ).await?;
+        // Sync history has no foreign key to cascade from
+        tx.execute(
+            "DELETE FROM brokerage_sync_runs WHERE connection_id = ?",
+            libsql::params![connection_id.as_str()],
+        ).await?;
+        Ok::<_, libsql::Error>(())
+    })
+    .await
+    .map_err(|e| {
         error!("Failed to delete connection: {}", e);
         actix_web::error::ErrorInternalServerError("Database error")
     })?;
@@ -1054,6 +936,9 @@ pub async fn get_account_detail(
     Ok(HttpResponse::Ok().json(ApiResponse::success(account_detail)))
 }
 
+/// Long enough for a slow broker; the lock expires on its own if an instance dies mid-sync
+const BROKERAGE_SYNC_LOCK_TTL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
 /// Route: Sync accounts
 pub async fn sync_accounts(
     req: HttpRequest,
@@ -1067,243 +952,40 @@ pub async fn sync_accounts(
 
     let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
 
-    // Get all connections for user
-    let rows = conn
-        .prepare("SELECT id, snaptrade_user_secret FROM brokerage_connections WHERE user_id = ? AND status = 'connected'")
-        .await
-        .map_err(|e| {
-            error!("Database error: {}", e);
-            actix_web::error::ErrorInternalServerError("Database error")
-        })?;
-
-    let user_id_clone = user_id.clone();
-    let mut result = rows.query(libsql::params![user_id_clone]).await
-        .map_err(|e| {
-            error!("Database query error: {}", e);
-            actix_web::error::ErrorInternalServerError("Database query error")
-        })?;
+    // A scheduled run or another instance may be syncing this user already
+    let Some(lock) = app_state.turso_client.locks().try_acquire(BROKERAGE_SYNC_JOB, &user_id, BROKERAGE_SYNC_LOCK_TTL).await else {
+        return Ok(HttpResponse::Conflict().json(ApiResponse::<()>::error("A sync is already running for this account")));
+    };
 
     let snaptrade_client = SnapTradeClient::new(app_state.config.snaptrade_service_url.clone())
         .map_err(|e| {
             error!("Failed to create SnapTrade client: {}", e);
             actix_web::error::ErrorInternalServerError("Service configuration error")
         })?;
+    let sync_service = BrokerageSyncService::new(&conn, &snaptrade_client);
+
+    // Get all connections for user
+    let targets = sync_service.connected_targets(&user_id).await.map_err(|e| {
+        error!("Database error: {}", e);
+        actix_web::error::ErrorInternalServerError("Database error")
+    })?;
 
     let mut total_accounts = 0;
     let mut total_holdings = 0;
     let mut total_transactions = 0;
 
-    while let Some(row) = result.next().await
-        .map_err(|e| {
-            error!("Database row error: {}", e);
-            actix_web::error::ErrorInternalServerError("Database row error")
-        })? {
-        let connection_id: String = row.get(0)
-            .map_err(|e| {
-                error!("Database get error: {}", e);
-                actix_web::error::ErrorInternalServerError("Database get error")
-            })?;
-        let user_secret: String = row.get(1)
-            .map_err(|e| {
-                error!("Database get error: {}", e);
-                actix_web::error::ErrorInternalServerError("Database get error")
-            })?;
-
-        // Call Go service to sync
-        let sync_req = serde_json::json!({
-            "user_secret": user_secret
-        });
-
-        let user_id_clone = user_id.clone();
-        let response = match snaptrade_client
-            .call_go_service("POST", "/api/v1/accounts/sync", Some(&sync_req), &user_id_clone, None)
-            .await
-        {
-            Ok(resp) => resp,
-            Err(e) => {
-                error!("Failed to sync accounts for connection {}: {}", connection_id, e);
-                continue; // Continue with other connections
+    for target in &targets {
+        match sync_service.sync(&user_id, target, "manual").await {
+            Ok(counts) => {
+                total_accounts += counts.accounts_synced;
+                total_holdings += counts.holdings_synced;
+                total_transactions += counts.transactions_imported;
             }
-        };
-
-        if !response.status().is_success() {
-            error!("Sync failed for connection {}", connection_id);
-            continue; // Continue with other connections
-        }
-
-        let sync_data: SyncAccountsResponse = match response.json().await {
-            Ok(data) => data,
             Err(e) => {
-                error!("Failed to parse sync response for connection {}: {}", connection_id, e);
+                error!("Failed to sync accounts for connection {}: {}", target.id, e);
                 continue; // Continue with other connections
             }
-        };
-
-        // Store synced data in database
-        // This is a simplified version - in production, you'd want more robust error handling
-        for account in sync_data.accounts {
-            if account.get("id").is_some() {
-                let snaptrade_account_id = account.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                let account_number = account.get("account_number").and_then(|v| v.as_str());
-                let account_name = account.get("name").and_then(|v| v.as_str());
-                let account_type = account.get("type").and_then(|v| v.as_str());
-                let balance = account.get("balance").and_then(|v| v.as_f64());
-                let currency = account.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
-                let institution_name = account.get("institution_name").and_then(|v| v.as_str());
-
-                // Check if account already exists to prevent duplicates
-                let connection_id_clone = connection_id.clone();
-                let account_uuid = match get_existing_account_id(&conn, &connection_id_clone, snaptrade_account_id).await {
-                    Some(existing_id) => {
-                        info!("Account {} already exists, updating: {}", snaptrade_account_id, existing_id);
-                        existing_id
-                    },
-                    None => {
-                        let new_id = Uuid::new_v4().to_string();
-                        info!("Creating new account: {} with ID: {}", snaptrade_account_id, new_id);
-                        new_id
-                    }
-                };
-
-                let now = Utc::now().to_rfc3339();
-                let raw_data = serde_json::to_string(&account).unwrap_or_default();
-
-                conn.execute(
-                    "INSERT OR REPLACE INTO brokerage_accounts (id, connection_id, snaptrade_account_id, account_number, account_name, account_type, balance, currency, institution_name, raw_data, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE((SELECT created_at FROM brokerage_accounts WHERE id = ?), ?), ?)",
-                    libsql::params![
-                        account_uuid.clone(),
-                        connection_id_clone,
-                        snaptrade_account_id,
-                        account_number,
-                        account_name,
-                        account_type,
-                        balance,
-                        currency,
-                        institution_name,
-                        raw_data,
-                        account_uuid.clone(),
-                        now.clone(),
-                        now
-                    ],
-                ).await.ok(); // Don't fail entire sync if one account fails
-
-                total_accounts += 1;
-
-                // Store holdings for this account
-                for holding in &sync_data.holdings {
-                    if let Some(holding_account_id) = holding.get("account_id").and_then(|v| v.as_str())
-                        && holding_account_id == snaptrade_account_id {
-                            let symbol = holding.get("symbol").and_then(|v| v.as_str()).unwrap_or("");
-                            if symbol.is_empty() {
-                                continue; // Skip holdings without symbols
-                            }
-                            
-                            let quantity = holding.get("quantity").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                            let average_cost = holding.get("average_cost").and_then(|v| v.as_f64());
-                            let current_price = holding.get("current_price").and_then(|v| v.as_f64());
-                            let market_value = holding.get("market_value").and_then(|v| v.as_f64());
-                            let currency = holding.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
-                            let raw_data = serde_json::to_string(holding).unwrap_or_default();
-
-                            // Check if holding already exists to prevent duplicates
-                            let holding_uuid = match get_existing_holding_id(&conn, &account_uuid, symbol).await {
-                                Some(existing_id) => {
-                                    info!("Holding {} for account {} already exists, updating: {}", symbol, account_uuid, existing_id);
-                                    existing_id
-                                },
-                                None => {
-                                    let new_id = Uuid::new_v4().to_string();
-                                    info!("Creating new holding: {} for account: {}", symbol, account_uuid);
-                                    new_id
-                                }
-                            };
-
-                            let now = Utc::now().to_rfc3339();
-
-                            conn.execute(
-                                "INSERT OR REPLACE INTO brokerage_holdings (id, account_id, symbol, quantity, average_cost, current_price, market_value, currency, last_updated, raw_data) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                                libsql::params![
-                                    holding_uuid,
-                                    account_uuid.clone(),
-                                    symbol,
-                                    quantity,
-                                    average_cost,
-                                    current_price,
-                                    market_value,
-                                    currency,
-                                    now,
-                                    raw_data
-                                ],
-                            ).await.ok();
-
-                            total_holdings += 1;
-                    }
-                }
-
-                // Store transactions for this account
-                for transaction in &sync_data.transactions {
-                    if let Some(trans_account_id) = transaction.get("account_id").and_then(|v| v.as_str())
-                        && trans_account_id == snaptrade_account_id {
-                            let snaptrade_transaction_id = transaction.get("id").and_then(|v| v.as_str()).unwrap_or("");
-                            if snaptrade_transaction_id.is_empty() {
-                                continue; // Skip transactions without IDs
-                            }
-
-                            // Check if transaction already exists to prevent duplicates
-                            if transaction_exists(&conn, &account_uuid, snaptrade_transaction_id).await {
-                                info!("Transaction {} for account {} already exists, skipping", snaptrade_transaction_id, account_uuid);
-                                continue;
-                            }
-
-                            let symbol = transaction.get("symbol").and_then(|v| v.as_str());
-                            let transaction_type = transaction.get("type").and_then(|v| v.as_str());
-                            let quantity = transaction.get("quantity").and_then(|v| v.as_f64());
-                            let price = transaction.get("price").and_then(|v| v.as_f64());
-                            let amount = transaction.get("amount").and_then(|v| v.as_f64());
-                            let currency = transaction.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
-                            let default_trade_date = Utc::now().to_rfc3339();
-                            let trade_date = transaction.get("date").and_then(|v| v.as_str()).unwrap_or(&default_trade_date);
-                            let settlement_date = transaction.get("settlement_date").and_then(|v| v.as_str());
-                            let fees = transaction.get("fees").and_then(|v| v.as_f64());
-                            let raw_data = serde_json::to_string(transaction).unwrap_or_default();
-
-                            let transaction_uuid = Uuid::new_v4().to_string();
-                            let transaction_now = Utc::now().to_rfc3339();
-
-                            conn.execute(
-                                "INSERT INTO brokerage_transactions (id, account_id, snaptrade_transaction_id, symbol, transaction_type, quantity, price, amount, currency, trade_date, settlement_date, fees, raw_data, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-                                libsql::params![
-                                    transaction_uuid,
-                                    account_uuid.clone(),
-                                    snaptrade_transaction_id,
-                                    symbol,
-                                    transaction_type,
-                                    quantity,
-                                    price,
-                                    amount,
-                                    currency,
-                                    trade_date,
-                                    settlement_date,
-                                    fees,
-                                    raw_data,
-                                    transaction_now.clone(),
-                                    transaction_now
-                                ],
-                            ).await.ok();
-
-                            total_transactions += 1;
-                    }
-                }
-            }
         }
-
-        // Update last_sync_at
-        let sync_now = Utc::now().to_rfc3339();
-        let connection_id_clone = connection_id.clone();
-        conn.execute(
-            "UPDATE brokerage_connections SET last_sync_at = ?, updated_at = ? WHERE id = ?",
-            libsql::params![sync_now.clone(), sync_now, connection_id_clone],
-        ).await.ok();
     }
 
     // Transform brokerage transactions to stocks/options trades
@@ -1319,12 +1001,26 @@ pub async fn sync_accounts(
         // Don't fail the entire sync if transformation fails
     }
 
+    // Broken connections aren't synced; tell the caller which ones need reconnecting
+    let reauth_required = SnapTradeWebhookService::new(&conn)
+        .connections_needing_reauth(&user_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load connections needing re-authentication: {}", e);
+            Vec::new()
+        })
+        .into_iter()
+        .map(|(id, brokerage_name)| serde_json::json!({"id": id, "brokerage_name": brokerage_name}))
+        .collect();
+
     let summary = SyncSummary {
         accounts_synced: total_accounts,
         holdings_synced: total_holdings,
         transactions_synced: total_transactions,
         last_sync_at: Utc::now().to_rfc3339(),
+        reauth_required,
     };
+    lock.release().await.ok();
 
     Ok(HttpResponse::Ok().json(ApiResponse::success(summary)))
 }
@@ -1458,6 +1154,12 @@ pub async fn get_transactions(
                 actix_web::error::ErrorInternalServerError("Database get error")
             })?;
 
+        // Broker-neutral view of the activity; None for types we don't classify yet
+        let normalized = raw_data
+            .as_deref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw).ok())
+            .and_then(|raw| normalize::normalize_activity(&raw, None).ok());
+
         transactions.push(serde_json::json!({
             "id": id,
             "account_id": account_id,
@@ -1473,6 +1175,7 @@ pub async fn get_transactions(
             "fees": fees,
             "created_at": created_at,
             "is_transformed": is_transformed.map(|v| v != 0),
+            "normalized": normalized,
             "raw_data": raw_data
         }));
     }
@@ -2480,6 +2183,7 @@ async fn resolve_unmatched_transaction(
     path: web::Path<String>,
     body: web::Json<ResolveUnmatchedRequest>,
     app_state: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
 ) -> ActixResult<HttpResponse> {
     let claims = get_authenticated_user(&req, &app_state.config.supabase).await?;
     let user_id = get_supabase_user_id(&claims);
@@ -2553,8 +2257,6 @@ async fn resolve_unmatched_transaction(
         actix_web::error::ErrorInternalServerError("Database error")
     })?.map(|v| v != 0).unwrap_or(false);
 
-    let vectorization_service_opt = Some(app_state.vectorization_service.as_ref());
-
     let trade_id = match body.action.as_str() {
         "merge" => {
             // Merge with another transaction
@@ -2648,6 +2350,9 @@ async fn resolve_unmatched_transaction(
             let entry_date = body.entry_date
                 .as_ref()
                 .ok_or_else(|| actix_web::error::ErrorBadRequest("entry_date required for create_open action"))?;
+            let entry_date = timestamps::parse_input(entry_date)
+                .map(|dt| timestamps::to_db(&dt))
+                .map_err(actix_web::error::ErrorBadRequest)?;
 
             if trade_type != "BUY" {
                 return Err(actix_web::error::ErrorBadRequest("Only BUY transactions can be created as open positions"));
@@ -2657,33 +2362,38 @@ async fn resolve_unmatched_transaction(
                 return Err(actix_web::error::ErrorBadRequest("Option open positions not yet implemented"));
             }
 
-            let trade_id = transform::create_open_stock_trade(
-                &conn,
-                &symbol,
-                entry_price,
-                units,
-                entry_date.clone(),
-                fee,
-                brokerage_name,
-                &user_id,
-                vectorization_service_opt,
-            ).await
-            .map_err(|e| {
-                error!("Failed to create open trade: {}", e);
-                actix_web::error::ErrorInternalServerError("Failed to create trade")
-            })?;
-
-            // Mark as resolved
+            // The trade and the resolution land together, so a retry never creates a second trade
             let now = Utc::now().to_rfc3339();
-            conn.execute(
-                "UPDATE unmatched_transactions SET status = 'resolved', resolved_trade_id = ?, resolved_at = ?, updated_at = ? WHERE id = ? AND user_id = ?",
-                libsql::params![trade_id, now.clone(), now.clone(), unmatched_id, user_id],
-            ).await
+            let trade_id = in_transaction(&conn, async |tx| {
+                let trade_id = transform::create_open_stock_trade(
+                    tx,
+                    &symbol,
+                    entry_price,
+                    units,
+                    entry_date,
+                    fee,
+                    brokerage_name,
+                    &user_id,
+                    None,
+                ).await?;
+                tx.execute(
+                    "UPDATE unmatched_transactions SET status = 'resolved', resolved_trade_id = ?, resolved_at = ?, updated_at = ? WHERE id = ? AND user_id = ?",
+                    libsql::params![trade_id, now.clone(), now.clone(), unmatched_id.as_str(), user_id.as_str()],
+                ).await?;
+                anyhow::Ok(trade_id)
+            })
+            .await
             .map_err(|e| {
-                error!("Failed to update unmatched transaction: {}", e);
-                actix_web::error::ErrorInternalServerError("Database error")
+                error!("Failed to resolve unmatched transaction {}: {}", unmatched_id, e);
+                actix_web::error::ErrorInternalServerError("Failed to create trade")
             })?;
 
+            // Vectorized only once committed
+            if let Err(e) = transform::vectorize_stock_trade(&conn, trade_id, &user_id, &app_state.vectorization_service).await {
+                error!("Failed to vectorize stock {} for user {}: {}", trade_id, user_id, e);
+            }
+            spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+
             trade_id
         }
         _ => {
@@ -2885,6 +2595,8 @@ pub struct MergeTransactionsRequest {
     pub reviewed: Option<bool>,
     pub mistakes: Option<String>,
     pub brokerage_name: Option<String>,
+    /// Defaults to the exchange reported on the merged transactions
+    pub venue: Option<String>,
     // Option-specific fields
     pub strategy_type: Option<String>,
     pub trade_direction: Option<String>,
@@ -2900,6 +2612,7 @@ pub async fn merge_transactions(
     app_state: web::Data<AppState>,
     supabase_config: web::Data<SupabaseConfig>,
     payload: web::Json<MergeTransactionsRequest>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
 ) -> ActixResult<HttpResponse> {
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let user_id = get_supabase_user_id(&claims);
@@ -2951,6 +2664,7 @@ pub async fn merge_transactions(
         price: Option<f64>,
         fees: Option<f64>,
         trade_date: String,
+        venue: Option<String>,
     }
 
     let mut transactions = Vec::new();
@@ -2988,6 +2702,12 @@ pub async fn merge_transactions(
                 error!("Failed to get trade_date: {}", e);
                 actix_web::error::ErrorInternalServerError("Database error")
             })?,
+            venue: row
+                .get::<Option<String>>(8)
+                .ok()
+                .flatten()
+                .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+                .and_then(|raw| venue::venue_from_brokerage_json(&raw)),
         });
     }
 
@@ -3004,23 +2724,23 @@ pub async fn merge_transactions(
         .collect();
 
     // Calculate weighted averages
-    let calculate_weighted_avg = |txns: &[&TransactionData]| -> (f64, f64, f64) {
-        let mut total_value = 0.0;
+    let calculate_weighted_avg = |txns: &[&TransactionData]| -> (Decimal, f64, Decimal) {
+        let mut total_value = Decimal::ZERO;
         let mut total_quantity = 0.0;
-        let mut total_fees = 0.0;
+        let mut total_fees = Decimal::ZERO;
 
         for txn in txns {
             let qty = txn.quantity.unwrap_or(0.0);
-            let price = txn.price.unwrap_or(0.0);
-            total_value += price * qty;
+            let price = money::from_f64(txn.price.unwrap_or(0.0));
+            total_value += price * money::from_f64(qty);
             total_quantity += qty;
-            total_fees += txn.fees.unwrap_or(0.0);
+            total_fees += money::from_f64(txn.fees.unwrap_or(0.0));
         }
 
         let avg_price = if total_quantity > 0.0 {
-            total_value / total_quantity
+            total_value / money::from_f64(total_quantity)
         } else {
-            0.0
+            Decimal::ZERO
         };
 
         (avg_price, total_quantity, total_fees)
@@ -3038,7 +2758,7 @@ pub async fn merge_transactions(
         let (price, qty, fees) = calculate_weighted_avg(&sells);
         (Some(price), qty, fees)
     } else {
-        (None, 0.0, 0.0)
+        (None, 0.0, Decimal::ZERO)
     };
 
     // Determine dates
@@ -3059,24 +2779,14 @@ pub async fn merge_transactions(
         None
     };
 
-    // Parse dates
+    // Broker dates come from our own storage; expiration may be a bare date (midnight UTC)
     let parse_date = |date_str: &str| -> Result<chrono::DateTime<Utc>, actix_web::Error> {
-        if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(date_str) {
-            return Ok(dt.with_timezone(&Utc));
-        }
-        if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(date_str, "%Y-%m-%d %H:%M:%S") {
-            return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-        }
-        if let Ok(date) = chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-            let ndt = date.and_hms_opt(0, 0, 0)
-                .ok_or_else(|| actix_web::error::ErrorInternalServerError("Invalid date"))?;
-            return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-        }
-        Err(actix_web::error::ErrorInternalServerError("Unsupported date format"))
+        timestamps::parse_stored(date_str).map_err(actix_web::error::ErrorBadRequest)
     };
 
     let entry_date_parsed = parse_date(entry_date)?;
     let _exit_date_parsed = exit_date.map(|d| parse_date(d)).transpose()?;
+    let venue = request.venue.clone().or_else(|| transactions.iter().find_map(|t| t.venue.clone()));
 
     // Create trade based on type
     if request.trade_type == "stock" {
@@ -3088,17 +2798,18 @@ pub async fn merge_transactions(
             trade_type: TradeType::BUY,
             order_type,
             entry_price,
-            stop_loss: request.stop_loss.unwrap_or(entry_price * 0.95),
+            stop_loss: request.stop_loss.map(money::from_f64).unwrap_or(entry_price * Decimal::new(95, 2)),
             commissions: entry_fees + exit_fees,
             number_shares: entry_quantity,
-            take_profit: request.take_profit,
-            initial_target: request.initial_target,
-            profit_target: request.profit_target,
+            take_profit: money::from_f64_opt(request.take_profit),
+            initial_target: money::from_f64_opt(request.initial_target),
+            profit_target: money::from_f64_opt(request.profit_target),
             trade_ratings: request.trade_ratings,
             entry_date: entry_date_parsed,
             reviewed: request.reviewed,
             mistakes: request.mistakes,
             brokerage_name: request.brokerage_name,
+            venue,
         };
 
         match Stock::create(&conn, create_request).await {
@@ -3123,6 +2834,7 @@ pub async fn merge_transactions(
                     })?;
 
                 info!("Successfully merged {} transactions into stock trade {}", request.transaction_ids.len(), stock.id);
+                spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
                 Ok(HttpResponse::Created().json(ApiResponse::success(stock)))
             }
             Err(e) => {
@@ -3152,7 +2864,7 @@ pub async fn merge_transactions(
         let implied_volatility = request.implied_volatility.unwrap_or(0.0);
 
         let number_of_contracts = entry_quantity as i32;
-        let total_premium = entry_price * entry_quantity;
+        let total_premium = entry_price * money::from_f64(entry_quantity);
         // Status is determined in OptionTrade::create (always Open initially)
         // Exit price and date are set via updates if needed
 
@@ -3162,19 +2874,21 @@ pub async fn merge_transactions(
             trade_direction,
             number_of_contracts,
             option_type,
-            strike_price,
+            strike_price: money::from_f64(strike_price),
             expiration_date: expiration_date_parsed,
             entry_price,
             total_premium,
             commissions: entry_fees + exit_fees,
             implied_volatility,
             entry_date: entry_date_parsed,
-            initial_target: request.initial_target,
-            profit_target: request.profit_target,
+            initial_target: money::from_f64_opt(request.initial_target),
+            profit_target: money::from_f64_opt(request.profit_target),
             trade_ratings: request.trade_ratings,
             reviewed: request.reviewed,
             mistakes: request.mistakes,
             brokerage_name: request.brokerage_name,
+            venue,
+            short_premium: None,
         };
 
         match OptionTrade::create(&conn, create_request).await {
@@ -3199,6 +2913,17 @@ pub async fn merge_transactions(
                     })?;
 
                 info!("Successfully merged {} transactions into option trade {}", request.transaction_ids.len(), option.id);
+                spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+
+                // Legs of a multi-leg order are merged one at a time; group them once all are in
+                match crate::service::option_strategies::recognize(&conn).await {
+                    Ok(recognized) if !recognized.is_empty() => {
+                        info!("Grouped imported legs into {} option strategies for user {}", recognized.len(), user_id);
+                        app_state.cache_service.invalidate_table_cache(&user_id, "options").await.ok();
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("Option strategy recognition failed for user {}: {}", user_id, e),
+                }
                 Ok(HttpResponse::Created().json(ApiResponse::success(option)))
             }
             Err(e) => {
@@ -3211,6 +2936,239 @@ pub async fn merge_transactions(
     }
 }
 
+#[derive(Deserialize)]
+pub struct SyncSettingsRequest {
+    pub sync_frequency: SyncFrequency,
+}
+
+#[derive(Deserialize)]
+pub struct SyncHistoryQuery {
+    pub connection_id: Option<String>,
+    pub limit: Option<i64>,
+}
+
+/// Route: Set how often a connection syncs automatically (hourly, daily or manual)
+pub async fn update_sync_settings(
+    req: HttpRequest,
+    path: web::Path<String>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    body: web::Json<SyncSettingsRequest>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let user_id = get_supabase_user_id(&claims);
+    let connection_id = path.into_inner();
+
+    let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
+
+    let updated = brokerage_sync::update_sync_frequency(&conn, &user_id, &connection_id, body.sync_frequency)
+        .await
+        .map_err(|e| {
+            error!("Failed to update sync settings: {}", e);
+            actix_web::error::ErrorInternalServerError("Database error")
+        })?;
+    if !updated {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Connection not found")));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"id": connection_id, "sync_frequency": body.sync_frequency}
+    })))
+}
+
+/// Route: Recent sync runs with rows imported per run
+pub async fn get_sync_history(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    query: web::Query<SyncHistoryQuery>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let user_id = get_supabase_user_id(&claims);
+
+    let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let runs = brokerage_sync::list_sync_runs(&conn, query.connection_id.as_deref(), limit)
+        .await
+        .map_err(|e| {
+            error!("Failed to load sync history: {}", e);
+            actix_web::error::ErrorInternalServerError("Database error")
+        })?;
+
+    Ok(HttpResponse::Ok().json(ApiResponse::success(runs)))
+}
+
+/// Cron: Sync every connection whose scheduled run is due, across all users.
+/// Schedule as often as the shortest cadence (hourly).
+pub async fn run_due_brokerage_syncs(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    crate::middleware::cron_auth::verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = app_state.turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for brokerage sync: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let snaptrade_client = SnapTradeClient::new(app_state.config.snaptrade_service_url.clone())
+        .map_err(|e| {
+            error!("Failed to create SnapTrade client: {}", e);
+            actix_web::error::ErrorInternalServerError("Service configuration error")
+        })?;
+
+    let mut synced = 0u64;
+    let mut failed = 0u64;
+    let mut skipped_locked = 0u64;
+    let mut transactions_imported = 0usize;
+
+    for user_id in user_ids {
+        let conn = match app_state.turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        // Another instance (or a manual sync) has this user; its runs stay due for the next pass
+        let Some(lock) = app_state.turso_client.locks().try_acquire(BROKERAGE_SYNC_JOB, &user_id, BROKERAGE_SYNC_LOCK_TTL).await else {
+            skipped_locked += 1;
+            continue;
+        };
+        let sync_service = BrokerageSyncService::new(&conn, &snaptrade_client);
+        let targets = match sync_service.due_targets(&user_id, Utc::now()).await {
+            Ok(targets) => targets,
+            Err(e) => {
+                error!("Failed to load due brokerage syncs for user {}: {}", user_id, e);
+                continue;
+            }
+        };
+
+        let mut imported = 0;
+        for target in &targets {
+            match sync_service.sync(&user_id, target, "scheduled").await {
+                Ok(counts) => {
+                    synced += 1;
+                    imported += counts.transactions_imported;
+                }
+                Err(_) => failed += 1,
+            }
+        }
+
+        if imported > 0
+            && let Err(e) = transform::transform_brokerage_transactions(&conn, &user_id, Some(app_state.vectorization_service.clone())).await {
+                error!("Failed to transform brokerage transactions for user {}: {}", user_id, e);
+        }
+        transactions_imported += imported;
+        lock.release().await.ok();
+    }
+
+    let summary = serde_json::json!({"synced": synced, "failed": failed, "skipped_locked": skipped_locked, "transactions_imported": transactions_imported});
+    info!("Scheduled brokerage sync completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
+
+#[derive(Deserialize)]
+pub struct CreateMissingEntriesRequest {
+    /// Only create entries for these symbols; all broker-only holdings when omitted
+    pub symbols: Option<Vec<String>>,
+}
+
+/// Route: Compare synced broker holdings with open journal positions
+pub async fn get_reconciliation(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let user_id = get_supabase_user_id(&claims);
+
+    let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
+
+    let items = holdings::reconcile_positions(&conn, &user_id).await.map_err(|e| {
+        error!("Failed to reconcile positions: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to reconcile positions")
+    })?;
+
+    let discrepancies = items.iter().filter(|i| i.status != holdings::ReconciliationStatus::Matched).count();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"items": items, "discrepancies": discrepancies}
+    })))
+}
+
+/// Route: Open journal trades for holdings the broker reports but the journal doesn't
+pub async fn create_missing_entries(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    body: Option<web::Json<CreateMissingEntriesRequest>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let user_id = get_supabase_user_id(&claims);
+
+    let conn = get_user_db_connection(&user_id, &app_state.turso_client).await?;
+
+    let symbols = body.and_then(|b| b.into_inner().symbols);
+    let created = holdings::create_missing_entries(&conn, &user_id, symbols.as_deref()).await.map_err(|e| {
+        error!("Failed to create missing journal entries: {}", e);
+        actix_web::error::ErrorInternalServerError("Failed to create missing journal entries")
+    })?;
+
+    info!("Created {} journal entries from broker holdings for user {}", created.len(), user_id);
+    if !created.is_empty() {
+        spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+    }
+    Ok(HttpResponse::Created().json(ApiResponse::success(created)))
+}
+
+/// Public webhook SnapTrade posts connection and account events to. The shared secret in
+/// the body authenticates the call; the user id routes it to that user's database.
+pub async fn snaptrade_webhook(
+    app_state: web::Data<AppState>,
+    body: web::Json<SnapTradeWebhook>,
+) -> ActixResult<HttpResponse> {
+    let Some(expected) = app_state.config.snaptrade_webhook_secret.as_deref() else {
+        warn!("Rejected SnapTrade webhook: SNAPTRADE_WEBHOOK_SECRET is not configured");
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let event = body.into_inner();
+    if !secrets_match(event.webhook_secret.as_deref().unwrap_or(""), expected) {
+        warn!("Rejected SnapTrade webhook with invalid secret");
+        return Err(actix_web::error::ErrorUnauthorized("Invalid webhook secret"));
+    }
+
+    info!("Received SnapTrade webhook {} for user {}", event.event_type, event.user_id);
+
+    // Unknown users and unmatched connections are acknowledged so SnapTrade doesn't retry
+    let conn = match app_state.turso_client.get_user_database_connection(&event.user_id).await {
+        Ok(Some(conn)) => conn,
+        Ok(None) => {
+            warn!("SnapTrade webhook for unknown user {}", event.user_id);
+            return Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"applied": false}})));
+        }
+        Err(e) => {
+            error!("Error getting user database connection: {}", e);
+            return Err(actix_web::error::ErrorInternalServerError("Database access error"));
+        }
+    };
+
+    let updated = SnapTradeWebhookService::new(&conn).apply(&event).await.map_err(|e| {
+        error!("Failed to apply SnapTrade webhook {}: {}", event.event_type, e);
+        actix_web::error::ErrorInternalServerError("Failed to apply webhook")
+    })?;
+
+    if let Some((connection_id, brokerage_name)) = &updated
+        && WebhookAction::from_event(&event.event_type) == WebhookAction::Broken {
+            webhooks::notify_reauth_required(&conn, &app_state.config.web_push, &event.user_id, connection_id, brokerage_name).await;
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"applied": updated.is_some(), "connection_id": updated.map(|(id, _)| id)}
+    })))
+}
+
 /// Configure brokerage routes
 pub fn configure_brokerage_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
@@ -3220,6 +3178,10 @@ pub fn configure_brokerage_routes(cfg: &mut web::ServiceConfig) {
             .route("/connections/{id}/status", web::get().to(get_connection_status))
             .route("/connections/{id}/complete", web::post().to(complete_connection_sync))
             .route("/connections/{id}", web::delete().to(delete_connection))
+            .route("/connections/{id}/sync-settings", web::put().to(update_sync_settings))
+            .route("/sync/history", web::get().to(get_sync_history))
+            // Cron: scheduled sync (secured with cron secret)
+            .route("/sync/run-due", web::post().to(run_due_brokerage_syncs))
             .route("/accounts", web::get().to(list_accounts))
             .route("/accounts/{id}/detail", web::get().to(get_account_detail))
             .route("/accounts/{id}/positions", web::get().to(get_account_positions))
@@ -3228,6 +3190,8 @@ pub fn configure_brokerage_routes(cfg: &mut web::ServiceConfig) {
             .route("/accounts/sync", web::post().to(sync_accounts))
             .route("/transactions", web::get().to(get_transactions))
             .route("/holdings", web::get().to(get_holdings))
+            .route("/reconciliation", web::get().to(get_reconciliation))
+            .route("/reconciliation/create-missing", web::post().to(create_missing_entries))
             .route("/unmatched-transactions", web::get().to(get_unmatched_transactions))
             .route("/unmatched-transactions/{id}/resolve", web::post().to(resolve_unmatched_transaction))
             .route("/unmatched-transactions/{id}/ignore", web::post().to(ignore_unmatched_transaction))
@@ -0,0 +1,38 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::error;
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::command_palette::{self, DEFAULT_LIMIT};
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct PaletteParams {
+    #[serde(default)]
+    q: String,
+    limit: Option<usize>,
+}
+
+pub fn configure_command_palette_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/command-palette").route("", web::get().to(search_palette))
+}
+
+/// Ranked trades, notes, playbooks, recent chats and settings actions for the ⌘K palette
+async fn search_palette(app: web::Data<AppState>, req: HttpRequest, query: web::Query<PaletteParams>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let today = Utc::now().date_naive();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    match command_palette::search(&conn, &app.cache_service, &user_id, &query.q, limit, today).await {
+        Ok(items) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": items}))),
+        Err(e) => {
+            error!("Command palette search failed for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Search failed"})))
+        }
+    }
+}
@@ -0,0 +1,32 @@
+// Request helpers shared by the route modules
+
+use actix_web::{HttpMessage, HttpRequest};
+use libsql::Connection;
+
+use crate::turso::config::SupabaseClaims;
+use crate::turso::AppState;
+
+/// User id from the claims the JWT validator put in the request extensions
+pub fn get_user_id_from_ext(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<SupabaseClaims>().map(|c| c.sub.clone())
+}
+
+/// Like `get_user_id_from_ext`, but a request without claims is a 401
+pub fn require_user_id(req: &HttpRequest) -> actix_web::Result<String> {
+    get_user_id_from_ext(req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))
+}
+
+/// Connection to a user's database; 403 when the user has none yet
+pub async fn user_db_connection(app: &AppState, user_id: &str) -> actix_web::Result<Connection> {
+    app.turso_client
+        .get_user_database_connection(user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))
+}
+
+/// Connection to the requesting user's database
+pub async fn user_connection(app: &AppState, req: &HttpRequest) -> actix_web::Result<Connection> {
+    let user_id = require_user_id(req)?;
+    user_db_connection(app, &user_id).await
+}
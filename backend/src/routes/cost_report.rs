@@ -0,0 +1,65 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
+use log::error;
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::service::cost_ledger;
+use crate::turso::AppState;
+
+/// Users listed in the report by default (most expensive first)
+const DEFAULT_USER_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize)]
+pub struct CostReportQuery {
+    /// YYYY-MM; defaults to the current month
+    month: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPlanTierRequest {
+    tier: String,
+}
+
+/// Admin endpoint: vendor cost, revenue and gross margin per user and plan tier for a month
+pub async fn get_cost_report(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<CostReportQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let month = query.month.clone().unwrap_or_else(|| Utc::now().format("%Y-%m").to_string());
+    if crate::service::goal_pacing::parse_month(&month).is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "month must be YYYY-MM"})));
+    }
+    let registry = app_state.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    let report = cost_ledger::monthly_report(&registry, &month, query.limit.unwrap_or(DEFAULT_USER_LIMIT)).await.map_err(|e| {
+        error!("Failed to build cost report for {}: {}", month, e);
+        actix_web::error::ErrorInternalServerError("Failed to build cost report")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report})))
+}
+
+/// Admin endpoint: set the plan tier a user's revenue is counted under
+pub async fn set_plan_tier(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<SetPlanTierRequest>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_id = path.into_inner();
+    if body.tier.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "tier is required"})));
+    }
+    let registry = app_state.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    match cost_ledger::set_plan_tier(&registry, &user_id, &body.tier).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"user_id": user_id, "tier": body.tier.trim().to_lowercase()}}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "User not found"}))),
+        Err(e) => {
+            error!("Failed to set plan tier for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to set plan tier"})))
+        }
+    }
+}
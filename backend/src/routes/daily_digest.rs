@@ -0,0 +1,134 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::notifications::daily_digest::{self, UpdateDigestSettings};
+use crate::turso::{AppState, client::TursoClient};
+use crate::turso::locks::{DAILY_DIGEST_JOB, NIGHTLY_JOB_LOCK_TTL};
+
+pub fn configure_daily_digest_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/notifications/digest")
+        .route("/settings", web::get().to(get_settings))
+        .route("/settings", web::put().to(update_settings))
+        .route("/preview", web::get().to(preview_digest))
+}
+
+async fn get_settings(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match daily_digest::load_settings(&conn).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": settings}))),
+        Err(e) => {
+            error!("Failed to load digest settings for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load digest settings"})))
+        }
+    }
+}
+
+async fn update_settings(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<UpdateDigestSettings>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    match daily_digest::save_settings(&conn, &payload).await {
+        Ok(settings) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": settings}))),
+        Err(e) => {
+            error!("Failed to save digest settings for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to save digest settings"})))
+        }
+    }
+}
+
+/// Today's digest as it stands, whether or not the digest is enabled
+async fn preview_digest(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match daily_digest::preview(&conn, &user_id, Utc::now()).await {
+        Ok(digest) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": digest}))),
+        Err(e) => {
+            error!("Failed to build digest preview for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to build digest"})))
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DigestRunSummary {
+    sent: u64,
+    empty: u64,
+    not_due: u64,
+    skipped: u64,
+    failed: u64,
+}
+
+/// Cron endpoint, hourly: send each user's digest once their local send hour has passed
+pub async fn send_all_daily_digests(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let Some(email_config) = app_state.config.email.as_ref() else {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Email is not configured"})));
+    };
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for daily digests: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let now = Utc::now();
+    let mut summary = DigestRunSummary::default();
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            summary.failed += 1;
+            continue;
+        };
+        let Some(lock) = turso_client.locks().try_acquire(DAILY_DIGEST_JOB, &user_id, NIGHTLY_JOB_LOCK_TTL).await else {
+            summary.skipped += 1;
+            continue;
+        };
+        let due = match daily_digest::find_due(&conn, now).await {
+            Ok(Some(due)) => due,
+            Ok(None) => {
+                summary.not_due += 1;
+                lock.release().await.ok();
+                continue;
+            }
+            Err(e) => {
+                summary.failed += 1;
+                error!("Failed to check daily digest for user {}: {}", user_id, e);
+                lock.release().await.ok();
+                continue;
+            }
+        };
+        let address = turso_client.get_user_database(&user_id).await.ok().flatten().map(|e| e.email).filter(|a| !a.is_empty());
+        match address {
+            Some(address) => match daily_digest::send_due(&conn, &user_id, &due, email_config, &address).await {
+                Ok(true) => summary.sent += 1,
+                Ok(false) => summary.empty += 1,
+                Err(e) => {
+                    summary.failed += 1;
+                    error!("Daily digest failed for user {}: {}", user_id, e);
+                }
+            },
+            None => summary.skipped += 1,
+        }
+        lock.release().await.ok();
+    }
+
+    info!("Daily digests: {} sent, {} empty, {} failed", summary.sent, summary.empty, summary.failed);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
@@ -0,0 +1,39 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::Deserialize;
+
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::dashboard::{build_dashboard, DashboardWidget};
+use crate::service::market_engine::client::MarketClient;
+use crate::turso::AppState;
+
+pub fn configure_dashboard_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/api/dashboard", web::get().to(get_dashboard));
+}
+
+#[derive(Debug, Deserialize)]
+struct DashboardQuery {
+    /// Comma-separated widget names, every widget when omitted
+    widgets: Option<String>,
+}
+
+/// Home screen widgets in one request
+async fn get_dashboard(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<DashboardQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let conn = app
+        .turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))?;
+
+    let widgets = DashboardWidget::parse_list(query.widgets.as_deref());
+    let market = MarketClient::new(&app.config.finance_query)
+        .map_err(|e| log::warn!("Dashboard market data unavailable: {}", e))
+        .ok();
+    let summary = build_dashboard(&conn, market.as_ref(), &widgets).await;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
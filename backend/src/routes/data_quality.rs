@@ -0,0 +1,131 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::data_quality;
+use crate::turso::{AppState, client::TursoClient};
+use crate::turso::locks::{DATA_QUALITY_JOB, NIGHTLY_JOB_LOCK_TTL};
+
+pub fn configure_data_quality_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/data-quality")
+        .route("", web::get().to(list_issues))
+        .route("/check", web::post().to(run_check))
+        .route("/{id}/dismiss", web::post().to(dismiss_issue))
+        .route("/{id}/reopen", web::post().to(reopen_issue))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListIssuesQuery {
+    #[serde(default)]
+    include_dismissed: bool,
+}
+
+async fn list_issues(app: web::Data<AppState>, req: HttpRequest, query: web::Query<ListIssuesQuery>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match data_quality::list_issues(&conn, query.include_dismissed).await {
+        Ok(issues) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": issues}))),
+        Err(e) => {
+            error!("Failed to load data issues: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load data issues"})))
+        }
+    }
+}
+
+/// Run the checks now instead of waiting for the nightly job
+async fn run_check(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match data_quality::run_checks(&conn, Utc::now()).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report}))),
+        Err(e) => {
+            error!("Data quality check failed for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to check data"})))
+        }
+    }
+}
+
+async fn update_status(app: web::Data<AppState>, req: HttpRequest, id: String, status: &str) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match data_quality::set_status(&conn, &id, status).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Issue not found"}))),
+        Err(e) => {
+            error!("Failed to update data issue {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update issue"})))
+        }
+    }
+}
+
+async fn dismiss_issue(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    update_status(app, req, path.into_inner(), "dismissed").await
+}
+
+async fn reopen_issue(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    update_status(app, req, path.into_inner(), "open").await
+}
+
+#[derive(Debug, Default, Serialize)]
+struct DataQualityRunSummary {
+    users_checked: u64,
+    users_failed: u64,
+    /// Being checked by another instance at the same time
+    users_skipped: u64,
+    open_issues: u64,
+    new_issues: u64,
+    resolved_issues: u64,
+}
+
+/// Cron endpoint: run the integrity checks for every user
+pub async fn run_all_data_quality_checks(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for data quality checks: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let now = Utc::now();
+    let mut summary = DataQualityRunSummary::default();
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            summary.users_failed += 1;
+            continue;
+        };
+        let Some(lock) = turso_client.locks().try_acquire(DATA_QUALITY_JOB, &user_id, NIGHTLY_JOB_LOCK_TTL).await else {
+            summary.users_skipped += 1;
+            continue;
+        };
+        match data_quality::run_checks(&conn, now).await {
+            Ok(report) => {
+                summary.users_checked += 1;
+                summary.open_issues += report.open_issues as u64;
+                summary.new_issues += report.new_issues as u64;
+                summary.resolved_issues += report.resolved_issues as u64;
+            }
+            Err(e) => {
+                summary.users_failed += 1;
+                error!("Data quality check failed for user {}: {}", user_id, e);
+            }
+        }
+        lock.release().await.ok();
+    }
+
+    info!(
+        "Data quality checks: {} users, {} new issues, {} resolved, {} open",
+        summary.users_checked, summary.new_issues, summary.resolved_issues, summary.open_issues
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
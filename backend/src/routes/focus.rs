@@ -0,0 +1,155 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::{Duration, NaiveDate, Utc};
+use libsql::Connection;
+use serde::Deserialize;
+
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::focus_sessions::{self, AnnotateFocusSessionRequest, FocusSession, StartFocusSessionRequest};
+use crate::turso::AppState;
+
+async fn user_conn(app: &web::Data<AppState>, req: &HttpRequest) -> actix_web::Result<Connection> {
+    let user_id = get_user_id_from_ext(req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    app.turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))
+}
+
+pub fn configure_focus_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/focus")
+        .route("/sessions", web::get().to(list_sessions))
+        .route("/sessions", web::post().to(start_session))
+        .route("/sessions/active", web::get().to(get_active_session))
+        .route("/sessions/{id}/stop", web::post().to(stop_session))
+        .route("/sessions/{id}", web::patch().to(annotate_session))
+        .route("/sessions/{id}", web::delete().to(delete_session))
+        .route("/performance", web::get().to(get_focus_performance))
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Focus session not found"}))
+}
+
+#[derive(Debug, Deserialize)]
+struct SessionsQuery {
+    /// `YYYY-MM-DD`, defaults to 7 days ago
+    start: Option<String>,
+    /// `YYYY-MM-DD`, defaults to today
+    end: Option<String>,
+}
+
+async fn list_sessions(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<SessionsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let today = Utc::now().date_naive();
+    let parse = |value: &Option<String>, default: NaiveDate| match value {
+        Some(value) => NaiveDate::parse_from_str(value, "%Y-%m-%d").ok(),
+        None => Some(default),
+    };
+    let (Some(start), Some(end)) = (parse(&query.start, today - Duration::days(7)), parse(&query.end, today)) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "Dates must be YYYY-MM-DD"})));
+    };
+    let conn = user_conn(&app, &req).await?;
+    let sessions = FocusSession::list(&conn, &start.to_string(), &end.to_string())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": sessions})))
+}
+
+/// Start a session; only one can run at a time
+async fn start_session(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<StartFocusSessionRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let request = payload.into_inner();
+    if let Err(message) = request.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    if let Some(active) = FocusSession::find_active(&conn).await.map_err(actix_web::error::ErrorInternalServerError)? {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "message": "A focus session is already running",
+            "data": active,
+        })));
+    }
+    let session = FocusSession::start(&conn, request).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": session})))
+}
+
+/// The running session; `data` is null when none is running
+async fn get_active_session(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let session = FocusSession::find_active(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": session})))
+}
+
+async fn stop_session(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    match FocusSession::stop(&conn, &path).await.map_err(actix_web::error::ErrorInternalServerError)? {
+        Some(session) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": session}))),
+        None => Ok(not_found()),
+    }
+}
+
+/// Add notes, a 1-5 focus rating or a label to a session
+async fn annotate_session(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<AnnotateFocusSessionRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let request = payload.into_inner();
+    if let Err(message) = request.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_conn(&app, &req).await?;
+    match FocusSession::annotate(&conn, &path, request).await.map_err(actix_web::error::ErrorInternalServerError)? {
+        Some(session) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": session}))),
+        None => Ok(not_found()),
+    }
+}
+
+async fn delete_session(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    if !FocusSession::delete(&conn, &path).await.map_err(actix_web::error::ErrorInternalServerError)? {
+        return Ok(not_found());
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+#[derive(Debug, Deserialize)]
+struct PerformanceQuery {
+    /// Look-back window, defaults to 90 days
+    days: Option<i64>,
+}
+
+/// Trading results on focus days versus other days
+async fn get_focus_performance(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<PerformanceQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let conn = user_conn(&app, &req).await?;
+    let since = (Utc::now() - Duration::days(query.days.unwrap_or(90).clamp(7, 730))).format("%Y-%m-%d").to_string();
+    let performance = focus_sessions::focus_performance(&conn, &since)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": performance})))
+}
@@ -0,0 +1,83 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::error;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::goal_pacing::{self, SetMonthlyGoalRequest};
+use crate::turso::AppState;
+
+pub fn configure_goals_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/goals")
+        .route("/monthly", web::get().to(list_goals))
+        .route("/monthly", web::put().to(set_goal))
+        .route("/monthly/{month}", web::delete().to(delete_goal))
+        .route("/pacing", web::get().to(get_pacing))
+}
+
+async fn list_goals(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match goal_pacing::list_goals(&conn).await {
+        Ok(goals) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": goals}))),
+        Err(e) => {
+            error!("Failed to list monthly goals: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list monthly goals"})))
+        }
+    }
+}
+
+/// Set the goal for a month (the current one by default); later months inherit it
+async fn set_goal(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<SetMonthlyGoalRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    let month = match &payload.month {
+        Some(month) => month.clone(),
+        None => PeriodDefinition::load(&conn).await.trading_date(Utc::now()).format("%Y-%m").to_string(),
+    };
+    match goal_pacing::set_goal(&conn, &month, payload.pnl_target, payload.trade_target).await {
+        Ok(goal) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": goal}))),
+        Err(e) => {
+            error!("Failed to save monthly goal: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to save monthly goal"})))
+        }
+    }
+}
+
+async fn delete_goal(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match goal_pacing::delete_goal(&conn, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No goal set for this month"}))),
+        Err(e) => {
+            error!("Failed to delete monthly goal: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to delete monthly goal"})))
+        }
+    }
+}
+
+/// Month-to-date progress against the goal with month-end projections; data is null
+/// when no goal is set
+async fn get_pacing(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match goal_pacing::pacing(&conn, Utc::now()).await {
+        Ok(pacing) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": pacing}))),
+        Err(e) => {
+            error!("Failed to compute goal pacing: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to compute goal pacing"})))
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use serde::Deserialize;
+
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::i18n::{self, Locale, NumberFormat, SUPPORTED_LOCALES};
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+struct LabelsQuery {
+    /// Override the profile locale (e.g. previewing a language before saving it)
+    locale: Option<String>,
+    /// Comma-separated namespaces; all when omitted
+    namespaces: Option<String>,
+}
+
+pub fn configure_i18n_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/i18n")
+        .route("/labels", web::get().to(get_labels))
+        .route("/number-format", web::get().to(get_number_format))
+}
+
+/// Enum labels (trade types, insight categories, time ranges) in the user's language
+async fn get_labels(app: web::Data<AppState>, req: HttpRequest, query: web::Query<LabelsQuery>) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+
+    // Explicit query > profile setting > Accept-Language > English
+    let locale = match query.locale.as_deref() {
+        Some(value) => Locale::parse(value).ok_or_else(|| actix_web::error::ErrorBadRequest("Unsupported locale"))?,
+        None => {
+            let profile_locale = match app.turso_client.get_user_database_connection(&user_id).await {
+                Ok(Some(conn)) => Some(i18n::user_locale(&conn).await),
+                _ => None,
+            };
+            let header_locale = req.headers()
+                .get("Accept-Language")
+                .and_then(|v| v.to_str().ok())
+                .and_then(Locale::from_accept_language);
+            profile_locale.filter(|l| *l != Locale::En).or(header_locale).unwrap_or_default()
+        }
+    };
+
+    let namespaces: Vec<&str> = query.namespaces
+        .as_deref()
+        .map(|n| n.split(',').map(str::trim).filter(|n| !n.is_empty()).collect())
+        .unwrap_or_default();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {
+            "locale": locale,
+            "supported_locales": SUPPORTED_LOCALES,
+            "labels": i18n::labels_for(locale, &namespaces),
+        }
+    })))
+}
+
+/// Separators, currency symbol and foreign account currencies for rendering amounts
+async fn get_number_format(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+
+    let format = match app.turso_client.get_user_database_connection(&user_id).await {
+        Ok(Some(conn)) => i18n::user_number_format(&conn).await,
+        _ => NumberFormat::default(),
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": format,
+    })))
+}
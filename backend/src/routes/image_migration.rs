@@ -0,0 +1,39 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::error;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::service::image_migration::{ImageMigrationService, MigrateImagesRequest, UploadcareClient};
+use crate::service::image_upload::{ImageUploadService, SupabaseStorageConfig, UploadcareConfig};
+use crate::turso::AppState;
+
+/// Admin endpoint: copy one tenant's images to the target provider and rewrite references
+pub async fn migrate_tenant_images(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<MigrateImagesRequest>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_id = path.into_inner();
+
+    let uploadcare = UploadcareConfig::from_env()
+        .map(UploadcareClient::new)
+        .ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Uploadcare is not configured"))?;
+    let storage_config = SupabaseStorageConfig::from_env().map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?;
+    let supabase = ImageUploadService::new(storage_config).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let conn = app_state.turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("User database not found"))?;
+
+    let report = ImageMigrationService::new(&conn, &supabase, &uploadcare)
+        .migrate(&user_id, &body)
+        .await
+        .map_err(|e| {
+            error!("Image migration failed for user {}: {}", user_id, e);
+            actix_web::error::ErrorInternalServerError("Image migration failed")
+        })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report})))
+}
@@ -2,14 +2,15 @@ use actix_web::{web, HttpRequest, HttpResponse, Result, ResponseError};
 use actix_multipart::Multipart;
 use futures_util::TryStreamExt;
 use serde::{Deserialize, Serialize};
-use log::{info, error};
+use log::{info, error, warn};
 use std::sync::Arc;
 
 use crate::turso::{AppState, client::TursoClient};
 use crate::turso::config::{SupabaseConfig, SupabaseClaims};
 use crate::turso::auth::{validate_supabase_jwt_token, AuthError};
 use crate::models::images::{
-    Image, CreateImageRequest, UpdateImageRequest, ImageQuery
+    Image, CreateImageRequest, UpdateImageRequest, ImageQuery,
+    ImageAnnotation, SaveAnnotationRequest, SaveAnnotationOutcome, validate_layers
 };
 use crate::service::image_upload::{
     ImageUploadService, SupabaseStorageConfig
@@ -302,6 +303,12 @@ pub async fn upload_image(
     info!("File: {} ({} bytes, {})", filename, file_data.len(), content_type);
     info!("User: {}", claims.sub);
 
+    // Enforce image and database quotas before anything reaches storage
+    if let Err(e) = app_state.storage_quota_service.check_upload_quota(&claims.sub, &conn, file_data.len() as u64).await {
+        error!("Upload rejected by storage quota for user {}: {}", claims.sub, e);
+        return Ok(e.error_response());
+    }
+
     // Upload to Supabase Storage
     let stored = upload_service.upload_file(&claims.sub, &file_data, &filename, &content_type).await
         .map_err(|e| {
@@ -314,15 +321,6 @@ pub async fn upload_image(
     // We are not decoding to get dimensions here
     let (width, height) = (None, None);
 
-    // Check storage quota before creating image record (metadata stored in Turso)
-    let stored_path_for_cleanup = stored.path.clone();
-    if let Err(e) = app_state.storage_quota_service.check_storage_quota(&claims.sub, &conn).await {
-        error!("Storage quota check failed for user {}: {}", claims.sub, e);
-        // Try to delete the uploaded file from Supabase Storage since quota is exceeded
-        let _ = upload_service.delete_file(&stored_path_for_cleanup).await;
-        return Ok(e.error_response());
-    }
-
     // Create image record in database
     let create_request = CreateImageRequest {
         trade_note_id: trade_note_id.clone(),
@@ -340,6 +338,14 @@ pub async fn upload_image(
     match Image::create(&conn, create_request).await {
         Ok(image) => {
             info!("✓ Image uploaded and saved successfully: {}", image.id);
+            match app_state.storage_quota_service.get_usage_breakdown(&claims.sub, &conn).await {
+                Ok(breakdown) => {
+                    if let Err(e) = app_state.storage_quota_service.notify_quota_warnings(&claims.sub, &conn, &app_state.config.web_push, &breakdown).await {
+                        warn!("Failed to process storage warnings for user {}: {}", claims.sub, e);
+                    }
+                }
+                Err(e) => warn!("Failed to compute storage usage for user {}: {}", claims.sub, e),
+            }
             Ok(HttpResponse::Created().json(ImageResponse {
                 success: true,
                 message: "Image uploaded successfully".to_string(),
@@ -603,6 +609,10 @@ pub async fn delete_image(
     match Image::delete(&conn, &image_id).await {
         Ok(true) => {
             info!("✓ Image deleted successfully: {}", image_id);
+
+            if let Err(e) = ImageAnnotation::delete(&conn, &image_id).await {
+                warn!("Failed to delete annotation for image {}: {}", image_id, e);
+            }
             
             // Optionally delete from Supabase Storage as well
             if let Ok(storage_config) = SupabaseStorageConfig::from_env()
@@ -742,6 +752,181 @@ pub struct ImageUrlQuery {
     pub expires_in: Option<i64>,
 }
 
+/// Query parameters for the annotation changes endpoint
+#[derive(Debug, Deserialize)]
+pub struct AnnotationChangesQuery {
+    pub since_version: Option<i64>,
+    pub limit: Option<i64>,
+}
+
+/// Get the annotation layers drawn over an image
+pub async fn get_image_annotation(
+    req: HttpRequest,
+    image_id: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match ImageAnnotation::find_by_image_id(&conn, &image_id).await {
+        Ok(annotation) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message": "Annotation retrieved successfully",
+            "data": annotation
+        }))),
+        Err(e) => {
+            error!("Failed to get annotation: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to get annotation: {}", e)
+            })))
+        }
+    }
+}
+
+/// Save (replace) the annotation layers drawn over an image
+pub async fn save_image_annotation(
+    req: HttpRequest,
+    image_id: web::Path<String>,
+    payload: web::Json<SaveAnnotationRequest>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    info!("=== Save Image Annotation Called ===");
+    info!("Image ID: {}", image_id);
+
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let payload = payload.into_inner();
+    if let Err(message) = validate_layers(&payload.layers) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": message
+        })));
+    }
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match ImageAnnotation::save(&conn, &image_id, payload).await {
+        Ok(SaveAnnotationOutcome::Saved(annotation)) => {
+            info!("✓ Annotation saved for image {} at version {}", image_id, annotation.version);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Annotation saved successfully",
+                "data": annotation
+            })))
+        }
+        Ok(SaveAnnotationOutcome::Conflict(current)) => Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "message": "Annotation was changed on another device",
+            "data": current
+        }))),
+        Ok(SaveAnnotationOutcome::ImageNotFound) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "Image not found"
+        }))),
+        Err(e) => {
+            error!("Failed to save annotation: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to save annotation: {}", e)
+            })))
+        }
+    }
+}
+
+/// Remove the annotation layers from an image
+pub async fn delete_image_annotation(
+    req: HttpRequest,
+    image_id: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match ImageAnnotation::delete(&conn, &image_id).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message": "Annotation deleted successfully"
+        }))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "Annotation not found"
+        }))),
+        Err(e) => {
+            error!("Failed to delete annotation: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to delete annotation: {}", e)
+            })))
+        }
+    }
+}
+
+/// Annotations changed since a version, for syncing markup across devices. Deleted
+/// annotations come back with `is_deleted` set; pass the returned `version` as
+/// `since_version` on the next pull.
+pub async fn get_annotation_changes(
+    req: HttpRequest,
+    query: web::Query<AnnotationChangesQuery>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+    let since_version = query.since_version.unwrap_or(0).max(0);
+    let limit = query.limit.unwrap_or(200).clamp(1, 1000);
+
+    match ImageAnnotation::changes_since(&conn, since_version, limit).await {
+        Ok(changes) => {
+            let version = changes.last().map_or(since_version, |a| a.version);
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "message": "Annotation changes retrieved successfully",
+                "data": {
+                    "changes": changes,
+                    "version": version,
+                    "has_more": changes.len() as i64 == limit
+                }
+            })))
+        }
+        Err(e) => {
+            error!("Failed to get annotation changes: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to get annotation changes: {}", e)
+            })))
+        }
+    }
+}
+
+/// A trade note's images with their annotation layers, for exports
+pub async fn get_annotated_images_by_trade_note(
+    req: HttpRequest,
+    trade_note_id: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match ImageAnnotation::annotated_images_for_trade_note(&conn, &trade_note_id).await {
+        Ok(images) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "message": "Annotated images retrieved successfully",
+            "total": images.len(),
+            "data": images
+        }))),
+        Err(e) => {
+            error!("Failed to get annotated images: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to get annotated images: {}", e)
+            })))
+        }
+    }
+}
+
 /// Simple test endpoint to verify routes are working
 async fn test_images_endpoint() -> Result<HttpResponse> {
     info!("Images test endpoint hit!");
@@ -761,8 +946,13 @@ pub fn configure_images_routes(cfg: &mut web::ServiceConfig) {
             .route("", web::get().to(get_images))
             .route("/count", web::get().to(get_images_count))
             .route("/trade-note/{trade_note_id}", web::get().to(get_images_by_trade_note))
+            .route("/trade-note/{trade_note_id}/annotated", web::get().to(get_annotated_images_by_trade_note))
+            .route("/annotations/changes", web::get().to(get_annotation_changes))
             .route("/{image_id}", web::get().to(get_image))
             .route("/{image_id}/url", web::get().to(get_image_url))
+            .route("/{image_id}/annotations", web::get().to(get_image_annotation))
+            .route("/{image_id}/annotations", web::put().to(save_image_annotation))
+            .route("/{image_id}/annotations", web::delete().to(delete_image_annotation))
             .route("/{image_id}", web::put().to(update_image))
             .route("/{image_id}", web::delete().to(delete_image))
     );
@@ -0,0 +1,97 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::csv_import::{self, ColumnMapping, CommitOutcome, MAX_IMPORT_BYTES};
+use crate::turso::AppState;
+
+pub fn configure_import_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/imports")
+        // The preview body is the CSV file itself
+        .app_data(web::PayloadConfig::new(MAX_IMPORT_BYTES))
+        .route("/preview", web::post().to(preview_import))
+        .route("/commit", web::post().to(commit_import))
+}
+
+#[derive(Debug, Deserialize)]
+struct PreviewQuery {
+    file_name: Option<String>,
+    /// Offset of the timestamps in the file, for formats that don't carry one
+    #[serde(default)]
+    utc_offset_minutes: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct CommitRequest {
+    token: String,
+    mapping: ColumnMapping,
+    #[serde(default)]
+    utc_offset_minutes: i32,
+    /// Import the rows that parse and report the rest, instead of importing nothing
+    #[serde(default)]
+    skip_invalid: bool,
+}
+
+/// Upload a CSV and get back its columns, a suggested mapping, sample rows and warnings
+async fn preview_import(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<PreviewQuery>,
+    body: web::Bytes,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let Ok(content) = std::str::from_utf8(&body) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "The file must be UTF-8 text"})));
+    };
+    let conn = user_db_connection(&app, &user_id).await?;
+    match csv_import::create_preview(&conn, query.file_name.as_deref(), content, query.utc_offset_minutes).await {
+        Ok(Ok(preview)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": preview}))),
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message}))),
+        Err(e) => {
+            error!("Failed to preview import for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to preview import"})))
+        }
+    }
+}
+
+/// Import a previewed file with the user's column mapping
+async fn commit_import(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<CommitRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    app.storage_quota_service.check_storage_quota(&user_id, &conn).await?;
+
+    match csv_import::commit(&conn, &payload.token, &payload.mapping, payload.utc_offset_minutes, payload.skip_invalid).await {
+        Ok(CommitOutcome::Imported { trade_ids, skipped }) => {
+            info!("Imported {} trades from CSV for user {} ({} rows skipped)", trade_ids.len(), user_id, skipped.len());
+            app.cache_service.invalidate_table_cache(&user_id, "stocks").await.ok();
+            app.cache_service.invalidate_user_analytics(&user_id).await.ok();
+            Ok(HttpResponse::Ok().json(serde_json::json!({
+                "success": true,
+                "data": {"imported": trade_ids.len(), "trade_ids": trade_ids, "skipped": skipped}
+            })))
+        }
+        Ok(CommitOutcome::NotFound) => Ok(HttpResponse::NotFound().json(serde_json::json!({
+            "success": false,
+            "message": "Import preview not found or expired; upload the file again"
+        }))),
+        Ok(CommitOutcome::Invalid { errors, row_errors }) => Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+            "success": false,
+            "message": errors.join("; "),
+            "errors": errors,
+            "row_errors": row_errors
+        }))),
+        Err(e) => {
+            error!("Failed to commit import for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to import trades"})))
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use actix_multipart::Multipart;
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use futures_util::TryStreamExt;
+use libsql::Connection;
+use log::{error, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::brokerage::email_confirmations::{self, ResolveDraftRequest};
+use crate::turso::AppState;
+
+/// Mailgun form fields the webhook reads; attachments and other fields are skipped
+const MAILGUN_FIELDS: [&str; 7] = ["recipient", "sender", "subject", "body-plain", "timestamp", "token", "signature"];
+
+async fn user_conn(app: &web::Data<AppState>, user_id: &str) -> actix_web::Result<Connection> {
+    app.turso_client
+        .get_user_database_connection(user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))
+}
+
+pub fn configure_inbound_email_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/trade-drafts")
+        .route("", web::get().to(list_trade_drafts))
+        .route("/address", web::get().to(get_forwarding_address))
+        .route("/address/rotate", web::post().to(rotate_forwarding_address))
+        .route("/{id}", web::put().to(resolve_trade_draft))
+}
+
+/// Mailgun route target for forwarded broker confirmations. Unknown addresses get a 406 so
+/// Mailgun drops the message instead of retrying.
+pub async fn inbound_email_webhook(app_state: web::Data<AppState>, mut payload: Multipart) -> actix_web::Result<HttpResponse> {
+    let Some(config) = app_state.config.inbound_email.clone() else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    let mut fields: HashMap<String, String> = HashMap::new();
+    while let Some(mut field) = payload.try_next().await.map_err(|e| {
+        error!("Failed to parse inbound email: {}", e);
+        actix_web::error::ErrorBadRequest("Invalid multipart data")
+    })? {
+        let name = field.name().to_string();
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(actix_web::error::ErrorBadRequest)? {
+            if MAILGUN_FIELDS.contains(&name.as_str()) {
+                bytes.extend_from_slice(&chunk);
+            }
+        }
+        if MAILGUN_FIELDS.contains(&name.as_str()) {
+            fields.insert(name, String::from_utf8_lossy(&bytes).to_string());
+        }
+    }
+    let field = |name: &str| fields.get(name).map(String::as_str).unwrap_or("");
+
+    if !email_confirmations::verify_mailgun_signature(&config.signing_key, field("timestamp"), field("token"), field("signature")) {
+        warn!("Rejected inbound email with invalid Mailgun signature");
+        return Err(actix_web::error::ErrorUnauthorized("Invalid signature"));
+    }
+
+    let Some(token) = email_confirmations::address_token(field("recipient")) else {
+        return Ok(HttpResponse::NotAcceptable().finish());
+    };
+    let registry = app_state
+        .turso_client
+        .get_registry_connection()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let Some(user_id) = email_confirmations::find_user_by_token(&registry, token)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+    else {
+        return Ok(HttpResponse::NotAcceptable().finish());
+    };
+
+    let received = chrono::Utc::now().date_naive();
+    let Some(confirmation) = email_confirmations::parse_confirmation(field("sender"), field("body-plain"), received) else {
+        info!("Inbound email for user {} is not from a supported broker", user_id);
+        return Ok(HttpResponse::Ok().finish());
+    };
+    if confirmation.drafts.is_empty() {
+        info!("No fills found in {} email for user {}", confirmation.broker, user_id);
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    let conn = user_conn(&app_state, &user_id).await?;
+    let subject = Some(field("subject")).filter(|s| !s.is_empty());
+    let ids = email_confirmations::save_drafts(&conn, &confirmation, subject)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    info!("Created {} trade drafts from {} email for user {}", ids.len(), confirmation.broker, user_id);
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// The user's forwarding address for broker confirmation emails
+async fn get_forwarding_address(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    address_response(app, req, false).await
+}
+
+/// Issue a new forwarding address; the previous one stops working
+async fn rotate_forwarding_address(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    address_response(app, req, true).await
+}
+
+async fn address_response(app: web::Data<AppState>, req: HttpRequest, rotate: bool) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let Some(config) = app.config.inbound_email.as_ref() else {
+        return Ok(HttpResponse::ServiceUnavailable().json(serde_json::json!({"success": false, "message": "Email forwarding is not configured"})));
+    };
+    let registry = app
+        .turso_client
+        .get_registry_connection()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let address = if rotate {
+        email_confirmations::rotate_forwarding_address(&registry, &user_id, &config.domain).await
+    } else {
+        email_confirmations::forwarding_address(&registry, &user_id, &config.domain).await
+    }
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"address": address}})))
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftsQuery {
+    /// pending (default), confirmed, discarded or all
+    status: Option<String>,
+}
+
+/// Drafts created from forwarded emails, newest first
+async fn list_trade_drafts(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<DraftsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let conn = user_conn(&app, &user_id).await?;
+    let status = match query.status.as_deref() {
+        Some("all") => None,
+        Some(status) => Some(status),
+        None => Some("pending"),
+    };
+    let drafts = email_confirmations::list_drafts(&conn, status)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": drafts})))
+}
+
+/// Mark a draft confirmed (after the client saved it through the stocks/options APIs) or discarded
+async fn resolve_trade_draft(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<ResolveDraftRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let request = payload.into_inner();
+    let message = match (request.status.as_str(), request.trade_id) {
+        ("confirmed", None) => Some("trade_id is required when confirming a draft"),
+        ("confirmed", Some(_)) | ("discarded", _) => None,
+        _ => Some("status must be confirmed or discarded"),
+    };
+    if let Some(message) = message {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+
+    let conn = user_conn(&app, &user_id).await?;
+    let updated = email_confirmations::resolve_draft(&conn, &path.into_inner(), &request)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if !updated {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Pending draft not found"})));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
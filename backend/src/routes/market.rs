@@ -1,10 +1,13 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
+use chrono::Datelike;
 use serde::Serialize;
 use std::sync::Arc;
 
 use crate::{
-    turso::AppState,
-    service::market_engine::{client::MarketClient, health, hours, quotes, historical, movers, news, indices, sectors, search as search_svc, indicators, ws_proxy::MarketWsProxy, financials, earnings_transcripts, earnings_calendar, holders},
+    middleware::cron_auth::verify_cron_secret,
+    turso::{redis::ttl, AppState},
+    service::market_engine::{client::MarketClient, health, hours, quotes, historical, movers, news, indices, sectors, search as search_svc, indicators, briefing, earnings_events, ws_proxy::MarketWsProxy, financials, earnings_transcripts, earnings_calendar, holders, options_chain},
+    service::trade_enrichment,
 };
 
 #[derive(Debug, Serialize)]
@@ -31,12 +34,40 @@ pub async fn get_health(app_state: web::Data<AppState>) -> Result<HttpResponse>
     }
 }
 
-pub async fn get_hours(app_state: web::Data<AppState>) -> Result<HttpResponse> {
-    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
-    match hours::get_hours(&client).await {
-        Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
-        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
-    }
+#[derive(serde::Deserialize)]
+pub struct HoursQuery {
+    /// nyse (default), cme or crypto
+    exchange: Option<String>,
+    /// Number of upcoming sessions to list, defaults to 5
+    sessions: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HoursResponse {
+    #[serde(flatten)]
+    live: Option<hours::MarketHours>,
+    schedule: hours::ExchangeSchedule,
+}
+
+/// Live US market status plus the upcoming sessions and early closes of an exchange
+pub async fn get_hours(app_state: web::Data<AppState>, query: web::Query<HoursQuery>) -> Result<HttpResponse> {
+    let exchange = match query.exchange.as_deref() {
+        None => hours::Exchange::Nyse,
+        Some(value) => match hours::Exchange::parse(value) {
+            Some(exchange) => exchange,
+            None => return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!("Unknown exchange '{}'", value)))),
+        },
+    };
+    let schedule = hours::schedule(exchange, chrono::Utc::now(), query.sessions.unwrap_or(5).clamp(1, 30));
+
+    // The provider only reports the US equity market
+    let live = if exchange == hours::Exchange::Nyse {
+        let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+        hours::get_hours(&client).await.map_err(|e| log::warn!("Live market hours unavailable: {}", e)).ok()
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok().json(ApiResponse::success(HoursResponse { live, schedule })))
 }
 
 #[derive(serde::Deserialize)]
@@ -74,6 +105,26 @@ pub async fn get_simple_quotes_handler(app_state: web::Data<AppState>, query: we
     }
 }
 
+#[derive(serde::Deserialize)]
+pub struct BatchQuotesRequest { symbols: Vec<String> }
+
+/// Snapshots for many symbols at once, with a status per symbol instead of failing the batch
+pub async fn batch_quotes_handler(app_state: web::Data<AppState>, payload: web::Json<BatchQuotesRequest>) -> Result<HttpResponse> {
+    let symbols = quotes::normalize_symbols(&payload.symbols);
+    if symbols.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error("symbols must not be empty".to_string())));
+    }
+    if symbols.len() > quotes::MAX_BATCH_SYMBOLS {
+        return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(format!(
+            "At most {} symbols per batch",
+            quotes::MAX_BATCH_SYMBOLS
+        ))));
+    }
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    let results = quotes::batch_quotes(&client, &app_state.cache_service, &symbols).await;
+    Ok(HttpResponse::Ok().json(ApiResponse::success(results)))
+}
+
 #[derive(serde::Deserialize)]
 pub struct SimilarQuery { symbol: String }
 
@@ -147,12 +198,41 @@ pub struct NewsQuery { symbol: Option<String>, limit: Option<u32> }
 
 pub async fn get_news_handler(app_state: web::Data<AppState>, query: web::Query<NewsQuery>) -> Result<HttpResponse> {
     let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
-    match news::get_news(&client, query.symbol.as_deref(), query.limit).await {
+    // Per-symbol requests share the cached, deduplicated feed
+    if let Some(symbol) = query.symbol.as_deref() {
+        let limit = query.limit.map_or(usize::MAX, |l| l as usize);
+        return match news::symbol_news(&client, &app_state.cache_service, symbol, None, limit).await {
+            Ok(page) => Ok(HttpResponse::Ok().json(ApiResponse::success(page.items))),
+            Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+        };
+    }
+    match news::get_news(&client, None, query.limit).await {
         Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
         Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
     }
 }
 
+#[derive(serde::Deserialize)]
+pub struct SymbolNewsQuery {
+    /// `next_since` from the previous page; only newer stories are returned
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<usize>,
+}
+
+/// Deduplicated news for one symbol with a `since` cursor for polling
+pub async fn get_symbol_news_handler(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    query: web::Query<SymbolNewsQuery>,
+) -> Result<HttpResponse> {
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    match news::symbol_news(&client, &app_state.cache_service, &path.into_inner(), query.since, limit).await {
+        Ok(page) => Ok(HttpResponse::Ok().json(ApiResponse::success(page))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
 pub async fn get_indices_handler(app_state: web::Data<AppState>) -> Result<HttpResponse> {
     let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
     match indices::get_indices(&client).await {
@@ -169,6 +249,16 @@ pub async fn get_sectors_handler(app_state: web::Data<AppState>) -> Result<HttpR
     }
 }
 
+/// Sector relative strength, breadth and index snapshot for the pre-market page
+pub async fn get_sector_dashboard_handler(app_state: web::Data<AppState>) -> Result<HttpResponse> {
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    let fetch = || sectors::get_sector_dashboard(&client);
+    match app_state.cache_service.get_or_fetch("market:sector_dashboard", ttl::SECTOR_DASHBOARD as u64, fetch).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub struct SearchQuery { 
     q: String,
@@ -178,7 +268,7 @@ pub struct SearchQuery {
 
 pub async fn search_handler(app_state: web::Data<AppState>, query: web::Query<SearchQuery>) -> Result<HttpResponse> {
     let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
-    match search_svc::search(&client, &query.q, query.hits, query.yahoo).await {
+    match search_svc::ranked_search(&client, &app_state.cache_service, &query.q, query.hits, query.yahoo).await {
         Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
         Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
     }
@@ -210,6 +300,37 @@ pub async fn get_financials_handler(app_state: web::Data<AppState>, query: web::
     }
 }
 
+#[derive(serde::Deserialize)]
+pub struct FundamentalsQuery {
+    symbol: String,
+    frequency: Option<String>,
+}
+
+/// Revenue growth, margins, EPS trend and valuation multiples for a symbol
+pub async fn get_fundamentals_handler(app_state: web::Data<AppState>, query: web::Query<FundamentalsQuery>) -> Result<HttpResponse> {
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    match financials::cached_fundamentals(&client, &app_state.cache_service, &query.symbol, query.frequency.as_deref()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+#[derive(serde::Deserialize)]
+pub struct OptionsChainQuery {
+    symbol: String,
+    /// YYYY-MM-DD; the nearest expiration when omitted
+    expiration: Option<String>,
+}
+
+/// Strikes for one expiration with Greeks, plus the underlying's IV rank and percentile
+pub async fn get_options_chain_handler(app_state: web::Data<AppState>, query: web::Query<OptionsChainQuery>) -> Result<HttpResponse> {
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    match options_chain::cached_options_chain(&client, &app_state.cache_service, &query.symbol, query.expiration.as_deref()).await {
+        Ok(res) => Ok(HttpResponse::Ok().json(ApiResponse::success(res))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub struct EarningsTranscriptQuery { 
     symbol: String,
@@ -225,6 +346,146 @@ pub async fn get_earnings_transcript_handler(app_state: web::Data<AppState>, que
     }
 }
 
+/// Summary of one call, from Redis when another user already asked for it
+async fn cached_transcript_summary(
+    app_state: &web::Data<AppState>,
+    transcript: &earnings_transcripts::Transcript,
+) -> anyhow::Result<earnings_transcripts::TranscriptSummary> {
+    let key = earnings_transcripts::summary_cache_key(&transcript.symbol, &transcript.quarter, transcript.year);
+    let fetch = || app_state.ai_coach_service.summarize_transcript(transcript);
+    app_state.cache_service.get_or_fetch(&key, ttl::TRANSCRIPT_SUMMARY as u64, fetch).await
+}
+
+/// Summarize a call and link the summary to every trade of the user held through it
+async fn summarize_and_link(
+    app_state: &web::Data<AppState>,
+    conn: &libsql::Connection,
+    transcript: &earnings_transcripts::Transcript,
+) -> anyhow::Result<(earnings_transcripts::TranscriptSummary, Vec<earnings_transcripts::HeldTrade>)> {
+    let summary = cached_transcript_summary(app_state, transcript).await?;
+    let held = match earnings_transcripts::call_date(transcript) {
+        Some(call) => earnings_transcripts::trades_held_through(conn, &summary.symbol, call).await?,
+        None => Vec::new(),
+    };
+    for trade in &held {
+        earnings_transcripts::link_summary(conn, trade, &summary).await?;
+    }
+    Ok((summary, held))
+}
+
+/// AI summary (guidance, surprises, tone) of an earnings call, latest when no quarter is
+/// given; also links it to the user's trades held through the call
+pub async fn get_transcript_summary_handler(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<EarningsTranscriptQuery>,
+) -> Result<HttpResponse> {
+    let user_id = extract_user_id_from_request(&req, &app_state.config.supabase).await?;
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let transcript = match earnings_transcripts::get_earnings_transcript(&client, &query.symbol, query.quarter.as_deref(), query.year).await {
+        Ok(res) => res.transcripts.into_iter().next(),
+        Err(e) => return Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    };
+    let Some(transcript) = transcript else {
+        return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("No transcript found".to_string())));
+    };
+    match summarize_and_link(&app_state, &conn, &transcript).await {
+        Ok((summary, held_trades)) => Ok(HttpResponse::Ok().json(ApiResponse::success(serde_json::json!({
+            "summary": summary,
+            "held_trades": held_trades,
+        })))),
+        Err(e) => {
+            log::error!("Transcript summary failed for {} {} {}: {}", transcript.symbol, transcript.quarter, transcript.year, e);
+            Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error("Failed to summarize transcript".to_string())))
+        }
+    }
+}
+
+/// Most calendar years of transcripts looked up for one trade
+const MAX_TRADE_TRANSCRIPT_YEARS: i32 = 3;
+
+/// Summaries of the earnings calls a trade was held through, for its review. Calls not
+/// linked yet are looked up and summarized first.
+pub async fn get_trade_transcript_summaries_handler(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse> {
+    let (trade_type, trade_id) = path.into_inner();
+    let user_id = extract_user_id_from_request(&req, &app_state.config.supabase).await?;
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let (symbol, entry, exit) = match earnings_transcripts::trade_window(&conn, &trade_type, trade_id).await {
+        Ok(Some(window)) => window,
+        Ok(None) => return Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Trade not found".to_string()))),
+        Err(e) => return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(e.to_string()))),
+    };
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let last_year = exit.unwrap_or_else(chrono::Utc::now).year();
+    let first_year = entry.year().max(last_year - MAX_TRADE_TRANSCRIPT_YEARS + 1);
+    for year in first_year..=last_year {
+        let transcripts = match earnings_transcripts::get_earnings_transcript(&client, &symbol, None, Some(year)).await {
+            Ok(res) => res.transcripts,
+            Err(e) => {
+                log::warn!("Transcripts for {} {} unavailable: {}", symbol, year, e);
+                continue;
+            }
+        };
+        for transcript in transcripts {
+            let held = earnings_transcripts::call_date(&transcript).is_some_and(|call| earnings_transcripts::held_through(entry, exit, call));
+            if held && let Err(e) = summarize_and_link(&app_state, &conn, &transcript).await {
+                log::error!("Transcript summary failed for {} {} {}: {}", symbol, transcript.quarter, transcript.year, e);
+            }
+        }
+    }
+
+    match earnings_transcripts::summaries_for_trade(&conn, &trade_type, trade_id).await {
+        Ok(summaries) => Ok(HttpResponse::Ok().json(ApiResponse::success(summaries))),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Entry-day OHLC, spread estimate and implied volatility for a trade; computed and
+/// stored on first request for trades logged before enrichment ran
+pub async fn get_trade_entry_context_handler(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<(String, i64)>,
+) -> Result<HttpResponse> {
+    let (trade_type, trade_id) = path.into_inner();
+    let user_id = extract_user_id_from_request(&req, &app_state.config.supabase).await?;
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+
+    let stored = match trade_enrichment::load_context(&conn, &trade_type, trade_id).await {
+        Ok(stored) => stored,
+        Err(e) => return Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error(e.to_string()))),
+    };
+    if let Some(context) = stored {
+        return Ok(HttpResponse::Ok().json(ApiResponse::success(context)));
+    }
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+    match trade_enrichment::enrich_existing(&conn, &client, &trade_type, trade_id).await {
+        Ok(Some(context)) => Ok(HttpResponse::Ok().json(ApiResponse::success(context))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("No market data for this trade's entry".to_string()))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
 #[derive(serde::Deserialize)]
 pub struct HoldersQuery { 
     symbol: String,
@@ -344,12 +605,108 @@ pub async fn unsubscribe_from_quotes(
     }
 }
 
+#[derive(serde::Deserialize)]
+pub struct BriefingQuery { summary: Option<bool> }
+
+/// Pre-market briefing for the morning-prep screen; `summary=true` adds an AI paragraph
+pub async fn get_briefing_handler(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<BriefingQuery>,
+) -> Result<HttpResponse> {
+    let user_id = extract_user_id_from_request(&req, &app_state.config.supabase).await?;
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let mut briefing = match briefing::build_briefing(&client, &conn).await {
+        Ok(briefing) => briefing,
+        Err(e) => return Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    };
+    if query.summary.unwrap_or(false) {
+        match app_state.ai_coach_service.summarize_briefing(&conn, &briefing).await {
+            Ok(summary) => briefing.summary = Some(summary),
+            Err(e) => log::warn!("Briefing summary failed for user {}: {}", user_id, e),
+        }
+    }
+    Ok(HttpResponse::Ok().json(ApiResponse::success(briefing)))
+}
+
+/// Mirror the user's upcoming earnings dates into their calendar now instead of waiting for the nightly sync
+pub async fn sync_earnings_events_handler(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    let user_id = extract_user_id_from_request(&req, &app_state.config.supabase).await?;
+    let conn = app_state
+        .get_user_db_connection(&user_id)
+        .await
+        .map_err(|e| actix_web::error::ErrorInternalServerError(e.to_string()))?
+        .ok_or_else(|| actix_web::error::ErrorBadRequest("User database not found"))?;
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match earnings_events::sync_earnings_events(&client, &conn).await {
+        Ok(summary) => Ok(HttpResponse::Ok().json(ApiResponse::success(summary))),
+        Err(e) => Ok(HttpResponse::BadGateway().json(ApiResponse::<()>::error(e.to_string()))),
+    }
+}
+
+/// Cron: refresh every tenant's earnings events, dropping those of closed positions
+pub async fn sync_all_earnings_events(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let user_ids = app_state.turso_client.list_active_user_ids().await.map_err(|e| {
+        log::error!("Failed to list active users for earnings sync: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut synced = 0u64;
+    let mut upserted = 0u64;
+    let mut removed = 0u64;
+    let mut failure_count = 0u64;
+
+    for user_id in user_ids {
+        let conn = match app_state.turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        match earnings_events::sync_earnings_events(&client, &conn).await {
+            Ok(summary) => {
+                synced += 1;
+                upserted += summary.upserted;
+                removed += summary.removed;
+            }
+            Err(e) => {
+                failure_count += 1;
+                log::error!("Earnings calendar sync failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    let summary = serde_json::json!({
+        "synced": synced,
+        "upserted": upserted,
+        "removed": removed,
+        "failure_count": failure_count,
+    });
+    log::info!("Earnings calendar sync completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(ApiResponse::success(summary)))
+}
+
 pub fn configure_market_routes(cfg: &mut web::ServiceConfig) {
     cfg
         .route("/api/market/health", web::get().to(get_health))
         .route("/api/market/hours", web::get().to(get_hours))
         .route("/api/market/quotes", web::get().to(get_quotes_handler))
         .route("/api/market/simple-quotes", web::get().to(get_simple_quotes_handler))
+        .route("/api/market/quotes/batch", web::post().to(batch_quotes_handler))
         .route("/api/market/similar", web::get().to(get_similar_handler))
         .route("/api/market/logo", web::get().to(get_logo_handler))
         .route("/api/market/historical", web::get().to(get_historical_handler))
@@ -358,13 +715,23 @@ pub fn configure_market_routes(cfg: &mut web::ServiceConfig) {
         .route("/api/market/losers", web::get().to(get_losers_handler))
         .route("/api/market/actives", web::get().to(get_most_active_handler))
         .route("/api/market/news", web::get().to(get_news_handler))
+        .route("/api/market/news/{symbol}", web::get().to(get_symbol_news_handler))
         .route("/api/market/indices", web::get().to(get_indices_handler))
         .route("/api/market/sectors", web::get().to(get_sectors_handler))
+        .route("/api/market/sector-dashboard", web::get().to(get_sector_dashboard_handler))
+        .route("/api/market/briefing", web::get().to(get_briefing_handler))
         .route("/api/market/search", web::get().to(search_handler))
         .route("/api/market/indicators", web::get().to(indicators_handler))
         .route("/api/market/financials", web::get().to(get_financials_handler))
+        .route("/api/market/fundamentals", web::get().to(get_fundamentals_handler))
+        .route("/api/market/options-chain", web::get().to(get_options_chain_handler))
         .route("/api/market/earnings-transcript", web::get().to(get_earnings_transcript_handler))
+        .route("/api/market/earnings-transcript/summary", web::get().to(get_transcript_summary_handler))
+        .route("/api/market/earnings-transcript/trades/{trade_type}/{id}", web::get().to(get_trade_transcript_summaries_handler))
+        .route("/api/market/trade-context/{trade_type}/{id}", web::get().to(get_trade_entry_context_handler))
         .route("/api/market/earnings-calendar", web::get().to(get_earnings_calendar_handler))
+        .route("/api/market/earnings-calendar/sync", web::post().to(sync_earnings_events_handler))
+        .route("/api/market/earnings-calendar/sync-all", web::post().to(sync_all_earnings_events))
         .route("/api/market/holders", web::get().to(get_holders_handler))
         .route("/api/market/subscribe", web::post().to(subscribe_to_quotes))
         .route("/api/market/unsubscribe", web::post().to(unsubscribe_from_quotes));
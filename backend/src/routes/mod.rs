@@ -1,3 +1,4 @@
+pub mod common;
 pub mod analytics;
 pub mod user;
 pub mod options;
@@ -14,6 +15,48 @@ pub mod trade_tags;
 pub mod watchlist_price;
 pub mod push;
 pub mod brokerage;
+pub mod prop_firm;
+pub mod notification_webhooks;
+pub mod notion;
+pub mod telegram;
+pub mod trade_parser;
+pub mod ai_coach;
+pub mod ai_feedback;
+pub mod ai_memory;
+pub mod i18n;
+pub mod ai_report_schedules;
+pub mod storage;
+pub mod task_monitor;
+pub mod tenant_cleanup;
+pub mod image_migration;
+pub mod realized_pnl;
+pub mod behavior;
+pub mod inbound_email;
+pub mod dashboard;
+pub mod focus;
+pub mod organizations;
+pub mod risk;
+pub mod retention;
+pub mod setup_discovery;
+pub mod goals;
+pub mod automations;
+pub mod trade_archive;
+pub mod onboarding;
+pub mod data_quality;
+pub mod activity;
+pub mod sandbox;
+pub mod schema_admin;
+pub mod ai_latency;
+pub mod cost_report;
+pub mod statements;
+pub mod command_palette;
+pub mod symbol_journal;
+pub mod year_review;
+pub mod operations;
+pub mod imports;
+pub mod trade_links;
+pub mod daily_digest;
+pub mod telemetry;
 
 pub use analytics::configure_analytics_routes;
 pub use user::configure_user_routes;
@@ -30,3 +73,37 @@ pub use trade_tags::configure_trade_tags_routes;
 pub use watchlist_price::configure_watchlist_price_routes;
 pub use push::configure_push_routes;
 pub use brokerage::configure_brokerage_routes;
+pub use prop_firm::configure_prop_firm_routes;
+pub use notification_webhooks::configure_notification_webhook_routes;
+pub use notion::configure_notion_routes;
+pub use telegram::configure_telegram_routes;
+pub use trade_parser::configure_trade_parser_routes;
+pub use ai_coach::configure_ai_coach_routes;
+pub use ai_feedback::configure_ai_feedback_routes;
+pub use ai_memory::configure_ai_memory_routes;
+pub use i18n::configure_i18n_routes;
+pub use ai_report_schedules::configure_ai_report_schedule_routes;
+pub use storage::configure_storage_routes;
+pub use behavior::configure_behavior_routes;
+pub use inbound_email::configure_inbound_email_routes;
+pub use dashboard::configure_dashboard_routes;
+pub use focus::configure_focus_routes;
+pub use organizations::configure_organization_routes;
+pub use risk::configure_risk_routes;
+pub use retention::configure_retention_routes;
+pub use setup_discovery::configure_setup_discovery_routes;
+pub use goals::configure_goals_routes;
+pub use automations::configure_automation_routes;
+pub use trade_archive::configure_trade_archive_routes;
+pub use onboarding::configure_onboarding_routes;
+pub use data_quality::configure_data_quality_routes;
+pub use activity::configure_activity_routes;
+pub use statements::configure_statement_routes;
+pub use command_palette::configure_command_palette_routes;
+pub use symbol_journal::configure_symbol_journal_routes;
+pub use year_review::configure_year_review_routes;
+pub use operations::configure_operation_routes;
+pub use imports::configure_import_routes;
+pub use trade_links::configure_trade_link_routes;
+pub use daily_digest::configure_daily_digest_routes;
+pub use telemetry::configure_telemetry_routes;
@@ -2,6 +2,7 @@ use actix_web::{web, HttpRequest, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
 use chrono::{Utc, Datelike};
 use log::{info, error};
+use std::collections::HashMap;
 use std::sync::Arc;
 use libsql::{Connection, Builder};
 
@@ -9,15 +10,20 @@ use crate::turso::{AppState, client::TursoClient};
 use crate::turso::config::SupabaseConfig;
 use crate::turso::auth::validate_supabase_jwt_token;
 use crate::models::notebook::{
-    NotebookNote, CreateNoteRequest, UpdateNoteRequest,
+    NotebookNote, CreateNoteRequest, UpdateNoteRequest, ReorderNotesRequest, NoteMoveError,
     NotebookTag, CreateTagRequest, UpdateTagRequest,
     NotebookTemplate, CreateTemplateRequest, UpdateTemplateRequest,
     NotebookReminder, CreateReminderRequest, UpdateReminderRequest,
-    CalendarEvent, ExternalCalendarConnection, ExternalCalendarEvent,
+    ExternalCalendarConnection, ExternalCalendarEvent,
 };
+use crate::service::agenda;
 use crate::service::calendar_service::CalendarService;
-use crate::service::holidays_service::HolidaysService;
+use crate::service::holidays_service::{HolidaysService, PublicHoliday};
+use crate::middleware::cron_auth::verify_cron_secret;
 use crate::service::cache_service::CacheService;
+use crate::service::ai_service::vectorization_service::VectorizationService;
+use crate::service::ai_service::content_extraction::{extract_plain_text_from_json, ImageCaptions};
+use crate::service::ai_service::note_assistant::{self, NoteAction, NoteSuggestion};
 
 #[derive(Debug, Serialize)]
 struct ApiList<T> { success: bool, message: String, data: Option<Vec<T>> }
@@ -59,6 +65,24 @@ async fn get_user_database_connection(
     db.connect().map_err(|_| actix_web::error::ErrorInternalServerError("Database connection failed"))
 }
 
+/// Re-embed a note's chunks in the background
+fn spawn_note_vectorization(service: Arc<VectorizationService>, user_id: String, note: NotebookNote) {
+    tokio::spawn(async move {
+        if let Err(e) = service.vectorize_notebook_note(&user_id, &note).await {
+            error!("Failed to vectorize notebook note {} for user {}: {}", note.id, user_id, e);
+        }
+    });
+}
+
+/// Drop a note's chunk vectors in the background
+fn spawn_note_vector_removal(service: Arc<VectorizationService>, user_id: String, note_id: String) {
+    tokio::spawn(async move {
+        if let Err(e) = service.delete_notebook_note_vectors(&user_id, &note_id).await {
+            error!("Failed to delete vectors for notebook note {} (user {}): {}", note_id, user_id, e);
+        }
+    });
+}
+
 // ==== Notes ====
 /// Create a note with cache invalidation
 pub async fn create_note(
@@ -90,6 +114,7 @@ pub async fn create_note(
                     Err(e) => error!("Failed to invalidate notebook notes cache for user {}: {}", user_id_clone, e),
                 }
             });
+            spawn_note_vectorization(app_state.vectorization_service.clone(), claims.sub.clone(), note.clone());
             
             Ok(HttpResponse::Created().json(ApiItem { 
                 success: true, 
@@ -186,11 +211,15 @@ pub async fn update_note(
     payload: web::Json<UpdateNoteRequest>,
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
 ) -> Result<HttpResponse> {
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
     match NotebookNote::update(&conn, &note_id, payload.into_inner()).await {
-        Ok(note) => Ok(HttpResponse::Ok().json(ApiItem { success: true, message: "Updated".into(), data: Some(note) })),
+        Ok(note) => {
+            spawn_note_vectorization(vectorization_service.get_ref().clone(), claims.sub.clone(), note.clone());
+            Ok(HttpResponse::Ok().json(ApiItem { success: true, message: "Updated".into(), data: Some(note) }))
+        }
         Err(e) => Ok(HttpResponse::InternalServerError().json(ApiItem::<NotebookNote> { success: false, message: e.to_string(), data: None })),
     }
 }
@@ -200,11 +229,15 @@ pub async fn delete_note(
     note_id: web::Path<String>,
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
 ) -> Result<HttpResponse> {
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
     match NotebookNote::soft_delete(&conn, &note_id).await {
-        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Deleted"}))),
+        Ok(true) => {
+            spawn_note_vector_removal(vectorization_service.get_ref().clone(), claims.sub.clone(), note_id.to_string());
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Deleted"})))
+        }
         Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Not found"}))),
         Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": e.to_string()}))),
     }
@@ -228,11 +261,17 @@ pub async fn restore_note(
     note_id: web::Path<String>,
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
 ) -> Result<HttpResponse> {
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
     match NotebookNote::restore(&conn, &note_id).await {
-        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Restored"}))),
+        Ok(true) => {
+            if let Ok(note) = NotebookNote::find_by_id(&conn, &note_id).await {
+                spawn_note_vectorization(vectorization_service.get_ref().clone(), claims.sub.clone(), note);
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Restored"})))
+        }
         Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Not found"}))),
         Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": e.to_string()}))),
     }
@@ -243,11 +282,15 @@ pub async fn permanent_delete_note(
     note_id: web::Path<String>,
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
 ) -> Result<HttpResponse> {
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
     match NotebookNote::permanent_delete(&conn, &note_id).await {
-        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Permanently deleted"}))),
+        Ok(true) => {
+            spawn_note_vector_removal(vectorization_service.get_ref().clone(), claims.sub.clone(), note_id.to_string());
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Permanently deleted"})))
+        }
         Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Not found"}))),
         Err(e) => Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": e.to_string()}))),
     }
@@ -271,6 +314,129 @@ pub async fn reorder_note(
     }
 }
 
+/// Apply a drag-and-drop reorganization (new parents and positions for many notes) as one
+/// atomic change; rejected as a whole if any move is invalid or would create a cycle
+pub async fn reorder_notes(
+    req: HttpRequest,
+    payload: web::Json<ReorderNotesRequest>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+    cache_service: web::Data<Arc<CacheService>>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+    match NotebookNote::apply_moves(&conn, &payload.moves).await {
+        Ok(notes) => {
+            // Child lists are cached per parent, and every one of them may have changed
+            let cache_service = cache_service.get_ref().clone();
+            let user_id = claims.sub.clone();
+            tokio::spawn(async move {
+                if let Err(e) = cache_service.invalidate_table_cache(&user_id, "notebook_notes").await {
+                    error!("Failed to invalidate notebook notes cache for user {}: {}", user_id, e);
+                }
+            });
+            Ok(HttpResponse::Ok().json(ApiList { success: true, message: "Reordered".into(), data: Some(notes) }))
+        }
+        Err(NoteMoveError::Invalid(message)) => Ok(HttpResponse::BadRequest().json(ApiList::<NotebookNote> { success: false, message, data: None })),
+        Err(e) => {
+            error!("Bulk note reorder failed for user {}: {}", claims.sub, e);
+            Ok(HttpResponse::InternalServerError().json(ApiList::<NotebookNote> { success: false, message: e.to_string(), data: None }))
+        }
+    }
+}
+
+// ==== AI writing assistant ====
+#[derive(Debug, Deserialize)]
+pub struct NoteAiRequest {
+    action: NoteAction,
+    /// Operate on this excerpt instead of the whole note
+    selection: Option<String>,
+}
+
+/// Suggest a rewrite, summary or action items for a note. The note itself is not changed.
+pub async fn note_ai_action(
+    req: HttpRequest,
+    note_id: web::Path<String>,
+    payload: web::Json<NoteAiRequest>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &app_state.turso_client).await?;
+    let Ok(note) = NotebookNote::find_by_id(&conn, &note_id).await else {
+        return Ok(HttpResponse::NotFound().json(ApiItem::<NoteSuggestion> { success: false, message: "Not found".into(), data: None }));
+    };
+    let original = match payload.selection.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        Some(selection) => selection.to_string(),
+        None => extract_plain_text_from_json(&note.content, &ImageCaptions::new()),
+    };
+    if original.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json(ApiItem::<NoteSuggestion> { success: false, message: "Note has no text".into(), data: None }));
+    }
+    if original.chars().count() > note_assistant::MAX_INPUT_CHARS {
+        return Ok(HttpResponse::PayloadTooLarge().json(ApiItem::<NoteSuggestion> {
+            success: false,
+            message: format!("Select at most {} characters", note_assistant::MAX_INPUT_CHARS),
+            data: None,
+        }));
+    }
+
+    match app_state.ai_notes_service.assist(payload.action, &original).await {
+        Ok(suggestion) => {
+            let data = NoteSuggestion {
+                note_id: note.id,
+                action: payload.action,
+                base_updated_at: note.updated_at,
+                diff: note_assistant::line_diff(&original, &suggestion),
+                action_items: (payload.action == NoteAction::ExtractActionItems).then(|| note_assistant::parse_action_items(&suggestion)),
+                original,
+                suggestion,
+            };
+            Ok(HttpResponse::Ok().json(ApiItem { success: true, message: "Suggestion".into(), data: Some(data) }))
+        }
+        Err(e) => {
+            error!("Notebook AI action failed for user {}: {}", claims.sub, e);
+            Ok(HttpResponse::BadGateway().json(ApiItem::<NoteSuggestion> { success: false, message: "AI assistant unavailable".into(), data: None }))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ApplySuggestionPayload {
+    /// New note content, in the editor's format
+    content: serde_json::Value,
+    /// `base_updated_at` of the suggestion being applied
+    base_updated_at: String,
+}
+
+/// Write an accepted suggestion, refusing if the note changed since it was suggested
+pub async fn apply_note_ai_suggestion(
+    req: HttpRequest,
+    note_id: web::Path<String>,
+    payload: web::Json<ApplySuggestionPayload>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &app_state.turso_client).await?;
+    let Ok(note) = NotebookNote::find_by_id(&conn, &note_id).await else {
+        return Ok(HttpResponse::NotFound().json(ApiItem::<NotebookNote> { success: false, message: "Not found".into(), data: None }));
+    };
+    if note.updated_at != payload.base_updated_at {
+        return Ok(HttpResponse::Conflict().json(ApiItem { success: false, message: "Note changed since the suggestion was made".into(), data: Some(note) }));
+    }
+
+    let payload = payload.into_inner();
+    let update = UpdateNoteRequest { title: None, content: Some(payload.content), parent_id: None, position: None, is_deleted: None };
+    match NotebookNote::update(&conn, &note_id, update).await {
+        Ok(note) => {
+            spawn_note_vectorization(app_state.vectorization_service.clone(), claims.sub.clone(), note.clone());
+            Ok(HttpResponse::Ok().json(ApiItem { success: true, message: "Applied".into(), data: Some(note) }))
+        }
+        Err(e) => Ok(HttpResponse::InternalServerError().json(ApiItem::<NotebookNote> { success: false, message: e.to_string(), data: None })),
+    }
+}
+
 // ==== Tags ====
 pub async fn create_tag(
     req: HttpRequest,
@@ -615,7 +781,7 @@ pub async fn list_calendar_events(
     let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
     
     // Get local events
-    let local_events = CalendarEvent::find_by_date_range(&conn, &query.start, &query.end).await
+    let local_events = CalendarService::events_in_range(&conn, &query.start, &query.end).await
         .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to fetch local events"))?;
     
     // Get external events
@@ -632,6 +798,32 @@ pub async fn list_calendar_events(
     })))
 }
 
+/// Everything on the user's calendar between two dates in one chronological list
+pub async fn get_agenda(
+    req: HttpRequest,
+    query: web::Query<DateRangeQuery>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let parse = |value: &str| chrono::NaiveDate::parse_from_str(value.get(..10).unwrap_or(value), "%Y-%m-%d").ok();
+    let (Some(start), Some(end)) = (parse(&query.start), parse(&query.end)) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "start and end must be YYYY-MM-DD dates"})));
+    };
+    if end < start || (end - start).num_days() >= agenda::MAX_AGENDA_DAYS {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": format!("end must be on or after start and at most {} days later", agenda::MAX_AGENDA_DAYS - 1),
+        })));
+    }
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    // Holidays are only synced for the US for now, as in get_public_holidays
+    let agenda = agenda::build_agenda(&conn, start, end, "US").await
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to build agenda"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Agenda", "data": agenda})))
+}
+
 pub async fn list_calendar_connections(
     req: HttpRequest,
     turso_client: web::Data<Arc<TursoClient>>,
@@ -655,10 +847,12 @@ pub struct DateRangeQuery {
 pub fn configure_notebook_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api/notebook")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::etag::etag_middleware))
             // Notes
             .route("/notes", web::post().to(create_note))
             .route("/notes", web::get().to(list_notes))
             .route("/notes/deleted", web::get().to(list_deleted_notes))
+            .route("/notes/reorder", web::post().to(reorder_notes))
             .route("/notes/{id}", web::get().to(get_note))
             .route("/notes/{id}", web::put().to(update_note))
             .route("/notes/{id}", web::delete().to(delete_note))
@@ -666,6 +860,8 @@ pub fn configure_notebook_routes(cfg: &mut web::ServiceConfig) {
             .route("/notes/{id}/permanent", web::delete().to(permanent_delete_note))
             .route("/notes/{id}/tree", web::get().to(get_note_tree))
             .route("/notes/{id}/reorder", web::post().to(reorder_note))
+            .route("/notes/{id}/ai", web::post().to(note_ai_action))
+            .route("/notes/{id}/ai/apply", web::post().to(apply_note_ai_suggestion))
             // Tags
             .route("/tags", web::post().to(create_tag))
             .route("/tags", web::get().to(list_tags))
@@ -698,6 +894,13 @@ pub fn configure_notebook_routes(cfg: &mut web::ServiceConfig) {
             .route("/oauth/google/exchange", web::post().to(google_oauth_exchange))
             .route("/oauth/microsoft/exchange", web::post().to(microsoft_oauth_exchange))
     );
+    cfg.service(
+        web::scope("/api/calendar")
+            .route("/agenda", web::get().to(get_agenda))
+            .route("/holidays", web::get().to(get_country_holidays))
+            // Cron: yearly holiday refresh (secured with cron secret)
+            .route("/holidays/refresh", web::post().to(refresh_all_holidays))
+    );
 }
 
 // ==== External calendar connect/sync stubs ====
@@ -880,6 +1083,109 @@ pub async fn get_public_holidays(
     Ok(HttpResponse::Ok().json(holidays))
 }
 
+#[derive(Deserialize)]
+pub struct CountryHolidaysQuery {
+    /// ISO 3166-1 alpha-2 code; US when omitted
+    pub country: Option<String>,
+    /// Current year when omitted
+    pub year: Option<i32>,
+}
+
+/// One country's holidays for a year, populated from Nager.Date on first request
+pub async fn get_country_holidays(
+    req: HttpRequest,
+    query: web::Query<CountryHolidaysQuery>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+
+    let country_code = query.country.as_deref().unwrap_or("US").trim().to_uppercase();
+    let year = query.year.unwrap_or_else(|| Utc::now().year());
+    if !HolidaysService::is_country_code(&country_code) || !(1975..=2100).contains(&year) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+            "success": false,
+            "message": "country must be a two-letter ISO code and year between 1975 and 2100",
+        })));
+    }
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match HolidaysService::holidays_for_year(&conn, &country_code, year).await {
+        Ok(holidays) => Ok(HttpResponse::Ok().json(ApiList { success: true, message: "OK".into(), data: Some(holidays) })),
+        Err(e) => {
+            error!("Failed to load {} holidays for {} (user {}): {}", country_code, year, claims.sub, e);
+            Ok(HttpResponse::BadGateway().json(ApiList::<PublicHoliday> { success: false, message: "Holidays unavailable".into(), data: None }))
+        }
+    }
+}
+
+/// Yearly cron: refresh this year's and next year's holidays for every user's configured
+/// countries. Each country-year is fetched once and written to every user database.
+pub async fn refresh_all_holidays(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for holiday refresh: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let this_year = Utc::now().year();
+    let mut fetched: HashMap<(String, i32), Option<Vec<PublicHoliday>>> = HashMap::new();
+    let (mut users_refreshed, mut users_failed, mut holidays_stored) = (0u32, 0u32, 0u64);
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            users_failed += 1;
+            continue;
+        };
+        let countries = match HolidaysService::configured_countries(&conn).await {
+            Ok(countries) => countries,
+            Err(e) => {
+                error!("Failed to read holiday countries for user {}: {}", user_id, e);
+                users_failed += 1;
+                continue;
+            }
+        };
+
+        let mut failed = false;
+        for country in countries {
+            for year in [this_year, this_year + 1] {
+                let key = (country.clone(), year);
+                if !fetched.contains_key(&key) {
+                    let holidays = match HolidaysService::fetch_nager_holidays(&country, year).await {
+                        Ok(holidays) => Some(holidays),
+                        Err(e) => {
+                            error!("Failed to fetch {} holidays for {}: {}", country, year, e);
+                            None
+                        }
+                    };
+                    fetched.insert(key.clone(), holidays);
+                }
+                // A failed fetch keeps whatever the user already has
+                let Some(Some(holidays)) = fetched.get(&key) else { continue };
+                match HolidaysService::replace_year(&conn, &country, year, holidays.clone()).await {
+                    Ok(stored) => holidays_stored += stored,
+                    Err(e) => {
+                        error!("Failed to store {} holidays for {} (user {}): {}", country, year, user_id, e);
+                        failed = true;
+                    }
+                }
+            }
+        }
+        if failed { users_failed += 1 } else { users_refreshed += 1 }
+    }
+
+    info!("Holiday refresh: {} users refreshed, {} failed, {} holidays stored", users_refreshed, users_failed, holidays_stored);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "users_refreshed": users_refreshed,
+        "users_failed": users_failed,
+        "holidays_stored": holidays_stored,
+    })))
+}
+
 #[derive(Deserialize)]
 pub struct HolidaysSyncQuery {
     pub country_code: Option<String>,
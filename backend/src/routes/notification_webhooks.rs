@@ -0,0 +1,123 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::user_connection;
+use crate::service::notifications::chat_webhooks::{
+    ChatWebhookService, ChatMessage, SaveChatWebhookRequest, send_daily_recap,
+};
+use crate::turso::{AppState, client::TursoClient};
+
+pub fn configure_notification_webhook_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/notifications/webhooks")
+        .route("", web::get().to(list_webhooks))
+        .route("", web::post().to(create_webhook))
+        .route("/{id}", web::put().to(update_webhook))
+        .route("/{id}", web::delete().to(delete_webhook))
+        .route("/{id}/test", web::post().to(send_test))
+}
+
+async fn list_webhooks(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let webhooks = ChatWebhookService::new(&conn).list_webhooks().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": webhooks})))
+}
+
+async fn create_webhook(app: web::Data<AppState>, req: HttpRequest, body: web::Json<SaveChatWebhookRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let body = body.into_inner();
+    body.validate().map_err(actix_web::error::ErrorBadRequest)?;
+    let webhook = ChatWebhookService::new(&conn).create_webhook(body).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": webhook})))
+}
+
+async fn update_webhook(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>, body: web::Json<SaveChatWebhookRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let body = body.into_inner();
+    body.validate().map_err(actix_web::error::ErrorBadRequest)?;
+    let webhook = ChatWebhookService::new(&conn)
+        .update_webhook(&path.into_inner(), body)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Webhook not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": webhook})))
+}
+
+async fn delete_webhook(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let removed = ChatWebhookService::new(&conn).delete_webhook(&path.into_inner()).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if !removed {
+        return Err(actix_web::error::ErrorNotFound("Webhook not found"));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+async fn send_test(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let service = ChatWebhookService::new(&conn);
+    let webhook = service
+        .get_webhook(&path.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Webhook not found"))?;
+
+    let message = ChatMessage {
+        title: "Tradstry is connected".to_string(),
+        description: "You'll receive your selected events in this channel.".to_string(),
+        fields: vec![("Events".to_string(), webhook.event_types.join(", "))],
+        color: 0x5865F2,
+        url: None,
+    };
+    service.deliver(&webhook, &message).await.map_err(actix_web::error::ErrorBadGateway)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+/// Cron endpoint: send today's P&L recap to every user's subscribed webhooks
+pub async fn send_all_daily_recaps(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for daily recap: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut users_notified = 0u64;
+    let mut deliveries = 0u64;
+    let mut failure_count = 0u64;
+
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+
+        match send_daily_recap(&conn, &user_id).await {
+            Ok(0) => {}
+            Ok(n) => {
+                users_notified += 1;
+                deliveries += n as u64;
+            }
+            Err(e) => {
+                failure_count += 1;
+                error!("Daily recap failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    let summary = serde_json::json!({
+        "users_notified": users_notified,
+        "deliveries": deliveries,
+        "failure_count": failure_count,
+    });
+    info!("Daily recap webhooks completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
@@ -0,0 +1,197 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::NaiveDate;
+use log::{error, info, warn};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::models::analytics::PeriodDefinition;
+use crate::routes::common::{require_user_id, user_connection, user_db_connection};
+use crate::service::integrations::notion::{
+    JournalEntryType, NotionService, UpdateNotionSettingsRequest, NOTION_PROVIDER, today,
+};
+use crate::service::integrations::oauth_state;
+use crate::service::secrets_store::SecretsStore;
+use crate::turso::config::NotionConfig;
+use crate::turso::{AppState, client::TursoClient};
+
+fn secrets_store(app: &web::Data<AppState>) -> actix_web::Result<SecretsStore> {
+    SecretsStore::from_config(app.config.secrets_encryption_key.as_deref())
+        .map_err(actix_web::error::ErrorServiceUnavailable)
+}
+
+fn notion_config(app: &web::Data<AppState>) -> actix_web::Result<&NotionConfig> {
+    app.config.notion.as_ref().ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Notion integration is not configured"))
+}
+
+pub fn configure_notion_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/integrations/notion")
+        .route("/authorize-url", web::get().to(get_authorize_url))
+        .route("/callback", web::post().to(oauth_callback))
+        .route("", web::get().to(get_settings))
+        .route("", web::put().to(update_settings))
+        .route("", web::delete().to(disconnect))
+        .route("/export", web::post().to(export_now))
+}
+
+async fn registry_connection(app: &web::Data<AppState>) -> actix_web::Result<libsql::Connection> {
+    app.turso_client
+        .get_registry_connection()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+async fn get_authorize_url(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let config = notion_config(&app)?;
+    // The callback must come back with this state for the same user
+    let registry = registry_connection(&app).await?;
+    let state = oauth_state::issue_state(&registry, &user_id, NOTION_PROVIDER)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    let url = NotionService::authorize_url(config, &state);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"url": url, "state": state}})))
+}
+
+#[derive(Deserialize)]
+struct CallbackReq {
+    code: String,
+    state: Option<String>,
+}
+
+async fn oauth_callback(app: web::Data<AppState>, req: HttpRequest, body: web::Json<CallbackReq>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_connection(&app).await?;
+    let state = body.state.as_deref().unwrap_or("");
+    let state_ok = oauth_state::consume_state(&registry, &user_id, NOTION_PROVIDER, state)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if !state_ok {
+        warn!("Rejected Notion OAuth callback for user {}: state missing or mismatched", user_id);
+        return Err(actix_web::error::ErrorBadRequest("Invalid or expired OAuth state"));
+    }
+
+    let conn = user_db_connection(&app, &user_id).await?;
+    let config = notion_config(&app)?;
+    let secrets = secrets_store(&app)?;
+    let settings = NotionService::new(&conn, &secrets)
+        .complete_oauth(config, &body.code)
+        .await
+        .map_err(|e| {
+            error!("Notion OAuth failed: {}", e);
+            actix_web::error::ErrorBadRequest(e.to_string())
+        })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": settings})))
+}
+
+async fn get_settings(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let secrets = secrets_store(&app)?;
+    let settings = NotionService::new(&conn, &secrets).get_settings().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": settings, "connected": settings.is_some()})))
+}
+
+async fn update_settings(app: web::Data<AppState>, req: HttpRequest, body: web::Json<UpdateNotionSettingsRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let secrets = secrets_store(&app)?;
+    let settings = NotionService::new(&conn, &secrets)
+        .update_settings(body.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Notion is not connected"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": settings})))
+}
+
+async fn disconnect(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let secrets = secrets_store(&app)?;
+    NotionService::new(&conn, &secrets).disconnect().await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+#[derive(Deserialize)]
+struct ExportReq {
+    entry_type: JournalEntryType,
+    /// YYYY-MM-DD, defaults to today (end of the week for weekly reviews)
+    date: Option<String>,
+}
+
+async fn export_now(app: web::Data<AppState>, req: HttpRequest, body: web::Json<ExportReq>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let secrets = secrets_store(&app)?;
+    let date = match &body.date {
+        Some(d) => NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|_| actix_web::error::ErrorBadRequest("date must be YYYY-MM-DD"))?,
+        None => today(),
+    };
+    let result = NotionService::new(&conn, &secrets)
+        .export_entry(body.entry_type, date)
+        .await
+        .map_err(actix_web::error::ErrorBadGateway)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": result})))
+}
+
+/// Cron endpoint: export today's journal (and weekly review on Sundays) for every connected user
+pub async fn export_all_notion_journals(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let secrets = secrets_store(&app_state)?;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for Notion export: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let date = today();
+    let mut exported = 0u64;
+    let mut retried = 0u64;
+    let mut failure_count = 0u64;
+
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        let service = NotionService::new(&conn, &secrets);
+        let settings = match service.get_settings().await {
+            Ok(Some(s)) if s.database_id.is_some() => s,
+            _ => continue,
+        };
+
+        let mut entries = Vec::new();
+        if settings.export_daily_journal {
+            entries.push(JournalEntryType::DailyJournal);
+        }
+        // Weekly reviews go out on the last day of the user's week
+        if settings.export_weekly_review && PeriodDefinition::load(&conn).await.is_week_end(date) {
+            entries.push(JournalEntryType::WeeklyReview);
+        }
+
+        for entry_type in entries {
+            match service.export_entry(entry_type, date).await {
+                Ok(result) if !result.skipped => exported += 1,
+                Ok(_) => {}
+                Err(e) => {
+                    failure_count += 1;
+                    error!("Notion {} export failed for user {}: {}", entry_type.as_str(), user_id, e);
+                }
+            }
+        }
+
+        retried += service.retry_failed_exports().await.unwrap_or(0) as u64;
+    }
+
+    let summary = serde_json::json!({
+        "exported": exported,
+        "retried": retried,
+        "failure_count": failure_count,
+    });
+    info!("Notion export completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
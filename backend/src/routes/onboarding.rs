@@ -0,0 +1,257 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::ai_service::data_formatter::DataFormatter;
+use crate::service::ai_service::upstash_vector_client::DataType;
+use crate::service::ai_service::vectorization_service::VectorizationService;
+use crate::service::demo_data::{self, RemovedDemoData, SeededDemoData};
+use crate::service::onboarding::{self, OnboardingProgress, OnboardingStep, StepAction};
+use crate::turso::AppState;
+use crate::websocket::{broadcast_to_user, ConnectionManager, EventType};
+
+/// Demo trades change every trade list and analytics result
+pub(crate) async fn invalidate_trade_caches(app: &AppState, user_id: &str) {
+    for table in ["stocks", "options"] {
+        app.cache_service.invalidate_table_cache(user_id, table).await.ok();
+    }
+    app.cache_service.invalidate_user_analytics(user_id).await.ok();
+}
+
+/// Embed the seeded trades and notes in the background so the AI chat can find them
+pub(crate) fn spawn_demo_vectorization(service: Arc<VectorizationService>, user_id: String, seeded: SeededDemoData) {
+    tokio::spawn(async move {
+        let trades = seeded
+            .stocks
+            .iter()
+            .map(|s| (DataType::Stock, s.id, DataFormatter::format_stock_for_embedding(s)))
+            .chain(seeded.options.iter().map(|o| (DataType::Option, o.id, DataFormatter::format_option_for_embedding(o))));
+        for (data_type, id, content) in trades {
+            if let Err(e) = service.vectorize_data(&user_id, data_type, &id.to_string(), &content).await {
+                error!("Failed to vectorize demo trade {} for user {}: {}", id, user_id, e);
+            }
+        }
+        for note in &seeded.notes {
+            if let Err(e) = service.vectorize_notebook_note(&user_id, note).await {
+                error!("Failed to vectorize demo note {} for user {}: {}", note.id, user_id, e);
+            }
+        }
+    });
+}
+
+fn spawn_demo_vector_removal(service: Arc<VectorizationService>, user_id: String, removed: RemovedDemoData) {
+    tokio::spawn(async move {
+        let trade_ids: Vec<String> = removed.stock_ids.iter().chain(&removed.option_ids).map(|id| id.to_string()).collect();
+        if let Err(e) = service.delete_vectors(&user_id, &trade_ids).await {
+            error!("Failed to delete demo trade vectors for user {}: {}", user_id, e);
+        }
+        for note_id in &removed.note_ids {
+            if let Err(e) = service.delete_notebook_note_vectors(&user_id, note_id).await {
+                error!("Failed to delete vectors for demo note {} (user {}): {}", note_id, user_id, e);
+            }
+        }
+    });
+}
+
+pub fn configure_onboarding_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/onboarding")
+        .route("/demo-data", web::get().to(demo_data_status))
+        .route("/demo-data", web::post().to(seed_demo_data))
+        .route("/demo-data", web::delete().to(remove_demo_data))
+        .route("/progress", web::get().to(get_progress))
+        .route("/steps/{step}/complete", web::post().to(complete_step))
+        .route("/steps/{step}/skip", web::post().to(skip_step))
+        .route("/steps/{step}/reopen", web::post().to(reopen_step))
+}
+
+/// Push the checklist to the user's other devices
+async fn broadcast_progress(ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>, user_id: &str, progress: &OnboardingProgress) {
+    broadcast_to_user(ws_manager, user_id, EventType::OnboardingUpdated, progress).await;
+}
+
+async fn get_progress(
+    app: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    req: HttpRequest,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match onboarding::progress(&conn).await {
+        Ok((progress, changed)) => {
+            if changed {
+                broadcast_progress(ws_manager, &user_id, &progress).await;
+            }
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": progress})))
+        }
+        Err(e) => {
+            error!("Failed to load onboarding progress: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load onboarding progress"})))
+        }
+    }
+}
+
+async fn update_step(
+    app: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    req: HttpRequest,
+    step: String,
+    action: StepAction,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let Some(step) = OnboardingStep::parse(&step) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Unknown onboarding step"})));
+    };
+    let conn = user_db_connection(&app, &user_id).await?;
+
+    let result: anyhow::Result<Result<OnboardingProgress, String>> = async {
+        let current = onboarding::step_status(&conn, step).await?;
+        let next = match onboarding::transition(current, action) {
+            Ok(next) => next,
+            Err(message) => return Ok(Err(message)),
+        };
+        if next != current {
+            onboarding::set_step(&conn, step, next).await?;
+        }
+        onboarding::progress(&conn).await.map(|(progress, _)| Ok(progress))
+    }
+    .await;
+
+    match result {
+        Ok(Ok(progress)) => {
+            broadcast_progress(ws_manager, &user_id, &progress).await;
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": progress})))
+        }
+        Ok(Err(message)) => Ok(HttpResponse::Conflict().json(serde_json::json!({"success": false, "message": message}))),
+        Err(e) => {
+            error!("Failed to update onboarding step {}: {}", step.as_str(), e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update onboarding step"})))
+        }
+    }
+}
+
+async fn complete_step(
+    app: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    update_step(app, ws_manager, req, path.into_inner(), StepAction::Complete).await
+}
+
+async fn skip_step(
+    app: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    update_step(app, ws_manager, req, path.into_inner(), StepAction::Skip).await
+}
+
+async fn reopen_step(
+    app: web::Data<AppState>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    update_step(app, ws_manager, req, path.into_inner(), StepAction::Reopen).await
+}
+
+async fn demo_data_status(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match demo_data::status(&conn).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": status}))),
+        Err(e) => {
+            error!("Failed to load demo data status: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load demo data status"})))
+        }
+    }
+}
+
+/// Seed the sample journal into an empty account
+async fn seed_demo_data(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let status = match demo_data::status(&conn).await {
+        Ok(status) => status,
+        Err(e) => {
+            error!("Failed to load demo data status: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load demo data status"})));
+        }
+    };
+    if status.seeded {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({"success": false, "message": "Demo data is already loaded"})));
+    }
+    if status.user_trades > 0 {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "success": false,
+            "message": "Demo data can only be added to a journal without trades"
+        })));
+    }
+
+    match demo_data::seed(&conn, Utc::now()).await {
+        Ok(seeded) => {
+            info!(
+                "Seeded demo data for user {}: {} stock trades, {} option trades, {} notes",
+                user_id,
+                seeded.stocks.len(),
+                seeded.options.len(),
+                seeded.notes.len()
+            );
+            invalidate_trade_caches(&app, &user_id).await;
+            let data = serde_json::json!({
+                "stock_trades": seeded.stocks.len(),
+                "option_trades": seeded.options.len(),
+                "notes": seeded.notes.len(),
+                "tags": seeded.tags,
+                "playbooks": seeded.playbooks,
+            });
+            spawn_demo_vectorization(app.vectorization_service.clone(), user_id, seeded);
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": data})))
+        }
+        Err(e) => {
+            // Rows created before the failure are tracked, so a teardown clears them
+            error!("Failed to seed demo data for user {}: {}", user_id, e);
+            invalidate_trade_caches(&app, &user_id).await;
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to seed demo data"})))
+        }
+    }
+}
+
+/// Remove everything the seed created
+async fn remove_demo_data(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match demo_data::teardown(&conn).await {
+        Ok(removed) => {
+            info!(
+                "Removed demo data for user {}: {} stock trades, {} option trades, {} notes",
+                user_id,
+                removed.stock_ids.len(),
+                removed.option_ids.len(),
+                removed.note_ids.len()
+            );
+            invalidate_trade_caches(&app, &user_id).await;
+            let data = serde_json::json!({
+                "stock_trades": removed.stock_ids.len(),
+                "option_trades": removed.option_ids.len(),
+                "notes": removed.note_ids.len(),
+                "tags": removed.tags,
+                "playbooks": removed.playbooks,
+            });
+            spawn_demo_vector_removal(app.vectorization_service.clone(), user_id, removed);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": data})))
+        }
+        Err(e) => {
+            error!("Failed to remove demo data for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to remove demo data"})))
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::bulk_operations::{self, UndoOutcome, MAX_BULK_IDS};
+use crate::turso::AppState;
+
+/// Deleted and restored trades change every trade list and analytics result
+async fn invalidate_trade_caches(app: &AppState, user_id: &str) {
+    for table in ["stocks", "options"] {
+        app.cache_service.invalidate_table_cache(user_id, table).await.ok();
+    }
+    app.cache_service.invalidate_user_analytics(user_id).await.ok();
+}
+
+pub fn configure_operation_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/operations")
+        .route("", web::get().to(list_operations))
+        .route("/bulk-delete", web::post().to(bulk_delete_trades))
+        .route("/{id}", web::get().to(get_operation))
+        .route("/{id}/undo", web::post().to(undo_operation))
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkDeleteRequest {
+    #[serde(default)]
+    stock_ids: Vec<i64>,
+    #[serde(default)]
+    option_ids: Vec<i64>,
+}
+
+impl BulkDeleteRequest {
+    fn validate(&self) -> Result<(), String> {
+        let total = self.stock_ids.len() + self.option_ids.len();
+        if total == 0 {
+            return Err("stock_ids or option_ids is required".to_string());
+        }
+        if total > MAX_BULK_IDS {
+            return Err(format!("At most {} trades can be deleted at once", MAX_BULK_IDS));
+        }
+        Ok(())
+    }
+}
+
+/// Operations that can still be undone
+async fn list_operations(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match bulk_operations::list_operations(&conn).await {
+        Ok(operations) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": operations}))),
+        Err(e) => {
+            error!("Failed to list bulk operations: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list operations"})))
+        }
+    }
+}
+
+async fn get_operation(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match bulk_operations::get_operation(&conn, &path.into_inner()).await {
+        Ok(Some(operation)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": operation}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Operation not found"}))),
+        Err(e) => {
+            error!("Failed to load bulk operation: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load operation"})))
+        }
+    }
+}
+
+/// Delete many trades at once; the response carries the operation to undo it with
+async fn bulk_delete_trades(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<BulkDeleteRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    match bulk_operations::bulk_delete_trades(&conn, &payload.stock_ids, &payload.option_ids).await {
+        Ok(Some(operation)) => {
+            info!("{} for user {} (operation {})", operation.description, user_id, operation.id);
+            invalidate_trade_caches(&app, &user_id).await;
+            // Same as a single delete: the trades drop out of chat retrieval. An undo brings
+            // them back to the journal; they are re-embedded on their next edit.
+            let ids: Vec<String> = payload.stock_ids.iter().chain(&payload.option_ids).map(|id| id.to_string()).collect();
+            let vectorization_service = app.vectorization_service.clone();
+            let user_id_clone = user_id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = vectorization_service.delete_vectors(&user_id_clone, &ids).await {
+                    error!("Failed to delete vectors for bulk-deleted trades of user {}: {}", user_id_clone, e);
+                }
+            });
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": operation})))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No matching trades found"}))),
+        Err(e) => {
+            error!("Failed to bulk delete trades for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to delete trades"})))
+        }
+    }
+}
+
+async fn undo_operation(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let id = path.into_inner();
+    match bulk_operations::undo(&conn, &id).await {
+        Ok(UndoOutcome::Restored(operation)) => {
+            info!("Undid operation {} for user {}", id, user_id);
+            invalidate_trade_caches(&app, &user_id).await;
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": operation})))
+        }
+        Ok(UndoOutcome::NotFound) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Operation not found"}))),
+        Ok(UndoOutcome::Unavailable(message)) => Ok(HttpResponse::Conflict().json(serde_json::json!({"success": false, "message": message}))),
+        Err(e) => {
+            error!("Failed to undo operation {} for user {}: {}", id, user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to undo operation"})))
+        }
+    }
+}
@@ -6,14 +6,21 @@ use crate::turso::{AppState, client::TursoClient};
 use crate::turso::config::{SupabaseConfig, SupabaseClaims};
 use crate::turso::auth::{validate_supabase_jwt_token, AuthError};
 use crate::models::options::{
-    OptionTrade, CreateOptionRequest, UpdateOptionRequest, OptionQuery
+    OptionTrade, CreateOptionRequest, UpdateOptionRequest, OptionQuery, TradeStatus
 };
 use crate::models::stock::stocks::TimeRange;
 use crate::service::cache_service::CacheService;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::trade_enrichment;
+use crate::service::trade_merge::{self, MergeRequest, SplitRequest};
+use crate::service::option_strategies;
 use crate::service::ai_service::vectorization_service::VectorizationService;
 use crate::service::ai_service::data_formatter::DataFormatter;
 use crate::service::ai_service::upstash_vector_client::DataType;
 use crate::websocket::{broadcast_option_update, ConnectionManager};
+use crate::service::notifications::loss_streak::spawn_loss_streak_check;
+use crate::service::notifications::overtrading::spawn_trade_counter_update;
+use crate::service::ai_service::setup_discovery::TradeAsset;
 use tokio::sync::Mutex;
 
 /// Response wrapper for API responses
@@ -182,7 +189,7 @@ pub async fn create_option(
     info!("📦 Raw request body: {}", String::from_utf8_lossy(&body));
     
     // Try to deserialize manually and log any errors
-    let payload: CreateOptionRequest = match serde_json::from_slice(&body) {
+    let mut payload: CreateOptionRequest = match serde_json::from_slice(&body) {
         Ok(p) => {
             info!("✅ Successfully deserialized payload: {:?}", p);
             p
@@ -211,9 +218,31 @@ pub async fn create_option(
             e
         })?;
 
+    let market_client = MarketClient::new(&app_state.config.finance_query)
+        .map_err(|e| error!("Failed to create market client: {}", e))
+        .ok();
+    if payload.implied_volatility <= 0.0
+        && let Some(client) = &market_client
+    {
+        trade_enrichment::fill_option_iv(client, &app_state.cache_service, &mut payload).await;
+    }
+
     match OptionTrade::create(&conn, payload).await {
         Ok(option) => {
             info!("Successfully created option with ID: {}", option.id);
+
+            if let Some(client) = market_client {
+                trade_enrichment::spawn_enrichment(
+                    conn.clone(),
+                    client,
+                    "option",
+                    option.id,
+                    option.symbol.clone(),
+                    option.entry_date,
+                    Some(option.implied_volatility),
+                    false,
+                );
+            }
             
             // Invalidate cache after successful creation
             let cache_service_clone = cache_service.get_ref().clone();
@@ -239,6 +268,13 @@ pub async fn create_option(
             tokio::spawn(async move {
                 broadcast_option_update(ws_manager_clone, &user_id_ws, "created", &option_ws).await;
             });
+            spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+
+            // A trade entered already closed can complete a losing streak
+            if option.status == TradeStatus::Closed {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id.clone(), TradeAsset::Option, option.id);
+            }
 
             // Vectorize the new option trade
             let vectorization_service_clone = vectorization_service.get_ref().clone();
@@ -370,6 +406,7 @@ pub async fn update_option(
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
     ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    app_state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let id = option_id.into_inner();
     info!("Updating option with ID: {}", id);
@@ -381,11 +418,16 @@ pub async fn update_option(
             info!("Successfully updated option with ID: {}", id);
             // Broadcast real-time update
             let ws_manager_clone = ws_manager.clone();
-            let user_id_ws = get_authenticated_user(&req, &supabase_config).await?.sub;
+            let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+            let user_id_ws = user_id.clone();
             let option_ws = option.clone();
             tokio::spawn(async move {
                 broadcast_option_update(ws_manager_clone, &user_id_ws, "updated", &option_ws).await;
             });
+            if option.status == TradeStatus::Closed {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id, TradeAsset::Option, option.id);
+            }
             Ok(HttpResponse::Ok().json(ApiResponse::success(option)))
         }
         Ok(None) => {
@@ -445,6 +487,237 @@ pub async fn delete_option(
     }
 }
 
+/// Clone an option trade, applying any fields in the body on top of the original. An empty
+/// body copies the trade as is.
+#[allow(clippy::too_many_arguments)]
+pub async fn duplicate_option(
+    req: HttpRequest,
+    option_id: web::Path<i64>,
+    body: web::Bytes,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    cache_service: web::Data<Arc<CacheService>>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let id = option_id.into_inner();
+    let overrides: UpdateOptionRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        UpdateOptionRequest::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&format!("Invalid request format: {}", e))));
+            }
+        }
+    };
+
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+    app_state.storage_quota_service.check_storage_quota(&user_id, &conn).await?;
+
+    match OptionTrade::duplicate(&conn, id, overrides).await {
+        Ok(Some(option)) => {
+            info!("Duplicated option {} as {}", id, option.id);
+
+            let cache_service_clone = cache_service.get_ref().clone();
+            let user_id_clone = user_id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = cache_service_clone.invalidate_table_cache(&user_id_clone, "options").await {
+                    error!("Failed to invalidate option cache for user {}: {}", user_id_clone, e);
+                }
+                if let Err(e) = cache_service_clone.invalidate_user_analytics(&user_id_clone).await {
+                    error!("Failed to invalidate analytics cache for user {}: {}", user_id_clone, e);
+                }
+            });
+
+            let ws_manager_clone = ws_manager.clone();
+            let user_id_ws = user_id.clone();
+            let option_ws = option.clone();
+            tokio::spawn(async move {
+                broadcast_option_update(ws_manager_clone, &user_id_ws, "created", &option_ws).await;
+            });
+
+            if option.status == TradeStatus::Closed {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id.clone(), TradeAsset::Option, option.id);
+            }
+
+            let vectorization_service_clone = vectorization_service.get_ref().clone();
+            let option_clone = option.clone();
+            tokio::spawn(async move {
+                let content = DataFormatter::format_option_for_embedding(&option_clone);
+                if let Err(e) = vectorization_service_clone
+                    .vectorize_data(&user_id, DataType::Option, &option_clone.id.to_string(), &content)
+                    .await
+                {
+                    error!("Failed to vectorize option {} for user {}: {}", option_clone.id, user_id, e);
+                }
+            });
+
+            Ok(HttpResponse::Created().json(ApiResponse::success(option)))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Option not found"))),
+        Err(e) => {
+            error!("Failed to duplicate option {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to duplicate option trade")))
+        }
+    }
+}
+
+/// Caches, websocket clients and vectors after a merge or split rewrote trade rows
+fn sync_restructured_options(
+    user_id: String,
+    changed: Vec<(OptionTrade, &'static str)>,
+    removed_ids: Vec<i64>,
+    cache_service: Arc<CacheService>,
+    vectorization_service: Arc<VectorizationService>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = cache_service.invalidate_table_cache(&user_id, "options").await {
+            error!("Failed to invalidate option cache for user {}: {}", user_id, e);
+        }
+        if let Err(e) = cache_service.invalidate_user_analytics(&user_id).await {
+            error!("Failed to invalidate analytics cache for user {}: {}", user_id, e);
+        }
+        for (option, event) in &changed {
+            broadcast_option_update(ws_manager.clone(), &user_id, event, option).await;
+        }
+        for id in &removed_ids {
+            broadcast_option_update(ws_manager.clone(), &user_id, "deleted", serde_json::json!({"id": id})).await;
+        }
+
+        if !removed_ids.is_empty() {
+            let ids: Vec<String> = removed_ids.iter().map(|id| id.to_string()).collect();
+            if let Err(e) = vectorization_service.delete_vectors(&user_id, &ids).await {
+                error!("Failed to delete vectors for merged options of user {}: {}", user_id, e);
+            }
+        }
+        for (option, _) in &changed {
+            let content = DataFormatter::format_option_for_embedding(option);
+            if let Err(e) = vectorization_service.vectorize_data(&user_id, DataType::Option, &option.id.to_string(), &content).await {
+                error!("Failed to vectorize option {} for user {}: {}", option.id, user_id, e);
+            }
+        }
+    });
+}
+
+/// Merge several rows that are really one trade, e.g. an import split across fills
+pub async fn merge_options(
+    req: HttpRequest,
+    payload: web::Json<MergeRequest>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+
+    match trade_merge::merge_options(&conn, &payload.ids).await {
+        Ok(Ok(merged)) => {
+            info!("Merged options {:?} into {}", merged.removed_ids, merged.trade.id);
+            sync_restructured_options(
+                user_id,
+                vec![(merged.trade.clone(), "updated")],
+                merged.removed_ids.clone(),
+                app_state.cache_service.clone(),
+                vectorization_service.get_ref().clone(),
+                ws_manager,
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(merged)))
+        }
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&message))),
+        Err(e) => {
+            error!("Failed to merge options {:?}: {}", payload.ids, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to merge option trades")))
+        }
+    }
+}
+
+/// Split one row into several trades by contract count
+pub async fn split_option(
+    req: HttpRequest,
+    option_id: web::Path<i64>,
+    payload: web::Json<SplitRequest>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let id = option_id.into_inner();
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+    app_state.storage_quota_service.check_storage_quota(&user_id, &conn).await?;
+
+    match trade_merge::split_option(&conn, id, &payload.parts).await {
+        Ok(Ok(trades)) => {
+            info!("Split option {} into {} trades", id, trades.len());
+            let changed = trades.iter().map(|t| (t.clone(), if t.id == id { "updated" } else { "created" })).collect();
+            sync_restructured_options(
+                user_id,
+                changed,
+                Vec::new(),
+                app_state.cache_service.clone(),
+                vectorization_service.get_ref().clone(),
+                ws_manager,
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(trades)))
+        }
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&message))),
+        Err(e) => {
+            error!("Failed to split option {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to split option trade")))
+        }
+    }
+}
+
+/// Group imported single-leg rows into spreads, strangles and condors
+pub async fn recognize_option_strategies(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+
+    match option_strategies::recognize(&conn).await {
+        Ok(recognized) => {
+            info!("Recognized {} option strategies for user {}", recognized.len(), user_id);
+            if !recognized.is_empty() {
+                app_state.cache_service.invalidate_table_cache(&user_id, "options").await.ok();
+                app_state.cache_service.invalidate_user_analytics(&user_id).await.ok();
+            }
+            Ok(HttpResponse::Ok().json(ApiResponse::success(recognized)))
+        }
+        Err(e) => {
+            error!("Failed to recognize option strategies for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to recognize option strategies")))
+        }
+    }
+}
+
+/// The multi-leg strategy an option trade belongs to
+pub async fn get_option_strategy(
+    req: HttpRequest,
+    option_id: web::Path<i64>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let id = option_id.into_inner();
+    let conn = get_user_db_connection(&req, &turso_client, &supabase_config).await?;
+
+    match option_strategies::strategy_for(&conn, id).await {
+        Ok(Some(strategy)) => Ok(HttpResponse::Ok().json(ApiResponse::success(strategy))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Option is not part of a recognized strategy"))),
+        Err(e) => {
+            error!("Failed to load strategy for option {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to load option strategy")))
+        }
+    }
+}
+
 /// Get total count of options for pagination
 pub async fn get_options_count(
     req: HttpRequest,
@@ -966,6 +1239,8 @@ pub fn configure_options_routes(cfg: &mut web::ServiceConfig) {
     info!("Setting up /api/options routes");
     cfg.service(
         web::scope("/api/options")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::fields::fields_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::etag::etag_middleware))
             // Test route
             .route("/test", web::get().to(test_endpoint))
             
@@ -973,9 +1248,14 @@ pub fn configure_options_routes(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(create_option))                    // POST /api/options
             .route("", web::get().to(get_all_options))                   // GET /api/options?filters
             .route("/count", web::get().to(get_options_count))           // GET /api/options/count
+            .route("/merge", web::post().to(merge_options))              // POST /api/options/merge
+            .route("/strategies/recognize", web::post().to(recognize_option_strategies)) // POST /api/options/strategies/recognize
             .route("/{id}", web::get().to(get_option_by_id))             // GET /api/options/{id}
             .route("/{id}", web::put().to(update_option))                // PUT /api/options/{id}
             .route("/{id}", web::delete().to(delete_option))             // DELETE /api/options/{id}
+            .route("/{id}/duplicate", web::post().to(duplicate_option))  // POST /api/options/{id}/duplicate
+            .route("/{id}/split", web::post().to(split_option))          // POST /api/options/{id}/split
+            .route("/{id}/strategy", web::get().to(get_option_strategy)) // GET /api/options/{id}/strategy
             
             // Analytics endpoints
             .route("/analytics", web::get().to(get_options_analytics))   // GET /api/options/analytics?time_range=
@@ -0,0 +1,378 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use libsql::Connection;
+use serde::Deserialize;
+
+use crate::models::stock::stocks::TimeRange;
+use crate::routes::common::require_user_id;
+use crate::service::organization_library::{self, OrganizationLibrary, SharedPlaybookRequest, SharedTagRequest};
+use crate::service::organizations::{OrganizationError, OrganizationRole, OrganizationService, UpdateMembershipRequest};
+use crate::turso::AppState;
+
+async fn registry_conn(app: &web::Data<AppState>) -> actix_web::Result<Connection> {
+    app.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)
+}
+
+fn error_response(e: OrganizationError) -> HttpResponse {
+    let body = |message: String| serde_json::json!({"success": false, "message": message});
+    match e {
+        OrganizationError::NotFound => HttpResponse::NotFound().json(body(e.to_string())),
+        OrganizationError::Forbidden(message) => HttpResponse::Forbidden().json(body(message)),
+        OrganizationError::Invalid(message) => HttpResponse::BadRequest().json(body(message)),
+        OrganizationError::Database(e) => {
+            log::error!("Organization request failed: {}", e);
+            HttpResponse::InternalServerError().json(body("Internal server error".to_string()))
+        }
+    }
+}
+
+fn respond<T: serde::Serialize>(result: Result<T, OrganizationError>) -> HttpResponse {
+    match result {
+        Ok(data) => HttpResponse::Ok().json(serde_json::json!({"success": true, "data": data})),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Provision shared playbooks and tags into every member's database in the background
+fn spawn_organization_sync(app: &web::Data<AppState>, organization_id: String) {
+    let (turso_client, cache) = (app.turso_client.clone(), app.cache_service.clone());
+    tokio::spawn(async move {
+        match organization_library::sync_organization(&turso_client, &cache, &organization_id).await {
+            Ok(count) => log::info!("Provisioned shared library of {} into {} member databases", organization_id, count),
+            Err(e) => log::warn!("Failed to provision shared library of {}: {}", organization_id, e),
+        }
+    });
+}
+
+pub fn configure_organization_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/organizations")
+        .route("", web::get().to(list_organizations))
+        .route("", web::post().to(create_organization))
+        .route("/join", web::post().to(join_organization))
+        .route("/{id}/members", web::get().to(list_members))
+        .route("/{id}/members/{user_id}", web::delete().to(remove_member))
+        .route("/{id}/membership", web::put().to(update_membership))
+        .route("/{id}/invites", web::post().to(create_invite))
+        .route("/{id}/analytics", web::get().to(get_team_analytics))
+        .route("/{id}/playbooks", web::get().to(list_shared_playbooks))
+        .route("/{id}/playbooks", web::post().to(create_shared_playbook))
+        .route("/{id}/playbooks/{playbook_id}", web::put().to(update_shared_playbook))
+        .route("/{id}/playbooks/{playbook_id}", web::delete().to(delete_shared_playbook))
+        .route("/{id}/tags", web::get().to(list_shared_tags))
+        .route("/{id}/tags", web::post().to(create_shared_tag))
+        .route("/{id}/tags/{tag_id}", web::put().to(update_shared_tag))
+        .route("/{id}/tags/{tag_id}", web::delete().to(delete_shared_tag))
+        .route("/{id}/sync", web::post().to(sync_shared_library))
+}
+
+/// Organizations the caller belongs to, with their role in each
+async fn list_organizations(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    Ok(respond(OrganizationService::new(&registry).list_for_user(&user_id).await))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateOrganizationRequest {
+    name: String,
+}
+
+/// Create an organization; the caller becomes its lead
+async fn create_organization(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<CreateOrganizationRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    match OrganizationService::new(&registry).create(&user_id, &payload.name).await {
+        Ok(organization) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": organization}))),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JoinRequest {
+    code: String,
+    display_name: Option<String>,
+}
+
+/// Join with an invite code. New members appear anonymized until they opt in to being named.
+async fn join_organization(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<JoinRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    let payload = payload.into_inner();
+    let result = OrganizationService::new(&registry).join(&user_id, &payload.code, payload.display_name).await;
+    if let Ok(organization) = &result {
+        let (turso_client, cache) = (app.turso_client.clone(), app.cache_service.clone());
+        let organization_id = organization.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = organization_library::sync_member(&turso_client, &cache, &organization_id, &user_id).await {
+                log::warn!("Failed to provision shared library for a new member of {}: {}", organization_id, e);
+            }
+        });
+    }
+    Ok(respond(result))
+}
+
+/// Member list (leads only)
+async fn list_members(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    Ok(respond(OrganizationService::new(&registry).members_for(&path, &user_id).await))
+}
+
+/// Remove a member, or leave when the member is the caller
+async fn remove_member(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let (organization_id, member_id) = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    let result = OrganizationService::new(&registry).remove_member(&organization_id, &user_id, &member_id).await;
+    if result.is_ok() {
+        // Shared items stay in the former member's journal as personal items
+        let (turso_client, cache) = (app.turso_client.clone(), app.cache_service.clone());
+        tokio::spawn(async move {
+            if let Err(e) = organization_library::release_member(&turso_client, &cache, &organization_id, &member_id).await {
+                log::warn!("Failed to detach shared library of {} from a former member: {}", organization_id, e);
+            }
+        });
+    }
+    Ok(respond(result.map(|()| serde_json::json!({"removed": true}))))
+}
+
+/// The caller's display name and whether they appear by name in team analytics
+async fn update_membership(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<UpdateMembershipRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    let result = OrganizationService::new(&registry).update_membership(&path, &user_id, payload.into_inner()).await;
+    Ok(respond(result.map(|()| serde_json::json!({"updated": true}))))
+}
+
+#[derive(Debug, Deserialize)]
+struct InviteRequest {
+    /// Defaults to member
+    role: Option<OrganizationRole>,
+}
+
+/// Single-use invite code, valid for a week (leads only)
+async fn create_invite(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: Option<web::Json<InviteRequest>>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let role = payload.and_then(|p| p.into_inner().role).unwrap_or(OrganizationRole::Member);
+    let registry = registry_conn(&app).await?;
+    match OrganizationService::new(&registry).create_invite(&path, &user_id, role).await {
+        Ok(invite) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": invite}))),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamAnalyticsQuery {
+    /// Same values as the analytics endpoints, defaults to 30d
+    time_range: Option<String>,
+    /// `named` shows the names of members who agreed to it; the default is anonymized
+    view: Option<String>,
+}
+
+/// Aggregated member analytics (leads only)
+async fn get_team_analytics(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    query: web::Query<TeamAnalyticsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let time_range = match query.time_range.as_deref() {
+        Some(value) => match TimeRange::parse(value) {
+            Some(range) => range,
+            None => {
+                return Ok(HttpResponse::BadRequest()
+                    .json(serde_json::json!({"success": false, "message": format!("Invalid time_range '{}'", value)})));
+            }
+        },
+        None => TimeRange::ThirtyDays,
+    };
+    let named = query.view.as_deref() == Some("named");
+    let registry = registry_conn(&app).await?;
+    let result = OrganizationService::new(&registry)
+        .team_analytics(&app.turso_client, &path, &user_id, &time_range, named)
+        .await;
+    Ok(respond(result))
+}
+
+/// Playbooks shared with the organization (any member)
+async fn list_shared_playbooks(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    Ok(respond(OrganizationLibrary::new(&registry).playbooks_for(&path, &user_id).await))
+}
+
+/// Share a new playbook with its rules (leads only)
+async fn create_shared_playbook(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<SharedPlaybookRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let organization_id = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    match OrganizationLibrary::new(&registry).save_playbook(&organization_id, &user_id, None, payload.into_inner()).await {
+        Ok(playbook) => {
+            spawn_organization_sync(&app, organization_id);
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": playbook})))
+        }
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// Replace a shared playbook, rules included (leads only)
+async fn update_shared_playbook(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Json<SharedPlaybookRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let (organization_id, playbook_id) = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    let result = OrganizationLibrary::new(&registry)
+        .save_playbook(&organization_id, &user_id, Some(&playbook_id), payload.into_inner())
+        .await;
+    if result.is_ok() {
+        spawn_organization_sync(&app, organization_id);
+    }
+    Ok(respond(result))
+}
+
+/// Stop sharing a playbook; members keep it as a personal playbook (leads only)
+async fn delete_shared_playbook(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let (organization_id, playbook_id) = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    match OrganizationLibrary::new(&registry).delete_playbook(&organization_id, &user_id, &playbook_id).await {
+        Ok(true) => {
+            spawn_organization_sync(&app, organization_id);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"deleted": true}})))
+        }
+        Ok(false) => Ok(error_response(OrganizationError::Invalid("Shared playbook not found".to_string()))),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// Tags shared with the organization (any member)
+async fn list_shared_tags(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_conn(&app).await?;
+    Ok(respond(OrganizationLibrary::new(&registry).tags_for(&path, &user_id).await))
+}
+
+/// Share a new tag, e.g. a mistake category the desk tracks (leads only)
+async fn create_shared_tag(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<SharedTagRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let organization_id = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    match OrganizationLibrary::new(&registry).save_tag(&organization_id, &user_id, None, payload.into_inner()).await {
+        Ok(tag) => {
+            spawn_organization_sync(&app, organization_id);
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": tag})))
+        }
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// Update a shared tag (leads only). Renaming detaches the old name from member journals.
+async fn update_shared_tag(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Json<SharedTagRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let (organization_id, tag_id) = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    let result = OrganizationLibrary::new(&registry)
+        .save_tag(&organization_id, &user_id, Some(&tag_id), payload.into_inner())
+        .await;
+    if result.is_ok() {
+        spawn_organization_sync(&app, organization_id);
+    }
+    Ok(respond(result))
+}
+
+/// Stop sharing a tag; members keep it as a personal tag (leads only)
+async fn delete_shared_tag(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let (organization_id, tag_id) = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    match OrganizationLibrary::new(&registry).delete_tag(&organization_id, &user_id, &tag_id).await {
+        Ok(true) => {
+            spawn_organization_sync(&app, organization_id);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"deleted": true}})))
+        }
+        Ok(false) => Ok(error_response(OrganizationError::Invalid("Shared tag not found".to_string()))),
+        Err(e) => Ok(error_response(e)),
+    }
+}
+
+/// Provision the shared library into the caller's own database now, instead of waiting
+/// for the next change
+async fn sync_shared_library(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let organization_id = path.into_inner();
+    let registry = registry_conn(&app).await?;
+    if let Err(e) = OrganizationService::new(&registry).role_of(&organization_id, &user_id).await {
+        return Ok(error_response(e));
+    }
+    let result = organization_library::sync_member(&app.turso_client, &app.cache_service, &organization_id, &user_id).await;
+    Ok(respond(result.map_err(OrganizationError::Database)))
+}
@@ -15,6 +15,8 @@ use crate::turso::config::{SupabaseClaims, SupabaseConfig};
 use crate::turso::auth::AuthError;
 use crate::service::cache_service::CacheService;
 use crate::service::analytics_engine::playbook_analytics::calculate_playbook_analytics;
+use crate::service::playbook_targets::{self, PlaybookTarget};
+use crate::middleware::cron_auth::verify_cron_secret;
 use crate::websocket::{broadcast_playbook_update, ConnectionManager};
 use tokio::sync::Mutex;
 use actix_web::web::Data;
@@ -115,6 +117,26 @@ async fn get_user_database_connection(
         .ok_or_else(|| actix_web::error::ErrorNotFound("User database not found"))
 }
 
+/// 403 for playbooks shared by an organization; they're managed by the team lead
+async fn reject_organization_playbook(conn: &Connection, playbook_id: &str) -> Option<HttpResponse> {
+    match Playbook::is_organization_shared(conn, playbook_id).await {
+        Ok(false) => None,
+        Ok(true) => Some(HttpResponse::Forbidden().json(serde_json::json!({
+            "success": false,
+            "message": "This playbook is shared by your organization and can only be changed by a team lead",
+            "data": null
+        }))),
+        Err(e) => {
+            log::error!("Failed to check playbook ownership: {}", e);
+            Some(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to check playbook",
+                "data": null
+            })))
+        }
+    }
+}
+
 /// Create a new playbook setup
 /// Create a playbook with cache invalidation
 pub async fn create_playbook(
@@ -370,6 +392,10 @@ pub async fn update_playbook(
 
     let conn = get_user_database_connection(user_id, &turso_client).await?;
 
+    if let Some(response) = reject_organization_playbook(&conn, &playbook_id).await {
+        return Ok(response);
+    }
+
     match Playbook::update(&conn, &playbook_id, payload.into_inner()).await {
         Ok(Some(playbook)) => {
             // Broadcast update via WebSocket
@@ -414,6 +440,10 @@ pub async fn delete_playbook(
 
     let conn = get_user_database_connection(user_id, &turso_client).await?;
 
+    if let Some(response) = reject_organization_playbook(&conn, &playbook_id).await {
+        return Ok(response);
+    }
+
     match Playbook::delete(&conn, &playbook_id).await {
         Ok(true) => {
             // Broadcast delete
@@ -699,6 +729,10 @@ pub fn configure_playbook_routes(cfg: &mut web::ServiceConfig) {
             .route("/{id}/missed-trades", web::post().to(create_missed_trade))
             .route("/{id}/missed-trades", web::get().to(get_missed_trades))
             .route("/{id}/missed-trades/{missed_id}", web::delete().to(delete_missed_trade))
+            // Performance targets and degradation alerts
+            .route("/{id}/targets", web::get().to(get_playbook_targets))
+            .route("/{id}/targets", web::put().to(set_playbook_targets))
+            .route("/{id}/targets", web::delete().to(delete_playbook_targets))
     );
 }
 
@@ -716,6 +750,10 @@ async fn create_playbook_rule(
 
     let conn = get_user_database_connection(user_id, &turso_client).await?;
 
+    if let Some(response) = reject_organization_playbook(&conn, &playbook_id).await {
+        return Ok(response);
+    }
+
     match PlaybookRule::create(&conn, &playbook_id, payload.into_inner()).await {
         Ok(rule) => Ok(HttpResponse::Created().json(serde_json::json!({
             "success": true,
@@ -785,12 +823,21 @@ async fn delete_playbook_rule(
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
 ) -> ActixResult<HttpResponse> {
-    let (_playbook_id, rule_id) = paths.into_inner();
+    let (playbook_id, rule_id) = paths.into_inner();
     let claims = get_authenticated_user(&req, &supabase_config).await?;
     let user_id = &claims.sub;
 
     let conn = get_user_database_connection(user_id, &turso_client).await?;
 
+    // The rule's own playbook decides, not the one in the path
+    let playbook_id = match PlaybookRule::find_by_id(&conn, &rule_id).await {
+        Ok(Some(rule)) => rule.playbook_id,
+        _ => playbook_id,
+    };
+    if let Some(response) = reject_organization_playbook(&conn, &playbook_id).await {
+        return Ok(response);
+    }
+
     match PlaybookRule::delete(&conn, &rule_id).await {
         Ok(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
             "success": true,
@@ -921,3 +968,128 @@ async fn get_all_playbooks_analytics(
         }
     }
 }
+
+/// Targets for a playbook with the current rolling numbers against them
+async fn get_playbook_targets(
+    req: HttpRequest,
+    path: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+    let playbook_id = path.into_inner();
+
+    let target = playbook_targets::get_target(&conn, &playbook_id).await.map_err(|e| {
+        error!("Failed to load targets for playbook {}: {}", playbook_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to load playbook targets")
+    })?;
+    let Some(target) = target else {
+        return Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": null})));
+    };
+    let status = playbook_targets::status(&conn, &target).await.map_err(|e| {
+        error!("Failed to evaluate targets for playbook {}: {}", playbook_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to evaluate playbook targets")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"target": target, "status": status}})))
+}
+
+/// Set a playbook's minimum win rate and/or average R
+async fn set_playbook_targets(
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<PlaybookTarget>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+    let playbook_id = path.into_inner();
+
+    let target = PlaybookTarget { playbook_id: playbook_id.clone(), ..payload.into_inner() };
+    if let Err(message) = target.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    match Playbook::exists(&conn, &playbook_id).await {
+        Ok(true) => {}
+        Ok(false) => return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Playbook not found"}))),
+        Err(e) => {
+            error!("Failed to look up playbook {}: {}", playbook_id, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to save playbook targets"})));
+        }
+    }
+    playbook_targets::save_target(&conn, &target).await.map_err(|e| {
+        error!("Failed to save targets for playbook {}: {}", playbook_id, e);
+        actix_web::error::ErrorInternalServerError("Failed to save playbook targets")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": target})))
+}
+
+async fn delete_playbook_targets(
+    req: HttpRequest,
+    path: web::Path<String>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> ActixResult<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+    let playbook_id = path.into_inner();
+
+    match playbook_targets::delete_target(&conn, &playbook_id).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Playbook targets removed"}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No targets set for this playbook"}))),
+        Err(e) => {
+            error!("Failed to delete targets for playbook {}: {}", playbook_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to remove playbook targets"})))
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct PlaybookTargetRunSummary {
+    users_checked: u64,
+    users_failed: u64,
+    alerts_sent: u64,
+}
+
+/// Cron endpoint: evaluate every user's playbook targets and alert on newly degraded setups
+pub async fn run_all_playbook_target_checks(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+) -> ActixResult<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_ids = app_state.turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for playbook target checks: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut summary = PlaybookTargetRunSummary::default();
+    for user_id in user_ids {
+        let Ok(Some(conn)) = app_state.turso_client.get_user_database_connection(&user_id).await else {
+            summary.users_failed += 1;
+            continue;
+        };
+        match playbook_targets::evaluate_all(&conn).await {
+            Ok(alerts) => {
+                summary.users_checked += 1;
+                for alert in alerts {
+                    info!("Playbook {} degraded for user {}: {:?}", alert.status.playbook_id, user_id, alert.status.failing);
+                    match playbook_targets::notify(&conn, &app_state.config.web_push, &user_id, &alert).await {
+                        Ok(()) => summary.alerts_sent += 1,
+                        Err(e) => error!("Failed to send playbook target alert to user {}: {}", user_id, e),
+                    }
+                }
+            }
+            Err(e) => {
+                summary.users_failed += 1;
+                error!("Playbook target check failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    info!(
+        "Playbook target checks: {} users, {} alerts, {} failed",
+        summary.users_checked, summary.alerts_sent, summary.users_failed
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
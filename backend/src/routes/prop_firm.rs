@@ -0,0 +1,86 @@
+use actix_web::{web, HttpResponse, Scope};
+use serde::Deserialize;
+
+use crate::models::prop_firm::{EvaluationProfile, CreateEvaluationProfileRequest, UpdateEvaluationProfileRequest};
+use crate::models::stock::stocks::TimeRange;
+use crate::routes::common::user_connection;
+use crate::service::analytics_engine::prop_firm_evaluator::evaluate_profile;
+use crate::turso::AppState;
+
+pub fn configure_prop_firm_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/prop-firm")
+        .route("/profiles", web::get().to(list_profiles))
+        .route("/profiles", web::post().to(create_profile))
+        .route("/profiles/{id}", web::get().to(get_profile))
+        .route("/profiles/{id}", web::put().to(update_profile))
+        .route("/profiles/{id}", web::delete().to(delete_profile))
+        .route("/profiles/{id}/evaluate", web::post().to(run_profile_evaluation))
+}
+
+async fn list_profiles(app: web::Data<AppState>, req: actix_web::HttpRequest) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let profiles = EvaluationProfile::find_all(&conn).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": profiles})))
+}
+
+async fn create_profile(app: web::Data<AppState>, req: actix_web::HttpRequest, body: web::Json<CreateEvaluationProfileRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let body = body.into_inner();
+    body.validate().map_err(actix_web::error::ErrorBadRequest)?;
+    let profile = EvaluationProfile::create(&conn, body).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": profile})))
+}
+
+async fn get_profile(app: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let profile = EvaluationProfile::find_by_id(&conn, &path.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Evaluation profile not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": profile})))
+}
+
+async fn update_profile(app: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>, body: web::Json<UpdateEvaluationProfileRequest>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let profile = EvaluationProfile::update(&conn, &path.into_inner(), body.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorBadRequest)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Evaluation profile not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": profile})))
+}
+
+async fn delete_profile(app: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let removed = EvaluationProfile::delete(&conn, &path.into_inner()).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    if !removed {
+        return Err(actix_web::error::ErrorNotFound("Evaluation profile not found"));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true})))
+}
+
+#[derive(Deserialize)]
+struct EvaluateReq {
+    /// "30d", "90d", "ytd", ... defaults to the last 30 days
+    time_range: Option<String>,
+}
+
+async fn run_profile_evaluation(app: web::Data<AppState>, req: actix_web::HttpRequest, path: web::Path<String>, body: Option<web::Json<EvaluateReq>>) -> actix_web::Result<HttpResponse> {
+    let conn = user_connection(&app, &req).await?;
+    let profile = EvaluationProfile::find_by_id(&conn, &path.into_inner())
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Evaluation profile not found"))?;
+
+    let time_range = body
+        .as_ref()
+        .and_then(|b| b.time_range.as_deref())
+        .and_then(TimeRange::parse)
+        .unwrap_or(TimeRange::ThirtyDays);
+
+    let result = evaluate_profile(&conn, &profile, &time_range).await.map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": result})))
+}
@@ -1,15 +1,11 @@
-use actix_web::{web, HttpResponse, Scope, HttpMessage};
+use actix_web::{web, HttpResponse, Scope};
 use serde::Deserialize;
 
+use crate::routes::common::get_user_id_from_ext;
 use crate::turso::{AppState};
 use crate::turso::config::WebPushConfig;
 use crate::service::notifications::push::{PushService, SaveSubscriptionRequest, PushPayload};
 
-fn get_user_id_from_ext(req: &actix_web::HttpRequest) -> Option<String> {
-    // Simplified: in this codebase, claims are inserted in extensions
-    req.extensions().get::<crate::turso::config::SupabaseClaims>().map(|c| c.sub.clone())
-}
-
 pub fn configure_push_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(api_scope());
 }
@@ -0,0 +1,67 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::models::options::OptionTrade;
+use crate::models::stock::stocks::Stock;
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct RecalculatePnlQuery {
+    /// Limit the backfill to one tenant; every active tenant otherwise
+    pub user_id: Option<String>,
+}
+
+/// Admin endpoint: recompute the stored `realized_pnl` of every stock and option trade
+pub async fn recalculate_pnl(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<RecalculatePnlQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+
+    let user_ids = match &query.user_id {
+        Some(user_id) => vec![user_id.clone()],
+        None => app_state.turso_client.list_active_user_ids().await.map_err(|e| {
+            error!("Failed to list active users for P&L recalculation: {}", e);
+            actix_web::error::ErrorInternalServerError("Registry query failed")
+        })?,
+    };
+
+    let mut tenants = 0u64;
+    let mut stocks_updated = 0u64;
+    let mut options_updated = 0u64;
+    let mut failure_count = 0u64;
+
+    for user_id in user_ids {
+        let conn = match app_state.turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => continue,
+        };
+        tenants += 1;
+        match Stock::recalculate_realized_pnl(&conn).await {
+            Ok(count) => stocks_updated += count as u64,
+            Err(e) => {
+                failure_count += 1;
+                error!("Stock P&L recalculation failed for user {}: {}", user_id, e);
+            }
+        }
+        match OptionTrade::recalculate_realized_pnl(&conn).await {
+            Ok(count) => options_updated += count as u64,
+            Err(e) => {
+                failure_count += 1;
+                error!("Option P&L recalculation failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    let summary = serde_json::json!({
+        "tenants": tenants,
+        "stocks_updated": stocks_updated,
+        "options_updated": options_updated,
+        "failure_count": failure_count,
+    });
+    info!("Realized P&L recalculation completed: {:?}", summary);
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
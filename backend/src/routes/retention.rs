@@ -0,0 +1,143 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::retention::{self, RetentionReport};
+use crate::turso::{AppState, client::TursoClient};
+
+pub fn configure_retention_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/retention")
+        .route("", web::get().to(list_policies))
+        .route("/preview", web::get().to(preview))
+        .route("/{table}", web::put().to(update_policy))
+}
+
+async fn list_policies(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match retention::load_policies(&conn).await {
+        Ok(policies) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": policies}))),
+        Err(e) => {
+            error!("Failed to load retention policies: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load retention policies"})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdatePolicyRequest {
+    /// null keeps rows forever
+    retention_days: Option<i64>,
+}
+
+async fn update_policy(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<UpdatePolicyRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let table = path.into_inner();
+    if retention::target(&table).is_none() {
+        return Ok(HttpResponse::NotFound()
+            .json(serde_json::json!({"success": false, "message": format!("No retention policy for table '{}'", table)})));
+    }
+    if let Err(message) = retention::validate_days(payload.retention_days) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    match retention::set_policy(&conn, &table, payload.retention_days).await {
+        Ok(()) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": retention::load_policies(&conn).await.unwrap_or_default()}))),
+        Err(e) => {
+            error!("Failed to update retention policy for {}: {}", table, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update retention policy"})))
+        }
+    }
+}
+
+/// What the next cleanup run would delete under the current policies
+async fn preview(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match retention::apply_retention(&conn, Utc::now(), true).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report}))),
+        Err(e) => {
+            error!("Retention preview failed: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to preview retention"})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunRetentionQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct RetentionRunSummary {
+    dry_run: bool,
+    users_checked: u64,
+    users_failed: u64,
+    rows: u64,
+    /// Users with anything to purge
+    reports: Vec<(String, RetentionReport)>,
+}
+
+/// Cron endpoint: enforce every user's retention policies (`?dry_run=true` to only report)
+pub async fn run_all_retention(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    query: web::Query<RunRetentionQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for retention: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let now = Utc::now();
+    let mut summary = RetentionRunSummary { dry_run: query.dry_run, ..Default::default() };
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            summary.users_failed += 1;
+            continue;
+        };
+        match retention::apply_retention(&conn, now, query.dry_run).await {
+            Ok(report) => {
+                summary.users_checked += 1;
+                if report.total_rows > 0 {
+                    summary.rows += report.total_rows;
+                    if !query.dry_run {
+                        for table in &report.tables {
+                            app_state.cache_service.invalidate_table_cache(&user_id, &table.table_name).await.ok();
+                        }
+                    }
+                    summary.reports.push((user_id, report));
+                }
+            }
+            Err(e) => {
+                summary.users_failed += 1;
+                error!("Retention failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    info!(
+        "Retention {}: {} users, {} rows{}",
+        if query.dry_run { "dry run" } else { "completed" },
+        summary.users_checked,
+        summary.rows,
+        if query.dry_run { " would be deleted" } else { " deleted" }
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
@@ -0,0 +1,81 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use serde::Deserialize;
+
+use crate::routes::common::require_user_id;
+use crate::service::margin::estimate_margin;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::stress_test::{run_stress_test, StressScenario, MAX_SCENARIOS};
+use crate::turso::AppState;
+
+pub fn configure_risk_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/risk")
+        .route("/stress", web::post().to(stress_test))
+        .route("/margin", web::get().to(margin_estimate))
+}
+
+#[derive(Debug, Deserialize)]
+struct StressRequest {
+    /// Market -3%, sector -5% and volatility +50% when omitted
+    scenarios: Option<Vec<StressScenario>>,
+}
+
+/// Estimated P&L of the open book under shock scenarios, per position and in total
+async fn stress_test(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: Option<web::Json<StressRequest>>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let scenarios = payload
+        .and_then(|p| p.into_inner().scenarios)
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(StressScenario::defaults);
+    if scenarios.len() > MAX_SCENARIOS {
+        return Ok(HttpResponse::BadRequest()
+            .json(serde_json::json!({"success": false, "message": format!("At most {} scenarios per request", MAX_SCENARIOS)})));
+    }
+    if let Some(message) = scenarios.iter().find_map(|s| s.validate().err()) {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+
+    let conn = app
+        .turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))?;
+    let market = MarketClient::new(&app.config.finance_query).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match run_stress_test(&conn, &market, scenarios).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report}))),
+        Err(e) => {
+            log::error!("Stress test failed: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to run stress test"})))
+        }
+    }
+}
+
+/// Estimated initial and maintenance margin of the open book, with utilization of the
+/// account equity when it is known
+async fn margin_estimate(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = app
+        .turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))?;
+    let market = MarketClient::new(&app.config.finance_query).map_err(actix_web::error::ErrorInternalServerError)?;
+
+    match estimate_margin(&conn, &market).await {
+        Ok(report) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": report}))),
+        Err(e) => {
+            log::error!("Margin estimate failed: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to estimate margin"})))
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::{error, info};
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::onboarding::{invalidate_trade_caches, spawn_demo_vectorization};
+use crate::service::sandbox;
+use crate::turso::AppState;
+
+/// Public: the sandbox token for the API docs playground
+pub async fn get_sandbox() -> actix_web::Result<HttpResponse> {
+    let (Some(token), Some(user_id)) = (sandbox::token(), sandbox::user_id()) else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "The API sandbox is not enabled"})));
+    };
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {
+            "token": token,
+            "user_id": user_id,
+            "note": "Shared demo journal, reset nightly. Broker, integration, notification, sharing and account endpoints are unavailable.",
+        }
+    })))
+}
+
+/// Cron: drop the sandbox tenant and reseed the demo journal
+pub async fn reset_sandbox(app_state: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let Some(user_id) = sandbox::user_id() else {
+        return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "The API sandbox is not enabled"})));
+    };
+
+    match sandbox::reset(&app_state.turso_client, &app_state.account_deletion_service).await {
+        Ok(seeded) => {
+            info!(
+                "Reset sandbox tenant {}: {} stock trades, {} option trades, {} notes",
+                user_id,
+                seeded.stocks.len(),
+                seeded.options.len(),
+                seeded.notes.len()
+            );
+            invalidate_trade_caches(&app_state, user_id).await;
+            let data = serde_json::json!({
+                "stock_trades": seeded.stocks.len(),
+                "option_trades": seeded.options.len(),
+                "notes": seeded.notes.len(),
+            });
+            spawn_demo_vectorization(app_state.vectorization_service.clone(), user_id.to_string(), seeded);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": data})))
+        }
+        Err(e) => {
+            error!("Failed to reset the sandbox tenant: {:#}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to reset the sandbox"})))
+        }
+    }
+}
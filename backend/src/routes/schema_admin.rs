@@ -0,0 +1,45 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::error;
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct MigrateSchemaRequest {
+    /// Also drop obsolete columns that still hold data
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Admin endpoint: dry-run diff of a tenant's schema against the current definition
+pub async fn plan_schema_migration(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_id = path.into_inner();
+    let diff = app_state.turso_client.plan_user_database_schema(&user_id).await.map_err(|e| {
+        error!("Schema plan failed for user {}: {}", user_id, e);
+        actix_web::error::ErrorInternalServerError("Schema plan failed")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": diff})))
+}
+
+/// Admin endpoint: sync a tenant's schema now; `force` allows dropping columns with data
+pub async fn run_schema_migration(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: Option<web::Json<MigrateSchemaRequest>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let user_id = path.into_inner();
+    let force = body.map(|b| b.force).unwrap_or(false);
+    let diff = app_state.turso_client.migrate_user_database_schema(&user_id, force).await.map_err(|e| {
+        error!("Schema migration failed for user {}: {}", user_id, e);
+        actix_web::error::ErrorInternalServerError("Schema migration failed")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"force": force, "applied": diff}})))
+}
@@ -0,0 +1,88 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::ai_service::setup_discovery;
+use crate::turso::AppState;
+
+pub fn configure_setup_discovery_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/setup-discovery")
+        .route("/run", web::post().to(run_discovery))
+        .route("/drafts", web::get().to(list_drafts))
+        .route("/drafts/{id}/accept", web::post().to(accept_draft))
+        .route("/drafts/{id}/dismiss", web::post().to(dismiss_draft))
+}
+
+/// Cluster untagged trades and replace pending drafts with the setups found
+async fn run_discovery(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match app.setup_discovery_service.discover(&conn, &user_id).await {
+        Ok(run) => {
+            info!("Setup discovery for user {}: {} trades, {} drafts", user_id, run.trades_considered, run.drafts.len());
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": run})))
+        }
+        Err(e) => {
+            error!("Setup discovery failed for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to discover setups"})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DraftsQuery {
+    /// pending (default), accepted, dismissed or all
+    status: Option<String>,
+}
+
+async fn list_drafts(app: web::Data<AppState>, req: HttpRequest, query: web::Query<DraftsQuery>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let status = match query.status.as_deref() {
+        Some("all") => None,
+        Some(status) => Some(status),
+        None => Some("pending"),
+    };
+    match setup_discovery::list_drafts(&conn, status).await {
+        Ok(drafts) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": drafts}))),
+        Err(e) => {
+            error!("Failed to list playbook drafts: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list playbook drafts"})))
+        }
+    }
+}
+
+/// Turn a draft into a playbook with its rules and tag the draft's trades
+async fn accept_draft(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match setup_discovery::accept_draft(&conn, &path.into_inner()).await {
+        Ok(Some(playbook)) => {
+            app.cache_service.invalidate_table_cache(&user_id, "playbook").await.ok();
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": playbook})))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No pending draft with this ID"}))),
+        Err(e) => {
+            error!("Failed to accept playbook draft: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to accept playbook draft"})))
+        }
+    }
+}
+
+async fn dismiss_draft(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match setup_discovery::dismiss_draft(&conn, &path.into_inner()).await {
+        Ok(true) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true}))),
+        Ok(false) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No pending draft with this ID"}))),
+        Err(e) => {
+            error!("Failed to dismiss playbook draft: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to dismiss playbook draft"})))
+        }
+    }
+}
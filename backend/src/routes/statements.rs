@@ -0,0 +1,171 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::image_upload::{ImageUploadService, SupabaseStorageConfig};
+use crate::service::statements;
+use crate::turso::client::TursoClient;
+use crate::turso::AppState;
+
+fn storage() -> actix_web::Result<ImageUploadService> {
+    let config = SupabaseStorageConfig::from_env().map_err(|e| actix_web::error::ErrorServiceUnavailable(e.to_string()))?;
+    ImageUploadService::new(config).map_err(actix_web::error::ErrorInternalServerError)
+}
+
+pub fn configure_statement_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/statements")
+        .route("", web::get().to(list_statements))
+        .route("", web::post().to(create_statement))
+        .route("/{id}", web::get().to(get_statement))
+        .route("/{id}/pdf", web::get().to(download_statement))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateStatementRequest {
+    /// YYYY-MM; defaults to the month before the current one
+    month: Option<String>,
+}
+
+async fn list_statements(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match statements::list_statements(&conn).await {
+        Ok(list) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": list}))),
+        Err(e) => {
+            error!("Failed to list statements: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list statements"})))
+        }
+    }
+}
+
+/// Generate (or regenerate) a month's statement on demand
+async fn create_statement(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<CreateStatementRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let month = payload.into_inner().month.unwrap_or_else(|| statements::previous_month(Utc::now()));
+    if crate::service::goal_pacing::parse_month(&month).is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "month must be YYYY-MM"})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    let storage = storage()?;
+    match statements::generate(&conn, &storage, &user_id, &month, false).await {
+        Ok(statement) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": statement}))),
+        Err(e) => {
+            error!("Failed to generate statement for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to generate statement"})))
+        }
+    }
+}
+
+async fn get_statement(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match statements::get_statement(&conn, &path.into_inner()).await {
+        Ok(Some(statement)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": statement}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Statement not found"}))),
+        Err(e) => {
+            error!("Failed to load statement: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load statement"})))
+        }
+    }
+}
+
+/// The statement PDF itself
+async fn download_statement(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let statement = match statements::get_statement(&conn, &path.into_inner()).await {
+        Ok(Some(statement)) => statement,
+        Ok(None) => return Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Statement not found"}))),
+        Err(e) => {
+            error!("Failed to load statement: {}", e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load statement"})));
+        }
+    };
+    let storage = storage()?;
+    match statements::download(&storage, &statement).await {
+        Ok(bytes) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(("Content-Disposition", format!("attachment; filename=\"tradstry-statement-{}.pdf\"", statement.month)))
+            .body(bytes)),
+        Err(e) => {
+            error!("Failed to download statement {} for user {}: {}", statement.id, user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to download statement"})))
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct StatementRunSummary {
+    month: String,
+    users_checked: usize,
+    statements_generated: usize,
+    users_skipped: usize,
+    users_failed: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunStatementsQuery {
+    /// Defaults to the month before the current one
+    month: Option<String>,
+    /// Regenerate statements that already exist
+    #[serde(default)]
+    force: bool,
+}
+
+/// Monthly cron: statement for the previous month for every user who closed trades in it
+pub async fn run_monthly_statements(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    query: web::Query<RunStatementsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let month = query.month.clone().unwrap_or_else(|| statements::previous_month(Utc::now()));
+    if crate::service::goal_pacing::parse_month(&month).is_none() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "month must be YYYY-MM"})));
+    }
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for statements: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+    let storage = storage()?;
+
+    let mut summary = StatementRunSummary { month: month.clone(), ..Default::default() };
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            summary.users_failed += 1;
+            continue;
+        };
+        summary.users_checked += 1;
+        if !query.force && matches!(statements::statement_for_month(&conn, &month).await, Ok(Some(_))) {
+            summary.users_skipped += 1;
+            continue;
+        }
+        match statements::generate(&conn, &storage, &user_id, &month, true).await {
+            Ok(Some(_)) => summary.statements_generated += 1,
+            Ok(None) => summary.users_skipped += 1,
+            Err(e) => {
+                summary.users_failed += 1;
+                error!("Statement generation failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    info!(
+        "Monthly statements for {}: {} generated, {} skipped, {} failed",
+        summary.month, summary.statements_generated, summary.users_skipped, summary.users_failed
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
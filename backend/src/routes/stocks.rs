@@ -8,11 +8,20 @@ use crate::turso::auth::{validate_supabase_jwt_token, AuthError};
 use crate::models::stock::stocks::{
     Stock, CreateStockRequest, UpdateStockRequest, StockQuery, TimeRange
 };
+use crate::models::money;
 use crate::service::cache_service::CacheService;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::trade_enrichment;
+use crate::service::trade_merge::{self, MergeRequest, SplitRequest};
+use crate::service::trade_links::{self, TradeKind};
+use rust_decimal::Decimal;
 use crate::service::ai_service::vectorization_service::VectorizationService;
 use crate::service::ai_service::data_formatter::DataFormatter;
 use crate::service::ai_service::upstash_vector_client::DataType;
 use crate::websocket::{broadcast_stock_update, ConnectionManager};
+use crate::service::notifications::loss_streak::spawn_loss_streak_check;
+use crate::service::notifications::overtrading::spawn_trade_counter_update;
+use crate::service::ai_service::setup_discovery::TradeAsset;
 use tokio::sync::Mutex;
 
 /// Response wrapper for API responses
@@ -254,7 +263,7 @@ pub async fn create_stock(
     info!("📦 Raw request body: {}", String::from_utf8_lossy(&body));
     
     // Try to deserialize manually and log any errors
-    let payload: CreateStockRequest = match serde_json::from_slice(&body) {
+    let mut payload: CreateStockRequest = match serde_json::from_slice(&body) {
         Ok(p) => {
             info!("✅ Successfully deserialized payload: {:?}", p);
             p
@@ -283,9 +292,48 @@ pub async fn create_stock(
             e
         })?;
 
+    // Trades logged with only symbol, size and time get their entry price from market data
+    let market_client = MarketClient::new(&app_state.config.finance_query)
+        .map_err(|e| error!("Failed to create market client: {}", e))
+        .ok();
+    let entry_price_estimated = payload.entry_price <= Decimal::ZERO;
+    if entry_price_estimated {
+        let estimate = match &market_client {
+            Some(client) => trade_enrichment::estimate_entry_price(client, &payload.symbol, payload.entry_date).await.unwrap_or_else(|e| {
+                error!("Failed to estimate entry price for {}: {}", payload.symbol, e);
+                None
+            }),
+            None => None,
+        };
+        match money::from_f64_opt(estimate).filter(|price| *price > Decimal::ZERO) {
+            Some(price) => payload.entry_price = price,
+            None => {
+                return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(
+                    "Entry price is required when no market data covers the entry time"
+                )));
+            }
+        }
+    }
+
     match Stock::create(&conn, payload).await {
         Ok(stock) => {
             info!("Successfully created stock with ID: {}", stock.id);
+
+            if let Some(client) = market_client {
+                if stock.venue.is_none() {
+                    trade_enrichment::spawn_venue_lookup(conn.clone(), client.clone(), stock.id, stock.symbol.clone());
+                }
+                trade_enrichment::spawn_enrichment(
+                    conn.clone(),
+                    client,
+                    "stock",
+                    stock.id,
+                    stock.symbol.clone(),
+                    stock.entry_date,
+                    None,
+                    entry_price_estimated,
+                );
+            }
             
             // Invalidate cache after successful creation
             let cache_service_clone = cache_service.get_ref().clone();
@@ -311,6 +359,13 @@ pub async fn create_stock(
             tokio::spawn(async move {
                 broadcast_stock_update(ws_manager_clone, &user_id_ws, "created", &stock_ws).await;
             });
+            spawn_trade_counter_update(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+
+            // A trade entered already closed can complete a losing streak
+            if stock.exit_price.is_some() {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id.clone(), TradeAsset::Stock, stock.id);
+            }
 
             // Vectorize the new stock trade
             let vectorization_service_clone = vectorization_service.get_ref().clone();
@@ -512,6 +567,7 @@ pub async fn update_stock(
     cache_service: web::Data<Arc<CacheService>>,
     vectorization_service: web::Data<Arc<VectorizationService>>,
     ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+    app_state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     let id = stock_id.into_inner();
     info!("🔄 [UPDATE_STOCK] Starting update for stock ID: {}", id);
@@ -602,23 +658,22 @@ pub async fn update_stock(
                 manager.broadcast_to_user(&user_id_ws, envelope);
             });
 
-            // Re-vectorize the updated stock trade
+            if stock.exit_price.is_some() {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id.clone(), TradeAsset::Stock, stock.id);
+            }
+
+            // Re-vectorize the updated stock trade, keeping its attached links in the text
             let vectorization_service_clone = vectorization_service.get_ref().clone();
-            let stock_clone = stock.clone();
+            let conn_clone = conn.clone();
+            let stock_id = stock.id;
             let user_id_clone = user_id.clone();
             
             tokio::spawn(async move {
-                let content = DataFormatter::format_stock_for_embedding(&stock_clone);
-                match vectorization_service_clone.vectorize_data(
-                    &user_id_clone,
-                    DataType::Stock,
-                    &stock_clone.id.to_string(),
-                    &content,
-                ).await {
-                    Ok(result) => info!("Successfully re-vectorized stock {} for user {}: {}ms", 
-                        stock_clone.id, user_id_clone, result.processing_time_ms),
+                match trade_links::refresh_trade_embedding(&conn_clone, &vectorization_service_clone, &user_id_clone, TradeKind::Stock, stock_id).await {
+                    Ok(()) => info!("Successfully re-vectorized stock {} for user {}", stock_id, user_id_clone),
                     Err(e) => error!("Failed to re-vectorize stock {} for user {}: {}", 
-                        stock_clone.id, user_id_clone, e),
+                        stock_id, user_id_clone, e),
                 }
             });
             
@@ -720,6 +775,192 @@ pub async fn delete_stock(
     }
 }
 
+/// Clone a stock trade, applying any fields in the body on top of the original. An empty
+/// body copies the trade as is.
+#[allow(clippy::too_many_arguments)]
+pub async fn duplicate_stock(
+    req: HttpRequest,
+    stock_id: web::Path<i64>,
+    body: web::Bytes,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    cache_service: web::Data<Arc<CacheService>>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let id = stock_id.into_inner();
+    let overrides: UpdateStockRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        UpdateStockRequest::default()
+    } else {
+        match serde_json::from_slice(&body) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                return Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&format!("Invalid request format: {}", e))));
+            }
+        }
+    };
+
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+    app_state.storage_quota_service.check_storage_quota(&user_id, &conn).await?;
+
+    match Stock::duplicate(&conn, id, overrides).await {
+        Ok(Some(stock)) => {
+            info!("Duplicated stock {} as {}", id, stock.id);
+
+            let cache_service_clone = cache_service.get_ref().clone();
+            let user_id_clone = user_id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = cache_service_clone.invalidate_table_cache(&user_id_clone, "stocks").await {
+                    error!("Failed to invalidate stock cache for user {}: {}", user_id_clone, e);
+                }
+                if let Err(e) = cache_service_clone.invalidate_user_analytics(&user_id_clone).await {
+                    error!("Failed to invalidate analytics cache for user {}: {}", user_id_clone, e);
+                }
+            });
+
+            let ws_manager_clone = ws_manager.clone();
+            let user_id_ws = user_id.clone();
+            let stock_ws = stock.clone();
+            tokio::spawn(async move {
+                broadcast_stock_update(ws_manager_clone, &user_id_ws, "created", &stock_ws).await;
+            });
+
+            if stock.exit_price.is_some() {
+                spawn_loss_streak_check(conn.clone(), user_id.clone(), app_state.config.web_push.clone(), ws_manager.get_ref().clone());
+                app_state.automation_service.spawn_trade_closed(conn.clone(), user_id.clone(), TradeAsset::Stock, stock.id);
+            }
+
+            let vectorization_service_clone = vectorization_service.get_ref().clone();
+            let stock_clone = stock.clone();
+            tokio::spawn(async move {
+                let content = DataFormatter::format_stock_for_embedding(&stock_clone);
+                if let Err(e) = vectorization_service_clone
+                    .vectorize_data(&user_id, DataType::Stock, &stock_clone.id.to_string(), &content)
+                    .await
+                {
+                    error!("Failed to vectorize stock {} for user {}: {}", stock_clone.id, user_id, e);
+                }
+            });
+
+            Ok(HttpResponse::Created().json(ApiResponse::success(stock)))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(ApiResponse::<()>::error("Stock not found"))),
+        Err(e) => {
+            error!("Failed to duplicate stock {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to duplicate stock trade")))
+        }
+    }
+}
+
+/// Caches, websocket clients and vectors after a merge or split rewrote trade rows
+fn sync_restructured_stocks(
+    user_id: String,
+    changed: Vec<(Stock, &'static str)>,
+    removed_ids: Vec<i64>,
+    cache_service: Arc<CacheService>,
+    vectorization_service: Arc<VectorizationService>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) {
+    tokio::spawn(async move {
+        if let Err(e) = cache_service.invalidate_table_cache(&user_id, "stocks").await {
+            error!("Failed to invalidate stock cache for user {}: {}", user_id, e);
+        }
+        if let Err(e) = cache_service.invalidate_user_analytics(&user_id).await {
+            error!("Failed to invalidate analytics cache for user {}: {}", user_id, e);
+        }
+        for (stock, event) in &changed {
+            broadcast_stock_update(ws_manager.clone(), &user_id, event, stock).await;
+        }
+        for id in &removed_ids {
+            broadcast_stock_update(ws_manager.clone(), &user_id, "deleted", serde_json::json!({"id": id})).await;
+        }
+
+        if !removed_ids.is_empty() {
+            let ids: Vec<String> = removed_ids.iter().map(|id| id.to_string()).collect();
+            if let Err(e) = vectorization_service.delete_vectors(&user_id, &ids).await {
+                error!("Failed to delete vectors for merged stocks of user {}: {}", user_id, e);
+            }
+        }
+        for (stock, _) in &changed {
+            let content = DataFormatter::format_stock_for_embedding(stock);
+            if let Err(e) = vectorization_service.vectorize_data(&user_id, DataType::Stock, &stock.id.to_string(), &content).await {
+                error!("Failed to vectorize stock {} for user {}: {}", stock.id, user_id, e);
+            }
+        }
+    });
+}
+
+/// Merge several rows that are really one trade, e.g. an import split across fills
+pub async fn merge_stocks(
+    req: HttpRequest,
+    payload: web::Json<MergeRequest>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+
+    match trade_merge::merge_stocks(&conn, &payload.ids).await {
+        Ok(Ok(merged)) => {
+            info!("Merged stocks {:?} into {}", merged.removed_ids, merged.trade.id);
+            sync_restructured_stocks(
+                user_id,
+                vec![(merged.trade.clone(), "updated")],
+                merged.removed_ids.clone(),
+                app_state.cache_service.clone(),
+                vectorization_service.get_ref().clone(),
+                ws_manager,
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(merged)))
+        }
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&message))),
+        Err(e) => {
+            error!("Failed to merge stocks {:?}: {}", payload.ids, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to merge stock trades")))
+        }
+    }
+}
+
+/// Split one row into several trades by share count
+pub async fn split_stock(
+    req: HttpRequest,
+    stock_id: web::Path<i64>,
+    payload: web::Json<SplitRequest>,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+    ws_manager: web::Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let id = stock_id.into_inner();
+    let user_id = get_authenticated_user(&req, &supabase_config).await?.sub;
+    let conn = get_user_db_connection(&req, &app_state.turso_client, &supabase_config).await?;
+    app_state.storage_quota_service.check_storage_quota(&user_id, &conn).await?;
+
+    match trade_merge::split_stock(&conn, id, &payload.parts).await {
+        Ok(Ok(trades)) => {
+            info!("Split stock {} into {} trades", id, trades.len());
+            let changed = trades.iter().map(|t| (t.clone(), if t.id == id { "updated" } else { "created" })).collect();
+            sync_restructured_stocks(
+                user_id,
+                changed,
+                Vec::new(),
+                app_state.cache_service.clone(),
+                vectorization_service.get_ref().clone(),
+                ws_manager,
+            );
+            Ok(HttpResponse::Ok().json(ApiResponse::success(trades)))
+        }
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(ApiResponse::<()>::error(&message))),
+        Err(e) => {
+            error!("Failed to split stock {}: {}", id, e);
+            Ok(HttpResponse::InternalServerError().json(ApiResponse::<()>::error("Failed to split stock trade")))
+        }
+    }
+}
+
 /// Get total count of stocks for pagination with caching
 pub async fn get_stocks_count(
     req: HttpRequest,
@@ -1285,6 +1526,8 @@ pub fn configure_stocks_routes(cfg: &mut web::ServiceConfig) {
     info!("Setting up /api/stocks routes");
     cfg.service(
         web::scope("/api/stocks")
+            .wrap(actix_web::middleware::from_fn(crate::middleware::fields::fields_middleware))
+            .wrap(actix_web::middleware::from_fn(crate::middleware::etag::etag_middleware))
             // Test route
             .route("/test", web::get().to(test_endpoint))
             
@@ -1292,9 +1535,12 @@ pub fn configure_stocks_routes(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(create_stock))                    // POST /api/stocks
             .route("", web::get().to(get_all_stocks))                   // GET /api/stocks?filters
             .route("/count", web::get().to(get_stocks_count))           // GET /api/stocks/count
+            .route("/merge", web::post().to(merge_stocks))              // POST /api/stocks/merge
             .route("/{id}", web::get().to(get_stock_by_id))             // GET /api/stocks/{id}
             .route("/{id}", web::put().to(update_stock))                // PUT /api/stocks/{id}
             .route("/{id}", web::delete().to(delete_stock))             // DELETE /api/stocks/{id}
+            .route("/{id}/duplicate", web::post().to(duplicate_stock))  // POST /api/stocks/{id}/duplicate
+            .route("/{id}/split", web::post().to(split_stock))          // POST /api/stocks/{id}/split
             
             // Analytics endpoints
             .route("/analytics", web::get().to(get_stocks_analytics))   // GET /api/stocks/analytics?time_range=
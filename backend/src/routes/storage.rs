@@ -0,0 +1,32 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::warn;
+
+use crate::routes::common::get_user_id_from_ext;
+use crate::turso::AppState;
+
+pub fn configure_storage_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/storage").route("/usage", web::get().to(get_usage))
+}
+
+/// Usage broken down by images, notes and vectors against the plan limits
+async fn get_usage(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let conn = app.turso_client
+        .get_user_database_connection(&user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?
+        .ok_or_else(|| actix_web::error::ErrorForbidden("No database"))?;
+
+    let breakdown = app.storage_quota_service
+        .get_usage_breakdown(&user_id, &conn)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Err(e) = app.storage_quota_service.notify_quota_warnings(&user_id, &conn, &app.config.web_push, &breakdown).await {
+        warn!("Failed to process storage warnings for user {}: {}", user_id, e);
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": breakdown})))
+}
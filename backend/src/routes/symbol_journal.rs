@@ -0,0 +1,30 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::error;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::symbol_journal;
+use crate::turso::AppState;
+
+pub fn configure_symbol_journal_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/symbols").route("/{symbol}/journal", web::get().to(get_symbol_journal))
+}
+
+/// Per-ticker dossier: trades, notes, images, missed trades, stats and AI insights
+async fn get_symbol_journal(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let Some(symbol) = symbol_journal::normalize_symbol(&path.into_inner()) else {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "Invalid symbol"})));
+    };
+    let conn = user_db_connection(&app, &user_id).await?;
+    match symbol_journal::symbol_journal(&conn, &symbol).await {
+        Ok(journal) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": journal}))),
+        Err(e) => {
+            error!("Failed to build {} journal for user {}: {}", symbol, user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load symbol journal"})))
+        }
+    }
+}
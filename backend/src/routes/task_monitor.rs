@@ -0,0 +1,61 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::{error, info, warn};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::service::task_monitor::{check_user_tasks, send_ops_alert, MonitorSummary};
+use crate::turso::{AppState, client::TursoClient};
+
+/// Cron endpoint: expire stuck generation tasks and alert ops on failure spikes
+pub async fn run_task_monitor(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let config = &app_state.config.task_monitor;
+
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for task monitor: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+
+    let mut summary = MonitorSummary::default();
+    for user_id in user_ids {
+        let conn = match turso_client.get_user_database_connection(&user_id).await {
+            Ok(Some(conn)) => conn,
+            _ => {
+                summary.users_unreachable += 1;
+                continue;
+            }
+        };
+        match check_user_tasks(&conn, config).await {
+            Ok(health) => {
+                if health.expired() > 0 {
+                    warn!("Expired {} stuck task(s) for user {}", health.expired(), user_id);
+                }
+                summary.record(&user_id, &health);
+            }
+            Err(e) => {
+                summary.users_unreachable += 1;
+                error!("Task monitor failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    if summary.finish(config) {
+        warn!("{}", summary.alert_text(config));
+        if let Some(url) = &config.ops_webhook_url {
+            match send_ops_alert(url, &summary, config).await {
+                Ok(()) => summary.alert_sent = true,
+                Err(e) => error!("Failed to send ops alert: {}", e),
+            }
+        }
+    }
+
+    info!(
+        "Task monitor completed: {} users, {} expired, {} failed",
+        summary.users_checked, summary.expired_tasks, summary.failed_tasks
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
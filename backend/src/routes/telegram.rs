@@ -0,0 +1,96 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::{error, warn};
+
+use crate::middleware::cron_auth::secrets_match;
+use crate::routes::common::get_user_id_from_ext;
+use crate::service::telegram_bot::{linking, TelegramBot, TelegramUpdate};
+use crate::turso::AppState;
+
+async fn registry_connection(app: &web::Data<AppState>) -> actix_web::Result<libsql::Connection> {
+    app.turso_client
+        .get_registry_connection()
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)
+}
+
+pub fn configure_telegram_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/telegram")
+        .route("/link-code", web::post().to(create_link_code))
+        .route("/status", web::get().to(get_status))
+        .route("/link", web::delete().to(unlink))
+}
+
+async fn create_link_code(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    if app.config.telegram.is_none() {
+        return Err(actix_web::error::ErrorServiceUnavailable("Telegram bot is not configured"));
+    }
+    let registry = registry_connection(&app).await?;
+    let code = linking::create_link_code(&registry, &user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"code": code, "command": format!("/link {}", code)}})))
+}
+
+async fn get_status(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let registry = registry_connection(&app).await?;
+    let chats = linking::linked_chat_count(&registry, &user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "data": {"enabled": app.config.telegram.is_some(), "linked": chats > 0, "linked_chats": chats}
+    })))
+}
+
+async fn unlink(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    let registry = registry_connection(&app).await?;
+    let removed = linking::unlink_user(&registry, &user_id)
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"removed": removed}})))
+}
+
+/// Public webhook Telegram posts updates to (register with setWebhook + secret_token)
+pub async fn telegram_webhook(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    body: web::Json<TelegramUpdate>,
+) -> actix_web::Result<HttpResponse> {
+    let Some(config) = app_state.config.telegram.clone() else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    // Without a secret anyone could post updates as any linked chat
+    let Some(expected) = config.webhook_secret.as_deref() else {
+        warn!("Rejected Telegram webhook: TELEGRAM_WEBHOOK_SECRET is not configured");
+        return Ok(HttpResponse::NotFound().finish());
+    };
+    let provided = req
+        .headers()
+        .get("X-Telegram-Bot-Api-Secret-Token")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !secrets_match(provided, expected) {
+        warn!("Rejected Telegram webhook with invalid secret token");
+        return Err(actix_web::error::ErrorUnauthorized("Invalid secret token"));
+    }
+
+    // Acknowledge immediately; Telegram retries updates that take too long
+    let update = body.into_inner();
+    let state = app_state.clone();
+    tokio::spawn(async move {
+        let bot = TelegramBot::new(config);
+        if let Err(e) = bot.handle_update(&state, update).await {
+            error!("Failed to handle Telegram update: {}", e);
+        }
+    });
+
+    Ok(HttpResponse::Ok().finish())
+}
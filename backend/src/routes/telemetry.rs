@@ -0,0 +1,100 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::{Duration, Utc};
+use log::error;
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::require_user_id;
+use crate::service::telemetry;
+use crate::turso::AppState;
+
+/// Default window of the maintainer usage report
+const DEFAULT_REPORT_DAYS: i64 = 30;
+
+async fn registry_connection(app: &AppState) -> actix_web::Result<libsql::Connection> {
+    app.turso_client.get_registry_connection().await.map_err(actix_web::error::ErrorInternalServerError)
+}
+
+pub fn configure_telemetry_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/telemetry")
+        .route("", web::get().to(get_status))
+        .route("", web::put().to(set_status))
+        .route("/export", web::get().to(export_usage))
+}
+
+#[derive(Debug, Deserialize)]
+struct SetTelemetryRequest {
+    enabled: bool,
+}
+
+async fn get_status(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_connection(&app).await?;
+    match telemetry::get_status(&registry, &user_id).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": status}))),
+        Err(e) => {
+            error!("Failed to load telemetry status for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load telemetry setting"})))
+        }
+    }
+}
+
+/// Opt in or out; opting out deletes everything recorded so far
+async fn set_status(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<SetTelemetryRequest>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_connection(&app).await?;
+    match telemetry::set_enabled(&registry, &user_id, payload.enabled).await {
+        Ok(status) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": status}))),
+        Err(e) => {
+            error!("Failed to update telemetry setting for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update telemetry setting"})))
+        }
+    }
+}
+
+/// Every count recorded for the user, as a JSON download
+async fn export_usage(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let registry = registry_connection(&app).await?;
+    match telemetry::export_for_user(&registry, &user_id).await {
+        Ok(counts) => Ok(HttpResponse::Ok()
+            .insert_header(("Content-Disposition", "attachment; filename=\"tradstry-telemetry.json\""))
+            .json(serde_json::json!({"success": true, "data": counts}))),
+        Err(e) => {
+            error!("Failed to export telemetry for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to export telemetry"})))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsageReportQuery {
+    days: Option<i64>,
+}
+
+/// Admin endpoint: requests and opted-in tenants per feature category
+pub async fn get_usage_report(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<UsageReportQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let days = query.days.unwrap_or(DEFAULT_REPORT_DAYS).clamp(1, 365);
+    let since = (Utc::now() - Duration::days(days - 1)).format("%Y-%m-%d").to_string();
+    let registry = registry_connection(&app_state).await?;
+    match telemetry::usage_report(&registry, &since).await {
+        Ok(usage) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"since": since, "categories": usage}}))),
+        Err(e) => {
+            error!("Failed to build feature usage report: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to build usage report"})))
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use log::error;
+use serde::Deserialize;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::service::tenant_cleanup::TenantCleanupService;
+use crate::turso::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct ListDeadTenantsQuery {
+    pub include_reclaimed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReclaimRequest {
+    /// Must repeat the candidate's database name
+    pub confirm_db_name: String,
+}
+
+fn cleanup_service(app_state: &AppState) -> TenantCleanupService<'_> {
+    TenantCleanupService::new(&app_state.turso_client, &app_state.account_deletion_service)
+}
+
+/// Admin endpoint: scan for dead tenants and record them as candidates (nothing is deleted)
+pub async fn scan_dead_tenants(req: HttpRequest, app_state: web::Data<AppState>) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let summary = cleanup_service(&app_state).scan().await.map_err(|e| {
+        error!("Dead tenant scan failed: {}", e);
+        actix_web::error::ErrorInternalServerError("Dead tenant scan failed")
+    })?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
+
+/// Admin endpoint: list recorded dead tenant candidates
+pub async fn list_dead_tenants(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    query: web::Query<ListDeadTenantsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let tenants = cleanup_service(&app_state)
+        .list(query.include_reclaimed.unwrap_or(false))
+        .await
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": tenants})))
+}
+
+/// Admin endpoint: delete a confirmed dead tenant's database, storage objects and vectors
+pub async fn reclaim_dead_tenant(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+    body: web::Json<ReclaimRequest>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let tenant = cleanup_service(&app_state)
+        .reclaim(&path.into_inner(), &body.confirm_db_name)
+        .await
+        .map_err(|e| {
+            error!("Dead tenant reclamation failed: {}", e);
+            actix_web::error::ErrorBadRequest(e.to_string())
+        })?
+        .ok_or_else(|| actix_web::error::ErrorNotFound("Candidate not found"))?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": tenant})))
+}
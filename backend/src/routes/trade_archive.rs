@@ -0,0 +1,143 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::Utc;
+use log::{error, info};
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::image_upload::{ImageUploadService, SupabaseStorageConfig};
+use crate::service::trade_archive::{self, MAX_ARCHIVE_YEARS, MIN_ARCHIVE_YEARS};
+use crate::turso::AppState;
+
+fn storage() -> actix_web::Result<ImageUploadService> {
+    let config = SupabaseStorageConfig::from_env().map_err(|e| actix_web::error::ErrorServiceUnavailable(e.to_string()))?;
+    ImageUploadService::new(config).map_err(actix_web::error::ErrorInternalServerError)
+}
+
+/// Archived and restored trades change every trade list and analytics result
+async fn invalidate_trade_caches(app: &AppState, user_id: &str) {
+    for table in ["stocks", "options"] {
+        app.cache_service.invalidate_table_cache(user_id, table).await.ok();
+    }
+    app.cache_service.invalidate_user_analytics(user_id).await.ok();
+}
+
+pub fn configure_trade_archive_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/archives")
+        .route("", web::get().to(list_archives))
+        .route("", web::post().to(create_archive))
+        .route("/preview", web::get().to(preview_archive))
+        .route("/{id}", web::get().to(get_archive))
+        .route("/{id}/restore", web::post().to(restore_archive))
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchiveParams {
+    /// Closed trades that exited more than this many years ago are archived
+    older_than_years: u32,
+}
+
+impl ArchiveParams {
+    fn validate(&self) -> Result<(), String> {
+        if !(MIN_ARCHIVE_YEARS..=MAX_ARCHIVE_YEARS).contains(&self.older_than_years) {
+            return Err(format!("older_than_years must be between {} and {}", MIN_ARCHIVE_YEARS, MAX_ARCHIVE_YEARS));
+        }
+        Ok(())
+    }
+}
+
+async fn list_archives(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_archive::list_archives(&conn).await {
+        Ok(archives) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": archives}))),
+        Err(e) => {
+            error!("Failed to list trade archives: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list trade archives"})))
+        }
+    }
+}
+
+/// Summary of the trades an archive with this cutoff would move
+async fn preview_archive(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    query: web::Query<ArchiveParams>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = query.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    let cutoff = trade_archive::cutoff_for(Utc::now(), query.older_than_years);
+    match trade_archive::preview(&conn, &cutoff).await {
+        Ok(summary) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"cutoff": cutoff, "summary": summary}}))),
+        Err(e) => {
+            error!("Failed to preview trade archive: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to preview trade archive"})))
+        }
+    }
+}
+
+async fn create_archive(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    payload: web::Json<ArchiveParams>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    if let Err(message) = payload.validate() {
+        return Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message})));
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    let storage = storage()?;
+    let cutoff = trade_archive::cutoff_for(Utc::now(), payload.older_than_years);
+    match trade_archive::archive(&conn, &storage, &user_id, &cutoff).await {
+        Ok(Some(archive)) => {
+            info!(
+                "Archived {} stock and {} option trades for user {}",
+                archive.summary.stock_trades, archive.summary.option_trades, user_id
+            );
+            invalidate_trade_caches(&app, &user_id).await;
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": archive})))
+        }
+        Ok(None) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": null, "message": "No trades old enough to archive"}))),
+        Err(e) => {
+            error!("Failed to archive trades for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to archive trades"})))
+        }
+    }
+}
+
+async fn get_archive(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_archive::get_archive(&conn, &path.into_inner()).await {
+        Ok(Some(archive)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": archive}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Archive not found"}))),
+        Err(e) => {
+            error!("Failed to load trade archive: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load trade archive"})))
+        }
+    }
+}
+
+/// Bring an archive's trades back into the database
+async fn restore_archive(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    let storage = storage()?;
+    match trade_archive::restore(&conn, &storage, &path.into_inner()).await {
+        Ok(Some(archive)) => {
+            invalidate_trade_caches(&app, &user_id).await;
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": archive})))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Archive not found"}))),
+        Err(e) => {
+            error!("Failed to restore trade archive for user {}: {}", user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": format!("Failed to restore archive: {}", e)})))
+        }
+    }
+}
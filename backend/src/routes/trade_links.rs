@@ -0,0 +1,103 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use log::error;
+use serde::Deserialize;
+
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::trade_links::{self, CreateTradeLink, TradeKind, UpdateTradeLink};
+use crate::turso::AppState;
+
+/// Links are part of the trade's embedding text, so re-embed it in the background
+fn spawn_embedding_refresh(app: &AppState, conn: libsql::Connection, user_id: String, kind: TradeKind, trade_id: i64) {
+    let vectorization_service = app.vectorization_service.clone();
+    tokio::spawn(async move {
+        if let Err(e) = trade_links::refresh_trade_embedding(&conn, &vectorization_service, &user_id, kind, trade_id).await {
+            error!("Failed to re-embed trade {} with its links for user {}: {}", trade_id, user_id, e);
+        }
+    });
+}
+
+pub fn configure_trade_link_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/trade-links")
+        .route("", web::get().to(list_links))
+        .route("", web::post().to(create_link))
+        .route("/{id}", web::put().to(update_link))
+        .route("/{id}", web::delete().to(delete_link))
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    trade_type: TradeKind,
+    trade_id: i64,
+}
+
+async fn list_links(app: web::Data<AppState>, req: HttpRequest, query: web::Query<ListQuery>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_links::list_for_trade(&conn, query.trade_type, query.trade_id).await {
+        Ok(Some(links)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": links}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Trade not found"}))),
+        Err(e) => {
+            error!("Failed to list trade links: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list links"})))
+        }
+    }
+}
+
+async fn create_link(app: web::Data<AppState>, req: HttpRequest, payload: web::Json<CreateTradeLink>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_links::create_link(&conn, &payload).await {
+        Ok(Ok(Some(link))) => {
+            spawn_embedding_refresh(&app, conn, user_id, link.trade_type, link.trade_id);
+            Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": link})))
+        }
+        Ok(Ok(None)) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Trade not found"}))),
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message}))),
+        Err(e) => {
+            error!("Failed to create trade link: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to create link"})))
+        }
+    }
+}
+
+async fn update_link(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<String>,
+    payload: web::Json<UpdateTradeLink>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_links::update_link(&conn, &path.into_inner(), &payload).await {
+        Ok(Ok(Some(link))) => {
+            spawn_embedding_refresh(&app, conn, user_id, link.trade_type, link.trade_id);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": link})))
+        }
+        Ok(Ok(None)) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Link not found"}))),
+        Ok(Err(message)) => Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": message}))),
+        Err(e) => {
+            error!("Failed to update trade link: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to update link"})))
+        }
+    }
+}
+
+async fn delete_link(app: web::Data<AppState>, req: HttpRequest, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match trade_links::delete_link(&conn, &path.into_inner()).await {
+        Ok(Some(link)) => {
+            spawn_embedding_refresh(&app, conn, user_id, link.trade_type, link.trade_id);
+            Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "message": "Link deleted"})))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Link not found"}))),
+        Err(e) => {
+            error!("Failed to delete trade link: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to delete link"})))
+        }
+    }
+}
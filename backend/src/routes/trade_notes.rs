@@ -13,6 +13,9 @@ use crate::models::notes::{
 };
 use crate::service::cache_service::CacheService;
 use crate::service::trade_notes_service::TradeNotesService;
+use crate::service::ai_service::vectorization_service::VectorizationService;
+use crate::service::ai_service::setup_discovery::TradeAsset;
+use crate::service::ai_service::similar_trades;
 use crate::models::stock::stocks::Stock;
 use crate::models::options::option_trade::OptionTrade;
 use crate::websocket::{broadcast_note_update, ConnectionManager};
@@ -169,6 +172,15 @@ async fn get_user_database_connection(
     Ok(conn)
 }
 
+/// Re-embed a note in the background so AI context picks up the latest text
+fn spawn_note_vectorization(service: Arc<VectorizationService>, conn: Connection, user_id: String, note: TradeNote) {
+    tokio::spawn(async move {
+        if let Err(e) = service.vectorize_trade_note(&conn, &user_id, &note).await {
+            error!("Failed to vectorize trade note {} for user {}: {}", note.id, user_id, e);
+        }
+    });
+}
+
 /// Create a new trade note
 pub async fn create_trade_note(
     req: HttpRequest,
@@ -206,6 +218,7 @@ pub async fn create_trade_note(
             tokio::spawn(async move {
                 broadcast_note_update(ws_manager_clone, &user_id_ws, "created", &note_ws).await;
             });
+            spawn_note_vectorization(app_state.vectorization_service.clone(), conn.clone(), claims.sub.clone(), note.clone());
             Ok(HttpResponse::Created().json(TradeNoteResponse {
                 success: true,
                 message: "Trade note created successfully".to_string(),
@@ -357,6 +370,7 @@ pub async fn update_trade_note(
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
     ws_manager: Data<StdArc<Mutex<ConnectionManager>>>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
 ) -> Result<HttpResponse> {
     info!("=== Update Trade Note Called ===");
     info!("Note ID: {}", note_id);
@@ -380,6 +394,7 @@ pub async fn update_trade_note(
             tokio::spawn(async move {
                 broadcast_note_update(ws_manager_clone, &user_id_ws, "updated", &note_ws).await;
             });
+            spawn_note_vectorization(vectorization_service.get_ref().clone(), conn.clone(), claims.sub.clone(), note.clone());
             Ok(HttpResponse::Ok().json(TradeNoteResponse {
                 success: true,
                 message: "Trade note updated successfully".to_string(),
@@ -868,6 +883,49 @@ pub async fn delete_trade_note_for_trade(
     }
 }
 
+/// Query parameters for similar trades
+#[derive(Debug, Deserialize)]
+pub struct SimilarTradesQuery {
+    pub k: Option<usize>,
+}
+
+/// Closed trades most similar to a trade, with their outcomes and shared mistakes
+pub async fn get_similar_trades(
+    req: HttpRequest,
+    path: web::Path<TradeNotePathParams>,
+    query: web::Query<SimilarTradesQuery>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    supabase_config: web::Data<SupabaseConfig>,
+    vectorization_service: web::Data<Arc<VectorizationService>>,
+) -> Result<HttpResponse> {
+    let asset = match path.trade_type.as_str() {
+        "stock" => TradeAsset::Stock,
+        "option" => TradeAsset::Option,
+        _ => {
+            return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                "success": false,
+                "message": "Invalid trade_type. Must be 'stock' or 'option'"
+            })));
+        }
+    };
+    let k = query.k.unwrap_or(similar_trades::DEFAULT_K).clamp(1, similar_trades::MAX_K);
+
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = get_user_database_connection(&claims.sub, &turso_client).await?;
+
+    match similar_trades::find_similar(&conn, &vectorization_service, &claims.sub, asset, path.trade_id, k).await {
+        Ok(Some(result)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": result}))),
+        Ok(None) => Ok(HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "Trade not found"}))),
+        Err(e) => {
+            error!("Failed to find similar trades for {} {}: {}", path.trade_type, path.trade_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": "Failed to find similar trades"
+            })))
+        }
+    }
+}
+
 /// Configure trade notes routes
 pub fn configure_trade_notes_routes(cfg: &mut web::ServiceConfig) {
     info!("Setting up /api/trade-notes routes");
@@ -890,6 +948,7 @@ pub fn configure_trade_notes_routes(cfg: &mut web::ServiceConfig) {
         web::scope("/api/trades")
             .route("/{trade_type}/{trade_id}/notes", web::post().to(upsert_trade_note_for_trade))
             .route("/{trade_type}/{trade_id}/notes", web::get().to(get_trade_note_for_trade))
+            .route("/{trade_type}/{trade_id}/similar", web::get().to(get_similar_trades))
             .route("/{trade_type}/{trade_id}/notes", web::delete().to(delete_trade_note_for_trade))
     );
 }
@@ -0,0 +1,35 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web_httpauth::middleware::HttpAuthentication;
+use log::warn;
+use serde::Deserialize;
+
+use crate::jwt_validator;
+use crate::middleware::rate_limit::rate_limit_middleware;
+use crate::routes::common::get_user_id_from_ext;
+use crate::turso::AppState;
+
+/// Registered as a single resource ahead of the trade notes `/api/trades` scope,
+/// which would otherwise swallow the path.
+pub fn configure_trade_parser_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::resource("/api/trades/parse")
+            .wrap(HttpAuthentication::bearer(jwt_validator))
+            .wrap(actix_web::middleware::from_fn(rate_limit_middleware))
+            .route(web::post().to(parse_trade)),
+    );
+}
+
+#[derive(Deserialize)]
+struct ParseTradeReq { text: String }
+
+/// Turn free text into a trade draft; the client confirms and saves it through the stocks/options APIs
+async fn parse_trade(app: web::Data<AppState>, req: HttpRequest, body: web::Json<ParseTradeReq>) -> actix_web::Result<HttpResponse> {
+    let user_id = get_user_id_from_ext(&req).ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthorized"))?;
+    match app.trade_parser_service.parse(&body.text).await {
+        Ok(draft) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": draft}))),
+        Err(e) => {
+            warn!("Trade parse failed for user {}: {}", user_id, e);
+            Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({"success": false, "error": e.to_string()})))
+        }
+    }
+}
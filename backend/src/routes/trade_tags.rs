@@ -4,7 +4,9 @@ use log::{info, error, warn, debug};
 
 use crate::turso::config::SupabaseConfig;
 use crate::turso::auth::{validate_supabase_jwt_token, AuthError};
-use crate::turso::{AppState, SupabaseClaims};
+use crate::turso::{in_transaction, AppState, SupabaseClaims};
+use crate::service::ai_service::setup_discovery::TradeAsset;
+use crate::service::bulk_operations;
 use crate::models::tags::{TradeTag, CreateTagRequest, UpdateTagRequest, TagQuery, TradeTagAssociation, AddTagsToTradeRequest};
 
 /// Parse JWT claims without full validation (for quick checks)
@@ -369,6 +371,14 @@ pub async fn create_tag(
     }
 }
 
+/// Tags shared by an organization are managed by the team lead
+fn organization_tag_response() -> HttpResponse {
+    HttpResponse::Forbidden().json(serde_json::json!({
+        "success": false,
+        "message": "This tag is shared by your organization and can only be changed by a team lead"
+    }))
+}
+
 /// Update a tag
 pub async fn update_tag(
     app_state: web::Data<AppState>,
@@ -394,6 +404,12 @@ pub async fn update_tag(
             actix_web::error::ErrorNotFound("User database not found")
         })?;
 
+    if let Ok(tag) = TradeTag::find_by_id(&conn, &tag_id).await
+        && tag.organization_id.is_some()
+    {
+        return Ok(organization_tag_response());
+    }
+
     match TradeTag::update(&conn, &tag_id, payload.into_inner()).await {
         Ok(tag) => {
             info!("✓ Tag updated successfully: {}", tag.id);
@@ -438,13 +454,21 @@ pub async fn delete_tag(
             actix_web::error::ErrorNotFound("User database not found")
         })?;
 
-    match TradeTag::delete(&conn, &tag_id).await {
-        Ok(deleted) => {
-            if deleted {
-                info!("✓ Tag deleted successfully: {}", tag_id);
+    if let Ok(tag) = TradeTag::find_by_id(&conn, &tag_id).await
+        && tag.organization_id.is_some()
+    {
+        return Ok(organization_tag_response());
+    }
+
+    // Deleting a tag strips it from every trade, so it's recorded for undo
+    match bulk_operations::delete_tag(&conn, &tag_id).await {
+        Ok(operation) => {
+            if let Some(operation) = operation {
+                info!("✓ Tag deleted successfully: {} (operation {})", tag_id, operation.id);
                 Ok(HttpResponse::Ok().json(serde_json::json!({
                     "success": true,
-                    "message": "Tag deleted successfully"
+                    "message": "Tag deleted successfully",
+                    "operation_id": operation.id
                 })))
             } else {
                 Ok(HttpResponse::NotFound().json(serde_json::json!({
@@ -574,18 +598,33 @@ pub async fn add_tags_to_stock_trade(
             actix_web::error::ErrorNotFound("User database not found")
         })?;
 
-    let mut added_count = 0;
-    let mut skipped_count = 0;
-
-    for tag_id in &payload.tag_ids {
-        match TradeTagAssociation::add_tag_to_stock_trade(&conn, stock_trade_id, tag_id).await {
-            Ok(true) => added_count += 1,
-            Ok(false) => skipped_count += 1, // Already exists
-            Err(e) => {
-                error!("Failed to add tag {} to stock trade: {}", tag_id, e);
+    // All or nothing: a failed insert leaves the trade's tags as they were
+    let tag_ids = &payload.tag_ids;
+    let added = in_transaction(&conn, async |tx| {
+        let mut added_tag_ids = Vec::new();
+        for tag_id in tag_ids {
+            // false: already tagged
+            if TradeTagAssociation::add_tag_to_stock_trade(tx, stock_trade_id, tag_id).await? {
+                added_tag_ids.push(tag_id.clone());
             }
         }
-    }
+        anyhow::Ok(added_tag_ids)
+    })
+    .await;
+    let added_tag_ids = match added {
+        Ok(ids) => ids,
+        Err(e) => {
+            error!("Failed to add tags to stock trade {}: {}", stock_trade_id, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to add tags: {}", e)
+            })));
+        }
+    };
+    let added_count = added_tag_ids.len();
+    let skipped_count = tag_ids.len() - added_count;
+
+    app_state.automation_service.spawn_tags_added(conn.clone(), user_id.clone(), TradeAsset::Stock, stock_trade_id, added_tag_ids);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
@@ -620,18 +659,33 @@ pub async fn add_tags_to_option_trade(
             actix_web::error::ErrorNotFound("User database not found")
         })?;
 
-    let mut added_count = 0;
-    let mut skipped_count = 0;
-
-    for tag_id in &payload.tag_ids {
-        match TradeTagAssociation::add_tag_to_option_trade(&conn, option_trade_id, tag_id).await {
-            Ok(true) => added_count += 1,
-            Ok(false) => skipped_count += 1, // Already exists
-            Err(e) => {
-                error!("Failed to add tag {} to option trade: {}", tag_id, e);
+    // All or nothing: a failed insert leaves the trade's tags as they were
+    let tag_ids = &payload.tag_ids;
+    let added = in_transaction(&conn, async |tx| {
+        let mut added_tag_ids = Vec::new();
+        for tag_id in tag_ids {
+            // false: already tagged
+            if TradeTagAssociation::add_tag_to_option_trade(tx, option_trade_id, tag_id).await? {
+                added_tag_ids.push(tag_id.clone());
             }
         }
-    }
+        anyhow::Ok(added_tag_ids)
+    })
+    .await;
+    let added_tag_ids = match added {
+        Ok(ids) => ids,
+        Err(e) => {
+            error!("Failed to add tags to option trade {}: {}", option_trade_id, e);
+            return Ok(HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "message": format!("Failed to add tags: {}", e)
+            })));
+        }
+    };
+    let added_count = added_tag_ids.len();
+    let skipped_count = tag_ids.len() - added_count;
+
+    app_state.automation_service.spawn_tags_added(conn.clone(), user_id.clone(), TradeAsset::Option, option_trade_id, added_tag_ids);
 
     Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
@@ -11,6 +11,10 @@ use crate::turso::auth::{validate_supabase_jwt_token, AuthError};
 use crate::turso::schema::get_current_schema_version;
 use crate::service::cache_service::CacheService;
 use crate::service::image_upload::{ImageUploadService, SupabaseStorageConfig};
+use crate::service::i18n::{Locale, SUPPORTED_LOCALES};
+use crate::models::ai::chat_templates::{AiPersona, AI_PERSONAS};
+use crate::service::note_encryption;
+use crate::models::analytics::periods::{format_weekdays, parse_weekdays, PeriodDefinition};
 
 /// Request payload for user database initialization
 #[derive(Debug, Deserialize)]
@@ -123,6 +127,7 @@ pub async fn initialize_user_database(
     turso_client: web::Data<Arc<TursoClient>>,
     supabase_config: web::Data<SupabaseConfig>,
     cache_service: web::Data<Arc<CacheService>>,
+    app_state: web::Data<AppState>,
 ) -> Result<HttpResponse> {
     info!("=== Initialize User Database Called ===");
     info!("User: {} ({})", payload.email, payload.user_id);
@@ -163,6 +168,15 @@ pub async fn initialize_user_database(
     match create_user_database_internal(&turso_client, &payload.user_id, &payload.email).await {
         Ok((db_url, db_token, schema_synced, schema_version)) => {
             info!("Successfully initialized database for user: {}", payload.email);
+
+            // Make sure the user's Qdrant collection exists (no-op for existing users)
+            let tenant_manager = app_state.qdrant_tenant_manager.clone();
+            let tenant_user_id = payload.user_id.clone();
+            tokio::spawn(async move {
+                if let Err(e) = tenant_manager.provision(&tenant_user_id).await {
+                    error!("Failed to provision Qdrant collection for user {}: {}", tenant_user_id, e);
+                }
+            });
             
             // Preload user data into cache asynchronously
             let cache_service_clone = cache_service.get_ref().clone();
@@ -496,6 +510,17 @@ pub struct UpdateProfileRequest {
     pub primary_trading_goal: Option<String>,
     pub asset_types: Option<String>, // JSON array as string
     pub trading_style: Option<String>,
+    /// Language for AI output and labels ("en", "es", "fr", "de", "pt")
+    pub locale: Option<String>,
+    /// AI coaching voice ("strict_risk_manager", "supportive_coach", "quant_analyst"); empty clears it
+    pub ai_persona: Option<String>,
+    // Period definitions used by ytd/quarterly analytics and trading-day bucketing
+    pub fiscal_year_start_month: Option<u32>,
+    pub fiscal_year_start_day: Option<u32>,
+    pub session_offset_minutes: Option<i32>,
+    pub excluded_weekdays: Option<Vec<u8>>,
+    /// First day of the week: 0 = Sunday, 1 = Monday
+    pub week_start_day: Option<u8>,
 }
 
 /// Response payload for profile update
@@ -566,13 +591,20 @@ pub async fn get_profile(
                         "asset_types": null,
                         "trading_style": null,
                         "profile_picture_uuid": null,
+                        "locale": null,
+                        "fiscal_year_start_month": null,
+                        "fiscal_year_start_day": null,
+                        "session_offset_minutes": null,
+                        "excluded_weekdays": null,
+                        "ai_persona": null,
+                        "week_start_day": null,
                     }
                 })));
             }
 
             // Try to query the profile table
             let stmt_result = conn.prepare(
-                "SELECT nickname, display_name, timezone, currency, trading_experience_level, primary_trading_goal, asset_types, trading_style, profile_picture_uuid FROM user_profile LIMIT 1"
+                "SELECT nickname, display_name, timezone, currency, trading_experience_level, primary_trading_goal, asset_types, trading_style, profile_picture_uuid, locale, fiscal_year_start_month, fiscal_year_start_day, session_offset_minutes, excluded_weekdays, ai_persona, week_start_day FROM user_profile LIMIT 1"
             ).await;
 
             let stmt = match stmt_result {
@@ -592,6 +624,13 @@ pub async fn get_profile(
                             "asset_types": null,
                             "trading_style": null,
                             "profile_picture_uuid": null,
+                            "locale": null,
+                            "fiscal_year_start_month": null,
+                            "fiscal_year_start_day": null,
+                            "session_offset_minutes": null,
+                            "excluded_weekdays": null,
+                            "ai_persona": null,
+                            "week_start_day": null,
                         }
                     })));
                 }
@@ -618,6 +657,13 @@ pub async fn get_profile(
                     "asset_types": row.get::<Option<String>>(6).ok().flatten(),
                     "trading_style": row.get::<Option<String>>(7).ok().flatten(),
                     "profile_picture_uuid": row.get::<Option<String>>(8).ok().flatten(),
+                    "locale": row.get::<Option<String>>(9).ok().flatten(),
+                    "fiscal_year_start_month": row.get::<Option<i64>>(10).ok().flatten(),
+                    "fiscal_year_start_day": row.get::<Option<i64>>(11).ok().flatten(),
+                    "session_offset_minutes": row.get::<Option<i64>>(12).ok().flatten(),
+                    "excluded_weekdays": row.get::<Option<String>>(13).ok().flatten().map(|s| parse_weekdays(&s)),
+                    "ai_persona": row.get::<Option<String>>(14).ok().flatten(),
+                    "week_start_day": row.get::<Option<i64>>(15).ok().flatten(),
                 });
 
                 Ok(HttpResponse::Ok().json(serde_json::json!({
@@ -638,6 +684,13 @@ pub async fn get_profile(
                         "asset_types": null,
                         "trading_style": null,
                         "profile_picture_uuid": null,
+                        "locale": null,
+                        "fiscal_year_start_month": null,
+                        "fiscal_year_start_day": null,
+                        "session_offset_minutes": null,
+                        "excluded_weekdays": null,
+                        "ai_persona": null,
+                        "week_start_day": null,
                     }
                 })))
             }
@@ -700,7 +753,14 @@ pub async fn update_profile(
                 || payload.trading_experience_level.is_some()
                 || payload.primary_trading_goal.is_some()
                 || payload.asset_types.is_some()
-                || payload.trading_style.is_some();
+                || payload.trading_style.is_some()
+                || payload.locale.is_some()
+                || payload.ai_persona.is_some()
+                || payload.fiscal_year_start_month.is_some()
+                || payload.fiscal_year_start_day.is_some()
+                || payload.session_offset_minutes.is_some()
+                || payload.excluded_weekdays.is_some()
+                || payload.week_start_day.is_some();
 
             if !has_fields {
                 return Ok(HttpResponse::BadRequest().json(serde_json::json!({
@@ -709,6 +769,53 @@ pub async fn update_profile(
                 })));
             }
 
+            // Store the normalized code so prompts and labels can rely on it
+            let locale = match payload.locale.as_deref() {
+                Some(value) => match Locale::parse(value) {
+                    Some(locale) => Some(locale.code()),
+                    None => {
+                        let supported: Vec<&str> = SUPPORTED_LOCALES.iter().map(|l| l.code()).collect();
+                        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                            "success": false,
+                            "error": format!("Unsupported locale '{}'. Supported: {}", value, supported.join(", "))
+                        })));
+                    }
+                },
+                None => None,
+            };
+
+            // Empty string clears the persona back to the default voice
+            let ai_persona = match payload.ai_persona.as_deref().map(str::trim) {
+                Some("") => Some(None),
+                Some(value) => match AiPersona::parse(value) {
+                    Some(persona) => Some(Some(persona.code())),
+                    None => {
+                        let supported: Vec<&str> = AI_PERSONAS.iter().map(|p| p.code()).collect();
+                        return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                            "success": false,
+                            "error": format!("Unsupported AI persona '{}'. Supported: {}", value, supported.join(", "))
+                        })));
+                    }
+                },
+                None => None,
+            };
+
+            // Validate period settings against defaults for any field not being changed
+            let defaults = PeriodDefinition::default();
+            let periods = PeriodDefinition {
+                fiscal_year_start_month: payload.fiscal_year_start_month.unwrap_or(defaults.fiscal_year_start_month),
+                fiscal_year_start_day: payload.fiscal_year_start_day.unwrap_or(defaults.fiscal_year_start_day),
+                session_offset_minutes: payload.session_offset_minutes.unwrap_or(defaults.session_offset_minutes),
+                excluded_weekdays: payload.excluded_weekdays.as_deref().map(|d| parse_weekdays(&format_weekdays(d))).unwrap_or_default(),
+                week_start_day: payload.week_start_day.unwrap_or(defaults.week_start_day),
+            };
+            if let Err(message) = periods.validate() {
+                return Ok(HttpResponse::BadRequest().json(serde_json::json!({
+                    "success": false,
+                    "error": message
+                })));
+            }
+
             // Use INSERT OR REPLACE pattern - simpler approach
             // First check if profile exists
             let check_stmt = conn.prepare("SELECT COUNT(*) FROM user_profile").await
@@ -748,8 +855,8 @@ pub async fn update_profile(
                 info!("Inserting new profile");
                 conn.execute(
                     r#"
-                    INSERT INTO user_profile (nickname, display_name, timezone, currency, trading_experience_level, primary_trading_goal, asset_types, trading_style)
-                    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                    INSERT INTO user_profile (nickname, display_name, timezone, currency, trading_experience_level, primary_trading_goal, asset_types, trading_style, locale, fiscal_year_start_month, fiscal_year_start_day, session_offset_minutes, excluded_weekdays, ai_persona, week_start_day)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
                     "#,
                     libsql::params![
                         payload.nickname.as_deref(),
@@ -760,6 +867,13 @@ pub async fn update_profile(
                         payload.primary_trading_goal.as_deref(),
                         payload.asset_types.as_deref(),
                         payload.trading_style.as_deref(),
+                        locale.unwrap_or("en"),
+                        periods.fiscal_year_start_month,
+                        periods.fiscal_year_start_day,
+                        periods.session_offset_minutes,
+                        format_weekdays(&periods.excluded_weekdays),
+                        ai_persona.flatten(),
+                        periods.week_start_day,
                     ]
                 ).await.map_err(|e| {
                     error!("Failed to insert profile: {}", e);
@@ -797,6 +911,27 @@ pub async fn update_profile(
                 if let Some(ref v) = payload.trading_style {
                     conn.execute("UPDATE user_profile SET trading_style = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![v.clone()]).await.ok();
                 }
+                if let Some(v) = locale {
+                    conn.execute("UPDATE user_profile SET locale = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![v]).await.ok();
+                }
+                if let Some(v) = ai_persona {
+                    conn.execute("UPDATE user_profile SET ai_persona = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![v]).await.ok();
+                }
+                if payload.fiscal_year_start_month.is_some() {
+                    conn.execute("UPDATE user_profile SET fiscal_year_start_month = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![periods.fiscal_year_start_month]).await.ok();
+                }
+                if payload.fiscal_year_start_day.is_some() {
+                    conn.execute("UPDATE user_profile SET fiscal_year_start_day = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![periods.fiscal_year_start_day]).await.ok();
+                }
+                if payload.session_offset_minutes.is_some() {
+                    conn.execute("UPDATE user_profile SET session_offset_minutes = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![periods.session_offset_minutes]).await.ok();
+                }
+                if payload.excluded_weekdays.is_some() {
+                    conn.execute("UPDATE user_profile SET excluded_weekdays = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![format_weekdays(&periods.excluded_weekdays)]).await.ok();
+                }
+                if payload.week_start_day.is_some() {
+                    conn.execute("UPDATE user_profile SET week_start_day = ?, updated_at = CURRENT_TIMESTAMP", libsql::params![periods.week_start_day]).await.ok();
+                }
             }
 
             info!("Profile updated successfully for user: {}", user_id);
@@ -988,6 +1123,79 @@ pub async fn get_storage_usage(
     }
 }
 
+async fn user_connection(app_state: &AppState, user_id: &str) -> Result<libsql::Connection> {
+    app_state
+        .get_user_db_connection(user_id)
+        .await
+        .map_err(|e| {
+            error!("Failed to get database connection for user {}: {}", user_id, e);
+            actix_web::error::ErrorInternalServerError("Database connection failed")
+        })?
+        .ok_or_else(|| {
+            error!("No database found for user: {}", user_id);
+            actix_web::error::ErrorNotFound("User database not found")
+        })
+}
+
+fn note_encryption_response(user_id: &str, action: &str, result: anyhow::Result<note_encryption::NoteEncryptionStatus>) -> HttpResponse {
+    match result {
+        Ok(status) => HttpResponse::Ok().json(serde_json::json!({
+            "success": true,
+            "data": status
+        })),
+        Err(e) => {
+            error!("Failed to {} note encryption for user {}: {}", action, user_id, e);
+            HttpResponse::InternalServerError().json(serde_json::json!({
+                "success": false,
+                "error": format!("Failed to {} note encryption: {}", action, e)
+            }))
+        }
+    }
+}
+
+/// Get whether the user's note content is encrypted at rest
+pub async fn get_note_encryption(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = user_connection(&app_state, &claims.sub).await?;
+    Ok(note_encryption_response(&claims.sub, "read", note_encryption::status(&conn).await))
+}
+
+/// Turn on note encryption and seal existing notes
+pub async fn enable_note_encryption(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = user_connection(&app_state, &claims.sub).await?;
+    let result = note_encryption::enable(&conn).await;
+    invalidate_note_caches(&app_state, &claims.sub).await;
+    Ok(note_encryption_response(&claims.sub, "enable", result))
+}
+
+/// Turn off note encryption, decrypting existing notes
+pub async fn disable_note_encryption(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    supabase_config: web::Data<SupabaseConfig>,
+) -> Result<HttpResponse> {
+    let claims = get_authenticated_user(&req, &supabase_config).await?;
+    let conn = user_connection(&app_state, &claims.sub).await?;
+    let result = note_encryption::disable(&conn).await;
+    invalidate_note_caches(&app_state, &claims.sub).await;
+    Ok(note_encryption_response(&claims.sub, "disable", result))
+}
+
+async fn invalidate_note_caches(app_state: &AppState, user_id: &str) {
+    for table in ["trade_notes", "notebook_notes"] {
+        app_state.cache_service.invalidate_table_cache(user_id, table).await.ok();
+    }
+}
+
 /// Delete user account (irreversible)
 /// This deletes all user data including Turso database, Supabase Storage, vectors, and auth account
 pub async fn delete_account(
@@ -1046,6 +1254,9 @@ pub fn configure_user_routes(cfg: &mut web::ServiceConfig) {
             .route("/profile/{user_id}", web::put().to(update_profile))
             .route("/profile/picture/{user_id}", web::post().to(upload_profile_picture))
             .route("/storage", web::get().to(get_storage_usage))
+            .route("/note-encryption", web::get().to(get_note_encryption))
+            .route("/note-encryption", web::post().to(enable_note_encryption))
+            .route("/note-encryption", web::delete().to(disable_note_encryption))
             .route("/account", web::delete().to(delete_account))
     );
 }
\ No newline at end of file
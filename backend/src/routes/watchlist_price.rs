@@ -14,6 +14,7 @@ use crate::service::market_engine::{
         refresh_watchlist_and_alerts, update_watchlist_prices, update_price_alert_prices, check_price_alerts,
     },
     client::MarketClient,
+    hours,
 };
 
 #[derive(Debug, Serialize)]
@@ -605,6 +606,7 @@ pub async fn check_all_price_alerts(
     let mut total_alerts_triggered = 0u64;
     let mut success_count = 0u64;
     let mut failure_count = 0u64;
+    let mut skipped_closed = 0u64;
     
     let client = client_from_state(&app_state).map_err(actix_web::error::ErrorInternalServerError)?;
     let web_push_config = &app_state.config.web_push;
@@ -632,7 +634,9 @@ pub async fn check_all_price_alerts(
             if let Some(alert_row) = alert_rows.next().await.map_err(|_| actix_web::error::ErrorInternalServerError("Query failed"))? {
                 let alert_count: i64 = alert_row.get(0).unwrap_or(0);
                 
-                if alert_count > 0 {
+                if alert_count > 0 && !has_active_alert_market(&conn).await {
+                    skipped_closed += 1;
+                } else if alert_count > 0 {
                     // User has alerts, process them
                     match refresh_watchlist_and_alerts(&conn, &client, Some(&user_id), Some(web_push_config)).await {
                         Ok(triggered_alerts) => {
@@ -656,12 +660,30 @@ pub async fn check_all_price_alerts(
         "total_alerts_triggered": total_alerts_triggered,
         "success_count": success_count,
         "failure_count": failure_count,
+        "skipped_market_closed": skipped_closed,
     });
 
     info!("Price alert check completed: {:?}", summary);
     Ok(HttpResponse::Ok().json(ApiResponse::success(summary)))
 }
 
+/// Whether any of the user's alert symbols trades right now, extended hours included.
+/// Errors count as active so a bad query never silences alerts.
+async fn has_active_alert_market(conn: &libsql::Connection) -> bool {
+    let now = chrono::Utc::now();
+    let mut rows = match conn.query("SELECT DISTINCT symbol FROM price_alert", libsql::params![]).await {
+        Ok(rows) => rows,
+        Err(_) => return true,
+    };
+    while let Ok(Some(row)) = rows.next().await {
+        let symbol: String = row.get(0).unwrap_or_default();
+        if hours::is_active(hours::Exchange::for_symbol(&symbol), now) {
+            return true;
+        }
+    }
+    false
+}
+
 // =====================================================
 // ROUTE CONFIGURATION
 // =====================================================
@@ -0,0 +1,263 @@
+use actix_web::{web, HttpRequest, HttpResponse, Scope};
+use chrono::{Datelike, Utc};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::middleware::cron_auth::verify_cron_secret;
+use crate::routes::common::{require_user_id, user_db_connection};
+use crate::service::ai_service::report_sharing::DEFAULT_SHARE_DAYS;
+use crate::service::cost_ledger;
+use crate::service::image_upload::{ImageUploadService, SupabaseStorageConfig};
+use crate::service::year_review;
+use crate::turso::client::TursoClient;
+use crate::turso::AppState;
+
+fn storage() -> actix_web::Result<ImageUploadService> {
+    let config = SupabaseStorageConfig::from_env().map_err(|e| actix_web::error::ErrorServiceUnavailable(e.to_string()))?;
+    ImageUploadService::new(config).map_err(actix_web::error::ErrorInternalServerError)
+}
+
+/// Years a review can cover: not before 2000 and not after the current year
+fn valid_year(year: i32) -> bool {
+    (2000..=Utc::now().year()).contains(&year)
+}
+
+fn bad_year() -> HttpResponse {
+    HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": "year must be between 2000 and the current year"}))
+}
+
+fn not_found() -> HttpResponse {
+    HttpResponse::NotFound().json(serde_json::json!({"success": false, "message": "No review for that year"}))
+}
+
+pub fn configure_year_review_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(api_scope());
+}
+
+fn api_scope() -> Scope {
+    web::scope("/api/year-review")
+        .route("", web::get().to(list_reviews))
+        .route("", web::post().to(create_review))
+        .route("/{year}", web::get().to(get_review))
+        .route("/{year}/pdf", web::get().to(download_review))
+        .route("/{year}/share", web::post().to(share_review))
+        .route("/{year}/share", web::delete().to(revoke_review_shares))
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateReviewRequest {
+    /// Defaults to the year before the current one
+    year: Option<i32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ShareReviewRequest {
+    /// Link lifetime in days (1-90, default 7)
+    expires_in_days: Option<i64>,
+}
+
+async fn list_reviews(app: web::Data<AppState>, req: HttpRequest) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match year_review::list_reviews(&conn).await {
+        Ok(list) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": list}))),
+        Err(e) => {
+            error!("Failed to list year reviews: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to list year reviews"})))
+        }
+    }
+}
+
+/// Generate (or regenerate) a year's review on demand; the current year gives a review so far
+async fn create_review(app: web::Data<AppState>, req: HttpRequest, payload: web::Json<CreateReviewRequest>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let year = payload.into_inner().year.unwrap_or_else(|| year_review::default_year(Utc::now().date_naive()));
+    if !valid_year(year) {
+        return Ok(bad_year());
+    }
+    let conn = user_db_connection(&app, &user_id).await?;
+    let storage = storage()?;
+    match app.year_review_service.generate(&conn, &storage, &user_id, year, false).await {
+        Ok(review) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": review}))),
+        Err(e) => {
+            error!("Failed to generate {} year review for user {}: {}", year, user_id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to generate year review"})))
+        }
+    }
+}
+
+async fn get_review(app: web::Data<AppState>, req: HttpRequest, path: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match year_review::review_for_year(&conn, path.into_inner()).await {
+        Ok(Some(review)) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": review}))),
+        Ok(None) => Ok(not_found()),
+        Err(e) => {
+            error!("Failed to load year review: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load year review"})))
+        }
+    }
+}
+
+async fn pdf_response(review: &year_review::YearReview, disposition: &str) -> actix_web::Result<HttpResponse> {
+    let storage = storage()?;
+    match year_review::download(&storage, review).await {
+        Ok(bytes) => Ok(HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header(("Content-Disposition", format!("{}; filename=\"tradstry-{}-year-in-review.pdf\"", disposition, review.year)))
+            .body(bytes)),
+        Err(e) => {
+            error!("Failed to download year review {}: {}", review.id, e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to download year review"})))
+        }
+    }
+}
+
+/// The review PDF itself
+async fn download_review(app: web::Data<AppState>, req: HttpRequest, path: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match year_review::review_for_year(&conn, path.into_inner()).await {
+        Ok(Some(review)) => pdf_response(&review, "attachment").await,
+        Ok(None) => Ok(not_found()),
+        Err(e) => {
+            error!("Failed to load year review: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to load year review"})))
+        }
+    }
+}
+
+/// Expiring public link to the review PDF
+async fn share_review(
+    app: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    payload: Option<web::Json<ShareReviewRequest>>,
+) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let config = app
+        .config
+        .report_sharing
+        .as_ref()
+        .ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Report sharing is not configured"))?;
+    let year = path.into_inner();
+    let days = payload.map(|p| p.into_inner()).unwrap_or_default().expires_in_days.unwrap_or(DEFAULT_SHARE_DAYS);
+    let conn = user_db_connection(&app, &user_id).await?;
+    match year_review::create_share_link(&conn, config, &user_id, year, days).await {
+        Ok(Some(url)) => Ok(HttpResponse::Created().json(serde_json::json!({"success": true, "data": {"url": url, "expires_in_days": days}}))),
+        Ok(None) => Ok(not_found()),
+        Err(e) => Ok(HttpResponse::BadRequest().json(serde_json::json!({"success": false, "message": e.to_string()}))),
+    }
+}
+
+/// Invalidate every public link to the review
+async fn revoke_review_shares(app: web::Data<AppState>, req: HttpRequest, path: web::Path<i32>) -> actix_web::Result<HttpResponse> {
+    let user_id = require_user_id(&req)?;
+    let conn = user_db_connection(&app, &user_id).await?;
+    match year_review::revoke_share_links(&conn, path.into_inner()).await {
+        Ok(revoked) => Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": {"revoked": revoked}}))),
+        Err(e) => {
+            error!("Failed to revoke year review links: {}", e);
+            Ok(HttpResponse::InternalServerError().json(serde_json::json!({"success": false, "message": "Failed to revoke links"})))
+        }
+    }
+}
+
+/// Public view of a shared review: the PDF, inline
+pub async fn view_shared_year_review(app_state: web::Data<AppState>, path: web::Path<String>) -> actix_web::Result<HttpResponse> {
+    let not_found = || HttpResponse::NotFound().content_type("text/plain; charset=utf-8").body("This link is invalid, expired or has been revoked.");
+    let config = app_state
+        .config
+        .report_sharing
+        .as_ref()
+        .ok_or_else(|| actix_web::error::ErrorServiceUnavailable("Report sharing is not configured"))?;
+    let Some((user_id, year, nonce)) = year_review::resolve_share_token(config, &path.into_inner()) else {
+        return Ok(not_found());
+    };
+    let conn = match app_state.turso_client.get_user_database_connection(&user_id).await {
+        Ok(Some(conn)) => conn,
+        _ => return Ok(not_found()),
+    };
+    match year_review::shared_review(&conn, year, &nonce).await {
+        Ok(Some(review)) => {
+            let mut response = pdf_response(&review, "inline").await?;
+            response.headers_mut().insert(
+                actix_web::http::header::HeaderName::from_static("x-robots-tag"),
+                actix_web::http::header::HeaderValue::from_static("noindex"),
+            );
+            Ok(response)
+        }
+        Ok(None) => Ok(not_found()),
+        Err(e) => {
+            error!("Failed to load shared year review for user {}: {}", user_id, e);
+            Err(actix_web::error::ErrorInternalServerError("Failed to load shared review"))
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ReviewRunSummary {
+    year: i32,
+    users_checked: usize,
+    reviews_generated: usize,
+    users_skipped: usize,
+    users_failed: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunReviewsQuery {
+    /// Defaults to the year before the current one
+    year: Option<i32>,
+    /// Regenerate reviews that already exist
+    #[serde(default)]
+    force: bool,
+}
+
+/// January cron: last year's review for every user who closed trades in it
+pub async fn run_year_reviews(
+    req: HttpRequest,
+    app_state: web::Data<AppState>,
+    turso_client: web::Data<Arc<TursoClient>>,
+    query: web::Query<RunReviewsQuery>,
+) -> actix_web::Result<HttpResponse> {
+    verify_cron_secret(&req, &app_state.config.cron_secret)?;
+    let year = query.year.unwrap_or_else(|| year_review::default_year(Utc::now().date_naive()));
+    if !valid_year(year) {
+        return Ok(bad_year());
+    }
+    let user_ids = turso_client.list_active_user_ids().await.map_err(|e| {
+        error!("Failed to list active users for year reviews: {}", e);
+        actix_web::error::ErrorInternalServerError("Registry query failed")
+    })?;
+    let storage = storage()?;
+
+    let mut summary = ReviewRunSummary { year, ..Default::default() };
+    for user_id in user_ids {
+        let Ok(Some(conn)) = turso_client.get_user_database_connection(&user_id).await else {
+            summary.users_failed += 1;
+            continue;
+        };
+        summary.users_checked += 1;
+        if !query.force && matches!(year_review::review_for_year(&conn, year).await, Ok(Some(_))) {
+            summary.users_skipped += 1;
+            continue;
+        }
+        // Charge the narrative to the user rather than the shared account
+        let generated = cost_ledger::attribute_to(user_id.clone(), app_state.year_review_service.generate(&conn, &storage, &user_id, year, true)).await;
+        match generated {
+            Ok(Some(_)) => summary.reviews_generated += 1,
+            Ok(None) => summary.users_skipped += 1,
+            Err(e) => {
+                summary.users_failed += 1;
+                error!("Year review generation failed for user {}: {}", user_id, e);
+            }
+        }
+    }
+
+    info!(
+        "Year reviews for {}: {} generated, {} skipped, {} failed",
+        summary.year, summary.reviews_generated, summary.users_skipped, summary.users_failed
+    );
+    Ok(HttpResponse::Ok().json(serde_json::json!({"success": true, "data": summary})))
+}
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 use crate::turso::client::TursoClient;
 use crate::service::image_upload::ImageUploadService;
 use crate::service::ai_service::vectorization_service::VectorizationService;
-use crate::service::ai_service::qdrant_client::QdrantDocumentClient;
+use crate::service::ai_service::qdrant_tenant_manager::QdrantTenantManager;
 use crate::service::ai_service::UpstashSearchClient;
 
 /// Account deletion service for completely removing user data
@@ -16,7 +16,7 @@ pub struct AccountDeletionService {
     image_upload_service: Arc<ImageUploadService>,
     #[allow(dead_code)]
     vectorization_service: Arc<VectorizationService>,
-    qdrant_client: Arc<QdrantDocumentClient>,
+    qdrant_tenants: Arc<QdrantTenantManager>,
     upstash_search_client: Arc<UpstashSearchClient>,
     supabase_url: String,
     supabase_service_role_key: String,
@@ -28,7 +28,7 @@ impl AccountDeletionService {
         turso_client: Arc<TursoClient>,
         image_upload_service: Arc<ImageUploadService>,
         vectorization_service: Arc<VectorizationService>,
-        qdrant_client: Arc<QdrantDocumentClient>,
+        qdrant_tenants: Arc<QdrantTenantManager>,
         upstash_search_client: Arc<UpstashSearchClient>,
         supabase_url: String,
         supabase_service_role_key: String,
@@ -37,7 +37,7 @@ impl AccountDeletionService {
             turso_client,
             image_upload_service,
             vectorization_service,
-            qdrant_client,
+            qdrant_tenants,
             upstash_search_client,
             supabase_url,
             supabase_service_role_key,
@@ -111,6 +111,15 @@ impl AccountDeletionService {
 
         // Step 5: Remove from Registry Database
         info!("Step 5/6: Removing registry entry");
+        // Feature usage counts go with the telemetry id kept on the registry row
+        match self.turso_client.get_registry_connection().await {
+            Ok(registry) => {
+                if let Err(e) = crate::service::telemetry::set_enabled(&registry, user_id, false).await {
+                    warn!("Failed to delete feature usage counts for user {}: {}", user_id, e);
+                }
+            }
+            Err(e) => warn!("Failed to delete feature usage counts for user {}: {}", user_id, e),
+        }
         self.turso_client
             .remove_user_database_entry(user_id)
             .await
@@ -133,6 +142,41 @@ impl AccountDeletionService {
         Ok(())
     }
 
+    /// Reclaim everything left behind by a dead tenant: the Turso database, storage objects,
+    /// vectors and (when `remove_registry_entry`) the registry row. The auth account is left alone.
+    pub async fn reclaim_tenant(&self, user_id: &str, db_name: &str, remove_registry_entry: bool) -> Result<()> {
+        info!("Reclaiming dead tenant {} (database {})", user_id, db_name);
+
+        self.turso_client.delete_user_database(db_name).await?;
+        self.delete_supabase_storage_files(user_id).await?;
+        self.delete_supabase_database_entries(user_id).await?;
+        self.delete_vector_databases(user_id).await?;
+        if remove_registry_entry {
+            self.turso_client.remove_user_database_entry(user_id).await?;
+        }
+
+        info!("Reclaimed dead tenant {}", user_id);
+        Ok(())
+    }
+
+    /// Whether the Supabase Auth account still exists. Errors on anything but a clear yes/no.
+    pub async fn supabase_user_exists(&self, user_id: &str) -> Result<bool> {
+        let url = format!("{}/auth/v1/admin/users/{}", self.supabase_url, user_id);
+        let response = reqwest::Client::new()
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.supabase_service_role_key))
+            .header("apikey", self.supabase_service_role_key.clone())
+            .send()
+            .await
+            .context("Failed to look up Supabase Auth user")?;
+
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            reqwest::StatusCode::NOT_FOUND => Ok(false),
+            status => anyhow::bail!("Supabase Auth lookup returned {}", status),
+        }
+    }
+
     /// Delete all files from Supabase Storage for a user
     async fn delete_supabase_storage_files(&self, user_id: &str) -> Result<()> {
         info!("Deleting Supabase Storage files for user: {}", user_id);
@@ -211,9 +255,9 @@ impl AccountDeletionService {
         // This is a placeholder for now - actual implementation may require listing vectors first
         info!("Upstash Vector cleanup for user: {} (may require listing vectors first)", user_id);
 
-        // Delete from Qdrant (delete entire collection)
-        let _ = self.qdrant_client
-            .delete_user_collection(user_id)
+        // Delete the tenant's Qdrant collection
+        let _ = self.qdrant_tenants
+            .deprovision(user_id)
             .await
             .map_err(|e| warn!("Failed to delete Qdrant collection: {}", e));
 
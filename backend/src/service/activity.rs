@@ -0,0 +1,243 @@
+// Account activity feed. Trades opened and closed, notes, insights, rule violations and
+// monthly goals reached are merged into one reverse-chronological list. Every event gets a
+// sort key of its normalized timestamp plus a stable event id, and the page cursor is the
+// key of the last event returned, so pages stay consistent while new events arrive.
+
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use libsql::{params_from_iter, Connection, Value};
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::service::goal_pacing;
+
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 200;
+
+/// Sort key format; fixed width, so keys compare correctly as strings in SQL and Rust
+const KEY_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+pub const KINDS: [&str; 6] = ["trade_opened", "trade_closed", "note_created", "insight_generated", "rule_violated", "goal_hit"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityEvent {
+    pub id: String,
+    /// `trade_opened`, `trade_closed`, `note_created`, `insight_generated`, `rule_violated` or `goal_hit`
+    pub kind: String,
+    pub occurred_at: String,
+    /// What the event links to: `stock`, `option`, `note`, `insight` or `goal`
+    pub ref_type: String,
+    pub ref_id: String,
+    pub title: String,
+    /// Realized P&L for closed trades, the target for goals
+    pub amount: Option<f64>,
+    #[serde(skip)]
+    sort_key: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityPage {
+    pub events: Vec<ActivityEvent>,
+    /// Pass back as `cursor` for the next page; None on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ActivityQuery {
+    pub cursor: Option<String>,
+    pub limit: usize,
+    /// Only events at or after this time, e.g. the start of a weekly recap
+    pub since: Option<DateTime<Utc>>,
+    /// Restrict to these kinds; every kind when empty
+    pub kinds: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Cursor {
+    sort_key: String,
+    event_id: String,
+}
+
+fn encode_cursor(sort_key: &str, event_id: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(format!("{}|{}", sort_key, event_id))
+}
+
+fn decode_cursor(cursor: &str) -> Option<Cursor> {
+    let raw = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let (sort_key, event_id) = std::str::from_utf8(&raw).ok()?.split_once('|')?;
+    NaiveDateTime::parse_from_str(sort_key, KEY_FORMAT).ok()?;
+    Some(Cursor { sort_key: sort_key.to_string(), event_id: event_id.to_string() })
+}
+
+/// True when the event sorts after the cursor, i.e. belongs on a later page
+fn after_cursor(sort_key: &str, event_id: &str, cursor: &Cursor) -> bool {
+    (sort_key, event_id) < (cursor.sort_key.as_str(), cursor.event_id.as_str())
+}
+
+pub fn validate_kinds(kinds: &[String]) -> Result<(), String> {
+    match kinds.iter().find(|k| !KINDS.contains(&k.as_str())) {
+        Some(kind) => Err(format!("Unknown activity kind '{}'", kind)),
+        None => Ok(()),
+    }
+}
+
+fn union_sql() -> String {
+    let key = |col: &str| format!("strftime('%Y-%m-%d %H:%M:%f', {})", col);
+    format!(
+        "SELECT 'stock_opened:' || id AS event_id, 'trade_opened' AS kind, 'stock' AS ref_type, CAST(id AS TEXT) AS ref_id,
+                trade_type || ' ' || symbol AS title, NULL AS amount, {entry} AS sort_key
+         FROM stocks WHERE is_deleted = 0
+         UNION ALL
+         SELECT 'stock_closed:' || id, 'trade_closed', 'stock', CAST(id AS TEXT), trade_type || ' ' || symbol,
+                COALESCE(realized_pnl, {STOCK_PNL_SQL}), {exit}
+         FROM stocks WHERE is_deleted = 0 AND exit_price IS NOT NULL AND exit_date IS NOT NULL
+         UNION ALL
+         SELECT 'option_opened:' || id, 'trade_opened', 'option', CAST(id AS TEXT), symbol || ' ' || strategy_type, NULL, {entry}
+         FROM options WHERE is_deleted = 0
+         UNION ALL
+         SELECT 'option_closed:' || id, 'trade_closed', 'option', CAST(id AS TEXT), symbol || ' ' || strategy_type,
+                COALESCE(realized_pnl, {OPTION_PNL_SQL}), {exit}
+         FROM options WHERE is_deleted = 0 AND status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL
+         UNION ALL
+         SELECT 'note:' || id, 'note_created', 'note', id, title, NULL, {note}
+         FROM notebook_notes WHERE is_deleted = 0
+         UNION ALL
+         SELECT 'insight:' || id, 'insight_generated', 'insight', id, title, NULL, {insight}
+         FROM ai_insights
+         UNION ALL
+         SELECT 'violation:' || c.id, 'rule_violated', 'stock', CAST(c.stock_trade_id AS TEXT), r.title || ' (' || s.symbol || ')', NULL, {violation}
+         FROM stock_trade_rule_compliance c
+         JOIN playbook_rules r ON r.id = c.rule_id
+         JOIN stocks s ON s.id = c.stock_trade_id AND s.is_deleted = 0
+         WHERE c.is_followed = 0
+         UNION ALL
+         SELECT 'violation:' || c.id, 'rule_violated', 'option', CAST(c.option_trade_id AS TEXT), r.title || ' (' || o.symbol || ')', NULL, {violation}
+         FROM option_trade_rule_compliance c
+         JOIN playbook_rules r ON r.id = c.rule_id
+         JOIN options o ON o.id = c.option_trade_id AND o.is_deleted = 0
+         WHERE c.is_followed = 0",
+        entry = key("entry_date"),
+        exit = key("exit_date"),
+        note = key("created_at"),
+        insight = key("COALESCE(generated_at, created_at)"),
+        violation = key("c.created_at"),
+    )
+}
+
+fn to_event(event_id: String, kind: String, ref_type: String, ref_id: String, title: String, amount: Option<f64>, sort_key: String) -> ActivityEvent {
+    let occurred_at = NaiveDateTime::parse_from_str(&sort_key, KEY_FORMAT)
+        .map(|dt| crate::models::timestamps::to_db(&dt.and_utc()))
+        .unwrap_or_else(|_| sort_key.clone());
+    ActivityEvent { id: event_id, kind, occurred_at, ref_type, ref_id, title, amount, sort_key }
+}
+
+/// Events stored in the journal tables, newest first
+async fn stored_events(conn: &Connection, query: &ActivityQuery, cursor: Option<&Cursor>, fetch: usize) -> Result<Vec<ActivityEvent>> {
+    let mut filters = vec!["sort_key IS NOT NULL".to_string()];
+    let mut values: Vec<Value> = Vec::new();
+    if let Some(cursor) = cursor {
+        filters.push("(sort_key < ? OR (sort_key = ? AND event_id < ?))".to_string());
+        values.push(Value::Text(cursor.sort_key.clone()));
+        values.push(Value::Text(cursor.sort_key.clone()));
+        values.push(Value::Text(cursor.event_id.clone()));
+    }
+    if let Some(since) = query.since {
+        filters.push("sort_key >= ?".to_string());
+        values.push(Value::Text(since.format(KEY_FORMAT).to_string()));
+    }
+    if !query.kinds.is_empty() {
+        filters.push(format!("kind IN ({})", vec!["?"; query.kinds.len()].join(", ")));
+        values.extend(query.kinds.iter().map(|k| Value::Text(k.clone())));
+    }
+    values.push(Value::Integer(fetch as i64));
+
+    let sql = format!(
+        "SELECT event_id, kind, ref_type, ref_id, title, amount, sort_key FROM ({}) WHERE {} ORDER BY sort_key DESC, event_id DESC LIMIT ?",
+        union_sql(),
+        filters.join(" AND ")
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params_from_iter(values)).await?;
+    let mut events = Vec::new();
+    while let Some(row) = rows.next().await? {
+        events.push(to_event(
+            row.get(0)?,
+            row.get(1)?,
+            row.get(2)?,
+            row.get(3)?,
+            row.get::<Option<String>>(4)?.unwrap_or_default(),
+            money::to_f64_opt(money::row_decimal_opt(&row, 5)),
+            row.get(6)?,
+        ));
+    }
+    Ok(events)
+}
+
+/// Monthly goals whose P&L target was reached, dated by the trading day it was crossed
+async fn goal_events(conn: &Connection) -> Result<Vec<ActivityEvent>> {
+    let periods = PeriodDefinition::load(conn).await;
+    let mut events = Vec::new();
+    for goal in goal_pacing::list_goals(conn).await? {
+        let totals = goal_pacing::daily_totals(conn, &periods, &goal.month).await?;
+        let Some(day) = goal_pacing::goal_hit_date(&totals, goal.pnl_target) else { continue };
+        events.push(to_event(
+            format!("goal:{}", goal.month),
+            "goal_hit".to_string(),
+            "goal".to_string(),
+            goal.month.clone(),
+            format!("Monthly P&L goal reached for {}", goal.month),
+            Some(goal.pnl_target),
+            end_of_day_key(day),
+        ));
+    }
+    Ok(events)
+}
+
+fn end_of_day_key(day: NaiveDate) -> String {
+    format!("{} 23:59:59.999", day.format("%Y-%m-%d"))
+}
+
+/// One page of the feed, newest first
+pub async fn feed(conn: &Connection, query: &ActivityQuery) -> Result<ActivityPage> {
+    let limit = query.limit.clamp(1, MAX_LIMIT);
+    let cursor = query.cursor.as_deref().and_then(decode_cursor);
+    let mut events = stored_events(conn, query, cursor.as_ref(), limit + 1).await?;
+
+    if query.kinds.is_empty() || query.kinds.iter().any(|k| k == "goal_hit") {
+        let since = query.since.map(|s| s.format(KEY_FORMAT).to_string());
+        events.extend(goal_events(conn).await?.into_iter().filter(|e| {
+            cursor.as_ref().is_none_or(|c| after_cursor(&e.sort_key, &e.id, c))
+                && since.as_ref().is_none_or(|s| e.sort_key >= *s)
+        }));
+    }
+    events.sort_by(|a, b| (&b.sort_key, &b.id).cmp(&(&a.sort_key, &a.id)));
+
+    let next_cursor = if events.len() > limit {
+        events.truncate(limit);
+        events.last().map(|e| encode_cursor(&e.sort_key, &e.id))
+    } else {
+        None
+    };
+    Ok(ActivityPage { events, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip_and_ordering() {
+        let cursor = encode_cursor("2026-10-14 15:30:00.000", "stock_closed:42");
+        let decoded = decode_cursor(&cursor).unwrap();
+        assert_eq!(decoded, Cursor { sort_key: "2026-10-14 15:30:00.000".to_string(), event_id: "stock_closed:42".to_string() });
+        assert!(decode_cursor("not a cursor").is_none());
+
+        // Same instant breaks ties on the event id
+        assert!(after_cursor("2026-10-14 15:30:00.000", "stock_closed:41", &decoded));
+        assert!(!after_cursor("2026-10-14 15:30:00.000", "stock_closed:43", &decoded));
+        assert!(after_cursor("2026-10-13 09:00:00.000", "note:zzz", &decoded));
+        assert_eq!(end_of_day_key(NaiveDate::from_ymd_opt(2026, 10, 14).unwrap()).len(), decoded.sort_key.len());
+    }
+}
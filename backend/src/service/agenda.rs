@@ -0,0 +1,246 @@
+//! One chronological agenda across every calendar source: the user's own events, expanded
+//! reminders, synced external calendars, public holidays, earnings dates and the economic
+//! calendar. Each item carries a `type` so clients can style it without merging feeds.
+
+use anyhow::Result;
+use chrono::{Duration, NaiveDate};
+use futures_util::future::join_all;
+use libsql::Connection;
+use serde::Serialize;
+use std::collections::HashSet;
+
+use crate::models::notebook::{CalendarEvent, ExternalCalendarEvent};
+use crate::service::calendar_service::CalendarService;
+use crate::service::holidays_service::HolidaysService;
+use crate::service::market_engine::briefing::fetch_economic_events;
+use crate::service::market_engine::earnings_events::EARNINGS_EVENT_SOURCE;
+
+/// Longest range one agenda request may cover
+pub const MAX_AGENDA_DAYS: i64 = 93;
+/// The economic calendar is fetched per day, so only short ranges include it
+const ECONOMIC_EVENTS_MAX_DAYS: i64 = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgendaItemType {
+    Event,
+    Reminder,
+    External,
+    Holiday,
+    Earnings,
+    Economic,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AgendaItem {
+    #[serde(rename = "type")]
+    pub item_type: AgendaItemType,
+    pub id: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// `HH:MM`, None for all-day items
+    pub start_time: Option<String>,
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    pub all_day: bool,
+    /// Whether the item blocks the user's time; holidays and market events don't
+    pub busy: bool,
+    /// Symbol of earnings items, importance of economic ones, location of external ones
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Agenda {
+    pub start: String,
+    pub end: String,
+    pub items: Vec<AgendaItem>,
+    /// Sources that failed and are missing from `items`
+    pub unavailable: Vec<String>,
+}
+
+/// Agenda between two dates, inclusive. Sources that fail are skipped and listed in
+/// `unavailable` rather than failing the whole agenda.
+pub async fn build_agenda(conn: &Connection, start: NaiveDate, end: NaiveDate, country_code: &str) -> Result<Agenda> {
+    let (start_str, end_str) = (start.format("%Y-%m-%d").to_string(), end.format("%Y-%m-%d").to_string());
+    // External events store full timestamps; cover the whole end day
+    let external_end = format!("{}T23:59:59Z", end_str);
+    let economic_dates: Vec<String> = if (end - start).num_days() < ECONOMIC_EVENTS_MAX_DAYS {
+        (0..=(end - start).num_days()).map(|d| (start + Duration::days(d)).format("%Y-%m-%d").to_string()).collect()
+    } else {
+        Vec::new()
+    };
+
+    let (local, external, holidays, economic) = tokio::join!(
+        CalendarService::events_in_range(conn, &start_str, &end_str),
+        ExternalCalendarEvent::find_by_date_range(conn, &start_str, &external_end),
+        HolidaysService::get_holidays(conn, country_code, &start_str, &end_str),
+        join_all(economic_dates.iter().map(|date| fetch_economic_events(date))),
+    );
+
+    let mut items = Vec::new();
+    let mut unavailable = Vec::new();
+    match local {
+        Ok(events) => items.extend(local_items(events)),
+        Err(e) => {
+            log::warn!("Agenda: failed to load calendar events: {}", e);
+            unavailable.push("events".to_string());
+        }
+    }
+    match external {
+        Ok(events) => items.extend(events.into_iter().map(external_item)),
+        Err(e) => {
+            log::warn!("Agenda: failed to load external events: {}", e);
+            unavailable.push("external".to_string());
+        }
+    }
+    match holidays {
+        Ok(holidays) => items.extend(holidays.into_iter().map(|h| AgendaItem {
+            item_type: AgendaItemType::Holiday,
+            id: h.id,
+            title: h.holiday_name,
+            description: h.description,
+            date: h.holiday_date,
+            start_time: None,
+            end_date: None,
+            end_time: None,
+            all_day: true,
+            busy: false,
+            detail: Some(h.country_code),
+        })),
+        Err(e) => {
+            log::warn!("Agenda: failed to load holidays: {}", e);
+            unavailable.push("holidays".to_string());
+        }
+    }
+    let mut economic_failed = false;
+    for events in economic {
+        match events {
+            Ok(events) => items.extend(events.into_iter().map(|e| AgendaItem {
+                item_type: AgendaItemType::Economic,
+                id: format!("economic:{}:{}", e.date, e.event),
+                title: e.event,
+                description: e.country,
+                date: e.date,
+                start_time: e.time,
+                end_date: None,
+                end_time: None,
+                all_day: false,
+                busy: false,
+                detail: e.importance,
+            })),
+            Err(_) => economic_failed = true,
+        }
+    }
+    if economic_failed {
+        unavailable.push("economic".to_string());
+    }
+
+    items.sort_by(|a, b| {
+        // All-day items first within a day, then by time
+        (&a.date, !a.all_day, &a.start_time).cmp(&(&b.date, !b.all_day, &b.start_time))
+    });
+    Ok(Agenda { start: start_str, end: end_str, items, unavailable })
+}
+
+fn local_items(events: Vec<CalendarEvent>) -> Vec<AgendaItem> {
+    // A reminder can have more than one event row; show each occurrence once
+    let mut seen_reminders = HashSet::new();
+    events
+        .into_iter()
+        .filter(|event| match &event.reminder_id {
+            Some(reminder_id) => seen_reminders.insert((reminder_id.clone(), event.start_date.clone())),
+            None => true,
+        })
+        .map(|event| {
+            let item_type = match event.source.as_str() {
+                "reminder" if event.reminder_id.is_some() => AgendaItemType::Reminder,
+                source if source == EARNINGS_EVENT_SOURCE => AgendaItemType::Earnings,
+                _ => AgendaItemType::Event,
+            };
+            AgendaItem {
+                item_type,
+                busy: matches!(item_type, AgendaItemType::Event | AgendaItemType::Reminder) && !event.is_all_day,
+                id: event.id,
+                title: event.event_title,
+                description: event.event_description,
+                date: event.start_date,
+                start_time: event.start_time,
+                end_date: Some(event.end_date),
+                end_time: event.end_time,
+                all_day: event.is_all_day,
+                detail: event.source_ref,
+            }
+        })
+        .collect()
+}
+
+fn external_item(event: ExternalCalendarEvent) -> AgendaItem {
+    // RFC3339 timestamps, or bare dates for all-day events
+    let split = |value: &str| (value.get(..10).unwrap_or(value).to_string(), value.get(11..16).map(str::to_string));
+    let (date, start_time) = split(&event.start_time);
+    let (end_date, end_time) = split(&event.end_time);
+    AgendaItem {
+        item_type: AgendaItemType::External,
+        id: event.id,
+        title: event.title,
+        description: event.description,
+        all_day: start_time.is_none(),
+        busy: true,
+        date,
+        start_time,
+        end_date: Some(end_date),
+        end_time,
+        detail: event.location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, reminder_id: Option<&str>, source: &str, date: &str, time: Option<&str>) -> CalendarEvent {
+        CalendarEvent {
+            id: id.to_string(),
+            reminder_id: reminder_id.map(str::to_string),
+            event_title: id.to_string(),
+            event_description: None,
+            start_date: date.to_string(),
+            end_date: date.to_string(),
+            start_time: time.map(str::to_string),
+            end_time: None,
+            is_all_day: time.is_none(),
+            is_synced: false,
+            source: source.to_string(),
+            source_ref: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_local_items_types_and_reminder_dedupe() {
+        let items = local_items(vec![
+            event("a", Some("r1"), "reminder", "2025-03-03", Some("09:00")),
+            event("b", Some("r1"), "reminder", "2025-03-03", Some("09:00")),
+            event("c", None, EARNINGS_EVENT_SOURCE, "2025-03-04", None),
+        ]);
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].item_type, items[0].busy), (AgendaItemType::Reminder, true));
+        assert_eq!((items[1].item_type, items[1].busy), (AgendaItemType::Earnings, false));
+
+        let external = external_item(ExternalCalendarEvent {
+            id: "x".to_string(),
+            connection_id: "c".to_string(),
+            external_event_id: "e".to_string(),
+            title: "Dentist".to_string(),
+            description: None,
+            start_time: "2025-03-05T14:00:00Z".to_string(),
+            end_time: "2025-03-05T15:00:00Z".to_string(),
+            location: None,
+            last_synced_at: String::new(),
+        });
+        assert_eq!((external.date.as_str(), external.start_time.as_deref()), ("2025-03-05", Some("14:00")));
+    }
+}
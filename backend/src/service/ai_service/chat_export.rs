@@ -0,0 +1,120 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ai::chat::{ChatMessage, ChatSessionDetailsResponse, ContextSource, MessageRole};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Markdown,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// JSON export body; one session or all of them
+#[derive(Debug, Serialize)]
+pub struct ChatExport<'a> {
+    pub exported_at: DateTime<Utc>,
+    pub sessions: &'a [ChatSessionDetailsResponse],
+}
+
+fn role_heading(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "You",
+        MessageRole::Assistant => "Assistant",
+        MessageRole::System => "System",
+    }
+}
+
+/// One line per referenced source. Messages stored before sources were recorded only list
+/// vector IDs.
+fn references(message: &ChatMessage) -> Vec<String> {
+    match (&message.sources, &message.context_vectors) {
+        (Some(sources), _) if !sources.is_empty() => sources.iter().map(source_line).collect(),
+        (_, Some(vectors)) => vectors.iter().map(|id| format!("vector `{}`", id)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn source_line(source: &ContextSource) -> String {
+    format!(
+        "{} `{}` (relevance {:.2})",
+        source.data_type.replace('_', " "),
+        source.entity_id,
+        source.similarity_score
+    )
+}
+
+pub fn session_markdown(details: &ChatSessionDetailsResponse) -> String {
+    let session = &details.session;
+    let mut out = format!(
+        "# {}\n\nStarted {} · {} messages\n",
+        session.title.as_deref().unwrap_or("Untitled chat"),
+        session.created_at.format("%Y-%m-%d %H:%M UTC"),
+        details.messages.len()
+    );
+    for message in &details.messages {
+        out.push_str(&format!(
+            "\n## {} · {}\n\n{}\n",
+            role_heading(&message.role),
+            message.timestamp.format("%Y-%m-%d %H:%M"),
+            message.content.trim()
+        ));
+        let refs = references(message);
+        if !refs.is_empty() {
+            out.push_str("\nReferenced:\n");
+            for line in refs {
+                out.push_str(&format!("- {}\n", line));
+            }
+        }
+    }
+    out
+}
+
+/// Render sessions in the requested format
+pub fn render(sessions: &[ChatSessionDetailsResponse], format: ExportFormat) -> serde_json::Result<String> {
+    match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&ChatExport { exported_at: Utc::now(), sessions }),
+        ExportFormat::Markdown => Ok(sessions.iter().map(session_markdown).collect::<Vec<_>>().join("\n---\n\n")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ai::chat::ChatSession;
+
+    #[test]
+    fn test_markdown_lists_referenced_sources() {
+        let session = ChatSession::new("u1".to_string(), Some("Sizing review".to_string()));
+        let question = ChatMessage::new(session.id.clone(), MessageRole::User, "Why did TSLA hurt?".to_string());
+        let answer = ChatMessage::new(session.id.clone(), MessageRole::Assistant, "You sized up after a loss.".to_string())
+            .with_sources(&[ContextSource::new("v1".to_string(), "stock_trade".to_string(), "42".to_string(), 0.876, String::new())]);
+        let legacy = ChatMessage::new(session.id.clone(), MessageRole::Assistant, "Older answer".to_string())
+            .with_context(vec!["v9".to_string()]);
+        let details = ChatSessionDetailsResponse { session, messages: vec![question, answer, legacy], total_messages: 3 };
+
+        let markdown = session_markdown(&details);
+        assert!(markdown.starts_with("# Sizing review\n"));
+        assert!(markdown.contains("## You · "));
+        assert!(markdown.contains("- stock trade `42` (relevance 0.88)\n"));
+        assert!(markdown.contains("- vector `v9`\n"));
+        assert_eq!(markdown.matches("Referenced:").count(), 2);
+    }
+}
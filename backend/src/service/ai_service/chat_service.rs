@@ -4,11 +4,19 @@ use crate::models::ai::chat::{
     ChatMessage, ChatSession, ChatRequest, ChatResponse, ContextSource, 
     MessageRole, ChatSessionDetailsResponse, ChatSessionListResponse, ChatSessionSummary
 };
-use crate::models::ai::chat_templates::{ChatPromptConfig, ContextFormatter};
+use crate::models::ai::chat_templates::{self, ChatPromptConfig, ContextFormatter};
+use crate::service::ai_service::context_budget::{self, ContextBudget};
+use crate::service::ai_service::data_query::{self, DataAnswer};
 use crate::service::ai_service::hybrid_search_service::HybridSearchService;
+use crate::service::ai_service::memory_service::{self, UserMemoryService};
+use crate::service::ai_service::report_chat;
+use crate::service::ai_service::request_trace::{self, RequestTrace, Stage};
 use crate::service::ai_service::vectorization_service::VectorizationService;
 use crate::service::ai_service::openrouter_client::{OpenRouterClient, MessageRole as OpenRouterMessageRole};
 use crate::service::ai_service::voyager_client::VoyagerClient;
+use crate::service::cache_service::CacheService;
+use crate::service::i18n;
+use crate::service::market_engine::{client::MarketClient, financials, news};
 use crate::turso::client::TursoClient;
 use anyhow::{Result, Context};
 use chrono::Utc;
@@ -17,6 +25,22 @@ use serde_json;
 use std::sync::Arc;
 use uuid::Uuid;
 
+/// Prompt tokens held back so a rolling summary can grow without overflowing the window
+const SUMMARY_RESERVE_TOKENS: usize = 400;
+/// Confirmed user memories injected into a chat prompt
+const MAX_PROMPT_MEMORIES: usize = 5;
+/// Symbols whose fundamentals are looked up for one chat message
+const MAX_FUNDAMENTALS_SYMBOLS: usize = 2;
+/// Headlines per symbol included when a chat message asks about news
+const MAX_PROMPT_HEADLINES: usize = 5;
+
+/// Market data the chat prompt can pull company fundamentals and headlines from
+#[derive(Clone)]
+pub struct FundamentalsContext {
+    pub market_client: MarketClient,
+    pub cache_service: Arc<CacheService>,
+}
+
 /// AI Chat Service for handling chat functionality
 #[derive(Clone)]
 pub struct AIChatService {
@@ -27,6 +51,7 @@ pub struct AIChatService {
     voyager_client: Arc<VoyagerClient>,
     max_context_vectors: usize,
     prompt_config: ChatPromptConfig,
+    fundamentals: Option<FundamentalsContext>,
 }
 
 impl AIChatService {
@@ -46,9 +71,16 @@ impl AIChatService {
             voyager_client,
             max_context_vectors,
             prompt_config: ChatPromptConfig::default(),
+            fundamentals: None,
         }
     }
 
+    /// Let chat answers cite company fundamentals and recent headlines
+    pub fn with_fundamentals(mut self, fundamentals: FundamentalsContext) -> Self {
+        self.fundamentals = Some(fundamentals);
+        self
+    }
+
     /// Configure prompt templates dynamically
     pub fn configure_prompts(&mut self, config: ChatPromptConfig) {
         self.prompt_config = config;
@@ -91,12 +123,17 @@ impl AIChatService {
         messages: &[ChatMessage],
         query: &str,
         context_sources: &[ContextSource],
+        extra_sections: &[String],
     ) -> Vec<crate::service::ai_service::openrouter_client::ChatMessage> {
         let mut openrouter_messages = Vec::new();
         
-        // Add system prompt if this is the first user message or if we have context
-        if messages.len() == 1 || !context_sources.is_empty() {
-            let system_prompt = self.build_enhanced_system_prompt(query, context_sources);
+        // Add system prompt if this is the first user message, if we have context,
+        // or if there are remembered facts / an earlier-conversation summary to carry over
+        if messages.len() == 1 || !context_sources.is_empty() || !extra_sections.is_empty() {
+            let mut system_prompt = self.build_enhanced_system_prompt(query, context_sources);
+            for section in extra_sections {
+                system_prompt.push_str(&format!("\n\n{}", section));
+            }
             openrouter_messages.push(crate::service::ai_service::openrouter_client::ChatMessage {
                 role: OpenRouterMessageRole::System,
                 content: system_prompt,
@@ -131,6 +168,7 @@ impl AIChatService {
         conn: &Connection,
     ) -> Result<ChatResponse> {
         let start_time = std::time::Instant::now();
+        let mut trace = RequestTrace::start("chat");
         let message_preview = request.message.chars().take(100).collect::<String>();
         
         log::info!(
@@ -155,8 +193,11 @@ impl AIChatService {
 
         // Retrieve relevant context using vector similarity search with fallback
         let context_start = std::time::Instant::now();
-        let context_sources = if request.include_context.unwrap_or(true) {
-            match self.retrieve_context(user_id, &request.message, request.max_context_vectors.unwrap_or(self.max_context_vectors)).await {
+        let context_sources = if let Some(report_id) = &session.report_id {
+            log::info!("Context retrieval skipped - session pinned to report {}, user={}", report_id, user_id);
+            Vec::new()
+        } else if request.include_context.unwrap_or(true) {
+            match self.retrieve_context(user_id, &request.message, request.max_context_vectors.unwrap_or(self.max_context_vectors), &mut trace).await {
                 Ok(sources) => {
                     let context_time = context_start.elapsed().as_millis();
                     log::info!(
@@ -194,20 +235,35 @@ impl AIChatService {
         let user_message = ChatMessage::new(session.id.clone(), MessageRole::User, request.message.clone());
         messages.push(user_message.clone());
 
-        // Convert to OpenRouter format with enhanced prompts
+        // Fit history and context into the model's window before building the prompt
         let prompt_start = std::time::Instant::now();
-        let openrouter_messages = self.build_enhanced_messages(&messages, &request.message, &context_sources);
+        let pinned_section = self.report_section(conn, &session).await;
+        let (history, prompt_sources, extra_sections) = self
+            .apply_context_budget(conn, &session.id, &messages, &request.message, &context_sources, pinned_section)
+            .await;
+        let openrouter_messages = self.build_enhanced_messages(&history, &request.message, &prompt_sources, &extra_sections);
         let prompt_time = prompt_start.elapsed().as_millis();
+        trace.record(Stage::PromptBuild, prompt_start.elapsed());
         
         log::info!(
-            "Enhanced messages built [{}ms] - context_sources={}, history_messages={}, user={}",
-            prompt_time, context_sources.len(), messages.len(), user_id
+            "Enhanced messages built [{}ms] - context_sources={}, history_messages={}/{}, extra_sections={}, user={}",
+            prompt_time, prompt_sources.len(), history.len(), messages.len(), extra_sections.len(), user_id
         );
 
         // Generate AI response
         let ai_start = std::time::Instant::now();
-        let ai_response = self.openrouter_client.generate_chat(openrouter_messages).await?;
+        trace.model = Some(self.openrouter_client.model_selector().select());
+        let ai_response = match self.openrouter_client.generate_chat(openrouter_messages).await {
+            Ok(response) => response,
+            Err(e) => {
+                trace.record(Stage::Provider, ai_start.elapsed());
+                trace.success = false;
+                request_trace::spawn_save(self.turso_client.clone(), user_id, trace);
+                return Err(e);
+            }
+        };
         let ai_time = ai_start.elapsed().as_millis();
+        trace.record(Stage::Provider, ai_start.elapsed());
         
         log::info!(
             "AI response generated [{}ms] - response_length={}, user={}",
@@ -216,16 +272,18 @@ impl AIChatService {
 
         // Create assistant message
         let assistant_message = ChatMessage::new(session.id.clone(), MessageRole::Assistant, ai_response.clone())
-            .with_context(context_sources.iter().map(|s| s.vector_id.clone()).collect());
+            .with_sources(&context_sources);
 
         // Store messages in database
         let storage_start = std::time::Instant::now();
         self.store_message(conn, &user_message).await?;
         self.vectorize_message(&user_message, user_id).await.ok();
+        self.spawn_memory_extraction(conn, &user_message);
 
         self.store_message(conn, &assistant_message).await?;
         self.vectorize_message(&assistant_message, user_id).await.ok();
         let storage_time = storage_start.elapsed().as_millis();
+        trace.record(Stage::Persistence, storage_start.elapsed());
         
         log::info!(
             "Messages stored and vectorized [{}ms] - user_msg={}, ai_msg={}, user={}",
@@ -243,6 +301,7 @@ impl AIChatService {
         }
 
         let processing_time = start_time.elapsed().as_millis() as u64;
+        request_trace::spawn_save(self.turso_client.clone(), user_id, trace);
         
         log::info!(
             "Response generation completed [{}ms] - session={}ms, context={}ms, ai={}ms, storage={}ms, user={}",
@@ -267,6 +326,7 @@ impl AIChatService {
         conn: &Connection,
     ) -> Result<(tokio::sync::mpsc::Receiver<String>, String, String)> {
         let start_time = std::time::Instant::now();
+        let mut trace = RequestTrace::start("chat_stream");
         let message_preview = request.message.chars().take(100).collect::<String>();
         
         log::info!(
@@ -291,8 +351,11 @@ impl AIChatService {
 
         // Retrieve relevant context with fallback
         let context_start = std::time::Instant::now();
-        let context_sources = if request.include_context.unwrap_or(true) {
-            match self.retrieve_context(user_id, &request.message, request.max_context_vectors.unwrap_or(self.max_context_vectors)).await {
+        let context_sources = if let Some(report_id) = &session.report_id {
+            log::info!("Context retrieval skipped - session pinned to report {}, user={}", report_id, user_id);
+            Vec::new()
+        } else if request.include_context.unwrap_or(true) {
+            match self.retrieve_context(user_id, &request.message, request.max_context_vectors.unwrap_or(self.max_context_vectors), &mut trace).await {
                 Ok(sources) => {
                     let context_time = context_start.elapsed().as_millis();
                     log::info!(
@@ -330,19 +393,33 @@ impl AIChatService {
         let user_message = ChatMessage::new(session.id.clone(), MessageRole::User, request.message.clone());
         messages.push(user_message.clone());
 
-        // Convert to OpenRouter format with enhanced prompts
+        // Fit history and context into the model's window before building the prompt
         let prompt_start = std::time::Instant::now();
-        let openrouter_messages = self.build_enhanced_messages(&messages, &request.message, &context_sources);
+        let pinned_section = self.report_section(conn, &session).await;
+        let (history, prompt_sources, extra_sections) = self
+            .apply_context_budget(conn, &session.id, &messages, &request.message, &context_sources, pinned_section)
+            .await;
+        let openrouter_messages = self.build_enhanced_messages(&history, &request.message, &prompt_sources, &extra_sections);
         let prompt_time = prompt_start.elapsed().as_millis();
+        trace.record(Stage::PromptBuild, prompt_start.elapsed());
         
         log::info!(
-            "Enhanced messages built [{}ms] - context_sources={}, history_messages={}, user={}",
-            prompt_time, context_sources.len(), messages.len(), user_id
+            "Enhanced messages built [{}ms] - context_sources={}, history_messages={}/{}, extra_sections={}, user={}",
+            prompt_time, prompt_sources.len(), history.len(), messages.len(), extra_sections.len(), user_id
         );
 
         // Generate streaming AI response
         let stream_start = std::time::Instant::now();
-        let mut stream_receiver = self.openrouter_client.generate_chat_stream(openrouter_messages).await?;
+        trace.model = Some(self.openrouter_client.model_selector().select());
+        let mut stream_receiver = match self.openrouter_client.generate_chat_stream(openrouter_messages).await {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                trace.record(Stage::Provider, stream_start.elapsed());
+                trace.success = false;
+                request_trace::spawn_save(self.turso_client.clone(), user_id, trace);
+                return Err(e);
+            }
+        };
         let stream_init_time = stream_start.elapsed().as_millis();
         
         log::info!(
@@ -354,7 +431,9 @@ impl AIChatService {
         let user_msg_start = std::time::Instant::now();
         self.store_message(conn, &user_message).await?;
         self.vectorize_message(&user_message, user_id).await.ok();
+        self.spawn_memory_extraction(conn, &user_message);
         let user_msg_time = user_msg_start.elapsed().as_millis();
+        trace.record(Stage::Persistence, user_msg_start.elapsed());
         
         log::info!(
             "User message stored and vectorized [{}ms] - message_id={}, user={}",
@@ -370,6 +449,7 @@ impl AIChatService {
             content: String::new(), // Will be updated as stream progresses
             timestamp: Utc::now(),
             context_vectors: Some(context_sources.iter().map(|s| s.vector_id.clone()).collect()),
+            sources: Some(context_sources.clone()),
             token_count: None,
         };
 
@@ -399,7 +479,9 @@ impl AIChatService {
         let msg_id = assistant_message_id.clone();
         let user_id_clone = user_id.to_string();
         let assistant_message_clone = assistant_message.clone();
-        tokio::spawn(async move {
+        // Vectorizing the finished reply is still this user's cost
+        tokio::spawn(crate::service::cost_ledger::attribute_to(user_id.to_string(), async move {
+            let mut trace = trace;
             let mut accumulated = String::new();
             let mut token_count = 0;
             
@@ -408,7 +490,11 @@ impl AIChatService {
             while let Some(token) = stream_receiver.recv().await {
                 accumulated.push_str(&token);
                 token_count += 1;
-                frontend_tx.send(token).await.ok();
+                if frontend_tx.send(token).await.is_err() {
+                    // Client disconnected; keep what was generated and let the provider stream stop
+                    log::info!("Client disconnected from stream for message={}, saving partial content", msg_id);
+                    break;
+                }
                 
                 // Log progress every 10 tokens
                 if token_count % 10 == 0 {
@@ -418,6 +504,10 @@ impl AIChatService {
                     );
                 }
             }
+            // Closing the receiver ends the provider stream if we stopped early
+            drop(stream_receiver);
+            // Provider time runs until the last token, not just until the stream opened
+            trace.record(Stage::Provider, stream_start.elapsed());
             
             log::info!(
                 "Token accumulation completed - message={}, total_tokens={}, final_length={}, user={}",
@@ -425,6 +515,7 @@ impl AIChatService {
             );
             
             // Update database with final content
+            let persist_start = std::time::Instant::now();
             if let Ok(Some(conn)) = service.turso_client.get_user_database_connection(&user_id_clone).await {
                 let update_start = std::time::Instant::now();
                 if let Err(e) = service.update_message_content(&conn, &msg_id, accumulated.clone()).await {
@@ -453,7 +544,9 @@ impl AIChatService {
             } else {
                 log::error!("Failed to get database connection for user {} to save message {}", user_id_clone, msg_id);
             }
-        });
+            trace.record(Stage::Persistence, persist_start.elapsed());
+            request_trace::spawn_save(service.turso_client.clone(), &user_id_clone, trace);
+        }));
 
         let total_time = start_time.elapsed().as_millis();
         log::info!(
@@ -519,6 +612,7 @@ impl AIChatService {
         user_id: &str,
         query: &str,
         max_vectors: usize,
+        trace: &mut RequestTrace,
     ) -> Result<Vec<ContextSource>> {
         let start_time = std::time::Instant::now();
         let query_preview = query.chars().take(100).collect::<String>();
@@ -535,10 +629,15 @@ impl AIChatService {
             user_id, query_preview, max_vectors
         );
 
+        let query_vector = self.hybrid_search_service.embed_query(user_id, query).await?;
+        trace.record(Stage::Embedding, search_start.elapsed());
+
+        let vector_start = std::time::Instant::now();
         let hybrid_results = self.hybrid_search_service
-            .hybrid_search_with_reranking(user_id, query, max_vectors, None)
+            .hybrid_search_with_reranking(user_id, query, &query_vector, max_vectors, None)
             .await
             .context("Failed to perform vector search")?;
+        trace.record(Stage::VectorSearch, vector_start.elapsed());
         
         let search_time = search_start.elapsed().as_millis();
         
@@ -598,10 +697,31 @@ impl AIChatService {
         title: Option<String>,
     ) -> Result<ChatSession> {
         let session = ChatSession::new(user_id.to_string(), title);
-        
+        self.insert_session(conn, &session).await?;
+        Ok(session)
+    }
+
+    /// Create a session pinned to a saved report; its answers come from the report's
+    /// analytics and trades as generated rather than from fresh retrieval
+    pub async fn create_report_session(
+        &self,
+        conn: &Connection,
+        user_id: &str,
+        report_id: &str,
+    ) -> Result<ChatSession> {
+        let snapshot = report_chat::load_snapshot(conn, report_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Report not found"))?;
+        let mut session = ChatSession::new(user_id.to_string(), Some(format!("Report: {}", snapshot.title)));
+        session.report_id = Some(snapshot.id);
+        self.insert_session(conn, &session).await?;
+        Ok(session)
+    }
+
+    async fn insert_session(&self, conn: &Connection, session: &ChatSession) -> Result<()> {
         conn.execute(
-            "INSERT INTO chat_sessions (id, user_id, title, created_at, updated_at, message_count, last_message_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO chat_sessions (id, user_id, title, created_at, updated_at, message_count, last_message_at, report_id) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 session.id.clone(),
                 session.user_id.clone(),
@@ -609,11 +729,28 @@ impl AIChatService {
                 session.created_at.to_rfc3339(),
                 session.updated_at.to_rfc3339(),
                 session.message_count,
-                session.last_message_at.map(|d| d.to_rfc3339())
+                session.last_message_at.map(|d| d.to_rfc3339()),
+                session.report_id.clone()
             ],
         ).await?;
+        Ok(())
+    }
 
-        Ok(session)
+    /// Pinned snapshot section for a report session; a report deleted since the session
+    /// was opened leaves the session as a plain chat
+    async fn report_section(&self, conn: &Connection, session: &ChatSession) -> Option<String> {
+        let report_id = session.report_id.as_deref()?;
+        match report_chat::load_snapshot(conn, report_id).await {
+            Ok(Some(snapshot)) => Some(report_chat::prompt_section(&snapshot, report_chat::MAX_SNAPSHOT_CHARS)),
+            Ok(None) => {
+                log::warn!("Report {} pinned to session {} no longer exists", report_id, session.id);
+                None
+            }
+            Err(e) => {
+                log::warn!("Failed to load report {} for session {}: {}", report_id, session.id, e);
+                None
+            }
+        }
     }
 
     /// Get a chat session by ID
@@ -624,7 +761,7 @@ impl AIChatService {
         user_id: &str,
     ) -> Result<ChatSession> {
         let stmt = conn.prepare(
-            "SELECT id, user_id, title, created_at, updated_at, message_count, last_message_at 
+            "SELECT id, user_id, title, created_at, updated_at, message_count, last_message_at, report_id 
              FROM chat_sessions WHERE id = ? AND user_id = ?"
         ).await?;
         
@@ -640,6 +777,7 @@ impl AIChatService {
                 message_count: row.get(5)?,
                 last_message_at: row.get::<Option<String>>(6)?
                     .map(|s| chrono::DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
+                report_id: row.get(7)?,
             })
         } else {
             Err(anyhow::anyhow!("Session not found"))
@@ -664,7 +802,7 @@ impl AIChatService {
 
         // Get sessions
         let stmt = conn.prepare(
-            "SELECT id, user_id, title, created_at, updated_at, message_count, last_message_at 
+            "SELECT id, user_id, title, created_at, updated_at, message_count, last_message_at, report_id 
              FROM chat_sessions WHERE user_id = ? 
              ORDER BY updated_at DESC LIMIT ? OFFSET ?"
         ).await?;
@@ -682,6 +820,7 @@ impl AIChatService {
                 message_count: row.get(5)?,
                 last_message_at: row.get::<Option<String>>(6)?
                     .map(|s| chrono::DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
+                report_id: row.get(7)?,
             };
             
             sessions.push(ChatSessionSummary::from(session));
@@ -710,6 +849,29 @@ impl AIChatService {
         })
     }
 
+    /// Every session with its messages, oldest first (for exports)
+    pub async fn get_all_session_details(
+        &self,
+        conn: &Connection,
+        user_id: &str,
+    ) -> Result<Vec<ChatSessionDetailsResponse>> {
+        let mut rows = conn
+            .prepare("SELECT id FROM chat_sessions WHERE user_id = ? ORDER BY created_at ASC")
+            .await?
+            .query([user_id])
+            .await?;
+        let mut session_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            session_ids.push(row.get::<String>(0)?);
+        }
+
+        let mut sessions = Vec::with_capacity(session_ids.len());
+        for session_id in session_ids {
+            sessions.push(self.get_session_details(conn, &session_id, user_id).await?);
+        }
+        Ok(sessions)
+    }
+
     /// Get messages for a session
     async fn get_session_messages(
         &self,
@@ -717,7 +879,7 @@ impl AIChatService {
         session_id: &str,
     ) -> Result<Vec<ChatMessage>> {
         let stmt = conn.prepare(
-            "SELECT id, session_id, role, content, context_vectors, token_count, created_at 
+            "SELECT id, session_id, role, content, context_vectors, token_count, created_at, context_sources 
              FROM chat_messages WHERE session_id = ? ORDER BY created_at ASC"
         ).await?;
         
@@ -744,6 +906,10 @@ impl AIChatService {
                 content: row.get(3)?,
                 timestamp: chrono::DateTime::parse_from_rfc3339(&row.get::<String>(6)?)?.with_timezone(&Utc),
                 context_vectors: context_vectors_parsed,
+                // Older messages only recorded vector IDs
+                sources: row
+                    .get::<Option<String>>(7)?
+                    .and_then(|json| serde_json::from_str(&json).ok()),
                 token_count: row.get(5)?,
             });
         }
@@ -751,6 +917,201 @@ impl AIChatService {
         Ok(messages)
     }
 
+    /// Trim history and retrieved context to the prompt budget. Turns that no longer fit are
+    /// folded into the session's rolling summary; if summarizing fails they are simply dropped.
+    /// Returns the kept history, the kept sources and extra system prompt sections
+    /// (pinned report snapshot, persona, reply language, confirmed user memories, fundamentals,
+    /// recent headlines and the rolling summary).
+    async fn apply_context_budget(
+        &self,
+        conn: &Connection,
+        session_id: &str,
+        messages: &[ChatMessage],
+        query: &str,
+        context_sources: &[ContextSource],
+        pinned_section: Option<String>,
+    ) -> (Vec<ChatMessage>, Vec<ContextSource>, Vec<String>) {
+        let budget = ContextBudget::from_env(self.openrouter_client.max_tokens());
+        let sources = context_budget::fit_context_sources(context_sources, budget.context_tokens());
+
+        let persona_section = chat_templates::user_persona(conn).await.map(|p| self.prompt_config.persona_template(p).prompt_section());
+        let language_section = i18n::prompt_instruction(conn).await;
+        let memory_section = memory_service::memory_prompt_section(conn, query, MAX_PROMPT_MEMORIES).await;
+        let fundamentals_section = self.fundamentals_section(conn, query).await;
+        let news_section = self.news_section(conn, query).await;
+
+        let (mut summary, summarized_until) = match self.get_rolling_summary(conn, session_id).await {
+            Ok(state) => state,
+            Err(e) => {
+                log::warn!("Failed to load rolling summary for session {}: {}", session_id, e);
+                (None, None)
+            }
+        };
+
+        let system_tokens = context_budget::estimate_tokens(&self.build_enhanced_system_prompt(query, &sources))
+            + pinned_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0)
+            + persona_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0)
+            + language_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0)
+            + memory_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0)
+            + fundamentals_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0)
+            + news_section.as_deref().map(context_budget::estimate_tokens).unwrap_or(0);
+        // Leave room for the summary to grow when new turns are folded in
+        let summary_tokens = summary.as_deref().map(context_budget::estimate_tokens).unwrap_or(0) + SUMMARY_RESERVE_TOKENS;
+        let history_budget = budget.prompt_tokens().saturating_sub(system_tokens + summary_tokens);
+        let plan = context_budget::plan_history(messages, history_budget);
+
+        let unsummarized: Vec<ChatMessage> = plan.overflow
+            .iter()
+            .filter(|m| summarized_until.is_none_or(|until| m.timestamp > until))
+            .cloned()
+            .collect();
+
+        if let Some(last) = unsummarized.last() {
+            let prompt = context_budget::build_summary_prompt(summary.as_deref(), &unsummarized, budget.prompt_tokens() / 2);
+            let request = vec![crate::service::ai_service::openrouter_client::ChatMessage {
+                role: OpenRouterMessageRole::User,
+                content: prompt,
+            }];
+            match self.openrouter_client.generate_chat(request).await {
+                Ok(updated) if !updated.trim().is_empty() => {
+                    let updated = updated.trim().to_string();
+                    if let Err(e) = self.save_rolling_summary(conn, session_id, &updated, &last.timestamp.to_rfc3339()).await {
+                        log::warn!("Failed to store rolling summary for session {}: {}", session_id, e);
+                    }
+                    summary = Some(updated);
+                }
+                Ok(_) => log::warn!("Empty rolling summary for session {}, dropping {} old messages", session_id, unsummarized.len()),
+                Err(e) => log::warn!("Failed to summarize session {}: {}. Dropping {} old messages", session_id, e, unsummarized.len()),
+            }
+        }
+
+        // A summary only matters once something has actually been dropped from the window
+        let summary = summary
+            .filter(|_| !plan.overflow.is_empty())
+            .map(|s| format!("Earlier conversation summary:\n{}", s));
+        let sections = pinned_section.into_iter().chain(persona_section).chain(language_section).chain(memory_section).chain(fundamentals_section).chain(news_section).chain(summary).collect();
+        (plan.kept, sources, sections)
+    }
+
+    /// Fundamentals for tickers named in a question about the business, so the answer can
+    /// weigh the user's trades against real numbers; failures only log
+    async fn fundamentals_section(&self, conn: &Connection, query: &str) -> Option<String> {
+        if !financials::asks_about_fundamentals(query) {
+            return None;
+        }
+        let market = self.fundamentals.as_ref()?;
+        let traded = match self.traded_symbols(conn).await {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                log::warn!("Failed to load traded symbols: {}", e);
+                Vec::new()
+            }
+        };
+        let mut lines = Vec::new();
+        for symbol in financials::mentioned_symbols(query, &traded).into_iter().take(MAX_FUNDAMENTALS_SYMBOLS) {
+            match financials::cached_fundamentals(&market.market_client, &market.cache_service, &symbol, None).await {
+                Ok(snapshot) => lines.push(format!("- {}", financials::format_for_prompt(&snapshot))),
+                Err(e) => log::warn!("Failed to load fundamentals for {}: {}", symbol, e),
+            }
+        }
+        (!lines.is_empty()).then(|| format!("Company fundamentals (from the latest filings):\n{}", lines.join("\n")))
+    }
+
+    /// Recent deduplicated headlines for tickers named in a question about news, from the
+    /// same cached feed the dossier page reads; failures only log
+    async fn news_section(&self, conn: &Connection, query: &str) -> Option<String> {
+        if !news::asks_about_news(query) {
+            return None;
+        }
+        let market = self.fundamentals.as_ref()?;
+        let traded = match self.traded_symbols(conn).await {
+            Ok(symbols) => symbols,
+            Err(e) => {
+                log::warn!("Failed to load traded symbols: {}", e);
+                Vec::new()
+            }
+        };
+        let mut blocks = Vec::new();
+        for symbol in financials::mentioned_symbols(query, &traded).into_iter().take(MAX_FUNDAMENTALS_SYMBOLS) {
+            match news::symbol_news(&market.market_client, &market.cache_service, &symbol, None, MAX_PROMPT_HEADLINES).await {
+                Ok(page) if !page.items.is_empty() => blocks.push(news::format_for_prompt(&page)),
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to load news for {}: {}", symbol, e),
+            }
+        }
+        (!blocks.is_empty()).then(|| format!("Recent headlines:\n{}", blocks.join("\n")))
+    }
+
+    async fn traded_symbols(&self, conn: &Connection) -> Result<Vec<String>> {
+        let mut rows = conn
+            .query("SELECT DISTINCT symbol FROM stocks UNION SELECT DISTINCT symbol FROM options", params![])
+            .await?;
+        let mut symbols = Vec::new();
+        while let Some(row) = rows.next().await? {
+            symbols.push(row.get::<String>(0)?);
+        }
+        Ok(symbols)
+    }
+
+    /// Look for durable facts in a user message in the background; they're stored as
+    /// proposals and only used once the user confirms them
+    fn spawn_memory_extraction(&self, conn: &Connection, message: &ChatMessage) {
+        if !memory_service::looks_memorable(&message.content) {
+            return;
+        }
+        let conn = conn.clone();
+        let openrouter = Arc::clone(&self.openrouter_client);
+        let message = message.clone();
+        tokio::spawn(async move {
+            let candidates = match memory_service::extract_memory_candidates(&openrouter, &message.content).await {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    log::debug!("Memory extraction failed for message {}: {}", message.id, e);
+                    return;
+                }
+            };
+            if candidates.is_empty() {
+                return;
+            }
+            match UserMemoryService::new(&conn).propose(candidates, Some(&message.session_id), Some(&message.id)).await {
+                Ok(proposed) if !proposed.is_empty() => log::info!("Proposed {} memories from message {}", proposed.len(), message.id),
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to store proposed memories: {}", e),
+            }
+        });
+    }
+
+    async fn get_rolling_summary(
+        &self,
+        conn: &Connection,
+        session_id: &str,
+    ) -> Result<(Option<String>, Option<chrono::DateTime<Utc>>)> {
+        let stmt = conn.prepare("SELECT rolling_summary, summarized_until FROM chat_sessions WHERE id = ?").await?;
+        let mut rows = stmt.query([session_id]).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok((None, None));
+        };
+        let summary: Option<String> = row.get(0)?;
+        let until = row.get::<Option<String>>(1)?
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(&s).ok())
+            .map(|d| d.with_timezone(&Utc));
+        Ok((summary, until))
+    }
+
+    async fn save_rolling_summary(
+        &self,
+        conn: &Connection,
+        session_id: &str,
+        summary: &str,
+        summarized_until: &str,
+    ) -> Result<()> {
+        conn.execute(
+            "UPDATE chat_sessions SET rolling_summary = ?, summarized_until = ? WHERE id = ?",
+            params![summary, summarized_until, session_id],
+        ).await?;
+        Ok(())
+    }
+
     /// Update message content after streaming completes
     async fn update_message_content(
         &self,
@@ -784,17 +1145,23 @@ impl AIChatService {
         } else {
             None
         };
+        let sources_json = message.sources.as_ref().map(serde_json::to_string).transpose()?;
+
+        // Remember which model answered so feedback can be attributed to it
+        let model = (message.role == MessageRole::Assistant).then(|| self.openrouter_client.active_model());
 
         conn.execute(
-            "INSERT INTO chat_messages (id, session_id, role, content, context_vectors, token_count, created_at) 
-             VALUES (?, ?, ?, ?, ?, ?, ?)",
+            "INSERT INTO chat_messages (id, session_id, role, content, context_vectors, context_sources, token_count, model, created_at) 
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 message.id.clone(),
                 message.session_id.clone(), // FIXED: Use actual session_id instead of timestamp
                 message.role.to_string(),
                 message.content.clone(),
                 context_vectors_json,
+                sources_json,
                 message.token_count,
+                model,
                 message.timestamp.to_rfc3339()
             ],
         ).await?;
@@ -921,6 +1288,54 @@ impl AIChatService {
         Ok(result)
     }
 
+    /// Answer an aggregate question ("what's my most profitable setup this year?") from
+    /// analytics rather than retrieved context: the model plans catalog calls, we run them,
+    /// and the model answers from the results. A plan that fails to parse is retried once
+    /// with the error.
+    pub async fn answer_data_question(&self, conn: &Connection, question: &str) -> Result<DataAnswer> {
+        use crate::service::ai_service::openrouter_client::ChatMessage as OpenRouterMessage;
+        let message = |role, content: String| OpenRouterMessage { role, content };
+
+        let mut planning = vec![
+            message(OpenRouterMessageRole::System, data_query::plan_prompt(Utc::now().date_naive())),
+            message(OpenRouterMessageRole::User, question.to_string()),
+        ];
+        let mut plan = None;
+        for _ in 0..2 {
+            let response = self.openrouter_client.generate_chat(planning.clone()).await?;
+            match data_query::parse_plan(&response) {
+                Ok(parsed) => {
+                    plan = Some(parsed);
+                    break;
+                }
+                Err(e) => {
+                    log::warn!("Rejected data query plan: {}", e);
+                    planning.push(message(OpenRouterMessageRole::Assistant, response));
+                    planning.push(message(OpenRouterMessageRole::User, format!("{}. Reply with a corrected plan only.", e)));
+                }
+            }
+        }
+        let plan = plan.context("AI service did not return a valid query plan")?;
+        if plan.steps.is_empty() {
+            return Ok(DataAnswer { answer: data_query::NOT_ANSWERABLE.to_string(), plan, results: Vec::new() });
+        }
+
+        let results = data_query::execute(conn, &plan).await?;
+        let mut system = data_query::answer_prompt(&results);
+        if let Some(language) = i18n::prompt_instruction(conn).await {
+            system.push_str("\n\n");
+            system.push_str(&language);
+        }
+        let answer = self
+            .openrouter_client
+            .generate_chat(vec![
+                message(OpenRouterMessageRole::System, system),
+                message(OpenRouterMessageRole::User, question.to_string()),
+            ])
+            .await?;
+        Ok(DataAnswer { answer: answer.trim().to_string(), plan, results })
+    }
+
     /// Health check for AI chat service
     pub async fn health_check(&self) -> Result<()> {
         // Check vectorization service
@@ -0,0 +1,518 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::models::ai::insights::{Insight, InsightType};
+use crate::models::analytics::CoreMetrics;
+use crate::models::analytics::confidence::{wilson_interval, LOW_SAMPLE_TRADES};
+use crate::models::stock::stocks::TimeRange;
+use crate::service::ai_service::insights_service::AIInsightsService;
+use crate::service::ai_service::memory_service;
+use crate::service::ai_service::openrouter_client::{ChatMessage, MessageRole, OpenRouterClient};
+use crate::service::ai_service::qdrant_client::{KeywordSearchHit, QdrantDocumentClient};
+use crate::service::analytics_engine::core_metrics::calculate_core_metrics;
+use crate::service::analytics_engine::drawdown_sizing::{recommend_position_size, SizingRecommendation};
+use crate::service::i18n::{self, NumberFormat};
+use crate::service::market_engine::briefing::MarketBriefing;
+use crate::service::market_engine::earnings_transcripts::{self, Transcript, TranscriptSummary};
+use crate::service::notifications::chat_webhooks::dispatch_insight;
+use crate::service::notifications::email::{escape_html, send_email, EmailMessage};
+use crate::service::notifications::loss_streak;
+use crate::service::notifications::push::{PushPayload, PushService};
+use crate::turso::config::{EmailConfig, WebPushConfig};
+
+/// Past notes pulled into the prompt; kept small so the digest stays focused
+const MAX_PAST_NOTES: usize = 5;
+const NOTE_EXCERPT_CHARS: usize = 300;
+/// Confirmed user memories added to the coach's system prompt
+const MAX_INSIGHT_MEMORIES: usize = 8;
+
+/// Per-playbook results for the week
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybookWeek {
+    pub name: String,
+    pub trades: u32,
+    pub wins: u32,
+    pub net_pnl: f64,
+    /// Share of checked rules that were followed, when any were checked
+    pub compliance_rate: Option<f64>,
+}
+
+/// Everything the coach looks at for one user's week
+#[derive(Debug, Clone)]
+pub struct WeeklyCoachContext {
+    pub week: CoreMetrics,
+    pub baseline: CoreMetrics,
+    pub goal: Option<String>,
+    pub playbooks: Vec<PlaybookWeek>,
+    pub worst_symbols: Vec<String>,
+    pub past_notes: Vec<KeywordSearchHit>,
+    /// Days this week the consecutive-loss circuit breaker tripped
+    pub loss_streak_days: Vec<String>,
+    /// Drawdown-aware size for next week, when sizing rules are set up
+    pub sizing: Option<SizingRecommendation>,
+    /// The user's currency and separators for every amount in the prompt
+    pub number_format: NumberFormat,
+}
+
+/// Structured digest returned by the model
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CoachDigest {
+    pub title: String,
+    pub summary: String,
+    #[serde(default)]
+    pub wins: Vec<String>,
+    #[serde(default)]
+    pub focus_areas: Vec<String>,
+    #[serde(default)]
+    pub action_items: Vec<String>,
+}
+
+/// Where a digest was delivered
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DigestDelivery {
+    pub push: bool,
+    pub email: bool,
+    pub chat_webhooks: usize,
+}
+
+/// Weekly AI coach: compares the week to the user's goal, baseline and playbooks
+pub struct AICoachService {
+    openrouter_client: Arc<OpenRouterClient>,
+    qdrant_client: Arc<QdrantDocumentClient>,
+    insights_service: Arc<AIInsightsService>,
+}
+
+impl AICoachService {
+    pub fn new(
+        openrouter_client: Arc<OpenRouterClient>,
+        qdrant_client: Arc<QdrantDocumentClient>,
+        insights_service: Arc<AIInsightsService>,
+    ) -> Self {
+        Self { openrouter_client, qdrant_client, insights_service }
+    }
+
+    /// Generate and store this week's digest. Returns None when there is nothing to coach on
+    /// (no closed trades this week) or a digest was already produced in the last six days.
+    pub async fn generate_weekly_digest(&self, conn: &Connection, user_id: &str) -> Result<Option<Insight>> {
+        if self.has_recent_digest(conn).await? {
+            return Ok(None);
+        }
+
+        let week = calculate_core_metrics(conn, &TimeRange::SevenDays).await?;
+        if week.total_trades == 0 {
+            return Ok(None);
+        }
+        let baseline = calculate_core_metrics(conn, &TimeRange::NinetyDays).await?;
+        let goal = fetch_primary_goal(conn).await?;
+        let playbooks = fetch_playbook_week(conn).await?;
+        let worst_symbols = fetch_worst_symbols(conn, 3).await?;
+        let past_notes = self.retrieve_past_notes(user_id, &worst_symbols, &playbooks).await;
+        let week_start = (Utc::now() - Duration::days(7)).format("%Y-%m-%d").to_string();
+        let loss_streak_days = loss_streak::list_events(conn, &week_start)
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(|event| event.trade_date)
+            .collect();
+        let sizing = recommend_position_size(conn).await.unwrap_or_default();
+
+        let number_format = i18n::user_number_format(conn).await;
+
+        let context = WeeklyCoachContext { week, baseline, goal, playbooks, worst_symbols, past_notes, loss_streak_days, sizing, number_format };
+        let started = std::time::Instant::now();
+
+        let mut system_prompt = COACH_SYSTEM_PROMPT.to_string();
+        if let Some(language) = i18n::prompt_instruction(conn).await {
+            system_prompt.push_str(&format!("\n\n{}", language));
+        }
+        if let Some(memories) = memory_service::memory_prompt_section(conn, "", MAX_INSIGHT_MEMORIES).await {
+            system_prompt.push_str(&format!("\n\n{}", memories));
+        }
+
+        let messages = vec![
+            ChatMessage { role: MessageRole::System, content: system_prompt },
+            ChatMessage { role: MessageRole::User, content: build_coach_prompt(&context) },
+        ];
+        let response = self.openrouter_client.generate_chat(messages).await?;
+        let digest = parse_digest(&response);
+
+        let mut findings = digest.wins.clone();
+        findings.extend(digest.focus_areas.iter().cloned());
+        let mut insight = Insight::new(
+            user_id.to_string(),
+            TimeRange::SevenDays,
+            InsightType::WeeklyCoach,
+            digest.title.clone(),
+            digest.summary.clone(),
+        )
+        .with_findings(findings)
+        .with_recommendations(digest.action_items.clone())
+        .with_confidence(0.8);
+        insight.data_sources = context.past_notes.iter().map(|n| format!("{}:{}", n.data_type, n.entity_id)).collect();
+        insight.expires_at = Some(Utc::now() + Duration::days(30));
+        insight.metadata.trade_count = context.week.total_trades;
+        insight.metadata.analysis_period_days = 7;
+        insight.metadata.model_version = self.openrouter_client.active_model();
+        insight.metadata.processing_time_ms = started.elapsed().as_millis() as u64;
+
+        self.insights_service.store_insight(conn, &insight).await?;
+        Ok(Some(insight))
+    }
+
+    /// One paragraph summarizing a pre-market briefing, in the user's language
+    pub async fn summarize_briefing(&self, conn: &Connection, briefing: &MarketBriefing) -> Result<String> {
+        let mut system_prompt = BRIEFING_SYSTEM_PROMPT.to_string();
+        if let Some(language) = i18n::prompt_instruction(conn).await {
+            system_prompt.push_str(&format!("\n\n{}", language));
+        }
+        let messages = vec![
+            ChatMessage { role: MessageRole::System, content: system_prompt },
+            ChatMessage { role: MessageRole::User, content: serde_json::to_string(briefing)? },
+        ];
+        let response = self.openrouter_client.generate_chat(messages).await?;
+        Ok(response.trim().to_string())
+    }
+
+    /// Guidance, surprises and tone of one earnings call. Not localized: the summary is
+    /// cached once per call and shared by every user who asks for it.
+    pub async fn summarize_transcript(&self, transcript: &Transcript) -> Result<TranscriptSummary> {
+        let messages = vec![
+            ChatMessage { role: MessageRole::System, content: earnings_transcripts::TRANSCRIPT_SUMMARY_PROMPT.to_string() },
+            ChatMessage { role: MessageRole::User, content: earnings_transcripts::summary_input(transcript) },
+        ];
+        let response = self.openrouter_client.generate_chat(messages).await?;
+        earnings_transcripts::parse_summary(transcript, &response)
+    }
+
+    async fn has_recent_digest(&self, conn: &Connection) -> Result<bool> {
+        let since = (Utc::now() - Duration::days(6)).to_rfc3339();
+        let mut rows = conn
+            .prepare("SELECT COUNT(*) FROM ai_insights WHERE insight_type = ? AND generated_at >= ?")
+            .await?
+            .query(params![InsightType::WeeklyCoach.as_str(), since])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<i64>(0)? > 0),
+            None => Ok(false),
+        }
+    }
+
+    /// Pull the user's own notes about this week's problem symbols and setups from Qdrant
+    async fn retrieve_past_notes(&self, user_id: &str, worst_symbols: &[String], playbooks: &[PlaybookWeek]) -> Vec<KeywordSearchHit> {
+        let mut queries: Vec<String> = worst_symbols.to_vec();
+        queries.extend(playbooks.iter().filter(|p| p.net_pnl < 0.0).map(|p| p.name.clone()));
+
+        let mut notes: Vec<KeywordSearchHit> = Vec::new();
+        for query in queries {
+            match self.qdrant_client.search_content(user_id, &query, Some("tradenote"), 3).await {
+                Ok(hits) => {
+                    for hit in hits {
+                        if !notes.iter().any(|n| n.entity_id == hit.entity_id) {
+                            notes.push(hit);
+                        }
+                    }
+                }
+                Err(e) => log::warn!("Coach note search failed for user {} ({}): {}", user_id, query, e),
+            }
+            if notes.len() >= MAX_PAST_NOTES {
+                break;
+            }
+        }
+        notes.truncate(MAX_PAST_NOTES);
+        notes
+    }
+
+    /// Deliver a digest by push, email (when configured and an address is known) and chat webhooks
+    pub async fn deliver_digest(
+        conn: &Connection,
+        user_id: &str,
+        insight: &Insight,
+        web_push: &WebPushConfig,
+        email: Option<(&EmailConfig, &str)>,
+    ) -> DigestDelivery {
+        let mut delivery = DigestDelivery::default();
+
+        let payload = PushPayload {
+            title: insight.title.clone(),
+            body: Some(insight.content.chars().take(180).collect()),
+            icon: Some("/icons/icon-192.png".to_string()),
+            url: Some(format!("/app/insights?id={}", insight.id)),
+            tag: Some("weekly-coach".to_string()),
+            data: Some(serde_json::json!({"type": "weekly_coach", "insight_id": insight.id})),
+        };
+        match PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+            Ok(()) => delivery.push = true,
+            Err(e) => log::warn!("Weekly coach push failed for user {}: {}", user_id, e),
+        }
+
+        if let Some((config, address)) = email {
+            let message = render_email(insight, address);
+            match send_email(config, &message).await {
+                Ok(()) => delivery.email = true,
+                Err(e) => log::warn!("Weekly coach email failed for user {}: {}", user_id, e),
+            }
+        }
+
+        delivery.chat_webhooks = dispatch_insight(conn, insight).await.unwrap_or(0);
+        delivery
+    }
+}
+
+const COACH_SYSTEM_PROMPT: &str = r#"You are a supportive but direct trading coach writing a short weekly digest.
+Use only the data provided. Reference the trader's own past notes when they are relevant.
+Figures marked low sample are anecdotes: mention them cautiously and never call them an edge or a pattern.
+Return ONLY a JSON object:
+{"title": "...", "summary": "2-4 sentences", "wins": ["..."], "focus_areas": ["..."], "action_items": ["max 3 concrete actions for next week"]}"#;
+
+fn fmt_metrics(label: &str, m: &CoreMetrics, format: &NumberFormat) -> String {
+    let (win_rate_low, win_rate_high) = wilson_interval(m.winning_trades, m.total_trades);
+    let mut line = format!(
+        "{}: {} trades, win rate {}% (95% CI {}-{}%), net P&L {}, profit factor {}, avg win {}, avg loss {}, max losing streak {}",
+        label,
+        m.total_trades,
+        format.number(m.win_rate, 1),
+        format.number(win_rate_low, 0),
+        format.number(win_rate_high, 0),
+        format.money(m.net_profit_loss),
+        format.number(m.profit_factor, 2),
+        format.money(m.average_win),
+        format.money(m.average_loss),
+        m.max_consecutive_losses
+    );
+    if m.total_trades < LOW_SAMPLE_TRADES {
+        line.push_str(" [low sample: too few trades to draw conclusions]");
+    }
+    line
+}
+
+/// Render the coaching context as the user prompt
+pub fn build_coach_prompt(context: &WeeklyCoachContext) -> String {
+    let mut prompt = String::new();
+    prompt.push_str(&format!("Goal: {}\n\n", context.goal.as_deref().unwrap_or("not set")));
+    prompt.push_str(&fmt_metrics("This week", &context.week, &context.number_format));
+    prompt.push('\n');
+    prompt.push_str(&fmt_metrics("Last 90 days", &context.baseline, &context.number_format));
+    prompt.push_str("\n\nPlaybooks this week:\n");
+    if context.playbooks.is_empty() {
+        prompt.push_str("- no trades tagged with a playbook\n");
+    }
+    for p in &context.playbooks {
+        let compliance = p.compliance_rate.map(|r| format!(", rules followed {:.0}%", r)).unwrap_or_default();
+        let net = context.number_format.money(p.net_pnl);
+        prompt.push_str(&format!("- {}: {} trades, {} wins, net {}{}\n", p.name, p.trades, p.wins, net, compliance));
+    }
+    if !context.worst_symbols.is_empty() {
+        prompt.push_str(&format!("\nWorst symbols this week: {}\n", context.worst_symbols.join(", ")));
+    }
+    if !context.loss_streak_days.is_empty() {
+        prompt.push_str(&format!(
+            "\nHit the consecutive-loss limit and got a step-away alert on: {}\n",
+            context.loss_streak_days.join(", ")
+        ));
+    }
+    if let Some(sizing) = context.sizing.as_ref().filter(|s| s.size_multiplier < 1.0) {
+        prompt.push_str(&format!("\nDrawdown sizing: {}\n", sizing.message));
+    }
+    if !context.past_notes.is_empty() {
+        prompt.push_str("\nTrader's past notes on similar situations:\n");
+        for note in &context.past_notes {
+            let excerpt: String = note.content.chars().take(NOTE_EXCERPT_CHARS).collect();
+            prompt.push_str(&format!("- [{}] {}\n", note.timestamp.as_deref().unwrap_or("undated"), excerpt.replace('\n', " ")));
+        }
+    }
+    prompt
+}
+
+/// Parse the model response, falling back to the raw text as the summary
+pub fn parse_digest(response: &str) -> CoachDigest {
+    let trimmed = response.trim();
+    let json = match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if end > start => &trimmed[start..=end],
+        _ => trimmed,
+    };
+    serde_json::from_str(json).unwrap_or_else(|e| {
+        log::warn!("Failed to parse coach digest as JSON: {}. Using raw text.", e);
+        CoachDigest {
+            title: "Your weekly coaching digest".to_string(),
+            summary: trimmed.chars().take(1500).collect(),
+            wins: Vec::new(),
+            focus_areas: Vec::new(),
+            action_items: Vec::new(),
+        }
+    })
+}
+
+fn render_email(insight: &Insight, to: &str) -> EmailMessage {
+    let mut text = format!("{}\n\n{}\n", insight.title, insight.content);
+    let mut html = format!("<h2>{}</h2><p>{}</p>", escape_html(&insight.title), escape_html(&insight.content));
+    if !insight.key_findings.is_empty() {
+        text.push_str("\nHighlights:\n");
+        html.push_str("<h3>Highlights</h3><ul>");
+        for f in &insight.key_findings {
+            text.push_str(&format!("- {}\n", f));
+            html.push_str(&format!("<li>{}</li>", escape_html(f)));
+        }
+        html.push_str("</ul>");
+    }
+    if !insight.recommendations.is_empty() {
+        text.push_str("\nNext week:\n");
+        html.push_str("<h3>Next week</h3><ol>");
+        for r in &insight.recommendations {
+            text.push_str(&format!("- {}\n", r));
+            html.push_str(&format!("<li>{}</li>", escape_html(r)));
+        }
+        html.push_str("</ol>");
+    }
+    EmailMessage {
+        to: to.to_string(),
+        subject: format!("Tradstry weekly coach: {}", insight.title),
+        text,
+        html: Some(html),
+    }
+}
+
+async fn fetch_primary_goal(conn: &Connection) -> Result<Option<String>> {
+    let mut rows = conn
+        .prepare("SELECT primary_trading_goal FROM user_profile LIMIT 1")
+        .await?
+        .query(params![])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get::<Option<String>>(0)?.filter(|g| !g.trim().is_empty())),
+        None => Ok(None),
+    }
+}
+
+/// Closed trades in the last 7 days with realized P&L, tagged with their link table
+const BRIEFING_SYSTEM_PROMPT: &str = "You write a trader's pre-market briefing. Given JSON with gap movers, \
+upcoming earnings of the trader's own symbols, economic events, sector and index performance, reply with one \
+plain-text paragraph of at most 120 words: the market tone, the events that matter today and any earnings \
+that affect the trader's positions or watchlist. No lists, no advice to buy or sell.";
+
+const WEEK_TRADES_SQL: &str = r#"
+    SELECT 'stock' as kind, id, symbol,
+        CASE
+            WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
+            ELSE (entry_price - exit_price) * number_shares - commissions
+        END as pnl
+    FROM stocks
+    WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND exit_date >= date('now', '-7 days')
+    UNION ALL
+    SELECT 'option' as kind, id, symbol,
+        (CASE WHEN short_premium = 1 THEN entry_price - exit_price ELSE exit_price - entry_price END) * number_of_contracts * 100 - commissions as pnl
+    FROM options
+    WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND exit_date >= date('now', '-7 days')
+"#;
+
+async fn fetch_playbook_week(conn: &Connection) -> Result<Vec<PlaybookWeek>> {
+    let sql = format!(
+        r#"
+        WITH week AS ({WEEK_TRADES_SQL}),
+        links AS (
+            SELECT 'stock' as kind, stock_trade_id as trade_id, setup_id FROM stock_trade_playbook
+            UNION ALL
+            SELECT 'option' as kind, option_trade_id as trade_id, setup_id FROM option_trade_playbook
+        ),
+        compliance AS (
+            SELECT 'stock' as kind, stock_trade_id as trade_id, playbook_id, is_followed FROM stock_trade_rule_compliance
+            UNION ALL
+            SELECT 'option' as kind, option_trade_id as trade_id, playbook_id, is_followed FROM option_trade_rule_compliance
+        )
+        SELECT p.name,
+            COUNT(*) as trades,
+            SUM(CASE WHEN w.pnl > 0 THEN 1 ELSE 0 END) as wins,
+            SUM(w.pnl) as net_pnl,
+            (SELECT SUM(CASE WHEN c.is_followed THEN 1 ELSE 0 END) * 100.0 / COUNT(*)
+             FROM compliance c JOIN week w2 ON w2.kind = c.kind AND w2.id = c.trade_id
+             WHERE c.playbook_id = p.id) as compliance_rate
+        FROM week w
+        JOIN links l ON l.kind = w.kind AND l.trade_id = w.id
+        JOIN playbook p ON p.id = l.setup_id
+        GROUP BY p.id, p.name
+        ORDER BY net_pnl ASC
+        "#
+    );
+
+    let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+    let mut playbooks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        playbooks.push(PlaybookWeek {
+            name: row.get(0)?,
+            trades: row.get::<i64>(1)? as u32,
+            wins: row.get::<Option<i64>>(2)?.unwrap_or(0) as u32,
+            net_pnl: row.get::<Option<f64>>(3)?.unwrap_or(0.0),
+            compliance_rate: row.get::<Option<f64>>(4)?,
+        });
+    }
+    Ok(playbooks)
+}
+
+async fn fetch_worst_symbols(conn: &Connection, limit: i64) -> Result<Vec<String>> {
+    let sql = format!(
+        "SELECT symbol, SUM(pnl) as net FROM ({WEEK_TRADES_SQL}) GROUP BY symbol HAVING net < 0 ORDER BY net ASC LIMIT ?"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![limit]).await?;
+    let mut symbols = Vec::new();
+    while let Some(row) = rows.next().await? {
+        symbols.push(row.get(0)?);
+    }
+    Ok(symbols)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(total: u32, net: f64) -> CoreMetrics {
+        serde_json::from_value(serde_json::json!({
+            "total_trades": total, "winning_trades": 0, "losing_trades": 0, "break_even_trades": 0,
+            "win_rate": 50.0, "loss_rate": 50.0, "total_pnl": net, "net_profit_loss": net,
+            "gross_profit": 0.0, "gross_loss": 0.0, "average_win": 0.0, "average_loss": 0.0,
+            "average_position_size": 0.0, "biggest_winner": 0.0, "biggest_loser": 0.0,
+            "profit_factor": 1.0, "win_loss_ratio": 1.0, "max_consecutive_wins": 0,
+            "max_consecutive_losses": 0, "total_commissions": 0.0, "average_commission_per_trade": 0.0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_digest_json_and_fallback() {
+        let digest = parse_digest("```json\n{\"title\":\"Tighten stops\",\"summary\":\"Good week.\",\"action_items\":[\"Size down on ORB\"]}\n```");
+        assert_eq!(digest.title, "Tighten stops");
+        assert_eq!(digest.action_items, vec!["Size down on ORB"]);
+        assert!(digest.wins.is_empty());
+
+        let fallback = parse_digest("Focus on fewer trades next week.");
+        assert_eq!(fallback.summary, "Focus on fewer trades next week.");
+    }
+
+    #[test]
+    fn test_build_coach_prompt_includes_context() {
+        let context = WeeklyCoachContext {
+            week: metrics(5, -120.0),
+            baseline: metrics(60, 900.0),
+            goal: Some("Consistent $500 weeks".to_string()),
+            playbooks: vec![PlaybookWeek { name: "ORB".to_string(), trades: 3, wins: 1, net_pnl: -150.0, compliance_rate: Some(66.7) }],
+            worst_symbols: vec!["TSLA".to_string()],
+            past_notes: vec![KeywordSearchHit {
+                entity_id: "n1".to_string(),
+                data_type: "tradenote".to_string(),
+                content: "Chased TSLA again after the open".to_string(),
+                timestamp: None,
+            }],
+            loss_streak_days: vec!["2025-03-04".to_string()],
+            sizing: None,
+            number_format: NumberFormat::default(),
+        };
+        let prompt = build_coach_prompt(&context);
+        assert!(prompt.contains("Consistent $500 weeks"));
+        assert!(prompt.contains("- ORB: 3 trades, 1 wins, net -$150.00, rules followed 67%"));
+        assert!(prompt.contains("Chased TSLA again"));
+        assert!(prompt.contains("step-away alert on: 2025-03-04"));
+        assert!(prompt.contains("This week: 5 trades, win rate 50.0% (95% CI 0-43%)"));
+        assert_eq!(prompt.matches("[low sample").count(), 1);
+    }
+}
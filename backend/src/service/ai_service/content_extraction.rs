@@ -0,0 +1,192 @@
+use anyhow::Result;
+use libsql::Connection;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Block-level HTML tags that end a line of text
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "p", "div", "br", "li", "ul", "ol", "h1", "h2", "h3", "h4", "h5", "h6", "blockquote", "pre", "tr", "table", "hr", "section",
+];
+
+/// Caption lookup for images embedded in note content, keyed by image id or storage file id
+pub type ImageCaptions = HashMap<String, String>;
+
+/// Turn stored note content (HTML, ProseMirror/TipTap JSON, BlockNote JSON or plain text)
+/// into clean text for embedding. Images become `[Image: caption]` when a caption is known.
+pub fn extract_plain_text(content: &str, captions: &ImageCaptions) -> String {
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && let Ok(json) = serde_json::from_str::<Value>(trimmed)
+    {
+        let mut out = String::new();
+        walk_json(&json, captions, &mut out);
+        return normalize_whitespace(&out);
+    }
+    if trimmed.contains('<') && trimmed.contains('>') {
+        return normalize_whitespace(&strip_html(trimmed, captions));
+    }
+    normalize_whitespace(trimmed)
+}
+
+/// Same as `extract_plain_text` for content that's already parsed JSON (notebook notes)
+pub fn extract_plain_text_from_json(content: &Value, captions: &ImageCaptions) -> String {
+    match content {
+        Value::String(s) => extract_plain_text(s, captions),
+        other => {
+            let mut out = String::new();
+            walk_json(other, captions, &mut out);
+            normalize_whitespace(&out)
+        }
+    }
+}
+
+/// Load captions (falling back to alt text) for the images attached to a trade note
+pub async fn load_image_captions(conn: &Connection, trade_note_id: &str) -> Result<ImageCaptions> {
+    let mut rows = conn
+        .prepare("SELECT id, uploadcare_file_id, COALESCE(NULLIF(caption, ''), alt_text) FROM images WHERE trade_note_id = ? AND is_deleted = 0")
+        .await?
+        .query(libsql::params![trade_note_id])
+        .await?;
+    let mut captions = ImageCaptions::new();
+    while let Some(row) = rows.next().await? {
+        let Some(caption) = row.get::<Option<String>>(2)?.filter(|c| !c.trim().is_empty()) else { continue };
+        captions.insert(row.get::<String>(0)?, caption.clone());
+        captions.insert(row.get::<String>(1)?, caption);
+    }
+    Ok(captions)
+}
+
+fn image_placeholder(src: Option<&str>, inline_caption: Option<&str>, captions: &ImageCaptions) -> String {
+    let resolved = src.and_then(|src| captions.iter().find(|(key, _)| src.contains(key.as_str())).map(|(_, c)| c.as_str()));
+    match resolved.or(inline_caption.filter(|c| !c.trim().is_empty())) {
+        Some(caption) => format!("\n[Image: {}]\n", caption.trim()),
+        None => String::new(),
+    }
+}
+
+fn walk_json(node: &Value, captions: &ImageCaptions, out: &mut String) {
+    match node {
+        Value::Array(items) => items.iter().for_each(|item| walk_json(item, captions, out)),
+        Value::Object(map) => {
+            let node_type = map.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            // ProseMirror keeps image details in `attrs`, BlockNote in `props`
+            let attrs = map.get("attrs").or_else(|| map.get("props"));
+            let attr = |key: &str| attrs.and_then(|a| a.get(key)).and_then(|v| v.as_str());
+
+            if node_type == "image" {
+                let inline = attr("caption").or_else(|| attr("alt")).or_else(|| attr("title")).or_else(|| attr("name"));
+                out.push_str(&image_placeholder(attr("src").or_else(|| attr("url")), inline, captions));
+                return;
+            }
+            if node_type == "hardBreak" {
+                out.push('\n');
+                return;
+            }
+            if let Some(text) = map.get("text").and_then(|t| t.as_str()) {
+                out.push_str(text);
+            }
+            for key in ["content", "children"] {
+                if let Some(child) = map.get(key) {
+                    walk_json(child, captions, out);
+                }
+            }
+            if !node_type.is_empty() && node_type != "text" {
+                out.push('\n');
+            }
+        }
+        _ => {}
+    }
+}
+
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lower = tag.to_ascii_lowercase();
+    let start = lower.find(&format!("{}=", name))? + name.len() + 1;
+    let rest = &tag[start..];
+    let quote = rest.chars().next()?;
+    if quote == '"' || quote == '\'' {
+        rest[1..].split(quote).next()
+    } else {
+        rest.split(|c: char| c.is_whitespace() || c == '>').next()
+    }
+}
+
+fn strip_html(html: &str, captions: &ImageCaptions) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut skip_until: Option<&str> = None;
+
+    while let Some(open) = rest.find('<') {
+        if skip_until.is_none() {
+            out.push_str(&decode_entities(&rest[..open]));
+        }
+        let Some(close) = rest[open..].find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[open + 1..open + close];
+        rest = &rest[open + close + 1..];
+
+        let name = tag.trim_start_matches('/').split(|c: char| c.is_whitespace() || c == '/').next().unwrap_or("").to_ascii_lowercase();
+        if let Some(end) = skip_until {
+            if tag.starts_with('/') && name == end {
+                skip_until = None;
+            }
+            continue;
+        }
+        match name.as_str() {
+            "script" | "style" if !tag.starts_with('/') => skip_until = Some(if name == "script" { "script" } else { "style" }),
+            "img" => out.push_str(&image_placeholder(attribute(tag, "src"), attribute(tag, "alt").or_else(|| attribute(tag, "title")), captions)),
+            n if HTML_BLOCK_TAGS.contains(&n) => out.push('\n'),
+            _ => {}
+        }
+    }
+    if skip_until.is_none() {
+        out.push_str(&decode_entities(rest));
+    }
+    out
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Collapse runs of spaces inside lines and drop empty lines
+fn normalize_whitespace(text: &str) -> String {
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_is_stripped_with_captions() {
+        let mut captions = ImageCaptions::new();
+        captions.insert("user-1/chart.png".to_string(), "AAPL breakout".to_string());
+        let html = "<h2>Plan</h2><p>Entered <strong>AAPL</strong> &amp; held</p><img src=\"https://x/user-1/chart.png\"><script>alert(1)</script><p>Done</p>";
+        assert_eq!(extract_plain_text(html, &captions), "Plan\nEntered AAPL & held\n[Image: AAPL breakout]\nDone");
+    }
+
+    #[test]
+    fn test_prosemirror_and_blocknote_json() {
+        let prosemirror = r#"{"type":"doc","content":[{"type":"heading","content":[{"type":"text","text":"Review"}]},{"type":"paragraph","content":[{"type":"text","text":"Cut losers "},{"type":"text","text":"fast"}]},{"type":"image","attrs":{"src":"a.png","alt":"daily chart"}}]}"#;
+        assert_eq!(extract_plain_text(prosemirror, &ImageCaptions::new()), "Review\nCut losers fast\n[Image: daily chart]");
+
+        let blocknote = serde_json::json!([{"type":"paragraph","props":{},"content":[{"type":"text","text":"Gap and go"}],"children":[]}]);
+        assert_eq!(extract_plain_text_from_json(&blocknote, &ImageCaptions::new()), "Gap and go");
+        assert_eq!(extract_plain_text("  plain   text ", &ImageCaptions::new()), "plain text");
+    }
+}
@@ -0,0 +1,213 @@
+use crate::models::ai::chat::{ChatMessage, ContextSource, MessageRole};
+
+/// Default prompt window when `CHAT_CONTEXT_WINDOW_TOKENS` is unset; conservative enough
+/// for the smaller free OpenRouter models
+pub const DEFAULT_CONTEXT_WINDOW_TOKENS: usize = 16_000;
+/// Per-message overhead for role markers and separators
+const MESSAGE_OVERHEAD_TOKENS: usize = 4;
+
+/// Rough token estimate (~4 characters per token for English text).
+/// Deliberately errs on the high side so budgets leave headroom.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4) + 1
+}
+
+pub fn message_tokens(message: &ChatMessage) -> usize {
+    message
+        .token_count
+        .filter(|t| *t > 0)
+        .map(|t| t as usize)
+        .unwrap_or_else(|| estimate_tokens(&message.content))
+        + MESSAGE_OVERHEAD_TOKENS
+}
+
+/// How the prompt window is split between the system prompt, retrieved context and history
+#[derive(Debug, Clone, Copy)]
+pub struct ContextBudget {
+    /// Total tokens the provider accepts for prompt + completion
+    pub window_tokens: usize,
+    /// Held back for the model's response
+    pub response_tokens: usize,
+    /// Fraction of the prompt budget retrieved vectors may use
+    pub context_share: f32,
+}
+
+impl ContextBudget {
+    pub fn new(window_tokens: usize, response_tokens: usize) -> Self {
+        Self { window_tokens, response_tokens, context_share: 0.35 }
+    }
+
+    /// Window from `CHAT_CONTEXT_WINDOW_TOKENS`, response reserve from the OpenRouter max_tokens
+    pub fn from_env(response_tokens: u32) -> Self {
+        let window = std::env::var("CHAT_CONTEXT_WINDOW_TOKENS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CONTEXT_WINDOW_TOKENS);
+        Self::new(window, response_tokens as usize)
+    }
+
+    /// Tokens available for everything we send
+    pub fn prompt_tokens(&self) -> usize {
+        self.window_tokens.saturating_sub(self.response_tokens)
+    }
+
+    pub fn context_tokens(&self) -> usize {
+        (self.prompt_tokens() as f32 * self.context_share) as usize
+    }
+}
+
+/// Keep the highest-ranked sources (input order) that fit, trimming the last snippet if needed
+pub fn fit_context_sources(sources: &[ContextSource], budget_tokens: usize) -> Vec<ContextSource> {
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for source in sources {
+        let cost = estimate_tokens(&source.snippet) + MESSAGE_OVERHEAD_TOKENS;
+        if used + cost <= budget_tokens {
+            used += cost;
+            kept.push(source.clone());
+            continue;
+        }
+        let remaining = budget_tokens.saturating_sub(used + MESSAGE_OVERHEAD_TOKENS);
+        // Only worth including a trimmed snippet if a meaningful part survives
+        if remaining >= 50 {
+            let mut trimmed = source.clone();
+            trimmed.snippet = trimmed.snippet.chars().take(remaining * 4).collect::<String>() + "…";
+            kept.push(trimmed);
+        }
+        break;
+    }
+    kept
+}
+
+/// Split of a session's history into what fits and what has to be summarized
+#[derive(Debug, Clone, Default)]
+pub struct HistoryPlan {
+    /// Most recent messages that fit the budget, oldest first
+    pub kept: Vec<ChatMessage>,
+    /// Older messages that no longer fit, oldest first
+    pub overflow: Vec<ChatMessage>,
+}
+
+/// Keep the newest messages within `budget_tokens`. The latest message is always kept
+/// (trimmed if it alone exceeds the budget) so the current question is never dropped.
+pub fn plan_history(messages: &[ChatMessage], budget_tokens: usize) -> HistoryPlan {
+    let history: Vec<&ChatMessage> = messages.iter().filter(|m| m.role != MessageRole::System).collect();
+    let Some((latest, earlier)) = history.split_last() else {
+        return HistoryPlan::default();
+    };
+
+    let mut latest = (*latest).clone();
+    let mut used = message_tokens(&latest);
+    if used > budget_tokens {
+        let keep_chars = budget_tokens.saturating_sub(MESSAGE_OVERHEAD_TOKENS + 1) * 4;
+        latest.content = latest.content.chars().take(keep_chars).collect();
+        latest.token_count = None;
+        used = message_tokens(&latest);
+    }
+
+    let mut kept = vec![latest];
+    let mut split_at = 0;
+    for (i, message) in earlier.iter().enumerate().rev() {
+        let cost = message_tokens(message);
+        if used + cost > budget_tokens {
+            split_at = i + 1;
+            break;
+        }
+        used += cost;
+        kept.push((*message).clone());
+    }
+    kept.reverse();
+
+    // Don't start the kept window on an assistant reply without its question
+    let mut overflow: Vec<ChatMessage> = earlier[..split_at].iter().map(|m| (*m).clone()).collect();
+    while kept.len() > 1 && kept[0].role == MessageRole::Assistant {
+        overflow.push(kept.remove(0));
+    }
+
+    HistoryPlan { kept, overflow }
+}
+
+/// Prompt used to fold overflowed turns into the session's rolling summary
+pub fn build_summary_prompt(existing_summary: Option<&str>, overflow: &[ChatMessage], max_tokens: usize) -> String {
+    let mut prompt = String::from(
+        "Update the running summary of this trading assistant conversation. Keep facts about the \
+         user's trades, goals, constraints and any conclusions reached. Be concise (under 200 words). \
+         Return only the summary text.\n\n",
+    );
+    if let Some(summary) = existing_summary {
+        prompt.push_str(&format!("Current summary:\n{}\n\n", summary));
+    }
+    prompt.push_str("New messages to fold in:\n");
+    let mut used = estimate_tokens(&prompt);
+    for message in overflow {
+        let line = format!("{}: {}\n", message.role, message.content);
+        let cost = estimate_tokens(&line);
+        if used + cost > max_tokens {
+            break;
+        }
+        used += cost;
+        prompt.push_str(&line);
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(role: MessageRole, content: &str) -> ChatMessage {
+        ChatMessage::new("s1".to_string(), role, content.to_string())
+    }
+
+    #[test]
+    fn test_plan_history_keeps_recent_turns() {
+        let messages = vec![
+            msg(MessageRole::User, &"old question ".repeat(40)),
+            msg(MessageRole::Assistant, &"old answer ".repeat(40)),
+            msg(MessageRole::User, "recent question"),
+            msg(MessageRole::Assistant, "recent answer"),
+            msg(MessageRole::User, "current question"),
+        ];
+        let plan = plan_history(&messages, 60);
+        assert_eq!(plan.kept.len(), 3);
+        assert_eq!(plan.kept[0].content, "recent question");
+        assert_eq!(plan.overflow.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_history_never_starts_with_assistant() {
+        let messages = vec![
+            msg(MessageRole::User, &"question ".repeat(40)),
+            msg(MessageRole::Assistant, "short answer"),
+            msg(MessageRole::User, "follow up"),
+        ];
+        let plan = plan_history(&messages, 30);
+        assert_eq!(plan.kept[0].role, MessageRole::User);
+        assert_eq!(plan.kept.last().unwrap().content, "follow up");
+        assert_eq!(plan.overflow.len(), 2);
+    }
+
+    #[test]
+    fn test_plan_history_trims_oversized_latest_message() {
+        let messages = vec![msg(MessageRole::User, &"x".repeat(10_000))];
+        let plan = plan_history(&messages, 100);
+        assert_eq!(plan.kept.len(), 1);
+        assert!(message_tokens(&plan.kept[0]) <= 100);
+    }
+
+    #[test]
+    fn test_fit_context_sources_respects_budget() {
+        let source = |id: &str, len: usize| ContextSource {
+            vector_id: id.to_string(),
+            data_type: "stock".to_string(),
+            entity_id: id.to_string(),
+            similarity_score: 0.9,
+            snippet: "a".repeat(len),
+        };
+        let sources = vec![source("1", 400), source("2", 400), source("3", 400)];
+        let kept = fit_context_sources(&sources, 300);
+        assert_eq!(kept.len(), 3);
+        assert!(kept[2].snippet.chars().count() < 400);
+        assert_eq!(fit_context_sources(&sources, 110).len(), 1);
+    }
+}
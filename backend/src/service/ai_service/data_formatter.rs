@@ -1,10 +1,12 @@
 #![allow(dead_code)]
 
+use crate::models::money::{self, Decimal};
 use crate::models::stock::stocks::Stock;
 use crate::models::options::OptionTrade;
 use crate::models::notes::trade_notes::TradeNote;
 use crate::models::notebook::notebook_note::NotebookNote;
 use crate::models::playbook::Playbook;
+use crate::service::ai_service::content_extraction::{extract_plain_text, extract_plain_text_from_json, ImageCaptions};
 use crate::service::ai_service::qdrant_client::{Document, DocumentMetadata};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -30,16 +32,17 @@ impl DataFormatter {
         let exit_date = stock.exit_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "Open".to_string());
         
         // Calculate P&L from entry and exit prices
+        let shares = money::from_f64(stock.number_shares);
         let pnl = if let Some(exit_price) = stock.exit_price {
-            (exit_price - stock.entry_price) * stock.number_shares - stock.commissions
+            (exit_price - stock.entry_price) * shares - stock.commissions
         } else {
-            0.0
+            Decimal::ZERO
         };
         
-        let pnl_percentage = if stock.entry_price > 0.0 {
-            (pnl / (stock.entry_price * stock.number_shares)) * 100.0
+        let pnl_percentage = if stock.entry_price > Decimal::ZERO {
+            (pnl * Decimal::ONE_HUNDRED).checked_div(stock.entry_price * shares).unwrap_or(Decimal::ZERO)
         } else {
-            0.0
+            Decimal::ZERO
         };
 
         format!(
@@ -54,7 +57,7 @@ impl DataFormatter {
             exit_date,
             pnl,
             pnl_percentage,
-            if pnl >= 0.0 { "gain" } else { "loss" },
+            if pnl >= Decimal::ZERO { "gain" } else { "loss" },
             stock.stop_loss,
             stock.commissions
         )
@@ -66,16 +69,17 @@ impl DataFormatter {
         let exit_date = option.exit_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "Open".to_string());
         
         // Calculate P&L from entry and exit prices
+        let contracts = Decimal::from(option.number_of_contracts);
         let pnl = if let Some(exit_price) = option.exit_price {
-            (exit_price - option.entry_price) * option.number_of_contracts as f64 - option.commissions
+            (exit_price - option.entry_price) * contracts - option.commissions
         } else {
-            0.0
+            Decimal::ZERO
         };
         
-        let pnl_percentage = if option.entry_price > 0.0 {
-            (pnl / (option.entry_price * option.number_of_contracts as f64)) * 100.0
+        let pnl_percentage = if option.entry_price > Decimal::ZERO {
+            (pnl * Decimal::ONE_HUNDRED).checked_div(option.entry_price * contracts).unwrap_or(Decimal::ZERO)
         } else {
-            0.0
+            Decimal::ZERO
         };
 
         format!(
@@ -94,7 +98,7 @@ impl DataFormatter {
             exit_date,
             pnl,
             pnl_percentage,
-            if pnl >= 0.0 { "gain" } else { "loss" },
+            if pnl >= Decimal::ZERO { "gain" } else { "loss" },
             option.total_premium,
             option.commissions
         )
@@ -102,10 +106,15 @@ impl DataFormatter {
 
     /// Format trade note for embedding
     pub fn format_trade_note_for_embedding(note: &TradeNote) -> String {
+        Self::format_trade_note_with_captions(note, &ImageCaptions::new())
+    }
+
+    /// Format trade note for embedding, replacing embedded images with their captions
+    pub fn format_trade_note_with_captions(note: &TradeNote, captions: &ImageCaptions) -> String {
         format!(
             "Trade note: {} - {}",
             note.name,
-            note.content
+            extract_plain_text(&note.content, captions)
         )
     }
 
@@ -114,7 +123,7 @@ impl DataFormatter {
         format!(
             "Notebook entry: {} - {}",
             notebook.title,
-            notebook.content
+            extract_plain_text_from_json(&notebook.content, &ImageCaptions::new())
         )
     }
 
@@ -133,16 +142,17 @@ impl DataFormatter {
         let exit_date = stock.exit_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "Open".to_string());
         
         // Calculate P&L from entry and exit prices
+        let shares = money::from_f64(stock.number_shares);
         let pnl = if let Some(exit_price) = stock.exit_price {
-            (exit_price - stock.entry_price) * stock.number_shares - stock.commissions
+            (exit_price - stock.entry_price) * shares - stock.commissions
         } else {
-            0.0
+            Decimal::ZERO
         };
         
-        let pnl_percentage = if stock.entry_price > 0.0 {
-            (pnl / (stock.entry_price * stock.number_shares)) * 100.0
+        let pnl_percentage = if stock.entry_price > Decimal::ZERO {
+            (pnl * Decimal::ONE_HUNDRED).checked_div(stock.entry_price * shares).unwrap_or(Decimal::ZERO)
         } else {
-            0.0
+            Decimal::ZERO
         };
 
         let mut content = HashMap::new();
@@ -159,7 +169,7 @@ impl DataFormatter {
             exit_date,
             pnl,
             pnl_percentage,
-            if pnl >= 0.0 { "gain" } else { "loss" },
+            if pnl >= Decimal::ZERO { "gain" } else { "loss" },
             stock.stop_loss,
             stock.commissions
         ));
@@ -195,16 +205,17 @@ impl DataFormatter {
         let exit_date = option.exit_date.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "Open".to_string());
         
         // Calculate P&L from entry and exit prices
+        let contracts = Decimal::from(option.number_of_contracts);
         let pnl = if let Some(exit_price) = option.exit_price {
-            (exit_price - option.entry_price) * option.number_of_contracts as f64 - option.commissions
+            (exit_price - option.entry_price) * contracts - option.commissions
         } else {
-            0.0
+            Decimal::ZERO
         };
         
-        let pnl_percentage = if option.entry_price > 0.0 {
-            (pnl / (option.entry_price * option.number_of_contracts as f64)) * 100.0
+        let pnl_percentage = if option.entry_price > Decimal::ZERO {
+            (pnl * Decimal::ONE_HUNDRED).checked_div(option.entry_price * contracts).unwrap_or(Decimal::ZERO)
         } else {
-            0.0
+            Decimal::ZERO
         };
 
         let mut content = HashMap::new();
@@ -225,7 +236,7 @@ impl DataFormatter {
             exit_date,
             pnl,
             pnl_percentage,
-            if pnl >= 0.0 { "gain" } else { "loss" },
+            if pnl >= Decimal::ZERO { "gain" } else { "loss" },
             option.total_premium,
             option.commissions
         ));
@@ -260,17 +271,18 @@ impl DataFormatter {
     /// Format trade note for search document
     pub fn format_trade_note_for_search(note: &TradeNote) -> Document {
         let mut content = HashMap::new();
+        let text = extract_plain_text(&note.content, &ImageCaptions::new());
         content.insert("title".to_string(), note.name.clone());
-        content.insert("description".to_string(), note.content.clone());
-        content.insert("content".to_string(), note.content.clone());
+        content.insert("description".to_string(), text.clone());
+        content.insert("content".to_string(), text.clone());
 
         let metadata = DocumentMetadata {
             user_id: "".to_string(), // Will be set by caller
             data_type: "tradenote".to_string(),
             entity_id: note.id.clone(),
             timestamp: Utc::now(),
-            tags: Self::extract_tags(&note.content, &DataType::TradeNote),
-            content_hash: Self::generate_content_hash(&note.content),
+            tags: Self::extract_tags(&text, &DataType::TradeNote),
+            content_hash: Self::generate_content_hash(&text),
         };
 
         Document {
@@ -285,8 +297,8 @@ impl DataFormatter {
         let mut content = HashMap::new();
         content.insert("title".to_string(), notebook.title.clone());
         
-        // Convert JSON content to string for storage
-        let content_str = serde_json::to_string(&notebook.content).unwrap_or_else(|_| "".to_string());
+        // Store the readable text rather than the editor JSON
+        let content_str = extract_plain_text_from_json(&notebook.content, &ImageCaptions::new());
         content.insert("description".to_string(), content_str.clone());
         content.insert("content".to_string(), content_str.clone());
 
@@ -443,12 +455,12 @@ mod tests {
             symbol: "AAPL".to_string(),
             trade_type: crate::models::stock::stocks::TradeType::BUY,
             order_type: crate::models::stock::stocks::OrderType::MARKET,
-            entry_price: 150.0,
-            exit_price: Some(160.0),
-            stop_loss: 140.0,
-            commissions: 5.0,
+            entry_price: Decimal::from(150),
+            exit_price: Some(Decimal::from(160)),
+            stop_loss: Decimal::from(140),
+            commissions: Decimal::from(5),
             number_shares: 100.0,
-            take_profit: Some(170.0),
+            take_profit: Some(Decimal::from(170)),
             initial_target: None,
             profit_target: None,
             trade_ratings: None,
@@ -459,6 +471,8 @@ mod tests {
             brokerage_name: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            realized_pnl: Some(Decimal::from(995)),
+            venue: Some("NASDAQ".to_string()),
         };
 
         let formatted = DataFormatter::format_stock_for_embedding(&stock);
@@ -0,0 +1,341 @@
+// "Ask my data": aggregate questions over the whole journal, such as "what's my most
+// profitable setup this year?". Vector retrieval hands the model a few similar notes and
+// trades, which can't answer a question about all of them. Here the model only writes a
+// plan: up to MAX_STEPS calls into a fixed catalog of analytics functions with typed
+// arguments. The plan is parsed strictly and run against the user's database, then the
+// results go back to the model, which answers citing the numbers by step. Nothing the
+// model writes reaches the database as SQL.
+
+use anyhow::Result;
+use chrono::{NaiveDate, TimeZone, Utc};
+use libsql::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::attribution::{self, AttributionDimension};
+use crate::service::analytics_engine::core_metrics::{self, streaks};
+use crate::service::analytics_engine::hold_time;
+use crate::service::analytics_engine::query::{Direction, TradeFilter};
+use crate::service::analytics_engine::rating_buckets;
+use super::trade_parser_service::extract_json;
+
+/// Longest question accepted
+pub const MAX_QUESTION_CHARS: usize = 500;
+/// Most catalog calls one plan may make
+pub const MAX_STEPS: usize = 3;
+/// Most rows a breakdown step returns
+const MAX_BREAKDOWN_ROWS: usize = 10;
+
+/// Trades the plan's filter narrows to. Tag ids are left out: the model never sees them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StepFilter {
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    /// Brokerage name
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+impl StepFilter {
+    fn trade_filter(&self) -> TradeFilter {
+        TradeFilter {
+            symbol: self.symbol.as_ref().map(|s| s.trim().to_uppercase()),
+            direction: self.direction,
+            tags: Vec::new(),
+            account: self.account.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankOrder {
+    #[default]
+    Best,
+    Worst,
+}
+
+/// One call into the catalog. `time_range` takes the analytics API forms (`ytd`, `90d`,
+/// `2025-W10..2025-W12`, ...) or a date span `2025-01-01..2025-12-31`; missing means all time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "function", rename_all = "snake_case", deny_unknown_fields)]
+pub enum PlanStep {
+    /// Trade count, win rate, net P&L, profit factor, averages, with the win rate interval
+    Summary {
+        #[serde(default)]
+        time_range: Option<String>,
+        #[serde(default)]
+        filter: StepFilter,
+    },
+    /// Net P&L, trades and win rate per symbol, playbook, direction or entry session, ranked
+    Breakdown {
+        dimension: AttributionDimension,
+        #[serde(default)]
+        time_range: Option<String>,
+        #[serde(default)]
+        filter: StepFilter,
+        #[serde(default)]
+        order: RankOrder,
+        #[serde(default)]
+        limit: Option<usize>,
+        /// Skip rows with fewer trades, so one lucky trade doesn't top the ranking
+        #[serde(default)]
+        min_trades: Option<u32>,
+    },
+    /// Current run and longest winning and losing streaks
+    Streaks {
+        #[serde(default)]
+        time_range: Option<String>,
+        #[serde(default)]
+        filter: StepFilter,
+    },
+    /// Distribution of holding periods and P&L per holding bucket
+    HoldTime {
+        #[serde(default)]
+        time_range: Option<String>,
+    },
+    /// Expectancy per self-assigned trade rating
+    RatingExpectancy {
+        #[serde(default)]
+        time_range: Option<String>,
+        #[serde(default)]
+        filter: StepFilter,
+    },
+}
+
+impl PlanStep {
+    fn name(&self) -> &'static str {
+        match self {
+            PlanStep::Summary { .. } => "summary",
+            PlanStep::Breakdown { .. } => "breakdown",
+            PlanStep::Streaks { .. } => "streaks",
+            PlanStep::HoldTime { .. } => "hold_time",
+            PlanStep::RatingExpectancy { .. } => "rating_expectancy",
+        }
+    }
+
+    fn time_range(&self) -> Option<&str> {
+        match self {
+            PlanStep::Summary { time_range, .. }
+            | PlanStep::Breakdown { time_range, .. }
+            | PlanStep::Streaks { time_range, .. }
+            | PlanStep::HoldTime { time_range }
+            | PlanStep::RatingExpectancy { time_range, .. } => time_range.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct QueryPlan {
+    /// Empty when the question isn't about the user's trade statistics
+    pub steps: Vec<PlanStep>,
+}
+
+/// Output of one step, numbered from 1 so the answer can cite it
+#[derive(Debug, Clone, Serialize)]
+pub struct StepResult {
+    pub step: usize,
+    pub function: &'static str,
+    pub time_range: String,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataAnswer {
+    pub answer: String,
+    pub plan: QueryPlan,
+    pub results: Vec<StepResult>,
+}
+
+/// `TimeRange::parse` forms, plus `YYYY-MM-DD..YYYY-MM-DD` with both days inclusive
+pub fn parse_time_range(value: Option<&str>) -> Option<TimeRange> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Some(TimeRange::AllTime);
+    };
+    if let Some((start, end)) = value.split_once("..")
+        && let (Ok(start), Ok(end)) = (
+            NaiveDate::parse_from_str(start.trim(), "%Y-%m-%d"),
+            NaiveDate::parse_from_str(end.trim(), "%Y-%m-%d"),
+        )
+    {
+        if end < start {
+            return None;
+        }
+        let day_start = |day: NaiveDate| Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).unwrap_or_default());
+        return Some(TimeRange::Custom {
+            start_date: Some(day_start(start)),
+            end_date: end.succ_opt().map(day_start).map(|end| end - chrono::Duration::seconds(1)),
+        });
+    }
+    TimeRange::parse(value)
+}
+
+/// Parse and check the model's plan. Anything outside the catalog is an error, never
+/// dropped silently: a plan with a step missing could answer a different question.
+pub fn parse_plan(response: &str) -> Result<QueryPlan, String> {
+    let plan: QueryPlan = serde_json::from_str(extract_json(response)).map_err(|e| format!("Plan is not valid: {}", e))?;
+    if plan.steps.len() > MAX_STEPS {
+        return Err(format!("Plan has {} steps, at most {} are allowed", plan.steps.len(), MAX_STEPS));
+    }
+    for (i, step) in plan.steps.iter().enumerate() {
+        if parse_time_range(step.time_range()).is_none() {
+            return Err(format!("Step {} has an unknown time_range {:?}", i + 1, step.time_range().unwrap_or_default()));
+        }
+        if let PlanStep::Breakdown { limit: Some(limit), .. } = step
+            && (*limit == 0 || *limit > MAX_BREAKDOWN_ROWS)
+        {
+            return Err(format!("Step {} limit must be between 1 and {}", i + 1, MAX_BREAKDOWN_ROWS));
+        }
+    }
+    Ok(plan)
+}
+
+/// Run every step against the user's database, in order
+pub async fn execute(conn: &Connection, plan: &QueryPlan) -> Result<Vec<StepResult>> {
+    let mut results = Vec::with_capacity(plan.steps.len());
+    for (i, step) in plan.steps.iter().enumerate() {
+        let time_range = parse_time_range(step.time_range()).unwrap_or(TimeRange::AllTime);
+        let data = match step {
+            PlanStep::Summary { filter, .. } => {
+                let filter = filter.trade_filter();
+                let metrics = core_metrics::calculate_filtered_core_metrics(conn, &time_range, &filter).await?;
+                let confidence = core_metrics::calculate_sample_confidence(conn, &time_range, &filter).await?;
+                serde_json::json!({
+                    "total_trades": metrics.total_trades,
+                    "winning_trades": metrics.winning_trades,
+                    "losing_trades": metrics.losing_trades,
+                    "win_rate": metrics.win_rate,
+                    "net_pnl": metrics.net_profit_loss,
+                    "gross_profit": metrics.gross_profit,
+                    "gross_loss": metrics.gross_loss,
+                    "average_win": metrics.average_win,
+                    "average_loss": metrics.average_loss,
+                    "profit_factor": metrics.profit_factor,
+                    "biggest_winner": metrics.biggest_winner,
+                    "biggest_loser": metrics.biggest_loser,
+                    "total_commissions": metrics.total_commissions,
+                    "confidence": confidence,
+                })
+            }
+            PlanStep::Breakdown { dimension, filter, order, limit, min_trades, .. } => {
+                let report = attribution::calculate_attribution(conn, &time_range, &filter.trade_filter()).await?;
+                let mut items = report
+                    .dimensions
+                    .into_iter()
+                    .find(|d| d.dimension == *dimension)
+                    .map(|d| d.items)
+                    .unwrap_or_default();
+                items.retain(|item| item.trade_count >= min_trades.unwrap_or(1));
+                if *order == RankOrder::Worst {
+                    items.reverse();
+                }
+                let rows = items.len();
+                items.truncate(limit.unwrap_or(MAX_BREAKDOWN_ROWS));
+                serde_json::json!({
+                    "total_pnl": report.total_pnl,
+                    "trade_count": report.trade_count,
+                    "rows_matching": rows,
+                    "items": items,
+                })
+            }
+            PlanStep::Streaks { filter, .. } => {
+                serde_json::to_value(streaks::calculate_streaks(conn, &time_range, &filter.trade_filter()).await?)?
+            }
+            PlanStep::HoldTime { .. } => {
+                serde_json::to_value(hold_time::calculate_hold_time_distribution(conn, &time_range).await?)?
+            }
+            PlanStep::RatingExpectancy { filter, .. } => {
+                serde_json::to_value(rating_buckets::calculate_rating_expectancy(conn, &time_range, &filter.trade_filter()).await?)?
+            }
+        };
+        results.push(StepResult {
+            step: i + 1,
+            function: step.name(),
+            time_range: step.time_range().unwrap_or("all_time").to_string(),
+            data,
+        });
+    }
+    Ok(results)
+}
+
+/// System prompt asking for a plan; `today` resolves relative dates like "last year"
+pub fn plan_prompt(today: NaiveDate) -> String {
+    format!(
+        r#"You turn a trader's question about their own journal into a query plan. Today is {today}.
+Reply with one JSON object {{"steps": [...]}} and nothing else, using at most {MAX_STEPS} steps from this catalog:
+
+{{"function": "summary", "time_range": T, "filter": F}}
+  trade count, win rate with its confidence interval, net P&L, profit factor, average win and loss, biggest winner and loser
+{{"function": "breakdown", "dimension": "symbol"|"playbook"|"direction"|"session", "time_range": T, "filter": F, "order": "best"|"worst", "limit": 1-{MAX_BREAKDOWN_ROWS}, "min_trades": N}}
+  net P&L, trade count and win rate per symbol, playbook (setup/strategy), direction, or entry session (pre-market, open, midday, close, after hours), ranked by P&L
+{{"function": "streaks", "time_range": T, "filter": F}}
+  current streak and longest winning and losing streaks
+{{"function": "hold_time", "time_range": T}}
+  how long trades were held and the P&L per holding period
+{{"function": "rating_expectancy", "time_range": T, "filter": F}}
+  expectancy per trade rating the trader gave
+
+T is one of "7d", "30d", "90d", "1y", "ytd", "mtd", "qtd", "last_month", "last_quarter", "all_time", ISO weeks "2025-W10..2025-W12", or dates "2025-01-01..2025-12-31". Omit it for all time.
+F is an optional object {{"symbol": "AAPL", "direction": "long"|"short", "account": "broker name"}}; omit fields you don't need.
+Omit optional fields rather than inventing values. Use "min_trades" of 3 or more when ranking for the best or worst.
+If the question is not about the trader's statistics, reply {{"steps": []}}."#
+    )
+}
+
+/// System prompt for the answer, with the step results the model may cite
+pub fn answer_prompt(results: &[StepResult]) -> String {
+    let data = serde_json::to_string(results).unwrap_or_default();
+    format!(
+        "You answer a trader's question about their journal using only the query results below. \
+         Cite every figure you use with its step in brackets, e.g. \"+$1,240 net [1]\". \
+         Do not estimate or invent numbers that are not in the results. If the results do not answer the question, say so. \
+         If a result has fewer than 30 trades or is flagged low_sample, say the sample is small. \
+         Keep it short: a direct answer first, then the supporting numbers.\n\nResults:\n{}",
+        data
+    )
+}
+
+/// Reply for questions the catalog can't answer
+pub const NOT_ANSWERABLE: &str =
+    "I can answer questions about your trading statistics, such as P&L, win rate, streaks and which symbols, setups or sessions perform best. That question isn't one I can look up in your journal.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plan_accepts_catalog_and_rejects_everything_else() {
+        let plan = parse_plan(
+            r#"```json
+            {"steps": [{"function": "breakdown", "dimension": "playbook", "time_range": "ytd", "min_trades": 3, "limit": 1},
+                       {"function": "summary", "time_range": "2025-01-01..2025-12-31", "filter": {"direction": "short"}}]}
+            ```"#,
+        )
+        .unwrap();
+        assert_eq!(plan.steps.len(), 2);
+        assert!(matches!(plan.steps[0], PlanStep::Breakdown { dimension: AttributionDimension::Playbook, order: RankOrder::Best, .. }));
+
+        assert!(parse_plan(r#"{"steps": [{"function": "raw_sql", "query": "DELETE FROM stocks"}]}"#).is_err());
+        assert!(parse_plan(r#"{"steps": [{"function": "summary", "sql": "1=1"}]}"#).is_err());
+        assert!(parse_plan(r#"{"steps": [{"function": "summary", "time_range": "forever"}]}"#).is_err());
+        assert!(parse_plan(r#"{"steps": [{"function": "breakdown", "dimension": "symbol", "limit": 500}]}"#).is_err());
+        let too_many = r#"{"steps": [{"function": "streaks"}, {"function": "streaks"}, {"function": "streaks"}, {"function": "streaks"}]}"#;
+        assert!(parse_plan(too_many).is_err());
+        assert!(parse_plan(r#"{"steps": []}"#).unwrap().steps.is_empty());
+    }
+
+    #[test]
+    fn test_date_span_time_range() {
+        let Some(TimeRange::Custom { start_date, end_date }) = parse_time_range(Some("2025-01-01..2025-12-31")) else {
+            panic!("expected a custom range");
+        };
+        assert_eq!(start_date.unwrap().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(end_date.unwrap().to_rfc3339(), "2025-12-31T23:59:59+00:00");
+        assert!(parse_time_range(Some("2025-12-31..2025-01-01")).is_none());
+        assert!(matches!(parse_time_range(None), Some(TimeRange::AllTime)));
+    }
+}
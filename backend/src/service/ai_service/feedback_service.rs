@@ -0,0 +1,182 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::ai::insights::{InsightMetadata, InsightType};
+use crate::service::ai_service::model_selector::ModelSelector;
+
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+/// What the feedback is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackTarget {
+    Insight,
+    ChatMessage,
+}
+
+impl FeedbackTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedbackTarget::Insight => "insight",
+            FeedbackTarget::ChatMessage => "chat_message",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmitFeedbackRequest {
+    /// true = thumbs up, false = thumbs down
+    pub helpful: bool,
+    pub comment: Option<String>,
+}
+
+impl SubmitFeedbackRequest {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(comment) = &self.comment
+            && comment.len() > MAX_COMMENT_LENGTH
+        {
+            anyhow::bail!("Comment must be at most {} characters", MAX_COMMENT_LENGTH);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AiFeedback {
+    pub id: String,
+    pub target_type: String,
+    pub target_id: String,
+    pub rating: i64,
+    pub comment: Option<String>,
+    pub model: Option<String>,
+    pub prompt_key: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Stores per-user feedback and keeps the global model scores in the registry in sync
+pub struct FeedbackService<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> FeedbackService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Record (or change) feedback. Returns None when the target doesn't exist.
+    pub async fn submit(
+        &self,
+        registry: &Connection,
+        selector: &ModelSelector,
+        target: FeedbackTarget,
+        target_id: &str,
+        req: SubmitFeedbackRequest,
+    ) -> Result<Option<AiFeedback>> {
+        req.validate()?;
+        let Some((model, prompt_key)) = self.resolve_target(target, target_id).await? else {
+            return Ok(None);
+        };
+
+        let previous = self.get(target, target_id).await?;
+        let rating: i64 = if req.helpful { 1 } else { -1 };
+        let comment = req.comment.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+
+        self.conn.execute(
+            "INSERT INTO ai_feedback (id, target_type, target_id, rating, comment, model, prompt_key) VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(target_type, target_id) DO UPDATE SET rating = excluded.rating, comment = excluded.comment",
+            params![Uuid::new_v4().to_string(), target.as_str(), target_id, rating, comment, model.clone(), prompt_key.clone()],
+        ).await?;
+
+        // Only count the vote once per target; a changed vote moves between buckets
+        if let Some(model) = &model {
+            let old_rating = previous.as_ref().map(|f| f.rating).unwrap_or(0);
+            if old_rating != rating {
+                let positive_delta = (rating == 1) as i64 - (old_rating == 1) as i64;
+                let negative_delta = (rating == -1) as i64 - (old_rating == -1) as i64;
+                let key = prompt_key.as_deref().unwrap_or("unknown");
+                ModelSelector::record_vote(registry, model, key, positive_delta, negative_delta).await?;
+                if let Err(e) = selector.refresh(registry).await {
+                    log::warn!("Failed to refresh model scores: {}", e);
+                }
+            }
+        }
+
+        self.get(target, target_id).await
+    }
+
+    pub async fn get(&self, target: FeedbackTarget, target_id: &str) -> Result<Option<AiFeedback>> {
+        let mut rows = self.conn
+            .prepare("SELECT id, target_type, target_id, rating, comment, model, prompt_key, created_at, updated_at FROM ai_feedback WHERE target_type = ? AND target_id = ?")
+            .await?
+            .query(params![target.as_str(), target_id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(AiFeedback {
+                id: row.get(0)?,
+                target_type: row.get(1)?,
+                target_id: row.get(2)?,
+                rating: row.get(3)?,
+                comment: row.get(4)?,
+                model: row.get(5)?,
+                prompt_key: row.get(6)?,
+                created_at: row.get(7)?,
+                updated_at: row.get(8)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Find the model and prompt that produced the target; None if the target doesn't exist
+    async fn resolve_target(&self, target: FeedbackTarget, target_id: &str) -> Result<Option<(Option<String>, Option<String>)>> {
+        match target {
+            FeedbackTarget::Insight => {
+                let mut rows = self.conn
+                    .prepare("SELECT insight_type, metadata FROM ai_insights WHERE id = ?")
+                    .await?
+                    .query(params![target_id])
+                    .await?;
+                let Some(row) = rows.next().await? else { return Ok(None) };
+                let insight_type: String = row.get(0)?;
+                let metadata: Option<String> = row.get(1)?;
+                let prompt_key = InsightType::parse(&insight_type)
+                    .map(|t| format!("insight:{}", t))
+                    .unwrap_or_else(|| format!("insight:{}", insight_type));
+                let model = metadata
+                    .and_then(|m| serde_json::from_str::<InsightMetadata>(&m).ok())
+                    .map(|m| m.model_version)
+                    // Insights generated before model tracking recorded a version number
+                    .filter(|v| v.contains('/'));
+                Ok(Some((model, Some(prompt_key))))
+            }
+            FeedbackTarget::ChatMessage => {
+                let mut rows = self.conn
+                    .prepare("SELECT role, model FROM chat_messages WHERE id = ?")
+                    .await?
+                    .query(params![target_id])
+                    .await?;
+                let Some(row) = rows.next().await? else { return Ok(None) };
+                let role: String = row.get(0)?;
+                if role != "assistant" {
+                    anyhow::bail!("Feedback can only be given on assistant messages");
+                }
+                Ok(Some((row.get(1)?, Some("chat".to_string()))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_length_validation() {
+        let ok = SubmitFeedbackRequest { helpful: true, comment: Some("Spot on".to_string()) };
+        assert!(ok.validate().is_ok());
+        let too_long = SubmitFeedbackRequest { helpful: false, comment: Some("x".repeat(MAX_COMMENT_LENGTH + 1)) };
+        assert!(too_long.validate().is_err());
+    }
+}
@@ -3,6 +3,7 @@
 use crate::service::ai_service::upstash_vector_client::UpstashVectorClient;
 use crate::service::ai_service::qdrant_client::QdrantDocumentClient;
 use crate::service::ai_service::voyager_client::VoyagerClient;
+use crate::service::ai_service::note_chunking::{chunk_query_top_k, merge_chunk_matches};
 use crate::turso::vector_config::HybridSearchConfig;
 use anyhow::{Context, Result};
 use std::collections::HashMap;
@@ -66,16 +67,32 @@ impl HybridSearchService {
             user_id, query.chars().take(50).collect::<String>(), top_k, data_types
         );
 
-        // Generate embedding for query
+        let query_vector = self.embed_query(user_id, query).await?;
+        self.vector_search_with_embedding(user_id, &query_vector, top_k, data_types).await
+    }
+
+    /// Embed a search query. Callers that time or reuse the embedding compute it once and
+    /// pass it to the `*_with_embedding` searches.
+    pub async fn embed_query(&self, user_id: &str, query: &str) -> Result<Vec<f32>> {
         log::debug!("Generating query embedding for user={}", user_id);
         let query_vector = self.voyager_client.embed_text(query).await
             .context("Failed to generate query embedding")?;
-        
+
         log::info!(
             "Query embedding generated - user={}, embedding_dim={}",
             user_id, query_vector.len()
         );
-        
+        Ok(query_vector)
+    }
+
+    /// Vector search with an already computed query embedding
+    pub async fn vector_search_with_embedding(
+        &self,
+        user_id: &str,
+        query_vector: &[f32],
+        top_k: usize,
+        data_types: Option<Vec<crate::service::ai_service::upstash_vector_client::DataType>>,
+    ) -> Result<Vec<HybridSearchResult>> {
         // Create filter if data types are specified
         let filter = if let Some(dtypes) = data_types {
             let filter_values: Vec<String> = dtypes.iter()
@@ -99,10 +116,12 @@ impl HybridSearchService {
             user_id, namespace, top_k
         );
 
+        // Chunked notes return one match per chunk; over-fetch, then merge them per note
         let vector_matches = self.vector_client
-            .query_similarity(&namespace, &query_vector, top_k, filter)
+            .query_similarity(&namespace, query_vector, chunk_query_top_k(top_k), filter)
             .await
             .context("Failed to perform vector search")?;
+        let vector_matches = merge_chunk_matches(vector_matches, top_k);
 
         log::info!(
             "Vector search completed - user={}, matches={}",
@@ -113,7 +132,7 @@ impl HybridSearchService {
         if !vector_matches.is_empty() {
             let top_scores: Vec<String> = vector_matches.iter()
                 .take(3)
-                .map(|vm| format!("{:.3}", vm.score))
+                .map(|(vm, _)| format!("{:.3}", vm.score))
                 .collect();
             log::debug!(
                 "Top vector search scores: [{}] - user={}",
@@ -123,21 +142,28 @@ impl HybridSearchService {
 
         let results: Vec<HybridSearchResult> = vector_matches
             .into_iter()
-            .filter_map(|vm| {
-                vm.metadata.map(|metadata| HybridSearchResult {
-                id: vm.id.clone(),
-                    entity_id: metadata.entity_id.clone(),
-                    data_type: format!("{:?}", metadata.data_type).to_lowercase(),
-                    content_snippet: metadata.content_hash.clone(), // Using hash as snippet placeholder
-                vector_score: Some(vm.score),
-                keyword_score: None,
-                combined_score: vm.score,
-                metadata: HashMap::from([
+            .filter_map(|(vm, matched_chunks)| {
+                vm.metadata.map(|metadata| {
+                    let mut result_metadata = HashMap::from([
                         ("user_id".to_string(), metadata.user_id),
                         ("data_type".to_string(), format!("{:?}", metadata.data_type).to_lowercase()),
-                        ("entity_id".to_string(), metadata.entity_id),
+                        ("entity_id".to_string(), metadata.entity_id.clone()),
                         ("timestamp".to_string(), metadata.timestamp.to_rfc3339()),
-                    ]),
+                    ]);
+                    if let Some(chunk_index) = metadata.chunk_index {
+                        result_metadata.insert("chunk_index".to_string(), chunk_index.to_string());
+                        result_metadata.insert("matched_chunks".to_string(), matched_chunks.to_string());
+                    }
+                    HybridSearchResult {
+                        id: vm.id.clone(),
+                        entity_id: metadata.entity_id,
+                        data_type: format!("{:?}", metadata.data_type).to_lowercase(),
+                        content_snippet: metadata.content_hash, // Using hash as snippet placeholder
+                        vector_score: Some(vm.score),
+                        keyword_score: None,
+                        combined_score: vm.score,
+                        metadata: result_metadata,
+                    }
                 })
             })
             .collect();
@@ -150,83 +176,112 @@ impl HybridSearchService {
         Ok(results)
     }
 
-    /// Perform keyword-only search (new functionality)
+    /// Keyword search over the Qdrant documents. Tickers, dates and quoted phrases in the query
+    /// are matched individually; documents are ranked by how many of those terms they contain.
     pub async fn keyword_only_search(
         &self,
         user_id: &str,
         query: &str,
         limit: usize,
-        _data_type: Option<&str>,
+        data_type: Option<&str>,
     ) -> Result<Vec<HybridSearchResult>> {
+        let terms = keyword_terms(query);
         log::info!(
-            "Starting keyword-only search - user={}, query_preview='{}', limit={}",
-            user_id, query.chars().take(50).collect::<String>(), limit
+            "Starting keyword-only search - user={}, terms={:?}, limit={}",
+            user_id, terms, limit
         );
 
-        // Use Qdrant for keyword search
-        let document_ids = self.search_client
-            .search_by_keyword(user_id, query, limit)
-            .await
-            .context("Failed to perform keyword search")?;
+        // (data_type, entity_id) -> (matched terms, content, timestamp)
+        let mut hits: HashMap<(String, String), (usize, String, Option<String>)> = HashMap::new();
+        for term in &terms {
+            let term_hits = self.search_client
+                .search_content(user_id, term, data_type, limit)
+                .await
+                .context("Failed to perform keyword search")?;
+            for hit in term_hits {
+                let entry = hits
+                    .entry((hit.data_type, hit.entity_id))
+                    .or_insert_with(|| (0, hit.content, hit.timestamp));
+                entry.0 += 1;
+            }
+        }
+
+        let mut ranked: Vec<_> = hits.into_iter().collect();
+        // Most matched terms first, newest first among ties
+        ranked.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| b.1.2.cmp(&a.1.2)));
+        ranked.truncate(limit);
 
         log::info!(
             "Keyword search completed - user={}, found {} documents",
-            user_id, document_ids.len()
+            user_id, ranked.len()
         );
 
-        // Convert document IDs to HybridSearchResult format
-        let results: Vec<HybridSearchResult> = document_ids.into_iter().map(|id| {
-            HybridSearchResult {
-                id: id.clone(),
-                entity_id: id.clone(), // Assuming document ID matches entity ID
-                data_type: "unknown".to_string(),
-                content_snippet: "".to_string(),
-                vector_score: None,
-                keyword_score: Some(1.0), // Default keyword score
-                combined_score: 1.0,
-                metadata: HashMap::from([
+        let term_count = terms.len().max(1) as f32;
+        let results = ranked
+            .into_iter()
+            .map(|((data_type, entity_id), (matched, content, timestamp))| {
+                let keyword_score = matched as f32 / term_count;
+                let mut metadata = HashMap::from([
                     ("user_id".to_string(), user_id.to_string()),
+                    ("data_type".to_string(), data_type.clone()),
+                    ("entity_id".to_string(), entity_id.clone()),
                     ("search_type".to_string(), "keyword".to_string()),
-                ]),
-            }
-        }).collect();
+                ]);
+                if let Some(timestamp) = timestamp {
+                    metadata.insert("timestamp".to_string(), timestamp);
+                }
+                HybridSearchResult {
+                    id: entity_id.clone(),
+                    entity_id,
+                    data_type,
+                    content_snippet: content.chars().take(KEYWORD_SNIPPET_CHARS).collect(),
+                    vector_score: None,
+                    keyword_score: Some(keyword_score),
+                    combined_score: keyword_score,
+                    metadata,
+                }
+            })
+            .collect();
 
         Ok(results)
     }
 
-    /// Perform hybrid search combining vector and keyword results
+    /// Perform hybrid search combining vector and keyword results. `query_vector` is the
+    /// query's embedding from `embed_query`.
     pub async fn hybrid_search(
         &self,
         user_id: &str,
         query: &str,
+        query_vector: &[f32],
         limit: usize,
         data_types: Option<Vec<crate::service::ai_service::upstash_vector_client::DataType>>,
     ) -> Result<Vec<HybridSearchResult>> {
         if !self.config.enabled {
             // Fallback to vector-only search
-            return self.vector_only_search(user_id, query, limit, data_types).await;
+            return self.vector_search_with_embedding(user_id, query_vector, limit, data_types).await;
         }
 
         // Perform vector search first (this should always work)
-        let vector_results = self.vector_only_search(user_id, query, limit, data_types.clone()).await?;
+        let vector_results = self.vector_search_with_embedding(user_id, query_vector, limit, data_types.clone()).await?;
         
         // Try keyword search, but don't fail if it's not available
+        let type_filter: Option<Vec<String>> = data_types
+            .as_ref()
+            .map(|types| types.iter().map(|dt| format!("{:?}", dt).to_lowercase()).collect());
         let keyword_results = match self.keyword_only_search(user_id, query, limit, None).await {
-            Ok(results) => results,
+            Ok(results) => results
+                .into_iter()
+                .filter(|r| type_filter.as_ref().is_none_or(|types| types.contains(&r.data_type)))
+                .collect(),
             Err(e) => {
                 log::warn!("Keyword search failed, continuing with vector-only results: {}", e);
                 vec![]
             }
         };
 
-        // Merge and deduplicate results
+        // Fuse both rankings; results are already sorted by fused score
         let mut merged_results = self.merge_search_results(vector_results, keyword_results);
-
-        // Sort by combined score
-        merged_results.sort_by(|a, b| b.combined_score.partial_cmp(&a.combined_score).unwrap());
-
-        // Limit results
-        merged_results.truncate(self.config.max_results);
+        merged_results.truncate(limit.min(self.config.max_results));
 
         Ok(merged_results)
     }
@@ -236,6 +291,7 @@ impl HybridSearchService {
         &self,
         user_id: &str,
         query: &str,
+        query_vector: &[f32],
         limit: usize,
         data_types: Option<Vec<crate::service::ai_service::upstash_vector_client::DataType>>,
     ) -> Result<Vec<HybridSearchResult>> {
@@ -245,7 +301,7 @@ impl HybridSearchService {
         );
 
         // First get hybrid results
-        let mut results = self.hybrid_search(user_id, query, limit, data_types).await
+        let mut results = self.hybrid_search(user_id, query, query_vector, limit, data_types).await
             .context("Failed to perform hybrid search")?;
 
         log::info!(
@@ -303,40 +359,19 @@ impl HybridSearchService {
         Ok(())
     }
 
-    /// Merge vector and keyword search results, deduplicating by entity_id
+    /// Merge vector and keyword search results with reciprocal rank fusion
     fn merge_search_results(
         &self,
         vector_results: Vec<HybridSearchResult>,
         keyword_results: Vec<HybridSearchResult>,
     ) -> Vec<HybridSearchResult> {
-        let mut merged_map: HashMap<String, HybridSearchResult> = HashMap::new();
-
-        // Add vector results
-        for result in vector_results {
-            merged_map.insert(result.entity_id.clone(), result);
-        }
-
-        // Merge keyword results
-        for keyword_result in keyword_results {
-            if let Some(existing) = merged_map.get_mut(&keyword_result.entity_id) {
-                // Merge scores
-                existing.keyword_score = keyword_result.keyword_score;
-                existing.combined_score = self.calculate_combined_score(
-                    existing.vector_score,
-                    keyword_result.keyword_score,
-                );
-                
-                // Update content snippet if keyword result has better description
-                if !keyword_result.content_snippet.is_empty() {
-                    existing.content_snippet = keyword_result.content_snippet;
-                }
-            } else {
-                // Add new result
-                merged_map.insert(keyword_result.entity_id.clone(), keyword_result);
-            }
-        }
-
-        merged_map.into_values().collect()
+        reciprocal_rank_fusion(
+            vector_results,
+            keyword_results,
+            self.config.vector_weight,
+            self.config.keyword_weight,
+            self.config.rrf_k,
+        )
     }
 
     /// Calculate combined score from vector and keyword scores
@@ -367,6 +402,119 @@ impl HybridSearchService {
     }
 }
 
+/// Upper-case words that look like tickers but aren't
+const NON_TICKER_WORDS: &[&str] = &[
+    "AM", "PM", "PNL", "USD", "AND", "OR", "THE", "IS", "IT", "MY", "ME", "DO", "IN", "ON", "AT", "TO", "OF", "WHAT", "HOW", "WHY", "WHEN",
+];
+const MAX_KEYWORD_TERMS: usize = 5;
+const KEYWORD_SNIPPET_CHARS: usize = 500;
+
+fn is_date_term(token: &str) -> bool {
+    let parts: Vec<&str> = token.split(['-', '/']).collect();
+    let numeric = parts.iter().all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()));
+    match parts.as_slice() {
+        // 2024-03-15
+        [y, m, d] if numeric && y.len() == 4 => m.len() <= 2 && d.len() <= 2,
+        // 3/15/2024, 3/15
+        [m, d, y] if numeric && y.len() == 4 => m.len() <= 2 && d.len() <= 2,
+        [m, d] if numeric && token.contains('/') => m.len() <= 2 && d.len() <= 2,
+        _ => false,
+    }
+}
+
+fn push_unique(terms: &mut Vec<String>, term: String) {
+    if !term.is_empty() && !terms.contains(&term) {
+        terms.push(term);
+    }
+}
+
+/// Exact-match terms worth a keyword lookup: quoted phrases, tickers (`AAPL`, `$aapl`) and
+/// dates. Falls back to the whole query when none are present.
+pub fn keyword_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for (i, phrase) in query.split('"').enumerate() {
+        if i % 2 == 1 {
+            push_unique(&mut terms, phrase.trim().to_string());
+        }
+    }
+
+    for raw in query.split(|c: char| c.is_whitespace() || matches!(c, ',' | '?' | '!' | '(' | ')' | '"' | ';' | ':')) {
+        let token = raw.trim_end_matches('.').trim_end_matches("'s");
+        if let Some(cashtag) = token.strip_prefix('$') {
+            if !cashtag.is_empty() && cashtag.len() <= 6 && cashtag.chars().all(|c| c.is_ascii_alphabetic() || c == '.') {
+                push_unique(&mut terms, cashtag.to_ascii_uppercase());
+            }
+        } else if is_date_term(token)
+            || ((2..=5).contains(&token.len())
+                && token.chars().all(|c| c.is_ascii_uppercase())
+                && !NON_TICKER_WORDS.contains(&token))
+        {
+            push_unique(&mut terms, token.to_string());
+        }
+    }
+
+    if terms.is_empty() {
+        push_unique(&mut terms, query.trim().to_string());
+    }
+    terms.truncate(MAX_KEYWORD_TERMS);
+    terms
+}
+
+/// Reciprocal rank fusion: each list contributes `weight / (k + rank)` per entity. Scores are
+/// scaled so an entity ranked first in both lists gets 1.0. Entities found by both searches
+/// keep the vector result with the keyword score and (real) content snippet folded in.
+pub fn reciprocal_rank_fusion(
+    vector_results: Vec<HybridSearchResult>,
+    keyword_results: Vec<HybridSearchResult>,
+    vector_weight: f32,
+    keyword_weight: f32,
+    k: f32,
+) -> Vec<HybridSearchResult> {
+    let mut fused: Vec<(f32, HybridSearchResult)> = Vec::new();
+    let mut positions: HashMap<(String, String), usize> = HashMap::new();
+
+    for (rank, result) in vector_results.into_iter().enumerate() {
+        let key = (result.data_type.clone(), result.entity_id.clone());
+        if positions.contains_key(&key) {
+            continue;
+        }
+        positions.insert(key, fused.len());
+        fused.push((vector_weight / (k + rank as f32 + 1.0), result));
+    }
+
+    for (rank, result) in keyword_results.into_iter().enumerate() {
+        let contribution = keyword_weight / (k + rank as f32 + 1.0);
+        let key = (result.data_type.clone(), result.entity_id.clone());
+        match positions.get(&key) {
+            Some(&pos) => {
+                let (score, existing) = &mut fused[pos];
+                *score += contribution;
+                existing.keyword_score = result.keyword_score;
+                if !result.content_snippet.is_empty() {
+                    existing.content_snippet = result.content_snippet;
+                }
+                existing.metadata.insert("search_type".to_string(), "hybrid".to_string());
+            }
+            None => {
+                positions.insert(key, fused.len());
+                fused.push((contribution, result));
+            }
+        }
+    }
+
+    let max_score = (vector_weight + keyword_weight) / (k + 1.0);
+    let mut results: Vec<HybridSearchResult> = fused
+        .into_iter()
+        .map(|(score, mut result)| {
+            result.combined_score = if max_score > 0.0 { score / max_score } else { 0.0 };
+            result
+        })
+        .collect();
+    results.sort_by(|a, b| b.combined_score.partial_cmp(&a.combined_score).unwrap_or(std::cmp::Ordering::Equal));
+    results
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -379,6 +527,7 @@ mod tests {
             vector_weight: 0.6,
             keyword_weight: 0.4,
             max_results: 20,
+            rrf_k: 60.0,
         };
 
         let service = HybridSearchService {
@@ -392,6 +541,37 @@ mod tests {
         assert_eq!(combined_score, 0.8 * 0.6 + 0.6 * 0.4); // 0.48 + 0.24 = 0.72
     }
 
+    fn result(entity_id: &str, snippet: &str) -> HybridSearchResult {
+        HybridSearchResult {
+            id: entity_id.to_string(),
+            entity_id: entity_id.to_string(),
+            data_type: "tradenote".to_string(),
+            content_snippet: snippet.to_string(),
+            vector_score: None,
+            keyword_score: None,
+            combined_score: 0.0,
+            metadata: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion_prefers_entities_found_by_both() {
+        let vector = vec![result("a", "hash-a"), result("b", "hash-b"), result("c", "hash-c")];
+        let keyword = vec![result("c", "TSLA gap fill on 2024-03-15")];
+        let fused = reciprocal_rank_fusion(vector, keyword, 0.5, 0.5, 60.0);
+
+        assert_eq!(fused.iter().map(|r| r.entity_id.as_str()).collect::<Vec<_>>(), vec!["c", "a", "b"]);
+        assert_eq!(fused[0].content_snippet, "TSLA gap fill on 2024-03-15");
+        assert!(fused[0].combined_score <= 1.0);
+    }
+
+    #[test]
+    fn test_keyword_terms() {
+        assert_eq!(keyword_terms("How did my $nvda and AMD trades do on 2024-03-15?"), vec!["NVDA", "AMD", "2024-03-15"]);
+        assert_eq!(keyword_terms("notes about \"opening range\" on 3/15"), vec!["opening range", "3/15"]);
+        assert_eq!(keyword_terms("what went wrong"), vec!["what went wrong"]);
+    }
+
     #[test]
     fn test_search_mode_enum() {
         assert_eq!(SearchMode::VectorOnly, SearchMode::VectorOnly);
@@ -0,0 +1,287 @@
+// Evidence for AI insight findings. The prompt carries the user's actual metrics from the
+// analytics engine, and the model must cite the ones each finding relies on (plus any trade
+// IDs) in a structured `evidence` object. Verification recomputes those metrics and
+// compares them with what the finding claimed, which catches invented statistics.
+//
+// Findings are stored in `ai_insights.key_findings`, as `{text, evidence}` objects when any
+// evidence exists and as plain strings otherwise, so older rows still parse.
+
+use std::collections::{BTreeMap, HashSet};
+
+use anyhow::Result;
+use libsql::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::analytics::CoreMetrics;
+use crate::models::ai::insights::{FindingEvidence, Insight};
+use crate::service::analytics_engine::core_metrics::calculate_core_metrics;
+
+/// Metrics the model may cite, from the core analytics for the insight's time range
+pub fn metric_snapshot(core: &CoreMetrics) -> BTreeMap<String, f64> {
+    [
+        ("total_trades", core.total_trades as f64),
+        ("winning_trades", core.winning_trades as f64),
+        ("losing_trades", core.losing_trades as f64),
+        ("win_rate", core.win_rate),
+        ("net_profit_loss", core.net_profit_loss),
+        ("gross_profit", core.gross_profit),
+        ("gross_loss", core.gross_loss),
+        ("average_win", core.average_win),
+        ("average_loss", core.average_loss),
+        ("biggest_winner", core.biggest_winner),
+        ("biggest_loser", core.biggest_loser),
+        ("profit_factor", core.profit_factor),
+        ("win_loss_ratio", core.win_loss_ratio),
+        ("max_consecutive_wins", core.max_consecutive_wins as f64),
+        ("max_consecutive_losses", core.max_consecutive_losses as f64),
+        ("total_commissions", core.total_commissions),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect()
+}
+
+/// Prompt section with the verified metrics and the required findings format
+pub fn prompt_section(snapshot: &BTreeMap<String, f64>) -> String {
+    let metrics = snapshot.iter().map(|(name, value)| format!("- {}: {:.2}", name, value)).collect::<Vec<_>>().join("\n");
+    format!(
+        "Verified metrics for this period (use these numbers; do not estimate your own):\n{}\n\n\
+         Respond in JSON with title, content, key_findings, recommendations and confidence_score. \
+         Each key finding must be an object {{\"text\": \"...\", \"evidence\": {{\"metrics\": {{\"metric_name\": value}}, \
+         \"stock_trade_ids\": [..], \"option_trade_ids\": [..]}}}} citing every metric and trade the finding relies on, \
+         with metric names from the list above.",
+        metrics
+    )
+}
+
+/// Key findings from the model response: plain strings or `{text, evidence}` objects
+pub fn parse_findings(findings: &Value) -> (Vec<String>, Vec<Option<FindingEvidence>>) {
+    findings
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::String(text) => Some((text.clone(), None)),
+                    Value::Object(object) => {
+                        let text = object.get("text").and_then(Value::as_str)?.to_string();
+                        let evidence = object.get("evidence").and_then(|e| serde_json::from_value(e.clone()).ok());
+                        Some((text, evidence))
+                    }
+                    _ => None,
+                })
+                .unzip()
+        })
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredFinding {
+    text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    evidence: Option<FindingEvidence>,
+}
+
+/// `key_findings` column value
+pub fn findings_json(findings: &[String], evidence: &[Option<FindingEvidence>]) -> serde_json::Result<String> {
+    if evidence.iter().all(Option::is_none) {
+        return serde_json::to_string(findings);
+    }
+    let stored: Vec<StoredFinding> = findings
+        .iter()
+        .enumerate()
+        .map(|(i, text)| StoredFinding { text: text.clone(), evidence: evidence.get(i).cloned().flatten() })
+        .collect();
+    serde_json::to_string(&stored)
+}
+
+/// Parse the `key_findings` column in either stored shape
+pub fn parse_stored_findings(json: &str) -> serde_json::Result<(Vec<String>, Vec<Option<FindingEvidence>>)> {
+    let value: Value = serde_json::from_str(json)?;
+    if !value.is_array() {
+        return Err(serde::de::Error::custom("key_findings is not an array"));
+    }
+    Ok(parse_findings(&value))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricCheck {
+    pub metric: String,
+    pub claimed: f64,
+    /// None for a metric name the analytics engine doesn't know
+    pub actual: Option<f64>,
+    pub matches: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingStatus {
+    /// Every cited metric matches and every cited trade exists
+    Verified,
+    /// At least one cited number is wrong or a cited trade doesn't exist
+    Contradicted,
+    /// No evidence, or nothing in it that can be checked
+    Unverifiable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FindingVerification {
+    pub finding: String,
+    pub status: FindingStatus,
+    pub metric_checks: Vec<MetricCheck>,
+    pub missing_stock_trade_ids: Vec<i64>,
+    pub missing_option_trade_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InsightVerification {
+    pub insight_id: String,
+    pub verified: usize,
+    pub contradicted: usize,
+    pub unverifiable: usize,
+    pub findings: Vec<FindingVerification>,
+}
+
+/// A claim matches when it's within 1% of the actual value or equals it rounded to 0-2
+/// decimals, since models round what they quote
+fn claim_matches(claimed: f64, actual: f64) -> bool {
+    if (claimed - actual).abs() <= (actual.abs() * 0.01).max(1e-9) {
+        return true;
+    }
+    [1.0, 10.0, 100.0].iter().any(|scale| ((actual * scale).round() / scale - claimed).abs() < 1e-9)
+}
+
+fn verify_finding(
+    finding: &str,
+    evidence: Option<&FindingEvidence>,
+    snapshot: &BTreeMap<String, f64>,
+    stock_ids: &HashSet<i64>,
+    option_ids: &HashSet<i64>,
+) -> FindingVerification {
+    let mut verification = FindingVerification {
+        finding: finding.to_string(),
+        status: FindingStatus::Unverifiable,
+        metric_checks: Vec::new(),
+        missing_stock_trade_ids: Vec::new(),
+        missing_option_trade_ids: Vec::new(),
+    };
+    let Some(evidence) = evidence else {
+        return verification;
+    };
+    verification.metric_checks = evidence
+        .metrics
+        .iter()
+        .map(|(metric, claimed)| {
+            let actual = snapshot.get(metric).copied();
+            MetricCheck { metric: metric.clone(), claimed: *claimed, actual, matches: actual.is_some_and(|a| claim_matches(*claimed, a)) }
+        })
+        .collect();
+    verification.missing_stock_trade_ids = evidence.stock_trade_ids.iter().filter(|id| !stock_ids.contains(id)).copied().collect();
+    verification.missing_option_trade_ids = evidence.option_trade_ids.iter().filter(|id| !option_ids.contains(id)).copied().collect();
+
+    let checked_metrics = verification.metric_checks.iter().filter(|c| c.actual.is_some()).count();
+    let checked_trades = evidence.stock_trade_ids.len() + evidence.option_trade_ids.len();
+    let contradicted = verification.metric_checks.iter().any(|c| c.actual.is_some() && !c.matches)
+        || !verification.missing_stock_trade_ids.is_empty()
+        || !verification.missing_option_trade_ids.is_empty();
+    verification.status = if contradicted {
+        FindingStatus::Contradicted
+    } else if checked_metrics + checked_trades > 0 {
+        FindingStatus::Verified
+    } else {
+        FindingStatus::Unverifiable
+    };
+    verification
+}
+
+async fn existing_ids(conn: &Connection, table: &str, ids: &[i64]) -> Result<HashSet<i64>> {
+    if ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let mut rows = conn
+        .prepare(&format!("SELECT id FROM {} WHERE id IN ({})", table, placeholders))
+        .await?
+        .query(libsql::params_from_iter(ids.iter().map(|id| libsql::Value::Integer(*id))))
+        .await?;
+    let mut found = HashSet::new();
+    while let Some(row) = rows.next().await? {
+        found.insert(row.get::<i64>(0)?);
+    }
+    Ok(found)
+}
+
+/// Check every finding's cited numbers and trades against the analytics engine
+pub async fn verify_insight(conn: &Connection, insight: &Insight) -> Result<InsightVerification> {
+    let snapshot = metric_snapshot(&calculate_core_metrics(conn, &insight.time_range).await?);
+    let evidence: Vec<&FindingEvidence> = insight.finding_evidence.iter().flatten().collect();
+    let stock_ids: Vec<i64> = evidence.iter().flat_map(|e| e.stock_trade_ids.iter().copied()).collect();
+    let option_ids: Vec<i64> = evidence.iter().flat_map(|e| e.option_trade_ids.iter().copied()).collect();
+    let stock_ids = existing_ids(conn, "stocks", &stock_ids).await?;
+    let option_ids = existing_ids(conn, "options", &option_ids).await?;
+
+    let findings: Vec<FindingVerification> = insight
+        .key_findings
+        .iter()
+        .enumerate()
+        .map(|(i, finding)| {
+            let evidence = insight.finding_evidence.get(i).and_then(Option::as_ref);
+            verify_finding(finding, evidence, &snapshot, &stock_ids, &option_ids)
+        })
+        .collect();
+    let count = |status: FindingStatus| findings.iter().filter(|f| f.status == status).count();
+    Ok(InsightVerification {
+        insight_id: insight.id.clone(),
+        verified: count(FindingStatus::Verified),
+        contradicted: count(FindingStatus::Contradicted),
+        unverifiable: count(FindingStatus::Unverifiable),
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_findings_round_trip_both_shapes() {
+        let (texts, evidence) = parse_findings(&serde_json::json!([
+            "Plain finding",
+            {"text": "Win rate is 54%", "evidence": {"metrics": {"win_rate": 54.0}, "stock_trade_ids": [7]}},
+        ]));
+        assert_eq!(texts, vec!["Plain finding", "Win rate is 54%"]);
+        assert_eq!(evidence[0], None);
+        assert_eq!(evidence[1].as_ref().unwrap().stock_trade_ids, vec![7]);
+
+        let stored = findings_json(&texts, &evidence).unwrap();
+        assert_eq!(parse_stored_findings(&stored).unwrap(), (texts.clone(), evidence));
+        // Without evidence the legacy string array is kept
+        assert_eq!(findings_json(&texts, &[None, None]).unwrap(), r#"["Plain finding","Win rate is 54%"]"#);
+    }
+
+    #[test]
+    fn test_verify_finding_flags_wrong_numbers() {
+        let snapshot: BTreeMap<String, f64> = [("win_rate".to_string(), 54.23), ("profit_factor".to_string(), 1.84)].into();
+        let stocks: HashSet<i64> = [7].into();
+        let evidence = |metrics: &[(&str, f64)], stock_trade_ids: Vec<i64>| FindingEvidence {
+            metrics: metrics.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+            stock_trade_ids,
+            option_trade_ids: Vec::new(),
+        };
+
+        let rounded = evidence(&[("win_rate", 54.0), ("profit_factor", 1.8)], vec![7]);
+        assert_eq!(verify_finding("", Some(&rounded), &snapshot, &stocks, &HashSet::new()).status, FindingStatus::Verified);
+
+        let invented = evidence(&[("profit_factor", 2.5)], Vec::new());
+        assert_eq!(verify_finding("", Some(&invented), &snapshot, &stocks, &HashSet::new()).status, FindingStatus::Contradicted);
+
+        let missing_trade = evidence(&[], vec![99]);
+        let result = verify_finding("", Some(&missing_trade), &snapshot, &stocks, &HashSet::new());
+        assert_eq!((result.status, result.missing_stock_trade_ids), (FindingStatus::Contradicted, vec![99]));
+
+        let unknown = evidence(&[("sharpe_ratio", 1.2)], Vec::new());
+        assert_eq!(verify_finding("", Some(&unknown), &snapshot, &stocks, &HashSet::new()).status, FindingStatus::Unverifiable);
+        assert_eq!(verify_finding("", None, &snapshot, &stocks, &HashSet::new()).status, FindingStatus::Unverifiable);
+    }
+}
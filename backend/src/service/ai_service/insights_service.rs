@@ -2,12 +2,23 @@
 
 use crate::models::ai::insights::{
     Insight, InsightRequest, InsightType, InsightListResponse, InsightSummary,
-    InsightGenerationTask, InsightTemplate, InsightMetadata
+    InsightGenerationTask, InsightGenerationStatus, InsightTemplate, InsightMetadata, InsightSource
 };
 use crate::models::stock::stocks::TimeRange;
+use crate::models::ai::insights::FindingEvidence;
+use crate::models::ai::chat_templates::{self, PersonaPromptTemplate};
+use crate::service::ai_service::insight_evidence;
+use crate::service::ai_service::memory_service;
 use crate::service::ai_service::vectorization_service::VectorizationService;
+use crate::service::ai_service::recommendation_outcomes;
+use crate::service::ai_service::rule_insights;
 use crate::service::ai_service::openrouter_client::{OpenRouterClient, MessageRole as OpenRouterMessageRole};
 use crate::service::ai_service::upstash_vector_client::DataType;
+use crate::service::analytics_engine::attribution::calculate_attribution;
+use crate::service::analytics_engine::core_metrics::calculate_core_metrics;
+use crate::service::analytics_engine::query::TradeFilter;
+use crate::service::automations::{self, AutomationService};
+use crate::service::i18n;
 use crate::turso::client::TursoClient;
 use anyhow::Result;
 use chrono::Utc;
@@ -15,11 +26,27 @@ use libsql::{Connection, params};
 use serde_json;
 use std::sync::Arc;
 
+/// Confirmed user memories appended to insight prompts
+const MAX_INSIGHT_MEMORIES: usize = 8;
+/// Queued regenerations older than this are dropped; the user has moved on
+const QUEUED_TASK_MAX_AGE_HOURS: i64 = 24;
+
+/// Outcome of one queued-regeneration pass for a user
+#[derive(Debug, Default, serde::Serialize)]
+pub struct QueuedRegeneration {
+    pub regenerated: u64,
+    pub still_queued: u64,
+    pub expired: u64,
+    /// A regeneration failed, so the rest were left for the next run
+    pub provider_unavailable: bool,
+}
+
 /// AI Insights Service for generating trading insights
 pub struct AIInsightsService {
     vectorization_service: Arc<VectorizationService>,
     openrouter_client: Arc<OpenRouterClient>,
     turso_client: Arc<TursoClient>,
+    automation_service: Arc<AutomationService>,
     max_context_vectors: usize,
 }
 
@@ -28,12 +55,14 @@ impl AIInsightsService {
         vectorization_service: Arc<VectorizationService>,
         openrouter_client: Arc<OpenRouterClient>,
         turso_client: Arc<TursoClient>,
+        automation_service: Arc<AutomationService>,
         max_context_vectors: usize,
     ) -> Self {
         Self {
             vectorization_service,
             openrouter_client,
             turso_client,
+            automation_service,
             max_context_vectors,
         }
     }
@@ -61,21 +90,98 @@ impl AIInsightsService {
         task.start();
         self.update_generation_task(conn, &task).await?;
 
+        // Rules when asked for explicitly, and whenever the LLM path fails. A still-valid
+        // cached insight beats both a rules rewrite and an error; it is served as stale and
+        // the task is queued for regeneration once the provider recovers.
+        let insight = match request.source {
+            Some(InsightSource::Rules) => rule_insights::generate(conn, user_id, &request).await?,
+            _ => match self.build_llm_insight(user_id, &request, conn, start_time).await {
+                Ok(insight) => insight,
+                Err(e) => {
+                    if let Some(mut cached) = self.get_recent_insight(conn, user_id, &request.time_range, &request.insight_type).await?
+                        && !cached.is_expired()
+                    {
+                        log::warn!("LLM insight generation failed for user {}, serving cached insight {}: {}", user_id, cached.id, e);
+                        task.queue(e.to_string());
+                        self.update_generation_task(conn, &task).await?;
+                        cached.stale = true;
+                        return Ok(cached);
+                    }
+                    if request.source.is_some() {
+                        return Err(e);
+                    }
+                    log::warn!("LLM insight generation failed for user {}, using rules instead: {}", user_id, e);
+                    rule_insights::generate(conn, user_id, &request).await?
+                }
+            },
+        };
+
+        // Store insight
+        self.store_insight(conn, &insight).await?;
+
+        // Forward to the user's Discord/Slack webhooks without blocking the response
+        let webhook_conn = conn.clone();
+        let webhook_insight = insight.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::service::notifications::chat_webhooks::dispatch_insight(&webhook_conn, &webhook_insight).await {
+                log::warn!("Failed to dispatch insight {} to chat webhooks: {}", webhook_insight.id, e);
+            }
+        });
+        self.automation_service.spawn(conn.clone(), user_id.to_string(), automations::insight_generated_event(&insight));
+
+        // Complete task
+        task.complete(insight.id.clone());
+        self.update_generation_task(conn, &task).await?;
+
+        Ok(insight)
+    }
+
+    /// Insight written by the model from the user's vectorized trades and metrics
+    async fn build_llm_insight(
+        &self,
+        user_id: &str,
+        request: &InsightRequest,
+        conn: &Connection,
+        start_time: std::time::Instant,
+    ) -> Result<Insight> {
         // Retrieve relevant trading data
         let trading_data = self.retrieve_trading_data(user_id, &request.time_range, &request.insight_type).await?;
 
+        // Speak in the user's chosen persona, carry over what they have told the assistant
+        // about themselves, and answer in their language
+        let mut prompt_sections: Vec<String> = chat_templates::user_persona(conn).await
+            .map(|p| PersonaPromptTemplate::for_persona(p).prompt_section())
+            .into_iter()
+            .chain(i18n::prompt_instruction(conn).await)
+            .chain(memory_service::memory_prompt_section(conn, "", MAX_INSIGHT_MEMORIES).await)
+            .collect();
+
+        // Performance commentary explains where the P&L came from
+        if request.insight_type == InsightType::PerformanceAnalysis
+            && let Ok(report) = calculate_attribution(conn, &request.time_range, &TradeFilter::default()).await
+        {
+            prompt_sections.extend(report.prompt_section());
+        }
+
+        // Real numbers to cite, so each finding's evidence can be checked later
+        match calculate_core_metrics(conn, &request.time_range).await {
+            Ok(core) => prompt_sections.push(insight_evidence::prompt_section(&insight_evidence::metric_snapshot(&core))),
+            Err(e) => log::warn!("Insight generation without verified metrics: {}", e),
+        }
+
         // Generate insight using AI
-        let insight_content = self.generate_insight_content(&request, &trading_data).await?;
+        let insight_content = self.generate_insight_content(request, &trading_data, &prompt_sections).await?;
 
         // Create insight
-        let mut insight = Insight::new(
+        let insight = Insight::new(
             user_id.to_string(),
             request.time_range.clone(),
-            request.insight_type,
+            request.insight_type.clone(),
             insight_content.title,
             insight_content.content,
         )
         .with_findings(insight_content.key_findings)
+        .with_finding_evidence(insight_content.finding_evidence)
         .with_recommendations(insight_content.recommendations)
         .with_confidence(insight_content.confidence_score);
 
@@ -84,22 +190,14 @@ impl AIInsightsService {
         let metadata = InsightMetadata {
             trade_count: trading_data.trade_count,
             analysis_period_days: self.get_period_days(&request.time_range),
-            model_version: "1.0".to_string(),
+            model_version: self.openrouter_client.active_model(),
             processing_time_ms: processing_time,
             data_quality_score: trading_data.data_quality_score,
         };
-        insight = insight.with_metadata(metadata);
+        let mut insight = insight.with_metadata(metadata);
 
         // Set expiration (24 hours for most insights)
         insight.set_expiration(24);
-
-        // Store insight
-        self.store_insight(conn, &insight).await?;
-
-        // Complete task
-        task.complete(insight.id.clone());
-        self.update_generation_task(conn, &task).await?;
-
         Ok(insight)
     }
 
@@ -145,6 +243,68 @@ impl AIInsightsService {
         Ok(())
     }
 
+    /// Retry insight generations queued while the AI provider was failing. Only the newest
+    /// task per time range and type is regenerated; older duplicates are completed with its
+    /// result. Stops at the first failure, since the provider is likely still down.
+    pub async fn regenerate_queued(&self, conn: &Connection, user_id: &str) -> Result<QueuedRegeneration> {
+        let mut rows = conn
+            .prepare("SELECT id FROM insight_generation_tasks WHERE status = ? ORDER BY created_at DESC")
+            .await?
+            .query(params![serde_json::to_string(&InsightGenerationStatus::Queued)?])
+            .await?;
+        let mut task_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            task_ids.push(row.get::<String>(0)?);
+        }
+
+        let mut summary = QueuedRegeneration::default();
+        let cutoff = Utc::now() - chrono::Duration::hours(QUEUED_TASK_MAX_AGE_HOURS);
+        // (time_range, insight_type) -> insight generated this run
+        let mut done: std::collections::HashMap<(String, String), String> = std::collections::HashMap::new();
+        for task_id in task_ids {
+            let mut task = self.get_generation_task(conn, &task_id).await?;
+            let key = (
+                task.insight_request.time_range.to_db(),
+                task.insight_request.insight_type.as_str().to_string(),
+            );
+            if let Some(insight_id) = done.get(&key) {
+                task.complete(insight_id.clone());
+                self.update_generation_task(conn, &task).await?;
+                continue;
+            }
+            if task.created_at < cutoff {
+                task.status = InsightGenerationStatus::Expired;
+                task.completed_at = Some(Utc::now());
+                self.update_generation_task(conn, &task).await?;
+                summary.expired += 1;
+                continue;
+            }
+            if summary.provider_unavailable {
+                summary.still_queued += 1;
+                continue;
+            }
+
+            let mut request = task.insight_request.clone();
+            request.force_regenerate = Some(true);
+            request.source = Some(InsightSource::Llm);
+            match self.build_llm_insight(user_id, &request, conn, std::time::Instant::now()).await {
+                Ok(insight) => {
+                    self.store_insight(conn, &insight).await?;
+                    task.complete(insight.id.clone());
+                    self.update_generation_task(conn, &task).await?;
+                    done.insert(key, insight.id);
+                    summary.regenerated += 1;
+                }
+                Err(e) => {
+                    log::warn!("Queued insight regeneration still failing for user {}: {}", user_id, e);
+                    summary.provider_unavailable = true;
+                    summary.still_queued += 1;
+                }
+            }
+        }
+        Ok(summary)
+    }
+
     /// Get user's insights
     pub async fn get_user_insights(
         &self,
@@ -165,18 +325,18 @@ impl AIInsightsService {
         let offset = offset.unwrap_or(0);
 
         // Build query
-        let mut query = "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata FROM ai_insights WHERE user_id = ?".to_string();
+        let mut query = "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata, source FROM ai_insights WHERE user_id = ?".to_string();
         let mut params: Vec<String> = vec![user_id.to_string()];
 
         if let Some(ref tr) = time_range {
             query.push_str(" AND time_range = ?");
-            params.push(serde_json::to_string(&tr)?);
+            params.push(tr.to_db());
             log::info!("Added time_range filter: {:?}", tr);
         }
 
         if let Some(ref it) = insight_type {
             query.push_str(" AND insight_type = ?");
-            params.push(serde_json::to_string(&it)?);
+            params.push(it.as_str().to_string());
             log::info!("Added insight_type filter: {:?}", it);
         }
 
@@ -193,12 +353,12 @@ impl AIInsightsService {
 
         if let Some(tr) = time_range {
             count_query.push_str(" AND time_range = ?");
-            count_params.push(serde_json::to_string(&tr)?);
+            count_params.push(tr.to_db());
         }
 
         if let Some(it) = insight_type {
             count_query.push_str(" AND insight_type = ?");
-            count_params.push(serde_json::to_string(&it)?);
+            count_params.push(it.as_str().to_string());
         }
 
         log::info!("Count query: {}", count_query);
@@ -290,7 +450,7 @@ impl AIInsightsService {
         user_id: &str,
     ) -> Result<Insight> {
         let stmt = conn.prepare(
-            "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata FROM ai_insights WHERE id = ? AND user_id = ?"
+            "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata, source FROM ai_insights WHERE id = ? AND user_id = ?"
         ).await?;
         
         let mut rows = stmt.query([insight_id, user_id]).await?;
@@ -313,6 +473,7 @@ impl AIInsightsService {
             "DELETE FROM ai_insights WHERE id = ? AND user_id = ?",
             params![insight_id, user_id],
         ).await?;
+        recommendation_outcomes::forget_insight(conn, insight_id).await?;
 
         Ok(())
     }
@@ -332,6 +493,7 @@ impl AIInsightsService {
             InsightType::BehavioralAnalysis => vec![DataType::Stock, DataType::Option, DataType::TradeNote],
             InsightType::MarketAnalysis => vec![DataType::Stock, DataType::Option],
             InsightType::OpportunityDetection => vec![DataType::Stock, DataType::Option],
+            InsightType::WeeklyCoach => vec![DataType::Stock, DataType::Option, DataType::TradeNote, DataType::PlaybookStrategy],
         };
 
         // Query vectors for context
@@ -358,6 +520,7 @@ async fn generate_insight_content(
     &self,
     request: &InsightRequest,
     trading_data: &TradingDataSummary,
+    prompt_sections: &[String],
 ) -> Result<InsightContent> {
     // Check if we have enough data
     if trading_data.vector_matches.is_empty() {
@@ -369,7 +532,10 @@ async fn generate_insight_content(
     let template = self.get_insight_template(&request.insight_type);
     
     // Build prompt
-    let prompt = self.build_insight_prompt(&template, request, trading_data);
+    let mut prompt = self.build_insight_prompt(&template, request, trading_data);
+    for section in prompt_sections {
+        prompt.push_str(&format!("\n\n{}", section));
+    }
 
     // Generate content using OpenRouter
     let messages = vec![crate::service::ai_service::openrouter_client::ChatMessage {
@@ -391,6 +557,7 @@ async fn generate_insight_content(
     match serde_json::from_str::<serde_json::Value>(&response) {
         Ok(parsed_response) => {
             // Successfully parsed as JSON
+            let (key_findings, finding_evidence) = insight_evidence::parse_findings(&parsed_response["key_findings"]);
             Ok(InsightContent {
                 title: parsed_response["title"]
                     .as_str()
@@ -400,15 +567,8 @@ async fn generate_insight_content(
                     .as_str()
                     .unwrap_or(&response)
                     .to_string(),
-                key_findings: parsed_response["key_findings"]
-                    .as_array()
-                    .map(|arr| {
-                        arr.iter()
-                            .filter_map(|v| v.as_str())
-                            .map(|s| s.to_string())
-                            .collect()
-                    })
-                    .unwrap_or_default(),
+                key_findings,
+                finding_evidence,
                 recommendations: parsed_response["recommendations"]
                     .as_array()
                     .map(|arr| {
@@ -435,6 +595,7 @@ async fn generate_insight_content(
                 key_findings: vec![
                     "Analysis based on available trading data".to_string()
                 ],
+                finding_evidence: vec![None],
                 recommendations: vec![
                     "Review the detailed analysis above".to_string()
                 ],
@@ -479,6 +640,7 @@ async fn generate_insight_content(
             TimeRange::OneYear => 365,
             TimeRange::Custom { .. } => 30, // Default to 30 days for custom ranges
             TimeRange::AllTime => 365, // Default to 1 year for all time
+            calendar => calendar.approx_days(),
         }
     }
 
@@ -491,13 +653,13 @@ async fn generate_insight_content(
         insight_type: &InsightType,
     ) -> Result<Option<Insight>> {
         let stmt = conn.prepare(
-            "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata FROM ai_insights WHERE user_id = ? AND time_range = ? AND insight_type = ? ORDER BY generated_at DESC LIMIT 1"
+            "SELECT id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata, source FROM ai_insights WHERE user_id = ? AND time_range = ? AND insight_type = ? ORDER BY generated_at DESC LIMIT 1"
         ).await?;
         
         let mut rows = stmt.query([
             user_id,
-            &serde_json::to_string(&time_range)?,
-            &serde_json::to_string(&insight_type)?,
+            &time_range.to_db(),
+            insight_type.as_str(),
         ]).await?;
         
         if let Some(row) = rows.next().await? {
@@ -579,25 +741,25 @@ async fn generate_insight_content(
         };
 
         // Parse JSON fields
-        let time_range: TimeRange = match serde_json::from_str(&time_range_str) {
-            Ok(tr) => {
+        let time_range = match TimeRange::from_db(&time_range_str) {
+            Some(tr) => {
                 log::debug!("Successfully parsed time_range: {:?}", tr);
                 tr
             }
-            Err(e) => {
-                log::error!("Failed to parse time_range JSON '{}': {}", time_range_str, e);
-                return Err(anyhow::anyhow!("Failed to parse time_range JSON: {}", e));
+            None => {
+                log::error!("Failed to parse time_range '{}'", time_range_str);
+                return Err(anyhow::anyhow!("Unknown time_range: {}", time_range_str));
             }
         };
 
-        let insight_type: InsightType = match serde_json::from_str(&insight_type_str) {
-            Ok(it) => {
+        let insight_type = match InsightType::parse(&insight_type_str) {
+            Some(it) => {
                 log::debug!("Successfully parsed insight_type: {:?}", it);
                 it
             }
-            Err(e) => {
-                log::error!("Failed to parse insight_type JSON '{}': {}", insight_type_str, e);
-                return Err(anyhow::anyhow!("Failed to parse insight_type JSON: {}", e));
+            None => {
+                log::error!("Unknown insight_type '{}'", insight_type_str);
+                return Err(anyhow::anyhow!("Unknown insight_type: {}", insight_type_str));
             }
         };
 
@@ -679,12 +841,21 @@ async fn generate_insight_content(
             }
         };
 
+        // Rows written before the column existed read as the default 'llm'
+        let source = match row.get::<Option<String>>(13) {
+            Ok(val) => InsightSource::parse(val.as_deref().unwrap_or("llm")),
+            Err(e) => {
+                log::error!("Failed to get source from row: {}", e);
+                return Err(anyhow::anyhow!("Failed to get source from row: {}", e));
+            }
+        };
+
         // Parse JSON arrays
-        let key_findings_vec = if let Some(kf) = key_findings {
-            match serde_json::from_str::<Vec<String>>(&kf) {
-                Ok(vec) => {
-                    log::debug!("Successfully parsed key_findings: {} items", vec.len());
-                    vec
+        let (key_findings_vec, finding_evidence) = if let Some(kf) = key_findings {
+            match insight_evidence::parse_stored_findings(&kf) {
+                Ok(parsed) => {
+                    log::debug!("Successfully parsed key_findings: {} items", parsed.0.len());
+                    parsed
                 }
                 Err(e) => {
                     log::error!("Failed to parse key_findings JSON '{}': {}", kf, e);
@@ -693,7 +864,7 @@ async fn generate_insight_content(
             }
             } else {
             log::debug!("No key_findings, using empty vec");
-                Vec::new()
+                (Vec::new(), Vec::new())
         };
 
         let recommendations_vec = if let Some(rec) = recommendations {
@@ -789,37 +960,35 @@ async fn generate_insight_content(
             title,
             content,
             key_findings: key_findings_vec,
+            finding_evidence,
             recommendations: recommendations_vec,
             data_sources: data_sources_vec,
             confidence_score: confidence_score as f32,
             generated_at,
             expires_at,
             metadata,
+            source,
+            stale: false,
         })
     }
 
-    /// Store insight
-    async fn store_insight(&self, conn: &Connection, insight: &Insight) -> Result<()> {
-        conn.execute(
-            "INSERT INTO ai_insights (id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
-            params![
-                insight.id.clone(),
-                insight.user_id.clone(),
-                serde_json::to_string(&insight.time_range)?,
-                serde_json::to_string(&insight.insight_type)?,
-                insight.title.clone(),
-                insight.content.clone(),
-                serde_json::to_string(&insight.key_findings)?,
-                serde_json::to_string(&insight.recommendations)?,
-                serde_json::to_string(&insight.data_sources)?,
-                insight.confidence_score,
-                insight.generated_at.to_rfc3339(),
-                insight.expires_at.map(|d| d.to_rfc3339()),
-                serde_json::to_string(&insight.metadata)?,
-                Utc::now().to_rfc3339()
-            ],
-        ).await?;
+    /// Check an insight's cited numbers and trades against the analytics engine
+    pub async fn verify_insight(
+        &self,
+        conn: &Connection,
+        insight_id: &str,
+        user_id: &str,
+    ) -> Result<insight_evidence::InsightVerification> {
+        let insight = self.get_insight(conn, insight_id, user_id).await?;
+        insight_evidence::verify_insight(conn, &insight).await
+    }
 
+    /// Store insight
+    pub async fn store_insight(&self, conn: &Connection, insight: &Insight) -> Result<()> {
+        insert_insight(conn, insight).await?;
+        if let Err(e) = recommendation_outcomes::track(conn, insight).await {
+            log::warn!("Failed to track recommendations of insight {}: {}", insight.id, e);
+        }
         Ok(())
     }
 
@@ -830,8 +999,8 @@ async fn generate_insight_content(
             params![
                 task.task_id.clone(),
                 task.user_id.clone(),
-                serde_json::to_string(&task.insight_request.time_range)?,
-                serde_json::to_string(&task.insight_request.insight_type)?,
+                task.insight_request.time_range.to_db(),
+                task.insight_request.insight_type.as_str(),
                 serde_json::to_string(&task.status)?,
                 task.created_at.to_rfc3339(),
                 task.started_at.map(|d| d.to_rfc3339()),
@@ -874,10 +1043,13 @@ async fn generate_insight_content(
                 task_id: row.get(0)?,
                 user_id: row.get(1)?,
                 insight_request: InsightRequest {
-                    time_range: serde_json::from_str(&row.get::<String>(2)?)?,
-                    insight_type: serde_json::from_str(&row.get::<String>(3)?)?,
+                    time_range: TimeRange::from_db(&row.get::<String>(2)?)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown time_range in task {}", task_id))?,
+                    insight_type: InsightType::parse(&row.get::<String>(3)?)
+                        .ok_or_else(|| anyhow::anyhow!("Unknown insight_type in task {}", task_id))?,
                     include_predictions: None,
                     force_regenerate: None,
+                    source: None,
                 },
                 status: serde_json::from_str(&row.get::<String>(4)?)?,
                 created_at: chrono::DateTime::parse_from_rfc3339(&row.get::<String>(5)?)?.with_timezone(&Utc),
@@ -914,6 +1086,7 @@ async fn generate_insight_content(
             vectorization_service: self.vectorization_service.clone(),
             openrouter_client: self.openrouter_client.clone(),
             turso_client: self.turso_client.clone(),
+            automation_service: self.automation_service.clone(),
             max_context_vectors: self.max_context_vectors,
         }
     }
@@ -933,10 +1106,36 @@ struct InsightContent {
     title: String,
     content: String,
     key_findings: Vec<String>,
+    finding_evidence: Vec<Option<FindingEvidence>>,
     recommendations: Vec<String>,
     confidence_score: f32,
 }
 
+/// Write an insight row; `time_range` and `insight_type` go in as plain values, not JSON
+pub async fn insert_insight(conn: &Connection, insight: &Insight) -> Result<()> {
+    conn.execute(
+        "INSERT INTO ai_insights (id, user_id, time_range, insight_type, title, content, key_findings, recommendations, data_sources, confidence_score, generated_at, expires_at, metadata, source, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            insight.id.clone(),
+            insight.user_id.clone(),
+            insight.time_range.to_db(),
+            insight.insight_type.as_str(),
+            insight.title.clone(),
+            insight.content.clone(),
+            insight_evidence::findings_json(&insight.key_findings, &insight.finding_evidence)?,
+            serde_json::to_string(&insight.recommendations)?,
+            serde_json::to_string(&insight.data_sources)?,
+            insight.confidence_score,
+            insight.generated_at.to_rfc3339(),
+            insight.expires_at.map(|d| d.to_rfc3339()),
+            serde_json::to_string(&insight.metadata)?,
+            insight.source.as_str(),
+            Utc::now().to_rfc3339()
+        ],
+    ).await?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -973,6 +1172,7 @@ mod tests {
                 crate::turso::vector_config::OpenRouterConfig::from_env().unwrap()
             ).unwrap()),
             turso_client: Arc::new(TursoClient::new(crate::turso::config::TursoConfig::from_env().unwrap()).await.unwrap()),
+            automation_service: Arc::new(AutomationService::new(crate::turso::config::WebPushConfig::from_env().unwrap())),
             max_context_vectors: 10,
         };
 
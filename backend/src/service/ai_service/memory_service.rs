@@ -0,0 +1,376 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::service::ai_service::openrouter_client::{ChatMessage, MessageRole, OpenRouterClient};
+use crate::service::ai_service::trade_parser_service::extract_json;
+
+const MAX_MEMORY_LENGTH: usize = 300;
+const MAX_CANDIDATES_PER_MESSAGE: usize = 3;
+pub const CATEGORIES: &[&str] = &["strategy", "risk", "goal", "schedule", "preference", "other"];
+
+const EXTRACTION_PROMPT: &str = r#"You extract durable facts about a trader from their chat message.
+Only keep facts that will still be true in future conversations: strategies they trade, risk limits,
+goals, trading schedule, instruments and preferences. Ignore questions, one-off trades, market opinions
+and anything about the current day only.
+
+Return ONLY a JSON object: {"memories": [{"content": "...", "category": "strategy|risk|goal|schedule|preference|other"}]}
+Write each fact in third person, under 20 words (e.g. "Trades a 9:45 opening range breakout", "Daily risk limit is $300").
+Return {"memories": []} when there is nothing durable."#;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryStatus {
+    /// Extracted by the AI, waiting for the user to confirm
+    Proposed,
+    Confirmed,
+    Rejected,
+}
+
+impl MemoryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MemoryStatus::Proposed => "proposed",
+            MemoryStatus::Confirmed => "confirmed",
+            MemoryStatus::Rejected => "rejected",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "confirmed" => MemoryStatus::Confirmed,
+            "rejected" => MemoryStatus::Rejected,
+            _ => MemoryStatus::Proposed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserMemory {
+    pub id: String,
+    pub content: String,
+    pub category: Option<String>,
+    pub status: MemoryStatus,
+    pub source_session_id: Option<String>,
+    pub source_message_id: Option<String>,
+    pub confirmed_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MemoryCandidate {
+    pub content: String,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExtractionOutput {
+    #[serde(default)]
+    memories: Vec<MemoryCandidate>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateMemoryRequest {
+    pub content: String,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateMemoryRequest {
+    pub content: Option<String>,
+    pub category: Option<String>,
+}
+
+fn validate_content(content: &str) -> Result<String> {
+    let content = content.trim();
+    if content.is_empty() {
+        anyhow::bail!("Memory content is required");
+    }
+    if content.chars().count() > MAX_MEMORY_LENGTH {
+        anyhow::bail!("Memory must be at most {} characters", MAX_MEMORY_LENGTH);
+    }
+    Ok(content.to_string())
+}
+
+fn normalize_category(category: Option<&str>) -> Option<String> {
+    category
+        .map(|c| c.trim().to_lowercase())
+        .filter(|c| !c.is_empty())
+        .map(|c| if CATEGORIES.contains(&c.as_str()) { c } else { "other".to_string() })
+}
+
+/// Per-user store of durable facts the assistant remembers across sessions
+pub struct UserMemoryService<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> UserMemoryService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    pub async fn list(&self, status: Option<MemoryStatus>) -> Result<Vec<UserMemory>> {
+        let mut rows = match status {
+            Some(status) => self.conn
+                .prepare(&format!("{} WHERE status = ? ORDER BY created_at DESC", SELECT_MEMORY))
+                .await?
+                .query(params![status.as_str()])
+                .await?,
+            None => self.conn
+                .prepare(&format!("{} ORDER BY created_at DESC", SELECT_MEMORY))
+                .await?
+                .query(params![])
+                .await?,
+        };
+        let mut memories = Vec::new();
+        while let Some(row) = rows.next().await? {
+            memories.push(row_to_memory(&row)?);
+        }
+        Ok(memories)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<UserMemory>> {
+        let mut rows = self.conn
+            .prepare(&format!("{} WHERE id = ?", SELECT_MEMORY))
+            .await?
+            .query(params![id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row_to_memory(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Memories the user adds themselves are confirmed straight away
+    pub async fn create(&self, req: CreateMemoryRequest) -> Result<UserMemory> {
+        let content = validate_content(&req.content)?;
+        let id = Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO user_memory (id, content, category, status, confirmed_at) VALUES (?, ?, ?, 'confirmed', datetime('now'))",
+            params![id.clone(), content, normalize_category(req.category.as_deref())],
+        ).await?;
+        self.get(&id).await?.ok_or_else(|| anyhow::anyhow!("Memory not found after insert"))
+    }
+
+    /// Store AI-extracted candidates as proposals, skipping anything already known (including rejected facts)
+    pub async fn propose(
+        &self,
+        candidates: Vec<MemoryCandidate>,
+        session_id: Option<&str>,
+        message_id: Option<&str>,
+    ) -> Result<Vec<UserMemory>> {
+        let mut known: HashSet<String> = self.list(None).await?.iter().map(|m| normalize_text(&m.content)).collect();
+        let mut proposed = Vec::new();
+        for candidate in candidates.into_iter().take(MAX_CANDIDATES_PER_MESSAGE) {
+            let Ok(content) = validate_content(&candidate.content) else { continue };
+            if !known.insert(normalize_text(&content)) {
+                continue;
+            }
+            let id = Uuid::new_v4().to_string();
+            self.conn.execute(
+                "INSERT INTO user_memory (id, content, category, status, source_session_id, source_message_id) VALUES (?, ?, ?, 'proposed', ?, ?)",
+                params![id.clone(), content, normalize_category(candidate.category.as_deref()), session_id, message_id],
+            ).await?;
+            if let Some(memory) = self.get(&id).await? {
+                proposed.push(memory);
+            }
+        }
+        Ok(proposed)
+    }
+
+    pub async fn set_status(&self, id: &str, status: MemoryStatus) -> Result<Option<UserMemory>> {
+        let confirmed_at = (status == MemoryStatus::Confirmed).then(|| chrono::Utc::now().to_rfc3339());
+        let affected = self.conn.execute(
+            "UPDATE user_memory SET status = ?, confirmed_at = ? WHERE id = ?",
+            params![status.as_str(), confirmed_at, id],
+        ).await?;
+        if affected == 0 {
+            return Ok(None);
+        }
+        self.get(id).await
+    }
+
+    pub async fn update(&self, id: &str, req: UpdateMemoryRequest) -> Result<Option<UserMemory>> {
+        let Some(existing) = self.get(id).await? else { return Ok(None) };
+        let content = match req.content {
+            Some(content) => validate_content(&content)?,
+            None => existing.content,
+        };
+        let category = match req.category {
+            Some(category) => normalize_category(Some(&category)),
+            None => existing.category,
+        };
+        self.conn.execute(
+            "UPDATE user_memory SET content = ?, category = ? WHERE id = ?",
+            params![content, category, id],
+        ).await?;
+        self.get(id).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.conn.execute("DELETE FROM user_memory WHERE id = ?", params![id]).await? > 0)
+    }
+
+    /// Confirmed memories most relevant to `query`, marked as used
+    pub async fn relevant(&self, query: &str, limit: usize) -> Result<Vec<UserMemory>> {
+        let confirmed = self.list(Some(MemoryStatus::Confirmed)).await?;
+        let selected = rank_memories(query, confirmed, limit);
+        for memory in &selected {
+            self.conn.execute(
+                "UPDATE user_memory SET last_used_at = datetime('now') WHERE id = ?",
+                params![memory.id.clone()],
+            ).await?;
+        }
+        Ok(selected)
+    }
+}
+
+const SELECT_MEMORY: &str = "SELECT id, content, category, status, source_session_id, source_message_id, confirmed_at, last_used_at, created_at, updated_at FROM user_memory";
+
+fn row_to_memory(row: &libsql::Row) -> Result<UserMemory> {
+    Ok(UserMemory {
+        id: row.get(0)?,
+        content: row.get(1)?,
+        category: row.get(2)?,
+        status: MemoryStatus::parse(&row.get::<String>(3)?),
+        source_session_id: row.get(4)?,
+        source_message_id: row.get(5)?,
+        confirmed_at: row.get(6)?,
+        last_used_at: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric() && c != '$')
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn keywords(text: &str) -> HashSet<String> {
+    normalize_text(text)
+        .split(' ')
+        .filter(|w| w.len() > 2 || w.chars().any(|c| c.is_ascii_digit()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Rank memories by keyword overlap with the query. Risk and strategy facts are always
+/// useful context, so they get a small boost and fill remaining slots when nothing matches.
+/// An empty query (e.g. for insight generation) returns the most recent memories.
+pub fn rank_memories(query: &str, mut memories: Vec<UserMemory>, limit: usize) -> Vec<UserMemory> {
+    let query_words = keywords(query);
+    if query_words.is_empty() {
+        memories.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        memories.truncate(limit);
+        return memories;
+    }
+    let mut scored: Vec<(f64, UserMemory)> = memories
+        .into_iter()
+        .map(|m| {
+            let overlap = keywords(&m.content).intersection(&query_words).count() as f64;
+            let boost = match m.category.as_deref() {
+                Some("risk") | Some("strategy") => 0.5,
+                _ => 0.0,
+            };
+            (overlap + boost, m)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then_with(|| b.1.created_at.cmp(&a.1.created_at)));
+    scored.into_iter().filter(|(score, _)| *score > 0.0).take(limit).map(|(_, m)| m).collect()
+}
+
+/// Render memories as a system prompt section
+pub fn format_memories_for_prompt(memories: &[UserMemory]) -> Option<String> {
+    if memories.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = memories.iter().map(|m| format!("- {}", m.content)).collect();
+    Some(format!("What you know about this trader (confirmed by them):\n{}", lines.join("\n")))
+}
+
+/// Prompt section with the user's confirmed memories relevant to `query`; failures only log
+pub async fn memory_prompt_section(conn: &Connection, query: &str, limit: usize) -> Option<String> {
+    match UserMemoryService::new(conn).relevant(query, limit).await {
+        Ok(memories) => format_memories_for_prompt(&memories),
+        Err(e) => {
+            log::warn!("Failed to load user memories: {}", e);
+            None
+        }
+    }
+}
+
+/// Cheap filter so we only ask the model to extract facts from messages that talk about the user
+pub fn looks_memorable(message: &str) -> bool {
+    if message.chars().count() < 15 || message.trim_end().ends_with('?') {
+        return false;
+    }
+    let lower = format!(" {} ", message.to_lowercase());
+    [" i ", " i'm ", " im ", " my ", " i've ", " i'll ", " me "].iter().any(|cue| lower.contains(cue))
+}
+
+/// Ask the model for durable facts in a user's chat message
+pub async fn extract_memory_candidates(openrouter: &OpenRouterClient, message: &str) -> Result<Vec<MemoryCandidate>> {
+    let messages = vec![
+        ChatMessage { role: MessageRole::System, content: EXTRACTION_PROMPT.to_string() },
+        ChatMessage { role: MessageRole::User, content: message.to_string() },
+    ];
+    let response = openrouter.generate_chat(messages).await?;
+    let output: ExtractionOutput = serde_json::from_str(extract_json(&response))?;
+    Ok(output.memories)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(content: &str, category: Option<&str>) -> UserMemory {
+        UserMemory {
+            id: content.to_string(),
+            content: content.to_string(),
+            category: category.map(str::to_string),
+            status: MemoryStatus::Confirmed,
+            source_session_id: None,
+            source_message_id: None,
+            confirmed_at: None,
+            last_used_at: None,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            updated_at: "2025-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rank_memories_prefers_overlap() {
+        let memories = vec![
+            memory("Prefers swing trading tech stocks", Some("preference")),
+            memory("Daily risk limit is $300", Some("risk")),
+            memory("Trades a 9:45 opening range breakout", Some("strategy")),
+        ];
+        let ranked = rank_memories("how did my opening range breakout trades do?", memories, 2);
+        assert_eq!(ranked.len(), 2);
+        assert_eq!(ranked[0].content, "Trades a 9:45 opening range breakout");
+        assert_eq!(ranked[1].content, "Daily risk limit is $300");
+        assert_eq!(rank_memories("", vec![memory("Trades futures", None)], 5).len(), 1);
+    }
+
+    #[test]
+    fn test_looks_memorable() {
+        assert!(looks_memorable("I only trade the 9:45 ORB and my risk limit is $300/day"));
+        assert!(!looks_memorable("What was my win rate last week?"));
+        assert!(!looks_memorable("thanks"));
+    }
+
+    #[test]
+    fn test_normalize_category() {
+        assert_eq!(normalize_category(Some(" Risk ")), Some("risk".to_string()));
+        assert_eq!(normalize_category(Some("psychology")), Some("other".to_string()));
+        assert_eq!(normalize_category(None), None);
+    }
+}
@@ -4,16 +4,39 @@ pub mod insights_service;
 pub mod reports_service;
 pub mod notes_service;
 pub mod openrouter_client;
+pub mod mock_model_client;
 pub mod voyager_client;
 pub mod upstash_vector_client;
 pub mod upstash_search_client;
 pub mod qdrant_client;
+pub mod qdrant_tenant_manager;
 pub mod hybrid_search_service;
 pub mod vectorization_service;
 pub mod data_formatter;
+pub mod content_extraction;
+pub mod note_chunking;
+pub mod trade_parser_service;
+pub mod coach_service;
+pub mod model_selector;
+pub mod feedback_service;
+pub mod context_budget;
+pub mod memory_service;
+pub mod report_scheduler;
+pub mod report_sharing;
+pub mod report_branding;
+pub mod chat_export;
+pub mod insight_evidence;
+pub mod rule_insights;
+pub mod similar_trades;
+pub mod setup_discovery;
+pub mod note_assistant;
+pub mod request_trace;
+pub mod data_query;
+pub mod recommendation_outcomes;
+pub mod report_chat;
 
 // Re-export commonly used types
-pub use chat_service::AIChatService;
+pub use chat_service::{AIChatService, FundamentalsContext};
 pub use insights_service::AIInsightsService;
 pub use reports_service::AiReportsService;
 pub use notes_service::AINotesService;
@@ -23,4 +46,9 @@ pub use voyager_client::VoyagerClient;
 pub use upstash_vector_client::UpstashVectorClient;
 pub use upstash_search_client::UpstashSearchClient;
 pub use qdrant_client::QdrantDocumentClient;
+pub use qdrant_tenant_manager::QdrantTenantManager;
 pub use hybrid_search_service::HybridSearchService;
+pub use trade_parser_service::TradeParserService;
+pub use coach_service::AICoachService;
+pub use model_selector::ModelSelector;
+pub use setup_discovery::SetupDiscoveryService;
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Votes a model needs before its score can push it down the candidate list
+pub const MIN_VOTES_FOR_RANKING: u64 = 20;
+/// Smoothed thumbs-up share below which a model is considered low-performing
+pub const LOW_QUALITY_THRESHOLD: f64 = 0.4;
+
+/// Aggregated user feedback for one model (optionally one prompt)
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct ModelScore {
+    pub model: String,
+    pub prompt_key: Option<String>,
+    pub positive: u64,
+    pub negative: u64,
+}
+
+impl ModelScore {
+    pub fn votes(&self) -> u64 {
+        self.positive + self.negative
+    }
+
+    /// Laplace-smoothed share of positive votes, 0.5 with no data
+    pub fn quality(&self) -> f64 {
+        (self.positive as f64 + 1.0) / (self.votes() as f64 + 2.0)
+    }
+
+    pub fn is_low_performing(&self) -> bool {
+        self.votes() >= MIN_VOTES_FOR_RANKING && self.quality() < LOW_QUALITY_THRESHOLD
+    }
+}
+
+/// Picks which OpenRouter model to use based on aggregated feedback.
+///
+/// Candidates keep their configured order (`OPENROUTER_MODEL` first, then
+/// `OPENROUTER_CANDIDATE_MODELS`); a model is skipped only once it has enough
+/// votes to be confidently low-performing.
+pub struct ModelSelector {
+    candidates: Vec<String>,
+    scores: RwLock<HashMap<String, ModelScore>>,
+}
+
+impl ModelSelector {
+    pub fn new(default_model: String, fallbacks: Vec<String>) -> Self {
+        let mut candidates = vec![default_model];
+        for model in fallbacks {
+            if !candidates.contains(&model) {
+                candidates.push(model);
+            }
+        }
+        Self { candidates, scores: RwLock::new(HashMap::new()) }
+    }
+
+    /// Build from the default model plus comma-separated `OPENROUTER_CANDIDATE_MODELS`
+    pub fn from_env(default_model: String) -> Self {
+        let fallbacks = std::env::var("OPENROUTER_CANDIDATE_MODELS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty())
+            .collect();
+        Self::new(default_model, fallbacks)
+    }
+
+    /// Model to use for the next request
+    pub fn select(&self) -> String {
+        match self.scores.read() {
+            Ok(scores) => select_model(&self.candidates, &scores),
+            Err(_) => self.candidates[0].clone(),
+        }
+    }
+
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// Current per-model scores for the configured candidates
+    pub fn scores(&self) -> Vec<ModelScore> {
+        let scores = self.scores.read().map(|s| s.clone()).unwrap_or_default();
+        self.candidates
+            .iter()
+            .map(|m| scores.get(m).cloned().unwrap_or(ModelScore { model: m.clone(), ..Default::default() }))
+            .collect()
+    }
+
+    /// Reload per-model totals from the registry database
+    pub async fn refresh(&self, registry: &Connection) -> Result<()> {
+        let mut rows = registry
+            .prepare("SELECT model, SUM(positive), SUM(negative) FROM model_quality_scores GROUP BY model")
+            .await?
+            .query(params![])
+            .await?;
+
+        let mut loaded = HashMap::new();
+        while let Some(row) = rows.next().await? {
+            let model: String = row.get(0)?;
+            let score = ModelScore {
+                model: model.clone(),
+                prompt_key: None,
+                positive: row.get::<i64>(1)?.max(0) as u64,
+                negative: row.get::<i64>(2)?.max(0) as u64,
+            };
+            loaded.insert(model, score);
+        }
+
+        if let Ok(mut scores) = self.scores.write() {
+            *scores = loaded;
+        }
+        Ok(())
+    }
+
+    /// Apply a vote change to the global totals (negative deltas retract an earlier vote)
+    pub async fn record_vote(registry: &Connection, model: &str, prompt_key: &str, positive_delta: i64, negative_delta: i64) -> Result<()> {
+        registry.execute(
+            "INSERT INTO model_quality_scores (model, prompt_key, positive, negative, updated_at) VALUES (?, ?, 0, 0, datetime('now'))
+             ON CONFLICT(model, prompt_key) DO NOTHING",
+            params![model, prompt_key],
+        ).await?;
+        registry.execute(
+            "UPDATE model_quality_scores
+             SET positive = MAX(positive + ?, 0), negative = MAX(negative + ?, 0), updated_at = datetime('now')
+             WHERE model = ? AND prompt_key = ?",
+            params![positive_delta, negative_delta, model, prompt_key],
+        ).await?;
+        Ok(())
+    }
+
+    /// Per-prompt breakdown for reporting
+    pub async fn prompt_scores(registry: &Connection) -> Result<Vec<ModelScore>> {
+        let mut rows = registry
+            .prepare("SELECT model, prompt_key, positive, negative FROM model_quality_scores ORDER BY model, prompt_key")
+            .await?
+            .query(params![])
+            .await?;
+        let mut scores = Vec::new();
+        while let Some(row) = rows.next().await? {
+            scores.push(ModelScore {
+                model: row.get(0)?,
+                prompt_key: Some(row.get(1)?),
+                positive: row.get::<i64>(2)?.max(0) as u64,
+                negative: row.get::<i64>(3)?.max(0) as u64,
+            });
+        }
+        Ok(scores)
+    }
+}
+
+/// First candidate that isn't low-performing; if every candidate is, the best scoring one
+pub fn select_model(candidates: &[String], scores: &HashMap<String, ModelScore>) -> String {
+    let score_of = |m: &String| scores.get(m).cloned().unwrap_or_default();
+
+    if let Some(model) = candidates.iter().find(|m| !score_of(m).is_low_performing()) {
+        return model.clone();
+    }
+
+    candidates
+        .iter()
+        .max_by(|a, b| score_of(a).quality().total_cmp(&score_of(b).quality()))
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(model: &str, positive: u64, negative: u64) -> (String, ModelScore) {
+        (model.to_string(), ModelScore { model: model.to_string(), prompt_key: None, positive, negative })
+    }
+
+    #[test]
+    fn test_keeps_default_without_enough_votes() {
+        let candidates = vec!["a".to_string(), "b".to_string()];
+        let scores = HashMap::from([score("a", 0, 10)]);
+        assert_eq!(select_model(&candidates, &scores), "a");
+    }
+
+    #[test]
+    fn test_skips_low_performing_default() {
+        let candidates = vec!["a".to_string(), "b".to_string()];
+        let scores = HashMap::from([score("a", 4, 30), score("b", 2, 1)]);
+        assert_eq!(select_model(&candidates, &scores), "b");
+    }
+
+    #[test]
+    fn test_all_low_performing_picks_best() {
+        let candidates = vec!["a".to_string(), "b".to_string()];
+        let scores = HashMap::from([score("a", 2, 30), score("b", 8, 30)]);
+        assert_eq!(select_model(&candidates, &scores), "b");
+    }
+}
@@ -0,0 +1,154 @@
+// Notebook writing assistant. The model rewrites or condenses a note's text for one of a
+// few fixed actions, and the result comes back as a suggestion with a line diff against
+// the original. Nothing is written to the note here; the client applies a suggestion
+// explicitly, and only if the note has not changed since the suggestion was made.
+
+use serde::{Deserialize, Serialize};
+
+/// Longest note text sent to the model
+pub const MAX_INPUT_CHARS: usize = 20_000;
+/// Above this many lines on either side the diff is reported as a full replacement
+const MAX_DIFF_LINES: usize = 2_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NoteAction {
+    Summarize,
+    Expand,
+    ExtractActionItems,
+    FixGrammar,
+}
+
+impl NoteAction {
+    fn instruction(&self) -> &'static str {
+        match self {
+            NoteAction::Summarize => {
+                "Summarize this trading journal note in a few short bullet points. Keep every number, ticker and price exactly as written."
+            }
+            NoteAction::Expand => {
+                "Expand this trading journal note into clear, complete sentences. Keep the author's voice and facts; do not invent trades, prices or outcomes."
+            }
+            NoteAction::ExtractActionItems => {
+                "List the concrete action items in this trading journal note, one per line starting with \"- \". If there are none, reply with nothing."
+            }
+            NoteAction::FixGrammar => {
+                "Fix spelling, grammar and punctuation in this trading journal note. Change nothing else: keep the wording, line breaks, tickers and numbers."
+            }
+        }
+    }
+}
+
+/// System and user message for the model: the action's instruction, then the note text
+pub fn prompt(action: NoteAction, text: &str) -> (String, String) {
+    let system = format!(
+        "You are a writing assistant inside a trading journal. {} Write in the same language as the note. \
+         Reply with the resulting text only, no preamble and no markdown code fences.",
+        action.instruction()
+    );
+    (system, text.chars().take(MAX_INPUT_CHARS).collect())
+}
+
+/// Strip a code fence the model may wrap its answer in
+pub fn clean_response(response: &str) -> String {
+    let trimmed = response.trim();
+    let unfenced = trimmed
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+        .map(|inner| inner.split_once('\n').map_or(inner, |(_, body)| body))
+        .unwrap_or(trimmed);
+    unfenced.trim().to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffLine {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+/// Line diff of `original` against `suggested` (longest common subsequence)
+pub fn line_diff(original: &str, suggested: &str) -> Vec<DiffLine> {
+    let a: Vec<&str> = original.lines().collect();
+    let b: Vec<&str> = suggested.lines().collect();
+    let line = |op, text: &str| DiffLine { op, text: text.to_string() };
+    if a.len() > MAX_DIFF_LINES || b.len() > MAX_DIFF_LINES {
+        return a.iter().map(|t| line(DiffOp::Delete, t)).chain(b.iter().map(|t| line(DiffOp::Insert, t))).collect();
+    }
+
+    // lcs[i][j]: common lines between a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let (mut i, mut j, mut out) = (0, 0, Vec::new());
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            out.push(line(DiffOp::Equal, a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            out.push(line(DiffOp::Delete, a[i]));
+            i += 1;
+        } else {
+            out.push(line(DiffOp::Insert, b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+/// What the assistant proposes for a note; applying it is a separate, explicit request
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteSuggestion {
+    pub note_id: String,
+    pub action: NoteAction,
+    /// Note version the suggestion was made against; apply is refused once it changes
+    pub base_updated_at: String,
+    pub original: String,
+    pub suggestion: String,
+    pub diff: Vec<DiffLine>,
+    /// Set for extract-action-items
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action_items: Option<Vec<String>>,
+}
+
+/// "- item" lines of an extract-action-items answer
+pub fn parse_action_items(suggestion: &str) -> Vec<String> {
+    suggestion
+        .lines()
+        .map(|l| l.trim().trim_start_matches(['-', '*', '•']).trim())
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff_marks_changed_lines() {
+        let diff = line_diff("Bought AAPL at 180\nit was a good trade\nStop at 175", "Bought AAPL at 180\nIt was a good trade.\nStop at 175");
+        let ops: Vec<DiffOp> = diff.iter().map(|d| d.op).collect();
+        assert_eq!(ops, vec![DiffOp::Equal, DiffOp::Delete, DiffOp::Insert, DiffOp::Equal]);
+        assert_eq!(diff[2].text, "It was a good trade.");
+        assert!(line_diff("same", "same").iter().all(|d| d.op == DiffOp::Equal));
+    }
+
+    #[test]
+    fn test_response_cleanup_and_action_items() {
+        assert_eq!(clean_response("```text\n- Review stops\n```"), "- Review stops");
+        assert_eq!(parse_action_items("- Review stops\n\n* Journal daily"), vec!["Review stops", "Journal daily"]);
+        assert_eq!(serde_json::from_str::<NoteAction>("\"extract-action-items\"").unwrap(), NoteAction::ExtractActionItems);
+    }
+}
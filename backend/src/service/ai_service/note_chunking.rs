@@ -0,0 +1,251 @@
+use crate::service::ai_service::content_extraction::{extract_plain_text, extract_plain_text_from_json, ImageCaptions};
+use crate::service::ai_service::upstash_vector_client::VectorMatch;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Target chunk size in bytes; keeps each chunk well inside the embedding model's context
+pub const CHUNK_TARGET_CHARS: usize = 1500;
+/// Text carried from the end of one chunk into the next within the same section
+pub const CHUNK_OVERLAP_CHARS: usize = 200;
+/// Upper bound on chunks per note, also used to clean up stale chunk vectors
+pub const MAX_CHUNKS_PER_NOTE: usize = 64;
+/// How many more matches to fetch than requested so merging chunk hits still fills `top_k`
+pub const CHUNK_QUERY_OVERFETCH: usize = 3;
+/// Upstash Vector's maximum `topK`
+const MAX_QUERY_TOP_K: usize = 1000;
+
+/// A top-level block of note content
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextBlock {
+    pub text: String,
+    pub is_heading: bool,
+}
+
+/// One embeddable slice of a note
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteChunk {
+    pub index: usize,
+    /// Nearest heading above the chunk, if any
+    pub heading: Option<String>,
+    pub text: String,
+}
+
+/// Vector id for a chunk. Notes that fit in one chunk keep the plain entity id so existing
+/// vectors and lookups are unaffected.
+pub fn chunk_vector_id(base_id: &str, index: usize, chunk_count: usize) -> String {
+    if chunk_count <= 1 {
+        base_id.to_string()
+    } else {
+        format!("{}_chunk_{}", base_id, index)
+    }
+}
+
+/// Every id a note's vectors could have been stored under
+pub fn all_chunk_vector_ids(base_id: &str) -> Vec<String> {
+    std::iter::once(base_id.to_string())
+        .chain((0..MAX_CHUNKS_PER_NOTE).map(|i| chunk_vector_id(base_id, i, MAX_CHUNKS_PER_NOTE)))
+        .collect()
+}
+
+/// Split notebook content (ProseMirror doc, BlockNote block list or a string) into top-level blocks
+pub fn blocks_from_content(content: &Value, captions: &ImageCaptions) -> Vec<TextBlock> {
+    let nodes: &[Value] = match content {
+        Value::Array(items) => items,
+        Value::Object(map) => match map.get("content") {
+            Some(Value::Array(items)) => items,
+            _ => std::slice::from_ref(content),
+        },
+        Value::String(s) => return blocks_from_text(&extract_plain_text(s, captions)),
+        _ => return Vec::new(),
+    };
+
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let text = extract_plain_text_from_json(node, captions);
+            if text.is_empty() {
+                return None;
+            }
+            let is_heading = node.get("type").and_then(|t| t.as_str()) == Some("heading");
+            Some(TextBlock { text, is_heading })
+        })
+        .collect()
+}
+
+/// Plain text blocks are lines; markdown-style `#` lines count as headings
+fn blocks_from_text(text: &str) -> Vec<TextBlock> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.strip_prefix('#') {
+            Some(rest) => TextBlock { text: rest.trim_start_matches('#').trim().to_string(), is_heading: true },
+            None => TextBlock { text: line.to_string(), is_heading: false },
+        })
+        .filter(|block| !block.text.is_empty())
+        .collect()
+}
+
+/// Group blocks into chunks of roughly `target` bytes. A heading always starts a new chunk;
+/// within a section consecutive chunks share `overlap` bytes of trailing text.
+pub fn chunk_blocks(blocks: &[TextBlock], target: usize, overlap: usize) -> Vec<NoteChunk> {
+    let mut chunks: Vec<NoteChunk> = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut buf = String::new();
+    // Whether `buf` holds anything beyond the overlap carried from the previous chunk
+    let mut fresh = false;
+
+    fn emit(buf: &str, heading: &Option<String>, chunks: &mut Vec<NoteChunk>) {
+        chunks.push(NoteChunk { index: chunks.len(), heading: heading.clone(), text: buf.to_string() });
+    }
+
+    for block in blocks {
+        if block.is_heading {
+            if fresh {
+                emit(&buf, &heading, &mut chunks);
+            }
+            heading = Some(block.text.clone());
+            buf = block.text.clone();
+            fresh = true;
+            continue;
+        }
+
+        for piece in split_long(&block.text, target) {
+            if fresh && buf.len() + piece.len() + 1 > target {
+                emit(&buf, &heading, &mut chunks);
+                buf = tail(&buf, overlap).to_string();
+            }
+            if !buf.is_empty() {
+                buf.push('\n');
+            }
+            buf.push_str(piece);
+            fresh = true;
+        }
+    }
+    if fresh {
+        emit(&buf, &heading, &mut chunks);
+    }
+    chunks
+}
+
+/// Split a paragraph longer than `max` bytes at word boundaries
+fn split_long(text: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = text.trim();
+    while rest.len() > max {
+        let mut cut = max;
+        while !rest.is_char_boundary(cut) {
+            cut -= 1;
+        }
+        // Prefer the last space before the limit; fall back to a hard cut for very long words
+        let cut = rest[..cut].rfind(char::is_whitespace).filter(|&i| i > 0).unwrap_or(cut);
+        pieces.push(rest[..cut].trim_end());
+        rest = rest[cut..].trim_start();
+    }
+    if !rest.is_empty() {
+        pieces.push(rest);
+    }
+    pieces
+}
+
+/// Last `len` bytes of `text`, starting at a word boundary
+fn tail(text: &str, len: usize) -> &str {
+    if len == 0 {
+        return "";
+    }
+    if text.len() <= len {
+        return text;
+    }
+    let mut start = text.len() - len;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    match text[start..].find(char::is_whitespace) {
+        Some(i) => text[start + i..].trim_start(),
+        None => &text[start..],
+    }
+}
+
+/// `top_k` to request from the vector store so there are enough distinct entities after merging
+pub fn chunk_query_top_k(top_k: usize) -> usize {
+    top_k.saturating_mul(CHUNK_QUERY_OVERFETCH).min(MAX_QUERY_TOP_K).max(top_k)
+}
+
+/// Collapse chunk matches of the same note into one match (its best-scoring chunk), keeping
+/// the order by score. Returns each match with the number of chunks that hit.
+pub fn merge_chunk_matches(matches: Vec<VectorMatch>, top_k: usize) -> Vec<(VectorMatch, usize)> {
+    let mut merged: Vec<(VectorMatch, usize)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+
+    for m in matches {
+        let key = match &m.metadata {
+            Some(meta) => format!("{:?}:{}", meta.data_type, meta.entity_id),
+            None => m.id.clone(),
+        };
+        match positions.get(&key) {
+            Some(&pos) => {
+                let entry = &mut merged[pos];
+                entry.1 += 1;
+                if m.score > entry.0.score {
+                    entry.0 = m;
+                }
+            }
+            None => {
+                positions.insert(key, merged.len());
+                merged.push((m, 1));
+            }
+        }
+    }
+
+    merged.sort_by(|a, b| b.0.score.partial_cmp(&a.0.score).unwrap_or(std::cmp::Ordering::Equal));
+    merged.truncate(top_k);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraph(text: &str) -> TextBlock {
+        TextBlock { text: text.to_string(), is_heading: false }
+    }
+
+    #[test]
+    fn test_chunks_split_on_headings_with_overlap() {
+        let blocks = vec![
+            TextBlock { text: "Setup".to_string(), is_heading: true },
+            paragraph("alpha beta gamma delta"),
+            paragraph("epsilon zeta eta theta"),
+            TextBlock { text: "Review".to_string(), is_heading: true },
+            paragraph("iota kappa"),
+        ];
+        let chunks = chunk_blocks(&blocks, 40, 10);
+
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[0].text, "Setup\nalpha beta gamma delta");
+        assert_eq!(chunks[1].heading.as_deref(), Some("Setup"));
+        // The second chunk starts with the tail of the first
+        assert_eq!(chunks[1].text, "delta\nepsilon zeta eta theta");
+        assert_eq!(chunks[2].text, "Review\niota kappa");
+        assert_eq!(chunks.iter().map(|c| c.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_blocks_from_prosemirror_and_markdown() {
+        let doc = serde_json::json!({"type":"doc","content":[
+            {"type":"heading","content":[{"type":"text","text":"Plan"}]},
+            {"type":"paragraph","content":[{"type":"text","text":"Buy the dip"}]},
+            {"type":"paragraph","content":[]}
+        ]});
+        assert_eq!(
+            blocks_from_content(&doc, &ImageCaptions::new()),
+            vec![TextBlock { text: "Plan".to_string(), is_heading: true }, paragraph("Buy the dip")]
+        );
+
+        let text = Value::String("## Notes\nsize down".to_string());
+        assert_eq!(
+            blocks_from_content(&text, &ImageCaptions::new()),
+            vec![TextBlock { text: "Notes".to_string(), is_heading: true }, paragraph("size down")]
+        );
+        assert_eq!(split_long("one two three", 8), vec!["one two", "three"]);
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use crate::service::ai_service::note_assistant::{self, NoteAction};
 use crate::service::ai_service::openrouter_client::{OpenRouterClient, ChatMessage, MessageRole};
 
 /// AI metadata extracted from trade note analysis
@@ -66,6 +67,21 @@ impl AINotesService {
         Ok(metadata)
     }
 
+    /// Run a notebook writing-assistant action over a note's text and return the model's text
+    pub async fn assist(&self, action: NoteAction, text: &str) -> Result<String> {
+        let (system, user) = note_assistant::prompt(action, text);
+        let messages = vec![
+            ChatMessage { role: MessageRole::System, content: system },
+            ChatMessage { role: MessageRole::User, content: user },
+        ];
+        let response = self.openrouter_client.generate_chat(messages).await?;
+        let cleaned = note_assistant::clean_response(&response);
+        if cleaned.is_empty() && action != NoteAction::ExtractActionItems {
+            return Err(anyhow::anyhow!("AI service returned empty response"));
+        }
+        Ok(cleaned)
+    }
+
     /// Build analysis prompt for AI
     fn build_analysis_prompt(&self, note_content: &str, trade_context: Option<&str>) -> String {
         let mut prompt = String::from(
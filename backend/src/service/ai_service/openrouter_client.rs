@@ -1,10 +1,15 @@
 #![allow(dead_code)]
 
+use crate::service::ai_service::mock_model_client::{mock_ai_enabled, MockModelClient, MOCK_MODEL};
+use crate::service::ai_service::context_budget::estimate_tokens;
+use crate::service::ai_service::model_selector::ModelSelector;
+use crate::service::cost_ledger::{self, Provider};
 use crate::turso::vector_config::OpenRouterConfig;
 use anyhow::{Context, Result};
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
@@ -77,6 +82,9 @@ pub struct ErrorDetails {
 pub struct OpenRouterClient {
     config: OpenRouterConfig,
     client: Client,
+    model_selector: Arc<ModelSelector>,
+    /// Set when `AI_PROVIDER=mock`; every completion is answered locally
+    mock: Option<MockModelClient>,
 }
 
 impl OpenRouterClient {
@@ -86,11 +94,23 @@ impl OpenRouterClient {
             .build()
             .context("Failed to create HTTP client")?;
 
-        Ok(Self { config, client })
+        let mock = mock_ai_enabled().then(MockModelClient::new);
+        let model_selector = Arc::new(match mock {
+            Some(_) => {
+                log::warn!("AI_PROVIDER=mock: chat completions are canned responses, not {}", config.model);
+                ModelSelector::new(MOCK_MODEL.to_string(), Vec::new())
+            }
+            None => ModelSelector::from_env(config.model.clone()),
+        });
+
+        Ok(Self { config, client, model_selector, mock })
     }
 
     /// Generate a non-streaming chat completion
     pub async fn generate_chat(&self, messages: Vec<ChatMessage>) -> Result<String> {
+        if let Some(mock) = &self.mock {
+            return mock.generate_chat(messages).await;
+        }
         let openrouter_messages: Vec<Message> = messages
             .into_iter()
             .map(|msg| Message {
@@ -100,7 +120,7 @@ impl OpenRouterClient {
             .collect();
 
         let request = ChatRequest {
-            model: self.config.model.clone(),
+            model: self.model_selector.select(),
             messages: openrouter_messages,
             stream: false,
             temperature: self.config.temperature,
@@ -111,6 +131,14 @@ impl OpenRouterClient {
         loop {
             match self.make_chat_request(&request).await {
                 Ok(response) => {
+                    let usage = response.usage.as_ref();
+                    let input_tokens = usage.and_then(|u| u.prompt_tokens).map(u64::from)
+                        .unwrap_or_else(|| request.messages.iter().map(|m| estimate_tokens(&m.content) as u64).sum());
+                    let output_tokens = usage.and_then(|u| u.completion_tokens).map(u64::from)
+                        .unwrap_or_else(|| response.choices.first().map_or(0, |c| estimate_tokens(&c.message.content) as u64));
+                    let cost = cost_ledger::rates().openrouter_cost(&request.model, input_tokens, output_tokens);
+                    cost_ledger::record(Provider::OpenRouter, &request.model, input_tokens, output_tokens, cost);
+
                     if let Some(choice) = response.choices.first() {
                         return Ok(choice.message.content.clone());
                     }
@@ -140,6 +168,9 @@ impl OpenRouterClient {
         &self,
         messages: Vec<ChatMessage>,
     ) -> Result<mpsc::Receiver<String>> {
+        if let Some(mock) = &self.mock {
+            return mock.generate_chat_stream(messages).await;
+        }
         let openrouter_messages: Vec<Message> = messages
             .into_iter()
             .map(|msg| Message {
@@ -149,7 +180,7 @@ impl OpenRouterClient {
             .collect();
 
         let request = ChatRequest {
-            model: self.config.model.clone(),
+            model: self.model_selector.select(),
             messages: openrouter_messages,
             stream: true,
             temperature: self.config.temperature,
@@ -163,11 +194,18 @@ impl OpenRouterClient {
         let config = self.config.clone();
         let url = self.config.get_chat_url();
         let request_json = serde_json::to_value(&request)?;
+        // Streams carry no usage block; cost is estimated from the text, charged to the caller's user
+        let cost_user = cost_ledger::current_user();
+        let input_tokens: u64 = request.messages.iter().map(|m| estimate_tokens(&m.content) as u64).sum();
+        let model = request.model.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = Self::handle_streaming_response(client, url, config, request_json, tx).await {
+            let mut output_tokens = 0u64;
+            if let Err(e) = Self::handle_streaming_response(client, url, config, request_json, tx, &mut output_tokens).await {
                 log::error!("Streaming error: {}", e);
             }
+            let cost = cost_ledger::rates().openrouter_cost(&model, input_tokens, output_tokens);
+            cost_ledger::record_for(cost_user, Provider::OpenRouter, &model, input_tokens, output_tokens, cost);
         });
 
         Ok(rx)
@@ -180,6 +218,7 @@ impl OpenRouterClient {
         config: OpenRouterConfig,
         request: serde_json::Value,
         tx: mpsc::Sender<String>,
+        output_tokens: &mut u64,
     ) -> Result<()> {
         let mut headers = reqwest::header::HeaderMap::new();
         headers.insert("Content-Type", "application/json".parse()?);
@@ -300,9 +339,11 @@ impl OpenRouterClient {
                                     && let Some(content) = &delta.content
                                 {
                                     log::debug!("Sending content: {}", content);
-                                    if let Err(e) = tx.send(content.clone()).await {
-                                        log::error!("Failed to send content through channel: {}", e);
-                                        break;
+                                    *output_tokens += estimate_tokens(content) as u64;
+                                    if tx.send(content.clone()).await.is_err() {
+                                        // Receiver dropped: the client went away, stop pulling tokens
+                                        log::info!("Stream receiver closed, cancelling OpenRouter stream");
+                                        return Ok(());
                                     }
                                 }
                                 
@@ -430,10 +471,24 @@ impl OpenRouterClient {
         Ok(())
     }
 
-    /// Get the model being used
+    /// Get the configured default model
     pub fn get_model(&self) -> &str {
         &self.config.model
     }
+
+    /// Model the next request will use, after feedback-based selection
+    pub fn active_model(&self) -> String {
+        self.model_selector.select()
+    }
+
+    /// Completion tokens requested per call
+    pub fn max_tokens(&self) -> u32 {
+        self.config.max_tokens
+    }
+
+    pub fn model_selector(&self) -> Arc<ModelSelector> {
+        Arc::clone(&self.model_selector)
+    }
 }
 
 /// Chat message structure
@@ -468,6 +523,7 @@ mod tests {
     async fn test_openrouter_client_creation() {
         let config = OpenRouterConfig {
             api_key: "test_key".to_string(),
+            api_url: "https://openrouter.ai/api/v1".to_string(),
             model: "meta-llama/llama-3.1-8b-instruct:free".to_string(),
             site_url: None,
             site_name: None,
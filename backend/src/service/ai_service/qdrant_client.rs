@@ -7,7 +7,8 @@ use qdrant_client::{
         vectors_config::Config, CreateCollection, Distance, PointStruct, 
         VectorParams, VectorsConfig, Filter, Condition,
         FieldCondition, Match, Value, PointId, ScrollPoints,
-        PointsSelector, PointsIdsList,
+        PointsSelector, PointsIdsList, CountPointsBuilder,
+        CreateFieldIndexCollectionBuilder, FieldType,
     },
 };
 use serde::{Deserialize, Serialize};
@@ -31,11 +32,37 @@ pub struct Document {
     pub metadata: DocumentMetadata,
 }
 
+/// Keyword search hit with its stored payload
+#[derive(Debug, Clone, Serialize)]
+pub struct KeywordSearchHit {
+    pub entity_id: String,
+    pub data_type: String,
+    pub content: String,
+    pub timestamp: Option<String>,
+}
+
 pub struct QdrantDocumentClient {
     client: Qdrant,
     config: QdrantConfig,
 }
 
+/// Payload fields indexed on every tenant collection
+const KEYWORD_INDEX_FIELDS: &[&str] = &["user_id", "data_type", "entity_id"];
+
+/// Restrict a query to one tenant's points. Collections are already per user; this keeps a
+/// misrouted query from ever returning another tenant's documents.
+fn user_condition(user_id: &str) -> Condition {
+    Condition {
+        condition_one_of: Some(qdrant_client::qdrant::condition::ConditionOneOf::Field(FieldCondition {
+            key: "user_id".to_string(),
+            r#match: Some(Match {
+                match_value: Some(qdrant_client::qdrant::r#match::MatchValue::Keyword(user_id.to_string())),
+            }),
+            ..Default::default()
+        })),
+    }
+}
+
 impl QdrantDocumentClient {
     pub async fn new(config: QdrantConfig) -> Result<Self> {
         let client = Qdrant::from_url(&config.url)
@@ -46,15 +73,14 @@ impl QdrantDocumentClient {
         Ok(Self { client, config })
     }
 
+    pub async fn collection_exists(&self, user_id: &str) -> Result<bool> {
+        Ok(self.client.collection_exists(self.config.get_collection_name(user_id)).await?)
+    }
+
     pub async fn ensure_collection(&self, user_id: &str) -> Result<()> {
         let collection_name = self.config.get_collection_name(user_id);
-        
-        // Check if collection exists
-        let collections = self.client.list_collections().await?;
-        let exists = collections.collections.iter()
-            .any(|c| c.name == collection_name);
 
-        if !exists {
+        if !self.client.collection_exists(collection_name.clone()).await? {
             log::info!("Creating Qdrant collection: {}", collection_name);
             
             self.client.create_collection(CreateCollection {
@@ -68,6 +94,18 @@ impl QdrantDocumentClient {
                 }),
                 ..Default::default()
             }).await?;
+
+            // Index the filter fields and the searchable content
+            for field in KEYWORD_INDEX_FIELDS {
+                self.client
+                    .create_field_index(CreateFieldIndexCollectionBuilder::new(collection_name.clone(), *field, FieldType::Keyword))
+                    .await
+                    .with_context(|| format!("Failed to index {} on {}", field, collection_name))?;
+            }
+            self.client
+                .create_field_index(CreateFieldIndexCollectionBuilder::new(collection_name.clone(), "content", FieldType::Text))
+                .await
+                .with_context(|| format!("Failed to index content on {}", collection_name))?;
             
             log::info!("Qdrant collection created: {}", collection_name);
         }
@@ -151,6 +189,7 @@ impl QdrantDocumentClient {
                         )
                     ),
                 },
+                user_condition(user_id),
             ],
             ..Default::default()
         };
@@ -183,6 +222,62 @@ impl QdrantDocumentClient {
         Ok(ids)
     }
 
+    /// Keyword search that returns payload content, optionally limited to one data type (e.g. "tradenote")
+    pub async fn search_content(&self, user_id: &str, query: &str, data_type: Option<&str>, limit: usize) -> Result<Vec<KeywordSearchHit>> {
+        let collection_name = self.config.get_collection_name(user_id);
+
+        let mut must = vec![user_condition(user_id), Condition {
+            condition_one_of: Some(qdrant_client::qdrant::condition::ConditionOneOf::Field(FieldCondition {
+                key: "content".to_string(),
+                r#match: Some(Match {
+                    match_value: Some(qdrant_client::qdrant::r#match::MatchValue::Text(query.to_string())),
+                }),
+                ..Default::default()
+            })),
+        }];
+        if let Some(data_type) = data_type {
+            must.push(Condition {
+                condition_one_of: Some(qdrant_client::qdrant::condition::ConditionOneOf::Field(FieldCondition {
+                    key: "data_type".to_string(),
+                    r#match: Some(Match {
+                        match_value: Some(qdrant_client::qdrant::r#match::MatchValue::Keyword(data_type.to_string())),
+                    }),
+                    ..Default::default()
+                })),
+            });
+        }
+
+        let scroll_request = ScrollPoints {
+            collection_name,
+            filter: Some(Filter { must, ..Default::default() }),
+            limit: Some(limit as u32),
+            with_payload: Some(true.into()),
+            ..Default::default()
+        };
+
+        let search_result = self.client.scroll(scroll_request).await?;
+
+        let payload_str = |payload: &HashMap<String, Value>, key: &str| -> Option<String> {
+            match payload.get(key).and_then(|v| v.kind.as_ref()) {
+                Some(qdrant_client::qdrant::value::Kind::StringValue(s)) => Some(s.clone()),
+                _ => None,
+            }
+        };
+
+        let hits = search_result.result.into_iter()
+            .filter_map(|point| {
+                Some(KeywordSearchHit {
+                    entity_id: payload_str(&point.payload, "entity_id")?,
+                    data_type: payload_str(&point.payload, "data_type").unwrap_or_default(),
+                    content: payload_str(&point.payload, "content")?,
+                    timestamp: payload_str(&point.payload, "timestamp"),
+                })
+            })
+            .collect();
+
+        Ok(hits)
+    }
+
     pub async fn delete_documents(&self, user_id: &str, document_ids: &[String]) -> Result<()> {
         if document_ids.is_empty() {
             return Ok(());
@@ -211,6 +306,7 @@ impl QdrantDocumentClient {
         }
 
         let filter = Filter {
+            must: vec![user_condition(user_id)],
             should: conditions,
             ..Default::default()
         };
@@ -252,17 +348,28 @@ impl QdrantDocumentClient {
     }
 
     /// Delete entire user collection from Qdrant
+    /// Number of points stored for a user (0 when the collection doesn't exist yet)
+    pub async fn count_user_points(&self, user_id: &str) -> Result<u64> {
+        let collection_name = self.config.get_collection_name(user_id);
+
+        if !self.client.collection_exists(collection_name.clone()).await? {
+            return Ok(0);
+        }
+
+        let response = self.client
+            .count(CountPointsBuilder::new(collection_name).exact(true))
+            .await
+            .context("Failed to count Qdrant points")?;
+
+        Ok(response.result.map(|r| r.count).unwrap_or(0))
+    }
+
     pub async fn delete_user_collection(&self, user_id: &str) -> Result<()> {
         let collection_name = self.config.get_collection_name(user_id);
         
         log::info!("Deleting Qdrant collection: {}", collection_name);
 
-        // Check if collection exists
-        let collections = self.client.list_collections().await?;
-        let exists = collections.collections.iter()
-            .any(|c| c.name == collection_name);
-
-        if !exists {
+        if !self.client.collection_exists(collection_name.clone()).await? {
             log::info!("Collection {} does not exist, skipping deletion", collection_name);
             return Ok(());
         }
@@ -0,0 +1,288 @@
+// Whether AI coaching pays off. Each recommendation of a stored insight is tracked with a
+// baseline of the user's metrics over the month before it. Once a month has passed, the
+// same metrics over the month after it are compared with the baseline and the change is
+// scored from -100 to 100. The user can mark recommendations acted on or dismissed, and the
+// report sets the scores of acted-on advice against the rest, which is the actual test of
+// whether following the advice helped.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ai::insights::Insight;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::core_metrics::calculate_filtered_core_metrics;
+use crate::service::analytics_engine::query::TradeFilter;
+
+/// Length of the baseline and follow-up windows
+pub const OUTCOME_WINDOW_DAYS: i64 = 30;
+/// Fewer trades in either window leave the recommendation unscored
+pub const MIN_TRADES: u32 = 5;
+/// Profit factors above this (few or no losses) are capped so they don't swamp the score
+const PROFIT_FACTOR_CAP: f64 = 5.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecommendationStatus {
+    Pending,
+    Acted,
+    Dismissed,
+}
+
+impl RecommendationStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Acted => "acted",
+            Self::Dismissed => "dismissed",
+        }
+    }
+
+    fn from_db(value: &str) -> Self {
+        match value {
+            "acted" => Self::Acted,
+            "dismissed" => Self::Dismissed,
+            _ => Self::Pending,
+        }
+    }
+}
+
+/// Metrics compared before and after a recommendation
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutcomeMetrics {
+    pub trades: u32,
+    pub win_rate: f64,
+    pub net_pnl: f64,
+    /// Net P&L per trade
+    pub expectancy: f64,
+    pub profit_factor: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecommendationOutcome {
+    pub id: String,
+    pub insight_id: String,
+    pub recommendation: String,
+    pub status: RecommendationStatus,
+    pub acted_at: Option<String>,
+    pub baseline: Option<OutcomeMetrics>,
+    pub follow_up: Option<OutcomeMetrics>,
+    /// -100 (results got worse) to 100 (better); None until evaluated or with too few trades
+    pub score: Option<f64>,
+    pub evaluated_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OutcomeSummary {
+    pub tracked: u32,
+    pub evaluated: u32,
+    pub acted: u32,
+    pub avg_score_acted: Option<f64>,
+    pub avg_score_not_acted: Option<f64>,
+    /// Percent of scored recommendations followed by better results
+    pub improved_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutcomeReport {
+    pub summary: OutcomeSummary,
+    /// Newest first
+    pub outcomes: Vec<RecommendationOutcome>,
+}
+
+/// Change from `before` to `after`: half expectancy, a quarter each win rate (10 points is
+/// the full scale) and profit factor, each part clamped to ±1
+fn outcome_score(before: &OutcomeMetrics, after: &OutcomeMetrics) -> Option<f64> {
+    if before.trades < MIN_TRADES || after.trades < MIN_TRADES {
+        return None;
+    }
+    let scale = before.expectancy.abs().max(after.expectancy.abs()).max(1.0);
+    let expectancy = ((after.expectancy - before.expectancy) / scale).clamp(-1.0, 1.0);
+    let win_rate = ((after.win_rate - before.win_rate) / 10.0).clamp(-1.0, 1.0);
+    let capped = |pf: f64| if pf.is_finite() { pf.min(PROFIT_FACTOR_CAP) } else { PROFIT_FACTOR_CAP };
+    let profit_factor = ((capped(after.profit_factor) - capped(before.profit_factor)) / capped(before.profit_factor).max(0.5)).clamp(-1.0, 1.0);
+    Some(100.0 * (0.5 * expectancy + 0.25 * win_rate + 0.25 * profit_factor))
+}
+
+fn summarize(outcomes: &[RecommendationOutcome]) -> OutcomeSummary {
+    let average = |scores: Vec<f64>| (!scores.is_empty()).then(|| scores.iter().sum::<f64>() / scores.len() as f64);
+    let scored: Vec<(RecommendationStatus, f64)> = outcomes.iter().filter_map(|o| Some((o.status, o.score?))).collect();
+    let improved = scored.iter().filter(|(_, score)| *score > 0.0).count();
+    OutcomeSummary {
+        tracked: outcomes.len() as u32,
+        evaluated: outcomes.iter().filter(|o| o.evaluated_at.is_some()).count() as u32,
+        acted: outcomes.iter().filter(|o| o.status == RecommendationStatus::Acted).count() as u32,
+        avg_score_acted: average(scored.iter().filter(|(s, _)| *s == RecommendationStatus::Acted).map(|(_, v)| *v).collect()),
+        avg_score_not_acted: average(scored.iter().filter(|(s, _)| *s != RecommendationStatus::Acted).map(|(_, v)| *v).collect()),
+        improved_pct: (!scored.is_empty()).then(|| improved as f64 / scored.len() as f64 * 100.0),
+    }
+}
+
+async fn window_metrics(conn: &Connection, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<OutcomeMetrics> {
+    let range = TimeRange::Custom { start_date: Some(start), end_date: Some(end) };
+    let core = calculate_filtered_core_metrics(conn, &range, &TradeFilter::default()).await?;
+    Ok(OutcomeMetrics {
+        trades: core.total_trades,
+        win_rate: core.win_rate,
+        net_pnl: core.net_profit_loss,
+        expectancy: if core.total_trades > 0 { core.net_profit_loss / core.total_trades as f64 } else { 0.0 },
+        profit_factor: core.profit_factor,
+    })
+}
+
+/// Start tracking an insight's recommendations, with the month before it as the baseline
+pub async fn track(conn: &Connection, insight: &Insight) -> Result<()> {
+    if insight.recommendations.is_empty() {
+        return Ok(());
+    }
+    let created_at = insight.generated_at;
+    let baseline = window_metrics(conn, created_at - Duration::days(OUTCOME_WINDOW_DAYS), created_at).await?;
+    let baseline = serde_json::to_string(&baseline)?;
+    for (index, recommendation) in insight.recommendations.iter().enumerate() {
+        conn.execute(
+            "INSERT INTO recommendation_outcomes (id, insight_id, recommendation_index, recommendation, status, baseline, created_at) \
+             VALUES (?, ?, ?, ?, 'pending', ?, ?) ON CONFLICT(insight_id, recommendation_index) DO NOTHING",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                insight.id.clone(),
+                index as i64,
+                recommendation.clone(),
+                baseline.clone(),
+                created_at.to_rfc3339()
+            ],
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Record whether the user acted on a recommendation; false when there is no such recommendation
+pub async fn set_status(conn: &Connection, insight_id: &str, index: u32, status: RecommendationStatus) -> Result<bool> {
+    let acted_at = (status == RecommendationStatus::Acted).then(|| Utc::now().to_rfc3339());
+    let updated = conn
+        .execute(
+            "UPDATE recommendation_outcomes SET status = ?, acted_at = ?, updated_at = ? WHERE insight_id = ? AND recommendation_index = ?",
+            params![status.as_str(), acted_at, Utc::now().to_rfc3339(), insight_id, index as i64],
+        )
+        .await?;
+    Ok(updated > 0)
+}
+
+/// Score every recommendation whose follow-up window has closed
+async fn evaluate_due(conn: &Connection) -> Result<u32> {
+    let cutoff = (Utc::now() - Duration::days(OUTCOME_WINDOW_DAYS)).to_rfc3339();
+    let mut rows = conn
+        .prepare("SELECT id, baseline, created_at FROM recommendation_outcomes WHERE evaluated_at IS NULL AND created_at <= ?")
+        .await?
+        .query(params![cutoff])
+        .await?;
+    let mut due = Vec::new();
+    while let Some(row) = rows.next().await? {
+        due.push((row.get::<String>(0)?, row.get::<Option<String>>(1)?, row.get::<String>(2)?));
+    }
+
+    let mut evaluated = 0;
+    for (id, baseline, created_at) in due {
+        let Ok(start) = DateTime::parse_from_rfc3339(&created_at).map(|d| d.with_timezone(&Utc)) else {
+            continue;
+        };
+        let follow_up = window_metrics(conn, start, start + Duration::days(OUTCOME_WINDOW_DAYS)).await?;
+        let baseline: Option<OutcomeMetrics> = baseline.and_then(|b| serde_json::from_str(&b).ok());
+        let score = baseline.as_ref().and_then(|b| outcome_score(b, &follow_up));
+        let now = Utc::now().to_rfc3339();
+        conn.execute(
+            "UPDATE recommendation_outcomes SET follow_up = ?, score = ?, evaluated_at = ?, updated_at = ? WHERE id = ?",
+            params![serde_json::to_string(&follow_up)?, score, now.clone(), now, id],
+        )
+        .await?;
+        evaluated += 1;
+    }
+    Ok(evaluated)
+}
+
+/// Outcomes of every tracked recommendation, scoring any that became due first
+pub async fn report(conn: &Connection) -> Result<OutcomeReport> {
+    evaluate_due(conn).await?;
+    let mut rows = conn
+        .prepare(
+            "SELECT id, insight_id, recommendation, status, acted_at, baseline, follow_up, score, evaluated_at, created_at \
+             FROM recommendation_outcomes ORDER BY created_at DESC, recommendation_index",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    let parse = |json: Option<String>| json.and_then(|j| serde_json::from_str::<OutcomeMetrics>(&j).ok());
+    let mut outcomes = Vec::new();
+    while let Some(row) = rows.next().await? {
+        outcomes.push(RecommendationOutcome {
+            id: row.get(0)?,
+            insight_id: row.get(1)?,
+            recommendation: row.get(2)?,
+            status: RecommendationStatus::from_db(&row.get::<String>(3)?),
+            acted_at: row.get(4)?,
+            baseline: parse(row.get(5)?),
+            follow_up: parse(row.get(6)?),
+            score: row.get(7)?,
+            evaluated_at: row.get(8)?,
+            created_at: row.get(9)?,
+        });
+    }
+    Ok(OutcomeReport { summary: summarize(&outcomes), outcomes })
+}
+
+/// Drop the tracking rows of a deleted insight
+pub async fn forget_insight(conn: &Connection, insight_id: &str) -> Result<()> {
+    conn.execute("DELETE FROM recommendation_outcomes WHERE insight_id = ?", params![insight_id]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(trades: u32, win_rate: f64, expectancy: f64, profit_factor: f64) -> OutcomeMetrics {
+        OutcomeMetrics { trades, win_rate, net_pnl: expectancy * trades as f64, expectancy, profit_factor }
+    }
+
+    #[test]
+    fn test_outcome_score() {
+        let before = metrics(20, 45.0, -10.0, 0.8);
+        let better = metrics(18, 55.0, 20.0, 1.6);
+        let score = outcome_score(&before, &better).unwrap();
+        assert!((score - 100.0).abs() < 1e-9);
+        assert!(outcome_score(&better, &before).unwrap() < 0.0);
+        assert_eq!(outcome_score(&before, &before), Some(0.0));
+        assert!(outcome_score(&before, &metrics(3, 100.0, 50.0, f64::INFINITY)).is_none());
+        // No losses doesn't produce an unbounded score
+        let flawless = outcome_score(&before, &metrics(10, 100.0, 20.0, f64::INFINITY)).unwrap();
+        assert!(flawless <= 100.0);
+    }
+
+    #[test]
+    fn test_summary_splits_acted_from_the_rest() {
+        let outcome = |status, score: Option<f64>| RecommendationOutcome {
+            id: String::new(),
+            insight_id: String::new(),
+            recommendation: String::new(),
+            status,
+            acted_at: None,
+            baseline: None,
+            follow_up: None,
+            score,
+            evaluated_at: score.map(|_| String::new()),
+            created_at: String::new(),
+        };
+        let summary = summarize(&[
+            outcome(RecommendationStatus::Acted, Some(40.0)),
+            outcome(RecommendationStatus::Acted, Some(-10.0)),
+            outcome(RecommendationStatus::Dismissed, Some(-20.0)),
+            outcome(RecommendationStatus::Pending, None),
+        ]);
+        assert_eq!((summary.tracked, summary.evaluated, summary.acted), (4, 3, 2));
+        assert_eq!(summary.avg_score_acted, Some(15.0));
+        assert_eq!(summary.avg_score_not_acted, Some(-20.0));
+        assert!((summary.improved_pct.unwrap() - 100.0 / 3.0).abs() < 1e-9);
+    }
+}
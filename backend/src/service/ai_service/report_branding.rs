@@ -0,0 +1,114 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+const MAX_DISPLAY_NAME_CHARS: usize = 80;
+const MAX_LOGO_URL_CHARS: usize = 500;
+/// Accent of the unbranded report page
+pub const DEFAULT_ACCENT_COLOR: &str = "#0f172a";
+
+/// White-label branding for reports shared outside Tradstry (coaches, funded traders).
+/// Every field is optional; unset fields fall back to the standard page.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReportBranding {
+    /// Shown as the report's author, e.g. a coaching business
+    pub display_name: Option<String>,
+    /// https image shown in the report header
+    pub logo_url: Option<String>,
+    /// `#rgb` or `#rrggbb`
+    pub accent_color: Option<String>,
+}
+
+impl ReportBranding {
+    /// Trim fields and treat blanks as unset
+    pub fn normalized(self) -> Self {
+        let clean = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        Self {
+            display_name: clean(self.display_name),
+            logo_url: clean(self.logo_url),
+            accent_color: clean(self.accent_color).map(|c| c.to_ascii_lowercase()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.display_name.as_ref().is_some_and(|n| n.chars().count() > MAX_DISPLAY_NAME_CHARS) {
+            return Err(format!("display_name must be at most {} characters", MAX_DISPLAY_NAME_CHARS));
+        }
+        if let Some(url) = &self.logo_url {
+            // The logo is loaded by viewers' browsers, so only plain https URLs are allowed
+            let valid = url.len() <= MAX_LOGO_URL_CHARS
+                && url.strip_prefix("https://").is_some_and(|rest| !rest.is_empty())
+                && !url.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '<' | '>' | '\\'));
+            if !valid {
+                return Err("logo_url must be an https URL".to_string());
+            }
+        }
+        if let Some(color) = &self.accent_color
+            && !is_hex_color(color)
+        {
+            return Err("accent_color must be a hex color like #1d4ed8".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn accent_color(&self) -> &str {
+        self.accent_color.as_deref().unwrap_or(DEFAULT_ACCENT_COLOR)
+    }
+
+    pub async fn load(conn: &Connection) -> Result<Self> {
+        let mut rows = conn
+            .prepare("SELECT display_name, logo_url, accent_color FROM report_branding WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Self {
+                display_name: row.get(0)?,
+                logo_url: row.get(1)?,
+                accent_color: row.get(2)?,
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO report_branding (id, display_name, logo_url, accent_color) VALUES ('default', ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET display_name = excluded.display_name, logo_url = excluded.logo_url, \
+             accent_color = excluded.accent_color",
+            params![self.display_name.clone(), self.logo_url.clone(), self.accent_color.clone()],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_branding_normalize_and_validate() {
+        let branding = ReportBranding {
+            display_name: Some("  Apex Coaching ".to_string()),
+            logo_url: Some(" ".to_string()),
+            accent_color: Some("#1D4ED8".to_string()),
+        }
+        .normalized();
+        assert_eq!(branding.display_name.as_deref(), Some("Apex Coaching"));
+        assert_eq!(branding.logo_url, None);
+        assert_eq!(branding.accent_color(), "#1d4ed8");
+        assert!(branding.validate().is_ok());
+
+        let bad_logo = ReportBranding { logo_url: Some("javascript:alert(1)".to_string()), ..Default::default() };
+        assert!(bad_logo.validate().is_err());
+        let bad_color = ReportBranding { accent_color: Some("red;}body{".to_string()), ..Default::default() };
+        assert!(bad_color.validate().is_err());
+    }
+}
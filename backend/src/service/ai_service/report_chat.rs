@@ -0,0 +1,94 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+
+/// Cap on the pinned snapshot so a long report can't crowd history out of the prompt
+pub const MAX_SNAPSHOT_CHARS: usize = 24_000;
+
+/// The parts of an `ai_reports` row a report chat answers against, exactly as stored
+#[derive(Debug, Clone)]
+pub struct ReportSnapshot {
+    pub id: String,
+    pub title: String,
+    pub time_range: String,
+    pub generated_at: String,
+    pub summary: String,
+    pub analytics: String,
+    pub trades: String,
+}
+
+pub async fn load_snapshot(conn: &Connection, report_id: &str) -> Result<Option<ReportSnapshot>> {
+    let mut rows = conn
+        .query(
+            "SELECT id, title, time_range, generated_at, summary, analytics, trades FROM ai_reports WHERE id = ?",
+            params![report_id],
+        )
+        .await?;
+    let Some(row) = rows.next().await? else {
+        return Ok(None);
+    };
+    Ok(Some(ReportSnapshot {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        time_range: row.get(2)?,
+        generated_at: row.get(3)?,
+        summary: row.get::<Option<String>>(4)?.unwrap_or_default(),
+        analytics: row.get::<Option<String>>(5)?.unwrap_or_else(|| "{}".to_string()),
+        trades: row.get::<Option<String>>(6)?.unwrap_or_else(|| "[]".to_string()),
+    }))
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}… [truncated]", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+/// System prompt section pinning the report. Analytics are kept whole where possible;
+/// the trade list gets whatever room is left under `max_chars`.
+pub fn prompt_section(snapshot: &ReportSnapshot, max_chars: usize) -> String {
+    let header = format!(
+        "You are discussing a saved report: \"{}\" ({}, generated {}). Answer follow-up questions \
+         from this snapshot only, not from newer data. If the snapshot doesn't contain what's needed, say so.\n\
+         Report summary:\n{}",
+        snapshot.title, snapshot.time_range, snapshot.generated_at, snapshot.summary
+    );
+    let analytics_budget = max_chars.saturating_sub(header.chars().count()) * 2 / 3;
+    let analytics = truncate_chars(&snapshot.analytics, analytics_budget);
+    let trades_budget = max_chars.saturating_sub(header.chars().count() + analytics.chars().count());
+    let trades = truncate_chars(&snapshot.trades, trades_budget);
+    format!("{}\n\nReport analytics (JSON):\n{}\n\nReport trades (JSON):\n{}", header, analytics, trades)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(trades: String) -> ReportSnapshot {
+        ReportSnapshot {
+            id: "r1".to_string(),
+            title: "October review".to_string(),
+            time_range: "30d".to_string(),
+            generated_at: "2026-10-01T00:00:00Z".to_string(),
+            summary: "Sharpe fell from 1.4 to 0.9".to_string(),
+            analytics: r#"{"sharpe_ratio":0.9}"#.to_string(),
+            trades,
+        }
+    }
+
+    #[test]
+    fn small_report_is_pinned_whole() {
+        let section = prompt_section(&snapshot(r#"[{"symbol":"AAPL"}]"#.to_string()), MAX_SNAPSHOT_CHARS);
+        assert!(section.contains(r#"{"sharpe_ratio":0.9}"#));
+        assert!(section.contains(r#"[{"symbol":"AAPL"}]"#));
+        assert!(!section.contains("[truncated]"));
+    }
+
+    #[test]
+    fn long_trade_list_is_cut_to_fit() {
+        let section = prompt_section(&snapshot("x".repeat(10_000)), 2_000);
+        assert!(section.contains("[truncated]"));
+        assert!(section.contains(r#"{"sharpe_ratio":0.9}"#));
+        assert!(section.chars().count() < 2_200);
+    }
+}
@@ -0,0 +1,347 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::ai::reports::{ReportRequest, ReportType, TradingReport};
+use crate::models::stock::stocks::TimeRange;
+use crate::service::ai_service::AiReportsService;
+use crate::service::cron_expression::CronExpression;
+use crate::service::notifications::chat_webhooks::dispatch_report;
+use crate::service::notifications::email::{escape_html, send_email, EmailMessage};
+use crate::service::notifications::push::{PushPayload, PushService};
+use crate::turso::config::{EmailConfig, WebPushConfig};
+
+const MAX_SCHEDULES_PER_USER: usize = 10;
+const MAX_NAME_LENGTH: usize = 100;
+/// Reports are expensive to generate, so schedules may not fire more than once an hour
+const MIN_INTERVAL_MINUTES: i64 = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSchedule {
+    pub id: String,
+    pub name: Option<String>,
+    pub cron_expression: String,
+    pub report_type: ReportType,
+    pub time_range: String,
+    pub is_active: bool,
+    pub next_run_at: Option<String>,
+    pub last_run_at: Option<String>,
+    pub last_report_id: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateReportScheduleRequest {
+    pub name: Option<String>,
+    /// Five-field cron expression in UTC, e.g. `0 9 * * SUN#1` for the first Sunday of each month
+    pub cron_expression: String,
+    pub report_type: Option<ReportType>,
+    /// Any range accepted by the reports API; defaults to `30d`
+    pub time_range: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateReportScheduleRequest {
+    pub name: Option<String>,
+    pub cron_expression: Option<String>,
+    pub report_type: Option<ReportType>,
+    pub time_range: Option<String>,
+    pub is_active: Option<bool>,
+}
+
+/// Parse and sanity-check a cron expression for report scheduling
+pub fn validate_cron(expression: &str) -> Result<CronExpression> {
+    let cron = CronExpression::parse(expression).map_err(|e| anyhow::anyhow!("Invalid cron expression: {}", e))?;
+    let now = Utc::now();
+    let first = cron.next_after(now).ok_or_else(|| anyhow::anyhow!("Cron expression never fires"))?;
+    if let Some(second) = cron.next_after(first)
+        && (second - first).num_minutes() < MIN_INTERVAL_MINUTES
+    {
+        anyhow::bail!("Reports can be scheduled at most once per hour");
+    }
+    Ok(cron)
+}
+
+fn validate_time_range(value: &str) -> Result<()> {
+    match TimeRange::parse(value) {
+        Some(TimeRange::AllTime) | None => anyhow::bail!("Invalid time range: {}", value),
+        Some(_) => Ok(()),
+    }
+}
+
+fn validate_name(name: &Option<String>) -> Result<()> {
+    if name.as_ref().is_some_and(|n| n.len() > MAX_NAME_LENGTH) {
+        anyhow::bail!("Name must be at most {} characters", MAX_NAME_LENGTH);
+    }
+    Ok(())
+}
+
+fn report_type_from_db(value: &str) -> ReportType {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).unwrap_or(ReportType::Comprehensive)
+}
+
+fn next_run(cron: &CronExpression, after: DateTime<Utc>) -> Option<String> {
+    cron.next_after(after).map(|t| t.to_rfc3339())
+}
+
+pub struct ReportScheduleService<'a> {
+    conn: &'a Connection,
+}
+
+const SCHEDULE_COLUMNS: &str = "id, name, cron_expression, report_type, time_range, is_active, next_run_at, last_run_at, last_report_id, last_error, created_at, updated_at";
+
+impl<'a> ReportScheduleService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    fn from_row(row: &libsql::Row) -> Result<ReportSchedule> {
+        Ok(ReportSchedule {
+            id: row.get(0)?,
+            name: row.get(1).ok().flatten(),
+            cron_expression: row.get(2)?,
+            report_type: report_type_from_db(&row.get::<String>(3)?),
+            time_range: row.get(4)?,
+            is_active: row.get::<i64>(5).map(|v| v != 0).unwrap_or(false),
+            next_run_at: row.get(6).ok().flatten(),
+            last_run_at: row.get(7).ok().flatten(),
+            last_report_id: row.get(8).ok().flatten(),
+            last_error: row.get(9).ok().flatten(),
+            created_at: row.get(10).unwrap_or_default(),
+            updated_at: row.get(11).unwrap_or_default(),
+        })
+    }
+
+    async fn query(&self, sql: &str, values: impl libsql::params::IntoParams) -> Result<Vec<ReportSchedule>> {
+        let mut rows = self.conn.prepare(sql).await?.query(values).await?;
+        let mut schedules = Vec::new();
+        while let Some(row) = rows.next().await? {
+            schedules.push(Self::from_row(&row)?);
+        }
+        Ok(schedules)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ReportSchedule>> {
+        self.query(&format!("SELECT {} FROM report_schedules ORDER BY created_at DESC", SCHEDULE_COLUMNS), params![]).await
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<ReportSchedule>> {
+        let mut schedules = self.query(&format!("SELECT {} FROM report_schedules WHERE id = ?", SCHEDULE_COLUMNS), params![id]).await?;
+        Ok(schedules.pop())
+    }
+
+    pub async fn create(&self, req: CreateReportScheduleRequest) -> Result<ReportSchedule> {
+        validate_name(&req.name)?;
+        let cron = validate_cron(&req.cron_expression)?;
+        let time_range = req.time_range.unwrap_or_else(|| "30d".to_string());
+        validate_time_range(&time_range)?;
+        if self.list().await?.len() >= MAX_SCHEDULES_PER_USER {
+            anyhow::bail!("At most {} report schedules are allowed", MAX_SCHEDULES_PER_USER);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let report_type = req.report_type.unwrap_or(ReportType::Comprehensive);
+        self.conn.execute(
+            "INSERT INTO report_schedules (id, name, cron_expression, report_type, time_range, is_active, next_run_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            params![
+                id.clone(),
+                req.name,
+                req.cron_expression.trim().to_string(),
+                report_type.to_string(),
+                time_range,
+                req.is_active.unwrap_or(true) as i64,
+                next_run(&cron, Utc::now())
+            ],
+        ).await?;
+        self.get(&id).await?.ok_or_else(|| anyhow::anyhow!("Schedule not found after insert"))
+    }
+
+    pub async fn update(&self, id: &str, req: UpdateReportScheduleRequest) -> Result<Option<ReportSchedule>> {
+        let Some(existing) = self.get(id).await? else { return Ok(None) };
+        validate_name(&req.name)?;
+        let cron_expression = req.cron_expression.map(|c| c.trim().to_string()).unwrap_or(existing.cron_expression);
+        let cron = validate_cron(&cron_expression)?;
+        let time_range = req.time_range.unwrap_or(existing.time_range);
+        validate_time_range(&time_range)?;
+
+        self.conn.execute(
+            "UPDATE report_schedules SET name = ?, cron_expression = ?, report_type = ?, time_range = ?, is_active = ?, next_run_at = ? WHERE id = ?",
+            params![
+                req.name.or(existing.name),
+                cron_expression,
+                req.report_type.unwrap_or(existing.report_type).to_string(),
+                time_range,
+                req.is_active.unwrap_or(existing.is_active) as i64,
+                next_run(&cron, Utc::now()),
+                id
+            ],
+        ).await?;
+        self.get(id).await
+    }
+
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        Ok(self.conn.execute("DELETE FROM report_schedules WHERE id = ?", params![id]).await? > 0)
+    }
+
+    /// Active schedules whose next run is at or before `now`
+    pub async fn due(&self, now: DateTime<Utc>) -> Result<Vec<ReportSchedule>> {
+        self.query(
+            &format!("SELECT {} FROM report_schedules WHERE is_active = 1 AND next_run_at IS NOT NULL AND next_run_at <= ?", SCHEDULE_COLUMNS),
+            params![now.to_rfc3339()],
+        ).await
+    }
+
+    /// Record the outcome of a run and advance `next_run_at`
+    pub async fn record_run(&self, schedule: &ReportSchedule, now: DateTime<Utc>, outcome: std::result::Result<&str, String>) -> Result<()> {
+        let next = CronExpression::parse(&schedule.cron_expression).ok().and_then(|cron| next_run(&cron, now));
+        let (report_id, error) = match outcome {
+            Ok(report_id) => (Some(report_id.to_string()), None),
+            Err(e) => (None, Some(e)),
+        };
+        self.conn.execute(
+            "UPDATE report_schedules SET last_run_at = ?, next_run_at = ?, last_report_id = COALESCE(?, last_report_id), last_error = ? WHERE id = ?",
+            params![now.to_rfc3339(), next, report_id, error, schedule.id.clone()],
+        ).await?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ReportDelivery {
+    pub push: bool,
+    pub email: bool,
+    pub chat_webhooks: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScheduleRunSummary {
+    pub generated: u64,
+    pub failed: u64,
+}
+
+/// Generate and deliver every due report for one user
+pub async fn run_due_schedules(
+    conn: &Connection,
+    user_id: &str,
+    reports_service: &AiReportsService,
+    web_push: &WebPushConfig,
+    email: Option<(&EmailConfig, &str)>,
+) -> Result<ScheduleRunSummary> {
+    let service = ReportScheduleService::new(conn);
+    let now = Utc::now();
+    let mut summary = ScheduleRunSummary::default();
+
+    for schedule in service.due(now).await? {
+        let Some(time_range) = TimeRange::parse(&schedule.time_range) else {
+            service.record_run(&schedule, now, Err(format!("Invalid time range: {}", schedule.time_range))).await?;
+            summary.failed += 1;
+            continue;
+        };
+        let request = ReportRequest {
+            time_range,
+            report_type: schedule.report_type.clone(),
+            include_sections: None,
+            include_charts: None,
+            include_predictions: None,
+            format: None,
+        };
+
+        match reports_service.generate_report(conn, user_id, request).await {
+            Ok(report) => {
+                deliver_report(conn, user_id, &report, web_push, email).await;
+                service.record_run(&schedule, now, Ok(&report.id)).await?;
+                summary.generated += 1;
+            }
+            Err(e) => {
+                log::error!("Scheduled report {} failed for user {}: {}", schedule.id, user_id, e);
+                service.record_run(&schedule, now, Err(e.to_string())).await?;
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Send a generated report through push, email and chat webhooks
+pub async fn deliver_report(
+    conn: &Connection,
+    user_id: &str,
+    report: &TradingReport,
+    web_push: &WebPushConfig,
+    email: Option<(&EmailConfig, &str)>,
+) -> ReportDelivery {
+    let mut delivery = ReportDelivery::default();
+
+    let payload = PushPayload {
+        title: report.title.clone(),
+        body: Some(report.summary.chars().take(180).collect()).filter(|b: &String| !b.is_empty()),
+        icon: Some("/icons/icon-192.png".to_string()),
+        url: Some(format!("/app/reports?id={}", report.id)),
+        tag: Some("scheduled-report".to_string()),
+        data: Some(serde_json::json!({"type": "scheduled_report", "report_id": report.id})),
+    };
+    match PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+        Ok(()) => delivery.push = true,
+        Err(e) => log::warn!("Scheduled report push failed for user {}: {}", user_id, e),
+    }
+
+    if let Some((config, address)) = email {
+        match send_email(config, &render_email(report, address)).await {
+            Ok(()) => delivery.email = true,
+            Err(e) => log::warn!("Scheduled report email failed for user {}: {}", user_id, e),
+        }
+    }
+
+    delivery.chat_webhooks = dispatch_report(conn, report).await.unwrap_or(0);
+    delivery
+}
+
+fn render_email(report: &TradingReport, to: &str) -> EmailMessage {
+    let analytics = &report.analytics;
+    let stats = format!(
+        "{} trades, win rate {:.1}%, net P&L ${:.2}, profit factor {:.2}",
+        analytics.total_trades, analytics.win_rate, analytics.net_pnl, analytics.profit_factor
+    );
+    let mut text = format!("{}\n\n{}\n\n{}\n", report.title, stats, report.summary);
+    let mut html = format!(
+        "<h2>{}</h2><p>{}</p><p>{}</p>",
+        escape_html(&report.title),
+        escape_html(&stats),
+        escape_html(&report.summary)
+    );
+    let custom_metrics: Vec<String> = analytics
+        .custom_metrics
+        .iter()
+        .filter_map(|metric| metric.value.map(|value| format!("{}: {:.2}", metric.name, value)))
+        .collect();
+    if !custom_metrics.is_empty() {
+        text.push_str("\nYour metrics:\n");
+        html.push_str("<h3>Your metrics</h3><ul>");
+        for metric in &custom_metrics {
+            text.push_str(&format!("- {}\n", metric));
+            html.push_str(&format!("<li>{}</li>", escape_html(metric)));
+        }
+        html.push_str("</ul>");
+    }
+    if !report.recommendations.is_empty() {
+        text.push_str("\nRecommendations:\n");
+        html.push_str("<h3>Recommendations</h3><ul>");
+        for r in &report.recommendations {
+            text.push_str(&format!("- {}\n", r));
+            html.push_str(&format!("<li>{}</li>", escape_html(r)));
+        }
+        html.push_str("</ul>");
+    }
+    EmailMessage {
+        to: to.to_string(),
+        subject: format!("Tradstry report: {}", report.title),
+        text,
+        html: Some(html),
+    }
+}
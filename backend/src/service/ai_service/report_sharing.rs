@@ -0,0 +1,269 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::models::ai::reports::TradingReport;
+use crate::service::ai_service::report_branding::ReportBranding;
+use crate::service::notifications::email::escape_html;
+use crate::turso::config::ReportSharingConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const DEFAULT_SHARE_DAYS: i64 = 7;
+pub const MAX_SHARE_DAYS: i64 = 90;
+const MAX_ACTIVE_SHARES_PER_REPORT: usize = 10;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CreateShareRequest {
+    /// Link lifetime in days (1-90, default 7)
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportShare {
+    pub id: String,
+    pub report_id: String,
+    pub expires_at: String,
+    pub revoked_at: Option<String>,
+    pub view_count: i64,
+    pub last_viewed_at: Option<String>,
+    pub created_at: String,
+    /// Only returned when the link is created; the token isn't stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Share that passed signature, expiry and revocation checks
+#[derive(Debug, Clone)]
+pub struct ResolvedShare {
+    pub share_id: String,
+    pub user_id: String,
+    pub report_id: String,
+}
+
+fn signature(secret: &str, share_id: &str, expires: i64) -> Option<String> {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(format!("{}.{}", share_id, expires).as_bytes());
+    Some(hex::encode(mac.finalize().into_bytes()))
+}
+
+/// `{share_id}.{expires_unix}.{hmac}` — the expiry is signed so it can't be extended client-side
+pub fn sign_token(secret: &str, share_id: &str, expires_at: DateTime<Utc>) -> Option<String> {
+    let expires = expires_at.timestamp();
+    Some(format!("{}.{}.{}", share_id, expires, signature(secret, share_id, expires)?))
+}
+
+/// Verify a token's signature and expiry, returning the share id
+pub fn verify_token(secret: &str, token: &str, now: DateTime<Utc>) -> Option<String> {
+    let mut parts = token.splitn(3, '.');
+    let (share_id, expires, provided) = (parts.next()?, parts.next()?, parts.next()?);
+    let expires: i64 = expires.parse().ok()?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).ok()?;
+    mac.update(format!("{}.{}", share_id, expires).as_bytes());
+    mac.verify_slice(&hex::decode(provided).ok()?).ok()?;
+    (expires > now.timestamp()).then(|| share_id.to_string())
+}
+
+fn share_from_row(row: &libsql::Row) -> Result<ReportShare> {
+    Ok(ReportShare {
+        id: row.get(0)?,
+        report_id: row.get(1)?,
+        expires_at: row.get(2)?,
+        revoked_at: row.get(3).ok().flatten(),
+        view_count: row.get(4).unwrap_or(0),
+        last_viewed_at: row.get(5).ok().flatten(),
+        created_at: row.get(6).unwrap_or_default(),
+        url: None,
+    })
+}
+
+/// Share links live in the registry so the public endpoint can find the owner's database
+pub struct ReportShareService<'a> {
+    registry: &'a Connection,
+    config: &'a ReportSharingConfig,
+}
+
+impl<'a> ReportShareService<'a> {
+    pub fn new(registry: &'a Connection, config: &'a ReportSharingConfig) -> Self {
+        Self { registry, config }
+    }
+
+    pub async fn create(&self, user_id: &str, report_id: &str, req: CreateShareRequest) -> Result<ReportShare> {
+        let days = req.expires_in_days.unwrap_or(DEFAULT_SHARE_DAYS);
+        if !(1..=MAX_SHARE_DAYS).contains(&days) {
+            anyhow::bail!("expires_in_days must be between 1 and {}", MAX_SHARE_DAYS);
+        }
+        let active = self.list(user_id, report_id).await?.into_iter().filter(|s| s.revoked_at.is_none()).count();
+        if active >= MAX_ACTIVE_SHARES_PER_REPORT {
+            anyhow::bail!("At most {} active links per report; revoke one first", MAX_ACTIVE_SHARES_PER_REPORT);
+        }
+
+        let id = Uuid::new_v4().simple().to_string();
+        let now = Utc::now();
+        let expires_at = now + Duration::days(days);
+        let token = sign_token(&self.config.signing_secret, &id, expires_at)
+            .ok_or_else(|| anyhow::anyhow!("Invalid share signing secret"))?;
+
+        self.registry.execute(
+            "INSERT INTO report_shares (id, user_id, report_id, expires_at, created_at) VALUES (?, ?, ?, ?, ?)",
+            params![id.clone(), user_id, report_id, expires_at.to_rfc3339(), now.to_rfc3339()],
+        ).await?;
+
+        Ok(ReportShare {
+            id,
+            report_id: report_id.to_string(),
+            expires_at: expires_at.to_rfc3339(),
+            revoked_at: None,
+            view_count: 0,
+            last_viewed_at: None,
+            created_at: now.to_rfc3339(),
+            url: Some(format!("{}/shared/reports/{}", self.config.public_base_url, token)),
+        })
+    }
+
+    pub async fn list(&self, user_id: &str, report_id: &str) -> Result<Vec<ReportShare>> {
+        let mut rows = self.registry
+            .prepare("SELECT id, report_id, expires_at, revoked_at, view_count, last_viewed_at, created_at FROM report_shares WHERE user_id = ? AND report_id = ? ORDER BY created_at DESC")
+            .await?
+            .query(params![user_id, report_id])
+            .await?;
+        let mut shares = Vec::new();
+        while let Some(row) = rows.next().await? {
+            shares.push(share_from_row(&row)?);
+        }
+        Ok(shares)
+    }
+
+    pub async fn revoke(&self, user_id: &str, share_id: &str) -> Result<bool> {
+        let updated = self.registry.execute(
+            "UPDATE report_shares SET revoked_at = ? WHERE id = ? AND user_id = ? AND revoked_at IS NULL",
+            params![Utc::now().to_rfc3339(), share_id, user_id],
+        ).await?;
+        Ok(updated > 0)
+    }
+
+    /// Revoke every link to a report (e.g. when the report is deleted)
+    pub async fn revoke_all(&self, user_id: &str, report_id: &str) -> Result<u64> {
+        Ok(self.registry.execute(
+            "UPDATE report_shares SET revoked_at = ? WHERE user_id = ? AND report_id = ? AND revoked_at IS NULL",
+            params![Utc::now().to_rfc3339(), user_id, report_id],
+        ).await?)
+    }
+
+    /// Validate a public token and count the view. None for bad, expired or revoked links.
+    pub async fn resolve(&self, token: &str) -> Result<Option<ResolvedShare>> {
+        let now = Utc::now();
+        let Some(share_id) = verify_token(&self.config.signing_secret, token, now) else { return Ok(None) };
+
+        let mut rows = self.registry
+            .prepare("SELECT user_id, report_id FROM report_shares WHERE id = ? AND revoked_at IS NULL AND expires_at > ?")
+            .await?
+            .query(params![share_id.clone(), now.to_rfc3339()])
+            .await?;
+        let Some(row) = rows.next().await? else { return Ok(None) };
+        let resolved = ResolvedShare { share_id, user_id: row.get(0)?, report_id: row.get(1)? };
+
+        self.registry.execute(
+            "UPDATE report_shares SET view_count = view_count + 1, last_viewed_at = ? WHERE id = ?",
+            params![now.to_rfc3339(), resolved.share_id.clone()],
+        ).await?;
+        Ok(Some(resolved))
+    }
+}
+
+/// Standalone read-only page for a shared report, styled with the owner's branding.
+/// Trade-level rows are left out on purpose.
+pub fn render_report_html(report: &TradingReport, branding: &ReportBranding) -> String {
+    let a = &report.analytics;
+    let stat = |label: &str, value: String| format!("<div class=\"stat\"><span>{}</span><strong>{}</strong></div>", label, escape_html(&value));
+    let stats = [
+        stat("Trades", a.total_trades.to_string()),
+        stat("Win rate", format!("{:.1}%", a.win_rate)),
+        stat("Net P&amp;L", format!("${:.2}", a.net_pnl)),
+        stat("Profit factor", format!("{:.2}", a.profit_factor)),
+        stat("Avg win", format!("${:.2}", a.avg_gain)),
+        stat("Avg loss", format!("${:.2}", a.avg_loss)),
+    ]
+    .join("");
+
+    let mut body = String::new();
+    if branding.logo_url.is_some() || branding.display_name.is_some() {
+        body.push_str("<header class=\"brand\">");
+        if let Some(logo) = &branding.logo_url {
+            let alt = branding.display_name.as_deref().unwrap_or("Logo");
+            body.push_str(&format!("<img src=\"{}\" alt=\"{}\">", escape_html(logo), escape_html(alt)));
+        }
+        if let Some(name) = &branding.display_name {
+            body.push_str(&format!("<span>{}</span>", escape_html(name)));
+        }
+        body.push_str("</header>");
+    }
+    body.push_str(&format!("<h1>{}</h1>", escape_html(&report.title)));
+    if !report.summary.is_empty() {
+        body.push_str(&format!("<p class=\"summary\">{}</p>", escape_html(&report.summary)));
+    }
+    body.push_str(&format!("<section class=\"stats\">{}</section>", stats));
+    for insight in &report.insights {
+        body.push_str(&format!("<h2>{}</h2><p>{}</p>", escape_html(&insight.title), escape_html(&insight.content)));
+    }
+    if !report.recommendations.is_empty() {
+        body.push_str("<h2>Recommendations</h2><ul>");
+        for r in &report.recommendations {
+            body.push_str(&format!("<li>{}</li>", escape_html(r)));
+        }
+        body.push_str("</ul>");
+    }
+
+    let attribution = match &branding.display_name {
+        Some(name) => format!("Prepared by {}", escape_html(name)),
+        None => "Shared from Tradstry".to_string(),
+    };
+    // The accent color is validated as a hex color before it is stored
+    format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <meta name=\"robots\" content=\"noindex\"><title>{}</title><style>:root{{--accent:{}}}{}</style></head>\
+         <body><main>{}<footer>Generated {} · {}</footer></main></body></html>",
+        escape_html(&report.title),
+        branding.accent_color(),
+        SHARE_PAGE_CSS,
+        body,
+        report.generated_at.format("%Y-%m-%d"),
+        attribution
+    )
+}
+
+const SHARE_PAGE_CSS: &str = "body{font-family:system-ui,sans-serif;background:#f8fafc;color:#0f172a;margin:0}\
+main{max-width:760px;margin:0 auto;padding:32px 20px}.summary{font-size:1.05rem}\
+.stats{display:grid;grid-template-columns:repeat(auto-fill,minmax(140px,1fr));gap:12px;margin:24px 0}\
+.stat{background:#fff;border:1px solid #e2e8f0;border-radius:8px;padding:12px;display:flex;flex-direction:column}\
+.stat span{color:#64748b;font-size:.85rem}footer{margin-top:40px;color:#94a3b8;font-size:.8rem}\
+h1,h2{color:var(--accent)}.stat{border-top:3px solid var(--accent)}\
+.brand{display:flex;align-items:center;gap:12px;margin-bottom:16px;font-weight:600}.brand img{max-height:48px;max-width:200px}";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_token_round_trip() {
+        let now = Utc::now();
+        let token = sign_token("secret", "abc123", now + Duration::days(1)).unwrap();
+        assert_eq!(verify_token("secret", &token, now).as_deref(), Some("abc123"));
+        assert_eq!(verify_token("other-secret", &token, now), None);
+        assert_eq!(verify_token("secret", &token, now + Duration::days(2)), None);
+    }
+
+    #[test]
+    fn test_tampered_expiry_is_rejected() {
+        let now = Utc::now();
+        let token = sign_token("secret", "abc123", now + Duration::days(1)).unwrap();
+        let parts: Vec<&str> = token.split('.').collect();
+        let extended = format!("{}.{}.{}", parts[0], (now + Duration::days(365)).timestamp(), parts[2]);
+        assert_eq!(verify_token("secret", &extended, now), None);
+        assert_eq!(verify_token("secret", "garbage", now), None);
+    }
+}
@@ -7,6 +7,8 @@ use crate::models::stock::stocks::TimeRange;
 use crate::models::ai::insights::{Insight, InsightRequest, InsightType};
 use crate::service::ai_service::AIInsightsService;
 use crate::service::analytics_engine::AnalyticsEngine;
+use crate::service::analytics_engine::snapshots;
+use crate::service::analytics_engine::custom_metrics;
 use crate::models::analytics::CoreMetrics;
 use crate::turso::TursoClient;
 use anyhow::Result as AnyhowResult;
@@ -17,6 +19,10 @@ use serde::Serialize;
 use serde_json;
 use std::sync::Arc;
 
+/// Relative move a metric needs to be mentioned in the report summary
+const REPORT_CHANGE_MIN_PCT: f64 = 10.0;
+const REPORT_CHANGE_LIMIT: usize = 5;
+
 /// AI Reports Service for generating comprehensive trading reports
 pub struct AiReportsService {
     #[allow(dead_code)]
@@ -57,6 +63,13 @@ impl AiReportsService {
         let analytics = self.generate_analytics_data(conn, user_id, &request.time_range).await?;
         report = report.with_analytics(analytics);
 
+        // Summarize what changed versus the previous period of the same length
+        match snapshots::diff_with_previous_period(conn, &request.time_range, "current period").await {
+            Ok(Some(changes)) => report = report.with_summary(changes.narrate(REPORT_CHANGE_MIN_PCT, REPORT_CHANGE_LIMIT)),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to diff report period for user {}: {}", user_id, e),
+        }
+
         // Generate insights
         let insights = self.generate_insights(conn, user_id, &request.time_range).await?;
         report = report.with_insights(insights);
@@ -121,6 +134,7 @@ impl AiReportsService {
             winning_trades: core_metrics.winning_trades,
             losing_trades: core_metrics.losing_trades,
             break_even_trades: core_metrics.break_even_trades,
+            custom_metrics: Vec::new(),
         }
     }
 
@@ -140,8 +154,17 @@ impl AiReportsService {
         
         log::info!("Successfully calculated core metrics: {} trades", core_metrics.total_trades);
         
+        let custom_metrics = match custom_metrics::calculate_custom_metrics(conn, time_range, &core_metrics).await {
+            Ok(values) => values,
+            Err(e) => {
+                log::warn!("Failed to calculate custom metrics for report: {}", e);
+                Vec::new()
+            }
+        };
+
         // Convert CoreMetrics to AnalyticsData
-        let analytics_data = self.map_core_metrics_to_analytics_data(core_metrics);
+        let mut analytics_data = self.map_core_metrics_to_analytics_data(core_metrics);
+        analytics_data.custom_metrics = custom_metrics;
         
         Ok(analytics_data)
     }
@@ -169,6 +192,7 @@ impl AiReportsService {
                 insight_type: insight_type.clone(),
                 include_predictions: Some(true),
                 force_regenerate: Some(false), // Use cached insights if available
+                source: None,
             };
 
             match self.ai_insights_service.generate_insights(user_id, insight_request, conn).await {
@@ -273,7 +297,7 @@ async fn generate_trade_data(
     // Get options trades
     let options_query = "
         SELECT id, symbol, number_of_contracts, entry_price, exit_price, 
-               created_at, exit_date
+               created_at, exit_date, short_premium
         FROM options 
         WHERE created_at >= ? AND created_at <= ?
         ORDER BY created_at DESC
@@ -319,8 +343,13 @@ async fn generate_trade_data(
             _ => None,
         };
         
+        let short_premium = matches!(row.get::<libsql::Value>(7)?, libsql::Value::Integer(1));
+
         // Calculate PNL if we have exit price (for options, 1 contract = 100 shares)
-        let pnl = exit_price.map(|exit| (exit - entry_price) * (number_of_contracts as f64) * 100.0);
+        let pnl = exit_price.map(|exit| {
+            let gain = if short_premium { entry_price - exit } else { exit - entry_price };
+            gain * (number_of_contracts as f64) * 100.0
+        });
         
         trades.push(TradeData {
             id,
@@ -468,6 +497,11 @@ async fn generate_trade_data(
             TimeRange::ThirtyDays => "Monthly",
             TimeRange::NinetyDays => "Quarterly",
             TimeRange::YearToDate => "Year-to-Date",
+            TimeRange::MonthToDate => "Month-to-Date",
+            TimeRange::QuarterToDate => "Quarter-to-Date",
+            TimeRange::LastMonth => "Last Month",
+            TimeRange::LastQuarter => "Last Quarter",
+            TimeRange::IsoWeeks { .. } => "Weekly",
             TimeRange::OneYear => "Annual",
             TimeRange::Custom { .. } => "Custom",
             TimeRange::AllTime => "All Time",
@@ -500,6 +534,13 @@ async fn generate_trade_data(
             TimeRange::Custom { start_date: Some(start), .. } => *start,
             TimeRange::Custom { start_date: None, .. } => now - chrono::Duration::days(30),
             TimeRange::AllTime => now - chrono::Duration::days(365 * 10), // 10 years ago
+            calendar => {
+                let (start, end) = calendar.to_dates();
+                return (
+                    start.unwrap_or(now).to_rfc3339(),
+                    end.unwrap_or(now).to_rfc3339(),
+                );
+            }
         };
 
         (start_date.to_rfc3339(), now.to_rfc3339())
@@ -525,6 +566,7 @@ async fn generate_trade_data(
             },
             TimeRange::Custom { start_date: None, .. } => 30,
             TimeRange::AllTime => 365 * 10, // 10 years
+            calendar => calendar.approx_days(),
         }
     }
 
@@ -573,8 +615,8 @@ async fn generate_trade_data(
 
         stmt.execute([
             report.id.as_str(),
-            &serde_json::to_string(&report.time_range)?,
-            &serde_json::to_string(&report.report_type)?,
+            &report.time_range.to_db(),
+            report.report_type.as_str(),
             report.title.as_str(),
             report.summary.as_str(),
             &serde_json::to_string(&report.analytics)?,
@@ -602,10 +644,12 @@ async fn generate_trade_data(
         let id: String = row.get(0)?;
         log::info!("Deserialized id: {}", id);
         
-        let time_range: TimeRange = serde_json::from_str(&row.get::<String>(1)?)?;
+        let time_range = TimeRange::from_db(&row.get::<String>(1)?)
+            .ok_or_else(|| anyhow::anyhow!("Unknown time_range in report {}", id))?;
         log::info!("Deserialized time_range");
         
-        let report_type: ReportType = serde_json::from_str(&row.get::<String>(2)?)?;
+        let report_type = ReportType::parse(&row.get::<String>(2)?)
+            .ok_or_else(|| anyhow::anyhow!("Unknown report_type in report {}", id))?;
         log::info!("Deserialized report_type");
         
         let title: String = row.get(3)?;
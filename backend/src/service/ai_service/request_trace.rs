@@ -0,0 +1,272 @@
+// Stage timings for AI requests. Each chat request records how long embedding, vector
+// search, prompt assembly, the model provider and persistence took into the registry's
+// `ai_request_traces`, so the admin latency report can say which stage to optimize.
+// Saving happens in the background and never fails the request being traced.
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::turso::client::TursoClient;
+
+/// Most recent traces the report reads for one window
+const MAX_REPORT_TRACES: i64 = 50_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Embedding,
+    VectorSearch,
+    PromptBuild,
+    Provider,
+    Persistence,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 5] = [Stage::Embedding, Stage::VectorSearch, Stage::PromptBuild, Stage::Provider, Stage::Persistence];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Embedding => "embedding",
+            Stage::VectorSearch => "vector_search",
+            Stage::PromptBuild => "prompt_build",
+            Stage::Provider => "provider",
+            Stage::Persistence => "persistence",
+        }
+    }
+}
+
+/// Timings of one AI request; stages that didn't run stay None
+#[derive(Debug, Clone)]
+pub struct RequestTrace {
+    pub endpoint: &'static str,
+    pub model: Option<String>,
+    pub success: bool,
+    started: Instant,
+    stages: [Option<u64>; 5],
+}
+
+impl RequestTrace {
+    pub fn start(endpoint: &'static str) -> Self {
+        Self { endpoint, model: None, success: true, started: Instant::now(), stages: [None; 5] }
+    }
+
+    /// Add time to a stage; a stage that runs more than once accumulates
+    pub fn record(&mut self, stage: Stage, elapsed: Duration) {
+        let slot = &mut self.stages[stage as usize];
+        *slot = Some(slot.unwrap_or(0) + elapsed.as_millis() as u64);
+    }
+
+    pub fn stage_ms(&self, stage: Stage) -> Option<u64> {
+        self.stages[stage as usize]
+    }
+
+    pub fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+}
+
+pub async fn save(registry: &Connection, user_id: &str, trace: &RequestTrace) -> Result<()> {
+    let ms = |stage: Stage| trace.stage_ms(stage).map(|v| v as i64);
+    registry
+        .execute(
+            "INSERT INTO ai_request_traces (id, user_id, endpoint, model, embedding_ms, vector_search_ms, prompt_build_ms, provider_ms, persistence_ms, total_ms, success, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            params![
+                Uuid::new_v4().to_string(),
+                user_id,
+                trace.endpoint,
+                trace.model.clone(),
+                ms(Stage::Embedding),
+                ms(Stage::VectorSearch),
+                ms(Stage::PromptBuild),
+                ms(Stage::Provider),
+                ms(Stage::Persistence),
+                trace.elapsed_ms() as i64,
+                trace.success as i64,
+                Utc::now().to_rfc3339()
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+/// Save a finished trace without holding up the response
+pub fn spawn_save(turso_client: Arc<TursoClient>, user_id: &str, trace: RequestTrace) {
+    let user_id = user_id.to_string();
+    tokio::spawn(async move {
+        let result = match turso_client.get_registry_connection().await {
+            Ok(conn) => save(&conn, &user_id, &trace).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to save AI request trace for endpoint {}: {}", trace.endpoint, e);
+        }
+    });
+}
+
+/// One stored trace as the report reads it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceRow {
+    pub endpoint: String,
+    pub stages: [Option<u64>; 5],
+    pub total_ms: u64,
+    pub success: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StageLatency {
+    pub stage: String,
+    pub samples: usize,
+    pub avg_ms: f64,
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+    /// Average stage time over average total time of the requests it ran in
+    pub share_of_total: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EndpointLatency {
+    pub endpoint: String,
+    pub requests: usize,
+    pub failures: usize,
+    pub total: StageLatency,
+    pub stages: Vec<StageLatency>,
+    /// Stage with the highest average time
+    pub slowest_stage: Option<String>,
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn stage_latency(stage: &str, samples: &[(u64, u64)]) -> StageLatency {
+    let mut values: Vec<u64> = samples.iter().map(|(v, _)| *v).collect();
+    values.sort_unstable();
+    let sum: u64 = values.iter().sum();
+    let total_sum: u64 = samples.iter().map(|(_, total)| *total).sum();
+    StageLatency {
+        stage: stage.to_string(),
+        samples: values.len(),
+        avg_ms: if values.is_empty() { 0.0 } else { sum as f64 / values.len() as f64 },
+        p50_ms: percentile(&values, 50.0),
+        p95_ms: percentile(&values, 95.0),
+        max_ms: values.last().copied().unwrap_or(0),
+        share_of_total: if total_sum == 0 { 0.0 } else { sum as f64 / total_sum as f64 },
+    }
+}
+
+/// Per-endpoint latency breakdown, busiest endpoint first
+pub fn summarize(rows: &[TraceRow]) -> Vec<EndpointLatency> {
+    let mut by_endpoint: BTreeMap<&str, Vec<&TraceRow>> = BTreeMap::new();
+    for row in rows {
+        by_endpoint.entry(row.endpoint.as_str()).or_default().push(row);
+    }
+
+    let mut endpoints: Vec<EndpointLatency> = by_endpoint
+        .into_iter()
+        .map(|(endpoint, rows)| {
+            let totals: Vec<(u64, u64)> = rows.iter().map(|r| (r.total_ms, r.total_ms)).collect();
+            let stages: Vec<StageLatency> = Stage::ALL
+                .iter()
+                .map(|stage| {
+                    let samples: Vec<(u64, u64)> = rows.iter().filter_map(|r| r.stages[*stage as usize].map(|v| (v, r.total_ms))).collect();
+                    stage_latency(stage.as_str(), &samples)
+                })
+                .filter(|s| s.samples > 0)
+                .collect();
+            let slowest_stage = stages
+                .iter()
+                .max_by(|a, b| a.avg_ms.partial_cmp(&b.avg_ms).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|s| s.stage.clone());
+            EndpointLatency {
+                endpoint: endpoint.to_string(),
+                requests: rows.len(),
+                failures: rows.iter().filter(|r| !r.success).count(),
+                total: stage_latency("total", &totals),
+                stages,
+                slowest_stage,
+            }
+        })
+        .collect();
+    endpoints.sort_by_key(|e| Reverse(e.requests));
+    endpoints
+}
+
+/// Latency report over traces created at or after `since` (RFC 3339)
+pub async fn latency_report(registry: &Connection, since: &str, endpoint: Option<&str>) -> Result<Vec<EndpointLatency>> {
+    let mut rows = registry
+        .prepare(
+            "SELECT endpoint, embedding_ms, vector_search_ms, prompt_build_ms, provider_ms, persistence_ms, total_ms, success
+             FROM ai_request_traces
+             WHERE created_at >= ? AND (? IS NULL OR endpoint = ?)
+             ORDER BY created_at DESC LIMIT ?",
+        )
+        .await?
+        .query(params![since, endpoint, endpoint, MAX_REPORT_TRACES])
+        .await?;
+
+    let mut traces = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let ms = |idx: i32| -> Result<Option<u64>> { Ok(row.get::<Option<i64>>(idx)?.map(|v| v.max(0) as u64)) };
+        traces.push(TraceRow {
+            endpoint: row.get(0)?,
+            stages: [ms(1)?, ms(2)?, ms(3)?, ms(4)?, ms(5)?],
+            total_ms: row.get::<i64>(6)?.max(0) as u64,
+            success: row.get::<i64>(7)? != 0,
+        });
+    }
+    Ok(summarize(&traces))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(endpoint: &str, stages: [Option<u64>; 5], total_ms: u64, success: bool) -> TraceRow {
+        TraceRow { endpoint: endpoint.to_string(), stages, total_ms, success }
+    }
+
+    #[test]
+    fn test_summarize_finds_slowest_stage() {
+        let rows = vec![
+            row("chat", [Some(100), Some(50), Some(10), Some(800), Some(40)], 1000, true),
+            row("chat", [Some(120), Some(70), Some(10), Some(1800), Some(0)], 2000, true),
+            row("chat", [None, None, Some(20), Some(2780), Some(200)], 3000, false),
+            row("chat_stream", [Some(90), Some(60), Some(5), Some(500), Some(45)], 700, true),
+        ];
+        let report = summarize(&rows);
+
+        assert_eq!(report.len(), 2);
+        let chat = &report[0];
+        assert_eq!((chat.endpoint.as_str(), chat.requests, chat.failures), ("chat", 3, 1));
+        assert_eq!(chat.slowest_stage.as_deref(), Some("provider"));
+        assert_eq!((chat.total.p50_ms, chat.total.p95_ms, chat.total.max_ms), (2000, 3000, 3000));
+
+        let embedding = chat.stages.iter().find(|s| s.stage == "embedding").unwrap();
+        assert_eq!(embedding.samples, 2);
+        assert_eq!(embedding.avg_ms, 110.0);
+        // 220ms of embedding over the 3000ms total of the two requests that embedded
+        assert!((embedding.share_of_total - 220.0 / 3000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_record_accumulates() {
+        let mut trace = RequestTrace::start("chat");
+        assert_eq!(trace.stage_ms(Stage::Persistence), None);
+        trace.record(Stage::Persistence, Duration::from_millis(30));
+        trace.record(Stage::Persistence, Duration::from_millis(12));
+        assert_eq!(trace.stage_ms(Stage::Persistence), Some(42));
+    }
+}
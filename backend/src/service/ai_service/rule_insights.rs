@@ -0,0 +1,261 @@
+// Rule-based insights. A deterministic generator that turns the analytics engine's numbers
+// into template findings ("win rate on short trades is 18 points lower than longs over the
+// last 30 days"). It backs the insights feed when the LLM provider is down and can be
+// requested explicitly with `source: "rules"`. Its insights go into `ai_insights` like any
+// other, with `source = 'rules'`, and every finding carries the metrics it was built from.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use libsql::Connection;
+
+use crate::models::ai::insights::{FindingEvidence, Insight, InsightMetadata, InsightRequest, InsightSource, InsightType};
+use crate::models::analytics::CoreMetrics;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::core_metrics::calculate_filtered_core_metrics;
+use crate::service::analytics_engine::query::{Direction, TradeFilter};
+
+pub const RULES_MODEL_VERSION: &str = "rules-v1";
+
+/// Trades each side needs before long and short are compared
+const MIN_TRADES_PER_SIDE: u32 = 5;
+/// Win rate gap, in percentage points, worth reporting
+const WIN_RATE_GAP_POINTS: f64 = 10.0;
+/// Losing streak long enough to call out
+const LOSING_STREAK: u32 = 5;
+/// Share of gross profit paid in commissions worth calling out
+const COMMISSION_SHARE: f64 = 0.10;
+
+/// One finding and the recommendation that goes with it, if any
+#[derive(Debug, Clone)]
+pub struct RuleFinding {
+    pub text: String,
+    pub evidence: FindingEvidence,
+    pub recommendation: Option<String>,
+}
+
+fn evidence(metrics: &[(&str, f64)]) -> FindingEvidence {
+    FindingEvidence {
+        // Infinite ratios (no losing trades) would not survive a JSON round trip
+        metrics: metrics
+            .iter()
+            .filter(|(_, value)| value.is_finite())
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect::<BTreeMap<_, _>>(),
+        ..Default::default()
+    }
+}
+
+/// "over the last 30 days", or "across all trades" for unbounded ranges
+pub fn period_label(range: &TimeRange) -> String {
+    match range {
+        TimeRange::AllTime => "across all trades".to_string(),
+        other => format!("over the last {} days", other.approx_days()),
+    }
+}
+
+/// Apply every rule to the period's metrics, overall and per direction
+pub fn findings(overall: &CoreMetrics, long: &CoreMetrics, short: &CoreMetrics, period: &str) -> Vec<RuleFinding> {
+    let mut out = Vec::new();
+    if overall.total_trades == 0 {
+        out.push(RuleFinding {
+            text: format!("No closed trades {}", period),
+            evidence: evidence(&[("total_trades", 0.0)]),
+            recommendation: None,
+        });
+        return out;
+    }
+
+    if long.total_trades >= MIN_TRADES_PER_SIDE && short.total_trades >= MIN_TRADES_PER_SIDE {
+        let gap = long.win_rate - short.win_rate;
+        if gap.abs() >= WIN_RATE_GAP_POINTS {
+            let (weaker, stronger) = if gap > 0.0 { ("short", "longs") } else { ("long", "shorts") };
+            out.push(RuleFinding {
+                text: format!(
+                    "Win rate on {} trades is {:.0} points lower than {} {} ({:.0}% vs {:.0}%)",
+                    weaker,
+                    gap.abs(),
+                    stronger,
+                    period,
+                    long.win_rate.min(short.win_rate),
+                    long.win_rate.max(short.win_rate)
+                ),
+                evidence: evidence(&[("long_win_rate", long.win_rate), ("short_win_rate", short.win_rate)]),
+                recommendation: Some(format!(
+                    "Review your {} setups and consider trading them smaller until their win rate catches up",
+                    weaker
+                )),
+            });
+        }
+    }
+
+    if overall.gross_loss != 0.0 && overall.profit_factor < 1.0 {
+        out.push(RuleFinding {
+            text: format!(
+                "Profit factor is {:.2} {}: losing trades cost more than winning trades earned",
+                overall.profit_factor, period
+            ),
+            evidence: evidence(&[
+                ("profit_factor", overall.profit_factor),
+                ("gross_profit", overall.gross_profit),
+                ("gross_loss", overall.gross_loss),
+            ]),
+            recommendation: Some("Cut losing trades sooner; gross losses currently outweigh gross profits".to_string()),
+        });
+    }
+
+    if overall.winning_trades > 0 && overall.losing_trades > 0 && overall.win_loss_ratio < 1.0 {
+        out.push(RuleFinding {
+            text: format!(
+                "Average loss (${:.2}) is larger than average win (${:.2}), a win/loss ratio of {:.2}",
+                overall.average_loss.abs(),
+                overall.average_win,
+                overall.win_loss_ratio
+            ),
+            evidence: evidence(&[
+                ("average_win", overall.average_win),
+                ("average_loss", overall.average_loss),
+                ("win_loss_ratio", overall.win_loss_ratio),
+            ]),
+            recommendation: Some("Let winners run further or tighten stops so wins outsize losses".to_string()),
+        });
+    }
+
+    if overall.max_consecutive_losses >= LOSING_STREAK {
+        out.push(RuleFinding {
+            text: format!("Longest losing streak {} was {} trades", period, overall.max_consecutive_losses),
+            evidence: evidence(&[("max_consecutive_losses", overall.max_consecutive_losses as f64)]),
+            recommendation: Some(format!(
+                "Set a rule to pause after {} consecutive losses and review before trading again",
+                LOSING_STREAK - 2
+            )),
+        });
+    }
+
+    if overall.gross_profit > 0.0 && overall.total_commissions / overall.gross_profit >= COMMISSION_SHARE {
+        out.push(RuleFinding {
+            text: format!(
+                "Commissions of ${:.2} took {:.0}% of gross profit {}",
+                overall.total_commissions,
+                overall.total_commissions / overall.gross_profit * 100.0,
+                period
+            ),
+            evidence: evidence(&[("total_commissions", overall.total_commissions), ("gross_profit", overall.gross_profit)]),
+            recommendation: Some("Trade less often or move to a cheaper commission plan".to_string()),
+        });
+    }
+
+    if out.is_empty() {
+        out.push(RuleFinding {
+            text: format!(
+                "{} closed trades {} at a {:.0}% win rate with no warning signs in the core metrics",
+                overall.total_trades, period, overall.win_rate
+            ),
+            evidence: evidence(&[("total_trades", overall.total_trades as f64), ("win_rate", overall.win_rate)]),
+            recommendation: None,
+        });
+    }
+    out
+}
+
+/// Build a rule-based insight for the request's time range
+pub async fn generate(conn: &Connection, user_id: &str, request: &InsightRequest) -> Result<Insight> {
+    let start_time = std::time::Instant::now();
+    let range = &request.time_range;
+    let side = |direction| TradeFilter { direction: Some(direction), ..Default::default() };
+    let overall = calculate_filtered_core_metrics(conn, range, &TradeFilter::default()).await?;
+    let long = calculate_filtered_core_metrics(conn, range, &side(Direction::Long)).await?;
+    let short = calculate_filtered_core_metrics(conn, range, &side(Direction::Short)).await?;
+
+    let period = period_label(range);
+    let rules = findings(&overall, &long, &short, &period);
+    let content = format!(
+        "{} closed trades {}: {:.0}% win rate, net P&L ${:.2}. Generated from your analytics without the AI model.",
+        overall.total_trades, period, overall.win_rate, overall.net_profit_loss
+    );
+    let title = match request.insight_type {
+        InsightType::RiskAssessment => "Risk check",
+        InsightType::BehavioralAnalysis => "Behavior check",
+        _ => "Performance check",
+    };
+    // More trades make the same rules more trustworthy
+    let data_quality = (overall.total_trades as f32 / 30.0).min(1.0);
+
+    let mut insight = Insight::new(user_id.to_string(), range.clone(), request.insight_type.clone(), title.to_string(), content)
+        .with_findings(rules.iter().map(|f| f.text.clone()).collect())
+        .with_finding_evidence(rules.iter().map(|f| Some(f.evidence.clone())).collect())
+        .with_recommendations(rules.iter().filter_map(|f| f.recommendation.clone()).collect())
+        .with_confidence(0.5 + 0.4 * data_quality)
+        .with_source(InsightSource::Rules)
+        .with_metadata(InsightMetadata {
+            trade_count: overall.total_trades,
+            analysis_period_days: range.approx_days(),
+            model_version: RULES_MODEL_VERSION.to_string(),
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+            data_quality_score: data_quality,
+        });
+    insight.set_expiration(24);
+    Ok(insight)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(winning: u32, losing: u32, gross_profit: f64, gross_loss: f64) -> CoreMetrics {
+        let total = winning + losing;
+        let average_win = if winning > 0 { gross_profit / winning as f64 } else { 0.0 };
+        let average_loss = if losing > 0 { gross_loss / losing as f64 } else { 0.0 };
+        CoreMetrics {
+            total_trades: total,
+            winning_trades: winning,
+            losing_trades: losing,
+            break_even_trades: 0,
+            win_rate: if total > 0 { winning as f64 / total as f64 * 100.0 } else { 0.0 },
+            loss_rate: if total > 0 { losing as f64 / total as f64 * 100.0 } else { 0.0 },
+            total_pnl: gross_profit + gross_loss,
+            net_profit_loss: gross_profit + gross_loss,
+            gross_profit,
+            gross_loss,
+            average_win,
+            average_loss,
+            average_position_size: 1000.0,
+            biggest_winner: average_win,
+            biggest_loser: average_loss,
+            profit_factor: if gross_loss != 0.0 { gross_profit / gross_loss.abs() } else { f64::INFINITY },
+            win_loss_ratio: if average_loss != 0.0 { average_win / average_loss.abs() } else { f64::INFINITY },
+            max_consecutive_wins: 2,
+            max_consecutive_losses: 2,
+            total_commissions: 0.0,
+            average_commission_per_trade: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_short_side_gap_is_reported() {
+        let long = metrics(6, 4, 600.0, -200.0);
+        let short = metrics(2, 8, 400.0, -300.0);
+        let overall = metrics(8, 12, 1000.0, -500.0);
+
+        let found = findings(&overall, &long, &short, "over the last 30 days");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "Win rate on short trades is 40 points lower than longs over the last 30 days (20% vs 60%)");
+        assert_eq!(found[0].evidence.metrics["short_win_rate"], 20.0);
+    }
+
+    #[test]
+    fn test_losing_period_and_empty_period() {
+        let overall = metrics(3, 2, 100.0, -400.0);
+        let found = findings(&overall, &metrics(3, 2, 100.0, -400.0), &metrics(0, 0, 0.0, 0.0), "over the last 7 days");
+        let texts: Vec<&str> = found.iter().map(|f| f.text.as_str()).collect();
+        assert!(texts[0].starts_with("Profit factor is 0.25 over the last 7 days"));
+        assert!(texts[1].starts_with("Average loss ($200.00) is larger than average win ($33.33)"));
+        assert_eq!(found.iter().filter(|f| f.recommendation.is_some()).count(), 2);
+
+        let empty = metrics(0, 0, 0.0, 0.0);
+        let found = findings(&empty, &empty, &empty, "across all trades");
+        assert_eq!(found[0].text, "No closed trades across all trades");
+        // An infinite profit factor is dropped from evidence rather than stored as null
+        assert!(evidence(&[("profit_factor", f64::INFINITY)]).metrics.is_empty());
+    }
+}
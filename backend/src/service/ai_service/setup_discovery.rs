@@ -0,0 +1,556 @@
+// Setup discovery. Closed trades that are not tagged with any playbook are clustered on
+// their stored embeddings (what the trade looked like, from the vector store) combined with
+// numeric features: time of day, hold time, position size and how much the symbol usually
+// moves. Each large enough cluster is a setup the user keeps trading without having written
+// it down. The model names it and drafts entry/exit rules, and the result is saved as a
+// playbook draft the user can accept (creating the playbook and tagging the trades) or
+// dismiss.
+
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::{Timelike, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::playbook::{CreatePlaybookRequest, CreateRuleRequest, Playbook, PlaybookRule, RuleType};
+use crate::models::timestamps;
+use crate::service::ai_service::openrouter_client::{ChatMessage, MessageRole, OpenRouterClient};
+use crate::service::ai_service::trade_parser_service::extract_json;
+use crate::service::ai_service::upstash_vector_client::DataType;
+use crate::service::ai_service::vectorization_service::VectorizationService;
+
+/// Fewer untagged trades than this are not worth clustering
+const MIN_TRADES: usize = 12;
+/// Smallest cluster reported as a setup
+const MIN_CLUSTER_SIZE: usize = 5;
+const MAX_CLUSTERS: usize = 8;
+const KMEANS_ITERATIONS: usize = 30;
+/// Scale of the unit-length embedding block relative to the standardized numeric features
+const EMBEDDING_WEIGHT: f64 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeAsset {
+    Stock,
+    Option,
+}
+
+/// A closed, untagged trade with the features it is clustered on
+#[derive(Debug, Clone)]
+pub struct TradePoint {
+    pub asset: TradeAsset,
+    pub id: i64,
+    pub symbol: String,
+    pub long: bool,
+    /// Minutes after midnight UTC at entry
+    pub entry_minute: u32,
+    pub hold_hours: f64,
+    pub notional: f64,
+    pub pnl: f64,
+    /// Absolute entry-to-exit move as a fraction of the entry price
+    pub move_pct: f64,
+}
+
+/// What the trades in a cluster have in common
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterProfile {
+    pub trade_count: usize,
+    /// Most traded symbols, most frequent first
+    pub top_symbols: Vec<String>,
+    /// Share of long trades, 0-1
+    pub long_share: f64,
+    /// Median entry time, `HH:MM` UTC
+    pub typical_entry_time: String,
+    pub median_hold_hours: f64,
+    pub median_position_size: f64,
+    /// Median of the symbols' typical move, in percent
+    pub median_symbol_move_pct: f64,
+    pub win_rate: f64,
+    pub total_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftRule {
+    pub rule_type: RuleType,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybookDraft {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub rules: Vec<DraftRule>,
+    pub stock_trade_ids: Vec<i64>,
+    pub option_trade_ids: Vec<i64>,
+    pub profile: ClusterProfile,
+    /// pending, accepted or dismissed
+    pub status: String,
+    /// Playbook created when the draft was accepted
+    pub playbook_id: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveryRun {
+    pub trades_considered: usize,
+    /// False when no stored embeddings were found and only numeric features were used
+    pub used_embeddings: bool,
+    pub clusters: usize,
+    pub drafts: Vec<PlaybookDraft>,
+}
+
+/// The model's label for a cluster
+#[derive(Debug, Deserialize)]
+struct ClusterLabel {
+    name: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    rules: Vec<DraftRule>,
+}
+
+/// Typical move per symbol across all of the user's trades in it
+fn symbol_moves(points: &[TradePoint]) -> HashMap<String, f64> {
+    let mut sums: HashMap<String, (f64, usize)> = HashMap::new();
+    for point in points {
+        let entry = sums.entry(point.symbol.clone()).or_default();
+        entry.0 += point.move_pct;
+        entry.1 += 1;
+    }
+    sums.into_iter().map(|(symbol, (sum, n))| (symbol, sum / n as f64)).collect()
+}
+
+fn standardize(values: &mut [Vec<f64>], column: usize) {
+    let n = values.len() as f64;
+    let mean = values.iter().map(|row| row[column]).sum::<f64>() / n;
+    let std = (values.iter().map(|row| (row[column] - mean).powi(2)).sum::<f64>() / n).sqrt();
+    for row in values.iter_mut() {
+        row[column] = if std > 0.0 { (row[column] - mean) / std } else { 0.0 };
+    }
+}
+
+/// One row per point: cyclic time of day, standardized log hold time, log size and symbol
+/// move, then the weighted unit-length embedding when one is given
+pub fn feature_matrix(points: &[TradePoint], embeddings: &[Option<&Vec<f32>>]) -> Vec<Vec<f64>> {
+    let moves = symbol_moves(points);
+    let mut rows: Vec<Vec<f64>> = points
+        .iter()
+        .map(|p| {
+            let angle = TAU * p.entry_minute as f64 / 1440.0;
+            vec![
+                angle.sin(),
+                angle.cos(),
+                p.hold_hours.max(0.0).ln_1p(),
+                p.notional.abs().ln_1p(),
+                moves.get(&p.symbol).copied().unwrap_or(0.0),
+            ]
+        })
+        .collect();
+    for column in 2..5 {
+        standardize(&mut rows, column);
+    }
+    for (row, embedding) in rows.iter_mut().zip(embeddings) {
+        if let Some(embedding) = embedding {
+            let norm = embedding.iter().map(|v| (*v as f64).powi(2)).sum::<f64>().sqrt().max(f64::EPSILON);
+            row.extend(embedding.iter().map(|v| *v as f64 / norm * EMBEDDING_WEIGHT));
+        }
+    }
+    rows
+}
+
+fn distance(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// Cluster count for `n` points: about sqrt(n/2), at least 2
+pub fn cluster_count(n: usize) -> usize {
+    ((n as f64 / 2.0).sqrt().round() as usize).clamp(2, MAX_CLUSTERS).min(n)
+}
+
+/// k-means with farthest-point seeding from the first row, so a run is reproducible.
+/// Returns each row's cluster index.
+pub fn kmeans(rows: &[Vec<f64>], k: usize) -> Vec<usize> {
+    if rows.is_empty() || k == 0 {
+        return Vec::new();
+    }
+    let mut centroids = vec![rows[0].clone()];
+    while centroids.len() < k {
+        let farthest = rows
+            .iter()
+            .max_by(|a, b| {
+                let da = centroids.iter().map(|c| distance(a, c)).fold(f64::MAX, f64::min);
+                let db = centroids.iter().map(|c| distance(b, c)).fold(f64::MAX, f64::min);
+                da.total_cmp(&db)
+            })
+            .cloned()
+            .unwrap_or_else(|| rows[0].clone());
+        centroids.push(farthest);
+    }
+
+    let mut assignment = vec![usize::MAX; rows.len()];
+    for _ in 0..KMEANS_ITERATIONS {
+        let next: Vec<usize> = rows
+            .iter()
+            .map(|row| {
+                (0..k)
+                    .min_by(|&a, &b| distance(row, &centroids[a]).total_cmp(&distance(row, &centroids[b])))
+                    .unwrap_or(0)
+            })
+            .collect();
+        if next == assignment {
+            break;
+        }
+        assignment = next;
+        for (cluster, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<&Vec<f64>> = rows.iter().zip(&assignment).filter(|(_, a)| **a == cluster).map(|(r, _)| r).collect();
+            if members.is_empty() {
+                continue;
+            }
+            for (d, value) in centroid.iter_mut().enumerate() {
+                *value = members.iter().map(|m| m[d]).sum::<f64>() / members.len() as f64;
+            }
+        }
+    }
+    assignment
+}
+
+fn median(mut values: Vec<f64>) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+pub fn profile(members: &[&TradePoint], moves: &HashMap<String, f64>) -> ClusterProfile {
+    let n = members.len().max(1) as f64;
+    let mut symbol_counts: HashMap<&str, usize> = HashMap::new();
+    for m in members {
+        *symbol_counts.entry(m.symbol.as_str()).or_default() += 1;
+    }
+    let mut symbols: Vec<(&str, usize)> = symbol_counts.into_iter().collect();
+    symbols.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    let entry_minute = median(members.iter().map(|m| m.entry_minute as f64).collect()).round() as u32;
+
+    ClusterProfile {
+        trade_count: members.len(),
+        top_symbols: symbols.into_iter().take(3).map(|(s, _)| s.to_string()).collect(),
+        long_share: members.iter().filter(|m| m.long).count() as f64 / n,
+        typical_entry_time: format!("{:02}:{:02}", entry_minute / 60, entry_minute % 60),
+        median_hold_hours: median(members.iter().map(|m| m.hold_hours).collect()),
+        median_position_size: median(members.iter().map(|m| m.notional.abs()).collect()),
+        median_symbol_move_pct: median(members.iter().map(|m| moves.get(&m.symbol).copied().unwrap_or(0.0) * 100.0).collect()),
+        win_rate: members.iter().filter(|m| m.pnl > 0.0).count() as f64 / n * 100.0,
+        total_pnl: members.iter().map(|m| m.pnl).sum(),
+    }
+}
+
+/// Name used when the model is unavailable, e.g. "Long AAPL around 14:30 (held ~3h)"
+pub fn fallback_name(profile: &ClusterProfile) -> String {
+    let side = match profile.long_share {
+        s if s >= 0.7 => "Long",
+        s if s <= 0.3 => "Short",
+        _ => "Mixed",
+    };
+    let symbol = profile.top_symbols.first().map(String::as_str).unwrap_or("trades");
+    let hold = if profile.median_hold_hours < 24.0 {
+        format!("{:.0}h", profile.median_hold_hours.max(1.0))
+    } else {
+        format!("{:.0}d", profile.median_hold_hours / 24.0)
+    };
+    format!("{} {} around {} (held ~{})", side, symbol, profile.typical_entry_time, hold)
+}
+
+fn label_prompt(profile: &ClusterProfile) -> String {
+    format!(
+        r#"A trader repeatedly takes trades that share these traits but has never written the setup down:
+{}
+
+Name the setup and draft playbook rules for it. Return ONLY a JSON object:
+{{"name": "short setup name", "description": "one or two sentences", "rules": [{{"rule_type": "entry_criteria|exit_criteria|market_factor", "title": "rule"}}]}}
+Give 2-5 rules grounded in the traits above. No markdown, no extra keys."#,
+        serde_json::to_string_pretty(profile).unwrap_or_default()
+    )
+}
+
+async fn load_points(conn: &Connection) -> Result<Vec<TradePoint>> {
+    let queries = [
+        (
+            TradeAsset::Stock,
+            "SELECT id, symbol, trade_type = 'BUY', entry_date, exit_date, CAST(entry_price * number_shares AS REAL),
+                    CAST(COALESCE(realized_pnl, 0) AS REAL), CAST(entry_price AS REAL), CAST(exit_price AS REAL)
+             FROM stocks
+             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+               AND id NOT IN (SELECT stock_trade_id FROM stock_trade_playbook)",
+        ),
+        (
+            TradeAsset::Option,
+            "SELECT id, symbol, trade_direction = 'Bullish', entry_date, exit_date, CAST(total_premium AS REAL),
+                    CAST(COALESCE(realized_pnl, 0) AS REAL), CAST(entry_price AS REAL), CAST(exit_price AS REAL)
+             FROM options
+             WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+               AND id NOT IN (SELECT option_trade_id FROM option_trade_playbook)",
+        ),
+    ];
+
+    let mut points = Vec::new();
+    for (asset, sql) in queries {
+        let mut rows = conn.prepare(sql).await?.query(params![]).await?;
+        while let Some(row) = rows.next().await? {
+            let (Ok(entry), Ok(exit)) = (
+                timestamps::parse_stored(&row.get::<String>(3)?),
+                timestamps::parse_stored(&row.get::<String>(4)?),
+            ) else {
+                continue;
+            };
+            let entry_price = row.get::<f64>(7)?;
+            points.push(TradePoint {
+                asset,
+                id: row.get(0)?,
+                symbol: row.get::<String>(1)?.to_uppercase(),
+                long: row.get::<i64>(2)? != 0,
+                entry_minute: entry.hour() * 60 + entry.minute(),
+                hold_hours: (exit - entry).num_minutes().max(0) as f64 / 60.0,
+                notional: row.get(5)?,
+                pnl: row.get(6)?,
+                move_pct: if entry_price > 0.0 { (row.get::<f64>(8)? - entry_price).abs() / entry_price } else { 0.0 },
+            });
+        }
+    }
+    Ok(points)
+}
+
+fn draft_from_row(row: &libsql::Row) -> Result<PlaybookDraft> {
+    Ok(PlaybookDraft {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        rules: serde_json::from_str(&row.get::<String>(3)?)?,
+        stock_trade_ids: serde_json::from_str(&row.get::<String>(4)?)?,
+        option_trade_ids: serde_json::from_str(&row.get::<String>(5)?)?,
+        profile: serde_json::from_str(&row.get::<String>(6)?)?,
+        status: row.get(7)?,
+        playbook_id: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+const DRAFT_COLUMNS: &str =
+    "id, name, description, rules, stock_trade_ids, option_trade_ids, profile, status, playbook_id, created_at";
+
+pub async fn list_drafts(conn: &Connection, status: Option<&str>) -> Result<Vec<PlaybookDraft>> {
+    let sql = format!(
+        "SELECT {} FROM playbook_drafts WHERE (?1 IS NULL OR status = ?1) ORDER BY created_at DESC",
+        DRAFT_COLUMNS
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![status]).await?;
+    let mut drafts = Vec::new();
+    while let Some(row) = rows.next().await? {
+        drafts.push(draft_from_row(&row)?);
+    }
+    Ok(drafts)
+}
+
+async fn find_draft(conn: &Connection, draft_id: &str) -> Result<Option<PlaybookDraft>> {
+    let sql = format!("SELECT {} FROM playbook_drafts WHERE id = ?", DRAFT_COLUMNS);
+    let mut rows = conn.prepare(&sql).await?.query(params![draft_id]).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(draft_from_row(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Create the playbook and its rules from a pending draft and tag the draft's trades with
+/// it. None when the draft does not exist or was already handled.
+pub async fn accept_draft(conn: &Connection, draft_id: &str) -> Result<Option<Playbook>> {
+    let Some(draft) = find_draft(conn, draft_id).await?.filter(|d| d.status == "pending") else {
+        return Ok(None);
+    };
+    let to_anyhow = |e: Box<dyn std::error::Error + Send + Sync>| anyhow::anyhow!("{}", e);
+
+    let playbook = Playbook::create(
+        conn,
+        CreatePlaybookRequest { name: draft.name.clone(), description: draft.description.clone(), icon: None, emoji: None, color: None },
+    )
+    .await
+    .map_err(to_anyhow)?;
+    for (position, rule) in draft.rules.iter().enumerate() {
+        PlaybookRule::create(
+            conn,
+            &playbook.id,
+            CreateRuleRequest {
+                rule_type: rule.rule_type.clone(),
+                title: rule.title.clone(),
+                description: None,
+                order_position: Some(position as i32),
+            },
+        )
+        .await
+        .map_err(to_anyhow)?;
+    }
+    for id in &draft.stock_trade_ids {
+        Playbook::tag_stock_trade(conn, *id, &playbook.id).await.map_err(to_anyhow)?;
+    }
+    for id in &draft.option_trade_ids {
+        Playbook::tag_option_trade(conn, *id, &playbook.id).await.map_err(to_anyhow)?;
+    }
+    conn.execute(
+        "UPDATE playbook_drafts SET status = 'accepted', playbook_id = ? WHERE id = ?",
+        params![playbook.id.clone(), draft_id],
+    )
+    .await?;
+    Ok(Some(playbook))
+}
+
+/// False when there was no pending draft with this ID
+pub async fn dismiss_draft(conn: &Connection, draft_id: &str) -> Result<bool> {
+    let updated = conn
+        .execute("UPDATE playbook_drafts SET status = 'dismissed' WHERE id = ? AND status = 'pending'", params![draft_id])
+        .await?;
+    Ok(updated > 0)
+}
+
+pub struct SetupDiscoveryService {
+    vectorization_service: Arc<VectorizationService>,
+    openrouter_client: Arc<OpenRouterClient>,
+}
+
+impl SetupDiscoveryService {
+    pub fn new(vectorization_service: Arc<VectorizationService>, openrouter_client: Arc<OpenRouterClient>) -> Self {
+        Self { vectorization_service, openrouter_client }
+    }
+
+    /// Stored embeddings of the points' trades; empty when the vector store has none
+    async fn embeddings(&self, user_id: &str, points: &[TradePoint]) -> HashMap<(TradeAsset, i64), Vec<f32>> {
+        let mut out = HashMap::new();
+        for (asset, data_type) in [(TradeAsset::Stock, DataType::Stock), (TradeAsset::Option, DataType::Option)] {
+            let ids: Vec<String> = points.iter().filter(|p| p.asset == asset).map(|p| p.id.to_string()).collect();
+            if ids.is_empty() {
+                continue;
+            }
+            match self.vectorization_service.fetch_entity_embeddings(user_id, data_type, &ids).await {
+                Ok(found) => out.extend(found.into_iter().filter_map(|(id, v)| Some(((asset, id.parse().ok()?), v)))),
+                Err(e) => log::warn!("Setup discovery without embeddings for user {}: {}", user_id, e),
+            }
+        }
+        out
+    }
+
+    async fn label(&self, profile: &ClusterProfile) -> ClusterLabel {
+        let messages = vec![ChatMessage { role: MessageRole::User, content: label_prompt(profile) }];
+        let labelled = match self.openrouter_client.generate_chat(messages).await {
+            Ok(response) => serde_json::from_str::<ClusterLabel>(extract_json(&response)).map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        match labelled {
+            Ok(label) if !label.name.trim().is_empty() => label,
+            Ok(_) => ClusterLabel { name: fallback_name(profile), description: None, rules: Vec::new() },
+            Err(e) => {
+                log::warn!("Setup discovery could not label a cluster: {}", e);
+                ClusterLabel { name: fallback_name(profile), description: None, rules: Vec::new() }
+            }
+        }
+    }
+
+    /// Cluster the user's untagged trades and replace their pending drafts with new ones
+    pub async fn discover(&self, conn: &Connection, user_id: &str) -> Result<DiscoveryRun> {
+        let mut points = load_points(conn).await?;
+        let embeddings = self.embeddings(user_id, &points).await;
+        // Mixing trades with and without an embedding would cluster them apart by that alone
+        let used_embeddings = !embeddings.is_empty();
+        if used_embeddings {
+            points.retain(|p| embeddings.contains_key(&(p.asset, p.id)));
+        }
+        let mut run = DiscoveryRun { trades_considered: points.len(), used_embeddings, clusters: 0, drafts: Vec::new() };
+        if points.len() < MIN_TRADES {
+            return Ok(run);
+        }
+
+        let k = cluster_count(points.len());
+        let vectors: Vec<Option<&Vec<f32>>> = points.iter().map(|p| embeddings.get(&(p.asset, p.id))).collect();
+        let assignment = kmeans(&feature_matrix(&points, &vectors), k);
+        let moves = symbol_moves(&points);
+
+        conn.execute("DELETE FROM playbook_drafts WHERE status = 'pending'", params![]).await?;
+        for cluster in 0..k {
+            let members: Vec<&TradePoint> = points.iter().zip(&assignment).filter(|(_, a)| **a == cluster).map(|(p, _)| p).collect();
+            if members.len() < MIN_CLUSTER_SIZE {
+                continue;
+            }
+            run.clusters += 1;
+            let traits = profile(&members, &moves);
+            let label = self.label(&traits).await;
+            let ids = |asset| members.iter().filter(|m| m.asset == asset).map(|m| m.id).collect::<Vec<i64>>();
+            let draft = PlaybookDraft {
+                id: Uuid::new_v4().to_string(),
+                name: label.name.trim().to_string(),
+                description: label.description,
+                rules: label.rules,
+                stock_trade_ids: ids(TradeAsset::Stock),
+                option_trade_ids: ids(TradeAsset::Option),
+                profile: traits,
+                status: "pending".to_string(),
+                playbook_id: None,
+                created_at: timestamps::to_db(&Utc::now()),
+            };
+            conn.execute(
+                "INSERT INTO playbook_drafts (id, name, description, rules, stock_trade_ids, option_trade_ids, profile, status, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, 'pending', ?)",
+                params![
+                    draft.id.clone(),
+                    draft.name.clone(),
+                    draft.description.clone(),
+                    serde_json::to_string(&draft.rules)?,
+                    serde_json::to_string(&draft.stock_trade_ids)?,
+                    serde_json::to_string(&draft.option_trade_ids)?,
+                    serde_json::to_string(&draft.profile)?,
+                    draft.created_at.clone()
+                ],
+            )
+            .await?;
+            run.drafts.push(draft);
+        }
+        Ok(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: i64, symbol: &str, entry_minute: u32, hold_hours: f64, pnl: f64) -> TradePoint {
+        TradePoint { asset: TradeAsset::Stock, id, symbol: symbol.to_string(), long: true, entry_minute, hold_hours, notional: 5000.0, pnl, move_pct: 0.02 }
+    }
+
+    #[test]
+    fn test_kmeans_separates_open_scalps_from_afternoon_swings() {
+        let mut points: Vec<TradePoint> = (0..6).map(|i| point(i, "TSLA", 570 + i as u32, 0.5, 50.0)).collect();
+        points.extend((6..12).map(|i| point(i, "MSFT", 900 + i as u32, 72.0, -20.0)));
+        let rows = feature_matrix(&points, &vec![None; points.len()]);
+
+        let assignment = kmeans(&rows, 2);
+        assert!(assignment[..6].iter().all(|a| *a == assignment[0]));
+        assert!(assignment[6..].iter().all(|a| *a == assignment[6]));
+        assert_ne!(assignment[0], assignment[6]);
+
+        let morning: Vec<&TradePoint> = points[..6].iter().collect();
+        let summary = profile(&morning, &symbol_moves(&points));
+        assert_eq!(summary.typical_entry_time, "09:33");
+        assert_eq!(summary.top_symbols, vec!["TSLA".to_string()]);
+        assert_eq!(summary.win_rate, 100.0);
+        assert_eq!(fallback_name(&summary), "Long TSLA around 09:33 (held ~1h)");
+    }
+
+    #[test]
+    fn test_cluster_count_bounds() {
+        assert_eq!(cluster_count(12), 2);
+        assert_eq!(cluster_count(50), 5);
+        assert_eq!(cluster_count(1000), MAX_CLUSTERS);
+    }
+}
@@ -0,0 +1,321 @@
+// Similar trades. For a trade under review, the closest closed trades in the journal by what
+// the trade looked like (the stored embedding of its notes and details) and how it was
+// traded (time of day, hold time, size and how much the symbol moves, the same numeric
+// features setup discovery clusters on). The matches come back with their outcomes and the
+// mistakes they share with the trade, so a repeated mistake shows up as a pattern.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{Timelike, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::models::timestamps;
+use crate::service::ai_service::setup_discovery::{feature_matrix, TradeAsset, TradePoint};
+use crate::service::ai_service::upstash_vector_client::DataType;
+use crate::service::ai_service::vectorization_service::VectorizationService;
+
+pub const DEFAULT_K: usize = 10;
+pub const MAX_K: usize = 50;
+/// Most recent closed trades compared against
+const MAX_CANDIDATES: i64 = 500;
+/// Weight of embedding similarity when both trades have one; the rest is numeric similarity
+const EMBEDDING_SHARE: f64 = 0.6;
+
+#[derive(Debug, Clone)]
+struct TradeRecord {
+    point: TradePoint,
+    entry_date: String,
+    exit_date: Option<String>,
+    realized_pnl: Option<f64>,
+    mistakes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarTrade {
+    pub trade_type: TradeAsset,
+    pub id: i64,
+    pub symbol: String,
+    pub entry_date: String,
+    pub exit_date: Option<String>,
+    pub realized_pnl: f64,
+    /// 0-1, higher is closer
+    pub similarity: f64,
+    pub mistakes: Vec<String>,
+    /// Mistakes also logged on the reviewed trade
+    pub shared_mistakes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MistakeCount {
+    pub mistake: String,
+    pub trades: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutcomeSummary {
+    pub trades: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub win_rate: f64,
+    pub average_pnl: f64,
+    pub total_pnl: f64,
+    /// The reviewed trade's mistakes, by how many similar trades repeat them
+    pub recurring_mistakes: Vec<MistakeCount>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimilarTrades {
+    pub trade_type: TradeAsset,
+    pub trade_id: i64,
+    pub symbol: String,
+    pub realized_pnl: Option<f64>,
+    pub mistakes: Vec<String>,
+    /// False when the vector store had no embedding for the trade and only numeric features were used
+    pub used_embeddings: bool,
+    pub similar: Vec<SimilarTrade>,
+    pub summary: OutcomeSummary,
+}
+
+/// Free-text mistakes list ("FOMO, moved stop; early exit") as normalized entries
+pub fn parse_mistakes(raw: Option<&str>) -> Vec<String> {
+    let mut mistakes: Vec<String> = Vec::new();
+    for entry in raw.unwrap_or("").split([',', ';', '\n']) {
+        let entry = entry.trim().to_lowercase();
+        if !entry.is_empty() && !mistakes.contains(&entry) {
+            mistakes.push(entry);
+        }
+    }
+    mistakes
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> Option<f64> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot: f64 = a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum();
+    let norm_a = a.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    let norm_b = b.iter().map(|x| (*x as f64).powi(2)).sum::<f64>().sqrt();
+    (norm_a > 0.0 && norm_b > 0.0).then(|| dot / (norm_a * norm_b))
+}
+
+/// Blend of numeric closeness (1 at identical features) and, when both trades have
+/// embeddings, their cosine similarity mapped onto 0-1
+pub fn similarity(numeric_a: &[f64], numeric_b: &[f64], embeddings: Option<(&[f32], &[f32])>) -> f64 {
+    let distance = numeric_a.iter().zip(numeric_b).map(|(x, y)| (x - y).powi(2)).sum::<f64>().sqrt();
+    let numeric = 1.0 / (1.0 + distance);
+    match embeddings.and_then(|(a, b)| cosine(a, b)) {
+        Some(cos) => EMBEDDING_SHARE * (cos + 1.0) / 2.0 + (1.0 - EMBEDDING_SHARE) * numeric,
+        None => numeric,
+    }
+}
+
+pub fn summarize(target_mistakes: &[String], similar: &[SimilarTrade]) -> OutcomeSummary {
+    let wins = similar.iter().filter(|t| t.realized_pnl > 0.0).count();
+    let losses = similar.iter().filter(|t| t.realized_pnl < 0.0).count();
+    let total_pnl: f64 = similar.iter().map(|t| t.realized_pnl).sum();
+    let mut recurring_mistakes: Vec<MistakeCount> = target_mistakes
+        .iter()
+        .map(|mistake| MistakeCount { mistake: mistake.clone(), trades: similar.iter().filter(|t| t.mistakes.contains(mistake)).count() })
+        .filter(|m| m.trades > 0)
+        .collect();
+    recurring_mistakes.sort_by_key(|m| Reverse(m.trades));
+    OutcomeSummary {
+        trades: similar.len(),
+        wins,
+        losses,
+        win_rate: if similar.is_empty() { 0.0 } else { wins as f64 / similar.len() as f64 * 100.0 },
+        average_pnl: if similar.is_empty() { 0.0 } else { total_pnl / similar.len() as f64 },
+        total_pnl,
+        recurring_mistakes,
+    }
+}
+
+const STOCK_COLUMNS: &str = "id, symbol, trade_type = 'BUY', entry_date, exit_date, CAST(entry_price * number_shares AS REAL),
+    CAST(realized_pnl AS REAL), CAST(entry_price AS REAL), CAST(exit_price AS REAL), mistakes";
+const OPTION_COLUMNS: &str = "id, symbol, trade_direction = 'Bullish', entry_date, exit_date, CAST(total_premium AS REAL),
+    CAST(realized_pnl AS REAL), CAST(entry_price AS REAL), CAST(exit_price AS REAL), mistakes";
+
+fn record_from_row(asset: TradeAsset, row: &libsql::Row) -> Result<Option<TradeRecord>> {
+    let entry_date: String = row.get(3)?;
+    let exit_date: Option<String> = row.get(4)?;
+    let Ok(entry) = timestamps::parse_stored(&entry_date) else { return Ok(None) };
+    // Open trades are measured up to now
+    let exit = exit_date.as_deref().and_then(|d| timestamps::parse_stored(d).ok()).unwrap_or_else(Utc::now);
+    let entry_price: f64 = row.get(7)?;
+    let exit_price: Option<f64> = row.get(8)?;
+    let realized_pnl: Option<f64> = row.get(6)?;
+    Ok(Some(TradeRecord {
+        point: TradePoint {
+            asset,
+            id: row.get(0)?,
+            symbol: row.get::<String>(1)?.to_uppercase(),
+            long: row.get::<i64>(2)? != 0,
+            entry_minute: entry.hour() * 60 + entry.minute(),
+            hold_hours: (exit - entry).num_minutes().max(0) as f64 / 60.0,
+            notional: row.get(5)?,
+            pnl: realized_pnl.unwrap_or(0.0),
+            move_pct: match exit_price {
+                Some(exit_price) if entry_price > 0.0 => (exit_price - entry_price).abs() / entry_price,
+                _ => 0.0,
+            },
+        },
+        entry_date,
+        exit_date,
+        realized_pnl,
+        mistakes: parse_mistakes(row.get::<Option<String>>(9)?.as_deref()),
+    }))
+}
+
+async fn load_trade(conn: &Connection, asset: TradeAsset, id: i64) -> Result<Option<TradeRecord>> {
+    let sql = match asset {
+        TradeAsset::Stock => format!("SELECT {} FROM stocks WHERE id = ? AND is_deleted = 0", STOCK_COLUMNS),
+        TradeAsset::Option => format!("SELECT {} FROM options WHERE id = ? AND is_deleted = 0", OPTION_COLUMNS),
+    };
+    let mut rows = conn.query(&sql, params![id]).await?;
+    match rows.next().await? {
+        Some(row) => record_from_row(asset, &row),
+        None => Ok(None),
+    }
+}
+
+/// Closed trades of both kinds, most recently closed first, without the reviewed trade
+async fn load_candidates(conn: &Connection, exclude: (TradeAsset, i64)) -> Result<Vec<TradeRecord>> {
+    let queries = [
+        (
+            TradeAsset::Stock,
+            format!("SELECT {} FROM stocks WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 ORDER BY exit_date DESC LIMIT ?", STOCK_COLUMNS),
+        ),
+        (
+            TradeAsset::Option,
+            format!(
+                "SELECT {} FROM options WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 ORDER BY exit_date DESC LIMIT ?",
+                OPTION_COLUMNS
+            ),
+        ),
+    ];
+    let mut records = Vec::new();
+    for (asset, sql) in queries {
+        let mut rows = conn.query(&sql, params![MAX_CANDIDATES]).await?;
+        while let Some(row) = rows.next().await? {
+            if let Some(record) = record_from_row(asset, &row)?
+                && (record.point.asset, record.point.id) != exclude
+            {
+                records.push(record);
+            }
+        }
+    }
+    Ok(records)
+}
+
+async fn embeddings(vectorization: &VectorizationService, user_id: &str, records: &[&TradeRecord]) -> HashMap<(TradeAsset, i64), Vec<f32>> {
+    let mut out = HashMap::new();
+    for (asset, data_type) in [(TradeAsset::Stock, DataType::Stock), (TradeAsset::Option, DataType::Option)] {
+        let ids: Vec<String> = records.iter().filter(|r| r.point.asset == asset).map(|r| r.point.id.to_string()).collect();
+        if ids.is_empty() {
+            continue;
+        }
+        match vectorization.fetch_entity_embeddings(user_id, data_type, &ids).await {
+            Ok(found) => out.extend(found.into_iter().filter_map(|(id, v)| Some(((asset, id.parse().ok()?), v)))),
+            Err(e) => log::warn!("Similar trades without embeddings for user {}: {}", user_id, e),
+        }
+    }
+    out
+}
+
+/// The `k` closed trades most like the given one; None when the trade doesn't exist
+pub async fn find_similar(
+    conn: &Connection,
+    vectorization: &VectorizationService,
+    user_id: &str,
+    asset: TradeAsset,
+    trade_id: i64,
+    k: usize,
+) -> Result<Option<SimilarTrades>> {
+    let Some(target) = load_trade(conn, asset, trade_id).await? else { return Ok(None) };
+    let candidates = load_candidates(conn, (asset, trade_id)).await?;
+
+    let records: Vec<&TradeRecord> = std::iter::once(&target).chain(candidates.iter()).collect();
+    let points: Vec<TradePoint> = records.iter().map(|r| r.point.clone()).collect();
+    let numeric = feature_matrix(&points, &vec![None; points.len()]);
+    let embeddings = embeddings(vectorization, user_id, &records).await;
+    let target_embedding = embeddings.get(&(asset, trade_id));
+
+    let mut similar: Vec<SimilarTrade> = candidates
+        .iter()
+        .zip(numeric.iter().skip(1))
+        .map(|(candidate, row)| {
+            let pair = target_embedding
+                .zip(embeddings.get(&(candidate.point.asset, candidate.point.id)))
+                .map(|(a, b)| (a.as_slice(), b.as_slice()));
+            SimilarTrade {
+                trade_type: candidate.point.asset,
+                id: candidate.point.id,
+                symbol: candidate.point.symbol.clone(),
+                entry_date: candidate.entry_date.clone(),
+                exit_date: candidate.exit_date.clone(),
+                realized_pnl: candidate.realized_pnl.unwrap_or(0.0),
+                similarity: similarity(&numeric[0], row, pair),
+                shared_mistakes: candidate.mistakes.iter().filter(|m| target.mistakes.contains(m)).cloned().collect(),
+                mistakes: candidate.mistakes.clone(),
+            }
+        })
+        .collect();
+    similar.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap_or(std::cmp::Ordering::Equal));
+    similar.truncate(k);
+
+    let summary = summarize(&target.mistakes, &similar);
+    Ok(Some(SimilarTrades {
+        trade_type: asset,
+        trade_id,
+        symbol: target.point.symbol.clone(),
+        realized_pnl: target.realized_pnl,
+        mistakes: target.mistakes.clone(),
+        used_embeddings: target_embedding.is_some(),
+        similar,
+        summary,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: i64, pnl: f64, mistakes: &str) -> SimilarTrade {
+        SimilarTrade {
+            trade_type: TradeAsset::Stock,
+            id,
+            symbol: "AAPL".to_string(),
+            entry_date: String::new(),
+            exit_date: None,
+            realized_pnl: pnl,
+            similarity: 0.5,
+            mistakes: parse_mistakes(Some(mistakes)),
+            shared_mistakes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_similarity_prefers_close_features_and_embeddings() {
+        let a = [0.0, 1.0, 0.5];
+        assert_eq!(similarity(&a, &a, None), 1.0);
+        assert!(similarity(&a, &[0.0, 1.0, 0.6], None) > similarity(&a, &[2.0, -1.0, 3.0], None));
+        let (e1, e2, e3) = ([1.0f32, 0.0], [1.0f32, 0.1], [-1.0f32, 0.0]);
+        assert!(similarity(&a, &a, Some((&e1, &e2))) > similarity(&a, &a, Some((&e1, &e3))));
+    }
+
+    #[test]
+    fn test_summarize_counts_recurring_mistakes() {
+        let target = parse_mistakes(Some("FOMO, Moved stop"));
+        let similar = vec![trade(1, -50.0, "fomo; chased"), trade(2, 30.0, ""), trade(3, -20.0, "moved stop, FOMO")];
+        let summary = summarize(&target, &similar);
+        assert_eq!((summary.wins, summary.losses), (1, 2));
+        assert_eq!(summary.total_pnl, -40.0);
+        assert_eq!(summary.recurring_mistakes, vec![
+            MistakeCount { mistake: "fomo".to_string(), trades: 2 },
+            MistakeCount { mistake: "moved stop".to_string(), trades: 1 },
+        ]);
+    }
+}
@@ -0,0 +1,323 @@
+use anyhow::Result;
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use crate::service::ai_service::openrouter_client::{OpenRouterClient, ChatMessage, MessageRole};
+
+/// Longest free-text entry accepted by the parser
+pub const MAX_TRADE_TEXT_LENGTH: usize = 500;
+
+/// Raw model output. Unknown fields are rejected so a drifting model fails loudly
+/// instead of silently dropping data.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ParsedTradeOutput {
+    asset_type: String,
+    symbol: String,
+    side: String,
+    quantity: f64,
+    entry_price: Option<f64>,
+    exit_price: Option<f64>,
+    entry_date: Option<String>,
+    exit_date: Option<String>,
+    stop_loss: Option<f64>,
+    take_profit: Option<f64>,
+    commissions: Option<f64>,
+    option_type: Option<String>,
+    strike_price: Option<f64>,
+    expiration_date: Option<String>,
+    confidence: f64,
+}
+
+/// Validated trade draft returned to the client for confirmation; never saved directly
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeDraft {
+    /// "stock" or "option"
+    pub asset_type: String,
+    pub symbol: String,
+    /// "buy" (long) or "sell" (short)
+    pub side: String,
+    pub quantity: f64,
+    pub entry_price: Option<f64>,
+    pub exit_price: Option<f64>,
+    pub entry_date: Option<String>,
+    pub exit_date: Option<String>,
+    pub stop_loss: Option<f64>,
+    pub take_profit: Option<f64>,
+    pub commissions: f64,
+    /// "Call" or "Put", matching `OptionType`
+    pub option_type: Option<String>,
+    pub strike_price: Option<f64>,
+    pub expiration_date: Option<String>,
+    pub confidence: f64,
+    /// Fields the client must fill in before the draft can be saved
+    pub missing_fields: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+/// Turns free-text trade descriptions into structured drafts
+pub struct TradeParserService {
+    openrouter_client: Arc<OpenRouterClient>,
+}
+
+impl TradeParserService {
+    pub fn new(openrouter_client: Arc<OpenRouterClient>) -> Self {
+        Self { openrouter_client }
+    }
+
+    /// Parse a trade description like "sold 2 SPY 500c for 3.20, bought at 2.10 yesterday"
+    pub async fn parse(&self, text: &str) -> Result<TradeDraft> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("Trade text is required"));
+        }
+        if text.len() > MAX_TRADE_TEXT_LENGTH {
+            return Err(anyhow::anyhow!("Trade text must be at most {} characters", MAX_TRADE_TEXT_LENGTH));
+        }
+
+        let today = Utc::now().date_naive();
+        let messages = vec![
+            ChatMessage {
+                role: MessageRole::System,
+                content: build_system_prompt(today),
+            },
+            ChatMessage {
+                role: MessageRole::User,
+                content: text.to_string(),
+            },
+        ];
+
+        let response = self.openrouter_client.generate_chat(messages).await?;
+        log::debug!("Trade parser response (first 200 chars): {}",
+                   response.chars().take(200).collect::<String>());
+
+        let output: ParsedTradeOutput = serde_json::from_str(extract_json(&response))
+            .map_err(|e| anyhow::anyhow!("Could not understand that trade, try adding symbol, quantity and price ({})", e))?;
+
+        validate_output(output, today).map_err(|e| anyhow::anyhow!(e))
+    }
+}
+
+fn build_system_prompt(today: NaiveDate) -> String {
+    format!(
+        r#"You convert a trader's shorthand description of ONE trade into JSON. Today is {today}.
+Return ONLY a JSON object with exactly these keys (use null when not stated, never invent values):
+
+{{
+  "asset_type": "stock" | "option",
+  "symbol": "underlying ticker, uppercase",
+  "side": "buy" | "sell",
+  "quantity": number (shares, or contracts for options),
+  "entry_price": number | null,
+  "exit_price": number | null,
+  "entry_date": "YYYY-MM-DD" | null,
+  "exit_date": "YYYY-MM-DD" | null,
+  "stop_loss": number | null,
+  "take_profit": number | null,
+  "commissions": number | null,
+  "option_type": "Call" | "Put" | null,
+  "strike_price": number | null,
+  "expiration_date": "YYYY-MM-DD" | null,
+  "confidence": number between 0 and 1
+}}
+
+Rules:
+- "side" is the opening side: a long position that was later sold is "buy" with an exit_price.
+- "500c" means a 500 strike call, "420p" a 420 strike put; option prices are per share.
+- Resolve relative dates ("yesterday", "last friday") against today.
+- No markdown, no comments, no extra keys."#
+    )
+}
+
+/// Strip markdown fences or surrounding prose the model may add around the object
+pub(crate) fn extract_json(response: &str) -> &str {
+    let trimmed = response.trim();
+    match (trimmed.find('{'), trimmed.rfind('}')) {
+        (Some(start), Some(end)) if end > start => &trimmed[start..=end],
+        _ => trimmed,
+    }
+}
+
+fn parse_date(field: &str, value: Option<String>) -> Result<Option<NaiveDate>, String> {
+    match value {
+        Some(v) => NaiveDate::parse_from_str(&v, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| format!("{} must be YYYY-MM-DD", field)),
+        None => Ok(None),
+    }
+}
+
+fn positive(field: &str, value: Option<f64>) -> Result<Option<f64>, String> {
+    match value {
+        Some(v) if !v.is_finite() || v <= 0.0 => Err(format!("{} must be greater than 0", field)),
+        other => Ok(other),
+    }
+}
+
+/// Check the model output against the trade models' constraints
+fn validate_output(output: ParsedTradeOutput, today: NaiveDate) -> Result<TradeDraft, String> {
+    let asset_type = output.asset_type.to_lowercase();
+    if asset_type != "stock" && asset_type != "option" {
+        return Err(format!("Unsupported asset type: {}", output.asset_type));
+    }
+
+    let symbol = output.symbol.trim().to_uppercase();
+    if symbol.is_empty() || symbol.len() > 10 || !symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+        return Err(format!("Invalid symbol: {}", output.symbol));
+    }
+
+    let side = output.side.to_lowercase();
+    if side != "buy" && side != "sell" {
+        return Err(format!("Side must be buy or sell, got {}", output.side));
+    }
+
+    if !output.quantity.is_finite() || output.quantity <= 0.0 {
+        return Err("Quantity must be greater than 0".to_string());
+    }
+
+    let entry_price = positive("entry_price", output.entry_price)?;
+    let exit_price = positive("exit_price", output.exit_price)?;
+    let stop_loss = positive("stop_loss", output.stop_loss)?;
+    let take_profit = positive("take_profit", output.take_profit)?;
+    let strike_price = positive("strike_price", output.strike_price)?;
+    let commissions = output.commissions.unwrap_or(0.0);
+    if !commissions.is_finite() || commissions < 0.0 {
+        return Err("Commissions cannot be negative".to_string());
+    }
+
+    let entry_date = parse_date("entry_date", output.entry_date)?;
+    let exit_date = parse_date("exit_date", output.exit_date)?;
+    let expiration_date = parse_date("expiration_date", output.expiration_date)?;
+
+    let mut missing_fields = Vec::new();
+    let mut warnings = Vec::new();
+
+    if entry_price.is_none() {
+        missing_fields.push("entryPrice".to_string());
+    }
+    if entry_date.is_none() {
+        missing_fields.push("entryDate".to_string());
+    }
+    if entry_date.is_some_and(|d| d > today) || exit_date.is_some_and(|d| d > today) {
+        warnings.push("Trade date is in the future".to_string());
+    }
+    if let (Some(entry), Some(exit)) = (entry_date, exit_date)
+        && exit < entry
+    {
+        return Err("exit_date cannot be before entry_date".to_string());
+    }
+
+    let is_option = asset_type == "option";
+    let option_type = if is_option {
+        let option_type = match output.option_type.as_deref().map(|t| t.to_lowercase()) {
+            Some(t) if t == "call" => Some("Call".to_string()),
+            Some(t) if t == "put" => Some("Put".to_string()),
+            Some(t) => return Err(format!("Invalid option type: {}", t)),
+            None => None,
+        };
+        if option_type.is_none() {
+            missing_fields.push("optionType".to_string());
+        }
+        if strike_price.is_none() {
+            missing_fields.push("strikePrice".to_string());
+        }
+        if expiration_date.is_none() {
+            missing_fields.push("expirationDate".to_string());
+        }
+        if output.quantity.fract() != 0.0 {
+            return Err("Option contracts must be a whole number".to_string());
+        }
+        option_type
+    } else {
+        // Stock trades require a stop loss when saved
+        if stop_loss.is_none() {
+            missing_fields.push("stopLoss".to_string());
+        }
+        None
+    };
+
+    if let (Some(entry), Some(stop)) = (entry_price, stop_loss) {
+        let wrong_side = if side == "buy" { stop >= entry } else { stop <= entry };
+        if wrong_side {
+            warnings.push("Stop loss is on the wrong side of the entry price".to_string());
+        }
+    }
+
+    let confidence = output.confidence.clamp(0.0, 1.0);
+    if confidence < 0.5 {
+        warnings.push("Low confidence parse, double-check every field".to_string());
+    }
+
+    Ok(TradeDraft {
+        asset_type,
+        symbol,
+        side,
+        quantity: output.quantity,
+        entry_price,
+        exit_price,
+        entry_date: entry_date.map(|d| d.to_string()),
+        exit_date: exit_date.map(|d| d.to_string()),
+        stop_loss,
+        take_profit,
+        commissions,
+        option_type,
+        strike_price: if is_option { strike_price } else { None },
+        expiration_date: if is_option { expiration_date.map(|d| d.to_string()) } else { None },
+        confidence,
+        missing_fields,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 15).unwrap()
+    }
+
+    #[test]
+    fn test_extract_json_strips_fences() {
+        let response = "```json\n{\"symbol\": \"SPY\"}\n```";
+        assert_eq!(extract_json(response), "{\"symbol\": \"SPY\"}");
+    }
+
+    #[test]
+    fn test_validate_option_draft() {
+        let raw = r#"{"asset_type":"option","symbol":"spy","side":"buy","quantity":2,"entry_price":2.10,
+            "exit_price":3.20,"entry_date":"2024-03-14","exit_date":"2024-03-15","stop_loss":null,
+            "take_profit":null,"commissions":null,"option_type":"call","strike_price":500,
+            "expiration_date":"2024-03-22","confidence":0.9}"#;
+        let draft = validate_output(serde_json::from_str(raw).unwrap(), today()).unwrap();
+        assert_eq!(draft.symbol, "SPY");
+        assert_eq!(draft.option_type.as_deref(), Some("Call"));
+        assert_eq!(draft.strike_price, Some(500.0));
+        assert!(draft.missing_fields.is_empty());
+        assert!(draft.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_validate_reports_missing_fields() {
+        let raw = r#"{"asset_type":"stock","symbol":"AAPL","side":"buy","quantity":100,"entry_price":182.45,
+            "exit_price":null,"entry_date":null,"exit_date":null,"stop_loss":null,"take_profit":null,
+            "commissions":null,"option_type":null,"strike_price":null,"expiration_date":null,"confidence":0.8}"#;
+        let draft = validate_output(serde_json::from_str(raw).unwrap(), today()).unwrap();
+        assert_eq!(draft.missing_fields, vec!["entryDate", "stopLoss"]);
+    }
+
+    #[test]
+    fn test_rejects_unknown_fields_and_bad_values() {
+        let extra = r#"{"asset_type":"stock","symbol":"AAPL","side":"buy","quantity":1,"entry_price":1,
+            "exit_price":null,"entry_date":null,"exit_date":null,"stop_loss":null,"take_profit":null,
+            "commissions":null,"option_type":null,"strike_price":null,"expiration_date":null,"confidence":1,
+            "note":"extra"}"#;
+        assert!(serde_json::from_str::<ParsedTradeOutput>(extra).is_err());
+
+        let negative = r#"{"asset_type":"stock","symbol":"AAPL","side":"buy","quantity":-5,"entry_price":1,
+            "exit_price":null,"entry_date":null,"exit_date":null,"stop_loss":null,"take_profit":null,
+            "commissions":null,"option_type":null,"strike_price":null,"expiration_date":null,"confidence":1}"#;
+        assert!(validate_output(serde_json::from_str(negative).unwrap(), today()).is_err());
+    }
+}
@@ -18,6 +18,11 @@ pub struct VectorMetadata {
     pub timestamp: DateTime<Utc>,
     pub tags: Vec<String>,
     pub content_hash: String,
+    /// Position of this vector within a chunked note; absent for single-vector entities
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_index: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_count: Option<u32>,
 }
 
 // Custom serializer for DataType to ensure lowercase string
@@ -93,6 +98,28 @@ pub struct VectorMatch {
     pub metadata: Option<VectorMetadata>,
 }
 
+/// Request structure for Upstash Vector fetch by ID
+#[derive(Debug, Serialize)]
+pub struct FetchRequest<'a> {
+    pub ids: &'a [String],
+    #[serde(rename = "includeVectors")]
+    pub include_vectors: bool,
+}
+
+/// Response structure from Upstash Vector fetch; missing IDs come back as null
+#[derive(Debug, Deserialize)]
+pub struct FetchResponse {
+    #[serde(default)]
+    pub result: Vec<Option<FetchedVector>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FetchedVector {
+    pub id: String,
+    #[serde(default)]
+    pub vector: Vec<f32>,
+}
+
 /// Upstash Vector API client
 pub struct UpstashVectorClient {
     config: VectorConfig,
@@ -313,6 +340,41 @@ impl UpstashVectorClient {
         }
     }
 
+    /// Fetch stored vectors by ID; IDs that do not exist are skipped
+    pub async fn fetch_vectors(&self, namespace: &str, vector_ids: &[String]) -> Result<Vec<FetchedVector>> {
+        if vector_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let url = format!("{}/fetch/{}", self.config.get_base_url(), namespace);
+        let request = FetchRequest { ids: vector_ids, include_vectors: true };
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", format!("Bearer {}", self.config.token))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await
+            .context("Failed to send fetch request to Upstash Vector")?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Upstash Vector fetch error: {} - {}",
+                status,
+                error_text
+            ));
+        }
+
+        let fetched: FetchResponse = response
+            .json()
+            .await
+            .context("Failed to parse Upstash Vector fetch response")?;
+        Ok(fetched.result.into_iter().flatten().filter(|v| !v.vector.is_empty()).collect())
+    }
+
     /// Make upsert request to Upstash Vector API
     async fn make_upsert_request(&self, namespace: &str, request: &UpsertRequest) -> Result<()> {
         let url = format!("{}/upsert/{}", self.config.get_base_url(), namespace);
@@ -450,6 +512,8 @@ impl VectorMatch {
             timestamp: Utc::now(),
             tags: Vec::new(),
             content_hash: String::new(),
+            chunk_index: None,
+            chunk_count: None,
         })
     }
 }
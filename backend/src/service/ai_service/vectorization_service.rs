@@ -4,6 +4,13 @@ use crate::service::ai_service::voyager_client::VoyagerClient;
 use crate::service::ai_service::upstash_vector_client::{UpstashVectorClient, VectorMetadata, DataType};
 use crate::service::ai_service::qdrant_client::{QdrantDocumentClient, Document, DocumentMetadata};
 use crate::service::ai_service::data_formatter::{DataFormatter, DataType as FormatterDataType};
+use crate::service::ai_service::content_extraction::{extract_plain_text, load_image_captions, ImageCaptions};
+use crate::service::ai_service::note_chunking::{
+    all_chunk_vector_ids, blocks_from_content, chunk_blocks, chunk_query_top_k, chunk_vector_id, merge_chunk_matches,
+    CHUNK_OVERLAP_CHARS, CHUNK_TARGET_CHARS, MAX_CHUNKS_PER_NOTE,
+};
+use crate::models::notebook::notebook_note::NotebookNote;
+use crate::models::notes::TradeNote;
 use crate::turso::vector_config::AIConfig;
 use anyhow::{Context, Result};
 use chrono::Utc;
@@ -22,6 +29,14 @@ fn convert_data_type(data_type: &DataType) -> FormatterDataType {
     }
 }
 
+/// Notes arrive as editor HTML/JSON; only their readable text should be embedded
+fn embedding_text<'a>(data_type: &DataType, content: &'a str) -> std::borrow::Cow<'a, str> {
+    match data_type {
+        DataType::TradeNote | DataType::NotebookEntry => extract_plain_text(content, &ImageCaptions::new()).into(),
+        _ => content.into(),
+    }
+}
+
 /// Vectorization task for processing
 #[derive(Debug, Clone)]
 pub struct VectorizationTask {
@@ -85,6 +100,8 @@ impl VectorizationService {
         let start_time = std::time::Instant::now();
         let task_id = Uuid::new_v4().to_string();
         let vector_id = format!("{}_{}_{}", user_id, data_type_to_string(&data_type), entity_id);
+        let content = embedding_text(&data_type, content);
+        let content = content.as_ref();
 
         // Validate content
         DataFormatter::validate_content(content)
@@ -116,6 +133,8 @@ impl VectorizationService {
             timestamp: Utc::now(),
             tags: DataFormatter::extract_tags(content, &convert_data_type(&data_type)),
             content_hash: content_hash.clone(),
+            chunk_index: None,
+            chunk_count: None,
         };
 
         log::debug!(
@@ -191,6 +210,164 @@ impl VectorizationService {
         })
     }
 
+    /// Embed a trade note's readable text, with attached images replaced by their captions
+    pub async fn vectorize_trade_note(
+        &self,
+        conn: &libsql::Connection,
+        user_id: &str,
+        note: &TradeNote,
+    ) -> Result<VectorizationResult> {
+        let captions = load_image_captions(conn, &note.id).await.unwrap_or_else(|e| {
+            log::warn!("Failed to load image captions for note {}: {}", note.id, e);
+            ImageCaptions::new()
+        });
+        let content = DataFormatter::format_trade_note_with_captions(note, &captions);
+        self.vectorize_data(user_id, DataType::TradeNote, &note.id, &content).await
+    }
+
+    /// Embed a notebook note as heading/paragraph chunks. Each chunk is its own vector (and
+    /// Qdrant document) sharing the note's entity id; stale chunks from a longer previous
+    /// version are removed.
+    pub async fn vectorize_notebook_note(&self, user_id: &str, note: &NotebookNote) -> Result<Vec<VectorizationResult>> {
+        let start_time = std::time::Instant::now();
+        let base_id = format!("{}_{}_{}", user_id, data_type_to_string(&DataType::NotebookEntry), note.id);
+
+        let blocks = blocks_from_content(&note.content, &ImageCaptions::new());
+        let mut chunks = chunk_blocks(&blocks, CHUNK_TARGET_CHARS, CHUNK_OVERLAP_CHARS);
+        if chunks.len() > MAX_CHUNKS_PER_NOTE {
+            log::warn!(
+                "Notebook note {} has {} chunks, only the first {} are embedded",
+                note.id, chunks.len(), MAX_CHUNKS_PER_NOTE
+            );
+            chunks.truncate(MAX_CHUNKS_PER_NOTE);
+        }
+        let chunk_count = chunks.len();
+
+        let texts: Vec<String> = chunks
+            .iter()
+            .map(|chunk| match &chunk.heading {
+                Some(heading) if !chunk.text.starts_with(heading.as_str()) => {
+                    format!("Notebook entry: {} › {} - {}", note.title, heading, chunk.text)
+                }
+                _ => format!("Notebook entry: {} - {}", note.title, chunk.text),
+            })
+            .collect();
+        if texts.is_empty() {
+            // Nothing to embed; make sure an emptied note doesn't keep old vectors around
+            self.delete_notebook_note_vectors(user_id, &note.id).await?;
+            return Ok(vec![]);
+        }
+
+        let embeddings = self.voyager_client
+            .embed_texts(&texts)
+            .await
+            .context("Failed to generate notebook chunk embeddings")?;
+        if embeddings.len() != texts.len() {
+            anyhow::bail!("Expected {} chunk embeddings, got {}", texts.len(), embeddings.len());
+        }
+
+        let namespace = self.upstash_vector.get_user_namespace(user_id);
+        let tags = DataFormatter::extract_tags(&texts.join("\n"), &FormatterDataType::NotebookEntry);
+        let mut vectors = Vec::with_capacity(chunk_count);
+        let mut documents = Vec::with_capacity(chunk_count);
+
+        for ((chunk, text), embedding) in chunks.iter().zip(&texts).zip(embeddings) {
+            let vector_id = chunk_vector_id(&base_id, chunk.index, chunk_count);
+            let content_hash = DataFormatter::generate_content_hash(text);
+            let (chunk_index, chunk_total) = if chunk_count > 1 {
+                (Some(chunk.index as u32), Some(chunk_count as u32))
+            } else {
+                (None, None)
+            };
+
+            vectors.push((vector_id.clone(), embedding, VectorMetadata {
+                user_id: user_id.to_string(),
+                data_type: DataType::NotebookEntry,
+                entity_id: note.id.clone(),
+                timestamp: Utc::now(),
+                tags: tags.clone(),
+                content_hash: content_hash.clone(),
+                chunk_index,
+                chunk_count: chunk_total,
+            }));
+
+            let mut content = std::collections::HashMap::from([
+                ("content".to_string(), text.clone()),
+                ("title".to_string(), note.title.clone()),
+                ("chunk_index".to_string(), chunk.index.to_string()),
+                ("chunk_count".to_string(), chunk_count.to_string()),
+            ]);
+            if let Some(heading) = &chunk.heading {
+                content.insert("heading".to_string(), heading.clone());
+            }
+            documents.push(Document {
+                id: vector_id,
+                content,
+                metadata: DocumentMetadata {
+                    user_id: user_id.to_string(),
+                    data_type: data_type_to_string(&DataType::NotebookEntry).to_string(),
+                    entity_id: note.id.clone(),
+                    timestamp: Utc::now(),
+                    tags: tags.clone(),
+                    content_hash,
+                },
+            });
+        }
+
+        let all_ids = all_chunk_vector_ids(&base_id);
+        let stored_ids: Vec<String> = vectors.iter().map(|(id, _, _)| id.clone()).collect();
+        let stale_ids: Vec<String> = all_ids.iter().filter(|id| !stored_ids.contains(id)).cloned().collect();
+
+        self.upstash_vector
+            .upsert_vectors(&namespace, vectors)
+            .await
+            .context("Failed to store notebook chunk vectors")?;
+        if let Err(e) = self.upstash_vector.delete_vectors(&namespace, &stale_ids).await {
+            log::warn!("Failed to remove stale chunk vectors for note {}: {}", note.id, e);
+        }
+
+        // Qdrant points get fresh ids on every upsert, so drop all of the note's old documents first
+        if let Err(e) = self.qdrant_client.delete_documents(user_id, &all_ids).await {
+            log::warn!("Failed to remove old search documents for note {}: {}", note.id, e);
+        }
+        if let Err(e) = self.qdrant_client.upsert_documents(user_id, documents).await {
+            log::error!("Failed to store search documents for note {}: {}", note.id, e);
+        }
+
+        let processing_time_ms = start_time.elapsed().as_millis() as u64;
+        log::info!(
+            "Vectorized notebook note {} for user {} as {} chunk(s) in {}ms",
+            note.id, user_id, chunk_count, processing_time_ms
+        );
+
+        Ok(stored_ids
+            .into_iter()
+            .map(|vector_id| VectorizationResult {
+                task_id: Uuid::new_v4().to_string(),
+                vector_id,
+                success: true,
+                error: None,
+                processing_time_ms,
+            })
+            .collect())
+    }
+
+    /// Remove every chunk vector and search document stored for a notebook note
+    pub async fn delete_notebook_note_vectors(&self, user_id: &str, note_id: &str) -> Result<()> {
+        let base_id = format!("{}_{}_{}", user_id, data_type_to_string(&DataType::NotebookEntry), note_id);
+        let ids = all_chunk_vector_ids(&base_id);
+        let namespace = self.upstash_vector.get_user_namespace(user_id);
+
+        self.upstash_vector
+            .delete_vectors(&namespace, &ids)
+            .await
+            .context("Failed to delete notebook chunk vectors")?;
+        if let Err(e) = self.qdrant_client.delete_documents(user_id, &ids).await {
+            log::warn!("Failed to delete search documents for note {}: {}", note_id, e);
+        }
+        Ok(())
+    }
+
     /// Vectorize multiple pieces of data in batch
     pub async fn vectorize_batch(
         &self,
@@ -227,7 +404,7 @@ impl VectorizationService {
         let mut results = Vec::new();
 
         // Prepare content for batch embedding
-        let contents: Vec<String> = tasks.iter().map(|t| t.content.clone()).collect();
+        let contents: Vec<String> = tasks.iter().map(|t| embedding_text(&t.data_type, &t.content).into_owned()).collect();
         
         // Generate embeddings in batch
         let embeddings = self.voyager_client
@@ -247,8 +424,10 @@ impl VectorizationService {
                     data_type: task.data_type.clone(),
                     entity_id: task.entity_id.clone(),
                     timestamp: task.created_at,
-                    tags: DataFormatter::extract_tags(&task.content, &convert_data_type(&task.data_type)),
-                    content_hash: DataFormatter::generate_content_hash(&task.content),
+                    tags: DataFormatter::extract_tags(&contents[i], &convert_data_type(&task.data_type)),
+                    content_hash: DataFormatter::generate_content_hash(&contents[i]),
+                    chunk_index: None,
+                    chunk_count: None,
                 };
 
                 vectors_to_upsert.push((vector_id, embedding.clone(), metadata));
@@ -344,11 +523,41 @@ impl VectorizationService {
             None
         };
 
+        // Long notes are stored as several chunk vectors; over-fetch and keep each note's best chunk
         let namespace = self.upstash_vector.get_user_namespace(user_id);
-        self.upstash_vector
-            .query_similarity(&namespace, &query_embedding, top_k, filter)
+        let matches = self.upstash_vector
+            .query_similarity(&namespace, &query_embedding, chunk_query_top_k(top_k), filter)
             .await
-            .context("Failed to query similar vectors")
+            .context("Failed to query similar vectors")?;
+
+        Ok(merge_chunk_matches(matches, top_k).into_iter().map(|(m, _)| m).collect())
+    }
+
+    /// Stored embeddings of single-vector entities (trades, playbooks), keyed by entity ID
+    pub async fn fetch_entity_embeddings(
+        &self,
+        user_id: &str,
+        data_type: DataType,
+        entity_ids: &[String],
+    ) -> Result<std::collections::HashMap<String, Vec<f32>>> {
+        let prefix = format!("{}_{}_", user_id, data_type_to_string(&data_type));
+        let vector_ids: Vec<String> = entity_ids.iter().map(|id| format!("{}{}", prefix, id)).collect();
+        let namespace = self.upstash_vector.get_user_namespace(user_id);
+
+        let mut embeddings = std::collections::HashMap::new();
+        // Upstash caps the IDs per fetch, so ask in batches
+        for batch in vector_ids.chunks(100) {
+            let fetched = self.upstash_vector
+                .fetch_vectors(&namespace, batch)
+                .await
+                .context("Failed to fetch stored embeddings")?;
+            for vector in fetched {
+                if let Some(entity_id) = vector.id.strip_prefix(&prefix) {
+                    embeddings.insert(entity_id.to_string(), vector.vector);
+                }
+            }
+        }
+        Ok(embeddings)
     }
 
     /// Health check for vectorization service
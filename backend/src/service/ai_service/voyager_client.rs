@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use crate::service::ai_service::mock_model_client::{mock_ai_enabled, mock_embedding};
+use crate::service::cost_ledger::{self, Provider};
 use crate::turso::vector_config::VoyagerConfig;
 use anyhow::{Context, Result};
 use reqwest::Client;
@@ -35,6 +37,8 @@ pub struct Usage {
 pub struct VoyagerClient {
     config: VoyagerConfig,
     client: Client,
+    /// `AI_PROVIDER=mock`: embeddings are derived from the text locally
+    mock: bool,
 }
 
 impl VoyagerClient {
@@ -44,8 +48,13 @@ impl VoyagerClient {
             .build()
             .context("Failed to create HTTP client")?;
 
-        let instance = Self { config, client };
-        instance.validate_config()?;
+        let mock = mock_ai_enabled();
+        let instance = Self { config, client, mock };
+        if mock {
+            log::warn!("AI_PROVIDER=mock: embeddings are generated locally, not by {}", instance.config.model);
+        } else {
+            instance.validate_config()?;
+        }
         
         Ok(instance)
     }
@@ -103,6 +112,9 @@ impl VoyagerClient {
         if texts.is_empty() {
             return Ok(vec![]);
         }
+        if self.mock {
+            return Ok(texts.iter().map(|t| mock_embedding(t, self.get_dimensions())).collect());
+        }
 
         // Split into batches if needed
         let mut all_embeddings = Vec::new();
@@ -136,6 +148,8 @@ impl VoyagerClient {
                         "Embedding successful - embeddings={}, tokens={}",
                         response.data.len(), response.usage.total_tokens
                     );
+                    let tokens = u64::from(response.usage.total_tokens);
+                    cost_ledger::record(Provider::Voyage, &self.config.model, tokens, 0, cost_ledger::rates().voyage_cost(tokens));
                     let embeddings: Vec<Vec<f32>> = response
                         .data
                         .into_iter()
@@ -0,0 +1,326 @@
+// Performance attribution. Net P&L over a period is split by symbol, playbook, direction
+// and session (US Eastern time of entry), each item with its share of the total. The
+// interaction effects show where a pair of dimensions does better or worse together than
+// their separate averages predict, e.g. a playbook that only works at the open.
+
+use anyhow::Result;
+use chrono::{Duration, Timelike};
+use libsql::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::market_engine::hours::eastern_offset_hours;
+use super::hold_time::parse_timestamp;
+use super::query::{AssetClass, TradeFilter, TradeQuery};
+
+/// Entry-time sessions: label and exclusive upper bound in minutes after midnight Eastern
+const SESSIONS: [(&str, u32); 5] = [
+    ("Pre-market", 9 * 60 + 30),
+    ("Open", 10 * 60 + 30),
+    ("Midday", 14 * 60),
+    ("Close", 16 * 60),
+    ("After hours", 24 * 60),
+];
+
+/// Interaction cells returned, largest absolute effect first
+const MAX_INTERACTIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributionDimension {
+    Symbol,
+    Playbook,
+    Direction,
+    Session,
+}
+
+impl AttributionDimension {
+    const ALL: [AttributionDimension; 4] = [Self::Symbol, Self::Playbook, Self::Direction, Self::Session];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Symbol => "symbol",
+            Self::Playbook => "playbook",
+            Self::Direction => "direction",
+            Self::Session => "session",
+        }
+    }
+}
+
+/// P&L of one value of a dimension, e.g. symbol AAPL
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AttributionItem {
+    pub key: String,
+    pub pnl: f64,
+    pub trade_count: u32,
+    pub win_rate: f64,
+    /// Share of the period's net P&L, against its absolute value so the items of a
+    /// dimension sum to +100% in a winning period and -100% in a losing one
+    pub contribution_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DimensionAttribution {
+    pub dimension: AttributionDimension,
+    /// Best P&L first
+    pub items: Vec<AttributionItem>,
+}
+
+/// One cell of a pair of dimensions
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InteractionEffect {
+    pub dimensions: [AttributionDimension; 2],
+    pub keys: [String; 2],
+    pub pnl: f64,
+    pub trade_count: u32,
+    /// P&L the cell would have if both values kept their own average P&L per trade
+    pub expected_pnl: f64,
+    /// `pnl - expected_pnl`
+    pub interaction_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AttributionReport {
+    pub total_pnl: f64,
+    pub trade_count: u32,
+    pub dimensions: Vec<DimensionAttribution>,
+    pub interactions: Vec<InteractionEffect>,
+}
+
+/// One closed trade with its attribution keys, in `AttributionDimension::ALL` order
+struct AttributedTrade {
+    keys: [String; 4],
+    pnl: f64,
+}
+
+pub async fn calculate_attribution(
+    conn: &Connection,
+    time_range: &TimeRange,
+    filter: &TradeFilter,
+) -> Result<AttributionReport> {
+    let periods = PeriodDefinition::load(conn).await;
+    let mut trades = Vec::new();
+    for source in [AssetClass::Stocks, AssetClass::Options] {
+        let query = TradeQuery::closed(source).time_range(time_range, &periods).filter(filter);
+        trades.extend(load_trades(conn, &query).await?);
+    }
+    Ok(attribute(&trades))
+}
+
+async fn load_trades(conn: &Connection, query: &TradeQuery) -> Result<Vec<AttributedTrade>> {
+    let (direction, junction, trade_column) = match query.source() {
+        AssetClass::Stocks => (
+            "CASE trade_type WHEN 'BUY' THEN 'Long' ELSE 'Short' END",
+            "stock_trade_playbook",
+            "stock_trade_id",
+        ),
+        AssetClass::Options => (
+            "CASE trade_direction WHEN 'Bullish' THEN 'Long' WHEN 'Bearish' THEN 'Short' ELSE 'Neutral' END",
+            "option_trade_playbook",
+            "option_trade_id",
+        ),
+    };
+    let sql = format!(
+        r#"
+        SELECT UPPER(symbol), {direction}, entry_date, {pnl},
+            (SELECT p.name FROM {junction} tp JOIN playbook p ON p.id = tp.setup_id
+             WHERE tp.{trade_column} = {table}.id ORDER BY p.name LIMIT 1)
+        FROM {table}
+        WHERE {condition}
+        "#,
+        pnl = query.pnl_sql(),
+        table = query.table(),
+        condition = query.condition(),
+    );
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query.params()))
+        .await?;
+
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let session = row.get::<String>(2).ok().and_then(|entry| session_of(&entry)).unwrap_or("Unknown");
+        trades.push(AttributedTrade {
+            keys: [
+                row.get::<String>(0)?,
+                row.get::<Option<String>>(4)?.unwrap_or_else(|| "No playbook".to_string()),
+                row.get::<String>(1)?,
+                session.to_string(),
+            ],
+            pnl: money::to_f64(money::row_decimal(&row, 3)),
+        });
+    }
+    Ok(trades)
+}
+
+/// Session label of an entry timestamp, by US Eastern wall-clock time
+fn session_of(entry: &str) -> Option<&'static str> {
+    let entry = parse_timestamp(entry)?;
+    let eastern = entry + Duration::hours(eastern_offset_hours(entry.date_naive()));
+    let minutes = eastern.hour() * 60 + eastern.minute();
+    SESSIONS.iter().find(|(_, end)| minutes < *end).map(|(label, _)| *label)
+}
+
+#[derive(Default)]
+struct Tally {
+    pnl: f64,
+    trades: u32,
+    wins: u32,
+}
+
+impl Tally {
+    fn add(&mut self, pnl: f64) {
+        self.pnl += pnl;
+        self.trades += 1;
+        if pnl > 0.0 {
+            self.wins += 1;
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        if self.trades == 0 { 0.0 } else { self.pnl / self.trades as f64 }
+    }
+}
+
+fn attribute(trades: &[AttributedTrade]) -> AttributionReport {
+    let total_pnl: f64 = trades.iter().map(|t| t.pnl).sum();
+    let overall_mean = if trades.is_empty() { 0.0 } else { total_pnl / trades.len() as f64 };
+
+    let mut tallies: Vec<HashMap<&str, Tally>> = (0..4).map(|_| HashMap::new()).collect();
+    for trade in trades {
+        for (dimension, key) in trade.keys.iter().enumerate() {
+            tallies[dimension].entry(key.as_str()).or_default().add(trade.pnl);
+        }
+    }
+
+    let dimensions = AttributionDimension::ALL
+        .iter()
+        .zip(&tallies)
+        .map(|(dimension, tally)| {
+            let mut items: Vec<AttributionItem> = tally
+                .iter()
+                .map(|(key, t)| AttributionItem {
+                    key: key.to_string(),
+                    pnl: t.pnl,
+                    trade_count: t.trades,
+                    win_rate: t.wins as f64 / t.trades as f64 * 100.0,
+                    contribution_pct: if total_pnl == 0.0 { 0.0 } else { t.pnl / total_pnl.abs() * 100.0 },
+                })
+                .collect();
+            items.sort_by(|a, b| b.pnl.total_cmp(&a.pnl).then_with(|| a.key.cmp(&b.key)));
+            DimensionAttribution { dimension: *dimension, items }
+        })
+        .collect();
+
+    // Two-way interaction per cell: n * (cell mean - row mean - column mean + overall mean)
+    let mut interactions = Vec::new();
+    for (a, tally_a) in tallies.iter().enumerate() {
+        for (b, tally_b) in tallies.iter().enumerate().skip(a + 1) {
+            let mut cells: HashMap<(&str, &str), Tally> = HashMap::new();
+            for trade in trades {
+                cells.entry((trade.keys[a].as_str(), trade.keys[b].as_str())).or_default().add(trade.pnl);
+            }
+            for ((key_a, key_b), cell) in cells {
+                let n = cell.trades as f64;
+                let expected = n * (tally_a[key_a].mean() + tally_b[key_b].mean() - overall_mean);
+                interactions.push(InteractionEffect {
+                    dimensions: [AttributionDimension::ALL[a], AttributionDimension::ALL[b]],
+                    keys: [key_a.to_string(), key_b.to_string()],
+                    pnl: cell.pnl,
+                    trade_count: cell.trades,
+                    expected_pnl: expected,
+                    interaction_pnl: cell.pnl - expected,
+                });
+            }
+        }
+    }
+    interactions.retain(|i| i.interaction_pnl.abs() >= 0.01);
+    interactions.sort_by(|a, b| {
+        b.interaction_pnl.abs().total_cmp(&a.interaction_pnl.abs()).then_with(|| a.keys.cmp(&b.keys))
+    });
+    interactions.truncate(MAX_INTERACTIONS);
+
+    AttributionReport { total_pnl, trade_count: trades.len() as u32, dimensions, interactions }
+}
+
+impl AttributionReport {
+    /// Compact summary for AI prompts; None when there were no closed trades
+    pub fn prompt_section(&self) -> Option<String> {
+        if self.trade_count == 0 {
+            return None;
+        }
+        let mut section = format!(
+            "P&L attribution ({} closed trades, net ${:.2}):",
+            self.trade_count, self.total_pnl
+        );
+        for dimension in &self.dimensions {
+            let items: Vec<String> = dimension
+                .items
+                .iter()
+                .take(3)
+                .chain(dimension.items.iter().skip(3).rev().take(2).rev())
+                .map(|i| format!("{} ${:.2} ({:.0}%, {} trades)", i.key, i.pnl, i.contribution_pct, i.trade_count))
+                .collect();
+            section.push_str(&format!("\n- By {}: {}", dimension.dimension.label(), items.join("; ")));
+        }
+        for interaction in self.interactions.iter().take(3) {
+            section.push_str(&format!(
+                "\n- {} {} with {} {}: ${:.2} vs ${:.2} expected",
+                interaction.dimensions[0].label(),
+                interaction.keys[0],
+                interaction.dimensions[1].label(),
+                interaction.keys[1],
+                interaction.pnl,
+                interaction.expected_pnl
+            ));
+        }
+        Some(section)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(symbol: &str, playbook: &str, direction: &str, session: &str, pnl: f64) -> AttributedTrade {
+        AttributedTrade {
+            keys: [symbol.to_string(), playbook.to_string(), direction.to_string(), session.to_string()],
+            pnl,
+        }
+    }
+
+    #[test]
+    fn test_attribute_contributions_and_interactions() {
+        let report = attribute(&[
+            trade("AAPL", "ORB", "Long", "Open", 300.0),
+            trade("AAPL", "ORB", "Long", "Midday", -100.0),
+            trade("TSLA", "Fade", "Short", "Open", -100.0),
+            trade("TSLA", "Fade", "Short", "Midday", 100.0),
+        ]);
+
+        assert_eq!(report.total_pnl, 200.0);
+        let symbols = &report.dimensions[0];
+        assert_eq!(symbols.dimension, AttributionDimension::Symbol);
+        assert_eq!((symbols.items[0].key.as_str(), symbols.items[0].contribution_pct), ("AAPL", 100.0));
+        assert_eq!(symbols.items[1].contribution_pct, 0.0);
+        let sessions = &report.dimensions[3];
+        assert_eq!(sessions.items[0].win_rate, 50.0);
+
+        // ORB at the open beats its separate averages: 300 vs 1 * (100 + 100 - 50)
+        let orb_open = report
+            .interactions
+            .iter()
+            .find(|i| i.keys == ["ORB".to_string(), "Open".to_string()])
+            .unwrap();
+        assert_eq!(orb_open.expected_pnl, 150.0);
+        assert_eq!(orb_open.interaction_pnl, 150.0);
+        assert!(report.prompt_section().unwrap().contains("By playbook: ORB $200.00 (100%, 2 trades)"));
+
+        assert_eq!(session_of("2025-03-03T14:45:00Z"), Some("Open"));
+        assert_eq!(session_of("2025-07-01T12:00:00Z"), Some("Pre-market"));
+    }
+}
@@ -0,0 +1,383 @@
+use anyhow::Result;
+use libsql::Connection;
+use crate::models::analytics::{ConsistencyScore, ConsistencyScoreSnapshot};
+use crate::models::money::{self, Decimal};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::stock::stocks::TimeRange;
+
+/// Default share of total profit a single day may contribute (funded-account style rule)
+pub const DEFAULT_CONSISTENCY_THRESHOLD: f64 = 30.0;
+
+// Component weights for the overall score
+const CONCENTRATION_WEIGHT: f64 = 0.4;
+const COMPLIANCE_WEIGHT: f64 = 0.3;
+const DRAWDOWN_WEIGHT: f64 = 0.3;
+
+/// Calculate the consistency score for the given time range
+pub async fn calculate_consistency_score(
+    conn: &Connection,
+    time_range: &TimeRange,
+    threshold_percentage: f64,
+) -> Result<ConsistencyScore> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+
+    let daily_pnl = fetch_daily_pnl(conn, &time_condition, &time_params).await?;
+    let pnl_values: Vec<f64> = daily_pnl.iter().map(|(_, pnl)| *pnl).collect();
+    let (rules_checked, rules_followed) = fetch_rule_compliance(conn, &time_condition, &time_params).await?;
+
+    Ok(score_from_inputs(&pnl_values, rules_checked, rules_followed, threshold_percentage))
+}
+
+/// Daily net P&L (stocks + options) ordered by date
+pub async fn fetch_daily_pnl(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<(String, f64)>> {
+    let sql = format!(
+        r#"
+        SELECT
+            DATE(exit_date) as trade_date,
+            SUM(calculated_pnl) as daily_pnl
+        FROM (
+            SELECT
+                exit_date,
+                {STOCK_PNL_SQL} as calculated_pnl
+            FROM stocks
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+
+            UNION ALL
+
+            SELECT
+                exit_date,
+                {OPTION_PNL_SQL} as calculated_pnl
+            FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+        )
+        GROUP BY DATE(exit_date)
+        ORDER BY trade_date
+        "#,
+        time_condition, time_condition
+    );
+
+    // Params appear once per sub-select
+    let mut query_params = Vec::new();
+    for _ in 0..2 {
+        for param in time_params {
+            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+        }
+    }
+
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query_params))
+        .await?;
+
+    let mut daily_pnl = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let date = row.get::<String>(0).unwrap_or_default();
+        daily_pnl.push((date, get_f64_value(&row, 1)));
+    }
+
+    Ok(daily_pnl)
+}
+
+/// Count playbook rule checks and how many were followed for closed trades in range
+async fn fetch_rule_compliance(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<(u32, u32)> {
+    let sql = format!(
+        r#"
+        SELECT COUNT(*), COALESCE(SUM(CASE WHEN is_followed THEN 1 ELSE 0 END), 0)
+        FROM (
+            SELECT c.is_followed
+            FROM stock_trade_rule_compliance c
+            JOIN stocks ON stocks.id = c.stock_trade_id
+            WHERE stocks.exit_date IS NOT NULL AND ({})
+
+            UNION ALL
+
+            SELECT c.is_followed
+            FROM option_trade_rule_compliance c
+            JOIN options ON options.id = c.option_trade_id
+            WHERE options.exit_date IS NOT NULL AND ({})
+        )
+        "#,
+        time_condition, time_condition
+    );
+
+    let mut query_params = Vec::new();
+    for _ in 0..2 {
+        for param in time_params {
+            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+        }
+    }
+
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query_params))
+        .await?;
+
+    if let Some(row) = rows.next().await? {
+        let checked = get_i64_value(&row, 0).max(0) as u32;
+        let followed = get_i64_value(&row, 1).max(0) as u32;
+        return Ok((checked, followed));
+    }
+
+    Ok((0, 0))
+}
+
+/// Build the score from daily P&L and rule compliance counts
+pub fn score_from_inputs(
+    daily_pnl: &[f64],
+    rules_checked: u32,
+    rules_followed: u32,
+    threshold_percentage: f64,
+) -> ConsistencyScore {
+    let trading_days = daily_pnl.len() as u32;
+    let profitable_days = daily_pnl.iter().filter(|&&p| p > 0.0).count() as u32;
+    let total_pnl: f64 = daily_pnl.iter().sum();
+    let gross_profit: f64 = daily_pnl.iter().filter(|&&p| p > 0.0).sum();
+    let best_day_pnl = daily_pnl.iter().cloned().fold(0.0_f64, f64::max);
+    let worst_day_pnl = daily_pnl.iter().cloned().fold(0.0_f64, f64::min);
+
+    // Profit concentration: share of gross profit earned on the best day
+    let best_day_share_percentage = if gross_profit > 0.0 {
+        (best_day_pnl / gross_profit) * 100.0
+    } else {
+        0.0
+    };
+    let profit_concentration_score = if gross_profit <= 0.0 {
+        0.0
+    } else if best_day_share_percentage <= threshold_percentage {
+        100.0
+    } else {
+        // Falls linearly to 0 when a single day holds all profit
+        let excess = (best_day_share_percentage - threshold_percentage) / (100.0 - threshold_percentage).max(1.0);
+        (100.0 * (1.0 - excess)).clamp(0.0, 100.0)
+    };
+
+    // Rule compliance: share of rule checks that were followed
+    let rule_compliance_score = if rules_checked > 0 {
+        Some((rules_followed as f64 / rules_checked as f64) * 100.0)
+    } else {
+        None
+    };
+
+    // Drawdown discipline: how much of the gross profit was given back at worst
+    let maximum_drawdown = calculate_max_drawdown(daily_pnl);
+    let drawdown_discipline_score = if trading_days == 0 {
+        0.0
+    } else if gross_profit > 0.0 {
+        (100.0 * (1.0 - maximum_drawdown / gross_profit)).clamp(0.0, 100.0)
+    } else {
+        0.0
+    };
+
+    let overall_score = match rule_compliance_score {
+        Some(compliance) => {
+            profit_concentration_score * CONCENTRATION_WEIGHT
+                + compliance * COMPLIANCE_WEIGHT
+                + drawdown_discipline_score * DRAWDOWN_WEIGHT
+        }
+        None => {
+            // No compliance data: redistribute its weight across the other components
+            let total_weight = CONCENTRATION_WEIGHT + DRAWDOWN_WEIGHT;
+            (profit_concentration_score * CONCENTRATION_WEIGHT
+                + drawdown_discipline_score * DRAWDOWN_WEIGHT)
+                / total_weight
+        }
+    };
+
+    ConsistencyScore {
+        overall_score,
+        grade: grade_for_score(overall_score).to_string(),
+        profit_concentration_score,
+        rule_compliance_score,
+        drawdown_discipline_score,
+        trading_days,
+        profitable_days,
+        total_pnl,
+        best_day_pnl,
+        best_day_share_percentage,
+        rules_checked,
+        rules_followed,
+        maximum_drawdown,
+        worst_day_pnl,
+        passes_consistency_rule: gross_profit > 0.0 && best_day_share_percentage <= threshold_percentage,
+        consistency_rule_threshold_percentage: threshold_percentage,
+    }
+}
+
+/// Peak-to-trough drawdown of the cumulative daily P&L curve
+fn calculate_max_drawdown(daily_pnl: &[f64]) -> f64 {
+    let mut cumulative = Decimal::ZERO;
+    let mut peak = Decimal::ZERO;
+    let mut max_drawdown = Decimal::ZERO;
+
+    for &pnl in daily_pnl {
+        cumulative += money::from_f64(pnl);
+        if cumulative > peak {
+            peak = cumulative;
+        }
+        max_drawdown = max_drawdown.max(peak - cumulative);
+    }
+
+    money::to_f64(max_drawdown)
+}
+
+fn grade_for_score(score: f64) -> &'static str {
+    match score {
+        s if s >= 90.0 => "A",
+        s if s >= 80.0 => "B",
+        s if s >= 70.0 => "C",
+        s if s >= 60.0 => "D",
+        _ => "F",
+    }
+}
+
+/// Record today's score for the given range (one snapshot per day per range)
+pub async fn save_consistency_snapshot(
+    conn: &Connection,
+    time_range_label: &str,
+    score: &ConsistencyScore,
+) -> Result<()> {
+    let snapshot_date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let id = uuid::Uuid::new_v4().to_string();
+
+    conn.execute(
+        r#"
+        INSERT INTO consistency_score_history (
+            id, snapshot_date, time_range, overall_score, profit_concentration_score,
+            rule_compliance_score, drawdown_discipline_score, best_day_share_percentage,
+            trading_days, total_pnl
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(snapshot_date, time_range) DO UPDATE SET
+            overall_score = excluded.overall_score,
+            profit_concentration_score = excluded.profit_concentration_score,
+            rule_compliance_score = excluded.rule_compliance_score,
+            drawdown_discipline_score = excluded.drawdown_discipline_score,
+            best_day_share_percentage = excluded.best_day_share_percentage,
+            trading_days = excluded.trading_days,
+            total_pnl = excluded.total_pnl
+        "#,
+        libsql::params![
+            id,
+            snapshot_date,
+            time_range_label,
+            score.overall_score,
+            score.profit_concentration_score,
+            score.rule_compliance_score,
+            score.drawdown_discipline_score,
+            score.best_day_share_percentage,
+            score.trading_days as i64,
+            score.total_pnl
+        ],
+    ).await?;
+
+    Ok(())
+}
+
+/// Load stored snapshots for a range, newest first
+pub async fn get_consistency_history(
+    conn: &Connection,
+    time_range_label: &str,
+    limit: i64,
+) -> Result<Vec<ConsistencyScoreSnapshot>> {
+    let mut rows = conn
+        .prepare(
+            r#"
+            SELECT id, snapshot_date, time_range, overall_score, profit_concentration_score,
+                   rule_compliance_score, drawdown_discipline_score, best_day_share_percentage,
+                   trading_days, total_pnl, created_at
+            FROM consistency_score_history
+            WHERE time_range = ?
+            ORDER BY snapshot_date DESC
+            LIMIT ?
+            "#,
+        )
+        .await?
+        .query(libsql::params![time_range_label, limit])
+        .await?;
+
+    let mut history = Vec::new();
+    while let Some(row) = rows.next().await? {
+        history.push(ConsistencyScoreSnapshot {
+            id: row.get(0)?,
+            snapshot_date: row.get(1)?,
+            time_range: row.get(2)?,
+            overall_score: get_f64_value(&row, 3),
+            profit_concentration_score: get_f64_value(&row, 4),
+            rule_compliance_score: row.get::<Option<f64>>(5).unwrap_or(None),
+            drawdown_discipline_score: get_f64_value(&row, 6),
+            best_day_share_percentage: get_f64_value(&row, 7),
+            trading_days: get_i64_value(&row, 8).max(0) as u32,
+            total_pnl: get_f64_value(&row, 9),
+            created_at: row.get(10)?,
+        });
+    }
+
+    Ok(history)
+}
+
+/// Helper function to safely extract f64 from libsql::Value
+fn get_f64_value(row: &libsql::Row, index: usize) -> f64 {
+    match row.get::<libsql::Value>(index as i32) {
+        Ok(libsql::Value::Integer(i)) => i as f64,
+        Ok(libsql::Value::Real(f)) => f,
+        Ok(libsql::Value::Null) => 0.0,
+        _ => 0.0,
+    }
+}
+
+/// Helper function to safely extract i64 from libsql::Value
+fn get_i64_value(row: &libsql::Row, index: usize) -> i64 {
+    match row.get::<libsql::Value>(index as i32) {
+        Ok(libsql::Value::Integer(i)) => i,
+        Ok(libsql::Value::Null) => 0,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evenly_distributed_profits_pass() {
+        let score = score_from_inputs(&[100.0, 120.0, 90.0, 110.0], 10, 10, DEFAULT_CONSISTENCY_THRESHOLD);
+        assert!(score.passes_consistency_rule);
+        assert_eq!(score.profit_concentration_score, 100.0);
+        assert_eq!(score.rule_compliance_score, Some(100.0));
+        assert_eq!(score.maximum_drawdown, 0.0);
+        assert_eq!(score.grade, "A");
+    }
+
+    #[test]
+    fn test_single_big_day_fails_consistency_rule() {
+        let score = score_from_inputs(&[900.0, 50.0, 50.0], 0, 0, DEFAULT_CONSISTENCY_THRESHOLD);
+        assert!(!score.passes_consistency_rule);
+        assert!(score.profit_concentration_score < 30.0);
+        assert!(score.rule_compliance_score.is_none());
+    }
+
+    #[test]
+    fn test_drawdown_reduces_discipline_score() {
+        let score = score_from_inputs(&[200.0, -100.0, 200.0], 4, 2, DEFAULT_CONSISTENCY_THRESHOLD);
+        assert_eq!(score.maximum_drawdown, 100.0);
+        assert_eq!(score.drawdown_discipline_score, 75.0);
+        assert_eq!(score.rule_compliance_score, Some(50.0));
+    }
+
+    #[test]
+    fn test_no_trades_scores_zero() {
+        let score = score_from_inputs(&[], 0, 0, DEFAULT_CONSISTENCY_THRESHOLD);
+        assert_eq!(score.overall_score, 0.0);
+        assert_eq!(score.trading_days, 0);
+        assert!(!score.passes_consistency_rule);
+    }
+}
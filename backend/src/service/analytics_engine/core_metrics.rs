@@ -1,9 +1,14 @@
 //! This file is for calculating the stats for both the options & stocks table combined.
 
+pub mod streaks;
+
 use anyhow::Result;
 use libsql::Connection;
-use crate::models::analytics::CoreMetrics;
+use crate::models::analytics::{CoreMetrics, PeriodDefinition, SampleConfidence};
+use crate::models::money;
+use crate::models::pnl::{self, OPTION_PNL_SQL, STOCK_PNL_SQL};
 use crate::models::stock::stocks::TimeRange;
+use super::query::{AssetClass, TradeFilter, TradeQuery};
 
 /// Helper function to safely extract f64 from libsql::Value
 fn get_f64_value(row: &libsql::Row, index: usize) -> f64 {
@@ -39,25 +44,50 @@ pub async fn calculate_core_metrics(
     conn: &Connection,
     time_range: &TimeRange,
 ) -> Result<CoreMetrics> {
-    let (time_condition, time_params) = time_range.to_sql_condition();
+    calculate_filtered_core_metrics(conn, time_range, &TradeFilter::default()).await
+}
+
+/// Core metrics over the trades matching `filter`
+pub async fn calculate_filtered_core_metrics(
+    conn: &Connection,
+    time_range: &TimeRange,
+    filter: &TradeFilter,
+) -> Result<CoreMetrics> {
+    let periods = PeriodDefinition::load(conn).await;
+    let query = |source| TradeQuery::closed(source).time_range(time_range, &periods).filter(filter);
     
     // Calculate stocks metrics
-    let stocks_metrics = calculate_stocks_core_metrics(conn, &time_condition, &time_params).await?;
+    let stocks_metrics = calculate_stocks_core_metrics(conn, &query(AssetClass::Stocks)).await?;
     
     // Calculate options metrics
-    let options_metrics = calculate_options_core_metrics(conn, &time_condition, &time_params).await?;
+    let options_metrics = calculate_options_core_metrics(conn, &query(AssetClass::Options)).await?;
     
+    // Streaks follow exit order across both tables rather than each table on its own
+    let pnls = streaks::load_pnls(conn, &[query(AssetClass::Stocks), query(AssetClass::Options)], None).await?;
+    let streaks = streaks::calculate(&pnls);
+
     // Combine metrics from both tables
-    let combined_metrics = combine_core_metrics(stocks_metrics, options_metrics);
+    let combined_metrics = combine_core_metrics(stocks_metrics, options_metrics, &streaks);
     
     Ok(combined_metrics)
 }
 
+/// Win-rate interval, expectancy standard error and low-sample flag for the trades the
+/// matching core metrics cover
+pub async fn calculate_sample_confidence(
+    conn: &Connection,
+    time_range: &TimeRange,
+    filter: &TradeFilter,
+) -> Result<SampleConfidence> {
+    let periods = PeriodDefinition::load(conn).await;
+    let queries = AssetClass::ALL.map(|source| TradeQuery::closed(source).time_range(time_range, &periods).filter(filter));
+    Ok(SampleConfidence::from_pnls(&streaks::load_pnls(conn, &queries, None).await?))
+}
+
 /// Calculate core metrics for stocks table
 async fn calculate_stocks_core_metrics(
     conn: &Connection,
-    time_condition: &str,
-    time_params: &[chrono::DateTime<chrono::Utc>],
+    query: &TradeQuery,
 ) -> Result<CoreMetrics> {
     let sql = format!(
         r#"
@@ -79,27 +109,18 @@ async fn calculate_stocks_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
-            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+            WHERE {}
         )
         "#,
-        time_condition
+        query.condition()
     );
 
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
     let mut rows = conn
         .prepare(&sql)
         .await?
-        .query(libsql::params_from_iter(query_params))
+        .query(libsql::params_from_iter(query.params()))
         .await?;
 
     if let Some(row) = rows.next().await? {
@@ -147,10 +168,6 @@ async fn calculate_stocks_core_metrics(
             0.0
         };
 
-        // Calculate consecutive streaks for stocks
-        let (max_consecutive_wins, max_consecutive_losses) = 
-            calculate_stocks_consecutive_streaks(conn, time_condition, time_params).await?;
-
         Ok(CoreMetrics {
             total_trades,
             winning_trades,
@@ -169,8 +186,9 @@ async fn calculate_stocks_core_metrics(
             biggest_loser,
             profit_factor,
             win_loss_ratio,
-            max_consecutive_wins,
-            max_consecutive_losses,
+            // Streaks span both tables; filled in by combine_core_metrics
+            max_consecutive_wins: 0,
+            max_consecutive_losses: 0,
             total_commissions,
             average_commission_per_trade,
         })
@@ -182,8 +200,7 @@ async fn calculate_stocks_core_metrics(
 /// Calculate core metrics for options table
 async fn calculate_options_core_metrics(
     conn: &Connection,
-    time_condition: &str,
-    time_params: &[chrono::DateTime<chrono::Utc>],
+    query: &TradeQuery,
 ) -> Result<CoreMetrics> {
     let sql = format!(
         r#"
@@ -205,27 +222,18 @@ async fn calculate_options_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
-            WHERE status = 'closed' AND ({})
+            WHERE {}
         )
         "#,
-        time_condition
+        query.condition()
     );
 
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
     let mut rows = conn
         .prepare(&sql)
         .await?
-        .query(libsql::params_from_iter(query_params))
+        .query(libsql::params_from_iter(query.params()))
         .await?;
 
     if let Some(row) = rows.next().await? {
@@ -273,10 +281,6 @@ async fn calculate_options_core_metrics(
             0.0
         };
 
-        // Calculate consecutive streaks for options
-        let (max_consecutive_wins, max_consecutive_losses) = 
-            calculate_options_consecutive_streaks(conn, time_condition, time_params).await?;
-
         Ok(CoreMetrics {
             total_trades,
             winning_trades,
@@ -295,8 +299,9 @@ async fn calculate_options_core_metrics(
             biggest_loser,
             profit_factor,
             win_loss_ratio,
-            max_consecutive_wins,
-            max_consecutive_losses,
+            // Streaks span both tables; filled in by combine_core_metrics
+            max_consecutive_wins: 0,
+            max_consecutive_losses: 0,
             total_commissions,
             average_commission_per_trade,
         })
@@ -305,122 +310,8 @@ async fn calculate_options_core_metrics(
     }
 }
 
-/// Calculate consecutive streaks for stocks
-async fn calculate_stocks_consecutive_streaks(
-    conn: &Connection,
-    time_condition: &str,
-    time_params: &[chrono::DateTime<chrono::Utc>],
-) -> Result<(u32, u32)> {
-    // Get all trades ordered by exit_date to track consecutive streaks
-    let sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM stocks
-        WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    let mut rows = conn
-        .prepare(&sql)
-        .await?
-        .query(libsql::params_from_iter(query_params))
-        .await?;
-
-    let mut trades = Vec::new();
-    while let Some(row) = rows.next().await? {
-        let pnl = get_f64_value(&row, 0);
-        trades.push(pnl);
-    }
-
-    let (max_wins, max_losses) = calculate_streaks(&trades);
-    Ok((max_wins, max_losses))
-}
-
-/// Calculate consecutive streaks for options
-async fn calculate_options_consecutive_streaks(
-    conn: &Connection,
-    time_condition: &str,
-    time_params: &[chrono::DateTime<chrono::Utc>],
-) -> Result<(u32, u32)> {
-    // Get all options trades ordered by exit_date
-    let sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM options
-        WHERE status = 'closed' AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    let mut rows = conn
-        .prepare(&sql)
-        .await?
-        .query(libsql::params_from_iter(query_params))
-        .await?;
-
-    let mut trades = Vec::new();
-    while let Some(row) = rows.next().await? {
-        let pnl = get_f64_value(&row, 0);
-        trades.push(pnl);
-    }
-
-    let (max_wins, max_losses) = calculate_streaks(&trades);
-    Ok((max_wins, max_losses))
-}
-
-/// Calculate consecutive win and loss streaks from a sequence of P&L values
-fn calculate_streaks(trades: &[f64]) -> (u32, u32) {
-    let mut current_wins = 0;
-    let mut current_losses = 0;
-    let mut max_wins = 0;
-    let mut max_losses = 0;
-
-    for &pnl in trades {
-        if pnl > 0.0 {
-            // Win
-            current_wins += 1;
-            current_losses = 0;
-            max_wins = max_wins.max(current_wins);
-        } else if pnl < 0.0 {
-            // Loss
-            current_losses += 1;
-            current_wins = 0;
-            max_losses = max_losses.max(current_losses);
-        } else {
-            // Break-even - resets streak
-            current_wins = 0;
-            current_losses = 0;
-        }
-    }
-
-    (max_wins, max_losses)
-}
-
 /// Combine metrics from stocks and options tables
-fn combine_core_metrics(stocks: CoreMetrics, options: CoreMetrics) -> CoreMetrics {
+fn combine_core_metrics(stocks: CoreMetrics, options: CoreMetrics, streaks: &streaks::Streaks) -> CoreMetrics {
     let total_trades = stocks.total_trades + options.total_trades;
     let total_winning_trades = stocks.winning_trades + options.winning_trades;
     let total_losing_trades = stocks.losing_trades + options.losing_trades;
@@ -472,9 +363,8 @@ fn combine_core_metrics(stocks: CoreMetrics, options: CoreMetrics) -> CoreMetric
         0.0
     };
 
-    // Take the maximum consecutive streaks from both stocks and options
-    let max_consecutive_wins = stocks.max_consecutive_wins.max(options.max_consecutive_wins);
-    let max_consecutive_losses = stocks.max_consecutive_losses.max(options.max_consecutive_losses);
+    let max_consecutive_wins = streaks.max_consecutive_wins;
+    let max_consecutive_losses = streaks.max_consecutive_losses;
 
     CoreMetrics {
         total_trades,
@@ -714,7 +604,8 @@ pub async fn calculate_individual_option_trade_analytics(
             total_premium,
             commissions,
             entry_date,
-            exit_date
+            exit_date,
+            short_premium
         FROM options
         WHERE id = ?
     "#;
@@ -736,12 +627,11 @@ pub async fn calculate_individual_option_trade_analytics(
         let commissions: f64 = get_f64_value(&row, 9);
         let entry_date: Option<String> = row.get(10).ok();
         let exit_date: Option<String> = row.get(11).ok();
+        let short = get_i64_value(&row, 12) == 1;
 
         // Calculate net P&L for options
         let net_pnl = if let Some(exit) = exit_price {
-            // Option P&L = (exit_price - entry_price) * contracts * 100 - commissions
-            let pnl = (exit - entry_price) * number_of_contracts as f64 * 100.0;
-            pnl - commissions
+            money::to_f64(pnl::option_pnl(short, money::from_f64(entry_price), money::from_f64(exit), number_of_contracts as i64, money::from_f64(commissions)))
         } else {
             0.0
         };
@@ -751,7 +641,8 @@ pub async fn calculate_individual_option_trade_analytics(
         let planned_rr = if let Some(target) = profit_target {
             let risk = total_premium;
             if risk > 0.0 {
-                let reward = (target - entry_price) * number_of_contracts as f64 * 100.0;
+                let per_share = if short { entry_price - target } else { target - entry_price };
+                let reward = per_share * number_of_contracts as f64 * 100.0;
                 if reward > 0.0 {
                     Some(reward / risk)
                 } else {
@@ -819,7 +710,10 @@ pub async fn calculate_symbol_analytics(
     symbol: &str,
     time_range: &TimeRange,
 ) -> Result<SymbolAnalytics> {
-    let (time_condition, time_params) = time_range.to_sql_condition();
+    let periods = PeriodDefinition::load(conn).await;
+    let query = |source| TradeQuery::closed(source).time_range(time_range, &periods).symbol(symbol);
+    let stocks_query = query(AssetClass::Stocks);
+    let options_query = query(AssetClass::Options);
     
     // Get stocks analytics for this symbol
     let stocks_sql = format!(
@@ -837,27 +731,18 @@ pub async fn calculate_symbol_analytics(
             MIN(calculated_pnl) as biggest_loser
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
-            WHERE symbol = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+            WHERE {}
         )
         "#,
-        time_condition
+        stocks_query.condition()
     );
 
-    let mut query_params = vec![libsql::Value::Text(symbol.to_string())];
-    for param in &time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
     let mut rows = conn
         .prepare(&stocks_sql)
         .await?
-        .query(libsql::params_from_iter(query_params.clone()))
+        .query(libsql::params_from_iter(stocks_query.params()))
         .await?;
 
     let mut stock_trades = 0u32;
@@ -900,27 +785,18 @@ pub async fn calculate_symbol_analytics(
             MIN(calculated_pnl) as biggest_loser
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
-            WHERE symbol = ? AND status = 'closed' AND exit_price IS NOT NULL AND ({})
+            WHERE {}
         )
         "#,
-        time_condition
+        options_query.condition()
     );
 
-    let mut query_params = vec![libsql::Value::Text(symbol.to_string())];
-    for param in &time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
     let mut rows = conn
         .prepare(&options_sql)
         .await?
-        .query(libsql::params_from_iter(query_params))
+        .query(libsql::params_from_iter(options_query.params()))
         .await?;
 
     let mut option_trades = 0u32;
@@ -0,0 +1,141 @@
+//! Win/loss streaks across every asset class.
+//!
+//! A streak describes the trader, not the instrument, so the closed trades of all asset
+//! classes are merged into one sequence ordered by exit date before counting. A breakeven
+//! trade ends whatever streak is running.
+
+use anyhow::Result;
+use libsql::{Connection, Value};
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::query::{AssetClass, TradeFilter, TradeQuery};
+
+/// Newest closed trades scanned for the current streak
+const CURRENT_STREAK_LOOKBACK: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CurrentStreak {
+    /// "win", "loss" or "none"
+    pub kind: String,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Streaks {
+    /// Run the newest trades belong to
+    pub current: CurrentStreak,
+    pub max_consecutive_wins: u32,
+    pub max_consecutive_losses: u32,
+    /// Closed trades the streaks were counted over
+    pub trades: u32,
+}
+
+/// Streaks over P&L values ordered oldest exit first
+pub fn calculate(pnls: &[f64]) -> Streaks {
+    let (mut wins, mut losses, mut max_wins, mut max_losses) = (0u32, 0u32, 0u32, 0u32);
+    for &pnl in pnls {
+        if pnl > 0.0 {
+            wins += 1;
+            losses = 0;
+            max_wins = max_wins.max(wins);
+        } else if pnl < 0.0 {
+            losses += 1;
+            wins = 0;
+            max_losses = max_losses.max(losses);
+        } else {
+            wins = 0;
+            losses = 0;
+        }
+    }
+
+    let current = match (wins, losses) {
+        (0, 0) => CurrentStreak { kind: "none".to_string(), count: 0 },
+        (0, count) => CurrentStreak { kind: "loss".to_string(), count },
+        (count, _) => CurrentStreak { kind: "win".to_string(), count },
+    };
+    Streaks { current, max_consecutive_wins: max_wins, max_consecutive_losses: max_losses, trades: pnls.len() as u32 }
+}
+
+/// P&L of the trades matched by `queries`, one per asset class, merged oldest exit first.
+/// With `latest` set only that many of the newest trades are read.
+pub async fn load_pnls(conn: &Connection, queries: &[TradeQuery], latest: Option<u32>) -> Result<Vec<f64>> {
+    if queries.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let union = queries
+        .iter()
+        .map(|q| {
+            format!(
+                "SELECT exit_date, id, '{table}' AS asset, {pnl} AS pnl FROM {table} WHERE is_deleted = 0 AND {condition}",
+                table = q.table(),
+                pnl = q.pnl_sql(),
+                condition = q.condition()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" UNION ALL ");
+    // Same-instant exits still need a stable order, or streaks would shift between calls
+    let sql = match latest {
+        Some(limit) => format!(
+            "SELECT pnl FROM (SELECT * FROM ({union}) ORDER BY exit_date DESC, asset DESC, id DESC LIMIT {limit}) \
+             ORDER BY exit_date, asset, id"
+        ),
+        None => format!("SELECT pnl FROM ({union}) ORDER BY exit_date, asset, id"),
+    };
+    let params: Vec<Value> = queries.iter().flat_map(|q| q.params()).collect();
+
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(params)).await?;
+    let mut pnls = Vec::new();
+    while let Some(row) = rows.next().await? {
+        pnls.push(money::to_f64(money::row_decimal(&row, 0)));
+    }
+    Ok(pnls)
+}
+
+/// Streaks over the closed trades of every asset class in `time_range` matching `filter`
+pub async fn calculate_streaks(conn: &Connection, time_range: &TimeRange, filter: &TradeFilter) -> Result<Streaks> {
+    let periods = PeriodDefinition::load(conn).await;
+    let queries: Vec<TradeQuery> = AssetClass::ALL
+        .iter()
+        .map(|source| TradeQuery::closed(*source).time_range(time_range, &periods).filter(filter))
+        .collect();
+    Ok(calculate(&load_pnls(conn, &queries, None).await?))
+}
+
+/// The run the newest closed trades belong to, across every asset class
+pub async fn current_streak(conn: &Connection) -> Result<CurrentStreak> {
+    let queries = AssetClass::ALL.map(TradeQuery::closed);
+    let pnls = load_pnls(conn, &queries, Some(CURRENT_STREAK_LOOKBACK)).await?;
+    Ok(calculate(&pnls).current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_and_longest_streaks() {
+        let streaks = calculate(&[5.0, -10.0, -3.0, -1.0, 20.0, 50.0]);
+        assert_eq!(streaks.current, CurrentStreak { kind: "win".to_string(), count: 2 });
+        assert_eq!((streaks.max_consecutive_wins, streaks.max_consecutive_losses), (2, 3));
+        assert_eq!(streaks.trades, 6);
+
+        let losses = calculate(&[-2.0, -1.0, -5.0]);
+        assert_eq!((losses.current.kind.as_str(), losses.current.count), ("loss", 3));
+    }
+
+    #[test]
+    fn test_breakeven_ends_streak() {
+        let streaks = calculate(&[10.0, 10.0, 0.0]);
+        assert_eq!(streaks.current.kind, "none");
+        assert_eq!(streaks.max_consecutive_wins, 2);
+
+        let resumed = calculate(&[-1.0, 0.0, -1.0]);
+        assert_eq!((resumed.current.count, resumed.max_consecutive_losses), (1, 1));
+        assert_eq!(calculate(&[]).current.count, 0);
+    }
+}
@@ -0,0 +1,224 @@
+// Return correlation among the symbols a user trades most. Daily closes come from the
+// market API (cached for a few hours); symbols whose returns move together above a
+// threshold are grouped into clusters, since positions in them are effectively one bet.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate};
+use futures_util::future::join_all;
+use libsql::Connection;
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::cache_service::CacheService;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::historical::get_historical;
+use crate::turso::redis::ttl;
+use super::query::{AssetClass, TradeQuery};
+
+pub const DEFAULT_SYMBOLS: usize = 10;
+pub const MAX_SYMBOLS: usize = 20;
+pub const DEFAULT_CLUSTER_THRESHOLD: f64 = 0.7;
+/// Overlapping daily returns needed before a pair gets a coefficient
+const MIN_OVERLAP_DAYS: usize = 20;
+const HISTORY_RANGE: &str = "6mo";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrelationMatrix {
+    /// Most traded first
+    pub symbols: Vec<String>,
+    /// `matrix[i][j]` correlates `symbols[i]` with `symbols[j]`; None without enough overlapping data
+    pub matrix: Vec<Vec<Option<f64>>>,
+    /// Groups of two or more symbols linked by correlations at or above the threshold
+    pub clusters: Vec<Vec<String>>,
+    pub cluster_threshold: f64,
+    /// Most traded symbols without price history, left out of the matrix
+    pub unavailable_symbols: Vec<String>,
+}
+
+/// Pearson correlation coefficient; None for fewer than two pairs or a constant series
+pub fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(cov / (var_x.sqrt() * var_y.sqrt()))
+}
+
+/// Close-to-close returns keyed by the later day
+fn daily_returns(closes: &[(NaiveDate, f64)]) -> BTreeMap<NaiveDate, f64> {
+    closes
+        .windows(2)
+        .filter(|w| w[0].1 > 0.0)
+        .map(|w| (w[1].0, w[1].1 / w[0].1 - 1.0))
+        .collect()
+}
+
+fn return_correlation(a: &BTreeMap<NaiveDate, f64>, b: &BTreeMap<NaiveDate, f64>) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = a.iter().filter_map(|(date, x)| b.get(date).map(|y| (*x, *y))).collect();
+    if pairs.len() < MIN_OVERLAP_DAYS {
+        return None;
+    }
+    pearson(&pairs)
+}
+
+fn root(group: &mut [usize], mut i: usize) -> usize {
+    while group[i] != i {
+        group[i] = group[group[i]];
+        i = group[i];
+    }
+    i
+}
+
+/// Connected groups of symbols whose pairwise correlation reaches `threshold`
+fn clusters(symbols: &[String], matrix: &[Vec<Option<f64>>], threshold: f64) -> Vec<Vec<String>> {
+    let mut group: Vec<usize> = (0..symbols.len()).collect();
+    for (i, row) in matrix.iter().enumerate() {
+        for (j, correlation) in row.iter().enumerate().skip(i + 1) {
+            if correlation.is_some_and(|c| c >= threshold) {
+                let (a, b) = (root(&mut group, i), root(&mut group, j));
+                group[b] = a;
+            }
+        }
+    }
+    let mut by_root: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for (i, symbol) in symbols.iter().enumerate() {
+        by_root.entry(root(&mut group, i)).or_default().push(symbol.clone());
+    }
+    by_root.into_values().filter(|members| members.len() > 1).collect()
+}
+
+/// Symbols with the most closed trades in the range, stocks and options combined
+async fn most_traded_symbols(conn: &Connection, time_range: &TimeRange, limit: usize) -> Result<Vec<String>> {
+    let periods = PeriodDefinition::load(conn).await;
+    let stocks = TradeQuery::closed(AssetClass::Stocks).time_range(time_range, &periods);
+    let options = TradeQuery::closed(AssetClass::Options).time_range(time_range, &periods);
+    let sql = format!(
+        "SELECT symbol, COUNT(*) AS trades FROM ( \
+             SELECT UPPER(symbol) AS symbol FROM {} WHERE {} \
+             UNION ALL SELECT UPPER(symbol) AS symbol FROM {} WHERE {} \
+         ) GROUP BY symbol ORDER BY trades DESC, symbol ASC LIMIT {}",
+        stocks.table(),
+        stocks.condition(),
+        options.table(),
+        options.condition(),
+        limit
+    );
+    let params: Vec<libsql::Value> = stocks.params().into_iter().chain(options.params()).collect();
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(params)).await?;
+    let mut symbols = Vec::new();
+    while let Some(row) = rows.next().await? {
+        symbols.push(row.get::<String>(0)?);
+    }
+    Ok(symbols)
+}
+
+/// Daily closes in date order, shared across users through the cache
+async fn cached_closes(cache: &CacheService, market: &MarketClient, symbol: &str) -> Result<Vec<(NaiveDate, f64)>> {
+    let key = format!("market:closes:{}:{}", symbol, HISTORY_RANGE);
+    cache
+        .get_or_fetch(&key, ttl::HISTORICAL_CLOSES as u64, || async {
+            let history = get_historical(market, symbol, Some(HISTORY_RANGE), Some("1d")).await?;
+            Ok(history
+                .candles
+                .iter()
+                .filter_map(|candle| {
+                    let date = DateTime::from_timestamp(candle.time.parse::<i64>().ok()?, 0)?.date_naive();
+                    Some((date, candle.adj_close.unwrap_or(candle.close)))
+                })
+                .collect::<Vec<(NaiveDate, f64)>>())
+        })
+        .await
+}
+
+pub async fn calculate_correlation_matrix(
+    conn: &Connection,
+    cache: &CacheService,
+    market: &MarketClient,
+    time_range: &TimeRange,
+    limit: usize,
+    threshold: f64,
+) -> Result<CorrelationMatrix> {
+    let traded = most_traded_symbols(conn, time_range, limit.clamp(2, MAX_SYMBOLS)).await?;
+    let histories = join_all(traded.iter().map(|symbol| async move {
+        match cached_closes(cache, market, symbol).await {
+            Ok(closes) if closes.len() > MIN_OVERLAP_DAYS => Some(daily_returns(&closes)),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Correlation: no price history for {}: {}", symbol, e);
+                None
+            }
+        }
+    }))
+    .await;
+
+    let mut symbols = Vec::new();
+    let mut returns = Vec::new();
+    let mut unavailable_symbols = Vec::new();
+    for (symbol, history) in traded.into_iter().zip(histories) {
+        match history {
+            Some(history) => {
+                symbols.push(symbol);
+                returns.push(history);
+            }
+            None => unavailable_symbols.push(symbol),
+        }
+    }
+
+    let matrix: Vec<Vec<Option<f64>>> = (0..returns.len())
+        .map(|i| {
+            (0..returns.len())
+                .map(|j| if i == j { Some(1.0) } else { return_correlation(&returns[i], &returns[j]) })
+                .collect()
+        })
+        .collect();
+    Ok(CorrelationMatrix {
+        clusters: clusters(&symbols, &matrix, threshold),
+        symbols,
+        matrix,
+        cluster_threshold: threshold,
+        unavailable_symbols,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clusters_link_transitively() {
+        let symbols: Vec<String> = ["AMD", "NVDA", "SMH", "XOM"].iter().map(|s| s.to_string()).collect();
+        let matrix = vec![
+            vec![Some(1.0), Some(0.85), Some(0.5), Some(0.1)],
+            vec![Some(0.85), Some(1.0), Some(0.75), None],
+            vec![Some(0.5), Some(0.75), Some(1.0), Some(0.2)],
+            vec![Some(0.1), None, Some(0.2), Some(1.0)],
+        ];
+        assert_eq!(clusters(&symbols, &matrix, 0.7), vec![vec!["AMD".to_string(), "NVDA".to_string(), "SMH".to_string()]]);
+        assert!(clusters(&symbols, &matrix, 0.9).is_empty());
+    }
+
+    #[test]
+    fn test_daily_returns_and_pearson() {
+        let date = |d: u32| NaiveDate::from_ymd_opt(2026, 3, d).unwrap();
+        let returns = daily_returns(&[(date(2), 100.0), (date(3), 110.0), (date(4), 99.0)]);
+        assert_eq!(returns.len(), 2);
+        assert!((returns[&date(3)] - 0.10).abs() < 1e-9);
+        assert!((returns[&date(4)] + 0.10).abs() < 1e-9);
+        assert!(pearson(&[(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)]).is_some_and(|c| (c - 1.0).abs() < 1e-9));
+        assert_eq!(pearson(&[(1.0, 5.0), (2.0, 5.0)]), None);
+    }
+}
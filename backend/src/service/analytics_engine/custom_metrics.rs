@@ -0,0 +1,137 @@
+// User-defined KPIs. Definitions are stored per user; each is evaluated against the
+// built-in metrics of the period being viewed and returned next to them.
+
+use anyhow::Result;
+use libsql::{params, Connection, Row};
+use uuid::Uuid;
+
+use crate::models::analytics::custom_metrics::{
+    CustomMetricDefinition, CustomMetricRequest, CustomMetricValue, Formula, MetricVariables,
+};
+use crate::models::analytics::{AnalyticsOptions, CoreMetrics, PerformanceMetrics, RiskMetrics};
+use crate::models::stock::stocks::TimeRange;
+
+use super::{performance_metrics, risk_metrics};
+
+/// Most custom metrics one user can define
+pub const MAX_CUSTOM_METRICS: usize = 50;
+
+impl CustomMetricRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        let name = self.name.trim();
+        if name.is_empty() || name.chars().count() > 80 {
+            return Err("name must be 1-80 characters".to_string());
+        }
+        Formula::parse_checked(&self.formula).map(|_| ())
+    }
+}
+
+fn definition_from_row(row: &Row) -> Result<CustomMetricDefinition> {
+    Ok(CustomMetricDefinition {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        formula: row.get(2)?,
+        description: row.get(3)?,
+        created_at: row.get(4)?,
+        updated_at: row.get(5)?,
+    })
+}
+
+const DEFINITION_COLUMNS: &str = "id, name, formula, description, created_at, updated_at";
+
+pub async fn list_definitions(conn: &Connection) -> Result<Vec<CustomMetricDefinition>> {
+    let sql = format!("SELECT {} FROM custom_metrics ORDER BY name ASC", DEFINITION_COLUMNS);
+    let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+    let mut definitions = Vec::new();
+    while let Some(row) = rows.next().await? {
+        definitions.push(definition_from_row(&row)?);
+    }
+    Ok(definitions)
+}
+
+pub async fn get_definition(conn: &Connection, id: &str) -> Result<Option<CustomMetricDefinition>> {
+    let sql = format!("SELECT {} FROM custom_metrics WHERE id = ?", DEFINITION_COLUMNS);
+    let mut rows = conn.prepare(&sql).await?.query(params![id]).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(definition_from_row(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Store a validated definition; names are unique per user
+pub async fn create_definition(conn: &Connection, request: &CustomMetricRequest) -> Result<CustomMetricDefinition> {
+    let id = Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO custom_metrics (id, name, formula, description) VALUES (?, ?, ?, ?)",
+        params![id.as_str(), request.name.trim(), request.formula.trim(), request.description.clone()],
+    )
+    .await?;
+    get_definition(conn, &id).await?.ok_or_else(|| anyhow::anyhow!("Custom metric not found after insert"))
+}
+
+pub async fn update_definition(
+    conn: &Connection,
+    id: &str,
+    request: &CustomMetricRequest,
+) -> Result<Option<CustomMetricDefinition>> {
+    conn.execute(
+        "UPDATE custom_metrics SET name = ?, formula = ?, description = ? WHERE id = ?",
+        params![request.name.trim(), request.formula.trim(), request.description.clone(), id],
+    )
+    .await?;
+    get_definition(conn, id).await
+}
+
+pub async fn delete_definition(conn: &Connection, id: &str) -> Result<bool> {
+    let affected = conn.execute("DELETE FROM custom_metrics WHERE id = ?", params![id]).await?;
+    Ok(affected > 0)
+}
+
+/// Evaluate definitions against one period's metrics. A formula that fails (division by
+/// zero, a metric that no longer exists) gets an error instead of failing the rest.
+pub fn evaluate(definitions: &[CustomMetricDefinition], variables: &MetricVariables) -> Vec<CustomMetricValue> {
+    definitions
+        .iter()
+        .map(|definition| {
+            let result = Formula::parse(&definition.formula).and_then(|formula| formula.evaluate(variables));
+            let (value, error) = match result {
+                Ok(value) => (Some(value), None),
+                Err(e) => (None, Some(e)),
+            };
+            CustomMetricValue {
+                id: definition.id.clone(),
+                name: definition.name.clone(),
+                formula: definition.formula.clone(),
+                value,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// Custom metrics for a period whose core metrics are already known. Risk and performance
+/// metrics are only computed when a formula references them.
+pub async fn calculate_custom_metrics(
+    conn: &Connection,
+    time_range: &TimeRange,
+    core: &CoreMetrics,
+) -> Result<Vec<CustomMetricValue>> {
+    let definitions = list_definitions(conn).await?;
+    if definitions.is_empty() {
+        return Ok(Vec::new());
+    }
+    let needs_extended = definitions
+        .iter()
+        .filter_map(|definition| Formula::parse(&definition.formula).ok())
+        .any(|formula| formula.needs_extended_metrics());
+    let (risk, performance) = if needs_extended {
+        let options = AnalyticsOptions { time_range: time_range.clone(), ..Default::default() };
+        (
+            risk_metrics::calculate_risk_metrics(conn, time_range, &options).await?,
+            performance_metrics::calculate_performance_metrics(conn, time_range).await?,
+        )
+    } else {
+        (RiskMetrics::default(), PerformanceMetrics::default())
+    };
+    Ok(evaluate(&definitions, &MetricVariables::new(core, &risk, &performance)))
+}
@@ -0,0 +1,330 @@
+// Pre-aggregated daily P&L. `daily_pnl_stocks` and `daily_pnl_options` hold one row per
+// exit date, recomputed by triggers whenever a trade on that date changes, so daily
+// analytics scan a few hundred rows instead of every closed trade in the journal.
+//
+// `daily_open_risk` holds the stop-based risk of open stock positions for every calendar
+// day. A change to one trade touches every day it was held, which triggers can't iterate
+// over, so triggers only clear the table and it's rebuilt on the next read.
+
+use anyhow::Result;
+use libsql::Connection;
+use log::info;
+use serde::Serialize;
+use crate::models::money::{self, Decimal};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::turso::schema::TriggerInfo;
+
+/// Trade table feeding an aggregate table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DailySource {
+    Stocks,
+    Options,
+}
+
+impl DailySource {
+    pub const ALL: [DailySource; 2] = [DailySource::Stocks, DailySource::Options];
+
+    pub fn table(&self) -> &'static str {
+        match self {
+            DailySource::Stocks => "stocks",
+            DailySource::Options => "options",
+        }
+    }
+
+    pub fn aggregate_table(&self) -> &'static str {
+        match self {
+            DailySource::Stocks => "daily_pnl_stocks",
+            DailySource::Options => "daily_pnl_options",
+        }
+    }
+
+    /// Same P&L and closed-trade rules the raw daily queries use
+    fn pnl_sql(&self) -> &'static str {
+        match self {
+            DailySource::Stocks => STOCK_PNL_SQL,
+            DailySource::Options => OPTION_PNL_SQL,
+        }
+    }
+
+    fn closed_filter(&self) -> &'static str {
+        match self {
+            DailySource::Stocks => "exit_price IS NOT NULL AND exit_date IS NOT NULL",
+            DailySource::Options => "status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL",
+        }
+    }
+
+    /// Columns that change a trade's P&L or exit day
+    fn tracked_columns(&self) -> &'static str {
+        match self {
+            DailySource::Stocks => "trade_type, entry_price, exit_price, number_shares, commissions, exit_date",
+            DailySource::Options => "status, short_premium, entry_price, exit_price, number_of_contracts, commissions, exit_date",
+        }
+    }
+}
+
+/// One day of realized P&L across stocks and options
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyPnl {
+    pub trade_date: String,
+    pub realized_pnl: Decimal,
+    pub trade_count: u32,
+    pub win_count: u32,
+}
+
+/// Statements that rebuild aggregate rows from the trade table. With `day` set only that
+/// date is recomputed (used by triggers with `DATE(NEW.exit_date)` and friends).
+pub fn refresh_sql(source: DailySource, day: Option<&str>) -> String {
+    let (delete_filter, day_filter) = match day {
+        Some(day) => (format!(" WHERE trade_date = {}", day), format!(" AND DATE(exit_date) = {}", day)),
+        None => (String::new(), String::new()),
+    };
+    format!(
+        "DELETE FROM {agg}{delete_filter}; \
+         INSERT INTO {agg} (trade_date, realized_pnl, trade_count, win_count, loss_count, gross_profit, gross_loss, updated_at) \
+         SELECT trade_date, SUM(pnl), COUNT(*), \
+         SUM(CASE WHEN pnl > 0 THEN 1 ELSE 0 END), SUM(CASE WHEN pnl < 0 THEN 1 ELSE 0 END), \
+         SUM(CASE WHEN pnl > 0 THEN pnl ELSE 0 END), SUM(CASE WHEN pnl < 0 THEN -pnl ELSE 0 END), datetime('now') \
+         FROM (SELECT DATE(exit_date) AS trade_date, {pnl} AS pnl FROM {src} WHERE {closed}{day_filter}) \
+         GROUP BY trade_date",
+        agg = source.aggregate_table(),
+        src = source.table(),
+        pnl = source.pnl_sql(),
+        closed = source.closed_filter(),
+    )
+}
+
+/// Triggers on the trade table that keep its aggregate table current. Updates refresh both
+/// the old and new exit day so moving a trade between days is handled.
+pub fn aggregate_triggers(source: DailySource) -> Vec<TriggerInfo> {
+    let trigger = |suffix: &str, event: String, action: String| TriggerInfo {
+        name: format!("{}_{}", source.aggregate_table(), suffix),
+        table_name: source.table().to_string(),
+        event,
+        timing: "AFTER".to_string(),
+        action,
+    };
+    vec![
+        trigger("after_insert", "INSERT".to_string(), refresh_sql(source, Some("DATE(NEW.exit_date)"))),
+        trigger(
+            "after_update",
+            format!("UPDATE OF {}", source.tracked_columns()),
+            format!("{}; {}", refresh_sql(source, Some("DATE(OLD.exit_date)")), refresh_sql(source, Some("DATE(NEW.exit_date)"))),
+        ),
+        trigger("after_delete", "DELETE".to_string(), refresh_sql(source, Some("DATE(OLD.exit_date)"))),
+    ]
+}
+
+/// Recompute every aggregate row. Run once when the tables are first created so existing
+/// journals are backfilled; triggers take over from there.
+pub async fn rebuild_daily_aggregates(conn: &Connection) -> Result<()> {
+    for source in DailySource::ALL {
+        conn.execute_batch(&refresh_sql(source, None)).await?;
+    }
+    info!("Rebuilt daily P&L aggregates");
+    Ok(())
+}
+
+/// Rewrite a calendar-day `exit_date` condition from `TimeRange::to_sql_condition_for`
+/// to run against the aggregates' `trade_date`. Only valid when the user's trading day
+/// is the calendar day.
+pub fn aggregate_time_condition(time_condition: &str) -> String {
+    time_condition.replace("exit_date", "trade_date").replace('?', "date(?)")
+}
+
+/// Daily P&L across stocks and options in date order
+pub async fn load_daily_pnl(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<DailyPnl>> {
+    let condition = aggregate_time_condition(time_condition);
+    let sql = format!(
+        r#"
+        SELECT trade_date, SUM(realized_pnl), SUM(trade_count), SUM(win_count)
+        FROM (
+            SELECT trade_date, realized_pnl, trade_count, win_count FROM daily_pnl_stocks WHERE {condition}
+            UNION ALL
+            SELECT trade_date, realized_pnl, trade_count, win_count FROM daily_pnl_options WHERE {condition}
+        )
+        GROUP BY trade_date
+        ORDER BY trade_date
+        "#
+    );
+
+    // The condition appears once per aggregate table
+    let query_params: Vec<libsql::Value> = time_params
+        .iter()
+        .chain(time_params.iter())
+        .map(|param| libsql::Value::Text(param.to_rfc3339()))
+        .collect();
+
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query_params))
+        .await?;
+
+    let mut days = Vec::new();
+    while let Some(row) = rows.next().await? {
+        days.push(DailyPnl {
+            trade_date: row.get::<String>(0)?,
+            realized_pnl: money::row_decimal(&row, 1),
+            trade_count: row.get::<i64>(2).unwrap_or(0) as u32,
+            win_count: row.get::<i64>(3).unwrap_or(0) as u32,
+        });
+    }
+    Ok(days)
+}
+
+/// Stock columns that change a position's open risk or the days it was held
+const OPEN_RISK_COLUMNS: &str = "entry_price, stop_loss, number_shares, entry_date, exit_date, is_deleted";
+
+/// Risk on open stock positions for one day: entry-to-stop distance times shares, summed
+/// over positions open at any point that day
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DailyOpenRisk {
+    pub trade_date: String,
+    pub open_risk: f64,
+    pub open_positions: u32,
+}
+
+/// Triggers on `stocks` that invalidate `daily_open_risk`
+pub fn open_risk_triggers() -> Vec<TriggerInfo> {
+    let trigger = |suffix: &str, event: String| TriggerInfo {
+        name: format!("daily_open_risk_{}", suffix),
+        table_name: "stocks".to_string(),
+        event,
+        timing: "AFTER".to_string(),
+        action: "DELETE FROM daily_open_risk".to_string(),
+    };
+    vec![
+        trigger("after_insert", "INSERT".to_string()),
+        trigger("after_update", format!("UPDATE OF {}", OPEN_RISK_COLUMNS)),
+        trigger("after_delete", "DELETE".to_string()),
+    ]
+}
+
+/// Recompute `daily_open_risk` from the first entry with a stop through today
+pub async fn rebuild_open_risk(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DELETE FROM daily_open_risk; \
+         WITH RECURSIVE days(d) AS ( \
+             SELECT MIN(DATE(entry_date)) FROM stocks WHERE is_deleted = 0 AND stop_loss > 0 \
+             UNION ALL SELECT DATE(d, '+1 day') FROM days WHERE d < DATE('now') \
+         ) \
+         INSERT INTO daily_open_risk (trade_date, open_risk, open_positions, updated_at) \
+         SELECT d, COALESCE(SUM(ABS(s.entry_price - s.stop_loss) * s.number_shares), 0), COUNT(s.id), datetime('now') \
+         FROM days LEFT JOIN stocks s ON s.is_deleted = 0 AND s.stop_loss > 0 \
+             AND DATE(s.entry_date) <= d AND (s.exit_date IS NULL OR DATE(s.exit_date) >= d) \
+         WHERE d IS NOT NULL \
+         GROUP BY d",
+    )
+    .await?;
+    Ok(())
+}
+
+/// Daily open risk in date order, rebuilding the table when a trade changed or a new day
+/// started since it was last filled
+pub async fn load_open_risk(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<DailyOpenRisk>> {
+    let mut rows = conn
+        .prepare("SELECT MAX(trade_date) >= DATE('now') FROM daily_open_risk")
+        .await?
+        .query(libsql::params![])
+        .await?;
+    let current = match rows.next().await? {
+        Some(row) => row.get::<Option<i64>>(0)?.unwrap_or(0) == 1,
+        None => false,
+    };
+    if !current {
+        rebuild_open_risk(conn).await?;
+    }
+
+    let sql = format!(
+        "SELECT trade_date, open_risk, open_positions FROM daily_open_risk WHERE {} ORDER BY trade_date",
+        aggregate_time_condition(time_condition)
+    );
+    let query_params: Vec<libsql::Value> = time_params.iter().map(|param| libsql::Value::Text(param.to_rfc3339())).collect();
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query_params))
+        .await?;
+
+    let mut days = Vec::new();
+    while let Some(row) = rows.next().await? {
+        days.push(DailyOpenRisk {
+            trade_date: row.get::<String>(0)?,
+            open_risk: money::to_f64(money::row_decimal(&row, 1)),
+            open_positions: row.get::<i64>(2).unwrap_or(0) as u32,
+        });
+    }
+    Ok(days)
+}
+
+/// Open-risk ("heat") series with its peak and average
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenRiskSeries {
+    pub points: Vec<DailyOpenRisk>,
+    pub peak_open_risk: f64,
+    pub peak_date: Option<String>,
+    /// Over days with at least one open position
+    pub average_open_risk: f64,
+}
+
+impl OpenRiskSeries {
+    pub fn new(points: Vec<DailyOpenRisk>) -> Self {
+        let peak = points.iter().filter(|p| p.open_positions > 0).max_by(|a, b| a.open_risk.total_cmp(&b.open_risk));
+        let active: Vec<f64> = points.iter().filter(|p| p.open_positions > 0).map(|p| p.open_risk).collect();
+        Self {
+            peak_open_risk: peak.map(|p| p.open_risk).unwrap_or(0.0),
+            peak_date: peak.map(|p| p.trade_date.clone()),
+            average_open_risk: if active.is_empty() { 0.0 } else { active.iter().sum::<f64>() / active.len() as f64 },
+            points,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_time_condition() {
+        assert_eq!(
+            aggregate_time_condition("exit_date >= ? AND exit_date < ?"),
+            "trade_date >= date(?) AND trade_date < date(?)"
+        );
+        assert_eq!(
+            aggregate_time_condition("exit_date >= date('now', '-7 days')"),
+            "trade_date >= date('now', '-7 days')"
+        );
+    }
+
+    #[test]
+    fn test_refresh_sql_scopes_to_day() {
+        let sql = refresh_sql(DailySource::Options, Some("DATE(NEW.exit_date)"));
+        assert!(sql.starts_with("DELETE FROM daily_pnl_options WHERE trade_date = DATE(NEW.exit_date);"));
+        assert!(sql.contains("status = 'closed'"));
+        assert!(sql.contains("AND DATE(exit_date) = DATE(NEW.exit_date)"));
+        assert!(!refresh_sql(DailySource::Stocks, None).contains("WHERE trade_date"));
+    }
+
+    #[test]
+    fn test_open_risk_series_peak_and_average() {
+        let day = |date: &str, open_risk: f64, open_positions: u32| DailyOpenRisk { trade_date: date.to_string(), open_risk, open_positions };
+        let series = OpenRiskSeries::new(vec![
+            day("2026-03-02", 0.0, 0),
+            day("2026-03-03", 300.0, 1),
+            day("2026-03-04", 900.0, 3),
+            day("2026-03-05", 0.0, 0),
+        ]);
+        assert_eq!(series.peak_open_risk, 900.0);
+        assert_eq!(series.peak_date.as_deref(), Some("2026-03-04"));
+        assert_eq!(series.average_open_risk, 600.0);
+        assert_eq!(series.points.len(), 4);
+    }
+}
@@ -0,0 +1,201 @@
+// Drawdown-aware position sizing. The equity curve is the configured account size plus
+// the cumulative daily P&L of closed trades; while equity sits below its peak by at least
+// a tier's percentage, the tier's size multiplier applies (e.g. half size from -5%).
+
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::stock::stocks::TimeRange;
+use super::consistency::fetch_daily_pnl;
+
+/// Reduce size once equity is this far below its peak
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SizingTier {
+    pub drawdown_pct: f64,
+    /// Fraction of normal size, e.g. 0.5 for half size
+    pub size_multiplier: f64,
+}
+
+/// Drawdown sizing setting (single row, id 'default')
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingRule {
+    pub is_enabled: bool,
+    /// Starting equity the P&L curve is added to; no recommendation until it is set
+    pub account_size: Option<f64>,
+    /// Normal dollar risk per trade, scaled into `recommended_risk_per_trade`
+    pub base_risk_per_trade: Option<f64>,
+    pub tiers: Vec<SizingTier>,
+}
+
+impl Default for SizingRule {
+    fn default() -> Self {
+        Self {
+            is_enabled: true,
+            account_size: None,
+            base_risk_per_trade: None,
+            tiers: vec![
+                SizingTier { drawdown_pct: 5.0, size_multiplier: 0.5 },
+                SizingTier { drawdown_pct: 10.0, size_multiplier: 0.25 },
+            ],
+        }
+    }
+}
+
+impl SizingRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.account_size.is_some_and(|size| !size.is_finite() || size <= 0.0) {
+            return Err("account_size must be greater than 0".to_string());
+        }
+        if self.base_risk_per_trade.is_some_and(|risk| !risk.is_finite() || risk <= 0.0) {
+            return Err("base_risk_per_trade must be greater than 0".to_string());
+        }
+        if self.tiers.len() > 10 {
+            return Err("At most 10 sizing tiers are allowed".to_string());
+        }
+        for tier in &self.tiers {
+            if !(tier.drawdown_pct > 0.0 && tier.drawdown_pct < 100.0) {
+                return Err("drawdown_pct must be between 0 and 100".to_string());
+            }
+            if !(tier.size_multiplier >= 0.0 && tier.size_multiplier < 1.0) {
+                return Err("size_multiplier must be at least 0 and below 1".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn load(conn: &Connection) -> Result<Self> {
+        let mut rows = conn
+            .prepare("SELECT is_enabled, account_size, base_risk_per_trade, tiers FROM sizing_rules WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Self {
+                is_enabled: row.get::<i64>(0)? != 0,
+                account_size: row.get::<Option<f64>>(1)?,
+                base_risk_per_trade: row.get::<Option<f64>>(2)?,
+                tiers: serde_json::from_str(&row.get::<String>(3)?).unwrap_or_default(),
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO sizing_rules (id, is_enabled, account_size, base_risk_per_trade, tiers) VALUES ('default', ?, ?, ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET is_enabled = excluded.is_enabled, account_size = excluded.account_size, \
+             base_risk_per_trade = excluded.base_risk_per_trade, tiers = excluded.tiers",
+            params![self.is_enabled, self.account_size, self.base_risk_per_trade, serde_json::to_string(&self.tiers)?],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Current drawdown and the size it calls for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingRecommendation {
+    pub equity: f64,
+    pub peak_equity: f64,
+    /// Date equity last set its peak, None while it has never risen above the account size
+    pub peak_date: Option<String>,
+    pub drawdown_pct: f64,
+    /// 1.0 outside any tier
+    pub size_multiplier: f64,
+    /// The tier in effect, if any
+    pub active_tier: Option<SizingTier>,
+    pub recommended_risk_per_trade: Option<f64>,
+    pub message: String,
+}
+
+/// Recommendation from the rule and the full daily P&L history. None when the rule is off
+/// or no account size is configured.
+pub async fn recommend_position_size(conn: &Connection) -> Result<Option<SizingRecommendation>> {
+    let rule = SizingRule::load(conn).await?;
+    if !rule.is_enabled {
+        return Ok(None);
+    }
+    let (time_condition, time_params) = TimeRange::AllTime.to_sql_condition();
+    let daily_pnl = fetch_daily_pnl(conn, &time_condition, &time_params).await?;
+    Ok(recommend(&rule, &daily_pnl))
+}
+
+fn recommend(rule: &SizingRule, daily_pnl: &[(String, f64)]) -> Option<SizingRecommendation> {
+    let account_size = rule.account_size?;
+    let mut equity = account_size;
+    let mut peak_equity = account_size;
+    let mut peak_date = None;
+    for (date, pnl) in daily_pnl {
+        equity += pnl;
+        if equity > peak_equity {
+            peak_equity = equity;
+            peak_date = Some(date.clone());
+        }
+    }
+
+    let drawdown_pct = if peak_equity > 0.0 { ((peak_equity - equity) / peak_equity * 100.0).max(0.0) } else { 0.0 };
+    // The deepest tier reached applies
+    let active_tier = rule
+        .tiers
+        .iter()
+        .filter(|tier| drawdown_pct >= tier.drawdown_pct)
+        .max_by(|a, b| a.drawdown_pct.total_cmp(&b.drawdown_pct))
+        .copied();
+    let size_multiplier = active_tier.map_or(1.0, |tier| tier.size_multiplier);
+
+    let message = match active_tier {
+        Some(tier) if tier.size_multiplier == 0.0 => format!(
+            "Equity is {:.1}% below its peak. Stop trading until you have reviewed the drawdown.",
+            drawdown_pct
+        ),
+        Some(tier) => format!(
+            "Equity is {:.1}% below its peak (tier -{:.0}%). Trade at {:.0}% of normal size until it recovers.",
+            drawdown_pct,
+            tier.drawdown_pct,
+            tier.size_multiplier * 100.0
+        ),
+        None if drawdown_pct > 0.0 => format!("Equity is {:.1}% below its peak. Normal size.", drawdown_pct),
+        None => "Equity is at its peak. Normal size.".to_string(),
+    };
+
+    Some(SizingRecommendation {
+        equity,
+        peak_equity,
+        peak_date,
+        drawdown_pct,
+        size_multiplier,
+        active_tier,
+        recommended_risk_per_trade: rule.base_risk_per_trade.map(|risk| risk * size_multiplier),
+        message,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(values: &[f64]) -> Vec<(String, f64)> {
+        values.iter().enumerate().map(|(i, pnl)| (format!("2025-03-{:02}", i + 1), *pnl)).collect()
+    }
+
+    #[test]
+    fn test_recommend_applies_deepest_tier_reached() {
+        let rule = SizingRule { account_size: Some(10_000.0), base_risk_per_trade: Some(100.0), ..Default::default() };
+
+        // Peak 12,000 on day 2, then down to 11,100: 7.5% drawdown, half size
+        let half = recommend(&rule, &days(&[1_000.0, 1_000.0, -900.0])).unwrap();
+        assert_eq!(half.peak_equity, 12_000.0);
+        assert_eq!(half.peak_date.as_deref(), Some("2025-03-02"));
+        assert!((half.drawdown_pct - 7.5).abs() < 1e-9);
+        assert_eq!(half.size_multiplier, 0.5);
+        assert_eq!(half.recommended_risk_per_trade, Some(50.0));
+
+        let quarter = recommend(&rule, &days(&[-1_500.0])).unwrap();
+        assert_eq!(quarter.size_multiplier, 0.25);
+        assert_eq!(recommend(&rule, &days(&[200.0])).unwrap().size_multiplier, 1.0);
+
+        assert!(recommend(&SizingRule::default(), &days(&[-1_500.0])).is_none());
+        assert!(SizingRule { tiers: vec![SizingTier { drawdown_pct: 5.0, size_multiplier: 1.5 }], ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,234 @@
+// Entry and exit efficiency: how much of the available move a trade captured. Prices come
+// from daily candles, so for intraday trades the "available move" is the whole session.
+//
+//   entry efficiency  long (day high - entry) / day range, short (entry - day low) / day range
+//   exit efficiency   long (exit - low) / holding range,   short (high - exit) / holding range
+//   total efficiency  captured move / holding range; negative for losing trades
+//
+// Entry and exit are clamped to 0-100, since fills outside regular hours can fall outside
+// the daily candle. Stocks only: option premiums don't map onto the underlying's candles.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::future::join_all;
+use libsql::Connection;
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::historical::get_historical;
+use super::hold_time::parse_timestamp;
+use super::query::{AssetClass, TradeQuery};
+
+/// Most recent closed trades measured per request; each distinct symbol is one candle fetch
+const MAX_TRADES: usize = 200;
+/// Candle history ranges offered by the market API, shortest first, with their length in days
+const HISTORY_RANGES: [(&str, i64); 7] = [
+    ("1mo", 30),
+    ("3mo", 91),
+    ("6mo", 182),
+    ("1y", 365),
+    ("2y", 730),
+    ("5y", 1826),
+    ("10y", 3652),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeEfficiency {
+    pub trade_id: i64,
+    pub symbol: String,
+    /// `long` or `short`
+    pub direction: String,
+    pub entry_date: String,
+    pub exit_date: String,
+    pub entry_efficiency: Option<f64>,
+    pub exit_efficiency: Option<f64>,
+    pub total_efficiency: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EfficiencySummary {
+    pub average_entry_efficiency: Option<f64>,
+    pub average_exit_efficiency: Option<f64>,
+    pub average_total_efficiency: Option<f64>,
+    /// Trades with candle data for their whole holding period
+    pub measured_trades: usize,
+    /// Trades without candle data (delisted symbols, market data errors)
+    pub unmeasured_trades: usize,
+    /// Most recent exit first
+    pub trades: Vec<TradeEfficiency>,
+}
+
+/// High and low of one session
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DayRange {
+    date: NaiveDate,
+    high: f64,
+    low: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Efficiency {
+    entry: f64,
+    exit: f64,
+    total: f64,
+}
+
+/// Efficiency of one trade; None when the candles don't cover the holding period or the
+/// range is flat
+fn measure(long: bool, entry_price: f64, exit_price: f64, entry_day: NaiveDate, exit_day: NaiveDate, days: &[DayRange]) -> Option<Efficiency> {
+    let entry_session = days.iter().find(|d| d.date == entry_day)?;
+    let held: Vec<&DayRange> = days.iter().filter(|d| d.date >= entry_day && d.date <= exit_day).collect();
+    if !held.iter().any(|d| d.date == exit_day) {
+        return None;
+    }
+    // Fills are part of the range even when the candle missed them
+    let high = held.iter().map(|d| d.high).fold(entry_price.max(exit_price), f64::max);
+    let low = held.iter().map(|d| d.low).fold(entry_price.min(exit_price), f64::min);
+    let day_range = entry_session.high - entry_session.low;
+    let held_range = high - low;
+    if day_range <= 0.0 || held_range <= 0.0 {
+        return None;
+    }
+
+    let pct = |value: f64| (value * 100.0).clamp(0.0, 100.0);
+    let (entry, exit, captured) = if long {
+        ((entry_session.high - entry_price) / day_range, (exit_price - low) / held_range, exit_price - entry_price)
+    } else {
+        ((entry_price - entry_session.low) / day_range, (high - exit_price) / held_range, entry_price - exit_price)
+    };
+    Some(Efficiency { entry: pct(entry), exit: pct(exit), total: captured / held_range * 100.0 })
+}
+
+/// Shortest candle history reaching back to `since`
+pub(crate) fn history_range(since: NaiveDate, today: NaiveDate) -> &'static str {
+    let days = (today - since).num_days() + 5;
+    HISTORY_RANGES.iter().find(|(_, length)| days <= *length).map(|(range, _)| *range).unwrap_or("max")
+}
+
+async fn daily_ranges(market: &MarketClient, symbol: &str, range: &str) -> Result<Vec<DayRange>> {
+    let history = get_historical(market, symbol, Some(range), Some("1d")).await?;
+    Ok(history
+        .candles
+        .iter()
+        .filter_map(|candle| {
+            let date = DateTime::from_timestamp(candle.time.parse::<i64>().ok()?, 0)?.date_naive();
+            Some(DayRange { date, high: candle.high, low: candle.low })
+        })
+        .collect())
+}
+
+pub async fn calculate_efficiency(conn: &Connection, market: &MarketClient, time_range: &TimeRange) -> Result<EfficiencySummary> {
+    let periods = PeriodDefinition::load(conn).await;
+    let query = TradeQuery::closed(AssetClass::Stocks).time_range(time_range, &periods);
+    let sql = format!(
+        "SELECT id, UPPER(symbol), trade_type, entry_price, exit_price, entry_date, exit_date FROM {} WHERE {} \
+         ORDER BY exit_date DESC LIMIT {}",
+        query.table(),
+        query.condition(),
+        MAX_TRADES
+    );
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(query.params())).await?;
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let (entry_date, exit_date): (String, String) = (row.get(5)?, row.get(6)?);
+        let (Some(entry), Some(exit)) = (parse_timestamp(&entry_date), parse_timestamp(&exit_date)) else {
+            continue;
+        };
+        trades.push((
+            row.get::<i64>(0)?,
+            row.get::<String>(1)?,
+            row.get::<String>(2)? != "SELL",
+            money::to_f64(money::row_decimal(&row, 3)),
+            money::to_f64(money::row_decimal(&row, 4)),
+            entry,
+            exit,
+        ));
+    }
+
+    let today = Utc::now().date_naive();
+    let mut earliest: HashMap<&str, NaiveDate> = HashMap::new();
+    for (_, symbol, _, _, _, entry, _) in &trades {
+        let date = earliest.entry(symbol.as_str()).or_insert(entry.date_naive());
+        *date = (*date).min(entry.date_naive());
+    }
+    let candles: HashMap<&str, Vec<DayRange>> = join_all(earliest.into_iter().map(|(symbol, since)| async move {
+        match daily_ranges(market, symbol, history_range(since, today)).await {
+            Ok(days) => Some((symbol, days)),
+            Err(e) => {
+                log::warn!("Efficiency: no candles for {}: {}", symbol, e);
+                None
+            }
+        }
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let measured: Vec<TradeEfficiency> = trades
+        .iter()
+        .map(|(id, symbol, long, entry_price, exit_price, entry, exit)| {
+            let efficiency = candles
+                .get(symbol.as_str())
+                .and_then(|days| measure(*long, *entry_price, *exit_price, entry.date_naive(), exit.date_naive(), days));
+            TradeEfficiency {
+                trade_id: *id,
+                symbol: symbol.clone(),
+                direction: if *long { "long" } else { "short" }.to_string(),
+                entry_date: entry.to_rfc3339(),
+                exit_date: exit.to_rfc3339(),
+                entry_efficiency: efficiency.map(|e| e.entry),
+                exit_efficiency: efficiency.map(|e| e.exit),
+                total_efficiency: efficiency.map(|e| e.total),
+            }
+        })
+        .collect();
+
+    let average = |value: fn(&TradeEfficiency) -> Option<f64>| {
+        let values: Vec<f64> = measured.iter().filter_map(value).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    };
+    let measured_trades = measured.iter().filter(|t| t.total_efficiency.is_some()).count();
+    Ok(EfficiencySummary {
+        average_entry_efficiency: average(|t| t.entry_efficiency),
+        average_exit_efficiency: average(|t| t.exit_efficiency),
+        average_total_efficiency: average(|t| t.total_efficiency),
+        measured_trades,
+        unmeasured_trades: measured.len() - measured_trades,
+        trades: measured,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32, high: f64, low: f64) -> DayRange {
+        DayRange { date: NaiveDate::from_ymd_opt(2026, 3, d).unwrap(), high, low }
+    }
+
+    #[test]
+    fn test_measure_long_and_short() {
+        let days = [day(2, 110.0, 100.0), day(3, 120.0, 105.0), day(4, 118.0, 108.0)];
+        let (entry_day, exit_day) = (days[0].date, days[2].date);
+
+        // Long bought near the low, sold 10 below the high of a 20 point range
+        let long = measure(true, 102.0, 110.0, entry_day, exit_day, &days).unwrap();
+        assert!((long.entry - 80.0).abs() < 1e-9);
+        assert!((long.exit - 50.0).abs() < 1e-9);
+        assert!((long.total - 40.0).abs() < 1e-9);
+
+        let short = measure(false, 102.0, 110.0, entry_day, exit_day, &days).unwrap();
+        assert!((short.entry - 20.0).abs() < 1e-9);
+        assert!((short.total + 40.0).abs() < 1e-9);
+
+        // No candle for the exit day
+        assert_eq!(measure(true, 102.0, 110.0, entry_day, NaiveDate::from_ymd_opt(2026, 3, 9).unwrap(), &days), None);
+        assert_eq!(history_range(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap(), NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()), "3mo");
+    }
+}
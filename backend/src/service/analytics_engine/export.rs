@@ -0,0 +1,175 @@
+// Computed time series as downloadable tables, for users who'd rather analyze their numbers
+// in a spreadsheet or a notebook. Each metric is one of the series the dashboard charts,
+// flattened to a date column plus the metric's value columns; CSV and JSON carry the same
+// rows.
+
+use anyhow::Result;
+use libsql::Connection;
+use serde_json::{Map, Value};
+
+use crate::models::analytics::{AnalyticsOptions, TimeSeriesPoint};
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::daily_aggregates::load_open_risk;
+use crate::service::analytics_engine::time_series::calculate_time_series_data;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportMetric {
+    DailyPnl,
+    WeeklyPnl,
+    MonthlyPnl,
+    Drawdown,
+    RollingWinRate20,
+    RollingWinRate50,
+    RollingWinRate100,
+    RollingSharpe20,
+    RollingSharpe50,
+    OpenRisk,
+}
+
+impl ExportMetric {
+    pub const ALL: [ExportMetric; 10] = [
+        ExportMetric::DailyPnl,
+        ExportMetric::WeeklyPnl,
+        ExportMetric::MonthlyPnl,
+        ExportMetric::Drawdown,
+        ExportMetric::RollingWinRate20,
+        ExportMetric::RollingWinRate50,
+        ExportMetric::RollingWinRate100,
+        ExportMetric::RollingSharpe20,
+        ExportMetric::RollingSharpe50,
+        ExportMetric::OpenRisk,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExportMetric::DailyPnl => "daily_pnl",
+            ExportMetric::WeeklyPnl => "weekly_pnl",
+            ExportMetric::MonthlyPnl => "monthly_pnl",
+            ExportMetric::Drawdown => "drawdown",
+            ExportMetric::RollingWinRate20 => "rolling_win_rate_20",
+            ExportMetric::RollingWinRate50 => "rolling_win_rate_50",
+            ExportMetric::RollingWinRate100 => "rolling_win_rate_100",
+            ExportMetric::RollingSharpe20 => "rolling_sharpe_20",
+            ExportMetric::RollingSharpe50 => "rolling_sharpe_50",
+            ExportMetric::OpenRisk => "open_risk",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_lowercase();
+        Self::ALL.into_iter().find(|m| m.as_str() == value)
+    }
+
+    /// Value columns after `date`
+    fn columns(&self) -> &'static [&'static str] {
+        match self {
+            ExportMetric::DailyPnl | ExportMetric::WeeklyPnl | ExportMetric::MonthlyPnl => &["pnl", "cumulative_pnl", "trade_count"],
+            ExportMetric::Drawdown => &["drawdown"],
+            ExportMetric::RollingWinRate20 | ExportMetric::RollingWinRate50 | ExportMetric::RollingWinRate100 => &["win_rate"],
+            ExportMetric::RollingSharpe20 | ExportMetric::RollingSharpe50 => &["sharpe_ratio"],
+            ExportMetric::OpenRisk => &["open_risk", "open_positions"],
+        }
+    }
+}
+
+/// A series flattened to rows of `date` plus the metric's columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportTable {
+    pub metric: ExportMetric,
+    pub rows: Vec<(String, Vec<f64>)>,
+}
+
+impl ExportTable {
+    fn from_points(metric: ExportMetric, points: &[TimeSeriesPoint]) -> Self {
+        let rows = points
+            .iter()
+            .map(|p| {
+                let values = match metric.columns().len() {
+                    3 => vec![p.value, p.cumulative_value, p.trade_count as f64],
+                    _ => vec![p.value],
+                };
+                (p.date.clone(), values)
+            })
+            .collect();
+        Self { metric, rows }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = format!("date,{}\n", self.metric.columns().join(","));
+        for (date, values) in &self.rows {
+            out.push_str(date);
+            for value in values {
+                out.push(',');
+                out.push_str(&value.to_string());
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Rows as objects keyed by column name
+    pub fn to_records(&self) -> Vec<Value> {
+        self.rows
+            .iter()
+            .map(|(date, values)| {
+                let mut record = Map::new();
+                record.insert("date".to_string(), Value::from(date.as_str()));
+                for (column, value) in self.metric.columns().iter().zip(values) {
+                    record.insert(column.to_string(), Value::from(*value));
+                }
+                Value::Object(record)
+            })
+            .collect()
+    }
+}
+
+pub async fn export_series(conn: &Connection, metric: ExportMetric, time_range: &TimeRange, options: &AnalyticsOptions) -> Result<ExportTable> {
+    if metric == ExportMetric::OpenRisk {
+        let (time_condition, time_params) = time_range.to_sql_condition();
+        let rows = load_open_risk(conn, &time_condition, &time_params)
+            .await?
+            .into_iter()
+            .map(|p| (p.trade_date, vec![p.open_risk, p.open_positions as f64]))
+            .collect();
+        return Ok(ExportTable { metric, rows });
+    }
+
+    let data = calculate_time_series_data(conn, time_range, options).await?;
+    let points = match metric {
+        ExportMetric::DailyPnl => &data.daily_pnl,
+        ExportMetric::WeeklyPnl => &data.weekly_pnl,
+        ExportMetric::MonthlyPnl => &data.monthly_pnl,
+        ExportMetric::Drawdown => &data.drawdown_curve,
+        ExportMetric::RollingWinRate20 => &data.rolling_win_rate_20,
+        ExportMetric::RollingWinRate50 => &data.rolling_win_rate_50,
+        ExportMetric::RollingWinRate100 => &data.rolling_win_rate_100,
+        ExportMetric::RollingSharpe20 => &data.rolling_sharpe_ratio_20,
+        ExportMetric::RollingSharpe50 => &data.rolling_sharpe_ratio_50,
+        ExportMetric::OpenRisk => unreachable!("handled above"),
+    };
+    Ok(ExportTable::from_points(metric, points))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(date: &str, value: f64, cumulative_value: f64, trade_count: u32) -> TimeSeriesPoint {
+        TimeSeriesPoint { date: date.to_string(), value, cumulative_value, trade_count }
+    }
+
+    #[test]
+    fn test_export_table_formats() {
+        assert_eq!(ExportMetric::parse(" Daily_PnL"), Some(ExportMetric::DailyPnl));
+        assert_eq!(ExportMetric::parse("open_risk"), Some(ExportMetric::OpenRisk));
+        assert_eq!(ExportMetric::parse("sortino"), None);
+
+        let points = [point("2026-10-01", 120.5, 120.5, 3), point("2026-10-02", -20.0, 100.5, 1)];
+        let daily = ExportTable::from_points(ExportMetric::DailyPnl, &points);
+        assert_eq!(daily.to_csv(), "date,pnl,cumulative_pnl,trade_count\n2026-10-01,120.5,120.5,3\n2026-10-02,-20,100.5,1\n");
+        assert_eq!(daily.to_records()[1], serde_json::json!({"date": "2026-10-02", "pnl": -20.0, "cumulative_pnl": 100.5, "trade_count": 1.0}));
+
+        let win_rate = ExportTable::from_points(ExportMetric::RollingWinRate20, &points[..1]);
+        assert_eq!(win_rate.to_csv(), "date,win_rate\n2026-10-01,120.5\n");
+    }
+}
@@ -1,8 +1,14 @@
 use anyhow::Result;
 use libsql::Connection;
 use std::collections::HashMap;
-use crate::models::analytics::{GroupedMetrics, GroupType, AnalyticsOptions, CoreMetrics, RiskMetrics, PerformanceMetrics};
+use crate::models::analytics::{GroupedMetrics, GroupType, AnalyticsOptions, CoreMetrics, RiskMetrics, PerformanceMetrics, PeriodDefinition, SampleConfidence};
+use crate::models::money::{self, Decimal};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
 use crate::models::stock::stocks::TimeRange;
+use crate::models::venue::UNKNOWN_VENUE;
+use super::core_metrics::streaks;
+use super::performance_metrics::std_dev_from_moments;
+use super::query::{AssetClass, TradeQuery};
 
 /// Calculate grouped analytics by symbol, strategy, or other criteria
 pub async fn calculate_grouped_analytics(
@@ -30,6 +36,10 @@ pub async fn calculate_grouped_analytics(
                 let period_analytics = calculate_period_grouped_analytics(conn, time_range).await?;
                 grouped_analytics.extend(period_analytics);
             },
+            crate::models::analytics::options::GroupingType::Venue => {
+                let venue_analytics = calculate_venue_grouped_analytics(conn, time_range).await?;
+                grouped_analytics.extend(venue_analytics);
+            },
         }
     }
     
@@ -101,6 +111,8 @@ async fn calculate_symbol_grouped_analytics(
         let core_metrics = calculate_symbol_core_metrics(conn, &symbol, &time_condition, &time_params).await?;
         let risk_metrics = calculate_symbol_risk_metrics(conn, &symbol, &time_condition, &time_params).await?;
         let performance_metrics = calculate_symbol_performance_metrics(conn, &symbol, &time_condition, &time_params).await?;
+        let symbol_param = [libsql::Value::Text(symbol.clone())];
+        let pnls = group_pnls(conn, &both_tables("symbol = ?"), &symbol_param, &time_condition, &time_params).await?;
 
         grouped_analytics.insert(symbol.clone(), GroupedMetrics {
             group_name: symbol.clone(),
@@ -108,6 +120,7 @@ async fn calculate_symbol_grouped_analytics(
             core_metrics,
             risk_metrics,
             performance_metrics,
+            confidence: SampleConfidence::from_pnls(&pnls),
         });
     }
 
@@ -155,6 +168,9 @@ async fn calculate_strategy_grouped_analytics(
         let core_metrics = calculate_strategy_core_metrics(conn, &strategy, &time_condition, &time_params).await?;
         let risk_metrics = calculate_strategy_risk_metrics(conn, &strategy, &time_condition, &time_params).await?;
         let performance_metrics = calculate_strategy_performance_metrics(conn, &strategy, &time_condition, &time_params).await?;
+        let strategy_param = [libsql::Value::Text(strategy.clone())];
+        let options_only = [(AssetClass::Options, Some("strategy_type = ?"))];
+        let pnls = group_pnls(conn, &options_only, &strategy_param, &time_condition, &time_params).await?;
 
         grouped_analytics.insert(strategy.clone(), GroupedMetrics {
             group_name: strategy.clone(),
@@ -162,6 +178,7 @@ async fn calculate_strategy_grouped_analytics(
             core_metrics,
             risk_metrics,
             performance_metrics,
+            confidence: SampleConfidence::from_pnls(&pnls),
         });
     }
 
@@ -174,11 +191,13 @@ async fn calculate_direction_grouped_analytics(
     time_range: &TimeRange,
 ) -> Result<HashMap<String, GroupedMetrics>> {
     let mut grouped_analytics = HashMap::new();
+    let (time_condition, time_params) = time_range.to_sql_condition();
     
     // Bullish trades (BUY stocks, CALL options)
     let bullish_core = calculate_direction_core_metrics(conn, "bullish", time_range).await?;
     let bullish_risk = calculate_direction_risk_metrics(conn, "bullish", time_range).await?;
     let bullish_performance = calculate_direction_performance_metrics(conn, "bullish", time_range).await?;
+    let bullish_pnls = group_pnls(conn, &direction_tables(true), &[], &time_condition, &time_params).await?;
 
     grouped_analytics.insert("Bullish".to_string(), GroupedMetrics {
         group_name: "Bullish".to_string(),
@@ -186,12 +205,14 @@ async fn calculate_direction_grouped_analytics(
         core_metrics: bullish_core,
         risk_metrics: bullish_risk,
         performance_metrics: bullish_performance,
+        confidence: SampleConfidence::from_pnls(&bullish_pnls),
     });
 
     // Bearish trades (SELL stocks, PUT options)
     let bearish_core = calculate_direction_core_metrics(conn, "bearish", time_range).await?;
     let bearish_risk = calculate_direction_risk_metrics(conn, "bearish", time_range).await?;
     let bearish_performance = calculate_direction_performance_metrics(conn, "bearish", time_range).await?;
+    let bearish_pnls = group_pnls(conn, &direction_tables(false), &[], &time_condition, &time_params).await?;
 
     grouped_analytics.insert("Bearish".to_string(), GroupedMetrics {
         group_name: "Bearish".to_string(),
@@ -199,6 +220,7 @@ async fn calculate_direction_grouped_analytics(
         core_metrics: bearish_core,
         risk_metrics: bearish_risk,
         performance_metrics: bearish_performance,
+        confidence: SampleConfidence::from_pnls(&bearish_pnls),
     });
 
     Ok(grouped_analytics)
@@ -211,19 +233,19 @@ async fn calculate_period_grouped_analytics(
 ) -> Result<HashMap<String, GroupedMetrics>> {
     let mut grouped_analytics = HashMap::new();
     
-    use chrono::{Datelike, Utc};
+    use chrono::Utc;
     let now = Utc::now();
     
-    // Calculate metrics for different time periods
-    let ytd_date = chrono::NaiveDate::from_ymd_opt(now.year(), 1, 1)
-        .and_then(|d| d.and_hms_opt(0, 0, 0))
-        .map(|ndt| chrono::DateTime::from_naive_utc_and_offset(ndt, Utc))
-        .unwrap_or(now);
+    // Year and quarter boundaries follow the user's fiscal calendar (Jan 1 by default)
+    let definition = PeriodDefinition::load(conn).await;
+    let ytd_date = definition.fiscal_year_start(now);
+    let qtd_date = definition.fiscal_quarter_start(now);
     
     let periods = vec![
         ("daily", "Last 24 Hours", now - chrono::Duration::days(1), now),
         ("weekly", "Last 7 Days", now - chrono::Duration::days(7), now),
         ("90days", "Last 90 Days", now - chrono::Duration::days(90), now),
+        ("qtd", "Quarter to Date", qtd_date, now),
         ("ytd", "Year to Date", ytd_date, now),
         ("year", "Last Year", now - chrono::Duration::days(365), now),
     ];
@@ -238,6 +260,8 @@ async fn calculate_period_grouped_analytics(
         let core = calculate_period_core_metrics(conn, &period_range, time_range).await?;
         let risk = calculate_period_risk_metrics(conn, &period_range, time_range).await?;
         let performance = calculate_period_performance_metrics(conn, &period_range, time_range).await?;
+        let (period_condition, period_params) = period_range.to_sql_condition();
+        let pnls = group_pnls(conn, &ALL_TRADES, &[], &period_condition, &period_params).await?;
 
         grouped_analytics.insert(period_name.to_string(), GroupedMetrics {
             group_name: period_name.to_string(),
@@ -245,6 +269,34 @@ async fn calculate_period_grouped_analytics(
             core_metrics: core,
             risk_metrics: risk,
             performance_metrics: performance,
+            confidence: SampleConfidence::from_pnls(&pnls),
+        });
+    }
+
+    Ok(grouped_analytics)
+}
+
+/// Calculate analytics grouped by the venue trades executed on, so fees and fill quality
+/// can be compared across exchanges. Trades without a venue are grouped under "Unknown".
+async fn calculate_venue_grouped_analytics(
+    conn: &Connection,
+    time_range: &TimeRange,
+) -> Result<HashMap<String, GroupedMetrics>> {
+    let mut grouped_analytics = HashMap::new();
+
+    for venue in list_venues(conn, time_range).await? {
+        let trades = venue_closed_trades(conn, &venue, time_range).await?;
+        let core = calculate_venue_core_metrics(conn, &venue, time_range, &trades).await?;
+        let risk = risk_metrics_from_daily_returns(venue_daily_returns(&trades)).await?;
+        let performance = calculate_venue_performance_metrics(conn, &venue, time_range, &core, &trades).await?;
+
+        grouped_analytics.insert(venue.clone(), GroupedMetrics {
+            group_name: venue,
+            group_type: GroupType::Venue,
+            core_metrics: core,
+            risk_metrics: risk,
+            performance_metrics: performance,
+            confidence: SampleConfidence::from_pnls(&trades.iter().map(|t| t.pnl).collect::<Vec<_>>()),
         });
     }
 
@@ -392,11 +444,7 @@ async fn calculate_symbol_stocks_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE symbol = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -514,11 +562,7 @@ async fn calculate_symbol_options_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE symbol = ? AND status = 'closed' AND ({})
         )
@@ -883,11 +927,7 @@ async fn calculate_symbol_daily_returns(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE symbol = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             
@@ -895,11 +935,7 @@ async fn calculate_symbol_daily_returns(
             
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE symbol = ? AND status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -939,16 +975,16 @@ async fn calculate_symbol_drawdown_metrics(daily_returns: &[f64]) -> Result<Draw
         });
     }
 
-    let mut cumulative_pnl: f64 = 0.0;
-    let mut peak: f64 = 0.0;
-    let mut max_drawdown: f64 = 0.0;
+    let mut cumulative_pnl = Decimal::ZERO;
+    let mut peak = Decimal::ZERO;
+    let mut max_drawdown = Decimal::ZERO;
     let mut max_drawdown_percentage: f64 = 0.0;
     let mut current_drawdown_duration = 0;
     let mut max_drawdown_duration = 0;
     let mut ulcer_sum: f64 = 0.0;
 
     for &pnl in daily_returns {
-        cumulative_pnl += pnl;
+        cumulative_pnl += money::from_f64(pnl);
         
         if cumulative_pnl > peak {
             peak = cumulative_pnl;
@@ -958,21 +994,21 @@ async fn calculate_symbol_drawdown_metrics(daily_returns: &[f64]) -> Result<Draw
             max_drawdown_duration = max_drawdown_duration.max(current_drawdown_duration);
         }
 
-        let drawdown: f64 = peak - cumulative_pnl;
+        let drawdown = peak - cumulative_pnl;
         max_drawdown = max_drawdown.max(drawdown);
         
-        if peak > 0.0 {
-            let drawdown_percentage: f64 = (drawdown / peak) * 100.0;
+        if peak > Decimal::ZERO {
+            let drawdown_percentage: f64 = money::to_f64(drawdown / peak) * 100.0;
             max_drawdown_percentage = max_drawdown_percentage.max(drawdown_percentage);
             ulcer_sum += ((drawdown_percentage / 100.0) * 100.0).powi(2);
         }
     }
 
-    let current_drawdown = peak - cumulative_pnl;
+    let current_drawdown = money::to_f64(peak - cumulative_pnl);
     let ulcer_index = (ulcer_sum / daily_returns.len() as f64).sqrt();
 
     Ok(DrawdownMetrics {
-        maximum_drawdown: max_drawdown,
+        maximum_drawdown: money::to_f64(max_drawdown),
         maximum_drawdown_percentage: max_drawdown_percentage,
         maximum_drawdown_duration_days: max_drawdown_duration,
         current_drawdown,
@@ -1075,7 +1111,8 @@ async fn calculate_symbol_position_sizing(
         r#"
         SELECT 
             AVG(number_shares * entry_price) as avg_position_size,
-            STDDEV(number_shares * entry_price) as position_size_std_dev
+            AVG((number_shares * entry_price) * (number_shares * entry_price)) as position_size_mean_square,
+            COUNT(*) as trade_count
         FROM stocks
         WHERE symbol = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         "#,
@@ -1091,7 +1128,7 @@ async fn calculate_symbol_position_sizing(
     
     if let Some(row) = rows.next().await? {
         let avg_size = get_f64_value(&row, 0);
-        let std_dev = get_f64_value(&row, 1);
+        let std_dev = std_dev_from_moments(get_i64_value(&row, 2), avg_size, get_f64_value(&row, 1));
         let variability = if avg_size > 0.0 { std_dev / avg_size } else { 0.0 };
         
         Ok(PositionSizing {
@@ -1138,11 +1175,7 @@ async fn calculate_strategy_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE strategy_type = ? AND status = 'closed' AND ({})
         )
@@ -1469,11 +1502,7 @@ async fn calculate_strategy_daily_returns(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE strategy_type = ? AND status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -1591,7 +1620,8 @@ async fn calculate_strategy_position_sizing(
         r#"
         SELECT 
             AVG(total_premium) as avg_position_size,
-            STDDEV(total_premium) as position_size_std_dev
+            AVG(total_premium * total_premium) as position_size_mean_square,
+            COUNT(*) as trade_count
         FROM options
         WHERE strategy_type = ? AND status = 'closed' AND ({})
         "#,
@@ -1607,7 +1637,7 @@ async fn calculate_strategy_position_sizing(
     
     if let Some(row) = rows.next().await? {
         let avg_size = get_f64_value(&row, 0);
-        let std_dev = get_f64_value(&row, 1);
+        let std_dev = std_dev_from_moments(get_i64_value(&row, 2), avg_size, get_f64_value(&row, 1));
         let variability = if avg_size > 0.0 { std_dev / avg_size } else { 0.0 };
         
         Ok(PositionSizing {
@@ -1662,11 +1692,7 @@ async fn calculate_direction_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE {} AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -1705,11 +1731,7 @@ async fn calculate_direction_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE {} AND status = 'closed' AND ({})
         )
@@ -1883,7 +1905,11 @@ async fn calculate_direction_risk_metrics(
     
     // Calculate daily returns for this direction
     let daily_returns = calculate_direction_daily_returns(conn, direction, &time_condition, &time_params).await?;
-    
+    risk_metrics_from_daily_returns(daily_returns).await
+}
+
+/// Risk metrics over a group's daily P&L series
+async fn risk_metrics_from_daily_returns(daily_returns: Vec<f64>) -> Result<RiskMetrics> {
     // Calculate drawdown metrics
     let drawdown_metrics = calculate_symbol_drawdown_metrics(&daily_returns).await?;
     
@@ -2078,11 +2104,7 @@ async fn calculate_direction_daily_returns(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE {} AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             
@@ -2090,11 +2112,7 @@ async fn calculate_direction_daily_returns(
             
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE {} AND status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -2240,6 +2258,318 @@ async fn calculate_direction_losers_hold_time(
     }
 }
 
+// Helper functions for venue-specific calculations
+
+/// A closed trade at a venue, in exit order
+struct VenueTrade {
+    exit_day: String,
+    pnl: f64,
+    hold_days: f64,
+}
+
+/// A trade's venue group; trades without a venue fall under `UNKNOWN_VENUE`
+fn venue_group_sql() -> String {
+    format!("COALESCE(venue, '{}')", UNKNOWN_VENUE)
+}
+
+/// The venue followed by the time condition's parameters
+fn venue_params(venue: &str, time_params: &[chrono::DateTime<chrono::Utc>]) -> Vec<libsql::Value> {
+    std::iter::once(libsql::Value::Text(venue.to_string()))
+        .chain(time_params.iter().map(|param| libsql::Value::Text(param.to_rfc3339())))
+        .collect()
+}
+
+/// Venues with closed trades in the time range
+async fn list_venues(conn: &Connection, time_range: &TimeRange) -> Result<Vec<String>> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+    let group = venue_group_sql();
+    let sql = format!(
+        r#"
+        SELECT {group} FROM stocks
+        WHERE is_deleted = 0 AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({time_condition})
+        UNION
+        SELECT {group} FROM options
+        WHERE is_deleted = 0 AND status = 'closed' AND ({time_condition})
+        "#
+    );
+
+    let mut query_params = Vec::new();
+    for _ in 0..2 {
+        for param in &time_params {
+            query_params.push(libsql::Value::Text(param.to_rfc3339()));
+        }
+    }
+
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(query_params)).await?;
+    let mut venues = Vec::new();
+    while let Some(row) = rows.next().await? {
+        venues.push(row.get::<String>(0)?);
+    }
+    Ok(venues)
+}
+
+async fn venue_closed_trades(conn: &Connection, venue: &str, time_range: &TimeRange) -> Result<Vec<VenueTrade>> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+    let group = venue_group_sql();
+    let sql = format!(
+        r#"
+        SELECT DATE(exit_date) as exit_day, calculated_pnl, hold_days
+        FROM (
+            SELECT exit_date, {STOCK_PNL_SQL} as calculated_pnl,
+                   JULIANDAY(exit_date) - JULIANDAY(entry_date) as hold_days
+            FROM stocks
+            WHERE {group} = ? AND is_deleted = 0 AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({time_condition})
+
+            UNION ALL
+
+            SELECT exit_date, {OPTION_PNL_SQL} as calculated_pnl,
+                   JULIANDAY(exit_date) - JULIANDAY(entry_date) as hold_days
+            FROM options
+            WHERE {group} = ? AND is_deleted = 0 AND status = 'closed' AND exit_price IS NOT NULL AND ({time_condition})
+        )
+        ORDER BY exit_date ASC
+        "#
+    );
+
+    let mut query_params = venue_params(venue, &time_params);
+    query_params.extend(venue_params(venue, &time_params));
+
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(query_params)).await?;
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        trades.push(VenueTrade {
+            exit_day: row.get::<Option<String>>(0)?.unwrap_or_default(),
+            pnl: get_f64_value(&row, 1),
+            hold_days: get_f64_value(&row, 2),
+        });
+    }
+    Ok(trades)
+}
+
+/// Daily P&L from trades already in exit order
+fn venue_daily_returns(trades: &[VenueTrade]) -> Vec<f64> {
+    let mut daily_returns: Vec<f64> = Vec::new();
+    let mut current_day: Option<&str> = None;
+    for trade in trades {
+        if current_day == Some(trade.exit_day.as_str())
+            && let Some(total) = daily_returns.last_mut()
+        {
+            *total += trade.pnl;
+        } else {
+            daily_returns.push(trade.pnl);
+            current_day = Some(trade.exit_day.as_str());
+        }
+    }
+    daily_returns
+}
+
+fn venue_avg_hold_time(trades: &[VenueTrade], include: impl Fn(&VenueTrade) -> bool) -> f64 {
+    let hold_days: Vec<f64> = trades.iter().filter(|t| include(t)).map(|t| t.hold_days).collect();
+    if hold_days.is_empty() {
+        0.0
+    } else {
+        hold_days.iter().sum::<f64>() / hold_days.len() as f64
+    }
+}
+
+/// Calculate core metrics for trades at a specific venue
+async fn calculate_venue_core_metrics(
+    conn: &Connection,
+    venue: &str,
+    time_range: &TimeRange,
+    trades: &[VenueTrade],
+) -> Result<CoreMetrics> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+    let group = venue_group_sql();
+    let core_sql = |pnl_sql: &str, table: &str, closed: &str, position_size: &str| {
+        format!(
+            r#"
+            SELECT 
+                COUNT(*) as total_trades,
+                SUM(CASE WHEN calculated_pnl > 0 THEN 1 ELSE 0 END) as winning_trades,
+                SUM(CASE WHEN calculated_pnl < 0 THEN 1 ELSE 0 END) as losing_trades,
+                SUM(CASE WHEN calculated_pnl = 0 THEN 1 ELSE 0 END) as break_even_trades,
+                SUM(calculated_pnl) as total_pnl,
+                SUM(CASE WHEN calculated_pnl > 0 THEN calculated_pnl ELSE 0 END) as gross_profit,
+                SUM(CASE WHEN calculated_pnl < 0 THEN calculated_pnl ELSE 0 END) as gross_loss,
+                AVG(CASE WHEN calculated_pnl > 0 THEN calculated_pnl END) as average_win,
+                AVG(CASE WHEN calculated_pnl < 0 THEN calculated_pnl END) as average_loss,
+                MAX(calculated_pnl) as biggest_winner,
+                MIN(calculated_pnl) as biggest_loser,
+                SUM(commissions) as total_commissions,
+                AVG(commissions) as average_commission_per_trade,
+                AVG({position_size}) as average_position_size
+            FROM (
+                SELECT 
+                    *,
+                    {pnl_sql} as calculated_pnl
+                FROM {table}
+                WHERE {group} = ? AND is_deleted = 0 AND {closed} AND ({time_condition})
+            )
+            "#
+        )
+    };
+
+    let stocks_sql = core_sql(STOCK_PNL_SQL, "stocks", "exit_price IS NOT NULL AND exit_date IS NOT NULL", "number_shares * entry_price");
+    let mut stocks_metrics = CoreMetrics::default();
+    if let Some(row) = conn.prepare(&stocks_sql).await?.query(libsql::params_from_iter(venue_params(venue, &time_params))).await?.next().await? {
+        stocks_metrics = build_core_metrics_from_row(&row)?;
+    }
+
+    let options_sql = core_sql(OPTION_PNL_SQL, "options", "status = 'closed'", "total_premium");
+    let mut options_metrics = CoreMetrics::default();
+    if let Some(row) = conn.prepare(&options_sql).await?.query(libsql::params_from_iter(venue_params(venue, &time_params))).await?.next().await? {
+        options_metrics = build_core_metrics_from_row(&row)?;
+    }
+
+    let pnls: Vec<f64> = trades.iter().map(|t| t.pnl).collect();
+    Ok(combine_core_metrics(&stocks_metrics, &options_metrics, calculate_streaks(&pnls)))
+}
+
+/// Merge one group's stock and option metrics into combined totals and rates
+fn combine_core_metrics(stocks: &CoreMetrics, options: &CoreMetrics, (max_consecutive_wins, max_consecutive_losses): (u32, u32)) -> CoreMetrics {
+    let total_trades = stocks.total_trades + options.total_trades;
+    let winning_trades = stocks.winning_trades + options.winning_trades;
+    let losing_trades = stocks.losing_trades + options.losing_trades;
+    let total_pnl = stocks.total_pnl + options.total_pnl;
+    let gross_profit = stocks.gross_profit + options.gross_profit;
+    let gross_loss = stocks.gross_loss + options.gross_loss;
+    let total_commissions = stocks.total_commissions + options.total_commissions;
+
+    let average_win = if winning_trades > 0 {
+        (stocks.average_win * stocks.winning_trades as f64 + options.average_win * options.winning_trades as f64) / winning_trades as f64
+    } else {
+        0.0
+    };
+    let average_loss = if losing_trades > 0 {
+        (stocks.average_loss * stocks.losing_trades as f64 + options.average_loss * options.losing_trades as f64) / losing_trades as f64
+    } else {
+        0.0
+    };
+
+    let stocks_weight = if total_trades > 0 { stocks.total_trades as f64 / total_trades as f64 } else { 0.0 };
+    let rate = |count: u32| if total_trades > 0 { (count as f64 / total_trades as f64) * 100.0 } else { 0.0 };
+
+    let profit_factor = if gross_loss != 0.0 {
+        gross_profit.abs() / gross_loss.abs()
+    } else if gross_profit > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+    let win_loss_ratio = if average_loss != 0.0 {
+        average_win.abs() / average_loss.abs()
+    } else if average_win > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    };
+
+    CoreMetrics {
+        total_trades,
+        winning_trades,
+        losing_trades,
+        break_even_trades: stocks.break_even_trades + options.break_even_trades,
+        win_rate: rate(winning_trades),
+        loss_rate: rate(losing_trades),
+        total_pnl,
+        net_profit_loss: total_pnl,
+        gross_profit,
+        gross_loss,
+        average_win,
+        average_loss,
+        average_position_size: stocks.average_position_size * stocks_weight + options.average_position_size * (1.0 - stocks_weight),
+        biggest_winner: stocks.biggest_winner.max(options.biggest_winner),
+        biggest_loser: stocks.biggest_loser.min(options.biggest_loser),
+        profit_factor,
+        win_loss_ratio,
+        max_consecutive_wins,
+        max_consecutive_losses,
+        total_commissions,
+        average_commission_per_trade: if total_trades > 0 { total_commissions / total_trades as f64 } else { 0.0 },
+    }
+}
+
+/// Calculate performance metrics for trades at a specific venue. Fill quality is the
+/// average stop-out slippage of its stock trades; fees show up as commission impact.
+async fn calculate_venue_performance_metrics(
+    conn: &Connection,
+    venue: &str,
+    time_range: &TimeRange,
+    core: &CoreMetrics,
+    trades: &[VenueTrade],
+) -> Result<PerformanceMetrics> {
+    let trade_expectancy = if core.total_trades > 0 {
+        (core.average_win * core.win_rate / 100.0) - (core.average_loss.abs() * core.loss_rate / 100.0)
+    } else {
+        0.0
+    };
+
+    let edge = if core.average_position_size > 0.0 {
+        trade_expectancy / core.average_position_size
+    } else {
+        0.0
+    };
+
+    let payoff_ratio = if core.average_loss != 0.0 {
+        core.average_win / core.average_loss.abs()
+    } else {
+        0.0
+    };
+
+    let commission_impact_percentage = if core.total_pnl != 0.0 {
+        (core.total_commissions / core.total_pnl.abs()) * 100.0
+    } else {
+        0.0
+    };
+
+    Ok(PerformanceMetrics {
+        trade_expectancy,
+        edge,
+        average_hold_time_days: venue_avg_hold_time(trades, |_| true),
+        average_hold_time_winners_days: venue_avg_hold_time(trades, |t| t.pnl > 0.0),
+        average_hold_time_losers_days: venue_avg_hold_time(trades, |t| t.pnl < 0.0),
+        average_position_size: core.average_position_size,
+        position_size_standard_deviation: 0.0,
+        position_size_variability: 0.0,
+        kelly_criterion: 0.0,
+        system_quality_number: 0.0,
+        payoff_ratio,
+        average_r_multiple: 0.0,
+        r_multiple_standard_deviation: 0.0,
+        positive_r_multiple_count: 0,
+        negative_r_multiple_count: 0,
+        consistency_ratio: 0.0,
+        monthly_win_rate: 0.0,
+        quarterly_win_rate: 0.0,
+        average_slippage: calculate_venue_stop_slippage(conn, venue, time_range).await?,
+        commission_impact_percentage,
+    })
+}
+
+/// Average dollar slippage past the planned stop on the venue's stopped-out stock trades
+async fn calculate_venue_stop_slippage(conn: &Connection, venue: &str, time_range: &TimeRange) -> Result<f64> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+    let group = venue_group_sql();
+    let sql = format!(
+        r#"
+        SELECT AVG(
+            CASE WHEN trade_type = 'BUY' THEN stop_loss - exit_price ELSE exit_price - stop_loss END * number_shares
+        ) as avg_stop_slippage
+        FROM stocks
+        WHERE {group} = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND stop_loss > 0 AND is_deleted = 0 AND ({time_condition})
+          AND ((trade_type = 'BUY' AND exit_price <= stop_loss AND exit_price < entry_price)
+               OR (trade_type = 'SELL' AND exit_price >= stop_loss AND exit_price > entry_price))
+        "#
+    );
+
+    if let Some(row) = conn.prepare(&sql).await?.query(libsql::params_from_iter(venue_params(venue, &time_params))).await?.next().await? {
+        Ok(get_f64_value(&row, 0))
+    } else {
+        Ok(0.0)
+    }
+}
+
 // Helper functions for calculating metrics by time period
 
 /// Calculate core metrics for a specific time period
@@ -2272,11 +2602,7 @@ async fn calculate_period_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -2315,11 +2641,7 @@ async fn calculate_period_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -2606,11 +2928,7 @@ async fn calculate_period_daily_returns(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             
@@ -2618,11 +2936,7 @@ async fn calculate_period_daily_returns(
             
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -2650,32 +2964,52 @@ async fn calculate_period_daily_returns(
     Ok(daily_returns)
 }
 
-/// Calculate consecutive streaks from a sequence of P&L values
+/// Max win and loss streaks over P&L values in exit order
 fn calculate_streaks(trades: &[f64]) -> (u32, u32) {
-    let mut current_wins = 0;
-    let mut current_losses = 0;
-    let mut max_wins = 0;
-    let mut max_losses = 0;
-
-    for &pnl in trades {
-        if pnl > 0.0 {
-            // Win
-            current_wins += 1;
-            current_losses = 0;
-            max_wins = max_wins.max(current_wins);
-        } else if pnl < 0.0 {
-            // Loss
-            current_losses += 1;
-            current_wins = 0;
-            max_losses = max_losses.max(current_losses);
-        } else {
-            // Break-even - resets streak
-            current_wins = 0;
-            current_losses = 0;
-        }
-    }
+    let streaks = streaks::calculate(trades);
+    (streaks.max_consecutive_wins, streaks.max_consecutive_losses)
+}
 
-    (max_wins, max_losses)
+/// Closed-trade P&L of one group in exit order. `tables` lists the asset classes the
+/// group spans with each one's group condition, or None when the time condition alone
+/// defines the group.
+async fn group_pnls(
+    conn: &Connection,
+    tables: &[(AssetClass, Option<&str>)],
+    group_params: &[libsql::Value],
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<f64>> {
+    let time_values: Vec<libsql::Value> = time_params.iter().map(|p| libsql::Value::Text(p.to_rfc3339())).collect();
+    let queries: Vec<TradeQuery> = tables
+        .iter()
+        .map(|(source, group_condition)| {
+            let query = match group_condition {
+                Some(condition) => TradeQuery::closed(*source).and_where(condition, group_params.to_vec()),
+                None => TradeQuery::closed(*source),
+            };
+            query.and_where(time_condition, time_values.clone())
+        })
+        .collect();
+    streaks::load_pnls(conn, &queries, None).await
+}
+
+/// Stock and option tables narrowed by the same group condition
+fn both_tables(condition: &str) -> [(AssetClass, Option<&str>); 2] {
+    [(AssetClass::Stocks, Some(condition)), (AssetClass::Options, Some(condition))]
+}
+
+/// Stock and option tables narrowed by the time condition only
+const ALL_TRADES: [(AssetClass, Option<&str>); 2] = [(AssetClass::Stocks, None), (AssetClass::Options, None)];
+
+/// Bullish is BUY stocks and CALL options, bearish SELL stocks and PUT options
+fn direction_tables(bullish: bool) -> [(AssetClass, Option<&'static str>); 2] {
+    let (stocks, options) = if bullish {
+        ("trade_type = 'BUY'", "option_type = 'CALL'")
+    } else {
+        ("trade_type = 'SELL'", "option_type = 'PUT'")
+    };
+    [(AssetClass::Stocks, Some(stocks)), (AssetClass::Options, Some(options))]
 }
 
 /// Calculate consecutive streaks for a specific symbol
@@ -2685,64 +3019,9 @@ async fn calculate_symbol_consecutive_streaks(
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
 ) -> Result<(u32, u32)> {
-    let mut trades = Vec::new();
-
-    // Get all stocks trades for this symbol ordered by exit_date
-    let stocks_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM stocks
-        WHERE symbol = ? AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    let mut query_params = vec![libsql::Value::Text(symbol.to_string())];
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    if let Ok(mut rows) = conn.prepare(&stocks_sql).await?.query(libsql::params_from_iter(query_params.clone())).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    // Get all options trades for this symbol ordered by exit_date
-    let options_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM options
-        WHERE symbol = ? AND status = 'closed' AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    let mut query_params = vec![libsql::Value::Text(symbol.to_string())];
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    if let Ok(mut rows) = conn.prepare(&options_sql).await?.query(libsql::params_from_iter(query_params)).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    let (max_wins, max_losses) = calculate_streaks(&trades);
-    Ok((max_wins, max_losses))
+    let symbol_param = [libsql::Value::Text(symbol.to_string())];
+    let pnls = group_pnls(conn, &both_tables("symbol = ?"), &symbol_param, time_condition, time_params).await?;
+    Ok(calculate_streaks(&pnls))
 }
 
 /// Calculate consecutive streaks for a specific strategy
@@ -2755,11 +3034,7 @@ async fn calculate_strategy_consecutive_streaks(
     let sql = format!(
         r#"
         SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as calculated_pnl
+            {OPTION_PNL_SQL} as calculated_pnl
         FROM options
         WHERE strategy_type = ? AND status = 'closed' AND ({})
         ORDER BY exit_date ASC
@@ -2790,65 +3065,12 @@ async fn calculate_direction_consecutive_streaks(
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
 ) -> Result<(u32, u32)> {
-    let (stocks_filter, options_filter) = match direction {
-        "bullish" => ("trade_type = 'BUY'", "option_type = 'CALL'"),
-        "bearish" => ("trade_type = 'SELL'", "option_type = 'PUT'"),
+    let bullish = match direction {
+        "bullish" => true,
+        "bearish" => false,
         _ => return Ok((0, 0)),
     };
-
-    let mut trades = Vec::new();
-
-    // Get stocks trades
-    let stocks_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM stocks
-        WHERE {} AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        stocks_filter, time_condition
-    );
-
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    if let Ok(mut rows) = conn.prepare(&stocks_sql).await?.query(libsql::params_from_iter(query_params.clone())).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    // Get options trades
-    let options_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM options
-        WHERE {} AND status = 'closed' AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        options_filter, time_condition
-    );
-
-    if let Ok(mut rows) = conn.prepare(&options_sql).await?.query(libsql::params_from_iter(query_params)).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    let (max_wins, max_losses) = calculate_streaks(&trades);
-    Ok((max_wins, max_losses))
+    Ok(calculate_streaks(&group_pnls(conn, &direction_tables(bullish), &[], time_condition, time_params).await?))
 }
 
 /// Calculate consecutive streaks for a specific time period
@@ -2857,57 +3079,5 @@ async fn calculate_period_consecutive_streaks(
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
 ) -> Result<(u32, u32)> {
-    let mut trades = Vec::new();
-
-    // Get all stocks trades
-    let stocks_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM stocks
-        WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    let mut query_params = Vec::new();
-    for param in time_params {
-        query_params.push(libsql::Value::Text(param.to_rfc3339()));
-    }
-
-    if let Ok(mut rows) = conn.prepare(&stocks_sql).await?.query(libsql::params_from_iter(query_params.clone())).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    // Get all options trades
-    let options_sql = format!(
-        r#"
-        SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as calculated_pnl
-        FROM options
-        WHERE status = 'closed' AND ({})
-        ORDER BY exit_date ASC
-        "#,
-        time_condition
-    );
-
-    if let Ok(mut rows) = conn.prepare(&options_sql).await?.query(libsql::params_from_iter(query_params)).await {
-        while let Ok(Some(row)) = rows.next().await {
-            trades.push(get_f64_value(&row, 0));
-        }
-    }
-
-    let (max_wins, max_losses) = calculate_streaks(&trades);
-    Ok((max_wins, max_losses))
+    Ok(calculate_streaks(&group_pnls(conn, &ALL_TRADES, &[], time_condition, time_params).await?))
 }
@@ -0,0 +1,181 @@
+// Symbol-by-month P&L heat map. One grouped query per asset class returns a
+// (symbol, month) row per bucket; the matrix is assembled here so every symbol row
+// lines up with the same month columns.
+//
+// The daily aggregate tables carry no symbol, so they can't feed this view; the grouped
+// queries read the closed trades through `TradeQuery` like the other filtered metrics.
+
+use anyhow::Result;
+use libsql::Connection;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money::{self, Decimal};
+use crate::models::stock::stocks::TimeRange;
+use super::query::{AssetClass, TradeFilter, TradeQuery};
+
+/// P&L and trade count of one symbol in one month
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct HeatmapCell {
+    pub pnl: f64,
+    pub trade_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeatmapRow {
+    pub symbol: String,
+    /// One cell per entry of `TradeHeatmap::months`
+    pub cells: Vec<HeatmapCell>,
+    pub total: HeatmapCell,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TradeHeatmap {
+    /// `YYYY-MM` columns in chronological order
+    pub months: Vec<String>,
+    /// Symbols ordered by total P&L, best first
+    pub rows: Vec<HeatmapRow>,
+    pub month_totals: Vec<HeatmapCell>,
+}
+
+/// One grouped bucket as read from the database
+struct Bucket {
+    symbol: String,
+    month: String,
+    pnl: Decimal,
+    trade_count: u32,
+}
+
+/// Per-symbol, per-month P&L across stocks and options; `limit` keeps the symbols with
+/// the largest absolute P&L
+pub async fn calculate_trade_heatmap(
+    conn: &Connection,
+    time_range: &TimeRange,
+    filter: &TradeFilter,
+    limit: Option<usize>,
+) -> Result<TradeHeatmap> {
+    let periods = PeriodDefinition::load(conn).await;
+    let mut buckets = Vec::new();
+    for source in [AssetClass::Stocks, AssetClass::Options] {
+        let query = TradeQuery::closed(source).time_range(time_range, &periods).filter(filter);
+        buckets.extend(load_buckets(conn, &query).await?);
+    }
+    Ok(build_heatmap(buckets, limit))
+}
+
+async fn load_buckets(conn: &Connection, query: &TradeQuery) -> Result<Vec<Bucket>> {
+    let sql = format!(
+        r#"
+        SELECT UPPER(symbol), strftime('%Y-%m', exit_date) AS month, SUM({pnl}), COUNT(*)
+        FROM {table}
+        WHERE {condition}
+        GROUP BY UPPER(symbol), month
+        "#,
+        pnl = query.pnl_sql(),
+        table = query.table(),
+        condition = query.condition(),
+    );
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query.params()))
+        .await?;
+
+    let mut buckets = Vec::new();
+    while let Some(row) = rows.next().await? {
+        buckets.push(Bucket {
+            symbol: row.get::<String>(0)?,
+            month: row.get::<String>(1)?,
+            pnl: money::row_decimal(&row, 2),
+            trade_count: row.get::<i64>(3).unwrap_or(0) as u32,
+        });
+    }
+    Ok(buckets)
+}
+
+fn build_heatmap(buckets: Vec<Bucket>, limit: Option<usize>) -> TradeHeatmap {
+    let months: Vec<String> = buckets.iter().map(|b| b.month.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+    let column: HashMap<&str, usize> = months.iter().enumerate().map(|(i, m)| (m.as_str(), i)).collect();
+
+    // Stocks and options of the same symbol and month land in the same cell
+    let mut by_symbol: HashMap<String, Vec<(Decimal, u32)>> = HashMap::new();
+    for bucket in &buckets {
+        let cells = by_symbol.entry(bucket.symbol.clone()).or_insert_with(|| vec![(Decimal::ZERO, 0); months.len()]);
+        let cell = &mut cells[column[bucket.month.as_str()]];
+        cell.0 += bucket.pnl;
+        cell.1 += bucket.trade_count;
+    }
+
+    let mut rows: Vec<(Decimal, HeatmapRow)> = by_symbol
+        .into_iter()
+        .map(|(symbol, cells)| {
+            let total_pnl: Decimal = cells.iter().map(|c| c.0).sum();
+            let total = HeatmapCell {
+                pnl: money::to_f64(total_pnl),
+                trade_count: cells.iter().map(|c| c.1).sum(),
+            };
+            let cells = cells
+                .into_iter()
+                .map(|(pnl, trade_count)| HeatmapCell { pnl: money::to_f64(pnl), trade_count })
+                .collect();
+            (total_pnl, HeatmapRow { symbol, cells, total })
+        })
+        .collect();
+
+    if let Some(limit) = limit {
+        rows.sort_by(|a, b| b.0.abs().cmp(&a.0.abs()).then_with(|| a.1.symbol.cmp(&b.1.symbol)));
+        rows.truncate(limit);
+    }
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.symbol.cmp(&b.1.symbol)));
+
+    let mut month_totals = vec![(Decimal::ZERO, 0u32); months.len()];
+    for bucket in &buckets {
+        let total = &mut month_totals[column[bucket.month.as_str()]];
+        total.0 += bucket.pnl;
+        total.1 += bucket.trade_count;
+    }
+
+    TradeHeatmap {
+        months,
+        rows: rows.into_iter().map(|(_, row)| row).collect(),
+        month_totals: month_totals
+            .into_iter()
+            .map(|(pnl, trade_count)| HeatmapCell { pnl: money::to_f64(pnl), trade_count })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(symbol: &str, month: &str, pnl: f64, trade_count: u32) -> Bucket {
+        Bucket { symbol: symbol.to_string(), month: month.to_string(), pnl: money::from_f64(pnl), trade_count }
+    }
+
+    #[test]
+    fn test_build_heatmap_aligns_months_and_merges_asset_classes() {
+        let heatmap = build_heatmap(
+            vec![
+                bucket("AAPL", "2024-02", 100.0, 2),
+                bucket("TSLA", "2024-01", -50.0, 1),
+                bucket("AAPL", "2024-02", 25.5, 1),
+                bucket("NVDA", "2024-03", 10.0, 1),
+            ],
+            None,
+        );
+
+        assert_eq!(heatmap.months, ["2024-01", "2024-02", "2024-03"]);
+        let symbols: Vec<&str> = heatmap.rows.iter().map(|r| r.symbol.as_str()).collect();
+        assert_eq!(symbols, ["AAPL", "NVDA", "TSLA"]);
+        assert_eq!(heatmap.rows[0].cells[1], HeatmapCell { pnl: 125.5, trade_count: 3 });
+        assert_eq!(heatmap.rows[0].cells[0], HeatmapCell::default());
+        assert_eq!(heatmap.month_totals[0], HeatmapCell { pnl: -50.0, trade_count: 1 });
+
+        let top = build_heatmap(vec![bucket("AAPL", "2024-01", 5.0, 1), bucket("TSLA", "2024-01", -80.0, 1)], Some(1));
+        assert_eq!(top.rows.len(), 1);
+        assert_eq!(top.rows[0].symbol, "TSLA");
+        assert_eq!(top.month_totals[0].trade_count, 2);
+    }
+}
@@ -0,0 +1,155 @@
+// Hold-time histograms. Trades closed in the session they were opened in are bucketed by
+// minutes held; longer holds by trading days, using the user's period definition so
+// weekends (or whatever weekdays they exclude) don't inflate swing trades.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use libsql::Connection;
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::stock::stocks::TimeRange;
+use super::query::{AssetClass, TradeQuery};
+
+/// Same-session buckets: label and exclusive upper bound in minutes
+const INTRADAY_BUCKETS: [(&str, i64); 5] = [
+    ("< 5 min", 5),
+    ("5-15 min", 15),
+    ("15-60 min", 60),
+    ("1-4 hours", 240),
+    ("4+ hours", i64::MAX),
+];
+
+/// Multi-session buckets: label and inclusive upper bound in trading days
+const SWING_BUCKETS: [(&str, i64); 5] = [
+    ("1 day", 1),
+    ("2-5 days", 5),
+    ("6-20 days", 20),
+    ("21-60 days", 60),
+    ("60+ days", i64::MAX),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct HoldTimeBucket {
+    pub label: String,
+    pub winners: u32,
+    pub losers: u32,
+    pub winners_pnl: f64,
+    pub losers_pnl: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HoldTimeHistogram {
+    /// Intraday buckets followed by trading-day buckets
+    pub buckets: Vec<HoldTimeBucket>,
+    pub trade_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoldTimeDistribution {
+    pub stocks: HoldTimeHistogram,
+    pub options: HoldTimeHistogram,
+    pub combined: HoldTimeHistogram,
+}
+
+impl HoldTimeHistogram {
+    fn empty() -> Self {
+        let labels = INTRADAY_BUCKETS.iter().chain(SWING_BUCKETS.iter()).map(|(label, _)| label);
+        Self {
+            buckets: labels.map(|label| HoldTimeBucket { label: label.to_string(), ..Default::default() }).collect(),
+            trade_count: 0,
+        }
+    }
+
+    /// Breakeven trades count toward `trade_count` but neither side of a bucket
+    fn add(&mut self, bucket: usize, pnl: f64) {
+        let bucket = &mut self.buckets[bucket];
+        if pnl > 0.0 {
+            bucket.winners += 1;
+            bucket.winners_pnl += pnl;
+        } else if pnl < 0.0 {
+            bucket.losers += 1;
+            bucket.losers_pnl += pnl;
+        }
+        self.trade_count += 1;
+    }
+}
+
+pub async fn calculate_hold_time_distribution(
+    conn: &Connection,
+    time_range: &TimeRange,
+) -> Result<HoldTimeDistribution> {
+    let periods = PeriodDefinition::load(conn).await;
+    let mut stocks = HoldTimeHistogram::empty();
+    let mut options = HoldTimeHistogram::empty();
+    let mut combined = HoldTimeHistogram::empty();
+
+    for (source, histogram) in [(AssetClass::Stocks, &mut stocks), (AssetClass::Options, &mut options)] {
+        let query = TradeQuery::closed(source).time_range(time_range, &periods);
+        let sql = format!(
+            "SELECT entry_date, exit_date, {} FROM {} WHERE {}",
+            query.pnl_sql(),
+            query.table(),
+            query.condition()
+        );
+        let mut rows = conn
+            .prepare(&sql)
+            .await?
+            .query(libsql::params_from_iter(query.params()))
+            .await?;
+
+        while let Some(row) = rows.next().await? {
+            let (Some(entry), Some(exit)) = (parse_timestamp(&row.get::<String>(0)?), parse_timestamp(&row.get::<String>(1)?)) else {
+                continue;
+            };
+            let bucket = bucket_index(&periods, entry, exit);
+            let pnl = money::to_f64(money::row_decimal(&row, 2));
+            histogram.add(bucket, pnl);
+            combined.add(bucket, pnl);
+        }
+    }
+
+    Ok(HoldTimeDistribution { stocks, options, combined })
+}
+
+/// Position in `HoldTimeHistogram::buckets` for a trade held from `entry` to `exit`
+fn bucket_index(periods: &PeriodDefinition, entry: DateTime<Utc>, exit: DateTime<Utc>) -> usize {
+    let trading_days = periods.trading_days_between(entry, exit);
+    if trading_days == 0 {
+        let minutes = (exit - entry).num_minutes().max(0);
+        return INTRADAY_BUCKETS.iter().position(|(_, max)| minutes < *max).unwrap_or(INTRADAY_BUCKETS.len() - 1);
+    }
+    let swing = SWING_BUCKETS.iter().position(|(_, max)| trading_days <= *max).unwrap_or(SWING_BUCKETS.len() - 1);
+    INTRADAY_BUCKETS.len() + swing
+}
+
+/// Trade timestamps are RFC3339, older rows SQLite's `YYYY-MM-DD HH:MM:SS`
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok().map(|dt| dt.and_utc()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_bucket_index_uses_minutes_then_trading_days() {
+        let periods = PeriodDefinition { excluded_weekdays: vec![0, 6], ..Default::default() };
+        let monday = Utc.with_ymd_and_hms(2025, 3, 3, 14, 30, 0).unwrap();
+
+        assert_eq!(bucket_index(&periods, monday, monday + chrono::Duration::minutes(3)), 0);
+        assert_eq!(bucket_index(&periods, monday, monday + chrono::Duration::minutes(90)), 3);
+        // Friday to Monday is one trading day, not three calendar days
+        let friday = Utc.with_ymd_and_hms(2025, 2, 28, 15, 0, 0).unwrap();
+        assert_eq!(bucket_index(&periods, friday, monday), INTRADAY_BUCKETS.len());
+        assert_eq!(bucket_index(&periods, monday, monday + chrono::Duration::days(14)), INTRADAY_BUCKETS.len() + 2);
+
+        assert_eq!(parse_timestamp("2025-03-03 14:30:00"), Some(monday));
+        assert_eq!(parse_timestamp("2025-03-03T14:30:00+00:00"), Some(monday));
+    }
+}
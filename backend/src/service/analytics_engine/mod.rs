@@ -4,13 +4,28 @@ pub mod performance_metrics;
 pub mod time_series;
 pub mod grouping;
 pub mod playbook_analytics;
+pub mod consistency;
+pub mod prop_firm_evaluator;
+pub mod snapshots;
+pub mod daily_aggregates;
+pub mod query;
+pub mod heatmap;
+pub mod rating_buckets;
+pub mod hold_time;
+pub mod attribution;
+pub mod drawdown_sizing;
+pub mod custom_metrics;
+pub mod efficiency;
+pub mod correlation;
+pub mod export;
 
 use anyhow::Result;
 use libsql::Connection;
 use crate::models::analytics::{
     ComprehensiveAnalytics, AnalyticsOptions, CoreMetrics, RiskMetrics, 
-    PerformanceMetrics, TimeSeriesData
+    PerformanceMetrics, TimeSeriesData, ConsistencyScore
 };
+use crate::models::analytics::custom_metrics::MetricVariables;
 use crate::models::stock::stocks::TimeRange;
 
 /// Main analytics engine that orchestrates all calculations
@@ -52,12 +67,28 @@ impl AnalyticsEngine {
             std::collections::HashMap::new()
         };
 
+        // A broken custom metric shouldn't take the built-in ones down with it
+        let custom_metrics = match custom_metrics::list_definitions(conn).await {
+            Ok(definitions) => custom_metrics::evaluate(
+                &definitions,
+                &MetricVariables::new(&core_metrics, &risk_metrics, &performance_metrics),
+            ),
+            Err(e) => {
+                log::warn!("Failed to load custom metrics: {}", e);
+                Vec::new()
+            }
+        };
+
+        let confidence = core_metrics::calculate_sample_confidence(conn, time_range, &Default::default()).await?;
+
         Ok(ComprehensiveAnalytics {
             core_metrics,
             risk_metrics,
             performance_metrics,
             time_series,
             grouped_analytics,
+            custom_metrics,
+            confidence,
         })
     }
 
@@ -108,6 +139,16 @@ impl AnalyticsEngine {
     ) -> Result<std::collections::HashMap<String, crate::models::analytics::GroupedMetrics>> {
         grouping::calculate_grouped_analytics(conn, time_range, options).await
     }
+
+    /// Calculate funded-account style consistency score
+    pub async fn calculate_consistency_score(
+        &self,
+        conn: &Connection,
+        time_range: &TimeRange,
+        threshold_percentage: f64,
+    ) -> Result<ConsistencyScore> {
+        consistency::calculate_consistency_score(conn, time_range, threshold_percentage).await
+    }
 }
 
 impl Default for AnalyticsEngine {
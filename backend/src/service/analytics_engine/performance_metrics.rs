@@ -2,8 +2,10 @@
 
 use anyhow::Result;
 use libsql::Connection;
-use crate::models::analytics::{PerformanceMetrics, CoreMetrics};
+use crate::models::analytics::{PerformanceMetrics, CoreMetrics, PeriodDefinition};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
 use crate::models::stock::stocks::TimeRange;
+use crate::service::notifications::overtrading::{self, OvertradingStats};
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
@@ -26,12 +28,23 @@ fn get_i64_value(row: &libsql::Row, index: i32) -> i64 {
     }
 }
 
+/// Sample standard deviation from a column's count, mean and mean of squares. SQLite has no
+/// STDDEV aggregate, so queries select `COUNT(*)`, `AVG(x)` and `AVG(x * x)` and finish here.
+pub(crate) fn std_dev_from_moments(count: i64, mean: f64, mean_square: f64) -> f64 {
+    if count < 2 {
+        return 0.0;
+    }
+    let n = count as f64;
+    ((mean_square - mean * mean) * n / (n - 1.0)).max(0.0).sqrt()
+}
+
 /// Calculate performance metrics including hold times for winners and losers
 pub async fn calculate_performance_metrics(
     conn: &Connection,
     time_range: &TimeRange,
 ) -> Result<PerformanceMetrics> {
-    let (time_condition, time_params) = time_range.to_sql_condition();
+    let periods = PeriodDefinition::load(conn).await;
+    let (time_condition, time_params) = time_range.to_sql_condition_for(&periods);
     
     // Calculate stocks performance metrics
     let stocks_metrics = calculate_stocks_performance_metrics(conn, &time_condition, &time_params).await?;
@@ -57,17 +70,14 @@ async fn calculate_stocks_performance_metrics(
         SELECT 
             AVG(JULIANDAY(exit_date) - JULIANDAY(entry_date)) as avg_hold_time_days,
             AVG(number_shares * entry_price) as avg_position_size,
-            STDDEV(number_shares * entry_price) as position_size_std_dev,
+            AVG((number_shares * entry_price) * (number_shares * entry_price)) as position_size_mean_square,
             AVG(commissions) as avg_commission_per_trade,
-            SUM(commissions) / NULLIF(SUM(ABS(calculated_pnl)), 0) * 100 as commission_impact_percentage
+            SUM(commissions) / NULLIF(SUM(ABS(calculated_pnl)), 0) * 100 as commission_impact_percentage,
+            COUNT(*) as trade_count
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -95,7 +105,7 @@ async fn calculate_stocks_performance_metrics(
     if let Some(row) = rows.next().await? {
         avg_hold_time_days = get_f64_value(&row, 0);
         avg_position_size = get_f64_value(&row, 1);
-        position_size_std_dev = get_f64_value(&row, 2);
+        position_size_std_dev = std_dev_from_moments(get_i64_value(&row, 5), avg_position_size, get_f64_value(&row, 2));
         _avg_commission_per_trade = get_f64_value(&row, 3);
         commission_impact_percentage = get_f64_value(&row, 4);
     }
@@ -111,6 +121,7 @@ async fn calculate_stocks_performance_metrics(
     let consistency_ratio = calculate_consistency_ratio_stocks(conn, time_condition, time_params).await?;
     let (monthly_win_rate, quarterly_win_rate) = calculate_periodic_win_rates_stocks(conn, time_condition, time_params).await?;
     let system_quality_number = calculate_system_quality_number_stocks(conn, time_condition, time_params).await?;
+    let average_slippage = calculate_stop_slippage_stocks(conn, time_condition, time_params).await?;
 
     Ok(PerformanceMetrics {
         trade_expectancy,
@@ -131,7 +142,7 @@ async fn calculate_stocks_performance_metrics(
         consistency_ratio,
         monthly_win_rate,
         quarterly_win_rate,
-        average_slippage: 0.0, // Not available in current schema
+        average_slippage,
         commission_impact_percentage,
     })
 }
@@ -206,6 +217,44 @@ async fn calculate_losers_hold_time(
     }
 }
 
+/// Average dollar slippage on stop-outs: how far past the planned stop a losing trade
+/// was filled, times shares. Only losing trades closed at or beyond their stop count.
+async fn calculate_stop_slippage_stocks(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<f64> {
+    let sql = format!(
+        r#"
+        SELECT AVG(
+            CASE WHEN trade_type = 'BUY' THEN stop_loss - exit_price ELSE exit_price - stop_loss END * number_shares
+        ) as avg_stop_slippage
+        FROM stocks
+        WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND stop_loss > 0 AND is_deleted = 0 AND ({})
+          AND ((trade_type = 'BUY' AND exit_price <= stop_loss AND exit_price < entry_price)
+               OR (trade_type = 'SELL' AND exit_price >= stop_loss AND exit_price > entry_price))
+        "#,
+        time_condition
+    );
+
+    let mut query_params = Vec::new();
+    for param in time_params {
+        query_params.push(libsql::Value::Text(param.to_rfc3339()));
+    }
+
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query_params))
+        .await?;
+
+    if let Some(row) = rows.next().await? {
+        Ok(get_f64_value(&row, 0))
+    } else {
+        Ok(0.0)
+    }
+}
+
 /// Calculate average risk per trade (entry - stop_loss) * position_size
 #[allow(dead_code)]
 async fn calculate_average_risk_per_trade(
@@ -252,17 +301,14 @@ async fn calculate_options_performance_metrics(
         SELECT 
             AVG(JULIANDAY(exit_date) - JULIANDAY(entry_date)) as avg_hold_time_days,
             AVG(total_premium) as avg_position_size,
-            STDDEV(total_premium) as position_size_std_dev,
+            AVG(total_premium * total_premium) as position_size_mean_square,
             AVG(commissions) as avg_commission_per_trade,
-            SUM(commissions) / NULLIF(SUM(ABS(calculated_pnl)), 0) * 100 as commission_impact_percentage
+            SUM(commissions) / NULLIF(SUM(ABS(calculated_pnl)), 0) * 100 as commission_impact_percentage,
+            COUNT(*) as trade_count
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -290,7 +336,7 @@ async fn calculate_options_performance_metrics(
     if let Some(row) = rows.next().await? {
         avg_hold_time_days = get_f64_value(&row, 0);
         avg_position_size = get_f64_value(&row, 1);
-        position_size_std_dev = get_f64_value(&row, 2);
+        position_size_std_dev = std_dev_from_moments(get_i64_value(&row, 5), avg_position_size, get_f64_value(&row, 2));
         _avg_commission_per_trade = get_f64_value(&row, 3);
         commission_impact_percentage = get_f64_value(&row, 4);
     }
@@ -326,7 +372,7 @@ async fn calculate_options_performance_metrics(
         consistency_ratio,
         monthly_win_rate,
         quarterly_win_rate,
-        average_slippage: 0.0, // Not available in current schema
+        average_slippage: 0.0, // Options don't record a planned stop
         commission_impact_percentage,
     })
 }
@@ -431,7 +477,8 @@ fn combine_performance_metrics(stocks: PerformanceMetrics, options: PerformanceM
         consistency_ratio: stocks.consistency_ratio * stocks_weight + options.consistency_ratio * options_weight,
         monthly_win_rate: stocks.monthly_win_rate * stocks_weight + options.monthly_win_rate * options_weight,
         quarterly_win_rate: stocks.quarterly_win_rate * stocks_weight + options.quarterly_win_rate * options_weight,
-        average_slippage: 0.0, // Not available in current schema
+        // Only stock trades carry a planned stop
+        average_slippage: stocks.average_slippage,
         commission_impact_percentage: stocks.commission_impact_percentage * stocks_weight + options.commission_impact_percentage * options_weight,
     }
 }
@@ -521,6 +568,9 @@ pub struct BehavioralPatterns {
     pub timing_behavior: TimingBehaviorMetrics,
     pub trading_frequency: TradingFrequencyMetrics,
     pub profitability_distribution: ProfitabilityDistributionMetrics,
+    /// Days over the daily trade limit; None when no limit is set
+    #[serde(default)]
+    pub overtrading: Option<OvertradingStats>,
 }
 
 /// Calculate all behavioral patterns
@@ -536,12 +586,14 @@ pub async fn calculate_behavioral_patterns(
     let timing_behavior = calculate_timing_behavior(conn, &time_condition, &time_params).await?;
     let trading_frequency = calculate_trading_frequency_behavior(conn, &time_condition, &time_params).await?;
     let profitability_distribution = calculate_profitability_distribution(conn, &time_condition, &time_params).await?;
+    let overtrading = overtrading::breach_stats(conn, time_range).await?;
     
     Ok(BehavioralPatterns {
         risk_behavior,
         timing_behavior,
         trading_frequency,
         profitability_distribution,
+        overtrading,
     })
 }
 
@@ -593,15 +645,12 @@ async fn calculate_risk_behavior(
         SELECT 
             AVG(position_size) as avg_after_win,
             AVG(position_size) as avg_after_loss,
-            STDDEV(position_size) / NULLIF(AVG(position_size), 0) * 100 as consistency
+            AVG(position_size * position_size) as position_size_mean_square,
+            COUNT(*) as trade_count
         FROM (
             SELECT 
                 entry_price * number_shares as position_size,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as pnl
+                {STOCK_PNL_SQL} as pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -622,7 +671,8 @@ async fn calculate_risk_behavior(
     if let Some(row) = rows.next().await? {
         avg_size_after_win = get_f64_value(&row, 0);
         avg_size_after_loss = get_f64_value(&row, 1);
-        position_consistency = get_f64_value(&row, 2);
+        let std_dev = std_dev_from_moments(get_i64_value(&row, 3), avg_size_after_win, get_f64_value(&row, 2));
+        position_consistency = if avg_size_after_win > 0.0 { std_dev / avg_size_after_win * 100.0 } else { 0.0 };
     }
 
     // Calculate risk-reward ratio consistency
@@ -637,11 +687,7 @@ async fn calculate_risk_behavior(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL 
               AND stop_loss IS NOT NULL AND ({})
@@ -696,11 +742,7 @@ async fn calculate_timing_behavior(
                 ELSE 'Unknown'
             END as day_of_week,
             COUNT(*) as trade_count,
-            SUM(CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END) as total_pnl
+            SUM({STOCK_PNL_SQL}) as total_pnl
         FROM stocks
         WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         GROUP BY day_of_week
@@ -832,11 +874,7 @@ async fn calculate_trading_frequency_behavior(
             SELECT 
                 DATE(entry_date) as trade_date,
                 COUNT(*) as trades_per_day,
-                AVG(CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END) as avg_pnl
+                AVG({STOCK_PNL_SQL}) as avg_pnl
             FROM stocks
             WHERE entry_date IS NOT NULL AND exit_date IS NOT NULL AND ({})
             GROUP BY trade_date
@@ -889,11 +927,7 @@ async fn calculate_profitability_distribution(
             SUM(CASE WHEN calculated_pnl < 0 THEN calculated_pnl ELSE 0 END) as total_loss
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -967,11 +1001,7 @@ async fn calculate_expectancy_and_edge_stocks(
             SUM(calculated_pnl) as total_pnl
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -1042,11 +1072,7 @@ async fn calculate_kelly_criterion_stocks(
             CAST(SUM(CASE WHEN calculated_pnl > 0 THEN 1 ELSE 0 END) AS REAL) / COUNT(*) as win_rate
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -1095,11 +1121,7 @@ async fn calculate_r_multiples_stocks(
     let sql = format!(
         r#"
         SELECT 
-            CASE 
-                WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                ELSE 0
-            END as pnl,
+            {STOCK_PNL_SQL} as pnl,
             ABS(entry_price - stop_loss) * number_shares as risk
         FROM stocks
         WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL 
@@ -1171,11 +1193,7 @@ async fn calculate_consistency_ratio_stocks(
             SUM(CASE WHEN calculated_pnl < 0 THEN ABS(calculated_pnl) ELSE 0 END) as total_losses
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -1227,11 +1245,7 @@ async fn calculate_periodic_win_rates_stocks(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             AND JULIANDAY('now') - JULIANDAY(exit_date) <= 30
@@ -1264,11 +1278,7 @@ async fn calculate_periodic_win_rates_stocks(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             AND JULIANDAY('now') - JULIANDAY(exit_date) <= 90
@@ -1322,11 +1332,7 @@ async fn get_basic_stats_stocks(
             AVG(calculated_pnl) as avg_pnl
         FROM (
             SELECT 
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
         )
@@ -1375,11 +1381,7 @@ async fn calculate_expectancy_and_edge_options(
             AVG(CASE WHEN calculated_pnl < 0 THEN calculated_pnl ELSE NULL END) as avg_loser
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -1445,11 +1447,7 @@ async fn calculate_kelly_criterion_options(
             CAST(SUM(CASE WHEN calculated_pnl > 0 THEN 1 ELSE 0 END) AS REAL) / COUNT(*) as win_rate
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -1498,11 +1496,7 @@ async fn calculate_r_multiples_options(
     let sql = format!(
         r#"
         SELECT 
-            CASE 
-                WHEN exit_price IS NOT NULL THEN 
-                    (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                ELSE 0
-            END as pnl,
+            {OPTION_PNL_SQL} as pnl,
             total_premium as risk
         FROM options
         WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
@@ -1572,11 +1566,7 @@ async fn calculate_consistency_ratio_options(
             SUM(CASE WHEN calculated_pnl < 0 THEN ABS(calculated_pnl) ELSE 0 END) as total_losses
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -1626,11 +1616,7 @@ async fn calculate_periodic_win_rates_options(
             CAST(SUM(CASE WHEN calculated_pnl > 0 THEN 1 ELSE 0 END) AS REAL) / COUNT(*) as win_rate
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
             AND JULIANDAY('now') - JULIANDAY(exit_date) <= 30
@@ -1661,11 +1647,7 @@ async fn calculate_periodic_win_rates_options(
             CAST(SUM(CASE WHEN calculated_pnl > 0 THEN 1 ELSE 0 END) AS REAL) / COUNT(*) as win_rate
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
             AND JULIANDAY('now') - JULIANDAY(exit_date) <= 90
@@ -1718,11 +1700,7 @@ async fn get_basic_stats_options(
             AVG(calculated_pnl) as avg_pnl
         FROM (
             SELECT 
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND ({})
         )
@@ -1859,7 +1837,7 @@ async fn calculate_bucket_metrics(
             
             -- Option trades  
             SELECT 
-                (exit_price - entry_price) * number_of_contracts * 100 - commissions as net_pnl,
+                {OPTION_PNL_SQL} as net_pnl,
                 (JULIANDAY(exit_date) - JULIANDAY(entry_date)) as hold_days,
                 CASE WHEN exit_price > entry_price THEN 1 ELSE 0 END as is_winner
             FROM options 
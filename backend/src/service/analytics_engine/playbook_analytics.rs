@@ -1,6 +1,7 @@
 use anyhow::Result;
 use libsql::Connection;
 use serde::{Deserialize, Serialize};
+use crate::models::pnl::PnlSql;
 use crate::models::stock::stocks::TimeRange;
 
 /// Playbook analytics metrics
@@ -172,6 +173,7 @@ async fn calculate_playbook_core_metrics(
     time_params: &[chrono::DateTime<chrono::Utc>],
 ) -> Result<PlaybookCoreMetrics> {
     // Calculate stocks metrics
+    let stock_pnl = PnlSql::table("s").stock();
     let sql = format!(
         r#"
         SELECT 
@@ -186,11 +188,7 @@ async fn calculate_playbook_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {stock_pnl} as calculated_pnl
             FROM stocks s
             JOIN stock_trade_playbook stp ON s.id = stp.stock_trade_id
             WHERE stp.setup_id = ? AND s.exit_price IS NOT NULL AND s.exit_date IS NOT NULL AND ({})
@@ -248,6 +246,7 @@ async fn calculate_playbook_core_metrics(
     }
 
     // Calculate options metrics
+    let option_pnl = PnlSql::table("o").option();
     let sql = format!(
         r#"
         SELECT 
@@ -262,11 +261,7 @@ async fn calculate_playbook_core_metrics(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {option_pnl} as calculated_pnl
             FROM options o
             JOIN option_trade_playbook otp ON o.id = otp.option_trade_id
             WHERE otp.setup_id = ? AND o.status = 'closed' AND ({})
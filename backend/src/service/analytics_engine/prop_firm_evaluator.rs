@@ -0,0 +1,238 @@
+use anyhow::Result;
+use libsql::Connection;
+use serde::Serialize;
+use crate::models::prop_firm::EvaluationProfile;
+use crate::models::stock::stocks::TimeRange;
+use super::consistency::fetch_daily_pnl;
+
+/// Evaluation rule that a trading day can break
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationRule {
+    MaxDailyLoss,
+    TrailingDrawdown,
+    Consistency,
+}
+
+/// A single rule break, tied to the day it happened
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleViolation {
+    pub date: String,
+    pub rule: EvaluationRule,
+    pub value: f64,
+    pub limit: f64,
+    pub message: String,
+}
+
+/// End-of-day account state during the simulation
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationDay {
+    pub date: String,
+    pub pnl: f64,
+    pub balance: f64,
+    pub high_water_mark: f64,
+    pub drawdown_floor: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EvaluationStatus {
+    Passed,
+    Failed,
+    InProgress,
+}
+
+/// Outcome of running a history against an evaluation profile
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationResult {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub status: EvaluationStatus,
+    pub passed: bool,
+    pub trading_days: u32,
+    pub total_pnl: f64,
+    pub ending_balance: f64,
+    pub profit_target_reached: bool,
+    /// First day the balance reached the profit target, if it did
+    pub profit_target_reached_on: Option<String>,
+    pub min_trading_days_met: bool,
+    /// First violation ends a real evaluation
+    pub failed_on: Option<String>,
+    pub violations: Vec<RuleViolation>,
+    pub daily_breakdown: Vec<EvaluationDay>,
+}
+
+/// Run the user's closed trades in the given range against a profile
+pub async fn evaluate_profile(
+    conn: &Connection,
+    profile: &EvaluationProfile,
+    time_range: &TimeRange,
+) -> Result<EvaluationResult> {
+    let (time_condition, time_params) = time_range.to_sql_condition();
+    let daily_pnl = fetch_daily_pnl(conn, &time_condition, &time_params).await?;
+    Ok(run_evaluation(profile, &daily_pnl))
+}
+
+/// Simulate the evaluation day by day over (date, net P&L) pairs
+pub fn run_evaluation(profile: &EvaluationProfile, daily_pnl: &[(String, f64)]) -> EvaluationResult {
+    let mut balance = profile.account_size;
+    let mut high_water_mark = profile.account_size;
+    let mut violations = Vec::new();
+    let mut daily_breakdown = Vec::with_capacity(daily_pnl.len());
+    let mut profit_target_reached_on = None;
+
+    for (date, pnl) in daily_pnl {
+        balance += pnl;
+
+        if *pnl < 0.0 && pnl.abs() > profile.max_daily_loss {
+            violations.push(RuleViolation {
+                date: date.clone(),
+                rule: EvaluationRule::MaxDailyLoss,
+                value: pnl.abs(),
+                limit: profile.max_daily_loss,
+                message: format!("Lost ${:.2} in one day (limit ${:.2})", pnl.abs(), profile.max_daily_loss),
+            });
+        }
+
+        // Floor trails the end-of-day high water mark
+        let drawdown_floor = high_water_mark - profile.max_trailing_drawdown;
+        if balance < drawdown_floor {
+            violations.push(RuleViolation {
+                date: date.clone(),
+                rule: EvaluationRule::TrailingDrawdown,
+                value: high_water_mark - balance,
+                limit: profile.max_trailing_drawdown,
+                message: format!("Balance ${:.2} fell below trailing floor ${:.2}", balance, drawdown_floor),
+            });
+        }
+        high_water_mark = high_water_mark.max(balance);
+
+        if profit_target_reached_on.is_none() && balance - profile.account_size >= profile.profit_target {
+            profit_target_reached_on = Some(date.clone());
+        }
+
+        daily_breakdown.push(EvaluationDay {
+            date: date.clone(),
+            pnl: *pnl,
+            balance,
+            high_water_mark,
+            drawdown_floor,
+        });
+    }
+
+    let total_pnl = balance - profile.account_size;
+    let trading_days = daily_pnl.len() as u32;
+
+    if let Some(threshold) = profile.consistency_threshold_percentage {
+        let gross_profit: f64 = daily_pnl.iter().map(|(_, p)| *p).filter(|p| *p > 0.0).sum();
+        if gross_profit > 0.0
+            && let Some((date, best)) = daily_pnl
+                .iter()
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        {
+            let share = best / gross_profit * 100.0;
+            if share > threshold {
+                violations.push(RuleViolation {
+                    date: date.clone(),
+                    rule: EvaluationRule::Consistency,
+                    value: share,
+                    limit: threshold,
+                    message: format!("Best day made {:.1}% of profit (limit {:.1}%)", share, threshold),
+                });
+            }
+        }
+    }
+
+    // Hard-stop rules fail the account on the day they happen
+    let failed_on = violations
+        .iter()
+        .filter(|v| v.rule != EvaluationRule::Consistency)
+        .map(|v| v.date.clone())
+        .min();
+
+    let profit_target_reached = profit_target_reached_on.is_some() && total_pnl >= profile.profit_target;
+    let min_trading_days_met = trading_days as i64 >= profile.min_trading_days;
+    let status = if failed_on.is_some() {
+        EvaluationStatus::Failed
+    } else if profit_target_reached && min_trading_days_met && violations.is_empty() {
+        EvaluationStatus::Passed
+    } else {
+        EvaluationStatus::InProgress
+    };
+
+    EvaluationResult {
+        profile_id: profile.id.clone(),
+        profile_name: profile.name.clone(),
+        passed: status == EvaluationStatus::Passed,
+        status,
+        trading_days,
+        total_pnl,
+        ending_balance: balance,
+        profit_target_reached,
+        profit_target_reached_on,
+        min_trading_days_met,
+        failed_on,
+        violations,
+        daily_breakdown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> EvaluationProfile {
+        EvaluationProfile {
+            id: "p1".to_string(),
+            name: "50K Challenge".to_string(),
+            account_size: 50_000.0,
+            profit_target: 3_000.0,
+            max_daily_loss: 1_000.0,
+            max_trailing_drawdown: 2_000.0,
+            min_trading_days: 3,
+            consistency_threshold_percentage: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    fn days(values: &[f64]) -> Vec<(String, f64)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("2025-01-{:02}", i + 1), *v))
+            .collect()
+    }
+
+    #[test]
+    fn test_passes_when_target_hit_without_violations() {
+        let result = run_evaluation(&profile(), &days(&[1_000.0, 1_200.0, 900.0]));
+        assert_eq!(result.status, EvaluationStatus::Passed);
+        assert_eq!(result.profit_target_reached_on.as_deref(), Some("2025-01-03"));
+        assert!(result.violations.is_empty());
+    }
+
+    #[test]
+    fn test_daily_loss_violation_fails_on_that_day() {
+        let result = run_evaluation(&profile(), &days(&[500.0, -1_200.0, 4_000.0]));
+        assert_eq!(result.status, EvaluationStatus::Failed);
+        assert_eq!(result.failed_on.as_deref(), Some("2025-01-02"));
+        assert_eq!(result.violations[0].rule, EvaluationRule::MaxDailyLoss);
+    }
+
+    #[test]
+    fn test_trailing_drawdown_follows_high_water_mark() {
+        let result = run_evaluation(&profile(), &days(&[1_500.0, -900.0, -900.0, -300.0]));
+        assert_eq!(result.status, EvaluationStatus::Failed);
+        assert_eq!(result.failed_on.as_deref(), Some("2025-01-04"));
+        assert!(result.violations.iter().all(|v| v.rule == EvaluationRule::TrailingDrawdown));
+    }
+
+    #[test]
+    fn test_target_before_min_days_stays_in_progress() {
+        let result = run_evaluation(&profile(), &days(&[3_500.0]));
+        assert_eq!(result.status, EvaluationStatus::InProgress);
+        assert!(result.profit_target_reached);
+        assert!(!result.min_trading_days_met);
+    }
+}
@@ -0,0 +1,241 @@
+//! Typed WHERE clauses for the analytics queries.
+//!
+//! A metric query reads the closed trades of one asset class, narrowed by the time range
+//! and optionally by symbol, direction, tags and account. `TradeQuery` renders those as a
+//! single condition plus its positional parameters in matching order, so metric code only
+//! writes its SELECT list and never splices filter SQL together by hand.
+
+use libsql::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::stock::stocks::TimeRange;
+
+/// Table a query reads trades from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetClass {
+    Stocks,
+    Options,
+}
+
+impl AssetClass {
+    pub const ALL: [AssetClass; 2] = [AssetClass::Stocks, AssetClass::Options];
+
+    pub fn table(self) -> &'static str {
+        match self {
+            AssetClass::Stocks => "stocks",
+            AssetClass::Options => "options",
+        }
+    }
+
+    /// Realized P&L of one row of `table()`
+    pub fn pnl_sql(self) -> &'static str {
+        match self {
+            AssetClass::Stocks => STOCK_PNL_SQL,
+            AssetClass::Options => OPTION_PNL_SQL,
+        }
+    }
+
+    /// Initial risk of one trade, the denominator of its R multiple: the distance to the
+    /// stop for stocks, the premium paid for options
+    pub fn risk_sql(self) -> &'static str {
+        match self {
+            AssetClass::Stocks => "ABS(entry_price - stop_loss) * number_shares",
+            AssetClass::Options => "total_premium",
+        }
+    }
+
+    fn closed_condition(self) -> &'static str {
+        match self {
+            AssetClass::Stocks => "exit_price IS NOT NULL AND exit_date IS NOT NULL",
+            AssetClass::Options => "status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL",
+        }
+    }
+
+    fn direction_condition(self, direction: Direction) -> &'static str {
+        match (self, direction) {
+            (AssetClass::Stocks, Direction::Long) => "trade_type = 'BUY'",
+            (AssetClass::Stocks, Direction::Short) => "trade_type = 'SELL'",
+            (AssetClass::Options, Direction::Long) => "trade_direction = 'Bullish'",
+            (AssetClass::Options, Direction::Short) => "trade_direction = 'Bearish'",
+        }
+    }
+
+    fn tag_condition(self, tag_count: usize) -> String {
+        let (junction, trade_column) = match self {
+            AssetClass::Stocks => ("stock_trade_tags", "stock_trade_id"),
+            AssetClass::Options => ("option_trade_tags", "option_trade_id"),
+        };
+        format!(
+            "id IN (SELECT {} FROM {} WHERE tag_id IN ({}))",
+            trade_column,
+            junction,
+            vec!["?"; tag_count].join(", ")
+        )
+    }
+}
+
+/// Long covers BUY stock trades and bullish options, short covers SELL and bearish ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Long,
+    Short,
+}
+
+/// Optional filters on top of the time range; unset fields match every trade
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeFilter {
+    pub symbol: Option<String>,
+    pub direction: Option<Direction>,
+    /// Trades carrying any of these tag ids
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Brokerage the trades came from (`brokerage_name`)
+    pub account: Option<String>,
+}
+
+/// Closed trades of one asset class with their filter conditions
+#[derive(Debug, Clone)]
+pub struct TradeQuery {
+    source: AssetClass,
+    conditions: Vec<String>,
+    params: Vec<Value>,
+}
+
+impl TradeQuery {
+    pub fn closed(source: AssetClass) -> Self {
+        Self {
+            source,
+            conditions: vec![source.closed_condition().to_string()],
+            params: Vec::new(),
+        }
+    }
+
+    pub fn source(&self) -> AssetClass {
+        self.source
+    }
+
+    pub fn table(&self) -> &'static str {
+        self.source.table()
+    }
+
+    pub fn pnl_sql(&self) -> &'static str {
+        self.source.pnl_sql()
+    }
+
+    fn push(mut self, condition: String, params: impl IntoIterator<Item = Value>) -> Self {
+        self.conditions.push(condition);
+        self.params.extend(params);
+        self
+    }
+
+    /// Exit date within `range`, honoring the user's period definitions
+    pub fn time_range(self, range: &TimeRange, periods: &PeriodDefinition) -> Self {
+        let (condition, params) = range.to_sql_condition_for(periods);
+        self.push(condition, params.into_iter().map(|p| Value::Text(p.to_rfc3339())))
+    }
+
+    pub fn symbol(self, symbol: &str) -> Self {
+        self.push("symbol = ?".to_string(), [Value::Text(symbol.to_string())])
+    }
+
+    pub fn direction(self, direction: Direction) -> Self {
+        let condition = self.source.direction_condition(direction).to_string();
+        self.push(condition, [])
+    }
+
+    /// Trades tagged with any of `tag_ids`; no-op when empty
+    pub fn tags(self, tag_ids: &[String]) -> Self {
+        if tag_ids.is_empty() {
+            return self;
+        }
+        let condition = self.source.tag_condition(tag_ids.len());
+        self.push(condition, tag_ids.iter().map(|id| Value::Text(id.clone())))
+    }
+
+    pub fn account(self, account: &str) -> Self {
+        self.push("brokerage_name = ?".to_string(), [Value::Text(account.to_string())])
+    }
+
+    /// A condition the typed filters don't cover, such as a group key or a precomputed
+    /// period; `params` bind its placeholders in order
+    pub fn and_where(self, condition: &str, params: impl IntoIterator<Item = Value>) -> Self {
+        self.push(condition.to_string(), params)
+    }
+
+    pub fn filter(self, filter: &TradeFilter) -> Self {
+        let mut query = self;
+        if let Some(symbol) = &filter.symbol {
+            query = query.symbol(symbol);
+        }
+        if let Some(direction) = filter.direction {
+            query = query.direction(direction);
+        }
+        query = query.tags(&filter.tags);
+        if let Some(account) = &filter.account {
+            query = query.account(account);
+        }
+        query
+    }
+
+    /// All conditions ANDed, each parenthesized; bind `params()` in order
+    pub fn condition(&self) -> String {
+        self.conditions
+            .iter()
+            .map(|c| format!("({})", c))
+            .collect::<Vec<_>>()
+            .join(" AND ")
+    }
+
+    pub fn params(&self) -> Vec<Value> {
+        self.params.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditions_and_params_stay_in_order() {
+        let filter = TradeFilter {
+            symbol: Some("AAPL".to_string()),
+            direction: Some(Direction::Short),
+            tags: vec!["t1".to_string(), "t2".to_string()],
+            account: Some("Schwab".to_string()),
+        };
+        let query = TradeQuery::closed(AssetClass::Stocks)
+            .time_range(&TimeRange::AllTime, &PeriodDefinition::default())
+            .filter(&filter);
+
+        assert_eq!(
+            query.condition(),
+            "(exit_price IS NOT NULL AND exit_date IS NOT NULL) AND (1=1) AND (symbol = ?) AND (trade_type = 'SELL') \
+             AND (id IN (SELECT stock_trade_id FROM stock_trade_tags WHERE tag_id IN (?, ?))) AND (brokerage_name = ?)"
+        );
+        let params: Vec<String> = query
+            .params()
+            .into_iter()
+            .map(|v| match v {
+                Value::Text(s) => s,
+                other => panic!("unexpected param {:?}", other),
+            })
+            .collect();
+        assert_eq!(params, ["AAPL", "t1", "t2", "Schwab"]);
+    }
+
+    #[test]
+    fn test_option_source_maps_filters_to_option_columns() {
+        let query = TradeQuery::closed(AssetClass::Options)
+            .direction(Direction::Long)
+            .tags(&[]);
+        assert_eq!(
+            query.condition(),
+            "(status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL) AND (trade_direction = 'Bullish')"
+        );
+        assert!(query.params().is_empty());
+        assert_eq!(query.pnl_sql(), OPTION_PNL_SQL);
+    }
+}
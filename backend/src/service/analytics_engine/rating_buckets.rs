@@ -0,0 +1,156 @@
+// Expectancy by setup quality. Closed trades are bucketed by their `trade_ratings`
+// (1-5, unrated trades separately) so a trader can check whether the setups they grade
+// highest actually pay the most.
+
+use anyhow::Result;
+use libsql::Connection;
+use serde::Serialize;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::stock::stocks::TimeRange;
+use super::query::{AssetClass, TradeFilter, TradeQuery};
+
+/// Metrics of one rating bucket
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RatingBucket {
+    /// 1-5, `None` for unrated trades
+    pub rating: Option<u8>,
+    pub trade_count: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+    pub win_rate: f64,
+    pub average_win: f64,
+    pub average_loss: f64,
+    /// Average P&L per trade: `win_rate * average_win - loss_rate * average_loss`
+    pub expectancy: f64,
+    pub total_pnl: f64,
+    /// Mean R multiple over the trades with a known initial risk
+    pub average_r_multiple: Option<f64>,
+    pub r_trade_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RatingExpectancy {
+    /// Ratings 1 through 5, empty buckets included so the chart axis is stable
+    pub buckets: Vec<RatingBucket>,
+    pub unrated: RatingBucket,
+}
+
+/// One closed trade as read from the database
+struct RatedTrade {
+    rating: Option<u8>,
+    pnl: f64,
+    risk: Option<f64>,
+}
+
+pub async fn calculate_rating_expectancy(
+    conn: &Connection,
+    time_range: &TimeRange,
+    filter: &TradeFilter,
+) -> Result<RatingExpectancy> {
+    let periods = PeriodDefinition::load(conn).await;
+    let mut trades = Vec::new();
+    for source in [AssetClass::Stocks, AssetClass::Options] {
+        let query = TradeQuery::closed(source).time_range(time_range, &periods).filter(filter);
+        trades.extend(load_rated_trades(conn, &query).await?);
+    }
+    Ok(bucket_by_rating(&trades))
+}
+
+async fn load_rated_trades(conn: &Connection, query: &TradeQuery) -> Result<Vec<RatedTrade>> {
+    let sql = format!(
+        "SELECT trade_ratings, {pnl}, {risk} FROM {table} WHERE {condition}",
+        pnl = query.pnl_sql(),
+        risk = query.source().risk_sql(),
+        table = query.table(),
+        condition = query.condition(),
+    );
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(libsql::params_from_iter(query.params()))
+        .await?;
+
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        trades.push(RatedTrade {
+            rating: row.get::<Option<i64>>(0)?.and_then(|r| u8::try_from(r).ok()).filter(|r| (1..=5).contains(r)),
+            pnl: money::to_f64(money::row_decimal(&row, 1)),
+            risk: money::to_f64_opt(money::row_decimal_opt(&row, 2)).filter(|risk| *risk > 0.0),
+        });
+    }
+    Ok(trades)
+}
+
+fn bucket_by_rating(trades: &[RatedTrade]) -> RatingExpectancy {
+    RatingExpectancy {
+        buckets: (1..=5).map(|rating| summarize(Some(rating), trades)).collect(),
+        unrated: summarize(None, trades),
+    }
+}
+
+fn summarize(rating: Option<u8>, trades: &[RatedTrade]) -> RatingBucket {
+    let trades: Vec<&RatedTrade> = trades.iter().filter(|t| t.rating == rating).collect();
+    let wins: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|pnl| *pnl > 0.0).collect();
+    let losses: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|pnl| *pnl < 0.0).collect();
+    let r_multiples: Vec<f64> = trades.iter().filter_map(|t| t.risk.map(|risk| t.pnl / risk)).collect();
+
+    let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    let count = trades.len() as f64;
+    let (win_rate, loss_rate) = if trades.is_empty() {
+        (0.0, 0.0)
+    } else {
+        (wins.len() as f64 / count, losses.len() as f64 / count)
+    };
+    let average_win = mean(&wins);
+    let average_loss = mean(&losses).abs();
+
+    RatingBucket {
+        rating,
+        trade_count: trades.len() as u32,
+        winning_trades: wins.len() as u32,
+        losing_trades: losses.len() as u32,
+        win_rate: win_rate * 100.0,
+        average_win,
+        average_loss,
+        expectancy: win_rate * average_win - loss_rate * average_loss,
+        total_pnl: trades.iter().map(|t| t.pnl).sum(),
+        average_r_multiple: (!r_multiples.is_empty()).then(|| mean(&r_multiples)),
+        r_trade_count: r_multiples.len() as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(rating: Option<u8>, pnl: f64, risk: Option<f64>) -> RatedTrade {
+        RatedTrade { rating, pnl, risk }
+    }
+
+    #[test]
+    fn test_bucket_by_rating() {
+        let result = bucket_by_rating(&[
+            trade(Some(5), 300.0, Some(100.0)),
+            trade(Some(5), -100.0, Some(100.0)),
+            trade(Some(5), 0.0, None),
+            trade(Some(1), -50.0, Some(25.0)),
+            trade(None, 20.0, None),
+        ]);
+
+        assert_eq!(result.buckets.len(), 5);
+        let a_plus = &result.buckets[4];
+        assert_eq!(a_plus.rating, Some(5));
+        assert_eq!((a_plus.trade_count, a_plus.winning_trades, a_plus.losing_trades), (3, 1, 1));
+        assert!((a_plus.expectancy - 200.0 / 3.0).abs() < 1e-9);
+        assert!((a_plus.expectancy - a_plus.total_pnl / 3.0).abs() < 1e-9);
+        assert_eq!(a_plus.average_r_multiple, Some(1.0));
+        assert_eq!(a_plus.r_trade_count, 2);
+
+        assert_eq!(result.buckets[0].average_r_multiple, Some(-2.0));
+        assert_eq!(result.buckets[2], RatingBucket { rating: Some(3), ..Default::default() });
+        assert_eq!(result.unrated.trade_count, 1);
+        assert_eq!(result.unrated.win_rate, 100.0);
+    }
+}
@@ -1,7 +1,10 @@
 use anyhow::Result;
 use libsql::Connection;
-use crate::models::analytics::{RiskMetrics, AnalyticsOptions};
+use crate::models::analytics::{RiskMetrics, AnalyticsOptions, PeriodDefinition};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
 use crate::models::stock::stocks::TimeRange;
+use crate::models::money::{self, Decimal};
+use super::daily_aggregates::load_daily_pnl;
 
 /// Calculate risk-adjusted metrics including average risk per trade
 pub async fn calculate_risk_metrics(
@@ -9,13 +12,23 @@ pub async fn calculate_risk_metrics(
     time_range: &TimeRange,
     options: &AnalyticsOptions,
 ) -> Result<RiskMetrics> {
-    let (time_condition, time_params) = time_range.to_sql_condition();
+    let periods = PeriodDefinition::load(conn).await;
+    let (time_condition, time_params) = time_range.to_sql_condition_for(&periods);
     
     // Calculate average risk per trade
     let avg_risk_per_trade = calculate_average_risk_per_trade(conn, &time_condition, &time_params).await?;
     
-    // Calculate daily returns for Sharpe/Sortino ratios
-    let daily_returns = calculate_daily_returns(conn, &time_condition, &time_params).await?;
+    // Calculate daily returns for Sharpe/Sortino ratios. Calendar-day users read the
+    // pre-aggregated table; shifted sessions still need the per-trade bucketing.
+    let daily_returns = if periods.uses_calendar_days() {
+        load_daily_pnl(conn, &time_condition, &time_params)
+            .await?
+            .into_iter()
+            .map(|day| money::to_f64(day.realized_pnl))
+            .collect()
+    } else {
+        calculate_daily_returns(conn, &time_condition, &time_params).await?
+    };
     
     // Calculate drawdown metrics
     let drawdown_metrics = calculate_drawdown_metrics(&daily_returns).await?;
@@ -122,11 +135,7 @@ async fn calculate_daily_returns(
         FROM (
             SELECT 
                 *,
-                CASE 
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
             
@@ -134,11 +143,7 @@ async fn calculate_daily_returns(
             
             SELECT 
                 *,
-                CASE 
-                    WHEN exit_price IS NOT NULL THEN 
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -174,16 +179,17 @@ async fn calculate_drawdown_metrics(daily_returns: &[f64]) -> Result<DrawdownMet
         return Ok(DrawdownMetrics::default());
     }
 
-    let mut cumulative_pnl = 0.0;
-    let mut peak = 0.0;
-    let mut max_drawdown: f64 = 0.0;
+    // Running totals in Decimal so a long history doesn't accumulate float error
+    let mut cumulative_pnl = Decimal::ZERO;
+    let mut peak = Decimal::ZERO;
+    let mut max_drawdown = Decimal::ZERO;
     let mut max_drawdown_percentage: f64 = 0.0;
     let mut current_drawdown_duration = 0;
     let mut max_drawdown_duration = 0;
     let mut ulcer_sum = 0.0;
 
     for &pnl in daily_returns {
-        cumulative_pnl += pnl;
+        cumulative_pnl += money::from_f64(pnl);
         
         if cumulative_pnl > peak {
             peak = cumulative_pnl;
@@ -196,14 +202,12 @@ async fn calculate_drawdown_metrics(daily_returns: &[f64]) -> Result<DrawdownMet
         let drawdown = peak - cumulative_pnl;
         max_drawdown = max_drawdown.max(drawdown);
         
-        if peak > 0.0 {
-            let drawdown_percentage = (drawdown / peak) * 100.0;
+        if peak > Decimal::ZERO {
+            let drawdown_percentage = money::to_f64(drawdown / peak) * 100.0;
             max_drawdown_percentage = max_drawdown_percentage.max(drawdown_percentage);
-        }
 
-        // Ulcer Index calculation
-        if peak > 0.0 {
-            ulcer_sum += ((drawdown / peak) * 100.0).powi(2);
+            // Ulcer Index calculation
+            ulcer_sum += drawdown_percentage.powi(2);
         }
     }
 
@@ -213,15 +217,15 @@ async fn calculate_drawdown_metrics(daily_returns: &[f64]) -> Result<DrawdownMet
         0.0
     };
 
-    let current_drawdown = peak - cumulative_pnl;
-    let recovery_factor = if max_drawdown > 0.0 {
-        cumulative_pnl / max_drawdown
+    let current_drawdown = money::to_f64(peak - cumulative_pnl);
+    let recovery_factor = if max_drawdown > Decimal::ZERO {
+        money::to_f64(cumulative_pnl / max_drawdown)
     } else {
         0.0
     };
 
     Ok(DrawdownMetrics {
-        maximum_drawdown: max_drawdown,
+        maximum_drawdown: money::to_f64(max_drawdown),
         maximum_drawdown_percentage: max_drawdown_percentage,
         maximum_drawdown_duration_days: max_drawdown_duration,
         current_drawdown,
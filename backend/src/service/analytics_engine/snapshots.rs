@@ -0,0 +1,119 @@
+use anyhow::Result;
+use libsql::Connection;
+use crate::models::analytics::{AnalyticsOptions, AnalyticsSnapshot, ChangeSet};
+use crate::models::stock::stocks::TimeRange;
+
+use super::{core_metrics, performance_metrics, risk_metrics};
+
+/// Compute core, risk and performance metrics for a range without storing them
+pub async fn compute_snapshot(
+    conn: &Connection,
+    time_range: &TimeRange,
+    time_range_label: &str,
+    label: Option<String>,
+) -> Result<AnalyticsSnapshot> {
+    let options = AnalyticsOptions { time_range: time_range.clone(), ..Default::default() };
+    let core = core_metrics::calculate_core_metrics(conn, time_range).await?;
+    let risk = risk_metrics::calculate_risk_metrics(conn, time_range, &options).await?;
+    let performance = performance_metrics::calculate_performance_metrics(conn, time_range).await?;
+
+    Ok(AnalyticsSnapshot {
+        id: uuid::Uuid::new_v4().to_string(),
+        label,
+        time_range: time_range_label.to_string(),
+        core_metrics: core,
+        risk_metrics: risk,
+        performance_metrics: performance,
+        created_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+pub async fn save_snapshot(conn: &Connection, snapshot: &AnalyticsSnapshot) -> Result<()> {
+    conn.execute(
+        r#"
+        INSERT INTO analytics_snapshots (id, label, time_range, core_metrics, risk_metrics, performance_metrics, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+        libsql::params![
+            snapshot.id.clone(),
+            snapshot.label.clone(),
+            snapshot.time_range.clone(),
+            serde_json::to_string(&snapshot.core_metrics)?,
+            serde_json::to_string(&snapshot.risk_metrics)?,
+            serde_json::to_string(&snapshot.performance_metrics)?,
+            snapshot.created_at.clone()
+        ],
+    ).await?;
+    Ok(())
+}
+
+const SNAPSHOT_COLUMNS: &str = "id, label, time_range, core_metrics, risk_metrics, performance_metrics, created_at";
+
+fn snapshot_from_row(row: &libsql::Row) -> Result<AnalyticsSnapshot> {
+    Ok(AnalyticsSnapshot {
+        id: row.get(0)?,
+        label: row.get::<Option<String>>(1)?,
+        time_range: row.get(2)?,
+        core_metrics: serde_json::from_str(&row.get::<String>(3)?)?,
+        risk_metrics: serde_json::from_str(&row.get::<String>(4)?)?,
+        performance_metrics: serde_json::from_str(&row.get::<String>(5)?)?,
+        created_at: row.get(6)?,
+    })
+}
+
+/// Stored snapshots, newest first
+pub async fn list_snapshots(conn: &Connection, limit: i64) -> Result<Vec<AnalyticsSnapshot>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {} FROM analytics_snapshots ORDER BY created_at DESC LIMIT ?", SNAPSHOT_COLUMNS))
+        .await?
+        .query(libsql::params![limit])
+        .await?;
+
+    let mut snapshots = Vec::new();
+    while let Some(row) = rows.next().await? {
+        snapshots.push(snapshot_from_row(&row)?);
+    }
+    Ok(snapshots)
+}
+
+pub async fn get_snapshot(conn: &Connection, id: &str) -> Result<Option<AnalyticsSnapshot>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {} FROM analytics_snapshots WHERE id = ?", SNAPSHOT_COLUMNS))
+        .await?
+        .query(libsql::params![id])
+        .await?;
+
+    match rows.next().await? {
+        Some(row) => Ok(Some(snapshot_from_row(&row)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn delete_snapshot(conn: &Connection, id: &str) -> Result<bool> {
+    let deleted = conn.execute("DELETE FROM analytics_snapshots WHERE id = ?", libsql::params![id]).await?;
+    Ok(deleted > 0)
+}
+
+/// Diff two stored snapshots; None if either doesn't exist
+pub async fn diff_snapshots(conn: &Connection, from_id: &str, to_id: &str) -> Result<Option<ChangeSet>> {
+    let (Some(before), Some(after)) = (get_snapshot(conn, from_id).await?, get_snapshot(conn, to_id).await?) else {
+        return Ok(None);
+    };
+    Ok(Some(ChangeSet::between(&before, &after)))
+}
+
+/// Diff two freshly computed ranges, each given with its label
+pub async fn diff_ranges(conn: &Connection, from: (&TimeRange, &str), to: (&TimeRange, &str)) -> Result<ChangeSet> {
+    let before = compute_snapshot(conn, from.0, from.1, None).await?;
+    let after = compute_snapshot(conn, to.0, to.1, None).await?;
+    Ok(ChangeSet::between(&before, &after))
+}
+
+/// Diff the range against the window of the same length right before it, e.g. this
+/// month-to-date against the same number of days of last month. None for open-ended ranges.
+pub async fn diff_with_previous_period(conn: &Connection, time_range: &TimeRange, label: &str) -> Result<Option<ChangeSet>> {
+    let Some(previous) = time_range.previous_period() else { return Ok(None) };
+    let before = compute_snapshot(conn, &previous, "previous", Some("previous period".to_string())).await?;
+    let after = compute_snapshot(conn, time_range, label, Some(label.to_string())).await?;
+    Ok(Some(ChangeSet::between(&before, &after)))
+}
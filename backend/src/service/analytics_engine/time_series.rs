@@ -7,8 +7,11 @@
 use anyhow::Result;
 use libsql::Connection;
 use std::collections::HashMap;
-use crate::models::analytics::{TimeSeriesData, TimeSeriesPoint, AnalyticsOptions};
+use crate::models::analytics::{TimeSeriesData, TimeSeriesPoint, AnalyticsOptions, PeriodDefinition};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
 use crate::models::stock::stocks::TimeRange;
+use super::daily_aggregates::{load_daily_pnl, DailyPnl};
+use crate::models::money::{self, Decimal};
 
 /// Calculate time series data for equity curves and rolling metrics
 pub async fn calculate_time_series_data(
@@ -16,25 +19,40 @@ pub async fn calculate_time_series_data(
     time_range: &TimeRange,
     options: &AnalyticsOptions,
 ) -> Result<TimeSeriesData> {
-    let (time_condition, time_params) = time_range.to_sql_condition();
+    let periods = PeriodDefinition::load(conn).await;
+    let (time_condition, time_params) = time_range.to_sql_condition_for(&periods);
+    // Bucket by trading date so sessions crossing midnight land on one day
+    let trading_date = periods.trading_date_sql("exit_date");
+
+    // Calendar-day users read the pre-aggregated daily table once for every daily series;
+    // shifted sessions still bucket trades individually
+    let aggregated = if periods.uses_calendar_days() {
+        Some(load_daily_pnl(conn, &time_condition, &time_params).await?)
+    } else {
+        None
+    };
+    let aggregated = aggregated.as_deref();
 
     // Calculate daily PnL time series
-    let daily_pnl = calculate_daily_pnl_series(conn, &time_condition, &time_params).await?;
+    let daily_pnl = match aggregated {
+        Some(days) => daily_pnl_points(days),
+        None => calculate_daily_pnl_series(conn, &time_condition, &time_params, &trading_date).await?,
+    };
 
     // Calculate weekly PnL time series
-    let weekly_pnl = calculate_weekly_pnl_series(conn, &time_condition, &time_params).await?;
+    let weekly_pnl = calculate_weekly_pnl_series(conn, &time_condition, &time_params, &trading_date, &periods).await?;
 
     // Calculate monthly PnL time series
     let monthly_pnl = calculate_monthly_pnl_series(conn, &time_condition, &time_params).await?;
 
     // Calculate rolling win rates
-    let rolling_win_rate_20 = calculate_rolling_win_rate(conn, &time_condition, &time_params, 20).await?;
-    let rolling_win_rate_50 = calculate_rolling_win_rate(conn, &time_condition, &time_params, 50).await?;
-    let rolling_win_rate_100 = calculate_rolling_win_rate(conn, &time_condition, &time_params, 100).await?;
+    let rolling_win_rate_20 = calculate_rolling_win_rate(conn, &time_condition, &time_params, aggregated, 20).await?;
+    let rolling_win_rate_50 = calculate_rolling_win_rate(conn, &time_condition, &time_params, aggregated, 50).await?;
+    let rolling_win_rate_100 = calculate_rolling_win_rate(conn, &time_condition, &time_params, aggregated, 100).await?;
 
     // Calculate rolling Sharpe ratios
-    let rolling_sharpe_20 = calculate_rolling_sharpe_ratio(conn, &time_condition, &time_params, 20, options.risk_free_rate).await?;
-    let rolling_sharpe_50 = calculate_rolling_sharpe_ratio(conn, &time_condition, &time_params, 50, options.risk_free_rate).await?;
+    let rolling_sharpe_20 = calculate_rolling_sharpe_ratio(conn, &time_condition, &time_params, aggregated, 20, options.risk_free_rate).await?;
+    let rolling_sharpe_50 = calculate_rolling_sharpe_ratio(conn, &time_condition, &time_params, aggregated, 50, options.risk_free_rate).await?;
 
     // Calculate calendar-based analytics
     let profit_by_day_of_week = calculate_profit_by_day_of_week(conn, &time_condition, &time_params).await?;
@@ -79,6 +97,7 @@ pub async fn calculate_time_series_data(
         annualized_return,
         total_return_percentage,
         total_trades,
+        week_start_day: periods.week_start_day,
     })
 }
 
@@ -88,6 +107,7 @@ async fn calculate_daily_pnl_series(
     conn: &Connection,
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
+    trading_date: &str,
 ) -> Result<Vec<TimeSeriesPoint>> {
     let sql = format!(
         r#"
@@ -97,31 +117,22 @@ async fn calculate_daily_pnl_series(
             COUNT(*) as trade_count
         FROM (
             SELECT
-                exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {trading_date} as exit_date,
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
-            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({time_condition})
 
             UNION ALL
 
             SELECT
-                exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {trading_date} as exit_date,
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
-            WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND ({time_condition})
         )
         GROUP BY DATE(exit_date)
         ORDER BY trade_date
-        "#,
-        time_condition, time_condition
+        "#
     );
 
     let mut query_params = Vec::new();
@@ -136,19 +147,13 @@ async fn calculate_daily_pnl_series(
         .await?;
 
     let mut time_series = Vec::new();
-    let mut cumulative_value = 0.0;
+    let mut cumulative_value = Decimal::ZERO;
 
     while let Some(row) = rows.next().await? {
         let date = row.get::<String>(0).unwrap_or_default();
         
-        // Safely handle the daily_pnl conversion
-        let daily_pnl = match row.get::<libsql::Value>(1) {
-            Ok(libsql::Value::Null) => 0.0,
-            Ok(libsql::Value::Real(val)) => val,
-            Ok(libsql::Value::Integer(val)) => val as f64,
-            Ok(libsql::Value::Text(_)) => 0.0, // Unexpected but handle gracefully
-            Err(_) | Ok(_) => 0.0,
-        };
+        // Read as Decimal so the running total doesn't drift
+        let daily_pnl = money::row_decimal(&row, 1);
         
         // Safely handle the trade_count conversion
         let trade_count = match row.get::<libsql::Value>(2) {
@@ -163,8 +168,8 @@ async fn calculate_daily_pnl_series(
 
         time_series.push(TimeSeriesPoint {
             date,
-            value: daily_pnl,
-            cumulative_value,
+            value: money::to_f64(daily_pnl),
+            cumulative_value: money::to_f64(cumulative_value),
             trade_count,
         });
     }
@@ -172,45 +177,55 @@ async fn calculate_daily_pnl_series(
     Ok(time_series)
 }
 
-/// Calculate weekly PnL time series
+/// Daily PnL series from pre-aggregated rows
+fn daily_pnl_points(days: &[DailyPnl]) -> Vec<TimeSeriesPoint> {
+    let mut cumulative_value = Decimal::ZERO;
+    days.iter()
+        .map(|day| {
+            cumulative_value += day.realized_pnl;
+            TimeSeriesPoint {
+                date: day.trade_date.clone(),
+                value: money::to_f64(day.realized_pnl),
+                cumulative_value: money::to_f64(cumulative_value),
+                trade_count: day.trade_count,
+            }
+        })
+        .collect()
+}
+
+/// Calculate weekly PnL time series, bucketed on the user's week start
 async fn calculate_weekly_pnl_series(
     conn: &Connection,
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
+    trading_date: &str,
+    periods: &PeriodDefinition,
 ) -> Result<Vec<TimeSeriesPoint>> {
+    let week_start = periods.week_start_sql("exit_date");
     let sql = format!(
         r#"
         SELECT
-            strftime('%Y-W%W', exit_date) as week,
+            {week_start} as week,
             SUM(calculated_pnl) as weekly_pnl,
             COUNT(*) as trade_count
         FROM (
             SELECT
-                exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {trading_date} as exit_date,
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
-            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({time_condition})
 
             UNION ALL
 
             SELECT
-                exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {trading_date} as exit_date,
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
-            WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND ({time_condition})
         )
-        GROUP BY strftime('%Y-W%W', exit_date)
+        GROUP BY {week_start}
         ORDER BY week
-        "#,
-        time_condition, time_condition
+        "#
     );
 
     let mut query_params = Vec::new();
@@ -225,19 +240,13 @@ async fn calculate_weekly_pnl_series(
         .await?;
 
     let mut time_series = Vec::new();
-    let mut cumulative_value = 0.0;
+    let mut cumulative_value = Decimal::ZERO;
 
     while let Some(row) = rows.next().await? {
         let week = row.get::<String>(0).unwrap_or_default();
         
-        // Safely handle the weekly_pnl conversion
-        let weekly_pnl = match row.get::<libsql::Value>(1) {
-            Ok(libsql::Value::Null) => 0.0,
-            Ok(libsql::Value::Real(val)) => val,
-            Ok(libsql::Value::Integer(val)) => val as f64,
-            Ok(libsql::Value::Text(_)) => 0.0,
-            Err(_) | Ok(_) => 0.0,
-        };
+        // Read as Decimal so the running total doesn't drift
+        let weekly_pnl = money::row_decimal(&row, 1);
         
         // Safely handle the trade_count conversion
         let trade_count = match row.get::<libsql::Value>(2) {
@@ -252,8 +261,8 @@ async fn calculate_weekly_pnl_series(
 
         time_series.push(TimeSeriesPoint {
             date: week,
-            value: weekly_pnl,
-            cumulative_value,
+            value: money::to_f64(weekly_pnl),
+            cumulative_value: money::to_f64(cumulative_value),
             trade_count,
         });
     }
@@ -276,11 +285,7 @@ async fn calculate_monthly_pnl_series(
         FROM (
             SELECT
                 exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
 
@@ -288,11 +293,7 @@ async fn calculate_monthly_pnl_series(
 
             SELECT
                 exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -314,19 +315,13 @@ async fn calculate_monthly_pnl_series(
         .await?;
 
     let mut time_series = Vec::new();
-    let mut cumulative_value = 0.0;
+    let mut cumulative_value = Decimal::ZERO;
 
     while let Some(row) = rows.next().await? {
         let month = row.get::<String>(0).unwrap_or_default();
         
-        // Safely handle the monthly_pnl conversion
-        let monthly_pnl = match row.get::<libsql::Value>(1) {
-            Ok(libsql::Value::Null) => 0.0,
-            Ok(libsql::Value::Real(val)) => val,
-            Ok(libsql::Value::Integer(val)) => val as f64,
-            Ok(libsql::Value::Text(_)) => 0.0,
-            Err(_) | Ok(_) => 0.0,
-        };
+        // Read as Decimal so the running total doesn't drift
+        let monthly_pnl = money::row_decimal(&row, 1);
         
         // Safely handle the trade_count conversion
         let trade_count = match row.get::<libsql::Value>(2) {
@@ -341,8 +336,8 @@ async fn calculate_monthly_pnl_series(
 
         time_series.push(TimeSeriesPoint {
             date: month,
-            value: monthly_pnl,
-            cumulative_value,
+            value: money::to_f64(monthly_pnl),
+            cumulative_value: money::to_f64(cumulative_value),
             trade_count,
         });
     }
@@ -355,10 +350,61 @@ async fn calculate_rolling_sharpe_ratio(
     conn: &Connection,
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
+    aggregated: Option<&[DailyPnl]>,
     window_size: u32,
     risk_free_rate: f64,
 ) -> Result<Vec<TimeSeriesPoint>> {
     // Get daily returns
+    let daily_returns = match aggregated {
+        Some(days) => days.iter().map(|day| (day.trade_date.clone(), money::to_f64(day.realized_pnl), day.trade_count as i64)).collect(),
+        None => query_daily_returns(conn, time_condition, time_params).await?,
+    };
+
+    // Calculate rolling Sharpe ratio
+    let mut rolling_sharpe = Vec::new();
+    for i in 0..daily_returns.len() {
+        let start_idx = i.saturating_sub(window_size as usize);
+        let end_idx = i + 1;
+
+        if start_idx >= end_idx {
+            // Not enough data yet
+            rolling_sharpe.push(TimeSeriesPoint {
+                date: daily_returns[i].0.clone(),
+                value: 0.0,
+                cumulative_value: 0.0,
+                trade_count: 0,
+            });
+            continue;
+        }
+
+        let window_returns: Vec<f64> = daily_returns[start_idx..end_idx]
+            .iter()
+            .map(|(_, ret, _)| *ret)
+            .collect();
+
+        let sharpe = calculate_sharpe_ratio(&window_returns, risk_free_rate);
+        let trade_count: u32 = daily_returns[start_idx..end_idx]
+            .iter()
+            .map(|(_, _, count)| *count as u32)
+            .sum();
+
+        rolling_sharpe.push(TimeSeriesPoint {
+            date: daily_returns[i].0.clone(),
+            value: sharpe,
+            cumulative_value: sharpe,
+            trade_count,
+        });
+    }
+
+    Ok(rolling_sharpe)
+}
+
+/// Daily returns and trade counts straight from the trade tables
+async fn query_daily_returns(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<(String, f64, i64)>> {
     let sql = format!(
         r#"
         SELECT
@@ -368,11 +414,7 @@ async fn calculate_rolling_sharpe_ratio(
         FROM (
             SELECT
                 exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
 
@@ -380,11 +422,7 @@ async fn calculate_rolling_sharpe_ratio(
 
             SELECT
                 exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -430,43 +468,7 @@ async fn calculate_rolling_sharpe_ratio(
         daily_returns.push((date, daily_return, trade_count));
     }
 
-    // Calculate rolling Sharpe ratio
-    let mut rolling_sharpe = Vec::new();
-    for i in 0..daily_returns.len() {
-        let start_idx = i.saturating_sub(window_size as usize);
-        let end_idx = i + 1;
-
-        if start_idx >= end_idx {
-            // Not enough data yet
-            rolling_sharpe.push(TimeSeriesPoint {
-                date: daily_returns[i].0.clone(),
-                value: 0.0,
-                cumulative_value: 0.0,
-                trade_count: 0,
-            });
-            continue;
-        }
-
-        let window_returns: Vec<f64> = daily_returns[start_idx..end_idx]
-            .iter()
-            .map(|(_, ret, _)| *ret)
-            .collect();
-
-        let sharpe = calculate_sharpe_ratio(&window_returns, risk_free_rate);
-        let trade_count: u32 = daily_returns[start_idx..end_idx]
-            .iter()
-            .map(|(_, _, count)| *count as u32)
-            .sum();
-
-        rolling_sharpe.push(TimeSeriesPoint {
-            date: daily_returns[i].0.clone(),
-            value: sharpe,
-            cumulative_value: sharpe,
-            trade_count,
-        });
-    }
-
-    Ok(rolling_sharpe)
+    Ok(daily_returns)
 }
 
 /// Calculate Sharpe ratio for a series of returns
@@ -506,8 +508,47 @@ async fn calculate_rolling_win_rate(
     conn: &Connection,
     time_condition: &str,
     time_params: &[chrono::DateTime<chrono::Utc>],
+    aggregated: Option<&[DailyPnl]>,
     window_size: u32,
 ) -> Result<Vec<TimeSeriesPoint>> {
+    let daily_data = match aggregated {
+        Some(days) => days.iter().map(|day| (day.trade_date.clone(), day.win_count, day.trade_count)).collect(),
+        None => query_daily_win_counts(conn, time_condition, time_params).await?,
+    };
+
+    // Calculate rolling win rate in Rust
+    let mut rolling_win_rate = Vec::new();
+    for i in 0..daily_data.len() {
+        let start_idx = i.saturating_sub(window_size as usize);
+        let end_idx = i + 1;
+
+        let window_data = &daily_data[start_idx..end_idx];
+        let total_wins: u32 = window_data.iter().map(|(_, wins, _)| wins).sum();
+        let total_trades: u32 = window_data.iter().map(|(_, _, total)| total).sum();
+
+        let win_rate = if total_trades > 0 {
+            (total_wins as f64 / total_trades as f64) * 100.0
+        } else {
+            0.0
+        };
+
+        rolling_win_rate.push(TimeSeriesPoint {
+            date: daily_data[i].0.clone(),
+            value: win_rate,
+            cumulative_value: win_rate,
+            trade_count: window_data.iter().map(|(_, _, total)| total).sum(),
+        });
+    }
+
+    Ok(rolling_win_rate)
+}
+
+/// Daily wins and trade counts straight from the trade tables
+async fn query_daily_win_counts(
+    conn: &Connection,
+    time_condition: &str,
+    time_params: &[chrono::DateTime<chrono::Utc>],
+) -> Result<Vec<(String, u32, u32)>> {
     let sql = format!(
         r#"
         SELECT
@@ -517,11 +558,7 @@ async fn calculate_rolling_win_rate(
         FROM (
             SELECT
                 exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
 
@@ -529,11 +566,7 @@ async fn calculate_rolling_win_rate(
 
             SELECT
                 exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -579,31 +612,7 @@ async fn calculate_rolling_win_rate(
         daily_data.push((date, wins, total_trades));
     }
 
-    // Calculate rolling win rate in Rust
-    let mut rolling_win_rate = Vec::new();
-    for i in 0..daily_data.len() {
-        let start_idx = i.saturating_sub(window_size as usize);
-        let end_idx = i + 1;
-
-        let window_data = &daily_data[start_idx..end_idx];
-        let total_wins: u32 = window_data.iter().map(|(_, wins, _)| wins).sum();
-        let total_trades: u32 = window_data.iter().map(|(_, _, total)| total).sum();
-
-        let win_rate = if total_trades > 0 {
-            (total_wins as f64 / total_trades as f64) * 100.0
-        } else {
-            0.0
-        };
-
-        rolling_win_rate.push(TimeSeriesPoint {
-            date: daily_data[i].0.clone(),
-            value: win_rate,
-            cumulative_value: win_rate,
-            trade_count: window_data.iter().map(|(_, _, total)| total).sum(),
-        });
-    }
-
-    Ok(rolling_win_rate)
+    Ok(daily_data)
 }
 
 /// Calculate profit by day of week
@@ -620,11 +629,7 @@ async fn calculate_profit_by_day_of_week(
         FROM (
             SELECT
                 exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
 
@@ -632,11 +637,7 @@ async fn calculate_profit_by_day_of_week(
 
             SELECT
                 exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -695,11 +696,7 @@ async fn calculate_profit_by_month(
         FROM (
             SELECT
                 exit_date,
-                CASE
-                    WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions
-                    WHEN trade_type = 'SELL' THEN (entry_price - exit_price) * number_shares - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {STOCK_PNL_SQL} as calculated_pnl
             FROM stocks
             WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND ({})
 
@@ -707,11 +704,7 @@ async fn calculate_profit_by_month(
 
             SELECT
                 exit_date,
-                CASE
-                    WHEN exit_price IS NOT NULL THEN
-                        (exit_price - entry_price) * number_of_contracts * 100 - commissions
-                    ELSE 0
-                END as calculated_pnl
+                {OPTION_PNL_SQL} as calculated_pnl
             FROM options
             WHERE status = 'closed' AND exit_price IS NOT NULL AND ({})
         )
@@ -797,6 +790,7 @@ impl Default for TimeSeriesData {
             annualized_return: 0.0,
             total_return_percentage: 0.0,
             total_trades: 0,
+            week_start_day: PeriodDefinition::default().week_start_day,
         }
     }
 }
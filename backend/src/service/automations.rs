@@ -0,0 +1,724 @@
+// User-defined automations ("if this then that"). A rule names a trigger (a trade closed, a
+// tag added to a trade, an insight generated), conditions on the event's fields and a list
+// of actions. Events are dispatched off the request path; each rule runs at most once per
+// event subject (trade, trade + tag, insight), which the unique index on `automation_runs`
+// enforces. Actions never raise new events, so rules cannot trigger each other in a loop.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use super::ai_service::setup_discovery::TradeAsset;
+use super::notifications::push::{PushPayload, PushService};
+use crate::models::ai::insights::Insight;
+use crate::models::money;
+use crate::models::notebook::{CreateNoteRequest, CreateReminderRequest, NotebookNote, NotebookReminder, NotebookTemplate};
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::tags::TradeTagAssociation;
+use crate::turso::config::WebPushConfig;
+
+const MAX_CONDITIONS: usize = 10;
+const MAX_ACTIONS: usize = 10;
+/// Longest delay for a reminder created by a rule (one week)
+const MAX_REMINDER_DELAY_MINUTES: i64 = 7 * 24 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerType {
+    TradeClosed,
+    TagAdded,
+    InsightGenerated,
+}
+
+impl TriggerType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriggerType::TradeClosed => "trade_closed",
+            TriggerType::TagAdded => "tag_added",
+            TriggerType::InsightGenerated => "insight_generated",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(Value::String(value.to_string())).ok()
+    }
+
+    /// Event fields conditions and `{{placeholders}}` can refer to
+    pub fn fields(&self) -> &'static [&'static str] {
+        match self {
+            TriggerType::TradeClosed => &["asset", "trade_id", "symbol", "direction", "pnl"],
+            TriggerType::TagAdded => &["asset", "trade_id", "symbol", "tag_id", "tag_name", "tag_category"],
+            TriggerType::InsightGenerated => &["insight_id", "insight_type", "title", "confidence", "source"],
+        }
+    }
+
+    fn on_trade(&self) -> bool {
+        matches!(self, TriggerType::TradeClosed | TriggerType::TagAdded)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOp {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    Contains,
+}
+
+/// `field op value`, e.g. `pnl lt -200` for "closed with a loss over $200"
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    pub field: String,
+    pub op: ConditionOp,
+    pub value: Value,
+}
+
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.to_string(),
+            None => format!("{:.2}", n.as_f64().unwrap_or_default()),
+        },
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+impl Condition {
+    /// A field missing from the event fails the condition
+    pub fn matches(&self, fields: &Map<String, Value>) -> bool {
+        let Some(actual) = fields.get(&self.field) else { return false };
+        let numbers = as_number(actual).zip(as_number(&self.value));
+        match self.op {
+            ConditionOp::Eq | ConditionOp::Ne => {
+                let equal = match numbers {
+                    Some((a, b)) => a == b,
+                    None => as_text(actual).eq_ignore_ascii_case(&as_text(&self.value)),
+                };
+                equal == (self.op == ConditionOp::Eq)
+            }
+            ConditionOp::Gt => numbers.is_some_and(|(a, b)| a > b),
+            ConditionOp::Gte => numbers.is_some_and(|(a, b)| a >= b),
+            ConditionOp::Lt => numbers.is_some_and(|(a, b)| a < b),
+            ConditionOp::Lte => numbers.is_some_and(|(a, b)| a <= b),
+            ConditionOp::Contains => as_text(actual).to_lowercase().contains(&as_text(&self.value).to_lowercase()),
+        }
+    }
+}
+
+/// Text fields may use `{{field}}` placeholders from the event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutomationAction {
+    /// Reminder due `delay_minutes` from now, attached to the note an earlier `create_note`
+    /// action made or to a new note of its own
+    CreateReminder {
+        title: String,
+        description: Option<String>,
+        #[serde(default)]
+        delay_minutes: i64,
+    },
+    SendNotification {
+        title: String,
+        body: Option<String>,
+    },
+    /// Only for trade triggers
+    AddTag { tag_id: String },
+    /// Notebook note from a template, or an empty one without `template_id`
+    CreateNote {
+        title: String,
+        template_id: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationRule {
+    pub id: String,
+    pub name: String,
+    pub trigger: TriggerType,
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<AutomationAction>,
+    pub is_enabled: bool,
+    pub trigger_count: i64,
+    pub last_triggered_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutomationRuleRequest {
+    pub name: String,
+    pub trigger: TriggerType,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<AutomationAction>,
+    #[serde(default = "default_enabled")]
+    pub is_enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl AutomationRuleRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.actions.is_empty() || self.actions.len() > MAX_ACTIONS {
+            return Err(format!("a rule needs between 1 and {} actions", MAX_ACTIONS));
+        }
+        if self.conditions.len() > MAX_CONDITIONS {
+            return Err(format!("a rule can have at most {} conditions", MAX_CONDITIONS));
+        }
+        let fields = self.trigger.fields();
+        if let Some(condition) = self.conditions.iter().find(|c| !fields.contains(&c.field.as_str())) {
+            return Err(format!(
+                "unknown field '{}' for {}; available: {}",
+                condition.field,
+                self.trigger.as_str(),
+                fields.join(", ")
+            ));
+        }
+        for action in &self.actions {
+            match action {
+                AutomationAction::CreateReminder { title, delay_minutes, .. } => {
+                    if title.trim().is_empty() {
+                        return Err("create_reminder needs a title".to_string());
+                    }
+                    if !(0..=MAX_REMINDER_DELAY_MINUTES).contains(delay_minutes) {
+                        return Err(format!("delay_minutes must be between 0 and {}", MAX_REMINDER_DELAY_MINUTES));
+                    }
+                }
+                AutomationAction::SendNotification { title, .. } if title.trim().is_empty() => {
+                    return Err("send_notification needs a title".to_string());
+                }
+                AutomationAction::AddTag { .. } if !self.trigger.on_trade() => {
+                    return Err("add_tag only works with trade_closed and tag_added triggers".to_string());
+                }
+                AutomationAction::CreateNote { title, .. } if title.trim().is_empty() => {
+                    return Err("create_note needs a title".to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Something that happened, with the fields rules are matched against
+#[derive(Debug, Clone)]
+pub struct AutomationEvent {
+    pub trigger: TriggerType,
+    /// What the event is about ("stock:42", "option:7:tag-id", "insight:id"); a rule runs
+    /// once per subject
+    pub subject: String,
+    pub fields: Map<String, Value>,
+    pub trade: Option<(TradeAsset, i64)>,
+}
+
+impl AutomationEvent {
+    pub fn matches(&self, rule: &AutomationRule) -> bool {
+        rule.is_enabled && rule.trigger == self.trigger && rule.conditions.iter().all(|c| c.matches(&self.fields))
+    }
+
+    /// Replace `{{field}}` placeholders; with `json_escape` the values are escaped for use
+    /// inside a JSON string
+    pub fn render(&self, template: &str, json_escape: bool) -> String {
+        let mut out = template.to_string();
+        for (name, value) in &self.fields {
+            let placeholder = format!("{{{{{}}}}}", name);
+            if !out.contains(&placeholder) {
+                continue;
+            }
+            let text = as_text(value);
+            let text = if json_escape {
+                let quoted = Value::String(text).to_string();
+                quoted[1..quoted.len() - 1].to_string()
+            } else {
+                text
+            };
+            out = out.replace(&placeholder, &text);
+        }
+        out
+    }
+}
+
+fn asset_name(asset: TradeAsset) -> &'static str {
+    match asset {
+        TradeAsset::Stock => "stock",
+        TradeAsset::Option => "option",
+    }
+}
+
+/// Symbol, direction and realized P&L of a trade, or None if it is missing or still open
+async fn trade_fields(conn: &Connection, asset: TradeAsset, trade_id: i64) -> Result<Option<Map<String, Value>>> {
+    let sql = match asset {
+        TradeAsset::Stock => format!(
+            "SELECT UPPER(symbol), CASE WHEN trade_type = 'SELL' THEN 'short' ELSE 'long' END, {STOCK_PNL_SQL} \
+             FROM stocks WHERE id = ? AND exit_price IS NOT NULL AND is_deleted = 0"
+        ),
+        TradeAsset::Option => format!(
+            "SELECT UPPER(symbol), LOWER(trade_direction), {OPTION_PNL_SQL} \
+             FROM options WHERE id = ? AND status = 'closed' AND exit_price IS NOT NULL AND is_deleted = 0"
+        ),
+    };
+    let mut rows = conn.prepare(&sql).await?.query(params![trade_id]).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let mut fields = Map::new();
+    fields.insert("asset".to_string(), Value::from(asset_name(asset)));
+    fields.insert("trade_id".to_string(), Value::from(trade_id));
+    fields.insert("symbol".to_string(), Value::from(row.get::<String>(0)?));
+    fields.insert("direction".to_string(), Value::from(row.get::<String>(1)?));
+    fields.insert("pnl".to_string(), Value::from(money::to_f64(money::row_decimal(&row, 2))));
+    Ok(Some(fields))
+}
+
+pub async fn trade_closed_event(conn: &Connection, asset: TradeAsset, trade_id: i64) -> Result<Option<AutomationEvent>> {
+    Ok(trade_fields(conn, asset, trade_id).await?.map(|fields| AutomationEvent {
+        trigger: TriggerType::TradeClosed,
+        subject: format!("{}:{}", asset_name(asset), trade_id),
+        fields,
+        trade: Some((asset, trade_id)),
+    }))
+}
+
+pub async fn tag_added_event(conn: &Connection, asset: TradeAsset, trade_id: i64, tag_id: &str) -> Result<AutomationEvent> {
+    let table = match asset {
+        TradeAsset::Stock => "stocks",
+        TradeAsset::Option => "options",
+    };
+    let mut fields = Map::new();
+    fields.insert("asset".to_string(), Value::from(asset_name(asset)));
+    fields.insert("trade_id".to_string(), Value::from(trade_id));
+    fields.insert("tag_id".to_string(), Value::from(tag_id));
+    let mut rows = conn
+        .prepare(&format!(
+            "SELECT (SELECT UPPER(symbol) FROM {table} WHERE id = ?), name, category FROM trade_tags WHERE id = ?"
+        ))
+        .await?
+        .query(params![trade_id, tag_id])
+        .await?;
+    if let Some(row) = rows.next().await? {
+        fields.insert("symbol".to_string(), row.get::<Option<String>>(0)?.map(Value::from).unwrap_or(Value::Null));
+        fields.insert("tag_name".to_string(), Value::from(row.get::<String>(1)?));
+        fields.insert("tag_category".to_string(), Value::from(row.get::<String>(2)?));
+    }
+    Ok(AutomationEvent {
+        trigger: TriggerType::TagAdded,
+        subject: format!("{}:{}:{}", asset_name(asset), trade_id, tag_id),
+        fields,
+        trade: Some((asset, trade_id)),
+    })
+}
+
+pub fn insight_generated_event(insight: &Insight) -> AutomationEvent {
+    let mut fields = Map::new();
+    fields.insert("insight_id".to_string(), Value::from(insight.id.clone()));
+    fields.insert("insight_type".to_string(), serde_json::to_value(&insight.insight_type).unwrap_or(Value::Null));
+    fields.insert("title".to_string(), Value::from(insight.title.clone()));
+    fields.insert("confidence".to_string(), Value::from(insight.confidence_score as f64));
+    fields.insert("source".to_string(), Value::from(insight.source.as_str()));
+    AutomationEvent {
+        trigger: TriggerType::InsightGenerated,
+        subject: format!("insight:{}", insight.id),
+        fields,
+        trade: None,
+    }
+}
+
+fn row_to_rule(row: &libsql::Row) -> Result<AutomationRule> {
+    let trigger: String = row.get(2)?;
+    Ok(AutomationRule {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        trigger: TriggerType::parse(&trigger).ok_or_else(|| anyhow::anyhow!("Unknown trigger '{}'", trigger))?,
+        conditions: serde_json::from_str(&row.get::<String>(3)?)?,
+        actions: serde_json::from_str(&row.get::<String>(4)?)?,
+        is_enabled: row.get::<i64>(5)? != 0,
+        trigger_count: row.get(6)?,
+        last_triggered_at: row.get(7)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+const RULE_COLUMNS: &str =
+    "id, name, trigger_type, conditions, actions, is_enabled, trigger_count, last_triggered_at, created_at, updated_at";
+
+pub async fn list_rules(conn: &Connection, trigger: Option<TriggerType>) -> Result<Vec<AutomationRule>> {
+    let mut rows = match trigger {
+        Some(trigger) => {
+            conn.prepare(&format!("SELECT {RULE_COLUMNS} FROM automation_rules WHERE trigger_type = ? ORDER BY created_at"))
+                .await?
+                .query(params![trigger.as_str()])
+                .await?
+        }
+        None => {
+            conn.prepare(&format!("SELECT {RULE_COLUMNS} FROM automation_rules ORDER BY created_at"))
+                .await?
+                .query(params![])
+                .await?
+        }
+    };
+    let mut rules = Vec::new();
+    while let Some(row) = rows.next().await? {
+        match row_to_rule(&row) {
+            Ok(rule) => rules.push(rule),
+            Err(e) => warn!("Skipping unreadable automation rule: {}", e),
+        }
+    }
+    Ok(rules)
+}
+
+pub async fn get_rule(conn: &Connection, id: &str) -> Result<Option<AutomationRule>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {RULE_COLUMNS} FROM automation_rules WHERE id = ?"))
+        .await?
+        .query(params![id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_rule(&row)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn create_rule(conn: &Connection, request: &AutomationRuleRequest) -> Result<AutomationRule> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO automation_rules (id, name, trigger_type, conditions, actions, is_enabled, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            id.clone(),
+            request.name.trim(),
+            request.trigger.as_str(),
+            serde_json::to_string(&request.conditions)?,
+            serde_json::to_string(&request.actions)?,
+            request.is_enabled,
+            now.clone(),
+            now
+        ],
+    )
+    .await?;
+    get_rule(conn, &id).await?.ok_or_else(|| anyhow::anyhow!("Automation rule not found after insert"))
+}
+
+pub async fn update_rule(conn: &Connection, id: &str, request: &AutomationRuleRequest) -> Result<Option<AutomationRule>> {
+    let updated = conn
+        .execute(
+            "UPDATE automation_rules SET name = ?, trigger_type = ?, conditions = ?, actions = ?, is_enabled = ?, updated_at = ? WHERE id = ?",
+            params![
+                request.name.trim(),
+                request.trigger.as_str(),
+                serde_json::to_string(&request.conditions)?,
+                serde_json::to_string(&request.actions)?,
+                request.is_enabled,
+                Utc::now().to_rfc3339(),
+                id
+            ],
+        )
+        .await?;
+    if updated == 0 {
+        return Ok(None);
+    }
+    get_rule(conn, id).await
+}
+
+pub async fn delete_rule(conn: &Connection, id: &str) -> Result<bool> {
+    let deleted = conn.execute("DELETE FROM automation_rules WHERE id = ?", params![id]).await?;
+    conn.execute("DELETE FROM automation_runs WHERE rule_id = ?", params![id]).await?;
+    Ok(deleted > 0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AutomationRun {
+    pub id: String,
+    pub rule_id: String,
+    pub subject: String,
+    /// "running", "success" or "failed"
+    pub status: String,
+    pub detail: Option<String>,
+    pub created_at: String,
+}
+
+pub async fn list_runs(conn: &Connection, rule_id: &str, limit: u32) -> Result<Vec<AutomationRun>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, rule_id, subject, status, detail, created_at FROM automation_runs \
+             WHERE rule_id = ? ORDER BY created_at DESC LIMIT ?",
+        )
+        .await?
+        .query(params![rule_id, limit as i64])
+        .await?;
+    let mut runs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        runs.push(AutomationRun {
+            id: row.get(0)?,
+            rule_id: row.get(1)?,
+            subject: row.get(2)?,
+            status: row.get(3)?,
+            detail: row.get(4)?,
+            created_at: row.get(5)?,
+        });
+    }
+    Ok(runs)
+}
+
+/// Note content from a template: its stored JSON with placeholders filled in, or the
+/// rendered text as paragraphs when the template isn't JSON
+fn note_content(event: &AutomationEvent, template: &str) -> Value {
+    serde_json::from_str(&event.render(template, true)).unwrap_or_else(|_| {
+        Value::Array(
+            event
+                .render(template, false)
+                .lines()
+                .map(|line| serde_json::json!({"type": "paragraph", "content": [{"type": "text", "text": line}]}))
+                .collect(),
+        )
+    })
+}
+
+/// Runs automation rules for events raised by trade, tag and insight writes
+pub struct AutomationService {
+    web_push: WebPushConfig,
+}
+
+impl AutomationService {
+    pub fn new(web_push: WebPushConfig) -> Self {
+        Self { web_push }
+    }
+
+    /// Run matching rules in the background
+    pub fn spawn(self: &Arc<Self>, conn: Connection, user_id: String, event: AutomationEvent) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = service.dispatch(&conn, &user_id, &event).await {
+                warn!("Automations for {} failed for user {}: {}", event.subject, user_id, e);
+            }
+        });
+    }
+
+    /// Dispatch `trade_closed` for a trade that may have just been closed
+    pub fn spawn_trade_closed(self: &Arc<Self>, conn: Connection, user_id: String, asset: TradeAsset, trade_id: i64) {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let result = match trade_closed_event(&conn, asset, trade_id).await {
+                Ok(Some(event)) => service.dispatch(&conn, &user_id, &event).await.map(|_| ()),
+                Ok(None) => Ok(()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                warn!("Trade-closed automations failed for user {}: {}", user_id, e);
+            }
+        });
+    }
+
+    /// Dispatch `tag_added` once per tag newly attached to a trade
+    pub fn spawn_tags_added(self: &Arc<Self>, conn: Connection, user_id: String, asset: TradeAsset, trade_id: i64, tag_ids: Vec<String>) {
+        if tag_ids.is_empty() {
+            return;
+        }
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            for tag_id in tag_ids {
+                let result = match tag_added_event(&conn, asset, trade_id, &tag_id).await {
+                    Ok(event) => service.dispatch(&conn, &user_id, &event).await.map(|_| ()),
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    warn!("Tag-added automations failed for user {}: {}", user_id, e);
+                }
+            }
+        });
+    }
+
+    /// Run every enabled rule matching the event; returns the runs recorded
+    pub async fn dispatch(&self, conn: &Connection, user_id: &str, event: &AutomationEvent) -> Result<Vec<AutomationRun>> {
+        let mut runs = Vec::new();
+        for rule in list_rules(conn, Some(event.trigger)).await? {
+            if !event.matches(&rule) {
+                continue;
+            }
+            let run_id = uuid::Uuid::new_v4().to_string();
+            let created_at = Utc::now().to_rfc3339();
+            let claimed = conn
+                .execute(
+                    "INSERT INTO automation_runs (id, rule_id, subject, status, created_at) VALUES (?, ?, ?, 'running', ?) \
+                     ON CONFLICT(rule_id, subject) DO NOTHING",
+                    params![run_id.clone(), rule.id.clone(), event.subject.clone(), created_at.clone()],
+                )
+                .await?;
+            if claimed == 0 {
+                continue;
+            }
+
+            let (status, detail) = match self.execute(conn, user_id, &rule, event).await {
+                Ok(done) => ("success", done.join("; ")),
+                Err(e) => ("failed", e.to_string()),
+            };
+            info!("Automation '{}' ran for user {} on {}: {}", rule.name, user_id, event.subject, status);
+            conn.execute(
+                "UPDATE automation_runs SET status = ?, detail = ? WHERE id = ?",
+                params![status, detail.clone(), run_id.clone()],
+            )
+            .await?;
+            conn.execute(
+                "UPDATE automation_rules SET trigger_count = trigger_count + 1, last_triggered_at = ? WHERE id = ?",
+                params![created_at.clone(), rule.id.clone()],
+            )
+            .await?;
+            runs.push(AutomationRun {
+                id: run_id,
+                rule_id: rule.id,
+                subject: event.subject.clone(),
+                status: status.to_string(),
+                detail: Some(detail),
+                created_at,
+            });
+        }
+        Ok(runs)
+    }
+
+    /// Run a rule's actions in order, stopping at the first failure
+    async fn execute(&self, conn: &Connection, user_id: &str, rule: &AutomationRule, event: &AutomationEvent) -> Result<Vec<String>> {
+        let mut done = Vec::new();
+        let mut note_id: Option<String> = None;
+        for action in &rule.actions {
+            match action {
+                AutomationAction::CreateNote { title, template_id } => {
+                    let content = match template_id {
+                        Some(id) => Some(note_content(event, &NotebookTemplate::find_by_id(conn, id).await?.content)),
+                        None => None,
+                    };
+                    let request = CreateNoteRequest { parent_id: None, title: event.render(title, false), content, position: None };
+                    let note = NotebookNote::create(conn, request).await?;
+                    done.push(format!("created note {}", note.id));
+                    note_id = Some(note.id);
+                }
+                AutomationAction::CreateReminder { title, description, delay_minutes } => {
+                    let title = event.render(title, false);
+                    let note_id = match &note_id {
+                        Some(id) => id.clone(),
+                        None => {
+                            let request = CreateNoteRequest { parent_id: None, title: title.clone(), content: None, position: None };
+                            NotebookNote::create(conn, request).await?.id
+                        }
+                    };
+                    let reminder = NotebookReminder::create(
+                        conn,
+                        CreateReminderRequest {
+                            note_id,
+                            title,
+                            description: description.as_deref().map(|d| event.render(d, false)),
+                            reminder_time: (Utc::now() + Duration::minutes(*delay_minutes)).to_rfc3339(),
+                            recurrence: None,
+                        },
+                    )
+                    .await?;
+                    done.push(format!("created reminder {}", reminder.id));
+                }
+                AutomationAction::SendNotification { title, body } => {
+                    let payload = PushPayload {
+                        title: event.render(title, false),
+                        body: body.as_deref().map(|b| event.render(b, false)),
+                        icon: None,
+                        url: None,
+                        tag: Some(format!("automation-{}", rule.id)),
+                        data: Some(serde_json::json!({"type": "automation", "rule_id": rule.id, "subject": event.subject})),
+                    };
+                    PushService::new(conn, &self.web_push).send_to_user(user_id, &payload).await?;
+                    done.push("sent notification".to_string());
+                }
+                AutomationAction::AddTag { tag_id } => {
+                    let Some((asset, trade_id)) = event.trade else {
+                        anyhow::bail!("add_tag needs a trade event");
+                    };
+                    match asset {
+                        TradeAsset::Stock => TradeTagAssociation::add_tag_to_stock_trade(conn, trade_id, tag_id).await?,
+                        TradeAsset::Option => TradeTagAssociation::add_tag_to_option_trade(conn, trade_id, tag_id).await?,
+                    };
+                    done.push(format!("tagged {} with {}", event.subject, tag_id));
+                }
+            }
+        }
+        Ok(done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn closed_trade(pnl: f64) -> AutomationEvent {
+        let mut fields = Map::new();
+        fields.insert("symbol".to_string(), Value::from("TSLA"));
+        fields.insert("pnl".to_string(), Value::from(pnl));
+        AutomationEvent { trigger: TriggerType::TradeClosed, subject: "stock:1".to_string(), fields, trade: Some((TradeAsset::Stock, 1)) }
+    }
+
+    #[test]
+    fn test_loss_over_threshold_matches_rule() {
+        let request: AutomationRuleRequest = serde_json::from_value(serde_json::json!({
+            "name": "Big loss review",
+            "trigger": "trade_closed",
+            "conditions": [{"field": "pnl", "op": "lt", "value": -200}, {"field": "symbol", "op": "eq", "value": "tsla"}],
+            "actions": [{"type": "create_note", "title": "Review {{symbol}}"}, {"type": "create_reminder", "title": "Review", "delay_minutes": 60}]
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+        let rule = AutomationRule {
+            id: "r1".to_string(),
+            name: request.name,
+            trigger: request.trigger,
+            conditions: request.conditions,
+            actions: request.actions,
+            is_enabled: true,
+            trigger_count: 0,
+            last_triggered_at: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        assert!(closed_trade(-350.0).matches(&rule));
+        assert!(!closed_trade(-150.0).matches(&rule));
+        assert_eq!(closed_trade(-350.0).render("Review {{symbol}} ({{pnl}})", false), "Review TSLA (-350.00)");
+    }
+
+    #[test]
+    fn test_validation_and_template_rendering() {
+        let insight_tagging: AutomationRuleRequest = serde_json::from_value(serde_json::json!({
+            "name": "Tag", "trigger": "insight_generated", "actions": [{"type": "add_tag", "tag_id": "t1"}]
+        }))
+        .unwrap();
+        assert!(insight_tagging.validate().is_err());
+        let unknown_field: AutomationRuleRequest = serde_json::from_value(serde_json::json!({
+            "name": "x", "trigger": "tag_added", "conditions": [{"field": "pnl", "op": "lt", "value": 0}],
+            "actions": [{"type": "send_notification", "title": "Tagged"}]
+        }))
+        .unwrap();
+        assert!(unknown_field.validate().is_err());
+
+        let mut event = closed_trade(10.0);
+        event.fields.insert("symbol".to_string(), Value::from("A\"B"));
+        let content = note_content(&event, r#"[{"type":"paragraph","content":[{"type":"text","text":"{{symbol}}"}]}]"#);
+        assert_eq!(content[0]["content"][0]["text"], "A\"B");
+        assert_eq!(note_content(&event, "Why {{symbol}}?\nPlan")[1]["content"][0]["text"], "Plan");
+    }
+}
@@ -0,0 +1,354 @@
+// Trade entry from forwarded broker fill confirmations, for brokers without API access.
+// Each user gets a forwarding address `trades+{token}@{domain}`; the token maps back to
+// the user in the registry database because inbound mail carries no user context. Fills
+// are read line by line with a template per known broker and stored as drafts that the
+// user confirms (saving through the stocks/options APIs) or discards.
+
+use anyhow::Result;
+use chrono::{Datelike, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::service::ai_service::trade_parser_service::TradeDraft;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Local part of every forwarding address; the token follows the `+`
+const ADDRESS_PREFIX: &str = "trades";
+
+/// Mailgun posts older than this are rejected as replays
+const MAX_SIGNATURE_AGE_SECONDS: i64 = 15 * 60;
+
+/// How a broker phrases a fill in its confirmation email
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FillPhrase {
+    /// "Your order to buy 10 shares of AAPL was executed at an average price of $150.25"
+    OrderTo,
+    /// "Bought 100 AAPL @ 150.25"
+    ActionAt,
+}
+
+struct BrokerTemplate {
+    name: &'static str,
+    /// Sender domains; forwarded mail keeps them in the quoted `From:` header
+    domains: &'static [&'static str],
+    phrase: FillPhrase,
+}
+
+const BROKER_TEMPLATES: [BrokerTemplate; 4] = [
+    BrokerTemplate { name: "Robinhood", domains: &["robinhood.com"], phrase: FillPhrase::OrderTo },
+    BrokerTemplate { name: "Fidelity", domains: &["fidelity.com"], phrase: FillPhrase::OrderTo },
+    BrokerTemplate { name: "Charles Schwab", domains: &["schwab.com"], phrase: FillPhrase::ActionAt },
+    BrokerTemplate { name: "Interactive Brokers", domains: &["interactivebrokers.com", "ibkr.com"], phrase: FillPhrase::ActionAt },
+];
+
+/// Fills read from one confirmation email
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedConfirmation {
+    pub broker: String,
+    pub drafts: Vec<TradeDraft>,
+}
+
+/// A stored draft awaiting review
+#[derive(Debug, Clone, Serialize)]
+pub struct StoredTradeDraft {
+    pub id: String,
+    pub source: String,
+    pub broker: Option<String>,
+    pub draft: TradeDraft,
+    /// pending, confirmed or discarded
+    pub status: String,
+    pub trade_id: Option<i64>,
+    pub email_subject: Option<String>,
+    pub received_at: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ResolveDraftRequest {
+    /// "confirmed" or "discarded"
+    pub status: String,
+    /// Stock or option id the draft was saved as, when confirmed
+    pub trade_id: Option<i64>,
+}
+
+/// Verify Mailgun's webhook signature: HMAC-SHA256 of timestamp + token with the signing key
+pub fn verify_mailgun_signature(signing_key: &str, timestamp: &str, token: &str, signature: &str) -> bool {
+    let Ok(sent_at) = timestamp.parse::<i64>() else { return false };
+    if (Utc::now().timestamp() - sent_at).abs() > MAX_SIGNATURE_AGE_SECONDS {
+        return false;
+    }
+    let Ok(mut mac) = HmacSha256::new_from_slice(signing_key.as_bytes()) else { return false };
+    mac.update(timestamp.as_bytes());
+    mac.update(token.as_bytes());
+    let Ok(signature) = hex::decode(signature) else { return false };
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// Forwarding address token from a recipient like `trades+abc123@inbox.tradstry.com`
+pub fn address_token(recipient: &str) -> Option<&str> {
+    let local = recipient.trim().split('@').next()?;
+    let token = local.strip_prefix(ADDRESS_PREFIX)?.strip_prefix('+')?;
+    (!token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric())).then_some(token)
+}
+
+/// The user's forwarding address, created on first use
+pub async fn forwarding_address(registry: &Connection, user_id: &str, domain: &str) -> Result<String> {
+    let mut rows = registry
+        .prepare("SELECT token FROM inbound_email_addresses WHERE user_id = ?")
+        .await?
+        .query(params![user_id])
+        .await?;
+    let token = match rows.next().await? {
+        Some(row) => row.get::<String>(0)?,
+        None => return rotate_forwarding_address(registry, user_id, domain).await,
+    };
+    Ok(format!("{}+{}@{}", ADDRESS_PREFIX, token, domain))
+}
+
+/// Replace the user's forwarding address, e.g. after it leaked; mail to the old one is dropped
+pub async fn rotate_forwarding_address(registry: &Connection, user_id: &str, domain: &str) -> Result<String> {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    registry
+        .execute(
+            "INSERT INTO inbound_email_addresses (token, user_id, created_at) VALUES (?, ?, ?)
+             ON CONFLICT(user_id) DO UPDATE SET token = excluded.token, created_at = excluded.created_at",
+            params![token.clone(), user_id, Utc::now().to_rfc3339()],
+        )
+        .await?;
+    Ok(format!("{}+{}@{}", ADDRESS_PREFIX, token, domain))
+}
+
+pub async fn find_user_by_token(registry: &Connection, token: &str) -> Result<Option<String>> {
+    let mut rows = registry
+        .prepare("SELECT user_id FROM inbound_email_addresses WHERE token = ?")
+        .await?
+        .query(params![token])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+/// Read the fills of a confirmation email. Returns None when no known broker sent it.
+pub fn parse_confirmation(sender: &str, body: &str, received: NaiveDate) -> Option<ParsedConfirmation> {
+    let haystack = format!("{}\n{}", sender, body).to_lowercase();
+    let template = BROKER_TEMPLATES
+        .iter()
+        .find(|t| t.domains.iter().any(|domain| haystack.contains(&format!("@{}", domain))))?;
+
+    let mut drafts = Vec::new();
+    for line in body.lines() {
+        // Quoted forwards prefix every line with `>`
+        let line = line.trim_start_matches(|c: char| c == '>' || c.is_whitespace());
+        if let Some(draft) = parse_fill_line(template.phrase, line, received) {
+            drafts.push(draft);
+        }
+    }
+    Some(ParsedConfirmation { broker: template.name.to_string(), drafts })
+}
+
+fn parse_fill_line(phrase: FillPhrase, line: &str, received: NaiveDate) -> Option<TradeDraft> {
+    let tokens: Vec<&str> = line
+        .split_whitespace()
+        .map(|t| t.trim_end_matches([',', '.', ';', ':', '!']))
+        .collect();
+    let lower: Vec<String> = tokens.iter().map(|t| t.to_lowercase()).collect();
+
+    // (side, quantity, symbol index, index of the word before the price)
+    let (side, quantity, symbol_at, price_marker) = match phrase {
+        FillPhrase::OrderTo => {
+            let i = (1..lower.len()).find(|&i| lower[i - 1] == "to" && (lower[i] == "buy" || lower[i] == "sell"))?;
+            if !matches!(lower.get(i + 2)?.as_str(), "share" | "shares" | "contract" | "contracts") || lower.get(i + 3)? != "of" {
+                return None;
+            }
+            let at = (i + 5..lower.len()).find(|&j| lower[j] == "at")?;
+            (lower[i].clone(), number(tokens[i + 1])?, i + 4, at)
+        }
+        FillPhrase::ActionAt => {
+            let i = lower.iter().position(|t| t == "bought" || t == "sold")?;
+            let side = if lower[i] == "bought" { "buy" } else { "sell" };
+            let at = (i + 3..lower.len()).find(|&j| lower[j] == "@")?;
+            (side.to_string(), number(tokens.get(i + 1)?)?, i + 2, at)
+        }
+    };
+
+    let symbol = tokens.get(symbol_at)?.to_uppercase();
+    if symbol.is_empty() || symbol.len() > 10 || !symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') {
+        return None;
+    }
+    // "at an average price of $150.25" and "@ 150.25" both end at the first number
+    let price = tokens[price_marker + 1..].iter().find_map(|t| number(t))?;
+    if quantity <= 0.0 || price <= 0.0 {
+        return None;
+    }
+
+    let contract = &lower[symbol_at + 1..price_marker];
+    let option_type = contract.iter().find_map(|t| match t.as_str() {
+        "call" | "calls" => Some("Call".to_string()),
+        "put" | "puts" => Some("Put".to_string()),
+        _ => None,
+    });
+    let is_option = option_type.is_some();
+    let strike_price = contract.iter().filter(|t| !t.contains('/')).find_map(|t| number(t)).filter(|_| is_option);
+    let expiration_date = contract.iter().find_map(|t| expiration(t, received)).filter(|_| is_option);
+
+    let mut missing_fields = Vec::new();
+    let mut warnings = Vec::new();
+    if is_option {
+        if strike_price.is_none() {
+            missing_fields.push("strikePrice".to_string());
+        }
+        if expiration_date.is_none() {
+            missing_fields.push("expirationDate".to_string());
+        }
+    } else {
+        // Stock trades require a stop loss when saved
+        missing_fields.push("stopLoss".to_string());
+    }
+    if side == "sell" {
+        warnings.push("Sell fills may close an existing position, check before saving as a new short".to_string());
+    }
+
+    Some(TradeDraft {
+        asset_type: if is_option { "option" } else { "stock" }.to_string(),
+        symbol,
+        side,
+        quantity,
+        entry_price: Some(price),
+        exit_price: None,
+        entry_date: Some(received.to_string()),
+        exit_date: None,
+        stop_loss: None,
+        take_profit: None,
+        commissions: 0.0,
+        option_type,
+        strike_price,
+        expiration_date: expiration_date.map(|d| d.to_string()),
+        confidence: 1.0,
+        missing_fields,
+        warnings,
+    })
+}
+
+/// "$1,250.50" or "100"
+fn number(token: &str) -> Option<f64> {
+    token.trim_start_matches('$').replace(',', "").parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// "3/21/2025", "3/21/25" or "3/21" (next such date on or after the email)
+fn expiration(token: &str, received: NaiveDate) -> Option<NaiveDate> {
+    ["%m/%d/%Y", "%m/%d/%y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(token, format).ok())
+        .or_else(|| {
+            let (month, day) = token.split_once('/')?;
+            let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+            NaiveDate::from_ymd_opt(received.year(), month, day)
+                .filter(|d| *d >= received)
+                .or_else(|| NaiveDate::from_ymd_opt(received.year() + 1, month, day))
+        })
+}
+
+/// Store the drafts of one email as pending
+pub async fn save_drafts(
+    conn: &Connection,
+    confirmation: &ParsedConfirmation,
+    email_subject: Option<&str>,
+) -> Result<Vec<String>> {
+    let received_at = Utc::now().to_rfc3339();
+    let mut ids = Vec::new();
+    for draft in &confirmation.drafts {
+        let id = uuid::Uuid::new_v4().to_string();
+        conn.execute(
+            "INSERT INTO trade_drafts (id, source, broker, draft, status, email_subject, received_at) VALUES (?, 'email', ?, ?, 'pending', ?, ?)",
+            params![
+                id.clone(),
+                confirmation.broker.clone(),
+                serde_json::to_string(draft)?,
+                email_subject.map(str::to_string),
+                received_at.clone()
+            ],
+        )
+        .await?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+/// Drafts newest first, optionally only those with `status`
+pub async fn list_drafts(conn: &Connection, status: Option<&str>) -> Result<Vec<StoredTradeDraft>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, source, broker, draft, status, trade_id, email_subject, received_at, created_at \
+             FROM trade_drafts WHERE (?1 IS NULL OR status = ?1) ORDER BY received_at DESC LIMIT 200",
+        )
+        .await?
+        .query(params![status.map(str::to_string)])
+        .await?;
+    let mut drafts = Vec::new();
+    while let Some(row) = rows.next().await? {
+        drafts.push(StoredTradeDraft {
+            id: row.get(0)?,
+            source: row.get(1)?,
+            broker: row.get(2)?,
+            draft: serde_json::from_str(&row.get::<String>(3)?)?,
+            status: row.get(4)?,
+            trade_id: row.get(5)?,
+            email_subject: row.get(6)?,
+            received_at: row.get(7)?,
+            created_at: row.get(8)?,
+        });
+    }
+    Ok(drafts)
+}
+
+/// Confirm or discard a pending draft. Returns false when no pending draft has that id.
+pub async fn resolve_draft(conn: &Connection, id: &str, request: &ResolveDraftRequest) -> Result<bool> {
+    let updated = conn
+        .execute(
+            "UPDATE trade_drafts SET status = ?, trade_id = ? WHERE id = ? AND status = 'pending'",
+            params![request.status.clone(), request.trade_id.filter(|_| request.status == "confirmed"), id],
+        )
+        .await?;
+    Ok(updated > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap()
+    }
+
+    #[test]
+    fn test_parse_confirmation_per_broker_template() {
+        let robinhood = "---------- Forwarded message ---------\nFrom: Robinhood <notifications@robinhood.com>\n\n\
+            > Your market order to buy 10 shares of AAPL was executed at an average price of $1,150.25 on March 3, 2025.\n\
+            > Your limit order to sell 2 contracts of SPY $500 Call 3/21 was executed at an average price of $3.20.";
+        let parsed = parse_confirmation("me@example.com", robinhood, received()).unwrap();
+        assert_eq!(parsed.broker, "Robinhood");
+        assert_eq!(parsed.drafts.len(), 2);
+        let stock = &parsed.drafts[0];
+        assert_eq!((stock.asset_type.as_str(), stock.symbol.as_str(), stock.side.as_str()), ("stock", "AAPL", "buy"));
+        assert_eq!((stock.quantity, stock.entry_price), (10.0, Some(1150.25)));
+        assert_eq!(stock.missing_fields, vec!["stopLoss"]);
+        let option = &parsed.drafts[1];
+        assert_eq!(option.option_type.as_deref(), Some("Call"));
+        assert_eq!((option.strike_price, option.expiration_date.as_deref()), (Some(500.0), Some("2025-03-21")));
+        assert!(option.missing_fields.is_empty());
+        assert_eq!(option.warnings.len(), 1);
+
+        let ibkr = parse_confirmation("alerts@interactivebrokers.com", "SOLD 100 TSLA @ 182.40 (U1234567)", received()).unwrap();
+        assert_eq!(ibkr.drafts[0].side, "sell");
+        assert_eq!(ibkr.drafts[0].entry_price, Some(182.4));
+
+        assert!(parse_confirmation("friend@example.com", "Bought 100 TSLA @ 182.40", received()).is_none());
+        assert_eq!(address_token("trades+ab12@inbox.tradstry.com"), Some("ab12"));
+        assert_eq!(address_token("other+ab12@inbox.tradstry.com"), None);
+    }
+}
@@ -0,0 +1,267 @@
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::money::{self, Decimal};
+use crate::models::stock::stocks::{CreateStockRequest, OrderType, Stock, TradeType};
+
+/// Share counts closer than this are treated as equal (brokers report fractional shares)
+const QUANTITY_TOLERANCE: f64 = 1e-6;
+
+/// A broker-reported position, summed across the user's accounts
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BrokerHolding {
+    pub symbol: String,
+    /// Signed share count; negative for short positions
+    pub quantity: f64,
+    pub average_cost: Option<f64>,
+    pub current_price: Option<f64>,
+    pub brokerage_name: Option<String>,
+}
+
+/// Open stock trades in the journal for one symbol
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JournalPosition {
+    pub symbol: String,
+    /// Signed share count; SELL (short) trades count negative
+    pub quantity: f64,
+    pub trade_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconciliationStatus {
+    Matched,
+    /// Held at the broker with no open journal trade
+    BrokerOnly,
+    /// Open in the journal but not held at the broker
+    JournalOnly,
+    /// Both sides have the symbol with different share counts
+    QuantityMismatch,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconciliationItem {
+    pub symbol: String,
+    pub status: ReconciliationStatus,
+    pub broker_quantity: f64,
+    pub journal_quantity: f64,
+    pub average_cost: Option<f64>,
+    pub brokerage_name: Option<String>,
+    pub journal_trade_ids: Vec<i64>,
+}
+
+/// Stock holdings from every synced brokerage account, one entry per symbol
+pub async fn load_holdings(conn: &Connection, user_id: &str) -> Result<Vec<BrokerHolding>> {
+    let mut rows = conn
+        .prepare(
+            r#"
+            SELECT h.symbol, h.quantity, h.average_cost, h.current_price, c.brokerage_name
+            FROM brokerage_holdings h
+            JOIN brokerage_accounts a ON a.id = h.account_id
+            JOIN brokerage_connections c ON c.id = a.connection_id
+            WHERE c.user_id = ?
+            "#,
+        )
+        .await?
+        .query(params![user_id])
+        .await?;
+
+    let mut by_symbol: BTreeMap<String, BrokerHolding> = BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        let symbol = normalize_symbol(&row.get::<String>(0)?);
+        let quantity: f64 = row.get(1)?;
+        let average_cost: Option<f64> = row.get(2)?;
+        let current_price: Option<f64> = row.get(3)?;
+        let brokerage_name: Option<String> = row.get(4)?;
+
+        let entry = by_symbol.entry(symbol.clone()).or_insert_with(|| BrokerHolding {
+            symbol,
+            quantity: 0.0,
+            average_cost: None,
+            current_price,
+            brokerage_name: brokerage_name.clone(),
+        });
+        // Share-weighted average cost across accounts
+        entry.average_cost = match (entry.average_cost, average_cost) {
+            (Some(existing), Some(cost)) => {
+                let total = entry.quantity.abs() + quantity.abs();
+                if total > QUANTITY_TOLERANCE {
+                    Some((existing * entry.quantity.abs() + cost * quantity.abs()) / total)
+                } else {
+                    Some(cost)
+                }
+            }
+            (existing, cost) => cost.or(existing),
+        };
+        entry.quantity += quantity;
+        entry.current_price = entry.current_price.or(current_price);
+        if entry.brokerage_name.is_none() {
+            entry.brokerage_name = brokerage_name;
+        }
+    }
+
+    Ok(by_symbol.into_values().filter(|h| h.quantity.abs() > QUANTITY_TOLERANCE).collect())
+}
+
+/// Open (no exit date) stock trades grouped by symbol
+pub async fn load_journal_positions(conn: &Connection) -> Result<Vec<JournalPosition>> {
+    let mut rows = conn
+        .prepare("SELECT id, symbol, trade_type, number_shares FROM stocks WHERE exit_date IS NULL AND is_deleted = 0")
+        .await?
+        .query(params![])
+        .await?;
+
+    let mut by_symbol: BTreeMap<String, JournalPosition> = BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        let id: i64 = row.get(0)?;
+        let symbol = normalize_symbol(&row.get::<String>(1)?);
+        let trade_type: String = row.get(2)?;
+        let shares: f64 = row.get(3)?;
+        let signed = if trade_type.eq_ignore_ascii_case("SELL") { -shares } else { shares };
+
+        let entry = by_symbol.entry(symbol.clone()).or_insert_with(|| JournalPosition {
+            symbol,
+            quantity: 0.0,
+            trade_ids: Vec::new(),
+        });
+        entry.quantity += signed;
+        entry.trade_ids.push(id);
+    }
+
+    Ok(by_symbol.into_values().collect())
+}
+
+/// Compare broker holdings with journal positions symbol by symbol
+pub fn reconcile(holdings: &[BrokerHolding], positions: &[JournalPosition]) -> Vec<ReconciliationItem> {
+    let mut items: BTreeMap<&str, ReconciliationItem> = BTreeMap::new();
+
+    for holding in holdings {
+        items.insert(&holding.symbol, ReconciliationItem {
+            symbol: holding.symbol.clone(),
+            status: ReconciliationStatus::BrokerOnly,
+            broker_quantity: holding.quantity,
+            journal_quantity: 0.0,
+            average_cost: holding.average_cost,
+            brokerage_name: holding.brokerage_name.clone(),
+            journal_trade_ids: Vec::new(),
+        });
+    }
+
+    for position in positions {
+        let item = items.entry(&position.symbol).or_insert_with(|| ReconciliationItem {
+            symbol: position.symbol.clone(),
+            status: ReconciliationStatus::JournalOnly,
+            broker_quantity: 0.0,
+            journal_quantity: 0.0,
+            average_cost: None,
+            brokerage_name: None,
+            journal_trade_ids: Vec::new(),
+        });
+        item.journal_quantity = position.quantity;
+        item.journal_trade_ids = position.trade_ids.clone();
+        if item.status == ReconciliationStatus::BrokerOnly {
+            item.status = if (item.broker_quantity - item.journal_quantity).abs() <= QUANTITY_TOLERANCE {
+                ReconciliationStatus::Matched
+            } else {
+                ReconciliationStatus::QuantityMismatch
+            };
+        }
+    }
+
+    items.into_values().collect()
+}
+
+/// Reconcile the user's synced holdings against their open journal trades
+pub async fn reconcile_positions(conn: &Connection, user_id: &str) -> Result<Vec<ReconciliationItem>> {
+    let holdings = load_holdings(conn, user_id).await?;
+    let positions = load_journal_positions(conn).await?;
+    Ok(reconcile(&holdings, &positions))
+}
+
+/// Open a journal trade for each broker-only holding, at the broker's average cost.
+/// `symbols` limits creation to those symbols; None creates all of them.
+pub async fn create_missing_entries(conn: &Connection, user_id: &str, symbols: Option<&[String]>) -> Result<Vec<Stock>> {
+    let wanted: Option<Vec<String>> = symbols.map(|s| s.iter().map(|sym| normalize_symbol(sym)).collect());
+    let mut created = Vec::new();
+
+    for item in reconcile_positions(conn, user_id).await? {
+        if item.status != ReconciliationStatus::BrokerOnly {
+            continue;
+        }
+        if wanted.as_ref().is_some_and(|w| !w.contains(&item.symbol)) {
+            continue;
+        }
+        let Some(entry_price) = item.average_cost.filter(|c| *c > 0.0) else {
+            log::warn!("Skipping {}: broker reported no average cost", item.symbol);
+            continue;
+        };
+        let entry_price = money::from_f64(entry_price);
+
+        let request = CreateStockRequest {
+            symbol: item.symbol.clone(),
+            trade_type: if item.broker_quantity < 0.0 { TradeType::SELL } else { TradeType::BUY },
+            order_type: OrderType::MARKET,
+            entry_price,
+            stop_loss: entry_price * Decimal::new(95, 2), // Default to 5% below entry price (user will review)
+            commissions: Decimal::ZERO,
+            number_shares: item.broker_quantity.abs(),
+            take_profit: None,
+            initial_target: None,
+            profit_target: None,
+            trade_ratings: None,
+            entry_date: Utc::now(),
+            reviewed: Some(false),
+            mistakes: None,
+            brokerage_name: item.brokerage_name.clone(),
+            venue: None,
+        };
+        let stock = Stock::create(conn, request)
+            .await
+            .map_err(|e| anyhow!("Failed to create journal entry for {}: {}", item.symbol, e))?;
+        created.push(stock);
+    }
+
+    Ok(created)
+}
+
+fn normalize_symbol(symbol: &str) -> String {
+    symbol.trim().to_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn holding(symbol: &str, quantity: f64) -> BrokerHolding {
+        BrokerHolding { symbol: symbol.to_string(), quantity, average_cost: Some(10.0), current_price: None, brokerage_name: None }
+    }
+
+    fn position(symbol: &str, quantity: f64) -> JournalPosition {
+        JournalPosition { symbol: symbol.to_string(), quantity, trade_ids: vec![1] }
+    }
+
+    #[test]
+    fn test_reconcile_flags_each_side() {
+        let items = reconcile(
+            &[holding("AAPL", 100.0), holding("MSFT", 10.0), holding("TSLA", 5.0)],
+            &[position("AAPL", 100.0), position("MSFT", 15.0), position("NVDA", 20.0)],
+        );
+        let status = |symbol: &str| items.iter().find(|i| i.symbol == symbol).unwrap().status;
+        assert_eq!(status("AAPL"), ReconciliationStatus::Matched);
+        assert_eq!(status("MSFT"), ReconciliationStatus::QuantityMismatch);
+        assert_eq!(status("TSLA"), ReconciliationStatus::BrokerOnly);
+        assert_eq!(status("NVDA"), ReconciliationStatus::JournalOnly);
+        assert_eq!(items.len(), 4);
+    }
+
+    #[test]
+    fn test_short_positions_match_by_sign() {
+        let items = reconcile(&[holding("SPY", -50.0)], &[position("SPY", 50.0)]);
+        assert_eq!(items[0].status, ReconciliationStatus::QuantityMismatch);
+        let items = reconcile(&[holding("SPY", -50.0)], &[position("SPY", -50.0)]);
+        assert_eq!(items[0].status, ReconciliationStatus::Matched);
+    }
+}
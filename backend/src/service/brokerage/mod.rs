@@ -0,0 +1,6 @@
+pub mod client;
+pub mod webhooks;
+pub mod holdings;
+pub mod sync;
+pub mod transform;
+pub mod email_confirmations;
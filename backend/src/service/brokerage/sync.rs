@@ -0,0 +1,589 @@
+// Brokerage sync: fetches accounts, holdings and transactions for one connection from the
+// SnapTrade service and stores them. Manual syncs and the scheduler share this path; each run
+// is recorded in `brokerage_sync_runs` and advances the connection's cursor so later runs only
+// ask for recent transactions.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use libsql::{params, Connection};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::service::brokerage::client::SnapTradeClient;
+
+/// Transactions can post a few days after they execute, so incremental syncs re-read this
+/// many days before the cursor; rows already imported are skipped by id
+const CURSOR_OVERLAP_DAYS: i64 = 3;
+/// First retry after a failed sync; doubles with each consecutive failure
+const RETRY_BASE_MINUTES: i64 = 15;
+const MAX_BACKOFF_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize)]
+pub struct SyncAccountsResponse {
+    pub accounts: Vec<serde_json::Value>,
+    pub holdings: Vec<serde_json::Value>,
+    pub transactions: Vec<serde_json::Value>,
+}
+
+/// How often the scheduler syncs a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncFrequency {
+    Hourly,
+    Daily,
+    /// Only synced when the user asks
+    Manual,
+}
+
+impl SyncFrequency {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncFrequency::Hourly => "hourly",
+            SyncFrequency::Daily => "daily",
+            SyncFrequency::Manual => "manual",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "hourly" => SyncFrequency::Hourly,
+            "manual" => SyncFrequency::Manual,
+            _ => SyncFrequency::Daily,
+        }
+    }
+
+    fn interval(&self) -> Option<Duration> {
+        match self {
+            SyncFrequency::Hourly => Some(Duration::hours(1)),
+            SyncFrequency::Daily => Some(Duration::days(1)),
+            SyncFrequency::Manual => None,
+        }
+    }
+}
+
+/// When the scheduler should next sync a connection. After a success that's one interval
+/// away; after failures it retries sooner with exponential backoff, capped at a day.
+pub fn next_sync_after(frequency: SyncFrequency, consecutive_failures: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let interval = frequency.interval()?;
+    if consecutive_failures == 0 {
+        return Some(now + interval);
+    }
+    let backoff = RETRY_BASE_MINUTES.saturating_mul(1i64 << (consecutive_failures - 1).min(16));
+    Some(now + Duration::minutes(backoff).min(Duration::hours(MAX_BACKOFF_HOURS)))
+}
+
+/// `start_date` for an incremental sync, or None for a full sync when there's no cursor yet
+pub fn incremental_start_date(cursor: Option<&str>) -> Option<String> {
+    let date = NaiveDate::parse_from_str(cursor?.get(..10)?, "%Y-%m-%d").ok()?;
+    Some((date - Duration::days(CURSOR_OVERLAP_DAYS)).format("%Y-%m-%d").to_string())
+}
+
+/// Rows written by one sync
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncCounts {
+    pub accounts_synced: usize,
+    pub holdings_synced: usize,
+    pub transactions_imported: usize,
+    /// Newest transaction date (YYYY-MM-DD) seen in the response
+    #[serde(skip)]
+    pub latest_trade_date: Option<String>,
+}
+
+/// A connection the sync can run for
+#[derive(Debug, Clone)]
+pub struct SyncTarget {
+    pub id: String,
+    pub user_secret: String,
+    pub authorization_id: Option<String>,
+    pub sync_cursor: Option<String>,
+    pub sync_frequency: SyncFrequency,
+    pub consecutive_failures: u32,
+}
+
+/// One recorded sync run
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncRun {
+    pub id: String,
+    pub connection_id: String,
+    /// "manual" or "scheduled"
+    pub triggered_by: String,
+    /// "running", "success" or "failed"
+    pub status: String,
+    pub accounts_synced: i64,
+    pub holdings_synced: i64,
+    pub transactions_imported: i64,
+    pub cursor_before: Option<String>,
+    pub cursor_after: Option<String>,
+    pub error: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+const TARGET_COLUMNS: &str = "id, snaptrade_user_secret, authorization_id, sync_cursor, sync_frequency, consecutive_failures";
+
+fn target_from_row(row: &libsql::Row) -> Result<SyncTarget> {
+    Ok(SyncTarget {
+        id: row.get(0)?,
+        user_secret: row.get(1)?,
+        authorization_id: row.get(2).ok().flatten(),
+        sync_cursor: row.get(3).ok().flatten(),
+        sync_frequency: SyncFrequency::from_db(&row.get::<String>(4).unwrap_or_default()),
+        consecutive_failures: row.get::<i64>(5).unwrap_or(0).max(0) as u32,
+    })
+}
+
+pub struct BrokerageSyncService<'a> {
+    conn: &'a Connection,
+    client: &'a SnapTradeClient,
+}
+
+impl<'a> BrokerageSyncService<'a> {
+    pub fn new(conn: &'a Connection, client: &'a SnapTradeClient) -> Self {
+        Self { conn, client }
+    }
+
+    /// Every connected connection, for a manual "sync now"
+    pub async fn connected_targets(&self, user_id: &str) -> Result<Vec<SyncTarget>> {
+        self.targets(
+            &format!("SELECT {} FROM brokerage_connections WHERE user_id = ? AND status = 'connected'", TARGET_COLUMNS),
+            params![user_id],
+        ).await
+    }
+
+    /// Connections the scheduler should sync now: scheduled ones whose next run has come, plus
+    /// any SnapTrade has told us (via webhook) have new data. Broken connections wait for the
+    /// user to reconnect.
+    pub async fn due_targets(&self, user_id: &str, now: DateTime<Utc>) -> Result<Vec<SyncTarget>> {
+        self.targets(
+            &format!(
+                "SELECT {} FROM brokerage_connections WHERE user_id = ? AND status = 'connected' AND reauth_required = 0 \
+                 AND sync_frequency != 'manual' \
+                 AND (next_sync_at IS NULL OR next_sync_at <= ? OR sync_requested_at > COALESCE(last_sync_at, ''))",
+                TARGET_COLUMNS
+            ),
+            params![user_id, now.to_rfc3339()],
+        ).await
+    }
+
+    async fn targets(&self, sql: &str, values: impl libsql::params::IntoParams) -> Result<Vec<SyncTarget>> {
+        let mut rows = self.conn.prepare(sql).await?.query(values).await?;
+        let mut targets = Vec::new();
+        while let Some(row) = rows.next().await? {
+            targets.push(target_from_row(&row)?);
+        }
+        Ok(targets)
+    }
+
+    /// Sync one connection, record the run, and move its cursor and next run time
+    pub async fn sync(&self, user_id: &str, target: &SyncTarget, trigger: &str) -> Result<SyncCounts> {
+        let run_id = Uuid::new_v4().to_string();
+        let started_at = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT INTO brokerage_sync_runs (id, connection_id, triggered_by, status, cursor_before, started_at) VALUES (?, ?, ?, 'running', ?, ?)",
+            params![run_id.clone(), target.id.clone(), trigger, target.sync_cursor.clone(), started_at],
+        ).await?;
+
+        let result = match self.fetch(user_id, target).await {
+            Ok(sync_data) => Ok(store_sync_data(self.conn, &target.id, sync_data).await),
+            Err(e) => Err(e),
+        };
+
+        let now = Utc::now();
+        let finished_at = now.to_rfc3339();
+        match &result {
+            Ok(counts) => {
+                let cursor = match (target.sync_cursor.clone(), counts.latest_trade_date.clone()) {
+                    (Some(before), Some(latest)) => Some(before.max(latest)),
+                    (before, latest) => latest.or(before),
+                };
+                let next = next_sync_after(target.sync_frequency, 0, now).map(|t| t.to_rfc3339());
+                self.conn.execute(
+                    "UPDATE brokerage_sync_runs SET status = 'success', accounts_synced = ?, holdings_synced = ?, transactions_imported = ?, cursor_after = ?, finished_at = ? WHERE id = ?",
+                    params![counts.accounts_synced as i64, counts.holdings_synced as i64, counts.transactions_imported as i64, cursor.clone(), finished_at.clone(), run_id],
+                ).await?;
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET last_sync_at = ?, sync_cursor = ?, next_sync_at = ?, consecutive_failures = 0, last_sync_error = NULL, updated_at = ? WHERE id = ?",
+                    params![finished_at.clone(), cursor, next, finished_at, target.id.clone()],
+                ).await?;
+                info!(
+                    "Synced connection {} ({}): {} accounts, {} holdings, {} new transactions",
+                    target.id, trigger, counts.accounts_synced, counts.holdings_synced, counts.transactions_imported
+                );
+            }
+            Err(e) => {
+                let failures = target.consecutive_failures + 1;
+                let next = next_sync_after(target.sync_frequency, failures, now).map(|t| t.to_rfc3339());
+                self.conn.execute(
+                    "UPDATE brokerage_sync_runs SET status = 'failed', error = ?, cursor_after = cursor_before, finished_at = ? WHERE id = ?",
+                    params![e.to_string(), finished_at.clone(), run_id],
+                ).await?;
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET next_sync_at = ?, consecutive_failures = ?, last_sync_error = ?, updated_at = ? WHERE id = ?",
+                    params![next, failures as i64, e.to_string(), finished_at, target.id.clone()],
+                ).await?;
+                warn!("Sync failed for connection {} ({} consecutive failures): {}", target.id, failures, e);
+            }
+        }
+
+        result
+    }
+
+    async fn fetch(&self, user_id: &str, target: &SyncTarget) -> Result<SyncAccountsResponse> {
+        let mut sync_req = serde_json::json!({ "user_secret": target.user_secret });
+        if let Some(authorization_id) = &target.authorization_id {
+            sync_req["authorization_id"] = serde_json::json!(authorization_id);
+        }
+        if let Some(start_date) = incremental_start_date(target.sync_cursor.as_deref()) {
+            sync_req["start_date"] = serde_json::json!(start_date);
+        }
+
+        let response = self.client
+            .call_go_service("POST", "/api/v1/accounts/sync", Some(&sync_req), user_id, None)
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            anyhow::bail!("SnapTrade service returned {}: {}", status, error_text);
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// Set how often the scheduler syncs a connection. Returns false when it isn't the user's.
+pub async fn update_sync_frequency(conn: &Connection, user_id: &str, connection_id: &str, frequency: SyncFrequency) -> Result<bool> {
+    let now = Utc::now();
+    let next = next_sync_after(frequency, 0, now).map(|t| t.to_rfc3339());
+    let updated = conn.execute(
+        "UPDATE brokerage_connections SET sync_frequency = ?, next_sync_at = ?, updated_at = ? WHERE id = ? AND user_id = ?",
+        params![frequency.as_str(), next, now.to_rfc3339(), connection_id, user_id],
+    ).await?;
+    Ok(updated > 0)
+}
+
+/// Recent sync runs, newest first, optionally for one connection
+pub async fn list_sync_runs(conn: &Connection, connection_id: Option<&str>, limit: i64) -> Result<Vec<SyncRun>> {
+    let mut sql = "SELECT id, connection_id, triggered_by, status, accounts_synced, holdings_synced, transactions_imported, cursor_before, cursor_after, error, started_at, finished_at FROM brokerage_sync_runs".to_string();
+    let mut values: Vec<libsql::Value> = Vec::new();
+    if let Some(connection_id) = connection_id {
+        sql.push_str(" WHERE connection_id = ?");
+        values.push(libsql::Value::Text(connection_id.to_string()));
+    }
+    sql.push_str(" ORDER BY started_at DESC LIMIT ?");
+    values.push(libsql::Value::Integer(limit));
+
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(values)).await?;
+    let mut runs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        runs.push(SyncRun {
+            id: row.get(0)?,
+            connection_id: row.get(1)?,
+            triggered_by: row.get(2)?,
+            status: row.get(3)?,
+            accounts_synced: row.get(4).unwrap_or(0),
+            holdings_synced: row.get(5).unwrap_or(0),
+            transactions_imported: row.get(6).unwrap_or(0),
+            cursor_before: row.get(7).ok().flatten(),
+            cursor_after: row.get(8).ok().flatten(),
+            error: row.get(9).ok().flatten(),
+            started_at: row.get(10)?,
+            finished_at: row.get(11).ok().flatten(),
+        });
+    }
+    Ok(runs)
+}
+
+/// Store accounts, holdings and new transactions from a sync response under `connection_id`
+pub async fn store_sync_data(conn: &Connection, connection_id: &str, sync_data: SyncAccountsResponse) -> SyncCounts {
+    let mut total_accounts = 0;
+    let mut total_holdings = 0;
+    let mut total_transactions = 0;
+    let latest_trade_date = sync_data
+        .transactions
+        .iter()
+        .filter_map(|t| t.get("date").and_then(|d| d.as_str()).and_then(|d| d.get(..10)))
+        .max()
+        .map(str::to_string);
+
+    // Store synced data in database
+    // This is a simplified version - in production, you'd want more robust error handling
+    for account in sync_data.accounts {
+        if account.get("id").is_some() {
+            let snaptrade_account_id = account.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            let account_number = account.get("account_number").and_then(|v| v.as_str());
+            let account_name = account.get("name").and_then(|v| v.as_str());
+            let account_type = account.get("type").and_then(|v| v.as_str());
+            let balance = account.get("balance").and_then(|v| v.as_f64());
+            let currency = account.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
+            let institution_name = account.get("institution_name").and_then(|v| v.as_str());
+
+            // Check if account already exists to prevent duplicates
+            let connection_id_clone = connection_id.to_string();
+            let account_uuid = match get_existing_account_id(conn, &connection_id_clone, snaptrade_account_id).await {
+                Some(existing_id) => {
+                    info!("Account {} already exists, updating: {}", snaptrade_account_id, existing_id);
+                    existing_id
+                },
+                None => {
+                    let new_id = Uuid::new_v4().to_string();
+                    info!("Creating new account: {} with ID: {}", snaptrade_account_id, new_id);
+                    new_id
+                }
+            };
+
+            let now = Utc::now().to_rfc3339();
+            let raw_data = serde_json::to_string(&account).unwrap_or_default();
+
+            conn.execute(
+                "INSERT OR REPLACE INTO brokerage_accounts (id, connection_id, snaptrade_account_id, account_number, account_name, account_type, balance, currency, institution_name, raw_data, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, COALESCE((SELECT created_at FROM brokerage_accounts WHERE id = ?), ?), ?)",
+                libsql::params![
+                    account_uuid.clone(),
+                    connection_id_clone,
+                    snaptrade_account_id,
+                    account_number,
+                    account_name,
+                    account_type,
+                    balance,
+                    currency,
+                    institution_name,
+                    raw_data,
+                    account_uuid.clone(),
+                    now.clone(),
+                    now
+                ],
+            ).await.ok(); // Don't fail entire sync if one account fails
+
+            total_accounts += 1;
+
+            // Store holdings for this account
+            for holding in &sync_data.holdings {
+                if let Some(holding_account_id) = holding.get("account_id").and_then(|v| v.as_str())
+                    && holding_account_id == snaptrade_account_id {
+                        let symbol = holding.get("symbol").and_then(|v| v.as_str()).unwrap_or("");
+                        if symbol.is_empty() {
+                            continue; // Skip holdings without symbols
+                        }
+                        
+                        let quantity = holding.get("quantity").and_then(|v| v.as_f64()).unwrap_or(0.0);
+                        let average_cost = holding.get("average_cost").and_then(|v| v.as_f64());
+                        let current_price = holding.get("current_price").and_then(|v| v.as_f64());
+                        let market_value = holding.get("market_value").and_then(|v| v.as_f64());
+                        let currency = holding.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
+                        let raw_data = serde_json::to_string(holding).unwrap_or_default();
+
+                        // Check if holding already exists to prevent duplicates
+                        let holding_uuid = match get_existing_holding_id(conn, &account_uuid, symbol).await {
+                            Some(existing_id) => {
+                                info!("Holding {} for account {} already exists, updating: {}", symbol, account_uuid, existing_id);
+                                existing_id
+                            },
+                            None => {
+                                let new_id = Uuid::new_v4().to_string();
+                                info!("Creating new holding: {} for account: {}", symbol, account_uuid);
+                                new_id
+                            }
+                        };
+
+                        let now = Utc::now().to_rfc3339();
+
+                        conn.execute(
+                            "INSERT OR REPLACE INTO brokerage_holdings (id, account_id, symbol, quantity, average_cost, current_price, market_value, currency, last_updated, raw_data) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                            libsql::params![
+                                holding_uuid,
+                                account_uuid.clone(),
+                                symbol,
+                                quantity,
+                                average_cost,
+                                current_price,
+                                market_value,
+                                currency,
+                                now,
+                                raw_data
+                            ],
+                        ).await.ok();
+
+                        total_holdings += 1;
+                }
+            }
+
+            // Store transactions for this account
+            for transaction in &sync_data.transactions {
+                if let Some(trans_account_id) = transaction.get("account_id").and_then(|v| v.as_str())
+                    && trans_account_id == snaptrade_account_id {
+                        let snaptrade_transaction_id = transaction.get("id").and_then(|v| v.as_str()).unwrap_or("");
+                        if snaptrade_transaction_id.is_empty() {
+                            continue; // Skip transactions without IDs
+                        }
+
+                        // Check if transaction already exists to prevent duplicates
+                        if transaction_exists(conn, &account_uuid, snaptrade_transaction_id).await {
+                            info!("Transaction {} for account {} already exists, skipping", snaptrade_transaction_id, account_uuid);
+                            continue;
+                        }
+
+                        let symbol = transaction.get("symbol").and_then(|v| v.as_str());
+                        let transaction_type = transaction.get("type").and_then(|v| v.as_str());
+                        let quantity = transaction.get("quantity").and_then(|v| v.as_f64());
+                        let price = transaction.get("price").and_then(|v| v.as_f64());
+                        let amount = transaction.get("amount").and_then(|v| v.as_f64());
+                        let currency = transaction.get("currency").and_then(|v| v.as_str()).unwrap_or("USD");
+                        let default_trade_date = Utc::now().to_rfc3339();
+                        let trade_date = transaction.get("date").and_then(|v| v.as_str()).unwrap_or(&default_trade_date);
+                        let settlement_date = transaction.get("settlement_date").and_then(|v| v.as_str());
+                        let fees = transaction.get("fees").and_then(|v| v.as_f64());
+                        let raw_data = serde_json::to_string(transaction).unwrap_or_default();
+
+                        let transaction_uuid = Uuid::new_v4().to_string();
+                        let transaction_now = Utc::now().to_rfc3339();
+
+                        conn.execute(
+                            "INSERT INTO brokerage_transactions (id, account_id, snaptrade_transaction_id, symbol, transaction_type, quantity, price, amount, currency, trade_date, settlement_date, fees, raw_data, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                            libsql::params![
+                                transaction_uuid,
+                                account_uuid.clone(),
+                                snaptrade_transaction_id,
+                                symbol,
+                                transaction_type,
+                                quantity,
+                                price,
+                                amount,
+                                currency,
+                                trade_date,
+                                settlement_date,
+                                fees,
+                                raw_data,
+                                transaction_now.clone(),
+                                transaction_now
+                            ],
+                        ).await.ok();
+
+                        total_transactions += 1;
+                }
+            }
+        }
+    }
+
+
+    SyncCounts {
+        accounts_synced: total_accounts,
+        holdings_synced: total_holdings,
+        transactions_imported: total_transactions,
+        latest_trade_date,
+    }
+}
+
+/// Helper function to get existing account ID by snaptrade_account_id
+pub(crate) async fn get_existing_account_id(
+    conn: &Connection,
+    connection_id: &str,
+    snaptrade_account_id: &str,
+) -> Option<String> {
+    let stmt = match conn
+        .prepare("SELECT id FROM brokerage_accounts WHERE connection_id = ? AND snaptrade_account_id = ?")
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Failed to prepare account query: {}", e);
+            return None;
+        }
+    };
+
+    let mut rows = match stmt.query(libsql::params![connection_id, snaptrade_account_id]).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Failed to query account: {}", e);
+            return None;
+        }
+    };
+
+    if let Ok(Some(row)) = rows.next().await
+        && let Ok(id) = row.get::<String>(0) {
+            return Some(id);
+    }
+    None
+}
+
+/// Helper function to get existing holding ID by account_id and symbol
+pub(crate) async fn get_existing_holding_id(
+    conn: &Connection,
+    account_id: &str,
+    symbol: &str,
+) -> Option<String> {
+    let stmt = match conn
+        .prepare("SELECT id FROM brokerage_holdings WHERE account_id = ? AND symbol = ?")
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Failed to prepare holding query: {}", e);
+            return None;
+        }
+    };
+
+    let mut rows = match stmt.query(libsql::params![account_id, symbol]).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Failed to query holding: {}", e);
+            return None;
+        }
+    };
+
+    if let Ok(Some(row)) = rows.next().await
+        && let Ok(id) = row.get::<String>(0) {
+            return Some(id);
+    }
+    None
+}
+
+/// Helper function to check if transaction already exists
+pub(crate) async fn transaction_exists(
+    conn: &Connection,
+    account_id: &str,
+    snaptrade_transaction_id: &str,
+) -> bool {
+    let stmt = match conn
+        .prepare("SELECT 1 FROM brokerage_transactions WHERE account_id = ? AND snaptrade_transaction_id = ? LIMIT 1")
+        .await
+    {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Failed to prepare transaction check query: {}", e);
+            return false;
+        }
+    };
+
+    let mut rows = match stmt.query(libsql::params![account_id, snaptrade_transaction_id]).await {
+        Ok(r) => r,
+        Err(e) => {
+            error!("Failed to query transaction: {}", e);
+            return false;
+        }
+    };
+
+    matches!(rows.next().await, Ok(Some(_)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_sync_backs_off_after_failures() {
+        let now = Utc::now();
+        assert_eq!(next_sync_after(SyncFrequency::Hourly, 0, now), Some(now + Duration::hours(1)));
+        assert_eq!(next_sync_after(SyncFrequency::Daily, 1, now), Some(now + Duration::minutes(15)));
+        assert_eq!(next_sync_after(SyncFrequency::Daily, 3, now), Some(now + Duration::minutes(60)));
+        assert_eq!(next_sync_after(SyncFrequency::Hourly, 20, now), Some(now + Duration::hours(24)));
+        assert_eq!(next_sync_after(SyncFrequency::Manual, 0, now), None);
+    }
+
+    #[test]
+    fn test_incremental_start_date_overlaps_cursor() {
+        assert_eq!(incremental_start_date(Some("2024-03-10")), Some("2024-03-07".to_string()));
+        assert_eq!(incremental_start_date(Some("2024-03-01T15:30:00Z")), Some("2024-02-27".to_string()));
+        assert_eq!(incremental_start_date(None), None);
+        assert_eq!(incremental_start_date(Some("garbage")), None);
+    }
+}
@@ -1,3 +1,8 @@
+//! Turns synced brokerage transactions into journal trades. `normalize` maps raw SnapTrade
+//! activities onto a broker-neutral `CanonicalTransaction` first.
+
+pub mod normalize;
+
 use anyhow::{Result, Context};
 use libsql::Connection;
 use log::{info, error};
@@ -9,6 +14,7 @@ use crate::service::ai_service::{
     data_formatter::DataFormatter,
 };
 use crate::service::ai_service::upstash_vector_client::DataType as VectorDataType;
+use crate::models::{money, timestamps, venue};
 
 /// Transaction data structure for matching
 #[allow(dead_code)]
@@ -312,7 +318,7 @@ pub async fn create_open_stock_trade(
 }
 
 /// Helper function to vectorize a stock trade
-async fn vectorize_stock_trade(
+pub(crate) async fn vectorize_stock_trade(
     conn: &Connection,
     stock_id: i64,
     user_id: &str,
@@ -325,7 +331,7 @@ async fn vectorize_stock_trade(
             SELECT id, symbol, trade_type, order_type, entry_price,
                    exit_price, stop_loss, commissions, number_shares, take_profit,
                    initial_target, profit_target, trade_ratings,
-                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                   entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
             FROM stocks
             WHERE id = ?
             "#
@@ -361,17 +367,7 @@ async fn vectorize_stock_trade(
         let updated_at_str: String = row.get(19)?;
 
         fn parse_dt(s: &str) -> Result<chrono::DateTime<Utc>> {
-            if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
-                return Ok(dt.with_timezone(&Utc));
-            }
-            if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-                return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-            }
-            if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                let ndt = date.and_hms_opt(0, 0, 0).ok_or_else(|| anyhow::anyhow!("invalid date"))?;
-                return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-            }
-            Err(anyhow::anyhow!("Unsupported datetime format: {}", s))
+            timestamps::parse_stored(s).map_err(|e| anyhow::anyhow!(e))
         }
 
         let entry_date = parse_dt(&entry_date_str)?;
@@ -384,50 +380,21 @@ async fn vectorize_stock_trade(
             symbol: row.get(1)?,
             trade_type,
             order_type,
-            entry_price: match row.get::<libsql::Value>(4)? {
-                libsql::Value::Integer(val) => val as f64,
-                libsql::Value::Real(val) => val,
-                _ => return Err(anyhow::anyhow!("Invalid entry_price type")),
-            },
-            exit_price: match row.get::<libsql::Value>(5)? {
-                libsql::Value::Integer(val) => Some(val as f64),
-                libsql::Value::Real(val) => Some(val),
-                libsql::Value::Null => None,
-                _ => None,
-            },
-            stop_loss: match row.get::<libsql::Value>(6)? {
-                libsql::Value::Integer(val) => val as f64,
-                libsql::Value::Real(val) => val,
-                _ => return Err(anyhow::anyhow!("Invalid stop_loss type")),
-            },
-            commissions: match row.get::<libsql::Value>(7)? {
-                libsql::Value::Integer(val) => val as f64,
-                libsql::Value::Real(val) => val,
-                _ => return Err(anyhow::anyhow!("Invalid commissions type")),
-            },
+            entry_price: money::row_decimal_opt(&row, 4)
+                .ok_or_else(|| anyhow::anyhow!("Invalid entry_price type"))?,
+            exit_price: money::row_decimal_opt(&row, 5),
+            stop_loss: money::row_decimal_opt(&row, 6)
+                .ok_or_else(|| anyhow::anyhow!("Invalid stop_loss type"))?,
+            commissions: money::row_decimal_opt(&row, 7)
+                .ok_or_else(|| anyhow::anyhow!("Invalid commissions type"))?,
             number_shares: match row.get::<libsql::Value>(8)? {
                 libsql::Value::Integer(val) => val as f64,
                 libsql::Value::Real(val) => val,
                 _ => return Err(anyhow::anyhow!("Invalid number_shares type")),
             },
-            take_profit: match row.get::<libsql::Value>(9)? {
-                libsql::Value::Integer(val) => Some(val as f64),
-                libsql::Value::Real(val) => Some(val),
-                libsql::Value::Null => None,
-                _ => None,
-            },
-            initial_target: match row.get::<libsql::Value>(10)? {
-                libsql::Value::Integer(val) => Some(val as f64),
-                libsql::Value::Real(val) => Some(val),
-                libsql::Value::Null => None,
-                _ => None,
-            },
-            profit_target: match row.get::<libsql::Value>(11)? {
-                libsql::Value::Integer(val) => Some(val as f64),
-                libsql::Value::Real(val) => Some(val),
-                libsql::Value::Null => None,
-                _ => None,
-            },
+            take_profit: money::row_decimal_opt(&row, 9),
+            initial_target: money::row_decimal_opt(&row, 10),
+            profit_target: money::row_decimal_opt(&row, 11),
             trade_ratings: row.get::<Option<i32>>(12)?,
             entry_date,
             exit_date,
@@ -436,6 +403,8 @@ async fn vectorize_stock_trade(
             brokerage_name,
             created_at,
             updated_at,
+            realized_pnl: money::row_decimal_opt(&row, 20),
+            venue: row.get(21)?,
         };
 
         // Format stock for embedding
@@ -513,10 +482,14 @@ async fn transform_to_stock(
         .get("trade_date")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing trade_date in transaction"))?;
+    let trade_date = timestamps::parse_stored(trade_date)
+        .map(|dt| timestamps::to_db(&dt))
+        .map_err(|e| anyhow::anyhow!("Invalid trade_date in transaction: {}", e))?;
 
     let brokerage_name = transaction
         .get("institution")
         .and_then(|v| v.as_str());
+    let venue = venue::venue_from_brokerage_json(transaction);
 
     // Set default values for required fields that might be missing
     let order_type = "MARKET"; // Default to MARKET since we don't have this info
@@ -528,8 +501,8 @@ async fn transform_to_stock(
             r#"
             INSERT INTO stocks (
                 symbol, trade_type, order_type, entry_price, stop_loss, commissions,
-                number_shares, entry_date, brokerage_name, reviewed, is_deleted
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, false, 0)
+                number_shares, entry_date, brokerage_name, venue, reviewed, is_deleted
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, false, 0)
             RETURNING id
             "#,
         )
@@ -546,7 +519,8 @@ async fn transform_to_stock(
             fee,
             units,
             trade_date,
-            brokerage_name
+            brokerage_name,
+            venue
         ])
         .await
         .context("Failed to insert stock trade")?;
@@ -566,7 +540,7 @@ async fn transform_to_stock(
                 SELECT id, symbol, trade_type, order_type, entry_price,
                        exit_price, stop_loss, commissions, number_shares, take_profit,
                        initial_target, profit_target, trade_ratings,
-                       entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at
+                       entry_date, exit_date, reviewed, mistakes, brokerage_name, created_at, updated_at, realized_pnl, venue
                 FROM stocks
                 WHERE id = ?
                 "#
@@ -602,17 +576,7 @@ async fn transform_to_stock(
             let updated_at_str: String = row.get(19)?;
 
             fn parse_dt(s: &str) -> Result<chrono::DateTime<Utc>> {
-                if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
-                    return Ok(dt.with_timezone(&Utc));
-                }
-                if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-                    return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-                }
-                if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    let ndt = date.and_hms_opt(0, 0, 0).ok_or_else(|| anyhow::anyhow!("invalid date"))?;
-                    return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-                }
-                Err(anyhow::anyhow!("Unsupported datetime format: {}", s))
+                timestamps::parse_stored(s).map_err(|e| anyhow::anyhow!(e))
             }
 
             let entry_date = parse_dt(&entry_date_str)?;
@@ -625,50 +589,21 @@ async fn transform_to_stock(
                 symbol: row.get(1)?,
                 trade_type,
                 order_type,
-                entry_price: match row.get::<libsql::Value>(4)? {
-                    libsql::Value::Integer(val) => val as f64,
-                    libsql::Value::Real(val) => val,
-                    _ => return Err(anyhow::anyhow!("Invalid entry_price type")),
-                },
-                exit_price: match row.get::<libsql::Value>(5)? {
-                    libsql::Value::Integer(val) => Some(val as f64),
-                    libsql::Value::Real(val) => Some(val),
-                    libsql::Value::Null => None,
-                    _ => None,
-                },
-                stop_loss: match row.get::<libsql::Value>(6)? {
-                    libsql::Value::Integer(val) => val as f64,
-                    libsql::Value::Real(val) => val,
-                    _ => return Err(anyhow::anyhow!("Invalid stop_loss type")),
-                },
-                commissions: match row.get::<libsql::Value>(7)? {
-                    libsql::Value::Integer(val) => val as f64,
-                    libsql::Value::Real(val) => val,
-                    _ => return Err(anyhow::anyhow!("Invalid commissions type")),
-                },
+                entry_price: money::row_decimal_opt(&row, 4)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid entry_price type"))?,
+                exit_price: money::row_decimal_opt(&row, 5),
+                stop_loss: money::row_decimal_opt(&row, 6)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid stop_loss type"))?,
+                commissions: money::row_decimal_opt(&row, 7)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid commissions type"))?,
                 number_shares: match row.get::<libsql::Value>(8)? {
                     libsql::Value::Integer(val) => val as f64,
                     libsql::Value::Real(val) => val,
                     _ => return Err(anyhow::anyhow!("Invalid number_shares type")),
                 },
-                take_profit: match row.get::<libsql::Value>(9)? {
-                    libsql::Value::Integer(val) => Some(val as f64),
-                    libsql::Value::Real(val) => Some(val),
-                    libsql::Value::Null => None,
-                    _ => None,
-                },
-                initial_target: match row.get::<libsql::Value>(10)? {
-                    libsql::Value::Integer(val) => Some(val as f64),
-                    libsql::Value::Real(val) => Some(val),
-                    libsql::Value::Null => None,
-                    _ => None,
-                },
-                profit_target: match row.get::<libsql::Value>(11)? {
-                    libsql::Value::Integer(val) => Some(val as f64),
-                    libsql::Value::Real(val) => Some(val),
-                    libsql::Value::Null => None,
-                    _ => None,
-                },
+                take_profit: money::row_decimal_opt(&row, 9),
+                initial_target: money::row_decimal_opt(&row, 10),
+                profit_target: money::row_decimal_opt(&row, 11),
                 trade_ratings: row.get::<Option<i32>>(12)?,
                 entry_date,
                 exit_date,
@@ -677,6 +612,8 @@ async fn transform_to_stock(
                 brokerage_name,
                 created_at,
                 updated_at,
+                realized_pnl: money::row_decimal_opt(&row, 20),
+                venue: row.get(21)?,
             };
             
             // Format stock for embedding
@@ -749,6 +686,9 @@ async fn transform_to_option(
         .get("trade_date")
         .and_then(|v| v.as_str())
         .ok_or_else(|| anyhow::anyhow!("Missing trade_date in transaction"))?;
+    let trade_date = timestamps::parse_stored(trade_date)
+        .map(|dt| timestamps::to_db(&dt))
+        .map_err(|e| anyhow::anyhow!("Invalid trade_date in transaction: {}", e))?;
 
     let brokerage_name = transaction
         .get("institution")
@@ -774,11 +714,13 @@ async fn transform_to_option(
     // Parse option symbol if available (format: SYMBOL YYMMDD C/P STRIKE)
     // For now, use defaults - user will need to review and update
     let strike_price = price; // Default to entry price, user will update
-    let expiration_date = trade_date; // Default to trade date, user will update
+    let expiration_date = trade_date.clone(); // Default to trade date, user will update
 
     // Set defaults for required fields
     let strategy_type = "Single"; // Default strategy
     let trade_direction = if trade_type == "BUY" { "Bullish" } else { "Bearish" };
+    // An opening sell writes the contract
+    let short_premium = trade_type == "SELL";
     let total_premium = price * units as f64;
     let implied_volatility = 0.0; // Default, user will update
 
@@ -790,8 +732,8 @@ async fn transform_to_option(
                 symbol, strategy_type, trade_direction, number_of_contracts,
                 option_type, strike_price, expiration_date, entry_price,
                 total_premium, commissions, implied_volatility, entry_date,
-                brokerage_name, reviewed, is_deleted, status
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, false, 0, 'open')
+                brokerage_name, short_premium, reviewed, is_deleted, status
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, false, 0, 'open')
             RETURNING id
             "#,
         )
@@ -812,7 +754,8 @@ async fn transform_to_option(
             fee,
             implied_volatility,
             trade_date,
-            brokerage_name
+            brokerage_name,
+            short_premium
         ])
         .await
         .context("Failed to insert option trade")?;
@@ -833,7 +776,7 @@ async fn transform_to_option(
                        option_type, strike_price, expiration_date, entry_price, exit_price,
                        total_premium, commissions, implied_volatility, entry_date, exit_date,
                        status, initial_target, profit_target, trade_ratings, reviewed, mistakes,
-                       brokerage_name, created_at, updated_at, is_deleted
+                       brokerage_name, created_at, updated_at, is_deleted, realized_pnl, venue, short_premium
                 FROM options
                 WHERE id = ?
                 "#
@@ -911,17 +854,7 @@ async fn transform_to_option(
             };
 
             fn parse_dt(s: &str) -> Result<chrono::DateTime<Utc>> {
-                if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
-                    return Ok(dt.with_timezone(&Utc));
-                }
-                if let Ok(ndt) = chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
-                    return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-                }
-                if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    let ndt = date.and_hms_opt(0, 0, 0).ok_or_else(|| anyhow::anyhow!("invalid date"))?;
-                    return Ok(chrono::DateTime::<Utc>::from_naive_utc_and_offset(ndt, Utc));
-                }
-                Err(anyhow::anyhow!("Unsupported datetime format: {}", s))
+                timestamps::parse_stored(s).map_err(|e| anyhow::anyhow!(e))
             }
 
             let expiration_date = parse_dt(&expiration_date_str)?;
@@ -947,18 +880,22 @@ async fn transform_to_option(
                     _ => return Err(anyhow::anyhow!("Failed to get number_of_contracts")),
                 },
                 option_type,
-                strike_price: row.get::<f64>(6)?,
+                strike_price: money::row_decimal_opt(&row, 6)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid strike_price type"))?,
                 expiration_date,
-                entry_price: row.get::<f64>(8)?,
-                exit_price: row.get::<Option<f64>>(9)?,
-                total_premium: row.get::<f64>(10)?,
-                commissions: row.get::<f64>(11)?,
+                entry_price: money::row_decimal_opt(&row, 8)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid entry_price type"))?,
+                exit_price: money::row_decimal_opt(&row, 9),
+                total_premium: money::row_decimal_opt(&row, 10)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid total_premium type"))?,
+                commissions: money::row_decimal_opt(&row, 11)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid commissions type"))?,
                 implied_volatility: row.get::<f64>(12)?,
                 entry_date,
                 exit_date,
                 status,
-                initial_target: row.get::<Option<f64>>(16)?,
-                profit_target: row.get::<Option<f64>>(17)?,
+                initial_target: money::row_decimal_opt(&row, 16),
+                profit_target: money::row_decimal_opt(&row, 17),
                 trade_ratings: match row.get::<libsql::Value>(18) {
                     Ok(libsql::Value::Integer(val)) => Some(val as i32),
                     Ok(libsql::Value::Null) => None,
@@ -975,6 +912,9 @@ async fn transform_to_option(
                     Ok(libsql::Value::Null) => false,
                     _ => false,
                 },
+                realized_pnl: money::row_decimal_opt(&row, 25),
+                venue: row.get(26)?,
+                short_premium: matches!(row.get::<libsql::Value>(27), Ok(libsql::Value::Integer(1))),
             };
 
             // Format option for embedding
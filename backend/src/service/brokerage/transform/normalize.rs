@@ -0,0 +1,380 @@
+//! Broker-neutral transaction normalization.
+//!
+//! SnapTrade hands us activities in one JSON shape, but what the fields mean still varies by
+//! broker: sells with negative units, fees as negative amounts, interest posted as a fee or a
+//! dividend. Everything downstream should see a `CanonicalTransaction` instead of raw JSON.
+//!
+//! Mapping runs in two steps:
+//!   1. `RawActivity::from_value` pulls the fields we use out of the SnapTrade payload.
+//!   2. The broker's mapper (if registered in `BROKER_MAPPERS`) gets the first chance to
+//!      classify it; returning None falls through to `map_default`, which handles the standard
+//!      SnapTrade activity types.
+//!
+//! Supporting a new broker means writing one `fn(&RawActivity) -> Option<TransactionKind>` for
+//! the activities it reports differently and adding it to `BROKER_MAPPERS`.
+
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::Value;
+
+/// Which way a trade moved the position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// Option leg details for trades and assignment events
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OptionContract {
+    /// OCC-style contract symbol as reported by the broker
+    pub contract_symbol: String,
+    pub underlying: Option<String>,
+    /// "CALL" or "PUT"
+    pub option_type: Option<String>,
+    pub strike: Option<f64>,
+    pub expiration: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentEvent {
+    Assigned,
+    Exercised,
+    Expired,
+}
+
+/// What a transaction did. Quantities and fees are always non-negative; cash amounts are
+/// signed from the account's point of view (positive = money in).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TransactionKind {
+    Trade {
+        symbol: String,
+        side: TradeSide,
+        quantity: f64,
+        price: f64,
+        fees: f64,
+        option: Option<OptionContract>,
+    },
+    Dividend {
+        symbol: Option<String>,
+        amount: f64,
+    },
+    Fee {
+        amount: f64,
+        description: Option<String>,
+    },
+    Transfer {
+        amount: f64,
+        symbol: Option<String>,
+        quantity: Option<f64>,
+    },
+    Interest {
+        amount: f64,
+    },
+    Assignment {
+        event: AssignmentEvent,
+        option: OptionContract,
+        contracts: f64,
+    },
+}
+
+impl TransactionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TransactionKind::Trade { .. } => "trade",
+            TransactionKind::Dividend { .. } => "dividend",
+            TransactionKind::Fee { .. } => "fee",
+            TransactionKind::Transfer { .. } => "transfer",
+            TransactionKind::Interest { .. } => "interest",
+            TransactionKind::Assignment { .. } => "assignment",
+        }
+    }
+}
+
+/// A brokerage activity in the journal's own terms
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CanonicalTransaction {
+    pub external_id: String,
+    pub trade_date: String,
+    pub currency: String,
+    pub institution: Option<String>,
+    #[serde(flatten)]
+    pub kind: TransactionKind,
+}
+
+/// The SnapTrade activity fields the mappers read. Nested objects (`symbol`,
+/// `option_symbol`, `currency`) are flattened; plain-string variants are accepted too.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawActivity {
+    pub id: String,
+    /// Upper-cased activity type, e.g. "BUY", "DIVIDEND", "OPTIONASSIGNMENT"
+    pub activity_type: String,
+    pub symbol: Option<String>,
+    pub option: Option<OptionContract>,
+    /// Order action for option trades, e.g. "BUY_TO_OPEN"
+    pub option_action: Option<String>,
+    pub units: Option<f64>,
+    pub price: Option<f64>,
+    pub amount: Option<f64>,
+    pub fee: Option<f64>,
+    pub description: Option<String>,
+    pub trade_date: Option<String>,
+    pub currency: Option<String>,
+    pub institution: Option<String>,
+}
+
+impl RawActivity {
+    pub fn from_value(raw: &Value) -> Self {
+        let text = |v: Option<&Value>| v.and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+        let number = |v: Option<&Value>| v.and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(|s| s.parse().ok())));
+        // `{"symbol": {"symbol": "AAPL"}}` in SnapTrade, `{"symbol": "AAPL"}` after our own flattening
+        let symbol_of = |v: Option<&Value>| match v {
+            Some(Value::Object(map)) => text(map.get("symbol")).or_else(|| text(map.get("raw_symbol"))),
+            other => text(other),
+        };
+
+        let option = match raw.get("option_symbol") {
+            Some(Value::Object(map)) => text(map.get("ticker")).map(|ticker| OptionContract {
+                contract_symbol: ticker,
+                underlying: symbol_of(map.get("underlying_symbol")),
+                option_type: text(map.get("option_type")).map(|t| t.to_uppercase()),
+                strike: number(map.get("strike_price")),
+                expiration: text(map.get("expiration_date")),
+            }),
+            other => text(other).map(|ticker| OptionContract {
+                contract_symbol: ticker,
+                underlying: None,
+                option_type: None,
+                strike: None,
+                expiration: None,
+            }),
+        };
+
+        Self {
+            id: text(raw.get("id")).unwrap_or_default(),
+            activity_type: text(raw.get("type")).unwrap_or_default().to_uppercase(),
+            symbol: symbol_of(raw.get("symbol")),
+            option,
+            option_action: text(raw.get("option_type")).map(|a| a.to_uppercase()),
+            units: number(raw.get("units")).or_else(|| number(raw.get("quantity"))),
+            price: number(raw.get("price")),
+            amount: number(raw.get("amount")),
+            fee: number(raw.get("fee")).or_else(|| number(raw.get("fees"))),
+            description: text(raw.get("description")),
+            trade_date: text(raw.get("trade_date")).or_else(|| text(raw.get("date"))),
+            currency: match raw.get("currency") {
+                Some(Value::Object(map)) => text(map.get("code")),
+                other => text(other),
+            },
+            institution: text(raw.get("institution")),
+        }
+    }
+
+    fn description_contains(&self, needle: &str) -> bool {
+        self.description.as_deref().is_some_and(|d| d.to_uppercase().contains(needle))
+    }
+
+    fn trade_symbol(&self) -> Option<String> {
+        self.symbol
+            .clone()
+            .or_else(|| self.option.as_ref().and_then(|o| o.underlying.clone()))
+    }
+}
+
+/// Broker-specific override: classify the activity, or None to use `map_default`
+pub type BrokerMapper = fn(&RawActivity) -> Option<TransactionKind>;
+
+/// Registered broker mappers, keyed by a lower-case fragment of the institution name
+const BROKER_MAPPERS: &[(&str, BrokerMapper)] = &[
+    ("interactive brokers", map_interactive_brokers),
+    ("schwab", map_schwab),
+];
+
+fn mapper_for(institution: Option<&str>) -> Option<BrokerMapper> {
+    let institution = institution?.to_lowercase();
+    BROKER_MAPPERS
+        .iter()
+        .find(|(fragment, _)| institution.contains(fragment))
+        .map(|(_, mapper)| *mapper)
+}
+
+/// Normalize one SnapTrade activity. `institution` overrides the payload's own
+/// `institution` field (e.g. the connection's brokerage name).
+pub fn normalize_activity(raw: &Value, institution: Option<&str>) -> Result<CanonicalTransaction> {
+    let mut activity = RawActivity::from_value(raw);
+    if let Some(institution) = institution {
+        activity.institution = Some(institution.to_string());
+    }
+    normalize(&activity)
+}
+
+pub fn normalize(activity: &RawActivity) -> Result<CanonicalTransaction> {
+    let kind = match mapper_for(activity.institution.as_deref()).and_then(|mapper| mapper(activity)) {
+        Some(kind) => kind,
+        None => map_default(activity)?,
+    };
+    Ok(CanonicalTransaction {
+        external_id: activity.id.clone(),
+        trade_date: activity.trade_date.clone().unwrap_or_default(),
+        currency: activity.currency.clone().unwrap_or_else(|| "USD".to_string()),
+        institution: activity.institution.clone(),
+        kind,
+    })
+}
+
+/// Standard SnapTrade activity types. Handles the quirks common to many brokers:
+/// - sells reported with negative units
+/// - fees and withdrawals reported with either sign
+/// - option trades reported as BUY/SELL with the real action in `option_type`
+/// - dividend reinvestment (REI) is a buy of the paying security
+pub fn map_default(activity: &RawActivity) -> Result<TransactionKind> {
+    let amount = activity.amount.unwrap_or(0.0);
+    let kind = match activity.activity_type.as_str() {
+        "BUY" | "SELL" | "REI" => {
+            let symbol = activity
+                .trade_symbol()
+                .ok_or_else(|| anyhow!("Trade {} has no symbol", activity.id))?;
+            let side = match activity.option_action.as_deref() {
+                Some(action) if action.starts_with("SELL") => TradeSide::Sell,
+                Some(action) if action.starts_with("BUY") => TradeSide::Buy,
+                _ if activity.activity_type == "SELL" => TradeSide::Sell,
+                _ => TradeSide::Buy,
+            };
+            let quantity = activity.units.unwrap_or(0.0).abs();
+            let price = match activity.price {
+                Some(price) => price.abs(),
+                // Some brokers omit the price and only report the net amount
+                None if quantity > 0.0 => (amount.abs() / quantity).abs(),
+                None => 0.0,
+            };
+            TransactionKind::Trade {
+                symbol,
+                side,
+                quantity,
+                price,
+                fees: activity.fee.unwrap_or(0.0).abs(),
+                option: activity.option.clone(),
+            }
+        }
+        "DIVIDEND" | "STOCK_DIVIDEND" => TransactionKind::Dividend { symbol: activity.symbol.clone(), amount },
+        "FEE" | "TAX" => TransactionKind::Fee { amount: amount.abs(), description: activity.description.clone() },
+        "INTEREST" => TransactionKind::Interest { amount },
+        "CONTRIBUTION" | "DEPOSIT" => TransactionKind::Transfer { amount: amount.abs(), symbol: None, quantity: None },
+        "WITHDRAWAL" => TransactionKind::Transfer { amount: -amount.abs(), symbol: None, quantity: None },
+        "TRANSFER" | "EXTERNAL_ASSET_TRANSFER_IN" | "EXTERNAL_ASSET_TRANSFER_OUT" => {
+            let outgoing = activity.activity_type.ends_with("_OUT");
+            let signed = |v: f64| if outgoing { -v.abs() } else { v };
+            TransactionKind::Transfer {
+                amount: signed(amount),
+                symbol: activity.symbol.clone(),
+                quantity: activity.units.map(signed),
+            }
+        }
+        "OPTIONASSIGNMENT" | "OPTIONEXERCISE" | "OPTIONEXPIRATION" => {
+            let option = activity
+                .option
+                .clone()
+                .ok_or_else(|| anyhow!("Option event {} has no contract", activity.id))?;
+            let event = match activity.activity_type.as_str() {
+                "OPTIONASSIGNMENT" => AssignmentEvent::Assigned,
+                "OPTIONEXERCISE" => AssignmentEvent::Exercised,
+                _ => AssignmentEvent::Expired,
+            };
+            TransactionKind::Assignment { event, option, contracts: activity.units.unwrap_or(0.0).abs() }
+        }
+        other => return Err(anyhow!("Unsupported activity type {:?} for {}", other, activity.id)),
+    };
+    Ok(kind)
+}
+
+/// IBKR posts margin and cash interest as FEE activities ("USD Debit Interest for ...",
+/// "USD Credit Interest for ...")
+fn map_interactive_brokers(activity: &RawActivity) -> Option<TransactionKind> {
+    if activity.activity_type != "FEE" {
+        return None;
+    }
+    let amount = activity.amount?.abs();
+    if activity.description_contains("DEBIT INTEREST") {
+        Some(TransactionKind::Interest { amount: -amount })
+    } else if activity.description_contains("CREDIT INTEREST") {
+        Some(TransactionKind::Interest { amount })
+    } else {
+        None
+    }
+}
+
+/// Schwab posts bank sweep interest as DIVIDEND activities described "Bank Interest"
+fn map_schwab(activity: &RawActivity) -> Option<TransactionKind> {
+    if activity.activity_type == "DIVIDEND" && activity.description_contains("BANK INT") {
+        return Some(TransactionKind::Interest { amount: activity.amount.unwrap_or(0.0) });
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kind(raw: Value) -> TransactionKind {
+        normalize_activity(&raw, None).unwrap().kind
+    }
+
+    #[test]
+    fn test_negative_sell_units_become_positive_quantity() {
+        let trade = kind(json!({"id": "t1", "type": "SELL", "symbol": {"symbol": "AAPL"}, "units": -10.0, "price": 190.5, "fee": -1.0}));
+        assert_eq!(trade, TransactionKind::Trade {
+            symbol: "AAPL".to_string(), side: TradeSide::Sell, quantity: 10.0, price: 190.5, fees: 1.0, option: None,
+        });
+    }
+
+    #[test]
+    fn test_option_trade_side_comes_from_option_action() {
+        let raw = json!({
+            "id": "t2", "type": "BUY", "option_type": "SELL_TO_CLOSE", "units": 2, "price": 3.1,
+            "option_symbol": {"ticker": "AAPL  240621C00200000", "option_type": "call", "strike_price": 200.0,
+                              "expiration_date": "2024-06-21", "underlying_symbol": {"symbol": "AAPL"}}
+        });
+        match kind(raw) {
+            TransactionKind::Trade { symbol, side, option, .. } => {
+                assert_eq!(symbol, "AAPL");
+                assert_eq!(side, TradeSide::Sell);
+                assert_eq!(option.unwrap().option_type.as_deref(), Some("CALL"));
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reinvestment_and_cash_activity_signs() {
+        assert!(matches!(kind(json!({"id": "r", "type": "REI", "symbol": "VTI", "units": 0.5, "amount": -120.0})),
+            TransactionKind::Trade { side: TradeSide::Buy, price, .. } if (price - 240.0).abs() < 1e-9));
+        assert_eq!(kind(json!({"id": "f", "type": "FEE", "amount": -4.95})), TransactionKind::Fee { amount: 4.95, description: None });
+        assert_eq!(kind(json!({"id": "w", "type": "WITHDRAWAL", "amount": 500.0})),
+            TransactionKind::Transfer { amount: -500.0, symbol: None, quantity: None });
+        assert!(normalize_activity(&json!({"id": "x", "type": "SPLIT"}), None).is_err());
+    }
+
+    #[test]
+    fn test_option_events_map_to_assignment() {
+        let raw = json!({"id": "a", "type": "OPTIONASSIGNMENT", "units": -1, "option_symbol": {"ticker": "SPY   240315P00500000"}});
+        assert!(matches!(kind(raw), TransactionKind::Assignment { event: AssignmentEvent::Assigned, contracts, .. } if contracts == 1.0));
+    }
+
+    #[test]
+    fn test_interactive_brokers_interest_posted_as_fee() {
+        let raw = json!({"id": "i", "type": "FEE", "amount": -12.34, "description": "USD Debit Interest for Mar-2024"});
+        assert_eq!(normalize_activity(&raw, Some("Interactive Brokers")).unwrap().kind, TransactionKind::Interest { amount: -12.34 });
+        // Other brokers keep it as a fee
+        assert_eq!(normalize_activity(&raw, Some("Fidelity")).unwrap().kind.label(), "fee");
+    }
+
+    #[test]
+    fn test_schwab_bank_interest_posted_as_dividend() {
+        let raw = json!({"id": "s", "type": "DIVIDEND", "amount": 0.87, "description": "BANK INTEREST", "institution": "Charles Schwab"});
+        assert_eq!(kind(raw), TransactionKind::Interest { amount: 0.87 });
+    }
+}
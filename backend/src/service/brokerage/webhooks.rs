@@ -0,0 +1,223 @@
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::Deserialize;
+
+use crate::service::notifications::push::{PushPayload, PushService};
+use crate::turso::config::WebPushConfig;
+
+/// Body SnapTrade posts to `/webhooks/snaptrade`. Only the fields we act on are read;
+/// `user_id` is the id we registered the SnapTrade user with, i.e. our own user id.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapTradeWebhook {
+    pub event_type: String,
+    pub user_id: String,
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+    #[serde(default)]
+    pub webhook_id: Option<String>,
+    #[serde(default)]
+    pub brokerage_authorization_id: Option<String>,
+    #[serde(default)]
+    pub account_id: Option<String>,
+}
+
+/// What a webhook event means for the stored connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookAction {
+    /// Credentials expired or were revoked; the user has to reconnect
+    Broken,
+    /// Connection created or repaired through the connection portal
+    Connected,
+    /// Connection removed on the SnapTrade side
+    Disconnected,
+    /// Broker has accounts or transactions we haven't synced yet
+    SyncRequested,
+    Ignored,
+}
+
+impl WebhookAction {
+    pub fn from_event(event_type: &str) -> Self {
+        match event_type {
+            "CONNECTION_BROKEN" | "CONNECTION_FAILED" => WebhookAction::Broken,
+            "CONNECTION_ADDED" | "CONNECTION_FIXED" => WebhookAction::Connected,
+            "CONNECTION_DELETED" => WebhookAction::Disconnected,
+            "NEW_ACCOUNT_AVAILABLE"
+            | "ACCOUNT_TRANSACTIONS_INITIAL_UPDATE"
+            | "ACCOUNT_TRANSACTIONS_UPDATED"
+            | "ACCOUNT_HOLDINGS_UPDATED" => WebhookAction::SyncRequested,
+            _ => WebhookAction::Ignored,
+        }
+    }
+}
+
+/// Applies SnapTrade webhook events to a user's `brokerage_connections` rows
+pub struct SnapTradeWebhookService<'a> {
+    pub conn: &'a Connection,
+}
+
+impl<'a> SnapTradeWebhookService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Update the matching connection and return its id and brokerage name, or None when
+    /// the event is ignored or no connection matches.
+    pub async fn apply(&self, event: &SnapTradeWebhook) -> Result<Option<(String, String)>> {
+        let action = WebhookAction::from_event(&event.event_type);
+        if action == WebhookAction::Ignored {
+            info!("Ignoring SnapTrade webhook {} for user {}", event.event_type, event.user_id);
+            return Ok(None);
+        }
+
+        let Some((id, brokerage_name)) = self.find_connection(event, action).await? else {
+            warn!(
+                "No brokerage connection matches SnapTrade webhook {} for user {}",
+                event.event_type, event.user_id
+            );
+            return Ok(None);
+        };
+
+        let now = Utc::now().to_rfc3339();
+        let reason = event.event_type.to_lowercase();
+        match action {
+            WebhookAction::Broken => {
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET status = 'error', status_reason = ?, reauth_required = 1, last_webhook_at = ?, updated_at = ? WHERE id = ?",
+                    params![reason, now.clone(), now, id.clone()],
+                ).await?;
+            }
+            WebhookAction::Connected => {
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET status = 'connected', status_reason = NULL, reauth_required = 0, authorization_id = COALESCE(?, authorization_id), sync_requested_at = ?, last_webhook_at = ?, updated_at = ? WHERE id = ?",
+                    params![event.brokerage_authorization_id.clone(), now.clone(), now.clone(), now, id.clone()],
+                ).await?;
+            }
+            WebhookAction::Disconnected => {
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET status = 'disconnected', status_reason = ?, reauth_required = 0, last_webhook_at = ?, updated_at = ? WHERE id = ?",
+                    params![reason, now.clone(), now, id.clone()],
+                ).await?;
+            }
+            WebhookAction::SyncRequested => {
+                self.conn.execute(
+                    "UPDATE brokerage_connections SET sync_requested_at = ?, last_webhook_at = ?, updated_at = ? WHERE id = ?",
+                    params![now.clone(), now.clone(), now, id.clone()],
+                ).await?;
+            }
+            WebhookAction::Ignored => {}
+        }
+
+        info!(
+            "Applied SnapTrade webhook {} ({}) to connection {}",
+            event.event_type,
+            event.webhook_id.as_deref().unwrap_or("no id"),
+            id
+        );
+        Ok(Some((id, brokerage_name)))
+    }
+
+    /// Match by authorization id first. Connections created before we stored it are matched
+    /// by the user's newest pending connection (for new connections) or, failing that, the
+    /// user's only connection.
+    async fn find_connection(&self, event: &SnapTradeWebhook, action: WebhookAction) -> Result<Option<(String, String)>> {
+        if let Some(authorization_id) = event.brokerage_authorization_id.as_deref() {
+            let mut rows = self.conn
+                .prepare("SELECT id, brokerage_name FROM brokerage_connections WHERE user_id = ? AND authorization_id = ? LIMIT 1")
+                .await?
+                .query(params![event.user_id.clone(), authorization_id])
+                .await?;
+            if let Some(row) = rows.next().await? {
+                return Ok(Some((row.get(0)?, row.get(1)?)));
+            }
+        }
+
+        if action == WebhookAction::Connected {
+            let mut rows = self.conn
+                .prepare("SELECT id, brokerage_name FROM brokerage_connections WHERE user_id = ? AND status = 'pending' AND authorization_id IS NULL ORDER BY created_at DESC LIMIT 1")
+                .await?
+                .query(params![event.user_id.clone()])
+                .await?;
+            if let Some(row) = rows.next().await? {
+                return Ok(Some((row.get(0)?, row.get(1)?)));
+            }
+        }
+
+        let mut rows = self.conn
+            .prepare("SELECT id, brokerage_name FROM brokerage_connections WHERE user_id = ? AND connection_id IS NOT NULL LIMIT 2")
+            .await?
+            .query(params![event.user_id.clone()])
+            .await?;
+        let only = match rows.next().await? {
+            Some(row) => Some((row.get(0)?, row.get(1)?)),
+            None => None,
+        };
+        if rows.next().await?.is_some() {
+            return Ok(None);
+        }
+        Ok(only)
+    }
+
+    /// Ids and names of connections the user must reconnect before they sync again
+    pub async fn connections_needing_reauth(&self, user_id: &str) -> Result<Vec<(String, String)>> {
+        let mut rows = self.conn
+            .prepare("SELECT id, brokerage_name FROM brokerage_connections WHERE user_id = ? AND reauth_required = 1")
+            .await?
+            .query(params![user_id])
+            .await?;
+        let mut connections = Vec::new();
+        while let Some(row) = rows.next().await? {
+            connections.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(connections)
+    }
+}
+
+/// Push the user a prompt to reconnect a broken brokerage connection
+pub async fn notify_reauth_required(
+    conn: &Connection,
+    web_push: &WebPushConfig,
+    user_id: &str,
+    connection_id: &str,
+    brokerage_name: &str,
+) {
+    let payload = PushPayload {
+        title: "Brokerage connection needs attention".to_string(),
+        body: Some(format!(
+            "Your {} connection stopped working. Reconnect it to keep your trades syncing.",
+            brokerage_name
+        )),
+        icon: None,
+        url: Some("/app/brokerage".to_string()),
+        tag: Some(format!("brokerage-reauth-{}", connection_id)),
+        data: Some(serde_json::json!({"type": "brokerage_reauth", "connection_id": connection_id})),
+    };
+    if let Err(e) = PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+        warn!("Failed to send reconnect prompt to user {}: {}", user_id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_event_mapping() {
+        assert_eq!(WebhookAction::from_event("CONNECTION_BROKEN"), WebhookAction::Broken);
+        assert_eq!(WebhookAction::from_event("CONNECTION_FIXED"), WebhookAction::Connected);
+        assert_eq!(WebhookAction::from_event("NEW_ACCOUNT_AVAILABLE"), WebhookAction::SyncRequested);
+        assert_eq!(WebhookAction::from_event("ACCOUNT_TRANSACTIONS_UPDATED"), WebhookAction::SyncRequested);
+        assert_eq!(WebhookAction::from_event("USER_REGISTERED"), WebhookAction::Ignored);
+    }
+
+    #[test]
+    fn test_webhook_body_parses_snaptrade_fields() {
+        let body = r#"{"webhookId":"w1","clientId":"c","eventTimestamp":"2024-01-01T00:00:00Z","userId":"u1","eventType":"CONNECTION_BROKEN","webhookSecret":"s","brokerageId":"b","brokerageAuthorizationId":"auth-1"}"#;
+        let event: SnapTradeWebhook = serde_json::from_str(body).unwrap();
+        assert_eq!(event.user_id, "u1");
+        assert_eq!(event.brokerage_authorization_id.as_deref(), Some("auth-1"));
+        assert!(event.account_id.is_none());
+    }
+}
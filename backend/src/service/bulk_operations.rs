@@ -0,0 +1,285 @@
+// Undo for destructive bulk actions. Before a bulk delete or a tag deletion runs, every row it
+// will remove is captured, in the same columnar layout trade archives use, into an operation
+// manifest in `bulk_operations`. Undoing writes those rows back, inside UNDO_WINDOW_HOURS of
+// the action; trade ids are AUTOINCREMENT and never reused, so restored rows keep their ids
+// and whatever still points at them lines up again. Manifests past the window are purged
+// the next time an operation is recorded.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use libsql::{params, Connection, Value};
+use serde::{Deserialize, Serialize};
+
+use crate::service::trade_archive::{self, json_to_value, value_to_json, TableDump};
+use crate::turso::in_transaction;
+
+pub const UNDO_WINDOW_HOURS: i64 = 24;
+pub const MAX_BULK_IDS: usize = 500;
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// Parents before dependents, the order rows are restored in
+    tables: Vec<TableDump>,
+}
+
+impl Manifest {
+    fn row_count(&self) -> usize {
+        self.tables.iter().map(|t| t.rows.len()).sum()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkOperation {
+    pub id: String,
+    /// "bulk_delete_trades" or "delete_tag"
+    pub kind: String,
+    pub description: String,
+    pub row_count: i64,
+    pub created_at: String,
+    pub expires_at: String,
+    pub undone_at: Option<String>,
+    pub undoable: bool,
+}
+
+#[derive(Debug)]
+pub enum UndoOutcome {
+    Restored(BulkOperation),
+    NotFound,
+    /// Already undone or past the undo window
+    Unavailable(String),
+}
+
+/// Why an operation can no longer be undone, if it can't
+fn undo_blocker(undone_at: Option<&str>, expires_at: &str, now: DateTime<Utc>) -> Option<String> {
+    if undone_at.is_some() {
+        return Some("Operation was already undone".to_string());
+    }
+    match DateTime::parse_from_rfc3339(expires_at) {
+        Ok(expires) if expires > now => None,
+        _ => Some(format!("Operations can only be undone within {} hours", UNDO_WINDOW_HOURS)),
+    }
+}
+
+const OPERATION_COLUMNS: &str = "id, kind, description, row_count, created_at, expires_at, undone_at";
+
+fn row_to_operation(row: &libsql::Row, now: DateTime<Utc>) -> Result<BulkOperation> {
+    let expires_at: String = row.get(5)?;
+    let undone_at: Option<String> = row.get(6)?;
+    Ok(BulkOperation {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        description: row.get(2)?,
+        row_count: row.get(3)?,
+        created_at: row.get(4)?,
+        undoable: undo_blocker(undone_at.as_deref(), &expires_at, now).is_none(),
+        expires_at,
+        undone_at,
+    })
+}
+
+/// Operations still inside the undo window, newest first
+pub async fn list_operations(conn: &Connection) -> Result<Vec<BulkOperation>> {
+    let now = Utc::now();
+    let mut rows = conn
+        .prepare(&format!("SELECT {OPERATION_COLUMNS} FROM bulk_operations WHERE expires_at > ? ORDER BY created_at DESC"))
+        .await?
+        .query(params![now.to_rfc3339()])
+        .await?;
+    let mut operations = Vec::new();
+    while let Some(row) = rows.next().await? {
+        operations.push(row_to_operation(&row, now)?);
+    }
+    Ok(operations)
+}
+
+pub async fn get_operation(conn: &Connection, id: &str) -> Result<Option<BulkOperation>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {OPERATION_COLUMNS} FROM bulk_operations WHERE id = ?"))
+        .await?
+        .query(params![id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_operation(&row, Utc::now())?)),
+        None => Ok(None),
+    }
+}
+
+async fn capture(conn: &Connection, table: &str, condition: &str, values: Vec<Value>) -> Result<TableDump> {
+    let mut rows = conn
+        .prepare(&format!("SELECT * FROM {} WHERE {}", table, condition))
+        .await?
+        .query(values)
+        .await?;
+    let mut out = TableDump { table: table.to_string(), ..Default::default() };
+    while let Some(row) = rows.next().await? {
+        if out.columns.is_empty() {
+            out.columns = (0..row.column_count()).filter_map(|i| row.column_name(i).map(str::to_string)).collect();
+        }
+        out.rows.push((0..row.column_count()).map(|i| row.get_value(i).map(value_to_json).unwrap_or_default()).collect());
+    }
+    Ok(out)
+}
+
+/// Store the manifest; runs inside the caller's transaction, after the deletes
+async fn record(conn: &Connection, kind: &str, description: &str, manifest: &Manifest) -> Result<String> {
+    let now = Utc::now();
+    conn.execute("DELETE FROM bulk_operations WHERE expires_at <= ?", params![now.to_rfc3339()]).await?;
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        &format!("INSERT INTO bulk_operations ({OPERATION_COLUMNS}, manifest) VALUES (?, ?, ?, ?, ?, ?, NULL, ?)"),
+        params![
+            id.clone(),
+            kind,
+            description,
+            manifest.row_count() as i64,
+            now.to_rfc3339(),
+            (now + Duration::hours(UNDO_WINDOW_HOURS)).to_rfc3339(),
+            serde_json::to_string(manifest)?
+        ],
+    )
+    .await?;
+    Ok(id)
+}
+
+fn id_list(ids: &[i64]) -> (String, Vec<Value>) {
+    (vec!["?"; ids.len()].join(", "), ids.iter().map(|id| Value::Integer(*id)).collect())
+}
+
+/// Delete stock and option trades along with their tags, playbook links, rule compliance
+/// and notes. Returns None when none of the ids exist.
+pub async fn bulk_delete_trades(conn: &Connection, stock_ids: &[i64], option_ids: &[i64]) -> Result<Option<BulkOperation>> {
+    let targets = [("stocks", stock_ids), ("options", option_ids)];
+    let mut manifest = Manifest { version: MANIFEST_VERSION, tables: Vec::new() };
+    let mut counts = Vec::new();
+    for (table, ids) in targets.iter().filter(|(_, ids)| !ids.is_empty()) {
+        let (list, values) = id_list(ids);
+        let parents = capture(conn, table, &format!("id IN ({})", list), values.clone()).await?;
+        counts.push((*table, parents.rows.len()));
+        manifest.tables.push(parents);
+        for (child, key) in trade_archive::trade_dependents(table) {
+            manifest.tables.push(capture(conn, child, &format!("{} IN ({})", key, list), values.clone()).await?);
+        }
+    }
+    let count = |table: &str| counts.iter().find(|(t, _)| *t == table).map(|(_, n)| *n).unwrap_or(0);
+    if count("stocks") + count("options") == 0 {
+        return Ok(None);
+    }
+    let description = format!("Deleted {} stock and {} option trades", count("stocks"), count("options"));
+
+    let id = in_transaction(conn, async |tx: &Connection| -> Result<String> {
+        for (table, ids) in targets.iter().filter(|(_, ids)| !ids.is_empty()) {
+            let (list, values) = id_list(ids);
+            // Dependents first so nothing is left dangling
+            for (child, key) in trade_archive::trade_dependents(table) {
+                tx.execute(&format!("DELETE FROM {} WHERE {} IN ({})", child, key, list), values.clone()).await?;
+            }
+            tx.execute(&format!("DELETE FROM {} WHERE id IN ({})", table, list), values).await?;
+        }
+        record(tx, "bulk_delete_trades", &description, &manifest).await
+    })
+    .await?;
+
+    get_operation(conn, &id).await
+}
+
+/// Delete a tag and strip it from every trade it was on. Returns None when the tag doesn't exist.
+pub async fn delete_tag(conn: &Connection, tag_id: &str) -> Result<Option<BulkOperation>> {
+    let tag = capture(conn, "trade_tags", "id = ?", vec![Value::Text(tag_id.to_string())]).await?;
+    if tag.rows.is_empty() {
+        return Ok(None);
+    }
+    let name = tag.columns.iter().position(|c| c == "name")
+        .and_then(|i| tag.rows[0][i].as_str().map(str::to_string))
+        .unwrap_or_else(|| tag_id.to_string());
+    let mut manifest = Manifest { version: MANIFEST_VERSION, tables: vec![tag] };
+    for link in ["stock_trade_tags", "option_trade_tags"] {
+        manifest.tables.push(capture(conn, link, "tag_id = ?", vec![Value::Text(tag_id.to_string())]).await?);
+    }
+    let description = format!("Deleted tag \"{}\" from {} trades", name, manifest.row_count() - 1);
+
+    let id = in_transaction(conn, async |tx: &Connection| -> Result<String> {
+        for link in ["stock_trade_tags", "option_trade_tags"] {
+            tx.execute(&format!("DELETE FROM {} WHERE tag_id = ?", link), params![tag_id]).await?;
+        }
+        tx.execute("DELETE FROM trade_tags WHERE id = ?", params![tag_id]).await?;
+        record(tx, "delete_tag", &description, &manifest).await
+    })
+    .await?;
+
+    get_operation(conn, &id).await
+}
+
+fn undoable_tables() -> Vec<&'static str> {
+    ["stocks", "options"]
+        .into_iter()
+        .flat_map(|table| std::iter::once(table).chain(trade_archive::trade_dependents(table).iter().map(|(t, _)| *t)))
+        .chain(["trade_tags", "stock_trade_tags", "option_trade_tags"])
+        .collect()
+}
+
+/// Write an operation's rows back. Rows that already exist again are left as they are.
+pub async fn undo(conn: &Connection, id: &str) -> Result<UndoOutcome> {
+    let mut rows = conn
+        .prepare("SELECT manifest, undone_at, expires_at FROM bulk_operations WHERE id = ?")
+        .await?
+        .query(params![id])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(UndoOutcome::NotFound) };
+    let (manifest, undone_at, expires_at): (String, Option<String>, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+    if let Some(reason) = undo_blocker(undone_at.as_deref(), &expires_at, Utc::now()) {
+        return Ok(UndoOutcome::Unavailable(reason));
+    }
+    let manifest: Manifest = serde_json::from_str(&manifest).context("Operation manifest is not readable")?;
+    if manifest.version > MANIFEST_VERSION {
+        anyhow::bail!("Manifest format {} is newer than this server supports", manifest.version);
+    }
+    let allowed = undoable_tables();
+
+    in_transaction(conn, async |tx: &Connection| -> Result<()> {
+        for dump in manifest.tables.iter().filter(|d| !d.rows.is_empty()) {
+            if !allowed.contains(&dump.table.as_str()) || dump.columns.iter().any(|c| !c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')) {
+                anyhow::bail!("Manifest contains an unexpected table or column: {}", dump.table);
+            }
+            let sql = trade_archive::insert_sql(dump);
+            for row in &dump.rows {
+                let values: Vec<Value> = row.iter().map(json_to_value).collect();
+                tx.execute(&sql, values).await?;
+            }
+        }
+        tx.execute("UPDATE bulk_operations SET undone_at = ? WHERE id = ?", params![Utc::now().to_rfc3339(), id]).await?;
+        Ok(())
+    })
+    .await?;
+
+    Ok(match get_operation(conn, id).await? {
+        Some(operation) => UndoOutcome::Restored(operation),
+        None => UndoOutcome::NotFound,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_window() {
+        let now = Utc::now();
+        let later = (now + Duration::hours(1)).to_rfc3339();
+        let earlier = (now - Duration::minutes(1)).to_rfc3339();
+        assert_eq!(undo_blocker(None, &later, now), None);
+        assert!(undo_blocker(None, &earlier, now).unwrap().contains("24 hours"));
+        assert!(undo_blocker(Some("2026-10-16T00:00:00Z"), &later, now).unwrap().contains("already undone"));
+        assert!(undo_blocker(None, "not a date", now).is_some());
+    }
+
+    #[test]
+    fn test_undoable_tables_cover_trade_dependents() {
+        let allowed = undoable_tables();
+        for table in ["stocks", "stock_trade_tags", "option_trade_playbook", "trade_notes", "trade_tags"] {
+            assert!(allowed.contains(&table), "{} should be undoable", table);
+        }
+        assert!(!allowed.contains(&"bulk_operations"));
+    }
+}
@@ -473,6 +473,23 @@ impl CacheService {
         Ok(data)
     }
 
+    /// Cached values for several keys in one round trip, None for misses
+    pub async fn get_many<T>(&self, cache_keys: &[String]) -> Result<Vec<Option<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.redis_client.mget(cache_keys).await
+    }
+
+    /// Store several values with the same TTL in one round trip
+    pub async fn set_many<T>(&self, entries: &[(String, T)], ttl_seconds: u64) -> Result<()>
+    where
+        T: Serialize,
+    {
+        self.redis_client.set_many(entries, ttl_seconds as usize).await
+            .context("Failed to cache values")
+    }
+
     /// Invalidate cache keys matching a pattern
     pub async fn invalidate_pattern(&self, pattern: &str) -> Result<usize> {
         let deleted_count = self.redis_client.del_pattern(pattern).await
@@ -1,16 +1,51 @@
 use anyhow::Result;
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use libsql::{Connection, params};
+use std::collections::HashSet;
+
+use crate::models::notebook::{CalendarEvent, Recurrence};
 // use serde::Deserialize;
 use reqwest::Client;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CalendarService;
 
 impl CalendarService {
     #[allow(dead_code)]
     pub fn new() -> Self { Self }
 
+    /// Local calendar events between two dates (`YYYY-MM-DD`, inclusive). Events of recurring
+    /// reminders are expanded into one entry per occurrence, with ids `<event id>:<date>`.
+    pub async fn events_in_range(conn: &Connection, start: &str, end: &str) -> Result<Vec<CalendarEvent>> {
+        let recurring = CalendarEvent::find_recurring_reminder_events(conn, end).await?;
+        let recurring_ids: HashSet<&str> = recurring.iter().map(|(event, _)| event.id.as_str()).collect();
+        let mut events: Vec<CalendarEvent> = CalendarEvent::find_by_date_range(conn, start, end)
+            .await?
+            .into_iter()
+            .filter(|event| !recurring_ids.contains(event.id.as_str()))
+            .collect();
+
+        let (Some(from), Some(to)) = (parse_date(start), parse_date(end)) else {
+            return Ok(events);
+        };
+        for (event, rule) in &recurring {
+            let (Ok(recurrence), Some(first)) = (Recurrence::parse(rule), parse_date(&event.start_date)) else {
+                log::warn!("Skipping recurring calendar event {} with rule '{}'", event.id, rule);
+                continue;
+            };
+            let span = parse_date(&event.end_date).map_or(chrono::Duration::zero(), |last| last - first);
+            for date in recurrence.dates_between(first, from, to) {
+                let mut occurrence = event.clone();
+                occurrence.id = format!("{}:{}", event.id, date);
+                occurrence.start_date = date.format("%Y-%m-%d").to_string();
+                occurrence.end_date = (date + span).format("%Y-%m-%d").to_string();
+                events.push(occurrence);
+            }
+        }
+        events.sort_by(|a, b| (&a.start_date, &a.start_time).cmp(&(&b.start_date, &b.start_time)));
+        Ok(events)
+    }
+
     // Token refresh methods
     pub async fn refresh_google_token(refresh_token: &str, client_id: &str, client_secret: &str) -> Result<(String, String, String)> {
         let client = Client::new();
@@ -287,4 +322,6 @@ impl CalendarService {
     }
 }
 
-
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
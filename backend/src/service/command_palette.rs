@@ -0,0 +1,273 @@
+// Search behind the command palette (⌘K). Everything the palette can jump to (trades,
+// notes, playbooks, recent chats and a fixed list of settings actions) is flattened into
+// one small index per user and cached in Redis, so a keystroke costs one cache read and
+// an in-memory scan. The index is rebuilt after its TTL; a trade logged a minute ago may
+// take that long to show up, which is fine for navigation.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use libsql::{params, Connection};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::service::cache_service::CacheService;
+use crate::turso::redis::ttl;
+
+pub const DEFAULT_LIMIT: usize = 20;
+pub const MAX_LIMIT: usize = 50;
+
+/// Most recent rows of each kind kept in the index
+const MAX_TRADES: i64 = 1000;
+const MAX_NOTES: i64 = 500;
+const MAX_CHATS: i64 = 25;
+
+/// Settings and app actions: id, title, extra search words
+const ACTIONS: &[(&str, &str, &str)] = &[
+    ("new_trade", "Log a new trade", "add create stock option entry"),
+    ("new_note", "New note", "create notebook journal write"),
+    ("new_chat", "Ask the AI coach", "chat assistant question"),
+    ("settings.profile", "Profile settings", "account name timezone currency language locale"),
+    ("settings.ai_persona", "AI coaching persona", "tone voice coach risk manager quant"),
+    ("settings.notifications", "Notification settings", "push alerts webhooks telegram email"),
+    ("settings.brokerage", "Connect a broker", "brokerage import sync snaptrade"),
+    ("settings.integrations", "Integrations", "notion calendar connect"),
+    ("settings.retention", "Data retention", "cleanup delete old"),
+    ("goals", "Monthly goal", "target pacing pnl"),
+    ("statements", "Monthly statements", "pdf report download"),
+    ("archives", "Archive old trades", "cold storage restore"),
+];
+
+/// One navigable thing in the cached index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteItem {
+    /// `stock`, `option`, `note`, `playbook`, `chat` or `action`
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// Trade date for trades, last activity otherwise; drives recency ordering
+    pub date: Option<String>,
+    /// Lowercased extra words the item matches on
+    pub keywords: String,
+}
+
+/// A palette result as returned to the client
+#[derive(Debug, Clone, Serialize)]
+pub struct PaletteResult {
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub date: Option<String>,
+}
+
+impl From<&PaletteItem> for PaletteResult {
+    fn from(item: &PaletteItem) -> Self {
+        Self { kind: item.kind.clone(), id: item.id.clone(), title: item.title.clone(), subtitle: item.subtitle.clone(), date: item.date.clone() }
+    }
+}
+
+impl PaletteItem {
+    fn new(kind: &str, id: String, title: String, subtitle: Option<String>, date: Option<String>, keywords: String) -> Self {
+        Self { kind: kind.to_string(), id, title, subtitle, date, keywords: keywords.to_lowercase() }
+    }
+}
+
+/// Weight of each kind when match quality ties
+fn kind_boost(kind: &str) -> u32 {
+    match kind {
+        "action" => 40,
+        "stock" | "option" => 30,
+        "playbook" => 25,
+        "note" => 20,
+        _ => 10,
+    }
+}
+
+fn match_score(query: &str, item: &PaletteItem) -> u32 {
+    let title = item.title.to_lowercase();
+    if title == query {
+        return 1000;
+    }
+    if title.starts_with(query) {
+        return 800;
+    }
+    if title.split_whitespace().any(|word| word.starts_with(query)) {
+        return 600;
+    }
+    if item.keywords.split_whitespace().any(|word| word.starts_with(query)) {
+        return 450;
+    }
+    if title.contains(query) {
+        return 400;
+    }
+    // Every word of a multi-word query somewhere in the title or keywords
+    let words: Vec<&str> = query.split_whitespace().collect();
+    if words.len() > 1 && words.iter().all(|w| title.contains(w) || item.keywords.contains(w)) {
+        return 350;
+    }
+    0
+}
+
+/// A query that reads as a date, e.g. `2026-10-14` or `10/14`
+fn parse_date_query(query: &str, current_year: i32) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(query, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let (month, day) = query.split_once('/')?;
+    NaiveDate::from_ymd_opt(current_year, month.parse().ok()?, day.parse().ok()?)
+}
+
+/// Best matches first; ties go to the more useful kind, then the more recent item
+pub fn rank(query: &str, items: &[PaletteItem], today: NaiveDate, limit: usize) -> Vec<PaletteResult> {
+    let query = query.trim().to_lowercase();
+    let date = parse_date_query(&query, chrono::Datelike::year(&today)).map(|d| d.to_string());
+    let mut scored: Vec<(u32, &PaletteItem)> = items
+        .iter()
+        .filter_map(|item| {
+            let score = if query.is_empty() {
+                // Nothing typed yet: recent chats, then actions, then the latest trades
+                if item.kind == "chat" { 100 } else { kind_boost(&item.kind) }
+            } else if let Some(date) = &date {
+                if item.date.as_deref().is_some_and(|d| d.starts_with(date.as_str())) { 900 } else { 0 }
+            } else {
+                match_score(&query, item)
+            };
+            (score > 0).then(|| (score + if query.is_empty() { 0 } else { kind_boost(&item.kind) }, item))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| b.1.date.cmp(&a.1.date)));
+    scored.into_iter().take(limit).map(|(_, item)| PaletteResult::from(item)).collect()
+}
+
+async fn collect(conn: &Connection, sql: &str, limit: i64, mut build: impl FnMut(&libsql::Row) -> Result<PaletteItem>) -> Result<Vec<PaletteItem>> {
+    let mut rows = conn.prepare(sql).await?.query(params![limit]).await?;
+    let mut items = Vec::new();
+    while let Some(row) = rows.next().await? {
+        items.push(build(&row)?);
+    }
+    Ok(items)
+}
+
+/// Everything the palette can jump to, read from the journal
+pub async fn build_index(conn: &Connection) -> Result<Vec<PaletteItem>> {
+    let mut items: Vec<PaletteItem> = ACTIONS
+        .iter()
+        .map(|(id, title, keywords)| PaletteItem::new("action", id.to_string(), title.to_string(), None, None, keywords.to_string()))
+        .collect();
+
+    items.extend(
+        collect(
+            conn,
+            "SELECT id, symbol, trade_type, date(entry_date), exit_date IS NULL FROM stocks WHERE is_deleted = 0 ORDER BY entry_date DESC LIMIT ?",
+            MAX_TRADES,
+            |row| {
+                let symbol: String = row.get(1)?;
+                let side: String = row.get(2)?;
+                let open = row.get::<i64>(4)? != 0;
+                Ok(PaletteItem::new(
+                    "stock",
+                    row.get::<i64>(0)?.to_string(),
+                    symbol.clone(),
+                    Some(format!("{} stock{}", side, if open { " (open)" } else { "" })),
+                    row.get(3)?,
+                    format!("{} {} stock trade {}", symbol, side, if open { "open" } else { "closed" }),
+                ))
+            },
+        )
+        .await?,
+    );
+    items.extend(
+        collect(
+            conn,
+            "SELECT id, symbol, option_type, strategy_type, date(entry_date), status FROM options WHERE is_deleted = 0 ORDER BY entry_date DESC LIMIT ?",
+            MAX_TRADES,
+            |row| {
+                let symbol: String = row.get(1)?;
+                let option_type: String = row.get(2)?;
+                let strategy: Option<String> = row.get(3)?;
+                let status: String = row.get(5)?;
+                Ok(PaletteItem::new(
+                    "option",
+                    row.get::<i64>(0)?.to_string(),
+                    symbol.clone(),
+                    Some(format!("{} {} ({})", strategy.as_deref().unwrap_or("Option"), option_type, status)),
+                    row.get(4)?,
+                    format!("{} {} {} option trade {}", symbol, option_type, strategy.unwrap_or_default(), status),
+                ))
+            },
+        )
+        .await?,
+    );
+    items.extend(
+        collect(
+            conn,
+            "SELECT id, title, updated_at FROM notebook_notes WHERE is_deleted = 0 ORDER BY updated_at DESC LIMIT ?",
+            MAX_NOTES,
+            |row| Ok(PaletteItem::new("note", row.get(0)?, row.get(1)?, Some("Note".to_string()), row.get(2)?, "note notebook".to_string())),
+        )
+        .await?,
+    );
+    items.extend(
+        collect(conn, "SELECT id, name, description, updated_at FROM playbook ORDER BY name LIMIT ?", MAX_NOTES, |row| {
+            let description: Option<String> = row.get(2)?;
+            Ok(PaletteItem::new("playbook", row.get(0)?, row.get(1)?, Some("Playbook".to_string()), row.get(3)?, format!("playbook setup {}", description.unwrap_or_default())))
+        })
+        .await?,
+    );
+    items.extend(
+        collect(
+            conn,
+            "SELECT id, COALESCE(title, 'Untitled chat'), COALESCE(last_message_at, updated_at) FROM chat_sessions ORDER BY COALESCE(last_message_at, updated_at) DESC LIMIT ?",
+            MAX_CHATS,
+            |row| Ok(PaletteItem::new("chat", row.get(0)?, row.get(1)?, Some("AI chat".to_string()), row.get(2)?, "chat conversation ai".to_string())),
+        )
+        .await?,
+    );
+    Ok(items)
+}
+
+fn index_key(user_id: &str) -> String {
+    format!("db:{}:command_palette:index", user_id)
+}
+
+/// Ranked palette results; falls back to reading the journal when Redis is unavailable
+pub async fn search(conn: &Connection, cache: &CacheService, user_id: &str, query: &str, limit: usize, today: NaiveDate) -> Result<Vec<PaletteResult>> {
+    let index = match cache.get_or_fetch(&index_key(user_id), ttl::COMMAND_PALETTE as u64, || build_index(conn)).await {
+        Ok(index) => index,
+        Err(e) => {
+            warn!("Command palette cache unavailable for user {}: {}", user_id, e);
+            build_index(conn).await?
+        }
+    };
+    Ok(rank(query, &index, today, limit.clamp(1, MAX_LIMIT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(kind: &str, id: &str, title: &str, date: Option<&str>, keywords: &str) -> PaletteItem {
+        PaletteItem::new(kind, id.to_string(), title.to_string(), None, date.map(str::to_string), keywords.to_string())
+    }
+
+    #[test]
+    fn test_rank_by_match_kind_and_recency() {
+        let today = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let items = vec![
+            item("stock", "1", "AAPL", Some("2026-09-01"), "aapl buy stock trade closed"),
+            item("stock", "2", "AAPL", Some("2026-10-14"), "aapl buy stock trade open"),
+            item("note", "n1", "Apple earnings plan", Some("2026-10-01"), "note notebook"),
+            item("action", "settings.ai_persona", "AI coaching persona", None, "tone voice coach"),
+            item("chat", "c1", "Why did I overtrade?", Some("2026-10-15"), "chat conversation ai"),
+        ];
+
+        let ids = |q: &str| rank(q, &items, today, 10).into_iter().map(|i| i.id).collect::<Vec<_>>();
+        assert_eq!(ids("aapl"), ["2", "1"]);
+        assert_eq!(ids("app"), ["n1"]);
+        assert_eq!(ids("tone"), ["settings.ai_persona"]);
+        assert_eq!(ids("10/14"), ["2"]);
+        assert_eq!(ids("")[0], "c1");
+        assert!(ids("zzz").is_empty());
+    }
+}
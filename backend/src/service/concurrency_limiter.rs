@@ -0,0 +1,117 @@
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Heavy computations one user may run at once
+const DEFAULT_MAX_CONCURRENT: usize = 4;
+/// Requests that may wait for a slot before further ones are rejected
+const DEFAULT_MAX_QUEUED: usize = 8;
+/// Longest a queued request waits for a slot
+const QUEUE_WAIT: Duration = Duration::from_secs(10);
+
+/// Error type for concurrency limiting
+#[derive(Debug, thiserror::Error)]
+pub enum ConcurrencyError {
+    #[error("Too many concurrent requests, retry after {retry_after_secs}s")]
+    Busy { retry_after_secs: u64 },
+}
+
+#[derive(Debug)]
+struct TenantSlots {
+    semaphore: Arc<Semaphore>,
+    waiting: AtomicUsize,
+}
+
+/// Per-user semaphore for heavy analytics and AI work
+///
+/// Each user gets `max_concurrent` slots. Requests beyond that queue for up to
+/// `QUEUE_WAIT`, and once `max_queued` requests are already waiting new ones are turned
+/// away immediately, so one user's refresh storm can't occupy the whole server.
+#[derive(Debug)]
+pub struct TenantConcurrencyLimiter {
+    max_concurrent: usize,
+    max_queued: usize,
+    tenants: DashMap<String, Arc<TenantSlots>>,
+}
+
+/// Slot held for the duration of a request; dropping it frees the slot
+pub struct TenantPermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl TenantConcurrencyLimiter {
+    pub fn new(max_concurrent: usize, max_queued: usize) -> Self {
+        Self { max_concurrent: max_concurrent.max(1), max_queued, tenants: DashMap::new() }
+    }
+
+    /// Limits from `TENANT_MAX_CONCURRENT_REQUESTS` and `TENANT_MAX_QUEUED_REQUESTS`
+    pub fn from_env() -> Self {
+        let read = |name: &str, default: usize| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self::new(
+            read("TENANT_MAX_CONCURRENT_REQUESTS", DEFAULT_MAX_CONCURRENT),
+            read("TENANT_MAX_QUEUED_REQUESTS", DEFAULT_MAX_QUEUED),
+        )
+    }
+
+    /// Take one of the user's slots, waiting in the queue if there is room
+    pub async fn acquire(&self, user_id: &str) -> Result<TenantPermit, ConcurrencyError> {
+        let slots = self
+            .tenants
+            .entry(user_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(TenantSlots {
+                    semaphore: Arc::new(Semaphore::new(self.max_concurrent)),
+                    waiting: AtomicUsize::new(0),
+                })
+            })
+            .clone();
+
+        if let Ok(permit) = Arc::clone(&slots.semaphore).try_acquire_owned() {
+            return Ok(TenantPermit { _permit: permit });
+        }
+        if slots.waiting.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+            slots.waiting.fetch_sub(1, Ordering::SeqCst);
+            return Err(ConcurrencyError::Busy { retry_after_secs: 1 });
+        }
+        let acquired = tokio::time::timeout(QUEUE_WAIT, Arc::clone(&slots.semaphore).acquire_owned()).await;
+        slots.waiting.fetch_sub(1, Ordering::SeqCst);
+        match acquired {
+            Ok(Ok(permit)) => Ok(TenantPermit { _permit: permit }),
+            _ => Err(ConcurrencyError::Busy { retry_after_secs: QUEUE_WAIT.as_secs() }),
+        }
+    }
+
+    /// Forget users with no request in flight or queued
+    pub fn release_idle(&self, user_id: &str) {
+        self.tenants.remove_if(user_id, |_, slots| {
+            Arc::strong_count(slots) == 1
+                && slots.waiting.load(Ordering::SeqCst) == 0
+                && slots.semaphore.available_permits() == self.max_concurrent
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_rejects_once_queue_is_full() {
+        let limiter = Arc::new(TenantConcurrencyLimiter::new(1, 0));
+        let held = limiter.acquire("user-a").await.unwrap();
+
+        // No queue: a second request for the same user is turned away, others are unaffected
+        assert!(matches!(limiter.acquire("user-a").await, Err(ConcurrencyError::Busy { .. })));
+        let other = limiter.acquire("user-b").await.unwrap();
+
+        drop(held);
+        assert!(limiter.acquire("user-a").await.is_ok());
+        drop(other);
+        limiter.release_idle("user-b");
+        assert!(!limiter.tenants.contains_key("user-b"));
+    }
+}
@@ -0,0 +1,357 @@
+// What each user costs us in vendor spend. Provider clients (OpenRouter, Voyage, the market
+// data upstream) record every billable call into the registry's `provider_cost_ledger`.
+// The clients don't know who they're serving, so the authenticated request's user id is
+// carried in a task-local set by the rate limit middleware; calls made outside a request
+// (cron jobs, background work that wasn't handed the user) land on the shared account and
+// are spread across users in the monthly report.
+//
+// Prices come from env (USD): OPENROUTER_INPUT_USD_PER_MTOK, OPENROUTER_OUTPUT_USD_PER_MTOK,
+// VOYAGE_USD_PER_MTOK, MARKET_DATA_USD_PER_CALL, and PLAN_PRICES_USD for the monthly price
+// of each plan tier (`free:0,pro:19`). OpenRouter `:free` models cost nothing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::turso::client::TursoClient;
+
+/// Ledger account for calls no user can be charged for
+pub const SHARED_USER: &str = "_shared";
+/// Tier of users without one set in the registry
+pub const DEFAULT_TIER: &str = "free";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    OpenRouter,
+    Voyage,
+    MarketData,
+}
+
+impl Provider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Provider::OpenRouter => "openrouter",
+            Provider::Voyage => "voyage",
+            Provider::MarketData => "market_data",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CostRates {
+    pub openrouter_input_per_mtok: f64,
+    pub openrouter_output_per_mtok: f64,
+    pub voyage_per_mtok: f64,
+    pub market_data_per_call: f64,
+    /// Monthly price per plan tier
+    pub plan_prices: HashMap<String, f64>,
+}
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok()).unwrap_or(default)
+}
+
+/// `free:0,pro:19` -> {free: 0, pro: 19}; malformed entries are skipped
+pub fn parse_plan_prices(raw: &str) -> HashMap<String, f64> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (tier, price) = entry.split_once(':')?;
+            Some((tier.trim().to_lowercase(), price.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+impl CostRates {
+    pub fn from_env() -> Self {
+        Self {
+            openrouter_input_per_mtok: env_f64("OPENROUTER_INPUT_USD_PER_MTOK", 0.15),
+            openrouter_output_per_mtok: env_f64("OPENROUTER_OUTPUT_USD_PER_MTOK", 0.60),
+            voyage_per_mtok: env_f64("VOYAGE_USD_PER_MTOK", 0.06),
+            market_data_per_call: env_f64("MARKET_DATA_USD_PER_CALL", 0.0),
+            plan_prices: parse_plan_prices(&std::env::var("PLAN_PRICES_USD").unwrap_or_else(|_| "free:0".to_string())),
+        }
+    }
+
+    pub fn openrouter_cost(&self, model: &str, input_tokens: u64, output_tokens: u64) -> f64 {
+        if model.ends_with(":free") {
+            return 0.0;
+        }
+        (input_tokens as f64 * self.openrouter_input_per_mtok + output_tokens as f64 * self.openrouter_output_per_mtok) / 1_000_000.0
+    }
+
+    pub fn voyage_cost(&self, tokens: u64) -> f64 {
+        tokens as f64 * self.voyage_per_mtok / 1_000_000.0
+    }
+
+    pub fn plan_price(&self, tier: &str) -> f64 {
+        self.plan_prices.get(tier).copied().unwrap_or(0.0)
+    }
+}
+
+static LEDGER: OnceLock<Arc<TursoClient>> = OnceLock::new();
+static RATES: OnceLock<CostRates> = OnceLock::new();
+
+tokio::task_local! {
+    static COST_USER: String;
+}
+
+/// Enable cost recording; called once at startup
+pub fn install(turso_client: Arc<TursoClient>) {
+    let _ = LEDGER.set(turso_client);
+}
+
+pub fn rates() -> &'static CostRates {
+    RATES.get_or_init(CostRates::from_env)
+}
+
+/// Run `fut` with provider costs charged to `user_id`
+pub async fn attribute_to<F: Future>(user_id: String, fut: F) -> F::Output {
+    COST_USER.scope(user_id, fut).await
+}
+
+/// User the current task's provider calls are charged to, if any
+pub fn current_user() -> Option<String> {
+    COST_USER.try_with(|user| user.clone()).ok()
+}
+
+/// Record a billable call for the current task's user
+pub fn record(provider: Provider, operation: &str, input_units: u64, output_units: u64, cost_usd: f64) {
+    record_for(current_user(), provider, operation, input_units, output_units, cost_usd);
+}
+
+/// Record a billable call for a user captured earlier (e.g. before spawning a task)
+pub fn record_for(user_id: Option<String>, provider: Provider, operation: &str, input_units: u64, output_units: u64, cost_usd: f64) {
+    let Some(turso_client) = LEDGER.get().cloned() else { return };
+    let user_id = user_id.unwrap_or_else(|| SHARED_USER.to_string());
+    let operation = operation.to_string();
+    tokio::spawn(async move {
+        let result = match turso_client.get_registry_connection().await {
+            Ok(conn) => conn
+                .execute(
+                    "INSERT INTO provider_cost_ledger (id, user_id, provider, operation, input_units, output_units, cost_usd, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                    params![
+                        Uuid::new_v4().to_string(),
+                        user_id,
+                        provider.as_str(),
+                        operation,
+                        input_units as i64,
+                        output_units as i64,
+                        cost_usd,
+                        Utc::now().to_rfc3339()
+                    ],
+                )
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to record {} cost: {}", provider.as_str(), e);
+        }
+    });
+}
+
+/// One user's vendor spend for the month
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct UserCost {
+    pub user_id: String,
+    pub tier: String,
+    pub requests: u64,
+    /// Cost per provider name
+    pub by_provider: BTreeMap<String, f64>,
+    pub cost_usd: f64,
+    pub revenue_usd: f64,
+    pub gross_margin_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TierCost {
+    pub tier: String,
+    pub users: usize,
+    pub revenue_usd: f64,
+    /// Costs charged to the tier's users
+    pub direct_cost_usd: f64,
+    /// The tier's per-user share of shared costs
+    pub shared_cost_usd: f64,
+    pub gross_margin_usd: f64,
+    /// Margin over revenue; None for tiers without revenue
+    pub gross_margin_pct: Option<f64>,
+    pub cost_per_user_usd: f64,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CostReport {
+    pub month: String,
+    pub total_cost_usd: f64,
+    pub shared_cost_usd: f64,
+    pub total_revenue_usd: f64,
+    pub by_provider: BTreeMap<String, f64>,
+    pub tiers: Vec<TierCost>,
+    /// Users by cost, most expensive first
+    pub users: Vec<UserCost>,
+}
+
+/// A ledger total: (user_id, provider, requests, cost_usd)
+pub type LedgerTotal = (String, String, u64, f64);
+
+/// Monthly report from each user's tier and ledger totals
+pub fn build_report(month: &str, user_tiers: &[(String, String)], totals: &[LedgerTotal], rates: &CostRates, max_users: usize) -> CostReport {
+    let mut users: BTreeMap<&str, UserCost> = user_tiers
+        .iter()
+        .map(|(user_id, tier)| {
+            let revenue = rates.plan_price(tier);
+            (
+                user_id.as_str(),
+                UserCost {
+                    user_id: user_id.clone(),
+                    tier: tier.clone(),
+                    requests: 0,
+                    by_provider: BTreeMap::new(),
+                    cost_usd: 0.0,
+                    revenue_usd: revenue,
+                    gross_margin_usd: revenue,
+                },
+            )
+        })
+        .collect();
+
+    let mut by_provider: BTreeMap<String, f64> = BTreeMap::new();
+    let mut shared_cost = 0.0;
+    for (user_id, provider, requests, cost) in totals {
+        *by_provider.entry(provider.clone()).or_default() += cost;
+        // Costs of users no longer in the registry count as shared
+        let Some(user) = users.get_mut(user_id.as_str()) else {
+            shared_cost += cost;
+            continue;
+        };
+        user.requests += requests;
+        *user.by_provider.entry(provider.clone()).or_default() += cost;
+        user.cost_usd += cost;
+        user.gross_margin_usd = user.revenue_usd - user.cost_usd;
+    }
+
+    let user_count = users.len().max(1) as f64;
+    let mut tiers: BTreeMap<&str, TierCost> = BTreeMap::new();
+    for user in users.values() {
+        let tier = tiers.entry(user.tier.as_str()).or_insert_with(|| TierCost {
+            tier: user.tier.clone(),
+            users: 0,
+            revenue_usd: 0.0,
+            direct_cost_usd: 0.0,
+            shared_cost_usd: 0.0,
+            gross_margin_usd: 0.0,
+            gross_margin_pct: None,
+            cost_per_user_usd: 0.0,
+        });
+        tier.users += 1;
+        tier.revenue_usd += user.revenue_usd;
+        tier.direct_cost_usd += user.cost_usd;
+    }
+    let tiers: Vec<TierCost> = tiers
+        .into_values()
+        .map(|mut tier| {
+            tier.shared_cost_usd = shared_cost * tier.users as f64 / user_count;
+            let cost = tier.direct_cost_usd + tier.shared_cost_usd;
+            tier.gross_margin_usd = tier.revenue_usd - cost;
+            tier.gross_margin_pct = (tier.revenue_usd > 0.0).then(|| tier.gross_margin_usd / tier.revenue_usd * 100.0);
+            tier.cost_per_user_usd = cost / tier.users.max(1) as f64;
+            tier
+        })
+        .collect();
+
+    let mut users: Vec<UserCost> = users.into_values().collect();
+    users.sort_by(|a, b| b.cost_usd.partial_cmp(&a.cost_usd).unwrap_or(std::cmp::Ordering::Equal));
+    users.truncate(max_users);
+
+    CostReport {
+        month: month.to_string(),
+        total_cost_usd: by_provider.values().sum(),
+        shared_cost_usd: shared_cost,
+        total_revenue_usd: tiers.iter().map(|t| t.revenue_usd).sum(),
+        by_provider,
+        tiers,
+        users,
+    }
+}
+
+/// Set a user's plan tier; false when the user isn't registered
+pub async fn set_plan_tier(registry: &Connection, user_id: &str, tier: &str) -> Result<bool> {
+    let updated = registry
+        .execute("UPDATE user_databases SET plan_tier = ? WHERE user_id = ?", params![tier.trim().to_lowercase(), user_id])
+        .await?;
+    Ok(updated > 0)
+}
+
+/// Cost and margin report for a month (`YYYY-MM`)
+pub async fn monthly_report(registry: &Connection, month: &str, max_users: usize) -> Result<CostReport> {
+    let mut rows = registry
+        .prepare("SELECT user_id, COALESCE(plan_tier, ?) FROM user_databases WHERE is_active = 1")
+        .await?
+        .query(params![DEFAULT_TIER])
+        .await?;
+    let mut user_tiers = Vec::new();
+    while let Some(row) = rows.next().await? {
+        user_tiers.push((row.get::<String>(0)?, row.get::<String>(1)?.to_lowercase()));
+    }
+
+    let mut rows = registry
+        .prepare(
+            "SELECT user_id, provider, COUNT(*), COALESCE(SUM(cost_usd), 0) FROM provider_cost_ledger
+             WHERE substr(created_at, 1, 7) = ? GROUP BY user_id, provider",
+        )
+        .await?
+        .query(params![month])
+        .await?;
+    let mut totals = Vec::new();
+    while let Some(row) = rows.next().await? {
+        totals.push((row.get::<String>(0)?, row.get::<String>(1)?, row.get::<i64>(2)?.max(0) as u64, row.get::<f64>(3)?));
+    }
+
+    Ok(build_report(month, &user_tiers, &totals, rates(), max_users))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_report_margins() {
+        let rates = CostRates {
+            openrouter_input_per_mtok: 1.0,
+            openrouter_output_per_mtok: 2.0,
+            voyage_per_mtok: 0.1,
+            market_data_per_call: 0.0,
+            plan_prices: parse_plan_prices("free:0, pro:20,bad"),
+        };
+        assert_eq!(rates.openrouter_cost("openai/gpt-4o-mini", 1_000_000, 500_000), 2.0);
+        assert_eq!(rates.openrouter_cost("deepseek/deepseek-r1:free", 1_000_000, 500_000), 0.0);
+
+        let user_tiers = vec![("a".to_string(), "pro".to_string()), ("b".to_string(), "free".to_string())];
+        let totals = vec![
+            ("a".to_string(), "openrouter".to_string(), 10, 3.0),
+            ("a".to_string(), "voyage".to_string(), 5, 1.0),
+            ("b".to_string(), "openrouter".to_string(), 2, 0.5),
+            (SHARED_USER.to_string(), "market_data".to_string(), 100, 2.0),
+        ];
+        let report = build_report("2026-10", &user_tiers, &totals, &rates, 10);
+
+        assert_eq!(report.total_cost_usd, 6.5);
+        assert_eq!(report.shared_cost_usd, 2.0);
+        assert_eq!(report.total_revenue_usd, 20.0);
+        assert_eq!(report.users[0].user_id, "a");
+        assert_eq!(report.users[0].gross_margin_usd, 16.0);
+
+        let pro = report.tiers.iter().find(|t| t.tier == "pro").unwrap();
+        // 4.0 direct plus half of the shared 2.0
+        assert_eq!(pro.gross_margin_usd, 15.0);
+        assert_eq!(pro.gross_margin_pct, Some(75.0));
+        let free = report.tiers.iter().find(|t| t.tier == "free").unwrap();
+        assert_eq!((free.gross_margin_usd, free.gross_margin_pct), (-1.5, None));
+    }
+}
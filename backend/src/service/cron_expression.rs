@@ -0,0 +1,192 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+
+/// How far ahead `next_after` searches before giving up (covers Feb 29 schedules)
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 5;
+
+/// Five-field cron expression (`minute hour day-of-month month day-of-week`), evaluated in UTC.
+///
+/// Supports `*`, lists (`1,15`), ranges (`1-5`), steps (`*/15`, `0-30/10`), month and weekday
+/// names (`JAN`, `MON`), `7` as Sunday, the `@hourly`/`@daily`/`@weekly`/`@monthly` macros and
+/// `weekday#n` for the n-th weekday of the month (`0 9 * * SUN#1` = first Sunday at 09:00).
+/// As in standard cron, when both day fields are restricted a day matching either one runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpression {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// `(weekday, n)` from the `#` syntax
+    nth_weekday: Option<(u32, u32)>,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+const MONTH_NAMES: &[&str] = &["JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC"];
+const WEEKDAY_NAMES: &[&str] = &["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+impl CronExpression {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expanded = match expression.trim().to_lowercase().as_str() {
+            "@hourly" => "0 * * * *".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            _ => expression.trim().to_uppercase(),
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(format!("expected 5 fields (minute hour day month weekday), got {}", fields.len()));
+        };
+
+        let (days_of_week, nth_weekday) = match day_of_week.split_once('#') {
+            Some((weekday, n)) => {
+                let weekday = parse_value(weekday, 0, 7, WEEKDAY_NAMES, 0)? % 7;
+                let n: u32 = n.parse().map_err(|_| format!("invalid weekday occurrence '{}'", n))?;
+                if !(1..=5).contains(&n) {
+                    return Err("weekday occurrence after '#' must be between 1 and 5".to_string());
+                }
+                (1 << weekday, Some((weekday, n)))
+            }
+            None => {
+                let mask = parse_field(day_of_week, 0, 7, WEEKDAY_NAMES, 0)?;
+                // 7 is an alias for Sunday
+                let mask = if mask & (1 << 7) != 0 { (mask | 1) & !(1 << 7) } else { mask };
+                (mask, None)
+            }
+        };
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0)?,
+            hours: parse_field(hour, 0, 23, &[], 0)?,
+            days_of_month: parse_field(day_of_month, 1, 31, &[], 0)?,
+            months: parse_field(month, 1, 12, MONTH_NAMES, 1)?,
+            days_of_week,
+            nth_weekday,
+            day_of_month_restricted: day_of_month != "*",
+            day_of_week_restricted: day_of_week != "*",
+        })
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let weekday = date.weekday().num_days_from_sunday();
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = match self.nth_weekday {
+            Some((target, n)) => weekday == target && (date.day() - 1) / 7 + 1 == n,
+            None => self.days_of_week & (1 << weekday) != 0,
+        };
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+
+    /// First run strictly after `after`, or None if the expression never fires (e.g. Feb 30)
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let first_date = start.date_naive();
+        for offset in 0..MAX_LOOKAHEAD_DAYS {
+            let date = first_date + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                for minute in (0..60).filter(|m| self.minutes & (1u64 << m) != 0) {
+                    let candidate = date.and_hms_opt(hour, minute, 0)?.and_utc();
+                    if candidate >= start {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parse one field into a bitmask of allowed values
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], name_offset: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be greater than zero".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (parse_value(a, min, max, names, name_offset)?, parse_value(b, min, max, names, name_offset)?),
+                // `5/10` means from 5 to the end in steps of 10
+                None if part.contains('/') => (parse_value(range, min, max, names, name_offset)?, max),
+                None => {
+                    let value = parse_value(range, min, max, names, name_offset)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range '{}' is reversed", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str], name_offset: u32) -> Result<u32, String> {
+    let parsed = match names.iter().position(|n| *n == value) {
+        Some(index) => index as u32 + name_offset,
+        None => value.parse().map_err(|_| format!("invalid value '{}'", value))?,
+    };
+    if parsed < min || parsed > max {
+        return Err(format!("value {} is outside {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, min, 0).unwrap()
+    }
+
+    #[test]
+    fn test_first_sunday_of_month() {
+        let cron = CronExpression::parse("0 9 * * SUN#1").unwrap();
+        // Sunday March 2 2025 is the first Sunday of March
+        assert_eq!(cron.next_after(at(2025, 2, 10, 0, 0)), Some(at(2025, 3, 2, 9, 0)));
+        assert_eq!(cron.next_after(at(2025, 3, 2, 9, 0)), Some(at(2025, 4, 6, 9, 0)));
+    }
+
+    #[test]
+    fn test_steps_lists_and_macros() {
+        let cron = CronExpression::parse("*/15 8-9 * * 1-5").unwrap();
+        // Friday 09:50 → next is Monday 08:00
+        assert_eq!(cron.next_after(at(2025, 3, 7, 9, 50)), Some(at(2025, 3, 10, 8, 0)));
+        assert_eq!(CronExpression::parse("@monthly").unwrap().next_after(at(2025, 1, 15, 0, 0)), Some(at(2025, 2, 1, 0, 0)));
+        assert_eq!(CronExpression::parse("0 0 * * 7").unwrap(), CronExpression::parse("0 0 * * 0").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!(CronExpression::parse("0 9 * *").is_err());
+        assert!(CronExpression::parse("60 * * * *").is_err());
+        assert!(CronExpression::parse("0 0 * * MON#6").is_err());
+        assert!(CronExpression::parse("*/0 * * * *").is_err());
+        assert_eq!(CronExpression::parse("0 0 30 2 *").unwrap().next_after(at(2025, 1, 1, 0, 0)), None);
+    }
+}
@@ -0,0 +1,466 @@
+// CSV trade import in two steps. A preview stores the uploaded file in `import_previews`
+// for PREVIEW_TTL_HOURS and returns its columns, a suggested column mapping, a few rows
+// parsed under that mapping and validation warnings; the preview id is the mapping token.
+// Commit takes the token with the mapping the user settled on, parses every row and
+// imports them as stock trades in one transaction. Broker exports rarely carry a timezone,
+// so times without an offset are read at the offset the client sends.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::models::money;
+use crate::models::stock::stocks::{CreateStockRequest, OrderType, Stock, TradeType, UpdateStockRequest};
+use crate::turso::in_transaction;
+
+pub const PREVIEW_TTL_HOURS: i64 = 1;
+pub const MAX_IMPORT_BYTES: usize = 5 * 1024 * 1024;
+pub const MAX_IMPORT_ROWS: usize = 5_000;
+const SAMPLE_ROWS: usize = 10;
+
+/// Trade field -> CSV column header
+pub type ColumnMapping = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportField {
+    pub name: &'static str,
+    pub required: bool,
+    /// Normalized headers the field is suggested for
+    #[serde(skip)]
+    aliases: &'static [&'static str],
+}
+
+pub const FIELDS: &[ImportField] = &[
+    ImportField { name: "symbol", required: true, aliases: &["symbol", "ticker", "underlying", "instrument", "security"] },
+    ImportField { name: "trade_type", required: true, aliases: &["side", "action", "tradetype", "type", "direction", "buysell"] },
+    ImportField { name: "quantity", required: true, aliases: &["quantity", "qty", "shares", "size", "numbershares", "filledqty"] },
+    ImportField { name: "entry_price", required: true, aliases: &["entryprice", "price", "avgprice", "fillprice", "openprice", "buyprice"] },
+    ImportField { name: "entry_date", required: true, aliases: &["entrydate", "date", "time", "datetime", "opendate", "tradedate", "executiontime", "filledtime"] },
+    ImportField { name: "exit_price", required: false, aliases: &["exitprice", "closeprice", "sellprice"] },
+    ImportField { name: "exit_date", required: false, aliases: &["exitdate", "closedate", "closetime", "exittime"] },
+    ImportField { name: "stop_loss", required: false, aliases: &["stoploss", "stop", "sl"] },
+    ImportField { name: "take_profit", required: false, aliases: &["takeprofit", "target", "tp"] },
+    ImportField { name: "commissions", required: false, aliases: &["commissions", "commission", "fees", "fee", "comm"] },
+    ImportField { name: "order_type", required: false, aliases: &["ordertype"] },
+    ImportField { name: "brokerage_name", required: false, aliases: &["broker", "brokerage", "account"] },
+    ImportField { name: "notes", required: false, aliases: &["notes", "note", "comment", "comments", "mistakes"] },
+];
+
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase()
+}
+
+/// Split CSV text into records: quoted fields, doubled quotes and CRLF line ends.
+/// Blank lines are dropped.
+pub fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let (mut records, mut record, mut field) = (Vec::new(), Vec::new(), String::new());
+    let mut chars = text.chars().peekable();
+    let mut in_quotes = false;
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                if record.iter().any(|f| !f.trim().is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quoted field".to_string());
+    }
+    record.push(field);
+    if record.iter().any(|f| !f.trim().is_empty()) {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// First matching column for each field, by header alias
+pub fn suggest_mapping(headers: &[String]) -> ColumnMapping {
+    let mut mapping = ColumnMapping::new();
+    for field in FIELDS {
+        let found = field.aliases.iter().find_map(|alias| {
+            headers.iter().find(|h| normalize_header(h) == *alias && !mapping.values().any(|used| used == *h))
+        });
+        if let Some(header) = found {
+            mapping.insert(field.name.to_string(), header.clone());
+        }
+    }
+    mapping
+}
+
+/// Problems with the mapping itself; any of these block a commit
+pub fn mapping_errors(headers: &[String], mapping: &ColumnMapping) -> Vec<String> {
+    let mut errors = Vec::new();
+    for (field, column) in mapping {
+        if !FIELDS.iter().any(|f| f.name == field.as_str()) {
+            errors.push(format!("Unknown field '{}'", field));
+        } else if !headers.contains(column) {
+            errors.push(format!("Column '{}' mapped to {} is not in the file", column, field));
+        }
+    }
+    for field in FIELDS.iter().filter(|f| f.required && !mapping.contains_key(f.name)) {
+        errors.push(format!("No column mapped to required field {}", field.name));
+    }
+    errors
+}
+
+fn parse_number(raw: &str) -> Option<f64> {
+    let cleaned: String = raw.trim().chars().filter(|c| !matches!(c, '$' | ',' | ' ')).collect();
+    match cleaned.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(inner) => inner.parse::<f64>().ok().map(|v| -v),
+        None => cleaned.parse().ok(),
+    }
+}
+
+fn parse_trade_type(raw: &str) -> Option<TradeType> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "buy" | "b" | "long" | "bot" | "bought" | "buy to open" => Some(TradeType::BUY),
+        "sell" | "s" | "short" | "sld" | "sold" | "sell short" | "sell to open" => Some(TradeType::SELL),
+        _ => None,
+    }
+}
+
+fn parse_order_type(raw: &str) -> Option<OrderType> {
+    match normalize_header(raw).as_str() {
+        "market" | "mkt" => Some(OrderType::MARKET),
+        "limit" | "lmt" => Some(OrderType::LIMIT),
+        "stop" | "stp" => Some(OrderType::STOP),
+        "stoplimit" | "stplmt" => Some(OrderType::StopLimit),
+        _ => None,
+    }
+}
+
+/// RFC3339 as is; common broker formats without an offset are read at `offset`
+fn parse_timestamp(raw: &str, offset: FixedOffset) -> Option<DateTime<Utc>> {
+    let raw = raw.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(crate::models::timestamps::truncate_to_millis(dt.with_timezone(&Utc)));
+    }
+    let naive = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M", "%m/%d/%Y %H:%M:%S", "%m/%d/%Y %H:%M", "%m/%d/%Y %I:%M:%S %p", "%m/%d/%Y %I:%M %p"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%m/%d/%Y", "%Y%m%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    offset.from_local_datetime(&naive).single().map(|dt| crate::models::timestamps::truncate_to_millis(dt.with_timezone(&Utc)))
+}
+
+/// One row turned into a trade to create, plus its exit when the file has one
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedTrade {
+    pub symbol: String,
+    pub trade_type: TradeType,
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub entry_price: f64,
+    pub entry_date: DateTime<Utc>,
+    pub exit_price: Option<f64>,
+    pub exit_date: Option<DateTime<Utc>>,
+    pub stop_loss: f64,
+    pub take_profit: Option<f64>,
+    pub commissions: f64,
+    pub brokerage_name: Option<String>,
+    pub notes: Option<String>,
+}
+
+pub fn parse_row(headers: &[String], row: &[String], mapping: &ColumnMapping, offset: FixedOffset) -> Result<ImportedTrade, String> {
+    let value = |field: &str| -> Option<&str> {
+        let column = mapping.get(field)?;
+        let index = headers.iter().position(|h| h == column)?;
+        row.get(index).map(|v| v.trim()).filter(|v| !v.is_empty())
+    };
+    let required = |field: &str| value(field).ok_or_else(|| format!("{} is empty", field));
+    let number = |field: &str| -> Result<Option<f64>, String> {
+        value(field).map(|raw| parse_number(raw).ok_or_else(|| format!("{} '{}' is not a number", field, raw))).transpose()
+    };
+    let timestamp = |field: &str| -> Result<Option<DateTime<Utc>>, String> {
+        value(field).map(|raw| parse_timestamp(raw, offset).ok_or_else(|| format!("{} '{}' is not a recognized date", field, raw))).transpose()
+    };
+
+    let symbol = required("symbol")?.to_ascii_uppercase();
+    let side = required("trade_type")?;
+    let trade_type = parse_trade_type(side).ok_or_else(|| format!("trade_type '{}' is not buy or sell", side))?;
+    let quantity = number("quantity")?.ok_or("quantity is empty")?.abs();
+    if quantity == 0.0 {
+        return Err("quantity is zero".to_string());
+    }
+    let entry_price = number("entry_price")?.ok_or("entry_price is empty")?;
+    if entry_price <= 0.0 {
+        return Err("entry_price must be positive".to_string());
+    }
+    let entry_date = timestamp("entry_date")?.ok_or("entry_date is empty")?;
+    let exit_price = number("exit_price")?;
+    let exit_date = timestamp("exit_date")?;
+    if exit_price.is_some() != exit_date.is_some() {
+        return Err("exit_price and exit_date must both be set or both be empty".to_string());
+    }
+    if let Some(exit) = exit_date
+        && exit < entry_date
+    {
+        return Err("exit_date is before entry_date".to_string());
+    }
+    let order_type = match value("order_type") {
+        Some(raw) => parse_order_type(raw).ok_or_else(|| format!("order_type '{}' is not recognized", raw))?,
+        None => OrderType::MARKET,
+    };
+
+    Ok(ImportedTrade {
+        symbol,
+        trade_type,
+        order_type,
+        quantity,
+        entry_price,
+        entry_date,
+        exit_price,
+        exit_date,
+        stop_loss: number("stop_loss")?.unwrap_or(0.0),
+        take_profit: number("take_profit")?,
+        commissions: number("commissions")?.unwrap_or(0.0).abs(),
+        brokerage_name: value("brokerage_name").map(str::to_string),
+        notes: value("notes").map(str::to_string),
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RowError {
+    /// 1-based line in the file, counting the header
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SampleRow {
+    pub line: usize,
+    pub values: Vec<String>,
+    pub trade: Option<ImportedTrade>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPreview {
+    pub token: String,
+    pub expires_at: String,
+    pub columns: Vec<String>,
+    pub row_count: usize,
+    pub fields: &'static [ImportField],
+    pub suggested_mapping: ColumnMapping,
+    pub sample_rows: Vec<SampleRow>,
+    pub warnings: Vec<String>,
+}
+
+struct ParsedFile {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn split_file(content: &str) -> Result<ParsedFile, String> {
+    let mut records = parse_csv(content)?.into_iter();
+    let headers: Vec<String> = records.next().ok_or("The file is empty")?.into_iter().map(|h| h.trim().to_string()).collect();
+    let rows: Vec<Vec<String>> = records.collect();
+    if rows.is_empty() {
+        return Err("The file has a header but no rows".to_string());
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        return Err(format!("At most {} rows can be imported at once", MAX_IMPORT_ROWS));
+    }
+    Ok(ParsedFile { headers, rows })
+}
+
+/// Everything worth flagging before the user commits to a mapping
+fn preview_warnings(file: &ParsedFile, mapping: &ColumnMapping, offset: FixedOffset) -> Vec<String> {
+    let mut warnings = mapping_errors(&file.headers, mapping);
+    let mut seen = std::collections::HashSet::new();
+    for header in &file.headers {
+        if header.is_empty() {
+            warnings.push("A column has no header and can't be mapped".to_string());
+        } else if !seen.insert(header) {
+            warnings.push(format!("Column '{}' appears more than once; only the first is used", header));
+        }
+    }
+    let ragged = file.rows.iter().filter(|r| r.len() != file.headers.len()).count();
+    if ragged > 0 {
+        warnings.push(format!("{} rows have a different number of columns than the header", ragged));
+    }
+    if warnings.is_empty() {
+        let failing = file.rows.iter().filter(|r| parse_row(&file.headers, r, mapping, offset).is_err()).count();
+        if failing > 0 {
+            warnings.push(format!("{} of {} rows can't be imported with this mapping", failing, file.rows.len()));
+        }
+    }
+    warnings
+}
+
+/// Store an uploaded file and describe it under the suggested mapping
+pub async fn create_preview(conn: &Connection, file_name: Option<&str>, content: &str, utc_offset_minutes: i32) -> Result<Result<ImportPreview, String>> {
+    if content.len() > MAX_IMPORT_BYTES {
+        return Ok(Err(format!("Files over {} MB can't be imported", MAX_IMPORT_BYTES / 1024 / 1024)));
+    }
+    let Some(offset) = FixedOffset::east_opt(utc_offset_minutes * 60) else {
+        return Ok(Err("utc_offset_minutes is out of range".to_string()));
+    };
+    let file = match split_file(content) {
+        Ok(file) => file,
+        Err(message) => return Ok(Err(message)),
+    };
+    let mapping = suggest_mapping(&file.headers);
+    let sample_rows = file.rows.iter().take(SAMPLE_ROWS).enumerate().map(|(i, row)| {
+        let parsed = parse_row(&file.headers, row, &mapping, offset);
+        SampleRow { line: i + 2, values: row.clone(), error: parsed.as_ref().err().cloned(), trade: parsed.ok() }
+    }).collect();
+    let warnings = preview_warnings(&file, &mapping, offset);
+
+    let now = Utc::now();
+    let expires_at = (now + Duration::hours(PREVIEW_TTL_HOURS)).to_rfc3339();
+    conn.execute("DELETE FROM import_previews WHERE expires_at <= ?", params![now.to_rfc3339()]).await?;
+    let token = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO import_previews (id, file_name, content, row_count, created_at, expires_at) VALUES (?, ?, ?, ?, ?, ?)",
+        params![token.clone(), file_name, content, file.rows.len() as i64, now.to_rfc3339(), expires_at.clone()],
+    )
+    .await?;
+
+    Ok(Ok(ImportPreview {
+        token,
+        expires_at,
+        columns: file.headers.clone(),
+        row_count: file.rows.len(),
+        fields: FIELDS,
+        suggested_mapping: mapping,
+        sample_rows,
+        warnings,
+    }))
+}
+
+#[derive(Debug)]
+pub enum CommitOutcome {
+    Imported { trade_ids: Vec<i64>, skipped: Vec<RowError> },
+    /// Token unknown or expired
+    NotFound,
+    /// Nothing was imported
+    Invalid { errors: Vec<String>, row_errors: Vec<RowError> },
+}
+
+/// Import every row of a previewed file under `mapping`. Rows that don't parse fail the
+/// whole commit unless `skip_invalid` is set, in which case they are reported and skipped.
+pub async fn commit(conn: &Connection, token: &str, mapping: &ColumnMapping, utc_offset_minutes: i32, skip_invalid: bool) -> Result<CommitOutcome> {
+    let mut rows = conn
+        .query("SELECT content FROM import_previews WHERE id = ? AND expires_at > ?", params![token, Utc::now().to_rfc3339()])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(CommitOutcome::NotFound) };
+    let content: String = row.get(0)?;
+    let invalid = |errors: Vec<String>| CommitOutcome::Invalid { errors, row_errors: Vec::new() };
+
+    let Some(offset) = FixedOffset::east_opt(utc_offset_minutes * 60) else {
+        return Ok(invalid(vec!["utc_offset_minutes is out of range".to_string()]));
+    };
+    let file = match split_file(&content) {
+        Ok(file) => file,
+        Err(message) => return Ok(invalid(vec![message])),
+    };
+    let errors = mapping_errors(&file.headers, mapping);
+    if !errors.is_empty() {
+        return Ok(invalid(errors));
+    }
+
+    let mut trades = Vec::new();
+    let mut row_errors = Vec::new();
+    for (i, values) in file.rows.iter().enumerate() {
+        match parse_row(&file.headers, values, mapping, offset) {
+            Ok(trade) => trades.push(trade),
+            Err(message) => row_errors.push(RowError { line: i + 2, message }),
+        }
+    }
+    if trades.is_empty() || (!row_errors.is_empty() && !skip_invalid) {
+        return Ok(CommitOutcome::Invalid { errors: vec!["Some rows can't be imported".to_string()], row_errors });
+    }
+
+    let trade_ids = in_transaction(conn, async |tx: &Connection| -> Result<Vec<i64>> {
+        let mut trade_ids = Vec::with_capacity(trades.len());
+        for trade in trades {
+            let request = CreateStockRequest {
+                symbol: trade.symbol,
+                trade_type: trade.trade_type,
+                order_type: trade.order_type,
+                entry_price: money::from_f64(trade.entry_price),
+                stop_loss: money::from_f64(trade.stop_loss),
+                commissions: money::from_f64(trade.commissions),
+                number_shares: trade.quantity,
+                take_profit: trade.take_profit.map(money::from_f64),
+                initial_target: None,
+                profit_target: None,
+                trade_ratings: None,
+                entry_date: trade.entry_date,
+                reviewed: None,
+                mistakes: trade.notes,
+                brokerage_name: trade.brokerage_name,
+                venue: None,
+            };
+            let stock = Stock::create(tx, request).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+            if let (Some(exit_price), Some(exit_date)) = (trade.exit_price, trade.exit_date) {
+                let update = UpdateStockRequest { exit_price: Some(money::from_f64(exit_price)), exit_date: Some(exit_date), ..Default::default() };
+                Stock::update(tx, stock.id, update).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+            }
+            trade_ids.push(stock.id);
+        }
+        tx.execute("DELETE FROM import_previews WHERE id = ?", params![token]).await?;
+        Ok(trade_ids)
+    })
+    .await?;
+
+    Ok(CommitOutcome::Imported { trade_ids, skipped: row_errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_csv_quotes_and_blank_lines() {
+        let records = parse_csv("Symbol,Notes\r\nAAPL,\"gap, then \"\"fade\"\"\"\r\n\r\nMSFT,\n").unwrap();
+        assert_eq!(records, vec![strings(&["Symbol", "Notes"]), strings(&["AAPL", "gap, then \"fade\""]), strings(&["MSFT", ""])]);
+        assert!(parse_csv("a,\"b").is_err());
+    }
+
+    #[test]
+    fn test_suggested_mapping_parses_a_broker_row() {
+        let headers = strings(&["Date/Time", "Ticker", "Side", "Qty", "Avg Price", "Fees", "Memo"]);
+        let mapping = suggest_mapping(&headers);
+        assert_eq!(mapping.get("symbol").map(String::as_str), Some("Ticker"));
+        assert_eq!(mapping.get("entry_price").map(String::as_str), Some("Avg Price"));
+        assert!(mapping_errors(&headers, &mapping).is_empty());
+
+        let offset = FixedOffset::west_opt(4 * 3600).unwrap();
+        let row = strings(&["03/14/2025 09:30", "aapl", "BOT", "100", "$1,212.50", "(1.00)", ""]);
+        let trade = parse_row(&headers, &row, &mapping, offset).unwrap();
+        assert_eq!(trade.symbol, "AAPL");
+        assert!(matches!(trade.trade_type, TradeType::BUY));
+        assert_eq!(trade.entry_price, 1212.5);
+        assert_eq!(trade.commissions, 1.0);
+        assert_eq!(trade.entry_date.to_rfc3339(), "2025-03-14T13:30:00+00:00");
+
+        let bad = strings(&["03/14/2025 09:30", "AAPL", "hold", "100", "10", "", ""]);
+        assert!(parse_row(&headers, &bad, &mapping, offset).unwrap_err().contains("not buy or sell"));
+    }
+}
@@ -0,0 +1,312 @@
+// Home screen summary. Each widget is an independent query run concurrently; a failing
+// widget is logged and left out so one slow or broken source doesn't blank the screen.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::notebook::reminder::NotebookReminder;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::prop_firm::EvaluationProfile;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::core_metrics::streaks::{self, CurrentStreak};
+use crate::service::analytics_engine::prop_firm_evaluator::{evaluate_profile, EvaluationStatus};
+use crate::service::goal_pacing::{self, GoalPacing};
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::quotes::get_simple_quotes;
+
+/// Upcoming reminders shown on the home screen
+const NEXT_REMINDERS: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardWidget {
+    TodayPnl,
+    OpenPositions,
+    Streak,
+    GoalProgress,
+    Reminders,
+    LatestInsight,
+}
+
+impl DashboardWidget {
+    pub const ALL: [DashboardWidget; 6] = [
+        Self::TodayPnl,
+        Self::OpenPositions,
+        Self::Streak,
+        Self::GoalProgress,
+        Self::Reminders,
+        Self::LatestInsight,
+    ];
+
+    /// Comma-separated widget names from `?widgets=`; unknown names are ignored and an
+    /// empty or missing list means every widget
+    pub fn parse_list(value: Option<&str>) -> HashSet<DashboardWidget> {
+        let selected: HashSet<DashboardWidget> = value
+            .unwrap_or("")
+            .split(',')
+            .filter_map(|name| serde_json::from_value(serde_json::Value::String(name.trim().to_string())).ok())
+            .collect();
+        if selected.is_empty() { Self::ALL.into_iter().collect() } else { selected }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TodayPnl {
+    pub trade_date: String,
+    pub realized_pnl: f64,
+    pub closed_trades: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenPositions {
+    pub stocks: u32,
+    pub options: u32,
+    /// Unrealized P&L of the open stock positions that could be priced
+    pub unrealized_pnl: Option<f64>,
+    pub priced_positions: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EvaluationProgress {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub status: EvaluationStatus,
+    pub total_pnl: f64,
+    pub profit_target: f64,
+    pub progress_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalProgress {
+    pub goal: Option<String>,
+    /// Newest prop-firm evaluation profile over the last 30 days
+    pub evaluation: Option<EvaluationProgress>,
+    /// This month's P&L goal pacing, when a monthly goal is set
+    pub pacing: Option<GoalPacing>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatestInsight {
+    pub id: String,
+    pub title: String,
+    pub generated_at: String,
+}
+
+/// Widgets not requested, or that failed, are null
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DashboardSummary {
+    pub today_pnl: Option<TodayPnl>,
+    pub open_positions: Option<OpenPositions>,
+    pub streak: Option<CurrentStreak>,
+    pub goal_progress: Option<GoalProgress>,
+    pub reminders: Option<Vec<NotebookReminder>>,
+    pub latest_insight: Option<LatestInsight>,
+}
+
+pub async fn build_dashboard(
+    conn: &Connection,
+    market: Option<&MarketClient>,
+    widgets: &HashSet<DashboardWidget>,
+) -> DashboardSummary {
+    let wants = |widget| widgets.contains(&widget);
+    let (today_pnl, open_positions, streak, goal_progress, reminders, latest_insight) = tokio::join!(
+        widget(wants(DashboardWidget::TodayPnl), "today_pnl", today_pnl(conn)),
+        widget(wants(DashboardWidget::OpenPositions), "open_positions", open_positions(conn, market)),
+        widget(wants(DashboardWidget::Streak), "streak", streaks::current_streak(conn)),
+        widget(wants(DashboardWidget::GoalProgress), "goal_progress", goal_progress(conn)),
+        widget(wants(DashboardWidget::Reminders), "reminders", next_reminders(conn)),
+        widget(wants(DashboardWidget::LatestInsight), "latest_insight", latest_insight(conn)),
+    );
+    DashboardSummary {
+        today_pnl,
+        open_positions,
+        streak,
+        goal_progress,
+        reminders,
+        latest_insight: latest_insight.flatten(),
+    }
+}
+
+async fn widget<T>(wanted: bool, name: &str, load: impl std::future::Future<Output = Result<T>>) -> Option<T> {
+    if !wanted {
+        return None;
+    }
+    load.await
+        .map_err(|e| log::warn!("Dashboard widget {} unavailable: {}", name, e))
+        .ok()
+}
+
+async fn today_pnl(conn: &Connection) -> Result<TodayPnl> {
+    let periods = PeriodDefinition::load(conn).await;
+    let trade_date = periods.trading_date(Utc::now()).format("%Y-%m-%d").to_string();
+    let day = format!("date({})", periods.trading_date_sql("exit_date"));
+    let sql = format!(
+        "SELECT COALESCE(SUM(pnl), 0), COUNT(*) FROM (
+            SELECT {STOCK_PNL_SQL} AS pnl FROM stocks
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 AND {day} = ?1
+            UNION ALL
+            SELECT {OPTION_PNL_SQL} AS pnl FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 AND {day} = ?1
+        )"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![trade_date.clone()]).await?;
+    let row = rows.next().await?.ok_or_else(|| anyhow::anyhow!("No aggregate row"))?;
+    Ok(TodayPnl {
+        trade_date,
+        realized_pnl: money::to_f64(money::row_decimal(&row, 0)),
+        closed_trades: row.get::<i64>(1)? as u32,
+    })
+}
+
+/// Open stock positions are priced with simple quotes when market data is available;
+/// options are only counted
+pub(crate) async fn open_positions(conn: &Connection, market: Option<&MarketClient>) -> Result<OpenPositions> {
+    let mut rows = conn
+        .prepare("SELECT UPPER(symbol), trade_type, entry_price, number_shares FROM stocks WHERE exit_date IS NULL AND is_deleted = 0")
+        .await?
+        .query(params![])
+        .await?;
+    let mut stocks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let direction = if row.get::<String>(1)? == "SELL" { -1.0 } else { 1.0 };
+        stocks.push((row.get::<String>(0)?, direction, money::to_f64(money::row_decimal(&row, 2)), money::to_f64(money::row_decimal(&row, 3))));
+    }
+
+    let mut rows = conn
+        .prepare("SELECT COUNT(*) FROM options WHERE status = 'open' AND is_deleted = 0")
+        .await?
+        .query(params![])
+        .await?;
+    let options = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? as u32,
+        None => 0,
+    };
+
+    let mut unrealized_pnl = None;
+    let mut priced_positions = 0;
+    if let Some(market) = market
+        && !stocks.is_empty()
+    {
+        let mut symbols: Vec<String> = stocks.iter().map(|(symbol, ..)| symbol.clone()).collect();
+        symbols.sort();
+        symbols.dedup();
+        match get_simple_quotes(market, &symbols).await {
+            Ok(quotes) => {
+                let mut total = 0.0;
+                for (symbol, direction, entry_price, shares) in &stocks {
+                    let price = quotes
+                        .iter()
+                        .find(|q| q.symbol.eq_ignore_ascii_case(symbol))
+                        .and_then(|q| q.price.as_deref())
+                        .and_then(|p| p.replace([',', '$'], "").parse::<f64>().ok());
+                    if let Some(price) = price {
+                        total += (price - entry_price) * shares * direction;
+                        priced_positions += 1;
+                    }
+                }
+                unrealized_pnl = (priced_positions > 0).then_some(total);
+            }
+            Err(e) => log::warn!("Dashboard quotes unavailable: {}", e),
+        }
+    }
+
+    Ok(OpenPositions { stocks: stocks.len() as u32, options, unrealized_pnl, priced_positions })
+}
+
+async fn goal_progress(conn: &Connection) -> Result<GoalProgress> {
+    let mut rows = conn
+        .prepare("SELECT primary_trading_goal FROM user_profile LIMIT 1")
+        .await?
+        .query(params![])
+        .await?;
+    let goal = match rows.next().await? {
+        Some(row) => row.get::<Option<String>>(0)?.filter(|goal| !goal.trim().is_empty()),
+        None => None,
+    };
+
+    let evaluation = match EvaluationProfile::find_all(conn).await?.into_iter().next() {
+        Some(profile) => {
+            let result = evaluate_profile(conn, &profile, &TimeRange::ThirtyDays).await?;
+            Some(EvaluationProgress {
+                profile_id: profile.id,
+                profile_name: profile.name,
+                status: result.status,
+                total_pnl: result.total_pnl,
+                profit_target: profile.profit_target,
+                progress_pct: (result.total_pnl / profile.profit_target * 100.0).clamp(0.0, 100.0),
+            })
+        }
+        None => None,
+    };
+    let pacing = goal_pacing::pacing(conn, Utc::now()).await?;
+    Ok(GoalProgress { goal, evaluation, pacing })
+}
+
+async fn next_reminders(conn: &Connection) -> Result<Vec<NotebookReminder>> {
+    let now = Utc::now();
+    // Recurring reminders left uncompleted are still due at their next occurrence
+    let mut rows = conn
+        .prepare(
+            "SELECT id, note_id, title, description, reminder_time, recurrence, is_completed, created_at, updated_at \
+             FROM notebook_reminders WHERE is_completed = 0 AND (reminder_time >= ? OR recurrence IS NOT NULL) \
+             ORDER BY reminder_time ASC",
+        )
+        .await?
+        .query(params![now.to_rfc3339()])
+        .await?;
+    let mut reminders = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let mut reminder = NotebookReminder {
+            id: row.get(0)?,
+            note_id: row.get(1)?,
+            title: row.get(2)?,
+            description: row.get(3)?,
+            reminder_time: row.get(4)?,
+            recurrence: row.get(5)?,
+            is_completed: row.get::<i64>(6)? != 0,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        };
+        let overdue = DateTime::parse_from_rfc3339(&reminder.reminder_time).is_ok_and(|time| time < now);
+        if overdue {
+            match reminder.next_occurrence(now).ok().flatten() {
+                Some(next) => reminder.reminder_time = next.to_rfc3339(),
+                None => continue,
+            }
+        }
+        reminders.push(reminder);
+    }
+    reminders.sort_by_key(|r| DateTime::parse_from_rfc3339(&r.reminder_time).ok());
+    reminders.truncate(NEXT_REMINDERS);
+    Ok(reminders)
+}
+
+async fn latest_insight(conn: &Connection) -> Result<Option<LatestInsight>> {
+    let mut rows = conn
+        .prepare("SELECT id, title, generated_at FROM ai_insights ORDER BY generated_at DESC LIMIT 1")
+        .await?
+        .query(params![])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(LatestInsight { id: row.get(0)?, title: row.get(1)?, generated_at: row.get(2)? })),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_widget_list() {
+        let selected = DashboardWidget::parse_list(Some("today_pnl, streak,unknown"));
+        assert_eq!(selected.len(), 2);
+        assert!(selected.contains(&DashboardWidget::Streak));
+        assert_eq!(DashboardWidget::parse_list(None).len(), DashboardWidget::ALL.len());
+    }
+}
@@ -0,0 +1,335 @@
+// Nightly data integrity checks. Each run looks for trades that can't be right (exit
+// before entry, non-positive size, options still open after expiry, stock trades without
+// a stop under a playbook whose rules call for one) and records them in `data_issues`
+// with a suggested fix. Issues that no longer reproduce are cleared on the next run;
+// dismissed ones stay dismissed for as long as the problem remains.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::models::money;
+use crate::models::timestamps;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueType {
+    ExitBeforeEntry,
+    NonPositiveSize,
+    OpenPastExpiry,
+    MissingStopLoss,
+}
+
+impl IssueType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IssueType::ExitBeforeEntry => "exit_before_entry",
+            IssueType::NonPositiveSize => "non_positive_size",
+            IssueType::OpenPastExpiry => "open_past_expiry",
+            IssueType::MissingStopLoss => "missing_stop_loss",
+        }
+    }
+
+    fn suggestion(&self) -> &'static str {
+        match self {
+            IssueType::ExitBeforeEntry => "Check the dates; if they were entered the wrong way round, swap entry and exit.",
+            IssueType::NonPositiveSize => "Enter the size as a positive number and use the trade direction for shorts.",
+            IssueType::OpenPastExpiry => {
+                "Close the trade: at 0 if it expired worthless, or at its intrinsic value if it was exercised or assigned."
+            }
+            IssueType::MissingStopLoss => "Add the stop loss you used, as the playbook's rules call for one.",
+        }
+    }
+}
+
+/// What the checks need to know about one trade
+#[derive(Debug, Clone)]
+pub struct TradeFacts {
+    /// `stock` or `option`
+    pub trade_type: &'static str,
+    pub trade_id: i64,
+    pub symbol: String,
+    pub entry_date: Option<DateTime<Utc>>,
+    pub exit_date: Option<DateTime<Utc>>,
+    pub size: f64,
+    /// Options only
+    pub expiration: Option<DateTime<Utc>>,
+    pub open: bool,
+    /// Stocks only; zero means none was entered
+    pub stop_loss: Option<f64>,
+    /// Linked to a playbook whose rules mention a stop
+    pub stop_required: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundIssue {
+    pub issue_type: IssueType,
+    pub message: String,
+}
+
+pub fn check_trade(trade: &TradeFacts, now: DateTime<Utc>) -> Vec<FoundIssue> {
+    let mut issues = Vec::new();
+    if let (Some(entry), Some(exit)) = (trade.entry_date, trade.exit_date)
+        && exit < entry
+    {
+        issues.push(FoundIssue {
+            issue_type: IssueType::ExitBeforeEntry,
+            message: format!("{} exit ({}) is before its entry ({})", trade.symbol, exit.format("%Y-%m-%d %H:%M"), entry.format("%Y-%m-%d %H:%M")),
+        });
+    }
+    if trade.size <= 0.0 {
+        let unit = if trade.trade_type == "option" { "contracts" } else { "shares" };
+        issues.push(FoundIssue {
+            issue_type: IssueType::NonPositiveSize,
+            message: format!("{} has {} {}", trade.symbol, trade.size, unit),
+        });
+    }
+    if let Some(expiration) = trade.expiration
+        && trade.open
+        && expiration.date_naive() < now.date_naive()
+    {
+        issues.push(FoundIssue {
+            issue_type: IssueType::OpenPastExpiry,
+            message: format!("{} is still open but expired on {}", trade.symbol, expiration.format("%Y-%m-%d")),
+        });
+    }
+    if trade.stop_required && trade.stop_loss.is_some_and(|stop| stop <= 0.0) {
+        issues.push(FoundIssue {
+            issue_type: IssueType::MissingStopLoss,
+            message: format!("{} has no stop loss", trade.symbol),
+        });
+    }
+    issues
+}
+
+fn parse_date(value: Option<String>) -> Option<DateTime<Utc>> {
+    value.and_then(|v| timestamps::parse_stored(&v).ok())
+}
+
+async fn load_trades(conn: &Connection) -> Result<Vec<TradeFacts>> {
+    let mut trades = Vec::new();
+    let mut rows = conn
+        .prepare(
+            "SELECT s.id, s.symbol, s.entry_date, s.exit_date, CAST(s.number_shares AS REAL), s.stop_loss, \
+             EXISTS (SELECT 1 FROM stock_trade_playbook sp JOIN playbook_rules r ON r.playbook_id = sp.setup_id \
+                     WHERE sp.stock_trade_id = s.id AND (LOWER(r.title) LIKE '%stop%' OR LOWER(COALESCE(r.description, '')) LIKE '%stop%')) \
+             FROM stocks s WHERE s.is_deleted = 0",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let exit_date = parse_date(row.get(3)?);
+        trades.push(TradeFacts {
+            trade_type: "stock",
+            trade_id: row.get(0)?,
+            symbol: row.get(1)?,
+            entry_date: parse_date(row.get(2)?),
+            open: exit_date.is_none(),
+            exit_date,
+            size: row.get::<Option<f64>>(4)?.unwrap_or(0.0),
+            expiration: None,
+            stop_loss: Some(money::to_f64(money::row_decimal(&row, 5))),
+            stop_required: row.get::<i64>(6)? != 0,
+        });
+    }
+
+    let mut rows = conn
+        .prepare(
+            "SELECT id, symbol, entry_date, exit_date, number_of_contracts, expiration_date, status \
+             FROM options WHERE is_deleted = 0",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        trades.push(TradeFacts {
+            trade_type: "option",
+            trade_id: row.get(0)?,
+            symbol: row.get(1)?,
+            entry_date: parse_date(row.get(2)?),
+            exit_date: parse_date(row.get(3)?),
+            size: row.get::<i64>(4)? as f64,
+            expiration: parse_date(row.get(5)?),
+            open: row.get::<String>(6)? == "open",
+            stop_loss: None,
+            stop_required: false,
+        });
+    }
+    Ok(trades)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CheckReport {
+    pub trades_checked: usize,
+    pub open_issues: usize,
+    pub new_issues: usize,
+    pub resolved_issues: usize,
+}
+
+type IssueKey = (String, String, i64);
+
+async fn recorded_issues(conn: &Connection) -> Result<Vec<(String, IssueKey)>> {
+    let mut rows = conn
+        .prepare("SELECT id, issue_type, trade_type, trade_id FROM data_issues")
+        .await?
+        .query(params![])
+        .await?;
+    let mut issues = Vec::new();
+    while let Some(row) = rows.next().await? {
+        issues.push((row.get::<String>(0)?, (row.get::<String>(1)?, row.get::<String>(2)?, row.get::<i64>(3)?)));
+    }
+    Ok(issues)
+}
+
+/// Check every trade and bring `data_issues` up to date
+pub async fn run_checks(conn: &Connection, now: DateTime<Utc>) -> Result<CheckReport> {
+    let trades = load_trades(conn).await?;
+    let recorded = recorded_issues(conn).await?;
+    let known: HashSet<&IssueKey> = recorded.iter().map(|(_, key)| key).collect();
+    let mut found: HashSet<IssueKey> = HashSet::new();
+    let mut report = CheckReport { trades_checked: trades.len(), ..CheckReport::default() };
+
+    for trade in &trades {
+        for issue in check_trade(trade, now) {
+            let key = (issue.issue_type.as_str().to_string(), trade.trade_type.to_string(), trade.trade_id);
+            if !known.contains(&key) {
+                report.new_issues += 1;
+            }
+            conn.execute(
+                "INSERT INTO data_issues (id, issue_type, trade_type, trade_id, message, suggestion, status, detected_at) \
+                 VALUES (?, ?, ?, ?, ?, ?, 'open', ?) \
+                 ON CONFLICT(issue_type, trade_type, trade_id) DO UPDATE SET message = excluded.message",
+                params![
+                    Uuid::new_v4().to_string(),
+                    issue.issue_type.as_str(),
+                    trade.trade_type,
+                    trade.trade_id,
+                    issue.message,
+                    issue.issue_type.suggestion(),
+                    now.to_rfc3339()
+                ],
+            )
+            .await?;
+            found.insert(key);
+        }
+    }
+
+    // Anything recorded before that didn't reproduce has been fixed
+    for (id, key) in &recorded {
+        if !found.contains(key) {
+            conn.execute("DELETE FROM data_issues WHERE id = ?", params![id.clone()]).await?;
+            report.resolved_issues += 1;
+        }
+    }
+    report.open_issues = list_issues(conn, false).await?.len();
+    Ok(report)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DataIssue {
+    pub id: String,
+    pub issue_type: String,
+    pub trade_type: String,
+    pub trade_id: i64,
+    pub message: String,
+    pub suggestion: String,
+    /// `open` or `dismissed`
+    pub status: String,
+    pub detected_at: String,
+}
+
+pub async fn list_issues(conn: &Connection, include_dismissed: bool) -> Result<Vec<DataIssue>> {
+    let sql = format!(
+        "SELECT id, issue_type, trade_type, trade_id, message, suggestion, status, detected_at FROM data_issues {} \
+         ORDER BY detected_at DESC, trade_id DESC",
+        if include_dismissed { "" } else { "WHERE status = 'open'" }
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+    let mut issues = Vec::new();
+    while let Some(row) = rows.next().await? {
+        issues.push(DataIssue {
+            id: row.get(0)?,
+            issue_type: row.get(1)?,
+            trade_type: row.get(2)?,
+            trade_id: row.get(3)?,
+            message: row.get(4)?,
+            suggestion: row.get(5)?,
+            status: row.get(6)?,
+            detected_at: row.get(7)?,
+        });
+    }
+    Ok(issues)
+}
+
+/// Set an issue to `open` or `dismissed`; false when it doesn't exist
+pub async fn set_status(conn: &Connection, id: &str, status: &str) -> Result<bool> {
+    let updated = conn
+        .execute(
+            "UPDATE data_issues SET status = ?, updated_at = ? WHERE id = ?",
+            params![status, Utc::now().to_rfc3339(), id],
+        )
+        .await?;
+    Ok(updated > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn trade() -> TradeFacts {
+        TradeFacts {
+            trade_type: "stock",
+            trade_id: 1,
+            symbol: "AAPL".to_string(),
+            entry_date: Some(Utc.with_ymd_and_hms(2024, 3, 5, 14, 0, 0).unwrap()),
+            exit_date: Some(Utc.with_ymd_and_hms(2024, 3, 6, 15, 0, 0).unwrap()),
+            size: 100.0,
+            expiration: None,
+            open: false,
+            stop_loss: Some(180.0),
+            stop_required: true,
+        }
+    }
+
+    #[test]
+    fn test_clean_trade_has_no_issues() {
+        assert!(check_trade(&trade(), Utc::now()).is_empty());
+    }
+
+    #[test]
+    fn test_check_trade_flags_impossible_data() {
+        let now = Utc.with_ymd_and_hms(2024, 4, 1, 12, 0, 0).unwrap();
+        let mut stock = trade();
+        stock.exit_date = Some(Utc.with_ymd_and_hms(2024, 3, 4, 15, 0, 0).unwrap());
+        stock.size = -100.0;
+        stock.stop_loss = Some(0.0);
+        let types: Vec<IssueType> = check_trade(&stock, now).into_iter().map(|i| i.issue_type).collect();
+        assert_eq!(types, vec![IssueType::ExitBeforeEntry, IssueType::NonPositiveSize, IssueType::MissingStopLoss]);
+
+        // No stop is fine when no playbook asks for one
+        stock = trade();
+        stock.stop_loss = Some(0.0);
+        stock.stop_required = false;
+        assert!(check_trade(&stock, now).is_empty());
+
+        let option = TradeFacts {
+            trade_type: "option",
+            exit_date: None,
+            expiration: Some(Utc.with_ymd_and_hms(2024, 3, 15, 20, 0, 0).unwrap()),
+            open: true,
+            stop_loss: None,
+            stop_required: false,
+            ..trade()
+        };
+        let issues = check_trade(&option, now);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].issue_type, IssueType::OpenPastExpiry);
+        // Expiring today is not past expiry yet
+        assert!(check_trade(&option, Utc.with_ymd_and_hms(2024, 3, 15, 23, 0, 0).unwrap()).is_empty());
+    }
+}
@@ -0,0 +1,624 @@
+// Onboarding demo data. A first-run user can load a small sample journal (closed stock
+// and option trades, tags, playbooks and notebook notes) to try analytics and the AI
+// features before importing anything. Trades go through the normal create/update paths so
+// realized P&L is stored exactly as for real trades. Every seeded row is recorded in
+// demo_data_rows, and teardown deletes those rows only, leaving anything the user added.
+//
+// The sample is generated from a fixed seed, so every user gets the same journal shifted
+// to end a few days before they loaded it.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use libsql::{params, Connection};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::models::money;
+use crate::models::notebook::{CreateNoteRequest, NotebookNote};
+use crate::models::options::{CreateOptionRequest, OptionTrade, OptionType, TradeDirection, TradeStatus, UpdateOptionRequest};
+use crate::models::playbook::{CreatePlaybookRequest, Playbook};
+use crate::models::stock::stocks::{CreateStockRequest, OrderType, Stock, TradeType, UpdateStockRequest};
+use crate::models::tags::{CreateTagRequest, TradeTag, TradeTagAssociation};
+
+const SEED: u64 = 0x5EED_7AD5;
+const STOCK_TRADES: usize = 36;
+const OPTION_TRADES: usize = 8;
+/// Calendar days the sample history covers
+const HISTORY_DAYS: i64 = 90;
+/// The newest sample trade closes at least this many days before seeding
+const HISTORY_GAP_DAYS: i64 = 4;
+/// Shown as the brokerage on every sample trade
+pub const DEMO_BROKERAGE: &str = "Demo account";
+
+/// Symbol and rough price level
+const SYMBOLS: &[(&str, f64)] = &[
+    ("AAPL", 190.0),
+    ("MSFT", 410.0),
+    ("NVDA", 120.0),
+    ("AMD", 155.0),
+    ("TSLA", 240.0),
+    ("META", 520.0),
+    ("AMZN", 185.0),
+    ("SPY", 560.0),
+];
+
+/// Category, name, color
+const TAGS: &[(&str, &str, &str)] = &[
+    ("Setup", "Breakout", "#22c55e"),
+    ("Setup", "Pullback", "#3b82f6"),
+    ("Mistake", "Chased entry", "#ef4444"),
+    ("Mistake", "Moved stop", "#f97316"),
+    ("Market", "Trend day", "#a855f7"),
+];
+const TAG_BREAKOUT: usize = 0;
+const TAG_PULLBACK: usize = 1;
+const TAG_CHASED: usize = 2;
+const TAG_MOVED_STOP: usize = 3;
+const TAG_TREND_DAY: usize = 4;
+
+/// Name, description, emoji
+const PLAYBOOKS: &[(&str, &str, &str)] = &[
+    (
+        "Opening Range Breakout",
+        "Enter on a break of the first 15-minute range with volume above average. Stop below the range, first target 2R.",
+        "🚀",
+    ),
+    (
+        "VWAP Pullback",
+        "In a trending name, buy the first pullback that holds VWAP. Stop below the pullback low, trail once price makes a new high.",
+        "📈",
+    ),
+];
+
+/// Tables rows are tracked for, in the order teardown removes them
+const TRACKED_TABLES: &[&str] = &["stocks", "options", "trade_tags", "playbook", "notebook_notes"];
+
+/// Small deterministic generator; the sample must not change between users or releases
+struct Lcg(u64);
+
+impl Lcg {
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[((self.next_f64() * items.len() as f64) as usize).min(items.len() - 1)]
+    }
+}
+
+fn cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+/// Weekdays from `HISTORY_DAYS` ago up to the gap before `now`, oldest first
+fn trading_days(now: DateTime<Utc>) -> Vec<NaiveDate> {
+    let last = now.date_naive() - Duration::days(HISTORY_GAP_DAYS);
+    (0..HISTORY_DAYS)
+        .rev()
+        .map(|offset| last - Duration::days(offset))
+        .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+        .collect()
+}
+
+/// Market-hours time on `day` (between 14:30 and 18:30 UTC)
+fn session_time(day: NaiveDate, minutes_after_open: f64) -> DateTime<Utc> {
+    day.and_hms_opt(14, 30, 0).expect("valid time").and_utc() + Duration::minutes(minutes_after_open as i64)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemoStockTrade {
+    pub symbol: &'static str,
+    pub trade_type: TradeType,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub stop_loss: f64,
+    pub shares: f64,
+    pub entry_date: DateTime<Utc>,
+    pub exit_date: DateTime<Utc>,
+    pub rating: i32,
+    pub mistakes: Option<&'static str>,
+    /// Index into `PLAYBOOKS`
+    pub playbook: usize,
+    /// Indexes into `TAGS`
+    pub tags: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DemoOptionTrade {
+    pub symbol: &'static str,
+    pub option_type: OptionType,
+    pub strike: f64,
+    pub contracts: i32,
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub implied_volatility: f64,
+    pub entry_date: DateTime<Utc>,
+    pub exit_date: DateTime<Utc>,
+    pub expiration_date: DateTime<Utc>,
+    pub tags: Vec<usize>,
+}
+
+/// The sample stock trades, ending `HISTORY_GAP_DAYS` before `now`. About 55% winners,
+/// winners between 0.5R and 2.5R, losers mostly stopped out near 1R.
+pub fn stock_plan(now: DateTime<Utc>) -> Vec<DemoStockTrade> {
+    let days = trading_days(now);
+    let mut rng = Lcg(SEED);
+    (0..STOCK_TRADES)
+        .map(|i| {
+            let day = days[i * days.len() / STOCK_TRADES];
+            let (symbol, level) = *rng.pick(SYMBOLS);
+            let long = rng.next_f64() < 0.8;
+            let direction = if long { 1.0 } else { -1.0 };
+            let entry_price = cents(level * rng.range(0.92, 1.08));
+            let risk = cents(entry_price * rng.range(0.008, 0.018)).max(0.05);
+            let win = rng.next_f64() < 0.55;
+            let moved_stop = !win && rng.next_f64() < 0.25;
+            let r_multiple = if win {
+                rng.range(0.5, 2.5)
+            } else if moved_stop {
+                -rng.range(1.3, 1.8)
+            } else {
+                -rng.range(0.4, 1.0)
+            };
+            let shares = (rng.range(100.0, 250.0) / risk).round().max(1.0);
+            let entry_date = session_time(day, rng.range(0.0, 120.0));
+            // Every fifth trade is held overnight, the rest are closed the same session
+            let held = if i % 5 == 4 { Duration::days(1 + (i % 3) as i64) } else { Duration::minutes(rng.range(10.0, 120.0) as i64) };
+            let breakout = i % 2 == 0;
+
+            let mut tags = vec![if breakout { TAG_BREAKOUT } else { TAG_PULLBACK }];
+            let mut mistakes = None;
+            if moved_stop {
+                tags.push(TAG_MOVED_STOP);
+                mistakes = Some("Moved my stop instead of taking the loss");
+            } else if !win && rng.next_f64() < 0.4 {
+                tags.push(TAG_CHASED);
+                mistakes = Some("Chased the entry well past the trigger");
+            }
+            if win && r_multiple > 1.8 {
+                tags.push(TAG_TREND_DAY);
+            }
+
+            DemoStockTrade {
+                symbol,
+                trade_type: if long { TradeType::BUY } else { TradeType::SELL },
+                entry_price,
+                exit_price: cents(entry_price + direction * r_multiple * risk),
+                stop_loss: cents(entry_price - direction * risk),
+                shares,
+                entry_date,
+                exit_date: entry_date + held,
+                rating: (3.0 + r_multiple).round().clamp(1.0, 5.0) as i32,
+                mistakes,
+                playbook: if breakout { 0 } else { 1 },
+                tags,
+            }
+        })
+        .collect()
+}
+
+/// The sample long calls and puts, spread over the same history as the stock trades
+pub fn option_plan(now: DateTime<Utc>) -> Vec<DemoOptionTrade> {
+    let days = trading_days(now);
+    let mut rng = Lcg(SEED ^ 0xA5A5);
+    (0..OPTION_TRADES)
+        .map(|i| {
+            let day = days[(i * days.len() / OPTION_TRADES + 2).min(days.len() - 1)];
+            let (symbol, level) = *rng.pick(&SYMBOLS[..5]);
+            let call = rng.next_f64() < 0.65;
+            let step = if level > 300.0 { 5.0 } else { 2.5 };
+            let underlying = level * rng.range(0.95, 1.05);
+            let strike = (underlying / step).round() * step + if call { step } else { -step };
+            let entry_price = cents(rng.range(1.5, 6.0));
+            let change = if rng.next_f64() < 0.5 { rng.range(0.2, 0.9) } else { -rng.range(0.2, 0.6) };
+            let entry_date = session_time(day, rng.range(15.0, 150.0));
+            DemoOptionTrade {
+                symbol,
+                option_type: if call { OptionType::Call } else { OptionType::Put },
+                strike,
+                contracts: 1 + (rng.next_f64() * 4.0) as i32,
+                entry_price,
+                exit_price: cents(entry_price * (1.0 + change)),
+                implied_volatility: (rng.range(0.25, 0.6) * 100.0).round() / 100.0,
+                entry_date,
+                exit_date: entry_date + Duration::minutes(rng.range(30.0, 200.0) as i64),
+                expiration_date: session_time(day + Duration::days(7 + 7 * (i % 3) as i64), 390.0),
+                tags: vec![if i % 2 == 0 { TAG_BREAKOUT } else { TAG_PULLBACK }],
+            }
+        })
+        .collect()
+}
+
+fn paragraphs(lines: &[&str]) -> Value {
+    Value::Array(
+        lines
+            .iter()
+            .map(|line| json!({"type": "paragraph", "content": [{"type": "text", "text": line}]}))
+            .collect(),
+    )
+}
+
+fn notes() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "Welcome to your sample journal",
+            paragraphs(&[
+                "Everything tagged with the \"Demo account\" brokerage, plus these notes, the sample tags and the two sample playbooks, is demo data.",
+                "Explore the dashboard, analytics and AI insights with it, then remove the demo data before importing your own trades.",
+            ]),
+        ),
+        (
+            "Trading plan",
+            paragraphs(&[
+                "Only two setups: opening range breakouts and VWAP pullbacks in names with relative strength.",
+                "Risk $100-$250 per trade. Stop goes in with the entry and never moves further away.",
+                "Stop trading for the day after two full losses.",
+            ]),
+        ),
+        (
+            "Weekly review",
+            paragraphs(&[
+                "Breakouts worked best on trend days; pullbacks paid more consistently.",
+                "The biggest losses came from moving stops. Next week: stop stays where it was placed.",
+                "Chased two entries after the trigger. Wait for the retest or skip the trade.",
+            ]),
+        ),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DemoDataStatus {
+    pub seeded: bool,
+    /// Tracked demo rows per table
+    pub rows: BTreeMap<String, u32>,
+    /// Trades that are not demo data
+    pub user_trades: u32,
+}
+
+/// What a seed created; the trades and notes are returned for vectorization
+#[derive(Debug, Clone)]
+pub struct SeededDemoData {
+    pub stocks: Vec<Stock>,
+    pub options: Vec<OptionTrade>,
+    pub notes: Vec<NotebookNote>,
+    pub tags: u32,
+    pub playbooks: u32,
+}
+
+/// Ids of what a teardown removed, for dropping their vectors
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RemovedDemoData {
+    pub stock_ids: Vec<i64>,
+    pub option_ids: Vec<i64>,
+    pub note_ids: Vec<String>,
+    pub tags: u32,
+    pub playbooks: u32,
+}
+
+async fn track(conn: &Connection, table: &str, row_id: &str) -> Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO demo_data_rows (table_name, row_id, created_at) VALUES (?, ?, ?)",
+        params![table, row_id, Utc::now().to_rfc3339()],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn tracked_ids(conn: &Connection, table: &str) -> Result<Vec<String>> {
+    let mut rows = conn
+        .prepare("SELECT row_id FROM demo_data_rows WHERE table_name = ? ORDER BY row_id")
+        .await?
+        .query(params![table])
+        .await?;
+    let mut ids = Vec::new();
+    while let Some(row) = rows.next().await? {
+        ids.push(row.get::<String>(0)?);
+    }
+    Ok(ids)
+}
+
+async fn count(conn: &Connection, sql: &str) -> Result<u32> {
+    let mut rows = conn.prepare(sql).await?.query(params![]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? as u32,
+        None => 0,
+    })
+}
+
+pub async fn status(conn: &Connection) -> Result<DemoDataStatus> {
+    let mut rows = conn
+        .prepare("SELECT table_name, COUNT(*) FROM demo_data_rows GROUP BY table_name")
+        .await?
+        .query(params![])
+        .await?;
+    let mut counts = BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        counts.insert(row.get::<String>(0)?, row.get::<i64>(1)? as u32);
+    }
+    let mut user_trades = 0;
+    for table in ["stocks", "options"] {
+        user_trades += count(
+            conn,
+            &format!(
+                "SELECT COUNT(*) FROM {table} WHERE is_deleted = 0 AND CAST(id AS TEXT) NOT IN \
+                 (SELECT row_id FROM demo_data_rows WHERE table_name = '{table}')"
+            ),
+        )
+        .await?;
+    }
+    Ok(DemoDataStatus { seeded: !counts.is_empty(), rows: counts, user_trades })
+}
+
+/// Existing tag or playbook with the same name is reused and left out of teardown
+async fn find_existing(conn: &Connection, sql: &str, values: Vec<String>) -> Result<Option<String>> {
+    let mut rows = conn.prepare(sql).await?.query(libsql::params_from_iter(values)).await?;
+    Ok(match rows.next().await? {
+        Some(row) => Some(row.get::<String>(0)?),
+        None => None,
+    })
+}
+
+/// Seed the sample journal. Callers check `status` first: seeding is meant for an empty
+/// journal and is not repeated while demo rows exist.
+pub async fn seed(conn: &Connection, now: DateTime<Utc>) -> Result<SeededDemoData> {
+    let mut tag_ids = Vec::new();
+    let mut tags = 0;
+    for (category, name, color) in TAGS {
+        let existing =
+            find_existing(conn, "SELECT id FROM trade_tags WHERE category = ? AND name = ?", vec![category.to_string(), name.to_string()]).await?;
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let request = CreateTagRequest {
+                    category: category.to_string(),
+                    name: name.to_string(),
+                    color: Some(color.to_string()),
+                    description: None,
+                };
+                let tag = TradeTag::create(conn, request).await?;
+                track(conn, "trade_tags", &tag.id).await?;
+                tags += 1;
+                tag.id
+            }
+        };
+        tag_ids.push(id);
+    }
+
+    let mut playbook_ids = Vec::new();
+    let mut playbooks = 0;
+    for (name, description, emoji) in PLAYBOOKS {
+        let id = match find_existing(conn, "SELECT id FROM playbook WHERE name = ?", vec![name.to_string()]).await? {
+            Some(id) => id,
+            None => {
+                let request = CreatePlaybookRequest {
+                    name: name.to_string(),
+                    description: Some(description.to_string()),
+                    icon: None,
+                    emoji: Some(emoji.to_string()),
+                    color: None,
+                };
+                let playbook = Playbook::create(conn, request).await.map_err(|e| anyhow!(e))?;
+                track(conn, "playbook", &playbook.id).await?;
+                playbooks += 1;
+                playbook.id
+            }
+        };
+        playbook_ids.push(id);
+    }
+
+    let mut stocks = Vec::new();
+    for trade in stock_plan(now) {
+        let request = CreateStockRequest {
+            symbol: trade.symbol.to_string(),
+            trade_type: trade.trade_type.clone(),
+            order_type: OrderType::LIMIT,
+            entry_price: money::from_f64(trade.entry_price),
+            stop_loss: money::from_f64(trade.stop_loss),
+            commissions: money::from_f64(0.0),
+            number_shares: trade.shares,
+            take_profit: None,
+            initial_target: None,
+            profit_target: None,
+            trade_ratings: Some(trade.rating),
+            entry_date: trade.entry_date,
+            reviewed: Some(true),
+            mistakes: trade.mistakes.map(str::to_string),
+            brokerage_name: Some(DEMO_BROKERAGE.to_string()),
+            venue: None,
+        };
+        let created = Stock::create(conn, request).await.map_err(|e| anyhow!(e))?;
+        track(conn, "stocks", &created.id.to_string()).await?;
+        let close = UpdateStockRequest {
+            symbol: None,
+            trade_type: None,
+            order_type: None,
+            entry_price: None,
+            exit_price: Some(money::from_f64(trade.exit_price)),
+            stop_loss: None,
+            commissions: None,
+            number_shares: None,
+            take_profit: None,
+            initial_target: None,
+            profit_target: None,
+            trade_ratings: None,
+            entry_date: None,
+            exit_date: Some(trade.exit_date),
+            reviewed: None,
+            mistakes: None,
+            brokerage_name: None,
+            venue: None,
+        };
+        let closed = Stock::update(conn, created.id, close)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .ok_or_else(|| anyhow!("Demo stock trade {} disappeared", created.id))?;
+        for tag in &trade.tags {
+            TradeTagAssociation::add_tag_to_stock_trade(conn, closed.id, &tag_ids[*tag]).await?;
+        }
+        Playbook::tag_stock_trade(conn, closed.id, &playbook_ids[trade.playbook]).await.map_err(|e| anyhow!(e))?;
+        stocks.push(closed);
+    }
+
+    let mut options = Vec::new();
+    for trade in option_plan(now) {
+        let call = trade.option_type == OptionType::Call;
+        let request = CreateOptionRequest {
+            symbol: trade.symbol.to_string(),
+            strategy_type: if call { "Long Call" } else { "Long Put" }.to_string(),
+            trade_direction: if call { TradeDirection::Bullish } else { TradeDirection::Bearish },
+            number_of_contracts: trade.contracts,
+            option_type: trade.option_type.clone(),
+            strike_price: money::from_f64(trade.strike),
+            expiration_date: trade.expiration_date,
+            entry_price: money::from_f64(trade.entry_price),
+            total_premium: money::from_f64(cents(trade.entry_price * 100.0 * trade.contracts as f64)),
+            commissions: money::from_f64(cents(1.3 * trade.contracts as f64)),
+            implied_volatility: trade.implied_volatility,
+            entry_date: trade.entry_date,
+            initial_target: None,
+            profit_target: None,
+            trade_ratings: None,
+            reviewed: Some(true),
+            mistakes: None,
+            brokerage_name: Some(DEMO_BROKERAGE.to_string()),
+            venue: None,
+            short_premium: Some(false),
+        };
+        let created = OptionTrade::create(conn, request).await.map_err(|e| anyhow!(e))?;
+        track(conn, "options", &created.id.to_string()).await?;
+        let close = UpdateOptionRequest {
+            symbol: None,
+            strategy_type: None,
+            trade_direction: None,
+            number_of_contracts: None,
+            option_type: None,
+            strike_price: None,
+            expiration_date: None,
+            entry_price: None,
+            exit_price: Some(money::from_f64(trade.exit_price)),
+            total_premium: None,
+            commissions: None,
+            implied_volatility: None,
+            entry_date: None,
+            exit_date: Some(trade.exit_date),
+            status: Some(TradeStatus::Closed),
+            initial_target: None,
+            profit_target: None,
+            trade_ratings: None,
+            reviewed: None,
+            mistakes: None,
+            brokerage_name: None,
+            venue: None,
+            short_premium: None,
+        };
+        let closed = OptionTrade::update(conn, created.id, close)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .ok_or_else(|| anyhow!("Demo option trade {} disappeared", created.id))?;
+        for tag in &trade.tags {
+            TradeTagAssociation::add_tag_to_option_trade(conn, closed.id, &tag_ids[*tag]).await?;
+        }
+        options.push(closed);
+    }
+
+    let mut created_notes = Vec::new();
+    for (position, (title, content)) in notes().into_iter().enumerate() {
+        let request = CreateNoteRequest { parent_id: None, title: title.to_string(), content: Some(content), position: Some(position as i64) };
+        let note = NotebookNote::create(conn, request).await?;
+        track(conn, "notebook_notes", &note.id).await?;
+        created_notes.push(note);
+    }
+
+    Ok(SeededDemoData { stocks, options, notes: created_notes, tags, playbooks })
+}
+
+/// Delete every tracked demo row and whatever hangs off it. Rows the user created are
+/// kept, including their own tags and playbooks attached to demo trades.
+pub async fn teardown(conn: &Connection) -> Result<RemovedDemoData> {
+    let mut ids: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for table in TRACKED_TABLES {
+        ids.insert(*table, tracked_ids(conn, table).await?);
+    }
+    let tracked = |table: &str| format!("(SELECT row_id FROM demo_data_rows WHERE table_name = '{}')", table);
+
+    let statements = [
+        format!("DELETE FROM stock_trade_tags WHERE CAST(stock_trade_id AS TEXT) IN {0} OR tag_id IN {1}", tracked("stocks"), tracked("trade_tags")),
+        format!("DELETE FROM option_trade_tags WHERE CAST(option_trade_id AS TEXT) IN {0} OR tag_id IN {1}", tracked("options"), tracked("trade_tags")),
+        format!("DELETE FROM stock_trade_playbook WHERE CAST(stock_trade_id AS TEXT) IN {0} OR setup_id IN {1}", tracked("stocks"), tracked("playbook")),
+        format!("DELETE FROM option_trade_playbook WHERE CAST(option_trade_id AS TEXT) IN {0} OR setup_id IN {1}", tracked("options"), tracked("playbook")),
+        format!("DELETE FROM stock_trade_rule_compliance WHERE CAST(stock_trade_id AS TEXT) IN {0} OR playbook_id IN {1}", tracked("stocks"), tracked("playbook")),
+        format!("DELETE FROM option_trade_rule_compliance WHERE CAST(option_trade_id AS TEXT) IN {0} OR playbook_id IN {1}", tracked("options"), tracked("playbook")),
+        format!("DELETE FROM trade_notes WHERE CAST(stock_trade_id AS TEXT) IN {}", tracked("stocks")),
+        format!("DELETE FROM trade_notes WHERE CAST(option_trade_id AS TEXT) IN {}", tracked("options")),
+        format!("DELETE FROM playbook_rules WHERE playbook_id IN {}", tracked("playbook")),
+        format!("DELETE FROM notebook_note_tags WHERE note_id IN {}", tracked("notebook_notes")),
+        format!("DELETE FROM notebook_reminders WHERE note_id IN {}", tracked("notebook_notes")),
+        format!("UPDATE notebook_notes SET parent_id = NULL WHERE parent_id IN {}", tracked("notebook_notes")),
+    ];
+
+    let tx = conn.transaction().await?;
+    for sql in &statements {
+        tx.execute(sql, params![]).await?;
+    }
+    for table in TRACKED_TABLES {
+        let key = if matches!(*table, "stocks" | "options") { "CAST(id AS TEXT)" } else { "id" };
+        tx.execute(&format!("DELETE FROM {} WHERE {} IN {}", table, key, tracked(table)), params![]).await?;
+    }
+    tx.execute("DELETE FROM demo_data_rows", params![]).await?;
+    tx.commit().await?;
+
+    let parse = |table: &str| ids[table].iter().filter_map(|id| id.parse().ok()).collect();
+    Ok(RemovedDemoData {
+        stock_ids: parse("stocks"),
+        option_ids: parse("options"),
+        note_ids: ids["notebook_notes"].clone(),
+        tags: ids["trade_tags"].len() as u32,
+        playbooks: ids["playbook"].len() as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_stock_plan_is_stable_and_realistic() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 15, 0, 0).unwrap();
+        let plan = stock_plan(now);
+        assert_eq!(plan, stock_plan(now));
+        assert_eq!(plan.len(), STOCK_TRADES);
+
+        let pnl = |t: &DemoStockTrade| {
+            let direction = if t.trade_type == TradeType::BUY { 1.0 } else { -1.0 };
+            (t.exit_price - t.entry_price) * direction * t.shares
+        };
+        let winners = plan.iter().filter(|t| pnl(t) > 0.0).count();
+        assert!(winners > STOCK_TRADES / 4 && winners < STOCK_TRADES * 3 / 4);
+        for trade in &plan {
+            assert!(trade.exit_date > trade.entry_date);
+            assert!(trade.exit_date < now - Duration::days(1));
+            assert!(!matches!(trade.entry_date.weekday(), Weekday::Sat | Weekday::Sun));
+            // The stop sits on the losing side of the entry
+            let direction = if trade.trade_type == TradeType::BUY { 1.0 } else { -1.0 };
+            assert!((trade.entry_price - trade.stop_loss) * direction > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_option_plan_expires_after_exit() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 15, 0, 0).unwrap();
+        let plan = option_plan(now);
+        assert_eq!(plan.len(), OPTION_TRADES);
+        for trade in &plan {
+            assert!(trade.expiration_date > trade.exit_date);
+            assert!(trade.contracts >= 1 && trade.exit_price > 0.0);
+        }
+    }
+}
@@ -0,0 +1,334 @@
+// Focus (pomodoro) sessions. Traders start and stop timed sessions and annotate them with
+// notes and a 1-5 focus rating; sessions are grouped by the day they were started so each
+// day's focus can be set against that day's closed-trade P&L ("process over outcome").
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use libsql::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use super::analytics_engine::consistency::fetch_daily_pnl;
+use super::analytics_engine::correlation::pearson;
+use crate::models::stock::stocks::TimeRange;
+
+/// Fewest trading days with focus data before a correlation is reported
+const MIN_CORRELATION_DAYS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusSession {
+    pub id: String,
+    /// Local day the session counts toward, `YYYY-MM-DD`
+    pub session_date: String,
+    pub started_at: String,
+    /// None while the session is running
+    pub ended_at: Option<String>,
+    pub planned_minutes: Option<i64>,
+    pub label: Option<String>,
+    pub notes: Option<String>,
+    pub focus_rating: Option<i64>,
+    /// Minutes between start and stop, None while running
+    pub duration_minutes: Option<f64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StartFocusSessionRequest {
+    /// The client's local date; defaults to today in UTC
+    pub session_date: Option<String>,
+    pub planned_minutes: Option<i64>,
+    pub label: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnnotateFocusSessionRequest {
+    pub notes: Option<String>,
+    pub focus_rating: Option<i64>,
+    pub label: Option<String>,
+}
+
+impl StartFocusSessionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(date) = &self.session_date
+            && NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err()
+        {
+            return Err("session_date must be YYYY-MM-DD".to_string());
+        }
+        if self.planned_minutes.is_some_and(|m| !(1..=480).contains(&m)) {
+            return Err("planned_minutes must be between 1 and 480".to_string());
+        }
+        Ok(())
+    }
+}
+
+impl AnnotateFocusSessionRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.focus_rating.is_some_and(|r| !(1..=5).contains(&r)) {
+            return Err("focus_rating must be between 1 and 5".to_string());
+        }
+        Ok(())
+    }
+}
+
+const SELECT_COLUMNS: &str = "id, session_date, started_at, ended_at, planned_minutes, label, notes, focus_rating, created_at, updated_at";
+
+impl FocusSession {
+    fn from_row(row: &Row) -> Result<Self> {
+        let started_at: String = row.get(2)?;
+        let ended_at: Option<String> = row.get(3)?;
+        let duration_minutes = ended_at.as_deref().and_then(|end| minutes_between(&started_at, end));
+        Ok(Self {
+            id: row.get(0)?,
+            session_date: row.get(1)?,
+            started_at,
+            ended_at,
+            planned_minutes: row.get(4)?,
+            label: row.get(5)?,
+            notes: row.get(6)?,
+            focus_rating: row.get(7)?,
+            duration_minutes,
+            created_at: row.get(8)?,
+            updated_at: row.get(9)?,
+        })
+    }
+
+    pub async fn find_by_id(conn: &Connection, id: &str) -> Result<Option<Self>> {
+        let sql = format!("SELECT {} FROM focus_sessions WHERE id = ?", SELECT_COLUMNS);
+        let mut rows = conn.prepare(&sql).await?.query(params![id]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The running session, if any; only one runs at a time
+    pub async fn find_active(conn: &Connection) -> Result<Option<Self>> {
+        let sql = format!(
+            "SELECT {} FROM focus_sessions WHERE ended_at IS NULL ORDER BY started_at DESC LIMIT 1",
+            SELECT_COLUMNS
+        );
+        let mut rows = conn.prepare(&sql).await?.query(params![]).await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(Self::from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sessions on days within `start..=end`, oldest first
+    pub async fn list(conn: &Connection, start: &str, end: &str) -> Result<Vec<Self>> {
+        let sql = format!(
+            "SELECT {} FROM focus_sessions WHERE session_date >= ? AND session_date <= ? ORDER BY started_at ASC",
+            SELECT_COLUMNS
+        );
+        let mut rows = conn.prepare(&sql).await?.query(params![start, end]).await?;
+        let mut sessions = Vec::new();
+        while let Some(row) = rows.next().await? {
+            sessions.push(Self::from_row(&row)?);
+        }
+        Ok(sessions)
+    }
+
+    pub async fn start(conn: &Connection, request: StartFocusSessionRequest) -> Result<Self> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        let session_date = request.session_date.unwrap_or_else(|| now.format("%Y-%m-%d").to_string());
+        conn.execute(
+            "INSERT INTO focus_sessions (id, session_date, started_at, planned_minutes, label) VALUES (?, ?, ?, ?, ?)",
+            params![id.as_str(), session_date, now.to_rfc3339(), request.planned_minutes, request.label],
+        )
+        .await?;
+        Self::find_by_id(conn, &id).await?.ok_or_else(|| anyhow::anyhow!("Focus session not found after insert"))
+    }
+
+    /// Stop a running session; a session that was already stopped is returned unchanged
+    pub async fn stop(conn: &Connection, id: &str) -> Result<Option<Self>> {
+        conn.execute(
+            "UPDATE focus_sessions SET ended_at = ? WHERE id = ? AND ended_at IS NULL",
+            params![Utc::now().to_rfc3339(), id],
+        )
+        .await?;
+        Self::find_by_id(conn, id).await
+    }
+
+    /// Set notes, rating or label; fields left out keep their values
+    pub async fn annotate(conn: &Connection, id: &str, request: AnnotateFocusSessionRequest) -> Result<Option<Self>> {
+        conn.execute(
+            "UPDATE focus_sessions SET notes = COALESCE(?, notes), focus_rating = COALESCE(?, focus_rating), \
+             label = COALESCE(?, label) WHERE id = ?",
+            params![request.notes, request.focus_rating, request.label, id],
+        )
+        .await?;
+        Self::find_by_id(conn, id).await
+    }
+
+    pub async fn delete(conn: &Connection, id: &str) -> Result<bool> {
+        let affected = conn.execute("DELETE FROM focus_sessions WHERE id = ?", params![id]).await?;
+        Ok(affected > 0)
+    }
+}
+
+fn minutes_between(start: &str, end: &str) -> Option<f64> {
+    let start = DateTime::parse_from_rfc3339(start).ok()?;
+    let end = DateTime::parse_from_rfc3339(end).ok()?;
+    Some(((end - start).num_seconds().max(0) as f64 / 60.0 * 10.0).round() / 10.0)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusDay {
+    pub date: String,
+    pub sessions: u32,
+    pub focus_minutes: f64,
+    pub avg_focus_rating: Option<f64>,
+    /// Closed-trade P&L that day, None when nothing closed
+    pub pnl: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DayGroupSummary {
+    pub trading_days: u32,
+    pub total_pnl: f64,
+    pub avg_pnl: f64,
+    /// Share of trading days that closed green, 0-100
+    pub win_day_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FocusPerformance {
+    pub since: String,
+    /// Trading days with at least one completed focus session
+    pub with_focus: DayGroupSummary,
+    pub without_focus: DayGroupSummary,
+    /// Pearson correlation of focus minutes and P&L across trading days, None with too few days
+    pub focus_minutes_pnl_correlation: Option<f64>,
+    pub days: Vec<FocusDay>,
+}
+
+/// Focus time against trading results for days on or after `since` (`YYYY-MM-DD`)
+pub async fn focus_performance(conn: &Connection, since: &str) -> Result<FocusPerformance> {
+    let mut rows = conn
+        .prepare(
+            "SELECT session_date, started_at, ended_at, focus_rating FROM focus_sessions \
+             WHERE ended_at IS NOT NULL AND session_date >= ?",
+        )
+        .await?
+        .query(params![since])
+        .await?;
+    let mut focus: BTreeMap<String, FocusDay> = BTreeMap::new();
+    let mut ratings: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    while let Some(row) = rows.next().await? {
+        let date: String = row.get(0)?;
+        let minutes = minutes_between(&row.get::<String>(1)?, &row.get::<String>(2)?).unwrap_or(0.0);
+        let day = focus.entry(date.clone()).or_insert_with(|| FocusDay {
+            date: date.clone(),
+            sessions: 0,
+            focus_minutes: 0.0,
+            avg_focus_rating: None,
+            pnl: None,
+        });
+        day.sessions += 1;
+        day.focus_minutes += minutes;
+        if let Some(rating) = row.get::<Option<i64>>(3)? {
+            ratings.entry(date).or_default().push(rating);
+        }
+    }
+    for (date, values) in ratings {
+        if let Some(day) = focus.get_mut(&date) {
+            day.avg_focus_rating = Some(values.iter().sum::<i64>() as f64 / values.len() as f64);
+        }
+    }
+
+    let start = NaiveDate::parse_from_str(since, "%Y-%m-%d")?.and_hms_opt(0, 0, 0).map(|d| d.and_utc());
+    let (time_condition, time_params) = TimeRange::Custom { start_date: start, end_date: None }.to_sql_condition();
+    let daily_pnl = fetch_daily_pnl(conn, &time_condition, &time_params).await?;
+    Ok(correlate(since, focus, &daily_pnl))
+}
+
+fn correlate(since: &str, mut focus: BTreeMap<String, FocusDay>, daily_pnl: &[(String, f64)]) -> FocusPerformance {
+    let mut with_focus = Vec::new();
+    let mut without_focus = Vec::new();
+    // Trading days paired with their focus minutes, zero when there was no session
+    let mut pairs = Vec::new();
+    for (date, pnl) in daily_pnl {
+        match focus.get_mut(date) {
+            Some(day) => {
+                day.pnl = Some(*pnl);
+                with_focus.push(*pnl);
+                pairs.push((day.focus_minutes, *pnl));
+            }
+            None => {
+                without_focus.push(*pnl);
+                pairs.push((0.0, *pnl));
+            }
+        }
+    }
+
+    let correlation = if with_focus.len() >= MIN_CORRELATION_DAYS { pearson(&pairs) } else { None };
+    FocusPerformance {
+        since: since.to_string(),
+        with_focus: summarize(&with_focus),
+        without_focus: summarize(&without_focus),
+        focus_minutes_pnl_correlation: correlation,
+        days: focus.into_values().collect(),
+    }
+}
+
+fn summarize(pnls: &[f64]) -> DayGroupSummary {
+    if pnls.is_empty() {
+        return DayGroupSummary::default();
+    }
+    let total_pnl: f64 = pnls.iter().sum();
+    let wins = pnls.iter().filter(|p| **p > 0.0).count();
+    DayGroupSummary {
+        trading_days: pnls.len() as u32,
+        total_pnl,
+        avg_pnl: total_pnl / pnls.len() as f64,
+        win_day_rate: wins as f64 / pnls.len() as f64 * 100.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, minutes: f64) -> (String, FocusDay) {
+        let focus = FocusDay { date: date.to_string(), sessions: 1, focus_minutes: minutes, avg_focus_rating: None, pnl: None };
+        (date.to_string(), focus)
+    }
+
+    #[test]
+    fn test_correlate_splits_days_and_correlates_minutes() {
+        let focus: BTreeMap<String, FocusDay> = [
+            day("2025-03-03", 25.0),
+            day("2025-03-04", 50.0),
+            day("2025-03-05", 75.0),
+            day("2025-03-06", 100.0),
+            day("2025-03-07", 125.0),
+            // Focused but no trades closed
+            day("2025-03-08", 25.0),
+        ]
+        .into_iter()
+        .collect();
+        let daily_pnl: Vec<(String, f64)> = [
+            ("2025-03-03", 10.0),
+            ("2025-03-04", 20.0),
+            ("2025-03-05", 30.0),
+            ("2025-03-06", 40.0),
+            ("2025-03-07", 50.0),
+            ("2025-03-10", -100.0),
+        ]
+        .into_iter()
+        .map(|(d, p)| (d.to_string(), p))
+        .collect();
+
+        let performance = correlate("2025-03-01", focus, &daily_pnl);
+        assert_eq!(performance.with_focus.trading_days, 5);
+        assert_eq!(performance.with_focus.win_day_rate, 100.0);
+        assert_eq!(performance.without_focus.avg_pnl, -100.0);
+        assert!(performance.focus_minutes_pnl_correlation.unwrap() > 0.5);
+        assert_eq!(performance.days.len(), 6);
+        assert!(performance.days[5].pnl.is_none());
+        assert_eq!(minutes_between("2025-03-03T09:00:00Z", "2025-03-03T09:25:30Z"), Some(25.5));
+    }
+}
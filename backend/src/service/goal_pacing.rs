@@ -0,0 +1,282 @@
+// Monthly goal pacing. Month-to-date P&L and trade count are compared with the user's
+// monthly goal and projected to month end at the current per-trading-day run rate. The
+// conservative and aggressive bands are one standard deviation of the remaining days'
+// expected total either side of the projection, so a choppy month gets a wide band.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthlyGoal {
+    /// `YYYY-MM`
+    pub month: String,
+    pub pnl_target: f64,
+    pub trade_target: Option<i64>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetMonthlyGoalRequest {
+    /// `YYYY-MM`; defaults to the current month
+    pub month: Option<String>,
+    pub pnl_target: f64,
+    pub trade_target: Option<i64>,
+}
+
+impl SetMonthlyGoalRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(month) = &self.month
+            && parse_month(month).is_none()
+        {
+            return Err("month must be YYYY-MM".to_string());
+        }
+        if !self.pnl_target.is_finite() || self.pnl_target <= 0.0 {
+            return Err("pnl_target must be a positive amount".to_string());
+        }
+        if self.trade_target.is_some_and(|t| t <= 0) {
+            return Err("trade_target must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A month-end projection with its conservative and aggressive bounds
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Projection {
+    pub conservative: f64,
+    pub expected: f64,
+    pub aggressive: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GoalPacing {
+    pub month: String,
+    pub goal: MonthlyGoal,
+    pub trading_days_elapsed: u32,
+    pub trading_days_total: u32,
+    pub mtd_pnl: f64,
+    pub mtd_trades: u32,
+    /// Share of the P&L goal reached so far
+    pub pnl_progress_pct: f64,
+    /// Month-to-date P&L against where an even pace would be by today (100 = on pace)
+    pub pace_pct: f64,
+    pub projected_pnl: Projection,
+    /// Set when the goal has a trade target
+    pub projected_trades: Option<Projection>,
+    /// Average P&L per remaining trading day still needed to hit the goal
+    pub required_daily_pnl: Option<f64>,
+    /// "ahead", "on_track" or "behind"
+    pub status: String,
+}
+
+pub fn parse_month(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d").ok()
+}
+
+/// Trading dates of the month containing `first`, by the user's excluded weekdays
+pub fn trading_days_in_month(periods: &PeriodDefinition, first: NaiveDate) -> Vec<NaiveDate> {
+    first
+        .iter_days()
+        .take_while(|day| day.month() == first.month())
+        .filter(|day| !periods.excluded_weekdays.contains(&(day.weekday().num_days_from_sunday() as u8)))
+        .collect()
+}
+
+/// Month-end total from per-day values observed so far. `per_day` holds one value per
+/// elapsed trading day, zeros included; `remaining` is the trading days left.
+pub fn project(mtd: f64, per_day: &[f64], remaining: u32) -> Projection {
+    if per_day.is_empty() {
+        return Projection { conservative: mtd, expected: mtd, aggressive: mtd };
+    }
+    let n = per_day.len() as f64;
+    let mean = per_day.iter().sum::<f64>() / n;
+    let variance = per_day.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    let expected = mtd + mean * remaining as f64;
+    let band = variance.sqrt() * (remaining as f64).sqrt();
+    Projection { conservative: expected - band, expected, aggressive: expected + band }
+}
+
+fn status(projection: &Projection, target: f64) -> &'static str {
+    if projection.conservative >= target {
+        "ahead"
+    } else if projection.expected >= target {
+        "on_track"
+    } else {
+        "behind"
+    }
+}
+
+fn row_to_goal(row: &libsql::Row) -> Result<MonthlyGoal> {
+    Ok(MonthlyGoal {
+        month: row.get(0)?,
+        pnl_target: money::to_f64(money::row_decimal(row, 1)),
+        trade_target: row.get(2)?,
+        created_at: row.get(3)?,
+        updated_at: row.get(4)?,
+    })
+}
+
+/// Goal in force for `month`: its own, or the latest one set for an earlier month
+pub async fn goal_for_month(conn: &Connection, month: &str) -> Result<Option<MonthlyGoal>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT month, pnl_target, trade_target, created_at, updated_at FROM monthly_goals \
+             WHERE month <= ? ORDER BY month DESC LIMIT 1",
+        )
+        .await?
+        .query(params![month])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_goal(&row)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn list_goals(conn: &Connection) -> Result<Vec<MonthlyGoal>> {
+    let mut rows = conn
+        .prepare("SELECT month, pnl_target, trade_target, created_at, updated_at FROM monthly_goals ORDER BY month DESC")
+        .await?
+        .query(params![])
+        .await?;
+    let mut goals = Vec::new();
+    while let Some(row) = rows.next().await? {
+        goals.push(row_to_goal(&row)?);
+    }
+    Ok(goals)
+}
+
+pub async fn set_goal(conn: &Connection, month: &str, pnl_target: f64, trade_target: Option<i64>) -> Result<MonthlyGoal> {
+    let now = Utc::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO monthly_goals (month, pnl_target, trade_target, created_at, updated_at) VALUES (?, ?, ?, ?, ?) \
+         ON CONFLICT(month) DO UPDATE SET pnl_target = excluded.pnl_target, trade_target = excluded.trade_target, updated_at = excluded.updated_at",
+        params![month, pnl_target, trade_target, now.clone(), now],
+    )
+    .await?;
+    goal_for_month(conn, month).await?.ok_or_else(|| anyhow::anyhow!("Goal not found after saving"))
+}
+
+pub async fn delete_goal(conn: &Connection, month: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM monthly_goals WHERE month = ?", params![month]).await? > 0)
+}
+
+/// Realized P&L and closed trades per trading date of the month
+pub(crate) async fn daily_totals(conn: &Connection, periods: &PeriodDefinition, month: &str) -> Result<HashMap<NaiveDate, (f64, u32)>> {
+    let day = format!("date({})", periods.trading_date_sql("exit_date"));
+    let sql = format!(
+        "SELECT day, COALESCE(SUM(pnl), 0), COUNT(*) FROM (
+            SELECT {day} AS day, {STOCK_PNL_SQL} AS pnl FROM stocks
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+            UNION ALL
+            SELECT {day} AS day, {OPTION_PNL_SQL} AS pnl FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+        ) WHERE strftime('%Y-%m', day) = ? GROUP BY day"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![month]).await?;
+    let mut totals = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(date) = NaiveDate::parse_from_str(&row.get::<String>(0)?, "%Y-%m-%d") {
+            totals.insert(date, (money::to_f64(money::row_decimal(&row, 1)), row.get::<i64>(2)? as u32));
+        }
+    }
+    Ok(totals)
+}
+
+/// First trading date on which the month's running P&L reached the target
+pub fn goal_hit_date(totals: &HashMap<NaiveDate, (f64, u32)>, pnl_target: f64) -> Option<NaiveDate> {
+    let mut days: Vec<(&NaiveDate, f64)> = totals.iter().map(|(day, t)| (day, t.0)).collect();
+    days.sort_by_key(|(day, _)| **day);
+    let mut running = 0.0;
+    days.into_iter().find_map(|(day, pnl)| {
+        running += pnl;
+        (running >= pnl_target).then_some(*day)
+    })
+}
+
+/// Pacing for the current month, or None when no goal is set
+pub async fn pacing(conn: &Connection, now: DateTime<Utc>) -> Result<Option<GoalPacing>> {
+    let periods = PeriodDefinition::load(conn).await;
+    let today = periods.trading_date(now);
+    let first = today - Duration::days(today.day0() as i64);
+    let month = first.format("%Y-%m").to_string();
+    let Some(goal) = goal_for_month(conn, &month).await? else {
+        return Ok(None);
+    };
+
+    let days = trading_days_in_month(&periods, first);
+    let elapsed: Vec<NaiveDate> = days.iter().copied().filter(|day| *day <= today).collect();
+    let remaining = (days.len() - elapsed.len()) as u32;
+    let totals = daily_totals(conn, &periods, &month).await?;
+    let daily = |pick: fn(&(f64, u32)) -> f64| -> Vec<f64> {
+        elapsed.iter().map(|day| totals.get(day).map(pick).unwrap_or(0.0)).collect()
+    };
+    let daily_pnl = daily(|t| t.0);
+    let daily_trades = daily(|t| t.1 as f64);
+    let mtd_pnl: f64 = totals.values().map(|t| t.0).sum();
+    let mtd_trades: u32 = totals.values().map(|t| t.1).sum();
+
+    let projected_pnl = project(mtd_pnl, &daily_pnl, remaining);
+    let projected_trades = goal.trade_target.map(|_| {
+        let p = project(mtd_trades as f64, &daily_trades, remaining);
+        Projection { conservative: p.conservative.max(mtd_trades as f64), ..p }
+    });
+    let expected_by_now = goal.pnl_target * elapsed.len() as f64 / days.len().max(1) as f64;
+
+    Ok(Some(GoalPacing {
+        month,
+        trading_days_elapsed: elapsed.len() as u32,
+        trading_days_total: days.len() as u32,
+        mtd_pnl,
+        mtd_trades,
+        pnl_progress_pct: mtd_pnl / goal.pnl_target * 100.0,
+        pace_pct: if expected_by_now > 0.0 { mtd_pnl / expected_by_now * 100.0 } else { 0.0 },
+        status: status(&projected_pnl, goal.pnl_target).to_string(),
+        required_daily_pnl: (remaining > 0).then(|| ((goal.pnl_target - mtd_pnl) / remaining as f64).max(0.0)),
+        projected_pnl,
+        projected_trades,
+        goal,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_bands_follow_daily_spread() {
+        let steady = project(400.0, &[100.0, 100.0, 100.0, 100.0], 16);
+        assert_eq!(steady, Projection { conservative: 2000.0, expected: 2000.0, aggressive: 2000.0 });
+
+        let choppy = project(400.0, &[300.0, -100.0, 300.0, -100.0], 16);
+        assert_eq!(choppy.expected, 2000.0);
+        assert_eq!(choppy.conservative, 2000.0 - 200.0 * 4.0);
+        assert_eq!(status(&choppy, 1500.0), "on_track");
+        assert_eq!(status(&steady, 1500.0), "ahead");
+        assert_eq!(status(&choppy, 2500.0), "behind");
+    }
+
+    #[test]
+    fn test_trading_days_skip_excluded_weekdays() {
+        let periods = PeriodDefinition { excluded_weekdays: vec![0, 6], ..Default::default() };
+        let first = parse_month("2026-10").unwrap();
+        assert_eq!(trading_days_in_month(&periods, first).len(), 22);
+        assert_eq!(trading_days_in_month(&PeriodDefinition::default(), first).len(), 31);
+        assert!(parse_month("2026-13").is_none());
+    }
+
+    #[test]
+    fn test_goal_hit_date_is_first_crossing() {
+        let day = |d: u32| NaiveDate::from_ymd_opt(2026, 10, d).unwrap();
+        let totals = HashMap::from([(day(9), (-200.0, 2)), (day(2), (600.0, 1)), (day(6), (500.0, 3)), (day(12), (400.0, 1))]);
+        assert_eq!(goal_hit_date(&totals, 1000.0), Some(day(6)));
+        assert_eq!(goal_hit_date(&totals, 1500.0), None);
+    }
+}
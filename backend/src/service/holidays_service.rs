@@ -3,10 +3,33 @@ use libsql::{Connection, params};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 
+use crate::turso::in_transaction;
+
+/// Free public holiday API, no key required
+const NAGER_BASE_URL: &str = "https://date.nager.at/api/v3";
+/// Countries refreshed for everyone, on top of the ones a user already synced
+const DEFAULT_COUNTRY_CODES: &str = "US";
+
+/// One entry of Nager.Date's `PublicHolidays/{year}/{country}` response
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NagerHoliday {
+    date: String,
+    local_name: String,
+    name: String,
+    /// False for holidays observed only in some states or provinces
+    #[serde(default = "default_true")]
+    global: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
 #[derive(Debug, Clone)]
 pub struct HolidaysService;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PublicHoliday {
     pub id: String,
     pub country_code: String,
@@ -62,6 +85,87 @@ impl HolidaysService {
         Ok(holidays)
     }
     
+    /// Fetch public holidays from Nager.Date
+    pub async fn fetch_nager_holidays(country_code: &str, year: i32) -> Result<Vec<PublicHoliday>> {
+        let url = format!("{}/PublicHolidays/{}/{}", NAGER_BASE_URL, year, country_code.to_uppercase());
+        let resp = Client::new().get(url).send().await?;
+
+        // Unknown country codes come back as 404
+        if !resp.status().is_success() {
+            return Err(anyhow::anyhow!("Failed to fetch holidays for {} {}: {}", country_code, year, resp.status()));
+        }
+
+        let entries: Vec<NagerHoliday> = resp.json().await?;
+        Ok(Self::from_nager(country_code, entries))
+    }
+
+    fn from_nager(country_code: &str, entries: Vec<NagerHoliday>) -> Vec<PublicHoliday> {
+        entries
+            .into_iter()
+            .map(|entry| PublicHoliday {
+                id: uuid::Uuid::new_v4().to_string(),
+                country_code: country_code.to_uppercase(),
+                description: (entry.local_name != entry.name).then_some(entry.local_name),
+                holiday_name: entry.name,
+                holiday_date: entry.date,
+                is_national: entry.global,
+            })
+            .collect()
+    }
+
+    /// Countries to keep populated: the `HOLIDAY_COUNTRY_CODES` list (comma separated, "US"
+    /// when unset) plus every country the user already has holidays stored for
+    pub async fn configured_countries(conn: &Connection) -> Result<Vec<String>> {
+        let configured = std::env::var("HOLIDAY_COUNTRY_CODES").unwrap_or_else(|_| DEFAULT_COUNTRY_CODES.to_string());
+        let mut countries: Vec<String> = configured
+            .split(',')
+            .map(|code| code.trim().to_uppercase())
+            .filter(|code| Self::is_country_code(code))
+            .collect();
+
+        let mut rows = conn.query("SELECT DISTINCT country_code FROM public_holidays", params![]).await?;
+        while let Some(row) = rows.next().await? {
+            let code = row.get::<String>(0)?.to_uppercase();
+            if !countries.contains(&code) {
+                countries.push(code);
+            }
+        }
+        Ok(countries)
+    }
+
+    /// ISO 3166-1 alpha-2, e.g. "US" or "de"
+    pub fn is_country_code(code: &str) -> bool {
+        code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic())
+    }
+
+    /// Replace one country's holidays for `year` with `holidays`, so renamed or cancelled
+    /// holidays don't linger next to the current list. Returns how many were stored.
+    pub async fn replace_year(conn: &Connection, country_code: &str, year: i32, holidays: Vec<PublicHoliday>) -> Result<u64> {
+        let country_code = country_code.to_uppercase();
+        in_transaction(conn, async |tx: &Connection| -> Result<u64> {
+            tx.execute(
+                "DELETE FROM public_holidays WHERE country_code = ? AND holiday_date BETWEEN ? AND ?",
+                params![country_code.clone(), format!("{}-01-01", year), format!("{}-12-31", year)],
+            )
+            .await?;
+            Self::store_holidays(tx, holidays).await
+        })
+        .await
+    }
+
+    /// Holidays for one country and year, fetched and stored the first time they're asked for
+    pub async fn holidays_for_year(conn: &Connection, country_code: &str, year: i32) -> Result<Vec<PublicHoliday>> {
+        let country_code = country_code.to_uppercase();
+        let (start, end) = (format!("{}-01-01", year), format!("{}-12-31", year));
+        let stored = Self::get_holidays(conn, &country_code, &start, &end).await?;
+        if !stored.is_empty() {
+            return Ok(stored);
+        }
+        let fetched = Self::fetch_nager_holidays(&country_code, year).await?;
+        Self::replace_year(conn, &country_code, year, fetched).await?;
+        Self::get_holidays(conn, &country_code, &start, &end).await
+    }
+
     /// Store holidays in database
     pub async fn store_holidays(conn: &Connection, holidays: Vec<PublicHoliday>) -> Result<u64> {
         let mut inserted = 0u64;
@@ -111,3 +215,26 @@ impl HolidaysService {
         Ok(holidays)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_nager_keeps_local_name_and_scope() {
+        let entries: Vec<NagerHoliday> = serde_json::from_value(serde_json::json!([
+            {"date": "2025-10-03", "localName": "Tag der Deutschen Einheit", "name": "German Unity Day", "countryCode": "DE", "global": true},
+            {"date": "2025-01-06", "localName": "Heilige Drei Könige", "name": "Epiphany", "countryCode": "DE", "global": false, "counties": ["DE-BW"]},
+            {"date": "2025-12-25", "localName": "Christmas Day", "name": "Christmas Day", "countryCode": "DE"}
+        ]))
+        .unwrap();
+        let holidays = HolidaysService::from_nager("de", entries);
+        assert_eq!(holidays[0].country_code, "DE");
+        assert_eq!(holidays[0].description.as_deref(), Some("Tag der Deutschen Einheit"));
+        assert!(!holidays[1].is_national);
+        assert!(holidays[2].is_national && holidays[2].description.is_none());
+
+        assert!(HolidaysService::is_country_code("gb"));
+        assert!(!HolidaysService::is_country_code("USA"));
+    }
+}
@@ -0,0 +1,333 @@
+use libsql::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Languages the AI can answer in and the API has labels for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+    De,
+    Pt,
+}
+
+pub const SUPPORTED_LOCALES: &[Locale] = &[Locale::En, Locale::Es, Locale::Fr, Locale::De, Locale::Pt];
+
+impl Locale {
+    /// Accepts "es", "es-MX", "pt_BR", etc.
+    pub fn parse(value: &str) -> Option<Self> {
+        let lang = value.trim().split(['-', '_']).next()?.to_lowercase();
+        match lang.as_str() {
+            "en" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            "fr" => Some(Locale::Fr),
+            "de" => Some(Locale::De),
+            "pt" => Some(Locale::Pt),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+            Locale::Fr => "fr",
+            Locale::De => "de",
+            Locale::Pt => "pt",
+        }
+    }
+
+    pub fn language_name(&self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Es => "Spanish",
+            Locale::Fr => "French",
+            Locale::De => "German",
+            Locale::Pt => "Portuguese",
+        }
+    }
+
+    /// Instruction appended to AI prompts; None for English since prompts are already in English
+    pub fn prompt_instruction(&self) -> Option<String> {
+        match self {
+            Locale::En => None,
+            other => Some(format!(
+                "Write all prose in {}. Keep ticker symbols and any JSON keys unchanged.",
+                other.language_name()
+            )),
+        }
+    }
+
+    /// (group, decimal) separators for numbers written in this language
+    pub fn separators(&self) -> (char, char) {
+        match self {
+            Locale::En => (',', '.'),
+            Locale::Es | Locale::De | Locale::Pt => ('.', ','),
+            Locale::Fr => ('\u{202F}', ','),
+        }
+    }
+
+    /// First supported language in an Accept-Language header
+    pub fn from_accept_language(header: &str) -> Option<Self> {
+        header.split(',').filter_map(|part| Locale::parse(part.split(';').next().unwrap_or(""))).next()
+    }
+}
+
+impl std::fmt::Display for Locale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// Locale stored in the user's profile (English when unset or the profile doesn't exist yet)
+pub async fn user_locale(conn: &Connection) -> Locale {
+    let locale: Option<String> = match conn.prepare("SELECT locale FROM user_profile LIMIT 1").await {
+        Ok(stmt) => match stmt.query(params![]).await {
+            Ok(mut rows) => rows.next().await.ok().flatten().and_then(|row| row.get(0).ok()),
+            Err(_) => None,
+        },
+        Err(_) => None,
+    };
+    locale.as_deref().and_then(Locale::parse).unwrap_or_default()
+}
+
+/// How amounts are written for one user: their locale's separators and their profile
+/// currency. Sent to clients as formatting metadata and handed to AI prompts so figures
+/// come back the way the user reads them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NumberFormat {
+    pub locale: Locale,
+    /// ISO 4217 code from the profile
+    pub currency: String,
+    pub currency_symbol: String,
+    pub group_separator: char,
+    pub decimal_separator: char,
+    /// Minor units the currency is written with (0 for JPY)
+    pub fraction_digits: usize,
+    /// Currencies of connected brokerage accounts other than `currency`. Their amounts are
+    /// stored as reported, never converted.
+    pub other_currencies: Vec<String>,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        Self::new(Locale::En, "USD")
+    }
+}
+
+impl NumberFormat {
+    pub fn new(locale: Locale, currency: &str) -> Self {
+        let currency = currency.trim().to_uppercase();
+        let (group_separator, decimal_separator) = locale.separators();
+        let currency_symbol = match currency.as_str() {
+            "USD" => "$".to_string(),
+            "EUR" => "€".to_string(),
+            "GBP" => "£".to_string(),
+            "JPY" => "¥".to_string(),
+            "INR" => "₹".to_string(),
+            "CAD" => "CA$".to_string(),
+            "AUD" => "A$".to_string(),
+            "BRL" => "R$".to_string(),
+            "MXN" => "MX$".to_string(),
+            other => format!("{} ", other),
+        };
+        let fraction_digits = if currency == "JPY" { 0 } else { 2 };
+        Self {
+            locale,
+            currency,
+            currency_symbol,
+            group_separator,
+            decimal_separator,
+            fraction_digits,
+            other_currencies: Vec::new(),
+        }
+    }
+
+    /// `value` with grouped thousands and `fraction_digits` decimals, e.g. "1.234,5"
+    pub fn number(&self, value: f64, fraction_digits: usize) -> String {
+        let fixed = format!("{:.*}", fraction_digits, value.abs());
+        let (integer, fraction) = fixed.split_once('.').unwrap_or((fixed.as_str(), ""));
+        let mut out = String::new();
+        // Rounds to zero: no "-0,00"
+        if value < 0.0 && fixed.bytes().any(|b| (b'1'..=b'9').contains(&b)) {
+            out.push('-');
+        }
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                out.push(self.group_separator);
+            }
+            out.push(digit);
+        }
+        if !fraction.is_empty() {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// An amount in the profile currency, e.g. "€1.234,56" or "-$80.00"
+    pub fn money(&self, value: f64) -> String {
+        let number = self.number(value, self.fraction_digits);
+        match number.strip_prefix('-') {
+            Some(magnitude) => format!("-{}{}", self.currency_symbol, magnitude),
+            None => format!("{}{}", self.currency_symbol, number),
+        }
+    }
+
+    /// Instruction appended to AI prompts; None for US-dollar English users with no foreign
+    /// accounts, whose prompts already read that way
+    pub fn prompt_instruction(&self) -> Option<String> {
+        if self.locale == Locale::En && self.currency == "USD" && self.other_currencies.is_empty() {
+            return None;
+        }
+        let mut instruction = format!(
+            "Amounts are in {}. Write money like {} and other numbers like {}.",
+            self.currency,
+            self.money(1234.56),
+            self.number(1234.5, 1)
+        );
+        if !self.other_currencies.is_empty() {
+            instruction.push_str(&format!(
+                " Some accounts are held in {}; their amounts are not converted to {}, so always name the currency when citing them and never add them to {} totals as if they were the same.",
+                self.other_currencies.join(", "),
+                self.currency,
+                self.currency
+            ));
+        }
+        Some(instruction)
+    }
+}
+
+/// Number format for the user's profile locale and currency, with the currencies of any
+/// connected accounts that differ from it
+pub async fn user_number_format(conn: &Connection) -> NumberFormat {
+    let currency: Option<String> = match conn.prepare("SELECT currency FROM user_profile LIMIT 1").await {
+        Ok(stmt) => match stmt.query(params![]).await {
+            Ok(mut rows) => rows.next().await.ok().flatten().and_then(|row| row.get(0).ok()),
+            Err(_) => None,
+        },
+        Err(_) => None,
+    };
+    let currency = currency.filter(|c| !c.trim().is_empty()).unwrap_or_else(|| "USD".to_string());
+    let mut format = NumberFormat::new(user_locale(conn).await, &currency);
+
+    // Missing until the user connects a brokerage
+    if let Ok(stmt) = conn
+        .prepare("SELECT DISTINCT UPPER(currency) FROM brokerage_accounts WHERE currency IS NOT NULL AND UPPER(currency) != ? ORDER BY 1")
+        .await
+        && let Ok(mut rows) = stmt.query(params![format.currency.clone()]).await
+    {
+        while let Ok(Some(row)) = rows.next().await {
+            if let Ok(code) = row.get::<String>(0) {
+                format.other_currencies.push(code);
+            }
+        }
+    }
+    format
+}
+
+/// Everything AI prompts need to speak the user's language and write their numbers
+pub async fn prompt_instruction(conn: &Connection) -> Option<String> {
+    let sections: Vec<String> = user_locale(conn)
+        .await
+        .prompt_instruction()
+        .into_iter()
+        .chain(user_number_format(conn).await.prompt_instruction())
+        .collect();
+    (!sections.is_empty()).then(|| sections.join(" "))
+}
+
+/// Enum values the API labels, grouped by namespace. English labels double as the fallback.
+/// Columns: key, en, es, fr, de, pt
+const LABELS: &[(&str, &str, [&str; 5])] = &[
+    ("trade_type", "BUY", ["Buy", "Compra", "Achat", "Kauf", "Compra"]),
+    ("trade_type", "SELL", ["Sell", "Venta", "Vente", "Verkauf", "Venda"]),
+    ("option_type", "Call", ["Call", "Call", "Call", "Call", "Call"]),
+    ("option_type", "Put", ["Put", "Put", "Put", "Put", "Put"]),
+    ("insight_type", "trading_patterns", ["Trading patterns", "Patrones de trading", "Schémas de trading", "Handelsmuster", "Padrões de trading"]),
+    ("insight_type", "performance_analysis", ["Performance analysis", "Análisis de rendimiento", "Analyse de performance", "Performanceanalyse", "Análise de desempenho"]),
+    ("insight_type", "risk_assessment", ["Risk assessment", "Evaluación de riesgo", "Évaluation du risque", "Risikobewertung", "Avaliação de risco"]),
+    ("insight_type", "behavioral_analysis", ["Behavioral analysis", "Análisis de comportamiento", "Analyse comportementale", "Verhaltensanalyse", "Análise comportamental"]),
+    ("insight_type", "market_analysis", ["Market analysis", "Análisis de mercado", "Analyse de marché", "Marktanalyse", "Análise de mercado"]),
+    ("insight_type", "opportunity_detection", ["Opportunity detection", "Detección de oportunidades", "Détection d'opportunités", "Chancenerkennung", "Detecção de oportunidades"]),
+    ("insight_type", "weekly_coach", ["Weekly coach", "Coach semanal", "Coach hebdomadaire", "Wöchentlicher Coach", "Coach semanal"]),
+    ("time_range", "7d", ["Last 7 days", "Últimos 7 días", "7 derniers jours", "Letzte 7 Tage", "Últimos 7 dias"]),
+    ("time_range", "30d", ["Last 30 days", "Últimos 30 días", "30 derniers jours", "Letzte 30 Tage", "Últimos 30 dias"]),
+    ("time_range", "90d", ["Last 90 days", "Últimos 90 días", "90 derniers jours", "Letzte 90 Tage", "Últimos 90 dias"]),
+    ("time_range", "ytd", ["Year to date", "En lo que va del año", "Depuis le début de l'année", "Seit Jahresbeginn", "No ano até agora"]),
+    ("time_range", "mtd", ["Month to date", "En lo que va del mes", "Depuis le début du mois", "Seit Monatsbeginn", "No mês até agora"]),
+    ("time_range", "qtd", ["Quarter to date", "En lo que va del trimestre", "Depuis le début du trimestre", "Seit Quartalsbeginn", "No trimestre até agora"]),
+    ("time_range", "last_month", ["Last month", "Mes anterior", "Mois dernier", "Letzter Monat", "Mês passado"]),
+    ("time_range", "last_quarter", ["Last quarter", "Trimestre anterior", "Trimestre dernier", "Letztes Quartal", "Trimestre passado"]),
+    ("time_range", "1y", ["Last year", "Último año", "Dernière année", "Letztes Jahr", "Último ano"]),
+    ("time_range", "all_time", ["All time", "Todo el historial", "Depuis toujours", "Gesamter Zeitraum", "Todo o período"]),
+];
+
+fn locale_index(locale: Locale) -> usize {
+    match locale {
+        Locale::En => 0,
+        Locale::Es => 1,
+        Locale::Fr => 2,
+        Locale::De => 3,
+        Locale::Pt => 4,
+    }
+}
+
+/// Localized label for an enum value; unknown values come back unchanged
+pub fn label(locale: Locale, namespace: &str, value: &str) -> String {
+    LABELS
+        .iter()
+        .find(|(ns, key, _)| *ns == namespace && *key == value)
+        .map(|(_, _, labels)| labels[locale_index(locale)].to_string())
+        .unwrap_or_else(|| value.to_string())
+}
+
+/// All labels for a locale as `{namespace: {value: label}}`, for clients to render enums
+pub fn labels_for(locale: Locale, namespaces: &[&str]) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut out: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for (ns, key, labels) in LABELS {
+        if namespaces.is_empty() || namespaces.contains(ns) {
+            out.entry(ns.to_string()).or_default().insert(key.to_string(), labels[locale_index(locale)].to_string());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("es-MX"), Some(Locale::Es));
+        assert_eq!(Locale::parse("pt_BR"), Some(Locale::Pt));
+        assert_eq!(Locale::parse("ja"), None);
+        assert_eq!(Locale::from_accept_language("ja;q=0.9, de-DE;q=0.8, en;q=0.7"), Some(Locale::De));
+    }
+
+    #[test]
+    fn test_labels_fall_back_to_value() {
+        assert_eq!(label(Locale::Fr, "insight_type", "risk_assessment"), "Évaluation du risque");
+        assert_eq!(label(Locale::Es, "insight_type", "unknown_type"), "unknown_type");
+        assert_eq!(labels_for(Locale::De, &["time_range"]).len(), 1);
+        assert!(Locale::En.prompt_instruction().is_none());
+    }
+
+    #[test]
+    fn test_number_format_follows_locale_and_currency() {
+        let eu = NumberFormat::new(Locale::De, "eur");
+        assert_eq!(eu.money(1234.56), "€1.234,56");
+        assert_eq!(eu.money(-80.0), "-€80,00");
+        assert_eq!(eu.number(1234567.0, 0), "1.234.567");
+        assert_eq!(NumberFormat::new(Locale::En, "JPY").money(-0.4), "¥0");
+        assert_eq!(NumberFormat::default().money(999.999), "$1,000.00");
+
+        assert!(NumberFormat::default().prompt_instruction().is_none());
+        let mut multi = NumberFormat::default();
+        multi.other_currencies.push("CAD".to_string());
+        assert!(multi.prompt_instruction().unwrap().contains("Some accounts are held in CAD"));
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{Context, Result};
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::service::image_upload::{ImageUploadService, UploadcareConfig};
+use crate::service::note_encryption::{self, NoteCipher};
+
+const UPLOADCARE_UPLOAD_URL: &str = "https://upload.uploadcare.com/base/";
+const UPLOADCARE_API_URL: &str = "https://api.uploadcare.com";
+
+/// Where an image's bytes live. `images.uploadcare_file_id` holds either a legacy Uploadcare
+/// UUID or a Supabase object path (`{user_id}/{file}`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageProvider {
+    Uploadcare,
+    Supabase,
+}
+
+impl StorageProvider {
+    pub fn detect(file_id: &str) -> Self {
+        if file_id.contains('/') {
+            StorageProvider::Supabase
+        } else {
+            StorageProvider::Uploadcare
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrateImagesRequest {
+    pub target: StorageProvider,
+    /// Report what would move without copying anything
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Remove the source object once the copy is verified
+    #[serde(default)]
+    pub delete_source: bool,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageMigrationItem {
+    pub image_id: String,
+    pub from: String,
+    pub to: Option<String>,
+    pub bytes: u64,
+    pub checksum: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ImageMigrationReport {
+    pub candidates: u64,
+    pub migrated: u64,
+    pub failed: u64,
+    pub notes_rewritten: u64,
+    pub dry_run: bool,
+    pub items: Vec<ImageMigrationItem>,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Minimal Uploadcare client: CDN download, direct upload and storage delete
+pub struct UploadcareClient {
+    config: UploadcareConfig,
+    http_client: reqwest::Client,
+}
+
+impl UploadcareClient {
+    pub fn new(config: UploadcareConfig) -> Self {
+        let http_client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(60))
+            .build()
+            .unwrap_or_default();
+        Self { config, http_client }
+    }
+
+    pub fn cdn_url(&self, uuid: &str) -> String {
+        format!("{}/{}/", self.config.cdn_base, uuid)
+    }
+
+    pub async fn download(&self, uuid: &str) -> Result<Vec<u8>> {
+        let response = self.http_client.get(self.cdn_url(uuid)).send().await.context("Failed to download from Uploadcare")?;
+        let status = response.status();
+        if !status.is_success() {
+            anyhow::bail!("Uploadcare download failed (status {})", status);
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Upload and store a file, returning its UUID
+    pub async fn upload(&self, data: &[u8], filename: &str, content_type: &str) -> Result<String> {
+        let part = reqwest::multipart::Part::bytes(data.to_vec())
+            .file_name(filename.to_string())
+            .mime_str(content_type)?;
+        let form = reqwest::multipart::Form::new()
+            .text("UPLOADCARE_PUB_KEY", self.config.public_key.clone())
+            .text("UPLOADCARE_STORE", "1")
+            .part("file", part);
+
+        let response = self.http_client.post(UPLOADCARE_UPLOAD_URL).multipart(form).send().await.context("Failed to upload to Uploadcare")?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Uploadcare upload failed (status {}): {}", status, text);
+        }
+        let body: serde_json::Value = response.json().await?;
+        body.get("file")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow::anyhow!("Uploadcare response missing file id: {}", body))
+    }
+
+    /// Delete a stored file; needs the secret key
+    pub async fn delete(&self, uuid: &str) -> Result<()> {
+        let secret = self.config.secret_key.as_ref().context("UPLOADCARE_SECRET_KEY is required to delete files")?;
+        let response = self.http_client
+            .delete(format!("{}/files/{}/storage/", UPLOADCARE_API_URL, uuid))
+            .header("Authorization", format!("Uploadcare.Simple {}:{}", self.config.public_key, secret))
+            .header("Accept", "application/vnd.uploadcare-v0.7+json")
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Uploadcare delete failed (status {})", response.status());
+        }
+        Ok(())
+    }
+}
+
+struct ImageRow {
+    id: String,
+    trade_note_id: String,
+    file_id: String,
+    filename: String,
+    mime_type: String,
+}
+
+/// Copies a tenant's images between Uploadcare and Supabase Storage
+pub struct ImageMigrationService<'a> {
+    conn: &'a Connection,
+    supabase: &'a ImageUploadService,
+    uploadcare: &'a UploadcareClient,
+}
+
+impl<'a> ImageMigrationService<'a> {
+    pub fn new(conn: &'a Connection, supabase: &'a ImageUploadService, uploadcare: &'a UploadcareClient) -> Self {
+        Self { conn, supabase, uploadcare }
+    }
+
+    /// Canonical URL a note would embed for the file
+    fn reference_url(&self, provider: StorageProvider, file_id: &str) -> String {
+        match provider {
+            StorageProvider::Uploadcare => self.uploadcare.cdn_url(file_id),
+            StorageProvider::Supabase => self.supabase.object_url(file_id),
+        }
+    }
+
+    async fn download(&self, provider: StorageProvider, file_id: &str) -> Result<Vec<u8>> {
+        match provider {
+            StorageProvider::Uploadcare => self.uploadcare.download(file_id).await,
+            StorageProvider::Supabase => self.supabase.download_file(file_id).await,
+        }
+    }
+
+    async fn delete(&self, provider: StorageProvider, file_id: &str) -> Result<()> {
+        match provider {
+            StorageProvider::Uploadcare => self.uploadcare.delete(file_id).await,
+            StorageProvider::Supabase => self.supabase.delete_file(file_id).await,
+        }
+    }
+
+    async fn images_to_migrate(&self, target: StorageProvider, limit: Option<i64>) -> Result<Vec<ImageRow>> {
+        let mut rows = self.conn
+            .prepare("SELECT id, trade_note_id, uploadcare_file_id, original_filename, mime_type FROM images WHERE is_deleted = 0 ORDER BY created_at")
+            .await?
+            .query(params![])
+            .await?;
+        let mut images = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let image = ImageRow { id: row.get(0)?, trade_note_id: row.get(1)?, file_id: row.get(2)?, filename: row.get(3)?, mime_type: row.get(4)? };
+            if StorageProvider::detect(&image.file_id) != target {
+                images.push(image);
+            }
+        }
+        if let Some(limit) = limit.filter(|l| *l > 0) {
+            images.truncate(limit as usize);
+        }
+        Ok(images)
+    }
+
+    /// Copy one image, verify the copy's checksum and point the row and notes at it
+    async fn migrate_one(&self, user_id: &str, image: &ImageRow, target: StorageProvider, delete_source: bool) -> Result<(String, u64, String, u64)> {
+        let source = StorageProvider::detect(&image.file_id);
+        let data = self.download(source, &image.file_id).await?;
+        let checksum = sha256_hex(&data);
+
+        let new_id = match target {
+            StorageProvider::Supabase => {
+                let path = self.supabase.generate_object_path(user_id, &image.filename);
+                self.supabase.put_object(&path, &data, &image.mime_type).await?;
+                path
+            }
+            StorageProvider::Uploadcare => self.uploadcare.upload(&data, &image.filename, &image.mime_type).await?,
+        };
+
+        let copied = self.download(target, &new_id).await?;
+        if sha256_hex(&copied) != checksum {
+            let _ = self.delete(target, &new_id).await;
+            anyhow::bail!("Checksum mismatch after copying to {:?}", target);
+        }
+
+        self.conn.execute(
+            "UPDATE images SET uploadcare_file_id = ?, updated_at = datetime('now') WHERE id = ?",
+            params![new_id.clone(), image.id.clone()],
+        ).await?;
+
+        // Full URLs first so a bare-id replacement can't leave a half-rewritten URL behind
+        let replacements = [
+            (self.reference_url(source, &image.file_id), self.reference_url(target, &new_id)),
+            (image.file_id.clone(), new_id.clone()),
+        ];
+        let mut notes_rewritten = 0;
+        for (old, new) in &replacements {
+            notes_rewritten += self.conn.execute(
+                "UPDATE trade_notes SET content = REPLACE(content, ?, ?) WHERE id = ? AND INSTR(content, ?) > 0",
+                params![old.clone(), new.clone(), image.trade_note_id.clone(), old.clone()],
+            ).await?;
+        }
+        notes_rewritten += self.rewrite_sealed_note(&image.trade_note_id, &replacements).await?;
+
+        if delete_source && let Err(e) = self.delete(source, &image.file_id).await {
+            warn!("Migrated image {} but failed to delete source object: {}", image.id, e);
+        }
+        Ok((new_id, data.len() as u64, checksum, notes_rewritten))
+    }
+
+    /// Encrypted note content can't be rewritten in SQL; open, replace and re-seal it
+    async fn rewrite_sealed_note(&self, note_id: &str, replacements: &[(String, String)]) -> Result<u64> {
+        let Some(cipher) = NoteCipher::for_connection(self.conn).await? else {
+            return Ok(0);
+        };
+        let mut rows = self.conn.prepare("SELECT content FROM trade_notes WHERE id = ?").await?.query(params![note_id]).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(0);
+        };
+        let stored: String = row.get(0)?;
+        if !note_encryption::is_sealed(&stored) {
+            return Ok(0);
+        }
+        let original = cipher.open(&stored)?;
+        let content = replacements.iter().fold(original.clone(), |content, (old, new)| content.replace(old.as_str(), new));
+        if content == original {
+            return Ok(0);
+        }
+        Ok(self.conn.execute(
+            "UPDATE trade_notes SET content = ? WHERE id = ?",
+            params![cipher.seal(&content)?, note_id],
+        ).await?)
+    }
+
+    pub async fn migrate(&self, user_id: &str, req: &MigrateImagesRequest) -> Result<ImageMigrationReport> {
+        let images = self.images_to_migrate(req.target, req.limit).await?;
+        let mut report = ImageMigrationReport { candidates: images.len() as u64, dry_run: req.dry_run, ..Default::default() };
+
+        for image in &images {
+            let mut item = ImageMigrationItem { image_id: image.id.clone(), from: image.file_id.clone(), to: None, bytes: 0, checksum: None, error: None };
+            if !req.dry_run {
+                match self.migrate_one(user_id, image, req.target, req.delete_source).await {
+                    Ok((new_id, bytes, checksum, notes)) => {
+                        report.migrated += 1;
+                        report.notes_rewritten += notes;
+                        item.to = Some(new_id);
+                        item.bytes = bytes;
+                        item.checksum = Some(checksum);
+                    }
+                    Err(e) => {
+                        report.failed += 1;
+                        item.error = Some(e.to_string());
+                    }
+                }
+            }
+            report.items.push(item);
+        }
+
+        info!(
+            "Image migration for {} to {:?}: {} candidates, {} migrated, {} failed",
+            user_id, req.target, report.candidates, report.migrated, report.failed
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_provider() {
+        assert_eq!(StorageProvider::detect("c2499162-eb07-4b93-b31d-2c6e0d5f1b2a"), StorageProvider::Uploadcare);
+        assert_eq!(StorageProvider::detect("user-1/20250101_120000_ab12cd34.png"), StorageProvider::Supabase);
+    }
+}
@@ -27,6 +27,28 @@ impl SupabaseStorageConfig {
     }
 }
 
+/// Legacy Uploadcare configuration, only needed to migrate images still stored there
+#[derive(Debug, Clone)]
+pub struct UploadcareConfig {
+    pub public_key: String,
+    pub secret_key: Option<String>,
+    pub cdn_base: String,
+}
+
+impl UploadcareConfig {
+    /// Returns None if no public key is set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            public_key: std::env::var("UPLOADCARE_PUBLIC_KEY").ok().filter(|k| !k.is_empty())?,
+            secret_key: std::env::var("UPLOADCARE_SECRET_KEY").ok().filter(|k| !k.is_empty()),
+            cdn_base: std::env::var("UPLOADCARE_CDN_URL")
+                .unwrap_or_else(|_| "https://ucarecdn.com".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
 /// File information stored in our DB (replaces UploadcareFileInfo)
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StoredFileInfo {
@@ -200,6 +222,50 @@ impl ImageUploadService {
         Ok(absolute)
     }
 
+    /// URL of an object in the bucket (requires auth unless the bucket is public)
+    pub fn object_url(&self, object_path: &str) -> String {
+        format!("{}/storage/v1/object/{}/{}", self.config.project_url, self.config.bucket_name, object_path)
+    }
+
+    /// Download an object's bytes
+    pub async fn download_file(&self, object_path: &str) -> Result<Vec<u8>> {
+        let response = self.http_client
+            .get(self.object_url(object_path))
+            .header("Authorization", format!("Bearer {}", self.config.service_role_key))
+            .header("apikey", self.config.anon_key.clone())
+            .send()
+            .await
+            .context("Failed to download file from Supabase Storage")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Supabase download failed (status {}): {}", status, text));
+        }
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Write bytes to an exact object path, overwriting anything already there
+    pub async fn put_object(&self, object_path: &str, file_data: &[u8], content_type: &str) -> Result<()> {
+        let response = self.http_client
+            .put(self.object_url(object_path))
+            .header("Authorization", format!("Bearer {}", self.config.service_role_key))
+            .header("apikey", self.config.anon_key.clone())
+            .header("x-upsert", "true")
+            .header("Content-Type", content_type)
+            .body(file_data.to_vec())
+            .send()
+            .await
+            .context("Failed to upload file to Supabase Storage")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Supabase upload failed (status {}): {}", status, text));
+        }
+        Ok(())
+    }
+
     /// Delete an object from Supabase Storage
     pub async fn delete_file(&self, object_path: &str) -> Result<()> {
         info!("Deleting file from Supabase Storage: {}", object_path);
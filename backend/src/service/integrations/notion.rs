@@ -0,0 +1,555 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{NaiveDate, Utc};
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::models::analytics::PeriodDefinition;
+use crate::service::note_encryption::{self, NoteCipher};
+use crate::service::secrets_store::SecretsStore;
+use crate::turso::config::NotionConfig;
+
+const NOTION_API_BASE: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+/// Provider key used in the secrets store
+pub const NOTION_PROVIDER: &str = "notion";
+const ACCESS_TOKEN_SECRET: &str = "access_token";
+
+const MAX_SEND_ATTEMPTS: u32 = 3;
+/// Failed exports are retried by the cron until they reach this many attempts
+pub const MAX_EXPORT_ATTEMPTS: i64 = 5;
+// Notion limits a rich text item to 2000 characters
+const MAX_TEXT_CHUNK: usize = 1900;
+
+/// Maps journal fields onto properties of the user's Notion database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotionFieldMapping {
+    /// Title property (every Notion database has exactly one)
+    pub title_property: String,
+    pub date_property: Option<String>,
+    /// Select property receiving "Daily Journal" / "Weekly Review"
+    pub type_property: Option<String>,
+    pub pnl_property: Option<String>,
+    pub trade_count_property: Option<String>,
+    pub win_rate_property: Option<String>,
+}
+
+impl Default for NotionFieldMapping {
+    fn default() -> Self {
+        Self {
+            title_property: "Name".to_string(),
+            date_property: Some("Date".to_string()),
+            type_property: Some("Type".to_string()),
+            pnl_property: Some("P&L".to_string()),
+            trade_count_property: Some("Trades".to_string()),
+            win_rate_property: None,
+        }
+    }
+}
+
+/// Per-user Notion integration settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotionIntegration {
+    pub workspace_id: Option<String>,
+    pub workspace_name: Option<String>,
+    pub database_id: Option<String>,
+    pub field_mapping: NotionFieldMapping,
+    pub export_daily_journal: bool,
+    pub export_weekly_review: bool,
+    pub last_export_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateNotionSettingsRequest {
+    pub database_id: Option<String>,
+    pub field_mapping: Option<NotionFieldMapping>,
+    pub export_daily_journal: Option<bool>,
+    pub export_weekly_review: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalEntryType {
+    DailyJournal,
+    WeeklyReview,
+}
+
+impl JournalEntryType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JournalEntryType::DailyJournal => "daily_journal",
+            JournalEntryType::WeeklyReview => "weekly_review",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            JournalEntryType::DailyJournal => "Daily Journal",
+            JournalEntryType::WeeklyReview => "Weekly Review",
+        }
+    }
+
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "daily_journal" => Some(JournalEntryType::DailyJournal),
+            "weekly_review" => Some(JournalEntryType::WeeklyReview),
+            _ => None,
+        }
+    }
+}
+
+/// Journal content assembled from the user's trades and notes
+#[derive(Debug, Clone, Serialize)]
+pub struct JournalEntry {
+    pub entry_type: JournalEntryType,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub title: String,
+    pub net_pnl: f64,
+    pub trade_count: u32,
+    pub win_rate: f64,
+    pub paragraphs: Vec<String>,
+}
+
+/// Outcome of a single export attempt
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportResult {
+    pub entry_type: JournalEntryType,
+    pub entry_date: String,
+    pub notion_page_id: Option<String>,
+    pub skipped: bool,
+}
+
+pub struct NotionService<'a> {
+    conn: &'a Connection,
+    secrets: &'a SecretsStore,
+    client: reqwest::Client,
+}
+
+impl<'a> NotionService<'a> {
+    pub fn new(conn: &'a Connection, secrets: &'a SecretsStore) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(20))
+            .build()
+            .unwrap_or_default();
+        Self { conn, secrets, client }
+    }
+
+    /// URL the client should redirect to for the Notion consent screen
+    pub fn authorize_url(config: &NotionConfig, state: &str) -> String {
+        format!(
+            "https://api.notion.com/v1/oauth/authorize?client_id={}&response_type=code&owner=user&redirect_uri={}&state={}",
+            urlencoding::encode(&config.client_id),
+            urlencoding::encode(&config.redirect_uri),
+            urlencoding::encode(state),
+        )
+    }
+
+    /// Exchange an OAuth code for an access token and store it encrypted
+    pub async fn complete_oauth(&self, config: &NotionConfig, code: &str) -> Result<NotionIntegration> {
+        let basic = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", config.client_id, config.client_secret));
+        let response = self.client
+            .post(format!("{}/oauth/token", NOTION_API_BASE))
+            .header("Authorization", format!("Basic {}", basic))
+            .header("Notion-Version", NOTION_VERSION)
+            .json(&serde_json::json!({
+                "grant_type": "authorization_code",
+                "code": code,
+                "redirect_uri": config.redirect_uri,
+            }))
+            .send()
+            .await
+            .context("Failed to reach Notion OAuth endpoint")?;
+
+        let status = response.status();
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        if !status.is_success() {
+            anyhow::bail!("Notion OAuth failed ({}): {}", status, body.get("error").and_then(|e| e.as_str()).unwrap_or("unknown error"));
+        }
+
+        let access_token = body.get("access_token").and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Notion OAuth response missing access_token"))?;
+        let workspace_id = body.get("workspace_id").and_then(|v| v.as_str()).map(|s| s.to_string());
+        let workspace_name = body.get("workspace_name").and_then(|v| v.as_str()).map(|s| s.to_string());
+        // Template-duplicated databases come back on the token response
+        let duplicated_template_id = body.get("duplicated_template_id").and_then(|v| v.as_str()).map(|s| s.to_string());
+
+        self.secrets.put(self.conn, NOTION_PROVIDER, ACCESS_TOKEN_SECRET, access_token).await?;
+
+        let mapping = serde_json::to_string(&NotionFieldMapping::default())?;
+        self.conn.execute(
+            "INSERT INTO notion_integration (id, workspace_id, workspace_name, database_id, field_mapping) VALUES ('default', ?, ?, ?, ?)
+             ON CONFLICT(id) DO UPDATE SET workspace_id = excluded.workspace_id, workspace_name = excluded.workspace_name,
+                database_id = COALESCE(excluded.database_id, notion_integration.database_id), last_error = NULL",
+            params![workspace_id, workspace_name, duplicated_template_id, mapping],
+        ).await?;
+
+        self.get_settings().await?.ok_or_else(|| anyhow::anyhow!("Notion integration was saved but could not be loaded"))
+    }
+
+    pub async fn get_settings(&self) -> Result<Option<NotionIntegration>> {
+        let mut rows = self.conn
+            .prepare("SELECT workspace_id, workspace_name, database_id, field_mapping, export_daily_journal, export_weekly_review, last_export_at, last_error FROM notion_integration WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+
+        let Some(row) = rows.next().await? else { return Ok(None) };
+        let mapping: Option<String> = row.get(3)?;
+        Ok(Some(NotionIntegration {
+            workspace_id: row.get(0)?,
+            workspace_name: row.get(1)?,
+            database_id: row.get(2)?,
+            field_mapping: mapping.and_then(|m| serde_json::from_str(&m).ok()).unwrap_or_default(),
+            export_daily_journal: row.get::<i64>(4).map(|v| v != 0).unwrap_or(true),
+            export_weekly_review: row.get::<i64>(5).map(|v| v != 0).unwrap_or(true),
+            last_export_at: row.get(6)?,
+            last_error: row.get(7)?,
+        }))
+    }
+
+    pub async fn update_settings(&self, req: UpdateNotionSettingsRequest) -> Result<Option<NotionIntegration>> {
+        let Some(current) = self.get_settings().await? else { return Ok(None) };
+
+        let mapping = req.field_mapping.unwrap_or(current.field_mapping);
+        if mapping.title_property.trim().is_empty() {
+            anyhow::bail!("title_property is required");
+        }
+        let database_id = req.database_id.map(|d| normalize_database_id(&d)).or(current.database_id);
+
+        self.conn.execute(
+            "UPDATE notion_integration SET database_id = ?, field_mapping = ?, export_daily_journal = ?, export_weekly_review = ? WHERE id = 'default'",
+            params![
+                database_id,
+                serde_json::to_string(&mapping)?,
+                req.export_daily_journal.unwrap_or(current.export_daily_journal),
+                req.export_weekly_review.unwrap_or(current.export_weekly_review)
+            ],
+        ).await?;
+
+        self.get_settings().await
+    }
+
+    /// Remove the integration and its stored token
+    pub async fn disconnect(&self) -> Result<()> {
+        SecretsStore::delete_provider(self.conn, NOTION_PROVIDER).await?;
+        self.conn.execute("DELETE FROM notion_integration WHERE id = 'default'", params![]).await?;
+        Ok(())
+    }
+
+    /// Build and push a journal entry, recording the export so it isn't duplicated
+    pub async fn export_entry(&self, entry_type: JournalEntryType, date: NaiveDate) -> Result<ExportResult> {
+        let settings = self.get_settings().await?
+            .ok_or_else(|| anyhow::anyhow!("Notion is not connected"))?;
+        let database_id = settings.database_id.clone()
+            .ok_or_else(|| anyhow::anyhow!("Choose a Notion database before exporting"))?;
+        let token = self.secrets.get(self.conn, NOTION_PROVIDER, ACCESS_TOKEN_SECRET).await?
+            .ok_or_else(|| anyhow::anyhow!("Notion access token missing, reconnect the integration"))?;
+
+        let entry_date = date.format("%Y-%m-%d").to_string();
+        if let Some(page_id) = self.exported_page_id(entry_type, &entry_date).await? {
+            return Ok(ExportResult { entry_type, entry_date, notion_page_id: Some(page_id), skipped: true });
+        }
+
+        let entry = build_journal_entry(self.conn, entry_type, date).await?;
+        let page = build_page_payload(&database_id, &settings.field_mapping, &entry);
+
+        match self.send_with_retry(&token, &page).await {
+            Ok(page_id) => {
+                self.record_export(entry_type, &entry_date, Some(&page_id), None).await?;
+                self.conn.execute(
+                    "UPDATE notion_integration SET last_export_at = datetime('now'), last_error = NULL WHERE id = 'default'",
+                    params![],
+                ).await?;
+                info!("Exported {} for {} to Notion page {}", entry_type.as_str(), entry_date, page_id);
+                Ok(ExportResult { entry_type, entry_date, notion_page_id: Some(page_id), skipped: false })
+            }
+            Err(e) => {
+                self.record_export(entry_type, &entry_date, None, Some(&e.to_string())).await?;
+                self.conn.execute(
+                    "UPDATE notion_integration SET last_error = ? WHERE id = 'default'",
+                    params![e.to_string()],
+                ).await?;
+                Err(e)
+            }
+        }
+    }
+
+    /// Retry exports that previously failed, up to MAX_EXPORT_ATTEMPTS
+    pub async fn retry_failed_exports(&self) -> Result<usize> {
+        let mut rows = self.conn
+            .prepare("SELECT entry_type, entry_date FROM notion_exports WHERE status = 'failed' AND attempts < ? ORDER BY entry_date")
+            .await?
+            .query(params![MAX_EXPORT_ATTEMPTS])
+            .await?;
+
+        let mut pending = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let entry_type: String = row.get(0)?;
+            let entry_date: String = row.get(1)?;
+            if let (Some(t), Ok(d)) = (JournalEntryType::from_db(&entry_type), NaiveDate::parse_from_str(&entry_date, "%Y-%m-%d")) {
+                pending.push((t, d));
+            }
+        }
+
+        let mut recovered = 0;
+        for (entry_type, date) in pending {
+            if self.export_entry(entry_type, date).await.is_ok() {
+                recovered += 1;
+            }
+        }
+        Ok(recovered)
+    }
+
+    async fn exported_page_id(&self, entry_type: JournalEntryType, entry_date: &str) -> Result<Option<String>> {
+        let mut rows = self.conn
+            .prepare("SELECT notion_page_id FROM notion_exports WHERE entry_type = ? AND entry_date = ? AND status = 'exported'")
+            .await?
+            .query(params![entry_type.as_str(), entry_date])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get::<Option<String>>(0)?),
+            None => Ok(None),
+        }
+    }
+
+    async fn record_export(&self, entry_type: JournalEntryType, entry_date: &str, page_id: Option<&str>, error: Option<&str>) -> Result<()> {
+        let status = if page_id.is_some() { "exported" } else { "failed" };
+        self.conn.execute(
+            "INSERT INTO notion_exports (id, entry_type, entry_date, notion_page_id, status, attempts, last_error) VALUES (?, ?, ?, ?, ?, 1, ?)
+             ON CONFLICT(entry_type, entry_date) DO UPDATE SET notion_page_id = excluded.notion_page_id, status = excluded.status,
+                attempts = notion_exports.attempts + 1, last_error = excluded.last_error, updated_at = datetime('now')",
+            params![uuid::Uuid::new_v4().to_string(), entry_type.as_str(), entry_date, page_id, status, error],
+        ).await?;
+        Ok(())
+    }
+
+    /// POST a page, retrying rate limits and server errors with backoff
+    async fn send_with_retry(&self, token: &str, page: &serde_json::Value) -> Result<String> {
+        let mut backoff_ms = 500u64;
+        let mut last_error = String::new();
+
+        for attempt in 1..=MAX_SEND_ATTEMPTS {
+            let response = self.client
+                .post(format!("{}/pages", NOTION_API_BASE))
+                .bearer_auth(token)
+                .header("Notion-Version", NOTION_VERSION)
+                .json(page)
+                .send()
+                .await;
+
+            match response {
+                Ok(resp) if resp.status().is_success() => {
+                    let body: serde_json::Value = resp.json().await?;
+                    return body.get("id").and_then(|v| v.as_str()).map(|s| s.to_string())
+                        .ok_or_else(|| anyhow::anyhow!("Notion response missing page id"));
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retry_after = resp.headers().get("Retry-After")
+                        .and_then(|v| v.to_str().ok())
+                        .and_then(|v| v.parse::<u64>().ok());
+                    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+                    last_error = format!("Notion returned {}: {}", status, body.get("message").and_then(|m| m.as_str()).unwrap_or(""));
+
+                    // Client errors (bad mapping, revoked token) won't succeed on retry
+                    if !(status.as_u16() == 429 || status.is_server_error()) {
+                        break;
+                    }
+                    if let Some(secs) = retry_after {
+                        backoff_ms = backoff_ms.max(secs * 1000);
+                    }
+                }
+                Err(e) => last_error = format!("Notion request failed: {}", e),
+            }
+
+            if attempt < MAX_SEND_ATTEMPTS {
+                warn!("Notion export attempt {} failed: {}, retrying in {}ms", attempt, last_error, backoff_ms);
+                tokio::time::sleep(std::time::Duration::from_millis(backoff_ms)).await;
+                backoff_ms *= 2;
+            }
+        }
+
+        Err(anyhow::anyhow!(last_error))
+    }
+}
+
+/// Accept either a raw database id or a Notion URL and return the 32-char id
+fn normalize_database_id(input: &str) -> String {
+    let trimmed = input.trim();
+    let last_segment = trimmed.split('?').next().unwrap_or(trimmed).rsplit('/').next().unwrap_or(trimmed);
+    let hex: String = last_segment.chars().filter(|c| c.is_ascii_hexdigit()).collect();
+    if hex.len() >= 32 {
+        hex[hex.len() - 32..].to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Collect stats and notes for the entry's date range. A weekly review covers the user's
+/// week (Sunday or Monday start) up to `date`.
+pub async fn build_journal_entry(conn: &Connection, entry_type: JournalEntryType, date: NaiveDate) -> Result<JournalEntry> {
+    let (start_date, end_date) = match entry_type {
+        JournalEntryType::DailyJournal => (date, date),
+        JournalEntryType::WeeklyReview => (PeriodDefinition::load(conn).await.week_start(date), date),
+    };
+    let start = start_date.format("%Y-%m-%d").to_string();
+    let end = end_date.format("%Y-%m-%d").to_string();
+
+    let mut rows = conn
+        .prepare(
+            r#"
+            SELECT
+                COUNT(*),
+                COALESCE(SUM(pnl), 0),
+                COALESCE(SUM(CASE WHEN pnl > 0 THEN 1 ELSE 0 END), 0)
+            FROM (
+                SELECT realized_pnl as pnl
+                FROM stocks
+                WHERE realized_pnl IS NOT NULL AND DATE(exit_date) BETWEEN ? AND ?
+
+                UNION ALL
+
+                SELECT realized_pnl as pnl
+                FROM options
+                WHERE realized_pnl IS NOT NULL AND DATE(exit_date) BETWEEN ? AND ?
+            )
+            "#,
+        )
+        .await?
+        .query(params![start.clone(), end.clone(), start.clone(), end.clone()])
+        .await?;
+
+    let (trade_count, net_pnl, wins) = match rows.next().await? {
+        Some(row) => (
+            row.get::<i64>(0).unwrap_or(0) as u32,
+            row.get::<f64>(1).unwrap_or(0.0),
+            row.get::<i64>(2).unwrap_or(0) as u32,
+        ),
+        None => (0, 0.0, 0),
+    };
+    let win_rate = if trade_count > 0 { wins as f64 / trade_count as f64 * 100.0 } else { 0.0 };
+
+    let mut paragraphs = vec![format!(
+        "{} closed trade(s), net P&L ${:.2}, win rate {:.0}%.",
+        trade_count, net_pnl, win_rate
+    )];
+
+    let cipher = NoteCipher::for_connection(conn).await?;
+    let mut notes = conn
+        .prepare("SELECT name, content FROM trade_notes WHERE DATE(updated_at) BETWEEN ? AND ? ORDER BY updated_at")
+        .await?
+        .query(params![start.clone(), end.clone()])
+        .await?;
+    while let Some(row) = notes.next().await? {
+        let name: String = row.get(0).unwrap_or_default();
+        let content: Option<String> = row.get(1).unwrap_or(None);
+        let content = note_encryption::open_content(cipher.as_ref(), content.unwrap_or_default())?;
+        if !content.trim().is_empty() {
+            paragraphs.push(format!("{}: {}", name, content.trim()));
+        }
+    }
+
+    let title = match entry_type {
+        JournalEntryType::DailyJournal => format!("Trading Journal — {}", end),
+        JournalEntryType::WeeklyReview => format!("Weekly Review — {} to {}", start, end),
+    };
+
+    Ok(JournalEntry { entry_type, start_date, end_date, title, net_pnl, trade_count, win_rate, paragraphs })
+}
+
+/// Build the Notion `pages.create` body using the user's field mapping
+pub fn build_page_payload(database_id: &str, mapping: &NotionFieldMapping, entry: &JournalEntry) -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    properties.insert(
+        mapping.title_property.clone(),
+        serde_json::json!({"title": [{"text": {"content": entry.title}}]}),
+    );
+    if let Some(prop) = &mapping.date_property {
+        let mut date = serde_json::json!({"start": entry.start_date.format("%Y-%m-%d").to_string()});
+        if entry.start_date != entry.end_date {
+            date["end"] = serde_json::json!(entry.end_date.format("%Y-%m-%d").to_string());
+        }
+        properties.insert(prop.clone(), serde_json::json!({"date": date}));
+    }
+    if let Some(prop) = &mapping.type_property {
+        properties.insert(prop.clone(), serde_json::json!({"select": {"name": entry.entry_type.label()}}));
+    }
+    if let Some(prop) = &mapping.pnl_property {
+        properties.insert(prop.clone(), serde_json::json!({"number": (entry.net_pnl * 100.0).round() / 100.0}));
+    }
+    if let Some(prop) = &mapping.trade_count_property {
+        properties.insert(prop.clone(), serde_json::json!({"number": entry.trade_count}));
+    }
+    if let Some(prop) = &mapping.win_rate_property {
+        properties.insert(prop.clone(), serde_json::json!({"number": (entry.win_rate * 10.0).round() / 10.0}));
+    }
+
+    // Notion caps a request at 100 child blocks
+    let children: Vec<serde_json::Value> = entry.paragraphs.iter()
+        .flat_map(|p| chunk_text(p))
+        .take(100)
+        .map(|text| serde_json::json!({
+            "object": "block",
+            "type": "paragraph",
+            "paragraph": {"rich_text": [{"type": "text", "text": {"content": text}}]}
+        }))
+        .collect();
+
+    serde_json::json!({
+        "parent": {"database_id": database_id},
+        "properties": properties,
+        "children": children,
+    })
+}
+
+fn chunk_text(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    chars.chunks(MAX_TEXT_CHUNK).map(|c| c.iter().collect()).collect()
+}
+
+/// Today's date in UTC, used by the scheduled export
+pub fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> JournalEntry {
+        JournalEntry {
+            entry_type: JournalEntryType::WeeklyReview,
+            start_date: NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2025, 3, 9).unwrap(),
+            title: "Weekly Review".to_string(),
+            net_pnl: 1234.567,
+            trade_count: 12,
+            win_rate: 58.33,
+            paragraphs: vec!["x".repeat(4000)],
+        }
+    }
+
+    #[test]
+    fn test_page_payload_uses_mapping() {
+        let mapping = NotionFieldMapping { pnl_property: Some("Net".to_string()), ..Default::default() };
+        let payload = build_page_payload("db123", &mapping, &entry());
+        assert_eq!(payload["parent"]["database_id"], "db123");
+        assert_eq!(payload["properties"]["Net"]["number"], 1234.57);
+        assert_eq!(payload["properties"]["Date"]["date"]["end"], "2025-03-09");
+        assert_eq!(payload["properties"]["Type"]["select"]["name"], "Weekly Review");
+        assert!(payload["properties"].get("P&L").is_none());
+        // 4000 chars split into 1900-char blocks
+        assert_eq!(payload["children"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_normalize_database_id_from_url() {
+        let id = normalize_database_id("https://www.notion.so/myworkspace/Journal-0123456789abcdef0123456789abcdef?v=fedcba");
+        assert_eq!(id, "0123456789abcdef0123456789abcdef");
+        assert_eq!(normalize_database_id("0123456789abcdef0123456789abcdef"), "0123456789abcdef0123456789abcdef");
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+
+use crate::middleware::cron_auth::secrets_match;
+
+/// Registry table, created with the other registry tables at startup
+pub const OAUTH_STATES_TABLE_SQL: &str = "CREATE TABLE IF NOT EXISTS oauth_states (user_id TEXT NOT NULL, provider TEXT NOT NULL, state TEXT NOT NULL, expires_at TEXT NOT NULL, PRIMARY KEY (user_id, provider))";
+
+/// Long enough to get through a consent screen, short enough that a leaked state goes stale
+const OAUTH_STATE_TTL_MINUTES: i64 = 10;
+
+/// Issue the `state` for a user's OAuth connect flow. One pending state per user and
+/// provider; starting the flow again replaces it.
+pub async fn issue_state(registry: &Connection, user_id: &str, provider: &str) -> Result<String> {
+    let state = uuid::Uuid::new_v4().to_string();
+    let now = chrono::Utc::now();
+    let expires_at = (now + chrono::Duration::minutes(OAUTH_STATE_TTL_MINUTES)).to_rfc3339();
+
+    registry.execute("DELETE FROM oauth_states WHERE expires_at < ?", params![now.to_rfc3339()]).await?;
+    registry.execute(
+        "INSERT INTO oauth_states (user_id, provider, state, expires_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(user_id, provider) DO UPDATE SET state = excluded.state, expires_at = excluded.expires_at",
+        params![user_id, provider, state.clone(), expires_at],
+    ).await?;
+    Ok(state)
+}
+
+/// Check the `state` a callback came back with against the one issued to this user. The
+/// pending state is used up either way, so a callback can't be replayed.
+pub async fn consume_state(registry: &Connection, user_id: &str, provider: &str, state: &str) -> Result<bool> {
+    let mut rows = registry
+        .prepare("SELECT state FROM oauth_states WHERE user_id = ? AND provider = ? AND expires_at > ?")
+        .await?
+        .query(params![user_id, provider, chrono::Utc::now().to_rfc3339()])
+        .await?;
+    let issued: Option<String> = match rows.next().await? {
+        Some(row) => Some(row.get(0)?),
+        None => None,
+    };
+
+    registry.execute("DELETE FROM oauth_states WHERE user_id = ? AND provider = ?", params![user_id, provider]).await?;
+    Ok(issued.is_some_and(|issued| secrets_match(state, &issued)))
+}
@@ -0,0 +1,357 @@
+// Margin estimates for the open book, so the risk dashboard can show utilization next to
+// exposure. Stocks and options follow Reg-T and the FINRA/Cboe minimums a typical US
+// margin account applies; futures use a SPAN approximation, the exchange's scanning range
+// as a share of notional. Brokers add house requirements on top, so these are floors.
+
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::models::money;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::quotes::{get_quotes, Quote};
+use crate::service::stress_test::parse_number;
+
+const OPTION_MULTIPLIER: f64 = 100.0;
+/// CME sets speculative initial margin at 110% of maintenance
+const FUTURES_INITIAL_TO_MAINTENANCE: f64 = 1.1;
+
+/// Contract size and approximate SPAN scanning range (percent of notional) of a futures root
+struct FuturesSpec {
+    root: &'static str,
+    multiplier: f64,
+    scan_range_pct: f64,
+}
+
+static FUTURES: [FuturesSpec; 22] = [
+    FuturesSpec { root: "ES", multiplier: 50.0, scan_range_pct: 6.5 },
+    FuturesSpec { root: "MES", multiplier: 5.0, scan_range_pct: 6.5 },
+    FuturesSpec { root: "NQ", multiplier: 20.0, scan_range_pct: 8.0 },
+    FuturesSpec { root: "MNQ", multiplier: 2.0, scan_range_pct: 8.0 },
+    FuturesSpec { root: "YM", multiplier: 5.0, scan_range_pct: 6.0 },
+    FuturesSpec { root: "MYM", multiplier: 0.5, scan_range_pct: 6.0 },
+    FuturesSpec { root: "RTY", multiplier: 50.0, scan_range_pct: 8.0 },
+    FuturesSpec { root: "M2K", multiplier: 5.0, scan_range_pct: 8.0 },
+    FuturesSpec { root: "CL", multiplier: 1000.0, scan_range_pct: 12.0 },
+    FuturesSpec { root: "MCL", multiplier: 100.0, scan_range_pct: 12.0 },
+    FuturesSpec { root: "NG", multiplier: 10000.0, scan_range_pct: 20.0 },
+    FuturesSpec { root: "GC", multiplier: 100.0, scan_range_pct: 5.5 },
+    FuturesSpec { root: "MGC", multiplier: 10.0, scan_range_pct: 5.5 },
+    FuturesSpec { root: "SI", multiplier: 5000.0, scan_range_pct: 9.0 },
+    FuturesSpec { root: "HG", multiplier: 25000.0, scan_range_pct: 7.0 },
+    FuturesSpec { root: "ZB", multiplier: 1000.0, scan_range_pct: 4.0 },
+    FuturesSpec { root: "ZN", multiplier: 1000.0, scan_range_pct: 2.0 },
+    FuturesSpec { root: "ZF", multiplier: 1000.0, scan_range_pct: 1.3 },
+    FuturesSpec { root: "ZT", multiplier: 2000.0, scan_range_pct: 0.6 },
+    FuturesSpec { root: "6E", multiplier: 125000.0, scan_range_pct: 2.5 },
+    FuturesSpec { root: "ZC", multiplier: 50.0, scan_range_pct: 7.0 },
+    FuturesSpec { root: "ZS", multiplier: 50.0, scan_range_pct: 6.0 },
+];
+
+/// Futures contract a journal symbol names: `/ES`, `ES=F`, or root plus month code and
+/// year (`ESZ5`, `/MNQH26`). A bare root is taken as a stock ticker (CL is Colgate).
+fn futures_spec(symbol: &str) -> Option<&'static FuturesSpec> {
+    let symbol = symbol.trim().to_uppercase();
+    let (body, marked) = match (symbol.strip_prefix('/'), symbol.strip_suffix("=F")) {
+        (Some(body), _) | (None, Some(body)) => (body.to_string(), true),
+        (None, None) => (symbol.clone(), false),
+    };
+    FUTURES.iter().find(|spec| {
+        let Some(expiry) = body.strip_prefix(spec.root) else {
+            return false;
+        };
+        if expiry.is_empty() {
+            return marked;
+        }
+        let mut chars = expiry.chars();
+        let month = chars.next().is_some_and(|c| "FGHJKMNQUVXZ".contains(c));
+        let year = chars.as_str();
+        month && (1..=2).contains(&year.len()) && year.chars().all(|c| c.is_ascii_digit())
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginMethod {
+    RegT,
+    SpanApprox,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarginPosition {
+    /// `stock`, `option` or `future`
+    pub kind: String,
+    pub trade_id: i64,
+    pub symbol: String,
+    pub description: String,
+    /// Signed: negative for short stock and futures and for written options
+    pub market_value: f64,
+    pub initial_margin: f64,
+    pub maintenance_margin: f64,
+    pub method: MarginMethod,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarginReport {
+    pub positions: Vec<MarginPosition>,
+    pub total_initial_margin: f64,
+    pub total_maintenance_margin: f64,
+    pub gross_exposure: f64,
+    pub net_exposure: f64,
+    /// Sum of brokerage account balances, or the sizing rule's account size without any
+    pub account_equity: Option<f64>,
+    pub initial_utilization_pct: Option<f64>,
+    pub maintenance_utilization_pct: Option<f64>,
+    /// Equity left above maintenance; negative means a margin call
+    pub excess_liquidity: Option<f64>,
+    /// Open positions without a usable quote, left out of the totals
+    pub unpriced_positions: Vec<String>,
+}
+
+/// Reg-T initial 50%; maintenance 25% long, 30% short, and for shorts under $5 the greater
+/// of $2.50 a share or the full value
+fn stock_margin(price: f64, shares: f64, long: bool) -> (f64, f64) {
+    let value = price * shares;
+    let maintenance = match (long, price >= 5.0) {
+        (true, _) => 0.25 * value,
+        (false, true) => 0.30 * value,
+        (false, false) => (2.5 * shares).max(value),
+    };
+    (0.5 * value, maintenance)
+}
+
+/// Uncovered short option: premium plus the greater of 20% of the underlying less the
+/// out-of-the-money amount, or 10% of the underlying (calls) / strike (puts)
+fn short_option_margin(call: bool, underlying: f64, strike: f64, premium: f64, contracts: f64) -> f64 {
+    let out_of_the_money = if call { (strike - underlying).max(0.0) } else { (underlying - strike).max(0.0) };
+    let base = (0.20 * underlying - out_of_the_money).max(0.10 * if call { underlying } else { strike });
+    (base + premium) * OPTION_MULTIPLIER * contracts
+}
+
+fn futures_margin(spec: &FuturesSpec, price: f64, contracts: f64) -> (f64, f64) {
+    let initial = price * spec.multiplier * contracts * spec.scan_range_pct / 100.0;
+    (initial, initial / FUTURES_INITIAL_TO_MAINTENANCE)
+}
+
+struct OpenStock {
+    id: i64,
+    symbol: String,
+    long: bool,
+    shares: f64,
+}
+
+struct OpenOption {
+    id: i64,
+    symbol: String,
+    call: bool,
+    short: bool,
+    contracts: f64,
+    strike: f64,
+    /// Per share, at entry
+    premium: f64,
+}
+
+async fn open_positions(conn: &Connection) -> Result<(Vec<OpenStock>, Vec<OpenOption>)> {
+    let mut rows = conn
+        .prepare("SELECT id, UPPER(symbol), trade_type, number_shares FROM stocks WHERE exit_date IS NULL AND is_deleted = 0")
+        .await?
+        .query(params![])
+        .await?;
+    let mut stocks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        stocks.push(OpenStock {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            long: row.get::<String>(2)? != "SELL",
+            shares: money::to_f64(money::row_decimal(&row, 3)),
+        });
+    }
+
+    let mut rows = conn
+        .prepare(
+            "SELECT id, UPPER(symbol), option_type, COALESCE(short_premium, 0), number_of_contracts, strike_price, entry_price \
+             FROM options WHERE status = 'open' AND is_deleted = 0",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    let mut options = Vec::new();
+    while let Some(row) = rows.next().await? {
+        options.push(OpenOption {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            call: row.get::<String>(2)? == "Call",
+            short: row.get::<i64>(3)? != 0,
+            contracts: row.get::<i64>(4)? as f64,
+            strike: money::to_f64(money::row_decimal(&row, 5)),
+            premium: money::to_f64(money::row_decimal(&row, 6)),
+        });
+    }
+    Ok((stocks, options))
+}
+
+/// Linked brokerage balances, falling back to the account size set for position sizing
+async fn account_equity(conn: &Connection) -> Result<Option<f64>> {
+    let mut rows = conn.prepare("SELECT SUM(balance) FROM brokerage_accounts").await?.query(params![]).await?;
+    let balances = match rows.next().await? {
+        Some(row) => row.get::<Option<f64>>(0)?,
+        None => None,
+    };
+    if balances.is_some_and(|b| b > 0.0) {
+        return Ok(balances);
+    }
+    let mut rows = conn
+        .prepare("SELECT account_size FROM sizing_rules WHERE id = 'default'")
+        .await?
+        .query(params![])
+        .await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<Option<f64>>(0)?.filter(|size| *size > 0.0),
+        None => None,
+    })
+}
+
+/// Quote symbol: futures use the continuous front-month contract, so every expiry of a
+/// root is valued at the front month's price
+fn quote_symbol(symbol: &str) -> String {
+    match futures_spec(symbol) {
+        Some(spec) => format!("{}=F", spec.root),
+        None => symbol.to_string(),
+    }
+}
+
+pub async fn estimate_margin(conn: &Connection, market: &MarketClient) -> Result<MarginReport> {
+    let (stocks, options) = open_positions(conn).await?;
+    let mut symbols: Vec<String> = stocks
+        .iter()
+        .map(|s| quote_symbol(&s.symbol))
+        .chain(options.iter().map(|o| o.symbol.clone()))
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+    let quotes: Vec<Quote> = if symbols.is_empty() { Vec::new() } else { get_quotes(market, &symbols).await? };
+    let price_of = |symbol: &str| {
+        let symbol = quote_symbol(symbol);
+        let quote = quotes.iter().find(|q| q.symbol.eq_ignore_ascii_case(&symbol))?;
+        parse_number(quote.price.as_deref()).filter(|p| *p > 0.0)
+    };
+
+    let mut positions = Vec::new();
+    let mut unpriced_positions = Vec::new();
+    for stock in &stocks {
+        let Some(price) = price_of(&stock.symbol) else {
+            unpriced_positions.push(stock.symbol.clone());
+            continue;
+        };
+        let side = if stock.long { "Long" } else { "Short" };
+        let sign = if stock.long { 1.0 } else { -1.0 };
+        let position = match futures_spec(&stock.symbol) {
+            Some(spec) => {
+                let (initial, maintenance) = futures_margin(spec, price, stock.shares);
+                MarginPosition {
+                    kind: "future".to_string(),
+                    trade_id: stock.id,
+                    symbol: stock.symbol.clone(),
+                    description: format!("{} {} {} contracts", side, stock.shares, spec.root),
+                    market_value: sign * price * spec.multiplier * stock.shares,
+                    initial_margin: initial,
+                    maintenance_margin: maintenance,
+                    method: MarginMethod::SpanApprox,
+                }
+            }
+            None => {
+                let (initial, maintenance) = stock_margin(price, stock.shares, stock.long);
+                MarginPosition {
+                    kind: "stock".to_string(),
+                    trade_id: stock.id,
+                    symbol: stock.symbol.clone(),
+                    description: format!("{} {} shares", side, stock.shares),
+                    market_value: sign * price * stock.shares,
+                    initial_margin: initial,
+                    maintenance_margin: maintenance,
+                    method: MarginMethod::RegT,
+                }
+            }
+        };
+        positions.push(position);
+    }
+
+    for option in &options {
+        let premium_value = option.premium * OPTION_MULTIPLIER * option.contracts;
+        // Long options are paid in full and need no quote
+        let requirement = if option.short {
+            let Some(underlying) = price_of(&option.symbol) else {
+                unpriced_positions.push(option.symbol.clone());
+                continue;
+            };
+            short_option_margin(option.call, underlying, option.strike, option.premium, option.contracts)
+        } else {
+            premium_value
+        };
+        positions.push(MarginPosition {
+            kind: "option".to_string(),
+            trade_id: option.id,
+            symbol: option.symbol.clone(),
+            description: format!(
+                "{} {} {} {}",
+                if option.short { "Short" } else { "Long" },
+                option.contracts,
+                option.strike,
+                if option.call { "call" } else { "put" }
+            ),
+            market_value: if option.short { -premium_value } else { premium_value },
+            initial_margin: requirement,
+            maintenance_margin: requirement,
+            method: MarginMethod::RegT,
+        });
+    }
+
+    let total_initial_margin: f64 = positions.iter().map(|p| p.initial_margin).sum();
+    let total_maintenance_margin: f64 = positions.iter().map(|p| p.maintenance_margin).sum();
+    let account_equity = account_equity(conn).await?;
+    let utilization = |margin: f64| account_equity.map(|equity| margin / equity * 100.0);
+    Ok(MarginReport {
+        gross_exposure: positions.iter().map(|p| p.market_value.abs()).sum(),
+        net_exposure: positions.iter().map(|p| p.market_value).sum(),
+        initial_utilization_pct: utilization(total_initial_margin),
+        maintenance_utilization_pct: utilization(total_maintenance_margin),
+        excess_liquidity: account_equity.map(|equity| equity - total_maintenance_margin),
+        account_equity,
+        total_initial_margin,
+        total_maintenance_margin,
+        positions,
+        unpriced_positions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reg_t_requirements() {
+        assert_eq!(stock_margin(100.0, 10.0, true), (500.0, 250.0));
+        assert_eq!(stock_margin(100.0, 10.0, false), (500.0, 300.0));
+        assert_eq!(stock_margin(2.0, 100.0, false), (100.0, 250.0));
+
+        // Short 1 put, strike 95, underlying 100, $1.50 premium: 20% - 5 OTM = 15 vs 10% of strike = 9.5
+        assert!((short_option_margin(false, 100.0, 95.0, 1.5, 1.0) - 1650.0).abs() < 1e-9);
+        // Far OTM call falls to the 10% minimum
+        assert!((short_option_margin(true, 100.0, 150.0, 0.2, 2.0) - 2040.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_futures_symbols_and_span_approximation() {
+        assert_eq!(futures_spec("/ES").map(|s| s.root), Some("ES"));
+        assert_eq!(futures_spec("ESZ5").map(|s| s.root), Some("ES"));
+        assert_eq!(futures_spec("/MNQH26").map(|s| s.root), Some("MNQ"));
+        assert_eq!(futures_spec("CL=F").map(|s| s.root), Some("CL"));
+        assert!(futures_spec("CL").is_none());
+        assert!(futures_spec("ESPR").is_none());
+
+        let (initial, maintenance) = futures_margin(futures_spec("/ES").unwrap(), 5000.0, 2.0);
+        assert!((initial - 32_500.0).abs() < 1e-6);
+        assert!((initial / maintenance - 1.1).abs() < 1e-9);
+        assert_eq!(quote_symbol("/ESZ5"), "ES=F");
+    }
+}
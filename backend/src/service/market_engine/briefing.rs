@@ -0,0 +1,148 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use libsql::Connection;
+use serde::{Deserialize, Serialize};
+
+use crate::service::analytics_engine::drawdown_sizing::{recommend_position_size, SizingRecommendation};
+use super::client::MarketClient;
+use super::earnings_calendar::{get_earnings_calendar, EarningsCalendar, EarningsCalendarParams};
+use super::indices::{get_indices, IndexItem};
+use super::movers::{get_gainers, get_losers, MoverItem};
+use super::sectors::{get_sectors, SectorPerformanceItem};
+use super::watchlist_price::get_watchlist_entries;
+
+/// Movers shown on each side of the briefing
+const GAP_MOVERS: u32 = 10;
+
+/// How far ahead the briefing looks for earnings of the user's symbols
+const EARNINGS_LOOKAHEAD_DAYS: i64 = 7;
+
+/// Scheduled macro release (CPI, FOMC, payrolls...)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomicEvent {
+    pub date: String,
+    pub time: Option<String>,
+    pub country: Option<String>,
+    pub event: String,
+    pub importance: Option<String>,
+    pub forecast: Option<String>,
+    pub previous: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GapMovers {
+    pub gainers: Vec<MoverItem>,
+    pub losers: Vec<MoverItem>,
+}
+
+/// Morning-prep payload: what moved, what reports, what's scheduled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketBriefing {
+    pub date: String,
+    pub gap_movers: GapMovers,
+    /// Upcoming earnings of symbols the user holds or watches
+    pub earnings: Vec<EarningsCalendar>,
+    pub economic_events: Vec<EconomicEvent>,
+    pub sectors: Vec<SectorPerformanceItem>,
+    pub indices: Vec<IndexItem>,
+    /// Symbols the earnings section was filtered on
+    pub tracked_symbols: Vec<String>,
+    /// Position size for today given the current drawdown, when sizing rules are set up
+    pub sizing: Option<SizingRecommendation>,
+    pub summary: Option<String>,
+}
+
+/// Symbols of open positions and watchlist entries, uppercased and deduplicated
+pub async fn tracked_symbols(conn: &Connection) -> Result<Vec<String>> {
+    let mut symbols = Vec::new();
+    let mut rows = conn
+        .prepare(
+            "SELECT symbol FROM stocks WHERE exit_date IS NULL AND is_deleted = 0
+             UNION
+             SELECT symbol FROM options WHERE status = 'open' AND is_deleted = 0",
+        )
+        .await?
+        .query(libsql::params![])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        symbols.push(row.get::<String>(0)?);
+    }
+    symbols.extend(get_watchlist_entries(conn).await?.into_iter().map(|e| e.ticker_symbol));
+
+    let mut symbols: Vec<String> = symbols.into_iter().map(|s| s.trim().to_uppercase()).filter(|s| !s.is_empty()).collect();
+    symbols.sort();
+    symbols.dedup();
+    Ok(symbols)
+}
+
+/// Today's economic calendar from `ECONOMIC_CALENDAR_URL`, which must return a JSON array
+/// of `EconomicEvent`s for a `date=YYYY-MM-DD` query. Empty when no source is configured.
+pub(crate) async fn fetch_economic_events(date: &str) -> Result<Vec<EconomicEvent>> {
+    let Ok(url) = std::env::var("ECONOMIC_CALENDAR_URL") else {
+        return Ok(Vec::new());
+    };
+    let events = reqwest::Client::new()
+        .get(&url)
+        .query(&[("date", date)])
+        .timeout(std::time::Duration::from_secs(8))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<EconomicEvent>>()
+        .await?;
+    Ok(events)
+}
+
+/// Compose the briefing. Each section degrades to empty on provider errors so one slow
+/// upstream doesn't blank the whole screen.
+pub async fn build_briefing(client: &MarketClient, conn: &Connection) -> Result<MarketBriefing> {
+    let today = Utc::now().date_naive();
+    let date = today.format("%Y-%m-%d").to_string();
+    let symbols = tracked_symbols(conn).await?;
+
+    let earnings = async {
+        if symbols.is_empty() {
+            return Ok(Vec::new());
+        }
+        let params = EarningsCalendarParams {
+            from_date: Some(date.clone()),
+            to_date: Some((today + Duration::days(EARNINGS_LOOKAHEAD_DAYS)).format("%Y-%m-%d").to_string()),
+            symbols: Some(symbols.clone()),
+        };
+        get_earnings_calendar(client, params).await
+    };
+
+    let (gainers, losers, earnings, economic_events, sectors, indices) = tokio::join!(
+        get_gainers(client, Some(GAP_MOVERS)),
+        get_losers(client, Some(GAP_MOVERS)),
+        earnings,
+        fetch_economic_events(&date),
+        get_sectors(client),
+        get_indices(client),
+    );
+
+    Ok(MarketBriefing {
+        date: date.clone(),
+        gap_movers: GapMovers {
+            gainers: section("gainers", gainers),
+            losers: section("losers", losers),
+        },
+        earnings: section("earnings", earnings),
+        economic_events: section("economic events", economic_events),
+        sectors: section("sectors", sectors),
+        indices: section("indices", indices),
+        tracked_symbols: symbols,
+        sizing: recommend_position_size(conn).await.unwrap_or_else(|e| {
+            log::warn!("Briefing sizing recommendation unavailable: {}", e);
+            None
+        }),
+        summary: None,
+    })
+}
+
+fn section<T>(name: &str, result: Result<Vec<T>>) -> Vec<T> {
+    result.unwrap_or_else(|e| {
+        log::warn!("Briefing section {} unavailable: {}", name, e);
+        Vec::new()
+    })
+}
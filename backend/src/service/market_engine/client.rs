@@ -2,6 +2,7 @@ use anyhow::{anyhow, Result};
 use reqwest::{Client, Response};
 use std::time::Duration;
 
+use crate::service::cost_ledger::{self, Provider};
 use crate::turso::config::FinanceQueryConfig;
 
 #[derive(Clone)]
@@ -57,6 +58,9 @@ impl MarketClient {
             match req.send().await {
                 Ok(resp) => {
                     if resp.status().is_success() {
+                        // Charged per call under the endpoint's first path segment (`quotes`, `historical`, ...)
+                        let endpoint = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+                        cost_ledger::record(Provider::MarketData, endpoint, 1, 0, cost_ledger::rates().market_data_per_call);
                         return Ok(resp);
                     } else {
                         let status = resp.status();
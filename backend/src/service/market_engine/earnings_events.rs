@@ -0,0 +1,141 @@
+//! Earnings dates of held and watched symbols, mirrored into the user's calendar.
+//!
+//! Each symbol gets one system-generated `calendar_events` row (`source = 'earnings'`,
+//! `source_ref = symbol`) that follows its next report date. Re-running the sync moves the
+//! event when the provider reschedules, and removes it once the symbol is neither held nor
+//! watched anymore. Reported dates stay on the calendar until the next one is announced.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use libsql::{Connection, params};
+use serde::Serialize;
+
+use super::briefing::tracked_symbols;
+use super::client::MarketClient;
+use super::earnings_calendar::{get_earnings_calendar, EarningsCalendar, EarningsCalendarParams};
+
+/// `calendar_events.source` of the generated events
+pub const EARNINGS_EVENT_SOURCE: &str = "earnings";
+
+/// Roughly one reporting cycle ahead
+const SYNC_LOOKAHEAD_DAYS: i64 = 100;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EarningsSyncSummary {
+    pub symbols: usize,
+    pub upserted: u64,
+    pub removed: u64,
+}
+
+/// Create, move or remove the earnings events of one user's calendar
+pub async fn sync_earnings_events(client: &MarketClient, conn: &Connection) -> Result<EarningsSyncSummary> {
+    let symbols = tracked_symbols(conn).await?;
+    let today = Utc::now().date_naive().format("%Y-%m-%d").to_string();
+
+    let earnings = if symbols.is_empty() {
+        Vec::new()
+    } else {
+        let params = EarningsCalendarParams {
+            from_date: Some(today.clone()),
+            to_date: Some((Utc::now().date_naive() + Duration::days(SYNC_LOOKAHEAD_DAYS)).format("%Y-%m-%d").to_string()),
+            symbols: Some(symbols.clone()),
+        };
+        get_earnings_calendar(client, params).await?
+    };
+    let upcoming = next_report_per_symbol(earnings, &symbols);
+
+    let mut upserted = 0u64;
+    for report in &upcoming {
+        upserted += conn
+            .execute(
+                "INSERT INTO calendar_events (id, reminder_id, event_title, event_description, start_date, end_date, start_time, end_time, is_all_day, is_synced, source, source_ref)
+                 VALUES (?, NULL, ?, ?, ?, ?, NULL, NULL, 1, 0, ?, ?)
+                 ON CONFLICT(source, source_ref) DO UPDATE SET
+                    event_title = excluded.event_title,
+                    event_description = excluded.event_description,
+                    start_date = excluded.start_date,
+                    end_date = excluded.end_date
+                 WHERE calendar_events.start_date != excluded.start_date
+                    OR IFNULL(calendar_events.event_description, '') != IFNULL(excluded.event_description, '')",
+                params![
+                    uuid::Uuid::new_v4().to_string(),
+                    format!("{} earnings", report.symbol),
+                    event_description(report),
+                    report.earnings_date.clone(),
+                    report.earnings_date.clone(),
+                    EARNINGS_EVENT_SOURCE,
+                    report.symbol.clone()
+                ],
+            )
+            .await?;
+    }
+
+    let removed = remove_stale_events(conn, &symbols, &upcoming, &today).await?;
+
+    Ok(EarningsSyncSummary { symbols: symbols.len(), upserted, removed })
+}
+
+/// Drop events of symbols no longer held or watched, and upcoming events whose report
+/// disappeared from the provider's calendar
+async fn remove_stale_events(
+    conn: &Connection,
+    symbols: &[String],
+    upcoming: &[EarningsCalendar],
+    today: &str,
+) -> Result<u64> {
+    let mut stale = Vec::new();
+    let mut rows = conn
+        .prepare("SELECT id, source_ref, start_date FROM calendar_events WHERE source = ?")
+        .await?
+        .query(params![EARNINGS_EVENT_SOURCE])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let id: String = row.get(0)?;
+        let symbol: Option<String> = row.get(1)?;
+        let start_date: String = row.get(2)?;
+        let tracked = symbol.as_ref().is_some_and(|s| symbols.contains(s));
+        let scheduled = symbol.as_ref().is_some_and(|s| upcoming.iter().any(|r| &r.symbol == s));
+        if !tracked || (start_date.as_str() >= today && !scheduled) {
+            stale.push(id);
+        }
+    }
+
+    let mut removed = 0u64;
+    for id in stale {
+        removed += conn.execute("DELETE FROM calendar_events WHERE id = ?", params![id]).await?;
+    }
+    Ok(removed)
+}
+
+/// Earliest report of each tracked symbol, symbols uppercased to match `tracked_symbols`
+fn next_report_per_symbol(earnings: Vec<EarningsCalendar>, symbols: &[String]) -> Vec<EarningsCalendar> {
+    let mut next: Vec<EarningsCalendar> = Vec::new();
+    for mut report in earnings {
+        report.symbol = report.symbol.trim().to_uppercase();
+        report.earnings_date = report.earnings_date.chars().take(10).collect();
+        if !symbols.contains(&report.symbol) {
+            continue;
+        }
+        match next.iter_mut().find(|r| r.symbol == report.symbol) {
+            Some(existing) if report.earnings_date < existing.earnings_date => *existing = report,
+            Some(_) => {}
+            None => next.push(report),
+        }
+    }
+    next
+}
+
+fn event_description(report: &EarningsCalendar) -> String {
+    let timing = match report.time_of_day.as_deref() {
+        Some("bmo") => "before market open",
+        Some("amc") => "after market close",
+        Some("dmh") => "during market hours",
+        _ => "time not confirmed",
+    };
+    let mut description = format!("{} reports earnings {}.", report.symbol, timing);
+    if let Some(eps) = report.eps_estimated {
+        description.push_str(&format!(" EPS estimate: {:.2}.", eps));
+    }
+    description.push_str(" Added automatically for a symbol you hold or watch.");
+    description
+}
@@ -1,7 +1,14 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use libsql::{params, Connection};
 use serde::{Deserialize, Serialize};
 
 use super::client::MarketClient;
+use crate::models::timestamps;
+use crate::service::ai_service::trade_parser_service::extract_json;
+
+/// Longest transcript excerpt sent to the model
+pub const MAX_TRANSCRIPT_CHARS: usize = 60_000;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Transcript {
@@ -67,3 +74,225 @@ pub async fn get_earnings_transcript(
     let body = resp.json::<EarningsTranscriptsResponse>().await?;
     Ok(body)
 }
+
+pub const TRANSCRIPT_SUMMARY_PROMPT: &str = "You summarize an earnings call transcript for a trader. Reply with JSON only: \
+{\"summary\": \"2-3 sentences\", \"guidance\": [\"forward guidance, with the numbers management gave\"], \
+\"surprises\": [\"results or remarks that differ from what was expected\"], \"tone\": \"positive|neutral|negative|mixed\", \
+\"tone_rationale\": \"one sentence\", \"key_quotes\": [\"short verbatim quotes from management\"]}. \
+Use only what the transcript says; keep every figure exactly as stated and leave a list empty rather than guessing.";
+
+/// What management said on one call, as summarized by the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSummary {
+    pub symbol: String,
+    pub quarter: String,
+    pub year: i32,
+    /// `YYYY-MM-DD` of the call
+    pub call_date: String,
+    pub summary: String,
+    pub guidance: Vec<String>,
+    pub surprises: Vec<String>,
+    /// "positive", "neutral", "negative" or "mixed"
+    pub tone: String,
+    pub tone_rationale: String,
+    pub key_quotes: Vec<String>,
+    pub generated_at: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct SummaryFields {
+    summary: String,
+    guidance: Vec<String>,
+    surprises: Vec<String>,
+    tone: String,
+    tone_rationale: String,
+    key_quotes: Vec<String>,
+}
+
+/// Redis key of a call's summary; transcripts don't change once published
+pub fn summary_cache_key(symbol: &str, quarter: &str, year: i32) -> String {
+    format!("market:transcript_summary:{}:{}:{}", symbol.trim().to_uppercase(), quarter.trim().to_uppercase(), year)
+}
+
+/// User message for the model: call header, then the transcript cut to `MAX_TRANSCRIPT_CHARS`
+pub fn summary_input(transcript: &Transcript) -> String {
+    format!(
+        "{} {} {} earnings call ({})\n\n{}",
+        transcript.symbol,
+        transcript.quarter,
+        transcript.year,
+        transcript.date,
+        transcript.transcript.chars().take(MAX_TRANSCRIPT_CHARS).collect::<String>()
+    )
+}
+
+pub fn call_date(transcript: &Transcript) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(transcript.date.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Build the summary from the model's JSON reply
+pub fn parse_summary(transcript: &Transcript, response: &str) -> Result<TranscriptSummary> {
+    let fields: SummaryFields =
+        serde_json::from_str(extract_json(response)).map_err(|e| anyhow!("Transcript summary was not valid JSON: {}", e))?;
+    if fields.summary.trim().is_empty() {
+        return Err(anyhow!("Transcript summary was empty"));
+    }
+    let tone = fields.tone.trim().to_lowercase();
+    let clean = |items: Vec<String>| items.into_iter().map(|i| i.trim().to_string()).filter(|i| !i.is_empty()).collect();
+    Ok(TranscriptSummary {
+        symbol: transcript.symbol.trim().to_uppercase(),
+        quarter: transcript.quarter.clone(),
+        year: transcript.year,
+        call_date: call_date(transcript).map(|d| d.to_string()).unwrap_or_else(|| transcript.date.clone()),
+        summary: fields.summary.trim().to_string(),
+        guidance: clean(fields.guidance),
+        surprises: clean(fields.surprises),
+        tone: if ["positive", "neutral", "negative", "mixed"].contains(&tone.as_str()) { tone } else { "neutral".to_string() },
+        tone_rationale: fields.tone_rationale.trim().to_string(),
+        key_quotes: clean(fields.key_quotes),
+        generated_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// A position open across the close on either side of the call: entered on or before the
+/// call date, exited on or after it, and not a same-day round trip.
+pub fn held_through(entry: DateTime<Utc>, exit: Option<DateTime<Utc>>, call: NaiveDate) -> bool {
+    let entry_day = entry.date_naive();
+    let exit_day = exit.map(|e| e.date_naive());
+    entry_day <= call && exit_day.is_none_or(|d| d >= call && d > entry_day)
+}
+
+/// A user trade held through a call
+#[derive(Debug, Clone, Serialize)]
+pub struct HeldTrade {
+    /// "stock" or "option"
+    pub trade_type: String,
+    pub trade_id: i64,
+    pub entry_date: DateTime<Utc>,
+    pub exit_date: Option<DateTime<Utc>>,
+}
+
+/// The user's trades in `symbol` that were held through a call on `call`
+pub async fn trades_held_through(conn: &Connection, symbol: &str, call: NaiveDate) -> Result<Vec<HeldTrade>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT 'stock', id, entry_date, exit_date FROM stocks WHERE UPPER(symbol) = ?1 AND is_deleted = 0 AND date(entry_date) <= ?2
+             UNION ALL
+             SELECT 'option', id, entry_date, exit_date FROM options WHERE UPPER(symbol) = ?1 AND is_deleted = 0 AND date(entry_date) <= ?2",
+        )
+        .await?
+        .query(params![symbol.trim().to_uppercase(), call.to_string()])
+        .await?;
+    let mut held = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let Ok(entry_date) = timestamps::parse_stored(&row.get::<String>(2)?) else { continue };
+        let exit_date = row.get::<Option<String>>(3)?.and_then(|e| timestamps::parse_stored(&e).ok());
+        if held_through(entry_date, exit_date, call) {
+            held.push(HeldTrade { trade_type: row.get(0)?, trade_id: row.get(1)?, entry_date, exit_date });
+        }
+    }
+    Ok(held)
+}
+
+/// Symbol, entry and exit of one of the user's trades; `trade_type` is "stock" or "option"
+pub async fn trade_window(
+    conn: &Connection,
+    trade_type: &str,
+    trade_id: i64,
+) -> Result<Option<(String, DateTime<Utc>, Option<DateTime<Utc>>)>> {
+    let table = match trade_type {
+        "stock" => "stocks",
+        "option" => "options",
+        _ => return Ok(None),
+    };
+    let mut rows = conn
+        .prepare(&format!("SELECT symbol, entry_date, exit_date FROM {} WHERE id = ? AND is_deleted = 0", table))
+        .await?
+        .query(params![trade_id])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let entry = timestamps::parse_stored(&row.get::<String>(1)?).map_err(|e| anyhow!(e))?;
+    let exit = row.get::<Option<String>>(2)?.and_then(|e| timestamps::parse_stored(&e).ok());
+    Ok(Some((row.get::<String>(0)?.trim().to_uppercase(), entry, exit)))
+}
+
+/// Attach a call summary to a trade so its review can show what management said
+pub async fn link_summary(conn: &Connection, trade: &HeldTrade, summary: &TranscriptSummary) -> Result<()> {
+    conn.execute(
+        "INSERT INTO trade_earnings_summaries (id, trade_type, trade_id, symbol, quarter, year, call_date, summary, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(trade_type, trade_id, quarter, year) DO UPDATE SET summary = excluded.summary, call_date = excluded.call_date",
+        params![
+            uuid::Uuid::new_v4().to_string(),
+            trade.trade_type.clone(),
+            trade.trade_id,
+            summary.symbol.clone(),
+            summary.quarter.clone(),
+            summary.year,
+            summary.call_date.clone(),
+            serde_json::to_string(summary)?,
+            Utc::now().to_rfc3339()
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Summaries linked to one trade, oldest call first
+pub async fn summaries_for_trade(conn: &Connection, trade_type: &str, trade_id: i64) -> Result<Vec<TranscriptSummary>> {
+    let mut rows = conn
+        .prepare("SELECT summary FROM trade_earnings_summaries WHERE trade_type = ? AND trade_id = ? ORDER BY call_date")
+        .await?
+        .query(params![trade_type, trade_id])
+        .await?;
+    let mut summaries = Vec::new();
+    while let Some(row) = rows.next().await? {
+        if let Ok(summary) = serde_json::from_str(&row.get::<String>(0)?) {
+            summaries.push(summary);
+        }
+    }
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn transcript() -> Transcript {
+        Transcript {
+            symbol: "aapl".to_string(),
+            quarter: "Q3".to_string(),
+            year: 2026,
+            date: "2026-07-30T21:00:00Z".to_string(),
+            transcript: "Operator: Welcome.".to_string(),
+            participants: vec![],
+            metadata: TranscriptMetadata { source: "test".to_string(), retrieved_at: String::new(), transcripts_id: 1 },
+        }
+    }
+
+    #[test]
+    fn test_parse_summary() {
+        let response = "```json\n{\"summary\": \"Beat on services.\", \"guidance\": [\"Revenue up low single digits\", \" \"], \"tone\": \"Upbeat\"}\n```";
+        let summary = parse_summary(&transcript(), response).unwrap();
+        assert_eq!(summary.symbol, "AAPL");
+        assert_eq!(summary.call_date, "2026-07-30");
+        assert_eq!(summary.guidance, vec!["Revenue up low single digits"]);
+        assert_eq!(summary.tone, "neutral");
+        assert!(summary.surprises.is_empty());
+        assert!(parse_summary(&transcript(), "no json here").is_err());
+    }
+
+    #[test]
+    fn test_held_through() {
+        let call = NaiveDate::from_ymd_opt(2026, 7, 30).unwrap();
+        let at = |d: u32, h: u32| Utc.with_ymd_and_hms(2026, 7, d, h, 0, 0).unwrap();
+        assert!(held_through(at(29, 15), Some(at(31, 15)), call));
+        assert!(held_through(at(30, 15), Some(at(31, 14)), call));
+        assert!(held_through(at(29, 15), Some(at(30, 14)), call));
+        assert!(held_through(at(28, 15), None, call));
+        assert!(!held_through(at(30, 14), Some(at(30, 19)), call));
+        assert!(!held_through(at(27, 15), Some(at(29, 19)), call));
+    }
+}
@@ -1,8 +1,12 @@
 use anyhow::Result;
+use chrono::{NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use super::client::MarketClient;
+use super::quotes::{self, Quote};
+use crate::service::cache_service::CacheService;
+use crate::turso::redis::ttl;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FinancialStatementRow {
@@ -46,3 +50,307 @@ pub async fn get_financials(
     let body = resp.json::<FinancialsResponse>().await?;
     Ok(body)
 }
+
+/// Fundamentals cached per symbol and frequency; statements only change with a new filing
+pub fn fundamentals_cache_key(symbol: &str, frequency: &str) -> String {
+    format!("market:fundamentals:{}:{}", symbol.to_uppercase(), frequency)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeriodValue {
+    pub period: String,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Valuation {
+    pub price: Option<f64>,
+    pub market_cap: Option<String>,
+    pub pe: Option<f64>,
+    /// P/E divided by the latest EPS growth in percent
+    pub peg: Option<f64>,
+    pub earnings_yield_pct: Option<f64>,
+}
+
+/// Normalized view of the income statement plus quote-based multiples
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundamentalsSnapshot {
+    pub symbol: String,
+    pub frequency: String,
+    pub sector: Option<String>,
+    pub industry: Option<String>,
+    /// Latest reported period the growth and margins come from
+    pub latest_period: Option<String>,
+    /// Newest first, as reported
+    pub revenue: Vec<PeriodValue>,
+    /// Year over year: previous year for annual data, same quarter a year ago for quarterly
+    pub revenue_growth_pct: Option<f64>,
+    pub gross_margin_pct: Option<f64>,
+    pub operating_margin_pct: Option<f64>,
+    pub net_margin_pct: Option<f64>,
+    /// Diluted EPS, newest first
+    pub eps_trend: Vec<PeriodValue>,
+    pub eps_growth_pct: Option<f64>,
+    pub valuation: Valuation,
+    pub generated_at: String,
+}
+
+const REVENUE_ROWS: &[&str] = &["Total Revenue", "Revenue", "Operating Revenue"];
+const GROSS_PROFIT_ROWS: &[&str] = &["Gross Profit"];
+const OPERATING_INCOME_ROWS: &[&str] = &["Operating Income", "Total Operating Income As Reported"];
+const NET_INCOME_ROWS: &[&str] = &["Net Income Common Stockholders", "Net Income", "Net Income From Continuing Operations"];
+const EPS_ROWS: &[&str] = &["Diluted EPS", "Basic EPS"];
+/// Reported periods kept in the revenue and EPS trends
+const TREND_PERIODS: usize = 5;
+
+/// Statement cell as a number: "391,035,000", "(1,200)", "6.08" or "-" for missing
+pub fn parse_statement_value(raw: &str) -> Option<f64> {
+    let cleaned: String = raw.trim().chars().filter(|c| *c != ',' && *c != '$' && *c != '%').collect();
+    let (negative, digits) = match cleaned.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        Some(inner) => (true, inner.to_string()),
+        None => (false, cleaned),
+    };
+    let value = digits.trim().parse::<f64>().ok().filter(|v| v.is_finite())?;
+    Some(if negative { -value } else { value })
+}
+
+/// Period column header as a date; "TTM" and other labels have none
+fn period_date(period: &str) -> Option<NaiveDate> {
+    let period = period.trim();
+    NaiveDate::parse_from_str(period, "%m/%d/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(period, "%Y-%m-%d"))
+        .ok()
+}
+
+/// Dated values of the first matching row, newest first
+fn row_series(statement: &FinancialStatement, names: &[&str]) -> Vec<(NaiveDate, String, f64)> {
+    let row = names.iter().find_map(|name| {
+        statement.rows.values().find(|row| row.breakdown.trim().eq_ignore_ascii_case(name))
+    });
+    let Some(row) = row else { return Vec::new() };
+    let mut series: Vec<(NaiveDate, String, f64)> = row
+        .period_data
+        .iter()
+        .filter_map(|(period, raw)| Some((period_date(period)?, period.clone(), parse_statement_value(raw)?)))
+        .collect();
+    series.sort_by_key(|s| std::cmp::Reverse(s.0));
+    series
+}
+
+fn value_at(series: &[(NaiveDate, String, f64)], date: NaiveDate) -> Option<f64> {
+    series.iter().find(|(d, _, _)| *d == date).map(|(_, _, v)| *v)
+}
+
+fn pct_change(current: f64, previous: f64) -> Option<f64> {
+    (previous != 0.0).then(|| (current - previous) / previous.abs() * 100.0)
+}
+
+fn margin(numerator: Option<f64>, revenue: Option<f64>) -> Option<f64> {
+    let revenue = revenue.filter(|r| *r != 0.0)?;
+    Some(numerator? / revenue * 100.0)
+}
+
+/// Growth of the newest value against the one `lag` periods back
+fn growth(series: &[(NaiveDate, String, f64)], lag: usize) -> Option<f64> {
+    pct_change(series.first()?.2, series.get(lag)?.2)
+}
+
+fn trend(series: &[(NaiveDate, String, f64)]) -> Vec<PeriodValue> {
+    series.iter().take(TREND_PERIODS).map(|(_, period, value)| PeriodValue { period: period.clone(), value: *value }).collect()
+}
+
+/// Build the snapshot from an income statement and an optional quote
+pub fn build_snapshot(symbol: &str, frequency: &str, statement: &FinancialStatement, quote: Option<&Quote>) -> FundamentalsSnapshot {
+    let revenue = row_series(statement, REVENUE_ROWS);
+    let gross = row_series(statement, GROSS_PROFIT_ROWS);
+    let operating = row_series(statement, OPERATING_INCOME_ROWS);
+    let net = row_series(statement, NET_INCOME_ROWS);
+    let eps = row_series(statement, EPS_ROWS);
+    // Quarterly data compares against the same quarter a year earlier
+    let lag = if frequency == "quarterly" { 4 } else { 1 };
+
+    let latest = revenue.first().map(|(date, _, _)| *date);
+    let latest_revenue = revenue.first().map(|(_, _, v)| *v);
+    let at_latest = |series: &[(NaiveDate, String, f64)]| latest.and_then(|date| value_at(series, date));
+
+    // Trailing EPS: the latest annual figure, or the sum of the last four quarters
+    let trailing_eps = if frequency == "quarterly" {
+        (eps.len() >= 4).then(|| eps.iter().take(4).map(|(_, _, v)| v).sum::<f64>())
+    } else {
+        eps.first().map(|(_, _, v)| *v)
+    };
+    let eps_growth_pct = growth(&eps, lag);
+    let price = quote.and_then(|q| q.price.as_deref()).and_then(parse_statement_value);
+    let pe = quote
+        .and_then(|q| q.pe.as_deref())
+        .and_then(parse_statement_value)
+        .or_else(|| Some(price? / trailing_eps.filter(|e| *e > 0.0)?));
+    let valuation = Valuation {
+        price,
+        market_cap: quote.and_then(|q| q.market_cap.clone()),
+        pe,
+        peg: pe.zip(eps_growth_pct.filter(|g| *g > 0.0)).map(|(pe, g)| pe / g),
+        earnings_yield_pct: pe.filter(|pe| *pe > 0.0).map(|pe| 100.0 / pe),
+    };
+
+    FundamentalsSnapshot {
+        symbol: symbol.to_uppercase(),
+        frequency: frequency.to_string(),
+        sector: quote.and_then(|q| q.sector.clone()),
+        industry: quote.and_then(|q| q.industry.clone()),
+        latest_period: revenue.first().map(|(_, period, _)| period.clone()),
+        revenue_growth_pct: growth(&revenue, lag),
+        gross_margin_pct: margin(at_latest(&gross), latest_revenue),
+        operating_margin_pct: margin(at_latest(&operating), latest_revenue),
+        net_margin_pct: margin(at_latest(&net), latest_revenue),
+        revenue: trend(&revenue),
+        eps_trend: trend(&eps),
+        eps_growth_pct,
+        valuation,
+        generated_at: Utc::now().to_rfc3339(),
+    }
+}
+
+/// Income statement and quote for `symbol`, normalized into growth, margins, EPS trend
+/// and valuation multiples. A missing quote only leaves the valuation empty.
+pub async fn get_fundamentals(client: &MarketClient, symbol: &str, frequency: Option<&str>) -> Result<FundamentalsSnapshot> {
+    let frequency = match frequency {
+        Some("quarterly") => "quarterly",
+        _ => "annual",
+    };
+    let financials = get_financials(client, symbol, Some("income"), Some(frequency)).await?;
+    let quote = match quotes::get_quotes(client, &[symbol.to_uppercase()]).await {
+        Ok(quotes) => quotes.into_iter().next(),
+        Err(e) => {
+            log::warn!("Failed to load quote for fundamentals of {}: {}", symbol, e);
+            None
+        }
+    };
+    Ok(build_snapshot(symbol, frequency, &financials.statement, quote.as_ref()))
+}
+
+/// `get_fundamentals` through the shared market cache
+pub async fn cached_fundamentals(client: &MarketClient, cache: &CacheService, symbol: &str, frequency: Option<&str>) -> Result<FundamentalsSnapshot> {
+    let frequency = if frequency == Some("quarterly") { "quarterly" } else { "annual" };
+    let key = fundamentals_cache_key(symbol, frequency);
+    let fetch = || get_fundamentals(client, symbol, Some(frequency));
+    cache.get_or_fetch(&key, ttl::FUNDAMENTALS as u64, fetch).await
+}
+
+fn fmt_pct(value: Option<f64>) -> String {
+    value.map_or("n/a".to_string(), |v| format!("{:.1}%", v))
+}
+
+/// Compact text for the chat prompt
+pub fn format_for_prompt(snapshot: &FundamentalsSnapshot) -> String {
+    let eps: Vec<String> = snapshot.eps_trend.iter().map(|p| format!("{} {:.2}", p.period, p.value)).collect();
+    let valuation = &snapshot.valuation;
+    format!(
+        "{} ({} fundamentals, latest period {}{}): revenue growth {}, gross margin {}, operating margin {}, net margin {}; \
+         diluted EPS {} (growth {}); price {}, market cap {}, P/E {}, PEG {}",
+        snapshot.symbol,
+        snapshot.frequency,
+        snapshot.latest_period.as_deref().unwrap_or("n/a"),
+        snapshot.sector.as_deref().map(|s| format!(", {}", s)).unwrap_or_default(),
+        fmt_pct(snapshot.revenue_growth_pct),
+        fmt_pct(snapshot.gross_margin_pct),
+        fmt_pct(snapshot.operating_margin_pct),
+        fmt_pct(snapshot.net_margin_pct),
+        if eps.is_empty() { "n/a".to_string() } else { eps.join(", ") },
+        fmt_pct(snapshot.eps_growth_pct),
+        valuation.price.map_or("n/a".to_string(), |p| format!("{:.2}", p)),
+        valuation.market_cap.as_deref().unwrap_or("n/a"),
+        valuation.pe.map_or("n/a".to_string(), |p| format!("{:.1}", p)),
+        valuation.peg.map_or("n/a".to_string(), |p| format!("{:.2}", p)),
+    )
+}
+
+const FUNDAMENTALS_CUES: &[&str] = &[
+    "fundamental", "earnings", "revenue", "sales", "margin", "eps", "valuation", "p/e", "multiple",
+    "overvalued", "undervalued", "profit", "growth",
+];
+
+/// Whether a chat message asks about the business rather than the trade alone
+pub fn asks_about_fundamentals(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    FUNDAMENTALS_CUES.iter().any(|cue| lower.contains(cue))
+}
+
+/// Tickers named in a message: `$cashtags`, or words matching a symbol the user has traded
+pub fn mentioned_symbols(message: &str, traded: &[String]) -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for word in message.split(|c: char| !(c.is_ascii_alphanumeric() || c == '$' || c == '.' || c == '-')) {
+        let word = word.trim_end_matches(['.', '-']);
+        let (cashtag, ticker) = match word.strip_prefix('$') {
+            Some(rest) => (true, rest),
+            None => (false, word),
+        };
+        if ticker.is_empty() || ticker.len() > 6 || !ticker.chars().next().is_some_and(|c| c.is_ascii_alphabetic()) {
+            continue;
+        }
+        let ticker = ticker.to_uppercase();
+        let known = traded.iter().any(|t| t.eq_ignore_ascii_case(&ticker));
+        if (cashtag || known) && !symbols.contains(&ticker) {
+            symbols.push(ticker);
+        }
+    }
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, values: &[(&str, &str)]) -> FinancialStatementRow {
+        FinancialStatementRow {
+            breakdown: name.to_string(),
+            period_data: values.iter().map(|(p, v)| (p.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_statement_value() {
+        assert_eq!(parse_statement_value("391,035,000"), Some(391_035_000.0));
+        assert_eq!(parse_statement_value("(1,200)"), Some(-1200.0));
+        assert_eq!(parse_statement_value("6.08"), Some(6.08));
+        assert_eq!(parse_statement_value("-"), None);
+        assert_eq!(parse_statement_value(""), None);
+    }
+
+    #[test]
+    fn test_build_snapshot() {
+        let rows = [
+            row("Total Revenue", &[("TTM", "420"), ("9/30/2024", "400"), ("9/30/2023", "320"), ("9/30/2022", "300")]),
+            row("Gross Profit", &[("TTM", "190"), ("9/30/2024", "180"), ("9/30/2023", "140")]),
+            row("Operating Income", &[("9/30/2024", "120"), ("9/30/2023", "90")]),
+            row("Net Income Common Stockholders", &[("9/30/2024", "100"), ("9/30/2023", "-")]),
+            row("Diluted EPS", &[("9/30/2024", "5.00"), ("9/30/2023", "4.00")]),
+        ];
+        let statement = FinancialStatement {
+            rows: rows.into_iter().enumerate().map(|(i, r)| (i.to_string(), r)).collect(),
+        };
+        let snapshot = build_snapshot("aapl", "annual", &statement, None);
+
+        assert_eq!(snapshot.symbol, "AAPL");
+        assert_eq!(snapshot.latest_period.as_deref(), Some("9/30/2024"));
+        assert_eq!(snapshot.revenue.len(), 3);
+        assert_eq!(snapshot.revenue_growth_pct, Some(25.0));
+        assert_eq!(snapshot.gross_margin_pct, Some(45.0));
+        assert_eq!(snapshot.operating_margin_pct, Some(30.0));
+        assert_eq!(snapshot.net_margin_pct, Some(25.0));
+        assert_eq!(snapshot.eps_growth_pct, Some(25.0));
+        assert!(snapshot.valuation.pe.is_none());
+    }
+
+    #[test]
+    fn test_mentioned_symbols() {
+        let traded = vec!["AAPL".to_string(), "BRK.B".to_string()];
+        assert_eq!(
+            mentioned_symbols("Is my aapl swing consistent with fundamentals? Compare $msft and BRK.B.", &traded),
+            vec!["AAPL", "MSFT", "BRK.B"]
+        );
+        assert!(mentioned_symbols("I traded a lot this week", &traded).is_empty());
+        assert!(asks_about_fundamentals("Is my AAPL swing consistent with fundamentals?"));
+        assert!(!asks_about_fundamentals("How did my AAPL swing go?"));
+    }
+}
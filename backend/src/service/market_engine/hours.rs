@@ -1,4 +1,12 @@
+//! Market status and exchange calendars.
+//!
+//! `get_hours` asks the provider whether the US market is open right now. The rest is a
+//! local calendar: NYSE full holidays and 1 pm early closes, CME equity futures sessions on
+//! the NYSE calendar, and crypto trading around the clock. Alerts and scheduled jobs use it
+//! to skip sessions that don't exist without a network round-trip.
+
 use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 
 use super::client::MarketClient;
@@ -16,3 +24,269 @@ pub async fn get_hours(client: &MarketClient) -> Result<MarketHours> {
     Ok(body)
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Exchange {
+    Nyse,
+    Cme,
+    Crypto,
+}
+
+impl Exchange {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "nyse" | "nasdaq" | "us" => Some(Exchange::Nyse),
+            "cme" | "futures" => Some(Exchange::Cme),
+            "crypto" => Some(Exchange::Crypto),
+            _ => None,
+        }
+    }
+
+    /// Best guess from a ticker: `BTC-USD` style pairs trade as crypto, `ES=F` style as futures
+    pub fn for_symbol(symbol: &str) -> Self {
+        let symbol = symbol.to_uppercase();
+        if symbol.ends_with("-USD") || symbol.ends_with("-USDT") {
+            Exchange::Crypto
+        } else if symbol.ends_with("=F") {
+            Exchange::Cme
+        } else {
+            Exchange::Nyse
+        }
+    }
+}
+
+/// One trading session; `date` is the trading date the session settles on
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketSession {
+    pub exchange: Exchange,
+    pub date: NaiveDate,
+    pub open: DateTime<Utc>,
+    pub close: DateTime<Utc>,
+    pub early_close: bool,
+}
+
+/// Calendar view of an exchange for the hours endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeSchedule {
+    pub exchange: Exchange,
+    pub is_open: bool,
+    pub sessions: Vec<MarketSession>,
+    /// Early-close dates among `sessions`
+    pub early_closes: Vec<NaiveDate>,
+}
+
+/// US Eastern offset from UTC in hours: DST from the second Sunday of March to the first
+/// Sunday of November (switching at 2 am local, close enough for session boundaries)
+pub(crate) fn eastern_offset_hours(date: NaiveDate) -> i64 {
+    let year = date.year();
+    let dst_start = nth_weekday(year, 3, Weekday::Sun, 2);
+    let dst_end = nth_weekday(year, 11, Weekday::Sun, 1);
+    if date >= dst_start && date < dst_end { -4 } else { -5 }
+}
+
+fn eastern(date: NaiveDate, hour: u32, minute: u32) -> DateTime<Utc> {
+    let local = date.and_time(NaiveTime::from_hms_opt(hour, minute, 0).expect("valid session time"));
+    Utc.from_utc_datetime(&local) - Duration::hours(eastern_offset_hours(date))
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u32) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n as u8).expect("month has the weekday")
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    (1..=5)
+        .rev()
+        .find_map(|n| NaiveDate::from_weekday_of_month_opt(year, month, weekday, n))
+        .expect("month has the weekday")
+}
+
+/// Western Easter Sunday (anonymous Gregorian algorithm)
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).expect("valid Easter date")
+}
+
+/// Fixed-date holidays move to Friday when on Saturday and Monday when on Sunday
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Duration::days(1),
+        Weekday::Sun => date + Duration::days(1),
+        _ => date,
+    }
+}
+
+/// NYSE full-day closures in `year`
+pub fn nyse_holidays(year: i32) -> Vec<NaiveDate> {
+    let ymd = |m, d| NaiveDate::from_ymd_opt(year, m, d).expect("valid holiday date");
+    let mut holidays = vec![
+        nth_weekday(year, 1, Weekday::Mon, 3),
+        nth_weekday(year, 2, Weekday::Mon, 3),
+        easter(year) - Duration::days(2),
+        last_weekday(year, 5, Weekday::Mon),
+        observed(ymd(7, 4)),
+        nth_weekday(year, 9, Weekday::Mon, 1),
+        nth_weekday(year, 11, Weekday::Thu, 4),
+        observed(ymd(12, 25)),
+    ];
+    // New Year's Day on a Saturday is not observed on the prior Friday
+    if ymd(1, 1).weekday() != Weekday::Sat {
+        holidays.push(observed(ymd(1, 1)));
+    }
+    if year >= 2022 {
+        holidays.push(observed(ymd(6, 19)));
+    }
+    holidays.sort();
+    holidays
+}
+
+/// NYSE closes at 1 pm ET the day before Independence Day, the day after Thanksgiving and
+/// on Christmas Eve, when those are trading days
+pub fn nyse_early_closes(year: i32) -> Vec<NaiveDate> {
+    let ymd = |m, d| NaiveDate::from_ymd_opt(year, m, d).expect("valid date");
+    let candidates = [
+        ymd(7, 3),
+        nth_weekday(year, 11, Weekday::Thu, 4) + Duration::days(1),
+        ymd(12, 24),
+    ];
+    let holidays = nyse_holidays(year);
+    candidates
+        .into_iter()
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun) && !holidays.contains(d))
+        .collect()
+}
+
+fn is_weekday(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+pub fn is_trading_day(exchange: Exchange, date: NaiveDate) -> bool {
+    match exchange {
+        Exchange::Crypto => true,
+        Exchange::Nyse => is_weekday(date) && !nyse_holidays(date.year()).contains(&date),
+        Exchange::Cme => {
+            // Globex equity futures trade through most NYSE holidays on a shortened session,
+            // but close for Good Friday, Christmas and New Year's Day
+            let closed = [
+                easter(date.year()) - Duration::days(2),
+                observed(NaiveDate::from_ymd_opt(date.year(), 12, 25).expect("valid date")),
+                observed(NaiveDate::from_ymd_opt(date.year(), 1, 1).expect("valid date")),
+            ];
+            is_weekday(date) && !closed.contains(&date)
+        }
+    }
+}
+
+/// The session settling on `date`, if the exchange trades that day
+pub fn session_on(exchange: Exchange, date: NaiveDate) -> Option<MarketSession> {
+    if !is_trading_day(exchange, date) {
+        return None;
+    }
+    let (open, close, early_close) = match exchange {
+        Exchange::Crypto => {
+            let open = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).expect("midnight"));
+            (open, open + Duration::days(1), false)
+        }
+        Exchange::Nyse => {
+            let early = nyse_early_closes(date.year()).contains(&date);
+            (eastern(date, 9, 30), eastern(date, if early { 13 } else { 16 }, 0), early)
+        }
+        Exchange::Cme => {
+            // Opens 6 pm ET the previous calendar evening (Sunday for Monday's session)
+            let early = nyse_holidays(date.year()).contains(&date) || nyse_early_closes(date.year()).contains(&date);
+            let open = eastern(date - Duration::days(1), 18, 0);
+            (open, eastern(date, if early { 13 } else { 17 }, 0), early)
+        }
+    };
+    Some(MarketSession { exchange, date, open, close, early_close })
+}
+
+/// The next `count` sessions that haven't closed by `from`, starting with the current one
+pub fn upcoming_sessions(exchange: Exchange, from: DateTime<Utc>, count: usize) -> Vec<MarketSession> {
+    let mut sessions = Vec::with_capacity(count);
+    let mut date = (from - Duration::days(1)).date_naive();
+    while sessions.len() < count {
+        if let Some(session) = session_on(exchange, date).filter(|s| s.close > from) {
+            sessions.push(session);
+        }
+        date += Duration::days(1);
+    }
+    sessions
+}
+
+pub fn is_open(exchange: Exchange, at: DateTime<Utc>) -> bool {
+    upcoming_sessions(exchange, at, 1)
+        .first()
+        .is_some_and(|s| s.open <= at && at < s.close)
+}
+
+/// Open, or inside the US pre-market (from 4 am ET) or after-hours (to 8 pm ET) window
+pub fn is_active(exchange: Exchange, at: DateTime<Utc>) -> bool {
+    match exchange {
+        Exchange::Nyse => session_on(Exchange::Nyse, (at - Duration::hours(4)).date_naive())
+            .or_else(|| session_on(Exchange::Nyse, at.date_naive()))
+            .is_some_and(|s| eastern(s.date, 4, 0) <= at && at < eastern(s.date, 20, 0)),
+        _ => is_open(exchange, at),
+    }
+}
+
+pub fn schedule(exchange: Exchange, at: DateTime<Utc>, count: usize) -> ExchangeSchedule {
+    let sessions = upcoming_sessions(exchange, at, count);
+    ExchangeSchedule {
+        exchange,
+        is_open: is_open(exchange, at),
+        early_closes: sessions.iter().filter(|s| s.early_close).map(|s| s.date).collect(),
+        sessions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn test_nyse_holidays_and_early_closes() {
+        let holidays = nyse_holidays(2025);
+        for d in [date(2025, 1, 1), date(2025, 4, 18), date(2025, 6, 19), date(2025, 7, 4), date(2025, 11, 27), date(2025, 12, 25)] {
+            assert!(holidays.contains(&d), "{} should be a holiday", d);
+        }
+        assert_eq!(holidays.len(), 10);
+        assert_eq!(nyse_early_closes(2025), vec![date(2025, 7, 3), date(2025, 11, 28), date(2025, 12, 24)]);
+        // 2022: New Year's Day fell on a Saturday and was not observed
+        assert!(!nyse_holidays(2022).contains(&date(2021, 12, 31)));
+    }
+
+    #[test]
+    fn test_sessions_skip_weekends_and_holidays() {
+        // Thursday before Good Friday 2025, after the close
+        let at = Utc.with_ymd_and_hms(2025, 4, 17, 21, 0, 0).unwrap();
+        let sessions = upcoming_sessions(Exchange::Nyse, at, 2);
+        assert_eq!(sessions[0].date, date(2025, 4, 21));
+        assert_eq!(sessions[0].open, Utc.with_ymd_and_hms(2025, 4, 21, 13, 30, 0).unwrap());
+        assert_eq!(sessions[1].date, date(2025, 4, 22));
+        assert!(!is_open(Exchange::Nyse, at));
+        assert!(is_active(Exchange::Nyse, Utc.with_ymd_and_hms(2025, 4, 17, 23, 30, 0).unwrap()));
+        assert!(is_open(Exchange::Crypto, at));
+
+        let thanksgiving_friday = session_on(Exchange::Nyse, date(2025, 11, 28)).unwrap();
+        assert!(thanksgiving_friday.early_close);
+        assert_eq!(thanksgiving_friday.close, Utc.with_ymd_and_hms(2025, 11, 28, 18, 0, 0).unwrap());
+        assert_eq!(Exchange::for_symbol("btc-usd"), Exchange::Crypto);
+    }
+}
@@ -18,3 +18,46 @@ pub async fn get_indices(client: &MarketClient) -> Result<Vec<IndexItem>> {
     Ok(body)
 }
 
+
+/// Advance/decline counts of a basket on its last session, and the share trading above its
+/// 50-day moving average when every series has enough history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BreadthStats {
+    pub advancers: u32,
+    pub decliners: u32,
+    pub unchanged: u32,
+    pub pct_above_50dma: Option<f64>,
+}
+
+/// Breadth over daily close series, oldest first; series shorter than two closes are skipped
+pub fn breadth(series: &[Vec<f64>]) -> BreadthStats {
+    let mut stats = BreadthStats::default();
+    let mut above = 0u32;
+    let mut with_average = 0u32;
+
+    for closes in series.iter().filter(|c| c.len() >= 2) {
+        let last = closes[closes.len() - 1];
+        let previous = closes[closes.len() - 2];
+        if last > previous {
+            stats.advancers += 1;
+        } else if last < previous {
+            stats.decliners += 1;
+        } else {
+            stats.unchanged += 1;
+        }
+
+        if closes.len() >= 50 {
+            with_average += 1;
+            let average = closes[closes.len() - 50..].iter().sum::<f64>() / 50.0;
+            if last > average {
+                above += 1;
+            }
+        }
+    }
+
+    let counted = stats.advancers + stats.decliners + stats.unchanged;
+    if counted > 0 && with_average == counted {
+        stats.pct_above_50dma = Some(above as f64 / counted as f64 * 100.0);
+    }
+    stats
+}
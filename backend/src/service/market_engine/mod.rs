@@ -16,4 +16,7 @@ pub mod earnings_transcripts;
 pub mod earnings_calendar;
 pub mod holders;
 pub mod watchlist_price;
+pub mod briefing;
+pub mod earnings_events;
+pub mod options_chain;
 
@@ -1,7 +1,12 @@
 use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
 
 use super::client::MarketClient;
+use crate::service::cache_service::CacheService;
+use crate::turso::redis::ttl;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewsItem {
@@ -22,3 +27,244 @@ pub async fn get_news(client: &MarketClient, symbol: Option<&str>, limit: Option
     Ok(body)
 }
 
+/// Stories requested from the provider per symbol refresh
+const FEED_FETCH_LIMIT: u32 = 50;
+/// Word overlap above which two headlines count as the same story
+const TITLE_SIMILARITY_THRESHOLD: f64 = 0.8;
+
+/// A provider story with its timestamp resolved. The provider reports `time` as either an
+/// ISO timestamp or a relative "3 hours ago"; relative times are resolved against the fetch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedItem {
+    #[serde(flatten)]
+    pub item: NewsItem,
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolNewsFeed {
+    pub symbol: String,
+    pub fetched_at: DateTime<Utc>,
+    /// Newest first
+    pub items: Vec<FeedItem>,
+}
+
+/// One page of a feed; pass `next_since` back as `since` to get only newer stories
+#[derive(Debug, Clone, Serialize)]
+pub struct NewsPage {
+    pub symbol: String,
+    pub items: Vec<FeedItem>,
+    pub next_since: Option<DateTime<Utc>>,
+}
+
+pub fn news_cache_key(symbol: &str) -> String {
+    format!("market:news:{}", symbol.to_uppercase())
+}
+
+/// "2025-10-10T14:00:00Z", "45 minutes ago", "1 day ago", "yesterday"
+pub fn parse_published_at(time: &str, fetched_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let time = time.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(time) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    let lower = time.to_lowercase();
+    if lower == "just now" {
+        return Some(fetched_at);
+    }
+    if lower == "yesterday" {
+        return Some(fetched_at - Duration::days(1));
+    }
+    let mut words = lower.strip_suffix(" ago")?.split_whitespace();
+    let amount = match words.next()? {
+        "a" | "an" => 1,
+        n => n.parse::<i64>().ok()?,
+    };
+    let unit = words.next()?.trim_end_matches('s');
+    let delta = match unit {
+        "second" | "sec" => Duration::seconds(amount),
+        "minute" | "min" => Duration::minutes(amount),
+        "hour" | "hr" => Duration::hours(amount),
+        "day" => Duration::days(amount),
+        "week" => Duration::weeks(amount),
+        "month" => Duration::days(amount * 30),
+        "year" => Duration::days(amount * 365),
+        _ => return None,
+    };
+    Some(fetched_at - delta)
+}
+
+/// Link without scheme, `www.`, query string, fragment or trailing slash; syndicated copies
+/// of a story differ only in tracking parameters
+fn normalize_link(link: &str) -> String {
+    let link = link.trim().to_lowercase();
+    let link = link.split_once("://").map_or(link.as_str(), |(_, rest)| rest);
+    let link = link.strip_prefix("www.").unwrap_or(link);
+    let link = link.split(['?', '#']).next().unwrap_or(link);
+    link.trim_end_matches('/').to_string()
+}
+
+fn title_words(title: &str) -> HashSet<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Jaccard overlap of the headlines' words
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (title_words(a), title_words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Drop repeats of a story: the same link, or a near-identical headline. The earliest
+/// published copy is kept. Returns newest first.
+pub fn dedup_items(mut items: Vec<FeedItem>) -> Vec<FeedItem> {
+    // Oldest first so the original wins; undated stories go last
+    items.sort_by_key(|i| (i.published_at.is_none(), i.published_at));
+    let mut seen_links = HashSet::new();
+    let mut kept: Vec<FeedItem> = Vec::new();
+    for item in items {
+        if !seen_links.insert(normalize_link(&item.item.link)) {
+            continue;
+        }
+        if kept.iter().any(|k| title_similarity(&k.item.title, &item.item.title) >= TITLE_SIMILARITY_THRESHOLD) {
+            continue;
+        }
+        kept.push(item);
+    }
+    kept.sort_by_key(|n| Reverse(n.published_at));
+    kept
+}
+
+async fn fetch_symbol_feed(client: &MarketClient, symbol: &str) -> Result<SymbolNewsFeed> {
+    let fetched_at = Utc::now();
+    let items = get_news(client, Some(symbol), Some(FEED_FETCH_LIMIT))
+        .await?
+        .into_iter()
+        .map(|item| {
+            let published_at = parse_published_at(&item.time, fetched_at);
+            FeedItem { item, published_at }
+        })
+        .collect();
+    Ok(SymbolNewsFeed { symbol: symbol.to_uppercase(), fetched_at, items: dedup_items(items) })
+}
+
+/// Deduplicated feed for a symbol, cached so the dossier page and chat share one provider call
+pub async fn cached_symbol_feed(client: &MarketClient, cache: &CacheService, symbol: &str) -> Result<SymbolNewsFeed> {
+    let fetch = || fetch_symbol_feed(client, symbol);
+    cache.get_or_fetch(&news_cache_key(symbol), ttl::NEWS_FEED as u64, fetch).await
+}
+
+/// Stories published after `since` (all when None), newest first. Undated stories are
+/// only included on the first page, since they can't be placed against a cursor.
+pub fn page_feed(feed: &SymbolNewsFeed, since: Option<DateTime<Utc>>, limit: usize) -> NewsPage {
+    let items: Vec<FeedItem> = feed
+        .items
+        .iter()
+        .filter(|i| match (since, i.published_at) {
+            (None, _) => true,
+            (Some(since), Some(at)) => at > since,
+            (Some(_), None) => false,
+        })
+        .take(limit)
+        .cloned()
+        .collect();
+    let next_since = items.iter().filter_map(|i| i.published_at).max().or(since);
+    NewsPage { symbol: feed.symbol.clone(), items, next_since }
+}
+
+pub async fn symbol_news(
+    client: &MarketClient,
+    cache: &CacheService,
+    symbol: &str,
+    since: Option<DateTime<Utc>>,
+    limit: usize,
+) -> Result<NewsPage> {
+    let feed = cached_symbol_feed(client, cache, symbol).await?;
+    Ok(page_feed(&feed, since, limit))
+}
+
+const NEWS_CUES: &[&str] = &["news", "headline", "catalyst", "announce", "why did", "why is", "what happened", "gap"];
+
+/// Whether a chat message is asking about recent events around a ticker
+pub fn asks_about_news(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    NEWS_CUES.iter().any(|cue| lower.contains(cue))
+}
+
+/// Compact headline list for the chat prompt
+pub fn format_for_prompt(page: &NewsPage) -> String {
+    let lines: Vec<String> = page
+        .items
+        .iter()
+        .map(|i| {
+            let when = i.published_at.map_or(i.item.time.clone(), |at| at.format("%Y-%m-%d").to_string());
+            match &i.item.source {
+                Some(source) => format!("  - {} ({}, {})", i.item.title, source, when),
+                None => format!("  - {} ({})", i.item.title, when),
+            }
+        })
+        .collect();
+    format!("{}:\n{}", page.symbol, lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, link: &str, published_at: Option<&str>) -> FeedItem {
+        FeedItem {
+            item: NewsItem { symbol: Some("TSLA".to_string()), title: title.to_string(), link: link.to_string(), source: None, img: None, time: String::new() },
+            published_at: published_at.map(|t| DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc)),
+        }
+    }
+
+    #[test]
+    fn test_parse_published_at() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(parse_published_at("3 hours ago", now), Some(now - Duration::hours(3)));
+        assert_eq!(parse_published_at("an hour ago", now), Some(now - Duration::hours(1)));
+        assert_eq!(parse_published_at("1 day ago", now), Some(now - Duration::days(1)));
+        assert_eq!(parse_published_at("2026-10-15T09:30:00Z", now), Some(now - Duration::minutes(26 * 60 + 30)));
+        assert_eq!(parse_published_at("sometime", now), None);
+    }
+
+    #[test]
+    fn test_dedup_by_link_and_title() {
+        let items = vec![
+            item("Tesla recalls 2 million vehicles over Autopilot", "https://www.cnbc.com/tesla-recall?utm_source=snapi", Some("2026-10-16T10:00:00Z")),
+            item("Tesla recalls 2 million vehicles over Autopilot", "https://cnbc.com/tesla-recall/", Some("2026-10-16T11:00:00Z")),
+            item("Tesla recalls 2 million vehicles over Autopilot issue", "https://other.example/story", Some("2026-10-16T09:00:00Z")),
+            item("Tesla deliveries beat estimates", "https://example.com/deliveries", Some("2026-10-16T08:00:00Z")),
+        ];
+        let kept = dedup_items(items);
+        let links: Vec<&str> = kept.iter().map(|i| i.item.link.as_str()).collect();
+        assert_eq!(links, vec!["https://other.example/story", "https://example.com/deliveries"]);
+    }
+
+    #[test]
+    fn test_page_feed_since_cursor() {
+        let feed = SymbolNewsFeed {
+            symbol: "TSLA".to_string(),
+            fetched_at: Utc::now(),
+            items: vec![
+                item("Newest", "https://a.example/1", Some("2026-10-16T12:00:00Z")),
+                item("Older", "https://a.example/2", Some("2026-10-16T08:00:00Z")),
+                item("Undated", "https://a.example/3", None),
+            ],
+        };
+        let first = page_feed(&feed, None, 10);
+        assert_eq!(first.items.len(), 3);
+        let cursor = first.next_since;
+        assert_eq!(page_feed(&feed, cursor, 10).items.len(), 0);
+        let since = DateTime::parse_from_rfc3339("2026-10-16T09:00:00Z").unwrap().with_timezone(&Utc);
+        let page = page_feed(&feed, Some(since), 10);
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.next_since, cursor);
+    }
+}
@@ -0,0 +1,322 @@
+// Options chain for an underlying with Black-Scholes Greeks per contract and an IV rank.
+// The upstream has no implied volatility history, so IV rank and percentile compare the
+// at-the-money IV against the last year of 20-day realized volatility: rank is where it
+// sits in that range, percentile the share of days realized vol was below it.
+
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::client::MarketClient;
+use super::historical;
+use super::quotes;
+use crate::service::cache_service::CacheService;
+use crate::service::stress_test::{greeks, volatility_fraction};
+use crate::turso::redis::ttl;
+
+/// Daily returns in each realized volatility window
+const REALIZED_VOL_WINDOW: usize = 20;
+/// Realized volatility observations needed before an IV rank is reported
+const MIN_VOL_HISTORY: usize = 60;
+const TRADING_DAYS: f64 = 252.0;
+
+pub fn chain_cache_key(symbol: &str, expiration: Option<&str>) -> String {
+    format!("market:options_chain:{}:{}", symbol.to_uppercase(), expiration.unwrap_or("nearest"))
+}
+
+/// Upstream numbers arrive as numbers or display strings ("1,234", "32.10%", "-")
+fn lenient_number<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<f64>, D::Error> {
+    Ok(match Option::<serde_json::Value>::deserialize(deserializer)? {
+        Some(serde_json::Value::Number(n)) => n.as_f64(),
+        Some(serde_json::Value::String(s)) => {
+            let percent = s.trim_end().ends_with('%');
+            s.replace([',', '$', '%'], "").trim().parse::<f64>().ok().map(|v| if percent { v / 100.0 } else { v })
+        }
+        _ => None,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UpstreamContract {
+    #[serde(alias = "contractSymbol")]
+    contract_symbol: Option<String>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    strike: Option<f64>,
+    #[serde(default, alias = "lastPrice", deserialize_with = "lenient_number")]
+    last_price: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    bid: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    ask: Option<f64>,
+    #[serde(default, deserialize_with = "lenient_number")]
+    volume: Option<f64>,
+    #[serde(default, alias = "openInterest", deserialize_with = "lenient_number")]
+    open_interest: Option<f64>,
+    #[serde(default, alias = "impliedVolatility", deserialize_with = "lenient_number")]
+    implied_volatility: Option<f64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct UpstreamChain {
+    #[serde(default, alias = "expirationDates")]
+    expirations: Vec<String>,
+    #[serde(default)]
+    expiration: Option<String>,
+    #[serde(default)]
+    calls: Vec<UpstreamContract>,
+    #[serde(default)]
+    puts: Vec<UpstreamContract>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainContract {
+    pub contract_symbol: Option<String>,
+    /// `call` or `put`
+    pub option_type: String,
+    pub strike: f64,
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub last_price: Option<f64>,
+    pub volume: Option<f64>,
+    pub open_interest: Option<f64>,
+    /// Fraction, e.g. 0.32
+    pub implied_volatility: Option<f64>,
+    pub delta: Option<f64>,
+    pub gamma: Option<f64>,
+    /// Per calendar day, per share
+    pub theta: Option<f64>,
+    /// Per 1 volatility point, per share
+    pub vega: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IvStats {
+    /// Fraction, averaged over the call and put nearest the money
+    pub atm_iv: Option<f64>,
+    /// 0-100 within the year's realized volatility range
+    pub iv_rank: Option<f64>,
+    /// 0-100, share of days realized volatility was below the current IV
+    pub iv_percentile: Option<f64>,
+    pub realized_vol_low: Option<f64>,
+    pub realized_vol_high: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionsChain {
+    pub symbol: String,
+    pub underlying_price: Option<f64>,
+    pub expiration: Option<String>,
+    pub expirations: Vec<String>,
+    pub contracts: Vec<ChainContract>,
+    pub iv: IvStats,
+    pub generated_at: String,
+}
+
+fn years_until(expiration: NaiveDate, today: NaiveDate) -> f64 {
+    // Expiring today still has a few hours left
+    ((expiration - today).num_days() as f64).max(0.5) / 365.0
+}
+
+fn to_contract(raw: UpstreamContract, call: bool, spot: Option<f64>, years: Option<f64>) -> Option<ChainContract> {
+    let strike = raw.strike.filter(|s| *s > 0.0)?;
+    let iv = raw.implied_volatility.filter(|v| *v > 0.0).map(volatility_fraction);
+    let g = match (spot, years, iv) {
+        (Some(spot), Some(years), Some(iv)) => greeks(call, spot, strike, years, iv),
+        _ => None,
+    };
+    Some(ChainContract {
+        contract_symbol: raw.contract_symbol,
+        option_type: if call { "call" } else { "put" }.to_string(),
+        strike,
+        bid: raw.bid,
+        ask: raw.ask,
+        last_price: raw.last_price,
+        volume: raw.volume,
+        open_interest: raw.open_interest,
+        implied_volatility: iv,
+        delta: g.map(|g| g.delta),
+        gamma: g.map(|g| g.gamma),
+        theta: g.map(|g| g.theta),
+        vega: g.map(|g| g.vega / 100.0),
+    })
+}
+
+/// IV of the strike nearest the underlying, averaging the call and put when both quote one
+pub fn atm_iv(contracts: &[ChainContract], spot: f64) -> Option<f64> {
+    let nearest = contracts
+        .iter()
+        .filter(|c| c.implied_volatility.is_some())
+        .min_by(|a, b| (a.strike - spot).abs().total_cmp(&(b.strike - spot).abs()))?
+        .strike;
+    let ivs: Vec<f64> = contracts.iter().filter(|c| c.strike == nearest).filter_map(|c| c.implied_volatility).collect();
+    (!ivs.is_empty()).then(|| ivs.iter().sum::<f64>() / ivs.len() as f64)
+}
+
+/// Annualized realized volatility over each rolling window of daily closes
+pub fn rolling_realized_vol(closes: &[f64], window: usize) -> Vec<f64> {
+    let returns: Vec<f64> = closes.windows(2).filter(|w| w[0] > 0.0 && w[1] > 0.0).map(|w| (w[1] / w[0]).ln()).collect();
+    returns
+        .windows(window)
+        .map(|w| {
+            let mean = w.iter().sum::<f64>() / w.len() as f64;
+            let variance = w.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (w.len() - 1) as f64;
+            (variance * TRADING_DAYS).sqrt()
+        })
+        .collect()
+}
+
+/// Rank and percentile of the current IV against a realized volatility history
+pub fn iv_stats(atm_iv: Option<f64>, realized: &[f64]) -> IvStats {
+    let mut stats = IvStats { atm_iv, ..IvStats::default() };
+    if realized.len() < MIN_VOL_HISTORY {
+        return stats;
+    }
+    let low = realized.iter().copied().fold(f64::INFINITY, f64::min);
+    let high = realized.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    stats.realized_vol_low = Some(low);
+    stats.realized_vol_high = Some(high);
+    if let Some(iv) = atm_iv {
+        if high > low {
+            stats.iv_rank = Some(((iv - low) / (high - low) * 100.0).clamp(0.0, 100.0));
+        }
+        stats.iv_percentile = Some(realized.iter().filter(|v| **v < iv).count() as f64 / realized.len() as f64 * 100.0);
+    }
+    stats
+}
+
+/// Chain for one expiration (the nearest when none is given) with Greeks and IV rank
+pub async fn get_options_chain(client: &MarketClient, symbol: &str, expiration: Option<&str>) -> Result<OptionsChain> {
+    let symbol = symbol.trim().to_uppercase();
+    let mut params: Vec<(&str, String)> = vec![("symbol", symbol.clone())];
+    if let Some(exp) = expiration {
+        params.push(("expiration", exp.to_string()));
+    }
+    let resp = client.get("/v1/options", Some(&params)).await?;
+    let upstream = resp.json::<UpstreamChain>().await?;
+
+    let spot = match quotes::get_quotes(client, std::slice::from_ref(&symbol)).await {
+        Ok(quotes) => quotes
+            .into_iter()
+            .next()
+            .and_then(|q| q.price)
+            .and_then(|p| p.replace([',', '$'], "").trim().parse::<f64>().ok()),
+        Err(e) => {
+            log::warn!("Failed to load quote for {} options chain: {}", symbol, e);
+            None
+        }
+    };
+
+    let chosen = expiration.map(str::to_string).or(upstream.expiration).or_else(|| upstream.expirations.first().cloned());
+    let years = chosen
+        .as_deref()
+        .and_then(|e| NaiveDate::parse_from_str(e.get(..10).unwrap_or(e), "%Y-%m-%d").ok())
+        .map(|e| years_until(e, Utc::now().date_naive()));
+
+    let mut contracts: Vec<ChainContract> = upstream
+        .calls
+        .into_iter()
+        .filter_map(|c| to_contract(c, true, spot, years))
+        .chain(upstream.puts.into_iter().filter_map(|p| to_contract(p, false, spot, years)))
+        .collect();
+    if contracts.is_empty() {
+        return Err(anyhow!("No option contracts for {}", symbol));
+    }
+    contracts.sort_by(|a, b| a.strike.total_cmp(&b.strike).then_with(|| a.option_type.cmp(&b.option_type)));
+
+    let atm = spot.and_then(|spot| atm_iv(&contracts, spot));
+    let realized = match historical::get_historical(client, &symbol, Some("1y"), Some("1d")).await {
+        Ok(history) => rolling_realized_vol(&history.candles.iter().map(|c| c.close).collect::<Vec<_>>(), REALIZED_VOL_WINDOW),
+        Err(e) => {
+            log::warn!("Failed to load price history for {} IV rank: {}", symbol, e);
+            Vec::new()
+        }
+    };
+
+    Ok(OptionsChain {
+        symbol,
+        underlying_price: spot,
+        expiration: chosen,
+        expirations: upstream.expirations,
+        contracts,
+        iv: iv_stats(atm, &realized),
+        generated_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// `get_options_chain` through the shared market cache; quotes move, so entries are short-lived
+pub async fn cached_options_chain(client: &MarketClient, cache: &CacheService, symbol: &str, expiration: Option<&str>) -> Result<OptionsChain> {
+    let key = chain_cache_key(symbol, expiration);
+    let fetch = || get_options_chain(client, symbol, expiration);
+    cache.get_or_fetch(&key, ttl::OPTIONS_CHAIN as u64, fetch).await
+}
+
+/// Current implied volatility (fraction) of one contract, used to fill it in on new trades
+pub async fn contract_iv(
+    client: &MarketClient,
+    cache: &CacheService,
+    symbol: &str,
+    expiration: NaiveDate,
+    strike: f64,
+    call: bool,
+) -> Result<Option<f64>> {
+    let expiration = expiration.format("%Y-%m-%d").to_string();
+    let chain = cached_options_chain(client, cache, symbol, Some(&expiration)).await?;
+    let option_type = if call { "call" } else { "put" };
+    Ok(chain
+        .contracts
+        .iter()
+        .find(|c| c.option_type == option_type && (c.strike - strike).abs() < 0.005)
+        .and_then(|c| c.implied_volatility))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(option_type: &str, strike: f64, iv: Option<f64>) -> ChainContract {
+        ChainContract {
+            contract_symbol: None,
+            option_type: option_type.to_string(),
+            strike,
+            bid: None,
+            ask: None,
+            last_price: None,
+            volume: None,
+            open_interest: None,
+            implied_volatility: iv,
+            delta: None,
+            gamma: None,
+            theta: None,
+            vega: None,
+        }
+    }
+
+    #[test]
+    fn test_atm_iv_and_upstream_parsing() {
+        let contracts = vec![
+            contract("call", 95.0, Some(0.40)),
+            contract("call", 100.0, Some(0.30)),
+            contract("put", 100.0, Some(0.34)),
+            contract("put", 105.0, None),
+        ];
+        assert!((atm_iv(&contracts, 101.0).unwrap() - 0.32).abs() < 1e-12);
+
+        let raw: UpstreamContract =
+            serde_json::from_str(r#"{"contractSymbol":"AAPL250117C00200000","strike":"1,200","impliedVolatility":"32.50%","bid":"-"}"#).unwrap();
+        assert_eq!(raw.strike, Some(1200.0));
+        assert_eq!(raw.implied_volatility, Some(0.325));
+        assert_eq!(raw.bid, None);
+    }
+
+    #[test]
+    fn test_iv_stats() {
+        let realized: Vec<f64> = (0..100).map(|i| 0.10 + i as f64 * 0.002).collect();
+        let stats = iv_stats(Some(0.20), &realized);
+        assert!((stats.iv_rank.unwrap() - 50.5050).abs() < 1e-3);
+        assert_eq!(stats.iv_percentile, Some(50.0));
+
+        // Too little history leaves rank and percentile empty
+        assert!(iv_stats(Some(0.20), &realized[..10]).iv_rank.is_none());
+        assert_eq!(rolling_realized_vol(&[100.0; 30], 20), vec![0.0; 10]);
+    }
+}
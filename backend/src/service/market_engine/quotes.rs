@@ -1,7 +1,10 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use super::client::MarketClient;
+use crate::service::cache_service::CacheService;
+use crate::turso::redis::ttl;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Quote {
@@ -114,3 +117,144 @@ pub async fn get_logo(client: &MarketClient, symbol: &str) -> Result<Option<Stri
     Ok(logo)
 }
 
+
+/// Most symbols accepted by one batch request
+pub const MAX_BATCH_SYMBOLS: usize = 100;
+
+pub fn quote_snapshot_cache_key(symbol: &str) -> String {
+    format!("market:quote:{}", symbol)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchQuoteStatus {
+    Ok,
+    /// The provider returned nothing for the symbol
+    NotFound,
+    InvalidSymbol,
+    /// The upstream call failed; other symbols may still have come from cache
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchQuote {
+    pub symbol: String,
+    pub status: BatchQuoteStatus,
+    pub quote: Option<SimpleQuote>,
+    pub cached: bool,
+    pub error: Option<String>,
+}
+
+impl BatchQuote {
+    fn failed(symbol: &str, status: BatchQuoteStatus, error: Option<String>) -> Self {
+        Self { symbol: symbol.to_string(), status, quote: None, cached: false, error }
+    }
+}
+
+fn is_valid_symbol(symbol: &str) -> bool {
+    !symbol.is_empty()
+        && symbol.len() <= 12
+        && symbol.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '^' | '=' | '/'))
+}
+
+/// Trimmed, upper-cased symbols in request order with repeats removed
+pub fn normalize_symbols(symbols: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for symbol in symbols {
+        let symbol = symbol.trim().to_uppercase();
+        if !normalized.contains(&symbol) {
+            normalized.push(symbol);
+        }
+    }
+    normalized
+}
+
+/// One result per requested symbol, in request order
+fn merge_batch(
+    symbols: &[String],
+    cached: &HashMap<String, SimpleQuote>,
+    fetched: &Result<HashMap<String, SimpleQuote>, String>,
+) -> Vec<BatchQuote> {
+    symbols
+        .iter()
+        .map(|symbol| {
+            if !is_valid_symbol(symbol) {
+                return BatchQuote::failed(symbol, BatchQuoteStatus::InvalidSymbol, None);
+            }
+            if let Some(quote) = cached.get(symbol) {
+                return BatchQuote { symbol: symbol.clone(), status: BatchQuoteStatus::Ok, quote: Some(quote.clone()), cached: true, error: None };
+            }
+            match fetched {
+                Ok(quotes) => match quotes.get(symbol) {
+                    Some(quote) => BatchQuote { symbol: symbol.clone(), status: BatchQuoteStatus::Ok, quote: Some(quote.clone()), cached: false, error: None },
+                    None => BatchQuote::failed(symbol, BatchQuoteStatus::NotFound, None),
+                },
+                Err(e) => BatchQuote::failed(symbol, BatchQuoteStatus::Error, Some(e.clone())),
+            }
+        })
+        .collect()
+}
+
+/// Quote snapshots for up to [`MAX_BATCH_SYMBOLS`] symbols: one cache read for all of them,
+/// then one upstream call for the misses. Cache failures fall through to the provider;
+/// provider failures only affect the symbols that weren't cached.
+pub async fn batch_quotes(client: &MarketClient, cache: &CacheService, symbols: &[String]) -> Vec<BatchQuote> {
+    let valid: Vec<String> = symbols.iter().filter(|s| is_valid_symbol(s)).cloned().collect();
+    let keys: Vec<String> = valid.iter().map(|s| quote_snapshot_cache_key(s)).collect();
+    let cached: HashMap<String, SimpleQuote> = match cache.get_many::<SimpleQuote>(&keys).await {
+        Ok(values) => valid.iter().cloned().zip(values).filter_map(|(s, q)| q.map(|q| (s, q))).collect(),
+        Err(e) => {
+            log::warn!("Quote cache read failed, fetching all {} symbols: {}", valid.len(), e);
+            HashMap::new()
+        }
+    };
+
+    let misses: Vec<String> = valid.into_iter().filter(|s| !cached.contains_key(s)).collect();
+    let fetched = if misses.is_empty() {
+        Ok(HashMap::new())
+    } else {
+        match get_simple_quotes(client, &misses).await {
+            Ok(quotes) => {
+                let by_symbol: HashMap<String, SimpleQuote> = quotes.into_iter().map(|q| (q.symbol.to_uppercase(), q)).collect();
+                let entries: Vec<(String, SimpleQuote)> = by_symbol.iter().map(|(s, q)| (quote_snapshot_cache_key(s), q.clone())).collect();
+                if let Err(e) = cache.set_many(&entries, ttl::QUOTE_SNAPSHOT as u64).await {
+                    log::warn!("Failed to cache {} quote snapshots: {}", entries.len(), e);
+                }
+                Ok(by_symbol)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    };
+    merge_batch(symbols, &cached, &fetched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, price: &str) -> SimpleQuote {
+        SimpleQuote { symbol: symbol.to_string(), name: None, price: Some(price.to_string()), after_hours_price: None, change: None, percent_change: None, logo: None }
+    }
+
+    #[test]
+    fn test_normalize_symbols() {
+        let raw = vec![" aapl ".to_string(), "AAPL".to_string(), "brk.b".to_string()];
+        assert_eq!(normalize_symbols(&raw), vec!["AAPL".to_string(), "BRK.B".to_string()]);
+    }
+
+    #[test]
+    fn test_merge_batch_partial_results() {
+        let symbols: Vec<String> = ["AAPL", "MSFT", "ZZZZ", "BAD SYMBOL"].iter().map(|s| s.to_string()).collect();
+        let cached = HashMap::from([("AAPL".to_string(), quote("AAPL", "190.00"))]);
+        let fetched = Ok(HashMap::from([("MSFT".to_string(), quote("MSFT", "410.00"))]));
+        let results = merge_batch(&symbols, &cached, &fetched);
+        let statuses: Vec<BatchQuoteStatus> = results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![BatchQuoteStatus::Ok, BatchQuoteStatus::Ok, BatchQuoteStatus::NotFound, BatchQuoteStatus::InvalidSymbol]);
+        assert!(results[0].cached && !results[1].cached);
+
+        let failed = merge_batch(&symbols, &cached, &Err("timeout".to_string()));
+        assert_eq!(failed[0].status, BatchQuoteStatus::Ok);
+        assert_eq!(failed[1].status, BatchQuoteStatus::Error);
+        assert_eq!(failed[1].error.as_deref(), Some("timeout"));
+    }
+}
@@ -1,7 +1,16 @@
 use anyhow::Result;
+use log::warn;
 use serde::{Deserialize, Serialize};
 
 use super::client::MarketClient;
+use crate::service::cache_service::CacheService;
+use crate::turso::redis::ttl;
+
+/// Candidates requested from the provider; ranking then trims to the caller's `hits`
+const PROVIDER_HITS: u32 = 25;
+
+/// Typos tolerated when matching a symbol or a word of the company name
+const MAX_EDIT_DISTANCE: usize = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchItem {
@@ -19,17 +28,162 @@ pub async fn search(
     yahoo: Option<bool>,
 ) -> Result<Vec<SearchItem>> {
     let mut params: Vec<(&str, String)> = vec![("query", query.to_string())];
-    
+
     // Default hits to 10 if not specified
     let hits_value = hits.unwrap_or(10);
     params.push(("hits", hits_value.to_string()));
-    
+
     // Default yahoo to true if not specified
     let yahoo_value = yahoo.unwrap_or(true);
     params.push(("yahoo", yahoo_value.to_string()));
-    
+
     let resp = client.get("/v1/search", Some(&params)).await?;
     let body = resp.json::<Vec<SearchItem>>().await?;
     Ok(body)
 }
 
+/// Autocomplete search: provider results cached per query, then ranked by how well the
+/// symbol or company name matches (exact, prefix, substring, within a typo or two) and by
+/// listing popularity.
+pub async fn ranked_search(
+    client: &MarketClient,
+    cache: &CacheService,
+    query: &str,
+    hits: Option<u32>,
+    yahoo: Option<bool>,
+) -> Result<Vec<SearchItem>> {
+    let normalized = query.trim().to_lowercase();
+    if normalized.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let cache_key = format!("market:search:{}:{}", yahoo.unwrap_or(true), cache_key_part(&normalized));
+    let fetch = || search(client, &normalized, Some(PROVIDER_HITS), yahoo);
+    let candidates = match cache.get_or_fetch(&cache_key, ttl::MARKET_SEARCH as u64, fetch).await {
+        Ok(items) => items,
+        Err(e) => {
+            warn!("Symbol search cache unavailable for '{}': {}", normalized, e);
+            search(client, &normalized, Some(PROVIDER_HITS), yahoo).await?
+        }
+    };
+
+    Ok(rank(&normalized, candidates, hits.unwrap_or(10) as usize))
+}
+
+/// Upstash takes keys in the URL path, so keep them to URL-safe characters
+fn cache_key_part(query: &str) -> String {
+    query
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Order candidates best match first; ties keep the provider's order, which already
+/// reflects search popularity
+fn rank(query: &str, candidates: Vec<SearchItem>, limit: usize) -> Vec<SearchItem> {
+    let mut scored: Vec<(u32, usize, SearchItem)> = candidates
+        .into_iter()
+        .enumerate()
+        .map(|(position, item)| (match_score(query, &item) + popularity_score(&item), position, item))
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored.into_iter().take(limit).map(|(_, _, item)| item).collect()
+}
+
+fn match_score(query: &str, item: &SearchItem) -> u32 {
+    let symbol = item.symbol.to_lowercase();
+    let name = item.name.as_deref().unwrap_or("").to_lowercase();
+
+    if symbol == query {
+        return 1000;
+    }
+    if symbol.starts_with(query) {
+        return 800;
+    }
+    if name.starts_with(query) {
+        return 600;
+    }
+    if name.split_whitespace().any(|word| word.starts_with(query)) {
+        return 500;
+    }
+    if name.contains(query) {
+        return 400;
+    }
+
+    let closest = std::iter::once(symbol.as_str())
+        .chain(name.split_whitespace())
+        .map(|word| edit_distance(query, word))
+        .min()
+        .unwrap_or(usize::MAX);
+    match closest {
+        d if d <= MAX_EDIT_DISTANCE && d < query.chars().count() => 300 - 100 * d as u32,
+        _ => 0,
+    }
+}
+
+/// Common stock and ETFs on the major US venues first
+fn popularity_score(item: &SearchItem) -> u32 {
+    let kind = match item.kind.as_deref().map(str::to_uppercase).as_deref() {
+        Some("EQUITY") => 30,
+        Some("ETF") => 20,
+        _ => 0,
+    };
+    let exchange = match item.exchange.as_deref().map(str::to_uppercase).as_deref() {
+        Some("NMS" | "NYQ" | "NASDAQ" | "NYSE" | "PCX" | "NYSEARCA") => 20,
+        _ => 0,
+    };
+    kind + exchange
+}
+
+/// Levenshtein distance over chars
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(symbol: &str, name: &str, kind: &str, exchange: &str) -> SearchItem {
+        SearchItem {
+            symbol: symbol.to_string(),
+            name: Some(name.to_string()),
+            kind: Some(kind.to_string()),
+            exchange: Some(exchange.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_rank_prefers_exact_symbol_then_prefix_then_name() {
+        let candidates = vec![
+            item("AAPL", "Apple Inc.", "EQUITY", "NMS"),
+            item("APLE", "Apple Hospitality REIT", "EQUITY", "NYQ"),
+            item("AAPL.MX", "Apple Inc.", "EQUITY", "MEX"),
+        ];
+        let symbols: Vec<String> = rank("aapl", candidates.clone(), 10).into_iter().map(|i| i.symbol).collect();
+        assert_eq!(symbols, ["AAPL", "AAPL.MX", "APLE"]);
+
+        let by_name: Vec<String> = rank("apple", candidates, 2).into_iter().map(|i| i.symbol).collect();
+        assert_eq!(by_name, ["AAPL", "APLE"]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_tolerates_typos() {
+        assert_eq!(edit_distance("tesla", "tesla"), 0);
+        assert_eq!(edit_distance("telsa", "tesla"), 2);
+        assert_eq!(edit_distance("nvdia", "nvidia"), 1);
+        assert!(match_score("nvdia", &item("NVDA", "NVIDIA Corporation", "EQUITY", "NMS")) > 0);
+        assert_eq!(match_score("xyzq", &item("NVDA", "NVIDIA Corporation", "EQUITY", "NMS")), 0);
+        assert_eq!(cache_key_part("berkshire b/c"), "berkshire_b_c");
+    }
+}
@@ -1,7 +1,31 @@
 use anyhow::Result;
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 
 use super::client::MarketClient;
+use super::historical::get_historical;
+use super::indices::{self, BreadthStats, IndexItem};
+
+/// SPDR sector ETFs standing in for each sector in relative-strength calculations
+pub const SECTOR_ETFS: [(&str, &str); 11] = [
+    ("Technology", "XLK"),
+    ("Financial Services", "XLF"),
+    ("Energy", "XLE"),
+    ("Healthcare", "XLV"),
+    ("Industrials", "XLI"),
+    ("Consumer Cyclical", "XLY"),
+    ("Consumer Defensive", "XLP"),
+    ("Utilities", "XLU"),
+    ("Basic Materials", "XLB"),
+    ("Real Estate", "XLRE"),
+    ("Communication Services", "XLC"),
+];
+
+/// Benchmark the sectors are measured against
+pub const BENCHMARK_SYMBOL: &str = "SPY";
+
+/// Lookbacks in trading sessions: one week, one month, three months
+const RS_LOOKBACKS: [usize; 3] = [5, 21, 63];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SectorPerformanceItem {
@@ -24,3 +48,100 @@ pub async fn get_sectors(client: &MarketClient) -> Result<Vec<SectorPerformanceI
     Ok(body)
 }
 
+
+/// Sector return minus benchmark return over the lookbacks, in percentage points.
+/// Positive means the sector led the market.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorRelativeStrength {
+    pub sector: String,
+    pub symbol: String,
+    pub rs_1w: Option<f64>,
+    pub rs_1m: Option<f64>,
+    pub rs_3m: Option<f64>,
+    /// 1-week relative strength ahead of 1-month: money rotating into the sector
+    pub improving: Option<bool>,
+}
+
+/// Everything the pre-market routine page shows about sectors and indices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectorDashboard {
+    pub sectors: Vec<SectorPerformanceItem>,
+    pub indices: Vec<IndexItem>,
+    pub relative_strength: Vec<SectorRelativeStrength>,
+    pub breadth: BreadthStats,
+    pub generated_at: String,
+}
+
+/// Percent change over the last `lookback` sessions of a close series
+fn period_return(closes: &[f64], lookback: usize) -> Option<f64> {
+    if closes.len() <= lookback {
+        return None;
+    }
+    let last = closes[closes.len() - 1];
+    let base = closes[closes.len() - 1 - lookback];
+    (base > 0.0).then(|| (last / base - 1.0) * 100.0)
+}
+
+pub fn relative_strength(closes: &[f64], benchmark: &[f64], lookback: usize) -> Option<f64> {
+    Some(period_return(closes, lookback)? - period_return(benchmark, lookback)?)
+}
+
+/// Daily closes for the last three months, oldest first; empty when the provider fails
+async fn daily_closes(client: &MarketClient, symbol: &str) -> Vec<f64> {
+    match get_historical(client, symbol, Some("3mo"), Some("1d")).await {
+        Ok(history) => history.candles.iter().map(|c| c.adj_close.unwrap_or(c.close)).collect(),
+        Err(e) => {
+            log::warn!("No daily history for {}: {}", symbol, e);
+            Vec::new()
+        }
+    }
+}
+
+pub async fn get_sector_dashboard(client: &MarketClient) -> Result<SectorDashboard> {
+    let symbols: Vec<&str> = std::iter::once(BENCHMARK_SYMBOL)
+        .chain(SECTOR_ETFS.iter().map(|(_, symbol)| *symbol))
+        .collect();
+    let histories = join_all(symbols.iter().map(|symbol| daily_closes(client, symbol))).await;
+    let (benchmark, sector_closes) = histories.split_first().expect("benchmark is always requested");
+
+    let relative_strength = SECTOR_ETFS
+        .iter()
+        .zip(sector_closes)
+        .map(|((sector, symbol), closes)| {
+            let [rs_1w, rs_1m, rs_3m] = RS_LOOKBACKS.map(|n| relative_strength(closes, benchmark, n));
+            SectorRelativeStrength {
+                sector: sector.to_string(),
+                symbol: symbol.to_string(),
+                rs_1w,
+                rs_1m,
+                rs_3m,
+                improving: rs_1w.zip(rs_1m).map(|(week, month)| week > month),
+            }
+        })
+        .collect();
+
+    let (sectors, indices) = futures_util::join!(get_sectors(client), indices::get_indices(client));
+
+    Ok(SectorDashboard {
+        sectors: sectors?,
+        indices: indices?,
+        relative_strength,
+        breadth: indices::breadth(sector_closes),
+        generated_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_strength_against_benchmark() {
+        let sector = [100.0, 102.0, 104.0, 110.0];
+        let benchmark = [200.0, 201.0, 202.0, 204.0];
+        let rs = relative_strength(&sector, &benchmark, 3).unwrap();
+        assert!((rs - 8.0).abs() < 1e-9);
+        assert_eq!(relative_strength(&sector, &benchmark, 4), None);
+        assert_eq!(relative_strength(&sector, &[], 1), None);
+    }
+}
@@ -218,7 +218,7 @@ impl MarketWsProxy {
                             );
                             
                             for user_id in user_ids {
-                                manager.broadcast_to_user(&user_id, message.clone());
+                                manager.broadcast_to_local_user(&user_id, message.clone());
                             }
                         }
                     }
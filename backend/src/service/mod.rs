@@ -1,15 +1,54 @@
 pub mod analytics_engine;
 pub mod image_upload;
 pub mod calendar_service;
+pub mod agenda;
 pub mod holidays_service;
 pub mod cache_service;
 pub mod trade_notes_service;
 pub mod rate_limiter;
+pub mod concurrency_limiter;
 pub mod storage_quota;
 pub mod account_deletion;
-pub mod transform;
+pub mod secrets_store;
+pub mod note_encryption;
+pub mod retention;
+pub mod i18n;
+pub mod cron_expression;
+pub mod task_monitor;
+pub mod tenant_cleanup;
+pub mod image_migration;
+pub mod dashboard;
+pub mod goal_pacing;
+pub mod automations;
+pub mod trade_archive;
+pub mod bulk_operations;
+pub mod csv_import;
+pub mod trade_links;
+pub mod trade_enrichment;
+pub mod demo_data;
+pub mod onboarding;
+pub mod data_quality;
+pub mod activity;
+pub mod trade_merge;
+pub mod sandbox;
+pub mod option_strategies;
+pub mod playbook_targets;
+pub mod statements;
+pub mod command_palette;
+pub mod focus_sessions;
+pub mod organizations;
+pub mod organization_library;
+pub mod stress_test;
+pub mod margin;
+pub mod cost_ledger;
+pub mod telemetry;
+pub mod symbol_journal;
+pub mod year_review;
 
 // AI Services - organized in dedicated module
 pub mod ai_service;
 pub mod market_engine;
 pub mod notifications;
+pub mod brokerage;
+pub mod integrations;
+pub mod telegram_bot;
@@ -0,0 +1,237 @@
+// Optional at-rest encryption of journal prose: `trade_notes.content` and
+// `notebook_notes.content`. Each user who opts in gets a random 256-bit data key, stored
+// in their own database wrapped by the server master key (SECRETS_ENCRYPTION_KEY), so a
+// copy of the database alone doesn't reveal what they wrote.
+//
+// Sealed values carry a version prefix, which lets plaintext and sealed rows coexist: rows
+// written before encryption was switched on keep reading, and enabling or disabling
+// rewrites existing notes in place.
+//
+// Search: SQL can't look inside sealed content, so while encryption is on, note search
+// matches names and titles only. `NoteEncryptionStatus::content_search` reports this.
+
+use std::sync::OnceLock;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use base64::Engine;
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::service::secrets_store::SecretsStore;
+
+const SEALED_PREFIX: &str = "enc:v1:";
+const KEY_ROW: &str = "default";
+/// Tables whose `content` column is sealed, each keyed by a TEXT `id`
+const SEALED_TABLES: [&str; 2] = ["trade_notes", "notebook_notes"];
+
+static MASTER_KEY: OnceLock<SecretsStore> = OnceLock::new();
+
+/// Install the master key used to wrap per-user data keys; called once at startup
+pub fn install_master_key(key_b64: Option<&str>) {
+    match key_b64.map(SecretsStore::new) {
+        Some(Ok(store)) => {
+            let _ = MASTER_KEY.set(store);
+        }
+        Some(Err(e)) => log::error!("Note encryption unavailable: {}", e),
+        None => log::info!("SECRETS_ENCRYPTION_KEY not set; note encryption unavailable"),
+    }
+}
+
+fn master_key() -> Result<&'static SecretsStore> {
+    MASTER_KEY.get().ok_or_else(|| anyhow::anyhow!("Note encryption is not configured on this server"))
+}
+
+/// Whether a stored value was produced by `NoteCipher::seal`
+pub fn is_sealed(value: &str) -> bool {
+    value.starts_with(SEALED_PREFIX)
+}
+
+/// A user's unwrapped data key
+#[derive(Clone)]
+pub struct NoteCipher {
+    cipher: Aes256Gcm,
+}
+
+impl NoteCipher {
+    fn from_key(key: &[u8]) -> Self {
+        Self { cipher: Aes256Gcm::new_from_slice(key).expect("note data keys are 32 bytes") }
+    }
+
+    /// Load the data key for the user owning `conn`; None when they haven't opted in
+    pub async fn for_connection(conn: &Connection) -> Result<Option<Self>> {
+        let mut rows = conn
+            .prepare("SELECT wrapped_key, nonce FROM note_encryption_key WHERE id = ?")
+            .await?
+            .query(params![KEY_ROW])
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+        let (wrapped, nonce): (String, String) = (row.get(0)?, row.get(1)?);
+        let key = base64::engine::general_purpose::STANDARD
+            .decode(master_key()?.decrypt(&wrapped, &nonce)?)
+            .context("Invalid note data key")?;
+        if key.len() != 32 {
+            anyhow::bail!("Note data key must be 32 bytes, got {}", key.len());
+        }
+        Ok(Some(Self::from_key(&key)))
+    }
+
+    /// Seal a value as `enc:v1:<nonce>:<ciphertext>`, both base64
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt note content"))?;
+        let engine = base64::engine::general_purpose::STANDARD;
+        Ok(format!("{}{}:{}", SEALED_PREFIX, engine.encode(nonce), engine.encode(ciphertext)))
+    }
+
+    /// Open a sealed value; plaintext values pass through unchanged
+    pub fn open(&self, value: &str) -> Result<String> {
+        let Some(sealed) = value.strip_prefix(SEALED_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let (nonce_b64, ciphertext_b64) = sealed.split_once(':').context("Malformed sealed note content")?;
+        let engine = base64::engine::general_purpose::STANDARD;
+        let nonce = engine.decode(nonce_b64).context("Invalid sealed note nonce")?;
+        let nonce: [u8; 12] = nonce.try_into().map_err(|_| anyhow::anyhow!("Invalid sealed note nonce length"))?;
+        let ciphertext = engine.decode(ciphertext_b64).context("Invalid sealed note ciphertext")?;
+        let plaintext = self
+            .cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt note content (wrong key or corrupted value)"))?;
+        String::from_utf8(plaintext).context("Decrypted note content is not valid UTF-8")
+    }
+}
+
+/// Content as it should be written: sealed when the user has a data key
+pub fn seal_content(cipher: Option<&NoteCipher>, content: String) -> Result<String> {
+    match cipher {
+        Some(cipher) => cipher.seal(&content),
+        None => Ok(content),
+    }
+}
+
+/// Content as it should be returned. Sealed content without a key is an error, so
+/// ciphertext never reaches a client as if it were the note.
+pub fn open_content(cipher: Option<&NoteCipher>, content: String) -> Result<String> {
+    if !is_sealed(&content) {
+        return Ok(content);
+    }
+    cipher
+        .ok_or_else(|| anyhow::anyhow!("Note content is encrypted but no data key is available"))?
+        .open(&content)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteEncryptionStatus {
+    pub enabled: bool,
+    /// Whether the server has a master key, i.e. encryption can be switched on
+    pub available: bool,
+    /// Whether note search looks inside content; false while encryption is on
+    pub content_search: bool,
+    pub sealed_notes: i64,
+}
+
+pub async fn status(conn: &Connection) -> Result<NoteEncryptionStatus> {
+    let mut rows = conn
+        .prepare("SELECT COUNT(*) FROM note_encryption_key WHERE id = ?")
+        .await?
+        .query(params![KEY_ROW])
+        .await?;
+    let enabled = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)? > 0,
+        None => false,
+    };
+    let mut sealed_notes = 0;
+    for table in SEALED_TABLES {
+        let mut rows = conn
+            .prepare(&format!("SELECT COUNT(*) FROM {} WHERE content LIKE '{}%'", table, SEALED_PREFIX))
+            .await?
+            .query(params![])
+            .await?;
+        if let Some(row) = rows.next().await? {
+            sealed_notes += row.get::<i64>(0)?;
+        }
+    }
+    Ok(NoteEncryptionStatus { enabled, available: MASTER_KEY.get().is_some(), content_search: !enabled, sealed_notes })
+}
+
+/// Rewrite every note's content from one key state to another
+async fn reseal_all(conn: &Connection, from: Option<&NoteCipher>, to: Option<&NoteCipher>) -> Result<u64> {
+    let mut rewritten = 0;
+    for table in SEALED_TABLES {
+        let mut rows = conn.prepare(&format!("SELECT id, content FROM {}", table)).await?.query(params![]).await?;
+        let mut notes: Vec<(String, String)> = Vec::new();
+        while let Some(row) = rows.next().await? {
+            notes.push((row.get(0)?, row.get(1)?));
+        }
+        for (id, content) in notes {
+            // Already in the target state (an earlier run stopped part way)
+            if is_sealed(&content) == to.is_some() {
+                continue;
+            }
+            let rewritten_content = seal_content(to, open_content(from, content)?)?;
+            conn.execute(&format!("UPDATE {} SET content = ? WHERE id = ?", table), params![rewritten_content, id])
+                .await?;
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Generate and store a data key, then seal existing notes. Idempotent: an existing key is
+/// reused, and a run that stopped part way is finished.
+pub async fn enable(conn: &Connection) -> Result<NoteEncryptionStatus> {
+    let cipher = match NoteCipher::for_connection(conn).await? {
+        Some(cipher) => cipher,
+        None => {
+            let key = Aes256Gcm::generate_key(&mut OsRng);
+            let (wrapped, nonce) = master_key()?.encrypt(&base64::engine::general_purpose::STANDARD.encode(key))?;
+            conn.execute(
+                "INSERT INTO note_encryption_key (id, wrapped_key, nonce) VALUES (?, ?, ?)",
+                params![KEY_ROW, wrapped, nonce],
+            )
+            .await
+            .context("Failed to store note data key")?;
+            NoteCipher::from_key(&key)
+        }
+    };
+    let sealed = reseal_all(conn, Some(&cipher), Some(&cipher)).await?;
+    log::info!("Note encryption enabled; sealed {} notes", sealed);
+    status(conn).await
+}
+
+/// Decrypt every note back to plaintext, then discard the data key
+pub async fn disable(conn: &Connection) -> Result<NoteEncryptionStatus> {
+    if let Some(cipher) = NoteCipher::for_connection(conn).await? {
+        let opened = reseal_all(conn, Some(&cipher), None).await?;
+        conn.execute("DELETE FROM note_encryption_key WHERE id = ?", params![KEY_ROW]).await?;
+        log::info!("Note encryption disabled; decrypted {} notes", opened);
+    }
+    status(conn).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_round_trip_and_plaintext_passthrough() {
+        let cipher = NoteCipher::from_key(&[9u8; 32]);
+        let sealed = cipher.seal("Chased the open again").unwrap();
+        assert!(is_sealed(&sealed));
+        assert!(!sealed.contains("Chased"));
+        assert_ne!(sealed, cipher.seal("Chased the open again").unwrap());
+        assert_eq!(open_content(Some(&cipher), sealed.clone()).unwrap(), "Chased the open again");
+
+        assert_eq!(open_content(None, "plain".to_string()).unwrap(), "plain");
+        assert_eq!(seal_content(None, "plain".to_string()).unwrap(), "plain");
+        assert!(open_content(None, sealed.clone()).is_err());
+        assert!(NoteCipher::from_key(&[1u8; 32]).open(&sealed).is_err());
+    }
+}
@@ -0,0 +1,441 @@
+use anyhow::{Context, Result};
+use libsql::{params, Connection};
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::ai::insights::Insight;
+use crate::models::ai::reports::TradingReport;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+
+/// Event type sent when an AI insight is generated
+pub const EVENT_INSIGHT_GENERATED: &str = "insight_generated";
+/// Event type sent by the daily P&L recap cron
+pub const EVENT_DAILY_PNL_RECAP: &str = "daily_pnl_recap";
+/// Event type sent when a scheduled AI report is generated
+pub const EVENT_REPORT_GENERATED: &str = "report_generated";
+
+const SUPPORTED_EVENTS: &[&str] = &[EVENT_INSIGHT_GENERATED, EVENT_DAILY_PNL_RECAP, EVENT_REPORT_GENERATED];
+
+// Brand colors used for embed accents
+const COLOR_NEUTRAL: u32 = 0x5865F2;
+const COLOR_PROFIT: u32 = 0x22C55E;
+const COLOR_LOSS: u32 = 0xEF4444;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatPlatform {
+    Discord,
+    Slack,
+}
+
+impl ChatPlatform {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatPlatform::Discord => "discord",
+            ChatPlatform::Slack => "slack",
+        }
+    }
+
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "discord" => Some(ChatPlatform::Discord),
+            "slack" => Some(ChatPlatform::Slack),
+            _ => None,
+        }
+    }
+
+    /// Only accept the platforms' own webhook hosts so the backend can't be used to POST anywhere
+    fn accepts_url(&self, url: &str) -> bool {
+        match self {
+            ChatPlatform::Discord => {
+                url.starts_with("https://discord.com/api/webhooks/")
+                    || url.starts_with("https://discordapp.com/api/webhooks/")
+            }
+            ChatPlatform::Slack => url.starts_with("https://hooks.slack.com/"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatWebhook {
+    pub id: String,
+    pub name: Option<String>,
+    pub platform: ChatPlatform,
+    pub webhook_url: String,
+    pub event_types: Vec<String>,
+    pub is_active: bool,
+    pub last_delivered_at: Option<String>,
+    pub last_error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SaveChatWebhookRequest {
+    pub name: Option<String>,
+    pub platform: ChatPlatform,
+    pub webhook_url: String,
+    pub event_types: Vec<String>,
+    pub is_active: Option<bool>,
+}
+
+impl SaveChatWebhookRequest {
+    pub fn validate(&self) -> Result<()> {
+        if !self.platform.accepts_url(self.webhook_url.trim()) {
+            anyhow::bail!("Webhook URL is not a valid {} webhook", self.platform.as_str());
+        }
+        if self.event_types.is_empty() {
+            anyhow::bail!("Choose at least one event type");
+        }
+        if let Some(unknown) = self.event_types.iter().find(|e| !SUPPORTED_EVENTS.contains(&e.as_str())) {
+            anyhow::bail!("Unsupported event type: {}", unknown);
+        }
+        Ok(())
+    }
+}
+
+/// Platform-agnostic message rendered as a Discord embed or Slack blocks
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub title: String,
+    pub description: String,
+    pub fields: Vec<(String, String)>,
+    pub color: u32,
+    pub url: Option<String>,
+}
+
+/// Day's closed-trade summary used for the recap message
+#[derive(Debug, Clone, Serialize)]
+pub struct DailyPnlRecap {
+    pub date: String,
+    pub net_pnl: f64,
+    pub trade_count: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+    pub best_trade: f64,
+    pub worst_trade: f64,
+}
+
+pub struct ChatWebhookService<'a> {
+    pub conn: &'a Connection,
+    client: reqwest::Client,
+}
+
+impl<'a> ChatWebhookService<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { conn, client }
+    }
+
+    pub async fn create_webhook(&self, req: SaveChatWebhookRequest) -> Result<ChatWebhook> {
+        req.validate()?;
+        let id = Uuid::new_v4().to_string();
+        let event_types = serde_json::to_string(&req.event_types)?;
+
+        self.conn.execute(
+            "INSERT INTO notification_webhooks (id, name, platform, webhook_url, event_types, is_active) VALUES (?, ?, ?, ?, ?, ?)",
+            params![id.clone(), req.name, req.platform.as_str(), req.webhook_url.trim().to_string(), event_types, req.is_active.unwrap_or(true)],
+        ).await.context("Failed to insert notification webhook")?;
+
+        self.get_webhook(&id).await?.ok_or_else(|| anyhow::anyhow!("Webhook was created but could not be retrieved"))
+    }
+
+    pub async fn update_webhook(&self, id: &str, req: SaveChatWebhookRequest) -> Result<Option<ChatWebhook>> {
+        req.validate()?;
+        let event_types = serde_json::to_string(&req.event_types)?;
+
+        let n = self.conn.execute(
+            "UPDATE notification_webhooks SET name = ?, platform = ?, webhook_url = ?, event_types = ?, is_active = ?, last_error = NULL WHERE id = ?",
+            params![req.name, req.platform.as_str(), req.webhook_url.trim().to_string(), event_types, req.is_active.unwrap_or(true), id],
+        ).await?;
+
+        if n == 0 {
+            return Ok(None);
+        }
+        self.get_webhook(id).await
+    }
+
+    pub async fn delete_webhook(&self, id: &str) -> Result<bool> {
+        let n = self.conn.execute("DELETE FROM notification_webhooks WHERE id = ?", params![id]).await?;
+        Ok(n > 0)
+    }
+
+    pub async fn get_webhook(&self, id: &str) -> Result<Option<ChatWebhook>> {
+        let mut rows = self.conn
+            .prepare("SELECT id, name, platform, webhook_url, event_types, is_active, last_delivered_at, last_error, created_at, updated_at FROM notification_webhooks WHERE id = ?")
+            .await?
+            .query(params![id])
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => Ok(Self::from_row(&row)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list_webhooks(&self) -> Result<Vec<ChatWebhook>> {
+        let mut rows = self.conn
+            .prepare("SELECT id, name, platform, webhook_url, event_types, is_active, last_delivered_at, last_error, created_at, updated_at FROM notification_webhooks ORDER BY created_at DESC")
+            .await?
+            .query(params![])
+            .await?;
+
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await? {
+            if let Some(webhook) = Self::from_row(&row) {
+                items.push(webhook);
+            }
+        }
+        Ok(items)
+    }
+
+    fn from_row(row: &libsql::Row) -> Option<ChatWebhook> {
+        let platform: String = row.get(2).ok()?;
+        let event_types: String = row.get(4).unwrap_or_default();
+        Some(ChatWebhook {
+            id: row.get(0).ok()?,
+            name: row.get(1).ok().flatten(),
+            platform: ChatPlatform::from_db(&platform)?,
+            webhook_url: row.get(3).ok()?,
+            event_types: serde_json::from_str(&event_types).unwrap_or_default(),
+            is_active: row.get::<i64>(5).map(|v| v != 0).unwrap_or(false),
+            last_delivered_at: row.get(6).ok().flatten(),
+            last_error: row.get(7).ok().flatten(),
+            created_at: row.get(8).unwrap_or_default(),
+            updated_at: row.get(9).unwrap_or_default(),
+        })
+    }
+
+    /// Send a message to every active webhook subscribed to the event.
+    /// Returns the number of successful deliveries.
+    pub async fn dispatch(&self, event_type: &str, message: &ChatMessage) -> Result<usize> {
+        let webhooks = self.list_webhooks().await?;
+        let mut delivered = 0;
+
+        for webhook in webhooks.iter().filter(|w| w.is_active && w.event_types.iter().any(|e| e == event_type)) {
+            match self.deliver(webhook, message).await {
+                Ok(()) => {
+                    delivered += 1;
+                    let _ = self.conn.execute(
+                        "UPDATE notification_webhooks SET last_delivered_at = datetime('now'), last_error = NULL WHERE id = ?",
+                        params![webhook.id.clone()],
+                    ).await;
+                }
+                Err(e) => {
+                    warn!("Failed to deliver {} webhook {}: {}", webhook.platform.as_str(), webhook.id, e);
+                    let _ = self.conn.execute(
+                        "UPDATE notification_webhooks SET last_error = ? WHERE id = ?",
+                        params![e.to_string(), webhook.id.clone()],
+                    ).await;
+                }
+            }
+        }
+
+        Ok(delivered)
+    }
+
+    /// Send a message to a single webhook
+    pub async fn deliver(&self, webhook: &ChatWebhook, message: &ChatMessage) -> Result<()> {
+        let body = match webhook.platform {
+            ChatPlatform::Discord => render_discord(message),
+            ChatPlatform::Slack => render_slack(message),
+        };
+
+        let response = self.client.post(&webhook.webhook_url).json(&body).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} returned {}: {}", webhook.platform.as_str(), status, text);
+        }
+        Ok(())
+    }
+}
+
+/// Build the message posted when an insight is generated
+pub fn insight_message(insight: &Insight) -> ChatMessage {
+    let summary: String = insight.content.chars().take(600).collect();
+    let mut fields = vec![("Type".to_string(), insight.insight_type.to_string())];
+    if !insight.key_findings.is_empty() {
+        let findings = insight.key_findings.iter().take(3).map(|f| format!("• {}", f)).collect::<Vec<_>>().join("\n");
+        fields.push(("Key findings".to_string(), findings));
+    }
+    if !insight.recommendations.is_empty() {
+        let recs = insight.recommendations.iter().take(3).map(|r| format!("• {}", r)).collect::<Vec<_>>().join("\n");
+        fields.push(("Recommendations".to_string(), recs));
+    }
+
+    ChatMessage {
+        title: insight.title.clone(),
+        description: summary,
+        fields,
+        color: COLOR_NEUTRAL,
+        url: None,
+    }
+}
+
+/// Build the daily P&L recap message
+pub fn recap_message(recap: &DailyPnlRecap) -> ChatMessage {
+    let win_rate = if recap.trade_count > 0 {
+        recap.winning_trades as f64 / recap.trade_count as f64 * 100.0
+    } else {
+        0.0
+    };
+
+    ChatMessage {
+        title: format!("Daily P&L recap — {}", recap.date),
+        description: format!("Net P&L: **{}${:.2}**", if recap.net_pnl < 0.0 { "-" } else { "" }, recap.net_pnl.abs()),
+        fields: vec![
+            ("Trades".to_string(), recap.trade_count.to_string()),
+            ("Win rate".to_string(), format!("{:.0}% ({}W / {}L)", win_rate, recap.winning_trades, recap.losing_trades)),
+            ("Best trade".to_string(), format!("${:.2}", recap.best_trade)),
+            ("Worst trade".to_string(), format!("${:.2}", recap.worst_trade)),
+        ],
+        color: if recap.net_pnl >= 0.0 { COLOR_PROFIT } else { COLOR_LOSS },
+        url: None,
+    }
+}
+
+/// Build the message posted when a scheduled report is generated
+pub fn report_message(report: &TradingReport) -> ChatMessage {
+    let analytics = &report.analytics;
+    let mut fields = vec![
+        ("Trades".to_string(), analytics.total_trades.to_string()),
+        ("Win rate".to_string(), format!("{:.0}%", analytics.win_rate)),
+        ("Net P&L".to_string(), format!("{}${:.2}", if analytics.net_pnl < 0.0 { "-" } else { "" }, analytics.net_pnl.abs())),
+    ];
+    if !report.recommendations.is_empty() {
+        let recs = report.recommendations.iter().take(3).map(|r| format!("• {}", r)).collect::<Vec<_>>().join("\n");
+        fields.push(("Recommendations".to_string(), recs));
+    }
+
+    ChatMessage {
+        title: report.title.clone(),
+        description: report.summary.chars().take(600).collect(),
+        fields,
+        color: if analytics.net_pnl >= 0.0 { COLOR_PROFIT } else { COLOR_LOSS },
+        url: None,
+    }
+}
+
+fn render_discord(message: &ChatMessage) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = message.fields.iter()
+        .map(|(name, value)| serde_json::json!({"name": name, "value": value, "inline": value.len() < 40}))
+        .collect();
+    let mut embed = serde_json::json!({
+        "title": message.title,
+        "description": message.description,
+        "color": message.color,
+        "fields": fields,
+        "footer": {"text": "Tradstry"},
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    });
+    if let Some(url) = &message.url {
+        embed["url"] = serde_json::json!(url);
+    }
+    serde_json::json!({"username": "Tradstry", "embeds": [embed]})
+}
+
+fn render_slack(message: &ChatMessage) -> serde_json::Value {
+    // Slack mrkdwn uses single asterisks for bold
+    let description = message.description.replace("**", "*");
+    let fields: Vec<serde_json::Value> = message.fields.iter()
+        .map(|(name, value)| serde_json::json!({"type": "mrkdwn", "text": format!("*{}*\n{}", name, value)}))
+        .collect();
+    serde_json::json!({
+        "text": message.title,
+        "attachments": [{
+            "color": format!("#{:06X}", message.color),
+            "blocks": [
+                {"type": "header", "text": {"type": "plain_text", "text": message.title}},
+                {"type": "section", "text": {"type": "mrkdwn", "text": description}},
+                {"type": "section", "fields": fields},
+            ]
+        }]
+    })
+}
+
+/// Post a freshly generated insight to the user's chat webhooks
+pub async fn dispatch_insight(conn: &Connection, insight: &Insight) -> Result<usize> {
+    let service = ChatWebhookService::new(conn);
+    service.dispatch(EVENT_INSIGHT_GENERATED, &insight_message(insight)).await
+}
+
+/// Post a generated report to the user's chat webhooks
+pub async fn dispatch_report(conn: &Connection, report: &TradingReport) -> Result<usize> {
+    let service = ChatWebhookService::new(conn);
+    service.dispatch(EVENT_REPORT_GENERATED, &report_message(report)).await
+}
+
+/// Build today's recap from closed trades. Returns None if nothing closed today.
+pub async fn build_daily_recap(conn: &Connection) -> Result<Option<DailyPnlRecap>> {
+    let sql = format!(
+        r#"
+        SELECT
+            COUNT(*),
+            COALESCE(SUM(pnl), 0),
+            COALESCE(SUM(CASE WHEN pnl > 0 THEN 1 ELSE 0 END), 0),
+            COALESCE(SUM(CASE WHEN pnl < 0 THEN 1 ELSE 0 END), 0),
+            COALESCE(MAX(pnl), 0),
+            COALESCE(MIN(pnl), 0)
+        FROM (
+            SELECT {STOCK_PNL_SQL} as pnl
+            FROM stocks
+            WHERE exit_price IS NOT NULL AND DATE(exit_date) = DATE('now')
+
+            UNION ALL
+
+            SELECT {OPTION_PNL_SQL} as pnl
+            FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND DATE(exit_date) = DATE('now')
+        )
+        "#
+    );
+    let mut rows = conn
+        .prepare(&sql)
+        .await?
+        .query(params![])
+        .await?;
+
+    let row = match rows.next().await? {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    let trade_count = row.get::<i64>(0).unwrap_or(0) as u32;
+    if trade_count == 0 {
+        return Ok(None);
+    }
+
+    Ok(Some(DailyPnlRecap {
+        date: chrono::Utc::now().format("%Y-%m-%d").to_string(),
+        net_pnl: row.get::<f64>(1).unwrap_or(0.0),
+        trade_count,
+        winning_trades: row.get::<i64>(2).unwrap_or(0) as u32,
+        losing_trades: row.get::<i64>(3).unwrap_or(0) as u32,
+        best_trade: row.get::<f64>(4).unwrap_or(0.0),
+        worst_trade: row.get::<f64>(5).unwrap_or(0.0),
+    }))
+}
+
+/// Send today's recap for one user; returns deliveries made
+pub async fn send_daily_recap(conn: &Connection, user_id: &str) -> Result<usize> {
+    let recap = match build_daily_recap(conn).await {
+        Ok(Some(recap)) => recap,
+        Ok(None) => return Ok(0),
+        Err(e) => {
+            error!("Failed to build daily recap for user {}: {}", user_id, e);
+            return Err(e);
+        }
+    };
+
+    let service = ChatWebhookService::new(conn);
+    let delivered = service.dispatch(EVENT_DAILY_PNL_RECAP, &recap_message(&recap)).await?;
+    if delivered > 0 {
+        info!("Sent daily P&L recap to {} webhook(s) for user {}", delivered, user_id);
+    }
+    Ok(delivered)
+}
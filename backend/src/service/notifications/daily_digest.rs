@@ -0,0 +1,441 @@
+// End-of-day email digest. Users opt in through `digest_settings` and pick a local send hour
+// and the sections they want: today's realized P&L, positions where the journal and the
+// synced broker disagree, tomorrow's reminders and events, and tomorrow's earnings. The cron
+// job runs hourly and sends each digest once per local day, at or after its hour.
+//
+// Profile timezones are IANA names and the server carries no tz database, so the digest
+// uses the UTC offset the client reports when saving settings. Fixed-offset profile
+// timezones ("UTC", "UTC+2", "-05:00") are used as they are.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, TimeZone, Timelike, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::service::agenda::{self, AgendaItemType};
+use crate::service::brokerage::holdings::{self, ReconciliationStatus};
+use crate::service::notifications::email::{escape_html, EmailMessage};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestSection {
+    Pnl,
+    Mismatches,
+    Reminders,
+    Earnings,
+}
+
+impl DigestSection {
+    pub const ALL: [DigestSection; 4] = [DigestSection::Pnl, DigestSection::Mismatches, DigestSection::Reminders, DigestSection::Earnings];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            DigestSection::Pnl => "pnl",
+            DigestSection::Mismatches => "mismatches",
+            DigestSection::Reminders => "reminders",
+            DigestSection::Earnings => "earnings",
+        }
+    }
+
+    fn parse_list(value: &str) -> Vec<DigestSection> {
+        DigestSection::ALL.into_iter().filter(|s| value.split(',').any(|v| v.trim() == s.as_str())).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestSettings {
+    pub enabled: bool,
+    /// Local hour (0-23) the digest goes out at
+    pub send_hour: u32,
+    pub utc_offset_minutes: i32,
+    pub sections: Vec<DigestSection>,
+    /// Local date of the last digest sent
+    pub last_sent_date: Option<String>,
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self { enabled: false, send_hour: 17, utc_offset_minutes: 0, sections: DigestSection::ALL.to_vec(), last_sent_date: None }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateDigestSettings {
+    pub enabled: Option<bool>,
+    pub send_hour: Option<u32>,
+    pub utc_offset_minutes: Option<i32>,
+    pub sections: Option<Vec<DigestSection>>,
+}
+
+impl UpdateDigestSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.send_hour.is_some_and(|h| h > 23) {
+            return Err("send_hour must be between 0 and 23".to_string());
+        }
+        if self.utc_offset_minutes.is_some_and(|m| !(-14 * 60..=14 * 60).contains(&m)) {
+            return Err("utc_offset_minutes must be within ±14 hours".to_string());
+        }
+        Ok(())
+    }
+}
+
+pub async fn load_settings(conn: &Connection) -> Result<DigestSettings> {
+    let mut rows = conn
+        .query("SELECT enabled, send_hour, utc_offset_minutes, sections, last_sent_date FROM digest_settings WHERE id = 1", params![])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(DigestSettings::default()) };
+    Ok(DigestSettings {
+        enabled: row.get::<i64>(0)? != 0,
+        send_hour: row.get::<i64>(1)?.clamp(0, 23) as u32,
+        utc_offset_minutes: row.get::<i64>(2)? as i32,
+        sections: DigestSection::parse_list(&row.get::<String>(3)?),
+        last_sent_date: row.get(4)?,
+    })
+}
+
+pub async fn save_settings(conn: &Connection, update: &UpdateDigestSettings) -> Result<DigestSettings> {
+    let current = load_settings(conn).await?;
+    let sections = update.sections.as_ref().unwrap_or(&current.sections);
+    let sections: Vec<&str> = DigestSection::ALL.iter().filter(|s| sections.contains(s)).map(|s| s.as_str()).collect();
+    conn.execute(
+        "INSERT INTO digest_settings (id, enabled, send_hour, utc_offset_minutes, sections, last_sent_date, updated_at) VALUES (1, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET enabled = excluded.enabled, send_hour = excluded.send_hour,
+             utc_offset_minutes = excluded.utc_offset_minutes, sections = excluded.sections, updated_at = excluded.updated_at",
+        params![
+            update.enabled.unwrap_or(current.enabled) as i64,
+            update.send_hour.unwrap_or(current.send_hour) as i64,
+            update.utc_offset_minutes.unwrap_or(current.utc_offset_minutes) as i64,
+            sections.join(","),
+            current.last_sent_date,
+            Utc::now().to_rfc3339()
+        ],
+    )
+    .await?;
+    load_settings(conn).await
+}
+
+async fn mark_sent(conn: &Connection, local_date: NaiveDate) -> Result<()> {
+    conn.execute("UPDATE digest_settings SET last_sent_date = ? WHERE id = 1", params![local_date.format("%Y-%m-%d").to_string()]).await?;
+    Ok(())
+}
+
+/// Minutes east of UTC for "UTC", "GMT", "UTC+2", "UTC-05:30" or "+05:30"; None for IANA names
+pub fn parse_fixed_offset(timezone: &str) -> Option<i32> {
+    let tz = timezone.trim();
+    let rest = tz.strip_prefix("UTC").or_else(|| tz.strip_prefix("GMT")).unwrap_or(tz);
+    if rest.is_empty() {
+        return Some(0);
+    }
+    let (sign, digits) = match rest.chars().next()? {
+        '+' => (1, &rest[1..]),
+        '-' => (-1, &rest[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((h, m)) => (h.parse::<i32>().ok()?, m.parse::<i32>().ok()?),
+        None => (digits.parse::<i32>().ok()?, 0),
+    };
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+/// The profile timezone when it is a fixed offset, otherwise the offset saved with the settings
+async fn effective_offset(conn: &Connection, settings: &DigestSettings) -> i32 {
+    let profile_tz = match conn.query("SELECT timezone FROM user_profile LIMIT 1", params![]).await {
+        Ok(mut rows) => match rows.next().await {
+            Ok(Some(row)) => row.get::<Option<String>>(0).ok().flatten(),
+            _ => None,
+        },
+        Err(_) => None,
+    };
+    profile_tz.as_deref().and_then(parse_fixed_offset).unwrap_or(settings.utc_offset_minutes)
+}
+
+/// Local date to send for, if the digest is due at `now`
+pub fn due_date(settings: &DigestSettings, offset_minutes: i32, now: DateTime<Utc>) -> Option<NaiveDate> {
+    if !settings.enabled || settings.sections.is_empty() {
+        return None;
+    }
+    let local = now + Duration::minutes(offset_minutes as i64);
+    let date = local.date_naive();
+    let already_sent = settings.last_sent_date.as_deref() == Some(date.format("%Y-%m-%d").to_string().as_str());
+    (local.hour() >= settings.send_hour && !already_sent).then_some(date)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestPnl {
+    pub closed_trades: i64,
+    pub net_pnl: f64,
+    pub winners: i64,
+    pub losers: i64,
+    pub opened_trades: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestMismatch {
+    pub symbol: String,
+    pub status: ReconciliationStatus,
+    pub broker_quantity: f64,
+    pub journal_quantity: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestItem {
+    pub title: String,
+    pub time: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub date: String,
+    pub pnl: Option<DigestPnl>,
+    pub mismatches: Option<Vec<DigestMismatch>>,
+    pub reminders: Option<Vec<DigestItem>>,
+    pub earnings: Option<Vec<DigestItem>>,
+}
+
+impl Digest {
+    /// Nothing worth an email: no trades, no mismatches, nothing tomorrow
+    pub fn is_empty(&self) -> bool {
+        self.pnl.as_ref().is_none_or(|p| p.closed_trades == 0 && p.opened_trades == 0)
+            && self.mismatches.as_ref().is_none_or(Vec::is_empty)
+            && self.reminders.as_ref().is_none_or(Vec::is_empty)
+            && self.earnings.as_ref().is_none_or(Vec::is_empty)
+    }
+}
+
+async fn load_pnl(conn: &Connection, start: &str, end: &str) -> Result<DigestPnl> {
+    let sql = format!(
+        "SELECT COUNT(*), COALESCE(SUM(pnl), 0), COALESCE(SUM(CASE WHEN pnl > 0 THEN 1 ELSE 0 END), 0), COALESCE(SUM(CASE WHEN pnl < 0 THEN 1 ELSE 0 END), 0)
+         FROM (
+             SELECT {STOCK_PNL_SQL} AS pnl FROM stocks WHERE exit_price IS NOT NULL AND exit_date >= ?1 AND exit_date < ?2
+             UNION ALL
+             SELECT {OPTION_PNL_SQL} AS pnl FROM options WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date >= ?1 AND exit_date < ?2
+         )"
+    );
+    let mut rows = conn.query(&sql, params![start, end]).await?;
+    let row = rows.next().await?.ok_or_else(|| anyhow::anyhow!("P&L query returned no row"))?;
+    let (closed_trades, net_pnl, winners, losers) = (row.get::<i64>(0)?, row.get::<f64>(1)?, row.get::<i64>(2)?, row.get::<i64>(3)?);
+
+    let mut rows = conn
+        .query(
+            "SELECT (SELECT COUNT(*) FROM stocks WHERE entry_date >= ?1 AND entry_date < ?2) + (SELECT COUNT(*) FROM options WHERE entry_date >= ?1 AND entry_date < ?2)",
+            params![start, end],
+        )
+        .await?;
+    let opened_trades = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)?,
+        None => 0,
+    };
+    Ok(DigestPnl { closed_trades, net_pnl, winners, losers, opened_trades })
+}
+
+/// Assemble the enabled sections for `local_date`
+pub async fn build_digest(conn: &Connection, user_id: &str, settings: &DigestSettings, local_date: NaiveDate, offset_minutes: i32) -> Result<Digest> {
+    let offset = FixedOffset::east_opt(offset_minutes * 60).unwrap_or(FixedOffset::east_opt(0).expect("zero offset"));
+    let day_start = offset
+        .from_local_datetime(&local_date.and_hms_opt(0, 0, 0).expect("midnight"))
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| local_date.and_hms_opt(0, 0, 0).expect("midnight").and_utc());
+    let (start, end) = (
+        crate::models::timestamps::to_db(&day_start),
+        crate::models::timestamps::to_db(&(day_start + Duration::days(1))),
+    );
+    let wants = |section: DigestSection| settings.sections.contains(&section);
+
+    let pnl = if wants(DigestSection::Pnl) { Some(load_pnl(conn, &start, &end).await?) } else { None };
+
+    let mismatches = if wants(DigestSection::Mismatches) {
+        let items = holdings::reconcile_positions(conn, user_id).await?;
+        Some(
+            items
+                .into_iter()
+                .filter(|i| i.status != ReconciliationStatus::Matched)
+                .map(|i| DigestMismatch { symbol: i.symbol, status: i.status, broker_quantity: i.broker_quantity, journal_quantity: i.journal_quantity })
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let (reminders, earnings) = if wants(DigestSection::Reminders) || wants(DigestSection::Earnings) {
+        let tomorrow = local_date + Duration::days(1);
+        let agenda = agenda::build_agenda(conn, tomorrow, tomorrow, "US").await?;
+        let pick = |types: &[AgendaItemType]| -> Vec<DigestItem> {
+            agenda
+                .items
+                .iter()
+                .filter(|item| types.contains(&item.item_type))
+                .map(|item| DigestItem { title: item.title.clone(), time: item.start_time.clone(), detail: item.description.clone() })
+                .collect()
+        };
+        (
+            wants(DigestSection::Reminders).then(|| pick(&[AgendaItemType::Reminder, AgendaItemType::Event, AgendaItemType::External])),
+            wants(DigestSection::Earnings).then(|| pick(&[AgendaItemType::Earnings])),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(Digest { date: local_date.format("%Y-%m-%d").to_string(), pnl, mismatches, reminders, earnings })
+}
+
+fn format_money(value: f64) -> String {
+    if value < 0.0 { format!("-${:.2}", -value) } else { format!("${:.2}", value) }
+}
+
+fn status_label(status: &ReconciliationStatus) -> &'static str {
+    match status {
+        ReconciliationStatus::Matched => "matched",
+        ReconciliationStatus::QuantityMismatch => "quantity differs",
+        ReconciliationStatus::BrokerOnly => "not in journal",
+        ReconciliationStatus::JournalOnly => "not at broker",
+    }
+}
+
+/// (heading, lines) for each section present
+fn digest_sections(digest: &Digest) -> Vec<(&'static str, Vec<String>)> {
+    let mut sections = Vec::new();
+    if let Some(pnl) = &digest.pnl {
+        let mut lines = vec![format!("Net P&L: {} across {} closed trades ({} winners, {} losers)", format_money(pnl.net_pnl), pnl.closed_trades, pnl.winners, pnl.losers)];
+        lines.push(format!("Trades opened today: {}", pnl.opened_trades));
+        sections.push(("Today's P&L", lines));
+    }
+    if let Some(mismatches) = &digest.mismatches {
+        let lines = if mismatches.is_empty() {
+            vec!["Journal and broker positions match.".to_string()]
+        } else {
+            mismatches
+                .iter()
+                .map(|m| format!("{}: {} (broker {}, journal {})", m.symbol, status_label(&m.status), m.broker_quantity, m.journal_quantity))
+                .collect()
+        };
+        sections.push(("Journal vs broker", lines));
+    }
+    let item_line = |item: &DigestItem| match (&item.time, &item.detail) {
+        (Some(time), Some(detail)) => format!("{} {} ({})", time, item.title, detail),
+        (Some(time), None) => format!("{} {}", time, item.title),
+        (None, Some(detail)) => format!("{} ({})", item.title, detail),
+        (None, None) => item.title.clone(),
+    };
+    if let Some(reminders) = &digest.reminders {
+        let lines = if reminders.is_empty() { vec!["Nothing scheduled.".to_string()] } else { reminders.iter().map(item_line).collect() };
+        sections.push(("Tomorrow", lines));
+    }
+    if let Some(earnings) = &digest.earnings {
+        let lines = if earnings.is_empty() { vec!["No earnings on your calendar.".to_string()] } else { earnings.iter().map(item_line).collect() };
+        sections.push(("Earnings tomorrow", lines));
+    }
+    sections
+}
+
+pub fn render_email(digest: &Digest, to: &str) -> EmailMessage {
+    let sections = digest_sections(digest);
+    let text = sections
+        .iter()
+        .map(|(heading, lines)| format!("{}\n{}", heading, lines.iter().map(|l| format!("- {}", l)).collect::<Vec<_>>().join("\n")))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let html = sections
+        .iter()
+        .map(|(heading, lines)| {
+            format!(
+                "<h3>{}</h3><ul>{}</ul>",
+                escape_html(heading),
+                lines.iter().map(|l| format!("<li>{}</li>", escape_html(l))).collect::<String>()
+            )
+        })
+        .collect::<String>();
+    EmailMessage {
+        to: to.to_string(),
+        subject: format!("Your trading day: {}", digest.date),
+        text,
+        html: Some(format!("<div style=\"font-family:sans-serif\"><h2>Daily digest for {}</h2>{}</div>", escape_html(&digest.date), html)),
+    }
+}
+
+/// A digest whose local send hour has passed and that hasn't gone out today
+pub struct DueDigest {
+    settings: DigestSettings,
+    offset_minutes: i32,
+    local_date: NaiveDate,
+}
+
+pub async fn find_due(conn: &Connection, now: DateTime<Utc>) -> Result<Option<DueDigest>> {
+    let settings = load_settings(conn).await?;
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let offset_minutes = effective_offset(conn, &settings).await;
+    Ok(due_date(&settings, offset_minutes, now).map(|local_date| DueDigest { settings, offset_minutes, local_date }))
+}
+
+/// Build and send a due digest; returns false when there was nothing to report. The local day
+/// is marked as handled either way.
+pub async fn send_due(
+    conn: &Connection,
+    user_id: &str,
+    due: &DueDigest,
+    email: &crate::turso::config::EmailConfig,
+    address: &str,
+) -> Result<bool> {
+    let digest = build_digest(conn, user_id, &due.settings, due.local_date, due.offset_minutes).await?;
+    let sent = !digest.is_empty();
+    if sent {
+        crate::service::notifications::email::send_email(email, &render_email(&digest, address)).await?;
+    }
+    mark_sent(conn, due.local_date).await?;
+    Ok(sent)
+}
+
+/// Digest for today as it stands, for previews
+pub async fn preview(conn: &Connection, user_id: &str, now: DateTime<Utc>) -> Result<Digest> {
+    let settings = load_settings(conn).await?;
+    let offset = effective_offset(conn, &settings).await;
+    let local_date = (now + Duration::minutes(offset as i64)).date_naive();
+    build_digest(conn, user_id, &settings, local_date, offset).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_date_respects_hour_offset_and_last_sent() {
+        let settings = DigestSettings { enabled: true, send_hour: 17, utc_offset_minutes: -240, ..Default::default() };
+        let now = DateTime::parse_from_rfc3339("2026-10-16T20:30:00Z").unwrap().with_timezone(&Utc);
+        // 16:30 in New York: not yet
+        assert_eq!(due_date(&settings, -240, now), None);
+        let later = now + Duration::hours(1);
+        assert_eq!(due_date(&settings, -240, later), NaiveDate::from_ymd_opt(2026, 10, 16));
+        let sent = DigestSettings { last_sent_date: Some("2026-10-16".to_string()), ..settings.clone() };
+        assert_eq!(due_date(&sent, -240, later), None);
+        assert_eq!(due_date(&DigestSettings { enabled: false, ..settings }, -240, later), None);
+    }
+
+    #[test]
+    fn test_parse_fixed_offset() {
+        assert_eq!(parse_fixed_offset("UTC"), Some(0));
+        assert_eq!(parse_fixed_offset("UTC+2"), Some(120));
+        assert_eq!(parse_fixed_offset("-05:30"), Some(-330));
+        assert_eq!(parse_fixed_offset("America/New_York"), None);
+    }
+
+    #[test]
+    fn test_render_email_escapes_and_lists_sections() {
+        let digest = Digest {
+            date: "2026-10-16".to_string(),
+            pnl: Some(DigestPnl { closed_trades: 2, net_pnl: -40.5, winners: 1, losers: 1, opened_trades: 1 }),
+            mismatches: None,
+            reminders: Some(vec![DigestItem { title: "Review <AAPL> plan".to_string(), time: Some("09:00".to_string()), detail: None }]),
+            earnings: Some(Vec::new()),
+        };
+        let email = render_email(&digest, "t@example.com");
+        assert!(email.text.contains("Net P&L: -$40.50 across 2 closed trades"));
+        assert!(email.text.contains("- 09:00 Review <AAPL> plan"));
+        assert!(email.html.unwrap().contains("Review &lt;AAPL&gt; plan"));
+        assert!(!email.text.contains("Journal vs broker"));
+    }
+}
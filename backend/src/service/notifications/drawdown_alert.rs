@@ -0,0 +1,327 @@
+// Drawdown alerts on account equity. Equity is the account size set for drawdown sizing plus
+// all realized P&L plus the unrealized P&L of open stock positions (options are not priced).
+// The peak is kept between checks, so an intraday high in open positions counts. When equity
+// falls a threshold's percentage below the peak, one alert goes out for that threshold; a new
+// peak starts a new episode and re-arms every threshold.
+//
+// Checks run from the cron endpoint rather than on trade close: closing a priced position
+// only moves P&L from unrealized to realized, so equity changes with prices, not with closes.
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::push::{PushPayload, PushService};
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::consistency::fetch_daily_pnl;
+use crate::service::analytics_engine::drawdown_sizing::SizingRule;
+use crate::service::dashboard::open_positions;
+use crate::service::market_engine::client::MarketClient;
+use crate::turso::config::WebPushConfig;
+use crate::websocket::{ConnectionManager, EventType, WsMessage};
+
+/// Drawdown alert setting (single row, id 'default'), which also holds the tracked peak
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawdownAlertRule {
+    pub is_enabled: bool,
+    /// Percentages below peak equity that each raise one alert per episode
+    pub thresholds: Vec<f64>,
+}
+
+impl Default for DrawdownAlertRule {
+    fn default() -> Self {
+        Self { is_enabled: true, thresholds: vec![5.0, 10.0] }
+    }
+}
+
+impl DrawdownAlertRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.thresholds.is_empty() || self.thresholds.len() > 10 {
+            return Err("Between 1 and 10 thresholds are required".to_string());
+        }
+        if self.thresholds.iter().any(|t| !(*t > 0.0 && *t < 100.0)) {
+            return Err("Thresholds must be between 0 and 100".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn load(conn: &Connection) -> Result<Self> {
+        let mut rows = conn
+            .prepare("SELECT is_enabled, thresholds FROM drawdown_alert_rules WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Self {
+                is_enabled: row.get::<i64>(0)? != 0,
+                thresholds: serde_json::from_str(&row.get::<String>(1)?).unwrap_or_default(),
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO drawdown_alert_rules (id, is_enabled, thresholds) VALUES ('default', ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET is_enabled = excluded.is_enabled, thresholds = excluded.thresholds",
+            params![self.is_enabled, serde_json::to_string(&self.thresholds)?],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Highest cumulative P&L seen and when; None `at` is the account start
+#[derive(Debug, Clone, PartialEq)]
+struct Peak {
+    pnl: f64,
+    at: Option<String>,
+}
+
+async fn load_peak(conn: &Connection) -> Result<Option<Peak>> {
+    let mut rows = conn
+        .prepare("SELECT peak_pnl, peak_at FROM drawdown_alert_rules WHERE id = 'default' AND peak_pnl IS NOT NULL")
+        .await?
+        .query(params![])
+        .await?;
+    Ok(match rows.next().await? {
+        Some(row) => Some(Peak { pnl: row.get(0)?, at: row.get(1)? }),
+        None => None,
+    })
+}
+
+async fn save_peak(conn: &Connection, rule: &DrawdownAlertRule, peak: &Peak) -> Result<()> {
+    conn.execute(
+        "INSERT INTO drawdown_alert_rules (id, is_enabled, thresholds, peak_pnl, peak_at) VALUES ('default', ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET peak_pnl = excluded.peak_pnl, peak_at = excluded.peak_at",
+        params![rule.is_enabled, serde_json::to_string(&rule.thresholds)?, peak.pnl, peak.at.clone()],
+    )
+    .await?;
+    Ok(())
+}
+
+/// Equity against its peak at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquitySnapshot {
+    pub account_size: f64,
+    pub realized_pnl: f64,
+    /// None when no open position could be priced
+    pub unrealized_pnl: Option<f64>,
+    pub equity: f64,
+    pub peak_equity: f64,
+    /// Date or time equity set its peak, None while it has never risen above the account size
+    pub peak_at: Option<String>,
+    pub drawdown_pct: f64,
+}
+
+/// The peak is the highest of the stored peak, the realized daily curve, and current equity
+fn snapshot(account_size: f64, daily_pnl: &[(String, f64)], unrealized_pnl: Option<f64>, stored: Option<Peak>, now: &str) -> (EquitySnapshot, Peak) {
+    let mut peak = stored.unwrap_or(Peak { pnl: 0.0, at: None });
+    let mut realized_pnl = 0.0;
+    for (date, pnl) in daily_pnl {
+        realized_pnl += pnl;
+        if realized_pnl > peak.pnl {
+            peak = Peak { pnl: realized_pnl, at: Some(date.clone()) };
+        }
+    }
+    let total_pnl = realized_pnl + unrealized_pnl.unwrap_or(0.0);
+    if total_pnl > peak.pnl {
+        peak = Peak { pnl: total_pnl, at: Some(now.to_string()) };
+    }
+
+    let equity = account_size + total_pnl;
+    let peak_equity = account_size + peak.pnl;
+    let drawdown_pct = if peak_equity > 0.0 { ((peak_equity - equity) / peak_equity * 100.0).max(0.0) } else { 0.0 };
+    let snapshot = EquitySnapshot { account_size, realized_pnl, unrealized_pnl, equity, peak_equity, peak_at: peak.at.clone(), drawdown_pct };
+    (snapshot, peak)
+}
+
+/// Deepest threshold reached that no alert in this episode has covered yet
+fn next_threshold(thresholds: &[f64], drawdown_pct: f64, alerted: &[f64]) -> Option<f64> {
+    thresholds
+        .iter()
+        .copied()
+        .filter(|t| drawdown_pct >= *t && !alerted.iter().any(|a| a >= t))
+        .max_by(|a, b| a.total_cmp(b))
+}
+
+async fn equity_snapshot(conn: &Connection, market: Option<&MarketClient>) -> Result<Option<(EquitySnapshot, Peak)>> {
+    let Some(account_size) = SizingRule::load(conn).await?.account_size else { return Ok(None) };
+    let (time_condition, time_params) = TimeRange::AllTime.to_sql_condition();
+    let daily_pnl = fetch_daily_pnl(conn, &time_condition, &time_params).await?;
+    let unrealized_pnl = open_positions(conn, market).await?.unrealized_pnl;
+    let stored = load_peak(conn).await?;
+    Ok(Some(snapshot(account_size, &daily_pnl, unrealized_pnl, stored, &Utc::now().to_rfc3339())))
+}
+
+/// Current equity and drawdown; None until an account size is set
+pub async fn current_drawdown(conn: &Connection, market: Option<&MarketClient>) -> Result<Option<EquitySnapshot>> {
+    Ok(equity_snapshot(conn, market).await?.map(|(snapshot, _)| snapshot))
+}
+
+/// One threshold crossing, kept for the behavior and risk views
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrawdownEvent {
+    pub id: String,
+    pub threshold_pct: f64,
+    pub drawdown_pct: f64,
+    pub equity: f64,
+    pub peak_equity: f64,
+    pub peak_at: Option<String>,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: Option<f64>,
+    pub created_at: String,
+}
+
+async fn alerted_since_peak(conn: &Connection, peak_at: Option<&str>) -> Result<Vec<f64>> {
+    let mut rows = conn
+        .prepare("SELECT threshold_pct FROM drawdown_events WHERE peak_at = ?")
+        .await?
+        .query(params![peak_at.unwrap_or_default()])
+        .await?;
+    let mut thresholds = Vec::new();
+    while let Some(row) = rows.next().await? {
+        thresholds.push(row.get::<f64>(0)?);
+    }
+    Ok(thresholds)
+}
+
+/// Update the peak and record an event when a new threshold is crossed. Returns None when
+/// alerts are off, no account size is set, or nothing new was crossed.
+pub async fn check_drawdown(conn: &Connection, market: Option<&MarketClient>) -> Result<Option<DrawdownEvent>> {
+    let rule = DrawdownAlertRule::load(conn).await?;
+    if !rule.is_enabled {
+        return Ok(None);
+    }
+    let Some((snapshot, peak)) = equity_snapshot(conn, market).await? else { return Ok(None) };
+    save_peak(conn, &rule, &peak).await?;
+
+    let alerted = alerted_since_peak(conn, snapshot.peak_at.as_deref()).await?;
+    let Some(threshold_pct) = next_threshold(&rule.thresholds, snapshot.drawdown_pct, &alerted) else { return Ok(None) };
+
+    let event = DrawdownEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        threshold_pct,
+        drawdown_pct: snapshot.drawdown_pct,
+        equity: snapshot.equity,
+        peak_equity: snapshot.peak_equity,
+        peak_at: snapshot.peak_at.clone(),
+        realized_pnl: snapshot.realized_pnl,
+        unrealized_pnl: snapshot.unrealized_pnl,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    conn.execute(
+        "INSERT INTO drawdown_events (id, threshold_pct, drawdown_pct, equity, peak_equity, peak_at, realized_pnl, unrealized_pnl, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        params![
+            event.id.clone(),
+            event.threshold_pct,
+            event.drawdown_pct,
+            event.equity,
+            event.peak_equity,
+            event.peak_at.clone().unwrap_or_default(),
+            event.realized_pnl,
+            event.unrealized_pnl,
+            event.created_at.clone()
+        ],
+    )
+    .await?;
+    Ok(Some(event))
+}
+
+/// Crossings on or after `since` (YYYY-MM-DD), newest first
+pub async fn list_events(conn: &Connection, since: &str) -> Result<Vec<DrawdownEvent>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, threshold_pct, drawdown_pct, equity, peak_equity, peak_at, realized_pnl, unrealized_pnl, created_at \
+             FROM drawdown_events WHERE created_at >= ? ORDER BY created_at DESC",
+        )
+        .await?
+        .query(params![since])
+        .await?;
+    let mut events = Vec::new();
+    while let Some(row) = rows.next().await? {
+        events.push(DrawdownEvent {
+            id: row.get(0)?,
+            threshold_pct: row.get(1)?,
+            drawdown_pct: row.get(2)?,
+            equity: row.get(3)?,
+            peak_equity: row.get(4)?,
+            peak_at: row.get::<String>(5).ok().filter(|at| !at.is_empty()),
+            realized_pnl: row.get(6)?,
+            unrealized_pnl: row.get(7)?,
+            created_at: row.get(8)?,
+        });
+    }
+    Ok(events)
+}
+
+/// Push and websocket alert for a crossing
+pub async fn notify_drawdown(
+    conn: &Connection,
+    web_push: &WebPushConfig,
+    ws_manager: &Arc<Mutex<ConnectionManager>>,
+    user_id: &str,
+    event: &DrawdownEvent,
+) {
+    let body = format!(
+        "Equity is {:.1}% below its peak (${:.2} vs ${:.2}), past your {:.0}% drawdown alert. Review open risk before adding to it.",
+        event.drawdown_pct, event.equity, event.peak_equity, event.threshold_pct
+    );
+    let payload = PushPayload {
+        title: format!("Drawdown past {:.0}%", event.threshold_pct),
+        body: Some(body.clone()),
+        icon: None,
+        url: Some("/app/analytics".to_string()),
+        tag: Some(format!("drawdown-{}", event.threshold_pct)),
+        data: Some(serde_json::json!({"type": "drawdown", "event_id": event.id})),
+    };
+    if let Err(e) = PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+        warn!("Failed to send drawdown alert to user {}: {}", user_id, e);
+    }
+
+    let envelope = WsMessage::new(EventType::DrawdownAlert, serde_json::json!({"event": event, "message": body}));
+    ws_manager.lock().await.broadcast_to_user(user_id, envelope);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days(values: &[f64]) -> Vec<(String, f64)> {
+        values.iter().enumerate().map(|(i, pnl)| (format!("2026-03-{:02}", i + 1), *pnl)).collect()
+    }
+
+    #[test]
+    fn test_snapshot_tracks_peak_including_unrealized() {
+        // Realized peak 12,000 on day 2, now 11,100 realized less 500 open
+        let (snap, peak) = snapshot(10_000.0, &days(&[1_000.0, 1_000.0, -900.0]), Some(-500.0), None, "now");
+        assert_eq!(snap.peak_equity, 12_000.0);
+        assert_eq!(snap.peak_at.as_deref(), Some("2026-03-02"));
+        assert!((snap.drawdown_pct - 1_400.0 / 120.0).abs() < 1e-9);
+
+        // A stored intraday peak above the realized curve still counts
+        let (snap, _) = snapshot(10_000.0, &days(&[1_000.0, 1_000.0, -900.0]), None, Some(Peak { pnl: 2_500.0, at: Some("t".into()) }), "now");
+        assert_eq!(snap.peak_equity, 12_500.0);
+
+        // Open gains above the old peak set a new one
+        let (snap, new_peak) = snapshot(10_000.0, &days(&[1_000.0, 1_000.0]), Some(300.0), Some(peak), "now");
+        assert_eq!(new_peak, Peak { pnl: 2_300.0, at: Some("now".into()) });
+        assert_eq!(snap.drawdown_pct, 0.0);
+    }
+
+    #[test]
+    fn test_next_threshold_alerts_once_per_episode() {
+        let thresholds = [5.0, 10.0];
+        assert_eq!(next_threshold(&thresholds, 4.0, &[]), None);
+        assert_eq!(next_threshold(&thresholds, 6.0, &[]), Some(5.0));
+        assert_eq!(next_threshold(&thresholds, 7.0, &[5.0]), None);
+        assert_eq!(next_threshold(&thresholds, 12.0, &[5.0]), Some(10.0));
+        assert_eq!(next_threshold(&thresholds, 6.0, &[10.0]), None);
+        assert!(DrawdownAlertRule { is_enabled: true, thresholds: vec![150.0] }.validate().is_err());
+    }
+}
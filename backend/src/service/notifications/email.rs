@@ -0,0 +1,53 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::turso::config::EmailConfig;
+
+const RESEND_API_URL: &str = "https://api.resend.com/emails";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EmailMessage {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    pub html: Option<String>,
+}
+
+/// Send a transactional email through Resend
+pub async fn send_email(config: &EmailConfig, message: &EmailMessage) -> Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(15))
+        .build()?;
+
+    let mut body = serde_json::json!({
+        "from": config.from_address,
+        "to": [message.to],
+        "subject": message.subject,
+        "text": message.text,
+    });
+    if let Some(html) = &message.html {
+        body["html"] = serde_json::Value::String(html.clone());
+    }
+
+    let response = client
+        .post(RESEND_API_URL)
+        .bearer_auth(&config.resend_api_key)
+        .json(&body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        anyhow::bail!("Resend returned {}: {}", status, text.chars().take(200).collect::<String>());
+    }
+    Ok(())
+}
+
+/// Minimal HTML escaping for user/AI generated text embedded in emails
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
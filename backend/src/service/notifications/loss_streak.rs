@@ -0,0 +1,241 @@
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::push::{PushPayload, PushService};
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::turso::config::WebPushConfig;
+use crate::websocket::{ConnectionManager, EventType, WsMessage};
+
+/// Consecutive-loss circuit breaker: after N losing trades in a row within one trading
+/// day, tell the trader to step away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LossStreakRule {
+    pub is_enabled: bool,
+    pub max_consecutive_losses: u32,
+}
+
+impl Default for LossStreakRule {
+    fn default() -> Self {
+        Self { is_enabled: true, max_consecutive_losses: 3 }
+    }
+}
+
+impl LossStreakRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(2..=20).contains(&self.max_consecutive_losses) {
+            return Err("max_consecutive_losses must be between 2 and 20".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn load(conn: &Connection) -> Result<Self> {
+        let mut rows = conn
+            .prepare("SELECT is_enabled, max_consecutive_losses FROM loss_streak_rules WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Self {
+                is_enabled: row.get::<i64>(0)? != 0,
+                max_consecutive_losses: row.get::<i64>(1)?.max(0) as u32,
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO loss_streak_rules (id, is_enabled, max_consecutive_losses) VALUES ('default', ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET is_enabled = excluded.is_enabled, max_consecutive_losses = excluded.max_consecutive_losses",
+            params![self.is_enabled, self.max_consecutive_losses as i64],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// One trip of the circuit breaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LossStreakEvent {
+    pub id: String,
+    pub trade_date: String,
+    pub consecutive_losses: u32,
+    pub threshold: u32,
+    pub realized_loss: f64,
+    pub created_at: String,
+}
+
+/// Losing trades in a row at the end of a day's P&L sequence (oldest first)
+fn trailing_losses(pnls: &[f64]) -> (u32, f64) {
+    let losses: Vec<f64> = pnls.iter().rev().take_while(|pnl| **pnl < 0.0).copied().collect();
+    (losses.len() as u32, losses.iter().sum())
+}
+
+/// Record a trip when today's closed trades end in a long enough losing streak. Returns
+/// None when the rule is off, the streak is short, or the breaker already tripped today.
+pub async fn check_loss_streak(conn: &Connection) -> Result<Option<LossStreakEvent>> {
+    let rule = LossStreakRule::load(conn).await?;
+    if !rule.is_enabled {
+        return Ok(None);
+    }
+
+    let periods = PeriodDefinition::load(conn).await;
+    let trade_date = periods.trading_date(Utc::now()).format("%Y-%m-%d").to_string();
+    let day = format!("date({})", periods.trading_date_sql("exit_date"));
+    let sql = format!(
+        "SELECT pnl FROM (
+            SELECT exit_date, {STOCK_PNL_SQL} AS pnl FROM stocks
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 AND {day} = ?1
+            UNION ALL
+            SELECT exit_date, {OPTION_PNL_SQL} AS pnl FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0 AND {day} = ?1
+        ) ORDER BY exit_date ASC"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![trade_date.clone()]).await?;
+    let mut pnls = Vec::new();
+    while let Some(row) = rows.next().await? {
+        pnls.push(money::to_f64(money::row_decimal(&row, 0)));
+    }
+
+    let (consecutive_losses, realized_loss) = trailing_losses(&pnls);
+    if consecutive_losses < rule.max_consecutive_losses {
+        return Ok(None);
+    }
+
+    let event = LossStreakEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        trade_date,
+        consecutive_losses,
+        threshold: rule.max_consecutive_losses,
+        realized_loss,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    // The unique trade_date index keeps this to one alert per day
+    let inserted = conn
+        .execute(
+            "INSERT INTO loss_streak_events (id, trade_date, consecutive_losses, threshold, realized_loss, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?) ON CONFLICT(trade_date) DO NOTHING",
+            params![
+                event.id.clone(),
+                event.trade_date.clone(),
+                event.consecutive_losses as i64,
+                event.threshold as i64,
+                event.realized_loss,
+                event.created_at.clone()
+            ],
+        )
+        .await?;
+    Ok((inserted > 0).then_some(event))
+}
+
+/// Trips on or after `since` (YYYY-MM-DD), newest first
+pub async fn list_events(conn: &Connection, since: &str) -> Result<Vec<LossStreakEvent>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, trade_date, consecutive_losses, threshold, realized_loss, created_at \
+             FROM loss_streak_events WHERE trade_date >= ? ORDER BY trade_date DESC",
+        )
+        .await?
+        .query(params![since])
+        .await?;
+    let mut events = Vec::new();
+    while let Some(row) = rows.next().await? {
+        events.push(LossStreakEvent {
+            id: row.get(0)?,
+            trade_date: row.get(1)?,
+            consecutive_losses: row.get::<i64>(2)? as u32,
+            threshold: row.get::<i64>(3)? as u32,
+            realized_loss: money::to_f64(money::row_decimal(&row, 4)),
+            created_at: row.get(5)?,
+        });
+    }
+    Ok(events)
+}
+
+async fn primary_goal(conn: &Connection) -> Option<String> {
+    let mut rows = conn
+        .prepare("SELECT primary_trading_goal FROM user_profile LIMIT 1")
+        .await
+        .ok()?
+        .query(params![])
+        .await
+        .ok()?;
+    let row = rows.next().await.ok()??;
+    row.get::<Option<String>>(0).ok().flatten().filter(|goal| !goal.trim().is_empty())
+}
+
+/// Push and websocket "step away" alert for a trip
+pub async fn notify_loss_streak(
+    conn: &Connection,
+    web_push: &WebPushConfig,
+    ws_manager: &Arc<Mutex<ConnectionManager>>,
+    user_id: &str,
+    event: &LossStreakEvent,
+) {
+    let mut body = format!(
+        "{} losing trades in a row today (${:.2}). Step away from the screen before the next trade.",
+        event.consecutive_losses,
+        event.realized_loss.abs()
+    );
+    if let Some(goal) = primary_goal(conn).await {
+        body.push_str(&format!(" Your goal: {}", goal));
+    }
+
+    let payload = PushPayload {
+        title: "Time to step away".to_string(),
+        body: Some(body.clone()),
+        icon: None,
+        url: Some("/app/analytics".to_string()),
+        tag: Some(format!("loss-streak-{}", event.trade_date)),
+        data: Some(serde_json::json!({"type": "loss_streak", "event_id": event.id})),
+    };
+    if let Err(e) = PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+        warn!("Failed to send loss streak alert to user {}: {}", user_id, e);
+    }
+
+    let envelope = WsMessage::new(
+        EventType::LossStreakAlert,
+        serde_json::json!({"event": event, "message": body}),
+    );
+    ws_manager.lock().await.broadcast_to_user(user_id, envelope);
+}
+
+/// Check the streak after a trade is closed and alert on a trip, off the request path
+pub fn spawn_loss_streak_check(
+    conn: Connection,
+    user_id: String,
+    web_push: WebPushConfig,
+    ws_manager: Arc<Mutex<ConnectionManager>>,
+) {
+    tokio::spawn(async move {
+        match check_loss_streak(&conn).await {
+            Ok(Some(event)) => {
+                info!("Loss streak circuit breaker tripped for user {}: {} losses", user_id, event.consecutive_losses);
+                notify_loss_streak(&conn, &web_push, &ws_manager, &user_id, &event).await;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Loss streak check failed for user {}: {}", user_id, e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trailing_losses_stop_at_last_non_loss() {
+        assert_eq!(trailing_losses(&[-10.0, 25.0, -5.0, -7.5, -2.5]), (3, -15.0));
+        assert_eq!(trailing_losses(&[-10.0, -5.0, 0.0]), (0, 0.0));
+        assert_eq!(trailing_losses(&[]), (0, 0.0));
+        assert!(LossStreakRule::default().validate().is_ok());
+        assert!(LossStreakRule { is_enabled: true, max_consecutive_losses: 1 }.validate().is_err());
+    }
+}
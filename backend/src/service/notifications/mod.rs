@@ -1,3 +1,8 @@
 pub mod push;
 pub mod price_alert;
-
+pub mod chat_webhooks;
+pub mod email;
+pub mod loss_streak;
+pub mod drawdown_alert;
+pub mod overtrading;
+pub mod daily_digest;
@@ -0,0 +1,368 @@
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, params_from_iter, Connection, Value};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use super::push::{PushPayload, PushService};
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::stock::stocks::TimeRange;
+use crate::turso::config::WebPushConfig;
+use crate::websocket::{ConnectionManager, EventType, WsMessage};
+
+/// Soft limit on overtrading: at most N trades opened per trading day. Going over never
+/// blocks a trade; it raises one alert that day and counts as a breach in the analytics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OvertradingRule {
+    pub is_enabled: bool,
+    pub max_trades_per_day: u32,
+}
+
+impl Default for OvertradingRule {
+    fn default() -> Self {
+        Self { is_enabled: false, max_trades_per_day: 10 }
+    }
+}
+
+impl OvertradingRule {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=200).contains(&self.max_trades_per_day) {
+            return Err("max_trades_per_day must be between 1 and 200".to_string());
+        }
+        Ok(())
+    }
+
+    pub async fn load(conn: &Connection) -> Result<Self> {
+        let mut rows = conn
+            .prepare("SELECT is_enabled, max_trades_per_day FROM overtrading_rules WHERE id = 'default'")
+            .await?
+            .query(params![])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Self {
+                is_enabled: row.get::<i64>(0)? != 0,
+                max_trades_per_day: row.get::<i64>(1)?.max(0) as u32,
+            }),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO overtrading_rules (id, is_enabled, max_trades_per_day) VALUES ('default', ?, ?) \
+             ON CONFLICT(id) DO UPDATE SET is_enabled = excluded.is_enabled, max_trades_per_day = excluded.max_trades_per_day",
+            params![self.is_enabled, self.max_trades_per_day as i64],
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+/// Trades opened so far in the current trading day, against the limit when one is set
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TradeCounter {
+    pub trade_date: String,
+    pub trades: u32,
+    pub max_trades_per_day: Option<u32>,
+    pub remaining: Option<u32>,
+    pub breached: bool,
+}
+
+impl TradeCounter {
+    fn new(trade_date: String, trades: u32, rule: &OvertradingRule) -> Self {
+        let limit = rule.is_enabled.then_some(rule.max_trades_per_day);
+        Self {
+            trade_date,
+            trades,
+            max_trades_per_day: limit,
+            remaining: limit.map(|limit| limit.saturating_sub(trades)),
+            breached: limit.is_some_and(|limit| trades > limit),
+        }
+    }
+}
+
+/// Count today's trades by entry date, both asset classes, manual and imported alike
+pub async fn intraday_counter(conn: &Connection) -> Result<TradeCounter> {
+    let rule = OvertradingRule::load(conn).await?;
+    let periods = PeriodDefinition::load(conn).await;
+    let trade_date = periods.trading_date(Utc::now()).format("%Y-%m-%d").to_string();
+    let day = format!("date({})", periods.trading_date_sql("entry_date"));
+    let sql = format!(
+        "SELECT (SELECT COUNT(*) FROM stocks WHERE is_deleted = 0 AND entry_date IS NOT NULL AND {day} = ?1) \
+              + (SELECT COUNT(*) FROM options WHERE is_deleted = 0 AND entry_date IS NOT NULL AND {day} = ?1)"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![trade_date.clone()]).await?;
+    let trades = match rows.next().await? {
+        Some(row) => row.get::<i64>(0)?.max(0) as u32,
+        None => 0,
+    };
+    Ok(TradeCounter::new(trade_date, trades, &rule))
+}
+
+/// First breach of a trading day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OvertradingEvent {
+    pub id: String,
+    pub trade_date: String,
+    /// Highest count reached that day
+    pub trade_count: u32,
+    pub threshold: u32,
+    pub created_at: String,
+}
+
+/// Record a breached counter. Returns the event only for the day's first breach; later
+/// trades that day just raise the stored count.
+async fn record_breach(conn: &Connection, counter: &TradeCounter) -> Result<Option<OvertradingEvent>> {
+    let Some(threshold) = counter.max_trades_per_day.filter(|_| counter.breached) else {
+        return Ok(None);
+    };
+    let event = OvertradingEvent {
+        id: uuid::Uuid::new_v4().to_string(),
+        trade_date: counter.trade_date.clone(),
+        trade_count: counter.trades,
+        threshold,
+        created_at: Utc::now().to_rfc3339(),
+    };
+    // The unique trade_date index keeps this to one alert per day
+    let inserted = conn
+        .execute(
+            "INSERT INTO overtrading_events (id, trade_date, trade_count, threshold, created_at) \
+             VALUES (?, ?, ?, ?, ?) ON CONFLICT(trade_date) DO NOTHING",
+            params![
+                event.id.clone(),
+                event.trade_date.clone(),
+                event.trade_count as i64,
+                event.threshold as i64,
+                event.created_at.clone()
+            ],
+        )
+        .await?;
+    if inserted > 0 {
+        return Ok(Some(event));
+    }
+    conn.execute(
+        "UPDATE overtrading_events SET trade_count = MAX(trade_count, ?) WHERE trade_date = ?",
+        params![event.trade_count as i64, event.trade_date],
+    )
+    .await?;
+    Ok(None)
+}
+
+/// Breaches on or after `since` (YYYY-MM-DD), newest first
+pub async fn list_events(conn: &Connection, since: &str) -> Result<Vec<OvertradingEvent>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, trade_date, trade_count, threshold, created_at \
+             FROM overtrading_events WHERE trade_date >= ? ORDER BY trade_date DESC",
+        )
+        .await?
+        .query(params![since])
+        .await?;
+    let mut events = Vec::new();
+    while let Some(row) = rows.next().await? {
+        events.push(OvertradingEvent {
+            id: row.get(0)?,
+            trade_date: row.get(1)?,
+            trade_count: row.get::<i64>(2)? as u32,
+            threshold: row.get::<i64>(3)? as u32,
+            created_at: row.get(4)?,
+        });
+    }
+    Ok(events)
+}
+
+/// Push and websocket alert for the day's first breach
+async fn notify_overtrading(
+    conn: &Connection,
+    web_push: &WebPushConfig,
+    ws_manager: &Arc<Mutex<ConnectionManager>>,
+    user_id: &str,
+    event: &OvertradingEvent,
+) {
+    let body = format!(
+        "That's trade {} today, over your limit of {}. Check the next one is in your plan.",
+        event.trade_count, event.threshold
+    );
+    let payload = PushPayload {
+        title: "Trade limit reached".to_string(),
+        body: Some(body.clone()),
+        icon: None,
+        url: Some("/app/analytics".to_string()),
+        tag: Some(format!("overtrading-{}", event.trade_date)),
+        data: Some(serde_json::json!({"type": "overtrading", "event_id": event.id})),
+    };
+    if let Err(e) = PushService::new(conn, web_push).send_to_user(user_id, &payload).await {
+        warn!("Failed to send overtrading alert to user {}: {}", user_id, e);
+    }
+
+    let envelope = WsMessage::new(EventType::OvertradingAlert, serde_json::json!({"event": event, "message": body}));
+    ws_manager.lock().await.broadcast_to_user(user_id, envelope);
+}
+
+/// Push the live counter to the user's sockets after trades are created, and alert on the
+/// day's first breach, off the request path
+pub fn spawn_trade_counter_update(
+    conn: Connection,
+    user_id: String,
+    web_push: WebPushConfig,
+    ws_manager: Arc<Mutex<ConnectionManager>>,
+) {
+    tokio::spawn(async move {
+        let counter = match intraday_counter(&conn).await {
+            Ok(counter) => counter,
+            Err(e) => {
+                warn!("Trade counter update failed for user {}: {}", user_id, e);
+                return;
+            }
+        };
+        ws_manager
+            .lock()
+            .await
+            .broadcast_to_user(&user_id, WsMessage::new(EventType::TradeCounterUpdated, serde_json::json!(counter)));
+
+        match record_breach(&conn, &counter).await {
+            Ok(Some(event)) => {
+                info!("Overtrading limit breached for user {}: {} trades", user_id, event.trade_count);
+                notify_overtrading(&conn, &web_push, &ws_manager, &user_id, &event).await;
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Failed to record overtrading breach for user {}: {}", user_id, e),
+        }
+    });
+}
+
+/// How often and how expensively the trader went over their daily limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OvertradingStats {
+    pub max_trades_per_day: u32,
+    pub trading_days: u32,
+    pub breach_days: u32,
+    /// Percent of trading days over the limit
+    pub breach_rate: f64,
+    pub max_trades_in_a_day: u32,
+    /// Trades opened after the limit was already reached
+    pub trades_over_limit: u32,
+    /// Realized P&L of those trades
+    pub pnl_over_limit: f64,
+    pub avg_daily_pnl_breach_days: f64,
+    pub avg_daily_pnl_other_days: f64,
+}
+
+/// Breach statistics from each day's trades in entry order; `None` is an open trade
+fn summarize(days: &[Vec<Option<f64>>], limit: u32) -> OvertradingStats {
+    let mut stats = OvertradingStats {
+        max_trades_per_day: limit,
+        trading_days: days.len() as u32,
+        breach_days: 0,
+        breach_rate: 0.0,
+        max_trades_in_a_day: 0,
+        trades_over_limit: 0,
+        pnl_over_limit: 0.0,
+        avg_daily_pnl_breach_days: 0.0,
+        avg_daily_pnl_other_days: 0.0,
+    };
+    let (mut breach_pnl, mut other_pnl) = (0.0, 0.0);
+    for trades in days {
+        let count = trades.len() as u32;
+        let day_pnl: f64 = trades.iter().flatten().sum();
+        stats.max_trades_in_a_day = stats.max_trades_in_a_day.max(count);
+        if count > limit {
+            stats.breach_days += 1;
+            stats.trades_over_limit += count - limit;
+            stats.pnl_over_limit += trades[limit as usize..].iter().flatten().sum::<f64>();
+            breach_pnl += day_pnl;
+        } else {
+            other_pnl += day_pnl;
+        }
+    }
+    let other_days = stats.trading_days - stats.breach_days;
+    if stats.trading_days > 0 {
+        stats.breach_rate = stats.breach_days as f64 / stats.trading_days as f64 * 100.0;
+    }
+    if stats.breach_days > 0 {
+        stats.avg_daily_pnl_breach_days = breach_pnl / stats.breach_days as f64;
+    }
+    if other_days > 0 {
+        stats.avg_daily_pnl_other_days = other_pnl / other_days as f64;
+    }
+    stats
+}
+
+/// Breach statistics over the range against the current limit, by trading day of entry.
+/// None when no limit is set.
+pub async fn breach_stats(conn: &Connection, time_range: &TimeRange) -> Result<Option<OvertradingStats>> {
+    let rule = OvertradingRule::load(conn).await?;
+    if !rule.is_enabled {
+        return Ok(None);
+    }
+
+    let periods = PeriodDefinition::load(conn).await;
+    let (start, end) = time_range.to_dates();
+    let mut range = String::new();
+    let mut range_params = Vec::new();
+    if let Some(start) = start {
+        range.push_str(" AND entry_date >= ?");
+        range_params.push(Value::Text(start.to_rfc3339()));
+    }
+    if let Some(end) = end {
+        range.push_str(" AND entry_date <= ?");
+        range_params.push(Value::Text(end.to_rfc3339()));
+    }
+    let day = format!("date({})", periods.trading_date_sql("entry_date"));
+    let sql = format!(
+        "SELECT day, pnl FROM (
+            SELECT {day} AS day, entry_date, id,
+                CASE WHEN exit_price IS NOT NULL AND exit_date IS NOT NULL THEN {STOCK_PNL_SQL} END AS pnl
+            FROM stocks WHERE is_deleted = 0 AND entry_date IS NOT NULL{range}
+            UNION ALL
+            SELECT {day} AS day, entry_date, id,
+                CASE WHEN status = 'closed' AND exit_price IS NOT NULL THEN {OPTION_PNL_SQL} END AS pnl
+            FROM options WHERE is_deleted = 0 AND entry_date IS NOT NULL{range}
+        ) ORDER BY day, entry_date, id"
+    );
+    let params: Vec<Value> = range_params.iter().chain(range_params.iter()).cloned().collect();
+    let mut rows = conn.prepare(&sql).await?.query(params_from_iter(params)).await?;
+
+    let mut days: Vec<Vec<Option<f64>>> = Vec::new();
+    let mut current_day: Option<String> = None;
+    while let Some(row) = rows.next().await? {
+        let day: String = row.get(0)?;
+        if current_day.as_deref() != Some(day.as_str()) {
+            days.push(Vec::new());
+            current_day = Some(day);
+        }
+        if let Some(trades) = days.last_mut() {
+            trades.push(money::to_f64_opt(money::row_decimal_opt(&row, 1)));
+        }
+    }
+    Ok(Some(summarize(&days, rule.max_trades_per_day)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counter_and_breach_summary() {
+        let rule = OvertradingRule { is_enabled: true, max_trades_per_day: 3 };
+        let counter = TradeCounter::new("2025-03-04".into(), 4, &rule);
+        assert_eq!((counter.remaining, counter.breached), (Some(0), true));
+        let off = TradeCounter::new("2025-03-04".into(), 40, &OvertradingRule::default());
+        assert_eq!((off.max_trades_per_day, off.breached), (None, false));
+
+        let days = vec![
+            vec![Some(50.0), Some(-20.0)],
+            vec![Some(100.0), Some(-10.0), Some(5.0), Some(-40.0), None, Some(-30.0)],
+        ];
+        let stats = summarize(&days, 3);
+        assert_eq!((stats.breach_days, stats.trades_over_limit, stats.max_trades_in_a_day), (1, 3, 6));
+        assert_eq!(stats.pnl_over_limit, -70.0);
+        assert_eq!((stats.avg_daily_pnl_breach_days, stats.avg_daily_pnl_other_days), (25.0, 30.0));
+        assert_eq!(stats.breach_rate, 50.0);
+        assert!(OvertradingRule { is_enabled: true, max_trades_per_day: 0 }.validate().is_err());
+    }
+}
@@ -0,0 +1,287 @@
+// Guided onboarding checklist. Each setup step is pending until the user completes or
+// skips it. Completion is detected from the user's data whenever progress is read (a real
+// trade, a playbook, a connected broker, a chat message) and recorded, so a step stays
+// done even if the data behind it is later deleted. The server also picks the next best
+// action, so every device shows the same checklist and the same suggestion.
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnboardingStep {
+    FirstTrade,
+    FirstPlaybook,
+    BrokerLink,
+    FirstAiChat,
+}
+
+impl OnboardingStep {
+    /// Checklist order
+    pub const ALL: [OnboardingStep; 4] =
+        [OnboardingStep::FirstTrade, OnboardingStep::FirstPlaybook, OnboardingStep::BrokerLink, OnboardingStep::FirstAiChat];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnboardingStep::FirstTrade => "first_trade",
+            OnboardingStep::FirstPlaybook => "first_playbook",
+            OnboardingStep::BrokerLink => "broker_link",
+            OnboardingStep::FirstAiChat => "first_ai_chat",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|step| step.as_str() == value)
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            OnboardingStep::FirstTrade => "Log your first trade",
+            OnboardingStep::FirstPlaybook => "Create a playbook",
+            OnboardingStep::BrokerLink => "Connect your broker",
+            OnboardingStep::FirstAiChat => "Ask the AI about your trading",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            OnboardingStep::FirstTrade => "Add a stock or option trade by hand, or import one from a broker statement.",
+            OnboardingStep::FirstPlaybook => "Write down a setup you trade so trades can be tagged and compared against it.",
+            OnboardingStep::BrokerLink => "Link a brokerage account to import your history and keep new trades in sync.",
+            OnboardingStep::FirstAiChat => "Start a chat to get answers grounded in your own journal.",
+        }
+    }
+
+    /// Whether the user's data already shows this step done. Demo data does not count.
+    fn detection_sql(&self) -> &'static str {
+        match self {
+            OnboardingStep::FirstTrade => {
+                "SELECT EXISTS (SELECT 1 FROM stocks WHERE is_deleted = 0 AND CAST(id AS TEXT) NOT IN \
+                    (SELECT row_id FROM demo_data_rows WHERE table_name = 'stocks')) \
+                 OR EXISTS (SELECT 1 FROM options WHERE is_deleted = 0 AND CAST(id AS TEXT) NOT IN \
+                    (SELECT row_id FROM demo_data_rows WHERE table_name = 'options'))"
+            }
+            OnboardingStep::FirstPlaybook => {
+                "SELECT EXISTS (SELECT 1 FROM playbook WHERE id NOT IN \
+                    (SELECT row_id FROM demo_data_rows WHERE table_name = 'playbook'))"
+            }
+            OnboardingStep::BrokerLink => "SELECT EXISTS (SELECT 1 FROM brokerage_connections WHERE status = 'connected')",
+            OnboardingStep::FirstAiChat => "SELECT EXISTS (SELECT 1 FROM chat_messages WHERE role = 'user')",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Completed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepAction {
+    Complete,
+    Skip,
+    Reopen,
+}
+
+/// Allowed moves: pending -> completed/skipped, skipped -> completed/pending. Completed
+/// is final; repeating the current state is a no-op.
+pub fn transition(current: StepStatus, action: StepAction) -> Result<StepStatus, String> {
+    match (current, action) {
+        (StepStatus::Completed, StepAction::Complete) => Ok(StepStatus::Completed),
+        (StepStatus::Completed, _) => Err("Step is already completed".to_string()),
+        (_, StepAction::Complete) => Ok(StepStatus::Completed),
+        (StepStatus::Pending, StepAction::Skip) | (StepStatus::Skipped, StepAction::Skip) => Ok(StepStatus::Skipped),
+        (StepStatus::Skipped, StepAction::Reopen) | (StepStatus::Pending, StepAction::Reopen) => Ok(StepStatus::Pending),
+    }
+}
+
+/// Suggested step: connecting a broker comes first while there are no trades, since the
+/// import also completes the first trade; otherwise the first pending step in order.
+pub fn next_best_action(statuses: &HashMap<OnboardingStep, StepStatus>) -> Option<OnboardingStep> {
+    let pending = |step: &OnboardingStep| statuses.get(step).copied().unwrap_or(StepStatus::Pending) == StepStatus::Pending;
+    if pending(&OnboardingStep::FirstTrade) && pending(&OnboardingStep::BrokerLink) {
+        return Some(OnboardingStep::BrokerLink);
+    }
+    OnboardingStep::ALL.into_iter().find(pending)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepProgress {
+    pub step: OnboardingStep,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub status: StepStatus,
+    /// "detected" or "manual"
+    pub source: Option<String>,
+    pub completed_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NextAction {
+    pub step: OnboardingStep,
+    pub title: &'static str,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OnboardingProgress {
+    pub steps: Vec<StepProgress>,
+    pub completed: u32,
+    pub total: u32,
+    /// Every step is completed or skipped
+    pub finished: bool,
+    pub next_action: Option<NextAction>,
+}
+
+struct StoredStep {
+    status: StepStatus,
+    source: String,
+    completed_at: Option<String>,
+}
+
+async fn stored_steps(conn: &Connection) -> Result<HashMap<OnboardingStep, StoredStep>> {
+    let mut rows = conn
+        .prepare("SELECT step, status, source, completed_at FROM onboarding_steps")
+        .await?
+        .query(params![])
+        .await?;
+    let mut steps = HashMap::new();
+    while let Some(row) = rows.next().await? {
+        let Some(step) = OnboardingStep::parse(&row.get::<String>(0)?) else { continue };
+        let status = match row.get::<String>(1)?.as_str() {
+            "completed" => StepStatus::Completed,
+            "skipped" => StepStatus::Skipped,
+            _ => continue,
+        };
+        steps.insert(step, StoredStep { status, source: row.get(2)?, completed_at: row.get(3)? });
+    }
+    Ok(steps)
+}
+
+async fn save_step(conn: &Connection, step: OnboardingStep, status: StepStatus, source: &str) -> Result<()> {
+    let now = Utc::now().to_rfc3339();
+    match status {
+        StepStatus::Pending => {
+            conn.execute("DELETE FROM onboarding_steps WHERE step = ?", params![step.as_str()]).await?;
+        }
+        StepStatus::Completed | StepStatus::Skipped => {
+            let (label, completed_at) = match status {
+                StepStatus::Completed => ("completed", Some(now.clone())),
+                _ => ("skipped", None),
+            };
+            conn.execute(
+                "INSERT INTO onboarding_steps (step, status, source, completed_at, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(step) DO UPDATE SET status = excluded.status, source = excluded.source, \
+                 completed_at = excluded.completed_at, updated_at = excluded.updated_at",
+                params![step.as_str(), label, source, completed_at, now.clone(), now],
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Record steps the user's data shows as done; returns whether anything changed
+async fn record_detected(conn: &Connection, stored: &HashMap<OnboardingStep, StoredStep>) -> Result<bool> {
+    let mut changed = false;
+    for step in OnboardingStep::ALL {
+        if stored.get(&step).is_some_and(|s| s.status == StepStatus::Completed) {
+            continue;
+        }
+        let mut rows = conn.prepare(step.detection_sql()).await?.query(params![]).await?;
+        let done = match rows.next().await? {
+            Some(row) => row.get::<i64>(0)? != 0,
+            None => false,
+        };
+        if done {
+            save_step(conn, step, StepStatus::Completed, "detected").await?;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+/// Current checklist, after recording anything newly detected. The flag is set when
+/// detection changed a step, so callers can notify the user's other devices.
+pub async fn progress(conn: &Connection) -> Result<(OnboardingProgress, bool)> {
+    let mut stored = stored_steps(conn).await?;
+    let changed = record_detected(conn, &stored).await?;
+    if changed {
+        stored = stored_steps(conn).await?;
+    }
+
+    let statuses: HashMap<OnboardingStep, StepStatus> = stored.iter().map(|(step, s)| (*step, s.status)).collect();
+    let steps: Vec<StepProgress> = OnboardingStep::ALL
+        .into_iter()
+        .map(|step| {
+            let saved = stored.get(&step);
+            StepProgress {
+                step,
+                title: step.title(),
+                description: step.description(),
+                status: saved.map_or(StepStatus::Pending, |s| s.status),
+                source: saved.map(|s| s.source.clone()),
+                completed_at: saved.and_then(|s| s.completed_at.clone()),
+            }
+        })
+        .collect();
+    let completed = steps.iter().filter(|s| s.status == StepStatus::Completed).count() as u32;
+    let next_action = next_best_action(&statuses).map(|step| NextAction { step, title: step.title(), description: step.description() });
+
+    Ok((
+        OnboardingProgress {
+            completed,
+            total: steps.len() as u32,
+            finished: steps.iter().all(|s| s.status != StepStatus::Pending),
+            next_action,
+            steps,
+        },
+        changed,
+    ))
+}
+
+pub async fn step_status(conn: &Connection, step: OnboardingStep) -> Result<StepStatus> {
+    Ok(stored_steps(conn).await?.get(&step).map_or(StepStatus::Pending, |s| s.status))
+}
+
+/// Save a status the user chose; check it with `transition` first
+pub async fn set_step(conn: &Connection, step: OnboardingStep, status: StepStatus) -> Result<()> {
+    save_step(conn, step, status, "manual").await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        assert_eq!(transition(StepStatus::Pending, StepAction::Skip), Ok(StepStatus::Skipped));
+        assert_eq!(transition(StepStatus::Skipped, StepAction::Complete), Ok(StepStatus::Completed));
+        assert_eq!(transition(StepStatus::Skipped, StepAction::Reopen), Ok(StepStatus::Pending));
+        assert_eq!(transition(StepStatus::Completed, StepAction::Complete), Ok(StepStatus::Completed));
+        assert!(transition(StepStatus::Completed, StepAction::Skip).is_err());
+        assert!(transition(StepStatus::Completed, StepAction::Reopen).is_err());
+    }
+
+    #[test]
+    fn test_next_best_action() {
+        let mut statuses = HashMap::new();
+        assert_eq!(next_best_action(&statuses), Some(OnboardingStep::BrokerLink));
+
+        statuses.insert(OnboardingStep::BrokerLink, StepStatus::Skipped);
+        assert_eq!(next_best_action(&statuses), Some(OnboardingStep::FirstTrade));
+
+        statuses.insert(OnboardingStep::FirstTrade, StepStatus::Completed);
+        statuses.insert(OnboardingStep::FirstPlaybook, StepStatus::Completed);
+        assert_eq!(next_best_action(&statuses), Some(OnboardingStep::FirstAiChat));
+
+        statuses.insert(OnboardingStep::FirstAiChat, StepStatus::Skipped);
+        assert_eq!(next_best_action(&statuses), None);
+    }
+}
@@ -0,0 +1,225 @@
+// Strategy recognizer for imported option legs. Brokers report a multi-leg order as one
+// fill per contract, and each fill lands in the journal as its own single-leg trade. Legs
+// on the same underlying and expiry entered within a few seconds of each other are grouped
+// and, when their shape matches a known strategy, tagged with that strategy_type and
+// linked in `option_strategy_legs`. Trades carry no long/short side, so recognition goes
+// by option types, strikes and contract counts alone; anything ambiguous is left as is.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use libsql::{params, params_from_iter, Connection, Value};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::money;
+use crate::models::timestamps;
+
+/// Fills of one order can be stamped a few seconds apart
+const LEG_WINDOW_SECONDS: i64 = 5;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Leg {
+    id: i64,
+    symbol: String,
+    call: bool,
+    strike: Decimal,
+    expiration: NaiveDate,
+    entry: DateTime<Utc>,
+    contracts: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecognizedStrategy {
+    pub group_id: String,
+    pub strategy: String,
+    pub symbol: String,
+    pub expiration_date: String,
+    pub option_ids: Vec<i64>,
+}
+
+/// Split legs into candidate orders: same underlying and expiry, entries within the window
+fn cluster(mut legs: Vec<Leg>) -> Vec<Vec<Leg>> {
+    legs.sort_by(|a, b| (&a.symbol, a.expiration, a.entry, a.id).cmp(&(&b.symbol, b.expiration, b.entry, b.id)));
+    let mut clusters: Vec<Vec<Leg>> = Vec::new();
+    for leg in legs {
+        match clusters.last_mut() {
+            Some(current)
+                if current[0].symbol == leg.symbol
+                    && current[0].expiration == leg.expiration
+                    && (leg.entry - current[0].entry).num_seconds() <= LEG_WINDOW_SECONDS =>
+            {
+                current.push(leg)
+            }
+            _ => clusters.push(vec![leg]),
+        }
+    }
+    clusters
+}
+
+/// Strategy name for a set of legs, or None when the shape isn't one we recognize
+fn classify(legs: &[Leg]) -> Option<&'static str> {
+    if legs.iter().any(|l| l.contracts != legs[0].contracts) {
+        return None;
+    }
+    let mut calls: Vec<Decimal> = legs.iter().filter(|l| l.call).map(|l| l.strike).collect();
+    let mut puts: Vec<Decimal> = legs.iter().filter(|l| !l.call).map(|l| l.strike).collect();
+    calls.sort();
+    puts.sort();
+    match (calls.as_slice(), puts.as_slice()) {
+        ([low, high], []) | ([], [low, high]) if low != high => Some("Vertical Spread"),
+        ([call], [put]) if call == put => Some("Straddle"),
+        ([_], [_]) => Some("Strangle"),
+        ([call_low, call_high], [put_low, put_high]) if put_low < put_high && call_low < call_high => {
+            if put_high < call_low {
+                Some("Iron Condor")
+            } else if put_high == call_low {
+                Some("Iron Butterfly")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Imported, live option trades not yet linked to a strategy
+async fn unlinked_imported_legs(conn: &Connection) -> Result<Vec<Leg>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, symbol, option_type, strike_price, expiration_date, entry_date, number_of_contracts FROM options
+             WHERE is_deleted = 0 AND brokerage_name IS NOT NULL
+             AND id NOT IN (SELECT option_id FROM option_strategy_legs)",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    let mut legs = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let (Ok(expiration), Ok(entry)) = (
+            timestamps::parse_stored(&row.get::<String>(4)?),
+            timestamps::parse_stored(&row.get::<String>(5)?),
+        ) else {
+            continue;
+        };
+        legs.push(Leg {
+            id: row.get(0)?,
+            symbol: row.get::<String>(1)?.trim().to_uppercase(),
+            call: row.get::<String>(2)?.eq_ignore_ascii_case("call"),
+            strike: money::row_decimal(&row, 3),
+            expiration: expiration.date_naive(),
+            entry,
+            contracts: row.get(6)?,
+        });
+    }
+    Ok(legs)
+}
+
+/// Group unlinked imported legs into strategies, tag them and link them
+pub async fn recognize(conn: &Connection) -> Result<Vec<RecognizedStrategy>> {
+    let mut recognized = Vec::new();
+    for legs in cluster(unlinked_imported_legs(conn).await?) {
+        if legs.len() < 2 {
+            continue;
+        }
+        let Some(strategy) = classify(&legs) else { continue };
+        let group_id = Uuid::new_v4().to_string();
+        let option_ids: Vec<i64> = legs.iter().map(|l| l.id).collect();
+        let now = timestamps::to_db(&Utc::now());
+
+        let tx = conn.transaction().await?;
+        for id in &option_ids {
+            tx.execute(
+                "INSERT INTO option_strategy_legs (option_id, group_id, strategy, created_at) VALUES (?, ?, ?, ?)",
+                params![*id, group_id.clone(), strategy, now.clone()],
+            )
+            .await?;
+        }
+        let placeholders = vec!["?"; option_ids.len()].join(", ");
+        let values = std::iter::once(Value::Text(strategy.to_string())).chain(option_ids.iter().map(|id| Value::Integer(*id)));
+        tx.execute(&format!("UPDATE options SET strategy_type = ? WHERE id IN ({})", placeholders), params_from_iter(values))
+            .await?;
+        tx.commit().await?;
+
+        recognized.push(RecognizedStrategy {
+            group_id,
+            strategy: strategy.to_string(),
+            symbol: legs[0].symbol.clone(),
+            expiration_date: legs[0].expiration.to_string(),
+            option_ids,
+        });
+    }
+    Ok(recognized)
+}
+
+/// The strategy an option trade was grouped into, with every leg's id
+pub async fn strategy_for(conn: &Connection, option_id: i64) -> Result<Option<RecognizedStrategy>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT l.group_id, l.strategy, o.symbol, o.expiration_date, o.id FROM option_strategy_legs l
+             JOIN options o ON o.id = l.option_id
+             WHERE o.is_deleted = 0 AND l.group_id = (SELECT group_id FROM option_strategy_legs WHERE option_id = ?)
+             ORDER BY o.option_type, o.strike_price, o.id",
+        )
+        .await?
+        .query(params![option_id])
+        .await?;
+    let mut strategy: Option<RecognizedStrategy> = None;
+    while let Some(row) = rows.next().await? {
+        let id: i64 = row.get(4)?;
+        match strategy.as_mut() {
+            Some(s) => s.option_ids.push(id),
+            None => {
+                let expiration: String = row.get(3)?;
+                strategy = Some(RecognizedStrategy {
+                    group_id: row.get(0)?,
+                    strategy: row.get(1)?,
+                    symbol: row.get(2)?,
+                    expiration_date: timestamps::parse_stored(&expiration).map(|d| d.date_naive().to_string()).unwrap_or(expiration),
+                    option_ids: vec![id],
+                })
+            }
+        }
+    }
+    Ok(strategy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leg(id: i64, call: bool, strike: i64, second: u32) -> Leg {
+        Leg {
+            id,
+            symbol: "SPY".to_string(),
+            call,
+            strike: Decimal::from(strike),
+            expiration: NaiveDate::from_ymd_opt(2026, 11, 20).unwrap(),
+            entry: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(14, 30, second).unwrap().and_utc(),
+            contracts: 1,
+        }
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(&[leg(1, true, 500, 0), leg(2, true, 505, 0)]), Some("Vertical Spread"));
+        assert_eq!(classify(&[leg(1, true, 500, 0), leg(2, false, 500, 0)]), Some("Straddle"));
+        assert_eq!(classify(&[leg(1, true, 510, 0), leg(2, false, 490, 0)]), Some("Strangle"));
+        let condor = [leg(1, false, 480, 0), leg(2, false, 490, 0), leg(3, true, 510, 0), leg(4, true, 520, 0)];
+        assert_eq!(classify(&condor), Some("Iron Condor"));
+        assert_eq!(classify(&[leg(1, true, 500, 0), leg(2, true, 500, 0)]), None);
+
+        let mut uneven = [leg(1, true, 500, 0), leg(2, true, 505, 0)];
+        uneven[1].contracts = 2;
+        assert_eq!(classify(&uneven), None);
+    }
+
+    #[test]
+    fn test_cluster_by_entry_window() {
+        let mut other_symbol = leg(4, true, 500, 1);
+        other_symbol.symbol = "QQQ".to_string();
+        let clusters = cluster(vec![leg(3, true, 505, 40), leg(1, true, 500, 0), leg(2, true, 505, 3), other_symbol]);
+        let ids: Vec<Vec<i64>> = clusters.iter().map(|c| c.iter().map(|l| l.id).collect()).collect();
+        assert_eq!(ids, vec![vec![4], vec![1, 2], vec![3]]);
+    }
+}
@@ -0,0 +1,540 @@
+// Playbooks and tag sets a team lead shares with an organization. The registry holds the
+// source of truth; a provisioning pass copies it into each member's database, where the
+// copies are marked with `organization_id` and are read-only. When an item is removed or a
+// member leaves, the copies are detached (kept as personal items) so trade links survive.
+
+use anyhow::Result;
+use chrono::Utc;
+use futures_util::future::join_all;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::service::cache_service::CacheService;
+use crate::service::organizations::{OrganizationError, OrganizationRole, OrganizationService};
+use crate::turso::TursoClient;
+
+/// Shared playbooks and tags per organization
+pub const MAX_SHARED_ITEMS: usize = 200;
+const MAX_RULES: usize = 50;
+const RULE_TYPES: [&str; 3] = ["entry_criteria", "exit_criteria", "market_factor"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedRule {
+    /// `entry_criteria`, `exit_criteria` or `market_factor`
+    pub rule_type: String,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedPlaybook {
+    pub id: String,
+    pub organization_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub emoji: Option<String>,
+    pub color: Option<String>,
+    /// In display order
+    pub rules: Vec<SharedRule>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SharedPlaybookRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    pub emoji: Option<String>,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<SharedRule>,
+}
+
+impl SharedPlaybookRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() || self.name.trim().chars().count() > 100 {
+            return Err("name must be 1-100 characters".to_string());
+        }
+        if self.rules.len() > MAX_RULES {
+            return Err(format!("A playbook can have at most {} rules", MAX_RULES));
+        }
+        for rule in &self.rules {
+            if !RULE_TYPES.contains(&rule.rule_type.as_str()) {
+                return Err(format!("rule_type must be one of {}", RULE_TYPES.join(", ")));
+            }
+            if rule.title.trim().is_empty() {
+                return Err("Rule titles can't be empty".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedTag {
+    pub id: String,
+    pub organization_id: String,
+    /// e.g. `setup` or `mistake`
+    pub category: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub description: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SharedTagRequest {
+    pub category: String,
+    pub name: String,
+    pub color: Option<String>,
+    pub description: Option<String>,
+}
+
+impl SharedTagRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.category.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("category and name are required".to_string());
+        }
+        if self.category.trim().chars().count() > 50 || self.name.trim().chars().count() > 50 {
+            return Err("category and name must be at most 50 characters".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Result of provisioning one member's database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncSummary {
+    pub playbooks_synced: usize,
+    pub tags_synced: usize,
+    /// Local copies no longer shared, now personal items
+    pub detached: usize,
+}
+
+pub struct OrganizationLibrary<'a> {
+    registry: &'a Connection,
+}
+
+impl<'a> OrganizationLibrary<'a> {
+    pub fn new(registry: &'a Connection) -> Self {
+        Self { registry }
+    }
+
+    async fn require_role(&self, organization_id: &str, user_id: &str, lead: bool) -> Result<(), OrganizationError> {
+        let role = OrganizationService::new(self.registry).role_of(organization_id, user_id).await?;
+        if lead && role != OrganizationRole::Lead {
+            return Err(OrganizationError::Forbidden("Only a team lead can change shared playbooks and tags".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn count(&self, table: &str, organization_id: &str) -> Result<usize, OrganizationError> {
+        let sql = format!("SELECT COUNT(*) FROM {} WHERE organization_id = ?", table);
+        let mut rows = self.registry.prepare(&sql).await?.query(params![organization_id]).await?;
+        let count: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
+        };
+        Ok(count as usize)
+    }
+
+    pub async fn playbooks(&self, organization_id: &str) -> Result<Vec<SharedPlaybook>, OrganizationError> {
+        let mut rows = self
+            .registry
+            .prepare(
+                "SELECT id, organization_id, name, description, icon, emoji, color, rules, updated_at \
+                 FROM organization_playbooks WHERE organization_id = ? ORDER BY name ASC",
+            )
+            .await?
+            .query(params![organization_id])
+            .await?;
+        let mut playbooks = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let rules: String = row.get(7)?;
+            playbooks.push(SharedPlaybook {
+                id: row.get(0)?,
+                organization_id: row.get(1)?,
+                name: row.get(2)?,
+                description: row.get(3)?,
+                icon: row.get(4)?,
+                emoji: row.get(5)?,
+                color: row.get(6)?,
+                rules: serde_json::from_str(&rules).unwrap_or_default(),
+                updated_at: row.get(8)?,
+            });
+        }
+        Ok(playbooks)
+    }
+
+    pub async fn tags(&self, organization_id: &str) -> Result<Vec<SharedTag>, OrganizationError> {
+        let mut rows = self
+            .registry
+            .prepare(
+                "SELECT id, organization_id, category, name, color, description, updated_at \
+                 FROM organization_tags WHERE organization_id = ? ORDER BY category ASC, name ASC",
+            )
+            .await?
+            .query(params![organization_id])
+            .await?;
+        let mut tags = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tags.push(SharedTag {
+                id: row.get(0)?,
+                organization_id: row.get(1)?,
+                category: row.get(2)?,
+                name: row.get(3)?,
+                color: row.get(4)?,
+                description: row.get(5)?,
+                updated_at: row.get(6)?,
+            });
+        }
+        Ok(tags)
+    }
+
+    /// Shared playbooks, for any member
+    pub async fn playbooks_for(&self, organization_id: &str, user_id: &str) -> Result<Vec<SharedPlaybook>, OrganizationError> {
+        self.require_role(organization_id, user_id, false).await?;
+        self.playbooks(organization_id).await
+    }
+
+    /// Shared tags, for any member
+    pub async fn tags_for(&self, organization_id: &str, user_id: &str) -> Result<Vec<SharedTag>, OrganizationError> {
+        self.require_role(organization_id, user_id, false).await?;
+        self.tags(organization_id).await
+    }
+
+    /// Create (`playbook_id` None) or replace a shared playbook
+    pub async fn save_playbook(
+        &self,
+        organization_id: &str,
+        user_id: &str,
+        playbook_id: Option<&str>,
+        request: SharedPlaybookRequest,
+    ) -> Result<SharedPlaybook, OrganizationError> {
+        self.require_role(organization_id, user_id, true).await?;
+        request.validate().map_err(OrganizationError::Invalid)?;
+        let rules = serde_json::to_string(&request.rules).map_err(anyhow::Error::from)?;
+        let now = Utc::now().to_rfc3339();
+        let id = match playbook_id {
+            Some(id) => {
+                let updated = self
+                    .registry
+                    .execute(
+                        "UPDATE organization_playbooks SET name = ?, description = ?, icon = ?, emoji = ?, color = ?, rules = ?, updated_at = ? \
+                         WHERE id = ? AND organization_id = ?",
+                        params![
+                            request.name.trim(), request.description.clone(), request.icon.clone(), request.emoji.clone(),
+                            request.color.clone(), rules.as_str(), now.as_str(), id, organization_id
+                        ],
+                    )
+                    .await?;
+                if updated == 0 {
+                    return Err(OrganizationError::Invalid("Shared playbook not found".to_string()));
+                }
+                id.to_string()
+            }
+            None => {
+                if self.count("organization_playbooks", organization_id).await? >= MAX_SHARED_ITEMS {
+                    return Err(OrganizationError::Invalid(format!("Organizations can share at most {} playbooks", MAX_SHARED_ITEMS)));
+                }
+                let id = Uuid::new_v4().to_string();
+                self.registry
+                    .execute(
+                        "INSERT INTO organization_playbooks (id, organization_id, name, description, icon, emoji, color, rules, updated_at) \
+                         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+                        params![
+                            id.as_str(), organization_id, request.name.trim(), request.description.clone(), request.icon.clone(),
+                            request.emoji.clone(), request.color.clone(), rules.as_str(), now.as_str()
+                        ],
+                    )
+                    .await?;
+                id
+            }
+        };
+        self.playbooks(organization_id)
+            .await?
+            .into_iter()
+            .find(|p| p.id == id)
+            .ok_or(OrganizationError::NotFound)
+    }
+
+    pub async fn delete_playbook(&self, organization_id: &str, user_id: &str, playbook_id: &str) -> Result<bool, OrganizationError> {
+        self.require_role(organization_id, user_id, true).await?;
+        let deleted = self
+            .registry
+            .execute(
+                "DELETE FROM organization_playbooks WHERE id = ? AND organization_id = ?",
+                params![playbook_id, organization_id],
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Create (`tag_id` None) or replace a shared tag; names are unique per category
+    pub async fn save_tag(
+        &self,
+        organization_id: &str,
+        user_id: &str,
+        tag_id: Option<&str>,
+        request: SharedTagRequest,
+    ) -> Result<SharedTag, OrganizationError> {
+        self.require_role(organization_id, user_id, true).await?;
+        request.validate().map_err(OrganizationError::Invalid)?;
+        let (category, name) = (request.category.trim(), request.name.trim());
+        if self.tags(organization_id).await?.iter().any(|t| t.category == category && t.name == name && Some(t.id.as_str()) != tag_id) {
+            return Err(OrganizationError::Invalid(format!("A shared tag '{}' already exists in '{}'", name, category)));
+        }
+        let now = Utc::now().to_rfc3339();
+        let id = match tag_id {
+            Some(id) => {
+                let updated = self
+                    .registry
+                    .execute(
+                        "UPDATE organization_tags SET category = ?, name = ?, color = ?, description = ?, updated_at = ? \
+                         WHERE id = ? AND organization_id = ?",
+                        params![category, name, request.color.clone(), request.description.clone(), now.as_str(), id, organization_id],
+                    )
+                    .await?;
+                if updated == 0 {
+                    return Err(OrganizationError::Invalid("Shared tag not found".to_string()));
+                }
+                id.to_string()
+            }
+            None => {
+                if self.count("organization_tags", organization_id).await? >= MAX_SHARED_ITEMS {
+                    return Err(OrganizationError::Invalid(format!("Organizations can share at most {} tags", MAX_SHARED_ITEMS)));
+                }
+                let id = Uuid::new_v4().to_string();
+                self.registry
+                    .execute(
+                        "INSERT INTO organization_tags (id, organization_id, category, name, color, description, updated_at) \
+                         VALUES (?, ?, ?, ?, ?, ?, ?)",
+                        params![id.as_str(), organization_id, category, name, request.color.clone(), request.description.clone(), now.as_str()],
+                    )
+                    .await?;
+                id
+            }
+        };
+        self.tags(organization_id)
+            .await?
+            .into_iter()
+            .find(|t| t.id == id)
+            .ok_or(OrganizationError::NotFound)
+    }
+
+    pub async fn delete_tag(&self, organization_id: &str, user_id: &str, tag_id: &str) -> Result<bool, OrganizationError> {
+        self.require_role(organization_id, user_id, true).await?;
+        let deleted = self
+            .registry
+            .execute("DELETE FROM organization_tags WHERE id = ? AND organization_id = ?", params![tag_id, organization_id])
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    /// Drop everything an organization shared, once the organization itself is gone
+    pub async fn remove_orphaned(&self) -> Result<()> {
+        for table in ["organization_playbooks", "organization_tags"] {
+            let sql = format!("DELETE FROM {} WHERE organization_id NOT IN (SELECT id FROM organizations)", table);
+            self.registry.execute(&sql, params![]).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Bring one member's database in line with the organization's shared items
+pub async fn provision_member(
+    member_db: &Connection,
+    organization_id: &str,
+    playbooks: &[SharedPlaybook],
+    tags: &[SharedTag],
+) -> Result<SyncSummary> {
+    let mut summary = SyncSummary::default();
+
+    for playbook in playbooks {
+        member_db
+            .execute(
+                "INSERT INTO playbook (id, name, description, icon, emoji, color, organization_id) VALUES (?, ?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(id) DO UPDATE SET name = excluded.name, description = excluded.description, icon = excluded.icon, \
+                 emoji = excluded.emoji, color = excluded.color, organization_id = excluded.organization_id",
+                params![
+                    playbook.id.as_str(), playbook.name.as_str(), playbook.description.clone(), playbook.icon.clone(),
+                    playbook.emoji.clone(), playbook.color.clone(), organization_id
+                ],
+            )
+            .await?;
+        // Rule ids are stable per position so compliance records that reference them survive a resync
+        for (position, rule) in playbook.rules.iter().enumerate() {
+            let rule_id = format!("{}:{}", playbook.id, position);
+            member_db
+                .execute(
+                    "INSERT INTO playbook_rules (id, playbook_id, rule_type, title, description, order_position) VALUES (?, ?, ?, ?, ?, ?) \
+                     ON CONFLICT(id) DO UPDATE SET rule_type = excluded.rule_type, title = excluded.title, \
+                     description = excluded.description, order_position = excluded.order_position",
+                    params![
+                        rule_id.as_str(), playbook.id.as_str(), rule.rule_type.as_str(), rule.title.trim(),
+                        rule.description.clone(), position as i64
+                    ],
+                )
+                .await?;
+        }
+        member_db
+            .execute(
+                "DELETE FROM playbook_rules WHERE playbook_id = ? AND order_position >= ?",
+                params![playbook.id.as_str(), playbook.rules.len() as i64],
+            )
+            .await?;
+        summary.playbooks_synced += 1;
+    }
+
+    for tag in tags {
+        // A personal tag with the same name is adopted rather than duplicated
+        member_db
+            .execute(
+                "INSERT INTO trade_tags (id, category, name, color, description, organization_id) VALUES (?, ?, ?, ?, ?, ?) \
+                 ON CONFLICT(category, name) DO UPDATE SET color = excluded.color, description = excluded.description, \
+                 organization_id = excluded.organization_id",
+                params![
+                    Uuid::new_v4().to_string(), tag.category.as_str(), tag.name.as_str(), tag.color.clone(),
+                    tag.description.clone(), organization_id
+                ],
+            )
+            .await?;
+        summary.tags_synced += 1;
+    }
+
+    let playbook_ids: Vec<&str> = playbooks.iter().map(|p| p.id.as_str()).collect();
+    let tag_keys: Vec<(&str, &str)> = tags.iter().map(|t| (t.category.as_str(), t.name.as_str())).collect();
+    summary.detached = detach(member_db, organization_id, &playbook_ids, &tag_keys).await?;
+    Ok(summary)
+}
+
+/// Turn local copies that are no longer shared into personal items; returns how many
+async fn detach(
+    member_db: &Connection,
+    organization_id: &str,
+    keep_playbooks: &[&str],
+    keep_tags: &[(&str, &str)],
+) -> Result<usize> {
+    let mut detached = 0;
+    let mut rows = member_db
+        .prepare("SELECT id FROM playbook WHERE organization_id = ?")
+        .await?
+        .query(params![organization_id])
+        .await?;
+    let mut stale_playbooks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let id: String = row.get(0)?;
+        if !keep_playbooks.contains(&id.as_str()) {
+            stale_playbooks.push(id);
+        }
+    }
+    for id in stale_playbooks {
+        detached += member_db.execute("UPDATE playbook SET organization_id = NULL WHERE id = ?", params![id]).await? as usize;
+    }
+
+    let mut rows = member_db
+        .prepare("SELECT id, category, name FROM trade_tags WHERE organization_id = ?")
+        .await?
+        .query(params![organization_id])
+        .await?;
+    let mut stale_tags = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let (id, category, name): (String, String, String) = (row.get(0)?, row.get(1)?, row.get(2)?);
+        if !keep_tags.contains(&(category.as_str(), name.as_str())) {
+            stale_tags.push(id);
+        }
+    }
+    for id in stale_tags {
+        detached += member_db.execute("UPDATE trade_tags SET organization_id = NULL WHERE id = ?", params![id]).await? as usize;
+    }
+    Ok(detached)
+}
+
+/// Provision every member of an organization. Members without a database are skipped.
+pub async fn sync_organization(turso_client: &TursoClient, cache: &CacheService, organization_id: &str) -> Result<usize> {
+    let registry = turso_client.get_registry_connection().await?;
+    let library = OrganizationLibrary::new(&registry);
+    let (playbooks, tags) = (library.playbooks(organization_id).await?, library.tags(organization_id).await?);
+    let members = OrganizationService::new(&registry).members(organization_id).await?;
+
+    let results = join_all(members.iter().map(|member| {
+        let (playbooks, tags) = (&playbooks, &tags);
+        async move {
+            let conn = turso_client.get_user_database_connection(&member.user_id).await.ok().flatten()?;
+            match provision_member(&conn, organization_id, playbooks, tags).await {
+                Ok(_) => {
+                    invalidate_member_cache(cache, &member.user_id).await;
+                    Some(())
+                }
+                Err(e) => {
+                    log::warn!("Shared library: failed to provision a member of {}: {}", organization_id, e);
+                    None
+                }
+            }
+        }
+    }))
+    .await;
+    Ok(results.into_iter().flatten().count())
+}
+
+/// Provision a single member, e.g. right after they join
+pub async fn sync_member(
+    turso_client: &TursoClient,
+    cache: &CacheService,
+    organization_id: &str,
+    user_id: &str,
+) -> Result<SyncSummary> {
+    let registry = turso_client.get_registry_connection().await?;
+    let library = OrganizationLibrary::new(&registry);
+    let (playbooks, tags) = (library.playbooks(organization_id).await?, library.tags(organization_id).await?);
+    let Some(conn) = turso_client.get_user_database_connection(user_id).await? else {
+        return Ok(SyncSummary::default());
+    };
+    let summary = provision_member(&conn, organization_id, &playbooks, &tags).await?;
+    invalidate_member_cache(cache, user_id).await;
+    Ok(summary)
+}
+
+/// Detach all of an organization's items from a member who left
+pub async fn release_member(turso_client: &TursoClient, cache: &CacheService, organization_id: &str, user_id: &str) -> Result<usize> {
+    let Some(conn) = turso_client.get_user_database_connection(user_id).await? else {
+        return Ok(0);
+    };
+    let detached = detach(&conn, organization_id, &[], &[]).await?;
+    invalidate_member_cache(cache, user_id).await;
+    Ok(detached)
+}
+
+async fn invalidate_member_cache(cache: &CacheService, user_id: &str) {
+    for table in ["playbook", "trade_tags"] {
+        if let Err(e) = cache.invalidate_table_cache(user_id, table).await {
+            log::warn!("Failed to invalidate {} cache for {}: {}", table, user_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_playbook_request_validation() {
+        let rule = |rule_type: &str, title: &str| SharedRule { rule_type: rule_type.to_string(), title: title.to_string(), description: None };
+        let mut request = SharedPlaybookRequest {
+            name: "Opening range breakout".to_string(),
+            description: None,
+            icon: None,
+            emoji: None,
+            color: None,
+            rules: vec![rule("entry_criteria", "Break of the 5m high"), rule("exit_criteria", "Close below VWAP")],
+        };
+        assert!(request.validate().is_ok());
+        request.rules.push(rule("gut_feeling", "Looks good"));
+        assert!(request.validate().is_err());
+        request.rules.pop();
+        request.name = "  ".to_string();
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,489 @@
+// Team workspaces for trading desks and mentorship groups. Organizations and memberships
+// live in the registry, since they span several user databases. A lead can see the
+// members' analytics; each member decides whether they appear by name or anonymized.
+
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use futures_util::future::join_all;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::analytics::CoreMetrics;
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::core_metrics::calculate_core_metrics;
+use crate::turso::TursoClient;
+
+const MAX_NAME_CHARS: usize = 80;
+/// Members one organization can have; team analytics opens every member's database
+pub const MAX_MEMBERS: usize = 50;
+const INVITE_DAYS: i64 = 7;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OrganizationError {
+    #[error("Organization not found")]
+    NotFound,
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error(transparent)]
+    Database(#[from] anyhow::Error),
+}
+
+impl From<libsql::Error> for OrganizationError {
+    fn from(e: libsql::Error) -> Self {
+        Self::Database(e.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrganizationRole {
+    /// Manages members and sees team analytics
+    Lead,
+    Member,
+}
+
+impl OrganizationRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Lead => "lead",
+            Self::Member => "member",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        if value == "lead" { Self::Lead } else { Self::Member }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    /// The caller's role
+    pub role: OrganizationRole,
+    pub member_count: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationMember {
+    pub user_id: String,
+    pub display_name: Option<String>,
+    pub role: OrganizationRole,
+    /// Whether the member agreed to appear by name in team analytics
+    pub share_named: bool,
+    pub joined_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrganizationInvite {
+    pub code: String,
+    pub organization_id: String,
+    pub role: OrganizationRole,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMembershipRequest {
+    pub display_name: Option<String>,
+    pub share_named: Option<bool>,
+}
+
+/// One member's row in team analytics
+#[derive(Debug, Clone, Serialize)]
+pub struct MemberAnalytics {
+    /// Display name when shown by name, otherwise `Member N`
+    pub label: String,
+    pub named: bool,
+    pub total_trades: u32,
+    pub net_pnl: f64,
+    pub win_rate: f64,
+    pub profit_factor: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamAnalytics {
+    pub organization_id: String,
+    pub member_count: usize,
+    pub members_with_trades: usize,
+    pub total_trades: u32,
+    pub net_pnl: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+    /// Across all members' trades, not an average of member win rates
+    pub win_rate: f64,
+    pub profit_factor: f64,
+    /// Best net P&L first
+    pub members: Vec<MemberAnalytics>,
+    /// Members whose analytics couldn't be computed (no database, errors)
+    pub unavailable_members: usize,
+}
+
+pub struct OrganizationService<'a> {
+    registry: &'a Connection,
+}
+
+impl<'a> OrganizationService<'a> {
+    pub fn new(registry: &'a Connection) -> Self {
+        Self { registry }
+    }
+
+    pub async fn create(&self, user_id: &str, name: &str) -> Result<Organization, OrganizationError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_CHARS {
+            return Err(OrganizationError::Invalid(format!("name must be 1-{} characters", MAX_NAME_CHARS)));
+        }
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now().to_rfc3339();
+        self.registry
+            .execute(
+                "INSERT INTO organizations (id, name, created_by, created_at) VALUES (?, ?, ?, ?)",
+                params![id.as_str(), name, user_id, now.as_str()],
+            )
+            .await?;
+        self.registry
+            .execute(
+                "INSERT INTO organization_members (organization_id, user_id, role, share_named, joined_at) VALUES (?, ?, 'lead', 1, ?)",
+                params![id.as_str(), user_id, now.as_str()],
+            )
+            .await?;
+        Ok(Organization { id, name: name.to_string(), role: OrganizationRole::Lead, member_count: 1, created_at: now })
+    }
+
+    /// Organizations the user belongs to
+    pub async fn list_for_user(&self, user_id: &str) -> Result<Vec<Organization>, OrganizationError> {
+        let mut rows = self
+            .registry
+            .prepare(
+                "SELECT o.id, o.name, m.role, (SELECT COUNT(*) FROM organization_members c WHERE c.organization_id = o.id), o.created_at \
+                 FROM organizations o JOIN organization_members m ON m.organization_id = o.id \
+                 WHERE m.user_id = ? ORDER BY o.name ASC",
+            )
+            .await?
+            .query(params![user_id])
+            .await?;
+        let mut organizations = Vec::new();
+        while let Some(row) = rows.next().await? {
+            organizations.push(Organization {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                role: OrganizationRole::parse(&row.get::<String>(2)?),
+                member_count: row.get(3)?,
+                created_at: row.get(4)?,
+            });
+        }
+        Ok(organizations)
+    }
+
+    /// The user's role, NotFound when they aren't a member (the organization's existence isn't revealed)
+    pub async fn role_of(&self, organization_id: &str, user_id: &str) -> Result<OrganizationRole, OrganizationError> {
+        let mut rows = self
+            .registry
+            .prepare("SELECT role FROM organization_members WHERE organization_id = ? AND user_id = ?")
+            .await?
+            .query(params![organization_id, user_id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(OrganizationRole::parse(&row.get::<String>(0)?)),
+            None => Err(OrganizationError::NotFound),
+        }
+    }
+
+    async fn require_lead(&self, organization_id: &str, user_id: &str) -> Result<(), OrganizationError> {
+        match self.role_of(organization_id, user_id).await? {
+            OrganizationRole::Lead => Ok(()),
+            OrganizationRole::Member => Err(OrganizationError::Forbidden("Only a team lead can do this".to_string())),
+        }
+    }
+
+    pub async fn members(&self, organization_id: &str) -> Result<Vec<OrganizationMember>, OrganizationError> {
+        let mut rows = self
+            .registry
+            .prepare(
+                "SELECT user_id, display_name, role, share_named, joined_at FROM organization_members \
+                 WHERE organization_id = ? ORDER BY joined_at ASC",
+            )
+            .await?
+            .query(params![organization_id])
+            .await?;
+        let mut members = Vec::new();
+        while let Some(row) = rows.next().await? {
+            members.push(OrganizationMember {
+                user_id: row.get(0)?,
+                display_name: row.get(1)?,
+                role: OrganizationRole::parse(&row.get::<String>(2)?),
+                share_named: row.get::<i64>(3)? != 0,
+                joined_at: row.get(4)?,
+            });
+        }
+        Ok(members)
+    }
+
+    /// Member list, for leads only
+    pub async fn members_for(&self, organization_id: &str, user_id: &str) -> Result<Vec<OrganizationMember>, OrganizationError> {
+        self.require_lead(organization_id, user_id).await?;
+        self.members(organization_id).await
+    }
+
+    /// Single-use invite code a lead hands to a new member
+    pub async fn create_invite(
+        &self,
+        organization_id: &str,
+        user_id: &str,
+        role: OrganizationRole,
+    ) -> Result<OrganizationInvite, OrganizationError> {
+        self.require_lead(organization_id, user_id).await?;
+        let code = Uuid::new_v4().simple().to_string();
+        let expires_at = (Utc::now() + Duration::days(INVITE_DAYS)).to_rfc3339();
+        self.registry
+            .execute(
+                "INSERT INTO organization_invites (code, organization_id, role, created_by, expires_at) VALUES (?, ?, ?, ?, ?)",
+                params![code.as_str(), organization_id, role.as_str(), user_id, expires_at.as_str()],
+            )
+            .await?;
+        Ok(OrganizationInvite { code, organization_id: organization_id.to_string(), role, expires_at })
+    }
+
+    /// Join with an invite code; the code is consumed
+    pub async fn join(&self, user_id: &str, code: &str, display_name: Option<String>) -> Result<Organization, OrganizationError> {
+        let now = Utc::now().to_rfc3339();
+        let mut rows = self
+            .registry
+            .prepare("SELECT organization_id, role FROM organization_invites WHERE code = ? AND accepted_at IS NULL AND expires_at > ?")
+            .await?
+            .query(params![code.trim(), now.as_str()])
+            .await?;
+        let Some(row) = rows.next().await? else {
+            return Err(OrganizationError::Invalid("Invite code is invalid or expired".to_string()));
+        };
+        let (organization_id, role): (String, String) = (row.get(0)?, row.get(1)?);
+
+        if self.role_of(&organization_id, user_id).await.is_ok() {
+            return Err(OrganizationError::Invalid("You are already a member of this organization".to_string()));
+        }
+        if self.members(&organization_id).await?.len() >= MAX_MEMBERS {
+            return Err(OrganizationError::Invalid(format!("Organizations are limited to {} members", MAX_MEMBERS)));
+        }
+        let claimed = self
+            .registry
+            .execute(
+                "UPDATE organization_invites SET accepted_by = ?, accepted_at = ? WHERE code = ? AND accepted_at IS NULL",
+                params![user_id, now.as_str(), code.trim()],
+            )
+            .await?;
+        if claimed == 0 {
+            return Err(OrganizationError::Invalid("Invite code is invalid or expired".to_string()));
+        }
+        let display_name = display_name.map(|n| n.trim().chars().take(MAX_NAME_CHARS).collect::<String>()).filter(|n| !n.is_empty());
+        self.registry
+            .execute(
+                "INSERT INTO organization_members (organization_id, user_id, role, display_name, share_named, joined_at) VALUES (?, ?, ?, ?, 0, ?)",
+                params![organization_id.as_str(), user_id, role, display_name, now.as_str()],
+            )
+            .await?;
+
+        self.list_for_user(user_id)
+            .await?
+            .into_iter()
+            .find(|o| o.id == organization_id)
+            .ok_or(OrganizationError::NotFound)
+    }
+
+    /// A member's own display name and naming consent
+    pub async fn update_membership(
+        &self,
+        organization_id: &str,
+        user_id: &str,
+        request: UpdateMembershipRequest,
+    ) -> Result<(), OrganizationError> {
+        self.role_of(organization_id, user_id).await?;
+        if let Some(name) = request.display_name {
+            let name = name.trim().chars().take(MAX_NAME_CHARS).collect::<String>();
+            self.registry
+                .execute(
+                    "UPDATE organization_members SET display_name = ? WHERE organization_id = ? AND user_id = ?",
+                    params![if name.is_empty() { None } else { Some(name) }, organization_id, user_id],
+                )
+                .await?;
+        }
+        if let Some(share_named) = request.share_named {
+            self.registry
+                .execute(
+                    "UPDATE organization_members SET share_named = ? WHERE organization_id = ? AND user_id = ?",
+                    params![share_named as i64, organization_id, user_id],
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Remove a member; leads can remove anyone, members only themselves. The last lead
+    /// can't leave while others remain, and the organization goes away with its last member.
+    pub async fn remove_member(&self, organization_id: &str, user_id: &str, member_id: &str) -> Result<(), OrganizationError> {
+        let caller_role = self.role_of(organization_id, user_id).await?;
+        if user_id != member_id && caller_role != OrganizationRole::Lead {
+            return Err(OrganizationError::Forbidden("Only a team lead can remove other members".to_string()));
+        }
+        let members = self.members(organization_id).await?;
+        let Some(target) = members.iter().find(|m| m.user_id == member_id) else {
+            return Err(OrganizationError::Invalid("Not a member of this organization".to_string()));
+        };
+        let leads = members.iter().filter(|m| m.role == OrganizationRole::Lead).count();
+        if target.role == OrganizationRole::Lead && leads == 1 && members.len() > 1 {
+            return Err(OrganizationError::Invalid("Invite another lead before the last lead leaves".to_string()));
+        }
+
+        self.registry
+            .execute(
+                "DELETE FROM organization_members WHERE organization_id = ? AND user_id = ?",
+                params![organization_id, member_id],
+            )
+            .await?;
+        if members.len() == 1 {
+            self.registry.execute("DELETE FROM organization_invites WHERE organization_id = ?", params![organization_id]).await?;
+            self.registry.execute("DELETE FROM organization_playbooks WHERE organization_id = ?", params![organization_id]).await?;
+            self.registry.execute("DELETE FROM organization_tags WHERE organization_id = ?", params![organization_id]).await?;
+            self.registry.execute("DELETE FROM organizations WHERE id = ?", params![organization_id]).await?;
+        }
+        Ok(())
+    }
+
+    /// Core metrics of every member for a range, for leads. Members who haven't agreed to be
+    /// named are anonymized even when `named` is requested.
+    pub async fn team_analytics(
+        &self,
+        turso_client: &TursoClient,
+        organization_id: &str,
+        user_id: &str,
+        time_range: &TimeRange,
+        named: bool,
+    ) -> Result<TeamAnalytics, OrganizationError> {
+        self.require_lead(organization_id, user_id).await?;
+        let members = self.members(organization_id).await?;
+
+        let metrics = join_all(members.iter().map(|member| async move {
+            let conn = turso_client.get_user_database_connection(&member.user_id).await.ok().flatten()?;
+            match calculate_core_metrics(&conn, time_range).await {
+                Ok(metrics) => Some(metrics),
+                Err(e) => {
+                    log::warn!("Team analytics: failed to compute metrics for a member of {}: {}", organization_id, e);
+                    None
+                }
+            }
+        }))
+        .await;
+
+        let rows = members
+            .iter()
+            .zip(metrics)
+            .map(|(member, metrics)| {
+                let name = if named && member.share_named { member.display_name.clone() } else { None };
+                (name, metrics)
+            })
+            .collect();
+        Ok(aggregate(organization_id, rows))
+    }
+
+    /// Drop a deleted user's memberships and any organizations left empty
+    pub async fn remove_user(&self, user_id: &str) -> Result<()> {
+        self.registry.execute("DELETE FROM organization_members WHERE user_id = ?", params![user_id]).await?;
+        self.registry
+            .execute(
+                "DELETE FROM organizations WHERE id NOT IN (SELECT DISTINCT organization_id FROM organization_members)",
+                params![],
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Combine member metrics. `None` metrics count as unavailable; `None` names are anonymized.
+fn aggregate(organization_id: &str, rows: Vec<(Option<String>, Option<CoreMetrics>)>) -> TeamAnalytics {
+    let member_count = rows.len();
+    let available: Vec<(Option<String>, CoreMetrics)> =
+        rows.into_iter().filter_map(|(name, metrics)| metrics.map(|m| (name, m))).collect();
+    let unavailable_members = member_count - available.len();
+
+    let total_trades: u32 = available.iter().map(|(_, m)| m.total_trades).sum();
+    let winning_trades: u32 = available.iter().map(|(_, m)| m.winning_trades).sum();
+    let net_pnl: f64 = available.iter().map(|(_, m)| m.net_profit_loss).sum();
+    let gross_profit: f64 = available.iter().map(|(_, m)| m.gross_profit).sum();
+    let gross_loss: f64 = available.iter().map(|(_, m)| m.gross_loss.abs()).sum();
+
+    let mut members: Vec<(Option<String>, CoreMetrics)> = available;
+    members.sort_by(|a, b| b.1.net_profit_loss.total_cmp(&a.1.net_profit_loss));
+    // Anonymous labels follow the sorted order, so they don't map to join order
+    let mut anonymous = 0;
+    let members: Vec<MemberAnalytics> = members
+        .into_iter()
+        .map(|(name, m)| {
+            let named = name.is_some();
+            let label = name.unwrap_or_else(|| {
+                anonymous += 1;
+                format!("Member {}", anonymous)
+            });
+            MemberAnalytics {
+                label,
+                named,
+                total_trades: m.total_trades,
+                net_pnl: m.net_profit_loss,
+                win_rate: m.win_rate,
+                profit_factor: m.profit_factor,
+            }
+        })
+        .collect();
+
+    TeamAnalytics {
+        organization_id: organization_id.to_string(),
+        member_count,
+        members_with_trades: members.iter().filter(|m| m.total_trades > 0).count(),
+        total_trades,
+        net_pnl,
+        gross_profit,
+        gross_loss,
+        win_rate: if total_trades > 0 { winning_trades as f64 / total_trades as f64 * 100.0 } else { 0.0 },
+        profit_factor: if gross_loss > 0.0 { gross_profit / gross_loss } else { 0.0 },
+        members,
+        unavailable_members,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(trades: u32, wins: u32, profit: f64, loss: f64) -> CoreMetrics {
+        CoreMetrics {
+            total_trades: trades,
+            winning_trades: wins,
+            gross_profit: profit,
+            gross_loss: loss,
+            net_profit_loss: profit + loss,
+            win_rate: wins as f64 / trades as f64 * 100.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_aggregate_weights_trades_and_anonymizes() {
+        let analytics = aggregate(
+            "org",
+            vec![
+                (None, Some(metrics(10, 4, 400.0, -300.0))),
+                (Some("Dana".to_string()), Some(metrics(30, 21, 900.0, -300.0))),
+                (None, Some(metrics(10, 5, 500.0, -100.0))),
+                (Some("Lee".to_string()), None),
+            ],
+        );
+        assert_eq!((analytics.member_count, analytics.unavailable_members), (4, 1));
+        assert_eq!(analytics.total_trades, 50);
+        assert_eq!(analytics.win_rate, 60.0);
+        assert_eq!(analytics.profit_factor, 1800.0 / 700.0);
+        let labels: Vec<&str> = analytics.members.iter().map(|m| m.label.as_str()).collect();
+        assert_eq!(labels, vec!["Dana", "Member 1", "Member 2"]);
+        assert_eq!(analytics.members[1].net_pnl, 400.0);
+    }
+}
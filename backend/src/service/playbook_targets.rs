@@ -0,0 +1,340 @@
+// Per-playbook performance targets. A playbook can set a minimum win rate and a minimum
+// average R; the periodic evaluator walks the playbook's closed trades in exit order,
+// measuring each target over a rolling window of recent trades, and flags the setup as
+// degraded once it has been under target for N consecutive trades. One alert goes out per
+// degradation; the state clears as soon as the rolling numbers recover.
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::money;
+use crate::models::pnl::{OPTION_CONTRACT_MULTIPLIER, OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::models::timestamps;
+use crate::service::notifications::push::{PushPayload, PushService};
+use crate::turso::config::WebPushConfig;
+
+/// Rolling numbers on fewer trades than this are noise and never count as a breach
+const MIN_SAMPLE: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybookTarget {
+    #[serde(default)]
+    pub playbook_id: String,
+    /// Fraction of winning trades, 0..1
+    pub min_win_rate: Option<f64>,
+    /// Average R multiple; trades without a defined risk are left out
+    pub min_avg_r: Option<f64>,
+    #[serde(default = "default_window_trades")]
+    pub window_trades: u32,
+    #[serde(default = "default_consecutive_trades")]
+    pub consecutive_trades: u32,
+    #[serde(default = "default_enabled")]
+    pub is_enabled: bool,
+}
+
+fn default_window_trades() -> u32 {
+    20
+}
+
+fn default_consecutive_trades() -> u32 {
+    3
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl PlaybookTarget {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_win_rate.is_none() && self.min_avg_r.is_none() {
+            return Err("Set min_win_rate, min_avg_r or both".to_string());
+        }
+        if self.min_win_rate.is_some_and(|w| !(0.0..=1.0).contains(&w)) {
+            return Err("min_win_rate must be between 0 and 1".to_string());
+        }
+        if self.min_avg_r.is_some_and(|r| !r.is_finite()) {
+            return Err("min_avg_r must be a number".to_string());
+        }
+        if !(MIN_SAMPLE as u32..=200).contains(&self.window_trades) {
+            return Err(format!("window_trades must be between {} and 200", MIN_SAMPLE));
+        }
+        if !(1..=50).contains(&self.consecutive_trades) {
+            return Err("consecutive_trades must be between 1 and 50".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One closed trade: whether it won, and its R multiple when the risk is known
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Outcome {
+    won: bool,
+    r: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TargetStatus {
+    pub playbook_id: String,
+    pub trades_evaluated: usize,
+    /// Over the latest window
+    pub win_rate: Option<f64>,
+    pub avg_r: Option<f64>,
+    /// Trades in a row, up to the latest, with the rolling numbers under target
+    pub breach_streak: u32,
+    pub is_degraded: bool,
+    /// `win_rate` and/or `avg_r` when under target on the latest trade
+    pub failing: Vec<String>,
+}
+
+fn window_stats(window: &[Outcome]) -> (f64, Option<f64>) {
+    let win_rate = window.iter().filter(|o| o.won).count() as f64 / window.len() as f64;
+    let rs: Vec<f64> = window.iter().filter_map(|o| o.r).collect();
+    let avg_r = (!rs.is_empty()).then(|| rs.iter().sum::<f64>() / rs.len() as f64);
+    (win_rate, avg_r)
+}
+
+fn failing_targets(target: &PlaybookTarget, win_rate: f64, avg_r: Option<f64>) -> Vec<String> {
+    let mut failing = Vec::new();
+    if target.min_win_rate.is_some_and(|min| win_rate < min) {
+        failing.push("win_rate".to_string());
+    }
+    if let (Some(min), Some(avg)) = (target.min_avg_r, avg_r)
+        && avg < min
+    {
+        failing.push("avg_r".to_string());
+    }
+    failing
+}
+
+/// Walk the outcomes (oldest first) with a rolling window and measure the trailing breach
+fn evaluate(target: &PlaybookTarget, outcomes: &[Outcome]) -> TargetStatus {
+    let window_len = target.window_trades.max(1) as usize;
+    let mut status = TargetStatus {
+        playbook_id: target.playbook_id.clone(),
+        trades_evaluated: outcomes.len(),
+        ..Default::default()
+    };
+    for end in 1..=outcomes.len() {
+        let window = &outcomes[end.saturating_sub(window_len)..end];
+        if window.len() < MIN_SAMPLE {
+            continue;
+        }
+        let (win_rate, avg_r) = window_stats(window);
+        let failing = failing_targets(target, win_rate, avg_r);
+        status.breach_streak = if failing.is_empty() { 0 } else { status.breach_streak + 1 };
+        status.win_rate = Some(win_rate);
+        status.avg_r = avg_r;
+        status.failing = failing;
+    }
+    status.is_degraded = status.breach_streak >= target.consecutive_trades;
+    status
+}
+
+pub async fn get_target(conn: &Connection, playbook_id: &str) -> Result<Option<PlaybookTarget>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT playbook_id, min_win_rate, min_avg_r, window_trades, consecutive_trades, is_enabled \
+             FROM playbook_targets WHERE playbook_id = ?",
+        )
+        .await?
+        .query(params![playbook_id])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    Ok(Some(PlaybookTarget {
+        playbook_id: row.get(0)?,
+        min_win_rate: row.get(1)?,
+        min_avg_r: row.get(2)?,
+        window_trades: row.get::<i64>(3)?.max(0) as u32,
+        consecutive_trades: row.get::<i64>(4)?.max(0) as u32,
+        is_enabled: row.get::<i64>(5)? != 0,
+    }))
+}
+
+/// Save targets; changing them resets the alert state so the next run judges them fresh
+pub async fn save_target(conn: &Connection, target: &PlaybookTarget) -> Result<()> {
+    conn.execute(
+        "INSERT INTO playbook_targets (playbook_id, min_win_rate, min_avg_r, window_trades, consecutive_trades, is_enabled, is_degraded, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, 0, ?) \
+         ON CONFLICT(playbook_id) DO UPDATE SET min_win_rate = excluded.min_win_rate, min_avg_r = excluded.min_avg_r, \
+         window_trades = excluded.window_trades, consecutive_trades = excluded.consecutive_trades, \
+         is_enabled = excluded.is_enabled, is_degraded = 0, updated_at = excluded.updated_at",
+        params![
+            target.playbook_id.clone(),
+            target.min_win_rate,
+            target.min_avg_r,
+            target.window_trades as i64,
+            target.consecutive_trades as i64,
+            target.is_enabled as i64,
+            timestamps::to_db(&Utc::now())
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_target(conn: &Connection, playbook_id: &str) -> Result<bool> {
+    Ok(conn.execute("DELETE FROM playbook_targets WHERE playbook_id = ?", params![playbook_id]).await? > 0)
+}
+
+/// Closed trades tagged with the playbook, oldest exit first
+async fn outcomes(conn: &Connection, playbook_id: &str) -> Result<Vec<Outcome>> {
+    let sql = format!(
+        "SELECT pnl, risk FROM (
+            SELECT s.exit_date, {STOCK_PNL_SQL} AS pnl,
+                   CASE WHEN s.stop_loss > 0 THEN ABS(s.entry_price - s.stop_loss) * s.number_shares END AS risk
+            FROM stocks s JOIN stock_trade_playbook p ON p.stock_trade_id = s.id
+            WHERE p.setup_id = ?1 AND s.is_deleted = 0 AND s.exit_price IS NOT NULL AND s.exit_date IS NOT NULL
+            UNION ALL
+            SELECT o.exit_date, {OPTION_PNL_SQL} AS pnl, o.entry_price * o.number_of_contracts * {OPTION_CONTRACT_MULTIPLIER} AS risk
+            FROM options o JOIN option_trade_playbook p ON p.option_trade_id = o.id
+            WHERE p.setup_id = ?1 AND o.is_deleted = 0 AND o.status = 'closed' AND o.exit_price IS NOT NULL AND o.exit_date IS NOT NULL
+        ) ORDER BY exit_date ASC"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![playbook_id]).await?;
+    let mut outcomes = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let pnl = money::to_f64(money::row_decimal(&row, 0));
+        let risk = money::to_f64_opt(money::row_decimal_opt(&row, 1)).filter(|r| *r > 0.0);
+        outcomes.push(Outcome { won: pnl > 0.0, r: risk.map(|risk| pnl / risk) });
+    }
+    Ok(outcomes)
+}
+
+/// Current numbers for a playbook against its targets
+pub async fn status(conn: &Connection, target: &PlaybookTarget) -> Result<TargetStatus> {
+    Ok(evaluate(target, &outcomes(conn, &target.playbook_id).await?))
+}
+
+/// A setup that just crossed into degraded
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradationAlert {
+    pub playbook_name: String,
+    pub status: TargetStatus,
+}
+
+/// Evaluate every enabled target, update the alert state and return setups that newly degraded
+pub async fn evaluate_all(conn: &Connection) -> Result<Vec<DegradationAlert>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT t.playbook_id, t.min_win_rate, t.min_avg_r, t.window_trades, t.consecutive_trades, t.is_degraded, p.name \
+             FROM playbook_targets t JOIN playbook p ON p.id = t.playbook_id WHERE t.is_enabled = 1",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    let mut targets = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let target = PlaybookTarget {
+            playbook_id: row.get(0)?,
+            min_win_rate: row.get(1)?,
+            min_avg_r: row.get(2)?,
+            window_trades: row.get::<i64>(3)?.max(0) as u32,
+            consecutive_trades: row.get::<i64>(4)?.max(0) as u32,
+            is_enabled: true,
+        };
+        targets.push((target, row.get::<i64>(5)? != 0, row.get::<String>(6)?));
+    }
+
+    let now = timestamps::to_db(&Utc::now());
+    let mut alerts = Vec::new();
+    for (target, was_degraded, playbook_name) in targets {
+        let status = status(conn, &target).await?;
+        let newly_degraded = status.is_degraded && !was_degraded;
+        conn.execute(
+            "UPDATE playbook_targets SET is_degraded = ?, last_evaluated_at = ?, \
+             last_alerted_at = CASE WHEN ? THEN ? ELSE last_alerted_at END WHERE playbook_id = ?",
+            params![status.is_degraded as i64, now.clone(), newly_degraded as i64, now.clone(), target.playbook_id.clone()],
+        )
+        .await?;
+        if newly_degraded {
+            alerts.push(DegradationAlert { playbook_name, status });
+        }
+    }
+    Ok(alerts)
+}
+
+fn alert_body(alert: &DegradationAlert) -> String {
+    let mut parts = Vec::new();
+    if let Some(win_rate) = alert.status.win_rate.filter(|_| alert.status.failing.iter().any(|f| f == "win_rate")) {
+        parts.push(format!("win rate {:.0}%", win_rate * 100.0));
+    }
+    if let Some(avg_r) = alert.status.avg_r.filter(|_| alert.status.failing.iter().any(|f| f == "avg_r")) {
+        parts.push(format!("average R {:.2}", avg_r));
+    }
+    format!(
+        "{} has been under target for {} trades in a row ({}). Review recent trades before taking the setup again.",
+        alert.playbook_name,
+        alert.status.breach_streak,
+        parts.join(", ")
+    )
+}
+
+/// Push notification for a newly degraded setup
+pub async fn notify(conn: &Connection, web_push: &WebPushConfig, user_id: &str, alert: &DegradationAlert) -> Result<()> {
+    let payload = PushPayload {
+        title: format!("{} is underperforming", alert.playbook_name),
+        body: Some(alert_body(alert)),
+        icon: None,
+        url: Some(format!("/app/playbook/{}", alert.status.playbook_id)),
+        tag: Some(format!("playbook-target-{}", alert.status.playbook_id)),
+        data: Some(serde_json::json!({"type": "playbook_target", "playbook_id": alert.status.playbook_id})),
+    };
+    PushService::new(conn, web_push).send_to_user(user_id, &payload).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(min_win_rate: Option<f64>, min_avg_r: Option<f64>) -> PlaybookTarget {
+        PlaybookTarget {
+            playbook_id: "pb".to_string(),
+            min_win_rate,
+            min_avg_r,
+            window_trades: 5,
+            consecutive_trades: 3,
+            is_enabled: true,
+        }
+    }
+
+    fn outcome(won: bool) -> Outcome {
+        Outcome { won, r: Some(if won { 2.0 } else { -1.0 }) }
+    }
+
+    #[test]
+    fn test_breach_streak_over_rolling_window() {
+        // Five wins, then losses: the 5-trade win rate drops to 80%, 60%, 40%, 20%
+        let mut outcomes: Vec<Outcome> = (0..5).map(|_| outcome(true)).collect();
+        outcomes.extend((0..4).map(|_| outcome(false)));
+
+        let status = evaluate(&target(Some(0.5), None), &outcomes);
+        assert_eq!(status.breach_streak, 2);
+        assert!(!status.is_degraded);
+        assert_eq!(status.failing, vec!["win_rate"]);
+
+        outcomes.push(outcome(false));
+        assert!(evaluate(&target(Some(0.5), None), &outcomes).is_degraded);
+
+        // Wins that lift the window back over target reset the streak
+        outcomes.extend((0..3).map(|_| outcome(true)));
+        assert_eq!(evaluate(&target(Some(0.5), None), &outcomes).breach_streak, 0);
+    }
+
+    #[test]
+    fn test_avg_r_ignores_trades_without_risk_and_short_samples() {
+        let outcomes = vec![Outcome { won: true, r: None }; 4];
+        let status = evaluate(&target(None, Some(1.0)), &outcomes);
+        assert_eq!(status.win_rate, None);
+        assert_eq!(status.breach_streak, 0);
+
+        let mut outcomes = vec![Outcome { won: true, r: Some(0.5) }; 5];
+        outcomes[0].r = None;
+        let status = evaluate(&target(None, Some(1.0)), &outcomes);
+        assert_eq!(status.avg_r, Some(0.5));
+        assert_eq!(status.failing, vec!["avg_r"]);
+        assert!(target(None, None).validate().is_err());
+    }
+}
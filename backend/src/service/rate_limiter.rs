@@ -1,11 +1,20 @@
 use anyhow::{Context, Result};
 use crate::turso::redis::RedisClient;
+use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Rate limit configuration constants
 const RATE_LIMIT_PER_HOUR: u64 = 300;
 const RATE_LIMIT_WINDOW_SECONDS: u64 = 3600;
 
+/// AI chat token bucket: a burst of short messages goes through, sustained use is held
+/// to the refill rate
+pub const CHAT_BURST_CAPACITY: f64 = 8.0;
+pub const CHAT_REFILL_PER_MINUTE: f64 = 2.0;
+/// Messages up to this long cost one token; longer ones cost more, up to the max
+const CHAT_SHORT_MESSAGE_CHARS: usize = 500;
+const CHAT_MAX_MESSAGE_COST: f64 = 3.0;
+
 /// Rate limit result containing remaining requests and reset time
 #[derive(Debug, Clone)]
 pub struct RateLimitResult {
@@ -23,6 +32,43 @@ pub enum RateLimitError {
     Redis(#[from] anyhow::Error),
 }
 
+/// Token bucket state kept in Redis per user
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TokenBucket {
+    pub tokens: f64,
+    pub updated_at_ms: u64,
+}
+
+impl TokenBucket {
+    pub fn full(capacity: f64, now_ms: u64) -> Self {
+        Self { tokens: capacity, updated_at_ms: now_ms }
+    }
+
+    /// Refill for the time since the last update, then take `cost` if there is enough
+    pub fn take(&mut self, cost: f64, capacity: f64, refill_per_sec: f64, now_ms: u64) -> bool {
+        let elapsed_secs = now_ms.saturating_sub(self.updated_at_ms) as f64 / 1000.0;
+        self.tokens = (self.tokens + elapsed_secs * refill_per_sec).min(capacity);
+        self.updated_at_ms = now_ms.max(self.updated_at_ms);
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Seconds until `target` tokens are available
+    pub fn secs_until(&self, target: f64, refill_per_sec: f64) -> u64 {
+        ((target - self.tokens).max(0.0) / refill_per_sec).ceil() as u64
+    }
+}
+
+/// Bucket tokens a chat message costs: one for a short message, more for long ones
+pub fn chat_message_cost(message: &str) -> f64 {
+    let chars = message.chars().count();
+    (1.0 + (chars.saturating_sub(CHAT_SHORT_MESSAGE_CHARS) as f64 / (CHAT_SHORT_MESSAGE_CHARS * 2) as f64)).min(CHAT_MAX_MESSAGE_COST)
+}
+
 /// Rate limiter service using Fixed Window Counter algorithm
 #[derive(Debug, Clone)]
 pub struct RateLimiter {
@@ -100,10 +146,69 @@ impl RateLimiter {
         
         Ok(result)
     }
+
+    /// Take a chat message's cost from the user's AI chat token bucket.
+    ///
+    /// `remaining` is the whole tokens left and `reset_at` the Unix time the bucket is full
+    /// again; when the message doesn't fit, `Exceeded.reset_at` is when it would.
+    pub async fn check_chat_burst(&self, user_id: &str, cost: f64) -> Result<RateLimitResult, RateLimitError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| anyhow::anyhow!("Failed to get system time: {}", e))?;
+        let now_ms = now.as_millis() as u64;
+        let refill_per_sec = CHAT_REFILL_PER_MINUTE / 60.0;
+        let key = format!("rate_limit:chat_burst:{}", user_id);
+
+        let mut bucket = self.redis_client
+            .get::<TokenBucket>(&key)
+            .await
+            .context("Failed to read chat burst bucket")?
+            .unwrap_or_else(|| TokenBucket::full(CHAT_BURST_CAPACITY, now_ms));
+        let allowed = bucket.take(cost, CHAT_BURST_CAPACITY, refill_per_sec, now_ms);
+
+        // An expired key reads as a full bucket, which is what it would have refilled to
+        let secs_to_full = bucket.secs_until(CHAT_BURST_CAPACITY, refill_per_sec);
+        self.redis_client
+            .set(&key, &bucket, secs_to_full.max(1) as usize)
+            .await
+            .context("Failed to store chat burst bucket")?;
+
+        if !allowed {
+            return Err(RateLimitError::Exceeded {
+                remaining: bucket.tokens.floor() as u64,
+                reset_at: now.as_secs() + bucket.secs_until(cost, refill_per_sec),
+            });
+        }
+        Ok(RateLimitResult {
+            remaining: bucket.tokens.floor() as u64,
+            limit: CHAT_BURST_CAPACITY as u64,
+            reset_at: now.as_secs() + secs_to_full,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat_bucket_allows_burst_then_refills() {
+        let refill = CHAT_REFILL_PER_MINUTE / 60.0;
+        let mut bucket = TokenBucket::full(CHAT_BURST_CAPACITY, 0);
+        let short = chat_message_cost("quick question");
+        assert_eq!(short, 1.0);
+        for _ in 0..8 {
+            assert!(bucket.take(short, CHAT_BURST_CAPACITY, refill, 1_000));
+        }
+        assert!(!bucket.take(short, CHAT_BURST_CAPACITY, refill, 2_000));
+        assert_eq!(bucket.secs_until(1.0, refill), 29);
+
+        // 30s at 2/min refills one message
+        assert!(bucket.take(short, CHAT_BURST_CAPACITY, refill, 31_000));
+        assert!(!bucket.take(short, CHAT_BURST_CAPACITY, refill, 31_000));
+        assert_eq!(chat_message_cost(&"x".repeat(1500)), 2.0);
+        assert_eq!(chat_message_cost(&"x".repeat(50_000)), CHAT_MAX_MESSAGE_COST);
+    }
 
     #[test]
     fn test_hour_window_calculation() {
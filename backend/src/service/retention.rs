@@ -0,0 +1,230 @@
+// Per-user data retention. Each user can set how long rows in a fixed catalog of
+// housekeeping tables are kept (chat history, generated insights and reports, job logs);
+// a cron job purges rows past their window. Nothing is purged until a user opts a table
+// in, and every run can be previewed as a dry run first.
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+
+pub const MIN_RETENTION_DAYS: i64 = 7;
+pub const MAX_RETENTION_DAYS: i64 = 3650;
+
+/// A table rows can be purged from
+pub struct RetentionTarget {
+    pub table: &'static str,
+    pub description: &'static str,
+    /// Timestamp a row's age is measured from
+    age_column: &'static str,
+    /// Rows that must never be purged regardless of age (e.g. work in progress)
+    keep_filter: Option<&'static str>,
+    /// `(table, foreign key)` of rows deleted along with each purged row
+    dependent: Option<(&'static str, &'static str)>,
+}
+
+pub const RETENTION_TARGETS: &[RetentionTarget] = &[
+    RetentionTarget {
+        table: "chat_sessions",
+        description: "AI chat sessions and their messages, by last activity",
+        age_column: "COALESCE(last_message_at, updated_at, created_at)",
+        keep_filter: None,
+        dependent: Some(("chat_messages", "session_id")),
+    },
+    RetentionTarget {
+        table: "ai_insights",
+        description: "Generated insights, counted from when they expired",
+        age_column: "expires_at",
+        keep_filter: Some("expires_at IS NULL"),
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "ai_reports",
+        description: "Generated reports, by generation date",
+        age_column: "generated_at",
+        keep_filter: None,
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "insight_generation_tasks",
+        description: "Finished insight generation jobs",
+        age_column: "COALESCE(completed_at, created_at)",
+        keep_filter: Some("LOWER(TRIM(status, '\"')) IN ('pending', 'processing')"),
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "report_generation_tasks",
+        description: "Finished report generation jobs",
+        age_column: "COALESCE(completed_at, created_at)",
+        keep_filter: Some("LOWER(TRIM(status, '\"')) IN ('pending', 'processing')"),
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "brokerage_sync_runs",
+        description: "Brokerage sync history",
+        age_column: "started_at",
+        keep_filter: Some("finished_at IS NULL"),
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "notion_exports",
+        description: "Notion journal export log",
+        age_column: "created_at",
+        keep_filter: None,
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "trade_drafts",
+        description: "Confirmed or dismissed email trade drafts",
+        age_column: "received_at",
+        keep_filter: Some("status = 'pending'"),
+        dependent: None,
+    },
+    RetentionTarget {
+        table: "alert_notifications_sent",
+        description: "Price alert delivery log",
+        age_column: "sent_at",
+        keep_filter: None,
+        dependent: None,
+    },
+];
+
+pub fn target(table: &str) -> Option<&'static RetentionTarget> {
+    RETENTION_TARGETS.iter().find(|t| t.table == table)
+}
+
+impl RetentionTarget {
+    /// WHERE clause selecting purgeable rows; binds the cutoff as `?1`
+    fn purge_condition(&self) -> String {
+        let aged = format!("{} IS NOT NULL AND datetime({}) < datetime(?1)", self.age_column, self.age_column);
+        match self.keep_filter {
+            Some(keep) => format!("{} AND NOT ({})", aged, keep),
+            None => aged,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RetentionPolicy {
+    pub table_name: String,
+    pub description: String,
+    /// None keeps rows forever
+    pub retention_days: Option<i64>,
+}
+
+/// Every catalog table with the user's setting, if any
+pub async fn load_policies(conn: &Connection) -> Result<Vec<RetentionPolicy>> {
+    let mut rows = conn.prepare("SELECT table_name, retention_days FROM retention_policies").await?.query(params![]).await?;
+    let mut configured = std::collections::HashMap::new();
+    while let Some(row) = rows.next().await? {
+        configured.insert(row.get::<String>(0)?, row.get::<Option<i64>>(1)?);
+    }
+    Ok(RETENTION_TARGETS
+        .iter()
+        .map(|t| RetentionPolicy {
+            table_name: t.table.to_string(),
+            description: t.description.to_string(),
+            retention_days: configured.get(t.table).copied().flatten(),
+        })
+        .collect())
+}
+
+pub fn validate_days(retention_days: Option<i64>) -> Result<(), String> {
+    match retention_days {
+        Some(days) if !(MIN_RETENTION_DAYS..=MAX_RETENTION_DAYS).contains(&days) => {
+            Err(format!("retention_days must be between {} and {}", MIN_RETENTION_DAYS, MAX_RETENTION_DAYS))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Set or clear (None) the retention window for a catalog table
+pub async fn set_policy(conn: &Connection, table: &str, retention_days: Option<i64>) -> Result<()> {
+    conn.execute(
+        "INSERT INTO retention_policies (table_name, retention_days, updated_at) VALUES (?, ?, datetime('now'))
+         ON CONFLICT(table_name) DO UPDATE SET retention_days = excluded.retention_days, updated_at = excluded.updated_at",
+        params![table, retention_days],
+    )
+    .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TableRetention {
+    pub table_name: String,
+    pub retention_days: i64,
+    /// Rows older than this are purged
+    pub cutoff: String,
+    /// Rows deleted, or that would be deleted on a dry run
+    pub rows: u64,
+    /// Dependent rows (e.g. chat messages) deleted along with them
+    pub dependent_rows: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RetentionReport {
+    pub dry_run: bool,
+    pub tables: Vec<TableRetention>,
+    pub total_rows: u64,
+}
+
+async fn count(conn: &Connection, sql: &str, cutoff: &str) -> Result<u64> {
+    let mut rows = conn.prepare(sql).await?.query(params![cutoff]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<i64>(0)?.max(0) as u64,
+        None => 0,
+    })
+}
+
+/// Apply the user's policies as of `now`; with `dry_run` only count what would go
+pub async fn apply_retention(conn: &Connection, now: DateTime<Utc>, dry_run: bool) -> Result<RetentionReport> {
+    let mut report = RetentionReport { dry_run, ..Default::default() };
+    for policy in load_policies(conn).await? {
+        let (Some(days), Some(target)) = (policy.retention_days, target(&policy.table_name)) else {
+            continue;
+        };
+        let cutoff = (now - Duration::days(days)).to_rfc3339();
+        let condition = target.purge_condition();
+        let dependent_condition = target.dependent.map(|(child, key)| {
+            (child, format!("{} IN (SELECT id FROM {} WHERE {})", key, target.table, condition))
+        });
+
+        let (rows, dependent_rows) = if dry_run {
+            let dependent_rows = match &dependent_condition {
+                Some((child, filter)) => count(conn, &format!("SELECT COUNT(*) FROM {} WHERE {}", child, filter), &cutoff).await?,
+                None => 0,
+            };
+            (count(conn, &format!("SELECT COUNT(*) FROM {} WHERE {}", target.table, condition), &cutoff).await?, dependent_rows)
+        } else {
+            let dependent_rows = match &dependent_condition {
+                Some((child, filter)) => conn.execute(&format!("DELETE FROM {} WHERE {}", child, filter), params![cutoff.clone()]).await?,
+                None => 0,
+            };
+            (conn.execute(&format!("DELETE FROM {} WHERE {}", target.table, condition), params![cutoff.clone()]).await?, dependent_rows)
+        };
+
+        report.total_rows += rows;
+        report.tables.push(TableRetention { table_name: policy.table_name, retention_days: days, cutoff, rows, dependent_rows });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_condition_keeps_work_in_progress() {
+        let drafts = target("trade_drafts").unwrap();
+        assert_eq!(
+            drafts.purge_condition(),
+            "received_at IS NOT NULL AND datetime(received_at) < datetime(?1) AND NOT (status = 'pending')"
+        );
+        assert!(!target("notion_exports").unwrap().purge_condition().contains("NOT ("));
+        assert!(target("stocks").is_none());
+
+        assert!(validate_days(None).is_ok());
+        assert!(validate_days(Some(90)).is_ok());
+        assert!(validate_days(Some(1)).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+// Public API sandbox. When SANDBOX_TOKEN_SECRET is set the server signs one long-lived
+// token, published with the API docs, that authenticates as a shared demo tenant. The
+// tenant holds the demo journal and is dropped and reseeded nightly, so third-party
+// developers can try the journaling API without an account or real data at stake. Only
+// journaling routes are open to the token; AI, notifications, automations, integrations,
+// share links and account management refuse sandbox requests.
+
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::service::account_deletion::AccountDeletionService;
+use crate::service::demo_data::{self, SeededDemoData};
+use crate::turso::client::TursoClient;
+use crate::turso::config::{SandboxConfig, SupabaseClaims};
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const SANDBOX_ISSUER: &str = "tradstry-sandbox";
+/// 2100-01-01; the token is revoked by rotating the secret, not by expiry
+const TOKEN_EXPIRY: i64 = 4_102_444_800;
+
+/// Journaling scopes a sandbox token may call; everything else is refused
+const ALLOWED_PREFIXES: [&str; 26] = [
+    "/api/stocks",
+    "/api/options",
+    "/api/trades",
+    "/api/trade-notes",
+    "/api/trade-tags",
+    "/api/trade-links",
+    "/api/playbooks",
+    "/api/notebook",
+    "/api/calendar",
+    "/api/analytics",
+    "/api/dashboard",
+    "/api/symbols",
+    "/api/activity",
+    "/api/command-palette",
+    "/api/behavior",
+    "/api/goals",
+    "/api/focus",
+    "/api/risk",
+    "/api/prop-firm",
+    "/api/year-review",
+    "/api/data-quality",
+    "/api/operations",
+    "/api/imports",
+    "/api/onboarding",
+    "/api/i18n",
+    "/api/sandbox",
+];
+
+/// Parts of allowed scopes that still leave the tenant: the LLM trade parser and external
+/// calendar connections
+const BLOCKED_PREFIXES: [&str; 3] = [
+    "/api/trades/parse",
+    "/api/notebook/calendar",
+    "/api/notebook/oauth",
+];
+
+struct Sandbox {
+    config: SandboxConfig,
+    token: String,
+}
+
+static SANDBOX: OnceLock<Sandbox> = OnceLock::new();
+
+/// Enable the sandbox; called once at startup
+pub fn install(config: Option<&SandboxConfig>) {
+    match config {
+        Some(config) => {
+            let token = mint_token(config);
+            let _ = SANDBOX.set(Sandbox { config: config.clone(), token });
+            log::info!("API sandbox enabled for tenant {}", config.user_id);
+        }
+        None => log::info!("SANDBOX_TOKEN_SECRET not set; API sandbox disabled"),
+    }
+}
+
+fn sandbox_claims(config: &SandboxConfig) -> SupabaseClaims {
+    SupabaseClaims {
+        aud: "authenticated".to_string(),
+        exp: TOKEN_EXPIRY,
+        iat: 0,
+        iss: SANDBOX_ISSUER.to_string(),
+        sub: config.user_id.clone(),
+        email: Some(config.email.clone()),
+        phone: None,
+        role: "authenticated".to_string(),
+        aal: "aal1".to_string(),
+        amr: Vec::new(),
+        session_id: "sandbox".to_string(),
+        is_anonymous: Some(true),
+        user_metadata: None,
+        app_metadata: None,
+    }
+}
+
+fn signature(secret: &str, signing_input: &str) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(signing_input.as_bytes());
+    mac
+}
+
+/// HS256 JWT carrying the sandbox tenant's claims; the same config always gives the same token
+fn mint_token(config: &SandboxConfig) -> String {
+    let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#);
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&sandbox_claims(config)).unwrap_or_default());
+    let signing_input = format!("{}.{}", header, payload);
+    let sig = URL_SAFE_NO_PAD.encode(signature(&config.token_secret, &signing_input).finalize().into_bytes());
+    format!("{}.{}", signing_input, sig)
+}
+
+fn verify(config: &SandboxConfig, token: &str) -> Option<SupabaseClaims> {
+    let (signing_input, sig) = token.rsplit_once('.')?;
+    let sig = URL_SAFE_NO_PAD.decode(sig).ok()?;
+    signature(&config.token_secret, signing_input).verify_slice(&sig).ok()?;
+    let payload = URL_SAFE_NO_PAD.decode(signing_input.split('.').nth(1)?).ok()?;
+    let claims: SupabaseClaims = serde_json::from_slice(&payload).ok()?;
+    (claims.iss == SANDBOX_ISSUER && claims.sub == config.user_id && claims.exp > Utc::now().timestamp()).then_some(claims)
+}
+
+/// Claims for a valid sandbox token; None for every other token or when the sandbox is off
+pub fn claims_for_token(token: &str) -> Option<SupabaseClaims> {
+    verify(&SANDBOX.get()?.config, token)
+}
+
+/// The published token, when the sandbox is on
+pub fn token() -> Option<&'static str> {
+    SANDBOX.get().map(|s| s.token.as_str())
+}
+
+pub fn user_id() -> Option<&'static str> {
+    SANDBOX.get().map(|s| s.config.user_id.as_str())
+}
+
+pub fn is_sandbox_user(user_id: &str) -> bool {
+    SANDBOX.get().is_some_and(|s| s.config.user_id == user_id)
+}
+
+/// Whether a sandbox request to this path is refused
+pub fn blocked_path(path: &str) -> bool {
+    let under = |prefix: &&str| path == *prefix || path.starts_with(&format!("{}/", prefix));
+    !ALLOWED_PREFIXES.iter().any(under)
+        || BLOCKED_PREFIXES.iter().any(under)
+        // AI actions inside journaling scopes, like the notebook assistant
+        || path.split('/').any(|segment| segment == "ai")
+        || path.ends_with("/share")
+        || path.contains("/shares")
+}
+
+/// Drop the sandbox tenant's database, storage and vectors, then recreate and reseed it
+pub async fn reset(turso_client: &TursoClient, account_deletion: &AccountDeletionService) -> Result<SeededDemoData> {
+    let config = &SANDBOX.get().ok_or_else(|| anyhow!("The API sandbox is not enabled"))?.config;
+    if let Some(entry) = turso_client.get_user_database(&config.user_id).await? {
+        account_deletion
+            .reclaim_tenant(&config.user_id, &entry.db_name, true)
+            .await
+            .context("Failed to drop the sandbox tenant")?;
+    }
+    turso_client.create_user_database(&config.user_id, &config.email).await?;
+    let conn = turso_client
+        .get_user_database_connection(&config.user_id)
+        .await?
+        .ok_or_else(|| anyhow!("Sandbox database missing after creation"))?;
+    demo_data::seed(&conn, Utc::now()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_round_trip_and_blocked_paths() {
+        let config = SandboxConfig { token_secret: "s3cret".to_string(), user_id: "sandbox-demo".to_string(), email: "sandbox@example.com".to_string() };
+        let token = mint_token(&config);
+        assert_eq!(token, mint_token(&config));
+        assert_eq!(verify(&config, &token).unwrap().sub, "sandbox-demo");
+
+        let rotated = SandboxConfig { token_secret: "other".to_string(), ..config.clone() };
+        assert!(verify(&rotated, &token).is_none());
+        assert!(verify(&config, &token[..token.len() - 1]).is_none());
+
+        assert!(blocked_path("/api/brokerage/connections"));
+        assert!(blocked_path("/api/ai/reports/abc/share"));
+        assert!(blocked_path("/api/ai/chat"));
+        assert!(blocked_path("/api/notebook/notes/n1/ai"));
+        assert!(blocked_path("/api/notifications/digest"));
+        assert!(blocked_path("/api/automations/rules"));
+        assert!(blocked_path("/api/trades/parse"));
+        assert!(blocked_path("/api/stocksplits"));
+        assert!(!blocked_path("/api/stocks/12"));
+        assert!(!blocked_path("/api/notebook/notes/n1"));
+        assert!(!blocked_path("/api/trades/stock/12/tags"));
+    }
+}
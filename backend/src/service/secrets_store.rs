@@ -0,0 +1,138 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Context, Result};
+use base64::Engine;
+use libsql::{params, Connection};
+
+/// Encrypted store for third-party credentials (OAuth tokens, bot tokens).
+///
+/// Values are sealed with AES-256-GCM before they reach the user's database,
+/// so a leaked database dump doesn't expose integration tokens.
+#[derive(Clone)]
+pub struct SecretsStore {
+    cipher: Aes256Gcm,
+}
+
+impl SecretsStore {
+    /// Build from a base64-encoded 32-byte key (`SECRETS_ENCRYPTION_KEY`)
+    pub fn new(key_b64: &str) -> Result<Self> {
+        let key_bytes = base64::engine::general_purpose::STANDARD
+            .decode(key_b64.trim())
+            .context("SECRETS_ENCRYPTION_KEY is not valid base64")?;
+        if key_bytes.len() != 32 {
+            anyhow::bail!("SECRETS_ENCRYPTION_KEY must decode to 32 bytes, got {}", key_bytes.len());
+        }
+        let cipher = Aes256Gcm::new_from_slice(&key_bytes).map_err(|_| anyhow::anyhow!("Invalid SECRETS_ENCRYPTION_KEY"))?;
+        Ok(Self { cipher })
+    }
+
+    /// Build from the optional config value, failing with a clear message when unset
+    pub fn from_config(key_b64: Option<&str>) -> Result<Self> {
+        let key = key_b64.ok_or_else(|| anyhow::anyhow!("SECRETS_ENCRYPTION_KEY environment variable not set"))?;
+        Self::new(key)
+    }
+
+    /// Encrypt a value, returning (ciphertext, nonce) as base64
+    pub fn encrypt(&self, plaintext: &str) -> Result<(String, String)> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt secret"))?;
+        let engine = base64::engine::general_purpose::STANDARD;
+        Ok((engine.encode(ciphertext), engine.encode(nonce)))
+    }
+
+    /// Decrypt a (ciphertext, nonce) pair produced by `encrypt`
+    pub fn decrypt(&self, ciphertext_b64: &str, nonce_b64: &str) -> Result<String> {
+        let engine = base64::engine::general_purpose::STANDARD;
+        let ciphertext = engine.decode(ciphertext_b64).context("Invalid secret ciphertext")?;
+        let nonce_bytes = engine.decode(nonce_b64).context("Invalid secret nonce")?;
+        let nonce: [u8; 12] = nonce_bytes.try_into().map_err(|_| anyhow::anyhow!("Invalid secret nonce length"))?;
+        let plaintext = self
+            .cipher
+            .decrypt(&Nonce::from(nonce), ciphertext.as_ref())
+            .map_err(|_| anyhow::anyhow!("Failed to decrypt secret (wrong key or corrupted value)"))?;
+        String::from_utf8(plaintext).context("Decrypted secret is not valid UTF-8")
+    }
+
+    /// Store or replace a secret for a provider
+    pub async fn put(&self, conn: &Connection, provider: &str, name: &str, value: &str) -> Result<()> {
+        let (ciphertext, nonce) = self.encrypt(value)?;
+        conn.execute(
+            "INSERT INTO integration_secrets (provider, name, ciphertext, nonce) VALUES (?, ?, ?, ?)
+             ON CONFLICT(provider, name) DO UPDATE SET ciphertext = excluded.ciphertext, nonce = excluded.nonce, updated_at = datetime('now')",
+            params![provider, name, ciphertext, nonce],
+        )
+        .await
+        .context("Failed to store secret")?;
+        Ok(())
+    }
+
+    /// Load and decrypt a secret, if present
+    pub async fn get(&self, conn: &Connection, provider: &str, name: &str) -> Result<Option<String>> {
+        let mut rows = conn
+            .prepare("SELECT ciphertext, nonce FROM integration_secrets WHERE provider = ? AND name = ?")
+            .await?
+            .query(params![provider, name])
+            .await?;
+
+        match rows.next().await? {
+            Some(row) => {
+                let ciphertext: String = row.get(0)?;
+                let nonce: String = row.get(1)?;
+                Ok(Some(self.decrypt(&ciphertext, &nonce)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove every secret stored for a provider
+    pub async fn delete_provider(conn: &Connection, provider: &str) -> Result<u64> {
+        let n = conn
+            .execute("DELETE FROM integration_secrets WHERE provider = ?", params![provider])
+            .await?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> SecretsStore {
+        let key = base64::engine::general_purpose::STANDARD.encode([7u8; 32]);
+        SecretsStore::new(&key).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let store = store();
+        let (ciphertext, nonce) = store.encrypt("secret_abc123").unwrap();
+        assert_ne!(ciphertext, "secret_abc123");
+        assert_eq!(store.decrypt(&ciphertext, &nonce).unwrap(), "secret_abc123");
+    }
+
+    #[test]
+    fn test_nonce_is_unique_per_value() {
+        let store = store();
+        let (c1, n1) = store.encrypt("same").unwrap();
+        let (c2, n2) = store.encrypt("same").unwrap();
+        assert_ne!(n1, n2);
+        assert_ne!(c1, c2);
+    }
+
+    #[test]
+    fn test_wrong_key_fails() {
+        let (ciphertext, nonce) = store().encrypt("token").unwrap();
+        let other_key = base64::engine::general_purpose::STANDARD.encode([9u8; 32]);
+        let other = SecretsStore::new(&other_key).unwrap();
+        assert!(other.decrypt(&ciphertext, &nonce).is_err());
+    }
+
+    #[test]
+    fn test_rejects_short_key() {
+        let key = base64::engine::general_purpose::STANDARD.encode([1u8; 16]);
+        assert!(SecretsStore::new(&key).is_err());
+    }
+}
@@ -0,0 +1,450 @@
+// Monthly performance statements. After a month closes each journal gets a one-page PDF in
+// the spirit of a broker statement: headline stats, a calendar heatmap of daily P&L, the
+// month's equity curve, the commissions paid and progress against the monthly goal. The
+// PDF is kept in object storage and listed in `statements`, one row per month; generating a
+// month again replaces its statement.
+
+pub mod pdf;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::service::goal_pacing;
+use crate::service::image_upload::ImageUploadService;
+use pdf::{Page, Rgb, PAGE_HEIGHT};
+
+const STATEMENT_COLUMNS: &str = "id, month, object_path, size_bytes, summary, created_at";
+
+const GREEN: Rgb = Rgb(0.13, 0.59, 0.33);
+const RED: Rgb = Rgb(0.8, 0.2, 0.2);
+const MARGIN: f64 = 48.0;
+/// Width of the equity curve and goal bar
+const CHART_WIDTH: f64 = 234.0;
+
+/// One closed trade counted in the statement
+#[derive(Debug, Clone, PartialEq)]
+struct ClosedTrade {
+    day: NaiveDate,
+    is_option: bool,
+    pnl: f64,
+    commissions: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeeSummary {
+    pub stock_commissions: f64,
+    pub option_commissions: f64,
+    pub total: f64,
+    /// Fees as a share of P&L before fees; None when that was not positive
+    pub pct_of_gross: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub pnl_target: f64,
+    pub progress_pct: f64,
+    pub hit_on: Option<String>,
+}
+
+/// Figures printed on the statement, also stored with it for the list view
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StatementSummary {
+    pub net_pnl: f64,
+    pub trades: u32,
+    pub winners: u32,
+    pub losers: u32,
+    pub win_rate: Option<f64>,
+    pub profit_factor: Option<f64>,
+    pub largest_win: Option<f64>,
+    pub largest_loss: Option<f64>,
+    pub best_day: Option<(String, f64)>,
+    pub worst_day: Option<(String, f64)>,
+    pub trading_days: u32,
+    pub fees: FeeSummary,
+    pub goal: Option<GoalProgress>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Statement {
+    pub id: String,
+    pub month: String,
+    #[serde(skip)]
+    pub object_path: String,
+    pub size_bytes: i64,
+    pub summary: StatementSummary,
+    pub created_at: String,
+}
+
+fn summarize(trades: &[ClosedTrade], daily: &BTreeMap<NaiveDate, f64>) -> StatementSummary {
+    let wins: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|p| *p > 0.0).collect();
+    let losses: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|p| *p < 0.0).collect();
+    let gross_win: f64 = wins.iter().sum();
+    let gross_loss: f64 = -losses.iter().sum::<f64>();
+    let net_pnl: f64 = trades.iter().map(|t| t.pnl).sum();
+
+    let stock_commissions: f64 = trades.iter().filter(|t| !t.is_option).map(|t| t.commissions).sum();
+    let option_commissions: f64 = trades.iter().filter(|t| t.is_option).map(|t| t.commissions).sum();
+    let total_fees = stock_commissions + option_commissions;
+    let gross = net_pnl + total_fees;
+
+    let day_entry = |(day, pnl): (&NaiveDate, &f64)| (day.to_string(), *pnl);
+    StatementSummary {
+        net_pnl,
+        trades: trades.len() as u32,
+        winners: wins.len() as u32,
+        losers: losses.len() as u32,
+        win_rate: (!trades.is_empty()).then(|| wins.len() as f64 / trades.len() as f64 * 100.0),
+        profit_factor: (gross_loss > 0.0).then(|| gross_win / gross_loss),
+        largest_win: wins.iter().copied().reduce(f64::max),
+        largest_loss: losses.iter().copied().reduce(f64::min),
+        best_day: daily.iter().max_by(|a, b| a.1.total_cmp(b.1)).filter(|(_, p)| **p > 0.0).map(day_entry),
+        worst_day: daily.iter().min_by(|a, b| a.1.total_cmp(b.1)).filter(|(_, p)| **p < 0.0).map(day_entry),
+        trading_days: daily.len() as u32,
+        fees: FeeSummary {
+            stock_commissions,
+            option_commissions,
+            total: total_fees,
+            pct_of_gross: (gross > 0.0).then(|| total_fees / gross * 100.0),
+        },
+        goal: None,
+    }
+}
+
+/// Trades closed on trading dates within `month` (YYYY-MM)
+async fn closed_trades(conn: &Connection, periods: &PeriodDefinition, month: &str) -> Result<Vec<ClosedTrade>> {
+    let day = format!("date({})", periods.trading_date_sql("exit_date"));
+    let sql = format!(
+        "SELECT day, is_option, pnl, commissions FROM (
+            SELECT {day} AS day, 0 AS is_option, {STOCK_PNL_SQL} AS pnl, commissions FROM stocks
+            WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+            UNION ALL
+            SELECT {day} AS day, 1 AS is_option, {OPTION_PNL_SQL} AS pnl, commissions FROM options
+            WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+        ) WHERE strftime('%Y-%m', day) = ? ORDER BY day"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![month]).await?;
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let Ok(day) = NaiveDate::parse_from_str(&row.get::<String>(0)?, "%Y-%m-%d") else { continue };
+        trades.push(ClosedTrade {
+            day,
+            is_option: row.get::<i64>(1)? != 0,
+            pnl: money::to_f64(money::row_decimal(&row, 2)),
+            commissions: money::to_f64(money::row_decimal(&row, 3)),
+        });
+    }
+    Ok(trades)
+}
+
+pub(crate) async fn account_name(conn: &Connection) -> Option<String> {
+    let mut rows = conn.prepare("SELECT COALESCE(display_name, nickname) FROM user_profile LIMIT 1").await.ok()?.query(params![]).await.ok()?;
+    rows.next().await.ok().flatten()?.get::<Option<String>>(0).ok().flatten().filter(|n| !n.trim().is_empty())
+}
+
+pub(crate) fn money_text(value: f64) -> String {
+    let sign = if value < 0.0 { "-" } else { "" };
+    format!("{}${:.2}", sign, value.abs())
+}
+
+pub(crate) fn pct_text(value: Option<f64>) -> String {
+    value.map(|v| format!("{:.1}%", v)).unwrap_or_else(|| "-".to_string())
+}
+
+/// Heatmap cell colour: stronger green or red the larger the day relative to the month's biggest
+fn heat_color(pnl: f64, max_abs: f64) -> Rgb {
+    if max_abs <= 0.0 || pnl == 0.0 {
+        return Rgb::LIGHT_GREY;
+    }
+    let t = 0.25 + 0.75 * (pnl.abs() / max_abs).min(1.0);
+    let base = if pnl > 0.0 { GREEN } else { RED };
+    let blend = |c: f64| 1.0 - t * (1.0 - c);
+    Rgb(blend(base.0), blend(base.1), blend(base.2))
+}
+
+/// Row and column of a date in a Sunday-first month calendar
+fn calendar_cell(day: NaiveDate) -> (u32, u32) {
+    let first = day.with_day(1).unwrap_or(day);
+    let offset = first.weekday().num_days_from_sunday();
+    let index = offset + day.day0();
+    (index / 7, index % 7)
+}
+
+fn render(month_start: NaiveDate, account: Option<&str>, summary: &StatementSummary, daily: &BTreeMap<NaiveDate, f64>) -> Vec<u8> {
+    let mut page = Page::new();
+    let month_label = month_start.format("%B %Y").to_string();
+    let title = format!("Monthly Statement - {}", month_label);
+
+    let top = PAGE_HEIGHT - MARGIN;
+    page.text(MARGIN, top - 20.0, 20.0, true, Rgb::BLACK, &title);
+    let subtitle = match account {
+        Some(name) => format!("{} - generated {}", name, Utc::now().format("%Y-%m-%d")),
+        None => format!("Generated {}", Utc::now().format("%Y-%m-%d")),
+    };
+    page.text(MARGIN, top - 38.0, 10.0, false, Rgb::GREY, &subtitle);
+
+    // Top stats, two columns
+    let pnl_color = if summary.net_pnl < 0.0 { RED } else { GREEN };
+    page.text(MARGIN, top - 72.0, 10.0, false, Rgb::GREY, "Net P&L");
+    page.text(MARGIN, top - 94.0, 22.0, true, pnl_color, &money_text(summary.net_pnl));
+    let day_text = |d: &Option<(String, f64)>| d.as_ref().map(|(day, pnl)| format!("{} ({})", money_text(*pnl), day)).unwrap_or_else(|| "-".to_string());
+    let stats = [
+        ("Trades closed", summary.trades.to_string()),
+        ("Win rate", pct_text(summary.win_rate)),
+        ("Profit factor", summary.profit_factor.map(|p| format!("{:.2}", p)).unwrap_or_else(|| "-".to_string())),
+        ("Trading days", summary.trading_days.to_string()),
+        ("Largest win", summary.largest_win.map(money_text).unwrap_or_else(|| "-".to_string())),
+        ("Largest loss", summary.largest_loss.map(money_text).unwrap_or_else(|| "-".to_string())),
+        ("Best day", day_text(&summary.best_day)),
+        ("Worst day", day_text(&summary.worst_day)),
+    ];
+    for (i, (label, value)) in stats.iter().enumerate() {
+        let x = 210.0 + (i / 4) as f64 * 190.0;
+        let y = top - 66.0 - (i % 4) as f64 * 16.0;
+        page.text(x, y, 9.0, false, Rgb::GREY, label);
+        page.text(x + 78.0, y, 9.0, true, Rgb::BLACK, value);
+    }
+
+    // Calendar heatmap
+    let (cell_w, cell_h) = (36.0, 30.0);
+    let grid_top = top - 170.0;
+    page.text(MARGIN, grid_top + 22.0, 12.0, true, Rgb::BLACK, "Daily P&L");
+    for (i, name) in ["S", "M", "T", "W", "T", "F", "S"].iter().enumerate() {
+        page.text(MARGIN + i as f64 * cell_w + 14.0, grid_top + 6.0, 8.0, false, Rgb::GREY, name);
+    }
+    let max_abs = daily.values().fold(0.0_f64, |m, p| m.max(p.abs()));
+    let mut day = month_start;
+    while day.month() == month_start.month() {
+        let (row, col) = calendar_cell(day);
+        let x = MARGIN + col as f64 * cell_w;
+        let y = grid_top - (row + 1) as f64 * cell_h;
+        let pnl = daily.get(&day).copied();
+        page.fill_rect(x + 1.0, y + 1.0, cell_w - 2.0, cell_h - 2.0, heat_color(pnl.unwrap_or(0.0), max_abs));
+        page.text(x + 3.0, y + cell_h - 10.0, 7.0, false, Rgb::BLACK, &day.day().to_string());
+        if let Some(pnl) = pnl {
+            page.text(x + 3.0, y + 5.0, 6.0, true, Rgb::BLACK, &format!("{:.0}", pnl));
+        }
+        day += Duration::days(1);
+    }
+
+    // Equity curve: running P&L through each calendar day of the month
+    let (chart_x, chart_w, chart_h) = (330.0, CHART_WIDTH, 150.0);
+    let chart_y = grid_top - 180.0;
+    page.text(chart_x, grid_top + 22.0, 12.0, true, Rgb::BLACK, "Equity curve");
+    let days_in_month = {
+        let next = if month_start.month() == 12 {
+            NaiveDate::from_ymd_opt(month_start.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(month_start.year(), month_start.month() + 1, 1)
+        };
+        next.map(|n| (n - month_start).num_days()).unwrap_or(30)
+    };
+    let mut running = 0.0;
+    let curve: Vec<f64> = std::iter::once(0.0)
+        .chain((0..days_in_month).map(|i| {
+            running += daily.get(&(month_start + Duration::days(i))).copied().unwrap_or(0.0);
+            running
+        }))
+        .collect();
+    let low = curve.iter().copied().fold(0.0_f64, f64::min);
+    let high = curve.iter().copied().fold(0.0_f64, f64::max);
+    let span = if high - low > 0.0 { high - low } else { 1.0 };
+    let to_y = |v: f64| chart_y + (v - low) / span * chart_h;
+    let step = chart_w / (curve.len().max(2) - 1) as f64;
+    page.line(&[(chart_x, chart_y), (chart_x, chart_y + chart_h)], 0.5, Rgb::GREY);
+    page.line(&[(chart_x, to_y(0.0)), (chart_x + chart_w, to_y(0.0))], 0.5, Rgb::GREY);
+    let points: Vec<(f64, f64)> = curve.iter().enumerate().map(|(i, v)| (chart_x + i as f64 * step, to_y(*v))).collect();
+    page.line(&points, 1.5, pnl_color);
+    page.text(chart_x + 4.0, chart_y + chart_h - 8.0, 7.0, false, Rgb::GREY, &money_text(high));
+    page.text(chart_x + 4.0, chart_y + 2.0, 7.0, false, Rgb::GREY, &money_text(low));
+
+    // Fees
+    let fees_top = chart_y - 50.0;
+    page.text(MARGIN, fees_top, 12.0, true, Rgb::BLACK, "Fees");
+    let fee_rows = [
+        ("Stock commissions", money_text(summary.fees.stock_commissions)),
+        ("Option commissions", money_text(summary.fees.option_commissions)),
+        ("Total fees", money_text(summary.fees.total)),
+        ("Share of P&L before fees", pct_text(summary.fees.pct_of_gross)),
+    ];
+    for (i, (label, value)) in fee_rows.iter().enumerate() {
+        let y = fees_top - 18.0 - i as f64 * 15.0;
+        page.text(MARGIN, y, 9.0, false, Rgb::GREY, label);
+        page.text(MARGIN + 140.0, y, 9.0, true, Rgb::BLACK, value);
+    }
+
+    // Goal progress
+    page.text(chart_x, fees_top, 12.0, true, Rgb::BLACK, "Monthly goal");
+    match &summary.goal {
+        Some(goal) => {
+            let bar_y = fees_top - 34.0;
+            page.fill_rect(chart_x, bar_y, CHART_WIDTH, 12.0, Rgb::LIGHT_GREY);
+            let filled = (goal.progress_pct / 100.0).clamp(0.0, 1.0) * CHART_WIDTH;
+            if filled > 0.0 {
+                page.fill_rect(chart_x, bar_y, filled, 12.0, GREEN);
+            }
+            let reached = match &goal.hit_on {
+                Some(day) => format!(", reached on {}", day),
+                None => String::new(),
+            };
+            page.text(
+                chart_x,
+                bar_y - 16.0,
+                9.0,
+                false,
+                Rgb::BLACK,
+                &format!("{} of {} target ({:.0}%){}", money_text(summary.net_pnl), money_text(goal.pnl_target), goal.progress_pct, reached),
+            );
+        }
+        None => page.text(chart_x, fees_top - 18.0, 9.0, false, Rgb::GREY, "No monthly goal was set"),
+    }
+
+    page.text(MARGIN, MARGIN - 16.0, 7.0, false, Rgb::GREY, "Realized P&L from journal entries, net of recorded commissions. Not a broker statement.");
+    page.into_pdf(&title)
+}
+
+/// Calendar month before the one containing `now`, as YYYY-MM
+pub fn previous_month(now: DateTime<Utc>) -> String {
+    let first = now.date_naive().with_day(1).unwrap_or(now.date_naive());
+    (first - Duration::days(1)).format("%Y-%m").to_string()
+}
+
+fn row_to_statement(row: &libsql::Row) -> Result<Statement> {
+    Ok(Statement {
+        id: row.get(0)?,
+        month: row.get(1)?,
+        object_path: row.get(2)?,
+        size_bytes: row.get(3)?,
+        summary: serde_json::from_str(&row.get::<String>(4)?).unwrap_or_default(),
+        created_at: row.get(5)?,
+    })
+}
+
+pub async fn list_statements(conn: &Connection) -> Result<Vec<Statement>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {STATEMENT_COLUMNS} FROM statements ORDER BY month DESC"))
+        .await?
+        .query(params![])
+        .await?;
+    let mut statements = Vec::new();
+    while let Some(row) = rows.next().await? {
+        statements.push(row_to_statement(&row)?);
+    }
+    Ok(statements)
+}
+
+pub async fn get_statement(conn: &Connection, id: &str) -> Result<Option<Statement>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {STATEMENT_COLUMNS} FROM statements WHERE id = ?"))
+        .await?
+        .query(params![id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_statement(&row)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn statement_for_month(conn: &Connection, month: &str) -> Result<Option<Statement>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {STATEMENT_COLUMNS} FROM statements WHERE month = ?"))
+        .await?
+        .query(params![month])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_statement(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Build the statement for `month` (YYYY-MM), store the PDF and record it. With
+/// `skip_empty`, a month without closed trades gets no statement and None is returned.
+pub async fn generate(conn: &Connection, storage: &ImageUploadService, user_id: &str, month: &str, skip_empty: bool) -> Result<Option<Statement>> {
+    let month_start = goal_pacing::parse_month(month).ok_or_else(|| anyhow!("month must be YYYY-MM"))?;
+    let month = month_start.format("%Y-%m").to_string();
+    let periods = PeriodDefinition::load(conn).await;
+    let trades = closed_trades(conn, &periods, &month).await?;
+    if skip_empty && trades.is_empty() {
+        return Ok(None);
+    }
+    let mut daily: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for trade in &trades {
+        *daily.entry(trade.day).or_default() += trade.pnl;
+    }
+
+    let mut summary = summarize(&trades, &daily);
+    if let Some(goal) = goal_pacing::goal_for_month(conn, &month).await? {
+        let totals: HashMap<NaiveDate, (f64, u32)> = daily.iter().map(|(day, pnl)| (*day, (*pnl, 0))).collect();
+        summary.goal = Some(GoalProgress {
+            pnl_target: goal.pnl_target,
+            progress_pct: if goal.pnl_target > 0.0 { summary.net_pnl / goal.pnl_target * 100.0 } else { 0.0 },
+            hit_on: goal_pacing::goal_hit_date(&totals, goal.pnl_target).map(|d| d.to_string()),
+        });
+    }
+
+    let bytes = render(month_start, account_name(conn).await.as_deref(), &summary, &daily);
+    let object_path = format!("{}/statements/{}.pdf", user_id, month);
+    storage.put_object(&object_path, &bytes, "application/pdf").await?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    conn.execute(
+        "INSERT INTO statements (id, month, object_path, size_bytes, summary, created_at) VALUES (?, ?, ?, ?, ?, ?)
+         ON CONFLICT(month) DO UPDATE SET object_path = excluded.object_path, size_bytes = excluded.size_bytes,
+             summary = excluded.summary, created_at = excluded.created_at",
+        params![id, month.as_str(), object_path, bytes.len() as i64, serde_json::to_string(&summary)?, Utc::now().to_rfc3339()],
+    )
+    .await?;
+    statement_for_month(conn, &month).await?.ok_or_else(|| anyhow!("Statement missing after saving")).map(Some)
+}
+
+/// The stored PDF
+pub async fn download(storage: &ImageUploadService, statement: &Statement) -> Result<Vec<u8>> {
+    storage.download_file(&statement.object_path).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(day: u32, is_option: bool, pnl: f64, commissions: f64) -> ClosedTrade {
+        ClosedTrade { day: NaiveDate::from_ymd_opt(2026, 9, day).unwrap(), is_option, pnl, commissions }
+    }
+
+    #[test]
+    fn test_summary_stats_and_fees() {
+        let trades = [trade(1, false, 300.0, 2.0), trade(1, true, -100.0, 3.0), trade(2, false, -50.0, 1.0)];
+        let mut daily = BTreeMap::new();
+        for t in &trades {
+            *daily.entry(t.day).or_insert(0.0) += t.pnl;
+        }
+        let summary = summarize(&trades, &daily);
+
+        assert_eq!(summary.net_pnl, 150.0);
+        assert_eq!((summary.trades, summary.winners, summary.losers), (3, 1, 2));
+        assert_eq!(summary.profit_factor, Some(2.0));
+        assert_eq!(summary.largest_loss, Some(-100.0));
+        assert_eq!(summary.best_day, Some(("2026-09-01".to_string(), 200.0)));
+        assert_eq!(summary.worst_day, Some(("2026-09-02".to_string(), -50.0)));
+        assert_eq!(summary.fees.total, 6.0);
+        assert_eq!(summary.fees.pct_of_gross, Some(6.0 / 156.0 * 100.0));
+    }
+
+    #[test]
+    fn test_calendar_layout_and_previous_month() {
+        // September 2026 starts on a Tuesday
+        assert_eq!(calendar_cell(NaiveDate::from_ymd_opt(2026, 9, 1).unwrap()), (0, 2));
+        assert_eq!(calendar_cell(NaiveDate::from_ymd_opt(2026, 9, 30).unwrap()), (4, 3));
+        assert_eq!(heat_color(0.0, 100.0), Rgb::LIGHT_GREY);
+        let (win, loss) = (heat_color(100.0, 100.0), heat_color(-20.0, 100.0));
+        assert!(win.1 > win.0 && loss.0 > loss.1);
+        assert!(loss.1 > RED.1, "small losses are paler than the biggest");
+
+        let now = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap().and_hms_opt(6, 0, 0).unwrap().and_utc();
+        assert_eq!(previous_month(now), "2025-12");
+    }
+}
@@ -0,0 +1,127 @@
+// Minimal single-page PDF writer: filled rectangles, polylines and text in the standard
+// Helvetica faces, which every viewer ships, so no fonts are embedded. Coordinates are PDF
+// points from the bottom-left corner of a US Letter page.
+
+use std::fmt::Write;
+
+pub const PAGE_WIDTH: f64 = 612.0;
+pub const PAGE_HEIGHT: f64 = 792.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rgb(pub f64, pub f64, pub f64);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0.0, 0.0, 0.0);
+    pub const GREY: Rgb = Rgb(0.45, 0.45, 0.45);
+    pub const LIGHT_GREY: Rgb = Rgb(0.92, 0.92, 0.92);
+}
+
+#[derive(Debug, Default)]
+pub struct Page {
+    ops: String,
+}
+
+/// Escape a string for a PDF literal; characters outside Latin-1 become '?'
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+impl Page {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&mut self, x: f64, y: f64, size: f64, bold: bool, color: Rgb, text: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        let _ = writeln!(
+            self.ops,
+            "BT {:.3} {:.3} {:.3} rg /{} {:.1} Tf {:.2} {:.2} Td ({}) Tj ET",
+            color.0, color.1, color.2, font, size, x, y, escape(text)
+        );
+    }
+
+    pub fn fill_rect(&mut self, x: f64, y: f64, width: f64, height: f64, color: Rgb) {
+        let _ = writeln!(self.ops, "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f", color.0, color.1, color.2, x, y, width, height);
+    }
+
+    pub fn line(&mut self, points: &[(f64, f64)], width: f64, color: Rgb) {
+        let Some(((x0, y0), rest)) = points.split_first() else { return };
+        let _ = write!(self.ops, "{:.3} {:.3} {:.3} RG {:.2} w {:.2} {:.2} m", color.0, color.1, color.2, width, x0, y0);
+        for (x, y) in rest {
+            let _ = write!(self.ops, " {:.2} {:.2} l", x, y);
+        }
+        self.ops.push_str(" S\n");
+    }
+
+    /// The page as a complete PDF document
+    pub fn into_pdf(self, title: &str) -> Vec<u8> {
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+                PAGE_WIDTH, PAGE_HEIGHT
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+            format!("<< /Length {} >>\nstream\n{}endstream", self.ops.len(), self.ops),
+            format!("<< /Title ({}) /Producer (Tradstry) >>", escape(title)),
+        ];
+
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, body) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, body);
+        }
+        let xref_at = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(out, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            objects.len(),
+            xref_at
+        );
+        out.into_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_xref_offsets_point_at_objects() {
+        let mut page = Page::new();
+        page.text(48.0, 740.0, 18.0, true, Rgb::BLACK, "P&L (October) \\ cafe\u{301}");
+        page.fill_rect(48.0, 600.0, 30.0, 20.0, Rgb::LIGHT_GREY);
+        page.line(&[(0.0, 0.0), (10.0, 5.0), (20.0, 2.0)], 1.0, Rgb::GREY);
+        let pdf = String::from_utf8(page.into_pdf("Statement")).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n") && pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("(P&L \\(October\\) \\\\ cafe?) Tj"));
+
+        let startxref: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[startxref..].starts_with("xref\n0 8\n"));
+        let entries: Vec<&str> = pdf[startxref..].lines().skip(3).take(7).collect();
+        for (i, entry) in entries.iter().enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
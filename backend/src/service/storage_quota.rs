@@ -4,11 +4,93 @@ use log::{info, warn, error};
 use thiserror::Error;
 use actix_web::{HttpResponse, ResponseError};
 
+use crate::service::ai_service::QdrantDocumentClient;
+use crate::service::notifications::push::{PushPayload, PushService};
 use crate::turso::client::TursoClient;
+use crate::turso::config::WebPushConfig;
 
 /// Storage quota limit per user (18 MB)
 pub const STORAGE_QUOTA_LIMIT_BYTES: u64 = 18 * 1024 * 1024; // 18,874,368 bytes
 
+/// Uploaded image storage limit per user (50 MB)
+pub const IMAGE_QUOTA_LIMIT_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Vector (AI memory) limit per user
+pub const VECTOR_QUOTA_LIMIT: u64 = 5_000;
+
+/// Usage percentages that trigger a notification
+const WARNING_THRESHOLDS: [u32; 2] = [80, 100];
+
+/// Limits of the user's plan. There is a single plan for now.
+#[derive(Debug, Clone, Copy, serde::Serialize)]
+pub struct PlanLimits {
+    pub plan: &'static str,
+    pub database_bytes: u64,
+    pub image_bytes: u64,
+    pub vector_count: u64,
+}
+
+impl PlanLimits {
+    pub const FREE: PlanLimits = PlanLimits {
+        plan: "free",
+        database_bytes: STORAGE_QUOTA_LIMIT_BYTES,
+        image_bytes: IMAGE_QUOTA_LIMIT_BYTES,
+        vector_count: VECTOR_QUOTA_LIMIT,
+    };
+}
+
+/// Usage of one resource against its limit
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResourceUsage {
+    pub used: u64,
+    pub limit: u64,
+    pub percentage_used: f64,
+    /// Highest warning threshold reached (80 or 100), if any
+    pub warning: Option<u32>,
+}
+
+impl ResourceUsage {
+    fn new(used: u64, limit: u64) -> Self {
+        let percentage_used = if limit == 0 { 0.0 } else { used as f64 / limit as f64 * 100.0 };
+        let warning = WARNING_THRESHOLDS.iter().rev().copied().find(|t| percentage_used >= *t as f64);
+        Self { used, limit, percentage_used, warning }
+    }
+}
+
+/// Storage usage broken down by what's using it
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StorageBreakdown {
+    pub plan: PlanLimits,
+    /// Turso database size in bytes (includes notes)
+    pub database: ResourceUsage,
+    /// Uploaded images in bytes
+    pub images: ResourceUsage,
+    pub image_count: u64,
+    /// Trade and notebook note content in bytes (part of the database size)
+    pub notes_bytes: u64,
+    pub note_count: u64,
+    /// Stored AI vectors; None when the vector store couldn't be reached
+    pub vectors: Option<ResourceUsage>,
+}
+
+impl StorageBreakdown {
+    fn resources(&self) -> Vec<(&'static str, &ResourceUsage)> {
+        let mut resources = vec![("database", &self.database), ("images", &self.images)];
+        if let Some(vectors) = &self.vectors {
+            resources.push(("vectors", vectors));
+        }
+        resources
+    }
+}
+
+async fn query_pair(conn: &Connection, sql: &str) -> Result<(u64, u64)> {
+    let mut rows = conn.prepare(sql).await?.query(libsql::params![]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => (row.get::<i64>(0).unwrap_or(0).max(0) as u64, row.get::<i64>(1).unwrap_or(0).max(0) as u64),
+        None => (0, 0),
+    })
+}
+
 /// Storage usage information
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct StorageUsage {
@@ -26,6 +108,8 @@ pub struct StorageUsage {
 pub enum StorageQuotaError {
     #[error("Storage quota exceeded: {used_bytes} bytes used of {limit_bytes} bytes limit")]
     QuotaExceeded { used_bytes: u64, limit_bytes: u64 },
+    #[error("Image storage full: {used_bytes} bytes used of {limit_bytes} bytes limit")]
+    ImageQuotaExceeded { used_bytes: u64, limit_bytes: u64 },
     #[error("Database error: {0}")]
     DatabaseError(#[from] anyhow::Error),
 }
@@ -50,6 +134,20 @@ impl ResponseError for StorageQuotaError {
                     "remaining_bytes": 0i64,
                 }))
             }
+            StorageQuotaError::ImageQuotaExceeded { used_bytes, limit_bytes } => {
+                let used_mb = *used_bytes as f64 / (1024.0 * 1024.0);
+                let limit_mb = *limit_bytes as f64 / (1024.0 * 1024.0);
+
+                HttpResponse::InsufficientStorage().json(serde_json::json!({
+                    "success": false,
+                    "error": format!(
+                        "Image storage full. This upload would use {:.2} MB of your {:.0} MB image limit. Please delete some images to free up space.",
+                        used_mb, limit_mb
+                    ),
+                    "used_bytes": used_bytes,
+                    "limit_bytes": limit_bytes,
+                }))
+            }
             StorageQuotaError::DatabaseError(e) => {
                 error!("Database error in storage quota check: {}", e);
                 HttpResponse::InternalServerError().json(serde_json::json!({
@@ -65,12 +163,13 @@ impl ResponseError for StorageQuotaError {
 #[derive(Clone)]
 pub struct StorageQuotaService {
     turso_client: std::sync::Arc<TursoClient>,
+    qdrant_client: std::sync::Arc<QdrantDocumentClient>,
 }
 
 impl StorageQuotaService {
     /// Create a new storage quota service
-    pub fn new(turso_client: std::sync::Arc<TursoClient>) -> Self {
-        Self { turso_client }
+    pub fn new(turso_client: std::sync::Arc<TursoClient>, qdrant_client: std::sync::Arc<QdrantDocumentClient>) -> Self {
+        Self { turso_client, qdrant_client }
     }
 
     /// Calculate the actual size of a user's database using SQLite PRAGMA commands
@@ -231,5 +330,116 @@ impl StorageQuotaService {
             percentage_used,
         })
     }
-}
 
+    /// Check an upload before it's sent to storage: the image must fit the image limit and
+    /// the database must have room for its metadata row
+    pub async fn check_upload_quota(
+        &self,
+        user_id: &str,
+        user_conn: &Connection,
+        upload_bytes: u64,
+    ) -> Result<(), StorageQuotaError> {
+        self.check_storage_quota(user_id, user_conn).await?;
+
+        let (image_bytes, _) = query_pair(user_conn, "SELECT COALESCE(SUM(file_size), 0), COUNT(*) FROM images WHERE is_deleted = 0")
+            .await
+            .map_err(StorageQuotaError::DatabaseError)?;
+        let after_upload = image_bytes + upload_bytes;
+        if after_upload > PlanLimits::FREE.image_bytes {
+            return Err(StorageQuotaError::ImageQuotaExceeded {
+                used_bytes: after_upload,
+                limit_bytes: PlanLimits::FREE.image_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Usage by images, notes and vectors against the plan limits
+    pub async fn get_usage_breakdown(&self, user_id: &str, user_conn: &Connection) -> Result<StorageBreakdown> {
+        let limits = PlanLimits::FREE;
+        let usage = self.get_storage_usage(user_id, user_conn).await?;
+        let (image_bytes, image_count) =
+            query_pair(user_conn, "SELECT COALESCE(SUM(file_size), 0), COUNT(*) FROM images WHERE is_deleted = 0").await?;
+        let (trade_note_bytes, trade_note_count) =
+            query_pair(user_conn, "SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB))), 0), COUNT(*) FROM trade_notes").await?;
+        let (notebook_bytes, notebook_count) = query_pair(
+            user_conn,
+            "SELECT COALESCE(SUM(LENGTH(CAST(content AS BLOB)) + LENGTH(CAST(title AS BLOB))), 0), COUNT(*) FROM notebook_notes WHERE is_deleted = 0",
+        )
+        .await?;
+
+        let vectors = match self.qdrant_client.count_user_points(user_id).await {
+            Ok(count) => Some(ResourceUsage::new(count, limits.vector_count)),
+            Err(e) => {
+                warn!("Failed to count vectors for user {}: {}", user_id, e);
+                None
+            }
+        };
+
+        Ok(StorageBreakdown {
+            plan: limits,
+            database: ResourceUsage::new(usage.used_bytes, limits.database_bytes),
+            images: ResourceUsage::new(image_bytes, limits.image_bytes),
+            image_count,
+            notes_bytes: trade_note_bytes + notebook_bytes,
+            note_count: trade_note_count + notebook_count,
+            vectors,
+        })
+    }
+
+    /// Push a notification the first time a resource crosses 80% or 100%. Dropping back
+    /// under 80% clears the record so the warning fires again next time.
+    pub async fn notify_quota_warnings(
+        &self,
+        user_id: &str,
+        user_conn: &Connection,
+        web_push: &WebPushConfig,
+        breakdown: &StorageBreakdown,
+    ) -> Result<()> {
+        for (resource, usage) in breakdown.resources() {
+            let mut rows = user_conn
+                .prepare("SELECT threshold FROM storage_quota_warnings WHERE resource = ?")
+                .await?
+                .query(libsql::params![resource])
+                .await?;
+            let notified: Option<u32> = match rows.next().await? {
+                Some(row) => Some(row.get::<i64>(0)? as u32),
+                None => None,
+            };
+
+            let Some(threshold) = usage.warning else {
+                if notified.is_some() {
+                    user_conn.execute("DELETE FROM storage_quota_warnings WHERE resource = ?", libsql::params![resource]).await?;
+                }
+                continue;
+            };
+            if notified.is_some_and(|n| n >= threshold) {
+                continue;
+            }
+
+            let body = if threshold >= 100 {
+                format!("Your {} storage is full. Delete some data to keep saving.", resource)
+            } else {
+                format!("You've used {:.0}% of your {} storage.", usage.percentage_used, resource)
+            };
+            let payload = PushPayload {
+                title: "Storage limit".to_string(),
+                body: Some(body),
+                icon: None,
+                url: Some("/app/settings".to_string()),
+                tag: Some(format!("storage-quota-{}", resource)),
+                data: Some(serde_json::json!({"type": "storage_quota", "resource": resource, "threshold": threshold})),
+            };
+            if let Err(e) = PushService::new(user_conn, web_push).send_to_user(user_id, &payload).await {
+                warn!("Failed to send storage warning to user {}: {}", user_id, e);
+            }
+
+            user_conn.execute(
+                "INSERT INTO storage_quota_warnings (resource, threshold, notified_at) VALUES (?, ?, ?) \
+                 ON CONFLICT(resource) DO UPDATE SET threshold = excluded.threshold, notified_at = excluded.notified_at",
+                libsql::params![resource, threshold as i64, chrono::Utc::now().to_rfc3339()],
+            ).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,330 @@
+// Shock scenarios on the open book, for checks before events like FOMC. Stocks move by
+// their beta times the market shock plus any sector shock; options are repriced with
+// Black-Scholes Greeks (delta, gamma, vega) from the trade's implied volatility. This is
+// a first-order estimate, not a full revaluation.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::money;
+use crate::service::analytics_engine::hold_time::parse_timestamp;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::quotes::{get_quotes, Quote};
+
+const RISK_FREE_RATE: f64 = 0.04;
+const CONTRACT_MULTIPLIER: f64 = 100.0;
+pub const MAX_SCENARIOS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StressScenario {
+    pub name: String,
+    /// Broad market move in percent, scaled by each symbol's beta
+    #[serde(default)]
+    pub market_move_pct: f64,
+    /// Sector move in percent, added to the market move
+    #[serde(default)]
+    pub sector_move_pct: f64,
+    /// Sector the sector move applies to; every position's own sector when omitted
+    pub sector: Option<String>,
+    /// Relative change in implied volatility in percent, e.g. 50 for vol +50%
+    #[serde(default)]
+    pub volatility_change_pct: f64,
+}
+
+impl StressScenario {
+    fn new(name: &str, market: f64, sector: f64, volatility: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            market_move_pct: market,
+            sector_move_pct: sector,
+            sector: None,
+            volatility_change_pct: volatility,
+        }
+    }
+
+    /// Market -3%, every sector -5%, implied volatility +50%
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            Self::new("Market -3%", -3.0, 0.0, 0.0),
+            Self::new("Sector -5%", 0.0, -5.0, 0.0),
+            Self::new("Volatility +50%", 0.0, 0.0, 50.0),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let moves = [self.market_move_pct, self.sector_move_pct];
+        if moves.iter().any(|m| !m.is_finite() || *m <= -100.0 || *m > 100.0) {
+            return Err(format!("{}: moves must be between -100% and 100%", self.name));
+        }
+        if !self.volatility_change_pct.is_finite() || self.volatility_change_pct <= -100.0 || self.volatility_change_pct > 500.0 {
+            return Err(format!("{}: volatility_change_pct must be between -100% and 500%", self.name));
+        }
+        Ok(())
+    }
+
+    /// Underlying move (fraction) for a symbol with this beta and sector
+    fn underlying_move(&self, beta: f64, sector: Option<&str>) -> f64 {
+        let sector_hit = match (&self.sector, sector) {
+            (None, _) => true,
+            (Some(target), Some(sector)) => target.eq_ignore_ascii_case(sector),
+            (Some(_), None) => false,
+        };
+        (self.market_move_pct * beta + if sector_hit { self.sector_move_pct } else { 0.0 }) / 100.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionImpact {
+    /// `stock` or `option`
+    pub kind: String,
+    pub trade_id: i64,
+    pub symbol: String,
+    pub description: String,
+    pub underlying_price: f64,
+    pub beta: f64,
+    pub sector: Option<String>,
+    /// P&L per scenario, same order as `StressReport::scenarios`
+    pub impacts: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StressReport {
+    pub scenarios: Vec<StressScenario>,
+    pub positions: Vec<PositionImpact>,
+    /// Total P&L per scenario
+    pub totals: Vec<f64>,
+    /// Open positions without a usable quote, left out of the totals
+    pub unpriced_positions: Vec<String>,
+}
+
+/// Black-Scholes sensitivities per share of one option
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Greeks {
+    pub delta: f64,
+    pub gamma: f64,
+    /// Per 1.00 (100 points) of volatility
+    pub vega: f64,
+    /// Per calendar day
+    pub theta: f64,
+}
+
+fn normal_pdf(x: f64) -> f64 {
+    (-0.5 * x * x).exp() / (2.0 * std::f64::consts::PI).sqrt()
+}
+
+/// Abramowitz-Stegun 26.2.17, accurate to ~1e-7
+fn normal_cdf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.2316419 * x.abs());
+    let poly = t * (0.319381530 + t * (-0.356563782 + t * (1.781477937 + t * (-1.821255978 + t * 1.330274429))));
+    let tail = normal_pdf(x) * poly;
+    if x >= 0.0 { 1.0 - tail } else { tail }
+}
+
+pub(crate) fn greeks(call: bool, spot: f64, strike: f64, years: f64, volatility: f64) -> Option<Greeks> {
+    if spot <= 0.0 || strike <= 0.0 || years <= 0.0 || volatility <= 0.0 {
+        return None;
+    }
+    let sqrt_t = years.sqrt();
+    let d1 = ((spot / strike).ln() + (RISK_FREE_RATE + 0.5 * volatility * volatility) * years) / (volatility * sqrt_t);
+    let d2 = d1 - volatility * sqrt_t;
+    let delta = if call { normal_cdf(d1) } else { normal_cdf(d1) - 1.0 };
+    let discounted_strike = strike * (-RISK_FREE_RATE * years).exp();
+    let decay = -spot * normal_pdf(d1) * volatility / (2.0 * sqrt_t);
+    let carry = if call { -RISK_FREE_RATE * discounted_strike * normal_cdf(d2) } else { RISK_FREE_RATE * discounted_strike * normal_cdf(-d2) };
+    Some(Greeks {
+        delta,
+        gamma: normal_pdf(d1) / (spot * volatility * sqrt_t),
+        vega: spot * normal_pdf(d1) * sqrt_t,
+        theta: (decay + carry) / 365.0,
+    })
+}
+
+/// Stored implied volatility may be a percentage (35.5) or a fraction (0.355)
+pub(crate) fn volatility_fraction(value: f64) -> f64 {
+    if value > 3.0 { value / 100.0 } else { value }
+}
+
+/// Option P&L per share for an underlying move (fraction) and relative volatility change
+fn option_impact(greeks: Greeks, spot: f64, volatility: f64, underlying_move: f64, volatility_change: f64) -> f64 {
+    let ds = spot * underlying_move;
+    greeks.delta * ds + 0.5 * greeks.gamma * ds * ds + greeks.vega * volatility * volatility_change
+}
+
+pub(crate) fn parse_number(value: Option<&str>) -> Option<f64> {
+    value.and_then(|v| v.replace([',', '$', '%'], "").trim().parse::<f64>().ok())
+}
+
+struct OpenStock {
+    id: i64,
+    symbol: String,
+    direction: f64,
+    shares: f64,
+}
+
+struct OpenOption {
+    id: i64,
+    symbol: String,
+    call: bool,
+    /// +1 long, -1 short, inferred from direction and type
+    side: f64,
+    contracts: f64,
+    strike: f64,
+    expiration: Option<DateTime<Utc>>,
+    volatility: f64,
+}
+
+async fn open_positions(conn: &Connection) -> Result<(Vec<OpenStock>, Vec<OpenOption>)> {
+    let mut rows = conn
+        .prepare("SELECT id, UPPER(symbol), trade_type, number_shares FROM stocks WHERE exit_date IS NULL AND is_deleted = 0")
+        .await?
+        .query(params![])
+        .await?;
+    let mut stocks = Vec::new();
+    while let Some(row) = rows.next().await? {
+        stocks.push(OpenStock {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            direction: if row.get::<String>(2)? == "SELL" { -1.0 } else { 1.0 },
+            shares: money::to_f64(money::row_decimal(&row, 3)),
+        });
+    }
+
+    let mut rows = conn
+        .prepare(
+            "SELECT id, UPPER(symbol), option_type, trade_direction, number_of_contracts, strike_price, expiration_date, implied_volatility \
+             FROM options WHERE status = 'open' AND is_deleted = 0",
+        )
+        .await?
+        .query(params![])
+        .await?;
+    let mut options = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let call = row.get::<String>(2)? == "Call";
+        let direction: String = row.get(3)?;
+        // Bullish puts and bearish calls are written (short); neutral positions are taken as long
+        let side = match (call, direction.as_str()) {
+            (true, "Bearish") | (false, "Bullish") => -1.0,
+            _ => 1.0,
+        };
+        options.push(OpenOption {
+            id: row.get(0)?,
+            symbol: row.get(1)?,
+            call,
+            side,
+            contracts: row.get::<i64>(4)? as f64,
+            strike: money::to_f64(money::row_decimal(&row, 5)),
+            expiration: parse_timestamp(&row.get::<String>(6)?),
+            volatility: volatility_fraction(money::to_f64(money::row_decimal(&row, 7))),
+        });
+    }
+    Ok((stocks, options))
+}
+
+pub async fn run_stress_test(conn: &Connection, market: &MarketClient, scenarios: Vec<StressScenario>) -> Result<StressReport> {
+    let (stocks, options) = open_positions(conn).await?;
+    let mut symbols: Vec<String> = stocks.iter().map(|s| s.symbol.clone()).chain(options.iter().map(|o| o.symbol.clone())).collect();
+    symbols.sort();
+    symbols.dedup();
+    let quotes: Vec<Quote> = if symbols.is_empty() { Vec::new() } else { get_quotes(market, &symbols).await? };
+    let quote_for = |symbol: &str| {
+        let quote = quotes.iter().find(|q| q.symbol.eq_ignore_ascii_case(symbol))?;
+        let price = parse_number(quote.price.as_deref()).filter(|p| *p > 0.0)?;
+        Some((price, parse_number(quote.beta.as_deref()).unwrap_or(1.0), quote.sector.clone()))
+    };
+
+    let mut positions = Vec::new();
+    let mut unpriced_positions = Vec::new();
+    for stock in &stocks {
+        let Some((price, beta, sector)) = quote_for(&stock.symbol) else {
+            unpriced_positions.push(stock.symbol.clone());
+            continue;
+        };
+        let impacts = scenarios
+            .iter()
+            .map(|s| price * s.underlying_move(beta, sector.as_deref()) * stock.shares * stock.direction)
+            .collect();
+        positions.push(PositionImpact {
+            kind: "stock".to_string(),
+            trade_id: stock.id,
+            symbol: stock.symbol.clone(),
+            description: format!("{} {} shares", if stock.direction > 0.0 { "Long" } else { "Short" }, stock.shares),
+            underlying_price: price,
+            beta,
+            sector,
+            impacts,
+        });
+    }
+
+    let now = Utc::now();
+    for option in &options {
+        let years = option.expiration.map(|e| (e - now).num_seconds() as f64 / (365.25 * 86_400.0)).unwrap_or(0.0);
+        let priced = quote_for(&option.symbol)
+            .and_then(|(price, beta, sector)| Some((price, beta, sector, greeks(option.call, price, option.strike, years, option.volatility)?)));
+        let Some((price, beta, sector, greeks)) = priced else {
+            unpriced_positions.push(option.symbol.clone());
+            continue;
+        };
+        let size = option.side * option.contracts * CONTRACT_MULTIPLIER;
+        let impacts = scenarios
+            .iter()
+            .map(|s| {
+                let change = option_impact(greeks, price, option.volatility, s.underlying_move(beta, sector.as_deref()), s.volatility_change_pct / 100.0);
+                change * size
+            })
+            .collect();
+        positions.push(PositionImpact {
+            kind: "option".to_string(),
+            trade_id: option.id,
+            symbol: option.symbol.clone(),
+            description: format!(
+                "{} {} {} {}",
+                if option.side > 0.0 { "Long" } else { "Short" },
+                option.contracts,
+                option.strike,
+                if option.call { "call" } else { "put" }
+            ),
+            underlying_price: price,
+            beta,
+            sector,
+            impacts,
+        });
+    }
+
+    let totals = (0..scenarios.len()).map(|i| positions.iter().map(|p| p.impacts[i]).sum()).collect();
+    Ok(StressReport { scenarios, positions, totals, unpriced_positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_greeks_at_the_money() {
+        let call = greeks(true, 100.0, 100.0, 0.25, 0.2).unwrap();
+        let put = greeks(false, 100.0, 100.0, 0.25, 0.2).unwrap();
+        assert!((call.delta - 0.5596).abs() < 1e-3);
+        assert!((call.delta - put.delta - 1.0).abs() < 1e-9);
+        assert!((call.vega - 19.72).abs() < 0.05);
+        assert_eq!(call.gamma, put.gamma);
+        assert!(call.theta < 0.0 && put.theta < 0.0);
+
+        // Long call loses on a 3% drop, gains on a vol spike
+        assert!(option_impact(call, 100.0, 0.2, -0.03, 0.0) < 0.0);
+        assert!(option_impact(call, 100.0, 0.2, 0.0, 0.5) > 0.0);
+        assert_eq!(volatility_fraction(35.0), 0.35);
+    }
+
+    #[test]
+    fn test_scenario_underlying_move() {
+        let market = StressScenario::new("Market -3%", -3.0, 0.0, 0.0);
+        assert!((market.underlying_move(1.5, Some("Technology")) + 0.045).abs() < 1e-12);
+
+        let mut tech = StressScenario::new("Tech -5%", 0.0, -5.0, 0.0);
+        tech.sector = Some("technology".to_string());
+        assert_eq!(tech.underlying_move(1.0, Some("Technology")), -0.05);
+        assert_eq!(tech.underlying_move(1.0, Some("Energy")), 0.0);
+        assert_eq!(tech.underlying_move(1.0, None), 0.0);
+    }
+}
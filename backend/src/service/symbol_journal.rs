@@ -0,0 +1,337 @@
+// Everything the journal knows about one ticker, for the per-symbol dossier page: the
+// stock and option trades, their trade notes and screenshots, notebook notes and AI
+// insights that mention the symbol, missed trades, and all-time stats.
+//
+// Trades and missed trades match on the symbol column. Notebook notes and insights are
+// free text, so they're narrowed with LIKE and then checked for the symbol as a whole word
+// (`AAPL` and `$AAPL`, not `AAPLX`). Sealed notebook content can't be narrowed in SQL, so
+// sealed notes are opened and checked too, within the same row cap.
+
+use anyhow::Result;
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::models::stock::stocks::TimeRange;
+use crate::service::analytics_engine::core_metrics::{calculate_symbol_analytics, SymbolAnalytics};
+use crate::service::note_encryption::{self, NoteCipher};
+
+/// Most recent rows of each kind included in the dossier
+const MAX_TRADES: i64 = 500;
+const MAX_TEXT_MATCHES: i64 = 50;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolTrade {
+    /// `stock` or `option`
+    pub kind: String,
+    pub id: i64,
+    /// BUY/SELL for stocks, the strategy and Call/Put for options
+    pub description: String,
+    pub entry_date: String,
+    pub exit_date: Option<String>,
+    pub entry_price: f64,
+    pub exit_price: Option<f64>,
+    pub quantity: f64,
+    pub realized_pnl: Option<f64>,
+    pub trade_ratings: Option<i64>,
+    pub mistakes: Option<String>,
+    pub venue: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolNote {
+    /// `trade_note` or `notebook`
+    pub kind: String,
+    pub id: String,
+    pub title: String,
+    pub content: String,
+    /// `stock:12` / `option:7` for trade notes
+    pub trade: Option<String>,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolImage {
+    pub id: String,
+    pub trade_note_id: String,
+    pub uploadcare_file_id: String,
+    pub original_filename: String,
+    pub caption: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolMissedTrade {
+    pub id: String,
+    pub playbook_id: String,
+    pub trade_type: String,
+    pub reason: String,
+    pub potential_entry_price: Option<f64>,
+    pub opportunity_date: String,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolInsight {
+    pub id: String,
+    pub insight_type: String,
+    pub title: String,
+    pub content: String,
+    pub generated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolJournal {
+    pub symbol: String,
+    pub stats: SymbolAnalytics,
+    pub open_trades: usize,
+    /// Newest first
+    pub trades: Vec<SymbolTrade>,
+    pub notes: Vec<SymbolNote>,
+    pub images: Vec<SymbolImage>,
+    pub missed_trades: Vec<SymbolMissedTrade>,
+    pub insights: Vec<SymbolInsight>,
+}
+
+/// Uppercased ticker, or None when it isn't a plausible symbol
+pub fn normalize_symbol(raw: &str) -> Option<String> {
+    let symbol = raw.trim().trim_start_matches('$').to_uppercase();
+    let valid = !symbol.is_empty()
+        && symbol.len() <= 20
+        && symbol.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '/' | '^' | '='));
+    valid.then_some(symbol)
+}
+
+/// Whether `text` mentions `symbol` (uppercased) as a whole word, case-insensitively
+pub fn mentions_symbol(text: &str, symbol: &str) -> bool {
+    let upper = text.to_uppercase();
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    upper.match_indices(symbol).any(|(start, matched)| {
+        let before = upper[..start].chars().next_back();
+        let after = upper[start + matched.len()..].chars().next();
+        !before.is_some_and(is_word) && !after.is_some_and(is_word)
+    })
+}
+
+async fn load_trades(conn: &Connection, symbol: &str) -> Result<Vec<SymbolTrade>> {
+    let mut trades = Vec::new();
+    let mut rows = conn
+        .prepare(
+            "SELECT id, trade_type, entry_date, exit_date, CAST(entry_price AS REAL), CAST(exit_price AS REAL), CAST(number_shares AS REAL),
+                    CAST(realized_pnl AS REAL), trade_ratings, mistakes, venue
+             FROM stocks WHERE UPPER(symbol) = ? AND is_deleted = 0 ORDER BY entry_date DESC LIMIT ?",
+        )
+        .await?
+        .query(params![symbol, MAX_TRADES])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        trades.push(SymbolTrade {
+            kind: "stock".to_string(),
+            id: row.get(0)?,
+            description: row.get(1)?,
+            entry_date: row.get(2)?,
+            exit_date: row.get(3)?,
+            entry_price: row.get(4)?,
+            exit_price: row.get(5)?,
+            quantity: row.get(6)?,
+            realized_pnl: row.get(7)?,
+            trade_ratings: row.get(8)?,
+            mistakes: row.get(9)?,
+            venue: row.get(10)?,
+        });
+    }
+
+    let mut rows = conn
+        .prepare(
+            "SELECT id, strategy_type, option_type, entry_date, exit_date, CAST(entry_price AS REAL), CAST(exit_price AS REAL), number_of_contracts,
+                    CAST(realized_pnl AS REAL), trade_ratings, mistakes, venue
+             FROM options WHERE UPPER(symbol) = ? AND is_deleted = 0 ORDER BY entry_date DESC LIMIT ?",
+        )
+        .await?
+        .query(params![symbol, MAX_TRADES])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let strategy: String = row.get(1)?;
+        let option_type: String = row.get(2)?;
+        trades.push(SymbolTrade {
+            kind: "option".to_string(),
+            id: row.get(0)?,
+            description: format!("{} {}", strategy, option_type),
+            entry_date: row.get(3)?,
+            exit_date: row.get(4)?,
+            entry_price: row.get(5)?,
+            exit_price: row.get(6)?,
+            quantity: row.get::<i64>(7)? as f64,
+            realized_pnl: row.get(8)?,
+            trade_ratings: row.get(9)?,
+            mistakes: row.get(10)?,
+            venue: row.get(11)?,
+        });
+    }
+    trades.sort_by(|a, b| b.entry_date.cmp(&a.entry_date));
+    Ok(trades)
+}
+
+async fn load_notes(conn: &Connection, symbol: &str, cipher: Option<&NoteCipher>) -> Result<Vec<SymbolNote>> {
+    let mut notes = Vec::new();
+    let mut rows = conn
+        .prepare(
+            "SELECT n.id, n.name, COALESCE(n.content, ''), n.trade_type, COALESCE(n.stock_trade_id, n.option_trade_id), n.updated_at
+             FROM trade_notes n
+             LEFT JOIN stocks s ON n.stock_trade_id = s.id
+             LEFT JOIN options o ON n.option_trade_id = o.id
+             WHERE UPPER(COALESCE(s.symbol, o.symbol)) = ?
+             ORDER BY n.updated_at DESC LIMIT ?",
+        )
+        .await?
+        .query(params![symbol, MAX_TRADES])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        let trade_type: Option<String> = row.get(3)?;
+        let trade_id: Option<i64> = row.get(4)?;
+        notes.push(SymbolNote {
+            kind: "trade_note".to_string(),
+            id: row.get(0)?,
+            title: row.get(1)?,
+            content: note_encryption::open_content(cipher, row.get(2)?)?,
+            trade: trade_type.zip(trade_id).map(|(kind, id)| format!("{}:{}", kind, id)),
+            updated_at: row.get(5)?,
+        });
+    }
+
+    let pattern = format!("%{}%", symbol);
+    let mut rows = conn
+        .prepare(
+            "SELECT id, title, COALESCE(content, ''), updated_at FROM notebook_notes
+             WHERE is_deleted = 0 AND (UPPER(title) LIKE ? OR UPPER(content) LIKE ? OR content LIKE 'enc:%')
+             ORDER BY updated_at DESC LIMIT ?",
+        )
+        .await?
+        .query(params![pattern.clone(), pattern, MAX_TEXT_MATCHES * 4])
+        .await?;
+    let mut notebook = 0;
+    while let Some(row) = rows.next().await? {
+        if notebook as i64 >= MAX_TEXT_MATCHES {
+            break;
+        }
+        let title: String = row.get(1)?;
+        let content = note_encryption::open_content(cipher, row.get(2)?)?;
+        if !mentions_symbol(&title, symbol) && !mentions_symbol(&content, symbol) {
+            continue;
+        }
+        notebook += 1;
+        notes.push(SymbolNote { kind: "notebook".to_string(), id: row.get(0)?, title, content, trade: None, updated_at: row.get(3)? });
+    }
+    Ok(notes)
+}
+
+async fn load_images(conn: &Connection, note_ids: &[&str]) -> Result<Vec<SymbolImage>> {
+    if note_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = vec!["?"; note_ids.len()].join(", ");
+    let sql = format!(
+        "SELECT id, trade_note_id, uploadcare_file_id, original_filename, caption, created_at FROM images
+         WHERE is_deleted = 0 AND trade_note_id IN ({}) ORDER BY trade_note_id, position_in_note",
+        placeholders
+    );
+    let values: Vec<libsql::Value> = note_ids.iter().map(|id| libsql::Value::Text(id.to_string())).collect();
+    let mut rows = conn.prepare(&sql).await?.query(libsql::params_from_iter(values)).await?;
+    let mut images = Vec::new();
+    while let Some(row) = rows.next().await? {
+        images.push(SymbolImage {
+            id: row.get(0)?,
+            trade_note_id: row.get(1)?,
+            uploadcare_file_id: row.get(2)?,
+            original_filename: row.get(3)?,
+            caption: row.get(4)?,
+            created_at: row.get(5)?,
+        });
+    }
+    Ok(images)
+}
+
+async fn load_missed_trades(conn: &Connection, symbol: &str) -> Result<Vec<SymbolMissedTrade>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT id, playbook_id, trade_type, reason, CAST(potential_entry_price AS REAL), opportunity_date, notes FROM missed_trades
+             WHERE UPPER(symbol) = ? ORDER BY opportunity_date DESC LIMIT ?",
+        )
+        .await?
+        .query(params![symbol, MAX_TRADES])
+        .await?;
+    let mut missed = Vec::new();
+    while let Some(row) = rows.next().await? {
+        missed.push(SymbolMissedTrade {
+            id: row.get(0)?,
+            playbook_id: row.get(1)?,
+            trade_type: row.get(2)?,
+            reason: row.get(3)?,
+            potential_entry_price: row.get(4)?,
+            opportunity_date: row.get(5)?,
+            notes: row.get(6)?,
+        });
+    }
+    Ok(missed)
+}
+
+async fn load_insights(conn: &Connection, symbol: &str) -> Result<Vec<SymbolInsight>> {
+    let pattern = format!("%{}%", symbol);
+    let mut rows = conn
+        .prepare(
+            "SELECT id, insight_type, title, content, COALESCE(key_findings, ''), generated_at FROM ai_insights
+             WHERE UPPER(title) LIKE ? OR UPPER(content) LIKE ? OR UPPER(key_findings) LIKE ?
+             ORDER BY generated_at DESC LIMIT ?",
+        )
+        .await?
+        .query(params![pattern.clone(), pattern.clone(), pattern, MAX_TEXT_MATCHES])
+        .await?;
+    let mut insights = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let title: String = row.get(2)?;
+        let content: String = row.get(3)?;
+        let findings: String = row.get(4)?;
+        if [&title, &content, &findings].iter().any(|text| mentions_symbol(text, symbol)) {
+            insights.push(SymbolInsight { id: row.get(0)?, insight_type: row.get(1)?, title, content, generated_at: row.get(5)? });
+        }
+    }
+    Ok(insights)
+}
+
+/// The dossier for one symbol; `symbol` must already be normalized
+pub async fn symbol_journal(conn: &Connection, symbol: &str) -> Result<SymbolJournal> {
+    let cipher = NoteCipher::for_connection(conn).await?;
+    let trades = load_trades(conn, symbol).await?;
+    let notes = load_notes(conn, symbol, cipher.as_ref()).await?;
+    let trade_note_ids: Vec<&str> = notes.iter().filter(|n| n.kind == "trade_note").map(|n| n.id.as_str()).collect();
+    let images = load_images(conn, &trade_note_ids).await?;
+
+    Ok(SymbolJournal {
+        symbol: symbol.to_string(),
+        stats: calculate_symbol_analytics(conn, symbol, &TimeRange::AllTime).await?,
+        open_trades: trades.iter().filter(|t| t.exit_date.is_none()).count(),
+        trades,
+        notes,
+        images,
+        missed_trades: load_missed_trades(conn, symbol).await?,
+        insights: load_insights(conn, symbol).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_matching() {
+        assert_eq!(normalize_symbol(" $aapl ").as_deref(), Some("AAPL"));
+        assert_eq!(normalize_symbol("BRK.B").as_deref(), Some("BRK.B"));
+        assert_eq!(normalize_symbol("drop table"), None);
+        assert_eq!(normalize_symbol(""), None);
+
+        assert!(mentions_symbol("Sized up on aapl into earnings", "AAPL"));
+        assert!(mentions_symbol("$AAPL gapped, AAPL's range held", "AAPL"));
+        assert!(mentions_symbol("Stopped out (AAPL).", "AAPL"));
+        assert!(!mentions_symbol("AAPLX is a different fund", "AAPL"));
+        assert!(!mentions_symbol("SPACE stocks", "SPA"));
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use chrono::{Duration, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::turso::config::TaskMonitorConfig;
+
+/// Tables holding background generation tasks
+const TASK_TABLES: &[&str] = &["insight_generation_tasks", "report_generation_tasks"];
+
+/// Insight tasks store their status as a serialized enum (`"Processing"`), report tasks as
+/// plain lowercase text, so statuses are normalised before comparing.
+const NORMALIZED_STATUS: &str = "LOWER(TRIM(status, '\"'))";
+
+/// Task health for a single user database
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskHealth {
+    pub expired_insight_tasks: u64,
+    pub expired_report_tasks: u64,
+    pub failed_insight_tasks: u64,
+    pub failed_report_tasks: u64,
+    pub failed_report_schedules: u64,
+}
+
+impl TaskHealth {
+    pub fn expired(&self) -> u64 {
+        self.expired_insight_tasks + self.expired_report_tasks
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.failed_insight_tasks + self.failed_report_tasks + self.failed_report_schedules
+    }
+}
+
+/// Aggregated result of one monitor run across all users
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MonitorSummary {
+    pub users_checked: u64,
+    pub users_unreachable: u64,
+    pub expired_tasks: u64,
+    pub failed_tasks: u64,
+    pub failure_spike: bool,
+    pub alert_sent: bool,
+    /// Users with the most failures in the window, worst first
+    pub top_failing_users: Vec<(String, u64)>,
+}
+
+impl MonitorSummary {
+    pub fn record(&mut self, user_id: &str, health: &TaskHealth) {
+        self.users_checked += 1;
+        self.expired_tasks += health.expired();
+        self.failed_tasks += health.failed();
+        if health.failed() > 0 {
+            self.top_failing_users.push((user_id.to_string(), health.failed()));
+        }
+    }
+
+    /// Sort and trim the per-user list and decide whether the run warrants an alert
+    pub fn finish(&mut self, config: &TaskMonitorConfig) -> bool {
+        self.top_failing_users.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        self.top_failing_users.truncate(5);
+        self.failure_spike = self.failed_tasks >= config.failure_spike_threshold;
+        self.failure_spike || self.expired_tasks > 0
+    }
+
+    pub fn alert_text(&self, config: &TaskMonitorConfig) -> String {
+        let mut lines = vec![format!(
+            "Background task monitor: {} failed in the last {} min (threshold {}), {} stuck task(s) expired after {} min",
+            self.failed_tasks, config.failure_window_minutes, config.failure_spike_threshold, self.expired_tasks, config.stuck_after_minutes
+        )];
+        for (user_id, failures) in &self.top_failing_users {
+            lines.push(format!("• {}: {} failure(s)", user_id, failures));
+        }
+        if self.users_unreachable > 0 {
+            lines.push(format!("{} user database(s) could not be checked", self.users_unreachable));
+        }
+        lines.join("\n")
+    }
+}
+
+async fn count(conn: &Connection, sql: &str, cutoff: &str) -> Result<u64> {
+    let mut rows = conn.prepare(sql).await?.query(params![cutoff]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<i64>(0)?.max(0) as u64,
+        None => 0,
+    })
+}
+
+/// Expire tasks stuck in "processing" past the timeout and count recent failures.
+/// Expired rows keep the status format they were written in so existing readers still parse them.
+pub async fn check_user_tasks(conn: &Connection, config: &TaskMonitorConfig) -> Result<TaskHealth> {
+    let now = Utc::now();
+    let stuck_cutoff = (now - Duration::minutes(config.stuck_after_minutes)).to_rfc3339();
+    let failure_cutoff = (now - Duration::minutes(config.failure_window_minutes)).to_rfc3339();
+    let mut health = TaskHealth::default();
+
+    for table in TASK_TABLES {
+        let expired = conn.execute(
+            &format!(
+                "UPDATE {table} SET status = CASE WHEN status LIKE '\"%' THEN '\"Expired\"' ELSE 'expired' END, \
+                 completed_at = ?, error_message = COALESCE(error_message, ?) \
+                 WHERE {NORMALIZED_STATUS} = 'processing' AND datetime(COALESCE(started_at, created_at)) < datetime(?)"
+            ),
+            params![
+                now.to_rfc3339(),
+                format!("Expired by task monitor after {} minutes in processing", config.stuck_after_minutes),
+                stuck_cutoff.clone()
+            ],
+        ).await?;
+        let failed = count(
+            conn,
+            &format!("SELECT COUNT(*) FROM {table} WHERE {NORMALIZED_STATUS} = 'failed' AND datetime(COALESCE(completed_at, created_at)) >= datetime(?)"),
+            &failure_cutoff,
+        ).await?;
+
+        if *table == "insight_generation_tasks" {
+            health.expired_insight_tasks = expired;
+            health.failed_insight_tasks = failed;
+        } else {
+            health.expired_report_tasks = expired;
+            health.failed_report_tasks = failed;
+        }
+    }
+
+    health.failed_report_schedules = count(
+        conn,
+        "SELECT COUNT(*) FROM report_schedules WHERE last_error IS NOT NULL AND datetime(last_run_at) >= datetime(?)",
+        &failure_cutoff,
+    ).await?;
+
+    Ok(health)
+}
+
+/// Post the alert to the ops webhook. Sends both `text` (Slack) and `content` (Discord).
+pub async fn send_ops_alert(webhook_url: &str, summary: &MonitorSummary, config: &TaskMonitorConfig) -> Result<()> {
+    let text = summary.alert_text(config);
+    let body = serde_json::json!({
+        "text": text,
+        "content": text.chars().take(2000).collect::<String>(),
+        "summary": summary,
+    });
+    let response = reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(10))
+        .build()?
+        .post(webhook_url)
+        .json(&body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        anyhow::bail!("Ops webhook returned {}: {}", status, response.text().await.unwrap_or_default());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TaskMonitorConfig {
+        TaskMonitorConfig { ops_webhook_url: None, stuck_after_minutes: 30, failure_spike_threshold: 5, failure_window_minutes: 60 }
+    }
+
+    #[test]
+    fn test_alert_only_on_spike_or_expired_tasks() {
+        let mut quiet = MonitorSummary::default();
+        quiet.record("u1", &TaskHealth { failed_insight_tasks: 2, ..Default::default() });
+        assert!(!quiet.finish(&config()));
+
+        let mut spike = MonitorSummary::default();
+        spike.record("u1", &TaskHealth { failed_insight_tasks: 2, ..Default::default() });
+        spike.record("u2", &TaskHealth { failed_report_tasks: 3, failed_report_schedules: 1, ..Default::default() });
+        assert!(spike.finish(&config()));
+        assert!(spike.failure_spike);
+        assert_eq!(spike.top_failing_users[0], ("u2".to_string(), 4));
+
+        let mut stuck = MonitorSummary::default();
+        stuck.record("u1", &TaskHealth { expired_report_tasks: 1, ..Default::default() });
+        assert!(stuck.finish(&config()));
+        assert!(!stuck.failure_spike);
+    }
+}
@@ -0,0 +1,156 @@
+use crate::models::stock::stocks::TradeType;
+
+/// Parsed trade entry from a /buy or /sell command
+#[derive(Debug, Clone, PartialEq)]
+pub struct TradeCommand {
+    pub trade_type: TradeType,
+    pub symbol: String,
+    pub quantity: f64,
+    pub entry_price: f64,
+    pub stop_loss: f64,
+    pub take_profit: Option<f64>,
+    pub commissions: f64,
+}
+
+/// Commands understood by the bot
+#[derive(Debug, Clone, PartialEq)]
+pub enum BotCommand {
+    /// `/start <code>` or `/link <code>` from the app's link screen
+    Link(String),
+    Unlink,
+    Trade(TradeCommand),
+    /// `/pnl [7d|30d|90d|ytd|1y|all]`
+    Pnl(Option<String>),
+    Today,
+    Help,
+}
+
+pub const HELP_TEXT: &str = "Tradstry bot commands:\n\
+/buy AAPL 100 @ 182.45 stop 180 [target 190] [fee 1]\n\
+/sell TSLA 10 @ 250 stop 260 — log a short\n\
+/pnl [7d|30d|90d|ytd|1y|all] — P&L summary\n\
+/today — today's closed trades\n\
+/unlink — disconnect this chat";
+
+/// Parse a message into a command. Errors are user-facing replies.
+pub fn parse_command(text: &str) -> Result<BotCommand, String> {
+    let mut tokens = text.split_whitespace();
+    let head = tokens.next().ok_or_else(|| HELP_TEXT.to_string())?;
+    // Group chats append the bot name: /buy@TradstryBot
+    let command = head.split('@').next().unwrap_or(head).to_lowercase();
+    let args: Vec<&str> = tokens.collect();
+
+    match command.as_str() {
+        "/start" | "/link" => match args.first() {
+            Some(code) => Ok(BotCommand::Link(code.trim().to_uppercase())),
+            None => Err("Open Settings → Integrations → Telegram in Tradstry to get your link code, then send /link <code>.".to_string()),
+        },
+        "/unlink" => Ok(BotCommand::Unlink),
+        "/buy" => parse_trade(TradeType::BUY, &args).map(BotCommand::Trade),
+        "/sell" | "/short" => parse_trade(TradeType::SELL, &args).map(BotCommand::Trade),
+        "/pnl" => Ok(BotCommand::Pnl(args.first().map(|s| s.to_lowercase()))),
+        "/today" => Ok(BotCommand::Today),
+        "/help" => Ok(BotCommand::Help),
+        _ => Err(HELP_TEXT.to_string()),
+    }
+}
+
+fn parse_trade(trade_type: TradeType, args: &[&str]) -> Result<TradeCommand, String> {
+    let usage = "Usage: /buy AAPL 100 @ 182.45 stop 180";
+    // Allow "@182.45" and "@ 182.45"
+    let args: Vec<String> = args
+        .iter()
+        .flat_map(|a| match a.strip_prefix('@') {
+            Some(rest) if !rest.is_empty() => vec!["@".to_string(), rest.to_string()],
+            _ => vec![a.to_string()],
+        })
+        .collect();
+
+    let symbol = args.first().ok_or(usage)?.to_uppercase();
+    if !symbol.chars().all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-') || symbol.len() > 10 {
+        return Err(format!("'{}' doesn't look like a ticker. {}", symbol, usage));
+    }
+    let quantity = parse_number(args.get(1).ok_or(usage)?, "quantity")?;
+
+    let mut rest = args[2..].iter().map(|s| s.as_str()).peekable();
+    if rest.peek() == Some(&"@") {
+        rest.next();
+    }
+    let entry_price = parse_number(rest.next().ok_or(usage)?, "price")?;
+
+    let mut stop_loss = None;
+    let mut take_profit = None;
+    let mut commissions = 0.0;
+    while let Some(key) = rest.next() {
+        let value = rest.next().ok_or_else(|| format!("Missing value after '{}'", key))?;
+        match key.to_lowercase().as_str() {
+            "stop" | "sl" => stop_loss = Some(parse_number(value, "stop")?),
+            "target" | "tp" => take_profit = Some(parse_number(value, "target")?),
+            "fee" | "fees" | "comm" => commissions = parse_number(value, "fee")?,
+            other => return Err(format!("Unknown option '{}'. {}", other, usage)),
+        }
+    }
+
+    let stop_loss = stop_loss.ok_or_else(|| format!("Add a stop so risk can be tracked. {}", usage))?;
+    if quantity <= 0.0 || entry_price <= 0.0 || stop_loss <= 0.0 {
+        return Err("Quantity, price and stop must be positive".to_string());
+    }
+
+    Ok(TradeCommand { trade_type, symbol, quantity, entry_price, stop_loss, take_profit, commissions })
+}
+
+fn parse_number(value: &str, field: &str) -> Result<f64, String> {
+    value
+        .trim_start_matches('$')
+        .replace(',', "")
+        .parse::<f64>()
+        .map_err(|_| format!("Couldn't read {} from '{}'", field, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_buy_with_stop() {
+        let cmd = parse_command("/buy AAPL 100 @ 182.45 stop 180").unwrap();
+        assert_eq!(
+            cmd,
+            BotCommand::Trade(TradeCommand {
+                trade_type: TradeType::BUY,
+                symbol: "AAPL".to_string(),
+                quantity: 100.0,
+                entry_price: 182.45,
+                stop_loss: 180.0,
+                take_profit: None,
+                commissions: 0.0,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_sell_with_attached_at_and_options() {
+        match parse_command("/sell@TradstryBot tsla 10 @250 sl 260 tp 230 fee 1.5").unwrap() {
+            BotCommand::Trade(t) => {
+                assert_eq!(t.trade_type, TradeType::SELL);
+                assert_eq!(t.symbol, "TSLA");
+                assert_eq!(t.entry_price, 250.0);
+                assert_eq!(t.take_profit, Some(230.0));
+                assert_eq!(t.commissions, 1.5);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_stop_is_rejected() {
+        assert!(parse_command("/buy AAPL 100 @ 182.45").is_err());
+    }
+
+    #[test]
+    fn test_link_and_queries() {
+        assert_eq!(parse_command("/start ab12cd").unwrap(), BotCommand::Link("AB12CD".to_string()));
+        assert_eq!(parse_command("/pnl 30D").unwrap(), BotCommand::Pnl(Some("30d".to_string())));
+        assert_eq!(parse_command("/today").unwrap(), BotCommand::Today);
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::Result;
+use libsql::{params, Connection};
+
+/// Link codes are short-lived so a leaked code is useless after a few minutes
+const LINK_CODE_TTL_MINUTES: i64 = 15;
+const LINK_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Create a one-time code the user sends to the bot to link their chat.
+/// Stored in the registry database because Telegram updates carry no user context.
+pub async fn create_link_code(registry: &Connection, user_id: &str) -> Result<String> {
+    // Derive 8 characters from a random UUID, skipping look-alike characters
+    let code: String = uuid::Uuid::new_v4()
+        .as_bytes()
+        .iter()
+        .take(8)
+        .map(|b| LINK_CODE_ALPHABET[(*b as usize) % LINK_CODE_ALPHABET.len()] as char)
+        .collect();
+    let expires_at = (chrono::Utc::now() + chrono::Duration::minutes(LINK_CODE_TTL_MINUTES)).to_rfc3339();
+
+    registry.execute("DELETE FROM telegram_link_codes WHERE user_id = ? OR expires_at < ?", params![user_id, chrono::Utc::now().to_rfc3339()]).await?;
+    registry.execute(
+        "INSERT INTO telegram_link_codes (code, user_id, expires_at) VALUES (?, ?, ?)",
+        params![code.clone(), user_id, expires_at],
+    ).await?;
+    Ok(code)
+}
+
+/// Consume a link code and bind the chat to its user. Returns the linked user id.
+pub async fn consume_link_code(registry: &Connection, code: &str, chat_id: i64) -> Result<Option<String>> {
+    let mut rows = registry
+        .prepare("SELECT user_id FROM telegram_link_codes WHERE code = ? AND expires_at > ?")
+        .await?
+        .query(params![code, chrono::Utc::now().to_rfc3339()])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let user_id: String = row.get(0)?;
+
+    registry.execute("DELETE FROM telegram_link_codes WHERE code = ?", params![code]).await?;
+    registry.execute(
+        "INSERT INTO telegram_links (chat_id, user_id, linked_at) VALUES (?, ?, datetime('now'))
+         ON CONFLICT(chat_id) DO UPDATE SET user_id = excluded.user_id, linked_at = excluded.linked_at",
+        params![chat_id, user_id.clone()],
+    ).await?;
+    Ok(Some(user_id))
+}
+
+pub async fn find_user_by_chat(registry: &Connection, chat_id: i64) -> Result<Option<String>> {
+    let mut rows = registry
+        .prepare("SELECT user_id FROM telegram_links WHERE chat_id = ?")
+        .await?
+        .query(params![chat_id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row.get(0)?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn unlink_chat(registry: &Connection, chat_id: i64) -> Result<bool> {
+    let n = registry.execute("DELETE FROM telegram_links WHERE chat_id = ?", params![chat_id]).await?;
+    Ok(n > 0)
+}
+
+/// Remove every chat linked to a user (from the app's settings screen)
+pub async fn unlink_user(registry: &Connection, user_id: &str) -> Result<u64> {
+    let n = registry.execute("DELETE FROM telegram_links WHERE user_id = ?", params![user_id]).await?;
+    Ok(n)
+}
+
+pub async fn linked_chat_count(registry: &Connection, user_id: &str) -> Result<i64> {
+    let mut rows = registry
+        .prepare("SELECT COUNT(*) FROM telegram_links WHERE user_id = ?")
+        .await?
+        .query(params![user_id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(row.get(0)?),
+        None => Ok(0),
+    }
+}
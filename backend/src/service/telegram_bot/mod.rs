@@ -0,0 +1,187 @@
+pub mod commands;
+pub mod linking;
+
+use anyhow::Result;
+use log::{error, info, warn};
+use serde::Deserialize;
+
+use crate::models::money;
+use crate::models::stock::stocks::{CreateStockRequest, OrderType, Stock, TimeRange, TradeType};
+use crate::service::analytics_engine::core_metrics::calculate_core_metrics;
+use crate::service::notifications::chat_webhooks::build_daily_recap;
+use crate::turso::AppState;
+use crate::turso::config::TelegramConfig;
+use commands::{BotCommand, TradeCommand, HELP_TEXT, parse_command};
+
+/// Subset of the Telegram `Update` object the bot cares about
+#[derive(Debug, Deserialize)]
+pub struct TelegramUpdate {
+    pub update_id: i64,
+    pub message: Option<TelegramMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramMessage {
+    pub message_id: i64,
+    pub chat: TelegramChat,
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+}
+
+pub struct TelegramBot {
+    config: TelegramConfig,
+    client: reqwest::Client,
+}
+
+impl TelegramBot {
+    pub fn new(config: TelegramConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self { config, client }
+    }
+
+    /// Reply to a chat with plain text
+    pub async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.config.bot_token);
+        let response = self.client
+            .post(url)
+            .json(&serde_json::json!({"chat_id": chat_id, "text": text, "disable_web_page_preview": true}))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            anyhow::bail!("Telegram sendMessage returned {}", response.status());
+        }
+        Ok(())
+    }
+
+    /// Handle an incoming update and reply in the same chat
+    pub async fn handle_update(&self, app_state: &AppState, update: TelegramUpdate) -> Result<()> {
+        let Some(message) = update.message else { return Ok(()) };
+        let Some(text) = message.text else { return Ok(()) };
+        let chat_id = message.chat.id;
+
+        let reply = match parse_command(&text) {
+            Ok(command) => self.execute(app_state, chat_id, command).await.unwrap_or_else(|e| {
+                error!("Telegram command failed for chat {}: {}", chat_id, e);
+                "Something went wrong, please try again.".to_string()
+            }),
+            Err(reply) => reply,
+        };
+
+        if let Err(e) = self.send_message(chat_id, &reply).await {
+            warn!("Failed to reply to Telegram chat {}: {}", chat_id, e);
+        }
+        Ok(())
+    }
+
+    async fn execute(&self, app_state: &AppState, chat_id: i64, command: BotCommand) -> Result<String> {
+        let registry = app_state.turso_client.get_registry_connection().await?;
+
+        match command {
+            BotCommand::Help => return Ok(HELP_TEXT.to_string()),
+            BotCommand::Link(code) => {
+                return Ok(match linking::consume_link_code(&registry, &code, chat_id).await? {
+                    Some(user_id) => {
+                        info!("Linked Telegram chat {} to user {}", chat_id, user_id);
+                        format!("Linked! You can now log trades from here.\n\n{}", HELP_TEXT)
+                    }
+                    None => "That code is invalid or expired. Generate a new one in Tradstry.".to_string(),
+                });
+            }
+            BotCommand::Unlink => {
+                let removed = linking::unlink_chat(&registry, chat_id).await?;
+                return Ok(if removed { "This chat is no longer linked.".to_string() } else { "This chat wasn't linked.".to_string() });
+            }
+            _ => {}
+        }
+
+        let Some(user_id) = linking::find_user_by_chat(&registry, chat_id).await? else {
+            return Ok("This chat isn't linked yet. Send /link <code> using the code from Tradstry settings.".to_string());
+        };
+        let conn = app_state
+            .turso_client
+            .get_user_database_connection(&user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("User database not found"))?;
+
+        match command {
+            BotCommand::Trade(trade) => self.log_trade(app_state, &conn, &user_id, trade).await,
+            BotCommand::Pnl(range) => {
+                let (time_range, label) = match range.as_deref() {
+                    Some("7d") => (TimeRange::SevenDays, "last 7 days"),
+                    Some("30d") => (TimeRange::ThirtyDays, "last 30 days"),
+                    Some("90d") => (TimeRange::NinetyDays, "last 90 days"),
+                    Some("ytd") => (TimeRange::YearToDate, "year to date"),
+                    Some("mtd") => (TimeRange::MonthToDate, "month to date"),
+                    Some("qtd") => (TimeRange::QuarterToDate, "quarter to date"),
+                    Some("last_month") => (TimeRange::LastMonth, "last month"),
+                    Some("last_quarter") => (TimeRange::LastQuarter, "last quarter"),
+                    Some("1y") => (TimeRange::OneYear, "last year"),
+                    _ => (TimeRange::AllTime, "all time"),
+                };
+                let metrics = calculate_core_metrics(&conn, &time_range).await?;
+                Ok(format!(
+                    "P&L ({}): ${:.2}\nTrades: {} ({}W / {}L)\nWin rate: {:.1}%\nProfit factor: {:.2}",
+                    label, metrics.net_profit_loss, metrics.total_trades, metrics.winning_trades,
+                    metrics.losing_trades, metrics.win_rate, metrics.profit_factor
+                ))
+            }
+            BotCommand::Today => Ok(match build_daily_recap(&conn).await? {
+                Some(recap) => format!(
+                    "Today: ${:.2} across {} trade(s) ({}W / {}L)\nBest: ${:.2}  Worst: ${:.2}",
+                    recap.net_pnl, recap.trade_count, recap.winning_trades, recap.losing_trades,
+                    recap.best_trade, recap.worst_trade
+                ),
+                None => "No closed trades today.".to_string(),
+            }),
+            BotCommand::Help | BotCommand::Link(_) | BotCommand::Unlink => Ok(HELP_TEXT.to_string()),
+        }
+    }
+
+    async fn log_trade(&self, app_state: &AppState, conn: &libsql::Connection, user_id: &str, trade: TradeCommand) -> Result<String> {
+        let side = if trade.trade_type == TradeType::BUY { "Bought" } else { "Shorted" };
+        let request = CreateStockRequest {
+            symbol: trade.symbol.clone(),
+            trade_type: trade.trade_type,
+            order_type: OrderType::MARKET,
+            entry_price: money::from_f64(trade.entry_price),
+            stop_loss: money::from_f64(trade.stop_loss),
+            commissions: money::from_f64(trade.commissions),
+            number_shares: trade.quantity,
+            take_profit: money::from_f64_opt(trade.take_profit),
+            initial_target: None,
+            profit_target: money::from_f64_opt(trade.take_profit),
+            trade_ratings: None,
+            entry_date: chrono::Utc::now(),
+            reviewed: Some(false),
+            mistakes: None,
+            brokerage_name: None,
+            venue: None,
+        };
+
+        let stock = Stock::create(conn, request).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+
+        let cache_service = app_state.cache_service.clone();
+        let user_id = user_id.to_string();
+        tokio::spawn(async move {
+            if let Err(e) = cache_service.invalidate_table_cache(&user_id, "stocks").await {
+                error!("Failed to invalidate stock cache for user {}: {}", user_id, e);
+            }
+            if let Err(e) = cache_service.invalidate_user_analytics(&user_id).await {
+                error!("Failed to invalidate analytics cache for user {}: {}", user_id, e);
+            }
+        });
+
+        let risk = (trade.entry_price - trade.stop_loss).abs() * trade.quantity;
+        Ok(format!(
+            "{} {} {} @ ${:.2} (stop ${:.2}, risk ${:.2}). Trade #{} logged.",
+            side, trade.quantity, stock.symbol, trade.entry_price, trade.stop_loss, risk, stock.id
+        ))
+    }
+}
@@ -0,0 +1,326 @@
+// Opt-in feature usage telemetry. When a user turns it on they get a random telemetry id in
+// the registry, and each authenticated request bumps a daily count for its endpoint
+// category (`ai`, `playbooks`, `calendar`, ...) under that id. Nothing else about the request
+// is kept: no paths beyond the category, no bodies, no user id. Turning telemetry off clears
+// the id and deletes its counts.
+//
+// Counts are buffered in memory and flushed to the registry once a minute. Opt-in state is
+// cached per user for a few minutes, so a toggle made on another instance applies there
+// within that window.
+
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Utc;
+use dashmap::DashMap;
+use libsql::{params, Connection};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::turso::client::TursoClient;
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+const OPT_IN_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
+/// Users whose opt-in state is cached at once
+const OPT_IN_CACHE_CAPACITY: usize = 10_000;
+
+/// Endpoint categories by path prefix; the first match wins
+const CATEGORIES: &[(&str, &str)] = &[
+    ("/api/ai/", "ai"),
+    ("/api/playbooks", "playbooks"),
+    ("/api/calendar", "calendar"),
+    ("/api/notebook", "notebook"),
+    ("/api/analytics", "analytics"),
+    ("/api/behavior", "analytics"),
+    ("/api/risk", "risk"),
+    ("/api/market/", "market"),
+    ("/api/watchlist", "market"),
+    ("/api/price-alerts", "market"),
+    ("/api/stocks", "trades"),
+    ("/api/options", "trades"),
+    ("/api/trades", "trades"),
+    ("/api/trade-", "trades"),
+    ("/api/imports", "imports"),
+    ("/api/statements", "imports"),
+    ("/api/brokerage", "brokerage"),
+    ("/api/goals", "goals"),
+    ("/api/prop-firm", "goals"),
+    ("/api/focus", "focus"),
+    ("/api/automations", "automations"),
+    ("/api/organizations", "organizations"),
+    ("/api/notifications", "notifications"),
+    ("/api/push", "notifications"),
+    ("/api/year-review", "reports"),
+    ("/api/integrations", "integrations"),
+    ("/api/telegram", "integrations"),
+];
+
+/// Coarse category for a request path; None for paths that aren't counted
+pub fn category_for_path(path: &str) -> Option<&'static str> {
+    if path.starts_with("/api/telemetry") {
+        return None;
+    }
+    CATEGORIES.iter().find(|(prefix, _)| path.starts_with(prefix)).map(|(_, category)| *category).or_else(|| path.starts_with("/api/").then_some("other"))
+}
+
+/// Opt-in state by user, bounded: when full, stale entries go first and if every entry is
+/// still fresh the cache starts over
+struct OptInCache {
+    capacity: usize,
+    /// user id -> (telemetry id when opted in, when it was looked up)
+    entries: DashMap<String, (Option<String>, Instant)>,
+}
+
+impl OptInCache {
+    fn new(capacity: usize) -> Self {
+        Self { capacity, entries: DashMap::new() }
+    }
+
+    /// The user's telemetry id (None when opted out); outer None when unknown or stale
+    fn get(&self, user_id: &str) -> Option<Option<String>> {
+        let entry = self.entries.get(user_id)?;
+        let (telemetry_id, looked_up) = entry.value();
+        (looked_up.elapsed() < OPT_IN_CACHE_TTL).then(|| telemetry_id.clone())
+    }
+
+    fn insert(&self, user_id: String, telemetry_id: Option<String>) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&user_id) {
+            self.entries.retain(|_, (_, looked_up)| looked_up.elapsed() < OPT_IN_CACHE_TTL);
+            if self.entries.len() >= self.capacity {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(user_id, (telemetry_id, Instant::now()));
+    }
+
+    fn remove(&self, user_id: &str) {
+        self.entries.remove(user_id);
+    }
+}
+
+struct Recorder {
+    turso_client: Arc<TursoClient>,
+    opt_ins: OptInCache,
+    /// (telemetry id, day, category) -> requests not yet flushed
+    pending: DashMap<(String, String, String), u64>,
+}
+
+static RECORDER: OnceLock<Arc<Recorder>> = OnceLock::new();
+
+/// Enable recording and start the flush loop; called once at startup
+pub fn install(turso_client: Arc<TursoClient>) {
+    let recorder = Arc::new(Recorder { turso_client, opt_ins: OptInCache::new(OPT_IN_CACHE_CAPACITY), pending: DashMap::new() });
+    if RECORDER.set(Arc::clone(&recorder)).is_err() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = recorder.flush().await {
+                log::warn!("Failed to flush feature usage counts: {}", e);
+            }
+        }
+    });
+}
+
+impl Recorder {
+    fn count(&self, telemetry_id: &str, category: &str) {
+        let day = Utc::now().format("%Y-%m-%d").to_string();
+        *self.pending.entry((telemetry_id.to_string(), day, category.to_string())).or_insert(0) += 1;
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let keys: Vec<(String, String, String)> = self.pending.iter().map(|e| e.key().clone()).collect();
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let conn = self.turso_client.get_registry_connection().await?;
+        for key in keys {
+            let Some((key, count)) = self.pending.remove(&key) else { continue };
+            let (telemetry_id, day, category) = key;
+            conn.execute(
+                "INSERT INTO feature_usage_counts (telemetry_id, day, category, count) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(telemetry_id, day, category) DO UPDATE SET count = count + excluded.count",
+                params![telemetry_id, day, category, count as i64],
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// Count one request for `user_id` if they opted in; cheap when the opt-in state is cached.
+/// Callers pass the id from verified claims only.
+pub fn record_request(user_id: &str, path: &str) {
+    let Some(recorder) = RECORDER.get() else { return };
+    let Some(category) = category_for_path(path) else { return };
+    if let Some(telemetry_id) = recorder.opt_ins.get(user_id) {
+        if let Some(telemetry_id) = telemetry_id {
+            recorder.count(&telemetry_id, category);
+        }
+        return;
+    }
+    let recorder = Arc::clone(recorder);
+    let user_id = user_id.to_string();
+    tokio::spawn(async move {
+        let lookup = match recorder.turso_client.get_registry_connection().await {
+            Ok(conn) => telemetry_id(&conn, &user_id).await,
+            Err(e) => Err(e),
+        };
+        match lookup {
+            Ok(id) => {
+                if let Some(id) = &id {
+                    recorder.count(id, category);
+                }
+                recorder.opt_ins.insert(user_id, id);
+            }
+            Err(e) => log::warn!("Failed to load telemetry opt-in for user {}: {}", user_id, e),
+        }
+    });
+}
+
+fn forget_opt_in(user_id: &str) {
+    if let Some(recorder) = RECORDER.get() {
+        recorder.opt_ins.remove(user_id);
+    }
+}
+
+async fn telemetry_id(registry: &Connection, user_id: &str) -> Result<Option<String>> {
+    let mut rows = registry.query("SELECT telemetry_id FROM user_databases WHERE user_id = ?", params![user_id]).await?;
+    Ok(match rows.next().await? {
+        Some(row) => row.get::<Option<String>>(0)?,
+        None => None,
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryStatus {
+    pub enabled: bool,
+    /// Categories that are counted, so the toggle can say exactly what is collected
+    pub categories: Vec<&'static str>,
+}
+
+fn status(enabled: bool) -> TelemetryStatus {
+    let mut categories: Vec<&'static str> = Vec::new();
+    for category in CATEGORIES.iter().map(|(_, c)| *c).chain(["other"]) {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    TelemetryStatus { enabled, categories }
+}
+
+pub async fn get_status(registry: &Connection, user_id: &str) -> Result<TelemetryStatus> {
+    Ok(status(telemetry_id(registry, user_id).await?.is_some()))
+}
+
+/// Opt in (keeping an existing id) or out (deleting the id and its counts)
+pub async fn set_enabled(registry: &Connection, user_id: &str, enabled: bool) -> Result<TelemetryStatus> {
+    let current = telemetry_id(registry, user_id).await?;
+    match (enabled, current) {
+        (true, None) => {
+            registry
+                .execute("UPDATE user_databases SET telemetry_id = ? WHERE user_id = ?", params![Uuid::new_v4().to_string(), user_id])
+                .await?;
+        }
+        (false, Some(id)) => {
+            registry.execute("DELETE FROM feature_usage_counts WHERE telemetry_id = ?", params![id.clone()]).await?;
+            registry.execute("UPDATE user_databases SET telemetry_id = NULL WHERE user_id = ?", params![user_id]).await?;
+            if let Some(recorder) = RECORDER.get() {
+                recorder.pending.retain(|(telemetry_id, _, _), _| telemetry_id != &id);
+            }
+        }
+        _ => {}
+    }
+    forget_opt_in(user_id);
+    Ok(status(enabled))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageCount {
+    pub day: String,
+    pub category: String,
+    pub count: i64,
+}
+
+/// Everything recorded for the user, for the export button
+pub async fn export_for_user(registry: &Connection, user_id: &str) -> Result<Vec<UsageCount>> {
+    let Some(id) = telemetry_id(registry, user_id).await? else { return Ok(Vec::new()) };
+    let mut rows = registry
+        .query("SELECT day, category, count FROM feature_usage_counts WHERE telemetry_id = ? ORDER BY day, category", params![id])
+        .await?;
+    let mut counts = Vec::new();
+    while let Some(row) = rows.next().await? {
+        counts.push(UsageCount { day: row.get(0)?, category: row.get(1)?, count: row.get(2)? });
+    }
+    Ok(counts)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryUsage {
+    pub category: String,
+    pub requests: i64,
+    /// Opted-in tenants that used the category at least once in the window
+    pub tenants: i64,
+}
+
+/// Maintainer report: requests and distinct tenants per category since `since_day`
+pub async fn usage_report(registry: &Connection, since_day: &str) -> Result<Vec<CategoryUsage>> {
+    let mut rows = registry
+        .query(
+            "SELECT category, SUM(count), COUNT(DISTINCT telemetry_id) FROM feature_usage_counts
+             WHERE day >= ? GROUP BY category ORDER BY SUM(count) DESC",
+            params![since_day],
+        )
+        .await?;
+    let mut usage = Vec::new();
+    while let Some(row) = rows.next().await? {
+        usage.push(CategoryUsage { category: row.get(0)?, requests: row.get(1)?, tenants: row.get(2)? });
+    }
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_for_path() {
+        assert_eq!(category_for_path("/api/ai/chat/sessions"), Some("ai"));
+        assert_eq!(category_for_path("/api/playbooks/12/rules"), Some("playbooks"));
+        assert_eq!(category_for_path("/api/trade-links"), Some("trades"));
+        assert_eq!(category_for_path("/api/user/profile"), Some("other"));
+        assert_eq!(category_for_path("/api/telemetry/export"), None);
+        assert_eq!(category_for_path("/health"), None);
+    }
+
+    #[test]
+    fn test_status_lists_each_category_once() {
+        let status = status(true);
+        let mut unique = status.categories.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), status.categories.len());
+        assert!(status.categories.contains(&"other"));
+    }
+
+    #[test]
+    fn test_opt_in_cache_is_bounded() {
+        let cache = OptInCache::new(2);
+        cache.insert("user-a".to_string(), Some("t-a".to_string()));
+        cache.insert("user-b".to_string(), None);
+        assert_eq!(cache.get("user-a"), Some(Some("t-a".to_string())));
+        assert_eq!(cache.get("user-b"), Some(None));
+
+        // Refreshing a cached user doesn't evict anyone
+        cache.insert("user-a".to_string(), Some("t-a".to_string()));
+        assert_eq!(cache.entries.len(), 2);
+
+        cache.insert("user-c".to_string(), None);
+        assert!(cache.entries.len() <= 2);
+        assert_eq!(cache.get("user-c"), Some(None));
+        assert_eq!(cache.get("user-z"), None);
+    }
+}
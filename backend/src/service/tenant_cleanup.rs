@@ -0,0 +1,205 @@
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::service::account_deletion::AccountDeletionService;
+use crate::turso::client::TursoClient;
+
+/// Prefix `create_user_database` gives every tenant database
+const USER_DB_PREFIX: &str = "user-";
+
+pub const REASON_ORPHANED_DATABASE: &str = "orphaned_database";
+pub const REASON_DELETED_AUTH_USER: &str = "deleted_auth_user";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeadTenant {
+    pub id: String,
+    pub db_name: String,
+    pub user_id: String,
+    /// `orphaned_database` (Turso database without a registry row) or `deleted_auth_user`
+    pub reason: String,
+    pub detected_at: String,
+    pub reclaimed_at: Option<String>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ScanSummary {
+    pub databases_checked: u64,
+    pub registry_entries_checked: u64,
+    pub new_candidates: u64,
+    /// Registry users whose auth lookup failed and were skipped
+    pub skipped: u64,
+}
+
+/// Find tenants nobody owns any more and reclaim them once an operator confirms
+pub struct TenantCleanupService<'a> {
+    turso_client: &'a TursoClient,
+    account_deletion: &'a AccountDeletionService,
+}
+
+const DEAD_TENANT_COLUMNS: &str = "id, db_name, user_id, reason, detected_at, reclaimed_at, last_error";
+
+fn dead_tenant_from_row(row: &libsql::Row) -> Result<DeadTenant> {
+    Ok(DeadTenant {
+        id: row.get(0)?,
+        db_name: row.get(1)?,
+        user_id: row.get(2)?,
+        reason: row.get(3)?,
+        detected_at: row.get(4)?,
+        reclaimed_at: row.get(5).ok().flatten(),
+        last_error: row.get(6).ok().flatten(),
+    })
+}
+
+/// Registry user ids are lowercased with `_` → `-` to build the database name; Supabase UUIDs
+/// survive that unchanged, so stripping the prefix recovers them.
+fn user_id_from_db_name(db_name: &str) -> Option<&str> {
+    db_name.strip_prefix(USER_DB_PREFIX).filter(|id| !id.is_empty())
+}
+
+impl<'a> TenantCleanupService<'a> {
+    pub fn new(turso_client: &'a TursoClient, account_deletion: &'a AccountDeletionService) -> Self {
+        Self { turso_client, account_deletion }
+    }
+
+    async fn registry(&self) -> Result<Connection> {
+        self.turso_client.get_registry_connection().await
+    }
+
+    /// Record tenant databases with no registry row and registry rows whose auth account is gone.
+    /// Nothing is deleted here.
+    pub async fn scan(&self) -> Result<ScanSummary> {
+        let mut summary = ScanSummary::default();
+        let registered = self.turso_client.list_registered_databases().await?;
+        let registered_names: HashSet<&str> = registered.iter().map(|(_, db)| db.as_str()).collect();
+        let registered_users: HashSet<&str> = registered.iter().map(|(user, _)| user.as_str()).collect();
+
+        for db_name in self.turso_client.list_organization_databases().await? {
+            let Some(user_id) = user_id_from_db_name(&db_name) else { continue };
+            summary.databases_checked += 1;
+            if registered_names.contains(db_name.as_str()) || registered_users.contains(user_id) {
+                continue;
+            }
+            if self.record(&db_name, user_id, REASON_ORPHANED_DATABASE).await? {
+                summary.new_candidates += 1;
+            }
+        }
+
+        for (user_id, db_name) in &registered {
+            summary.registry_entries_checked += 1;
+            // The sandbox tenant has no Supabase account by design
+            if crate::service::sandbox::is_sandbox_user(user_id) {
+                continue;
+            }
+            match self.account_deletion.supabase_user_exists(user_id).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    if self.record(db_name, user_id, REASON_DELETED_AUTH_USER).await? {
+                        summary.new_candidates += 1;
+                    }
+                }
+                Err(e) => {
+                    summary.skipped += 1;
+                    warn!("Skipping auth check for {}: {}", user_id, e);
+                }
+            }
+        }
+
+        info!("Dead tenant scan: {:?}", summary);
+        Ok(summary)
+    }
+
+    /// Insert a candidate unless it's already recorded; true when newly added
+    async fn record(&self, db_name: &str, user_id: &str, reason: &str) -> Result<bool> {
+        let inserted = self.registry().await?.execute(
+            "INSERT OR IGNORE INTO dead_tenants (id, db_name, user_id, reason, detected_at) VALUES (?, ?, ?, ?, ?)",
+            params![Uuid::new_v4().to_string(), db_name, user_id, reason, Utc::now().to_rfc3339()],
+        ).await?;
+        Ok(inserted > 0)
+    }
+
+    /// Candidates, unreclaimed first
+    pub async fn list(&self, include_reclaimed: bool) -> Result<Vec<DeadTenant>> {
+        let filter = if include_reclaimed { "" } else { "WHERE reclaimed_at IS NULL" };
+        let mut rows = self.registry().await?
+            .prepare(&format!("SELECT {} FROM dead_tenants {} ORDER BY reclaimed_at IS NOT NULL, detected_at DESC", DEAD_TENANT_COLUMNS, filter))
+            .await?
+            .query(params![])
+            .await?;
+        let mut tenants = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tenants.push(dead_tenant_from_row(&row)?);
+        }
+        Ok(tenants)
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<DeadTenant>> {
+        let mut rows = self.registry().await?
+            .prepare(&format!("SELECT {} FROM dead_tenants WHERE id = ?", DEAD_TENANT_COLUMNS))
+            .await?
+            .query(params![id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(dead_tenant_from_row(&row)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Re-check that the tenant is still dead, then delete its database, storage and vectors.
+    /// `confirm_db_name` must repeat the database name so a stray request can't reclaim anything.
+    pub async fn reclaim(&self, id: &str, confirm_db_name: &str) -> Result<Option<DeadTenant>> {
+        let Some(tenant) = self.get(id).await? else { return Ok(None) };
+        if tenant.reclaimed_at.is_some() {
+            anyhow::bail!("Tenant {} was already reclaimed", tenant.db_name);
+        }
+        if tenant.db_name != confirm_db_name {
+            anyhow::bail!("Confirmation does not match database name {}", tenant.db_name);
+        }
+
+        let registry_entry = self.turso_client.get_user_database(&tenant.user_id).await?;
+        let still_dead = match tenant.reason.as_str() {
+            REASON_ORPHANED_DATABASE => registry_entry.is_none(),
+            _ => !self.account_deletion.supabase_user_exists(&tenant.user_id).await?,
+        };
+        if !still_dead {
+            self.registry().await?.execute("DELETE FROM dead_tenants WHERE id = ?", params![id]).await?;
+            anyhow::bail!("Tenant {} is active again; candidate removed", tenant.db_name);
+        }
+
+        let result = self
+            .account_deletion
+            .reclaim_tenant(&tenant.user_id, &tenant.db_name, registry_entry.is_some())
+            .await;
+        let (reclaimed_at, last_error) = match &result {
+            Ok(()) => (Some(Utc::now().to_rfc3339()), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        self.registry().await?.execute(
+            "UPDATE dead_tenants SET reclaimed_at = ?, last_error = ? WHERE id = ?",
+            params![reclaimed_at, last_error, id],
+        ).await?;
+        result?;
+
+        self.get(id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_id_from_db_name() {
+        assert_eq!(
+            user_id_from_db_name("user-3f2b8c1e-9d4a-4b7e-8f00-1a2b3c4d5e6f"),
+            Some("3f2b8c1e-9d4a-4b7e-8f00-1a2b3c4d5e6f")
+        );
+        assert_eq!(user_id_from_db_name("registry"), None);
+        assert_eq!(user_id_from_db_name("user-"), None);
+    }
+}
@@ -0,0 +1,391 @@
+// Cold storage for old trades. Closed trades that exited more than N years ago are copied,
+// with the rows that hang off them (tags, playbook links, rule compliance, trade notes),
+// into a JSON blob in object storage and then deleted from the tenant database. A monthly
+// P&L summary of what was archived stays behind in `trade_archives`, and an archive can be
+// restored on demand; trade ids are AUTOINCREMENT and never reused, so restored rows get
+// their original ids back and anything that still points at them lines up again.
+//
+// Blobs use a columnar layout (column names once per table, then rows as arrays), which
+// keeps the repeated JSON keys of a row-per-object dump out of the payload.
+
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use libsql::{params, Connection, Value};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::service::image_upload::ImageUploadService;
+use crate::turso::in_transaction;
+
+pub const MIN_ARCHIVE_YEARS: u32 = 1;
+pub const MAX_ARCHIVE_YEARS: u32 = 20;
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// A trade table and the `(table, foreign key)` rows that belong to its trades
+struct ArchivedTable {
+    table: &'static str,
+    /// Trades eligible for archiving; binds the cutoff as `?1`
+    condition: &'static str,
+    pnl_sql: &'static str,
+    dependents: &'static [(&'static str, &'static str)],
+}
+
+const ARCHIVED_TABLES: &[ArchivedTable] = &[
+    ArchivedTable {
+        table: "stocks",
+        condition: "exit_price IS NOT NULL AND exit_date IS NOT NULL AND datetime(exit_date) < datetime(?1)",
+        pnl_sql: STOCK_PNL_SQL,
+        dependents: &[
+            ("stock_trade_tags", "stock_trade_id"),
+            ("stock_trade_playbook", "stock_trade_id"),
+            ("stock_trade_rule_compliance", "stock_trade_id"),
+            ("trade_notes", "stock_trade_id"),
+            ("trade_links", "stock_trade_id"),
+        ],
+    },
+    ArchivedTable {
+        table: "options",
+        condition: "status = 'closed' AND exit_date IS NOT NULL AND datetime(exit_date) < datetime(?1)",
+        pnl_sql: OPTION_PNL_SQL,
+        dependents: &[
+            ("option_trade_tags", "option_trade_id"),
+            ("option_trade_playbook", "option_trade_id"),
+            ("option_trade_rule_compliance", "option_trade_id"),
+            ("trade_notes", "option_trade_id"),
+            ("trade_links", "option_trade_id"),
+        ],
+    },
+];
+
+/// Tables whose rows hang off a `stocks` or `options` trade, with the column that points at it
+pub(crate) fn trade_dependents(table: &str) -> &'static [(&'static str, &'static str)] {
+    ARCHIVED_TABLES.iter().find(|spec| spec.table == table).map(|spec| spec.dependents).unwrap_or(&[])
+}
+
+/// Rows of one table in the blob
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TableDump {
+    pub table: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchiveBlob {
+    version: u32,
+    archive_id: String,
+    cutoff: String,
+    /// Parents before dependents, the order rows are restored in
+    tables: Vec<TableDump>,
+}
+
+/// SQLite value as JSON; blobs become `{"base64": ...}` so they survive the round trip
+pub fn value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Integer(i) => json!(i),
+        Value::Real(r) => serde_json::Number::from_f64(r).map(serde_json::Value::Number).unwrap_or(serde_json::Value::Null),
+        Value::Text(s) => json!(s),
+        Value::Blob(b) => json!({"base64": base64::engine::general_purpose::STANDARD.encode(b)}),
+    }
+}
+
+pub fn json_to_value(value: &serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        serde_json::Value::Object(map) => match map.get("base64").and_then(|b| b.as_str()) {
+            Some(encoded) => base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map(Value::Blob)
+                .unwrap_or(Value::Null),
+            None => Value::Text(value.to_string()),
+        },
+        serde_json::Value::Array(_) => Value::Text(value.to_string()),
+    }
+}
+
+async fn dump(conn: &Connection, table: &str, condition: &str, cutoff: &str) -> Result<TableDump> {
+    let mut rows = conn
+        .prepare(&format!("SELECT * FROM {} WHERE {}", table, condition))
+        .await?
+        .query(params![cutoff])
+        .await?;
+    let mut out = TableDump { table: table.to_string(), ..Default::default() };
+    while let Some(row) = rows.next().await? {
+        if out.columns.is_empty() {
+            out.columns = (0..row.column_count()).filter_map(|i| row.column_name(i).map(str::to_string)).collect();
+        }
+        let values = (0..row.column_count()).map(|i| row.get_value(i).map(value_to_json).unwrap_or_default()).collect();
+        out.rows.push(values);
+    }
+    Ok(out)
+}
+
+/// P&L of the archived trades by exit month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MonthSummary {
+    pub month: String,
+    pub trades: u32,
+    pub winning_trades: u32,
+    pub losing_trades: u32,
+    pub net_pnl: f64,
+    pub gross_profit: f64,
+    pub gross_loss: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveSummary {
+    pub stock_trades: u32,
+    pub option_trades: u32,
+    pub net_pnl: f64,
+    pub first_exit: Option<String>,
+    pub last_exit: Option<String>,
+    pub months: Vec<MonthSummary>,
+}
+
+impl ArchiveSummary {
+    fn add_month(&mut self, month: MonthSummary) {
+        self.net_pnl += month.net_pnl;
+        match self.months.iter_mut().find(|m| m.month == month.month) {
+            Some(existing) => {
+                existing.trades += month.trades;
+                existing.winning_trades += month.winning_trades;
+                existing.losing_trades += month.losing_trades;
+                existing.net_pnl += month.net_pnl;
+                existing.gross_profit += month.gross_profit;
+                existing.gross_loss += month.gross_loss;
+            }
+            None => self.months.push(month),
+        }
+        self.months.sort_by(|a, b| a.month.cmp(&b.month));
+    }
+}
+
+async fn summarize(conn: &Connection, cutoff: &str) -> Result<ArchiveSummary> {
+    let mut summary = ArchiveSummary::default();
+    for spec in ARCHIVED_TABLES {
+        let sql = format!(
+            "SELECT strftime('%Y-%m', exit_date), COUNT(*), \
+                    SUM(CASE WHEN pnl > 0 THEN 1 ELSE 0 END), SUM(CASE WHEN pnl < 0 THEN 1 ELSE 0 END), \
+                    COALESCE(SUM(pnl), 0), COALESCE(SUM(CASE WHEN pnl > 0 THEN pnl ELSE 0 END), 0), \
+                    COALESCE(SUM(CASE WHEN pnl < 0 THEN pnl ELSE 0 END), 0), MIN(exit_date), MAX(exit_date) \
+             FROM (SELECT exit_date, {} AS pnl FROM {} WHERE {}) GROUP BY 1",
+            spec.pnl_sql, spec.table, spec.condition
+        );
+        let mut rows = conn.prepare(&sql).await?.query(params![cutoff]).await?;
+        while let Some(row) = rows.next().await? {
+            let trades = row.get::<i64>(1)? as u32;
+            match spec.table {
+                "stocks" => summary.stock_trades += trades,
+                _ => summary.option_trades += trades,
+            }
+            let (first, last): (Option<String>, Option<String>) = (row.get(7)?, row.get(8)?);
+            if first.is_some() && (summary.first_exit.is_none() || first < summary.first_exit) {
+                summary.first_exit = first;
+            }
+            if last > summary.last_exit {
+                summary.last_exit = last;
+            }
+            summary.add_month(MonthSummary {
+                month: row.get::<Option<String>>(0)?.unwrap_or_default(),
+                trades,
+                winning_trades: row.get::<i64>(2)? as u32,
+                losing_trades: row.get::<i64>(3)? as u32,
+                net_pnl: money::to_f64(money::row_decimal(&row, 4)),
+                gross_profit: money::to_f64(money::row_decimal(&row, 5)),
+                gross_loss: money::to_f64(money::row_decimal(&row, 6)),
+            });
+        }
+    }
+    Ok(summary)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeArchive {
+    pub id: String,
+    pub cutoff: String,
+    /// "archived" or "restored"
+    pub status: String,
+    pub object_path: String,
+    pub byte_size: i64,
+    pub summary: ArchiveSummary,
+    pub created_at: String,
+    pub restored_at: Option<String>,
+}
+
+const ARCHIVE_COLUMNS: &str = "id, cutoff, status, object_path, byte_size, summary, created_at, restored_at";
+
+fn row_to_archive(row: &libsql::Row) -> Result<TradeArchive> {
+    Ok(TradeArchive {
+        id: row.get(0)?,
+        cutoff: row.get(1)?,
+        status: row.get(2)?,
+        object_path: row.get(3)?,
+        byte_size: row.get(4)?,
+        summary: serde_json::from_str(&row.get::<String>(5)?).unwrap_or_default(),
+        created_at: row.get(6)?,
+        restored_at: row.get(7)?,
+    })
+}
+
+pub async fn list_archives(conn: &Connection) -> Result<Vec<TradeArchive>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {ARCHIVE_COLUMNS} FROM trade_archives ORDER BY created_at DESC"))
+        .await?
+        .query(params![])
+        .await?;
+    let mut archives = Vec::new();
+    while let Some(row) = rows.next().await? {
+        archives.push(row_to_archive(&row)?);
+    }
+    Ok(archives)
+}
+
+pub async fn get_archive(conn: &Connection, id: &str) -> Result<Option<TradeArchive>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {ARCHIVE_COLUMNS} FROM trade_archives WHERE id = ?"))
+        .await?
+        .query(params![id])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_archive(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Exit-date cutoff for trades older than `years`
+pub fn cutoff_for(now: DateTime<Utc>, years: u32) -> String {
+    (now - Duration::days(365 * years as i64)).to_rfc3339()
+}
+
+/// What archiving with this cutoff would move, without touching anything
+pub async fn preview(conn: &Connection, cutoff: &str) -> Result<ArchiveSummary> {
+    summarize(conn, cutoff).await
+}
+
+/// Move closed trades that exited before `cutoff` to object storage. Returns None when
+/// nothing is old enough.
+pub async fn archive(conn: &Connection, storage: &ImageUploadService, user_id: &str, cutoff: &str) -> Result<Option<TradeArchive>> {
+    let summary = summarize(conn, cutoff).await?;
+    if summary.stock_trades + summary.option_trades == 0 {
+        return Ok(None);
+    }
+
+    let archive_id = uuid::Uuid::new_v4().to_string();
+    let mut tables = Vec::new();
+    for spec in ARCHIVED_TABLES {
+        tables.push(dump(conn, spec.table, spec.condition, cutoff).await?);
+        for (child, key) in spec.dependents {
+            let condition = format!("{} IN (SELECT id FROM {} WHERE {})", key, spec.table, spec.condition);
+            tables.push(dump(conn, child, &condition, cutoff).await?);
+        }
+    }
+    let blob = ArchiveBlob { version: ARCHIVE_FORMAT_VERSION, archive_id: archive_id.clone(), cutoff: cutoff.to_string(), tables };
+    let bytes = serde_json::to_vec(&blob)?;
+    let object_path = format!("{}/archives/{}.json", user_id, archive_id);
+    storage.put_object(&object_path, &bytes, "application/json").await?;
+
+    // Only delete once the blob is stored; dependents go first so nothing is left dangling
+    in_transaction(conn, async |tx: &Connection| -> Result<()> {
+        for spec in ARCHIVED_TABLES {
+            for (child, key) in spec.dependents {
+                tx.execute(
+                    &format!("DELETE FROM {} WHERE {} IN (SELECT id FROM {} WHERE {})", child, key, spec.table, spec.condition),
+                    params![cutoff],
+                )
+                .await?;
+            }
+            tx.execute(&format!("DELETE FROM {} WHERE {}", spec.table, spec.condition), params![cutoff]).await?;
+        }
+        tx.execute(
+            &format!("INSERT INTO trade_archives ({ARCHIVE_COLUMNS}) VALUES (?, ?, 'archived', ?, ?, ?, ?, NULL)"),
+            params![archive_id.clone(), cutoff, object_path, bytes.len() as i64, serde_json::to_string(&summary)?, Utc::now().to_rfc3339()],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+
+    get_archive(conn, &archive_id).await
+}
+
+pub(crate) fn insert_sql(dump: &TableDump) -> String {
+    let placeholders = vec!["?"; dump.columns.len()].join(", ");
+    format!("INSERT OR IGNORE INTO {} ({}) VALUES ({})", dump.table, dump.columns.join(", "), placeholders)
+}
+
+/// Put an archive's rows back and drop the blob. Rows that already exist are left as they are.
+pub async fn restore(conn: &Connection, storage: &ImageUploadService, id: &str) -> Result<Option<TradeArchive>> {
+    let Some(archive) = get_archive(conn, id).await? else { return Ok(None) };
+    if archive.status != "archived" {
+        anyhow::bail!("Archive {} was already restored", id);
+    }
+
+    let bytes = storage.download_file(&archive.object_path).await?;
+    let blob: ArchiveBlob = serde_json::from_slice(&bytes).context("Archive blob is not readable")?;
+    if blob.version > ARCHIVE_FORMAT_VERSION {
+        anyhow::bail!("Archive format {} is newer than this server supports", blob.version);
+    }
+    // Only tables this server archives are written back
+    let allowed: Vec<&str> = ARCHIVED_TABLES.iter().flat_map(|spec| std::iter::once(spec.table).chain(spec.dependents.iter().map(|(t, _)| *t))).collect();
+
+    in_transaction(conn, async |tx: &Connection| -> Result<()> {
+        for dump in blob.tables.iter().filter(|d| !d.rows.is_empty()) {
+            if !allowed.contains(&dump.table.as_str()) || dump.columns.iter().any(|c| !c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')) {
+                anyhow::bail!("Archive contains an unexpected table or column: {}", dump.table);
+            }
+            let sql = insert_sql(dump);
+            for row in &dump.rows {
+                let values: Vec<Value> = row.iter().map(json_to_value).collect();
+                tx.execute(&sql, values).await?;
+            }
+        }
+        tx.execute(
+            "UPDATE trade_archives SET status = 'restored', restored_at = ? WHERE id = ?",
+            params![Utc::now().to_rfc3339(), id],
+        )
+        .await?;
+        Ok(())
+    })
+    .await?;
+
+    if let Err(e) = storage.delete_file(&archive.object_path).await {
+        log::warn!("Restored archive {} but could not delete its blob: {}", id, e);
+    }
+    get_archive(conn, id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_round_trip_through_json() {
+        for value in [Value::Null, Value::Integer(42), Value::Real(101.25), Value::Text("AAPL".into()), Value::Blob(vec![0, 1, 255])] {
+            assert_eq!(json_to_value(&value_to_json(value.clone())), value);
+        }
+        let dump = TableDump { table: "stocks".into(), columns: vec!["id".into(), "symbol".into()], rows: vec![] };
+        assert_eq!(insert_sql(&dump), "INSERT OR IGNORE INTO stocks (id, symbol) VALUES (?, ?)");
+    }
+
+    #[test]
+    fn test_summary_merges_months_across_tables() {
+        let mut summary = ArchiveSummary::default();
+        let month = |m: &str, pnl: f64| MonthSummary { month: m.into(), trades: 1, net_pnl: pnl, ..Default::default() };
+        summary.add_month(month("2021-03", 100.0));
+        summary.add_month(month("2020-11", -40.0));
+        summary.add_month(month("2021-03", 25.0));
+        assert_eq!(summary.months.iter().map(|m| m.month.as_str()).collect::<Vec<_>>(), vec!["2020-11", "2021-03"]);
+        assert_eq!(summary.months[1].trades, 2);
+        assert_eq!(summary.net_pnl, 85.0);
+    }
+}
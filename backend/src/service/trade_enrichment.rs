@@ -0,0 +1,326 @@
+// Market data attached to trades at entry, so a trade logged with little more than symbol,
+// size and time still has what analytics need: the entry session's OHLC and volume, a
+// bid-ask spread estimate and, for options, the contract's implied volatility. The spread
+// comes from the Corwin-Schultz high-low estimator over the entry session and the one
+// before it, since historical quotes aren't available. A missing stock entry price is
+// estimated from the 5-minute bar at the entry time (the session close for older trades),
+// and a missing stock venue is taken from the symbol's listing exchange.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, Utc};
+use libsql::{params, Connection};
+use serde::Serialize;
+
+use crate::models::{money, venue};
+use crate::models::options::{CreateOptionRequest, OptionType};
+use crate::service::analytics_engine::efficiency::history_range;
+use crate::service::cache_service::CacheService;
+use crate::service::market_engine::client::MarketClient;
+use crate::service::market_engine::historical::{get_historical, HistoricalCandle};
+use crate::service::market_engine::{earnings_transcripts, options_chain, search};
+
+/// 5-minute bars only reach back about this far upstream
+const INTRADAY_DAYS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bar {
+    time: DateTime<Utc>,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: Option<u64>,
+}
+
+fn to_bars(candles: &[HistoricalCandle]) -> Vec<Bar> {
+    let mut bars: Vec<Bar> = candles
+        .iter()
+        .filter_map(|c| {
+            Some(Bar {
+                time: DateTime::from_timestamp(c.time.parse::<i64>().ok()?, 0)?,
+                open: c.open,
+                high: c.high,
+                low: c.low,
+                close: c.close,
+                volume: c.volume,
+            })
+        })
+        .collect();
+    bars.sort_by_key(|b| b.time);
+    bars
+}
+
+/// Corwin-Schultz spread (fraction of price) from two consecutive sessions' highs and lows;
+/// negative estimates, which come from overnight moves, are reported as zero
+pub fn corwin_schultz_spread(previous: (f64, f64), current: (f64, f64)) -> Option<f64> {
+    let ((h1, l1), (h2, l2)) = (previous, current);
+    if l1 <= 0.0 || l2 <= 0.0 || h1 < l1 || h2 < l2 {
+        return None;
+    }
+    let beta = (h1 / l1).ln().powi(2) + (h2 / l2).ln().powi(2);
+    let gamma = (h1.max(h2) / l1.min(l2)).ln().powi(2);
+    let k = 3.0 - 2.0 * std::f64::consts::SQRT_2;
+    let alpha = ((2.0 * beta).sqrt() - beta.sqrt()) / k - (gamma / k).sqrt();
+    let spread = 2.0 * (alpha.exp() - 1.0) / (1.0 + alpha.exp());
+    spread.is_finite().then_some(spread.max(0.0))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryContext {
+    pub trade_type: String,
+    pub trade_id: i64,
+    pub symbol: String,
+    pub session_date: String,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: Option<i64>,
+    pub spread_estimate_pct: Option<f64>,
+    /// Fraction, options only
+    pub implied_volatility: Option<f64>,
+    /// The entry price was filled in from market data rather than entered
+    pub entry_price_estimated: bool,
+}
+
+/// Entry session and the session before it
+fn entry_sessions(bars: &[Bar], entry_day: NaiveDate) -> Option<(Option<Bar>, Bar)> {
+    let index = bars.iter().position(|b| b.time.date_naive() == entry_day)?;
+    Some((index.checked_sub(1).map(|i| bars[i]), bars[index]))
+}
+
+/// Daily bars for `symbol` covering `entry_date`
+async fn daily_bars(client: &MarketClient, symbol: &str, entry_date: DateTime<Utc>) -> Result<Vec<Bar>> {
+    let range = history_range(entry_date.date_naive(), Utc::now().date_naive());
+    Ok(to_bars(&get_historical(client, symbol, Some(range), Some("1d")).await?.candles))
+}
+
+/// Price at `entry_date`: the last 5-minute bar that opened by then, else the session close
+pub async fn estimate_entry_price(client: &MarketClient, symbol: &str, entry_date: DateTime<Utc>) -> Result<Option<f64>> {
+    if (Utc::now() - entry_date).num_days() < INTRADAY_DAYS {
+        let bars = to_bars(&get_historical(client, symbol, Some("1mo"), Some("5m")).await?.candles);
+        let bar = bars.iter().rev().find(|b| b.time <= entry_date && b.time.date_naive() == entry_date.date_naive());
+        if let Some(bar) = bar {
+            return Ok(Some(bar.close));
+        }
+    }
+    let bars = daily_bars(client, symbol, entry_date).await?;
+    Ok(entry_sessions(&bars, entry_date.date_naive()).map(|(_, session)| session.close))
+}
+
+/// Entry-day context for a trade, or None when the market data doesn't cover the entry day
+pub async fn build_context(
+    client: &MarketClient,
+    trade_type: &str,
+    trade_id: i64,
+    symbol: &str,
+    entry_date: DateTime<Utc>,
+    implied_volatility: Option<f64>,
+    entry_price_estimated: bool,
+) -> Result<Option<EntryContext>> {
+    let symbol = symbol.trim().to_uppercase();
+    let bars = daily_bars(client, &symbol, entry_date).await?;
+    let Some((previous, session)) = entry_sessions(&bars, entry_date.date_naive()) else {
+        return Ok(None);
+    };
+    let spread = previous.and_then(|p| corwin_schultz_spread((p.high, p.low), (session.high, session.low)));
+    Ok(Some(EntryContext {
+        trade_type: trade_type.to_string(),
+        trade_id,
+        symbol,
+        session_date: session.time.date_naive().to_string(),
+        open: session.open,
+        high: session.high,
+        low: session.low,
+        close: session.close,
+        volume: session.volume.map(|v| v as i64),
+        spread_estimate_pct: spread.map(|s| s * 100.0),
+        implied_volatility: implied_volatility.filter(|iv| *iv > 0.0),
+        entry_price_estimated,
+    }))
+}
+
+pub async fn save_context(conn: &Connection, context: &EntryContext) -> Result<()> {
+    conn.execute(
+        "INSERT INTO trade_entry_context (trade_type, trade_id, symbol, session_date, open, high, low, close, volume, \
+         spread_estimate_pct, implied_volatility, entry_price_estimated) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(trade_type, trade_id) DO UPDATE SET symbol = excluded.symbol, session_date = excluded.session_date, \
+         open = excluded.open, high = excluded.high, low = excluded.low, close = excluded.close, volume = excluded.volume, \
+         spread_estimate_pct = excluded.spread_estimate_pct, implied_volatility = excluded.implied_volatility, \
+         entry_price_estimated = excluded.entry_price_estimated",
+        params![
+            context.trade_type.clone(),
+            context.trade_id,
+            context.symbol.clone(),
+            context.session_date.clone(),
+            context.open,
+            context.high,
+            context.low,
+            context.close,
+            context.volume,
+            context.spread_estimate_pct,
+            context.implied_volatility,
+            context.entry_price_estimated as i64
+        ],
+    )
+    .await?;
+    Ok(())
+}
+
+pub async fn load_context(conn: &Connection, trade_type: &str, trade_id: i64) -> Result<Option<EntryContext>> {
+    let mut rows = conn
+        .prepare(
+            "SELECT trade_type, trade_id, symbol, session_date, open, high, low, close, volume, spread_estimate_pct, \
+             implied_volatility, entry_price_estimated FROM trade_entry_context WHERE trade_type = ? AND trade_id = ?",
+        )
+        .await?
+        .query(params![trade_type, trade_id])
+        .await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    Ok(Some(EntryContext {
+        trade_type: row.get(0)?,
+        trade_id: row.get(1)?,
+        symbol: row.get(2)?,
+        session_date: row.get(3)?,
+        open: row.get(4)?,
+        high: row.get(5)?,
+        low: row.get(6)?,
+        close: row.get(7)?,
+        volume: row.get(8)?,
+        spread_estimate_pct: row.get(9)?,
+        implied_volatility: row.get(10)?,
+        entry_price_estimated: row.get::<i64>(11)? != 0,
+    }))
+}
+
+/// Build and store the entry context of a trade logged before enrichment existed
+pub async fn enrich_existing(conn: &Connection, client: &MarketClient, trade_type: &str, trade_id: i64) -> Result<Option<EntryContext>> {
+    let Some((symbol, entry, _)) = earnings_transcripts::trade_window(conn, trade_type, trade_id).await? else {
+        return Ok(None);
+    };
+    let implied_volatility = if trade_type == "option" {
+        let mut rows = conn
+            .prepare("SELECT CAST(implied_volatility AS REAL) FROM options WHERE id = ?")
+            .await?
+            .query(params![trade_id])
+            .await?;
+        match rows.next().await? {
+            Some(row) => row.get::<Option<f64>>(0)?,
+            None => None,
+        }
+    } else {
+        None
+    };
+    let Some(context) = build_context(client, trade_type, trade_id, &symbol, entry, implied_volatility, false).await? else {
+        return Ok(None);
+    };
+    save_context(conn, &context).await?;
+    Ok(Some(context))
+}
+
+/// Build and store a trade's entry context in the background; failures only log
+#[allow(clippy::too_many_arguments)]
+pub fn spawn_enrichment(
+    conn: Connection,
+    client: MarketClient,
+    trade_type: &'static str,
+    trade_id: i64,
+    symbol: String,
+    entry_date: DateTime<Utc>,
+    implied_volatility: Option<f64>,
+    entry_price_estimated: bool,
+) {
+    tokio::spawn(async move {
+        match build_context(&client, trade_type, trade_id, &symbol, entry_date, implied_volatility, entry_price_estimated).await {
+            Ok(Some(context)) => {
+                if let Err(e) = save_context(&conn, &context).await {
+                    log::error!("Failed to save entry context for {} {}: {}", trade_type, trade_id, e);
+                }
+            }
+            Ok(None) => log::info!("No market data for the entry day of {} {} ({})", trade_type, trade_id, symbol),
+            Err(e) => log::warn!("Failed to enrich {} {} ({}): {}", trade_type, trade_id, symbol, e),
+        }
+    });
+}
+
+/// Listing exchange of a symbol from the provider's symbol search, normalized
+pub async fn lookup_venue(client: &MarketClient, symbol: &str) -> Result<Option<String>> {
+    let symbol = symbol.trim().to_uppercase();
+    let items = search::search(client, &symbol, Some(5), None).await?;
+    Ok(items
+        .into_iter()
+        .find(|item| item.symbol.eq_ignore_ascii_case(&symbol))
+        .and_then(|item| venue::normalize_venue_opt(item.exchange.as_deref())))
+}
+
+/// Fill a stock trade's venue from symbol metadata in the background, unless one was set
+/// in the meantime; failures only log
+pub fn spawn_venue_lookup(conn: Connection, client: MarketClient, trade_id: i64, symbol: String) {
+    tokio::spawn(async move {
+        match lookup_venue(&client, &symbol).await {
+            Ok(Some(venue)) => {
+                if let Err(e) = conn
+                    .execute("UPDATE stocks SET venue = ? WHERE id = ? AND venue IS NULL", params![venue, trade_id])
+                    .await
+                {
+                    log::error!("Failed to save venue for stock {}: {}", trade_id, e);
+                }
+            }
+            Ok(None) => log::info!("No listing exchange found for {}", symbol),
+            Err(e) => log::warn!("Failed to look up venue for {}: {}", symbol, e),
+        }
+    });
+}
+
+/// Look up a missing implied volatility in the live chain. Only trades entered within the
+/// last day on an unexpired contract qualify; today's IV says nothing about an old entry.
+pub async fn fill_option_iv(client: &MarketClient, cache: &CacheService, payload: &mut CreateOptionRequest) {
+    let now = Utc::now();
+    if now - payload.entry_date > chrono::Duration::days(1) || payload.expiration_date < now {
+        return;
+    }
+    let call = matches!(payload.option_type, OptionType::Call);
+    let strike = money::to_f64(payload.strike_price);
+    match options_chain::contract_iv(client, cache, &payload.symbol, payload.expiration_date.date_naive(), strike, call).await {
+        Ok(Some(iv)) => {
+            log::info!("Filled implied volatility {:.4} for {} {} {}", iv, payload.symbol, strike, payload.option_type);
+            payload.implied_volatility = iv;
+        }
+        Ok(None) => log::info!("No chain quote for {} {} {}; implied volatility left empty", payload.symbol, strike, payload.option_type),
+        Err(e) => log::error!("Failed to look up implied volatility for {}: {}", payload.symbol, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corwin_schultz_spread() {
+        // Identical 2% ranges on both days give a spread of about 2%
+        let spread = corwin_schultz_spread((101.0, 99.0), (101.0, 99.0)).unwrap();
+        assert!((spread - 0.02).abs() < 1e-4);
+
+        // A gap between sessions makes the raw estimate negative; it is floored at zero
+        assert_eq!(corwin_schultz_spread((101.0, 100.0), (111.0, 110.0)), Some(0.0));
+        assert_eq!(corwin_schultz_spread((101.0, 0.0), (101.0, 99.0)), None);
+    }
+
+    #[test]
+    fn test_entry_sessions() {
+        let bar = |day: u32, close: f64| Bar {
+            time: NaiveDate::from_ymd_opt(2024, 3, day).unwrap().and_hms_opt(13, 30, 0).unwrap().and_utc(),
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: Some(1000),
+        };
+        let bars = vec![bar(4, 10.0), bar(5, 11.0), bar(6, 12.0)];
+        let (previous, session) = entry_sessions(&bars, NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()).unwrap();
+        assert_eq!(previous.map(|b| b.close), Some(10.0));
+        assert_eq!(session.close, 11.0);
+        assert!(entry_sessions(&bars, NaiveDate::from_ymd_opt(2024, 3, 4).unwrap()).unwrap().0.is_none());
+        assert!(entry_sessions(&bars, NaiveDate::from_ymd_opt(2024, 3, 9).unwrap()).is_none());
+    }
+}
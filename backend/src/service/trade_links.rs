@@ -0,0 +1,322 @@
+// External links attached to a trade: the article, tweet or video behind it. They hang off
+// a stock or option trade the way trade notes do (one of `stock_trade_id` /
+// `option_trade_id`), so archiving, bulk deletes and undo carry them along. Links are also
+// appended to the trade's embedding text so chat retrieval sees why the trade was taken.
+
+use anyhow::Result;
+use chrono::Utc;
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+
+use crate::models::options::option_trade::OptionTrade;
+use crate::models::stock::stocks::Stock;
+use crate::service::ai_service::data_formatter::DataFormatter;
+use crate::service::ai_service::upstash_vector_client::DataType;
+use crate::service::ai_service::VectorizationService;
+
+pub const MAX_URL_LEN: usize = 2048;
+pub const MAX_TITLE_LEN: usize = 300;
+pub const MAX_LINKS_PER_TRADE: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkType {
+    Tweet,
+    Article,
+    Video,
+}
+
+impl LinkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkType::Tweet => "tweet",
+            LinkType::Article => "article",
+            LinkType::Video => "video",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "tweet" => LinkType::Tweet,
+            "video" => LinkType::Video,
+            _ => LinkType::Article,
+        }
+    }
+
+    /// Guess from the host when the client doesn't say
+    pub fn detect(url: &str) -> Self {
+        let host = url
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split(['/', '?', '#']).next())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let host = host.strip_prefix("www.").unwrap_or(&host);
+        match host {
+            "twitter.com" | "x.com" | "mobile.twitter.com" | "bsky.app" | "stocktwits.com" => LinkType::Tweet,
+            "youtube.com" | "m.youtube.com" | "youtu.be" | "vimeo.com" | "twitch.tv" => LinkType::Video,
+            _ => LinkType::Article,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeKind {
+    Stock,
+    Option,
+}
+
+impl TradeKind {
+    fn column(&self) -> &'static str {
+        match self {
+            TradeKind::Stock => "stock_trade_id",
+            TradeKind::Option => "option_trade_id",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            TradeKind::Stock => "stocks",
+            TradeKind::Option => "options",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeLink {
+    pub id: String,
+    pub trade_type: TradeKind,
+    pub trade_id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub link_type: LinkType,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateTradeLink {
+    pub trade_type: TradeKind,
+    pub trade_id: i64,
+    pub url: String,
+    pub title: Option<String>,
+    pub link_type: Option<LinkType>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateTradeLink {
+    pub url: Option<String>,
+    pub title: Option<String>,
+    pub link_type: Option<LinkType>,
+}
+
+pub fn validate_url(url: &str) -> Result<(), String> {
+    let url = url.trim();
+    if url.len() > MAX_URL_LEN {
+        return Err(format!("URLs are limited to {} characters", MAX_URL_LEN));
+    }
+    let Some(rest) = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://")) else {
+        return Err("Only http and https links can be attached".to_string());
+    };
+    if rest.is_empty() || rest.starts_with('/') || url.chars().any(char::is_whitespace) {
+        return Err("Invalid URL".to_string());
+    }
+    Ok(())
+}
+
+fn validate_title(title: Option<&str>) -> Result<(), String> {
+    match title {
+        Some(title) if title.chars().count() > MAX_TITLE_LEN => Err(format!("Titles are limited to {} characters", MAX_TITLE_LEN)),
+        _ => Ok(()),
+    }
+}
+
+const LINK_COLUMNS: &str = "id, stock_trade_id, option_trade_id, url, title, link_type, created_at, updated_at";
+
+fn row_to_link(row: &libsql::Row) -> Result<TradeLink> {
+    let stock_trade_id: Option<i64> = row.get(1)?;
+    let option_trade_id: Option<i64> = row.get(2)?;
+    let (trade_type, trade_id) = match (stock_trade_id, option_trade_id) {
+        (Some(id), _) => (TradeKind::Stock, id),
+        (None, Some(id)) => (TradeKind::Option, id),
+        (None, None) => anyhow::bail!("Trade link has no trade"),
+    };
+    Ok(TradeLink {
+        id: row.get(0)?,
+        trade_type,
+        trade_id,
+        url: row.get(3)?,
+        title: row.get(4)?,
+        link_type: LinkType::parse(&row.get::<String>(5)?),
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+async fn trade_exists(conn: &Connection, kind: TradeKind, trade_id: i64) -> Result<bool> {
+    let mut rows = conn.query(&format!("SELECT 1 FROM {} WHERE id = ?", kind.table()), params![trade_id]).await?;
+    Ok(rows.next().await?.is_some())
+}
+
+/// Links on one trade, oldest first. None when the trade doesn't exist.
+pub async fn list_for_trade(conn: &Connection, kind: TradeKind, trade_id: i64) -> Result<Option<Vec<TradeLink>>> {
+    if !trade_exists(conn, kind, trade_id).await? {
+        return Ok(None);
+    }
+    let mut rows = conn
+        .query(&format!("SELECT {LINK_COLUMNS} FROM trade_links WHERE {} = ? ORDER BY created_at", kind.column()), params![trade_id])
+        .await?;
+    let mut links = Vec::new();
+    while let Some(row) = rows.next().await? {
+        links.push(row_to_link(&row)?);
+    }
+    Ok(Some(links))
+}
+
+pub async fn get_link(conn: &Connection, id: &str) -> Result<Option<TradeLink>> {
+    let mut rows = conn.query(&format!("SELECT {LINK_COLUMNS} FROM trade_links WHERE id = ?"), params![id]).await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_link(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// Attach a link. Outer error is storage; inner is a message for the client.
+/// Ok(Ok(None)) means the trade doesn't exist.
+pub async fn create_link(conn: &Connection, request: &CreateTradeLink) -> Result<Result<Option<TradeLink>, String>> {
+    let url = request.url.trim();
+    if let Err(message) = validate_url(url).and_then(|_| validate_title(request.title.as_deref())) {
+        return Ok(Err(message));
+    }
+    if !trade_exists(conn, request.trade_type, request.trade_id).await? {
+        return Ok(Ok(None));
+    }
+    let mut rows = conn
+        .query(&format!("SELECT COUNT(*) FROM trade_links WHERE {} = ?", request.trade_type.column()), params![request.trade_id])
+        .await?;
+    let count: i64 = match rows.next().await? {
+        Some(row) => row.get(0)?,
+        None => 0,
+    };
+    if count >= MAX_LINKS_PER_TRADE {
+        return Ok(Err(format!("A trade can have at most {} links", MAX_LINKS_PER_TRADE)));
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let now = Utc::now().to_rfc3339();
+    let link_type = request.link_type.unwrap_or_else(|| LinkType::detect(url));
+    let title = request.title.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    conn.execute(
+        &format!(
+            "INSERT INTO trade_links (id, {}, url, title, link_type, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            request.trade_type.column()
+        ),
+        params![id.clone(), request.trade_id, url, title, link_type.as_str(), now.clone(), now],
+    )
+    .await?;
+    Ok(Ok(get_link(conn, &id).await?))
+}
+
+/// Same conventions as `create_link`; Ok(Ok(None)) means the link doesn't exist
+pub async fn update_link(conn: &Connection, id: &str, request: &UpdateTradeLink) -> Result<Result<Option<TradeLink>, String>> {
+    let Some(current) = get_link(conn, id).await? else { return Ok(Ok(None)) };
+    let url = request.url.as_deref().map(str::trim).unwrap_or(&current.url);
+    if let Err(message) = validate_url(url).and_then(|_| validate_title(request.title.as_deref())) {
+        return Ok(Err(message));
+    }
+    let title = match &request.title {
+        Some(title) => Some(title.trim()).filter(|t| !t.is_empty()),
+        None => current.title.as_deref(),
+    };
+    let link_type = request.link_type.unwrap_or(current.link_type);
+    conn.execute(
+        "UPDATE trade_links SET url = ?, title = ?, link_type = ?, updated_at = ? WHERE id = ?",
+        params![url, title, link_type.as_str(), Utc::now().to_rfc3339(), id],
+    )
+    .await?;
+    Ok(Ok(get_link(conn, id).await?))
+}
+
+/// The deleted link, so the caller knows which trade to refresh
+pub async fn delete_link(conn: &Connection, id: &str) -> Result<Option<TradeLink>> {
+    let Some(link) = get_link(conn, id).await? else { return Ok(None) };
+    conn.execute("DELETE FROM trade_links WHERE id = ?", params![id]).await?;
+    Ok(Some(link))
+}
+
+/// Section appended to a trade's embedding text; empty when it has no links
+pub fn embedding_section(links: &[TradeLink]) -> String {
+    if links.is_empty() {
+        return String::new();
+    }
+    let lines: Vec<String> = links
+        .iter()
+        .map(|link| match &link.title {
+            Some(title) => format!("- {} ({}): {}", title, link.link_type.as_str(), link.url),
+            None => format!("- {} ({})", link.url, link.link_type.as_str()),
+        })
+        .collect();
+    format!("\nLinked sources that motivated the trade:\n{}", lines.join("\n"))
+}
+
+/// Re-embed a trade with its current links, after they change
+pub async fn refresh_trade_embedding(
+    conn: &Connection,
+    vectorization: &VectorizationService,
+    user_id: &str,
+    kind: TradeKind,
+    trade_id: i64,
+) -> Result<()> {
+    let content = match kind {
+        TradeKind::Stock => match Stock::find_by_id(conn, trade_id).await.map_err(|e| anyhow::anyhow!("{}", e))? {
+            Some(stock) => DataFormatter::format_stock_for_embedding(&stock),
+            None => return Ok(()),
+        },
+        TradeKind::Option => match OptionTrade::find_by_id(conn, trade_id).await.map_err(|e| anyhow::anyhow!("{}", e))? {
+            Some(option) => DataFormatter::format_option_for_embedding(&option),
+            None => return Ok(()),
+        },
+    };
+    let links = list_for_trade(conn, kind, trade_id).await?.unwrap_or_default();
+    let data_type = match kind {
+        TradeKind::Stock => DataType::Stock,
+        TradeKind::Option => DataType::Option,
+    };
+    vectorization
+        .vectorize_data(user_id, data_type, &trade_id.to_string(), &format!("{}{}", content, embedding_section(&links)))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_link_type_and_validate() {
+        assert_eq!(LinkType::detect("https://x.com/trader/status/1"), LinkType::Tweet);
+        assert_eq!(LinkType::detect("https://www.youtube.com/watch?v=abc"), LinkType::Video);
+        assert_eq!(LinkType::detect("https://www.reuters.com/markets/nvda"), LinkType::Article);
+        assert!(validate_url("https://reuters.com/a").is_ok());
+        assert!(validate_url("javascript:alert(1)").is_err());
+        assert!(validate_url("https:// spaced.com").is_err());
+    }
+
+    #[test]
+    fn test_embedding_section_lists_links() {
+        assert_eq!(embedding_section(&[]), "");
+        let link = TradeLink {
+            id: "l1".to_string(),
+            trade_type: TradeKind::Stock,
+            trade_id: 7,
+            url: "https://reuters.com/nvda-guidance".to_string(),
+            title: Some("NVDA raises guidance".to_string()),
+            link_type: LinkType::Article,
+            created_at: String::new(),
+            updated_at: String::new(),
+        };
+        let section = embedding_section(&[link]);
+        assert!(section.contains("- NVDA raises guidance (article): https://reuters.com/nvda-guidance"));
+    }
+}
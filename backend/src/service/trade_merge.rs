@@ -0,0 +1,524 @@
+// Merging and splitting journal rows. Broker imports often record one logical trade as
+// several fills, and a position scaled out of in pieces is sometimes logged as one row.
+//
+// Merge folds rows into the one entered first: quantities and costs are summed, prices
+// become quantity-weighted averages (so realized P&L is unchanged), and tags, playbooks,
+// rule checks, notes and note images move to the surviving row. Split is the reverse: the
+// original row keeps the first part and each further part becomes a new trade carrying the
+// same tags, playbooks and rule checks, with commissions and premium shared pro rata. The
+// trade note stays on the original row. Each operation runs in one transaction.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use libsql::{params, Connection, Value};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::models::money;
+use crate::models::notes::TradeNote;
+use crate::models::options::{CreateOptionRequest, OptionTrade, TradeStatus, UpdateOptionRequest};
+use crate::models::stock::stocks::{CreateStockRequest, Stock, UpdateStockRequest};
+use crate::models::timestamps;
+use crate::service::note_encryption::{self, NoteCipher};
+use crate::turso::in_transaction;
+
+/// Most rows one merge or split may touch
+pub const MAX_ROWS: usize = 50;
+
+/// Tables holding a trade's journal data, per asset
+struct AssetTables {
+    kind: &'static str,
+    trade_column: &'static str,
+    tags: &'static str,
+    playbooks: &'static str,
+    compliance: &'static str,
+}
+
+const STOCK_TABLES: AssetTables = AssetTables {
+    kind: "stock",
+    trade_column: "stock_trade_id",
+    tags: "stock_trade_tags",
+    playbooks: "stock_trade_playbook",
+    compliance: "stock_trade_rule_compliance",
+};
+
+const OPTION_TABLES: AssetTables = AssetTables {
+    kind: "option",
+    trade_column: "option_trade_id",
+    tags: "option_trade_tags",
+    playbooks: "option_trade_playbook",
+    compliance: "option_trade_rule_compliance",
+};
+
+#[derive(Debug, Deserialize)]
+pub struct MergeRequest {
+    pub ids: Vec<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SplitPart {
+    /// Shares for stocks, contracts for options
+    pub quantity: f64,
+    /// Defaults to the original trade's exit
+    pub exit_price: Option<Decimal>,
+    #[serde(default, with = "timestamps::utc_millis_option")]
+    pub exit_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SplitRequest {
+    pub parts: Vec<SplitPart>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Merged<T> {
+    pub trade: T,
+    pub removed_ids: Vec<i64>,
+}
+
+/// Quantity-weighted average price
+pub fn weighted_price(fills: &[(Decimal, f64)]) -> Option<Decimal> {
+    let total: Decimal = fills.iter().map(|(_, qty)| money::from_f64(*qty)).sum();
+    if total <= Decimal::ZERO {
+        return None;
+    }
+    let value: Decimal = fills.iter().map(|(price, qty)| *price * money::from_f64(*qty)).sum();
+    Some((value / total).round_dp(8))
+}
+
+/// Share `total` out in proportion to `weights`, rounded to `dp` places. The last share takes
+/// the rounding remainder so the parts add back up exactly.
+pub fn allocate(total: Decimal, weights: &[f64], dp: u32) -> Vec<Decimal> {
+    let sum: f64 = weights.iter().sum();
+    if weights.is_empty() || sum <= 0.0 {
+        return vec![Decimal::ZERO; weights.len()];
+    }
+    let mut shares: Vec<Decimal> = weights[..weights.len() - 1]
+        .iter()
+        .map(|w| (total * money::from_f64(w / sum)).round_dp(dp))
+        .collect();
+    let allocated: Decimal = shares.iter().sum();
+    shares.push(total - allocated);
+    shares
+}
+
+fn validate_ids(ids: &[i64]) -> Result<Vec<i64>, String> {
+    let mut unique: Vec<i64> = Vec::new();
+    for id in ids {
+        if !unique.contains(id) {
+            unique.push(*id);
+        }
+    }
+    if unique.len() < 2 {
+        return Err("At least two different trades are needed to merge".to_string());
+    }
+    if unique.len() > MAX_ROWS {
+        return Err(format!("At most {} trades can be merged at once", MAX_ROWS));
+    }
+    Ok(unique)
+}
+
+fn validate_parts(parts: &[SplitPart], quantity: f64, whole_units: bool) -> Result<(), String> {
+    if parts.len() < 2 || parts.len() > MAX_ROWS {
+        return Err(format!("A split needs between 2 and {} parts", MAX_ROWS));
+    }
+    if parts.iter().any(|p| !p.quantity.is_finite() || p.quantity <= 0.0) {
+        return Err("Every part needs a positive quantity".to_string());
+    }
+    if whole_units && parts.iter().any(|p| p.quantity.fract() != 0.0) {
+        return Err("Option parts must be whole contracts".to_string());
+    }
+    let total: f64 = parts.iter().map(|p| p.quantity).sum();
+    if (total - quantity).abs() > 1e-9 * quantity.abs().max(1.0) {
+        return Err(format!("Part quantities add up to {} but the trade has {}", total, quantity));
+    }
+    Ok(())
+}
+
+fn in_list(ids: &[i64]) -> (String, Vec<Value>) {
+    (vec!["?"; ids.len()].join(", "), ids.iter().map(|id| Value::Integer(*id)).collect())
+}
+
+fn with_first(first: i64, rest: &[Value]) -> Vec<Value> {
+    std::iter::once(Value::Integer(first)).chain(rest.iter().cloned()).collect()
+}
+
+/// Move everything attached to `removed` onto `survivor`, then drop what could not move
+async fn fold_links(conn: &Connection, tables: &AssetTables, survivor: i64, removed: &[i64]) -> Result<()> {
+    let (list, ids) = in_list(removed);
+    let col = tables.trade_column;
+    for table in [tables.tags, tables.playbooks] {
+        conn.execute(&format!("UPDATE OR IGNORE {table} SET {col} = ? WHERE {col} IN ({list})"), with_first(survivor, &ids)).await?;
+        conn.execute(&format!("DELETE FROM {table} WHERE {col} IN ({list})"), ids.clone()).await?;
+    }
+
+    // A rule broken on any fill is broken on the merged trade; keep one check per rule
+    let compliance = tables.compliance;
+    conn.execute(&format!("UPDATE {compliance} SET {col} = ? WHERE {col} IN ({list})"), with_first(survivor, &ids)).await?;
+    conn.execute(
+        &format!(
+            "UPDATE {compliance} SET is_followed = 0 WHERE {col} = ?1
+             AND rule_id IN (SELECT rule_id FROM {compliance} WHERE {col} = ?1 AND is_followed = 0)"
+        ),
+        params![survivor],
+    )
+    .await?;
+    conn.execute(
+        &format!("DELETE FROM {compliance} WHERE {col} = ?1 AND id NOT IN (SELECT MIN(id) FROM {compliance} WHERE {col} = ?1 GROUP BY rule_id)"),
+        params![survivor],
+    )
+    .await?;
+
+    let kind_and_ids = std::iter::once(Value::Text(tables.kind.to_string())).chain(ids.iter().cloned()).collect::<Vec<_>>();
+    conn.execute(
+        &format!("UPDATE OR IGNORE trade_earnings_summaries SET trade_id = ? WHERE trade_type = ? AND trade_id IN ({list})"),
+        with_first(survivor, &kind_and_ids),
+    )
+    .await?;
+    // Derived per-trade rows are rebuilt for the survivor by their own jobs
+    for table in ["trade_earnings_summaries", "trade_entry_context", "data_issues"] {
+        conn.execute(&format!("DELETE FROM {table} WHERE trade_type = ? AND trade_id IN ({list})"), kind_and_ids.clone()).await?;
+    }
+
+    fold_notes(conn, tables, survivor, removed).await
+}
+
+async fn find_note(conn: &Connection, tables: &AssetTables, trade_id: i64) -> Result<Option<TradeNote>> {
+    let note = if tables.kind == "stock" {
+        TradeNote::find_by_stock_trade_id(conn, trade_id).await
+    } else {
+        TradeNote::find_by_option_trade_id(conn, trade_id).await
+    };
+    note.map_err(|e| anyhow!(e))
+}
+
+/// A trade has at most one note: the first note found moves to the survivor, later ones are
+/// appended to it and their images re-pointed
+async fn fold_notes(conn: &Connection, tables: &AssetTables, survivor: i64, removed: &[i64]) -> Result<()> {
+    let col = tables.trade_column;
+    let cipher = NoteCipher::for_connection(conn).await?;
+    let mut kept = find_note(conn, tables, survivor).await?;
+    for trade_id in removed {
+        let Some(note) = find_note(conn, tables, *trade_id).await? else { continue };
+        match &mut kept {
+            None => {
+                conn.execute(&format!("UPDATE trade_notes SET {col} = ? WHERE id = ?"), params![survivor, note.id.clone()]).await?;
+                kept = Some(note);
+            }
+            Some(target) => {
+                if !note.content.trim().is_empty() {
+                    target.content = if target.content.trim().is_empty() {
+                        note.content.clone()
+                    } else {
+                        format!("{}\n\n{}", target.content, note.content)
+                    };
+                    let sealed = note_encryption::seal_content(cipher.as_ref(), target.content.clone())?;
+                    conn.execute(
+                        "UPDATE trade_notes SET content = ?, updated_at = ? WHERE id = ?",
+                        params![sealed, Utc::now().to_rfc3339(), target.id.clone()],
+                    )
+                    .await?;
+                }
+                conn.execute("UPDATE images SET trade_note_id = ? WHERE trade_note_id = ?", params![target.id.clone(), note.id.clone()]).await?;
+                conn.execute("DELETE FROM trade_notes WHERE id = ?", params![note.id]).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Give a new split part the original's tags, playbooks and rule checks
+async fn copy_links(conn: &Connection, tables: &AssetTables, from: i64, to: i64) -> Result<()> {
+    let col = tables.trade_column;
+    let (tags, playbooks, compliance) = (tables.tags, tables.playbooks, tables.compliance);
+    conn.execute(&format!("INSERT OR IGNORE INTO {tags} ({col}, tag_id) SELECT ?, tag_id FROM {tags} WHERE {col} = ?"), params![to, from]).await?;
+    conn.execute(
+        &format!("INSERT OR IGNORE INTO {playbooks} ({col}, setup_id) SELECT ?, setup_id FROM {playbooks} WHERE {col} = ?"),
+        params![to, from],
+    )
+    .await?;
+    conn.execute(
+        &format!(
+            "INSERT INTO {compliance} (id, {col}, playbook_id, rule_id, is_followed, notes, created_at)
+             SELECT lower(hex(randomblob(16))), ?, playbook_id, rule_id, is_followed, notes, created_at FROM {compliance} WHERE {col} = ?"
+        ),
+        params![to, from],
+    )
+    .await?;
+    Ok(())
+}
+
+async fn delete_rows(conn: &Connection, table: &str, ids: &[i64]) -> Result<()> {
+    let (list, values) = in_list(ids);
+    conn.execute(&format!("DELETE FROM {table} WHERE id IN ({list})"), values).await?;
+    Ok(())
+}
+
+fn latest(dates: impl Iterator<Item = Option<DateTime<Utc>>>) -> Option<DateTime<Utc>> {
+    dates.flatten().max()
+}
+
+/// Merge stock rows into the one entered first. Rows must share symbol and side, and be all
+/// open or all closed. The inner error is a message for the client.
+pub async fn merge_stocks(conn: &Connection, ids: &[i64]) -> Result<Result<Merged<Stock>, String>> {
+    let ids = match validate_ids(ids) {
+        Ok(ids) => ids,
+        Err(message) => return Ok(Err(message)),
+    };
+    let mut trades = Vec::new();
+    for id in &ids {
+        match Stock::find_by_id(conn, *id).await.map_err(|e| anyhow!(e))? {
+            Some(trade) => trades.push(trade),
+            None => return Ok(Err(format!("Stock trade {} not found", id))),
+        }
+    }
+    trades.sort_by_key(|t| (t.entry_date, t.id));
+    let first = &trades[0];
+    if trades.iter().any(|t| t.symbol != first.symbol || t.trade_type != first.trade_type) {
+        return Ok(Err("Only trades with the same symbol and side can be merged".to_string()));
+    }
+    let closed = trades.iter().filter(|t| t.exit_price.is_some() && t.exit_date.is_some()).count();
+    if closed != 0 && closed != trades.len() {
+        return Ok(Err("Open and closed trades cannot be merged together".to_string()));
+    }
+
+    let entries: Vec<(Decimal, f64)> = trades.iter().map(|t| (t.entry_price, t.number_shares)).collect();
+    let exits: Vec<(Decimal, f64)> = trades.iter().filter_map(|t| t.exit_price.map(|p| (p, t.number_shares))).collect();
+    let update = UpdateStockRequest {
+        entry_price: weighted_price(&entries),
+        exit_price: weighted_price(&exits),
+        number_shares: Some(trades.iter().map(|t| t.number_shares).sum()),
+        commissions: Some(trades.iter().map(|t| t.commissions).sum()),
+        exit_date: latest(trades.iter().map(|t| t.exit_date)),
+        ..Default::default()
+    };
+    let survivor = first.id;
+    let removed: Vec<i64> = trades[1..].iter().map(|t| t.id).collect();
+
+    let merged = in_transaction(conn, async |tx: &Connection| -> Result<Stock> {
+        let merged = Stock::update(tx, survivor, update).await.map_err(|e| anyhow!(e))?.ok_or_else(|| anyhow!("Stock trade {} vanished during merge", survivor))?;
+        fold_links(tx, &STOCK_TABLES, survivor, &removed).await?;
+        delete_rows(tx, "stocks", &removed).await?;
+        Ok(merged)
+    })
+    .await?;
+    Ok(Ok(Merged { trade: merged, removed_ids: removed }))
+}
+
+/// Split a stock row into parts by share count; returns the original row first
+pub async fn split_stock(conn: &Connection, id: i64, parts: &[SplitPart]) -> Result<Result<Vec<Stock>, String>> {
+    let Some(original) = Stock::find_by_id(conn, id).await.map_err(|e| anyhow!(e))? else {
+        return Ok(Err(format!("Stock trade {} not found", id)));
+    };
+    if let Err(message) = validate_parts(parts, original.number_shares, false) {
+        return Ok(Err(message));
+    }
+    let weights: Vec<f64> = parts.iter().map(|p| p.quantity).collect();
+    let commissions = allocate(original.commissions, &weights, 4);
+
+    let trades = in_transaction(conn, async |tx: &Connection| -> Result<Vec<Stock>> {
+        let mut trades = Vec::with_capacity(parts.len());
+        for (index, (part, commission)) in parts.iter().zip(commissions).enumerate() {
+            let exit = UpdateStockRequest {
+                number_shares: Some(part.quantity),
+                commissions: Some(commission),
+                exit_price: part.exit_price.or(original.exit_price),
+                exit_date: part.exit_date.or(original.exit_date),
+                ..Default::default()
+            };
+            let target = if index == 0 {
+                original.id
+            } else {
+                let request = CreateStockRequest {
+                    symbol: original.symbol.clone(),
+                    trade_type: original.trade_type.clone(),
+                    order_type: original.order_type.clone(),
+                    entry_price: original.entry_price,
+                    stop_loss: original.stop_loss,
+                    commissions: commission,
+                    number_shares: part.quantity,
+                    take_profit: original.take_profit,
+                    initial_target: original.initial_target,
+                    profit_target: original.profit_target,
+                    trade_ratings: original.trade_ratings,
+                    entry_date: original.entry_date,
+                    reviewed: Some(original.reviewed),
+                    mistakes: original.mistakes.clone(),
+                    brokerage_name: original.brokerage_name.clone(),
+                    venue: original.venue.clone(),
+                };
+                let created = Stock::create(tx, request).await.map_err(|e| anyhow!(e))?;
+                copy_links(tx, &STOCK_TABLES, original.id, created.id).await?;
+                created.id
+            };
+            let trade = Stock::update(tx, target, exit).await.map_err(|e| anyhow!(e))?.ok_or_else(|| anyhow!("Stock trade {} vanished during split", target))?;
+            trades.push(trade);
+        }
+        Ok(trades)
+    })
+    .await?;
+    Ok(Ok(trades))
+}
+
+/// Merge option rows into the one entered first. Rows must be the same contract (symbol,
+/// type, strike and expiration) and share a status. The inner error is a message for the client.
+pub async fn merge_options(conn: &Connection, ids: &[i64]) -> Result<Result<Merged<OptionTrade>, String>> {
+    let ids = match validate_ids(ids) {
+        Ok(ids) => ids,
+        Err(message) => return Ok(Err(message)),
+    };
+    let mut trades = Vec::new();
+    for id in &ids {
+        match OptionTrade::find_by_id(conn, *id).await.map_err(|e| anyhow!(e))? {
+            Some(trade) if !trade.is_deleted => trades.push(trade),
+            _ => return Ok(Err(format!("Option trade {} not found", id))),
+        }
+    }
+    trades.sort_by_key(|t| (t.entry_date, t.id));
+    let first = &trades[0];
+    if trades.iter().any(|t| {
+        t.symbol != first.symbol
+            || t.option_type != first.option_type
+            || t.strike_price != first.strike_price
+            || t.expiration_date != first.expiration_date
+    }) {
+        return Ok(Err("Only trades on the same contract can be merged".to_string()));
+    }
+    if trades.iter().any(|t| t.status != first.status) {
+        return Ok(Err("Open and closed trades cannot be merged together".to_string()));
+    }
+
+    let contracts = |t: &OptionTrade| t.number_of_contracts as f64;
+    let entries: Vec<(Decimal, f64)> = trades.iter().map(|t| (t.entry_price, contracts(t))).collect();
+    let exits: Vec<(Decimal, f64)> = trades.iter().filter_map(|t| t.exit_price.map(|p| (p, contracts(t)))).collect();
+    let ivs: Vec<(Decimal, f64)> = trades.iter().map(|t| (money::from_f64(t.implied_volatility), contracts(t))).collect();
+    let update = UpdateOptionRequest {
+        number_of_contracts: Some(trades.iter().map(|t| t.number_of_contracts).sum()),
+        entry_price: weighted_price(&entries),
+        exit_price: weighted_price(&exits),
+        total_premium: Some(trades.iter().map(|t| t.total_premium).sum()),
+        commissions: Some(trades.iter().map(|t| t.commissions).sum()),
+        implied_volatility: weighted_price(&ivs).map(money::to_f64),
+        exit_date: latest(trades.iter().map(|t| t.exit_date)),
+        ..Default::default()
+    };
+    let survivor = first.id;
+    let removed: Vec<i64> = trades[1..].iter().map(|t| t.id).collect();
+
+    let merged = in_transaction(conn, async |tx: &Connection| -> Result<OptionTrade> {
+        let merged = OptionTrade::update(tx, survivor, update)
+            .await
+            .map_err(|e| anyhow!(e))?
+            .ok_or_else(|| anyhow!("Option trade {} vanished during merge", survivor))?;
+        fold_links(tx, &OPTION_TABLES, survivor, &removed).await?;
+        delete_rows(tx, "options", &removed).await?;
+        Ok(merged)
+    })
+    .await?;
+    Ok(Ok(Merged { trade: merged, removed_ids: removed }))
+}
+
+/// Split an option row into parts by contract count; returns the original row first
+pub async fn split_option(conn: &Connection, id: i64, parts: &[SplitPart]) -> Result<Result<Vec<OptionTrade>, String>> {
+    let original = match OptionTrade::find_by_id(conn, id).await.map_err(|e| anyhow!(e))? {
+        Some(trade) if !trade.is_deleted => trade,
+        _ => return Ok(Err(format!("Option trade {} not found", id))),
+    };
+    if let Err(message) = validate_parts(parts, original.number_of_contracts as f64, true) {
+        return Ok(Err(message));
+    }
+    let weights: Vec<f64> = parts.iter().map(|p| p.quantity).collect();
+    let commissions = allocate(original.commissions, &weights, 4);
+    let premiums = allocate(original.total_premium, &weights, 8);
+
+    let trades = in_transaction(conn, async |tx: &Connection| -> Result<Vec<OptionTrade>> {
+        let mut trades = Vec::with_capacity(parts.len());
+        for (index, ((part, commission), premium)) in parts.iter().zip(commissions).zip(premiums).enumerate() {
+            let exit_price = part.exit_price.or(original.exit_price);
+            let exit_date = part.exit_date.or(original.exit_date);
+            let closed = original.status == TradeStatus::Closed || (exit_price.is_some() && exit_date.is_some());
+            let exit = UpdateOptionRequest {
+                number_of_contracts: Some(part.quantity as i32),
+                commissions: Some(commission),
+                total_premium: Some(premium),
+                exit_price,
+                exit_date,
+                status: Some(if closed { TradeStatus::Closed } else { TradeStatus::Open }),
+                ..Default::default()
+            };
+            let target = if index == 0 {
+                original.id
+            } else {
+                let request = CreateOptionRequest {
+                    symbol: original.symbol.clone(),
+                    strategy_type: original.strategy_type.clone(),
+                    trade_direction: original.trade_direction.clone(),
+                    number_of_contracts: part.quantity as i32,
+                    option_type: original.option_type.clone(),
+                    strike_price: original.strike_price,
+                    expiration_date: original.expiration_date,
+                    entry_price: original.entry_price,
+                    total_premium: premium,
+                    commissions: commission,
+                    implied_volatility: original.implied_volatility,
+                    entry_date: original.entry_date,
+                    initial_target: original.initial_target,
+                    profit_target: original.profit_target,
+                    trade_ratings: original.trade_ratings,
+                    reviewed: Some(original.reviewed),
+                    mistakes: original.mistakes.clone(),
+                    brokerage_name: original.brokerage_name.clone(),
+                    venue: original.venue.clone(),
+                    short_premium: Some(original.short_premium),
+                };
+                let created = OptionTrade::create(tx, request).await.map_err(|e| anyhow!(e))?;
+                copy_links(tx, &OPTION_TABLES, original.id, created.id).await?;
+                created.id
+            };
+            let trade = OptionTrade::update(tx, target, exit)
+                .await
+                .map_err(|e| anyhow!(e))?
+                .ok_or_else(|| anyhow!("Option trade {} vanished during split", target))?;
+            trades.push(trade);
+        }
+        Ok(trades)
+    })
+    .await?;
+    Ok(Ok(trades))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_weighted_prices_keep_pnl() {
+        // Two long fills: 100 @ 10 out @ 12, 300 @ 11 out @ 11.5
+        let entry = weighted_price(&[(dec("10"), 100.0), (dec("11"), 300.0)]).unwrap();
+        let exit = weighted_price(&[(dec("12"), 100.0), (dec("11.5"), 300.0)]).unwrap();
+        assert_eq!(entry, dec("10.75"));
+        assert_eq!(exit, dec("11.625"));
+        let separate = dec("2") * dec("100") + dec("0.5") * dec("300");
+        assert_eq!((exit - entry) * dec("400"), separate);
+        assert_eq!(weighted_price(&[]), None);
+    }
+
+    #[test]
+    fn test_allocate_and_part_validation() {
+        let shares = allocate(dec("10.00"), &[1.0, 1.0, 1.0], 4);
+        assert_eq!(shares, vec![dec("3.3333"), dec("3.3333"), dec("3.3334")]);
+        assert_eq!(shares.iter().sum::<Decimal>(), dec("10.00"));
+
+        let part = |quantity: f64| SplitPart { quantity, exit_price: None, exit_date: None };
+        assert!(validate_parts(&[part(40.0), part(60.0)], 100.0, false).is_ok());
+        assert!(validate_parts(&[part(40.0), part(50.0)], 100.0, false).is_err());
+        assert!(validate_parts(&[part(1.5), part(1.5)], 3.0, true).is_err());
+        assert!(validate_ids(&[4, 4]).is_err());
+    }
+}
@@ -0,0 +1,722 @@
+// Annual "year in review". After a year closes (or on demand, for the year so far) each
+// journal gets a one-page PDF: the year's headline stats, P&L by month, the best and worst
+// trades, the metrics that improved most from the first half of the year to the second,
+// discipline stats and a short AI-written narrative. Reviews are listed in `year_reviews`,
+// one row per year, with the PDF in object storage; generating a year again replaces it.
+//
+// Sharing: a review can be handed out as an expiring public link, signed the same way as
+// shared AI reports. The link also carries a nonce stored on the review, so revoking (or
+// regenerating) the review invalidates every link given out before.
+
+use anyhow::{anyhow, Result};
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use libsql::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::models::analytics::PeriodDefinition;
+use crate::models::money;
+use crate::models::pnl::{OPTION_PNL_SQL, STOCK_PNL_SQL};
+use crate::service::ai_service::report_sharing::{sign_token, verify_token, MAX_SHARE_DAYS};
+use crate::service::ai_service::openrouter_client::{ChatMessage, MessageRole, OpenRouterClient};
+use crate::service::image_upload::ImageUploadService;
+use crate::service::statements::pdf::{Page, Rgb, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::service::statements::{money_text, pct_text};
+use crate::turso::config::ReportSharingConfig;
+
+const REVIEW_COLUMNS: &str = "id, year, object_path, size_bytes, summary, narrative, created_at";
+
+/// Best and worst trades listed
+const TOP_TRADES: usize = 3;
+/// Trades each half of the year needs before halves are compared
+const MIN_HALF_TRADES: usize = 5;
+/// Narrative lines that fit at the bottom of the page
+const MAX_NARRATIVE_LINES: usize = 11;
+const NARRATIVE_WRAP_CHARS: usize = 105;
+
+const GREEN: Rgb = Rgb(0.13, 0.59, 0.33);
+const RED: Rgb = Rgb(0.8, 0.2, 0.2);
+const MARGIN: f64 = 48.0;
+
+const NARRATIVE_PROMPT: &str = "You write the short narrative for a trader's year-in-review report. \
+Using only the figures given, write at most 120 words of plain text (no markdown, no lists, no headings) \
+in the second person: how the year went overall, what improved, and one thing to focus on next year. \
+Be honest about losing years and don't give financial advice.";
+
+/// One closed trade counted in the review
+#[derive(Debug, Clone, PartialEq)]
+struct ClosedTrade {
+    id: i64,
+    is_option: bool,
+    symbol: String,
+    day: NaiveDate,
+    pnl: f64,
+    reviewed: bool,
+    has_mistake: bool,
+    rating: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewTrade {
+    /// `stock` or `option`
+    pub kind: String,
+    pub id: i64,
+    pub symbol: String,
+    pub closed_on: String,
+    pub pnl: f64,
+}
+
+/// A metric compared between the first and second half of the year
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricChange {
+    pub metric: String,
+    pub first_half: f64,
+    pub second_half: f64,
+    /// Improvement relative to the first half; None when that was zero
+    pub improvement_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DisciplineStats {
+    pub reviewed_pct: Option<f64>,
+    /// Share of trades with a mistake written down
+    pub mistake_pct: Option<f64>,
+    pub avg_rating: Option<f64>,
+    /// Playbook rules followed, over all rule checks on the year's trades
+    pub rule_compliance_pct: Option<f64>,
+    pub longest_win_streak: u32,
+    pub longest_loss_streak: u32,
+}
+
+/// Figures printed on the review, also stored with it for the list view
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct YearReviewSummary {
+    pub year: i32,
+    pub net_pnl: f64,
+    pub trades: u32,
+    pub winners: u32,
+    pub losers: u32,
+    pub win_rate: Option<f64>,
+    pub profit_factor: Option<f64>,
+    pub expectancy: Option<f64>,
+    pub trading_days: u32,
+    /// Net P&L of each month with closed trades, YYYY-MM
+    pub monthly_pnl: Vec<(String, f64)>,
+    pub best_month: Option<(String, f64)>,
+    pub worst_month: Option<(String, f64)>,
+    /// Symbol with the highest net P&L
+    pub best_symbol: Option<(String, f64)>,
+    pub best_trades: Vec<ReviewTrade>,
+    pub worst_trades: Vec<ReviewTrade>,
+    /// Metrics that got better in the second half, most improved first
+    pub most_improved: Vec<MetricChange>,
+    pub discipline: DisciplineStats,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct YearReview {
+    pub id: String,
+    pub year: i32,
+    #[serde(skip)]
+    pub object_path: String,
+    pub size_bytes: i64,
+    pub summary: YearReviewSummary,
+    pub narrative: Option<String>,
+    pub created_at: String,
+}
+
+/// Year before the one containing `today`: what a review generated in January covers
+pub fn default_year(today: NaiveDate) -> i32 {
+    today.year() - 1
+}
+
+fn pct(part: usize, whole: usize) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64 * 100.0)
+}
+
+fn review_trade(trade: &ClosedTrade) -> ReviewTrade {
+    ReviewTrade {
+        kind: if trade.is_option { "option" } else { "stock" }.to_string(),
+        id: trade.id,
+        symbol: trade.symbol.clone(),
+        closed_on: trade.day.to_string(),
+        pnl: trade.pnl,
+    }
+}
+
+/// Metrics of a slice of trades, for comparing the two halves of the year
+struct HalfMetrics {
+    win_rate: f64,
+    profit_factor: Option<f64>,
+    expectancy: f64,
+    avg_loss: Option<f64>,
+    mistake_rate: f64,
+    reviewed_rate: f64,
+}
+
+impl HalfMetrics {
+    fn of(trades: &[&ClosedTrade]) -> Self {
+        let count = trades.len().max(1) as f64;
+        let gross_win: f64 = trades.iter().map(|t| t.pnl).filter(|p| *p > 0.0).sum();
+        let losses: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|p| *p < 0.0).collect();
+        let gross_loss = -losses.iter().sum::<f64>();
+        Self {
+            win_rate: trades.iter().filter(|t| t.pnl > 0.0).count() as f64 / count * 100.0,
+            profit_factor: (gross_loss > 0.0).then(|| gross_win / gross_loss),
+            expectancy: trades.iter().map(|t| t.pnl).sum::<f64>() / count,
+            avg_loss: (!losses.is_empty()).then(|| gross_loss / losses.len() as f64),
+            mistake_rate: trades.iter().filter(|t| t.has_mistake).count() as f64 / count * 100.0,
+            reviewed_rate: trades.iter().filter(|t| t.reviewed).count() as f64 / count * 100.0,
+        }
+    }
+}
+
+/// Metrics that improved from January-June to July-December
+fn most_improved(trades: &[ClosedTrade]) -> Vec<MetricChange> {
+    let (first, second): (Vec<&ClosedTrade>, Vec<&ClosedTrade>) = trades.iter().partition(|t| t.day.month() <= 6);
+    if first.len() < MIN_HALF_TRADES || second.len() < MIN_HALF_TRADES {
+        return Vec::new();
+    }
+    let (a, b) = (HalfMetrics::of(&first), HalfMetrics::of(&second));
+    // (metric, first half, second half, higher is better)
+    let candidates = [
+        ("win_rate", Some(a.win_rate), Some(b.win_rate), true),
+        ("profit_factor", a.profit_factor, b.profit_factor, true),
+        ("expectancy", Some(a.expectancy), Some(b.expectancy), true),
+        ("average_loss", a.avg_loss, b.avg_loss, false),
+        ("mistake_rate", Some(a.mistake_rate), Some(b.mistake_rate), false),
+        ("reviewed_rate", Some(a.reviewed_rate), Some(b.reviewed_rate), true),
+    ];
+    let mut improved: Vec<MetricChange> = candidates
+        .into_iter()
+        .filter_map(|(metric, first, second, higher_is_better)| {
+            let (first, second) = (first?, second?);
+            let gain = if higher_is_better { second - first } else { first - second };
+            (gain > 1e-9).then(|| MetricChange {
+                metric: metric.to_string(),
+                first_half: first,
+                second_half: second,
+                improvement_pct: (first.abs() > 1e-9).then(|| gain / first.abs() * 100.0),
+            })
+        })
+        .collect();
+    improved.sort_by(|x, y| {
+        let key = |m: &MetricChange| m.improvement_pct.unwrap_or(f64::NEG_INFINITY);
+        key(y).total_cmp(&key(x))
+    });
+    improved
+}
+
+fn summarize(year: i32, trades: &[ClosedTrade], rule_compliance_pct: Option<f64>) -> YearReviewSummary {
+    let wins: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|p| *p > 0.0).collect();
+    let losses: Vec<f64> = trades.iter().map(|t| t.pnl).filter(|p| *p < 0.0).collect();
+    let gross_win: f64 = wins.iter().sum();
+    let gross_loss: f64 = -losses.iter().sum::<f64>();
+    let net_pnl: f64 = trades.iter().map(|t| t.pnl).sum();
+
+    let mut months: BTreeMap<String, f64> = BTreeMap::new();
+    let mut symbols: BTreeMap<&str, f64> = BTreeMap::new();
+    let mut days: Vec<NaiveDate> = trades.iter().map(|t| t.day).collect();
+    days.dedup();
+    for trade in trades {
+        *months.entry(trade.day.format("%Y-%m").to_string()).or_default() += trade.pnl;
+        *symbols.entry(trade.symbol.as_str()).or_default() += trade.pnl;
+    }
+
+    let mut by_pnl: Vec<&ClosedTrade> = trades.iter().collect();
+    by_pnl.sort_by(|a, b| b.pnl.total_cmp(&a.pnl));
+    let best_trades = by_pnl.iter().filter(|t| t.pnl > 0.0).take(TOP_TRADES).map(|t| review_trade(t)).collect();
+    let worst_trades = by_pnl.iter().rev().filter(|t| t.pnl < 0.0).take(TOP_TRADES).map(|t| review_trade(t)).collect();
+
+    // Trades are in closing order, so streaks are consecutive closes
+    let (mut win_streak, mut loss_streak, mut longest_win, mut longest_loss) = (0, 0, 0, 0);
+    for trade in trades {
+        win_streak = if trade.pnl > 0.0 { win_streak + 1 } else { 0 };
+        loss_streak = if trade.pnl < 0.0 { loss_streak + 1 } else { 0 };
+        longest_win = longest_win.max(win_streak);
+        longest_loss = longest_loss.max(loss_streak);
+    }
+    let ratings: Vec<i64> = trades.iter().filter_map(|t| t.rating).collect();
+
+    let month_entry = |(month, pnl): (&String, &f64)| (month.clone(), *pnl);
+    YearReviewSummary {
+        year,
+        net_pnl,
+        trades: trades.len() as u32,
+        winners: wins.len() as u32,
+        losers: losses.len() as u32,
+        win_rate: pct(wins.len(), trades.len()),
+        profit_factor: (gross_loss > 0.0).then(|| gross_win / gross_loss),
+        expectancy: (!trades.is_empty()).then(|| net_pnl / trades.len() as f64),
+        trading_days: days.len() as u32,
+        best_month: months.iter().max_by(|a, b| a.1.total_cmp(b.1)).filter(|(_, p)| **p > 0.0).map(month_entry),
+        worst_month: months.iter().min_by(|a, b| a.1.total_cmp(b.1)).filter(|(_, p)| **p < 0.0).map(month_entry),
+        monthly_pnl: months.iter().map(month_entry).collect(),
+        best_symbol: symbols.iter().max_by(|a, b| a.1.total_cmp(b.1)).filter(|(_, p)| **p > 0.0).map(|(s, p)| (s.to_string(), *p)),
+        best_trades,
+        worst_trades,
+        most_improved: most_improved(trades),
+        discipline: DisciplineStats {
+            reviewed_pct: pct(trades.iter().filter(|t| t.reviewed).count(), trades.len()),
+            mistake_pct: pct(trades.iter().filter(|t| t.has_mistake).count(), trades.len()),
+            avg_rating: (!ratings.is_empty()).then(|| ratings.iter().sum::<i64>() as f64 / ratings.len() as f64),
+            rule_compliance_pct,
+            longest_win_streak: longest_win,
+            longest_loss_streak: longest_loss,
+        },
+    }
+}
+
+/// Trades closed on trading dates within `year`, in closing order
+async fn closed_trades(conn: &Connection, periods: &PeriodDefinition, year: i32) -> Result<Vec<ClosedTrade>> {
+    let day = format!("date({})", periods.trading_date_sql("exit_date"));
+    let sql = format!(
+        "SELECT id, is_option, symbol, day, pnl, reviewed, has_mistake, trade_ratings FROM (
+            SELECT id, 0 AS is_option, symbol, {day} AS day, {STOCK_PNL_SQL} AS pnl,
+                   CASE WHEN reviewed IN (1, '1', 'true') THEN 1 ELSE 0 END AS reviewed,
+                   TRIM(COALESCE(mistakes, '')) != '' AS has_mistake, trade_ratings
+            FROM stocks WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+            UNION ALL
+            SELECT id, 1 AS is_option, symbol, {day} AS day, {OPTION_PNL_SQL} AS pnl,
+                   CASE WHEN reviewed IN (1, '1', 'true') THEN 1 ELSE 0 END AS reviewed,
+                   TRIM(COALESCE(mistakes, '')) != '' AS has_mistake, trade_ratings
+            FROM options WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL AND is_deleted = 0
+        ) WHERE strftime('%Y', day) = ? ORDER BY day, id"
+    );
+    let mut rows = conn.prepare(&sql).await?.query(params![format!("{:04}", year)]).await?;
+    let mut trades = Vec::new();
+    while let Some(row) = rows.next().await? {
+        let Ok(day) = NaiveDate::parse_from_str(&row.get::<String>(3)?, "%Y-%m-%d") else { continue };
+        trades.push(ClosedTrade {
+            id: row.get(0)?,
+            is_option: row.get::<i64>(1)? != 0,
+            symbol: row.get::<String>(2)?.to_uppercase(),
+            day,
+            pnl: money::to_f64(money::row_decimal(&row, 4)),
+            reviewed: row.get::<i64>(5)? != 0,
+            has_mistake: row.get::<i64>(6)? != 0,
+            rating: row.get(7)?,
+        });
+    }
+    Ok(trades)
+}
+
+/// Share of playbook rule checks followed on trades closed in `year`
+async fn rule_compliance(conn: &Connection, periods: &PeriodDefinition, year: i32) -> Result<Option<f64>> {
+    let stock_day = periods.trading_date_sql("s.exit_date");
+    let option_day = periods.trading_date_sql("o.exit_date");
+    let sql = format!(
+        "SELECT COUNT(*), COALESCE(SUM(followed), 0) FROM (
+            SELECT CASE WHEN c.is_followed IN (1, '1', 'true') THEN 1 ELSE 0 END AS followed
+            FROM stock_trade_rule_compliance c JOIN stocks s ON s.id = c.stock_trade_id
+            WHERE s.is_deleted = 0 AND s.exit_date IS NOT NULL AND strftime('%Y', date({stock_day})) = ?
+            UNION ALL
+            SELECT CASE WHEN c.is_followed IN (1, '1', 'true') THEN 1 ELSE 0 END
+            FROM option_trade_rule_compliance c JOIN options o ON o.id = c.option_trade_id
+            WHERE o.is_deleted = 0 AND o.exit_date IS NOT NULL AND strftime('%Y', date({option_day})) = ?
+        )"
+    );
+    let year = format!("{:04}", year);
+    let mut rows = conn.prepare(&sql).await?.query(params![year.clone(), year]).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let (checks, followed): (i64, i64) = (row.get(0)?, row.get(1)?);
+    Ok(pct(followed.max(0) as usize, checks.max(0) as usize))
+}
+
+/// Greedy word wrap to at most `width` characters per line
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines().filter(|p| !p.trim().is_empty()) {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+fn metric_label(metric: &str) -> &str {
+    match metric {
+        "win_rate" => "Win rate",
+        "profit_factor" => "Profit factor",
+        "expectancy" => "Expectancy",
+        "average_loss" => "Average loss",
+        "mistake_rate" => "Trades with mistakes",
+        "reviewed_rate" => "Trades reviewed",
+        other => other,
+    }
+}
+
+fn metric_value(metric: &str, value: f64) -> String {
+    match metric {
+        "win_rate" | "mistake_rate" | "reviewed_rate" => format!("{:.1}%", value),
+        "profit_factor" => format!("{:.2}", value),
+        _ => money_text(value),
+    }
+}
+
+fn render(summary: &YearReviewSummary, narrative: Option<&str>, account: Option<&str>) -> Vec<u8> {
+    let mut page = Page::new();
+    let title = format!("{} Year in Review", summary.year);
+    let dash = || "-".to_string();
+
+    let top = PAGE_HEIGHT - MARGIN;
+    page.text(MARGIN, top - 20.0, 20.0, true, Rgb::BLACK, &title);
+    let subtitle = match account {
+        Some(name) => format!("{} - generated {}", name, Utc::now().format("%Y-%m-%d")),
+        None => format!("Generated {}", Utc::now().format("%Y-%m-%d")),
+    };
+    page.text(MARGIN, top - 38.0, 10.0, false, Rgb::GREY, &subtitle);
+
+    // Headline and stats
+    let pnl_color = if summary.net_pnl < 0.0 { RED } else { GREEN };
+    page.text(MARGIN, top - 72.0, 10.0, false, Rgb::GREY, "Net P&L");
+    page.text(MARGIN, top - 94.0, 22.0, true, pnl_color, &money_text(summary.net_pnl));
+    let entry_text = |e: &Option<(String, f64)>| e.as_ref().map(|(k, v)| format!("{} ({})", money_text(*v), k)).unwrap_or_else(dash);
+    let stats = [
+        ("Trades closed", summary.trades.to_string()),
+        ("Win rate", pct_text(summary.win_rate)),
+        ("Profit factor", summary.profit_factor.map(|p| format!("{:.2}", p)).unwrap_or_else(dash)),
+        ("Expectancy", summary.expectancy.map(money_text).unwrap_or_else(dash)),
+        ("Trading days", summary.trading_days.to_string()),
+        ("Best month", entry_text(&summary.best_month)),
+        ("Worst month", entry_text(&summary.worst_month)),
+        ("Best symbol", entry_text(&summary.best_symbol)),
+    ];
+    for (i, (label, value)) in stats.iter().enumerate() {
+        let x = 210.0 + (i / 4) as f64 * 190.0;
+        let y = top - 66.0 - (i % 4) as f64 * 16.0;
+        page.text(x, y, 9.0, false, Rgb::GREY, label);
+        page.text(x + 78.0, y, 9.0, true, Rgb::BLACK, value);
+    }
+
+    // P&L by month
+    let chart_top = top - 150.0;
+    page.text(MARGIN, chart_top, 12.0, true, Rgb::BLACK, "P&L by month");
+    let monthly: BTreeMap<&str, f64> = summary.monthly_pnl.iter().map(|(m, p)| (m.as_str(), *p)).collect();
+    let values: Vec<f64> = (1..=12).map(|m| monthly.get(format!("{:04}-{:02}", summary.year, m).as_str()).copied().unwrap_or(0.0)).collect();
+    let (chart_y, chart_h) = (chart_top - 130.0, 110.0);
+    let low = values.iter().copied().fold(0.0_f64, f64::min);
+    let high = values.iter().copied().fold(0.0_f64, f64::max);
+    let span = if high - low > 0.0 { high - low } else { 1.0 };
+    let to_y = |v: f64| chart_y + (v - low) / span * chart_h;
+    let slot = (PAGE_WIDTH - 2.0 * MARGIN) / 12.0;
+    page.line(&[(MARGIN, to_y(0.0)), (PAGE_WIDTH - MARGIN, to_y(0.0))], 0.5, Rgb::GREY);
+    for (i, value) in values.iter().enumerate() {
+        let x = MARGIN + i as f64 * slot + 6.0;
+        let (y0, y1) = (to_y(0.0), to_y(*value));
+        if (y1 - y0).abs() > 0.0 {
+            page.fill_rect(x, y0.min(y1), slot - 12.0, (y1 - y0).abs(), if *value < 0.0 { RED } else { GREEN });
+        }
+        let month = NaiveDate::from_ymd_opt(summary.year, i as u32 + 1, 1).map(|d| d.format("%b").to_string()).unwrap_or_default();
+        page.text(x + 4.0, chart_y - 14.0, 7.0, false, Rgb::GREY, &month);
+    }
+
+    // Best and worst trades
+    let trades_top = chart_y - 44.0;
+    for (x, heading, trades) in [(MARGIN, "Best trades", &summary.best_trades), (330.0, "Worst trades", &summary.worst_trades)] {
+        page.text(x, trades_top, 12.0, true, Rgb::BLACK, heading);
+        if trades.is_empty() {
+            page.text(x, trades_top - 18.0, 9.0, false, Rgb::GREY, "None");
+        }
+        for (i, trade) in trades.iter().enumerate() {
+            let y = trades_top - 18.0 - i as f64 * 15.0;
+            page.text(x, y, 9.0, false, Rgb::GREY, &format!("{} {} ({})", trade.closed_on, trade.symbol, trade.kind));
+            page.text(x + 170.0, y, 9.0, true, if trade.pnl < 0.0 { RED } else { GREEN }, &money_text(trade.pnl));
+        }
+    }
+
+    // Most improved and discipline
+    let lower_top = trades_top - 84.0;
+    page.text(MARGIN, lower_top, 12.0, true, Rgb::BLACK, "Most improved (H1 to H2)");
+    if summary.most_improved.is_empty() {
+        page.text(MARGIN, lower_top - 18.0, 9.0, false, Rgb::GREY, "Not enough trades in both halves to compare");
+    }
+    for (i, change) in summary.most_improved.iter().take(5).enumerate() {
+        let y = lower_top - 18.0 - i as f64 * 15.0;
+        page.text(MARGIN, y, 9.0, false, Rgb::GREY, metric_label(&change.metric));
+        page.text(
+            MARGIN + 110.0,
+            y,
+            9.0,
+            true,
+            Rgb::BLACK,
+            &format!("{} -> {}", metric_value(&change.metric, change.first_half), metric_value(&change.metric, change.second_half)),
+        );
+    }
+    let discipline = &summary.discipline;
+    page.text(330.0, lower_top, 12.0, true, Rgb::BLACK, "Discipline");
+    let discipline_rows = [
+        ("Trades reviewed", pct_text(discipline.reviewed_pct)),
+        ("Trades with mistakes", pct_text(discipline.mistake_pct)),
+        ("Rules followed", pct_text(discipline.rule_compliance_pct)),
+        ("Average rating", discipline.avg_rating.map(|r| format!("{:.1} / 5", r)).unwrap_or_else(dash)),
+        ("Longest win streak", discipline.longest_win_streak.to_string()),
+        ("Longest losing streak", discipline.longest_loss_streak.to_string()),
+    ];
+    for (i, (label, value)) in discipline_rows.iter().enumerate() {
+        let y = lower_top - 18.0 - i as f64 * 15.0;
+        page.text(330.0, y, 9.0, false, Rgb::GREY, label);
+        page.text(450.0, y, 9.0, true, Rgb::BLACK, value);
+    }
+
+    // Narrative
+    let narrative_top = lower_top - 128.0;
+    page.text(MARGIN, narrative_top, 12.0, true, Rgb::BLACK, "Your year");
+    let mut lines = wrap(narrative.unwrap_or("The written summary wasn't available when this review was generated."), NARRATIVE_WRAP_CHARS);
+    if lines.len() > MAX_NARRATIVE_LINES {
+        lines.truncate(MAX_NARRATIVE_LINES);
+        if let Some(last) = lines.last_mut() {
+            last.push_str(" ...");
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        page.text(MARGIN, narrative_top - 18.0 - i as f64 * 11.0, 9.0, false, Rgb::BLACK, line);
+    }
+
+    page.text(MARGIN, MARGIN - 16.0, 7.0, false, Rgb::GREY, "Realized P&L from journal entries, net of recorded commissions. Not a broker statement.");
+    page.into_pdf(&title)
+}
+
+fn row_to_review(row: &libsql::Row) -> Result<YearReview> {
+    Ok(YearReview {
+        id: row.get(0)?,
+        year: row.get::<i64>(1)? as i32,
+        object_path: row.get(2)?,
+        size_bytes: row.get(3)?,
+        summary: serde_json::from_str(&row.get::<String>(4)?).unwrap_or_default(),
+        narrative: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub async fn list_reviews(conn: &Connection) -> Result<Vec<YearReview>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {REVIEW_COLUMNS} FROM year_reviews ORDER BY year DESC"))
+        .await?
+        .query(params![])
+        .await?;
+    let mut reviews = Vec::new();
+    while let Some(row) = rows.next().await? {
+        reviews.push(row_to_review(&row)?);
+    }
+    Ok(reviews)
+}
+
+pub async fn review_for_year(conn: &Connection, year: i32) -> Result<Option<YearReview>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {REVIEW_COLUMNS} FROM year_reviews WHERE year = ?"))
+        .await?
+        .query(params![year as i64])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_review(&row)?)),
+        None => Ok(None),
+    }
+}
+
+/// The stored PDF
+pub async fn download(storage: &ImageUploadService, review: &YearReview) -> Result<Vec<u8>> {
+    storage.download_file(&review.object_path).await
+}
+
+/// `{year}~{nonce}~{user_id}`, the id signed into share tokens
+fn share_id(user_id: &str, year: i32, nonce: &str) -> String {
+    format!("{}~{}~{}", year, nonce, user_id)
+}
+
+fn parse_share_id(share_id: &str) -> Option<(String, i32, String)> {
+    let mut parts = share_id.splitn(3, '~');
+    let year = parts.next()?.parse().ok()?;
+    let nonce = parts.next()?.to_string();
+    let user_id = parts.next().filter(|u| !u.is_empty())?.to_string();
+    Some((user_id, year, nonce))
+}
+
+/// Public link to a review, valid for `days`; None when the year has no review
+pub async fn create_share_link(conn: &Connection, config: &ReportSharingConfig, user_id: &str, year: i32, days: i64) -> Result<Option<String>> {
+    if !(1..=MAX_SHARE_DAYS).contains(&days) {
+        anyhow::bail!("expires_in_days must be between 1 and {}", MAX_SHARE_DAYS);
+    }
+    // Links share the review's nonce; an existing one is kept so earlier links stay valid
+    let mut rows = conn.prepare("SELECT share_nonce FROM year_reviews WHERE year = ?").await?.query(params![year as i64]).await?;
+    let Some(row) = rows.next().await? else { return Ok(None) };
+    let nonce = match row.get::<Option<String>>(0)? {
+        Some(nonce) => nonce,
+        None => {
+            let nonce = uuid::Uuid::new_v4().simple().to_string();
+            conn.execute("UPDATE year_reviews SET share_nonce = ? WHERE year = ?", params![nonce.clone(), year as i64]).await?;
+            nonce
+        }
+    };
+    let token = sign_token(&config.signing_secret, &share_id(user_id, year, &nonce), Utc::now() + Duration::days(days))
+        .ok_or_else(|| anyhow!("Invalid share signing secret"))?;
+    Ok(Some(format!("{}/shared/year-review/{}", config.public_base_url, token)))
+}
+
+/// Invalidate every public link to a review
+pub async fn revoke_share_links(conn: &Connection, year: i32) -> Result<bool> {
+    let updated = conn
+        .execute("UPDATE year_reviews SET share_nonce = NULL WHERE year = ? AND share_nonce IS NOT NULL", params![year as i64])
+        .await?;
+    Ok(updated > 0)
+}
+
+/// User and year of a valid share token; the nonce is checked by `shared_review`
+pub fn resolve_share_token(config: &ReportSharingConfig, token: &str) -> Option<(String, i32, String)> {
+    parse_share_id(&verify_token(&config.signing_secret, token, Utc::now())?)
+}
+
+/// The review a share link points at, if the link hasn't been revoked
+pub async fn shared_review(conn: &Connection, year: i32, nonce: &str) -> Result<Option<YearReview>> {
+    let mut rows = conn
+        .prepare(&format!("SELECT {REVIEW_COLUMNS} FROM year_reviews WHERE year = ? AND share_nonce = ?"))
+        .await?
+        .query(params![year as i64, nonce])
+        .await?;
+    match rows.next().await? {
+        Some(row) => Ok(Some(row_to_review(&row)?)),
+        None => Ok(None),
+    }
+}
+
+pub struct YearReviewService {
+    openrouter_client: Arc<OpenRouterClient>,
+}
+
+impl YearReviewService {
+    pub fn new(openrouter_client: Arc<OpenRouterClient>) -> Self {
+        Self { openrouter_client }
+    }
+
+    /// The AI-written narrative; None when the provider fails, so the review still ships
+    async fn narrative(&self, summary: &YearReviewSummary) -> Option<String> {
+        let figures = serde_json::to_string(summary).ok()?;
+        let messages = vec![
+            ChatMessage { role: MessageRole::System, content: NARRATIVE_PROMPT.to_string() },
+            ChatMessage { role: MessageRole::User, content: format!("Year in review figures (USD):\n{}", figures) },
+        ];
+        match self.openrouter_client.generate_chat(messages).await {
+            Ok(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Year review narrative unavailable for {}: {}", summary.year, e);
+                None
+            }
+        }
+    }
+
+    /// Build the review for `year`, store the PDF and record it. With `skip_empty`, a year
+    /// without closed trades gets no review and None is returned.
+    pub async fn generate(&self, conn: &Connection, storage: &ImageUploadService, user_id: &str, year: i32, skip_empty: bool) -> Result<Option<YearReview>> {
+        let periods = PeriodDefinition::load(conn).await;
+        let trades = closed_trades(conn, &periods, year).await?;
+        if skip_empty && trades.is_empty() {
+            return Ok(None);
+        }
+        let summary = summarize(year, &trades, rule_compliance(conn, &periods, year).await?);
+        let narrative = if trades.is_empty() { None } else { self.narrative(&summary).await };
+
+        let account = crate::service::statements::account_name(conn).await;
+        let bytes = render(&summary, narrative.as_deref(), account.as_deref());
+        let object_path = format!("{}/year-reviews/{}.pdf", user_id, year);
+        storage.put_object(&object_path, &bytes, "application/pdf").await?;
+
+        // A regenerated review starts without share links
+        conn.execute(
+            "INSERT INTO year_reviews (id, year, object_path, size_bytes, summary, narrative, share_nonce, created_at) VALUES (?, ?, ?, ?, ?, ?, NULL, ?)
+             ON CONFLICT(year) DO UPDATE SET object_path = excluded.object_path, size_bytes = excluded.size_bytes, summary = excluded.summary,
+                 narrative = excluded.narrative, share_nonce = NULL, created_at = excluded.created_at",
+            params![
+                uuid::Uuid::new_v4().to_string(),
+                year as i64,
+                object_path,
+                bytes.len() as i64,
+                serde_json::to_string(&summary)?,
+                narrative,
+                Utc::now().to_rfc3339()
+            ],
+        )
+        .await?;
+        review_for_year(conn, year).await?.ok_or_else(|| anyhow!("Year review missing after saving")).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: i64, month: u32, day: u32, symbol: &str, pnl: f64, mistake: bool) -> ClosedTrade {
+        ClosedTrade {
+            id,
+            is_option: false,
+            symbol: symbol.to_string(),
+            day: NaiveDate::from_ymd_opt(2025, month, day).unwrap(),
+            pnl,
+            reviewed: month > 6,
+            has_mistake: mistake,
+            rating: Some(if pnl > 0.0 { 4 } else { 2 }),
+        }
+    }
+
+    #[test]
+    fn test_summary_and_most_improved() {
+        // First half: 2 wins, 3 losses, all with mistakes. Second half: 4 wins, 1 loss, clean.
+        let mut trades = vec![
+            trade(1, 1, 10, "AAPL", 200.0, true),
+            trade(2, 2, 3, "TSLA", -300.0, true),
+            trade(3, 3, 5, "TSLA", -100.0, true),
+            trade(4, 4, 7, "AAPL", 100.0, true),
+            trade(5, 5, 9, "NVDA", -120.0, true),
+        ];
+        trades.extend([
+            trade(6, 7, 1, "NVDA", 150.0, false),
+            trade(7, 8, 1, "NVDA", 250.0, false),
+            trade(8, 9, 1, "AAPL", 50.0, false),
+            trade(9, 10, 1, "AAPL", -50.0, false),
+            trade(10, 11, 2, "NVDA", 400.0, false),
+        ]);
+        let summary = summarize(2025, &trades, Some(80.0));
+
+        assert_eq!(summary.net_pnl, 580.0);
+        assert_eq!((summary.trades, summary.winners, summary.losers), (10, 6, 4));
+        assert_eq!(summary.best_month, Some(("2025-11".to_string(), 400.0)));
+        assert_eq!(summary.worst_month, Some(("2025-02".to_string(), -300.0)));
+        assert_eq!(summary.best_symbol, Some(("NVDA".to_string(), 680.0)));
+        assert_eq!(summary.best_trades.iter().map(|t| t.id).collect::<Vec<_>>(), [10, 7, 1]);
+        assert_eq!(summary.worst_trades.iter().map(|t| t.id).collect::<Vec<_>>(), [2, 5, 3]);
+        assert_eq!((summary.discipline.longest_win_streak, summary.discipline.longest_loss_streak), (3, 2));
+        assert_eq!(summary.discipline.mistake_pct, Some(50.0));
+        assert_eq!(summary.discipline.rule_compliance_pct, Some(80.0));
+
+        let metrics: Vec<&str> = summary.most_improved.iter().map(|m| m.metric.as_str()).collect();
+        assert!(metrics.contains(&"win_rate") && metrics.contains(&"expectancy") && metrics.contains(&"mistake_rate"));
+        // Reviews went from none to all, which has no relative improvement and sorts last
+        assert_eq!(metrics.last(), Some(&"reviewed_rate"));
+        let win_rate = summary.most_improved.iter().find(|m| m.metric == "win_rate").unwrap();
+        assert_eq!((win_rate.first_half, win_rate.second_half, win_rate.improvement_pct), (40.0, 80.0, Some(100.0)));
+
+        assert!(summarize(2025, &trades[..6], None).most_improved.is_empty());
+    }
+
+    #[test]
+    fn test_wrap_and_share_ids() {
+        assert_eq!(wrap("one two three four\n\nfive", 9), ["one two", "three", "four", "five"]);
+        assert_eq!(wrap("", 10), Vec::<String>::new());
+
+        let id = share_id("0b6c-user", 2025, "abc123");
+        assert_eq!(parse_share_id(&id), Some(("0b6c-user".to_string(), 2025, "abc123".to_string())));
+        assert_eq!(parse_share_id("2025~abc"), None);
+        assert_eq!(default_year(NaiveDate::from_ymd_opt(2026, 1, 3).unwrap()), 2025);
+    }
+}
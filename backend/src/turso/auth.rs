@@ -220,6 +220,10 @@ pub fn get_user_id(claims: ClerkClaims) -> Result<String, anyhow::Error> {
 
 /// Validate Supabase JWT token for Actix-Web (no caching)
 pub async fn validate_supabase_jwt_token(token: &str, config: &SupabaseConfig) -> Result<SupabaseClaims, AuthError> {
+    // The published sandbox token is signed by this server, not Supabase
+    if let Some(claims) = crate::service::sandbox::claims_for_token(token) {
+        return Ok(claims);
+    }
     log::debug!("Validating JWT token with Supabase (no caching)");
     let supabase_auth = SupabaseAuth::new(config.clone());
     let claims = supabase_auth.validate_token(token).await?;
@@ -3,9 +3,14 @@ use libsql::{Connection, Database, Builder};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use log::{info, warn, error};
+use log::info;
+use crate::models::options::OptionTrade;
+use crate::models::stock::stocks::Stock;
+use crate::models::timestamps::normalize_trade_timestamps;
+use crate::service::analytics_engine::daily_aggregates::{rebuild_daily_aggregates, DailySource};
 
 use super::config::TursoConfig;
+use super::locks::{DistributedLock, SCHEMA_SYNC_JOB};
 use super::schema::{
     SchemaVersion, TableSchema, ColumnInfo,
     initialize_user_database_schema,
@@ -16,15 +21,27 @@ use super::schema::{
     get_current_tables,
     create_table,
     update_table_schema,
+    sync_table_schema,
+    plan_schema_sync,
+    prune_schema_backups,
+    drop_unlisted_tables,
+    rebuild_stale_constraints,
+    SchemaDiff,
     ensure_indexes,
     ensure_triggers,
 };
 
+/// Longest a schema sync may hold its lock before another instance can take over
+const SCHEMA_LOCK_TTL: std::time::Duration = std::time::Duration::from_secs(300);
+/// How long a login waits on a sync another instance is running
+const SCHEMA_LOCK_WAIT: std::time::Duration = std::time::Duration::from_secs(15);
+
 /// Turso client for managing user databases
 pub struct TursoClient {
     config: TursoConfig,
     registry_db: Database,
     http_client: Client,
+    locks: DistributedLock,
 }
 
 /// User database registry entry
@@ -92,6 +109,122 @@ impl TursoClient {
             "ALTER TABLE user_databases ADD COLUMN storage_used_bytes INTEGER DEFAULT 0",
             libsql::params![],
         ).await.ok(); // Ignore error if column already exists
+
+        // Plan tier for the cost and margin report
+        conn.execute(
+            "ALTER TABLE user_databases ADD COLUMN plan_tier TEXT DEFAULT 'free'",
+            libsql::params![],
+        ).await.ok();
+
+        // Telegram bot account linking (updates carry a chat id, not a user id)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS telegram_link_codes (code TEXT PRIMARY KEY, user_id TEXT NOT NULL, expires_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS telegram_links (chat_id INTEGER PRIMARY KEY, user_id TEXT NOT NULL, linked_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_telegram_links_user_id ON telegram_links(user_id)",
+            libsql::params![],
+        ).await.ok();
+
+        // Pending OAuth `state` of integration connect flows, checked on the callback
+        conn.execute(crate::service::integrations::oauth_state::OAUTH_STATES_TABLE_SQL, libsql::params![]).await.ok();
+
+        // Global AI feedback totals used by the model selector
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_quality_scores (model TEXT NOT NULL, prompt_key TEXT NOT NULL, positive INTEGER NOT NULL DEFAULT 0, negative INTEGER NOT NULL DEFAULT 0, updated_at TEXT NOT NULL, PRIMARY KEY (model, prompt_key))",
+            libsql::params![],
+        ).await.ok();
+
+        // Per-request stage timings for AI endpoints (admin latency report)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS ai_request_traces (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, endpoint TEXT NOT NULL, model TEXT, embedding_ms INTEGER, vector_search_ms INTEGER, prompt_build_ms INTEGER, provider_ms INTEGER, persistence_ms INTEGER, total_ms INTEGER NOT NULL, success INTEGER NOT NULL DEFAULT 1, created_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_ai_request_traces_created ON ai_request_traces(created_at, endpoint)",
+            libsql::params![],
+        ).await.ok();
+
+        // Vendor cost of each billable provider call, per user (admin cost report)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_cost_ledger (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, provider TEXT NOT NULL, operation TEXT NOT NULL, input_units INTEGER NOT NULL DEFAULT 0, output_units INTEGER NOT NULL DEFAULT 0, cost_usd REAL NOT NULL DEFAULT 0, created_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_provider_cost_ledger_created ON provider_cost_ledger(created_at, user_id)",
+            libsql::params![],
+        ).await.ok();
+
+        // Public report links (resolved without auth, so they live in the registry)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS report_shares (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, report_id TEXT NOT NULL, expires_at TEXT NOT NULL, revoked_at TEXT, view_count INTEGER NOT NULL DEFAULT 0, last_viewed_at TEXT, created_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_report_shares_user_report ON report_shares(user_id, report_id)",
+            libsql::params![],
+        ).await.ok();
+
+        // Per-user forwarding addresses for broker confirmation emails (inbound mail carries no user context)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inbound_email_addresses (token TEXT PRIMARY KEY, user_id TEXT NOT NULL UNIQUE, created_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+
+        // Tenants found by the dead tenant scan, waiting for confirmation before reclamation
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS dead_tenants (id TEXT PRIMARY KEY, db_name TEXT NOT NULL UNIQUE, user_id TEXT NOT NULL, reason TEXT NOT NULL, detected_at TEXT NOT NULL, reclaimed_at TEXT, last_error TEXT)",
+            libsql::params![],
+        ).await.ok();
+        
+        // Team workspaces span several user databases, so they live in the registry
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS organizations (id TEXT PRIMARY KEY, name TEXT NOT NULL, created_by TEXT NOT NULL, created_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS organization_members (organization_id TEXT NOT NULL, user_id TEXT NOT NULL, role TEXT NOT NULL DEFAULT 'member', display_name TEXT, share_named INTEGER NOT NULL DEFAULT 0, joined_at TEXT NOT NULL, PRIMARY KEY (organization_id, user_id))",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_organization_members_user_id ON organization_members(user_id)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS organization_invites (code TEXT PRIMARY KEY, organization_id TEXT NOT NULL, role TEXT NOT NULL, created_by TEXT NOT NULL, expires_at TEXT NOT NULL, accepted_by TEXT, accepted_at TEXT)",
+            libsql::params![],
+        ).await.ok();
+        // Playbooks and tags a lead shares; copied into member databases by provisioning
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS organization_playbooks (id TEXT PRIMARY KEY, organization_id TEXT NOT NULL, name TEXT NOT NULL, description TEXT, icon TEXT, emoji TEXT, color TEXT, rules TEXT NOT NULL DEFAULT '[]', updated_at TEXT NOT NULL)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_organization_playbooks_org ON organization_playbooks(organization_id)",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS organization_tags (id TEXT PRIMARY KEY, organization_id TEXT NOT NULL, category TEXT NOT NULL, name TEXT NOT NULL, color TEXT, description TEXT, updated_at TEXT NOT NULL, UNIQUE(organization_id, category, name))",
+            libsql::params![],
+        ).await.ok();
+
+        // Opt-in feature usage counts, keyed by a random id rather than the user id
+        conn.execute(
+            "ALTER TABLE user_databases ADD COLUMN telemetry_id TEXT",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS feature_usage_counts (telemetry_id TEXT NOT NULL, day TEXT NOT NULL, category TEXT NOT NULL, count INTEGER NOT NULL DEFAULT 0, PRIMARY KEY (telemetry_id, day, category))",
+            libsql::params![],
+        ).await.ok();
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_feature_usage_counts_day ON feature_usage_counts(day, category)",
+            libsql::params![],
+        ).await.ok();
         
         info!("Registry database migration completed");
 
@@ -99,9 +232,15 @@ impl TursoClient {
             config,
             registry_db,
             http_client,
+            locks: DistributedLock::from_env().await,
         })
     }
 
+    /// Per-tenant locks so instances don't run the same job for a user at once
+    pub fn locks(&self) -> &DistributedLock {
+        &self.locks
+    }
+
     /// Get a connection to the registry database
     pub async fn get_registry_connection(&self) -> Result<Connection> {
         self.registry_db
@@ -313,6 +452,79 @@ impl TursoClient {
         }
     }
 
+    /// List user IDs of all active user databases (used by scheduled jobs)
+    pub async fn list_active_user_ids(&self) -> Result<Vec<String>> {
+        let conn = self.get_registry_connection().await?;
+
+        let mut rows = conn
+            .prepare("SELECT user_id FROM user_databases WHERE is_active = 1")
+            .await
+            .context("Failed to prepare query")?
+            .query(libsql::params![])
+            .await
+            .context("Failed to execute query")?;
+
+        let mut user_ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            user_ids.push(row.get::<String>(0)?);
+        }
+        Ok(user_ids)
+    }
+
+    /// List `(user_id, db_name)` for every registry entry, active or not
+    pub async fn list_registered_databases(&self) -> Result<Vec<(String, String)>> {
+        let conn = self.get_registry_connection().await?;
+
+        let mut rows = conn
+            .prepare("SELECT user_id, db_name FROM user_databases")
+            .await
+            .context("Failed to prepare query")?
+            .query(libsql::params![])
+            .await
+            .context("Failed to execute query")?;
+
+        let mut entries = Vec::new();
+        while let Some(row) = rows.next().await? {
+            entries.push((row.get::<String>(0)?, row.get::<String>(1)?));
+        }
+        Ok(entries)
+    }
+
+    /// List the names of all databases in the Turso organization
+    pub async fn list_organization_databases(&self) -> Result<Vec<String>> {
+        let url = format!("https://api.turso.tech/v1/organizations/{}/databases", self.config.turso_org);
+
+        let response = self.http_client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", self.config.turso_api_token))
+            .send()
+            .await
+            .context("Failed to list databases")?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Failed to list databases: {}", error_text);
+        }
+
+        #[derive(Deserialize)]
+        struct ListedDb {
+            #[serde(rename = "Name")]
+            name: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ListDbResponse {
+            databases: Vec<ListedDb>,
+        }
+
+        let list: ListDbResponse = response
+            .json()
+            .await
+            .context("Failed to parse database list response")?;
+
+        Ok(list.databases.into_iter().map(|db| db.name).collect())
+    }
+
     /// Get user database connection
     pub async fn get_user_database_connection(&self, user_id: &str) -> Result<Option<Connection>> {
         if let Some(entry) = self.get_user_database(user_id).await? {
@@ -373,6 +585,16 @@ impl TursoClient {
         ).await
         .context("Failed to remove user database entry from registry")?;
 
+        // Links to reports that no longer exist
+        conn.execute(
+            "DELETE FROM report_shares WHERE user_id = ?",
+            libsql::params![user_id],
+        ).await.ok();
+
+        // Team memberships, and organizations this leaves empty
+        crate::service::organizations::OrganizationService::new(&conn).remove_user(user_id).await.ok();
+        crate::service::organization_library::OrganizationLibrary::new(&conn).remove_orphaned().await.ok();
+
         info!("Successfully removed user database entry from registry: {}", user_id);
         Ok(())
     }
@@ -425,8 +647,20 @@ impl TursoClient {
         }
     }
 
-    /// Synchronize user database schema with current application schema
+    /// Synchronize user database schema with current application schema. If another
+    /// instance is already syncing this user, wait for it; the version check then finds
+    /// the schema current.
     pub async fn sync_user_database_schema(&self, user_id: &str) -> Result<()> {
+        let guard = self.locks
+            .acquire_within(SCHEMA_SYNC_JOB, user_id, SCHEMA_LOCK_TTL, SCHEMA_LOCK_WAIT)
+            .await
+            .with_context(|| format!("Schema sync already in progress for user: {}", user_id))?;
+        let result = self.sync_user_database_schema_locked(user_id).await;
+        guard.release().await.ok();
+        result
+    }
+
+    async fn sync_user_database_schema_locked(&self, user_id: &str) -> Result<()> {
         info!("Starting schema synchronization for user: {}", user_id);
 
         if let Some(conn) = self.get_user_database_connection(user_id).await? {
@@ -438,7 +672,7 @@ impl TursoClient {
             if current_version.is_none() {
                 info!("No schema version found, initializing with current schema");
                 initialize_schema_version_table(&conn).await?;
-                self.apply_schema_migrations(&conn, &expected_schema).await?;
+                self.apply_schema_migrations(&conn, &expected_schema, false).await?;
                 update_schema_version(&conn, &expected_version).await?;
                 return Ok(());
             }
@@ -451,7 +685,7 @@ impl TursoClient {
                       current_version.version, expected_version.version);
 
                 // Apply schema migrations
-                self.apply_schema_migrations(&conn, &expected_schema).await?;
+                self.apply_schema_migrations(&conn, &expected_schema, false).await?;
                 update_schema_version(&conn, &expected_version).await?;
 
                 info!("Schema synchronized successfully for user: {}", user_id);
@@ -465,6 +699,40 @@ impl TursoClient {
         Ok(())
     }
 
+    /// Dry run: what a sync would change in this user's database, with row counts
+    pub async fn plan_user_database_schema(&self, user_id: &str) -> Result<SchemaDiff> {
+        let conn = self
+            .get_user_database_connection(user_id)
+            .await?
+            .with_context(|| format!("Could not get database connection for user: {}", user_id))?;
+        plan_schema_sync(&conn, &get_expected_schema()).await
+    }
+
+    /// Admin sync: apply the expected schema whatever version is recorded. With `force`,
+    /// obsolete tables and columns that still hold data are dropped too (a snapshot is kept).
+    /// Returns the diff taken before anything changed.
+    pub async fn migrate_user_database_schema(&self, user_id: &str, force: bool) -> Result<SchemaDiff> {
+        let conn = self
+            .get_user_database_connection(user_id)
+            .await?
+            .with_context(|| format!("Could not get database connection for user: {}", user_id))?;
+        let guard = self.locks
+            .try_acquire(SCHEMA_SYNC_JOB, user_id, SCHEMA_LOCK_TTL)
+            .await
+            .with_context(|| format!("Schema sync already in progress for user: {}", user_id))?;
+        let expected_schema = get_expected_schema();
+        let diff = plan_schema_sync(&conn, &expected_schema).await?;
+        info!(
+            "Admin schema migration for user {} (force={}): {} tables to create, {} to drop, {} to change",
+            user_id, force, diff.created_tables.len(), diff.dropped_tables.len(), diff.changed_tables.len()
+        );
+        initialize_schema_version_table(&conn).await?;
+        self.apply_schema_migrations(&conn, &expected_schema, force).await?;
+        update_schema_version(&conn, &get_current_schema_version()).await?;
+        guard.release().await.ok();
+        Ok(diff)
+    }
+
     /// Initialize the schema version table
     #[allow(dead_code)]
     async fn initialize_schema_version_table(&self, conn: &Connection) -> Result<()> { initialize_schema_version_table(conn).await }
@@ -475,92 +743,67 @@ impl TursoClient {
 
     /// Apply schema migrations to bring database up to current schema
     /// This function makes schema.rs the source of truth - it will drop any tables
-    /// that exist in the database but are not in the expected schema
+    /// that exist in the database but are not in the expected schema (after a snapshot)
+    /// Obsolete tables and columns that still hold data are only dropped when `force` is set
     #[allow(dead_code)]
-    async fn apply_schema_migrations(&self, conn: &Connection, expected_schema: &[TableSchema]) -> Result<()> {
+    async fn apply_schema_migrations(&self, conn: &Connection, expected_schema: &[TableSchema], force: bool) -> Result<()> {
         info!("Applying schema migrations");
-        
-        // Get list of expected table names (source of truth)
-        let expected_table_names: std::collections::HashSet<String> = expected_schema
-            .iter()
-            .map(|s| s.name.clone())
-            .collect();
-        
-        // Also include system tables that should never be dropped
-        let protected_tables: std::collections::HashSet<String> = [
-            "schema_version".to_string(),
-            "sqlite_sequence".to_string(), // SQLite internal table
-        ].iter().cloned().collect();
+        prune_schema_backups(conn).await?;
         
         // Get current tables in database
         let current_tables = get_current_tables(conn).await?;
         
-        // Drop tables that exist in database but are not in expected schema
-        // Temporarily disable foreign key constraints to allow dropping tables with dependencies
-        conn.execute("PRAGMA foreign_keys = OFF", libsql::params![]).await?;
-        
-        for table_name in &current_tables {
-            if !expected_table_names.contains(table_name) && !protected_tables.contains(table_name) {
-                info!("Dropping table '{}' - not in expected schema (schema.rs is source of truth)", table_name);
-                
-                // Drop all indexes for this table first
-                let mut index_rows = conn
-                    .prepare("SELECT name FROM sqlite_master WHERE type='index' AND tbl_name=? AND name NOT LIKE 'sqlite_autoindex_%'")
-                    .await?
-                    .query(libsql::params![table_name.clone()])
-                    .await?;
-                
-                while let Some(index_row) = index_rows.next().await? {
-                    let index_name: String = index_row.get(0)?;
-                    info!("Dropping index '{}' for table '{}'", index_name, table_name);
-                    if let Err(e) = conn.execute(&format!("DROP INDEX IF EXISTS {}", index_name), libsql::params![]).await {
-                        warn!("Failed to drop index '{}': {}", index_name, e);
-                    }
-                }
-                
-                // Drop all triggers for this table
-                let mut trigger_rows = conn
-                    .prepare("SELECT name FROM sqlite_master WHERE type='trigger' AND tbl_name=?")
-                    .await?
-                    .query(libsql::params![table_name.clone()])
-                    .await?;
-                
-                while let Some(trigger_row) = trigger_rows.next().await? {
-                    let trigger_name: String = trigger_row.get(0)?;
-                    info!("Dropping trigger '{}' for table '{}'", trigger_name, table_name);
-                    if let Err(e) = conn.execute(&format!("DROP TRIGGER IF EXISTS {}", trigger_name), libsql::params![]).await {
-                        warn!("Failed to drop trigger '{}': {}", trigger_name, e);
-                    }
-                }
-                
-                // Finally, drop the table
-                if let Err(e) = conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), libsql::params![]).await {
-                    error!("Failed to drop table '{}': {}", table_name, e);
-                } else {
-                    info!("Successfully dropped table '{}'", table_name);
-                }
-            }
-        }
-        
-        // Re-enable foreign key constraints
-        conn.execute("PRAGMA foreign_keys = ON", libsql::params![]).await?;
+        // Drop tables that exist in database but are not in expected schema; each is
+        // snapshotted first, and ones still holding rows are only dropped when forced
+        drop_unlisted_tables(conn, expected_schema, force).await?;
         
         // Create or update expected tables
+        let mut created_tables = Vec::new();
         for table_schema in expected_schema {
             if !current_tables.contains(&table_schema.name) {
                 info!("Creating missing table: {}", table_schema.name);
                 create_table(conn, table_schema).await?;
+                created_tables.push(table_schema.name.clone());
             } else {
                 info!("Checking table schema for: {}", table_schema.name);
-                update_table_schema(conn, table_schema).await?;
+                sync_table_schema(conn, table_schema, force).await?;
             }
         }
         
+        // CHECK constraints can't be altered in place; tables created with constraints that
+        // have since changed are rebuilt (their indexes come back just below)
+        rebuild_stale_constraints(conn).await?;
+
         // Ensure indexes and triggers for all expected tables
         for table_schema in expected_schema {
             ensure_indexes(conn, table_schema).await?;
             ensure_triggers(conn, table_schema).await?;
         }
+
+        // Older rows stored entry/exit times with mixed offsets and precision; shifting them to
+        // UTC can move a trade to another day, so aggregates are rebuilt when anything changed
+        let normalized_timestamps = normalize_trade_timestamps(conn).await?;
+
+        // Option trades logged before the premium side was stored; the sold ones get their
+        // P&L sign flipped below, and the aggregate triggers pick up the change
+        let short_options = OptionTrade::classify_premium_side(conn).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+        if short_options > 0 {
+            info!("Classified {} option trades as sold premium", short_options);
+        }
+
+        // Fill realized_pnl for rows written before the column existed
+        let recalculated = Stock::recalculate_realized_pnl(conn).await.map_err(|e| anyhow::anyhow!("{}", e))?
+            + OptionTrade::recalculate_realized_pnl(conn).await.map_err(|e| anyhow::anyhow!("{}", e))?;
+        if recalculated > 0 {
+            info!("Recalculated realized P&L for {} trades", recalculated);
+        }
+
+        // New aggregate tables start empty; backfill them from existing trades
+        if normalized_timestamps > 0
+            || DailySource::ALL.iter().any(|source| created_tables.iter().any(|t| t == source.aggregate_table()))
+        {
+            rebuild_daily_aggregates(conn).await?;
+        }
         
         info!("Schema migrations completed - database now matches schema.rs (source of truth)");
         Ok(())
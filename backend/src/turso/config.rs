@@ -29,6 +29,24 @@ pub struct TursoConfig {
     pub web_push: WebPushConfig,
     /// SnapTrade service URL
     pub snaptrade_service_url: String,
+    /// Shared secret SnapTrade includes as `webhookSecret` in webhook bodies (webhooks rejected when unset)
+    pub snaptrade_webhook_secret: Option<String>,
+    /// Base64 AES-256 key for the encrypted secrets store (integration tokens)
+    pub secrets_encryption_key: Option<String>,
+    /// Notion OAuth configuration (integration disabled when unset)
+    pub notion: Option<NotionConfig>,
+    /// Telegram bot configuration (bot disabled when unset)
+    pub telegram: Option<TelegramConfig>,
+    /// Transactional email (Resend) configuration (email delivery disabled when unset)
+    pub email: Option<EmailConfig>,
+    /// Public report links (sharing disabled when unset)
+    pub report_sharing: Option<ReportSharingConfig>,
+    /// Inbound broker confirmation emails (forwarding disabled when unset)
+    pub inbound_email: Option<InboundEmailConfig>,
+    /// Background task monitoring and ops alerting
+    pub task_monitor: TaskMonitorConfig,
+    /// Public API sandbox tenant (disabled when unset)
+    pub sandbox: Option<SandboxConfig>,
 }
 
 /// Supabase authentication configuration
@@ -88,6 +106,15 @@ impl TursoConfig {
             web_push: web_push_config,
             snaptrade_service_url: env::var("SNAPTRADE_SERVICE_URL")
                 .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+            snaptrade_webhook_secret: env::var("SNAPTRADE_WEBHOOK_SECRET").ok().filter(|s| !s.is_empty()),
+            secrets_encryption_key: env::var("SECRETS_ENCRYPTION_KEY").ok(),
+            notion: NotionConfig::from_env(),
+            telegram: TelegramConfig::from_env(),
+            email: EmailConfig::from_env(),
+            report_sharing: ReportSharingConfig::from_env(),
+            inbound_email: InboundEmailConfig::from_env(),
+            task_monitor: TaskMonitorConfig::from_env(),
+            sandbox: SandboxConfig::from_env(),
         })
     }
 }
@@ -178,6 +205,150 @@ impl WebPushConfig {
     }
 }
 
+/// Notion public integration (OAuth) configuration
+#[derive(Debug, Clone)]
+pub struct NotionConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_uri: String,
+}
+
+impl NotionConfig {
+    /// Load Notion OAuth configuration; returns None if the integration isn't configured
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            client_id: env::var("NOTION_CLIENT_ID").ok()?,
+            client_secret: env::var("NOTION_CLIENT_SECRET").ok()?,
+            redirect_uri: env::var("NOTION_REDIRECT_URI")
+                .unwrap_or_else(|_| "http://localhost:3000/app/settings/integrations/notion".to_string()),
+        })
+    }
+}
+
+/// Telegram bot configuration
+#[derive(Debug, Clone)]
+pub struct TelegramConfig {
+    pub bot_token: String,
+    /// Sent back by Telegram in `X-Telegram-Bot-Api-Secret-Token` on every webhook call.
+    /// The webhook answers 404 until it is set.
+    pub webhook_secret: Option<String>,
+}
+
+impl TelegramConfig {
+    /// Load Telegram bot configuration; returns None if no bot token is set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            bot_token: env::var("TELEGRAM_BOT_TOKEN").ok().filter(|t| !t.is_empty())?,
+            webhook_secret: env::var("TELEGRAM_WEBHOOK_SECRET").ok().filter(|s| !s.is_empty()),
+        })
+    }
+}
+
+/// Transactional email configuration (Resend API)
+#[derive(Debug, Clone)]
+pub struct EmailConfig {
+    pub resend_api_key: String,
+    pub from_address: String,
+}
+
+impl EmailConfig {
+    /// Load email configuration; returns None if no API key is set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            resend_api_key: env::var("RESEND_API_KEY").ok().filter(|k| !k.is_empty())?,
+            from_address: env::var("EMAIL_FROM")
+                .unwrap_or_else(|_| "Tradstry <noreply@tradstry.com>".to_string()),
+        })
+    }
+}
+
+/// Signing configuration for public report links
+#[derive(Debug, Clone)]
+pub struct ReportSharingConfig {
+    /// HMAC key used to sign share tokens
+    pub signing_secret: String,
+    /// Base URL the public links point at, e.g. https://api.tradstry.com
+    pub public_base_url: String,
+}
+
+impl ReportSharingConfig {
+    /// Load sharing configuration; returns None if no signing secret is set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            signing_secret: env::var("REPORT_SHARE_SECRET").ok().filter(|k| !k.is_empty())?,
+            public_base_url: env::var("PUBLIC_API_URL")
+                .unwrap_or_else(|_| "http://localhost:9000".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+        })
+    }
+}
+
+/// Inbound email (Mailgun route) configuration for forwarded broker confirmations
+#[derive(Debug, Clone)]
+pub struct InboundEmailConfig {
+    /// Domain of the forwarding addresses, e.g. inbox.tradstry.com
+    pub domain: String,
+    /// Mailgun webhook signing key used to verify inbound posts
+    pub signing_key: String,
+}
+
+impl InboundEmailConfig {
+    /// Load inbound email configuration; returns None unless both domain and signing key are set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            domain: env::var("INBOUND_EMAIL_DOMAIN").ok().filter(|d| !d.is_empty())?,
+            signing_key: env::var("MAILGUN_WEBHOOK_SIGNING_KEY").ok().filter(|k| !k.is_empty())?,
+        })
+    }
+}
+
+/// Thresholds for the background task monitor
+#[derive(Debug, Clone)]
+pub struct TaskMonitorConfig {
+    /// Ops webhook (Slack/Discord-compatible) for alerts; alerts are only logged when unset
+    pub ops_webhook_url: Option<String>,
+    /// Tasks in "processing" longer than this are expired
+    pub stuck_after_minutes: i64,
+    /// Failures across all users within the window that count as a spike
+    pub failure_spike_threshold: u64,
+    /// Look-back window for failure counts; schedule the monitor at the same interval
+    pub failure_window_minutes: i64,
+}
+
+impl TaskMonitorConfig {
+    /// Load monitor configuration, falling back to defaults for unset values
+    pub fn from_env() -> Self {
+        let number = |key: &str, default: i64| env::var(key).ok().and_then(|v| v.parse().ok()).filter(|v| *v > 0).unwrap_or(default);
+        Self {
+            ops_webhook_url: env::var("OPS_ALERT_WEBHOOK_URL").ok().filter(|u| !u.is_empty()),
+            stuck_after_minutes: number("TASK_STUCK_TIMEOUT_MINUTES", 30),
+            failure_spike_threshold: number("TASK_FAILURE_ALERT_THRESHOLD", 10) as u64,
+            failure_window_minutes: number("TASK_FAILURE_WINDOW_MINUTES", 60),
+        }
+    }
+}
+
+/// Shared demo tenant for trying the API without an account
+#[derive(Debug, Clone)]
+pub struct SandboxConfig {
+    /// HMAC key the sandbox token is signed with; rotating it revokes the published token
+    pub token_secret: String,
+    pub user_id: String,
+    pub email: String,
+}
+
+impl SandboxConfig {
+    /// Load sandbox configuration; returns None if no token secret is set
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            token_secret: env::var("SANDBOX_TOKEN_SECRET").ok().filter(|s| !s.is_empty())?,
+            user_id: env::var("SANDBOX_USER_ID").ok().filter(|u| !u.is_empty()).unwrap_or_else(|| "sandbox-demo".to_string()),
+            email: env::var("SANDBOX_EMAIL").unwrap_or_else(|_| "sandbox@tradstry.com".to_string()),
+        })
+    }
+}
+
 /// JWT Claims structure from Supabase Auth
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SupabaseClaims {
@@ -0,0 +1,136 @@
+//! Per-tenant locks shared by every server instance. A lock is a Redis key set with
+//! `SET NX EX`, holding a random token so only its owner releases it; the TTL frees
+//! the key if an instance dies mid-job. Without Redis configured (single-instance and
+//! local setups) every acquire succeeds, and a Redis error fails open with a warning
+//! rather than stalling logins and scheduled jobs.
+
+use anyhow::Result;
+use log::{debug, warn};
+use std::time::Duration;
+
+use super::redis::{RedisClient, RedisConfig};
+
+/// How often `acquire_within` retries a held lock
+const RETRY_INTERVAL: Duration = Duration::from_millis(250);
+
+pub const SCHEMA_SYNC_JOB: &str = "schema_sync";
+pub const BROKERAGE_SYNC_JOB: &str = "brokerage_sync";
+pub const DATA_QUALITY_JOB: &str = "data_quality";
+pub const INSIGHT_REGENERATION_JOB: &str = "insight_regeneration";
+pub const DAILY_DIGEST_JOB: &str = "daily_digest";
+
+/// Default hold for nightly per-user jobs, which finish in seconds for a typical tenant
+pub const NIGHTLY_JOB_LOCK_TTL: Duration = Duration::from_secs(10 * 60);
+
+pub fn lock_key(job: &str, tenant: &str) -> String {
+    format!("lock:{}:{}", job, tenant)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DistributedLock {
+    redis: Option<RedisClient>,
+}
+
+impl DistributedLock {
+    pub fn new(redis: Option<RedisClient>) -> Self {
+        Self { redis }
+    }
+
+    /// Use Redis when UPSTASH_REDIS_REST_URL/TOKEN are set, otherwise lock nothing
+    pub async fn from_env() -> Self {
+        let redis = match RedisConfig::from_env() {
+            Ok(config) => RedisClient::new(config).await.ok(),
+            Err(_) => None,
+        };
+        if redis.is_none() {
+            warn!("Redis not configured; per-tenant job locks are disabled");
+        }
+        Self { redis }
+    }
+
+    /// Take the lock for `job` on `tenant`, or None when another instance holds it
+    pub async fn try_acquire(&self, job: &str, tenant: &str, ttl: Duration) -> Option<LockGuard> {
+        let key = lock_key(job, tenant);
+        let Some(redis) = &self.redis else {
+            return Some(LockGuard { redis: None, key, token: String::new() });
+        };
+        let token = uuid::Uuid::new_v4().to_string();
+        match redis.set_nx(&key, &token, ttl.as_secs().max(1) as usize).await {
+            Ok(true) => Some(LockGuard { redis: Some(redis.clone()), key, token }),
+            Ok(false) => {
+                debug!("Lock {} is held by another instance", key);
+                None
+            }
+            Err(e) => {
+                warn!("Could not take lock {}, continuing without it: {}", key, e);
+                Some(LockGuard { redis: None, key, token })
+            }
+        }
+    }
+
+    /// Like `try_acquire`, but keep retrying for up to `wait` before giving up
+    pub async fn acquire_within(&self, job: &str, tenant: &str, ttl: Duration, wait: Duration) -> Option<LockGuard> {
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            if let Some(guard) = self.try_acquire(job, tenant, ttl).await {
+                return Some(guard);
+            }
+            if tokio::time::Instant::now() + RETRY_INTERVAL > deadline {
+                return None;
+            }
+            tokio::time::sleep(RETRY_INTERVAL).await;
+        }
+    }
+}
+
+/// A held lock. Call `release` when the work is done; a guard dropped without it
+/// (an early return or an error) releases in the background.
+pub struct LockGuard {
+    redis: Option<RedisClient>,
+    key: String,
+    token: String,
+}
+
+impl LockGuard {
+    pub async fn release(mut self) -> Result<()> {
+        if let Some(redis) = self.redis.take() {
+            redis.del_if_value(&self.key, &self.token).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some(redis) = self.redis.take() {
+            let (key, token) = (std::mem::take(&mut self.key), std::mem::take(&mut self.token));
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                handle.spawn(async move {
+                    if let Err(e) = redis.del_if_value(&key, &token).await {
+                        warn!("Failed to release lock {}: {}", key, e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_key_is_per_job_and_tenant() {
+        assert_eq!(lock_key(SCHEMA_SYNC_JOB, "user_1"), "lock:schema_sync:user_1");
+        assert_ne!(lock_key(SCHEMA_SYNC_JOB, "user_1"), lock_key(BROKERAGE_SYNC_JOB, "user_1"));
+    }
+
+    #[tokio::test]
+    async fn test_without_redis_every_acquire_succeeds() {
+        let lock = DistributedLock::default();
+        let first = lock.try_acquire(DATA_QUALITY_JOB, "user_1", Duration::from_secs(60)).await;
+        let second = lock.try_acquire(DATA_QUALITY_JOB, "user_1", Duration::from_secs(60)).await;
+        assert!(first.is_some() && second.is_some());
+        first.unwrap().release().await.unwrap();
+    }
+}
@@ -11,8 +11,10 @@ pub mod client;
 pub mod config;
 pub mod webhook;
 pub mod redis;
+pub mod locks;
 pub mod vector_config;
 pub mod jwt_cache;
+pub mod transaction;
 
 // Re-export commonly used items
 pub use auth::{
@@ -24,6 +26,7 @@ pub use auth::{
     AuthError,
 };
 pub use client::TursoClient;
+pub use transaction::in_transaction;
 pub use config::{TursoConfig, ClerkClaims, SupabaseClaims};
 pub use webhook::ClerkWebhookHandler;
 
@@ -31,9 +34,12 @@ use std::sync::Arc;
 use crate::service::cache_service::CacheService;
 use crate::service::trade_notes_service::TradeNotesService;
 use crate::service::rate_limiter::RateLimiter;
+use crate::service::concurrency_limiter::TenantConcurrencyLimiter;
 use crate::service::storage_quota::StorageQuotaService;
 use crate::service::account_deletion::AccountDeletionService;
-use crate::service::ai_service::{AIChatService, AIInsightsService, AiReportsService, AINotesService, VectorizationService, OpenRouterClient, VoyagerClient, UpstashVectorClient, QdrantDocumentClient, HybridSearchService, UpstashSearchClient};
+use crate::service::automations::AutomationService;
+use crate::service::year_review::YearReviewService;
+use crate::service::ai_service::{AIChatService, FundamentalsContext, AIInsightsService, AiReportsService, AINotesService, VectorizationService, OpenRouterClient, VoyagerClient, UpstashVectorClient, QdrantDocumentClient, QdrantTenantManager, HybridSearchService, UpstashSearchClient, TradeParserService, AICoachService, ModelSelector, SetupDiscoveryService};
 
 /// Application state containing Turso configuration and connections
 #[derive(Clone)]
@@ -43,6 +49,7 @@ pub struct AppState {
     pub webhook_handler: Arc<ClerkWebhookHandler>,
     pub cache_service: Arc<CacheService>,
     pub rate_limiter: Arc<RateLimiter>,
+    pub tenant_limiter: Arc<TenantConcurrencyLimiter>,
     pub storage_quota_service: Arc<StorageQuotaService>,
     pub account_deletion_service: Arc<AccountDeletionService>,
     pub ai_chat_service: Arc<AIChatService>,
@@ -53,7 +60,14 @@ pub struct AppState {
     #[allow(dead_code)]
     pub ai_notes_service: Arc<AINotesService>,
     pub trade_notes_service: Arc<TradeNotesService>,
+    pub trade_parser_service: Arc<TradeParserService>,
+    pub ai_coach_service: Arc<AICoachService>,
+    pub model_selector: Arc<ModelSelector>,
     pub vectorization_service: Arc<VectorizationService>,
+    pub qdrant_tenant_manager: Arc<QdrantTenantManager>,
+    pub setup_discovery_service: Arc<SetupDiscoveryService>,
+    pub automation_service: Arc<AutomationService>,
+    pub year_review_service: Arc<YearReviewService>,
 }
 
 impl AppState {
@@ -88,13 +102,24 @@ impl AppState {
         // Initialize rate limiter (uses same Redis client)
         let rate_limiter = Arc::new(RateLimiter::new(redis_client));
 
-        // Initialize storage quota service
-        let storage_quota_service = Arc::new(StorageQuotaService::new(Arc::clone(&turso_client)));
+        // Per-user concurrency limit for heavy analytics/AI work (in-process)
+        let tenant_limiter = Arc::new(TenantConcurrencyLimiter::from_env());
 
         // Initialize AI services
         let openrouter_config = crate::turso::vector_config::OpenRouterConfig::from_env()
             .map_err(|e| format!("Failed to load OpenRouter config: {}", e))?;
         let openrouter_client = Arc::new(OpenRouterClient::new(openrouter_config)?);
+
+        // Load feedback-based model scores so low-rated models are skipped from the first request
+        let model_selector = openrouter_client.model_selector();
+        match turso_client.get_registry_connection().await {
+            Ok(registry) => {
+                if let Err(e) = model_selector.refresh(&registry).await {
+                    log::warn!("Failed to load model quality scores: {}", e);
+                }
+            }
+            Err(e) => log::warn!("Failed to connect to registry for model scores: {}", e),
+        }
         
         let vector_config = crate::turso::vector_config::VectorConfig::from_env()
             .map_err(|e| format!("Failed to load Vector config: {}", e))?;
@@ -108,6 +133,10 @@ impl AppState {
             .map_err(|e| format!("Failed to load Qdrant config: {}", e))?;
         let qdrant_client = Arc::new(QdrantDocumentClient::new(qdrant_config).await
             .map_err(|e| format!("Failed to create Qdrant client: {}", e))?);
+        let qdrant_tenant_manager = Arc::new(QdrantTenantManager::new(Arc::clone(&qdrant_client)));
+
+        // Initialize storage quota service (vector counts come from Qdrant)
+        let storage_quota_service = Arc::new(StorageQuotaService::new(Arc::clone(&turso_client), Arc::clone(&qdrant_client)));
         
         let ai_config = crate::turso::vector_config::AIConfig::from_env()
             .map_err(|e| format!("Failed to load AI config: {}", e))?;
@@ -133,12 +162,19 @@ impl AppState {
             Arc::clone(&turso_client),
             Arc::clone(&voyager_client),
             10, // max_context_vectors
-        ));
+        ).with_fundamentals(FundamentalsContext {
+            market_client: crate::service::market_engine::client::MarketClient::new(&config.finance_query)
+                .map_err(|e| format!("Failed to create market client: {}", e))?,
+            cache_service: Arc::clone(&cache_service),
+        }));
         
+        let automation_service = Arc::new(AutomationService::new(config.web_push.clone()));
+
         let ai_insights_service = Arc::new(AIInsightsService::new(
             Arc::clone(&vectorization_service),
             Arc::clone(&openrouter_client),
             Arc::clone(&turso_client),
+            Arc::clone(&automation_service),
             10, // max_context_vectors
         ));
 
@@ -151,6 +187,23 @@ impl AppState {
             Arc::clone(&openrouter_client),
         ));
 
+        let trade_parser_service = Arc::new(TradeParserService::new(
+            Arc::clone(&openrouter_client),
+        ));
+
+        let ai_coach_service = Arc::new(AICoachService::new(
+            Arc::clone(&openrouter_client),
+            Arc::clone(&qdrant_client),
+            Arc::clone(&ai_insights_service),
+        ));
+
+        let setup_discovery_service = Arc::new(SetupDiscoveryService::new(
+            Arc::clone(&vectorization_service),
+            Arc::clone(&openrouter_client),
+        ));
+
+        let year_review_service = Arc::new(YearReviewService::new(Arc::clone(&openrouter_client)));
+
         let trade_notes_service = Arc::new(TradeNotesService::new(
             Arc::clone(&ai_notes_service),
             Arc::clone(&cache_service),
@@ -179,7 +232,7 @@ impl AppState {
             Arc::clone(&turso_client),
             Arc::clone(&image_upload_service),
             Arc::clone(&vectorization_service),
-            Arc::clone(&qdrant_client),
+            Arc::clone(&qdrant_tenant_manager),
             Arc::clone(&upstash_search_client),
             supabase_url,
             supabase_service_role_key,
@@ -191,6 +244,7 @@ impl AppState {
             webhook_handler,
             cache_service,
             rate_limiter,
+            tenant_limiter,
             storage_quota_service,
             account_deletion_service,
             ai_chat_service,
@@ -198,7 +252,14 @@ impl AppState {
             ai_reports_service,
             ai_notes_service,
             trade_notes_service,
+            trade_parser_service,
+            ai_coach_service,
+            model_selector,
             vectorization_service,
+            qdrant_tenant_manager,
+            setup_discovery_service,
+            automation_service,
+            year_review_service,
         })
     }
 
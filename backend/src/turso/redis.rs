@@ -138,6 +138,96 @@ impl RedisClient {
         Ok(())
     }
 
+    /// Run one command through the REST root endpoint, e.g. `["SET", key, value, "NX"]`
+    async fn command(&self, args: &[&str]) -> Result<serde_json::Value> {
+        let response = self.client
+            .post(&self.base_url)
+            .header("Authorization", format!("Bearer {}", self.token))
+            .json(&args)
+            .send()
+            .await?
+            .error_for_status()?;
+        let result: UpstashResponse = response.json().await?;
+        Ok(result.result)
+    }
+
+    /// SET NX with a TTL: true when the key was created, false when it already existed
+    pub async fn set_nx(&self, key: &str, value: &str, ttl_seconds: usize) -> Result<bool> {
+        let result = self.command(&["SET", key, value, "NX", "EX", &ttl_seconds.to_string()]).await?;
+        Ok(result.as_str() == Some("OK"))
+    }
+
+    /// Delete `key` only while it still holds `value`, so an expired lock taken over by
+    /// someone else isn't released by its previous owner
+    pub async fn del_if_value(&self, key: &str, value: &str) -> Result<bool> {
+        const SCRIPT: &str = "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end";
+        let result = self.command(&["EVAL", SCRIPT, "1", key, value]).await?;
+        Ok(result.as_i64() == Some(1))
+    }
+
+    /// Read several keys in one MGET round trip; values that are missing or fail to
+    /// deserialize come back as None
+    pub async fn mget<T>(&self, keys: &[String]) -> Result<Vec<Option<T>>>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let args: Vec<&str> = std::iter::once("MGET").chain(keys.iter().map(String::as_str)).collect();
+        let result = self.command(&args).await?;
+        let values = result.as_array().cloned().unwrap_or_default();
+        Ok((0..keys.len())
+            .map(|i| match values.get(i) {
+                Some(serde_json::Value::String(s)) => serde_json::from_str(s).ok(),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// SETEX several keys in one pipelined request
+    pub async fn set_many<T>(&self, entries: &[(String, T)], ttl_seconds: usize) -> Result<()>
+    where
+        T: Serialize,
+    {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let ttl = ttl_seconds.to_string();
+        let commands = entries
+            .iter()
+            .map(|(key, value)| Ok(vec!["SETEX".to_string(), key.clone(), ttl.clone(), serde_json::to_string(value)?]))
+            .collect::<Result<Vec<Vec<String>>>>()?;
+        self.client
+            .post(format!("{}/pipeline", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .json(&commands)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /// Publish a pub/sub message; returns how many subscribers received it
+    pub async fn publish(&self, channel: &str, message: &str) -> Result<i64> {
+        let result = self.command(&["PUBLISH", channel, message]).await?;
+        Ok(result.as_i64().unwrap_or(0))
+    }
+
+    /// Open a long-lived subscription. Upstash streams it as server-sent events, one
+    /// `data: message,<channel>,<payload>` line per message; read it with `chunk()`.
+    /// Uses its own HTTP client since the shared one times requests out.
+    pub async fn subscribe(&self, channel: &str) -> Result<reqwest::Response> {
+        let response = reqwest::Client::new()
+            .post(format!("{}/subscribe/{}", self.base_url, channel))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .header("Accept", "text/event-stream")
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response)
+    }
+
     /// Delete all keys matching a pattern
     pub async fn del_pattern(&self, pattern: &str) -> Result<usize> {
         // Get keys matching pattern
@@ -228,4 +318,13 @@ pub mod ttl {
     pub const MARKET_DATA: usize = 120; // 2 minutes
     #[allow(dead_code)]
     pub const MARKET_MOVERS: usize = 300; // 5 minutes
+    pub const MARKET_SEARCH: usize = 3600; // 1 hour
+    pub const SECTOR_DASHBOARD: usize = 900; // 15 minutes
+    pub const HISTORICAL_CLOSES: usize = 21600; // 6 hours
+    pub const TRANSCRIPT_SUMMARY: usize = 2592000; // 30 days
+    pub const FUNDAMENTALS: usize = 43200; // 12 hours
+    pub const OPTIONS_CHAIN: usize = 300; // 5 minutes
+    pub const COMMAND_PALETTE: usize = 120; // 2 minutes
+    pub const NEWS_FEED: usize = 300; // 5 minutes
+    pub const QUOTE_SNAPSHOT: usize = 15; // 15 seconds
 }
\ No newline at end of file
@@ -2,6 +2,12 @@ use anyhow::Result;
 use libsql::Connection;
 use log::info;
 
+use crate::models::ai::insights::InsightType;
+use crate::models::ai::reports::ReportType;
+use crate::service::analytics_engine::daily_aggregates::{self, DailySource};
+
+use super::in_transaction;
+
 /// Schema version information
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 pub struct SchemaVersion {
@@ -56,7 +62,13 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
         .build()
         .await?;
     let conn = user_db.connect()?;
+    create_base_schema(&conn).await?;
+    info!("Trading+notebook schema initialized successfully");
+    Ok(())
+}
 
+/// Create the base per-user tables, indexes and triggers on an open connection
+pub async fn create_base_schema(conn: &Connection) -> Result<()> {
     // Stocks table
     conn.execute(
         r#"
@@ -81,7 +93,9 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             brokerage_name TEXT,
             created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            is_deleted INTEGER NOT NULL DEFAULT 0
+            is_deleted INTEGER NOT NULL DEFAULT 0,
+            realized_pnl REAL, -- set on write from the trade's fields; NULL while open
+            venue TEXT -- normalized exchange name (models::venue); NULL when unknown
         )
         "#,
         libsql::params![],
@@ -106,6 +120,13 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             primary_trading_goal TEXT,
             asset_types TEXT,
             trading_style TEXT,
+            locale TEXT DEFAULT 'en',
+            fiscal_year_start_month INTEGER DEFAULT 1,
+            fiscal_year_start_day INTEGER DEFAULT 1,
+            session_offset_minutes INTEGER DEFAULT 0, -- shifts exits onto trading dates (overnight sessions)
+            excluded_weekdays TEXT, -- comma-separated 0-6 (Sunday = 0), rolled into the next trading day
+            ai_persona TEXT, -- strict_risk_manager, supportive_coach or quant_analyst; NULL keeps the default voice
+            week_start_day INTEGER DEFAULT 1, -- 0 = Sunday, 1 = Monday; first day of weekly buckets
             created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
         )
@@ -141,7 +162,9 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             brokerage_name TEXT,
             created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
-            is_deleted INTEGER NOT NULL DEFAULT 0
+            is_deleted INTEGER NOT NULL DEFAULT 0,
+            realized_pnl REAL, -- set on write from the trade's fields; NULL while open
+            venue TEXT -- normalized exchange name (models::venue); NULL when unknown
         )
         "#,
         libsql::params![],
@@ -273,7 +296,8 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             color TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now')),
-            version INTEGER NOT NULL DEFAULT 0
+            version INTEGER NOT NULL DEFAULT 0,
+            organization_id TEXT
         )
         "#,
         libsql::params![],
@@ -391,6 +415,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             description TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+            organization_id TEXT,
             UNIQUE(category, name)
         )
         "#,
@@ -507,6 +532,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             title TEXT NOT NULL,
             description TEXT,
             reminder_time TEXT NOT NULL,
+            recurrence TEXT,
             is_completed BOOLEAN NOT NULL DEFAULT false,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now')),
@@ -524,7 +550,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
         r#"
         CREATE TABLE IF NOT EXISTS calendar_events (
             id TEXT PRIMARY KEY,
-            reminder_id TEXT NOT NULL,
+            reminder_id TEXT,
             event_title TEXT NOT NULL,
             event_description TEXT,
             start_date DATE NOT NULL,
@@ -533,6 +559,8 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             end_time TIME,
             is_all_day BOOLEAN NOT NULL DEFAULT false,
             is_synced BOOLEAN NOT NULL DEFAULT false,
+            source TEXT NOT NULL DEFAULT 'reminder',
+            source_ref TEXT,
             created_at TEXT NOT NULL DEFAULT (datetime('now')),
             updated_at TEXT NOT NULL DEFAULT (datetime('now')),
             FOREIGN KEY (reminder_id) REFERENCES notebook_reminders(id) ON DELETE CASCADE
@@ -544,6 +572,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
     conn.execute("CREATE INDEX IF NOT EXISTS idx_calendar_events_start_date ON calendar_events(start_date)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_calendar_events_end_date ON calendar_events(end_date)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_calendar_events_date_range ON calendar_events(start_date, end_date)", libsql::params![]).await?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_calendar_events_source_ref ON calendar_events(source, source_ref)", libsql::params![]).await?;
 
     // External calendars (connections and events cache)
     conn.execute(
@@ -670,7 +699,10 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             message_count INTEGER DEFAULT 0,
-            last_message_at TEXT
+            last_message_at TEXT,
+            rolling_summary TEXT, -- summary of turns dropped from the prompt window
+            summarized_until TEXT, -- created_at of the last message folded into rolling_summary
+            report_id TEXT -- ai_reports row pinned as the session's context, if any
         )
         "#,
         libsql::params![],
@@ -686,7 +718,9 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             role TEXT NOT NULL CHECK (role IN ('user', 'assistant', 'system')),
             content TEXT NOT NULL,
             context_vectors TEXT, -- JSON array of vector IDs
+            context_sources TEXT, -- JSON array of the sources behind those vectors (type, entity, score)
             token_count INTEGER,
+            model TEXT, -- OpenRouter model that produced an assistant message
             created_at TEXT NOT NULL,
             FOREIGN KEY (session_id) REFERENCES chat_sessions(id) ON DELETE CASCADE
         )
@@ -697,27 +731,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
     conn.execute("CREATE INDEX IF NOT EXISTS idx_chat_messages_created_at ON chat_messages(created_at)", libsql::params![]).await?;
 
     // AI Insights Tables
-    conn.execute(
-        r#"
-        CREATE TABLE IF NOT EXISTS ai_insights (
-            id TEXT PRIMARY KEY,
-            user_id TEXT NOT NULL,
-            time_range TEXT NOT NULL CHECK (time_range IN ('7d', '30d', '90d', 'ytd', '1y')),
-            insight_type TEXT NOT NULL CHECK (insight_type IN ('trading_patterns', 'performance_analysis', 'risk_assessment', 'behavioral_analysis', 'market_analysis', 'opportunity_detection')),
-            title TEXT NOT NULL,
-            content TEXT NOT NULL,
-            key_findings TEXT, -- JSON array
-            recommendations TEXT, -- JSON array
-            data_sources TEXT, -- JSON array
-            confidence_score REAL DEFAULT 0.0,
-            generated_at TEXT NOT NULL,
-            expires_at TEXT,
-            metadata TEXT, -- JSON object with additional metadata
-            created_at TEXT NOT NULL DEFAULT (datetime('now'))
-        )
-        "#,
-        libsql::params![],
-    ).await?;
+    conn.execute(&ai_insights_table_sql("ai_insights"), libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_insights_user_id ON ai_insights(user_id)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_insights_time_range ON ai_insights(time_range)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_insights_type ON ai_insights(insight_type)", libsql::params![]).await?;
@@ -747,31 +761,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
     conn.execute("CREATE INDEX IF NOT EXISTS idx_insight_tasks_created_at ON insight_generation_tasks(created_at)", libsql::params![]).await?;
 
     // AI Reports Tables
-    conn.execute(
-        r#"
-        CREATE TABLE IF NOT EXISTS ai_reports (
-            id TEXT PRIMARY KEY,
-            time_range TEXT NOT NULL CHECK (time_range IN ('7d', '30d', '90d', 'ytd', '1y')),
-            report_type TEXT NOT NULL CHECK (report_type IN ('comprehensive', 'performance', 'risk', 'trading', 'behavioral', 'market')),
-            title TEXT NOT NULL,
-            summary TEXT NOT NULL,
-            analytics TEXT NOT NULL, -- JSON object with analytics data
-            insights TEXT NOT NULL, -- JSON array of insights
-            trades TEXT NOT NULL, -- JSON array of trade data
-            recommendations TEXT NOT NULL, -- JSON array of recommendations
-            patterns TEXT, -- JSON array of trading patterns
-            risk_metrics TEXT, -- JSON object with risk metrics
-            performance_metrics TEXT, -- JSON object with performance metrics
-            behavioral_insights TEXT, -- JSON array of behavioral insights
-            market_analysis TEXT, -- JSON object with market analysis
-            generated_at TEXT NOT NULL,
-            expires_at TEXT,
-            metadata TEXT, -- JSON object with additional metadata
-            created_at TEXT NOT NULL DEFAULT (datetime('now'))
-        )
-        "#,
-        libsql::params![],
-    ).await?;
+    conn.execute(&ai_reports_table_sql("ai_reports"), libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_reports_time_range ON ai_reports(time_range)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_reports_type ON ai_reports(report_type)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_ai_reports_generated_at ON ai_reports(generated_at)", libsql::params![]).await?;
@@ -962,6 +952,16 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
             brokerage_name TEXT NOT NULL,
             status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'connected', 'error', 'disconnected')),
             last_sync_at TIMESTAMP,
+            authorization_id TEXT,
+            status_reason TEXT,
+            reauth_required INTEGER NOT NULL DEFAULT 0,
+            sync_requested_at TIMESTAMP,
+            last_webhook_at TIMESTAMP,
+            sync_frequency TEXT NOT NULL DEFAULT 'daily',
+            sync_cursor TEXT,
+            next_sync_at TIMESTAMP,
+            consecutive_failures INTEGER NOT NULL DEFAULT 0,
+            last_sync_error TEXT,
             created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
             updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
         )
@@ -971,6 +971,7 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
     conn.execute("CREATE INDEX IF NOT EXISTS idx_brokerage_connections_user_id ON brokerage_connections(user_id)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_brokerage_connections_connection_id ON brokerage_connections(connection_id)", libsql::params![]).await?;
     conn.execute("CREATE INDEX IF NOT EXISTS idx_brokerage_connections_status ON brokerage_connections(status)", libsql::params![]).await?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_brokerage_connections_authorization_id ON brokerage_connections(authorization_id)", libsql::params![]).await?;
 
     // Brokerage accounts table
     conn.execute(
@@ -1121,15 +1122,73 @@ pub async fn initialize_user_database_schema(db_url: &str, token: &str) -> Resul
         }
     }
 
-    info!("Trading+notebook schema initialized successfully");
     Ok(())
 }
 
-/// Current schema version (bumped for unmatched_transactions table)
+/// `ai_insights` as new databases get it. The insight_type CHECK lists every InsightType, so
+/// adding a type widens the constraint; existing tables pick it up through
+/// `rebuild_stale_constraints`.
+pub fn ai_insights_table_sql(table: &str) -> String {
+    let insight_types: Vec<String> = InsightType::ALL.iter().map(|t| format!("'{}'", t.as_str())).collect();
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {table} (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            time_range TEXT NOT NULL,
+            insight_type TEXT NOT NULL CHECK (insight_type IN ({insight_types})),
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            key_findings TEXT, -- JSON array
+            recommendations TEXT, -- JSON array
+            data_sources TEXT, -- JSON array
+            confidence_score REAL DEFAULT 0.0,
+            generated_at TEXT NOT NULL,
+            expires_at TEXT,
+            metadata TEXT, -- JSON object with additional metadata
+            source TEXT NOT NULL DEFAULT 'llm', -- 'llm' or 'rules'
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#,
+        insight_types = insight_types.join(", "),
+    )
+}
+
+/// `ai_reports` as new databases get it; `time_range` is unconstrained like in `ai_insights`
+pub fn ai_reports_table_sql(table: &str) -> String {
+    let report_types: Vec<String> = ReportType::ALL.iter().map(|t| format!("'{}'", t.as_str())).collect();
+    format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS {table} (
+            id TEXT PRIMARY KEY,
+            time_range TEXT NOT NULL,
+            report_type TEXT NOT NULL CHECK (report_type IN ({report_types})),
+            title TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            analytics TEXT NOT NULL, -- JSON object with analytics data
+            insights TEXT NOT NULL, -- JSON array of insights
+            trades TEXT NOT NULL, -- JSON array of trade data
+            recommendations TEXT NOT NULL, -- JSON array of recommendations
+            patterns TEXT, -- JSON array of trading patterns
+            risk_metrics TEXT, -- JSON object with risk metrics
+            performance_metrics TEXT, -- JSON object with performance metrics
+            behavioral_insights TEXT, -- JSON array of behavioral insights
+            market_analysis TEXT, -- JSON object with market analysis
+            generated_at TEXT NOT NULL,
+            expires_at TEXT,
+            metadata TEXT, -- JSON object with additional metadata
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )
+        "#,
+        report_types = report_types.join(", "),
+    )
+}
+
+/// Current schema version (bumped for the ai_reports time_range rebuild)
 pub fn get_current_schema_version() -> SchemaVersion {
     SchemaVersion {
-        version: "0.0.28".to_string(),
-        description: "Added brokerage_name column to stocks and options tables.".to_string(),
+        version: "0.0.87".to_string(),
+        description: "Rebuild ai_reports without its time_range CHECK and store time ranges plain".to_string(),
         created_at: chrono::Utc::now().to_rfc3339(),
     }
 }
@@ -1162,6 +1221,8 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
                 ColumnInfo { name: "created_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
                 ColumnInfo { name: "updated_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
                 ColumnInfo { name: "is_deleted".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+                ColumnInfo { name: "realized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                ColumnInfo { name: "venue".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ],
             indexes: vec![
                 IndexInfo { name: "idx_stocks_symbol".to_string(), table_name: "stocks".to_string(), columns: vec!["symbol".to_string()], is_unique: false },
@@ -1203,6 +1264,10 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
                 ColumnInfo { name: "created_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
                 ColumnInfo { name: "updated_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
                 ColumnInfo { name: "is_deleted".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+                ColumnInfo { name: "realized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                ColumnInfo { name: "venue".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                // NULL until classified by `OptionTrade::classify_premium_side`
+                ColumnInfo { name: "short_premium".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ],
             indexes: vec![
                 IndexInfo { name: "idx_options_symbol".to_string(), table_name: "options".to_string(), columns: vec!["symbol".to_string()], is_unique: false },
@@ -1256,6 +1321,13 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
                 ColumnInfo { name: "primary_trading_goal".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
                 ColumnInfo { name: "asset_types".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
                 ColumnInfo { name: "trading_style".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                ColumnInfo { name: "locale".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: Some("'en'".to_string()), is_primary_key: false },
+                ColumnInfo { name: "fiscal_year_start_month".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: Some("1".to_string()), is_primary_key: false },
+                ColumnInfo { name: "fiscal_year_start_day".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: Some("1".to_string()), is_primary_key: false },
+                ColumnInfo { name: "session_offset_minutes".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: Some("0".to_string()), is_primary_key: false },
+                ColumnInfo { name: "excluded_weekdays".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                ColumnInfo { name: "ai_persona".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+                ColumnInfo { name: "week_start_day".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: Some("1".to_string()), is_primary_key: false },
                 ColumnInfo { name: "created_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
                 ColumnInfo { name: "updated_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
             ],
@@ -1273,6 +1345,7 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
                 ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
                 ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
                 ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+                ColumnInfo { name: "organization_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ],
             indexes: vec![
                 IndexInfo { name: "idx_trade_tags_category".to_string(), table_name: "trade_tags".to_string(), columns: vec!["category".to_string()], is_unique: false },
@@ -1337,7 +1410,7 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             triggers: vec![ TriggerInfo { name: "update_images_timestamp".to_string(), table_name: "images".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE images SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
         },
         // Playbook + junction tables
-        TableSchema { name: "playbook".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "icon".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "emoji".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "color".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "version".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_playbook_updated_at".to_string(), table_name: "playbook".to_string(), columns: vec!["updated_at".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_playbook_timestamp".to_string(), table_name: "playbook".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE playbook SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] },
+        TableSchema { name: "playbook".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "icon".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "emoji".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "color".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "version".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false }, ColumnInfo { name: "organization_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_playbook_updated_at".to_string(), table_name: "playbook".to_string(), columns: vec!["updated_at".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_playbook_timestamp".to_string(), table_name: "playbook".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE playbook SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] },
         TableSchema { name: "stock_trade_playbook".to_string(), columns: vec![ ColumnInfo { name: "stock_trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "setup_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_stock_trade_playbook_stock_trade_id".to_string(), table_name: "stock_trade_playbook".to_string(), columns: vec!["stock_trade_id".to_string()], is_unique: false }, IndexInfo { name: "idx_stock_trade_playbook_setup_id".to_string(), table_name: "stock_trade_playbook".to_string(), columns: vec!["setup_id".to_string()], is_unique: false } ], triggers: vec![] },
         TableSchema { name: "option_trade_playbook".to_string(), columns: vec![ ColumnInfo { name: "option_trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "setup_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_option_trade_playbook_option_trade_id".to_string(), table_name: "option_trade_playbook".to_string(), columns: vec!["option_trade_id".to_string()], is_unique: false }, IndexInfo { name: "idx_option_trade_playbook_setup_id".to_string(), table_name: "option_trade_playbook".to_string(), columns: vec!["setup_id".to_string()], is_unique: false } ], triggers: vec![] },
 
@@ -1362,9 +1435,9 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
 
     schemas.push(TableSchema { name: "notebook_templates".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "content".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_notebook_templates_created_at".to_string(), table_name: "notebook_templates".to_string(), columns: vec!["created_at".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_notebook_templates_timestamp".to_string(), table_name: "notebook_templates".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE notebook_templates SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] });
 
-    schemas.push(TableSchema { name: "notebook_reminders".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "note_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "title".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "reminder_time".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "is_completed".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_notebook_reminders_note_id".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["note_id".to_string()], is_unique: false }, IndexInfo { name: "idx_notebook_reminders_reminder_time".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["reminder_time".to_string()], is_unique: false }, IndexInfo { name: "idx_notebook_reminders_is_completed".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["is_completed".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_notebook_reminders_timestamp".to_string(), table_name: "notebook_reminders".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE notebook_reminders SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] });
+    schemas.push(TableSchema { name: "notebook_reminders".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "note_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "title".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "reminder_time".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "recurrence".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "is_completed".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_notebook_reminders_note_id".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["note_id".to_string()], is_unique: false }, IndexInfo { name: "idx_notebook_reminders_reminder_time".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["reminder_time".to_string()], is_unique: false }, IndexInfo { name: "idx_notebook_reminders_is_completed".to_string(), table_name: "notebook_reminders".to_string(), columns: vec!["is_completed".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_notebook_reminders_timestamp".to_string(), table_name: "notebook_reminders".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE notebook_reminders SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] });
 
-    schemas.push(TableSchema { name: "calendar_events".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "reminder_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "event_title".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "event_description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "start_date".to_string(), data_type: "DATE".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "end_date".to_string(), data_type: "DATE".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "start_time".to_string(), data_type: "TIME".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "end_time".to_string(), data_type: "TIME".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "is_all_day".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "is_synced".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_calendar_events_reminder_id".to_string(), table_name: "calendar_events".to_string(), columns: vec!["reminder_id".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_start_date".to_string(), table_name: "calendar_events".to_string(), columns: vec!["start_date".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_end_date".to_string(), table_name: "calendar_events".to_string(), columns: vec!["end_date".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_date_range".to_string(), table_name: "calendar_events".to_string(), columns: vec!["start_date".to_string(), "end_date".to_string()], is_unique: false } ], triggers: vec![ TriggerInfo { name: "update_calendar_events_timestamp".to_string(), table_name: "calendar_events".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE calendar_events SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] });
+    schemas.push(TableSchema { name: "calendar_events".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "reminder_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "event_title".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "event_description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "start_date".to_string(), data_type: "DATE".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "end_date".to_string(), data_type: "DATE".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "start_time".to_string(), data_type: "TIME".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "end_time".to_string(), data_type: "TIME".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "is_all_day".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "is_synced".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false }, ColumnInfo { name: "source".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'reminder'".to_string()), is_primary_key: false }, ColumnInfo { name: "source_ref".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_calendar_events_reminder_id".to_string(), table_name: "calendar_events".to_string(), columns: vec!["reminder_id".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_start_date".to_string(), table_name: "calendar_events".to_string(), columns: vec!["start_date".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_end_date".to_string(), table_name: "calendar_events".to_string(), columns: vec!["end_date".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_date_range".to_string(), table_name: "calendar_events".to_string(), columns: vec!["start_date".to_string(), "end_date".to_string()], is_unique: false }, IndexInfo { name: "idx_calendar_events_source_ref".to_string(), table_name: "calendar_events".to_string(), columns: vec!["source".to_string(), "source_ref".to_string()], is_unique: true } ], triggers: vec![ TriggerInfo { name: "update_calendar_events_timestamp".to_string(), table_name: "calendar_events".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE calendar_events SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ] });
 
     schemas.push(TableSchema { name: "external_calendar_connections".to_string(), columns: vec![ ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true }, ColumnInfo { name: "provider".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "access_token".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "refresh_token".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "token_expiry".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false }, ColumnInfo { name: "calendar_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false }, ColumnInfo { name: "is_active".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false }, ColumnInfo { name: "last_sync_timestamp".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: Some("''".to_string()), is_primary_key: false }, ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("''".to_string()), is_primary_key: false }, ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("''".to_string()), is_primary_key: false } ], indexes: vec![ IndexInfo { name: "idx_external_calendar_connections_last_sync".to_string(), table_name: "external_calendar_connections".to_string(), columns: vec!["last_sync_timestamp".to_string()], is_unique: false } ], triggers: vec![] });
 
@@ -1445,6 +1518,9 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
             ColumnInfo { name: "message_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
             ColumnInfo { name: "last_message_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "rolling_summary".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "summarized_until".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "report_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
         ],
         indexes: vec![
             IndexInfo { name: "idx_chat_sessions_user_id".to_string(), table_name: "chat_sessions".to_string(), columns: vec!["user_id".to_string()], is_unique: false },
@@ -1461,7 +1537,9 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             ColumnInfo { name: "role".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
             ColumnInfo { name: "content".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
             ColumnInfo { name: "context_vectors".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "context_sources".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ColumnInfo { name: "token_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "model".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
         ],
         indexes: vec![
@@ -1488,6 +1566,7 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             ColumnInfo { name: "generated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
             ColumnInfo { name: "expires_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ColumnInfo { name: "metadata".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "source".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'llm'".to_string()), is_primary_key: false },
             ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
         ],
         indexes: vec![
@@ -1587,6 +1666,16 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             ColumnInfo { name: "brokerage_name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
             ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pending'".to_string()), is_primary_key: false },
             ColumnInfo { name: "last_sync_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "authorization_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status_reason".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "reauth_required".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "sync_requested_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_webhook_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "sync_frequency".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'daily'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "sync_cursor".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "next_sync_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "consecutive_failures".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_sync_error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
             ColumnInfo { name: "created_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
             ColumnInfo { name: "updated_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
         ],
@@ -1594,6 +1683,7 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
             IndexInfo { name: "idx_brokerage_connections_user_id".to_string(), table_name: "brokerage_connections".to_string(), columns: vec!["user_id".to_string()], is_unique: false },
             IndexInfo { name: "idx_brokerage_connections_connection_id".to_string(), table_name: "brokerage_connections".to_string(), columns: vec!["connection_id".to_string()], is_unique: false },
             IndexInfo { name: "idx_brokerage_connections_status".to_string(), table_name: "brokerage_connections".to_string(), columns: vec!["status".to_string()], is_unique: false },
+            IndexInfo { name: "idx_brokerage_connections_authorization_id".to_string(), table_name: "brokerage_connections".to_string(), columns: vec!["authorization_id".to_string()], is_unique: false },
         ],
         triggers: vec![],
     });
@@ -1726,202 +1816,1468 @@ pub fn get_expected_schema() -> Vec<TableSchema> {
         triggers: vec![ TriggerInfo { name: "update_push_subscriptions_timestamp".to_string(), table_name: "push_subscriptions".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE push_subscriptions SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
     });
 
-    schemas
-}
+    // Consistency score history (one snapshot per day per time range)
+    schemas.push(TableSchema {
+        name: "consistency_score_history".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "snapshot_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "time_range".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'all_time'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "overall_score".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "profit_concentration_score".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "rule_compliance_score".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "drawdown_discipline_score".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "best_day_share_percentage".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "trading_days".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "total_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_consistency_score_history_date_range".to_string(), table_name: "consistency_score_history".to_string(), columns: vec!["snapshot_date".to_string(), "time_range".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
 
-/// Initialize the schema version table if needed
-pub async fn initialize_schema_version_table(conn: &Connection) -> Result<()> {
-    conn.execute(
-        r#"
-        CREATE TABLE IF NOT EXISTS schema_version (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            version TEXT NOT NULL,
-            description TEXT NOT NULL,
-            created_at TEXT NOT NULL
-        )
-        "#,
-        libsql::params![],
-    ).await?;
-    Ok(())
-}
+    // Prop-firm evaluation profiles
+    schemas.push(TableSchema {
+        name: "evaluation_profiles".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "account_size".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "profit_target".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "max_daily_loss".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "max_trailing_drawdown".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "min_trading_days".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "consistency_threshold_percentage".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_evaluation_profiles_timestamp".to_string(), table_name: "evaluation_profiles".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE evaluation_profiles SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
 
-/// Update schema version in the database
-pub async fn update_schema_version(conn: &Connection, version: &SchemaVersion) -> Result<()> {
-    conn.execute(
-        "INSERT INTO schema_version (version, description, created_at) VALUES (?, ?, ?)",
-        libsql::params![version.version.clone(), version.description.clone(), version.created_at.clone()],
-    ).await?;
-    Ok(())
-}
+    // Discord/Slack webhooks for insights and daily recaps
+    schemas.push(TableSchema {
+        name: "notification_webhooks".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "platform".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "webhook_url".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "event_types".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_active".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_delivered_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_notification_webhooks_timestamp".to_string(), table_name: "notification_webhooks".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE notification_webhooks SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
 
-/// Ensure indexes for a table
-pub async fn ensure_indexes(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
-    for index in &table_schema.indexes {
-        let mut rows = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type='index' AND name=?")
-            .await?
-            .query(libsql::params![index.name.clone()])
-            .await?;
-        if rows.next().await?.is_none() {
-            let create_index_sql = format!(
-                "CREATE INDEX IF NOT EXISTS {} ON {} ({})",
-                index.name,
-                index.table_name,
-                index.columns.join(", ")
-            );
-            conn.execute(&create_index_sql, libsql::params![]).await?;
-        }
-    }
-    Ok(())
-}
+    // Encrypted third-party credentials (AES-256-GCM sealed)
+    schemas.push(TableSchema {
+        name: "integration_secrets".to_string(),
+        columns: vec![
+            ColumnInfo { name: "provider".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "ciphertext".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "nonce".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
 
-/// Ensure triggers for a table
-pub async fn ensure_triggers(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
-    for trigger in &table_schema.triggers {
-        let mut rows = conn
-            .prepare("SELECT name FROM sqlite_master WHERE type='trigger' AND name=?")
-            .await?
-            .query(libsql::params![trigger.name.clone()])
-            .await?;
-        if rows.next().await?.is_none() {
-            let create_trigger_sql = format!(
-                "CREATE TRIGGER IF NOT EXISTS {} {} {} ON {} FOR EACH ROW BEGIN {}; END",
-                trigger.name,
-                trigger.timing,
-                trigger.event,
-                trigger.table_name,
-                trigger.action
-            );
-            conn.execute(&create_trigger_sql, libsql::params![]).await?;
-        }
-    }
-    Ok(())
-}
+    // Notion integration settings (single row, id = 'default')
+    schemas.push(TableSchema {
+        name: "notion_integration".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "workspace_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "workspace_name".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "database_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "field_mapping".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "export_daily_journal".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "export_weekly_review".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_export_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_notion_integration_timestamp".to_string(), table_name: "notion_integration".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE notion_integration SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
 
-/// Get list of current tables in the database
-pub async fn get_current_tables(conn: &Connection) -> Result<Vec<String>> {
-    let mut tables = Vec::new();
-    let mut rows = conn
-        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")
-        .await?
-        .query(libsql::params![])
-        .await?;
-    while let Some(row) = rows.next().await? { tables.push(row.get(0)?); }
-    Ok(tables)
-}
+    // Notion export log used for de-duplication and retries
+    schemas.push(TableSchema {
+        name: "notion_exports".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "entry_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "entry_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "notion_page_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pending'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "attempts".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_notion_exports_entry_unique".to_string(), table_name: "notion_exports".to_string(), columns: vec!["entry_type".to_string(), "entry_date".to_string()], is_unique: true },
+            IndexInfo { name: "idx_notion_exports_status".to_string(), table_name: "notion_exports".to_string(), columns: vec!["status".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
 
-/// Create a table based on schema definition
-pub async fn create_table(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
-    let mut create_sql = format!("CREATE TABLE IF NOT EXISTS {} (", table_schema.name);
-    let primary_keys: Vec<String> = table_schema.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect();
-    let column_definitions: Vec<String> = table_schema.columns.iter().map(|col| {
-        let mut def = format!("{} {}", col.name, col.data_type);
-        if col.is_primary_key && primary_keys.len() == 1 {
-            if col.data_type.to_uppercase().contains("INTEGER") { def.push_str(" PRIMARY KEY AUTOINCREMENT"); } else { def.push_str(" PRIMARY KEY"); }
-        } else if !col.is_nullable { def.push_str(" NOT NULL"); }
-        if let Some(default) = &col.default_value { def.push_str(&format!(" DEFAULT {}", default)); }
-        def
-    }).collect();
-    create_sql.push_str(&column_definitions.join(", "));
-    if primary_keys.len() > 1 { create_sql.push_str(&format!(", PRIMARY KEY ({})", primary_keys.join(", "))); }
-    create_sql.push(')');
-    conn.execute(&create_sql, libsql::params![]).await?;
-    Ok(())
-}
+    // Thumbs up/down feedback on AI insights and chat messages
+    schemas.push(TableSchema {
+        name: "ai_feedback".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "target_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "target_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "rating".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "comment".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "model".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "prompt_key".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_ai_feedback_target".to_string(), table_name: "ai_feedback".to_string(), columns: vec!["target_type".to_string(), "target_id".to_string()], is_unique: true },
+            IndexInfo { name: "idx_ai_feedback_model".to_string(), table_name: "ai_feedback".to_string(), columns: vec!["model".to_string()], is_unique: false },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_ai_feedback_timestamp".to_string(), table_name: "ai_feedback".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE ai_feedback SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
 
-/// Get current columns for a table
-pub async fn get_table_columns(conn: &Connection, table_name: &str) -> Result<Vec<ColumnInfo>> {
-    let mut columns = Vec::new();
-    let mut rows = conn
-        .prepare(&format!("PRAGMA table_info({})", table_name))
-        .await?
-        .query(libsql::params![])
-        .await?;
-    while let Some(row) = rows.next().await? {
-        columns.push(ColumnInfo {
-            name: row.get(1)?,
-            data_type: row.get(2)?,
-            is_nullable: row.get::<i32>(3)? == 0,
-            default_value: row.get(4)?,
-            is_primary_key: row.get::<i32>(5)? == 1,
-        });
-    }
-    Ok(columns)
+    // Durable user facts extracted from chats (confirmed by the user before use)
+    schemas.push(TableSchema {
+        name: "user_memory".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "content".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "category".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'proposed'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "source_session_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "source_message_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "confirmed_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_used_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_user_memory_status".to_string(), table_name: "user_memory".to_string(), columns: vec!["status".to_string()], is_unique: false },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_user_memory_timestamp".to_string(), table_name: "user_memory".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE user_memory SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Stored analytics metric sets for "what changed" comparisons
+    schemas.push(TableSchema {
+        name: "analytics_snapshots".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "label".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "time_range".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "core_metrics".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "risk_metrics".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "performance_metrics".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_analytics_snapshots_created_at".to_string(), table_name: "analytics_snapshots".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Recurring AI report schedules (cron expressions, UTC)
+    schemas.push(TableSchema {
+        name: "report_schedules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "cron_expression".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "report_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'comprehensive'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "time_range".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'30d'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_active".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("1".to_string()), is_primary_key: false },
+            ColumnInfo { name: "next_run_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_run_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_report_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_report_schedules_next_run".to_string(), table_name: "report_schedules".to_string(), columns: vec!["is_active".to_string(), "next_run_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_report_schedules_timestamp".to_string(), table_name: "report_schedules".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE report_schedules SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Last storage quota warning sent per resource (80/100%), so each threshold notifies once
+    schemas.push(TableSchema {
+        name: "storage_quota_warnings".to_string(),
+        columns: vec![
+            ColumnInfo { name: "resource".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "threshold".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "notified_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // Daily realized P&L for closed stock trades, kept in sync by triggers on stocks
+    schemas.push(TableSchema {
+        name: "daily_pnl_stocks".to_string(),
+        columns: vec![
+            ColumnInfo { name: "trade_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "realized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "trade_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "win_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "loss_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "gross_profit".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "gross_loss".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: daily_aggregates::aggregate_triggers(DailySource::Stocks),
+    });
+
+    // Daily realized P&L for closed option trades, kept in sync by triggers on options
+    schemas.push(TableSchema {
+        name: "daily_pnl_options".to_string(),
+        columns: vec![
+            ColumnInfo { name: "trade_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "realized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "trade_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "win_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "loss_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "gross_profit".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "gross_loss".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: daily_aggregates::aggregate_triggers(DailySource::Options),
+    });
+
+    // Stop-based open risk per calendar day; triggers on stocks clear it, reads rebuild it
+    schemas.push(TableSchema {
+        name: "daily_open_risk".to_string(),
+        columns: vec![
+            ColumnInfo { name: "trade_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "open_risk".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "open_positions".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: daily_aggregates::open_risk_triggers(),
+    });
+
+    // Brokerage sync history (one row per manual or scheduled sync run)
+    schemas.push(TableSchema {
+        name: "brokerage_sync_runs".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "connection_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "triggered_by".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'manual'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'running'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "accounts_synced".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "holdings_synced".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "transactions_imported".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "cursor_before".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "cursor_after".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "error".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "started_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+            ColumnInfo { name: "finished_at".to_string(), data_type: "TIMESTAMP".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_brokerage_sync_runs_connection_id".to_string(), table_name: "brokerage_sync_runs".to_string(), columns: vec!["connection_id".to_string()], is_unique: false },
+            IndexInfo { name: "idx_brokerage_sync_runs_started_at".to_string(), table_name: "brokerage_sync_runs".to_string(), columns: vec!["started_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Consecutive-loss circuit breaker setting (single row, id 'default')
+    schemas.push(TableSchema {
+        name: "loss_streak_rules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "max_consecutive_losses".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("3".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_loss_streak_rules_timestamp".to_string(), table_name: "loss_streak_rules".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE loss_streak_rules SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Circuit breaker trips, at most one per trading day
+    schemas.push(TableSchema {
+        name: "loss_streak_events".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "trade_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "consecutive_losses".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "threshold".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "realized_loss".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_loss_streak_events_trade_date".to_string(), table_name: "loss_streak_events".to_string(), columns: vec!["trade_date".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Trade drafts parsed from forwarded broker confirmation emails, pending user confirmation
+    schemas.push(TableSchema {
+        name: "trade_drafts".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "source".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'email'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "broker".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "draft".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pending'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "email_subject".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "received_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_trade_drafts_status".to_string(), table_name: "trade_drafts".to_string(), columns: vec!["status".to_string(), "received_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_trade_drafts_timestamp".to_string(), table_name: "trade_drafts".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE trade_drafts SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Drawdown-aware sizing rule (single row, id 'default'); tiers is a JSON array of {drawdown_pct, size_multiplier}
+    schemas.push(TableSchema {
+        name: "sizing_rules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "account_size".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "base_risk_per_trade".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "tiers".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_sizing_rules_timestamp".to_string(), table_name: "sizing_rules".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE sizing_rules SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Drawdown alert setting and tracked equity peak (single row, id 'default')
+    schemas.push(TableSchema {
+        name: "drawdown_alert_rules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("true".to_string()), is_primary_key: false },
+            ColumnInfo { name: "thresholds".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("[5,10]".to_string()), is_primary_key: false },
+            ColumnInfo { name: "peak_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "peak_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_drawdown_alert_rules_timestamp".to_string(), table_name: "drawdown_alert_rules".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE drawdown_alert_rules SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Drawdown threshold crossings, one per threshold per peak
+    schemas.push(TableSchema {
+        name: "drawdown_events".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "threshold_pct".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "drawdown_pct".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "equity".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "peak_equity".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "peak_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("''".to_string()), is_primary_key: false },
+            ColumnInfo { name: "realized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "unrealized_pnl".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_drawdown_events_created_at".to_string(), table_name: "drawdown_events".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+            IndexInfo { name: "idx_drawdown_events_peak_at".to_string(), table_name: "drawdown_events".to_string(), columns: vec!["peak_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Focus (pomodoro) sessions, one row per session, grouped by the user's local day
+    schemas.push(TableSchema {
+        name: "focus_sessions".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "session_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "started_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "ended_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "planned_minutes".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "label".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "notes".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "focus_rating".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_focus_sessions_session_date".to_string(), table_name: "focus_sessions".to_string(), columns: vec!["session_date".to_string()], is_unique: false },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_focus_sessions_timestamp".to_string(), table_name: "focus_sessions".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE focus_sessions SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // User-defined KPI formulas over the built-in analytics metrics
+    schemas.push(TableSchema {
+        name: "custom_metrics".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "formula".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_custom_metrics_name".to_string(), table_name: "custom_metrics".to_string(), columns: vec!["name".to_string()], is_unique: true },
+        ],
+        triggers: vec![ TriggerInfo { name: "update_custom_metrics_timestamp".to_string(), table_name: "custom_metrics".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE custom_metrics SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // White-label branding for shared reports, a single 'default' row
+    schemas.push(TableSchema {
+        name: "report_branding".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "display_name".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "logo_url".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "accent_color".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_report_branding_timestamp".to_string(), table_name: "report_branding".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE report_branding SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Per-user note data key, wrapped by the server master key (single row, id = 'default')
+    schemas.push(TableSchema {
+        name: "note_encryption_key".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "wrapped_key".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "nonce".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // Per-table retention windows enforced by the retention cron (NULL keeps rows forever)
+    schemas.push(TableSchema {
+        name: "retention_policies".to_string(),
+        columns: vec![
+            ColumnInfo { name: "table_name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "retention_days".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // Playbook drafts proposed by setup discovery, pending user review
+    schemas.push(TableSchema {
+        name: "playbook_drafts".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "rules".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "stock_trade_ids".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "option_trade_ids".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "profile".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pending'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "playbook_id".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_playbook_drafts_status".to_string(), table_name: "playbook_drafts".to_string(), columns: vec!["status".to_string(), "created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Monthly P&L and trade count goals; a month without its own goal uses the latest earlier one
+    schemas.push(TableSchema {
+        name: "monthly_goals".to_string(),
+        columns: vec![
+            ColumnInfo { name: "month".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "pnl_target".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_target".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // User-defined automations: a trigger, conditions on the event and actions to run
+    schemas.push(TableSchema {
+        name: "automation_rules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trigger_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "conditions".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "actions".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("1".to_string()), is_primary_key: false },
+            ColumnInfo { name: "trigger_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_triggered_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_automation_rules_trigger".to_string(), table_name: "automation_rules".to_string(), columns: vec!["trigger_type".to_string(), "is_enabled".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // One row per rule and event subject; the unique index keeps a rule from firing twice for the same trade, tag or insight
+    schemas.push(TableSchema {
+        name: "automation_runs".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "rule_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "subject".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "detail".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_automation_runs_rule_subject".to_string(), table_name: "automation_runs".to_string(), columns: vec!["rule_id".to_string(), "subject".to_string()], is_unique: true },
+            IndexInfo { name: "idx_automation_runs_created_at".to_string(), table_name: "automation_runs".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Old trades moved to object storage, with a monthly P&L summary of what was archived
+    schemas.push(TableSchema {
+        name: "trade_archives".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "cutoff".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'archived'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "object_path".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "byte_size".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "summary".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'{}'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "restored_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_trade_archives_created_at".to_string(), table_name: "trade_archives".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Rows created by the onboarding demo-data seed, so teardown removes only those
+    schemas.push(TableSchema {
+        name: "demo_data_rows".to_string(),
+        columns: vec![
+            ColumnInfo { name: "table_name".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "row_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_demo_data_rows_unique".to_string(), table_name: "demo_data_rows".to_string(), columns: vec!["table_name".to_string(), "row_id".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Guided onboarding checklist; a step without a row is still pending
+    schemas.push(TableSchema {
+        name: "onboarding_steps".to_string(),
+        columns: vec![
+            ColumnInfo { name: "step".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "source".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'manual'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "completed_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // Earnings call summaries of calls a trade was held through
+    schemas.push(TableSchema {
+        name: "trade_earnings_summaries".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "trade_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "symbol".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "quarter".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "year".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "call_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "summary".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_trade_earnings_summaries_unique".to_string(), table_name: "trade_earnings_summaries".to_string(), columns: vec!["trade_type".to_string(), "trade_id".to_string(), "quarter".to_string(), "year".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Trade entry context (entry-day OHLC, spread estimate, implied volatility)
+    schemas.push(TableSchema {
+        name: "trade_entry_context".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "trade_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "symbol".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "session_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "open".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "high".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "low".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "close".to_string(), data_type: "REAL".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "volume".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "spread_estimate_pct".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "implied_volatility".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "entry_price_estimated".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_trade_entry_context_trade".to_string(), table_name: "trade_entry_context".to_string(), columns: vec!["trade_type".to_string(), "trade_id".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Data integrity issues found by the nightly checks
+    schemas.push(TableSchema {
+        name: "data_issues".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "issue_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "message".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "suggestion".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'open'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "detected_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("CURRENT_TIMESTAMP".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_data_issues_trade".to_string(), table_name: "data_issues".to_string(), columns: vec!["issue_type".to_string(), "trade_type".to_string(), "trade_id".to_string()], is_unique: true },
+            IndexInfo { name: "idx_data_issues_status".to_string(), table_name: "data_issues".to_string(), columns: vec!["status".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Imported option legs grouped into a multi-leg strategy by the recognizer
+    schemas.push(TableSchema {
+        name: "option_strategy_legs".to_string(),
+        columns: vec![
+            ColumnInfo { name: "option_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "group_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "strategy".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_option_strategy_legs_group".to_string(), table_name: "option_strategy_legs".to_string(), columns: vec!["group_id".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Per-playbook performance targets and the evaluator's alert state
+    schemas.push(TableSchema {
+        name: "playbook_targets".to_string(),
+        columns: vec![
+            ColumnInfo { name: "playbook_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "min_win_rate".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "min_avg_r".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "window_trades".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("20".to_string()), is_primary_key: false },
+            ColumnInfo { name: "consecutive_trades".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("3".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("1".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_degraded".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_alerted_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "last_evaluated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    // Markup layers (arrows, lines, text) drawn over trade images
+    schemas.push(TableSchema {
+        name: "image_annotations".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "image_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "layers".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'[]'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "version".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "is_deleted".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_image_annotations_image_id".to_string(), table_name: "image_annotations".to_string(), columns: vec!["image_id".to_string()], is_unique: true },
+            IndexInfo { name: "idx_image_annotations_version".to_string(), table_name: "image_annotations".to_string(), columns: vec!["version".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Monthly performance statement PDFs kept in object storage
+    schemas.push(TableSchema {
+        name: "statements".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "month".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "object_path".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "size_bytes".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "summary".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'{}'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_statements_month".to_string(), table_name: "statements".to_string(), columns: vec!["month".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Annual year-in-review PDFs kept in object storage
+    schemas.push(TableSchema {
+        name: "year_reviews".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "year".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "object_path".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "size_bytes".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "summary".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'{}'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "narrative".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "share_nonce".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_year_reviews_year".to_string(), table_name: "year_reviews".to_string(), columns: vec!["year".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // Daily trade limit setting (single row, id 'default')
+    schemas.push(TableSchema {
+        name: "overtrading_rules".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "is_enabled".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false },
+            ColumnInfo { name: "max_trades_per_day".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("10".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![ TriggerInfo { name: "update_overtrading_rules_timestamp".to_string(), table_name: "overtrading_rules".to_string(), event: "UPDATE".to_string(), timing: "AFTER".to_string(), action: "UPDATE overtrading_rules SET updated_at = datetime('now') WHERE id = NEW.id".to_string() } ],
+    });
+
+    // Days the trade limit was exceeded, one row per trading day
+    schemas.push(TableSchema {
+        name: "overtrading_events".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "trade_date".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "trade_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "threshold".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_overtrading_events_trade_date".to_string(), table_name: "overtrading_events".to_string(), columns: vec!["trade_date".to_string()], is_unique: true },
+        ],
+        triggers: vec![],
+    });
+
+    // AI insight recommendations with metrics the month before and after, and whether the user acted
+    schemas.push(TableSchema {
+        name: "recommendation_outcomes".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "insight_id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "recommendation_index".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "recommendation".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "status".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pending'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "acted_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "baseline".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "follow_up".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "score".to_string(), data_type: "REAL".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "evaluated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("(datetime('now'))".to_string()), is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_recommendation_outcomes_insight".to_string(), table_name: "recommendation_outcomes".to_string(), columns: vec!["insight_id".to_string(), "recommendation_index".to_string()], is_unique: true },
+            IndexInfo { name: "idx_recommendation_outcomes_created_at".to_string(), table_name: "recommendation_outcomes".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // Rows removed by a destructive bulk action, kept so the action can be undone for 24 hours
+    schemas.push(TableSchema {
+        name: "bulk_operations".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "kind".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "description".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "row_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "manifest".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "expires_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "undone_at".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_bulk_operations_created_at".to_string(), table_name: "bulk_operations".to_string(), columns: vec!["created_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // CSV files uploaded for import, held between preview and commit; the id is the mapping token
+    schemas.push(TableSchema {
+        name: "import_previews".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "file_name".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "content".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "row_count".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "expires_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_import_previews_expires_at".to_string(), table_name: "import_previews".to_string(), columns: vec!["expires_at".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // External links (news, tweets, videos) attached to a stock or option trade
+    schemas.push(TableSchema {
+        name: "trade_links".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "stock_trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "option_trade_id".to_string(), data_type: "INTEGER".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "url".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "title".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "link_type".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'article'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "created_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![
+            IndexInfo { name: "idx_trade_links_stock_trade_id".to_string(), table_name: "trade_links".to_string(), columns: vec!["stock_trade_id".to_string()], is_unique: false },
+            IndexInfo { name: "idx_trade_links_option_trade_id".to_string(), table_name: "trade_links".to_string(), columns: vec!["option_trade_id".to_string()], is_unique: false },
+        ],
+        triggers: vec![],
+    });
+
+    // End-of-day email digest preferences, one row
+    schemas.push(TableSchema {
+        name: "digest_settings".to_string(),
+        columns: vec![
+            ColumnInfo { name: "id".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: None, is_primary_key: true },
+            ColumnInfo { name: "enabled".to_string(), data_type: "BOOLEAN".to_string(), is_nullable: false, default_value: Some("false".to_string()), is_primary_key: false },
+            ColumnInfo { name: "send_hour".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("17".to_string()), is_primary_key: false },
+            ColumnInfo { name: "utc_offset_minutes".to_string(), data_type: "INTEGER".to_string(), is_nullable: false, default_value: Some("0".to_string()), is_primary_key: false },
+            ColumnInfo { name: "sections".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: Some("'pnl,mismatches,reminders,earnings'".to_string()), is_primary_key: false },
+            ColumnInfo { name: "last_sent_date".to_string(), data_type: "TEXT".to_string(), is_nullable: true, default_value: None, is_primary_key: false },
+            ColumnInfo { name: "updated_at".to_string(), data_type: "TEXT".to_string(), is_nullable: false, default_value: None, is_primary_key: false },
+        ],
+        indexes: vec![],
+        triggers: vec![],
+    });
+
+    schemas
+}
+
+/// Initialize the schema version table if needed
+pub async fn initialize_schema_version_table(conn: &Connection) -> Result<()> {
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_version (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            version TEXT NOT NULL,
+            description TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+        libsql::params![],
+    ).await?;
+    Ok(())
+}
+
+/// Update schema version in the database
+pub async fn update_schema_version(conn: &Connection, version: &SchemaVersion) -> Result<()> {
+    conn.execute(
+        "INSERT INTO schema_version (version, description, created_at) VALUES (?, ?, ?)",
+        libsql::params![version.version.clone(), version.description.clone(), version.created_at.clone()],
+    ).await?;
+    Ok(())
+}
+
+/// Ensure indexes for a table
+pub async fn ensure_indexes(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
+    for index in &table_schema.indexes {
+        let mut rows = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type='index' AND name=?")
+            .await?
+            .query(libsql::params![index.name.clone()])
+            .await?;
+        if rows.next().await?.is_none() {
+            let create_index_sql = format!(
+                "CREATE INDEX IF NOT EXISTS {} ON {} ({})",
+                index.name,
+                index.table_name,
+                index.columns.join(", ")
+            );
+            conn.execute(&create_index_sql, libsql::params![]).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Ensure triggers for a table
+pub async fn ensure_triggers(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
+    for trigger in &table_schema.triggers {
+        let mut rows = conn
+            .prepare("SELECT sql FROM sqlite_master WHERE type='trigger' AND name=?")
+            .await?
+            .query(libsql::params![trigger.name.clone()])
+            .await?;
+        let existing: Option<String> = match rows.next().await? {
+            Some(row) => Some(row.get::<Option<String>>(0)?.unwrap_or_default()),
+            None => None,
+        };
+        drop(rows);
+        // A trigger whose definition changed (e.g. a P&L formula) is replaced, not kept
+        let stale = existing.as_ref().is_some_and(|sql| !sql.contains(&trigger.event) || !sql.contains(&trigger.action));
+        if stale {
+            info!("Recreating trigger '{}' with its current definition", trigger.name);
+            conn.execute(&format!("DROP TRIGGER IF EXISTS {}", trigger.name), libsql::params![]).await?;
+        }
+        if existing.is_none() || stale {
+            let create_trigger_sql = format!(
+                "CREATE TRIGGER IF NOT EXISTS {} {} {} ON {} FOR EACH ROW BEGIN {}; END",
+                trigger.name,
+                trigger.timing,
+                trigger.event,
+                trigger.table_name,
+                trigger.action
+            );
+            conn.execute(&create_trigger_sql, libsql::params![]).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Get list of current tables in the database
+pub async fn get_current_tables(conn: &Connection) -> Result<Vec<String>> {
+    let mut tables = Vec::new();
+    let mut rows = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'")
+        .await?
+        .query(libsql::params![])
+        .await?;
+    while let Some(row) = rows.next().await? { tables.push(row.get(0)?); }
+    Ok(tables)
+}
+
+/// Create a table based on schema definition
+pub async fn create_table(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
+    let mut create_sql = format!("CREATE TABLE IF NOT EXISTS {} (", table_schema.name);
+    let primary_keys: Vec<String> = table_schema.columns.iter().filter(|c| c.is_primary_key).map(|c| c.name.clone()).collect();
+    let column_definitions: Vec<String> = table_schema.columns.iter().map(|col| {
+        let mut def = format!("{} {}", col.name, col.data_type);
+        if col.is_primary_key && primary_keys.len() == 1 {
+            if col.data_type.to_uppercase().contains("INTEGER") { def.push_str(" PRIMARY KEY AUTOINCREMENT"); } else { def.push_str(" PRIMARY KEY"); }
+        } else if !col.is_nullable { def.push_str(" NOT NULL"); }
+        if let Some(default) = &col.default_value { def.push_str(&format!(" DEFAULT {}", default)); }
+        def
+    }).collect();
+    create_sql.push_str(&column_definitions.join(", "));
+    if primary_keys.len() > 1 { create_sql.push_str(&format!(", PRIMARY KEY ({})", primary_keys.join(", "))); }
+    create_sql.push(')');
+    conn.execute(&create_sql, libsql::params![]).await?;
+    Ok(())
+}
+
+/// Get current columns for a table
+pub async fn get_table_columns(conn: &Connection, table_name: &str) -> Result<Vec<ColumnInfo>> {
+    let mut columns = Vec::new();
+    let mut rows = conn
+        .prepare(&format!("PRAGMA table_info({})", table_name))
+        .await?
+        .query(libsql::params![])
+        .await?;
+    while let Some(row) = rows.next().await? {
+        columns.push(ColumnInfo {
+            name: row.get(1)?,
+            data_type: row.get(2)?,
+            is_nullable: row.get::<i32>(3)? == 0,
+            default_value: row.get(4)?,
+            is_primary_key: row.get::<i32>(5)? == 1,
+        });
+    }
+    Ok(columns)
+}
+
+/// Prefix of the snapshot tables taken before a table rebuild; sync never drops these
+pub const SCHEMA_BACKUP_PREFIX: &str = "schema_backup_";
+/// Snapshots older than this are removed by the next sync
+const SCHEMA_BACKUP_RETENTION_DAYS: i64 = 7;
+const SCHEMA_BACKUP_TIMESTAMP: &str = "%Y%m%d%H%M%S";
+
+/// Tables the sync leaves alone even though get_expected_schema doesn't list them
+pub fn is_protected_table(name: &str) -> bool {
+    name == "schema_version" || name == "sqlite_sequence" || name.starts_with(SCHEMA_BACKUP_PREFIX)
+}
+
+/// An obsolete column and how many rows still hold a value in it
+#[derive(Debug, serde::Serialize, Clone)]
+pub struct RemovedColumn {
+    pub name: String,
+    pub non_null_rows: i64,
+}
+
+/// Changes needed to bring one existing table in line with the expected schema
+#[derive(Debug, serde::Serialize, Clone, Default)]
+pub struct TableDiff {
+    pub table: String,
+    pub row_count: i64,
+    pub added_columns: Vec<String>,
+    pub removed_columns: Vec<RemovedColumn>,
+    /// NOT NULL columns the expected schema makes nullable
+    pub relaxed_columns: Vec<String>,
+}
+
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_columns.is_empty() && self.removed_columns.is_empty() && self.relaxed_columns.is_empty()
+    }
+
+    /// Removed columns that still hold data; only a forced sync drops them
+    pub fn blocked_columns(&self) -> Vec<String> {
+        self.removed_columns.iter().filter(|c| c.non_null_rows > 0).map(|c| c.name.clone()).collect()
+    }
+}
+
+/// A table the sync would drop because get_expected_schema no longer lists it
+#[derive(Debug, serde::Serialize, Clone)]
+pub struct DroppedTable {
+    pub name: String,
+    pub row_count: i64,
+}
+
+/// Dry-run output: everything a sync would change in one database
+#[derive(Debug, serde::Serialize, Clone, Default)]
+pub struct SchemaDiff {
+    pub created_tables: Vec<String>,
+    pub dropped_tables: Vec<DroppedTable>,
+    pub changed_tables: Vec<TableDiff>,
+}
+
+/// Added, removed and relaxed column names; primary keys are never removed
+fn column_changes(current: &[ColumnInfo], expected: &TableSchema) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let added = expected.columns.iter()
+        .filter(|e| !current.iter().any(|c| c.name == e.name))
+        .map(|e| e.name.clone())
+        .collect();
+    let removed = current.iter()
+        .filter(|c| !c.is_primary_key && !expected.columns.iter().any(|e| e.name == c.name))
+        .map(|c| c.name.clone())
+        .collect();
+    // NOT NULL can't be dropped with ALTER TABLE either, so relaxed columns also need a rebuild
+    let relaxed = current.iter()
+        .filter(|c| !c.is_nullable && !c.is_primary_key)
+        .filter(|c| expected.columns.iter().any(|e| e.name == c.name && e.is_nullable))
+        .map(|c| c.name.clone())
+        .collect();
+    (added, removed, relaxed)
 }
 
-/// Update table schema if needed
+async fn count_rows(conn: &Connection, sql: &str) -> Result<i64> {
+    let mut rows = conn.prepare(sql).await?.query(libsql::params![]).await?;
+    Ok(match rows.next().await? { Some(row) => row.get(0)?, None => 0 })
+}
+
+/// Compare an existing table against its expected schema without changing anything
+pub async fn diff_table(conn: &Connection, table_schema: &TableSchema) -> Result<TableDiff> {
+    let current_columns = get_table_columns(conn, &table_schema.name).await?;
+    let (added_columns, removed, relaxed_columns) = column_changes(&current_columns, table_schema);
+    let mut removed_columns = Vec::new();
+    for name in removed {
+        let non_null_rows = count_rows(conn, &format!("SELECT COUNT(*) FROM {} WHERE {} IS NOT NULL", table_schema.name, name)).await?;
+        removed_columns.push(RemovedColumn { name, non_null_rows });
+    }
+    Ok(TableDiff {
+        table: table_schema.name.clone(),
+        row_count: count_rows(conn, &format!("SELECT COUNT(*) FROM {}", table_schema.name)).await?,
+        added_columns,
+        removed_columns,
+        relaxed_columns,
+    })
+}
+
+/// Dry run of a full sync against the expected schema
+pub async fn plan_schema_sync(conn: &Connection, expected_schema: &[TableSchema]) -> Result<SchemaDiff> {
+    let current_tables = get_current_tables(conn).await?;
+    let mut diff = SchemaDiff::default();
+    for table_schema in expected_schema {
+        if !current_tables.contains(&table_schema.name) {
+            diff.created_tables.push(table_schema.name.clone());
+            continue;
+        }
+        let table_diff = diff_table(conn, table_schema).await?;
+        if !table_diff.is_empty() {
+            diff.changed_tables.push(table_diff);
+        }
+    }
+    for name in current_tables {
+        if !is_protected_table(&name) && !expected_schema.iter().any(|s| s.name == name) {
+            let row_count = count_rows(conn, &format!("SELECT COUNT(*) FROM {}", name)).await?;
+            diff.dropped_tables.push(DroppedTable { name, row_count });
+        }
+    }
+    Ok(diff)
+}
+
+fn schema_backup_name(table: &str, now: chrono::DateTime<chrono::Utc>) -> String {
+    format!("{}{}_{}", SCHEMA_BACKUP_PREFIX, table, now.format(SCHEMA_BACKUP_TIMESTAMP))
+}
+
+fn schema_backup_taken_at(name: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    let (_, stamp) = name.strip_prefix(SCHEMA_BACKUP_PREFIX)?.rsplit_once('_')?;
+    chrono::NaiveDateTime::parse_from_str(stamp, SCHEMA_BACKUP_TIMESTAMP).ok().map(|dt| dt.and_utc())
+}
+
+/// Drop rebuild snapshots past the retention window
+pub async fn prune_schema_backups(conn: &Connection) -> Result<()> {
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(SCHEMA_BACKUP_RETENTION_DAYS);
+    for name in get_current_tables(conn).await? {
+        if schema_backup_taken_at(&name).is_some_and(|taken_at| taken_at < cutoff) {
+            info!("Dropping expired schema backup {}", name);
+            conn.execute(&format!("DROP TABLE IF EXISTS {}", name), libsql::params![]).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Drop tables get_expected_schema no longer lists (schema.rs is the source of truth) and
+/// return the names dropped. Each table is snapshotted first; without `force`, tables that
+/// still hold rows are kept instead.
+pub async fn drop_unlisted_tables(conn: &Connection, expected_schema: &[TableSchema], force: bool) -> Result<Vec<String>> {
+    let mut dropped = Vec::new();
+    for table_name in get_current_tables(conn).await? {
+        if is_protected_table(&table_name) || expected_schema.iter().any(|s| s.name == table_name) {
+            continue;
+        }
+        let row_count = count_rows(conn, &format!("SELECT COUNT(*) FROM {}", table_name)).await?;
+        if row_count > 0 && !force {
+            log::warn!(
+                "Keeping table '{}' ({} rows): not in expected schema but still holds data (force the sync from the admin schema API to drop it)",
+                table_name, row_count
+            );
+            continue;
+        }
+
+        let backup_table = schema_backup_name(&table_name, chrono::Utc::now());
+        conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", backup_table, table_name), libsql::params![]).await?;
+        info!("Dropping table '{}' ({} rows, snapshot in {}) - not in expected schema", table_name, row_count, backup_table);
+
+        // Indexes and triggers go with the table; foreign keys are off so dependents don't block it
+        conn.execute("PRAGMA foreign_keys = OFF", libsql::params![]).await?;
+        let result = conn.execute(&format!("DROP TABLE IF EXISTS {}", table_name), libsql::params![]).await;
+        conn.execute("PRAGMA foreign_keys = ON", libsql::params![]).await?;
+        result?;
+        dropped.push(table_name);
+    }
+    Ok(dropped)
+}
+
+/// Update table schema if needed, keeping obsolete columns that still hold data
 pub async fn update_table_schema(conn: &Connection, table_schema: &TableSchema) -> Result<()> {
+    sync_table_schema(conn, table_schema, false).await.map(|_| ())
+}
+
+/// Bring a table in line with its expected schema and return what was found.
+///
+/// Columns are added in place. Removing a column or relaxing NOT NULL means rebuilding
+/// the table, which happens only after a snapshot copy is taken, and inside a transaction
+/// that rolls back unless every row made it across. Without `force`, obsolete columns
+/// that still hold data are kept (as nullable) instead of dropped.
+pub async fn sync_table_schema(conn: &Connection, table_schema: &TableSchema, force: bool) -> Result<TableDiff> {
     let current_columns = get_table_columns(conn, &table_schema.name).await?;
+    let diff = diff_table(conn, table_schema).await?;
     
     // Add missing columns
-    for expected_col in &table_schema.columns {
-        if !current_columns.iter().any(|c| c.name == expected_col.name) {
-            let mut alter_sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table_schema.name, expected_col.name, expected_col.data_type);
-            
-            // For NOT NULL columns without explicit defaults, provide appropriate defaults
-            if !expected_col.is_nullable {
-                if let Some(default) = &expected_col.default_value {
-                    alter_sql.push_str(&format!(" NOT NULL DEFAULT {}", default));
-                } else {
-                    // Provide default values for NOT NULL columns based on data type
-                    match expected_col.data_type.to_uppercase().as_str() {
-                        "TEXT" | "VARCHAR" => alter_sql.push_str(" NOT NULL DEFAULT ''"),
-                        "INTEGER" => alter_sql.push_str(" NOT NULL DEFAULT 0"),
-                        "REAL" | "DECIMAL" => alter_sql.push_str(" NOT NULL DEFAULT 0.0"),
-                        "BOOLEAN" => alter_sql.push_str(" NOT NULL DEFAULT false"),
-                        "DATE" => alter_sql.push_str(" NOT NULL DEFAULT '1970-01-01'"),
-                        "TIME" => alter_sql.push_str(" NOT NULL DEFAULT '00:00:00'"),
-                        _ => alter_sql.push_str(" NOT NULL DEFAULT ''"),
-                    }
+    for expected_col in table_schema.columns.iter().filter(|c| diff.added_columns.contains(&c.name)) {
+        let mut alter_sql = format!("ALTER TABLE {} ADD COLUMN {} {}", table_schema.name, expected_col.name, expected_col.data_type);
+        
+        // For NOT NULL columns without explicit defaults, provide appropriate defaults
+        if !expected_col.is_nullable {
+            if let Some(default) = &expected_col.default_value {
+                alter_sql.push_str(&format!(" NOT NULL DEFAULT {}", default));
+            } else {
+                // Provide default values for NOT NULL columns based on data type
+                match expected_col.data_type.to_uppercase().as_str() {
+                    "TEXT" | "VARCHAR" => alter_sql.push_str(" NOT NULL DEFAULT ''"),
+                    "INTEGER" => alter_sql.push_str(" NOT NULL DEFAULT 0"),
+                    "REAL" | "DECIMAL" => alter_sql.push_str(" NOT NULL DEFAULT 0.0"),
+                    "BOOLEAN" => alter_sql.push_str(" NOT NULL DEFAULT false"),
+                    "DATE" => alter_sql.push_str(" NOT NULL DEFAULT '1970-01-01'"),
+                    "TIME" => alter_sql.push_str(" NOT NULL DEFAULT '00:00:00'"),
+                    _ => alter_sql.push_str(" NOT NULL DEFAULT ''"),
                 }
-            } else if let Some(default) = &expected_col.default_value {
-                alter_sql.push_str(&format!(" DEFAULT {}", default));
             }
-            
-            conn.execute(&alter_sql, libsql::params![]).await?;
+        } else if let Some(default) = &expected_col.default_value {
+            alter_sql.push_str(&format!(" DEFAULT {}", default));
         }
+        
+        conn.execute(&alter_sql, libsql::params![]).await?;
+    }
+    
+    let kept = if force { Vec::new() } else { diff.blocked_columns() };
+    if !kept.is_empty() {
+        log::warn!(
+            "Keeping obsolete columns {:?} on {}: they still hold data (force the sync from the admin schema API to drop them)",
+            kept, table_schema.name
+        );
     }
+    let dropped: Vec<&str> = diff.removed_columns.iter().map(|c| c.name.as_str()).filter(|name| !kept.iter().any(|k| k.as_str() == *name)).collect();
+    if dropped.is_empty() && diff.relaxed_columns.is_empty() {
+        return Ok(diff);
+    }
+    log::info!(
+        "Rebuilding {} ({} rows; dropping columns: {:?}, now nullable: {:?})",
+        table_schema.name, diff.row_count, dropped, diff.relaxed_columns
+    );
+    
+    // Kept columns ride along as nullable, since nothing writes them anymore
+    let mut target = table_schema.clone();
+    target.columns.extend(current_columns.iter().filter(|c| kept.contains(&c.name)).map(|c| ColumnInfo {
+        is_nullable: true,
+        is_primary_key: false,
+        ..c.clone()
+    }));
+    
+    // SQLite doesn't support DROP COLUMN directly, so the table is recreated from a
+    // snapshot that outlives the sync for SCHEMA_BACKUP_RETENTION_DAYS
+    let backup_table = schema_backup_name(&table_schema.name, chrono::Utc::now());
+    conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", backup_table, table_schema.name), libsql::params![]).await?;
+    
+    let tx = conn.transaction().await?;
+    tx.execute(&format!("DROP TABLE {}", table_schema.name), libsql::params![]).await?;
+    create_table(&tx, &target).await?;
     
-    // Remove columns that are not in the expected schema
-    let expected_names: std::collections::HashSet<String> = table_schema.columns.iter().map(|c| c.name.clone()).collect();
-    let columns_to_remove: Vec<String> = current_columns.iter()
-        .filter(|c| !expected_names.contains(&c.name) && !c.is_primary_key)
+    // Copy data back (only for columns that exist in both schemas)
+    let common_columns: Vec<String> = current_columns.iter()
+        .filter(|c| target.columns.iter().any(|t| t.name == c.name))
         .map(|c| c.name.clone())
         .collect();
+    if !common_columns.is_empty() {
+        let columns_str = common_columns.join(", ");
+        tx.execute(&format!("INSERT INTO {} ({}) SELECT {} FROM {}",
+            table_schema.name, columns_str, columns_str, backup_table), libsql::params![]).await?;
+    }
     
-    if !columns_to_remove.is_empty() {
-        log::info!("Removing obsolete columns from {}: {:?}", table_schema.name, columns_to_remove);
-        
-        // SQLite doesn't support DROP COLUMN directly, so we need to recreate the table
-        // First, create a backup of existing data
-        let backup_table = format!("{}_backup", table_schema.name);
-        conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", backup_table, table_schema.name), libsql::params![]).await?;
-        
-        // Drop the original table
-        conn.execute(&format!("DROP TABLE {}", table_schema.name), libsql::params![]).await?;
-        
-        // Recreate the table with the correct schema
-        create_table(conn, table_schema).await?;
-        
-        // Copy data back (only for columns that exist in both schemas)
-        let common_columns: Vec<String> = current_columns.iter()
-            .filter(|c| expected_names.contains(&c.name))
-            .map(|c| c.name.clone())
-            .collect();
-        
-        if !common_columns.is_empty() {
-            let columns_str = common_columns.join(", ");
-            conn.execute(&format!("INSERT INTO {} ({}) SELECT {} FROM {}", 
-                table_schema.name, columns_str, columns_str, backup_table), libsql::params![]).await?;
-        }
-        
-        // Drop the backup table
-        conn.execute(&format!("DROP TABLE {}", backup_table), libsql::params![]).await?;
-        
-        // Recreate indexes and triggers
-        ensure_indexes(conn, table_schema).await?;
-        ensure_triggers(conn, table_schema).await?;
+    let copied = count_rows(&tx, &format!("SELECT COUNT(*) FROM {}", table_schema.name)).await?;
+    if copied != diff.row_count {
+        tx.rollback().await?;
+        anyhow::bail!(
+            "Rebuild of {} copied {} of {} rows; rolled back (snapshot kept in {})",
+            table_schema.name, copied, diff.row_count, backup_table
+        );
     }
     
+    // Recreate indexes and triggers
+    ensure_indexes(&tx, table_schema).await?;
+    ensure_triggers(&tx, table_schema).await?;
+    tx.commit().await?;
+    
+    Ok(diff)
+}
+
+/// A table whose CHECK constraints changed after databases were created with the old ones.
+/// SQLite can't alter a constraint in place, so a stale table is rebuilt from `create_sql`.
+struct ConstraintRebuild {
+    table: &'static str,
+    /// DDL for the table under the given name
+    create_sql: fn(&str) -> String,
+}
+
+const CONSTRAINT_REBUILDS: &[ConstraintRebuild] = &[
+    ConstraintRebuild { table: "ai_insights", create_sql: ai_insights_table_sql },
+    ConstraintRebuild { table: "ai_reports", create_sql: ai_reports_table_sql },
+];
+
+/// Columns that used to be written as JSON strings (`"7d"` with the quotes) and now hold
+/// the plain value
+const UNQUOTED_COLUMNS: &[(&str, &str)] = &[
+    ("ai_insights", "time_range"),
+    ("ai_reports", "time_range"),
+    ("ai_reports", "report_type"),
+    ("insight_generation_tasks", "time_range"),
+];
+
+/// The CHECK clauses of a CREATE TABLE statement, whitespace-normalized
+fn check_clauses(sql: &str) -> Vec<String> {
+    let upper = sql.to_uppercase();
+    let mut clauses = Vec::new();
+    let mut from = 0;
+    while let Some(found) = upper[from..].find("CHECK") {
+        let start = from + found;
+        let Some(open) = sql[start..].find('(').map(|i| start + i) else { break };
+        let mut depth = 0;
+        let mut end = open;
+        for (i, c) in sql[open..].char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                end = open + i;
+                break;
+            }
+        }
+        clauses.push(sql[open..=end].split_whitespace().collect::<Vec<_>>().join(" "));
+        from = end + 1;
+    }
+    clauses
+}
+
+/// A table is stale when it carries a constraint the current DDL no longer has. Tables
+/// created from get_expected_schema have no CHECKs at all and are left as they are.
+fn has_stale_constraints(current_sql: &str, expected_sql: &str) -> bool {
+    let expected = check_clauses(expected_sql);
+    check_clauses(current_sql).iter().any(|clause| !expected.contains(clause))
+}
+
+async fn table_sql(conn: &Connection, table: &str) -> Result<Option<String>> {
+    let mut rows = conn
+        .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name=?")
+        .await?
+        .query(libsql::params![table])
+        .await?;
+    Ok(match rows.next().await? { Some(row) => row.get(0)?, None => None })
+}
+
+/// Rows written before insight types were stored plain hold the JSON-quoted variant name
+async fn normalize_insight_types(conn: &Connection) -> Result<()> {
+    if table_sql(conn, "ai_insights").await?.is_none() {
+        return Ok(());
+    }
+    for insight_type in InsightType::ALL {
+        conn.execute(
+            "UPDATE ai_insights SET insight_type = ? WHERE insight_type = ?",
+            libsql::params![insight_type.as_str(), serde_json::to_string(&insight_type)?],
+        ).await?;
+    }
+    Ok(())
+}
+
+async fn unquote_stored_values(conn: &Connection) -> Result<()> {
+    for (table, column) in UNQUOTED_COLUMNS {
+        if table_sql(conn, table).await?.is_none() {
+            continue;
+        }
+        conn.execute(
+            &format!("UPDATE {table} SET {column} = substr({column}, 2, length({column}) - 2) WHERE {column} LIKE '\"%\"'"),
+            libsql::params![],
+        ).await?;
+    }
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Rebuild every table whose constraints went stale and return their names. Like a column
+/// rebuild, a snapshot is taken first and the copy rolls back unless every row made it.
+pub async fn rebuild_stale_constraints(conn: &Connection) -> Result<Vec<String>> {
+    normalize_insight_types(conn).await?;
+    unquote_stored_values(conn).await?;
+    let mut rebuilt = Vec::new();
+    for rebuild in CONSTRAINT_REBUILDS {
+        let Some(current_sql) = table_sql(conn, rebuild.table).await? else { continue };
+        if !has_stale_constraints(&current_sql, &(rebuild.create_sql)(rebuild.table)) {
+            continue;
+        }
+        info!("Rebuilding {} to pick up its current constraints", rebuild.table);
+        rebuild_table(conn, rebuild).await?;
+        rebuilt.push(rebuild.table.to_string());
+    }
+    Ok(rebuilt)
+}
+
+async fn rebuild_table(conn: &Connection, rebuild: &ConstraintRebuild) -> Result<()> {
+    let table = rebuild.table;
+    let current_columns = get_table_columns(conn, table).await?;
+    let row_count = count_rows(conn, &format!("SELECT COUNT(*) FROM {}", table)).await?;
+    let backup_table = schema_backup_name(table, chrono::Utc::now());
+    conn.execute(&format!("CREATE TABLE {} AS SELECT * FROM {}", backup_table, table), libsql::params![]).await?;
+
+    // Dropping the old table must not cascade into rows that reference it
+    conn.execute("PRAGMA foreign_keys = OFF", libsql::params![]).await?;
+    let result = in_transaction(conn, async |tx: &Connection| -> Result<()> {
+        let staging = format!("{}_rebuild", table);
+        tx.execute(&(rebuild.create_sql)(&staging), libsql::params![]).await?;
+        // Columns added after the DDL was written ride along as nullable
+        let staging_columns = get_table_columns(tx, &staging).await?;
+        for column in current_columns.iter().filter(|c| !staging_columns.iter().any(|s| s.name == c.name)) {
+            tx.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", staging, column.name, column.data_type), libsql::params![]).await?;
+        }
+        let columns = current_columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", ");
+        tx.execute(&format!("INSERT INTO {} ({}) SELECT {} FROM {}", staging, columns, columns, table), libsql::params![]).await?;
+        let copied = count_rows(tx, &format!("SELECT COUNT(*) FROM {}", staging)).await?;
+        if copied != row_count {
+            anyhow::bail!("Rebuild of {} copied {} of {} rows; rolled back (snapshot kept in {})", table, copied, row_count, backup_table);
+        }
+        tx.execute(&format!("DROP TABLE {}", table), libsql::params![]).await?;
+        tx.execute(&format!("ALTER TABLE {} RENAME TO {}", staging, table), libsql::params![]).await?;
+        Ok(())
+    })
+    .await;
+    conn.execute("PRAGMA foreign_keys = ON", libsql::params![]).await?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, is_nullable: bool, is_primary_key: bool) -> ColumnInfo {
+        ColumnInfo { name: name.to_string(), data_type: "TEXT".to_string(), is_nullable, default_value: None, is_primary_key }
+    }
+
+    #[test]
+    fn test_column_changes() {
+        let current = vec![column("id", false, true), column("symbol", false, false), column("legacy", true, false)];
+        let expected = TableSchema {
+            name: "stocks".to_string(),
+            columns: vec![column("id", false, true), column("symbol", true, false), column("notes", true, false)],
+            indexes: vec![],
+            triggers: vec![],
+        };
+        let (added, removed, relaxed) = column_changes(&current, &expected);
+        assert_eq!(added, vec!["notes"]);
+        assert_eq!(removed, vec!["legacy"]);
+        assert_eq!(relaxed, vec!["symbol"]);
+    }
+
+    #[test]
+    fn test_stale_constraints() {
+        let old = "CREATE TABLE ai_insights (id TEXT PRIMARY KEY, time_range TEXT NOT NULL CHECK (time_range IN ('7d', '30d')),\n insight_type TEXT CHECK (insight_type IN ('trading_patterns')))";
+        assert_eq!(check_clauses(old), vec!["(time_range IN ('7d', '30d'))", "(insight_type IN ('trading_patterns'))"]);
+        let current = ai_insights_table_sql("ai_insights");
+        assert!(has_stale_constraints(old, &current));
+        assert!(!has_stale_constraints(&current, &current));
+        // Tables created from get_expected_schema carry no CHECKs
+        assert!(!has_stale_constraints("CREATE TABLE ai_insights (id TEXT PRIMARY KEY, insight_type TEXT NOT NULL)", &current));
+    }
+
+    #[test]
+    fn test_schema_backup_names() {
+        let now = chrono::DateTime::parse_from_rfc3339("2026-10-16T08:30:00Z").unwrap().with_timezone(&chrono::Utc);
+        let name = schema_backup_name("trade_notes", now);
+        assert_eq!(name, "schema_backup_trade_notes_20261016083000");
+        assert_eq!(schema_backup_taken_at(&name), Some(now));
+        assert!(is_protected_table(&name));
+        assert!(!is_protected_table("trade_notes"));
+        assert_eq!(schema_backup_taken_at("trade_notes"), None);
+    }
+}
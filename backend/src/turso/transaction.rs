@@ -0,0 +1,34 @@
+//! Atomic multi-statement writes against a user database.
+//!
+//! Handlers that write several rows for one request (a trade and its tags, a resolved
+//! brokerage transaction and the trade it became) run them through [`in_transaction`] so a
+//! failure part way leaves nothing behind.
+
+use libsql::Connection;
+use log::warn;
+
+/// Run `work` inside a transaction: committed when it returns `Ok`, rolled back when it
+/// returns `Err`. The closure gets the transaction as a plain `&Connection`, so the model
+/// functions handlers already call work unchanged.
+///
+/// Side effects that can't be undone (vectorization, broadcasts, outbound calls) belong
+/// after this returns, not inside `work`.
+pub async fn in_transaction<T, E, F>(conn: &Connection, work: F) -> Result<T, E>
+where
+    F: AsyncFnOnce(&Connection) -> Result<T, E>,
+    E: From<libsql::Error>,
+{
+    let tx = conn.transaction().await?;
+    match work(&tx).await {
+        Ok(value) => {
+            tx.commit().await?;
+            Ok(value)
+        }
+        Err(e) => {
+            if let Err(rollback_error) = tx.rollback().await {
+                warn!("Failed to roll back transaction: {}", rollback_error);
+            }
+            Err(e)
+        }
+    }
+}
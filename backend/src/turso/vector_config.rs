@@ -39,6 +39,15 @@ impl VectorConfig {
     }
 }
 
+/// Model and embedding keys are optional with `AI_PROVIDER=mock`, which never calls the vendors
+fn api_key_from_env(name: &str) -> Result<String, Box<dyn std::error::Error>> {
+    match env::var(name) {
+        Ok(key) => Ok(key),
+        Err(_) if crate::service::ai_service::mock_model_client::mock_ai_enabled() => Ok(String::new()),
+        Err(_) => Err(format!("{} environment variable not set", name).into()),
+    }
+}
+
 /// Configuration for Voyager API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoyagerConfig {
@@ -53,8 +62,7 @@ pub struct VoyagerConfig {
 impl VoyagerConfig {
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         Ok(VoyagerConfig {
-            api_key: env::var("VOYAGER_API_KEY")
-                .map_err(|_| "VOYAGER_API_KEY environment variable not set")?,
+            api_key: api_key_from_env("VOYAGER_API_KEY")?,
             api_url: env::var("VOYAGER_API_URL")
                 .unwrap_or_else(|_| "https://api.voyageai.com/v1".to_string()),
             model: "voyage-finance-2".to_string(),
@@ -74,6 +82,7 @@ impl VoyagerConfig {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenRouterConfig {
     pub api_key: String,
+    pub api_url: String,
     pub model: String,
     pub site_url: Option<String>,
     pub site_name: Option<String>,
@@ -86,8 +95,9 @@ pub struct OpenRouterConfig {
 impl OpenRouterConfig {
     pub fn from_env() -> Result<Self, Box<dyn std::error::Error>> {
         Ok(OpenRouterConfig {
-            api_key: env::var("OPENROUTER_API_KEY")
-                .map_err(|_| "OPENROUTER_API_KEY environment variable not set")?,
+            api_key: api_key_from_env("OPENROUTER_API_KEY")?,
+            api_url: env::var("OPENROUTER_API_URL")
+                .unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string()),
             model: env::var("OPENROUTER_MODEL")
                 .unwrap_or_else(|_| "deepseek/deepseek-chat-v3.1:free".to_string()),
             site_url: env::var("OPENROUTER_SITE_URL").ok(),
@@ -101,7 +111,7 @@ impl OpenRouterConfig {
 
     /// Get the chat completion endpoint URL
     pub fn get_chat_url(&self) -> String {
-        "https://openrouter.ai/api/v1/chat/completions".to_string()
+        format!("{}/chat/completions", self.api_url)
     }
 }
 
@@ -167,8 +177,14 @@ impl QdrantConfig {
         })
     }
 
+    /// One collection per tenant. Characters Qdrant doesn't allow in collection names are
+    /// replaced; Supabase UUIDs pass through unchanged.
     pub fn get_collection_name(&self, user_id: &str) -> String {
-        format!("{}_{}", self.collection_prefix, user_id)
+        let tenant: String = user_id
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        format!("{}_{}", self.collection_prefix, tenant)
     }
 }
 
@@ -180,6 +196,8 @@ pub struct HybridSearchConfig {
     pub vector_weight: f32,
     pub keyword_weight: f32,
     pub max_results: usize,
+    /// Reciprocal rank fusion constant; larger values flatten the advantage of top ranks
+    pub rrf_k: f32,
 }
 
 impl HybridSearchConfig {
@@ -205,6 +223,10 @@ impl HybridSearchConfig {
                 .unwrap_or_else(|_| "20".to_string())
                 .parse()
                 .unwrap_or(20),
+            rrf_k: env::var("HYBRID_RRF_K")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60.0),
         }
     }
 }
@@ -1,5 +1,6 @@
 use dashmap::DashMap;
 use super::messages::WsMessage;
+use super::relay::Relay;
 use std::sync::Arc;
 
 /// WebSocket session data
@@ -14,15 +15,23 @@ pub struct WsSession {
 pub struct ConnectionManager {
     /// Maps user_id -> Vec<sender>
     pub(crate) clients: Arc<DashMap<String, Vec<tokio::sync::mpsc::UnboundedSender<String>>>>,
+    /// Publishes broadcasts to the other instances when running more than one
+    relay: Option<Relay>,
 }
 
 impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             clients: Arc::new(DashMap::new()),
+            relay: None,
         }
     }
 
+    pub(crate) fn with_relay(mut self, relay: Relay) -> Self {
+        self.relay = Some(relay);
+        self
+    }
+
     /// Register a new WebSocket connection for a user
     #[allow(dead_code)]
     pub fn register(&self, user_id: String, sender: tokio::sync::mpsc::UnboundedSender<String>) {
@@ -36,11 +45,7 @@ impl ConnectionManager {
     #[allow(dead_code)]
     pub fn unregister(&self, user_id: &str, sender: &tokio::sync::mpsc::UnboundedSender<String>) {
         if let Some(mut clients) = self.clients.get_mut(user_id) {
-            let sender_ptr: *const _ = sender;
-            clients.retain(|s| {
-                let s_ptr: *const _ = s;
-                s_ptr != sender_ptr
-            });
+            clients.retain(|s| !s.same_channel(sender));
             if clients.is_empty() {
                 drop(clients);
                 self.clients.remove(user_id);
@@ -48,23 +53,48 @@ impl ConnectionManager {
         }
     }
 
-    /// Broadcast a message to all connections for a specific user
+    /// Broadcast a message to all connections for a specific user, on every instance
     pub fn broadcast_to_user(&self, user_id: &str, message: WsMessage) {
-        if let Some(clients) = self.clients.get(user_id) {
-            let message_json = serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string());
-            for sender in clients.iter() {
-                let _ = sender.send(message_json.clone());
-            }
+        let message_json = serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string());
+        self.deliver_local(Some(user_id), &message_json);
+        if let Some(relay) = &self.relay {
+            relay.publish(Some(user_id), &message_json);
         }
     }
 
+    /// Broadcast to this instance's connections only, for feeds every instance
+    /// produces itself (market quotes from its own upstream subscriptions)
+    pub fn broadcast_to_local_user(&self, user_id: &str, message: WsMessage) {
+        let message_json = serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string());
+        self.deliver_local(Some(user_id), &message_json);
+    }
+
     /// Broadcast to all users (for admin messages)
     #[allow(dead_code)]
     pub fn broadcast_to_all(&self, message: WsMessage) {
         let message_json = serde_json::to_string(&message).unwrap_or_else(|_| "{}".to_string());
-        for clients in self.clients.iter() {
-            for sender in clients.value().iter() {
-                let _ = sender.send(message_json.clone());
+        self.deliver_local(None, &message_json);
+        if let Some(relay) = &self.relay {
+            relay.publish(None, &message_json);
+        }
+    }
+
+    /// Send a serialized message to one user's connections here, or everyone's when `user_id` is None
+    pub(crate) fn deliver_local(&self, user_id: Option<&str>, message_json: &str) {
+        match user_id {
+            Some(user_id) => {
+                if let Some(clients) = self.clients.get(user_id) {
+                    for sender in clients.iter() {
+                        let _ = sender.send(message_json.to_string());
+                    }
+                }
+            }
+            None => {
+                for clients in self.clients.iter() {
+                    for sender in clients.value().iter() {
+                        let _ = sender.send(message_json.to_string());
+                    }
+                }
             }
         }
     }
@@ -37,6 +37,15 @@ pub enum EventType {
     // Market data events
     MarketQuote,
     MarketUpdate,
+
+    // Behavior events
+    LossStreakAlert,
+    DrawdownAlert,
+    TradeCounterUpdated,
+    OvertradingAlert,
+
+    // Onboarding events
+    OnboardingUpdated,
 }
 
 /// WebSocket message envelope
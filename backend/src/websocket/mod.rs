@@ -2,10 +2,14 @@ mod manager;
 mod messages;
 mod server;
 mod broadcast;
+mod sse;
+mod relay;
 
 pub use manager::ConnectionManager;
 pub use messages::{WsMessage, EventType};
 // Re-export message types only where needed to avoid unused warnings
 pub use server::ws_handler;
+pub use sse::sse_handler;
+pub use relay::with_redis_relay;
 pub use broadcast::*;
 
@@ -0,0 +1,152 @@
+//! Fans broadcasts out to every backend replica. Each instance publishes what it
+//! broadcasts to one Redis channel and subscribes to it, delivering messages from other
+//! instances to the connections it holds. Without Redis configured, broadcasts stay
+//! local, which is all a single instance needs.
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+use super::manager::ConnectionManager;
+use crate::turso::redis::{RedisClient, RedisConfig};
+
+pub const RELAY_CHANNEL: &str = "tradstry:ws";
+/// Longest wait between subscription reconnects
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// One broadcast on the wire
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RelayEnvelope {
+    /// Instance that published it, so it skips its own messages
+    pub origin: String,
+    /// None for a broadcast to every user
+    pub user_id: Option<String>,
+    /// Serialized `WsMessage`
+    pub message: String,
+}
+
+/// Publishing half, held by the connection manager
+#[derive(Clone)]
+pub(crate) struct Relay {
+    instance_id: String,
+    outbound: UnboundedSender<RelayEnvelope>,
+}
+
+impl Relay {
+    pub fn publish(&self, user_id: Option<&str>, message: &str) {
+        let _ = self.outbound.send(RelayEnvelope {
+            origin: self.instance_id.clone(),
+            user_id: user_id.map(str::to_string),
+            message: message.to_string(),
+        });
+    }
+}
+
+/// Payload of a subscription stream line, if it carries a message on our channel
+fn parse_subscription_line(line: &str) -> Option<&str> {
+    let data = line.strip_prefix("data:")?.trim_start();
+    let mut parts = data.splitn(3, ',');
+    match (parts.next()?, parts.next()?, parts.next()) {
+        ("message", RELAY_CHANNEL, Some(payload)) => Some(payload),
+        _ => None,
+    }
+}
+
+/// Attach a Redis relay to `manager` when Redis is configured, starting the publish
+/// and subscribe tasks; otherwise return it unchanged.
+pub async fn with_redis_relay(manager: ConnectionManager) -> ConnectionManager {
+    let redis = match RedisConfig::from_env() {
+        Ok(config) => match RedisClient::new(config).await {
+            Ok(redis) => redis,
+            Err(e) => {
+                warn!("Websocket relay disabled, Redis client failed: {}", e);
+                return manager;
+            }
+        },
+        Err(_) => {
+            info!("Redis not configured; websocket broadcasts reach this instance only");
+            return manager;
+        }
+    };
+    let instance_id = uuid::Uuid::new_v4().to_string();
+    let (outbound, receiver) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(publish_loop(redis.clone(), receiver));
+    tokio::spawn(subscribe_loop(redis, manager.clone(), instance_id.clone()));
+    info!("Websocket relay started on channel {} (instance {})", RELAY_CHANNEL, instance_id);
+    manager.with_relay(Relay { instance_id, outbound })
+}
+
+async fn publish_loop(redis: RedisClient, mut receiver: UnboundedReceiver<RelayEnvelope>) {
+    while let Some(envelope) = receiver.recv().await {
+        let Ok(payload) = serde_json::to_string(&envelope) else { continue };
+        if let Err(e) = redis.publish(RELAY_CHANNEL, &payload).await {
+            warn!("Failed to relay websocket message: {}", e);
+        }
+    }
+}
+
+async fn subscribe_loop(redis: RedisClient, manager: ConnectionManager, instance_id: String) {
+    let mut delay = Duration::from_secs(1);
+    loop {
+        match redis.subscribe(RELAY_CHANNEL).await {
+            Ok(mut response) => {
+                delay = Duration::from_secs(1);
+                // Chunks can end mid-character, so bytes are only decoded once a line is complete
+                let mut buffer: Vec<u8> = Vec::new();
+                loop {
+                    match response.chunk().await {
+                        Ok(Some(chunk)) => {
+                            buffer.extend_from_slice(&chunk);
+                            while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                                let line: Vec<u8> = buffer.drain(..=end).collect();
+                                deliver_line(&manager, &instance_id, String::from_utf8_lossy(&line).trim_end());
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!("Websocket relay subscription dropped: {}", e);
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => error!("Websocket relay subscription failed: {}", e),
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+fn deliver_line(manager: &ConnectionManager, instance_id: &str, line: &str) {
+    let Some(payload) = parse_subscription_line(line) else { return };
+    let Ok(envelope) = serde_json::from_str::<RelayEnvelope>(payload) else {
+        warn!("Ignoring unreadable websocket relay message");
+        return;
+    };
+    if envelope.origin != instance_id {
+        manager.deliver_local(envelope.user_id.as_deref(), &envelope.message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_subscription_line() {
+        let envelope = RelayEnvelope {
+            origin: "a".to_string(),
+            user_id: Some("user_1".to_string()),
+            message: "{\"event\":\"stock_created\",\"data\":{\"x\":1}}".to_string(),
+        };
+        let payload = serde_json::to_string(&envelope).unwrap();
+        let line = format!("data: message,{},{}", RELAY_CHANNEL, payload);
+        let parsed = parse_subscription_line(&line).unwrap();
+        assert_eq!(serde_json::from_str::<RelayEnvelope>(parsed).unwrap(), envelope);
+
+        assert_eq!(parse_subscription_line(&format!("data: subscribe,{},1", RELAY_CHANNEL)), None);
+        assert_eq!(parse_subscription_line("data: message,other,{}"), None);
+        assert_eq!(parse_subscription_line(": keepalive"), None);
+    }
+}
@@ -0,0 +1,160 @@
+use actix_web::{
+    web::{Bytes, Data, Query},
+    HttpRequest, HttpResponse, Result,
+};
+use log::info;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::Mutex;
+
+use super::manager::ConnectionManager;
+use super::messages::{EventType, WsMessage};
+use crate::turso::validate_jwt_token_from_query;
+
+/// Comment line sent while idle so proxies keep the connection open
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Client reconnect delay, in milliseconds
+const RETRY_MS: u64 = 5000;
+
+#[derive(Debug, Deserialize)]
+pub struct SseQuery {
+    /// Auth token; EventSource cannot set headers
+    token: Option<String>,
+    /// Comma-separated event names to receive; everything when absent
+    events: Option<String>,
+}
+
+/// One SSE frame carrying a serialized `WsMessage`
+fn sse_frame(message: &str) -> String {
+    let mut frame: String = message.lines().map(|line| format!("data: {}\n", line)).collect();
+    frame.push('\n');
+    frame
+}
+
+/// Whether a serialized `WsMessage` passes the client's event filter
+fn event_allowed(filter: &Option<HashSet<String>>, message: &str) -> bool {
+    let Some(filter) = filter else { return true };
+    serde_json::from_str::<serde_json::Value>(message)
+        .ok()
+        .and_then(|v| v.get("event").and_then(|e| e.as_str()).map(|e| filter.contains(e)))
+        .unwrap_or(false)
+}
+
+/// Unregisters the stream's sender once the client goes away and the stream is dropped
+struct SseRegistration {
+    manager: Arc<Mutex<ConnectionManager>>,
+    user_id: String,
+    sender: UnboundedSender<String>,
+}
+
+impl Drop for SseRegistration {
+    fn drop(&mut self) {
+        let manager = self.manager.clone();
+        let user_id = std::mem::take(&mut self.user_id);
+        let sender = self.sender.clone();
+        actix_web::rt::spawn(async move {
+            manager.lock().await.unregister(&user_id, &sender);
+            info!("Event stream closed for user: {}", user_id);
+        });
+    }
+}
+
+struct SseState {
+    receiver: UnboundedReceiver<String>,
+    keepalive: tokio::time::Interval,
+    filter: Option<HashSet<String>>,
+    _registration: SseRegistration,
+}
+
+/// Server-sent events for clients that cannot open a websocket. The stream registers with
+/// the same connection manager as `/api/ws`, so it carries every event broadcast to the
+/// user, each as a `data:` line holding the websocket message envelope.
+pub async fn sse_handler(
+    req: HttpRequest,
+    query: Query<SseQuery>,
+    manager: Data<Arc<Mutex<ConnectionManager>>>,
+) -> Result<HttpResponse> {
+    let header_token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let Some(token) = header_token.or_else(|| query.token.clone()) else {
+        return Ok(HttpResponse::Unauthorized().body("Missing authentication token"));
+    };
+    let claims = validate_jwt_token_from_query(&token)
+        .await
+        .map_err(|e| actix_web::error::ErrorUnauthorized(e.to_string()))?;
+    let user_id = claims.sub.clone();
+
+    let filter = query.events.as_ref().map(|events| {
+        events.split(',').map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect::<HashSet<_>>()
+    });
+
+    let manager = manager.as_ref().clone();
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    manager.lock().await.register(user_id.clone(), tx.clone());
+    info!("Event stream opened for user: {}", user_id);
+
+    let connected = WsMessage::new(EventType::Connected, serde_json::json!({"transport": "sse"}));
+    let opening = format!(
+        "retry: {}\n\n{}",
+        RETRY_MS,
+        sse_frame(&serde_json::to_string(&connected).unwrap_or_else(|_| "{}".to_string()))
+    );
+
+    let mut keepalive = tokio::time::interval(KEEPALIVE_INTERVAL);
+    keepalive.reset();
+    let state = SseState {
+        receiver: rx,
+        keepalive,
+        filter,
+        _registration: SseRegistration { manager, user_id, sender: tx },
+    };
+
+    let events = futures_util::stream::unfold(state, |mut state| async move {
+        loop {
+            tokio::select! {
+                message = state.receiver.recv() => {
+                    let message = message?;
+                    if event_allowed(&state.filter, &message) {
+                        return Some((Ok::<Bytes, std::io::Error>(Bytes::from(sse_frame(&message))), state));
+                    }
+                }
+                _ = state.keepalive.tick() => {
+                    return Some((Ok(Bytes::from_static(b": keepalive\n\n")), state));
+                }
+            }
+        }
+    });
+    let stream = futures_util::StreamExt::chain(
+        futures_util::stream::once(async move { Ok::<Bytes, std::io::Error>(Bytes::from(opening)) }),
+        events,
+    );
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .append_header(("Cache-Control", "no-cache"))
+        .append_header(("X-Accel-Buffering", "no"))
+        .streaming(stream))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sse_frame_and_filter() {
+        assert_eq!(sse_frame("{\"event\":\"stock_created\"}"), "data: {\"event\":\"stock_created\"}\n\n");
+        assert_eq!(sse_frame("a\nb"), "data: a\ndata: b\n\n");
+
+        let filter = Some(HashSet::from(["stock_created".to_string()]));
+        assert!(event_allowed(&filter, "{\"event\":\"stock_created\",\"data\":{}}"));
+        assert!(!event_allowed(&filter, "{\"event\":\"note_created\",\"data\":{}}"));
+        assert!(event_allowed(&None, "{\"event\":\"note_created\",\"data\":{}}"));
+    }
+}
@@ -0,0 +1,59 @@
+// End-to-end analytics over a seeded tenant database: the same path the analytics routes
+// take once they have the user's connection.
+
+mod common;
+
+use common::fixtures::seeded_database;
+use rust_decimal::Decimal;
+use tradstry_backend::models::analytics::AnalyticsOptions;
+use tradstry_backend::models::money;
+use tradstry_backend::models::stock::stocks::TimeRange;
+use tradstry_backend::service::analytics_engine::daily_aggregates::load_daily_pnl;
+use tradstry_backend::service::analytics_engine::AnalyticsEngine;
+
+const TRADES: usize = 2_000;
+
+#[tokio::test]
+async fn comprehensive_analytics_covers_every_closed_trade() {
+    let fixture = seeded_database(TRADES).await.unwrap();
+    let analytics = AnalyticsEngine::new()
+        .calculate_comprehensive_analytics(&fixture.conn, &TimeRange::AllTime, AnalyticsOptions::default())
+        .await
+        .unwrap();
+
+    assert_eq!(analytics.time_series.total_trades as usize, TRADES);
+    assert!(!analytics.time_series.daily_pnl.is_empty());
+    let daily_total: f64 = analytics.time_series.daily_pnl.iter().map(|p| p.value).sum();
+    assert!((daily_total - analytics.time_series.cumulative_return).abs() < 1e-4);
+}
+
+/// Realized P&L straight from the trade tables, using the analytics closed-trade rules
+async fn raw_total(conn: &libsql::Connection) -> f64 {
+    let sql = "SELECT \
+        (SELECT COALESCE(SUM(CASE WHEN trade_type = 'BUY' THEN (exit_price - entry_price) * number_shares - commissions ELSE (entry_price - exit_price) * number_shares - commissions END), 0) FROM stocks WHERE exit_price IS NOT NULL AND exit_date IS NOT NULL) + \
+        (SELECT COALESCE(SUM((exit_price - entry_price) * number_of_contracts * 100 - commissions), 0) FROM options WHERE status = 'closed' AND exit_price IS NOT NULL AND exit_date IS NOT NULL)";
+    let mut rows = conn.query(sql, libsql::params![]).await.unwrap();
+    rows.next().await.unwrap().unwrap().get::<f64>(0).unwrap()
+}
+
+async fn aggregate_total(conn: &libsql::Connection) -> f64 {
+    let total: Decimal = load_daily_pnl(conn, "1 = 1", &[]).await.unwrap().iter().map(|d| d.realized_pnl).sum();
+    money::to_f64(total)
+}
+
+#[tokio::test]
+async fn daily_aggregates_track_trade_writes() {
+    let fixture = seeded_database(TRADES).await.unwrap();
+    let conn = &fixture.conn;
+    assert!((aggregate_total(conn).await - raw_total(conn).await).abs() < 1e-4);
+
+    // Moving a trade to another day and deleting one must both be reflected
+    conn.execute("UPDATE stocks SET exit_date = '2020-06-01T15:00:00+00:00', exit_price = exit_price + 1 WHERE id = 1", libsql::params![]).await.unwrap();
+    conn.execute("DELETE FROM options WHERE id = 1", libsql::params![]).await.unwrap();
+    assert!((aggregate_total(conn).await - raw_total(conn).await).abs() < 1e-4);
+
+    let days = load_daily_pnl(conn, "exit_date < ?", &["2020-06-02T00:00:00Z".parse().unwrap()]).await.unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0].trade_date, "2020-06-01");
+    assert_eq!(days[0].trade_count, 1);
+}
@@ -0,0 +1,154 @@
+// Seeded local libsql databases standing in for a tenant's Turso database. Each fixture is a
+// throwaway file with the full per-user schema and a deterministic journal, so test and
+// benchmark runs are comparable across commits. Shared by tests/ and benches/.
+
+use anyhow::Result;
+use chrono::{Duration, TimeZone, Utc};
+use libsql::{Builder, Connection, Database};
+use std::path::PathBuf;
+use tradstry_backend::models::timestamps::to_db;
+use tradstry_backend::turso::schema::{create_table, ensure_indexes, ensure_triggers, get_expected_schema};
+
+const SYMBOLS: &[&str] = &["AAPL", "MSFT", "NVDA", "TSLA", "AMD", "META", "AMZN", "GOOGL", "SPY", "QQQ", "NFLX", "COIN"];
+const STRATEGIES: &[&str] = &["Long Call", "Long Put", "Covered Call", "Iron Condor", "Vertical Spread"];
+/// Rows per multi-row INSERT
+const INSERT_BATCH: usize = 500;
+
+/// A seeded tenant database; the file is removed on drop
+pub struct Fixture {
+    // Kept alive for the connection's lifetime
+    _db: Database,
+    pub conn: Connection,
+    path: PathBuf,
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Small deterministic generator so fixtures don't depend on `rand`
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (self.next() % 10_000) as f64 / 10_000.0 * (high - low)
+    }
+}
+
+/// Create a local database with the current schema and `trades` closed trades
+/// (roughly 80% stocks, 20% options) spread over three years starting January 2023.
+pub async fn seeded_database(trades: usize) -> Result<Fixture> {
+    let fixture = empty_database().await?;
+    let schema = get_expected_schema();
+    for table in &schema {
+        create_table(&fixture.conn, table).await?;
+    }
+    for table in &schema {
+        ensure_indexes(&fixture.conn, table).await?;
+        ensure_triggers(&fixture.conn, table).await?;
+    }
+
+    seed_trades(&fixture.conn, trades, 42).await?;
+    Ok(fixture)
+}
+
+/// A local database with no tables, for tests that set up or migrate the schema themselves
+pub async fn empty_database() -> Result<Fixture> {
+    // Tests run in parallel within one process, so every fixture gets its own file
+    let path = std::env::temp_dir().join(format!("tradstry-fixture-{}.db", uuid::Uuid::new_v4().simple()));
+    let _ = std::fs::remove_file(&path);
+
+    let db = Builder::new_local(&path).build().await?;
+    let conn = db.connect()?;
+    Ok(Fixture { _db: db, conn, path })
+}
+
+async fn seed_trades(conn: &Connection, trades: usize, seed: u64) -> Result<()> {
+    let mut rng = Lcg(seed);
+    let start = Utc.with_ymd_and_hms(2023, 1, 2, 14, 30, 0).unwrap();
+    let span_minutes = 3 * 365 * 24 * 60;
+    let options = trades / 5;
+    let stocks = trades - options;
+
+    conn.execute("BEGIN", libsql::params![]).await?;
+    for batch_start in (0..stocks).step_by(INSERT_BATCH) {
+        let rows = INSERT_BATCH.min(stocks - batch_start);
+        let mut values = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let entry_date = start + Duration::minutes(rng.below(span_minutes) as i64);
+            let exit_date = entry_date + Duration::minutes(5 + rng.below(6 * 60) as i64);
+            let entry = rng.range(10.0, 500.0);
+            let exit = entry * rng.range(0.95, 1.06);
+            values.push(format!(
+                "('{}', '{}', 'MARKET', {:.4}, {:.4}, {:.4}, {:.2}, {}, '{}', '{}')",
+                SYMBOLS[rng.below(SYMBOLS.len() as u64) as usize],
+                if rng.below(3) == 0 { "SELL" } else { "BUY" },
+                entry,
+                exit,
+                entry * 0.97,
+                rng.range(0.0, 2.0),
+                1 + rng.below(200),
+                to_db(&entry_date),
+                to_db(&exit_date),
+            ));
+        }
+        conn.execute(
+            &format!(
+                "INSERT INTO stocks (symbol, trade_type, order_type, entry_price, exit_price, stop_loss, commissions, number_shares, entry_date, exit_date) VALUES {}",
+                values.join(", ")
+            ),
+            libsql::params![],
+        )
+        .await?;
+    }
+
+    for batch_start in (0..options).step_by(INSERT_BATCH) {
+        let rows = INSERT_BATCH.min(options - batch_start);
+        let mut values = Vec::with_capacity(rows);
+        for _ in 0..rows {
+            let entry_date = start + Duration::minutes(rng.below(span_minutes) as i64);
+            let exit_date = entry_date + Duration::minutes(30 + rng.below(5 * 24 * 60) as i64);
+            let premium = rng.range(0.5, 15.0);
+            let contracts = 1 + rng.below(10);
+            let is_call = rng.below(2) == 0;
+            values.push(format!(
+                "('{}', '{}', '{}', {}, '{}', {:.2}, '{}', {:.4}, {:.4}, {:.4}, {:.2}, {:.4}, '{}', '{}', 'closed')",
+                SYMBOLS[rng.below(SYMBOLS.len() as u64) as usize],
+                STRATEGIES[rng.below(STRATEGIES.len() as u64) as usize],
+                if is_call { "Bullish" } else { "Bearish" },
+                contracts,
+                if is_call { "Call" } else { "Put" },
+                rng.range(50.0, 500.0),
+                to_db(&(exit_date + Duration::days(7))),
+                premium,
+                premium * rng.range(0.2, 2.2),
+                premium * contracts as f64 * 100.0,
+                rng.range(0.5, 5.0),
+                rng.range(0.1, 1.2),
+                to_db(&entry_date),
+                to_db(&exit_date),
+            ));
+        }
+        conn.execute(
+            &format!(
+                "INSERT INTO options (symbol, strategy_type, trade_direction, number_of_contracts, option_type, strike_price, expiration_date, entry_price, exit_price, total_premium, commissions, implied_volatility, entry_date, exit_date, status) VALUES {}",
+                values.join(", ")
+            ),
+            libsql::params![],
+        )
+        .await?;
+    }
+    conn.execute("COMMIT", libsql::params![]).await?;
+    Ok(())
+}
@@ -0,0 +1,6 @@
+// Shared harness for integration tests: seeded tenant databases plus local vendor stubs.
+// Each test binary compiles this module separately and uses only part of it.
+#![allow(dead_code)]
+
+pub mod fixtures;
+pub mod stubs;
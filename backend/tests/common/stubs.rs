@@ -0,0 +1,214 @@
+// In-process stand-ins for the HTTP vendors the backend talks to, so tests run in CI without
+// live accounts. One actix server on a random local port serves:
+//   /redis/...       Upstash Redis REST (get, setex, incr, del, expire, keys, ping)
+//   /openrouter/...  OpenRouter chat completions (non-streaming)
+//   /voyage/...      Voyage embeddings, deterministic per input text
+// Qdrant speaks gRPC and isn't stubbed; see `qdrant_from_env`.
+
+use actix_web::{dev::ServerHandle, web, App, HttpResponse, HttpServer};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tradstry_backend::service::ai_service::mock_model_client::mock_embedding;
+use tradstry_backend::service::ai_service::QdrantDocumentClient;
+use tradstry_backend::turso::redis::RedisConfig;
+use tradstry_backend::turso::vector_config::{OpenRouterConfig, QdrantConfig, VoyagerConfig};
+
+pub const DEFAULT_CHAT_REPLY: &str = "Stub reply: your win rate is steady and losses are contained.";
+/// Matches voyage-finance-2
+pub const STUB_EMBEDDING_DIMENSIONS: usize = 1024;
+
+#[derive(Default)]
+struct StubState {
+    redis: Mutex<HashMap<String, String>>,
+    chat_reply: Mutex<String>,
+    chat_requests: Mutex<Vec<Value>>,
+    embedding_inputs: Mutex<Vec<String>>,
+}
+
+/// Running vendor stub; point clients at it with the `*_config` helpers
+pub struct VendorStub {
+    pub base_url: String,
+    state: web::Data<StubState>,
+    handle: ServerHandle,
+}
+
+impl VendorStub {
+    /// Start the stub. Must be called inside an actix runtime (`#[actix_web::test]`).
+    pub async fn start() -> Self {
+        let state = web::Data::new(StubState {
+            chat_reply: Mutex::new(DEFAULT_CHAT_REPLY.to_string()),
+            ..Default::default()
+        });
+        let app_state = state.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(app_state.clone())
+                .route("/redis/get/{key}", web::get().to(redis_get))
+                .route("/redis/setex/{key}/{ttl}", web::post().to(redis_setex))
+                .route("/redis/incr/{key}", web::post().to(redis_incr))
+                .route("/redis/del/{key}", web::post().to(redis_del))
+                .route("/redis/expire/{key}/{ttl}", web::post().to(redis_expire))
+                .route("/redis/keys/{pattern}", web::get().to(redis_keys))
+                .route("/redis/ping", web::get().to(|| async { HttpResponse::Ok().json(json!({"result": "PONG"})) }))
+                .route("/openrouter/chat/completions", web::post().to(chat_completions))
+                .route("/voyage/embeddings", web::post().to(embeddings))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .expect("Failed to bind vendor stub");
+
+        let addr = server.addrs()[0];
+        let server = server.run();
+        let handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        Self { base_url: format!("http://{}", addr), state, handle }
+    }
+
+    pub async fn stop(self) {
+        self.handle.stop(false).await;
+    }
+
+    pub fn redis_config(&self) -> RedisConfig {
+        RedisConfig { url: format!("{}/redis", self.base_url), token: "stub-token".to_string() }
+    }
+
+    pub fn openrouter_config(&self) -> OpenRouterConfig {
+        OpenRouterConfig {
+            api_key: "stub-key".to_string(),
+            api_url: format!("{}/openrouter", self.base_url),
+            model: "stub/model".to_string(),
+            site_url: None,
+            site_name: None,
+            max_retries: 1,
+            timeout_seconds: 5,
+            max_tokens: 512,
+            temperature: 0.0,
+        }
+    }
+
+    pub fn voyager_config(&self) -> VoyagerConfig {
+        VoyagerConfig {
+            api_key: "stub-key".to_string(),
+            api_url: format!("{}/voyage", self.base_url),
+            model: "voyage-finance-2".to_string(),
+            max_retries: 1,
+            timeout_seconds: 5,
+            batch_size: 10,
+        }
+    }
+
+    pub fn set_chat_reply(&self, reply: &str) {
+        *self.state.chat_reply.lock().unwrap() = reply.to_string();
+    }
+
+    /// Request bodies received on the chat endpoint, oldest first
+    pub fn chat_requests(&self) -> Vec<Value> {
+        self.state.chat_requests.lock().unwrap().clone()
+    }
+
+    pub fn embedding_inputs(&self) -> Vec<String> {
+        self.state.embedding_inputs.lock().unwrap().clone()
+    }
+
+    pub fn redis_value(&self, key: &str) -> Option<String> {
+        self.state.redis.lock().unwrap().get(key).cloned()
+    }
+}
+
+/// Qdrant client for tests that need a real instance (e.g. `docker run -p 6334:6334
+/// qdrant/qdrant`). Returns None when `TEST_QDRANT_URL` isn't set so those tests skip.
+pub async fn qdrant_from_env() -> Option<QdrantDocumentClient> {
+    let url = std::env::var("TEST_QDRANT_URL").ok()?;
+    let config = QdrantConfig {
+        url,
+        api_key: std::env::var("TEST_QDRANT_API_KEY").unwrap_or_default(),
+        collection_prefix: format!("test_{}", uuid::Uuid::new_v4().simple()),
+        max_retries: 1,
+        timeout_seconds: 10,
+    };
+    QdrantDocumentClient::new(config).await.ok()
+}
+
+/// Same vectors the mock AI provider produces, so stubbed and mocked runs agree
+pub fn stub_embedding(text: &str) -> Vec<f32> {
+    mock_embedding(text, STUB_EMBEDDING_DIMENSIONS)
+}
+
+/// Redis glob match supporting `*`
+fn glob_matches(pattern: &str, key: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == key;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !key.starts_with(first) || !key.ends_with(last) || key.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &key[first.len()..key.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+async fn redis_get(state: web::Data<StubState>, path: web::Path<String>) -> HttpResponse {
+    let value = state.redis.lock().unwrap().get(path.as_str()).cloned();
+    HttpResponse::Ok().json(json!({ "result": value }))
+}
+
+async fn redis_setex(state: web::Data<StubState>, path: web::Path<(String, u64)>, body: String) -> HttpResponse {
+    state.redis.lock().unwrap().insert(path.into_inner().0, body);
+    HttpResponse::Ok().json(json!({ "result": "OK" }))
+}
+
+async fn redis_incr(state: web::Data<StubState>, path: web::Path<String>) -> HttpResponse {
+    let mut redis = state.redis.lock().unwrap();
+    let value = redis.get(path.as_str()).and_then(|v| v.parse::<i64>().ok()).unwrap_or(0) + 1;
+    redis.insert(path.into_inner(), value.to_string());
+    HttpResponse::Ok().json(json!({ "result": value }))
+}
+
+async fn redis_del(state: web::Data<StubState>, path: web::Path<String>) -> HttpResponse {
+    let removed = state.redis.lock().unwrap().remove(path.as_str()).is_some();
+    HttpResponse::Ok().json(json!({ "result": removed as i64 }))
+}
+
+async fn redis_expire(state: web::Data<StubState>, path: web::Path<(String, u64)>) -> HttpResponse {
+    // TTLs aren't simulated; report whether the key exists like EXPIRE does
+    let exists = state.redis.lock().unwrap().contains_key(&path.0);
+    HttpResponse::Ok().json(json!({ "result": exists as i64 }))
+}
+
+async fn redis_keys(state: web::Data<StubState>, path: web::Path<String>) -> HttpResponse {
+    let keys: Vec<String> = state.redis.lock().unwrap().keys().filter(|k| glob_matches(&path, k)).cloned().collect();
+    HttpResponse::Ok().json(json!({ "result": keys }))
+}
+
+async fn chat_completions(state: web::Data<StubState>, body: web::Json<Value>) -> HttpResponse {
+    state.chat_requests.lock().unwrap().push(body.into_inner());
+    let reply = state.chat_reply.lock().unwrap().clone();
+    HttpResponse::Ok().json(json!({
+        "choices": [{ "message": { "role": "assistant", "content": reply }, "finish_reason": "stop" }],
+        "usage": { "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 }
+    }))
+}
+
+async fn embeddings(state: web::Data<StubState>, body: web::Json<Value>) -> HttpResponse {
+    let inputs: Vec<String> = body
+        .get("input")
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    let data: Vec<Value> = inputs
+        .iter()
+        .enumerate()
+        .map(|(index, text)| json!({ "embedding": stub_embedding(text), "index": index }))
+        .collect();
+    state.embedding_inputs.lock().unwrap().extend(inputs);
+    HttpResponse::Ok().json(json!({ "data": data, "usage": { "total_tokens": 0 } }))
+}
@@ -0,0 +1,98 @@
+// Request middleware in front of a stand-in handler: what gets through, and what is refused
+// before any route runs.
+
+use std::sync::Arc;
+
+use actix_web::middleware::from_fn;
+use actix_web::web::{Bytes, Data};
+use actix_web::{http::StatusCode, test, web, App, HttpResponse};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use tradstry_backend::middleware::concurrency::tenant_concurrency_middleware;
+use tradstry_backend::middleware::sandbox::sandbox_guard_middleware;
+use tradstry_backend::service::concurrency_limiter::TenantConcurrencyLimiter;
+use tradstry_backend::service::sandbox;
+use tradstry_backend::turso::config::{SandboxConfig, SupabaseClaims};
+
+/// Bearer token whose payload names the user; the middleware under test only buckets by it
+fn bearer_for(user_id: &str) -> String {
+    let claims = SupabaseClaims {
+        aud: "authenticated".to_string(),
+        exp: 4_102_444_800,
+        iat: 0,
+        iss: "test".to_string(),
+        sub: user_id.to_string(),
+        email: None,
+        phone: None,
+        role: "authenticated".to_string(),
+        aal: "aal1".to_string(),
+        amr: Vec::new(),
+        session_id: "test".to_string(),
+        is_anonymous: None,
+        user_metadata: None,
+        app_metadata: None,
+    };
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+    format!("Bearer e30.{}.sig", payload)
+}
+
+#[actix_web::test]
+async fn sandbox_token_only_reaches_journaling_routes() {
+    sandbox::install(Some(&SandboxConfig {
+        token_secret: "sandbox-test-secret".to_string(),
+        user_id: "sandbox-demo".to_string(),
+        email: "sandbox@example.com".to_string(),
+    }));
+    let token = sandbox::token().unwrap();
+    let app = test::init_service(
+        App::new()
+            .wrap(from_fn(sandbox_guard_middleware))
+            .default_service(web::to(|| async { HttpResponse::Ok().finish() })),
+    )
+    .await;
+
+    let call = async |path: &str, bearer: &str| {
+        let req = test::TestRequest::post()
+            .uri(path)
+            .insert_header(("Authorization", format!("Bearer {}", bearer)))
+            .to_request();
+        test::call_service(&app, req).await.status()
+    };
+
+    for path in ["/api/ai/chat", "/api/ai/chat/stream", "/api/notebook/notes/n1/ai", "/api/notifications/digest/preferences", "/api/automations"] {
+        assert_eq!(call(path, token).await, StatusCode::FORBIDDEN, "{}", path);
+    }
+    assert_eq!(call("/api/stocks", token).await, StatusCode::OK);
+    // Other tokens are left to the route's own authentication
+    assert_eq!(call("/api/ai/chat", "user-token").await, StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn streaming_response_holds_its_concurrency_slot() {
+    let limiter = Arc::new(TenantConcurrencyLimiter::new(1, 0));
+    let app = test::init_service(
+        App::new().app_data(Data::new(limiter)).service(
+            web::scope("/api/ai")
+                .wrap(from_fn(tenant_concurrency_middleware))
+                // A reply that is still streaming
+                .route("/chat/stream", web::post().to(|| async {
+                    HttpResponse::Ok().streaming(futures_util::stream::pending::<Result<Bytes, actix_web::Error>>())
+                }))
+                .route("/chat", web::post().to(|| async { HttpResponse::Ok().finish() })),
+        ),
+    )
+    .await;
+    let request = |path: &str, user_id: &str| {
+        test::TestRequest::post().uri(path).insert_header(("Authorization", bearer_for(user_id))).to_request()
+    };
+
+    let streaming = test::call_service(&app, request("/api/ai/chat/stream", "user-a")).await;
+    assert_eq!(streaming.status(), StatusCode::OK);
+    let busy = test::call_service(&app, request("/api/ai/chat", "user-a")).await;
+    assert_eq!(busy.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(busy.headers().contains_key("retry-after"));
+    assert_eq!(test::call_service(&app, request("/api/ai/chat", "user-b")).await.status(), StatusCode::OK);
+
+    // The client going away ends the stream and frees the slot
+    drop(streaming);
+    assert_eq!(test::call_service(&app, request("/api/ai/chat", "user-a")).await.status(), StatusCode::OK);
+}
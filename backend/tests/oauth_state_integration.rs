@@ -0,0 +1,41 @@
+// OAuth connect flow state: issued per user and provider in the registry, checked once on
+// the callback.
+
+mod common;
+
+use common::fixtures::empty_database;
+use tradstry_backend::service::integrations::oauth_state::{consume_state, issue_state, OAUTH_STATES_TABLE_SQL};
+
+#[tokio::test]
+async fn callback_state_must_match_the_issued_one() {
+    let fixture = empty_database().await.unwrap();
+    let registry = &fixture.conn;
+    registry.execute(OAUTH_STATES_TABLE_SQL, libsql::params![]).await.unwrap();
+
+    let state = issue_state(registry, "user_1", "notion").await.unwrap();
+    // Another user's state, a missing state and a different provider don't pass
+    assert!(!consume_state(registry, "user_2", "notion", &state).await.unwrap());
+    assert!(!consume_state(registry, "user_1", "dropbox", &state).await.unwrap());
+    assert!(consume_state(registry, "user_1", "notion", &state).await.unwrap());
+    // Used up by the first callback
+    assert!(!consume_state(registry, "user_1", "notion", &state).await.unwrap());
+
+    let state = issue_state(registry, "user_1", "notion").await.unwrap();
+    assert!(!consume_state(registry, "user_1", "notion", "").await.unwrap());
+    // A failed check also burns the pending state
+    assert!(!consume_state(registry, "user_1", "notion", &state).await.unwrap());
+}
+
+#[tokio::test]
+async fn expired_state_is_rejected() {
+    let fixture = empty_database().await.unwrap();
+    let registry = &fixture.conn;
+    registry.execute(OAUTH_STATES_TABLE_SQL, libsql::params![]).await.unwrap();
+
+    let state = issue_state(registry, "user_1", "notion").await.unwrap();
+    registry
+        .execute("UPDATE oauth_states SET expires_at = '2020-01-01T00:00:00+00:00'", libsql::params![])
+        .await
+        .unwrap();
+    assert!(!consume_state(registry, "user_1", "notion", &state).await.unwrap());
+}
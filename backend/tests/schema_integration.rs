@@ -0,0 +1,170 @@
+// Schema setup and migrations against local databases: the tables new users get from
+// create_base_schema, and the rebuilds that bring databases created earlier in line.
+
+mod common;
+
+use common::fixtures::empty_database;
+use libsql::Connection;
+use tradstry_backend::models::ai::insights::{Insight, InsightType};
+use tradstry_backend::models::stock::stocks::TimeRange;
+use tradstry_backend::service::ai_service::insights_service::insert_insight;
+use tradstry_backend::turso::schema::{
+    create_base_schema, drop_unlisted_tables, get_current_tables, get_expected_schema, rebuild_stale_constraints, SCHEMA_BACKUP_PREFIX,
+};
+
+/// ai_insights as databases created before the weekly coach digest have it
+const LEGACY_AI_INSIGHTS: &str = "CREATE TABLE ai_insights (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    time_range TEXT NOT NULL CHECK (time_range IN ('7d', '30d', '90d', 'ytd', '1y')),
+    insight_type TEXT NOT NULL CHECK (insight_type IN ('trading_patterns', 'performance_analysis', 'risk_assessment', 'behavioral_analysis', 'market_analysis', 'opportunity_detection')),
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    key_findings TEXT,
+    recommendations TEXT,
+    data_sources TEXT,
+    confidence_score REAL DEFAULT 0.0,
+    generated_at TEXT NOT NULL,
+    expires_at TEXT,
+    metadata TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+)";
+
+/// ai_reports as databases created before calendar time ranges have it
+const LEGACY_AI_REPORTS: &str = "CREATE TABLE ai_reports (
+    id TEXT PRIMARY KEY,
+    time_range TEXT NOT NULL CHECK (time_range IN ('7d', '30d', '90d', 'ytd', '1y')),
+    report_type TEXT NOT NULL CHECK (report_type IN ('comprehensive', 'performance', 'risk', 'trading', 'behavioral', 'market')),
+    title TEXT NOT NULL,
+    summary TEXT NOT NULL,
+    analytics TEXT NOT NULL,
+    insights TEXT NOT NULL,
+    trades TEXT NOT NULL,
+    recommendations TEXT NOT NULL,
+    patterns TEXT,
+    risk_metrics TEXT,
+    performance_metrics TEXT,
+    behavioral_insights TEXT,
+    market_analysis TEXT,
+    generated_at TEXT NOT NULL,
+    expires_at TEXT,
+    metadata TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+)";
+
+async fn single_text(conn: &Connection, sql: &str) -> String {
+    let mut rows = conn.query(sql, libsql::params![]).await.unwrap();
+    rows.next().await.unwrap().unwrap().get::<String>(0).unwrap()
+}
+
+async fn count(conn: &Connection, sql: &str) -> i64 {
+    let mut rows = conn.query(sql, libsql::params![]).await.unwrap();
+    rows.next().await.unwrap().unwrap().get::<i64>(0).unwrap()
+}
+
+fn weekly_digest() -> Insight {
+    Insight::new(
+        "user_1".to_string(),
+        TimeRange::SevenDays,
+        InsightType::WeeklyCoach,
+        "Your week".to_string(),
+        "Three wins, one oversized loss.".to_string(),
+    )
+}
+
+#[tokio::test]
+async fn weekly_coach_insight_fits_the_base_schema() {
+    let fixture = empty_database().await.unwrap();
+    create_base_schema(&fixture.conn).await.unwrap();
+
+    insert_insight(&fixture.conn, &weekly_digest()).await.unwrap();
+    assert_eq!(single_text(&fixture.conn, "SELECT insight_type FROM ai_insights").await, "weekly_coach");
+    assert_eq!(single_text(&fixture.conn, "SELECT time_range FROM ai_insights").await, "7d");
+}
+
+#[tokio::test]
+async fn stale_insight_constraint_is_rebuilt_with_its_rows() {
+    let fixture = empty_database().await.unwrap();
+    let conn = &fixture.conn;
+    conn.execute(LEGACY_AI_INSIGHTS, libsql::params![]).await.unwrap();
+    conn.execute(
+        "INSERT INTO ai_insights (id, user_id, time_range, insight_type, title, content, generated_at) \
+         VALUES ('old', 'user_1', '30d', 'trading_patterns', 'Patterns', 'Body', '2025-01-01T00:00:00Z')",
+        libsql::params![],
+    )
+    .await
+    .unwrap();
+    assert!(insert_insight(conn, &weekly_digest()).await.is_err());
+
+    assert_eq!(rebuild_stale_constraints(conn).await.unwrap(), vec!["ai_insights"]);
+    insert_insight(conn, &weekly_digest()).await.unwrap();
+    assert_eq!(count(conn, "SELECT COUNT(*) FROM ai_insights").await, 2);
+    assert_eq!(single_text(conn, "SELECT source FROM ai_insights WHERE id = 'old'").await, "llm");
+    let backups = format!("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name LIKE '{}ai_insights_%'", SCHEMA_BACKUP_PREFIX);
+    assert_eq!(count(conn, &backups).await, 1);
+
+    // A second sync finds nothing left to rebuild
+    assert!(rebuild_stale_constraints(conn).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn time_ranges_are_unquoted_and_unconstrained() {
+    let fixture = empty_database().await.unwrap();
+    let conn = &fixture.conn;
+    conn.execute(LEGACY_AI_REPORTS, libsql::params![]).await.unwrap();
+    conn.execute(
+        "INSERT INTO ai_reports (id, time_range, report_type, title, summary, analytics, insights, trades, recommendations, generated_at) \
+         VALUES ('r1', '30d', 'performance', 'Monthly', 'Summary', '{}', '[]', '[]', '[]', '2025-01-01T00:00:00Z')",
+        libsql::params![],
+    )
+    .await
+    .unwrap();
+    // Tables created from the expected schema have no CHECKs and took the JSON-quoted values
+    conn.execute(
+        "CREATE TABLE insight_generation_tasks (id TEXT PRIMARY KEY, user_id TEXT NOT NULL, time_range TEXT NOT NULL, insight_type TEXT NOT NULL, status TEXT NOT NULL)",
+        libsql::params![],
+    )
+    .await
+    .unwrap();
+    conn.execute(
+        "INSERT INTO insight_generation_tasks (id, user_id, time_range, insight_type, status) VALUES ('t1', 'user_1', '\"ytd\"', 'weekly_coach', 'pending')",
+        libsql::params![],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(rebuild_stale_constraints(conn).await.unwrap(), vec!["ai_reports"]);
+    assert_eq!(single_text(conn, "SELECT time_range FROM insight_generation_tasks").await, "ytd");
+    assert_eq!(single_text(conn, "SELECT time_range FROM ai_reports").await, "30d");
+
+    let weeks = TimeRange::parse("2025-W10..2025-W12").unwrap();
+    conn.execute(
+        "INSERT INTO ai_reports (id, time_range, report_type, title, summary, analytics, insights, trades, recommendations, generated_at) \
+         VALUES ('r2', ?, 'risk', 'Weeks', 'Summary', '{}', '[]', '[]', '[]', '2025-03-24T00:00:00Z')",
+        libsql::params![weeks.to_db()],
+    )
+    .await
+    .unwrap();
+    let stored = single_text(conn, "SELECT time_range FROM ai_reports WHERE id = 'r2'").await;
+    assert_eq!(TimeRange::from_db(&stored), Some(weeks));
+}
+
+#[tokio::test]
+async fn unlisted_table_with_rows_survives_sync() {
+    let fixture = empty_database().await.unwrap();
+    let conn = &fixture.conn;
+    let expected = get_expected_schema();
+    conn.execute("CREATE TABLE legacy_notes (id TEXT PRIMARY KEY, body TEXT)", libsql::params![]).await.unwrap();
+    conn.execute("INSERT INTO legacy_notes (id, body) VALUES ('n1', 'keep me')", libsql::params![]).await.unwrap();
+    conn.execute("CREATE TABLE legacy_scratch (id TEXT PRIMARY KEY)", libsql::params![]).await.unwrap();
+
+    // Empty leftovers go; ones holding rows stay until the sync is forced
+    assert_eq!(drop_unlisted_tables(conn, &expected, false).await.unwrap(), vec!["legacy_scratch"]);
+    assert_eq!(single_text(conn, "SELECT body FROM legacy_notes").await, "keep me");
+
+    assert_eq!(drop_unlisted_tables(conn, &expected, true).await.unwrap(), vec!["legacy_notes"]);
+    let tables = get_current_tables(conn).await.unwrap();
+    assert!(!tables.contains(&"legacy_notes".to_string()));
+    let backup = tables.iter().find(|t| t.starts_with(&format!("{}legacy_notes_", SCHEMA_BACKUP_PREFIX))).unwrap();
+    assert_eq!(single_text(conn, &format!("SELECT body FROM {}", backup)).await, "keep me");
+}
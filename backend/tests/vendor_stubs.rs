@@ -0,0 +1,66 @@
+// Vendor clients against the local stubs: cache round trips through the Upstash REST
+// protocol, chat completions and embeddings without API keys.
+
+mod common;
+
+use common::stubs::{qdrant_from_env, stub_embedding, VendorStub, DEFAULT_CHAT_REPLY, STUB_EMBEDDING_DIMENSIONS};
+use std::sync::Arc;
+use tradstry_backend::service::ai_service::openrouter_client::{ChatMessage, MessageRole};
+use tradstry_backend::service::ai_service::{OpenRouterClient, QdrantTenantManager, VoyagerClient};
+use tradstry_backend::turso::redis::RedisClient;
+
+#[actix_web::test]
+async fn redis_client_round_trips_through_stub() {
+    let stub = VendorStub::start().await;
+    let redis = RedisClient::new(stub.redis_config()).await.unwrap();
+
+    redis.set("db:user-1:stocks:all", &vec![1, 2, 3], 60).await.unwrap();
+    assert_eq!(redis.get::<Vec<i32>>("db:user-1:stocks:all").await.unwrap(), Some(vec![1, 2, 3]));
+    assert_eq!(redis.incr("rate:user-1").await.unwrap(), 1);
+    assert_eq!(redis.incr("rate:user-1").await.unwrap(), 2);
+
+    assert_eq!(redis.del_pattern("db:user-1:*").await.unwrap(), 1);
+    assert_eq!(stub.redis_value("db:user-1:stocks:all"), None);
+    assert_eq!(stub.redis_value("rate:user-1").as_deref(), Some("2"));
+    stub.stop().await;
+}
+
+#[actix_web::test]
+async fn openrouter_client_uses_stubbed_completions() {
+    let stub = VendorStub::start().await;
+    let client = OpenRouterClient::new(stub.openrouter_config()).unwrap();
+    let messages = vec![ChatMessage { role: MessageRole::User, content: "How did I do this week?".to_string() }];
+
+    assert_eq!(client.generate_chat(messages.clone()).await.unwrap(), DEFAULT_CHAT_REPLY);
+    stub.set_chat_reply("Cut size after two losses.");
+    assert_eq!(client.generate_chat(messages).await.unwrap(), "Cut size after two losses.");
+
+    let requests = stub.chat_requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0]["messages"][0]["content"], "How did I do this week?");
+    stub.stop().await;
+}
+
+#[actix_web::test]
+async fn voyager_client_gets_deterministic_embeddings() {
+    let stub = VendorStub::start().await;
+    let client = VoyagerClient::new(stub.voyager_config()).unwrap();
+
+    let embeddings = client.embed_texts(&["AAPL breakout".to_string(), "TSLA fade".to_string()]).await.unwrap();
+    assert_eq!(embeddings.len(), 2);
+    assert_eq!(embeddings[0].len(), STUB_EMBEDDING_DIMENSIONS);
+    assert_eq!(embeddings[0], stub_embedding("AAPL breakout"));
+    assert_ne!(embeddings[0], embeddings[1]);
+    assert_eq!(stub.embedding_inputs(), vec!["AAPL breakout", "TSLA fade"]);
+    stub.stop().await;
+}
+
+#[actix_web::test]
+async fn qdrant_tenants_are_isolated() {
+    let Some(qdrant) = qdrant_from_env().await else {
+        eprintln!("TEST_QDRANT_URL not set; skipping Qdrant isolation test");
+        return;
+    };
+    let report = QdrantTenantManager::new(Arc::new(qdrant)).verify_isolation().await.unwrap();
+    assert!(report.isolated, "{:?}", report);
+}
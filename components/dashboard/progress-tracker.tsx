@@ -58,11 +58,14 @@ export function ProgressTracker() {
     return map;
   }, [data]);
 
+  // Week start from the user's settings (0 = Sunday, 1 = Monday)
+  const weekStartsOn = data?.week_start_day === 0 ? 0 : 1;
+
   // Get all weeks in the date range
   const weeks = useMemo(() => {
-    const weekStarts = eachWeekOfInterval(dateRange, { weekStartsOn: 1 }); // Start week on Monday
+    const weekStarts = eachWeekOfInterval(dateRange, { weekStartsOn });
     return weekStarts;
-  }, [dateRange]);
+  }, [dateRange, weekStartsOn]);
 
   // Get all days in the date range
   const allDays = useMemo(() => {
@@ -72,7 +75,7 @@ export function ProgressTracker() {
   // Organize days by week
   const weeksData = useMemo(() => {
     return weeks.map((weekStart) => {
-      const weekEnd = endOfWeek(weekStart, { weekStartsOn: 1 });
+      const weekEnd = endOfWeek(weekStart, { weekStartsOn });
       const weekDays: DayData[] = [];
       
       // Get all days in this week
@@ -103,7 +106,7 @@ export function ProgressTracker() {
         days: weekDays,
       };
     });
-  }, [weeks, dateRange, activityMap]);
+  }, [weeks, dateRange, activityMap, weekStartsOn]);
 
   // Get intensity color based on trade count
   const getIntensityColor = (tradeCount: number): string => {
@@ -220,7 +223,7 @@ export function ProgressTracker() {
                 <div className="flex-1 flex gap-0.5 items-stretch min-h-0">
                   {/* Day of Week Labels */}
                   <div className="flex flex-col gap-0.5 mr-1.5 flex-shrink-0 justify-between">
-                    {['Mon', '', 'Wed', '', 'Fri', '', 'Sun'].map((day, idx) => (
+                    {(weekStartsOn === 0 ? ['Sun', '', 'Tue', '', 'Thu', '', 'Sat'] : ['Mon', '', 'Wed', '', 'Fri', '', 'Sun']).map((day, idx) => (
                       <div 
                         key={idx} 
                         className={cn(
@@ -260,6 +260,7 @@ const endpoints = {
       hours: "/market/hours",
       quotes: "/market/quotes",
       simpleQuotes: "/market/simple-quotes",
+      quotesBatch: "/market/quotes/batch",
       similar: "/market/similar",
       logo: "/market/logo",
       historical: "/market/historical",
@@ -5,6 +5,7 @@ import type {
   MarketHours,
   Quote,
   SimpleQuote,
+  BatchQuote,
   HistoricalResponse,
   MoversResponse,
   MoverItem,
@@ -96,6 +97,17 @@ class MarketDataService {
     return this.unwrapResponse(response);
   }
 
+  /**
+   * Get quote snapshots for up to 100 symbols in one request, with a status per symbol
+   */
+  async getQuoteBatch(symbols: string[]): Promise<BatchQuote[]> {
+    const response = await apiClient.post<BackendApiResponse<BatchQuote[]>>(
+      apiConfig.endpoints.endpoints.market.quotesBatch, {
+      symbols,
+    });
+    return this.unwrapResponse(response);
+  }
+
   /**
    * Get similar quotes to a symbol
    */
@@ -110,6 +110,8 @@ export interface TimeSeriesData {
   annualized_return: number;
   total_return_percentage: number;
   total_trades: number;
+  // User's first day of the week: 0 = Sunday, 1 = Monday
+  week_start_day?: number;
   
   // Legacy/computed fields for backward compatibility
   rolling_win_rate?: TimeSeriesPoint[];
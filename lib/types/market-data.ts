@@ -69,6 +69,17 @@ export interface SimpleQuote {
   logo?: string | null;
 }
 
+// Per-symbol result of a batch quote request
+export type BatchQuoteStatus = "ok" | "not_found" | "invalid_symbol" | "error";
+
+export interface BatchQuote {
+  symbol: string;
+  status: BatchQuoteStatus;
+  quote?: SimpleQuote | null;
+  cached: boolean;
+  error?: string | null;
+}
+
 // Logo URL for a symbol
 export type LogoUrl = string | null;
 
@@ -90,7 +90,7 @@ export interface UpdateReminderRequest {
 
 export interface CalendarEvent {
   id: string;
-  reminder_id: string;
+  reminder_id: string | null;
   event_title: string;
   event_description?: string | null;
   start_date: string;
@@ -99,6 +99,9 @@ export interface CalendarEvent {
   end_time?: string | null;
   is_all_day: boolean;
   is_synced: boolean;
+  /** 'reminder' for user reminders, 'earnings' for system-generated earnings dates */
+  source: string;
+  source_ref?: string | null;
   created_at: string;
   updated_at: string;
 }